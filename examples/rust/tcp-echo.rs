@@ -22,6 +22,7 @@ use ::demikernel::{
     runtime::types::{
         demi_opcode_t,
         demi_qresult_t,
+        DEMI_QR_FLAG_EOF,
     },
     LibOS,
     LibOSName,
@@ -331,6 +332,21 @@ impl Application {
                 demi_opcode_t::DEMI_OPC_POP => {
                     let qd: QDesc = qr.qr_qd.into();
                     let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+
+                    // Remote peer closed the connection.
+                    if qr.qr_flags & DEMI_QR_FLAG_EOF != 0 {
+                        println!("connection closed!");
+                        nclients -= 1;
+                        clients.retain(|&client| client != qd);
+                        if let Err(e) = self.libos.sgafree(sga) {
+                            panic!("failed to release scatter-gather array: {:?}", e);
+                        }
+                        if let Err(e) = self.libos.close(qd) {
+                            panic!("failed to close socket: {:?}", e.cause);
+                        }
+                        continue;
+                    }
+
                     nbytes += sga.sga_segs[0].sgaseg_len as usize;
                     let qt: QToken = match self.libos.push(qd, &sga) {
                         Ok(qt) => qt,
@@ -6,6 +6,7 @@
 
 #include <demi/types.h>
 #include <stddef.h>
+#include <time.h>
 
 #ifdef __linux__
 #include <sys/socket.h>
@@ -31,6 +32,29 @@ extern "C"
      */
     extern int demi_init(int argc, char *const argv[]);
 
+    /**
+     * @brief Tears down Demikernel.
+     *
+     * @details All pending operations are canceled, all I/O queues are closed, and the resources of the underlying
+     * LibOS are released. No Demikernel function may be called afterwards. This function is not async-signal-safe, so
+     * signal handlers must call demi_request_teardown() instead.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_teardown(void);
+
+    /**
+     * @brief Requests Demikernel to be torn down.
+     *
+     * @details This only flags the request, and is async-signal-safe, so it may be called from signal handlers. Waits
+     * that are under way fail with ECANCELED, and Demikernel is torn down as soon as the Demikernel function that is
+     * running, or else the next one that is called, returns. That function fails with ECANCELED, and no Demikernel
+     * function may be called afterwards, except for demi_teardown(), which fails with ENOSYS.
+     *
+     * @return This function always returns zero.
+     */
+    extern int demi_request_teardown(void);
+
     /**
      * @brief Creates a new memory I/O queue.
      *
@@ -74,7 +98,7 @@ extern "C"
     extern int demi_listen(int sockqd, int backlog);
 
     /**
-     * @brief Binds an address to a socket I/O queue.
+     * @brief Binds an address to a socket I/O queue. Unix domain sockets are bound to the path of a sockaddr_un.
      *
      * @param sockqd I/O queue descriptor of the target socket.
      * @param addr   Bind address.
@@ -95,7 +119,9 @@ extern "C"
     extern int demi_accept(demi_qtoken_t *qt_out, int sockqd);
 
     /**
-     * @brief Asynchronously initiates a connection on a socket I/O queue.
+     * @brief Asynchronously initiates a connection on a socket I/O queue. On a UDP socket, this sets the default remote
+     * address: data may then be pushed without an address, and only datagrams from that address are popped. Unix domain
+     * sockets are connected to the path of a sockaddr_un.
      *
      * @param qt_out Store location for I/O queue token.
      * @param sockqd I/O queue descriptor of the target socket.
@@ -106,6 +132,21 @@ extern "C"
      */
     extern int demi_connect(demi_qtoken_t *qt_out, int sockqd, const struct sockaddr *addr, socklen_t size);
 
+    /**
+     * @brief Asynchronously initiates a connection on a socket I/O queue, which fails with ETIMEDOUT if it is not
+     * established by a given deadline.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param sockqd  I/O queue descriptor of the target socket.
+     * @param addr    Address of remote host.
+     * @param size    Effective size of the socked address data structure.
+     * @param abstime Deadline, in absolute seconds and nanoseconds since the epoch.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_timedconnect(demi_qtoken_t *qt_out, int sockqd, const struct sockaddr *addr, socklen_t size,
+                                 const struct timespec *abstime);
+
     /**
      * @brief Closes an I/O queue descriptor.
      *
@@ -115,6 +156,44 @@ extern "C"
      */
     extern int demi_close(int qd);
 
+    /**
+     * @brief Asynchronously closes an I/O queue descriptor. The operation completes with DEMI_OPC_CLOSE once the I/O
+     * queue is closed. On TCP sockets, this happens once the remote peer acknowledges the end of the connection.
+     *
+     * @param qt_out Store location for I/O queue token.
+     * @param qd     Target I/O queue descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_async_close(demi_qtoken_t *qt_out, int qd);
+
+    /**
+     * @brief Duplicates an I/O queue descriptor.
+     *
+     * @details The new I/O queue descriptor refers to the same I/O queue as the target one. The I/O queue itself is
+     * closed only once all I/O queue descriptors that refer to it are closed.
+     *
+     * @param qd_out Store location for the new I/O queue descriptor.
+     * @param qd     Target I/O queue descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_dup(int *qd_out, int qd);
+
+    /**
+     * @brief Shuts down part of a full-duplex connection on a socket I/O queue.
+     *
+     * Shutting down the sending side (SHUT_WR or SHUT_RDWR) signals end-of-stream to the remote peer. Data that is
+     * still in flight may continue to be popped. Once no more data is available, pops complete with a zero-length
+     * scatter-gather array.
+     *
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param how    Which side of the connection to shut down (SHUT_RD, SHUT_WR or SHUT_RDWR).
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_shutdown(int sockqd, int how);
+
     /**
      * @brief Asynchronously pushes a scatter-gather array to an I/O queue.
      *
@@ -127,7 +206,20 @@ extern "C"
     extern int demi_push(demi_qtoken_t *qt_out, int qd, const demi_sgarray_t *sga);
 
     /**
-     * @brief Asynchronously pushes a scatter-gather array to a socket I/O queue.
+     * @brief Asynchronously pushes multiple scatter-gather arrays to an I/O queue as a single unit.
+     *
+     * @param qt_out   Store location for I/O queue token.
+     * @param qd       Target I/O queue descriptor.
+     * @param sgas     List of scatter-gather arrays to push.
+     * @param num_sgas Length of the list of scatter-gather arrays to push.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_pushv(demi_qtoken_t *qt_out, int qd, const demi_sgarray_t sgas[], int num_sgas);
+
+    /**
+     * @brief Asynchronously pushes a scatter-gather array to a socket I/O queue. Datagrams of unix domain sockets are
+     * pushed to the path of a sockaddr_un.
      *
      * @param qt_out    Store location for I/O queue token.
      * @param sockqd    I/O queue descriptor of the target socket.
@@ -140,6 +232,20 @@ extern "C"
     extern int demi_pushto(demi_qtoken_t *qt_out, int sockqd, const demi_sgarray_t *sga,
                            const struct sockaddr *dest_addr, socklen_t size);
 
+    /**
+     * @brief Asynchronously pushes a scatter-gather array to a socket I/O queue, and pops the response from it. The
+     * operation completes with DEMI_OPC_POP once the response arrives, which saves a round trip for request/response
+     * exchanges. This is only supported on Catcollar, which links both operations in the io_uring. The operation fails
+     * with EIO if the request cannot be pushed as a whole.
+     *
+     * @param qt_out Store location for I/O queue token.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param sga    Scatter-gather array to push.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_pushpop(demi_qtoken_t *qt_out, int sockqd, const demi_sgarray_t *sga);
+
     /**
      * @brief Asynchronously pops a scatter-gather array from an I/O queue.
      *
@@ -150,6 +256,123 @@ extern "C"
      */
     extern int demi_pop(demi_qtoken_t *qt_out, int qd);
 
+    /**
+     * @brief Asynchronously pops a scatter-gather array from an I/O queue, which fails with ETIMEDOUT if no data
+     * arrives by a given deadline.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param qd      Target I/O queue descriptor.
+     * @param abstime Deadline, in absolute seconds and nanoseconds since the epoch.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_timedpop(demi_qtoken_t *qt_out, int qd, const struct timespec *abstime);
+
+    /**
+     * @brief Asynchronously forwards data from a source I/O queue to a destination I/O queue, without copying it,
+     * until the remote peer of the source I/O queue closes the connection. The operation completes with
+     * DEMI_OPC_SPLICE once all data has been forwarded.
+     *
+     * @param qt_out Store location for I/O queue token.
+     * @param srcqd  Source I/O queue descriptor.
+     * @param dstqd  Destination I/O queue descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_splice(demi_qtoken_t *qt_out, int srcqd, int dstqd);
+
+    /**
+     * @brief Asynchronously sends an ICMP echo request to a remote host. The operation completes with DEMI_OPC_PING
+     * and the round-trip time once the echo reply arrives, or fails with ETIMEDOUT if it does not arrive in time.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param addr    Address of remote host. The port is ignored.
+     * @param size    Effective size of the socked address data structure.
+     * @param timeout Time to wait for the echo reply, or NULL to wait for a default of five seconds.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_ping(demi_qtoken_t *qt_out, const struct sockaddr *addr, socklen_t size,
+                         const struct timespec *timeout);
+
+    /**
+     * @brief Creates an I/O queue that reports changes of the link state. Each pop on it completes with DEMI_OPC_LINK
+     * once the link goes up or down. While the link is down, pending connect and pop operations on sockets fail with
+     * ENETDOWN.
+     *
+     * @param qd_out Store location for the I/O queue descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_link_events(int *qd_out);
+
+    /**
+     * @brief Gets the address to which a socket I/O queue is bound. Unix domain sockets report a sockaddr_un, which is
+     * truncated to the size of the store location if it does not fit.
+     *
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param addr   Store location for the local address.
+     * @param size   Store location for the effective size of the socket address data structure.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_getsockname(int sockqd, struct sockaddr *addr, socklen_t *size);
+
+    /**
+     * @brief Gets the address of the peer connected to a socket I/O queue. Unix domain sockets report a sockaddr_un,
+     * which is truncated to the size of the store location if it does not fit.
+     *
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param addr   Store location for the remote address.
+     * @param size   Store location for the effective size of the socket address data structure.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_getpeername(int sockqd, struct sockaddr *addr, socklen_t *size);
+
+    /**
+     * @brief Gets the times at which the network interface last received and transmitted a frame on a socket I/O
+     * queue, as read from its clock. Kernel-based LibOSes cannot timestamp frames, and fail with ENOTSUP.
+     *
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param ts_out Store location for the timestamps.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_gettimestamps(int sockqd, demi_timestamps_t *ts_out);
+
+    /**
+     * @brief Sets an option on a socket I/O queue.
+     *
+     * @param sockqd  I/O queue descriptor of the target socket.
+     * @param level   Protocol level at which the option resides (SOL_SOCKET, IPPROTO_TCP or IPPROTO_IP).
+     * @param optname Name of the target option (SO_SNDBUF, SO_RCVBUF, SO_KEEPALIVE, SO_BROADCAST, TCP_NODELAY,
+     *                TCP_CORK, TCP_QUICKACK, IP_TTL or IP_PKTINFO). SO_KEEPALIVE and IP_TTL are only supported by
+     *                LibOSes that rely on kernel sockets. TCP_CORK and TCP_QUICKACK are not
+     *                supported on Windows.
+     * @param optval  Pointer to an int that holds the new value of the option.
+     * @param optlen  Size of the data pointed to by @p optval.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_setsockopt(int sockqd, int level, int optname, const void *optval, socklen_t optlen);
+
+    /**
+     * @brief Gets an option of a socket I/O queue.
+     *
+     * @param sockqd  I/O queue descriptor of the target socket.
+     * @param level   Protocol level at which the option resides (SOL_SOCKET, IPPROTO_TCP or IPPROTO_IP).
+     * @param optname Name of the target option (SO_SNDBUF, SO_RCVBUF, SO_KEEPALIVE, SO_BROADCAST, TCP_NODELAY,
+     *                TCP_CORK, TCP_QUICKACK, IP_TTL or IP_PKTINFO). SO_KEEPALIVE and IP_TTL are only supported by
+     *                LibOSes that rely on kernel sockets. TCP_CORK and TCP_QUICKACK are not
+     *                supported on Windows.
+     * @param optval  Store location for an int that holds the current value of the option.
+     * @param optlen  Store location for the effective size of the data stored in @p optval.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_getsockopt(int sockqd, int level, int optname, void *optval, socklen_t *optlen);
+
 #ifdef __cplusplus
 }
 #endif
@@ -22,6 +22,22 @@ extern "C"
      */
     extern demi_sgarray_t demi_sgaalloc(size_t size);
 
+    /**
+     * @brief Registers application-owned memory as a scatter-gather array.
+     *
+     * @details Scatter-gather arrays that are backed by registered memory are pushed without copying their data. The
+     * memory must remain valid until the scatter-gather array is released with demi_sgafree() and all pushes of it
+     * have completed. On TCP sockets, such pushes complete only once the remote peer has acknowledged their data.
+     * Releasing the scatter-gather array does not release the underlying memory.
+     *
+     * @param buf  Base address of the memory to register.
+     * @param size Size of the memory to register.
+     *
+     * @return On successful completion, a scatter-gather array describing the registered memory is returned. On error,
+     * a null scatter-gather array is returned instead.
+     */
+    extern demi_sgarray_t demi_sgaregister(void *buf, size_t size);
+
     /**
      * @brief Releases a scatter-gather array.
      *
@@ -10,6 +10,7 @@
 #ifdef __linux__
 #include <netinet/in.h>
 #include <sys/socket.h>
+#include <sys/un.h>
 #endif
 
 #ifdef _WIN32
@@ -24,7 +25,27 @@ extern "C"
 /**
  * @brief Maximum number of segments in a scatter-gather array.
  */
-#define DEMI_SGARRAY_MAXSIZE 1
+#define DEMI_SGARRAY_MAXSIZE 16
+
+/**
+ * @brief Result flag set on a pop operation that hit end-of-stream (i.e. the remote peer closed the connection).
+ */
+#define DEMI_QR_FLAG_EOF 0x1
+
+/**
+ * @brief Result flag set on a pop operation that returned a datagram truncated to fit the receive buffer.
+ */
+#define DEMI_QR_FLAG_TRUNC 0x2
+
+/**
+ * @brief Result flag set on a pop operation that carries packet information in the popped scatter-gather array.
+ */
+#define DEMI_QR_FLAG_PKTINFO 0x4
+
+/**
+ * @brief Result flag set on a pop operation from a unix domain socket, whose source address is in sga_unaddr.
+ */
+#define DEMI_QR_FLAG_UNIXADDR 0x8
 
     /**
      * @brief An I/O queue token.
@@ -40,6 +61,26 @@ extern "C"
         uint32_t sgaseg_len; /**< Size in bytes of data. */
     } demi_sgaseg_t;
 
+    /**
+     * @brief Packet information of a popped datagram.
+     */
+    typedef struct demi_pktinfo
+    {
+        struct in_addr pi_dstaddr; /**< Local address that the datagram was sent to.                          */
+        int pi_ttl;                /**< Time-to-live of the datagram, or -1 if unknown.                       */
+        int64_t pi_timestamp;      /**< Arrival time in nanoseconds since the Unix epoch, or zero if unknown. */
+        uint64_t pi_hwtimestamp;   /**< Hardware receive time in nanoseconds, or zero if not timestamped.     */
+    } demi_pktinfo_t;
+
+    /**
+     * @brief Hardware timestamps of a socket.
+     */
+    typedef struct demi_timestamps
+    {
+        uint64_t ts_rx; /**< Hardware time of the last received frame in nanoseconds, or zero if unknown.    */
+        uint64_t ts_tx; /**< Hardware time of the last transmitted frame in nanoseconds, or zero if unknown. */
+    } demi_timestamps_t;
+
     /**
      * @brief A scatter-gather array.
      */
@@ -49,6 +90,10 @@ extern "C"
         uint32_t sga_numsegs;                         /**< Number of segments in the scatter-gather array. */
         demi_sgaseg_t sga_segs[DEMI_SGARRAY_MAXSIZE]; /**< Scatter-gather array segments.                  */
         struct sockaddr_in sga_addr;                  /**< Source address of scatter-gather array.         */
+        demi_pktinfo_t sga_pktinfo;                   /**< Packet information (see DEMI_QR_FLAG_PKTINFO).  */
+#ifdef __linux__
+        struct sockaddr_un sga_unaddr; /**< Source address on unix domain sockets (see DEMI_QR_FLAG_UNIXADDR). */
+#endif
     } demi_sgarray_t;
 
     /**
@@ -62,6 +107,10 @@ extern "C"
         DEMI_OPC_ACCEPT,      /**< Accept operation.  */
         DEMI_OPC_CONNECT,     /**< Connect operation. */
         DEMI_OPC_FAILED,      /**< Operation failed.  */
+        DEMI_OPC_SPLICE,      /**< Splice operation.  */
+        DEMI_OPC_CLOSE,       /**< Close operation.   */
+        DEMI_OPC_PING,        /**< Ping operation.    */
+        DEMI_OPC_LINK,        /**< Link event.        */
     } demi_opcode_t;
 
     /**
@@ -81,6 +130,8 @@ extern "C"
         enum demi_opcode qr_opcode; /**< Opcode of completed operation.                              */
         int qr_qd;                  /**< I/O queue descriptor associated to the completed operation. */
         demi_qtoken_t qr_qt;        /**< I/O queue token of the completed operation.                 */
+        uint32_t qr_flags;          /**< Flags of the completed operation (DEMI_QR_FLAG_*).          */
+        int64_t qr_ret;             /**< Error code of a failed operation, or zero otherwise.        */
 
         /**
          * @brief Result value.
@@ -88,6 +139,8 @@ extern "C"
         union {
             demi_sgarray_t sga;        /**< Pushed/popped scatter-gather array. */
             demi_accept_result_t ares; /**< Accept result.                      */
+            int64_t rtt;               /**< Round-trip time of a ping, in ns.   */
+            uint32_t link_up;          /**< Link state after a link event.      */
         } qr_value;
     } demi_qresult_t;
 
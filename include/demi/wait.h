@@ -47,6 +47,19 @@ extern "C"
      */
     extern int demi_wait_any(demi_qresult_t *qr_out, int *ready_offset, const demi_qtoken_t qts[], int num_qts, const struct timespec *timeout);
 
+    /**
+     * @brief Retrieves an event file descriptor that becomes readable when some I/O operation may have completed.
+     *
+     * @details The returned file descriptor may be waited on along with other file descriptors (e.g. with epoll_wait()),
+     * so as to only call demi_wait() or demi_wait_any() once woken up. It is owned by Demikernel and must not be
+     * closed. This is only supported on Linux.
+     *
+     * @param fd_out Store location for the event file descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_eventfd(int *fd_out);
+
 #ifdef __cplusplus
 }
 #endif
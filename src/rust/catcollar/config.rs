@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::demikernel::config::Config;
+use ::std::time::Duration;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Time after which an idle submission queue polling thread goes to sleep, unless configured otherwise.
+const DEFAULT_SQPOLL_IDLE: Duration = Duration::from_millis(1000);
+
+/// Maximum number of operations that are submitted, or completions that are reaped, at once, unless configured
+/// otherwise.
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Catcollar associated functions for Demikernel configuration object.
+impl Config {
+    /// Reads the "submission queue polling" parameters from the underlying configuration file. If enabled, this is the
+    /// time after which the kernel thread that polls the submission queue goes to sleep when it finds no work to do.
+    pub fn io_uring_sqpoll(&self) -> Option<Duration> {
+        // FIXME: this function should return a Result.
+        if !self.0["catcollar"]["sqpoll"].as_bool().unwrap_or(false) {
+            return None;
+        }
+        match self.0["catcollar"]["sqpoll_idle_ms"].as_i64() {
+            Some(idle) if idle <= 0 || idle > u32::MAX as i64 => panic!("Invalid SQPOLL idle time {}", idle),
+            Some(idle) => Some(Duration::from_millis(idle as u64)),
+            None => Some(DEFAULT_SQPOLL_IDLE),
+        }
+    }
+
+    /// Reads the "zero-copy send threshold" parameter from the underlying configuration file. Pushes of at least this
+    /// many bytes are sent without the kernel copying their payload, which requires Linux 6.1 or later.
+    pub fn io_uring_send_zc_threshold(&self) -> Option<usize> {
+        // FIXME: this function should return a Result.
+        match self.0["catcollar"]["send_zc_threshold"].as_i64() {
+            Some(threshold) if threshold <= 0 => panic!("Invalid zero-copy send threshold {}", threshold),
+            Some(threshold) => Some(threshold as usize),
+            None => None,
+        }
+    }
+
+    /// Reads the "NAPI busy polling" parameter from the underlying configuration file. If set, this is the time for
+    /// which the kernel busy polls the network device for packets, instead of waiting for its interrupts.
+    pub fn napi_busy_poll(&self) -> Option<Duration> {
+        // FIXME: this function should return a Result.
+        match self.0["catcollar"]["napi_busy_poll_us"].as_i64() {
+            Some(timeout) if timeout <= 0 || timeout > u32::MAX as i64 => {
+                panic!("Invalid NAPI busy polling time {}", timeout)
+            },
+            Some(timeout) => Some(Duration::from_micros(timeout as u64)),
+            None => None,
+        }
+    }
+
+    /// Reads the "prefer NAPI busy polling" parameter from the underlying configuration file. If set, the network
+    /// device keeps its interrupts masked while it is busy polled.
+    pub fn napi_prefer_busy_poll(&self) -> bool {
+        self.0["catcollar"]["napi_prefer_busy_poll"].as_bool().unwrap_or(false)
+    }
+
+    /// Reads the "NAPI busy polling budget" parameter from the underlying configuration file. This is the maximum
+    /// number of packets that are processed on each busy poll of a socket.
+    pub fn napi_busy_poll_budget(&self) -> Option<u16> {
+        // FIXME: this function should return a Result.
+        match self.0["catcollar"]["napi_busy_poll_budget"].as_i64() {
+            Some(budget) if budget <= 0 || budget > u16::MAX as i64 => {
+                panic!("Invalid NAPI busy polling budget {}", budget)
+            },
+            Some(budget) => Some(budget as u16),
+            None => None,
+        }
+    }
+
+    /// Reads the "batch size" parameter from the underlying configuration file. This is the maximum number of
+    /// operations that are submitted at once, and of completions that are reaped at once.
+    pub fn io_uring_batch_size(&self) -> usize {
+        // FIXME: this function should return a Result.
+        match self.0["catcollar"]["batch_size"].as_i64() {
+            Some(size) if size <= 0 || size > u32::MAX as i64 => panic!("Invalid batch size {}", size),
+            Some(size) => size as usize,
+            None => DEFAULT_BATCH_SIZE,
+        }
+    }
+}
@@ -6,20 +6,33 @@
 //==============================================================================
 
 use crate::{
+    catcollar::{
+        runtime::RequestId,
+        IoUringRuntime,
+    },
     pal::linux,
     runtime::{
         fail::Fail,
         QDesc,
     },
+    scheduler::OffloadFuture,
 };
 use ::nix::{
     errno::Errno,
     sys::socket,
+    unistd,
 };
 use ::std::{
     future::Future,
-    os::unix::prelude::RawFd,
+    os::unix::prelude::{
+        AsRawFd,
+        FromRawFd,
+        IntoRawFd,
+        OwnedFd,
+        RawFd,
+    },
     pin::Pin,
+    sync::Arc,
     task::{
         Context,
         Poll,
@@ -32,12 +45,21 @@ use ::std::{
 
 /// Accept Operation Descriptor
 pub struct AcceptFuture {
+    /// Underlying runtime.
+    rt: IoUringRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     /// Underlying file descriptor.
     fd: RawFd,
     /// Queue descriptor of incoming connection.
     new_qd: QDesc,
+    /// Duplicate of the socket that offloaded accepts run on. It is created once per operation, and it stays valid even
+    /// if the queue descriptor is closed in the meantime.
+    sock: Option<Arc<OwnedFd>>,
+    /// Accept that is offloaded to some worker of the scheduler, if any.
+    job: Option<OffloadFuture<Result<OwnedFd, Errno>>>,
+    /// Request that waits for a connection to be pending, if any.
+    ready: Option<RequestId>,
 }
 
 //==============================================================================
@@ -47,8 +69,16 @@ pub struct AcceptFuture {
 /// Associate Functions for Accept Operation Descriptors
 impl AcceptFuture {
     /// Creates a descriptor for an accept operation.
-    pub fn new(qd: QDesc, fd: RawFd, new_qd: QDesc) -> Self {
-        Self { qd, fd, new_qd }
+    pub fn new(rt: IoUringRuntime, qd: QDesc, fd: RawFd, new_qd: QDesc) -> Self {
+        Self {
+            rt,
+            qd,
+            fd,
+            new_qd,
+            sock: None,
+            job: None,
+            ready: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target accept operation
@@ -62,43 +92,36 @@ impl AcceptFuture {
     pub fn get_new_qd(&self) -> QDesc {
         self.new_qd
     }
-}
-
-//==============================================================================
-// Trait Implementations
-//==============================================================================
 
-/// Future Trait Implementation for Accept Operation Descriptors
-impl Future for AcceptFuture {
-    type Output = Result<RawFd, Fail>;
+    /// Cancels the request that waits for a connection to be pending, if any, as the target accept operation is no
+    /// longer polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        match self.ready.take() {
+            Some(request_id) => self.rt.cancel_request(request_id),
+            None => Ok(()),
+        }
+    }
 
-    /// Polls the underlying accept operation.
-    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
-        let self_: &AcceptFuture = self.get_mut();
-        match socket::accept(self_.fd as i32) {
+    /// Completes the target accept operation with the `result` of an accept attempt. If no connection is pending, this
+    /// waits for one through the I/O user ring, which wakes up the operation once the socket becomes readable.
+    fn complete(&mut self, result: Result<OwnedFd, Errno>, ctx: &mut Context<'_>) -> Poll<Result<RawFd, Fail>> {
+        match result {
             // Operation completed.
             Ok(new_fd) => {
+                let new_fd: RawFd = new_fd.into_raw_fd();
                 trace!("connection accepted ({:?})", new_fd);
-
-                // Set socket options.
-                unsafe {
-                    if linux::set_tcp_nodelay(new_fd) != 0 {
-                        warn!("cannot set TCP_NONDELAY option");
-                    }
-                    if linux::set_nonblock(new_fd) != 0 {
-                        warn!("cannot set NONBLOCK option");
-                    }
-                    if linux::set_so_reuseport(new_fd) != 0 {
-                        warn!("cannot set SO_REUSEPORT option");
-                    }
-                }
-
                 Poll::Ready(Ok(new_fd))
             },
             // Operation in progress.
             Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
-                ctx.waker().wake_by_ref();
-                Poll::Pending
+                match self.rt.poll_add(self.fd, libc::POLLIN as u32) {
+                    Ok(request_id) => {
+                        self.ready = Some(request_id);
+                        self.rt.wait_completion(request_id, ctx.waker());
+                        Poll::Pending
+                    },
+                    Err(e) => Poll::Ready(Err(e)),
+                }
             },
             // Operation failed.
             Err(e) => {
@@ -108,3 +131,82 @@ impl Future for AcceptFuture {
         }
     }
 }
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Accept Operation Descriptors
+impl Future for AcceptFuture {
+    type Output = Result<RawFd, Fail>;
+
+    /// Polls the underlying accept operation.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut AcceptFuture = self.get_mut();
+
+        // Wait for a connection to be pending. Errors of the socket are reported by the next accept attempt.
+        if let Some(request_id) = self_.ready {
+            match self_.rt.peek(request_id) {
+                Ok((_, Some(_))) => self_.ready = None,
+                Ok((_, None)) => {
+                    self_.rt.wait_completion(request_id, ctx.waker());
+                    return Poll::Pending;
+                },
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+
+        // Accept on some worker of the scheduler, if there is any, since this takes several system calls. Only the first
+        // attempt is made right away, and later ones follow the readiness of the socket. Connections that are accepted
+        // after the operation is dropped are closed along with its output.
+        if self_.job.is_none() {
+            if self_.rt.scheduler.nworkers() == 0 {
+                let result: Result<OwnedFd, Errno> = accept(self_.fd);
+                return self_.complete(result, ctx);
+            }
+            let sock: Arc<OwnedFd> = match self_.sock.as_ref() {
+                Some(sock) => sock.clone(),
+                None => match unistd::dup(self_.fd) {
+                    Ok(fd) => self_.sock.insert(Arc::new(unsafe { OwnedFd::from_raw_fd(fd) })).clone(),
+                    Err(e) => return self_.complete(Err(e), ctx),
+                },
+            };
+            match self_.rt.scheduler.offload(move || accept(sock.as_raw_fd())) {
+                Ok(job) => self_.job = Some(job),
+                Err(job) => return self_.complete(job(), ctx),
+            }
+        }
+        let result: Result<OwnedFd, Errno> =
+            match Future::poll(Pin::new(self_.job.as_mut().expect("accept should be offloaded")), ctx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+        self_.job = None;
+        self_.complete(result, ctx)
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Accepts an incoming connection on the socket `fd`, and sets up its options. This only issues system calls, so it may
+/// run on any thread. The accepted socket is closed if it is dropped.
+fn accept(fd: RawFd) -> Result<OwnedFd, Errno> {
+    let new_fd: OwnedFd = unsafe { OwnedFd::from_raw_fd(socket::accept(fd)?) };
+
+    // Set socket options.
+    unsafe {
+        if linux::set_tcp_nodelay(new_fd.as_raw_fd()) != 0 {
+            warn!("cannot set TCP_NONDELAY option");
+        }
+        if linux::set_nonblock(new_fd.as_raw_fd()) != 0 {
+            warn!("cannot set NONBLOCK option");
+        }
+        if linux::set_so_reuseport(new_fd.as_raw_fd()) != 0 {
+            warn!("cannot set SO_REUSEPORT option");
+        }
+    }
+
+    Ok(new_fd)
+}
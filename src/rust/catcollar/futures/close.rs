@@ -0,0 +1,96 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catcollar::{
+        runtime::RequestId,
+        IoUringRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+};
+use ::std::{
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Close Operation Descriptor
+pub struct CloseFuture {
+    /// Underlying runtime.
+    rt: IoUringRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    /// Associated request, if the underlying file descriptor is being closed.
+    request_id: Option<RequestId>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Close Operation Descriptors
+impl CloseFuture {
+    /// Creates a descriptor for a close operation. The operation waits for the request `request_id`, if any, which
+    /// closes the underlying file descriptor.
+    pub fn new(rt: IoUringRuntime, request_id: Option<RequestId>, qd: QDesc) -> Self {
+        Self { rt, qd, request_id }
+    }
+
+    /// Returns the queue descriptor associated to the target [CloseFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Close Operation Descriptors
+impl Future for CloseFuture {
+    type Output = Result<(), Fail>;
+
+    /// Polls the target [CloseFuture].
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut CloseFuture = self.get_mut();
+        let request_id: RequestId = match self_.request_id {
+            Some(request_id) => request_id,
+            // Other queue descriptors still refer to the socket.
+            None => return Poll::Ready(Ok(())),
+        };
+        match self_.rt.peek(request_id) {
+            // Operation completed.
+            Ok((_, Some(0))) => Poll::Ready(Ok(())),
+            // Operation in progress, wait for its completion.
+            Ok((_, None)) => {
+                self_.rt.wait_completion(request_id, ctx.waker());
+                Poll::Pending
+            },
+            // Underlying asynchronous operation failed.
+            Ok((_, Some(size))) => {
+                let errno: i32 = -size;
+                warn!("close failed ({:?})", errno);
+                Poll::Ready(Err(Fail::new(errno, "operation failed")))
+            },
+            // Operation failed.
+            Err(e) => {
+                warn!("close failed ({:?})", e);
+                Poll::Ready(Err(e))
+            },
+        }
+    }
+}
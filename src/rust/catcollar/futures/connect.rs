@@ -5,20 +5,18 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    QDesc,
-};
-use ::nix::{
-    errno::Errno,
-    sys::{
-        socket,
-        socket::SockaddrStorage,
+use crate::{
+    catcollar::{
+        runtime::RequestId,
+        IoUringRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
     },
 };
 use ::std::{
     future::Future,
-    os::unix::prelude::RawFd,
     pin::Pin,
     task::{
         Context,
@@ -32,12 +30,12 @@ use ::std::{
 
 /// Connect Operation Descriptor
 pub struct ConnectFuture {
+    /// Underlying runtime.
+    rt: IoUringRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
-    // Underlying file descriptor.
-    fd: RawFd,
-    /// Destination address.
-    addr: SockaddrStorage,
+    /// Associated request.
+    request_id: RequestId,
 }
 
 //==============================================================================
@@ -47,8 +45,8 @@ pub struct ConnectFuture {
 /// Associate Functions for Connect Operation Descriptors
 impl ConnectFuture {
     /// Creates a descriptor for a connect operation.
-    pub fn new(qd: QDesc, fd: RawFd, addr: SockaddrStorage) -> Self {
-        Self { qd, fd, addr }
+    pub fn new(rt: IoUringRuntime, request_id: RequestId, qd: QDesc) -> Self {
+        Self { rt, qd, request_id }
     }
 
     /// Returns the queue descriptor associated to the target connect operation
@@ -56,6 +54,11 @@ impl ConnectFuture {
     pub fn get_qd(&self) -> QDesc {
         self.qd
     }
+
+    /// Cancels the underlying request of the target connect operation descriptor, which is no longer polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        self.rt.cancel_request(self.request_id)
+    }
 }
 
 //==============================================================================
@@ -69,22 +72,33 @@ impl Future for ConnectFuture {
     /// Polls the underlying connect operation.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut ConnectFuture = self.get_mut();
-        match socket::connect(self_.fd as i32, &self_.addr) {
+        match self_.rt.peek(self_.request_id) {
             // Operation completed.
-            Ok(()) => {
-                trace!("connection established ({:?})", self_.addr);
+            Ok((_, Some(0))) => {
+                trace!("connection established");
                 Poll::Ready(Ok(()))
             },
-            // Operation not ready yet.
-            Err(errno) if errno == Errno::EINPROGRESS || errno == Errno::EALREADY => {
-                trace!("connect in progress ({:?})", errno);
-                ctx.waker().wake_by_ref();
+            // Operation in progress, wait for its completion.
+            Ok((_, None)) => {
+                trace!("connect in progress");
+                self_.rt.wait_completion(self_.request_id, ctx.waker());
                 Poll::Pending
             },
-            // Operation failed.
-            Err(errno) => {
+            // Underlying asynchronous operation was canceled by its linked timeout.
+            Ok((_, Some(size))) if size == -libc::ECANCELED => {
+                trace!("connect timed out");
+                Poll::Ready(Err(Fail::new(libc::ETIMEDOUT, "operation timed out")))
+            },
+            // Underlying asynchronous operation failed.
+            Ok((_, Some(size))) => {
+                let errno: i32 = -size;
                 warn!("failed to establish connection ({:?})", errno);
-                Poll::Ready(Err(Fail::new(errno as i32, "operation failed")))
+                Poll::Ready(Err(Fail::new(errno, "operation failed")))
+            },
+            // Operation failed.
+            Err(e) => {
+                warn!("failed to establish connection ({:?})", e);
+                Poll::Ready(Err(e))
             },
         }
     }
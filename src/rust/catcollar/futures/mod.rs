@@ -6,10 +6,13 @@
 //==============================================================================
 
 pub mod accept;
+pub mod close;
 pub mod connect;
 pub mod pop;
 pub mod push;
+pub mod pushpop;
 pub mod pushto;
+pub mod splice;
 
 //==============================================================================
 // Imports
@@ -17,14 +20,22 @@ pub mod pushto;
 
 use self::{
     accept::AcceptFuture,
+    close::CloseFuture,
     connect::ConnectFuture,
     pop::PopFuture,
     push::PushFuture,
+    pushpop::PushPopFuture,
     pushto::PushtoFuture,
+    splice::SpliceFuture,
 };
 use crate::{
     inetstack::operations::OperationResult,
-    runtime::QDesc,
+    runtime::{
+        fail::Fail,
+        network::types::SocketAddress,
+        types::DEMI_QR_FLAG_TRUNC,
+        QDesc,
+    },
     scheduler::{
         FutureResult,
         SchedulerFuture,
@@ -49,14 +60,20 @@ use ::std::{
 pub enum Operation {
     /// Accept operation.
     Accept(FutureResult<AcceptFuture>),
+    /// Close operation.
+    Close(FutureResult<CloseFuture>),
     /// Connection operation
     Connect(FutureResult<ConnectFuture>),
     /// Push operation
     Push(FutureResult<PushFuture>),
     /// Pushto operation.
     Pushto(FutureResult<PushtoFuture>),
+    /// Push-pop operation.
+    PushPop(FutureResult<PushPopFuture>),
     /// Pop operation.
     Pop(FutureResult<PopFuture>),
+    /// Splice operation.
+    Splice(FutureResult<SpliceFuture>),
 }
 
 //==============================================================================
@@ -88,6 +105,16 @@ impl Operation {
                 OperationResult::Failed(e),
             ),
 
+            // Close operation.
+            Operation::Close(FutureResult {
+                future,
+                done: Some(Ok(())),
+            }) => (future.get_qd(), None, None, OperationResult::Close),
+            Operation::Close(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             // Connect operation.
             Operation::Connect(FutureResult {
                 future,
@@ -121,16 +148,67 @@ impl Operation {
             // Pop operation.
             Operation::Pop(FutureResult {
                 future,
-                done: Some(Ok((addr, buf))),
-            }) => (future.get_qd(), None, None, OperationResult::Pop(addr, buf)),
+                done: Some(Ok((addr, buf, truncated))),
+            }) => {
+                let flags: u32 = if truncated { DEMI_QR_FLAG_TRUNC } else { 0 };
+                (
+                    future.get_qd(),
+                    None,
+                    None,
+                    OperationResult::Pop(addr.map(SocketAddress::from), buf, flags, None),
+                )
+            },
             Operation::Pop(FutureResult {
                 future,
                 done: Some(Err(e)),
             }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
 
+            // Push-pop operation.
+            Operation::PushPop(FutureResult {
+                future,
+                done: Some(Ok((addr, buf, truncated))),
+            }) => {
+                let flags: u32 = if truncated { DEMI_QR_FLAG_TRUNC } else { 0 };
+                (
+                    future.get_qd(),
+                    None,
+                    None,
+                    OperationResult::Pop(addr.map(SocketAddress::from), buf, flags, None),
+                )
+            },
+            Operation::PushPop(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
+            // Splice operation.
+            Operation::Splice(FutureResult {
+                future,
+                done: Some(Ok(nbytes)),
+            }) => (future.get_qd(), None, None, OperationResult::Splice(nbytes)),
+            Operation::Splice(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             _ => panic!("future not ready"),
         }
     }
+
+    /// Cancels the requests that the target [Operation] has in flight in the underlying I/O user ring, once it was
+    /// aborted. Closes have no such request.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        match self {
+            Operation::Accept(FutureResult { future, done: None }) => future.cancel(),
+            Operation::Connect(FutureResult { future, done: None }) => future.cancel(),
+            Operation::Push(FutureResult { future, done: None }) => future.cancel(),
+            Operation::Pushto(FutureResult { future, done: None }) => future.cancel(),
+            Operation::PushPop(FutureResult { future, done: None }) => future.cancel(),
+            Operation::Pop(FutureResult { future, done: None }) => future.cancel(),
+            Operation::Splice(FutureResult { future, done: None }) => future.cancel(),
+            _ => Ok(()),
+        }
+    }
 }
 
 //==============================================================================
@@ -146,6 +224,32 @@ impl SchedulerFuture for Operation {
     fn get_future(&self) -> &dyn Future<Output = ()> {
         todo!()
     }
+
+    fn get_kind(&self) -> &'static str {
+        match self {
+            Operation::Accept(_) => "Accept",
+            Operation::Close(_) => "Close",
+            Operation::Connect(_) => "Connect",
+            Operation::Push(_) => "Push",
+            Operation::Pushto(_) => "Pushto",
+            Operation::PushPop(_) => "PushPop",
+            Operation::Pop(_) => "Pop",
+            Operation::Splice(_) => "Splice",
+        }
+    }
+
+    fn get_qd(&self) -> Option<QDesc> {
+        match self {
+            Operation::Accept(f) => Some(f.future.get_qd()),
+            Operation::Close(f) => Some(f.future.get_qd()),
+            Operation::Connect(f) => Some(f.future.get_qd()),
+            Operation::Push(f) => Some(f.future.get_qd()),
+            Operation::Pushto(f) => Some(f.future.get_qd()),
+            Operation::PushPop(f) => Some(f.future.get_qd()),
+            Operation::Pop(f) => Some(f.future.get_qd()),
+            Operation::Splice(f) => Some(f.future.get_qd()),
+        }
+    }
 }
 
 /// Future Trait Implementation for Operation Descriptors
@@ -156,10 +260,13 @@ impl Future for Operation {
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.get_mut() {
             Operation::Accept(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Close(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Connect(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Push(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Pushto(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Pop(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::PushPop(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Splice(ref mut f) => Future::poll(Pin::new(f), ctx),
         }
     }
 }
@@ -171,6 +278,13 @@ impl From<AcceptFuture> for Operation {
     }
 }
 
+/// From Trait Implementation for Operation Descriptors
+impl From<CloseFuture> for Operation {
+    fn from(f: CloseFuture) -> Self {
+        Operation::Close(FutureResult::new(f, None))
+    }
+}
+
 /// From Trait Implementation for Operation Descriptors
 impl From<ConnectFuture> for Operation {
     fn from(f: ConnectFuture) -> Self {
@@ -198,3 +312,17 @@ impl From<PopFuture> for Operation {
         Operation::Pop(FutureResult::new(f, None))
     }
 }
+
+/// From Trait Implementation for Operation Descriptors
+impl From<PushPopFuture> for Operation {
+    fn from(f: PushPopFuture) -> Self {
+        Operation::PushPop(FutureResult::new(f, None))
+    }
+}
+
+/// From Trait Implementation for Operation Descriptors
+impl From<SpliceFuture> for Operation {
+    fn from(f: SpliceFuture) -> Self {
+        Operation::Splice(FutureResult::new(f, None))
+    }
+}
@@ -62,6 +62,11 @@ impl PopFuture {
     pub fn get_qd(&self) -> QDesc {
         self.qd
     }
+
+    /// Cancels the underlying request of the target pop operation descriptor, which is no longer polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        self.rt.cancel_request(self.request_id)
+    }
 }
 
 //==============================================================================
@@ -70,28 +75,33 @@ impl PopFuture {
 
 /// Future Trait Implementation for Pop Operation Descriptors
 impl Future for PopFuture {
-    type Output = Result<(Option<SocketAddrV4>, DemiBuffer), Fail>;
+    type Output = Result<(Option<SocketAddrV4>, DemiBuffer, bool), Fail>;
 
-    /// Polls the underlying pop operation.
+    /// Polls the underlying pop operation. The output also tells whether the received datagram was truncated.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PopFuture = self.get_mut();
-        match self_.rt.peek(self_.request_id) {
+        match self_.rt.peek_msg(self_.request_id) {
             // Operation completed.
-            Ok((addr, Some(size))) if size >= 0 => {
+            Ok((addr, Some(size), flags)) if size >= 0 => {
                 trace!("data received ({:?} bytes)", size);
                 let trim_size: usize = self_.buf.len() - (size as usize);
                 let mut buf: DemiBuffer = self_.buf.clone();
                 buf.trim(trim_size);
-                Poll::Ready(Ok((addr, buf)))
+                Poll::Ready(Ok((addr, buf, flags & libc::MSG_TRUNC != 0)))
             },
-            // Operation in progress, re-schedule future.
-            Ok((_, None)) => {
+            // Operation in progress, wait for its completion.
+            Ok((_, None, _)) => {
                 trace!("pop in progress");
-                ctx.waker().wake_by_ref();
+                self_.rt.wait_completion(self_.request_id, ctx.waker());
                 Poll::Pending
             },
+            // Underlying asynchronous operation was canceled by its linked timeout.
+            Ok((_, Some(size), _)) if size == -libc::ECANCELED => {
+                trace!("pop timed out");
+                Poll::Ready(Err(Fail::new(libc::ETIMEDOUT, "operation timed out")))
+            },
             // Underlying asynchronous operation failed.
-            Ok((_, Some(size))) if size < 0 => {
+            Ok((_, Some(size), _)) if size < 0 => {
                 let errno: i32 = -size;
                 warn!("pop failed ({:?})", errno);
                 Poll::Ready(Err(Fail::new(errno, "I/O error")))
@@ -12,11 +12,13 @@ use crate::{
     },
     runtime::{
         fail::Fail,
+        memory::DemiBuffer,
         QDesc,
     },
 };
 use ::std::{
     future::Future,
+    os::unix::prelude::RawFd,
     pin::Pin,
     task::{
         Context,
@@ -34,6 +36,10 @@ pub struct PushFuture {
     rt: IoUringRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
+    /// Underlying file descriptor.
+    fd: RawFd,
+    /// Buffers that remain to be sent.
+    bufs: Vec<DemiBuffer>,
     /// Associated request.
     request_id: RequestId,
 }
@@ -44,15 +50,26 @@ pub struct PushFuture {
 
 /// Associate Functions for Push Operation Descriptors
 impl PushFuture {
-    /// Creates a descriptor for a push operation.
-    pub fn new(rt: IoUringRuntime, request_id: RequestId, qd: QDesc) -> Self {
-        Self { rt, request_id, qd }
+    /// Creates a descriptor for a push operation, whose request `request_id` sends `bufs` on `fd`.
+    pub fn new(rt: IoUringRuntime, qd: QDesc, fd: RawFd, bufs: Vec<DemiBuffer>, request_id: RequestId) -> Self {
+        Self {
+            rt,
+            qd,
+            fd,
+            bufs,
+            request_id,
+        }
     }
 
     /// Returns the queue descriptor associated to the target push operation descriptor.
     pub fn get_qd(&self) -> QDesc {
         self.qd
     }
+
+    /// Cancels the underlying request of the target push operation descriptor, which is no longer polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        self.rt.cancel_request(self.request_id)
+    }
 }
 
 //==============================================================================
@@ -63,34 +80,183 @@ impl PushFuture {
 impl Future for PushFuture {
     type Output = Result<(), Fail>;
 
-    /// Polls the underlying push operation.
+    /// Polls the underlying push operation. Writes that fall short are resumed from where they stopped.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PushFuture = self.get_mut();
-        match self_.rt.peek(self_.request_id) {
-            // Operation completed.
-            Ok((_, Some(size))) if size >= 0 => {
-                trace!("data pushed ({:?} bytes)", size);
-                Poll::Ready(Ok(()))
-            },
-            // Operation in progress, re-schedule future.
-            Ok((None, None)) => {
-                trace!("push in progress");
-                ctx.waker().wake_by_ref();
-                Poll::Pending
-            },
-            // Underlying asynchronous operation failed.
-            Ok((None, Some(size))) if size < 0 => {
-                let errno: i32 = -size;
-                warn!("push failed ({:?})", errno);
-                Poll::Ready(Err(Fail::new(errno, "I/O error")))
-            },
-            // Operation failed.
-            Err(e) => {
-                warn!("push failed ({:?})", e);
-                Poll::Ready(Err(e))
+        loop {
+            match self_.rt.peek(self_.request_id) {
+                // Operation completed.
+                Ok((_, Some(size))) if size >= 0 => {
+                    trace!("data pushed ({:?} bytes)", size);
+                    if let Err(e) = consume(&mut self_.bufs, size as usize) {
+                        warn!("push failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    }
+                    if self_.bufs.is_empty() {
+                        return Poll::Ready(Ok(()));
+                    }
+                    // The write was short, so push the remaining bytes.
+                    match self_.rt.pushv(self_.fd, &self_.bufs) {
+                        Ok(request_id) => self_.request_id = request_id,
+                        Err(e) => {
+                            warn!("push failed ({:?})", e);
+                            return Poll::Ready(Err(e));
+                        },
+                    }
+                },
+                // Operation in progress, wait for its completion.
+                Ok((None, None)) => {
+                    trace!("push in progress");
+                    self_.rt.wait_completion(self_.request_id, ctx.waker());
+                    return Poll::Pending;
+                },
+                // Underlying asynchronous operation failed.
+                Ok((None, Some(size))) if size < 0 => {
+                    let errno: i32 = -size;
+                    warn!("push failed ({:?})", errno);
+                    return Poll::Ready(Err(Fail::new(errno, "I/O error")));
+                },
+                // Operation failed.
+                Err(e) => {
+                    warn!("push failed ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+                // Should not happen.
+                _ => panic!("push failed: unknown error"),
+            }
+        }
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Removes the first `nbytes` bytes of `bufs`, which were sent.
+fn consume(bufs: &mut Vec<DemiBuffer>, mut nbytes: usize) -> Result<(), Fail> {
+    while let Some(buf) = bufs.first_mut() {
+        if nbytes < buf.len() {
+            return buf.adjust(nbytes);
+        }
+        nbytes -= buf.len();
+        bufs.remove(0);
+    }
+    Ok(())
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        consume,
+        PushFuture,
+    };
+    use crate::{
+        catcollar::{
+            runtime::RequestId,
+            IoUringRuntime,
+        },
+        runtime::{
+            memory::DemiBuffer,
+            QDesc,
+        },
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        ffi::c_void,
+        future::Future,
+        os::unix::prelude::RawFd,
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+    };
+
+    #[test]
+    fn consume_drops_sent_bytes() {
+        let mut bufs: Vec<DemiBuffer> = vec![
+            DemiBuffer::from_slice(&[1, 2, 3]).unwrap(),
+            DemiBuffer::from_slice(&[4, 5]).unwrap(),
+        ];
+
+        // A write that stops within a buffer resumes from there.
+        consume(&mut bufs, 1).unwrap();
+        assert_eq!(bufs.len(), 2);
+        assert_eq!(&bufs[0][..], &[2, 3]);
+
+        // A write that stops at the end of a buffer resumes from the next one.
+        consume(&mut bufs, 2).unwrap();
+        assert_eq!(bufs.len(), 1);
+        assert_eq!(&bufs[0][..], &[4, 5]);
+
+        consume(&mut bufs, 2).unwrap();
+        assert!(bufs.is_empty());
+    }
+
+    /// Pushes more data than the send buffer of the socket takes at once, so that writes fall short and are resumed.
+    #[test]
+    fn pushv_resumes_short_writes() {
+        let mut rt: IoUringRuntime = IoUringRuntime::new(None, None, 32);
+        let mut fds: [libc::c_int; 2] = [0; 2];
+        assert_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+        let (fd, reader): (RawFd, RawFd) = (fds[0], fds[1]);
+        let sndbuf: libc::c_int = 4096;
+        assert_eq!(
+            unsafe {
+                libc::setsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_SNDBUF,
+                    &sndbuf as *const libc::c_int as *const c_void,
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                )
             },
-            // Should not happen.
-            _ => panic!("push failed: unknown error"),
+            0
+        );
+
+        let data: Vec<u8> = (0..(96 * 1024)).map(|i: usize| i as u8).collect();
+        let bufs: Vec<DemiBuffer> = data
+            .chunks(32 * 1024)
+            .map(|chunk: &[u8]| DemiBuffer::from_slice(chunk).unwrap())
+            .collect();
+        let request_id: RequestId = rt.pushv(fd, &bufs).unwrap();
+        let mut push_future: PushFuture = PushFuture::new(rt, QDesc::from(0), fd, bufs, request_id);
+
+        let mut received: Vec<u8> = Vec::with_capacity(data.len());
+        let mut chunk: [u8; 4096] = [0; 4096];
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        loop {
+            let done: bool = match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+                Poll::Ready(Ok(())) => true,
+                Poll::Ready(Err(e)) => panic!("push failed ({:?})", e),
+                Poll::Pending => false,
+            };
+            let n: isize = unsafe {
+                libc::recv(
+                    reader,
+                    chunk.as_mut_ptr() as *mut c_void,
+                    chunk.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if n > 0 {
+                received.extend_from_slice(&chunk[..n as usize]);
+            }
+            if done && received.len() == data.len() {
+                break;
+            }
+        }
+        assert!(received == data);
+
+        for fd in [fd, reader] {
+            unsafe { libc::close(fd) };
         }
     }
 }
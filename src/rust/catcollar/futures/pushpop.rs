@@ -0,0 +1,248 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catcollar::{
+        runtime::RequestId,
+        IoUringRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+};
+use ::std::{
+    future::Future,
+    net::SocketAddrV4,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Push-Pop Operation Descriptor
+///
+/// Pushes a request and pops its response, through a push and a pop that are linked in the underlying I/O user ring.
+pub struct PushPopFuture {
+    /// Underlying runtime.
+    rt: IoUringRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    /// Associated receive buffer.
+    buf: DemiBuffer,
+    /// Associated push request.
+    push_id: RequestId,
+    /// Number of bytes that the push request sends.
+    push_len: usize,
+    /// Associated pop request.
+    pop_id: RequestId,
+    /// Outcome of the push request, once it completed.
+    push_result: Option<i32>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Push-Pop Operation Descriptors
+impl PushPopFuture {
+    /// Creates a descriptor for a push-pop operation, whose push request `push_id` sends `push_len` bytes.
+    pub fn new(
+        rt: IoUringRuntime,
+        push_id: RequestId,
+        push_len: usize,
+        pop_id: RequestId,
+        qd: QDesc,
+        buf: DemiBuffer,
+    ) -> Self {
+        Self {
+            rt,
+            qd,
+            buf,
+            push_id,
+            push_len,
+            pop_id,
+            push_result: None,
+        }
+    }
+
+    /// Returns the queue descriptor associated to the target push-pop operation descriptor.
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+
+    /// Cancels the underlying requests of the target push-pop operation descriptor, which is no longer polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        // Cancel both requests, even if canceling the push fails, and report the first error.
+        let push_result: Result<(), Fail> = match self.push_result {
+            None => self.rt.cancel_request(self.push_id),
+            Some(_) => Ok(()),
+        };
+        let pop_result: Result<(), Fail> = self.rt.cancel_request(self.pop_id);
+        push_result.and(pop_result)
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Push-Pop Operation Descriptors
+impl Future for PushPopFuture {
+    type Output = Result<(Option<SocketAddrV4>, DemiBuffer, bool), Fail>;
+
+    /// Polls the underlying push-pop operation. The push is waited for first, since the kernel cancels the pop if the
+    /// push fails. The output is the one of the pop.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut PushPopFuture = self.get_mut();
+
+        // Wait for the push to complete.
+        if self_.push_result.is_none() {
+            match self_.rt.peek(self_.push_id) {
+                // The push fell short, either because the kernel gave up on sending all of the buffer, in which case it
+                // canceled the pop, or because it does not honor MSG_WAITALL on sends. Either way, a response to a
+                // partial request is not waited for.
+                Ok((_, Some(size))) if size >= 0 && (size as usize) < self_.push_len => {
+                    self_.push_result = Some(size);
+                    warn!("push was short ({:?}/{:?} bytes)", size, self_.push_len);
+                    if let Err(e) = self_.rt.cancel_request(self_.pop_id) {
+                        warn!("cannot cancel pop ({:?})", e);
+                    }
+                    return Poll::Ready(Err(Fail::new(libc::EIO, "partial push")));
+                },
+                Ok((_, Some(size))) => self_.push_result = Some(size),
+                Ok((_, None)) => {
+                    trace!("push in progress");
+                    self_.rt.wait_completion(self_.push_id, ctx.waker());
+                    return Poll::Pending;
+                },
+                Err(e) => {
+                    warn!("push failed ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+            }
+        }
+
+        // Wait for the pop to complete.
+        match self_.rt.peek_msg(self_.pop_id) {
+            // Operation completed.
+            Ok((addr, Some(size), flags)) if size >= 0 => {
+                trace!("data received ({:?} bytes)", size);
+                let trim_size: usize = self_.buf.len() - (size as usize);
+                let mut buf: DemiBuffer = self_.buf.clone();
+                buf.trim(trim_size)?;
+                Poll::Ready(Ok((addr, buf, flags & libc::MSG_TRUNC != 0)))
+            },
+            // Operation in progress, wait for its completion.
+            Ok((_, None, _)) => {
+                trace!("pop in progress");
+                self_.rt.wait_completion(self_.pop_id, ctx.waker());
+                Poll::Pending
+            },
+            // Underlying asynchronous operation failed.
+            Ok((_, Some(size), _)) => match self_.push_result {
+                // The push failed, thus the kernel canceled the pop.
+                Some(push_size) if push_size < 0 => {
+                    let errno: i32 = -push_size;
+                    warn!("push failed ({:?})", errno);
+                    Poll::Ready(Err(Fail::new(errno, "I/O error")))
+                },
+                _ => {
+                    let errno: i32 = -size;
+                    warn!("pop failed ({:?})", errno);
+                    Poll::Ready(Err(Fail::new(errno, "I/O error")))
+                },
+            },
+            // Operation failed.
+            Err(e) => {
+                warn!("pop failed ({:?})", e);
+                Poll::Ready(Err(e))
+            },
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::PushPopFuture;
+    use crate::{
+        catcollar::{
+            runtime::RequestId,
+            IoUringRuntime,
+        },
+        runtime::{
+            memory::DemiBuffer,
+            QDesc,
+        },
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        ffi::c_void,
+        future::Future,
+        os::unix::prelude::RawFd,
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+    };
+
+    #[test]
+    fn pushpop_pops_the_response() {
+        let mut rt: IoUringRuntime = IoUringRuntime::new(None, None, 32);
+        let mut fds: [libc::c_int; 2] = [0; 2];
+        assert_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+        let (client, server): (RawFd, RawFd) = (fds[0], fds[1]);
+
+        let request: DemiBuffer = DemiBuffer::from_slice(b"request").unwrap();
+        let recvbuf: DemiBuffer = DemiBuffer::new(64);
+        let (push_id, pop_id): (RequestId, RequestId) = rt.pushpop(client, request, recvbuf.clone()).unwrap();
+        let mut pushpop_future: PushPopFuture = PushPopFuture::new(rt, push_id, 7, pop_id, QDesc::from(0), recvbuf);
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        assert!(Future::poll(Pin::new(&mut pushpop_future), &mut ctx).is_pending());
+
+        // The server gets the request and answers it.
+        let mut chunk: [u8; 64] = [0; 64];
+        let n: isize = unsafe { libc::recv(server, chunk.as_mut_ptr() as *mut c_void, chunk.len(), 0) };
+        assert_eq!(&chunk[..n as usize], b"request");
+        let response: &[u8] = b"response";
+        assert_eq!(
+            unsafe { libc::send(server, response.as_ptr() as *const c_void, response.len(), 0) },
+            response.len() as isize
+        );
+
+        // The operation completes with the response.
+        loop {
+            match Future::poll(Pin::new(&mut pushpop_future), &mut ctx) {
+                Poll::Ready(Ok((_, buf, truncated))) => {
+                    assert_eq!(&buf[..], response);
+                    assert!(!truncated);
+                    break;
+                },
+                Poll::Ready(Err(e)) => panic!("pushpop failed ({:?})", e),
+                Poll::Pending => (),
+            }
+        }
+
+        for fd in [client, server] {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
@@ -53,6 +53,11 @@ impl PushtoFuture {
     pub fn get_qd(&self) -> QDesc {
         self.qd
     }
+
+    /// Cancels the underlying request of the target pushto operation descriptor, which is no longer polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        self.rt.cancel_request(self.request_id)
+    }
 }
 
 //==============================================================================
@@ -72,10 +77,10 @@ impl Future for PushtoFuture {
                 trace!("data pushed ({:?} bytes)", size);
                 Poll::Ready(Ok(()))
             },
-            // Operation in progress, re-schedule future.
+            // Operation in progress, wait for its completion.
             Ok((None, None)) => {
                 trace!("push in progress");
-                ctx.waker().wake_by_ref();
+                self_.rt.wait_completion(self_.request_id, ctx.waker());
                 Poll::Pending
             },
             // Underlying asynchronous operation failed.
@@ -0,0 +1,294 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catcollar::{
+        runtime::RequestId,
+        IoUringRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+};
+use ::std::{
+    future::Future,
+    os::unix::prelude::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// State of a Splice Operation
+enum SpliceState {
+    /// No request is in flight.
+    Idle,
+    /// Waiting for data to be received into the associated buffer.
+    Popping(RequestId, DemiBuffer),
+    /// Waiting for received data in the associated buffer to be sent.
+    Pushing(RequestId, DemiBuffer),
+}
+
+/// Splice Operation Descriptor
+pub struct SpliceFuture {
+    /// Underlying runtime.
+    rt: IoUringRuntime,
+    /// Queue descriptor of the source socket.
+    qd: QDesc,
+    /// Underlying file descriptor of the source socket.
+    src: RawFd,
+    /// Underlying file descriptor of the destination socket.
+    dst: RawFd,
+    /// Size of receive buffers.
+    size: u16,
+    /// Current state.
+    state: SpliceState,
+    /// Number of bytes forwarded so far.
+    nbytes: usize,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Splice Operation Descriptors
+impl SpliceFuture {
+    /// Creates a descriptor for a splice operation. Data is received into buffers of `size` bytes.
+    pub fn new(rt: IoUringRuntime, qd: QDesc, src: RawFd, dst: RawFd, size: u16) -> Self {
+        Self {
+            rt,
+            qd,
+            src,
+            dst,
+            size,
+            state: SpliceState::Idle,
+            nbytes: 0,
+        }
+    }
+
+    /// Returns the queue descriptor associated to the target splice operation descriptor.
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+
+    /// Cancels the request that the target splice operation descriptor has in flight, if any, since it is no longer
+    /// polled.
+    pub fn cancel(&mut self) -> Result<(), Fail> {
+        match self.state {
+            SpliceState::Idle => Ok(()),
+            SpliceState::Popping(request_id, _) | SpliceState::Pushing(request_id, _) => {
+                self.state = SpliceState::Idle;
+                self.rt.cancel_request(request_id)
+            },
+        }
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Splice Operation Descriptors
+impl Future for SpliceFuture {
+    type Output = Result<usize, Fail>;
+
+    /// Polls the underlying splice operation. Data is forwarded until the source socket reaches end-of-stream.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut SpliceFuture = self.get_mut();
+        loop {
+            match self_.state {
+                // Receive more data.
+                SpliceState::Idle => {
+                    let buf: DemiBuffer = DemiBuffer::new(self_.size);
+                    let request_id: RequestId = self_.rt.pop(self_.src, buf.clone(), None)?;
+                    self_.state = SpliceState::Popping(request_id, buf);
+                },
+                SpliceState::Popping(request_id, ref buf) => match self_.rt.peek(request_id) {
+                    // End of stream.
+                    Ok((_, Some(size))) if size == 0 => return Poll::Ready(Ok(self_.nbytes)),
+                    // Data received, so forward it.
+                    Ok((_, Some(size))) if size > 0 => {
+                        trace!("data received ({:?} bytes)", size);
+                        let mut buf: DemiBuffer = buf.clone();
+                        buf.trim(buf.len() - (size as usize))?;
+                        let request_id: RequestId = self_.rt.push(self_.dst, buf.clone())?;
+                        self_.state = SpliceState::Pushing(request_id, buf);
+                    },
+                    // Operation in progress, wait for its completion.
+                    Ok((_, None)) => {
+                        self_.rt.wait_completion(request_id, ctx.waker());
+                        return Poll::Pending;
+                    },
+                    // Underlying asynchronous operation failed.
+                    Ok((_, Some(size))) => {
+                        let errno: i32 = -size;
+                        warn!("splice failed ({:?})", errno);
+                        return Poll::Ready(Err(Fail::new(errno, "I/O error")));
+                    },
+                    // Operation failed.
+                    Err(e) => {
+                        warn!("splice failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    },
+                },
+                SpliceState::Pushing(request_id, ref buf) => match self_.rt.peek(request_id) {
+                    // Data sent, so push what is left or receive more.
+                    Ok((_, Some(size))) if size >= 0 => {
+                        trace!("data forwarded ({:?} bytes)", size);
+                        self_.nbytes += size as usize;
+                        if (size as usize) < buf.len() {
+                            let mut buf: DemiBuffer = buf.clone();
+                            buf.adjust(size as usize)?;
+                            let request_id: RequestId = self_.rt.push(self_.dst, buf.clone())?;
+                            self_.state = SpliceState::Pushing(request_id, buf);
+                        } else {
+                            self_.state = SpliceState::Idle;
+                        }
+                    },
+                    // Operation in progress, wait for its completion.
+                    Ok((_, None)) => {
+                        self_.rt.wait_completion(request_id, ctx.waker());
+                        return Poll::Pending;
+                    },
+                    // Underlying asynchronous operation failed.
+                    Ok((_, Some(size))) => {
+                        let errno: i32 = -size;
+                        warn!("splice failed ({:?})", errno);
+                        return Poll::Ready(Err(Fail::new(errno, "I/O error")));
+                    },
+                    // Operation failed.
+                    Err(e) => {
+                        warn!("splice failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    },
+                },
+            }
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::SpliceFuture;
+    use crate::{
+        catcollar::IoUringRuntime,
+        runtime::QDesc,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        ffi::c_void,
+        future::Future,
+        os::unix::prelude::RawFd,
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+    };
+
+    /// Creates a pair of connected stream sockets.
+    fn socketpair() -> (RawFd, RawFd) {
+        let mut fds: [libc::c_int; 2] = [0; 2];
+        assert_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+        (fds[0], fds[1])
+    }
+
+    /// Forwards data through a destination socket with a small send buffer, which is drained as data goes, so that
+    /// pushes fall short of the received data and their unsent tail has to be pushed again.
+    #[test]
+    fn splice_forwards_everything_in_order() {
+        let rt: IoUringRuntime = IoUringRuntime::new(None, None, 32);
+        let (writer, src): (RawFd, RawFd) = socketpair();
+        let (dst, reader): (RawFd, RawFd) = socketpair();
+        let sndbuf: libc::c_int = 4096;
+        assert_eq!(
+            unsafe {
+                libc::setsockopt(
+                    dst,
+                    libc::SOL_SOCKET,
+                    libc::SO_SNDBUF,
+                    &sndbuf as *const libc::c_int as *const c_void,
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                )
+            },
+            0
+        );
+
+        let data: Vec<u8> = (0..(256 * 1024)).map(|i: usize| i as u8).collect();
+        let mut received: Vec<u8> = Vec::with_capacity(data.len());
+        let mut written: usize = 0;
+        let mut chunk: [u8; 4096] = [0; 4096];
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        let mut splice_future: SpliceFuture = SpliceFuture::new(rt, QDesc::from(0), src, dst, 16384);
+        let nbytes: usize = loop {
+            // Feed the source socket, and signal end-of-stream once everything is written.
+            if written < data.len() {
+                let remaining: &[u8] = &data[written..];
+                let n: isize = unsafe {
+                    libc::send(
+                        writer,
+                        remaining.as_ptr() as *const c_void,
+                        remaining.len(),
+                        libc::MSG_DONTWAIT,
+                    )
+                };
+                if n > 0 {
+                    written += n as usize;
+                    if written == data.len() {
+                        assert_eq!(unsafe { libc::shutdown(writer, libc::SHUT_WR) }, 0);
+                    }
+                }
+            }
+
+            match Future::poll(Pin::new(&mut splice_future), &mut ctx) {
+                Poll::Ready(Ok(nbytes)) => break nbytes,
+                Poll::Ready(Err(e)) => panic!("splice failed ({:?})", e),
+                Poll::Pending => (),
+            }
+
+            // Drain the destination socket.
+            let n: isize = unsafe {
+                libc::recv(
+                    reader,
+                    chunk.as_mut_ptr() as *mut c_void,
+                    chunk.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if n > 0 {
+                received.extend_from_slice(&chunk[..n as usize]);
+            }
+        };
+        assert_eq!(nbytes, data.len());
+
+        // Read what is left in the destination socket.
+        while received.len() < data.len() {
+            let n: isize = unsafe { libc::recv(reader, chunk.as_mut_ptr() as *mut c_void, chunk.len(), 0) };
+            assert!(n > 0);
+            received.extend_from_slice(&chunk[..n as usize]);
+        }
+        assert!(received == data);
+
+        for fd in [writer, src, dst, reader] {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
@@ -10,7 +10,6 @@ use crate::runtime::{
     liburing,
     memory::DemiBuffer,
 };
-use ::libc::socklen_t;
 use ::nix::{
     errno,
     sys::socket::{
@@ -20,11 +19,15 @@ use ::nix::{
     },
 };
 use ::std::{
+    collections::HashMap,
     ffi::{
         c_void,
         CString,
     },
-    mem::MaybeUninit,
+    mem::{
+        self,
+        MaybeUninit,
+    },
     os::{
         raw::c_int,
         unix::prelude::RawFd,
@@ -34,40 +37,413 @@ use ::std::{
         null_mut,
     },
     rc::Rc,
+    time::Duration,
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Setup flag that makes a kernel thread poll the submission queue (see `IORING_SETUP_SQPOLL` in Linux).
+const IORING_SETUP_SQPOLL: u32 = 1 << 1;
+
+/// Setup flag that promises that a single task submits requests (see `IORING_SETUP_SINGLE_ISSUER` in Linux).
+const IORING_SETUP_SINGLE_ISSUER: u32 = 1 << 12;
+
+/// Flag of the submission queue that is set once its polling thread went to sleep (see `IORING_SQ_NEED_WAKEUP` in
+/// Linux).
+const IORING_SQ_NEED_WAKEUP: u32 = 1 << 0;
+
+/// Flags of `io_uring_enter()` that wake up the polling thread, and that wait for room in the submission queue (see
+/// `IORING_ENTER_SQ_WAKEUP` and `IORING_ENTER_SQ_WAIT` in Linux).
+const IORING_ENTER_SQ_WAKEUP: u32 = 1 << 1;
+const IORING_ENTER_SQ_WAIT: u32 = 1 << 2;
+
+/// Opcode of `io_uring_register()` that makes the ring busy poll the network devices of its sockets while waiting for
+/// completions (see `IORING_REGISTER_NAPI` in Linux).
+const IORING_REGISTER_NAPI: u32 = 27;
+
+/// Opcode of `io_uring_register()` that makes the ring signal an event file descriptor whenever it posts a completion
+/// (see `IORING_REGISTER_EVENTFD` in Linux).
+const IORING_REGISTER_EVENTFD: u32 = 4;
+
+/// Opcode of zero-copy message sends (see `IORING_OP_SENDMSG_ZC` in Linux). It behaves as `IORING_OP_SEND_ZC`, but
+/// takes a message header, as all other operations of this ring do.
+const IORING_OP_SENDMSG_ZC: u8 = 48;
+
+/// Flag of completion queue entries that are followed by another entry for the same request (see `IORING_CQE_F_MORE`
+/// in Linux).
+const IORING_CQE_F_MORE: u32 = 1 << 1;
+
+/// Flag of completion queue entries that notify that the kernel is done with the buffers of a zero-copy send (see
+/// `IORING_CQE_F_NOTIF` in Linux).
+const IORING_CQE_F_NOTIF: u32 = 1 << 3;
+
 //==============================================================================
 // Structures
 //==============================================================================
 
+/// Request that is handed to the kernel. It owns its message header, along with everything that the header and the
+/// submission queue entries point to, since the kernel may only read them after the submission returns, once the
+/// thread that polls the submission queue (SQPOLL) picks up the entries.
+#[repr(C)]
+pub struct Request {
+    /// Message header, which comes first so that requests are identified by a pointer to their header.
+    pub msg: liburing::msghdr,
+    /// I/O vectors that the message header points to.
+    iovs: Vec<liburing::iovec>,
+    /// Address that the message header points to, if any.
+    addr: Option<libc::sockaddr_in>,
+    /// Timeout that the linked timeout of the request points to, if any.
+    timeout: Option<liburing::__kernel_timespec>,
+    /// Buffers that the I/O vectors point to. They are held until the request is released, which, for zero-copy
+    /// sends, is only once the kernel notifies that it no longer reads them.
+    _bufs: Vec<DemiBuffer>,
+}
+
+/// Busy polling parameters of an IO user ring (see `struct io_uring_napi` in Linux).
+#[repr(C)]
+struct IoUringNapi {
+    /// Time for which the network device is busy polled, in microseconds.
+    busy_poll_to: u32,
+    /// Whether the network device should keep its interrupts masked while busy polled.
+    prefer_busy_poll: u8,
+    pad: [u8; 3],
+    resv: u64,
+}
+
 /// IO User Ring
+///
+/// When the submission queue is polled by a kernel thread (SQPOLL), submitting requests takes no system call, unless
+/// the thread went to sleep after having been idle for a while. `io_uring_submit()` then wakes it up.
+///
+/// Operations are not submitted one at a time. Their entries are queued in the submission queue, and submitted
+/// together with a single `io_uring_enter()` once `batch_size` of them are queued, or once the ring is waited on.
+/// Completions are reaped in batches of up to `batch_size` entries as well.
+///
+/// Sockets may be registered as fixed files of the ring. Operations then refer to them by their slot in the table of
+/// registered files, which spares the kernel from looking up, and taking a reference on, the file of each operation.
+///
+/// Pushes of at least `send_zc_threshold` bytes are zero-copy sends, which complete in two phases: the kernel first
+/// reports the outcome of the send, and later notifies that it is done with the buffers. The push is only reported as
+/// complete once that notification arrives.
 pub struct IoUring {
     /// Underlying io_uring.
     io_uring: liburing::io_uring,
+    /// Size from which pushes are zero-copy sends, if any.
+    send_zc_threshold: Option<usize>,
+    /// Zero-copy sends whose notification has not arrived yet.
+    zc_sends: ZeroCopySends,
+    /// Maximum number of entries that are submitted or reaped at once.
+    batch_size: usize,
+    /// Number of entries that are queued in the submission queue but not submitted yet.
+    unsubmitted: usize,
+    /// Completion queue entries of the last batch that was reaped.
+    cqes: Vec<*mut liburing::io_uring_cqe>,
+    /// Slots of registered files, keyed by file descriptor.
+    files: HashMap<RawFd, u32>,
+    /// Free slots in the table of registered files, or `None` if files cannot be registered.
+    free_files: Option<Vec<u32>>,
+    /// Event file descriptor that is signaled on every completion, if any.
+    eventfd: Option<RawFd>,
+}
+
+/// Zero-copy sends whose notification has not arrived yet, along with the outcome that the kernel reported for each of
+/// them. Their completion is held back until the notification, so that applications do not reuse their buffers while
+/// the kernel may still read them.
+#[derive(Default)]
+struct ZeroCopySends {
+    /// Outcome of each send, keyed by its request.
+    outcomes: HashMap<*mut liburing::msghdr, i32>,
 }
 
 //==============================================================================
 // Associated Functions
 //==============================================================================
 
+impl Request {
+    /// Creates a request whose message covers `iovs` and, if given, is sent to `addr`. The pointers of the message
+    /// header are set once the request is in place, so that they remain valid for as long as the request lives.
+    fn new(
+        iovs: Vec<liburing::iovec>,
+        bufs: Vec<DemiBuffer>,
+        addr: Option<libc::sockaddr_in>,
+        timeout: Option<Duration>,
+    ) -> Rc<Self> {
+        let mut request: Rc<Self> = Rc::new(Self {
+            msg: liburing::msghdr {
+                msg_name: ptr::null_mut() as *mut _,
+                msg_namelen: 0,
+                msg_iov: ptr::null_mut(),
+                msg_iovlen: 0,
+                msg_control: ptr::null_mut() as *mut _,
+                msg_controllen: 0,
+                msg_flags: 0,
+            },
+            iovs,
+            addr,
+            timeout: timeout.map(|timeout| liburing::__kernel_timespec {
+                tv_sec: timeout.as_secs() as i64,
+                tv_nsec: timeout.subsec_nanos() as i64,
+            }),
+            _bufs: bufs,
+        });
+        let request_: &mut Self = Rc::get_mut(&mut request).expect("request should not be shared yet");
+        request_.msg.msg_iov = request_.iovs.as_mut_ptr();
+        request_.msg.msg_iovlen = request_.iovs.len() as u64;
+        if let Some(addr) = request_.addr.as_mut() {
+            request_.msg.msg_name = addr as *mut libc::sockaddr_in as *mut c_void;
+            request_.msg.msg_namelen = mem::size_of::<libc::sockaddr_in>() as u32;
+        }
+        request
+    }
+}
+
 impl IoUring {
-    /// Instantiates an IO user ring.
-    pub fn new(nentries: u32) -> Result<Self, Fail> {
+    /// Instantiates an IO user ring. If `sqpoll` is set, the submission queue is polled by a kernel thread, which goes
+    /// to sleep once it has been idle for that long. If `send_zc_threshold` is set, pushes of at least that many bytes
+    /// are zero-copy sends. Up to `batch_size` entries are submitted or reaped at once. Up to `nfiles` files may be
+    /// registered.
+    pub fn new(
+        nentries: u32,
+        sqpoll: Option<Duration>,
+        send_zc_threshold: Option<usize>,
+        batch_size: usize,
+        nfiles: u32,
+    ) -> Result<Self, Fail> {
+        if batch_size == 0 {
+            return Err(Fail::new(libc::EINVAL, "batch size should be positive"));
+        }
+        let ret: Result<liburing::io_uring, c_int> = match sqpoll {
+            Some(idle) => {
+                let idle_ms: u32 = idle.as_millis().min(u32::MAX as u128) as u32;
+                match Self::init(nentries, IORING_SETUP_SQPOLL | IORING_SETUP_SINGLE_ISSUER, idle_ms) {
+                    // Kernels older than 6.0 do not know about single issuers.
+                    Err(errno) if errno == libc::EINVAL => {
+                        warn!("IORING_SETUP_SINGLE_ISSUER is not supported, falling back to a shared ring");
+                        Self::init(nentries, IORING_SETUP_SQPOLL, idle_ms)
+                    },
+                    ret => ret,
+                }
+            },
+            None => Self::init(nentries, 0, 0),
+        };
+
+        match ret {
+            Ok(mut io_uring) => Ok(Self {
+                free_files: Self::init_files(&mut io_uring, nfiles),
+                io_uring,
+                send_zc_threshold,
+                zc_sends: ZeroCopySends::default(),
+                batch_size,
+                unsubmitted: 0,
+                cqes: vec![null_mut(); batch_size],
+                files: HashMap::new(),
+                eventfd: None,
+            }),
+            // Failed to initialize io_uring structure.
+            Err(errno) => unsafe {
+                let strerror: CString = CString::from_raw(libc::strerror(errno));
+                let cause: &str = strerror.to_str().unwrap_or("failed to initialize io_uring");
+                Err(Fail::new(errno, cause))
+            },
+        }
+    }
+
+    /// Initializes an io_uring with the given setup flags. On failure, this returns the error code of the kernel.
+    fn init(nentries: u32, flags: u32, sq_thread_idle: u32) -> Result<liburing::io_uring, c_int> {
         unsafe {
             let mut params: MaybeUninit<liburing::io_uring_params> = MaybeUninit::zeroed();
+            (*params.as_mut_ptr()).flags = flags;
+            (*params.as_mut_ptr()).sq_thread_idle = sq_thread_idle;
             let mut io_uring: MaybeUninit<liburing::io_uring> = MaybeUninit::zeroed();
             let ret: c_int = liburing::io_uring_queue_init_params(nentries, io_uring.as_mut_ptr(), params.as_mut_ptr());
-            // Failed to initialize io_uring structure.
             if ret < 0 {
-                let errno: i32 = -ret;
-                let strerror: CString = CString::from_raw(libc::strerror(errno));
-                let cause: &str = strerror.to_str().unwrap_or("failed to initialize io_uring");
-                return Err(Fail::new(errno, cause));
+                return Err(-ret);
+            }
+            Ok(io_uring.assume_init())
+        }
+    }
+
+    /// Sets up an empty table of `nfiles` registered files, and returns its slots. Kernels older than 5.19 do not
+    /// support empty tables, thus files are not registered on them.
+    fn init_files(io_uring: &mut liburing::io_uring, nfiles: u32) -> Option<Vec<u32>> {
+        let ret: c_int = unsafe { liburing::io_uring_register_files_sparse(io_uring, nfiles) };
+        if ret < 0 {
+            warn!(
+                "failed to set up registered files ({:?}), falling back to file descriptors",
+                -ret
+            );
+            return None;
+        }
+        Some((0..nfiles).rev().collect())
+    }
+
+    /// Registers the file `fd` in the target IO user ring, so that operations on it refer to its slot. Files are not
+    /// registered if the table of registered files is full, or not supported.
+    pub fn register_file(&mut self, fd: RawFd) -> Result<(), Fail> {
+        let free_files: &mut Vec<u32> = match self.free_files.as_mut() {
+            Some(free_files) => free_files,
+            None => return Ok(()),
+        };
+        let slot: u32 = match free_files.pop() {
+            Some(slot) => slot,
+            None => {
+                warn!("table of registered files is full (fd={:?})", fd);
+                return Ok(());
+            },
+        };
+        let ret: c_int = unsafe { liburing::io_uring_register_files_update(&mut self.io_uring, slot, &fd, 1) };
+        if ret < 0 {
+            free_files.push(slot);
+            return Err(Fail::new(-ret, "failed to register file"));
+        }
+        self.files.insert(fd, slot);
+        Ok(())
+    }
+
+    /// Unregisters the file `fd` from the target IO user ring. The ring holds a reference on registered files, thus
+    /// this should come before closing them.
+    pub fn unregister_file(&mut self, fd: RawFd) -> Result<(), Fail> {
+        if let Some(slot) = self.files.remove(&fd) {
+            let unused: RawFd = -1;
+            let ret: c_int = unsafe { liburing::io_uring_register_files_update(&mut self.io_uring, slot, &unused, 1) };
+            if ret < 0 {
+                return Err(Fail::new(-ret, "failed to unregister file"));
+            }
+            if let Some(free_files) = self.free_files.as_mut() {
+                free_files.push(slot);
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the target IO user ring busy poll the network devices of its sockets for `timeout` while it waits for
+    /// completions, instead of sleeping until their interrupts fire. If `prefer` is set, devices keep their interrupts
+    /// masked while they are busy polled. This requires Linux 6.9 or later.
+    pub fn register_napi(&mut self, timeout: Duration, prefer: bool) -> Result<(), Fail> {
+        let napi: IoUringNapi = IoUringNapi {
+            busy_poll_to: timeout.as_micros().min(u32::MAX as u128) as u32,
+            prefer_busy_poll: prefer as u8,
+            pad: [0; 3],
+            resv: 0,
+        };
+        let ret: libc::c_long = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                self.io_uring.ring_fd,
+                IORING_REGISTER_NAPI,
+                &napi as *const IoUringNapi,
+                1,
+            )
+        };
+        if ret < 0 {
+            let errno: i32 = errno::errno();
+            return Err(Fail::new(errno, "failed to register NAPI busy polling"));
+        }
+        Ok(())
+    }
+
+    /// Makes the target IO user ring signal the event file descriptor `fd` whenever it posts a completion. Registering
+    /// the same descriptor again has no effect.
+    pub fn register_eventfd(&mut self, fd: RawFd) -> Result<(), Fail> {
+        if self.eventfd == Some(fd) {
+            return Ok(());
+        }
+        let ret: libc::c_long = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                self.io_uring.ring_fd,
+                IORING_REGISTER_EVENTFD,
+                &fd as *const RawFd,
+                1,
+            )
+        };
+        if ret < 0 {
+            let errno: i32 = errno::errno();
+            return Err(Fail::new(errno, "failed to register event file descriptor"));
+        }
+        self.eventfd = Some(fd);
+        Ok(())
+    }
+
+    /// Makes the submission queue entry `sqe` refer to the file `fd` by its slot, if it is registered.
+    unsafe fn set_file(&self, sqe: *mut liburing::io_uring_sqe, fd: RawFd) {
+        if let Some(&slot) = self.files.get(&fd) {
+            (*sqe).fd = slot as i32;
+            (*sqe).flags |= (1 << liburing::IOSQE_FIXED_FILE_BIT) as u8;
+        }
+    }
+
+    /// Allocates a submission queue entry. The queued entries are submitted first, if they fill a batch or the
+    /// submission queue. When the submission queue is polled by a kernel thread, entries are only released once that
+    /// thread consumes them. Thus, if the queue is still full, we wake up the thread should it be asleep, and wait for
+    /// it to make room.
+    unsafe fn get_sqe(&mut self) -> Result<*mut liburing::io_uring_sqe, Fail> {
+        if self.unsubmitted >= self.batch_size {
+            self.submit()?;
+        }
+        let mut sqe: *mut liburing::io_uring_sqe = liburing::io_uring_get_sqe(&mut self.io_uring);
+        if sqe.is_null() && self.unsubmitted > 0 {
+            self.submit()?;
+            sqe = liburing::io_uring_get_sqe(&mut self.io_uring);
+        }
+        let io_uring: &mut liburing::io_uring = &mut self.io_uring;
+        if sqe.is_null() && io_uring.flags & IORING_SETUP_SQPOLL != 0 {
+            let mut flags: u32 = IORING_ENTER_SQ_WAIT;
+            if ptr::read_volatile(io_uring.sq.kflags) & IORING_SQ_NEED_WAKEUP != 0 {
+                flags |= IORING_ENTER_SQ_WAKEUP;
             }
+            let ret: c_int = liburing::io_uring_enter(io_uring.ring_fd as u32, 0, 0, flags, null_mut());
+            if ret < 0 {
+                return Err(Fail::new(-ret, "failed to wait for room in submission queue"));
+            }
+            sqe = liburing::io_uring_get_sqe(io_uring);
+        }
+        if sqe.is_null() {
+            let errno: i32 = errno::errno();
+            let strerror: CString = CString::from_raw(libc::strerror(errno));
+            let cause: &str = strerror.to_str().unwrap_or("failed to get sqe");
+            return Err(Fail::new(errno, cause));
+        }
+        Ok(sqe)
+    }
 
-            Ok(Self {
-                io_uring: io_uring.assume_init(),
-            })
+    /// Submits all entries that are queued in the submission queue of the target IO user ring.
+    pub fn submit(&mut self) -> Result<(), Fail> {
+        if self.unsubmitted == 0 {
+            return Ok(());
+        }
+        let ret: c_int = unsafe { liburing::io_uring_submit(&mut self.io_uring) };
+        if ret < 0 {
+            let errno: i32 = -ret;
+            warn!("io_uring_submit() failed ({:?})", errno);
+            return Err(Fail::new(errno, "failed to submit operations"));
+        }
+        self.unsubmitted = self.unsubmitted.saturating_sub(ret as usize);
+        Ok(())
+    }
+
+    /// Checks if a push of `len` bytes should be a zero-copy send.
+    fn is_zero_copy(&self, len: usize) -> bool {
+        match self.send_zc_threshold {
+            Some(threshold) => len >= threshold,
+            None => false,
+        }
+    }
+
+    /// Prepares a send of the message `msg_ptr` with `flags` in the submission queue entry `sqe`, which is a zero-copy
+    /// send if `zero_copy` is set.
+    unsafe fn prep_send(
+        sqe: *mut liburing::io_uring_sqe,
+        sockfd: RawFd,
+        msg_ptr: *const liburing::msghdr,
+        flags: u32,
+        zero_copy: bool,
+    ) {
+        liburing::io_uring_prep_sendmsg(sqe, sockfd, msg_ptr, flags);
+        if zero_copy {
+            (*sqe).opcode = IORING_OP_SENDMSG_ZC;
         }
     }
 
@@ -76,39 +452,48 @@ impl IoUring {
         let len: usize = buf.len();
         let data: &[u8] = &buf[..];
         let data_ptr: *const u8 = data.as_ptr();
-        let io_uring: &mut liburing::io_uring = &mut self.io_uring;
-
+        let zero_copy: bool = self.is_zero_copy(len);
         unsafe {
             // Allocate a submission queue entry.
-            let sqe: *mut liburing::io_uring_sqe = liburing::io_uring_get_sqe(io_uring);
-            if sqe.is_null() {
-                let errno: i32 = errno::errno();
-                let strerror: CString = CString::from_raw(libc::strerror(errno));
-                let cause: &str = strerror.to_str().unwrap_or("failed to get sqe");
-                return Err(Fail::new(errno, cause));
-            }
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
 
             // Submit operation.
-            let mut iov: Box<liburing::iovec> = Box::new(liburing::iovec {
+            let iov: liburing::iovec = liburing::iovec {
                 iov_base: data_ptr as *mut c_void,
                 iov_len: len as u64,
-            });
-            let iov_ptr: *mut liburing::iovec = iov.as_mut() as *mut liburing::iovec;
-            let msg: Rc<liburing::msghdr> = Rc::new(liburing::msghdr {
-                msg_name: ptr::null_mut() as *mut _,
-                msg_namelen: 0,
-                msg_iov: iov_ptr,
-                msg_iovlen: 1,
-                msg_control: ptr::null_mut() as *mut _,
-                msg_controllen: 0,
-                msg_flags: 0,
-            });
-            let msg_ptr: *const liburing::msghdr = Rc::into_raw(msg);
+            };
+            let request: Rc<Request> = Request::new(vec![iov], vec![buf.clone()], None, None);
+            let msg_ptr: *const liburing::msghdr = Rc::into_raw(request) as *const liburing::msghdr;
             liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
-            liburing::io_uring_prep_sendmsg(sqe, sockfd, msg_ptr, 0);
-            if liburing::io_uring_submit(io_uring) < 1 {
-                return Err(Fail::new(libc::EAGAIN, "failed to submit push operation"));
-            }
+            Self::prep_send(sqe, sockfd, msg_ptr, 0, zero_copy);
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
+
+            Ok(msg_ptr)
+        }
+    }
+
+    /// Pushes multiple buffers to the target IO user ring in a single operation.
+    pub fn pushv(&mut self, sockfd: RawFd, bufs: &[DemiBuffer]) -> Result<*const liburing::msghdr, Fail> {
+        let zero_copy: bool = self.is_zero_copy(bufs.iter().map(|buf| buf.len()).sum());
+        unsafe {
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
+            // Submit operation.
+            let iovs: Vec<liburing::iovec> = bufs
+                .iter()
+                .map(|buf| liburing::iovec {
+                    iov_base: buf.as_ptr() as *mut c_void,
+                    iov_len: buf.len() as u64,
+                })
+                .collect();
+            let request: Rc<Request> = Request::new(iovs, bufs.to_vec(), None, None);
+            let msg_ptr: *const liburing::msghdr = Rc::into_raw(request) as *const liburing::msghdr;
+            liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
+            Self::prep_send(sqe, sockfd, msg_ptr, 0, zero_copy);
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
 
             Ok(msg_ptr)
         }
@@ -128,108 +513,398 @@ impl IoUring {
             Some(addr) => addr,
             None => return Err(Fail::new(libc::EINVAL, "invalid socket address")),
         };
-        let (sockaddr, addrlen): (&libc::sockaddr_in, socklen_t) = (saddr.as_ref(), saddr.len());
-        let sockaddr_ptr: *const libc::sockaddr_in = sockaddr as *const libc::sockaddr_in;
-        let io_uring: &mut liburing::io_uring = &mut self.io_uring;
-
+        let sockaddr: libc::sockaddr_in = *saddr.as_ref();
+        let zero_copy: bool = self.is_zero_copy(len);
         unsafe {
             // Allocate a submission queue entry.
-            let sqe: *mut liburing::io_uring_sqe = liburing::io_uring_get_sqe(io_uring);
-            if sqe.is_null() {
-                let errno: i32 = errno::errno();
-                let strerror: CString = CString::from_raw(libc::strerror(errno));
-                let cause: &str = strerror.to_str().unwrap_or("failed to get sqe");
-                return Err(Fail::new(errno, cause));
-            }
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
 
             // Submit operation.
-            let mut iov: Box<liburing::iovec> = Box::new(liburing::iovec {
+            let iov: liburing::iovec = liburing::iovec {
                 iov_base: data_ptr as *mut c_void,
                 iov_len: len as u64,
-            });
-            let iov_ptr: *mut liburing::iovec = iov.as_mut() as *mut liburing::iovec;
-            let msg: Rc<liburing::msghdr> = Rc::new(liburing::msghdr {
-                msg_name: sockaddr_ptr as *mut c_void,
-                msg_namelen: addrlen as u32,
-                msg_iov: iov_ptr,
-                msg_iovlen: 1,
-                msg_control: ptr::null_mut() as *mut _,
-                msg_controllen: 0,
-                msg_flags: 0,
-            });
-            let msg_ptr: *const liburing::msghdr = Rc::into_raw(msg);
+            };
+            let request: Rc<Request> = Request::new(vec![iov], vec![buf.clone()], Some(sockaddr), None);
+            let msg_ptr: *const liburing::msghdr = Rc::into_raw(request) as *const liburing::msghdr;
             liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
-            liburing::io_uring_prep_sendmsg(sqe, sockfd, msg_ptr, 0);
-            if liburing::io_uring_submit(io_uring) < 1 {
-                return Err(Fail::new(libc::EAGAIN, "failed to submit push operation"));
-            }
+            Self::prep_send(sqe, sockfd, msg_ptr, 0, zero_copy);
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
 
             Ok(msg_ptr)
         }
     }
 
-    /// Pops a buffer from the target IO user ring.
-    pub fn pop(&mut self, sockfd: RawFd, buf: DemiBuffer) -> Result<*const liburing::msghdr, Fail> {
+    /// Pops a buffer from the target IO user ring. If a timeout is given, the operation is linked to a timeout, so
+    /// that the kernel cancels it once the timeout expires.
+    pub fn pop(
+        &mut self,
+        sockfd: RawFd,
+        buf: DemiBuffer,
+        timeout: Option<Duration>,
+    ) -> Result<*const liburing::msghdr, Fail> {
         let len: usize = buf.len();
         let data: &[u8] = &buf[..];
         let data_ptr: *const u8 = data.as_ptr();
-        let io_uring: &mut liburing::io_uring = &mut self.io_uring;
 
         unsafe {
-            // Allocate a submission queue entry.
-            let sqe: *mut liburing::io_uring_sqe = liburing::io_uring_get_sqe(io_uring);
-            if sqe.is_null() {
-                let errno: i32 = errno::errno();
-                let strerror: CString = CString::from_raw(libc::strerror(errno));
-                let cause: &str = strerror.to_str().unwrap_or("failed to get sqe");
-                return Err(Fail::new(errno, cause));
+            // Check if there is room for the linked timeout.
+            if timeout.is_some() {
+                self.reserve_link()?;
             }
 
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
             // Submit operation.
-            let mut iov: Box<liburing::iovec> = Box::new(liburing::iovec {
+            let iov: liburing::iovec = liburing::iovec {
                 iov_base: data_ptr as *mut c_void,
                 iov_len: len as u64,
-            });
-            let iov_ptr: *mut liburing::iovec = iov.as_mut() as *mut liburing::iovec;
-            let msg: Rc<liburing::msghdr> = Rc::new(liburing::msghdr {
-                msg_name: ptr::null_mut() as *mut _,
-                msg_namelen: 0,
-                msg_iov: iov_ptr,
-                msg_iovlen: 1,
-                msg_control: ptr::null_mut() as *mut _,
-                msg_controllen: 0,
-                msg_flags: 0,
-            });
-            let msg_ptr: *const liburing::msghdr = Rc::into_raw(msg);
+            };
+            let request_ptr: *const Request = Rc::into_raw(Request::new(vec![iov], vec![], None, timeout));
+            let msg_ptr: *const liburing::msghdr = request_ptr as *const liburing::msghdr;
             liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
             liburing::io_uring_prep_recvmsg(sqe, sockfd, msg_ptr as *mut liburing::msghdr, 0);
-            if liburing::io_uring_submit(io_uring) < 1 {
-                return Err(Fail::new(libc::EAGAIN, "failed to submit pop operation"));
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
+            self.link_timeout(sqe, request_ptr);
+
+            Ok(msg_ptr)
+        }
+    }
+
+    /// Connects a socket to a remote address through the target IO user ring. If a timeout is given, the operation is
+    /// linked to a timeout, so that the kernel cancels it once the timeout expires.
+    pub fn connect(
+        &mut self,
+        sockfd: RawFd,
+        addr: SockaddrStorage,
+        timeout: Option<Duration>,
+    ) -> Result<*const liburing::msghdr, Fail> {
+        let saddr: &SockaddrIn = match addr.as_sockaddr_in() {
+            Some(addr) => addr,
+            None => return Err(Fail::new(libc::EINVAL, "invalid socket address")),
+        };
+        let sockaddr: libc::sockaddr_in = *saddr.as_ref();
+
+        unsafe {
+            // Check if there is room for the linked timeout.
+            if timeout.is_some() {
+                self.reserve_link()?;
             }
 
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
+            // Submit operation. The request owns the address, since the kernel may only read it once the polling
+            // thread picks up the entry.
+            let request_ptr: *const Request = Rc::into_raw(Request::new(vec![], vec![], Some(sockaddr), timeout));
+            let msg_ptr: *const liburing::msghdr = request_ptr as *const liburing::msghdr;
+            liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
+            liburing::io_uring_prep_connect(
+                sqe,
+                sockfd,
+                (*request_ptr).msg.msg_name as *const libc::sockaddr as *const _,
+                saddr.len(),
+            );
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
+            self.link_timeout(sqe, request_ptr);
+
+            Ok(msg_ptr)
+        }
+    }
+
+    /// Closes a file descriptor through the target IO user ring.
+    pub fn close(&mut self, fd: RawFd) -> Result<*const liburing::msghdr, Fail> {
+        unsafe {
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
+            // Submit operation. The request carries no message, and only identifies the operation.
+            let msg_ptr: *const liburing::msghdr = Rc::into_raw(Request::new(vec![], vec![], None, None)) as *const _;
+            liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
+            liburing::io_uring_prep_close(sqe, fd);
+            self.unsubmitted += 1;
+
+            Ok(msg_ptr)
+        }
+    }
+
+    /// Waits for a file descriptor to become ready for `events` (e.g. `POLLIN`) through the target IO user ring. The
+    /// operation completes with the events that are ready.
+    pub fn poll_add(&mut self, fd: RawFd, events: u32) -> Result<*const liburing::msghdr, Fail> {
+        unsafe {
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
+            // Submit operation. The request carries no message, and only identifies the operation.
+            let msg_ptr: *const liburing::msghdr = Rc::into_raw(Request::new(vec![], vec![], None, None)) as *const _;
+            liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
+            liburing::io_uring_prep_poll_add(sqe, fd, events);
+            self.set_file(sqe, fd);
+            self.unsubmitted += 1;
+
             Ok(msg_ptr)
         }
     }
 
-    /// Waits for an operation to complete in the target IO user ring.
-    pub fn wait(&mut self) -> Result<(*mut liburing::msghdr, i32), Fail> {
+    /// Shuts down the receive side, send side, or both sides (`how`) of a socket through the target IO user ring.
+    pub fn shutdown(&mut self, sockfd: RawFd, how: c_int) -> Result<*const liburing::msghdr, Fail> {
+        unsafe {
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
+            // Submit operation. The request carries no message, and only identifies the operation.
+            let msg_ptr: *const liburing::msghdr = Rc::into_raw(Request::new(vec![], vec![], None, None)) as *const _;
+            liburing::io_uring_sqe_set_data(sqe, msg_ptr as *mut c_void);
+            liburing::io_uring_prep_shutdown(sqe, sockfd, how);
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
+
+            Ok(msg_ptr)
+        }
+    }
+
+    /// Pushes a buffer to a socket through the target IO user ring, and pops the response into another buffer. Both
+    /// operations are linked, so that the kernel issues the pop only once the push completes, and cancels it if the
+    /// push fails. The push is sent with `MSG_WAITALL`, so that the kernel keeps sending until all of the buffer is
+    /// sent, and fails the push otherwise.
+    pub fn pushpop(
+        &mut self,
+        sockfd: RawFd,
+        buf: DemiBuffer,
+        recvbuf: DemiBuffer,
+    ) -> Result<(*const liburing::msghdr, *const liburing::msghdr), Fail> {
+        let zero_copy: bool = self.is_zero_copy(buf.len());
+
+        unsafe {
+            // Check if there is room for both operations.
+            self.reserve_link()?;
+
+            // Queue push operation.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+            let iov: liburing::iovec = liburing::iovec {
+                iov_base: buf.as_ptr() as *mut c_void,
+                iov_len: buf.len() as u64,
+            };
+            let request: Rc<Request> = Request::new(vec![iov], vec![buf], None, None);
+            let push_ptr: *const liburing::msghdr = Rc::into_raw(request) as *const liburing::msghdr;
+            liburing::io_uring_sqe_set_data(sqe, push_ptr as *mut c_void);
+            Self::prep_send(sqe, sockfd, push_ptr, libc::MSG_WAITALL as u32, zero_copy);
+            self.set_file(sqe, sockfd);
+            (*sqe).flags |= (1 << liburing::IOSQE_IO_LINK_BIT) as u8;
+            self.unsubmitted += 1;
+
+            // Queue linked pop operation. This cannot fail, since room was made for both operations.
+            let sqe: *mut liburing::io_uring_sqe = liburing::io_uring_get_sqe(&mut self.io_uring);
+            let iov: liburing::iovec = liburing::iovec {
+                iov_base: recvbuf.as_ptr() as *mut c_void,
+                iov_len: recvbuf.len() as u64,
+            };
+            let request: Rc<Request> = Request::new(vec![iov], vec![], None, None);
+            let pop_ptr: *const liburing::msghdr = Rc::into_raw(request) as *const liburing::msghdr;
+            liburing::io_uring_sqe_set_data(sqe, pop_ptr as *mut c_void);
+            liburing::io_uring_prep_recvmsg(sqe, sockfd, pop_ptr as *mut liburing::msghdr, 0);
+            self.set_file(sqe, sockfd);
+            self.unsubmitted += 1;
+
+            Ok((push_ptr, pop_ptr))
+        }
+    }
+
+    /// Makes room for an operation and the one linked to it in the submission queue, since both should be queued
+    /// along.
+    fn reserve_link(&mut self) -> Result<(), Fail> {
+        unsafe {
+            if liburing::io_uring_sq_space_left(&mut self.io_uring) < 2 {
+                self.submit()?;
+                if liburing::io_uring_sq_space_left(&mut self.io_uring) < 2 {
+                    return Err(Fail::new(libc::EAGAIN, "not enough room for linked operations"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Links the timeout of the request `request_ptr`, if any, to the submission queue entry `sqe`, which should be
+    /// the last one that was queued. The request owns the timeout, since the kernel may only read it once the polling
+    /// thread picks up the entry. Completions of linked timeouts carry no data, thus they are ignored by the runtime.
+    unsafe fn link_timeout(&mut self, sqe: *mut liburing::io_uring_sqe, request_ptr: *const Request) {
+        if let Some(ts) = (*request_ptr).timeout.as_ref() {
+            (*sqe).flags |= (1 << liburing::IOSQE_IO_LINK_BIT) as u8;
+            let timeout_sqe: *mut liburing::io_uring_sqe = liburing::io_uring_get_sqe(&mut self.io_uring);
+            liburing::io_uring_prep_link_timeout(
+                timeout_sqe,
+                ts as *const liburing::__kernel_timespec as *mut liburing::__kernel_timespec,
+                0,
+            );
+            liburing::io_uring_sqe_set_data(timeout_sqe, null_mut());
+            self.unsubmitted += 1;
+        }
+    }
+
+    /// Requests the cancellation of an operation in the target IO user ring. The canceled operation completes with
+    /// `-ECANCELED`, unless it has already completed.
+    pub fn cancel(&mut self, msg_ptr: *const liburing::msghdr) -> Result<(), Fail> {
+        unsafe {
+            // Allocate a submission queue entry.
+            let sqe: *mut liburing::io_uring_sqe = self.get_sqe()?;
+
+            // Submit operation. Completions of cancel requests carry no data, thus they are ignored by the runtime.
+            liburing::io_uring_prep_cancel(sqe, msg_ptr as *mut c_void, 0);
+            liburing::io_uring_sqe_set_data(sqe, null_mut());
+            self.unsubmitted += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if zero-copy sends of the target IO user ring still wait for their notification.
+    pub fn has_notifications(&self) -> bool {
+        !self.zc_sends.outcomes.is_empty()
+    }
+
+    /// Submits the queued entries of the target IO user ring, and reaps a batch of completions, waiting for one if
+    /// none is available. Zero-copy sends are only reported once their notification arrives, and completions that carry
+    /// no data are not reported.
+    pub fn wait(&mut self) -> Result<Vec<(*mut liburing::msghdr, i32)>, Fail> {
+        self.reap(true)
+    }
+
+    /// Submits the queued entries of the target IO user ring, and reaps a batch of completions without waiting for
+    /// any, like [IoUring::wait].
+    pub fn try_wait(&mut self) -> Result<Vec<(*mut liburing::msghdr, i32)>, Fail> {
+        self.reap(false)
+    }
+
+    /// Submits the queued entries of the target IO user ring, and reaps a batch of completions, waiting for one if
+    /// none is available and `block` is set.
+    fn reap(&mut self, block: bool) -> Result<Vec<(*mut liburing::msghdr, i32)>, Fail> {
         let io_uring: &mut liburing::io_uring = &mut self.io_uring;
+        let mut completions: Vec<(*mut liburing::msghdr, i32)> = Vec::with_capacity(self.batch_size);
         unsafe {
-            let mut cqe_ptr: *mut liburing::io_uring_cqe = null_mut();
-            let cqe_ptr_ptr: *mut *mut liburing::io_uring_cqe = ptr::addr_of_mut!(cqe_ptr);
-            let wait_nr: c_int = liburing::io_uring_wait_cqe(io_uring, cqe_ptr_ptr);
-            if wait_nr < 0 {
-                let errno: i32 = -wait_nr;
-                warn!("io_uring_wait_cqe() failed ({:?})", errno);
-                return Err(Fail::new(errno, "operation in progress"));
-            } else if wait_nr == 0 {
+            let mut count: u32 =
+                liburing::io_uring_peek_batch_cqe(io_uring, self.cqes.as_mut_ptr(), self.batch_size as u32);
+            if count == 0 && block {
+                let ret: c_int = liburing::io_uring_submit_and_wait(io_uring, 1);
+                if ret < 0 {
+                    let errno: i32 = -ret;
+                    warn!("io_uring_submit_and_wait() failed ({:?})", errno);
+                    return Err(Fail::new(errno, "operation in progress"));
+                }
+                self.unsubmitted = self.unsubmitted.saturating_sub(ret as usize);
+                count = liburing::io_uring_peek_batch_cqe(io_uring, self.cqes.as_mut_ptr(), self.batch_size as u32);
+            } else {
+                self.submit()?;
+                if count == 0 {
+                    count = liburing::io_uring_peek_batch_cqe(
+                        &mut self.io_uring,
+                        self.cqes.as_mut_ptr(),
+                        self.batch_size as u32,
+                    );
+                }
+            }
+
+            for i in 0..count as usize {
+                let cqe_ptr: *mut liburing::io_uring_cqe = self.cqes[i];
                 let size: i32 = (*cqe_ptr).res;
+                let flags: u32 = (*cqe_ptr).flags;
                 let msg_ptr: *mut liburing::msghdr = liburing::io_uring_cqe_get_data(cqe_ptr) as *mut liburing::msghdr;
-                liburing::io_uring_cqe_seen(io_uring, cqe_ptr);
-                return Ok((msg_ptr, size));
+                liburing::io_uring_cqe_seen(&mut self.io_uring, cqe_ptr);
+
+                // Completions of cancel requests and linked timeouts carry no data.
+                if msg_ptr.is_null() {
+                    continue;
+                }
+
+                if let Some(completion) = self.zc_sends.complete(msg_ptr, size, flags) {
+                    completions.push(completion);
+                }
             }
         }
 
-        unreachable!("should not happen")
+        Ok(completions)
+    }
+}
+
+impl ZeroCopySends {
+    /// Handles a completion queue entry of the request `msg_ptr`, returning the completion to report, if any. The
+    /// outcome of a zero-copy send is held back until its notification arrives.
+    fn complete(
+        &mut self,
+        msg_ptr: *mut liburing::msghdr,
+        size: i32,
+        flags: u32,
+    ) -> Option<(*mut liburing::msghdr, i32)> {
+        // The kernel is done with the buffers of a zero-copy send, thus report its outcome.
+        if flags & IORING_CQE_F_NOTIF != 0 {
+            match self.outcomes.remove(&msg_ptr) {
+                Some(size) => return Some((msg_ptr, size)),
+                None => {
+                    warn!("notification of an unknown zero-copy send");
+                    return None;
+                },
+            }
+        }
+
+        // A notification follows, until which the request keeps its buffers.
+        if flags & IORING_CQE_F_MORE != 0 {
+            self.outcomes.insert(msg_ptr, size);
+            return None;
+        }
+
+        Some((msg_ptr, size))
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Drop Trait Implementation for IO User Rings
+impl Drop for IoUring {
+    /// Releases the underlying io_uring.
+    fn drop(&mut self) {
+        unsafe { liburing::io_uring_queue_exit(&mut self.io_uring) };
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ZeroCopySends,
+        IORING_CQE_F_MORE,
+        IORING_CQE_F_NOTIF,
+    };
+    use crate::runtime::liburing;
+
+    #[test]
+    fn zero_copy_send_completes_on_notification() {
+        let mut zc_sends: ZeroCopySends = ZeroCopySends::default();
+        let mut msg: liburing::msghdr = unsafe { std::mem::zeroed() };
+        let msg_ptr: *mut liburing::msghdr = &mut msg;
+
+        // The outcome of the send is held back, as a notification follows.
+        assert!(zc_sends.complete(msg_ptr, 64, IORING_CQE_F_MORE).is_none());
+        assert!(!zc_sends.outcomes.is_empty());
+
+        // The send completes with its outcome once the kernel is done with its buffers.
+        assert_eq!(zc_sends.complete(msg_ptr, 0, IORING_CQE_F_NOTIF), Some((msg_ptr, 64)));
+        assert!(zc_sends.outcomes.is_empty());
+    }
+
+    #[test]
+    fn copying_send_completes_right_away() {
+        let mut zc_sends: ZeroCopySends = ZeroCopySends::default();
+        let mut msg: liburing::msghdr = unsafe { std::mem::zeroed() };
+        let msg_ptr: *mut liburing::msghdr = &mut msg;
+
+        assert_eq!(zc_sends.complete(msg_ptr, 64, 0), Some((msg_ptr, 64)));
+        assert!(zc_sends.outcomes.is_empty());
     }
 }
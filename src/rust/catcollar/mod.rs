@@ -1,6 +1,7 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod config;
 mod futures;
 mod iouring;
 mod runtime;
@@ -18,45 +19,81 @@ pub use self::runtime::IoUringRuntime;
 use self::{
     futures::{
         accept::AcceptFuture,
+        close::CloseFuture,
         connect::ConnectFuture,
         pop::PopFuture,
         push::PushFuture,
+        pushpop::PushPopFuture,
         pushto::PushtoFuture,
+        splice::SpliceFuture,
         Operation,
     },
     runtime::RequestId,
 };
 use crate::{
-    demikernel::config::Config,
+    demikernel::{
+        config::Config,
+        libos::network::NetworkLibOSTrait,
+    },
     inetstack::operations::OperationResult,
+    pal::linux,
     runtime::{
         fail::Fail,
         memory::{
             DemiBuffer,
             MemoryRuntime,
         },
-        queue::IoQueueTable,
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketAddress,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::{
+            IoQueueLimitStats,
+            IoQueueTable,
+        },
         types::{
             demi_accept_result_t,
             demi_opcode_t,
+            demi_pktinfo_t,
             demi_qr_value_t,
             demi_qresult_t,
             demi_sgarray_t,
+            DEMI_QR_FLAG_EOF,
+            DEMI_QR_FLAG_PKTINFO,
+            DEMI_QR_FLAG_TRUNC,
         },
         QDesc,
         QToken,
         QType,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        Priority,
+        Scheduler,
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
 };
 use ::libc::c_int;
 use ::nix::{
+    errno::Errno,
     sys::socket::{
         self,
+        sockopt,
         AddressFamily,
+        IpMembershipRequest,
         SockFlag,
         SockProtocol,
         SockType,
+        SockaddrIn,
         SockaddrStorage,
     },
     unistd,
@@ -64,12 +101,19 @@ use ::nix::{
 use ::std::{
     any::Any,
     collections::HashMap,
+    future::Future,
     mem,
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     os::unix::prelude::RawFd,
+    pin::Pin,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //======================================================================================================================
@@ -83,14 +127,28 @@ const CATCOLLAR_RECVBUF_SIZE: u16 = 9000;
 // Structures
 //======================================================================================================================
 
+/// Busy polling parameters of sockets, for kernels that cannot busy poll from the io_uring.
+struct BusyPoll {
+    /// Time for which the network device is busy polled (SO_BUSY_POLL).
+    timeout: Duration,
+    /// Whether the network device keeps its interrupts masked while busy polled (SO_PREFER_BUSY_POLL).
+    prefer: bool,
+    /// Maximum number of packets that are processed on each busy poll (SO_BUSY_POLL_BUDGET).
+    budget: Option<u16>,
+}
+
 /// Catcollar LibOS
 pub struct CatcollarLibOS {
     /// Table of queue descriptors.
     qtable: IoQueueTable, // TODO: Move this to Demikernel module.
     /// Established sockets.
     sockets: HashMap<QDesc, RawFd>,
+    /// Number of truncated datagrams on each socket.
+    truncations: HashMap<RawFd, u64>,
     /// Underlying runtime.
     runtime: IoUringRuntime,
+    /// Busy polling parameters that are set on each socket, if the io_uring does not busy poll itself.
+    busy_poll: Option<BusyPoll>,
 }
 
 //======================================================================================================================
@@ -100,20 +158,61 @@ pub struct CatcollarLibOS {
 /// Associate Functions for Catcollar LibOS
 impl CatcollarLibOS {
     /// Instantiates a Catcollar LibOS.
-    pub fn new(_config: &Config) -> Self {
-        let qtable: IoQueueTable = IoQueueTable::new();
+    pub fn new(config: &Config) -> Self {
+        let mut qtable: IoQueueTable = IoQueueTable::new();
+        qtable.set_limits(config.queue_limits());
         let sockets: HashMap<QDesc, RawFd> = HashMap::new();
-        let runtime: IoUringRuntime = IoUringRuntime::new();
+        let mut runtime: IoUringRuntime = IoUringRuntime::new(
+            config.io_uring_sqpoll(),
+            config.io_uring_send_zc_threshold(),
+            config.io_uring_batch_size(),
+        );
+        runtime.scheduler.set_budget(config.poll_budget());
+        if let Some(policy) = config.idle_policy() {
+            runtime.scheduler.set_idle_policy(policy);
+        }
+        runtime.scheduler.set_poll_timing(config.poll_timing());
+        let worker_cores: Vec<usize> = config.worker_cores();
+        if !worker_cores.is_empty() {
+            if let Err(e) = runtime.scheduler.set_workers(&worker_cores) {
+                panic!("cannot spawn scheduler workers ({:?})", e);
+            }
+        }
+
+        // Busy poll network devices from the io_uring, or from each socket on older kernels. The budget only applies
+        // to the latter.
+        let busy_poll: Option<BusyPoll> = match config.napi_busy_poll() {
+            Some(timeout) => match runtime.register_napi(timeout, config.napi_prefer_busy_poll()) {
+                Ok(()) => None,
+                Err(e) => {
+                    warn!("cannot busy poll from io_uring ({:?}), falling back to SO_BUSY_POLL", e);
+                    Some(BusyPoll {
+                        timeout,
+                        prefer: config.napi_prefer_busy_poll(),
+                        budget: config.napi_busy_poll_budget(),
+                    })
+                },
+            },
+            None => None,
+        };
+
         Self {
             qtable,
             sockets,
+            truncations: HashMap::new(),
             runtime,
+            busy_poll,
         }
     }
 
     /// Creates a socket.
-    pub fn socket(&mut self, domain: c_int, typ: c_int, _protocol: c_int) -> Result<QDesc, Fail> {
-        trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, _protocol);
+    pub fn socket(&mut self, domain: c_int, typ: c_int, protocol: c_int) -> Result<QDesc, Fail> {
+        trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, protocol);
+
+        // Raw sockets carry the IP protocol that is requested by the application.
+        if domain == libc::AF_INET && typ == libc::SOCK_RAW {
+            return self.raw_socket(protocol);
+        }
 
         // All operations are asynchronous.
         let flags: SockFlag = SockFlag::SOCK_NONBLOCK;
@@ -133,6 +232,9 @@ impl CatcollarLibOS {
             },
         };
 
+        // Check if a queue descriptor may be allocated for the socket.
+        self.qtable.check_qd_limit()?;
+
         // Create socket.
         match socket::socket(domain, ty, flags, protocol) {
             Ok(fd) => {
@@ -146,6 +248,12 @@ impl CatcollarLibOS {
                 if socket::setsockopt(fd, socket::sockopt::ReusePort, &true).is_err() {
                     warn!("cannot set SO_REUSEPORT option");
                 }
+                self.set_busy_poll(fd);
+                // Try to register the socket in the io_uring. If we fail, keep going because operations fall back to
+                // the file descriptor.
+                if let Err(e) = self.runtime.register_file(fd) {
+                    warn!("cannot register socket ({:?})", e);
+                }
                 let qd: QDesc = self.qtable.alloc(qtype.into());
                 assert_eq!(self.sockets.insert(qd, fd).is_none(), true);
                 Ok(qd)
@@ -154,6 +262,24 @@ impl CatcollarLibOS {
         }
     }
 
+    /// Creates a raw IP socket for `protocol`.
+    fn raw_socket(&mut self, protocol: c_int) -> Result<QDesc, Fail> {
+        self.qtable.check_qd_limit()?;
+        let fd: RawFd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_RAW | libc::SOCK_NONBLOCK, protocol) };
+        if fd < 0 {
+            return Err(Fail::new(Errno::last() as i32, "failed to create socket"));
+        }
+        self.set_busy_poll(fd);
+        // Try to register the socket in the io_uring. If we fail, keep going because operations fall back to the file
+        // descriptor.
+        if let Err(e) = self.runtime.register_file(fd) {
+            warn!("cannot register socket ({:?})", e);
+        }
+        let qd: QDesc = self.qtable.alloc(QType::RawSocket.into());
+        assert_eq!(self.sockets.insert(qd, fd).is_none(), true);
+        Ok(qd)
+    }
+
     /// Binds a socket to a local endpoint.
     pub fn bind(&mut self, qd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
         trace!("bind() qd={:?}, local={:?}", qd, local);
@@ -190,52 +316,340 @@ impl CatcollarLibOS {
         // Issue accept operation.
         match self.sockets.get(&qd) {
             Some(&fd) => {
+                self.qtable.check_qd_limit()?;
+                self.qtable.check_operation_limit()?;
                 let new_qd: QDesc = self.qtable.alloc(QType::TcpSocket.into());
-                let future: Operation = Operation::from(AcceptFuture::new(qd, fd, new_qd));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let future: Operation = Operation::from(AcceptFuture::new(self.runtime.clone(), qd, fd, new_qd));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => {
                         self.qtable.free(new_qd);
                         return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine"));
                     },
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], Some(new_qd));
+                Ok(qt)
             },
             _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
         }
     }
 
-    /// Establishes a connection to a remote endpoint.
+    /// Establishes a connection to a remote endpoint. On UDP sockets, the kernel records `remote` as the default
+    /// destination of pushes and drops datagrams that come from other endpoints.
     pub fn connect(&mut self, qd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
         trace!("connect() qd={:?}, remote={:?}", qd, remote);
 
-        // Issue connect operation.
+        self.do_connect(qd, remote, None)
+    }
+
+    /// Establishes a connection to a remote endpoint, failing with `ETIMEDOUT` if it is not established by `deadline`.
+    pub fn timedconnect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        trace!("timedconnect() qd={:?}, remote={:?}", qd, remote);
+
+        self.do_connect(qd, remote, Some(deadline))
+    }
+
+    /// Issues a connect operation.
+    fn do_connect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Option<Instant>) -> Result<QToken, Fail> {
         match self.sockets.get(&qd) {
             Some(&fd) => {
+                self.qtable.check_operation_limit()?;
+                // Issue operation.
                 let addr: SockaddrStorage = parse_addr(remote);
-                let future: Operation = Operation::from(ConnectFuture::new(qd, fd, addr));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let timeout: Option<Duration> =
+                    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                let request_id: RequestId = self.runtime.connect(fd, addr, timeout)?;
+
+                let future: Operation = Operation::from(ConnectFuture::new(self.runtime.clone(), request_id, qd));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
             },
             _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
         }
     }
 
-    /// Closes a socket.
+    /// Closes a socket. Like all other operations on sockets, this goes through the io_uring, but waits for the
+    /// operation to complete.
     pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
         trace!("close() qd={:?}", qd);
+        self.cancel_operations(qd);
+        match self.sockets.get(&qd) {
+            Some(&fd) => match self.qtable.release(qd) {
+                // Other queue descriptors still refer to the socket.
+                Some(false) => {
+                    self.sockets.remove(&qd);
+                    Ok(())
+                },
+                // This was the last queue descriptor that refers to the socket.
+                Some(true) => {
+                    self.sockets.remove(&qd);
+                    self.truncations.remove(&fd);
+                    self.runtime.unregister_file(fd)?;
+                    let request_id: RequestId = self.runtime.close(fd)?;
+                    match self.runtime.wait(request_id)? {
+                        0 => Ok(()),
+                        size => Err(Fail::new(-size, "failed to close socket")),
+                    }
+                },
+                None => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+            },
+            _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Asynchronously closes a socket. The returned operation completes once the underlying file descriptor is closed.
+    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
+        trace!("async_close() qd={:?}", qd);
+        self.cancel_operations(qd);
+        let fd: Option<RawFd> = match self.sockets.get(&qd) {
+            Some(&fd) => match self.qtable.release(qd) {
+                // Other queue descriptors still refer to the socket.
+                Some(false) => None,
+                // This was the last queue descriptor that refers to the socket.
+                Some(true) => {
+                    self.truncations.remove(&fd);
+                    Some(fd)
+                },
+                None => return Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+            },
+            _ => return Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        };
+        self.sockets.remove(&qd);
+
+        // Issue close operation.
+        let request_id: Option<RequestId> = match fd {
+            Some(fd) => {
+                self.runtime.unregister_file(fd)?;
+                Some(self.runtime.close(fd)?)
+            },
+            None => None,
+        };
+        let future: Operation = Operation::from(CloseFuture::new(self.runtime.clone(), request_id, qd));
+        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        Ok(qt)
+    }
+
+    /// Duplicates a queue descriptor. The new queue descriptor refers to the same socket, which is closed only once all
+    /// queue descriptors that refer to it are closed.
+    pub fn dup(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        trace!("dup() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(&fd) => {
+                self.qtable.check_qd_limit()?;
+                match self.qtable.dup(qd) {
+                    Some(new_qd) => {
+                        self.sockets.insert(new_qd, fd);
+                        Ok(new_qd)
+                    },
+                    None => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+                }
+            },
+            _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Shuts down the receive side, send side, or both sides of a socket. This goes through the io_uring, but waits
+    /// for the operation to complete.
+    pub fn shutdown(&mut self, qd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        trace!("shutdown() qd={:?} how={:?}", qd, how);
+        let how: c_int = match how {
+            Shutdown::Read => libc::SHUT_RD,
+            Shutdown::Write => libc::SHUT_WR,
+            Shutdown::Both => libc::SHUT_RDWR,
+        };
         match self.sockets.get(&qd) {
-            Some(&fd) => match unistd::close(fd) {
-                Ok(_) => Ok(()),
-                _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+            Some(&fd) => {
+                let request_id: RequestId = self.runtime.shutdown(fd, how)?;
+                match self.runtime.wait(request_id)? {
+                    0 => Ok(()),
+                    size => Err(Fail::new(-size, "failed to shutdown socket")),
+                }
             },
             _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
         }
     }
 
+    /// Gets the local address of a socket.
+    pub fn local_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("local_addr() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(&fd) => match socket::getsockname::<SockaddrIn>(fd) {
+                Ok(addr) => Ok(SocketAddrV4::from(addr)),
+                Err(err) => Err(Fail::new(err as i32, "failed to get local address")),
+            },
+            _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the remote address of a socket.
+    pub fn remote_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("remote_addr() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(&fd) => match socket::getpeername::<SockaddrIn>(fd) {
+                Ok(addr) => Ok(SocketAddrV4::from(addr)),
+                Err(err) => Err(Fail::new(err as i32, "failed to get remote address")),
+            },
+            _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the current value of a socket option.
+    pub fn get_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        trace!("get_socket_option() qd={:?} opt={:?}", qd, opt);
+        let fd: RawFd = match self.sockets.get(&qd) {
+            Some(&fd) => fd,
+            _ => return Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        };
+        let result: Result<SocketOption, Errno> = match opt {
+            SocketOption::SendBufferSize(_) => {
+                socket::getsockopt(fd, sockopt::SndBuf).map(SocketOption::SendBufferSize)
+            },
+            SocketOption::ReceiveBufferSize(_) => {
+                socket::getsockopt(fd, sockopt::RcvBuf).map(SocketOption::ReceiveBufferSize)
+            },
+            SocketOption::NoDelay(_) => socket::getsockopt(fd, sockopt::TcpNoDelay).map(SocketOption::NoDelay),
+            SocketOption::Cork(_) => {
+                let mut cork: bool = false;
+                Errno::result(unsafe { linux::get_tcp_flag(fd, libc::TCP_CORK, &mut cork) })
+                    .map(|_| SocketOption::Cork(cork))
+            },
+            SocketOption::QuickAck(_) => {
+                let mut quickack: bool = false;
+                Errno::result(unsafe { linux::get_tcp_flag(fd, libc::TCP_QUICKACK, &mut quickack) })
+                    .map(|_| SocketOption::QuickAck(quickack))
+            },
+            SocketOption::KeepAlive(_) => socket::getsockopt(fd, sockopt::KeepAlive).map(SocketOption::KeepAlive),
+            SocketOption::Ttl(_) => socket::getsockopt(fd, sockopt::Ipv4Ttl).map(|ttl| SocketOption::Ttl(ttl as u8)),
+            SocketOption::Broadcast(_) => socket::getsockopt(fd, sockopt::Broadcast).map(SocketOption::Broadcast),
+            // Pops do not collect ancillary data, so packet information is never reported.
+            SocketOption::PacketInfo(_) => Err(Errno::ENOPROTOOPT),
+            // The kernel runs its own congestion control algorithms, which we do not expose.
+            SocketOption::CongestionControl(_) => Err(Errno::ENOPROTOOPT),
+            // Pacing is left to the queueing discipline of the kernel.
+            SocketOption::MaxPacingRate(_) => Err(Errno::ENOPROTOOPT),
+            // The kernel only lets users switch to quick-ack mode.
+            SocketOption::AckDelay(_) | SocketOption::AckSegments(_) => Err(Errno::ENOPROTOOPT),
+            // The kernel autotunes buffers within system-wide bounds.
+            SocketOption::SendBufferBounds(..) | SocketOption::ReceiveBufferBounds(..) => Err(Errno::ENOPROTOOPT),
+        };
+        match result {
+            Ok(opt) => Ok(opt),
+            Err(err) => Err(Fail::new(err as i32, "failed to get socket option")),
+        }
+    }
+
+    /// Sets a socket option.
+    pub fn set_socket_option(&mut self, qd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        trace!("set_socket_option() qd={:?} opt={:?}", qd, opt);
+        let fd: RawFd = match self.sockets.get(&qd) {
+            Some(&fd) => fd,
+            _ => return Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        };
+        let result: Result<(), Errno> = match opt {
+            SocketOption::SendBufferSize(size) => socket::setsockopt(fd, sockopt::SndBuf, &size),
+            SocketOption::ReceiveBufferSize(size) => socket::setsockopt(fd, sockopt::RcvBuf, &size),
+            SocketOption::NoDelay(nodelay) => socket::setsockopt(fd, sockopt::TcpNoDelay, &nodelay),
+            SocketOption::Cork(cork) => {
+                Errno::result(unsafe { linux::set_tcp_flag(fd, libc::TCP_CORK, cork) }).map(drop)
+            },
+            SocketOption::QuickAck(quickack) => {
+                Errno::result(unsafe { linux::set_tcp_flag(fd, libc::TCP_QUICKACK, quickack) }).map(drop)
+            },
+            SocketOption::KeepAlive(keepalive) => socket::setsockopt(fd, sockopt::KeepAlive, &keepalive),
+            SocketOption::Ttl(ttl) => socket::setsockopt(fd, sockopt::Ipv4Ttl, &(ttl as c_int)),
+            SocketOption::Broadcast(broadcast) => socket::setsockopt(fd, sockopt::Broadcast, &broadcast),
+            SocketOption::PacketInfo(_) => Err(Errno::ENOPROTOOPT),
+            SocketOption::CongestionControl(_)
+            | SocketOption::MaxPacingRate(_)
+            | SocketOption::AckDelay(_)
+            | SocketOption::AckSegments(_)
+            | SocketOption::SendBufferBounds(..)
+            | SocketOption::ReceiveBufferBounds(..) => Err(Errno::ENOPROTOOPT),
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Fail::new(err as i32, "failed to set socket option")),
+        }
+    }
+
+    /// Gets the number of datagrams that were truncated when popped from a UDP socket.
+    pub fn truncations(&self, qd: QDesc) -> Result<u64, Fail> {
+        trace!("truncations() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(fd) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => {
+                Ok(self.truncations.get(fd).copied().unwrap_or(0))
+            },
+            Some(_) => Err(Fail::new(libc::EINVAL, "invalid queue type")),
+            None => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Joins a UDP socket to a multicast group on the interface that has the address `iface`.
+    pub fn join_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!("join_multicast_group() qd={:?} group={:?} iface={:?}", qd, group, iface);
+        let fd: RawFd = self.get_udp_fd(qd)?;
+        let request: IpMembershipRequest = IpMembershipRequest::new(group, Some(iface));
+        match socket::setsockopt(fd, sockopt::IpAddMembership, &request) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Fail::new(err as i32, "failed to join multicast group")),
+        }
+    }
+
+    /// Removes a UDP socket from a multicast group on the interface that has the address `iface`.
+    pub fn leave_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!(
+            "leave_multicast_group() qd={:?} group={:?} iface={:?}",
+            qd,
+            group,
+            iface
+        );
+        let fd: RawFd = self.get_udp_fd(qd)?;
+        let request: IpMembershipRequest = IpMembershipRequest::new(group, Some(iface));
+        match socket::setsockopt(fd, sockopt::IpDropMembership, &request) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Fail::new(err as i32, "failed to leave multicast group")),
+        }
+    }
+
+    /// Sets the busy polling options of a socket, if the io_uring does not busy poll itself. If we fail, keep going
+    /// because sockets still work without busy polling.
+    fn set_busy_poll(&self, fd: RawFd) {
+        if let Some(busy_poll) = self.busy_poll.as_ref() {
+            let timeout: u32 = busy_poll.timeout.as_micros().min(u32::MAX as u128) as u32;
+            let mut options: Vec<(c_int, u32)> = vec![
+                (libc::SO_BUSY_POLL, timeout),
+                (libc::SO_PREFER_BUSY_POLL, busy_poll.prefer as u32),
+            ];
+            if let Some(budget) = busy_poll.budget {
+                options.push((libc::SO_BUSY_POLL_BUDGET, budget as u32));
+            }
+            for (optname, value) in options {
+                if unsafe { linux::set_socket_option(fd, optname, value) } != 0 {
+                    warn!("cannot set busy polling option {:?} ({:?})", optname, Errno::last());
+                }
+            }
+        }
+    }
+
+    /// Gets the underlying file descriptor of a UDP socket.
+    fn get_udp_fd(&self, qd: QDesc) -> Result<RawFd, Fail> {
+        match self.sockets.get(&qd) {
+            Some(&fd) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => Ok(fd),
+            Some(_) => Err(Fail::new(libc::EINVAL, "invalid queue type")),
+            None => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
     /// Pushes a scatter-gather array to a socket.
     pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
         trace!("push() qd={:?}", qd);
@@ -249,15 +663,46 @@ impl CatcollarLibOS {
         // Issue push operation.
         match self.sockets.get(&qd) {
             Some(&fd) => {
+                self.qtable.check_operation_limit()?;
                 // Issue operation.
                 let request_id: RequestId = self.runtime.push(fd, buf.clone())?;
 
-                let future: Operation = Operation::from(PushFuture::new(self.runtime.clone(), request_id, qd));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let future: Operation =
+                    Operation::from(PushFuture::new(self.runtime.clone(), qd, fd, vec![buf], request_id));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
+            },
+            _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Pushes multiple scatter-gather arrays to a socket as a single unit.
+    pub fn pushv(&mut self, qd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        trace!("pushv() qd={:?}, nsgas={:?}", qd, sgas.len());
+
+        let bufs: Vec<DemiBuffer> = self.runtime.clone_sgarrays(sgas)?;
+
+        // Issue vectored push operation.
+        match self.sockets.get(&qd) {
+            Some(&fd) => {
+                self.qtable.check_operation_limit()?;
+                // Issue operation.
+                let request_id: RequestId = self.runtime.pushv(fd, &bufs)?;
+
+                let future: Operation =
+                    Operation::from(PushFuture::new(self.runtime.clone(), qd, fd, bufs, request_id));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+                    Some(handle) => handle,
+                    None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+                };
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
             },
             _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
         }
@@ -276,17 +721,20 @@ impl CatcollarLibOS {
                 // Issue pushto operation.
                 match self.sockets.get(&qd) {
                     Some(&fd) => {
+                        self.qtable.check_operation_limit()?;
                         // Issue operation.
                         let addr: SockaddrStorage = parse_addr(remote);
                         let request_id: RequestId = self.runtime.pushto(fd, addr, buf.clone())?;
 
                         let future: Operation =
                             Operation::from(PushtoFuture::new(self.runtime.clone(), request_id, qd));
-                        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                             Some(handle) => handle,
                             None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                         };
-                        Ok(handle.into_raw().into())
+                        let qt: QToken = handle.into_raw().into();
+                        self.qtable.track(qt, &[qd], None);
+                        Ok(qt)
                     },
                     _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
                 }
@@ -295,30 +743,167 @@ impl CatcollarLibOS {
         }
     }
 
+    /// Pushes a scatter-gather array to a socket, and pops the response from it. The push and the pop are linked in the
+    /// io_uring, so that the kernel issues the pop right after the push completes, and the whole exchange completes as
+    /// a single pop operation.
+    pub fn pushpop(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        trace!("pushpop() qd={:?}", qd);
+
+        let buf: DemiBuffer = self.runtime.clone_sgarray(sga)?;
+        if buf.len() == 0 {
+            return Err(Fail::new(libc::EINVAL, "zero-length buffer"));
+        }
+
+        // Issue push-pop operation.
+        match self.sockets.get(&qd) {
+            Some(&fd) => {
+                self.qtable.check_operation_limit()?;
+                let recvbuf: DemiBuffer = DemiBuffer::new(CATCOLLAR_RECVBUF_SIZE);
+                let len: usize = buf.len();
+                let (push_id, pop_id): (RequestId, RequestId) = self.runtime.pushpop(fd, buf, recvbuf.clone())?;
+
+                let future: Operation = Operation::from(PushPopFuture::new(
+                    self.runtime.clone(),
+                    push_id,
+                    len,
+                    pop_id,
+                    qd,
+                    recvbuf,
+                ));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+                    Some(handle) => handle,
+                    None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+                };
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
+            },
+            _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        }
+    }
+
     /// Pops data from a socket.
     pub fn pop(&mut self, qd: QDesc) -> Result<QToken, Fail> {
         trace!("pop() qd={:?}", qd);
 
-        let buf: DemiBuffer = DemiBuffer::new(CATCOLLAR_RECVBUF_SIZE);
+        self.do_pop(qd, CATCOLLAR_RECVBUF_SIZE, None)
+    }
+
+    /// Pops at most `size` bytes of data from a socket.
+    pub fn pop_sized(&mut self, qd: QDesc, size: usize) -> Result<QToken, Fail> {
+        trace!("pop_sized() qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if size == 0 || size > u16::MAX as usize {
+            return Err(Fail::new(libc::EINVAL, "invalid pop size"));
+        }
+
+        self.do_pop(qd, size as u16, None)
+    }
+
+    /// Pops data from a socket, failing with `ETIMEDOUT` if no data arrives by `deadline`. If `size` is set, this
+    /// behaves as [CatcollarLibOS::pop_sized], otherwise it behaves as [CatcollarLibOS::pop].
+    pub fn timedpop(&mut self, qd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        trace!("timedpop() qd={:?}, size={:?}", qd, size);
+
+        let size: u16 = match size {
+            // Check if pop size is valid.
+            Some(size) if size == 0 || size > u16::MAX as usize => {
+                return Err(Fail::new(libc::EINVAL, "invalid pop size"));
+            },
+            Some(size) => size as u16,
+            None => CATCOLLAR_RECVBUF_SIZE,
+        };
+
+        self.do_pop(qd, size, Some(deadline))
+    }
+
+    /// Issues a pop operation that receives into a buffer of `size` bytes. The operation is linked to a timeout that
+    /// expires at `deadline`, if any.
+    fn do_pop(&mut self, qd: QDesc, size: u16, deadline: Option<Instant>) -> Result<QToken, Fail> {
+        let buf: DemiBuffer = DemiBuffer::new(size);
+        let timeout: Option<Duration> = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
 
         // Issue pop operation.
         match self.sockets.get(&qd) {
             Some(&fd) => {
-                let request_id: RequestId = self.runtime.pop(fd, buf.clone())?;
+                self.qtable.check_operation_limit()?;
+                let request_id: RequestId = self.runtime.pop(fd, buf.clone(), timeout)?;
                 let future: Operation = Operation::from(PopFuture::new(self.runtime.clone(), request_id, qd, buf));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
                 let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
                 Ok(qt)
             },
             _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
         }
     }
 
-    pub fn poll(&self) {
-        self.runtime.scheduler.poll()
+    /// Forwards data from one socket to another until the remote peer of the source socket closes the connection.
+    pub fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        trace!("splice() srcqd={:?}, dstqd={:?}", srcqd, dstqd);
+
+        let (src, dst): (RawFd, RawFd) = match (self.sockets.get(&srcqd), self.sockets.get(&dstqd)) {
+            (Some(&src), Some(&dst)) => (src, dst),
+            _ => return Err(Fail::new(libc::EBADF, "invalid queue descriptor")),
+        };
+
+        // Check if queue types are valid.
+        let tcp: Option<u32> = Some(QType::TcpSocket.into());
+        if self.qtable.get(srcqd) != tcp || self.qtable.get(dstqd) != tcp {
+            return Err(Fail::new(libc::EINVAL, "invalid queue type"));
+        }
+        if src == dst {
+            return Err(Fail::new(libc::EINVAL, "cannot splice a queue into itself"));
+        }
+
+        // Issue splice operation.
+        self.qtable.check_operation_limit()?;
+        let future: Operation = Operation::from(SpliceFuture::new(
+            self.runtime.clone(),
+            srcqd,
+            src,
+            dst,
+            CATCOLLAR_RECVBUF_SIZE,
+        ));
+        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        self.qtable.track(qt, &[srcqd, dstqd], None);
+        Ok(qt)
+    }
+
+    pub fn poll(&mut self) {
+        self.runtime.scheduler.advance_clock(Instant::now());
+        self.runtime.scheduler.poll();
+        // Completions are reaped once the event file descriptor is reset, so that those that are posted afterwards
+        // still signal it.
+        self.runtime.poll_completions();
+    }
+
+    /// Returns an event file descriptor that becomes readable when some queue token may have completed. The I/O user
+    /// ring signals it whenever some request completes.
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        let fd: RawFd = self.runtime.scheduler.event_fd()?;
+        self.runtime.register_eventfd(fd)?;
+        Ok(fd)
+    }
+
+    /// Parks for at most `timeout` if the scheduler has been idle for long enough, until some request completes or some
+    /// queue token may have completed. If the event file descriptor is not available, this sleeps instead.
+    pub fn park(&self, timeout: Option<Duration>) {
+        if let Err(e) = self
+            .event_fd()
+            .and_then(|fd| self.runtime.scheduler.park_on(fd, timeout))
+        {
+            warn!("park(): cannot park on event file descriptor ({:?})", e);
+            self.runtime.scheduler.park(timeout)
+        }
     }
 
     pub fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
@@ -328,8 +913,16 @@ impl CatcollarLibOS {
         }
     }
 
+    /// Takes out the earliest completed operation that satisfies `pred`.
+    pub fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        self.runtime
+            .scheduler
+            .take_completed(|key| pred(QToken::from(key)))
+            .map(QToken::from)
+    }
+
     pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
-        let (qd, r): (QDesc, OperationResult) = self.take_result(handle);
+        let (qd, r): (QDesc, OperationResult) = self.take_result(handle, qt);
         Ok(pack_result(&self.runtime, r, qd, qt.into()))
     }
 
@@ -339,12 +932,48 @@ impl CatcollarLibOS {
         self.runtime.alloc_sgarray(size)
     }
 
+    /// Registers application-owned memory as a scatter-gather array.
+    pub fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        trace!("sgaregister() len={:?}", len);
+        self.runtime.register_sgarray(data, len)
+    }
+
     /// Frees a scatter-gather array.
     pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
         trace!("sgafree()");
         self.runtime.free_sgarray(sga)
     }
 
+    /// Tears down the target LibOS. All pending operations are canceled, all sockets are closed, and the underlying
+    /// io_uring is released. If some socket fails to close, the first error is returned once teardown completes.
+    pub fn teardown(mut self) -> Result<(), Fail> {
+        trace!("teardown()");
+
+        // Cancel requests in the io_uring first, so that the kernel no longer accesses their buffers. Then cancel the
+        // pending operations that own these buffers.
+        self.runtime.cancel_requests()?;
+        let ncanceled: usize = self.runtime.scheduler.cancel_all();
+        trace!("teardown(): canceled {:?} operations", ncanceled);
+
+        // Close all sockets.
+        let mut result: Result<(), Fail> = Ok(());
+        for (qd, fd) in self.sockets.drain() {
+            // Duplicated sockets are closed along with their last queue descriptor.
+            if self.qtable.release(qd) != Some(true) {
+                continue;
+            }
+            if let Err(e) = self.runtime.unregister_file(fd) {
+                warn!("failed to unregister socket (qd={:?}): {:?}", qd, e);
+            }
+            if let Err(e) = unistd::close(fd) {
+                warn!("failed to close socket (qd={:?}): {:?}", qd, e);
+                result = result.and(Err(Fail::new(e as i32, "failed to close socket")));
+            }
+        }
+
+        result
+    }
+
     #[deprecated]
     pub fn local_ipv4_addr(&self) -> Ipv4Addr {
         todo!()
@@ -355,8 +984,37 @@ impl CatcollarLibOS {
         &self.runtime
     }
 
-    /// Takes out the operation result descriptor associated with the target scheduler handle.
-    fn take_result(&mut self, handle: SchedulerHandle) -> (QDesc, OperationResult) {
+    /// Cancels the operations that are pending on the queue descriptor `qd`, which is about to be closed. These
+    /// operations complete with `ECANCELED`, unless they have already completed. Their requests in the io_uring are
+    /// canceled as well, and released only once the kernel is done with them.
+    fn cancel_operations(&mut self, qd: QDesc) {
+        let scheduler: &Scheduler = &self.runtime.scheduler;
+        let ncanceled: usize = self.qtable.cancel(qd, |qt| match scheduler.abort(qt.into()) {
+            Some(boxed_future) => {
+                let boxed_future: Box<dyn Any> = boxed_future.as_any();
+                let mut operation: Operation = *boxed_future.downcast::<Operation>().expect("Wrong type!");
+                if let Err(e) = operation.cancel() {
+                    warn!("cannot cancel request (qd={:?}): {:?}", qd, e);
+                }
+                true
+            },
+            None => false,
+        });
+        trace!("canceled {:?} operations (qd={:?})", ncanceled, qd);
+    }
+
+    /// Takes out the operation result descriptor associated with the target scheduler handle, which refers to the
+    /// operation `qt`.
+    fn take_result(&mut self, handle: SchedulerHandle, qt: QToken) -> (QDesc, OperationResult) {
+        // The operation was canceled because its queue descriptor was closed.
+        if let Some(qd) = self.qtable.untrack(qt) {
+            self.runtime.scheduler.take(handle);
+            return (
+                qd,
+                OperationResult::Failed(Fail::new(libc::ECANCELED, "operation canceled")),
+            );
+        }
+
         let boxed_future: Box<dyn Any> = self.runtime.scheduler.take(handle).as_any();
         let boxed_concrete_type: Operation = *boxed_future.downcast::<Operation>().expect("Wrong type!");
 
@@ -368,6 +1026,10 @@ impl CatcollarLibOS {
         if let Some(new_qd) = new_qd {
             // Associate raw file descriptor with queue descriptor.
             if let Some(new_fd) = new_fd {
+                self.set_busy_poll(new_fd);
+                if let Err(e) = self.runtime.register_file(new_fd) {
+                    warn!("cannot register socket ({:?})", e);
+                }
                 assert_eq!(self.sockets.insert(new_qd, new_fd).is_none(), true);
             }
             // Release entry in queue table.
@@ -376,10 +1038,336 @@ impl CatcollarLibOS {
             }
         }
 
+        // A zero-length read on a stream socket means that the remote peer has closed the connection.
+        let qr: OperationResult = match qr {
+            OperationResult::Pop(addr, buf, flags, info)
+                if buf.is_empty() && self.qtable.get(qd) == Some(QType::TcpSocket.into()) =>
+            {
+                OperationResult::Pop(addr, buf, flags | DEMI_QR_FLAG_EOF, info)
+            },
+            qr => qr,
+        };
+
+        // Account datagrams that did not fit in the receive buffer.
+        if let OperationResult::Pop(_, _, flags, _) = qr {
+            if flags & DEMI_QR_FLAG_TRUNC != 0 {
+                if let Some(&fd) = self.sockets.get(&qd) {
+                    *self.truncations.entry(fd).or_insert(0) += 1;
+                }
+            }
+        }
+
         (qd, qr)
     }
 }
 
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Network LibOS Trait Implementation for Catcollar LibOS
+impl NetworkLibOSTrait for CatcollarLibOS {
+    fn socket(&mut self, domain: libc::c_int, socket_type: libc::c_int, protocol: libc::c_int) -> Result<QDesc, Fail> {
+        CatcollarLibOS::socket(self, domain, socket_type, protocol)
+    }
+
+    fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
+        CatcollarLibOS::bind(self, sockqd, local)
+    }
+
+    fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+        CatcollarLibOS::listen(self, sockqd, backlog)
+    }
+
+    fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatcollarLibOS::accept(self, sockqd)
+    }
+
+    fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
+        CatcollarLibOS::connect(self, sockqd, remote)
+    }
+
+    fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        CatcollarLibOS::timedconnect(self, sockqd, remote, deadline)
+    }
+
+    fn close(&mut self, sockqd: QDesc) -> Result<(), Fail> {
+        CatcollarLibOS::close(self, sockqd)
+    }
+
+    fn async_close(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatcollarLibOS::async_close(self, sockqd)
+    }
+
+    fn dup(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        CatcollarLibOS::dup(self, sockqd)
+    }
+
+    fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        CatcollarLibOS::shutdown(self, sockqd, how)
+    }
+
+    fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        CatcollarLibOS::local_addr(self, sockqd)
+    }
+
+    fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        CatcollarLibOS::remote_addr(self, sockqd)
+    }
+
+    fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        CatcollarLibOS::get_socket_option(self, sockqd, opt)
+    }
+
+    fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        CatcollarLibOS::set_socket_option(self, sockqd, opt)
+    }
+
+    fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        CatcollarLibOS::truncations(self, sockqd)
+    }
+
+    fn backlog_overflows(&self, _sockqd: QDesc) -> Result<u64, Fail> {
+        // The kernel only keeps track of backlog overflows system-wide.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "backlog_overflows() is not supported on kernel sockets",
+        ))
+    }
+
+    fn tcp_info(&self, _sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        // The kernel keeps this state, and only reports it through its own socket options.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "tcp_info() is not supported on kernel sockets",
+        ))
+    }
+
+    fn timestamps(&self, _sockqd: QDesc) -> Result<Timestamps, Fail> {
+        // Hardware timestamps are only reported by the kernel through SO_TIMESTAMPING control messages.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "timestamps() is not supported on kernel sockets",
+        ))
+    }
+
+    fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        CatcollarLibOS::join_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        CatcollarLibOS::leave_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        CatcollarLibOS::push(self, sockqd, sga)
+    }
+
+    fn pushv(&mut self, sockqd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        CatcollarLibOS::pushv(self, sockqd, sgas)
+    }
+
+    fn pushpop(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        CatcollarLibOS::pushpop(self, sockqd, sga)
+    }
+
+    fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
+        CatcollarLibOS::pushto(self, sockqd, sga, to)
+    }
+
+    fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatcollarLibOS::pop(self, sockqd)
+    }
+
+    fn pop_sized(&mut self, sockqd: QDesc, size: usize) -> Result<QToken, Fail> {
+        CatcollarLibOS::pop_sized(self, sockqd, size)
+    }
+
+    fn timedpop(&mut self, sockqd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        CatcollarLibOS::timedpop(self, sockqd, size, deadline)
+    }
+
+    fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        CatcollarLibOS::splice(self, srcqd, dstqd)
+    }
+
+    fn ping(&mut self, _addr: Ipv4Addr, _timeout: Option<Duration>) -> Result<QToken, Fail> {
+        // Sending ICMP messages through the kernel requires raw sockets, and thus privileges that we do not assume.
+        Err(Fail::new(libc::ENOTSUP, "ping() is not supported on kernel sockets"))
+    }
+
+    fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        // The kernel keeps its own neighbor table, which it only exposes through netlink.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_entries() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_insert(&mut self, _ipv4_addr: Ipv4Addr, _link_addr: MacAddress) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_insert() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_pending_drops() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_flush(&mut self) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_flush() is not supported on kernel sockets",
+        ))
+    }
+
+    fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "ipv4_addrs() is not supported on kernel sockets",
+        ))
+    }
+
+    fn add_ipv4_addr(&mut self, _ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_ipv4_addr() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_ipv4_addr(&mut self, _ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_ipv4_addr() is not supported on kernel sockets",
+        ))
+    }
+
+    fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "dhcp_lease() is not supported on kernel sockets",
+        ))
+    }
+
+    fn link_events(&mut self) -> Result<QDesc, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "link_events() is not supported on kernel sockets",
+        ))
+    }
+
+    fn link_state(&self) -> Result<LinkState, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "link_state() is not supported on kernel sockets",
+        ))
+    }
+
+    fn routes(&self) -> Result<Vec<Route>, Fail> {
+        Err(Fail::new(libc::ENOTSUP, "routes() is not supported on kernel sockets"))
+    }
+
+    fn add_route(&mut self, _route: Route) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_route() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_route(&mut self, _destination: Ipv4Addr, _prefix_len: u8) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_route() is not supported on kernel sockets",
+        ))
+    }
+
+    fn set_arp_policy(
+        &mut self,
+        _cache_ttl: Duration,
+        _request_timeout: Duration,
+        _retry_count: usize,
+    ) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "set_arp_policy() is not supported on kernel sockets",
+        ))
+    }
+
+    fn add_flow_rule(&mut self, _rule: FlowRule) -> Result<u32, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_flow_rule() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_flow_rule(&mut self, _id: u32) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_flow_rule() is not supported on kernel sockets",
+        ))
+    }
+
+    fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        self.runtime.scheduler.spawn(future)
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        self.runtime.scheduler.sleep_until(deadline)
+    }
+
+    fn poll(&mut self) {
+        CatcollarLibOS::poll(self)
+    }
+
+    fn park(&mut self, timeout: Option<Duration>) {
+        CatcollarLibOS::park(self, timeout)
+    }
+
+    fn tasks(&self) -> Vec<TaskInfo> {
+        self.runtime.scheduler.tasks()
+    }
+
+    fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.qtable.limit_stats()
+    }
+
+    fn event_fd(&self) -> Result<RawFd, Fail> {
+        CatcollarLibOS::event_fd(self)
+    }
+
+    fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
+        CatcollarLibOS::schedule(self, qt)
+    }
+
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        CatcollarLibOS::take_completed(self, pred)
+    }
+
+    fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
+        CatcollarLibOS::pack_result(self, handle, qt)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        CatcollarLibOS::sgaalloc(self, size)
+    }
+
+    fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        CatcollarLibOS::sgaregister(self, data, len)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        CatcollarLibOS::sgafree(self, sga)
+    }
+
+    fn teardown(self: Box<Self>) -> Result<(), Fail> {
+        CatcollarLibOS::teardown(*self)
+    }
+}
+
 //======================================================================================================================
 // Standalone Functions
 //======================================================================================================================
@@ -399,6 +1387,8 @@ fn pack_result(rt: &IoUringRuntime, result: OperationResult, qd: QDesc, qt: u64)
             qr_opcode: demi_opcode_t::DEMI_OPC_CONNECT,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
         OperationResult::Accept(new_qd) => {
@@ -413,6 +1403,8 @@ fn pack_result(rt: &IoUringRuntime, result: OperationResult, qd: QDesc, qt: u64)
                 qr_opcode: demi_opcode_t::DEMI_OPC_ACCEPT,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: 0,
                 qr_value,
             }
         },
@@ -420,11 +1412,49 @@ fn pack_result(rt: &IoUringRuntime, result: OperationResult, qd: QDesc, qt: u64)
             qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
-        OperationResult::Pop(addr, bytes) => match rt.into_sgarray(bytes) {
+        OperationResult::Splice(_) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_SPLICE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Ping(rtt) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_PING,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                rtt: rtt.as_nanos() as i64,
+            },
+        },
+        OperationResult::Link(state) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_LINK,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                link_up: state.is_up() as u32,
+            },
+        },
+        OperationResult::Close => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_CLOSE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Pop(addr, bytes, mut flags, info) => match rt.into_sgarray(bytes) {
             Ok(mut sga) => {
-                if let Some(endpoint) = addr {
+                if let Some(SocketAddress::Inet(endpoint)) = addr {
                     let saddr: libc::sockaddr_in = {
                         // TODO: check the following byte order conversion.
                         libc::sockaddr_in {
@@ -438,11 +1468,17 @@ fn pack_result(rt: &IoUringRuntime, result: OperationResult, qd: QDesc, qt: u64)
                     };
                     sga.sga_addr = unsafe { mem::transmute::<libc::sockaddr_in, libc::sockaddr>(saddr) };
                 }
+                if let Some(info) = info {
+                    sga.sga_pktinfo = demi_pktinfo_t::from(info);
+                    flags |= DEMI_QR_FLAG_PKTINFO;
+                }
                 let qr_value: demi_qr_value_t = demi_qr_value_t { sga };
                 demi_qresult_t {
                     qr_opcode: demi_opcode_t::DEMI_OPC_POP,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: flags,
+                    qr_ret: 0,
                     qr_value,
                 }
             },
@@ -452,6 +1488,8 @@ fn pack_result(rt: &IoUringRuntime, result: OperationResult, qd: QDesc, qt: u64)
                     qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: e.errno as i64,
                     qr_value: unsafe { mem::zeroed() },
                 }
             },
@@ -462,6 +1500,8 @@ fn pack_result(rt: &IoUringRuntime, result: OperationResult, qd: QDesc, qt: u64)
                 qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: e.errno as i64,
                 qr_value: unsafe { mem::zeroed() },
             }
         },
@@ -8,7 +8,10 @@ mod network;
 // Imports
 //==============================================================================
 
-use super::iouring::IoUring;
+use super::iouring::{
+    IoUring,
+    Request,
+};
 use crate::{
     runtime::{
         fail::Fail,
@@ -18,9 +21,13 @@ use crate::{
     },
     scheduler::scheduler::Scheduler,
 };
+use ::libc::c_int;
 use ::nix::sys::socket::SockaddrStorage;
 use ::std::{
-    cell::RefCell,
+    cell::{
+        RefCell,
+        RefMut,
+    },
     collections::{
         HashMap,
         HashSet,
@@ -32,6 +39,8 @@ use ::std::{
     },
     os::unix::prelude::RawFd,
     rc::Rc,
+    task::Waker,
+    time::Duration,
 };
 
 //==============================================================================
@@ -41,6 +50,9 @@ use ::std::{
 /// Number of slots in an I/O User ring.
 const CATCOLLAR_NUM_RINGS: u32 = 128;
 
+/// Number of slots in the table of registered files of an I/O user ring.
+const CATCOLLAR_NUM_FILES: u32 = 1024;
+
 //==============================================================================
 // Structures
 //==============================================================================
@@ -56,10 +68,14 @@ pub struct IoUringRuntime {
     pub scheduler: Scheduler,
     /// Underlying io_uring.
     io_uring: Rc<RefCell<IoUring>>,
-    /// Pending requests.
-    pending: HashSet<RequestId>,
+    /// Pending requests, which are shared by all operations, since any of them may reap their completions.
+    pending: Rc<RefCell<HashSet<RequestId>>>,
     /// Completed requests.
-    completed: HashMap<RequestId, i32>,
+    completed: Rc<RefCell<HashMap<RequestId, i32>>>,
+    /// Tasks that wait for pending requests to complete.
+    waiters: Rc<RefCell<HashMap<RequestId, Waker>>>,
+    /// Pending requests that were canceled, and are released as soon as they complete.
+    orphaned: Rc<RefCell<HashSet<RequestId>>>,
 }
 
 //==============================================================================
@@ -68,22 +84,63 @@ pub struct IoUringRuntime {
 
 /// Associate Functions for I/O User Ring Runtime
 impl IoUringRuntime {
-    /// Creates an I/O user ring runtime.
-    pub fn new() -> Self {
-        let io_uring: IoUring = IoUring::new(CATCOLLAR_NUM_RINGS).expect("cannot create io_uring");
+    /// Creates an I/O user ring runtime. If `sqpoll` is set, the submission queue of the ring is polled by a kernel
+    /// thread, which goes to sleep once it has been idle for that long. If `send_zc_threshold` is set, pushes of at
+    /// least that many bytes are zero-copy sends. Operations are submitted, and their completions reaped, in batches of
+    /// up to `batch_size`.
+    pub fn new(sqpoll: Option<Duration>, send_zc_threshold: Option<usize>, batch_size: usize) -> Self {
+        let io_uring: IoUring = IoUring::new(
+            CATCOLLAR_NUM_RINGS,
+            sqpoll,
+            send_zc_threshold,
+            batch_size,
+            CATCOLLAR_NUM_FILES,
+        )
+        .expect("cannot create io_uring");
         Self {
             scheduler: Scheduler::default(),
             io_uring: Rc::new(RefCell::new(io_uring)),
-            pending: HashSet::new(),
-            completed: HashMap::new(),
+            pending: Rc::new(RefCell::new(HashSet::new())),
+            completed: Rc::new(RefCell::new(HashMap::new())),
+            waiters: Rc::new(RefCell::new(HashMap::new())),
+            orphaned: Rc::new(RefCell::new(HashSet::new())),
         }
     }
 
+    /// Registers a socket in the target I/O user ring, so that operations on it avoid looking up its file.
+    pub fn register_file(&mut self, fd: RawFd) -> Result<(), Fail> {
+        self.io_uring.borrow_mut().register_file(fd)
+    }
+
+    /// Unregisters a socket from the target I/O user ring. This should come before closing the socket.
+    pub fn unregister_file(&mut self, fd: RawFd) -> Result<(), Fail> {
+        self.io_uring.borrow_mut().unregister_file(fd)
+    }
+
+    /// Makes the target I/O user ring signal the event file descriptor `fd` whenever some request completes.
+    pub fn register_eventfd(&self, fd: RawFd) -> Result<(), Fail> {
+        self.io_uring.borrow_mut().register_eventfd(fd)
+    }
+
+    /// Makes the target I/O user ring busy poll the network devices of its sockets for `timeout` while it waits for
+    /// completions.
+    pub fn register_napi(&mut self, timeout: Duration, prefer: bool) -> Result<(), Fail> {
+        self.io_uring.borrow_mut().register_napi(timeout, prefer)
+    }
+
     /// Pushes a buffer to the target I/O user ring.
     pub fn push(&mut self, sockfd: RawFd, buf: DemiBuffer) -> Result<RequestId, Fail> {
         let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().push(sockfd, buf)?;
         let request_id: RequestId = RequestId(msg_ptr);
-        self.pending.insert(request_id);
+        self.pending.borrow_mut().insert(request_id);
+        Ok(request_id)
+    }
+
+    /// Pushes multiple buffers to the target I/O user ring in a single operation.
+    pub fn pushv(&mut self, sockfd: RawFd, bufs: &[DemiBuffer]) -> Result<RequestId, Fail> {
+        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().pushv(sockfd, bufs)?;
+        let request_id: RequestId = RequestId(msg_ptr);
+        self.pending.borrow_mut().insert(request_id);
         Ok(request_id)
     }
 
@@ -91,81 +148,236 @@ impl IoUringRuntime {
     pub fn pushto(&mut self, sockfd: i32, addr: SockaddrStorage, buf: DemiBuffer) -> Result<RequestId, Fail> {
         let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().pushto(sockfd, addr, buf)?;
         let request_id: RequestId = RequestId(msg_ptr);
-        self.pending.insert(request_id);
+        self.pending.borrow_mut().insert(request_id);
+        Ok(request_id)
+    }
+
+    /// Pops a buffer from the target I/O user ring. The operation is canceled if it does not complete within
+    /// `timeout`.
+    pub fn pop(&mut self, sockfd: RawFd, buf: DemiBuffer, timeout: Option<Duration>) -> Result<RequestId, Fail> {
+        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().pop(sockfd, buf, timeout)?;
+        let request_id: RequestId = RequestId(msg_ptr);
+        self.pending.borrow_mut().insert(request_id);
+        Ok(request_id)
+    }
+
+    /// Pushes a buffer to the target I/O user ring, and pops the response into `recvbuf` in a linked operation, which
+    /// the kernel issues once the push completes.
+    pub fn pushpop(
+        &mut self,
+        sockfd: RawFd,
+        buf: DemiBuffer,
+        recvbuf: DemiBuffer,
+    ) -> Result<(RequestId, RequestId), Fail> {
+        let (push_ptr, pop_ptr): (*const liburing::msghdr, *const liburing::msghdr) =
+            self.io_uring.borrow_mut().pushpop(sockfd, buf, recvbuf)?;
+        let (push_id, pop_id): (RequestId, RequestId) = (RequestId(push_ptr), RequestId(pop_ptr));
+        let mut pending: RefMut<HashSet<RequestId>> = self.pending.borrow_mut();
+        pending.insert(push_id);
+        pending.insert(pop_id);
+        Ok((push_id, pop_id))
+    }
+
+    /// Connects a socket to a remote address through the target I/O user ring. The operation is canceled if it does not
+    /// complete within `timeout`.
+    pub fn connect(
+        &mut self,
+        sockfd: RawFd,
+        addr: SockaddrStorage,
+        timeout: Option<Duration>,
+    ) -> Result<RequestId, Fail> {
+        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().connect(sockfd, addr, timeout)?;
+        let request_id: RequestId = RequestId(msg_ptr);
+        self.pending.borrow_mut().insert(request_id);
+        Ok(request_id)
+    }
+
+    /// Closes a file descriptor through the target I/O user ring.
+    pub fn close(&mut self, fd: RawFd) -> Result<RequestId, Fail> {
+        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().close(fd)?;
+        let request_id: RequestId = RequestId(msg_ptr);
+        self.pending.borrow_mut().insert(request_id);
+        Ok(request_id)
+    }
+
+    /// Waits for a file descriptor to become ready for `events` through the target I/O user ring.
+    pub fn poll_add(&mut self, fd: RawFd, events: u32) -> Result<RequestId, Fail> {
+        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().poll_add(fd, events)?;
+        let request_id: RequestId = RequestId(msg_ptr);
+        self.pending.borrow_mut().insert(request_id);
         Ok(request_id)
     }
 
-    /// Pops a buffer from the target I/O user ring.
-    pub fn pop(&mut self, sockfd: RawFd, buf: DemiBuffer) -> Result<RequestId, Fail> {
-        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().pop(sockfd, buf)?;
+    /// Shuts down a socket through the target I/O user ring.
+    pub fn shutdown(&mut self, sockfd: RawFd, how: c_int) -> Result<RequestId, Fail> {
+        let msg_ptr: *const liburing::msghdr = self.io_uring.borrow_mut().shutdown(sockfd, how)?;
         let request_id: RequestId = RequestId(msg_ptr);
-        self.pending.insert(request_id);
+        self.pending.borrow_mut().insert(request_id);
         Ok(request_id)
     }
 
+    /// Waits for an operation to complete in the target I/O user ring, and returns its outcome.
+    pub fn wait(&mut self, request_id: RequestId) -> Result<i32, Fail> {
+        loop {
+            if let (_, Some(size)) = self.peek(request_id)? {
+                return Ok(size);
+            }
+            match self.reap(true) {
+                Ok(()) => (),
+                Err(e) if e.errno == libc::EAGAIN || e.errno == libc::EINTR => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Wakes up the task of `waker` once the request `request_id` completes. Tasks that wait on a request are only
+    /// polled again once its completion is reaped, either by [IoUringRuntime::poll_completions] or by some peek.
+    pub fn wait_completion(&self, request_id: RequestId, waker: &Waker) {
+        if self.completed.borrow().contains_key(&request_id) {
+            waker.wake_by_ref();
+            return;
+        }
+        self.waiters.borrow_mut().insert(request_id, waker.clone());
+    }
+
+    /// Cancels the request `request_id`, whose operation is no longer waited for. Pending requests are released once
+    /// the kernel completes them, so that their buffers outlive the accesses of the kernel.
+    pub fn cancel_request(&mut self, request_id: RequestId) -> Result<(), Fail> {
+        self.waiters.borrow_mut().remove(&request_id);
+
+        // The request has completed already, but its outcome was not peeked yet.
+        if self.completed.borrow_mut().remove(&request_id).is_some() {
+            drop(unsafe { Rc::from_raw(request_id.0 as *const Request) });
+            return Ok(());
+        }
+
+        if self.pending.borrow().contains(&request_id) {
+            self.orphaned.borrow_mut().insert(request_id);
+            self.io_uring.borrow_mut().cancel(request_id.0)?;
+        }
+        Ok(())
+    }
+
+    /// Submits the operations that are queued in the target I/O user ring, and reaps completions without waiting for
+    /// any, waking up the tasks that wait on them.
+    pub fn poll_completions(&mut self) {
+        match self.reap(false) {
+            Ok(()) => (),
+            Err(e) if e.errno == libc::EAGAIN || e.errno == libc::EINTR => (),
+            Err(e) => warn!("failed to reap completions ({:?})", e),
+        }
+    }
+
     /// Peeks for the completion of an operation in the target I/O user ring.
     pub fn peek(&mut self, request_id: RequestId) -> Result<(Option<SocketAddrV4>, Option<i32>), Fail> {
+        let (addr, size, _): (Option<SocketAddrV4>, Option<i32>, c_int) = self.peek_msg(request_id)?;
+        Ok((addr, size))
+    }
+
+    /// Peeks for the completion of an operation in the target I/O user ring. Along with the outcome of the operation,
+    /// this returns the flags that the kernel reported for the message (e.g. `MSG_TRUNC`).
+    pub fn peek_msg(&mut self, request_id: RequestId) -> Result<(Option<SocketAddrV4>, Option<i32>, c_int), Fail> {
+        // Reap a batch of completions, unless the target request has already completed.
+        if !self.completed.borrow().contains_key(&request_id) {
+            match self.reap(false) {
+                Ok(()) => (),
+                // Operation in progress.
+                Err(e) if e.errno == libc::EAGAIN => return Ok((None, None, 0)),
+                // Operation failed.
+                Err(e) => return Err(e),
+            }
+        }
+
         // Check if pending request has completed.
-        match self.completed.remove(&request_id) {
-            // The target request has already completed.
-            Some(size) => {
-                let msg: Rc<liburing::msghdr> = unsafe { Rc::from_raw(request_id.0) };
-                let addr: Option<SocketAddrV4> = if msg.msg_name.is_null() {
-                    None
-                } else {
-                    let saddr: *const libc::sockaddr = msg.msg_name as *const libc::sockaddr;
-                    let sin: libc::sockaddr_in =
-                        unsafe { *mem::transmute::<*const libc::sockaddr, *const libc::sockaddr_in>(saddr) };
-                    let addr: Ipv4Addr = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
-                    let port: u16 = u16::from_be(sin.sin_port);
-                    Some(SocketAddrV4::new(addr, port))
-                };
-
-                // Done.
-                Ok((addr, Some(size)))
-            },
-            // The target request may not be completed.
-            None => {
-                // Peek the underlying io_uring.
-                match self.io_uring.borrow_mut().wait() {
-                    // Some operation has completed.
-                    Ok((other_request_id, size)) => {
-                        // This is not the request that we are waiting for.
-                        if request_id.0 != other_request_id {
-                            let other_request_id: RequestId = RequestId(other_request_id);
-                            match self.pending.remove(&other_request_id) {
-                                true => self.completed.insert(other_request_id, size),
-                                false => None,
-                            };
-                            return Ok((None, None));
-                        }
-                        let msg: Rc<liburing::msghdr> = unsafe { Rc::from_raw(request_id.0) };
-                        let addr: Option<SocketAddrV4> = if msg.msg_name.is_null() {
-                            None
-                        } else {
-                            let saddr: *const libc::sockaddr = msg.msg_name as *const libc::sockaddr;
-                            let sin: libc::sockaddr_in =
-                                unsafe { *mem::transmute::<*const libc::sockaddr, *const libc::sockaddr_in>(saddr) };
-                            let addr: Ipv4Addr = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
-                            let port: u16 = u16::from_be(sin.sin_port);
-                            Some(SocketAddrV4::new(addr, port))
-                        };
-
-                        // Done.
-                        Ok((addr, Some(size)))
-                    },
-                    // Something bad has happened.
-                    Err(e) => {
-                        match e.errno {
-                            // Operation in progress.
-                            libc::EAGAIN => Ok((None, None)),
-                            // Operation failed.
-                            _ => Err(e),
-                        }
-                    },
+        let size: i32 = match self.completed.borrow_mut().remove(&request_id) {
+            Some(size) => size,
+            // The target request has not completed yet.
+            None => return Ok((None, None, 0)),
+        };
+        let request: Rc<Request> = unsafe { Rc::from_raw(request_id.0 as *const Request) };
+        let msg: &liburing::msghdr = &request.msg;
+        let addr: Option<SocketAddrV4> = if msg.msg_name.is_null() {
+            None
+        } else {
+            let saddr: *const libc::sockaddr = msg.msg_name as *const libc::sockaddr;
+            let sin: libc::sockaddr_in =
+                unsafe { *mem::transmute::<*const libc::sockaddr, *const libc::sockaddr_in>(saddr) };
+            let addr: Ipv4Addr = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+            let port: u16 = u16::from_be(sin.sin_port);
+            Some(SocketAddrV4::new(addr, port))
+        };
+
+        // Done.
+        Ok((addr, Some(size), msg.msg_flags))
+    }
+
+    /// Submits the operations that are queued in the target I/O user ring, and reaps a batch of completions, waiting
+    /// for one if none is available and `block` is set. Tasks that wait on completed requests are woken up.
+    fn reap(&mut self, block: bool) -> Result<(), Fail> {
+        let completions: Vec<(*mut liburing::msghdr, i32)> = if block {
+            self.io_uring.borrow_mut().wait()?
+        } else {
+            self.io_uring.borrow_mut().try_wait()?
+        };
+        let wakers: Vec<Waker> = {
+            let mut pending: RefMut<HashSet<RequestId>> = self.pending.borrow_mut();
+            let mut completed: RefMut<HashMap<RequestId, i32>> = self.completed.borrow_mut();
+            let mut waiters: RefMut<HashMap<RequestId, Waker>> = self.waiters.borrow_mut();
+            let mut orphaned: RefMut<HashSet<RequestId>> = self.orphaned.borrow_mut();
+            let mut wakers: Vec<Waker> = Vec::new();
+            for (msg_ptr, size) in completions {
+                let request_id: RequestId = RequestId(msg_ptr);
+                if !pending.remove(&request_id) {
+                    continue;
+                }
+                // Nobody waits for canceled requests, so release them right away.
+                if orphaned.remove(&request_id) {
+                    drop(unsafe { Rc::from_raw(request_id.0 as *const Request) });
+                    continue;
                 }
-            },
+                completed.insert(request_id, size);
+                wakers.extend(waiters.remove(&request_id));
+            }
+            wakers
+        };
+        self.scheduler.wake(wakers);
+        Ok(())
+    }
+
+    /// Cancels all pending requests in the target I/O user ring, and waits for them to complete, so that the kernel
+    /// no longer accesses their buffers. Requests that were not peeked yet are discarded as well.
+    pub fn cancel_requests(&mut self) -> Result<(), Fail> {
+        let mut io_uring: RefMut<IoUring> = self.io_uring.borrow_mut();
+        let mut pending: RefMut<HashSet<RequestId>> = self.pending.borrow_mut();
+        let mut completed: RefMut<HashMap<RequestId, i32>> = self.completed.borrow_mut();
+        for request_id in pending.iter() {
+            io_uring.cancel(request_id.0)?;
+        }
+
+        // Wait for all pending requests to complete, and for the kernel to be done with the buffers of zero-copy sends.
+        while !pending.is_empty() || io_uring.has_notifications() {
+            match io_uring.wait() {
+                Ok(completions) => {
+                    for (request_id, _) in completions {
+                        let request_id: RequestId = RequestId(request_id);
+                        if pending.remove(&request_id) {
+                            completed.insert(request_id, -libc::ECANCELED);
+                        }
+                    }
+                },
+                Err(e) if e.errno == libc::EAGAIN || e.errno == libc::EINTR => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Release requests. Tasks that wait on them are not polled anymore.
+        self.waiters.borrow_mut().clear();
+        self.orphaned.borrow_mut().clear();
+        for (request_id, _) in completed.drain() {
+            drop(unsafe { Rc::from_raw(request_id.0 as *const Request) });
         }
+
+        Ok(())
     }
 }
 
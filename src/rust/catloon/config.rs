@@ -0,0 +1,33 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::demikernel::config::Config;
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Config {
+    /// Reads the "XDP queue" parameter from the underlying configuration file. This is the receive queue of the network
+    /// interface that the XDP socket is bound to, which defaults to the first one. Shards are bound to the queues that
+    /// follow it, by their index.
+    pub fn xdp_queue_id(&self) -> u32 {
+        // FIXME: this function should return a Result.
+        match self.0["catloon"]["queue_id"].as_i64() {
+            Some(queue_id) if queue_id < 0 || queue_id > u32::MAX as i64 => panic!("Invalid XDP queue {}", queue_id),
+            Some(queue_id) => queue_id as u32,
+            None => 0,
+        }
+    }
+
+    /// Reads the "XDP zero-copy" parameter from the underlying configuration file, if present. If set, the XDP socket
+    /// must (or must not) share its UMEM with the network interface. Otherwise, zero-copy mode is used if the driver of
+    /// the network interface supports it.
+    pub fn xdp_zero_copy(&self) -> Option<bool> {
+        self.0["catloon"]["zero_copy"].as_bool()
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    catloon::XdpRuntime,
+    runtime::{
+        memory::MemoryRuntime,
+        network::types::SocketAddress,
+        types::{
+            demi_accept_result_t,
+            demi_opcode_t,
+            demi_pktinfo_t,
+            demi_qr_value_t,
+            demi_qresult_t,
+            DEMI_QR_FLAG_PKTINFO,
+        },
+        QDesc,
+    },
+    OperationResult,
+};
+use ::std::{
+    mem,
+    rc::Rc,
+};
+
+pub fn pack_result(rt: Rc<XdpRuntime>, result: OperationResult, qd: QDesc, qt: u64) -> demi_qresult_t {
+    match result {
+        OperationResult::Connect => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_CONNECT,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Accept(new_qd) => {
+            let sin = unsafe { mem::zeroed() };
+            let qr_value = demi_qr_value_t {
+                ares: demi_accept_result_t {
+                    qd: new_qd.into(),
+                    addr: sin,
+                },
+            };
+            demi_qresult_t {
+                qr_opcode: demi_opcode_t::DEMI_OPC_ACCEPT,
+                qr_qd: qd.into(),
+                qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: 0,
+                qr_value,
+            }
+        },
+        OperationResult::Push => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Splice(_) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_SPLICE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Ping(rtt) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_PING,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                rtt: rtt.as_nanos() as i64,
+            },
+        },
+        OperationResult::Link(state) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_LINK,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                link_up: state.is_up() as u32,
+            },
+        },
+        OperationResult::Close => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_CLOSE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Pop(addr, bytes, mut flags, info) => match rt.into_sgarray(bytes) {
+            Ok(mut sga) => {
+                if let Some(SocketAddress::Inet(endpoint)) = addr {
+                    let saddr: libc::sockaddr_in = {
+                        // TODO: check the following byte order conversion.
+                        libc::sockaddr_in {
+                            sin_family: libc::AF_INET as u16,
+                            sin_port: endpoint.port().into(),
+                            sin_addr: libc::in_addr {
+                                s_addr: u32::from_le_bytes(endpoint.ip().octets()),
+                            },
+                            sin_zero: [0; 8],
+                        }
+                    };
+                    sga.sga_addr = unsafe { mem::transmute::<libc::sockaddr_in, libc::sockaddr>(saddr) };
+                }
+                if let Some(info) = info {
+                    sga.sga_pktinfo = demi_pktinfo_t::from(info);
+                    flags |= DEMI_QR_FLAG_PKTINFO;
+                }
+                let qr_value = demi_qr_value_t { sga };
+                demi_qresult_t {
+                    qr_opcode: demi_opcode_t::DEMI_OPC_POP,
+                    qr_qd: qd.into(),
+                    qr_qt: qt,
+                    qr_flags: flags,
+                    qr_ret: 0,
+                    qr_value,
+                }
+            },
+            Err(e) => {
+                warn!("Operation Failed: {:?}", e);
+                demi_qresult_t {
+                    qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
+                    qr_qd: qd.into(),
+                    qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: e.errno as i64,
+                    qr_value: unsafe { mem::zeroed() },
+                }
+            },
+        },
+        OperationResult::Failed(e) => {
+            warn!("Operation Failed: {:?}", e);
+            demi_qresult_t {
+                qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
+                qr_qd: qd.into(),
+                qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: e.errno as i64,
+                qr_value: unsafe { mem::zeroed() },
+            }
+        },
+    }
+}
@@ -0,0 +1,510 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod config;
+mod interop;
+pub mod runtime;
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use self::{
+    interop::pack_result,
+    runtime::XdpRuntime,
+};
+use crate::{
+    demikernel::{
+        config::Config,
+        libos::network::NetworkLibOSTrait,
+    },
+    inetstack::{
+        operations::OperationResult,
+        InetStack,
+    },
+    runtime::{
+        fail::Fail,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::IoQueueLimitStats,
+        timer::{
+            Timer,
+            TimerRc,
+        },
+        types::{
+            demi_qresult_t,
+            demi_sgarray_t,
+        },
+        QDesc,
+        QToken,
+    },
+    scheduler::{
+        Scheduler,
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
+};
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
+use ::std::{
+    collections::HashMap,
+    future::Future,
+    net::{
+        Ipv4Addr,
+        Shutdown,
+        SocketAddrV4,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    pin::Pin,
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+#[cfg(feature = "profiler")]
+use crate::timer;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Catloon LibOS
+pub struct CatloonLibOS {
+    scheduler: Scheduler,
+    inetstack: InetStack,
+    rt: Rc<XdpRuntime>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Catloon LibOS
+impl CatloonLibOS {
+    /// Instantiates a Catloon LibOS.
+    pub fn new(config: &Config) -> Self {
+        let rt: Rc<XdpRuntime> = Rc::new(XdpRuntime::new(
+            config.local_ipv4_addr(),
+            &config.local_interface_name(),
+            HashMap::default(),
+            config.arp_announcements(),
+            config.routes(),
+            config.mtu(),
+            config.shard(),
+            config.gro(),
+            config.xdp_queue_id(),
+            config.xdp_zero_copy(),
+        ));
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_budget(config.poll_budget());
+        if let Some(policy) = config.idle_policy() {
+            scheduler.set_idle_policy(policy);
+        }
+        scheduler.set_poll_timing(config.poll_timing());
+        let clock: TimerRc = TimerRc(Rc::new(Timer::from_scheduler(&scheduler)));
+        let rng_seed: [u8; 32] = [0; 32];
+        let mut inetstack: InetStack = InetStack::new(
+            rt.clone(),
+            scheduler.clone(),
+            clock,
+            rt.link_addr,
+            rt.ipv4_addr,
+            rt.udp_options.clone(),
+            rt.tcp_options.clone(),
+            rng_seed,
+            rt.arp_options.clone(),
+        )
+        .unwrap();
+        inetstack.set_queue_limits(config.queue_limits());
+        for ipv4_addr in config.secondary_ipv4_addrs() {
+            inetstack
+                .add_ipv4_addr(ipv4_addr)
+                .expect("invalid secondary IPv4 address in config");
+        }
+        if config.dhcp() {
+            inetstack.start_dhcp().expect("failed to start DHCP client");
+            inetstack
+                .wait_for_dhcp_lease(config.dhcp_timeout())
+                .expect("failed to get a DHCP lease");
+        }
+        CatloonLibOS {
+            scheduler,
+            inetstack,
+            rt,
+        }
+    }
+
+    /// Create a push request for Demikernel to asynchronously write data from `sga` to the
+    /// IO connection represented by `qd`. This operation returns immediately with a `QToken`.
+    /// The data has been written when [`wait`ing](Self::wait) on the QToken returns.
+    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("catloon::push");
+        trace!("push(): qd={:?}", qd);
+
+        match self.rt.clone_sgarray(sga) {
+            Ok(buf) => {
+                if buf.len() == 0 {
+                    return Err(Fail::new(libc::EINVAL, "zero-length buffer"));
+                }
+                let future = self.do_push(qd, buf)?;
+                let qt: QToken = self.insert_operation(future, &[qd])?;
+                Ok(qt)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn pushv(&mut self, qd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("catloon::pushv");
+        trace!("pushv(): qd={:?}, nsgas={:?}", qd, sgas.len());
+        let bufs: Vec<DemiBuffer> = self.rt.clone_sgarrays(sgas)?;
+        let future = self.do_pushv(qd, bufs)?;
+        let qt: QToken = self.insert_operation(future, &[qd])?;
+        Ok(qt)
+    }
+
+    pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("catloon::pushto");
+        trace!("pushto2(): qd={:?}", qd);
+        match self.rt.clone_sgarray(sga) {
+            Ok(buf) => {
+                if buf.len() == 0 {
+                    return Err(Fail::new(libc::EINVAL, "zero-length buffer"));
+                }
+                let future = self.do_pushto(qd, buf, to)?;
+                let qt: QToken = self.insert_operation(future, &[qd])?;
+                Ok(qt)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
+        match self.scheduler.from_raw_handle(qt.into()) {
+            Some(handle) => Ok(handle),
+            None => return Err(Fail::new(libc::EINVAL, "invalid queue token")),
+        }
+    }
+
+    pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
+        let (qd, r): (QDesc, OperationResult) = self.take_operation(handle, qt);
+        Ok(pack_result(self.rt.clone(), r, qd, qt.into()))
+    }
+
+    /// Allocates a scatter-gather array.
+    pub fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        self.rt.alloc_sgarray(size)
+    }
+
+    /// Registers application-owned memory as a scatter-gather array.
+    pub fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        self.rt.register_sgarray(data, len)
+    }
+
+    /// Releases a scatter-gather array.
+    pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        self.rt.free_sgarray(sga)
+    }
+
+    /// Tears down the target LibOS. All queue descriptors are closed, pending frames are flushed, pending operations
+    /// are canceled, and the underlying XDP socket is closed once the runtime is dropped.
+    pub fn teardown(mut self) -> Result<(), Fail> {
+        trace!("teardown()");
+        self.inetstack.teardown()
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// De-Reference Trait Implementation for Catloon LibOS
+impl Deref for CatloonLibOS {
+    type Target = InetStack;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inetstack
+    }
+}
+
+/// Mutable De-Reference Trait Implementation for Catloon LibOS
+impl DerefMut for CatloonLibOS {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inetstack
+    }
+}
+
+/// Network LibOS Trait Implementation for Catloon LibOS
+impl NetworkLibOSTrait for CatloonLibOS {
+    fn socket(&mut self, domain: libc::c_int, socket_type: libc::c_int, protocol: libc::c_int) -> Result<QDesc, Fail> {
+        InetStack::socket(self, domain, socket_type, protocol)
+    }
+
+    fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
+        InetStack::bind(self, sockqd, local)
+    }
+
+    fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+        InetStack::listen(self, sockqd, backlog)
+    }
+
+    fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::accept(self, sockqd)
+    }
+
+    fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
+        InetStack::connect(self, sockqd, remote)
+    }
+
+    fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        InetStack::timedconnect(self, sockqd, remote, deadline)
+    }
+
+    fn close(&mut self, sockqd: QDesc) -> Result<(), Fail> {
+        InetStack::close(self, sockqd)
+    }
+
+    fn async_close(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::async_close(self, sockqd)
+    }
+
+    fn dup(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        InetStack::dup(self, sockqd)
+    }
+
+    fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        InetStack::shutdown(self, sockqd, how)
+    }
+
+    fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        InetStack::local_addr(self, sockqd)
+    }
+
+    fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        InetStack::remote_addr(self, sockqd)
+    }
+
+    fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        InetStack::get_socket_option(self, sockqd, opt)
+    }
+
+    fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        InetStack::set_socket_option(self, sockqd, opt)
+    }
+
+    fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        InetStack::truncations(self, sockqd)
+    }
+
+    fn backlog_overflows(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        InetStack::backlog_overflows(self, sockqd)
+    }
+
+    fn tcp_info(&self, sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        InetStack::tcp_info(self, sockqd)
+    }
+
+    fn timestamps(&self, sockqd: QDesc) -> Result<Timestamps, Fail> {
+        InetStack::timestamps(self, sockqd)
+    }
+
+    fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::join_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::leave_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        CatloonLibOS::push(self, sockqd, sga)
+    }
+
+    fn pushv(&mut self, sockqd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        CatloonLibOS::pushv(self, sockqd, sgas)
+    }
+
+    fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
+        CatloonLibOS::pushto(self, sockqd, sga, to)
+    }
+
+    fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::pop(self, sockqd)
+    }
+
+    fn pop_sized(&mut self, sockqd: QDesc, size: usize) -> Result<QToken, Fail> {
+        InetStack::pop_sized(self, sockqd, size)
+    }
+
+    fn timedpop(&mut self, sockqd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        InetStack::timedpop(self, sockqd, size, deadline)
+    }
+
+    fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::splice(self, srcqd, dstqd)
+    }
+
+    fn ping(&mut self, addr: Ipv4Addr, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        InetStack::ping(self, addr, timeout)
+    }
+
+    fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        InetStack::arp_entries(self)
+    }
+
+    fn arp_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Result<(), Fail> {
+        InetStack::arp_insert(self, ipv4_addr, link_addr)
+    }
+
+    fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        InetStack::arp_pending_drops(self)
+    }
+
+    fn arp_flush(&mut self) -> Result<(), Fail> {
+        InetStack::arp_flush(self)
+    }
+
+    fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        InetStack::ipv4_addrs(self)
+    }
+
+    fn add_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::add_ipv4_addr(self, ipv4_addr)
+    }
+
+    fn remove_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::remove_ipv4_addr(self, ipv4_addr)
+    }
+
+    fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        InetStack::dhcp_lease(self)
+    }
+
+    fn link_events(&mut self) -> Result<QDesc, Fail> {
+        InetStack::link_events(self)
+    }
+
+    fn link_state(&self) -> Result<LinkState, Fail> {
+        InetStack::link_state(self)
+    }
+
+    fn routes(&self) -> Result<Vec<Route>, Fail> {
+        InetStack::routes(self)
+    }
+
+    fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        InetStack::add_route(self, route)
+    }
+
+    fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        InetStack::remove_route(self, destination, prefix_len)
+    }
+
+    fn set_arp_policy(
+        &mut self,
+        cache_ttl: Duration,
+        request_timeout: Duration,
+        retry_count: usize,
+    ) -> Result<(), Fail> {
+        InetStack::set_arp_policy(self, cache_ttl, request_timeout, retry_count)
+    }
+
+    fn add_flow_rule(&mut self, _rule: FlowRule) -> Result<u32, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "hardware flow rules are not supported on this libos",
+        ))
+    }
+
+    fn remove_flow_rule(&mut self, _id: u32) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "hardware flow rules are not supported on this libos",
+        ))
+    }
+
+    fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        self.scheduler.spawn(future)
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        self.scheduler.sleep_until(deadline)
+    }
+
+    fn poll(&mut self) {
+        InetStack::poll_bg_work(self)
+    }
+
+    fn park(&mut self, timeout: Option<Duration>) {
+        // Incoming packets are only noticed when the network stack is polled, and the device runs without interrupts,
+        // so there is nothing to park on.
+        self.scheduler.park(timeout)
+    }
+
+    fn tasks(&self) -> Vec<TaskInfo> {
+        self.scheduler.tasks()
+    }
+
+    fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.inetstack.queue_limit_stats()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn event_fd(&self) -> Result<RawFd, Fail> {
+        // Incoming packets are only noticed when the network stack is polled, so there is no event to wait on.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "event file descriptors are not supported on this libos",
+        ))
+    }
+
+    fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
+        CatloonLibOS::schedule(self, qt)
+    }
+
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        InetStack::take_completed(self, pred)
+    }
+
+    fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
+        CatloonLibOS::pack_result(self, handle, qt)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        CatloonLibOS::sgaalloc(self, size)
+    }
+
+    fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        CatloonLibOS::sgaregister(self, data, len)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        CatloonLibOS::sgafree(self, sga)
+    }
+
+    fn teardown(self: Box<Self>) -> Result<(), Fail> {
+        CatloonLibOS::teardown(*self)
+    }
+}
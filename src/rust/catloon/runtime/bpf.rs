@@ -0,0 +1,393 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::fail::Fail;
+use ::libc;
+use ::std::{
+    collections::HashMap,
+    mem,
+    os::unix::prelude::RawFd,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        Weak,
+    },
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Commands of the `bpf()` system call (see `enum bpf_cmd` in Linux).
+const BPF_MAP_CREATE: libc::c_long = 0;
+const BPF_MAP_UPDATE_ELEM: libc::c_long = 2;
+const BPF_MAP_DELETE_ELEM: libc::c_long = 3;
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_LINK_CREATE: libc::c_long = 28;
+
+/// Type of the maps that redirect frames to XDP sockets (see `BPF_MAP_TYPE_XSKMAP` in Linux).
+const BPF_MAP_TYPE_XSKMAP: u32 = 17;
+
+/// Type of XDP programs (see `BPF_PROG_TYPE_XDP` in Linux).
+const BPF_PROG_TYPE_XDP: u32 = 6;
+
+/// Attach type of XDP programs (see `BPF_XDP` in Linux).
+const BPF_XDP: u32 = 37;
+
+/// Helper function that redirects frames to the entry of a map (see `BPF_FUNC_redirect_map` in Linux).
+const BPF_FUNC_REDIRECT_MAP: i32 = 51;
+
+/// Action of XDP programs that hands frames to the network stack of the kernel (see `XDP_PASS` in Linux).
+const XDP_PASS: i32 = 2;
+
+/// Offset of the index of the receive queue in the context of XDP programs (see `struct xdp_md` in Linux).
+const XDP_MD_RX_QUEUE_INDEX_OFFSET: i16 = 16;
+
+/// Source register of 64-bit immediate loads that refer to maps by file descriptor (see `BPF_PSEUDO_MAP_FD` in Linux).
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+
+/// Number of entries of the map, which bounds the receive queues that XDP sockets may be bound to.
+const MAX_QUEUES: u32 = 64;
+
+/// License of the program, which is compatible with the GPL-only helpers of the kernel.
+const LICENSE: &[u8] = b"Dual MIT/GPL\0";
+
+/// Attributes of `BPF_MAP_CREATE` (see `union bpf_attr` in Linux).
+#[repr(C)]
+struct MapCreateAttr {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+}
+
+/// Attributes of `BPF_MAP_UPDATE_ELEM` and `BPF_MAP_DELETE_ELEM` (see `union bpf_attr` in Linux).
+#[repr(C)]
+struct MapElemAttr {
+    map_fd: u32,
+    _pad: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+/// Attributes of `BPF_PROG_LOAD` (see `union bpf_attr` in Linux).
+#[repr(C)]
+struct ProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+    prog_ifindex: u32,
+    expected_attach_type: u32,
+}
+
+/// Attributes of `BPF_LINK_CREATE` (see `union bpf_attr` in Linux).
+#[repr(C)]
+struct LinkCreateAttr {
+    prog_fd: u32,
+    target_ifindex: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+/// Instruction of BPF programs (see `struct bpf_insn` in Linux).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BpfInsn {
+    code: u8,
+    /// Destination register in the low nibble, and source register in the high one.
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+/// XDP Program
+///
+/// Redirects the frames that are received on a queue of a network interface to the XDP socket that is bound to that
+/// queue, and hands all other frames to the network stack of the kernel. The program is detached once dropped.
+struct XdpProgram {
+    map_fd: libc::c_int,
+    prog_fd: libc::c_int,
+    link_fd: libc::c_int,
+}
+
+/// XDP Redirection
+///
+/// Redirects the frames that are received on a queue of a network interface to an XDP socket. Only one program may be
+/// attached to a network interface, thus the redirections of all queues of the interface share one program and its
+/// map, which is indexed by queue. The redirection is removed once dropped, and the program is detached along with the
+/// last redirection of its interface.
+pub struct XdpRedirect {
+    /// Program of the network interface. It is only dropped while [XDP_PROGRAMS] is locked.
+    program: Option<Arc<XdpProgram>>,
+    queue_id: u32,
+}
+
+//==============================================================================
+// Static Variables
+//==============================================================================
+
+/// Programs that are attached to network interfaces by this process, indexed by interface, so that the runtimes of all
+/// shards share them. Programs are attached and detached while this is locked.
+static XDP_PROGRAMS: Mutex<Option<HashMap<u32, Weak<XdpProgram>>>> = Mutex::new(None);
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associated functions for XDP redirections.
+impl XdpRedirect {
+    /// Redirects the frames that are received on the queue `queue_id` of the network interface with index `ifindex` to
+    /// the XDP socket `xsk_fd`. The program of the interface is attached, unless some other redirection did already.
+    pub fn new(ifindex: u32, queue_id: u32, xsk_fd: RawFd) -> Result<Self, Fail> {
+        let mut programs: MutexGuard<Option<HashMap<u32, Weak<XdpProgram>>>> = Self::lock_programs()?;
+        let programs: &mut HashMap<u32, Weak<XdpProgram>> = programs.get_or_insert_with(HashMap::new);
+        let program: Arc<XdpProgram> = match programs.get(&ifindex).and_then(Weak::upgrade) {
+            Some(program) => program,
+            None => {
+                let program: Arc<XdpProgram> = Arc::new(XdpProgram::attach(ifindex)?);
+                programs.insert(ifindex, Arc::downgrade(&program));
+                program
+            },
+        };
+        program.insert(queue_id, xsk_fd)?;
+        Ok(Self {
+            program: Some(program),
+            queue_id,
+        })
+    }
+
+    /// Locks the programs that are attached by this process.
+    fn lock_programs() -> Result<MutexGuard<'static, Option<HashMap<u32, Weak<XdpProgram>>>>, Fail> {
+        XDP_PROGRAMS
+            .lock()
+            .map_err(|_| Fail::new(libc::EIO, "XDP programs are in an inconsistent state"))
+    }
+}
+
+/// Associated functions for XDP programs.
+impl XdpProgram {
+    /// Loads the program, and attaches it to the network interface with index `ifindex`.
+    fn attach(ifindex: u32) -> Result<Self, Fail> {
+        let mut program: XdpProgram = XdpProgram {
+            map_fd: -1,
+            prog_fd: -1,
+            link_fd: -1,
+        };
+
+        let map_attr: MapCreateAttr = MapCreateAttr {
+            map_type: BPF_MAP_TYPE_XSKMAP,
+            key_size: mem::size_of::<u32>() as u32,
+            value_size: mem::size_of::<u32>() as u32,
+            max_entries: MAX_QUEUES,
+            map_flags: 0,
+        };
+        program.map_fd = Self::bpf(BPF_MAP_CREATE, &map_attr, "failed to create XDP socket map")?;
+
+        // return bpf_redirect_map(&map, ctx->rx_queue_index, XDP_PASS);
+        let insns: [BpfInsn; 6] = [
+            // r2 = *(u32 *)(r1 + offsetof(struct xdp_md, rx_queue_index))
+            Self::insn(0x61, 2, 1, XDP_MD_RX_QUEUE_INDEX_OFFSET, 0),
+            // r1 = map
+            Self::insn(0x18, 1, BPF_PSEUDO_MAP_FD, 0, program.map_fd),
+            Self::insn(0, 0, 0, 0, 0),
+            // r3 = XDP_PASS
+            Self::insn(0xb7, 3, 0, 0, XDP_PASS),
+            // r0 = bpf_redirect_map(r1, r2, r3)
+            Self::insn(0x85, 0, 0, 0, BPF_FUNC_REDIRECT_MAP),
+            // return r0
+            Self::insn(0x95, 0, 0, 0, 0),
+        ];
+        let mut prog_name: [u8; 16] = [0; 16];
+        prog_name[..10].copy_from_slice(b"demikernel");
+        let prog_attr: ProgLoadAttr = ProgLoadAttr {
+            prog_type: BPF_PROG_TYPE_XDP,
+            insn_cnt: insns.len() as u32,
+            insns: insns.as_ptr() as u64,
+            license: LICENSE.as_ptr() as u64,
+            log_level: 0,
+            log_size: 0,
+            log_buf: 0,
+            kern_version: 0,
+            prog_flags: 0,
+            prog_name,
+            prog_ifindex: 0,
+            expected_attach_type: BPF_XDP,
+        };
+        program.prog_fd = Self::bpf(BPF_PROG_LOAD, &prog_attr, "failed to load XDP program")?;
+
+        let link_attr: LinkCreateAttr = LinkCreateAttr {
+            prog_fd: program.prog_fd as u32,
+            target_ifindex: ifindex,
+            attach_type: BPF_XDP,
+            flags: 0,
+        };
+        program.link_fd = Self::bpf(BPF_LINK_CREATE, &link_attr, "failed to attach XDP program")?;
+
+        Ok(program)
+    }
+
+    /// Redirects the frames that are received on the queue `queue_id` to the XDP socket `xsk_fd`.
+    fn insert(&self, queue_id: u32, xsk_fd: RawFd) -> Result<(), Fail> {
+        if queue_id >= MAX_QUEUES {
+            return Err(Fail::new(libc::EINVAL, "invalid receive queue"));
+        }
+        let key: u32 = queue_id;
+        let value: u32 = xsk_fd as u32;
+        let attr: MapElemAttr = MapElemAttr {
+            map_fd: self.map_fd as u32,
+            _pad: 0,
+            key: &key as *const u32 as u64,
+            value: &value as *const u32 as u64,
+            flags: 0,
+        };
+        Self::bpf(BPF_MAP_UPDATE_ELEM, &attr, "failed to insert XDP socket in map")?;
+        Ok(())
+    }
+
+    /// Stops redirecting the frames that are received on the queue `queue_id`.
+    fn remove(&self, queue_id: u32) -> Result<(), Fail> {
+        let key: u32 = queue_id;
+        let attr: MapElemAttr = MapElemAttr {
+            map_fd: self.map_fd as u32,
+            _pad: 0,
+            key: &key as *const u32 as u64,
+            value: 0,
+            flags: 0,
+        };
+        Self::bpf(BPF_MAP_DELETE_ELEM, &attr, "failed to remove XDP socket from map")?;
+        Ok(())
+    }
+
+    /// Builds an instruction.
+    fn insn(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> BpfInsn {
+        BpfInsn {
+            code,
+            regs: (src << 4) | (dst & 0xf),
+            off,
+            imm,
+        }
+    }
+
+    /// Issues a `bpf()` system call with the given attributes.
+    fn bpf<T>(cmd: libc::c_long, attr: &T, msg: &str) -> Result<libc::c_int, Fail> {
+        let ret: libc::c_long = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                cmd,
+                attr as *const T,
+                mem::size_of::<T>() as libc::c_uint,
+            )
+        };
+        if ret < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, msg));
+        }
+        Ok(ret as libc::c_int)
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Drop trait implementation for XDP redirections. The program of the interface is dropped while the programs are
+/// locked, so that it is detached before any other redirection attaches it again.
+impl Drop for XdpRedirect {
+    fn drop(&mut self) {
+        let programs: Result<MutexGuard<Option<HashMap<u32, Weak<XdpProgram>>>>, Fail> = Self::lock_programs();
+        if let Some(program) = self.program.take() {
+            if let Err(e) = program.remove(self.queue_id) {
+                warn!("failed to stop redirecting queue {:?}: {:?}", self.queue_id, e);
+            }
+            drop(program);
+        }
+        drop(programs);
+    }
+}
+
+/// Drop trait implementation for XDP programs. Closing the link detaches the program from the network interface.
+impl Drop for XdpProgram {
+    fn drop(&mut self) {
+        for fd in [self.link_fd, self.prog_fd, self.map_fd] {
+            if fd >= 0 {
+                unsafe { libc::close(fd) };
+            }
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BpfInsn,
+        LinkCreateAttr,
+        MapCreateAttr,
+        MapElemAttr,
+        ProgLoadAttr,
+        XdpProgram,
+        XdpRedirect,
+        BPF_PSEUDO_MAP_FD,
+        XDP_PROGRAMS,
+    };
+    use ::std::{
+        collections::HashMap,
+        mem,
+        sync::{
+            MutexGuard,
+            Weak,
+        },
+    };
+
+    #[test]
+    fn bpf_attr_layout() {
+        // These match the layout of the corresponding members of `union bpf_attr` in Linux.
+        assert_eq!(mem::size_of::<MapCreateAttr>(), 20);
+        assert_eq!(mem::size_of::<MapElemAttr>(), 32);
+        assert_eq!(mem::size_of::<ProgLoadAttr>(), 72);
+        assert_eq!(mem::size_of::<LinkCreateAttr>(), 16);
+        assert_eq!(mem::size_of::<BpfInsn>(), 8);
+    }
+
+    #[test]
+    fn bpf_insn() {
+        // Destination and source registers share a byte.
+        let insn: BpfInsn = XdpProgram::insn(0x18, 1, BPF_PSEUDO_MAP_FD, 0, 42);
+        assert_eq!(insn.code, 0x18);
+        assert_eq!(insn.regs, 0x11);
+        assert_eq!(insn.imm, 42);
+        let insn: BpfInsn = XdpProgram::insn(0x61, 2, 1, 16, 0);
+        assert_eq!(insn.regs, 0x12);
+        assert_eq!(insn.off, 16);
+    }
+
+    #[test]
+    fn xdp_redirect_failure() {
+        // There is no such network interface, so no program remains attached to it.
+        let ifindex: u32 = u32::MAX;
+        assert!(XdpRedirect::new(ifindex, 0, -1).is_err());
+        let programs: MutexGuard<Option<HashMap<u32, Weak<XdpProgram>>>> = XDP_PROGRAMS.lock().unwrap();
+        let attached: bool = programs
+            .as_ref()
+            .and_then(|programs| programs.get(&ifindex))
+            .map_or(false, |program| program.upgrade().is_some());
+        assert!(!attached);
+    }
+}
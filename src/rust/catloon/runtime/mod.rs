@@ -0,0 +1,147 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod bpf;
+mod memory;
+mod network;
+mod xsk;
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use self::{
+    bpf::XdpRedirect,
+    xsk::XdpSocket,
+};
+use crate::{
+    inetstack::protocols::ethernet2::ETHERNET2_HEADER_SIZE,
+    runtime::{
+        network::{
+            config::{
+                ArpConfig,
+                TcpConfig,
+                UdpConfig,
+            },
+            types::{
+                MacAddress,
+                Route,
+            },
+            xsk::FRAME_SIZE,
+        },
+        Runtime,
+    },
+};
+use ::std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    net::Ipv4Addr,
+    num::ParseIntError,
+    rc::Rc,
+    time::Duration,
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// XDP Runtime
+///
+/// Sends and receives frames through an XDP socket that is bound to a receive queue of a network interface. An XDP
+/// program redirects the frames that arrive on that queue to the socket, thus traffic of the kernel should be steered
+/// to other queues (e.g. with `ethtool` flow rules). Runtimes of different shards are bound to different queues, and
+/// they share the program of their network interface.
+#[derive(Clone)]
+pub struct XdpRuntime {
+    pub tcp_options: TcpConfig,
+    pub udp_options: UdpConfig,
+    pub arp_options: ArpConfig,
+    pub link_addr: MacAddress,
+    pub ipv4_addr: Ipv4Addr,
+    /// Redirection of frames to the socket. It comes first so that it is removed before the socket closes.
+    _redirect: Rc<XdpRedirect>,
+    socket: Rc<RefCell<XdpSocket>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for XDP Runtime
+impl XdpRuntime {
+    /// Instantiates an XDP Runtime on the receive queue `queue_id` of the network interface named `ifname`, or on the
+    /// queue that follows it by the index of `shard`, if any. If `zero_copy` is not set, zero-copy mode is used if the
+    /// driver of the interface supports it.
+    pub fn new(
+        ipv4_addr: Ipv4Addr,
+        ifname: &str,
+        arp: HashMap<Ipv4Addr, MacAddress>,
+        arp_announcements: Option<usize>,
+        routes: Vec<Route>,
+        mtu: u16,
+        shard: Option<(usize, usize)>,
+        gro: Option<bool>,
+        queue_id: u32,
+        zero_copy: Option<bool>,
+    ) -> Self {
+        if mtu as usize + ETHERNET2_HEADER_SIZE > FRAME_SIZE {
+            panic!("MTU {} does not fit in UMEM frames", mtu);
+        }
+        let arp_options: ArpConfig = ArpConfig::new(
+            Some(Duration::from_secs(600)),
+            Some(Duration::from_secs(1)),
+            Some(2),
+            Some(arp),
+            Some(false),
+            arp_announcements,
+            Some(routes),
+        );
+
+        // TODO: Make this constructor return a Result and drop expect() calls bellow.
+        let ifindex: u32 = Self::get_ifindex(ifname).expect("could not parse ifindex");
+        let link_addr: MacAddress = Self::get_link_addr(ifname);
+        let queue_id: u32 = queue_id + shard.map_or(0, |(index, _)| index as u32);
+        let socket: XdpSocket = XdpSocket::new(ifindex, queue_id, zero_copy).expect("could not create XDP socket");
+        let redirect: XdpRedirect =
+            XdpRedirect::new(ifindex, queue_id, socket.fd()).expect("could not redirect frames to XDP socket");
+
+        let mut tcp_options: TcpConfig = TcpConfig::default().set_mtu(mtu as usize);
+        if let Some((index, count)) = shard {
+            tcp_options = tcp_options.set_shard(index, count);
+        }
+        if let Some(gro) = gro {
+            tcp_options = tcp_options.set_gro(gro);
+        }
+
+        Self {
+            tcp_options,
+            udp_options: UdpConfig::new(None, None, Some(mtu as usize)),
+            arp_options,
+            link_addr,
+            ipv4_addr,
+            _redirect: Rc::new(redirect),
+            socket: Rc::new(RefCell::new(socket)),
+        }
+    }
+
+    /// Gets the interface index of the network interface named `ifname`.
+    fn get_ifindex(ifname: &str) -> Result<u32, ParseIntError> {
+        let path: String = format!("/sys/class/net/{}/ifindex", ifname);
+        fs::read_to_string(path).expect("could not read ifindex").trim().parse()
+    }
+
+    /// Gets the link address of the network interface named `ifname`.
+    fn get_link_addr(ifname: &str) -> MacAddress {
+        let path: String = format!("/sys/class/net/{}/address", ifname);
+        let link_addr: String = fs::read_to_string(path).expect("could not read link address");
+        MacAddress::parse_str(link_addr.trim()).expect("could not parse link address")
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Runtime Trait Implementation for XDP Runtime
+impl Runtime for XdpRuntime {}
@@ -0,0 +1,37 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::XdpRuntime;
+use crate::runtime::{
+    memory::DemiBuffer,
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        NetworkRuntime,
+        PacketBuf,
+    },
+};
+use ::arrayvec::ArrayVec;
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Network Runtime Trait Implementation for XDP Runtime
+impl NetworkRuntime for XdpRuntime {
+    /// Transmits a single [PacketBuf]. Its headers and body are written straight into a frame of the UMEM.
+    fn transmit(&self, pkt: Box<dyn PacketBuf>) {
+        if let Err(e) = self.socket.borrow_mut().transmit(pkt) {
+            warn!("dropping packet: {:?}", e);
+        }
+    }
+
+    /// Receives a batch of [DemiBuffer]. Frames are not copied out of the UMEM, and they are handed back once their
+    /// buffers are dropped.
+    fn receive(&self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
+        self.socket.borrow_mut().receive()
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        xsk::{
+            XskDescriptor,
+            XskFrames,
+            XskRing,
+            FRAME_HEADROOM,
+            FRAME_SIZE,
+            RING_SIZE,
+            UMEM_SIZE,
+        },
+        PacketBuf,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::libc;
+use ::std::{
+    mem,
+    os::unix::prelude::RawFd,
+    ptr,
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Address family of XDP sockets (see `AF_XDP` in Linux).
+const AF_XDP: libc::c_int = 44;
+
+/// Level of the options of XDP sockets (see `SOL_XDP` in Linux).
+const SOL_XDP: libc::c_int = 283;
+
+/// Socket options of XDP sockets (see `XDP_MMAP_OFFSETS` and the following ones in Linux).
+const XDP_MMAP_OFFSETS: libc::c_int = 1;
+const XDP_RX_RING: libc::c_int = 2;
+const XDP_TX_RING: libc::c_int = 3;
+const XDP_UMEM_REG: libc::c_int = 4;
+const XDP_UMEM_FILL_RING: libc::c_int = 5;
+const XDP_UMEM_COMPLETION_RING: libc::c_int = 6;
+
+/// Offsets at which the rings of XDP sockets are mapped (see `XDP_PGOFF_RX_RING` and the following ones in Linux).
+const XDP_PGOFF_RX_RING: libc::off_t = 0;
+const XDP_PGOFF_TX_RING: libc::off_t = 0x80000000;
+const XDP_UMEM_PGOFF_FILL_RING: libc::off_t = 0x100000000;
+const XDP_UMEM_PGOFF_COMPLETION_RING: libc::off_t = 0x180000000;
+
+/// Flags of XDP sockets addresses (see `XDP_COPY` and the following ones in Linux).
+const XDP_COPY: u16 = 1 << 1;
+const XDP_ZEROCOPY: u16 = 1 << 2;
+const XDP_USE_NEED_WAKEUP: u16 = 1 << 3;
+
+/// Flag of rings that is set when the kernel should be woken up to process them (see `XDP_RING_NEED_WAKEUP` in Linux).
+const XDP_RING_NEED_WAKEUP: u32 = 1 << 0;
+
+/// Address of an XDP socket (see `struct sockaddr_xdp` in Linux).
+#[repr(C)]
+struct SockAddrXdp {
+    sxdp_family: u16,
+    sxdp_flags: u16,
+    sxdp_ifindex: u32,
+    sxdp_queue_id: u32,
+    sxdp_shared_umem_fd: u32,
+}
+
+/// Offsets of the fields of a ring in its mapping (see `struct xdp_ring_offset` in Linux).
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct XdpRingOffset {
+    producer: u64,
+    consumer: u64,
+    desc: u64,
+    flags: u64,
+}
+
+/// Offsets of the fields of all rings in their mappings (see `struct xdp_mmap_offsets` in Linux).
+#[repr(C)]
+#[derive(Default)]
+struct XdpMmapOffsets {
+    rx: XdpRingOffset,
+    tx: XdpRingOffset,
+    fr: XdpRingOffset,
+    cr: XdpRingOffset,
+}
+
+/// Registration of a UMEM (see `struct xdp_umem_reg` in Linux).
+#[repr(C)]
+struct XdpUmemReg {
+    addr: u64,
+    len: u64,
+    chunk_size: u32,
+    headroom: u32,
+    flags: u32,
+    tx_metadata_len: u32,
+}
+
+/// Descriptor of the receive and transmit rings (see `struct xdp_desc` in Linux).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct XdpDesc {
+    addr: u64,
+    len: u32,
+    options: u32,
+}
+
+/// Mapping of a ring of an XDP socket, which is unmapped once dropped.
+struct RingMap {
+    map: *mut libc::c_void,
+    map_len: usize,
+}
+
+/// XDP Socket
+///
+/// Frames are exchanged with the kernel through the rings of the socket (see [XskFrames]), which own the UMEM.
+pub struct XdpSocket {
+    fd: libc::c_int,
+    frames: XskFrames<XdpDesc>,
+    /// Mappings of the rings, which are unmapped once the socket is closed.
+    _maps: Vec<RingMap>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associated functions for ring mappings.
+impl RingMap {
+    /// Maps the ring of an XDP socket that starts at `pgoff`, and whose descriptors are `desc_size` bytes long.
+    fn new(fd: libc::c_int, offsets: &XdpRingOffset, pgoff: libc::off_t, desc_size: usize) -> Result<Self, Fail> {
+        let map_len: usize = offsets.desc as usize + RING_SIZE as usize * desc_size;
+        let map: *mut libc::c_void = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd,
+                pgoff,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, "failed to map ring of XDP socket"));
+        }
+        Ok(Self { map, map_len })
+    }
+
+    /// Builds a ring out of the target mapping, whose descriptors are `desc_size` bytes long.
+    ///
+    /// # Safety
+    ///
+    /// The ring must not outlive the mapping.
+    unsafe fn ring(&self, offsets: &XdpRingOffset, desc_size: usize) -> XskRing {
+        let base: *mut u8 = self.map as *mut u8;
+        XskRing::new(
+            base.add(offsets.producer as usize),
+            base.add(offsets.consumer as usize),
+            base.add(offsets.flags as usize),
+            base.add(offsets.desc as usize),
+            desc_size,
+            XDP_RING_NEED_WAKEUP,
+        )
+    }
+}
+
+/// Associated functions for XDP sockets.
+impl XdpSocket {
+    /// Creates an XDP socket that is bound to the receive queue `queue_id` of the network interface with index
+    /// `ifindex`. If `zero_copy` is not set, zero-copy mode is used if the driver of the interface supports it.
+    pub fn new(ifindex: u32, queue_id: u32, zero_copy: Option<bool>) -> Result<Self, Fail> {
+        match zero_copy {
+            Some(true) => Self::create(ifindex, queue_id, XDP_ZEROCOPY),
+            Some(false) => Self::create(ifindex, queue_id, XDP_COPY),
+            None => match Self::create(ifindex, queue_id, XDP_ZEROCOPY) {
+                Ok(socket) => Ok(socket),
+                Err(e) => {
+                    warn!("zero-copy mode is not available ({:?}), falling back to copy mode", e);
+                    Self::create(ifindex, queue_id, XDP_COPY)
+                },
+            },
+        }
+    }
+
+    /// Gets the file descriptor of the target XDP socket.
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
+
+    /// Transmits a [PacketBuf].
+    pub fn transmit(&mut self, pkt: Box<dyn PacketBuf>) -> Result<(), Fail> {
+        // The kernel only processes the transmit ring when it is kicked.
+        if self.frames.transmit_packet(pkt)? {
+            let ret: isize = unsafe { libc::sendto(self.fd, ptr::null(), 0, libc::MSG_DONTWAIT, ptr::null(), 0) };
+            if ret == -1 {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                // These report that the kernel is busy, and will process the ring later on.
+                if errno != libc::EAGAIN && errno != libc::EBUSY && errno != libc::ENOBUFS {
+                    return Err(Fail::new(errno, "failed to kick XDP socket"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Receives a batch of frames, without copying them out of the UMEM.
+    pub fn receive(&mut self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
+        let (bufs, needs_wakeup): (ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>, bool) = self.frames.receive_batch();
+
+        // The kernel stops receiving frames when it runs out of frames to fill, until it is woken up.
+        if needs_wakeup {
+            unsafe {
+                libc::recvfrom(
+                    self.fd,
+                    ptr::null_mut(),
+                    0,
+                    libc::MSG_DONTWAIT,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            };
+        }
+        bufs
+    }
+
+    /// Creates an XDP socket, and binds it with the given flags.
+    fn create(ifindex: u32, queue_id: u32, flags: u16) -> Result<Self, Fail> {
+        let fd: libc::c_int = unsafe { libc::socket(AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0) };
+        if fd == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, "failed to create XDP socket"));
+        }
+
+        // Should anything fail from here on, the UMEM is released and the socket is closed.
+        let umem: *mut libc::c_void = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                UMEM_SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_POPULATE,
+                -1,
+                0,
+            )
+        };
+        if umem == libc::MAP_FAILED {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            unsafe { libc::close(fd) };
+            return Err(Fail::new(errno, "failed to allocate UMEM"));
+        }
+        let umem_reg: XdpUmemReg = XdpUmemReg {
+            addr: umem as u64,
+            len: UMEM_SIZE as u64,
+            chunk_size: FRAME_SIZE as u32,
+            headroom: FRAME_HEADROOM,
+            flags: 0,
+            tx_metadata_len: 0,
+        };
+        let maps: Result<(XdpMmapOffsets, Vec<RingMap>), Fail> = (|| {
+            Self::setsockopt(fd, XDP_UMEM_REG, &umem_reg)?;
+            Self::setsockopt(fd, XDP_UMEM_FILL_RING, &RING_SIZE)?;
+            Self::setsockopt(fd, XDP_UMEM_COMPLETION_RING, &RING_SIZE)?;
+            Self::setsockopt(fd, XDP_RX_RING, &RING_SIZE)?;
+            Self::setsockopt(fd, XDP_TX_RING, &RING_SIZE)?;
+            let offsets: XdpMmapOffsets = Self::mmap_offsets(fd)?;
+            let maps: Vec<RingMap> = vec![
+                RingMap::new(fd, &offsets.fr, XDP_UMEM_PGOFF_FILL_RING, mem::size_of::<u64>())?,
+                RingMap::new(fd, &offsets.cr, XDP_UMEM_PGOFF_COMPLETION_RING, mem::size_of::<u64>())?,
+                RingMap::new(fd, &offsets.rx, XDP_PGOFF_RX_RING, mem::size_of::<XdpDesc>())?,
+                RingMap::new(fd, &offsets.tx, XDP_PGOFF_TX_RING, mem::size_of::<XdpDesc>())?,
+            ];
+            Ok((offsets, maps))
+        })();
+        let (offsets, maps): (XdpMmapOffsets, Vec<RingMap>) = match maps {
+            Ok(maps) => maps,
+            Err(e) => {
+                unsafe {
+                    libc::munmap(umem, UMEM_SIZE);
+                    libc::close(fd);
+                }
+                return Err(e);
+            },
+        };
+
+        // Hand all receive frames to the kernel before binding, so that it can receive right away. Safety: the rings
+        // live as long as the socket, and the UMEM is aligned on pages.
+        let frames: XskFrames<XdpDesc> = unsafe {
+            XskFrames::new(
+                umem as *mut u8,
+                Self::release_umem,
+                maps[0].ring(&offsets.fr, mem::size_of::<u64>()),
+                maps[1].ring(&offsets.cr, mem::size_of::<u64>()),
+                maps[2].ring(&offsets.rx, mem::size_of::<XdpDesc>()),
+                maps[3].ring(&offsets.tx, mem::size_of::<XdpDesc>()),
+            )
+        };
+        let socket: XdpSocket = XdpSocket {
+            fd,
+            frames,
+            _maps: maps,
+        };
+
+        let addr: SockAddrXdp = SockAddrXdp {
+            sxdp_family: AF_XDP as u16,
+            sxdp_flags: flags | XDP_USE_NEED_WAKEUP,
+            sxdp_ifindex: ifindex,
+            sxdp_queue_id: queue_id,
+            sxdp_shared_umem_fd: 0,
+        };
+        let ret: libc::c_int = unsafe {
+            libc::bind(
+                socket.fd,
+                &addr as *const SockAddrXdp as *const libc::sockaddr,
+                mem::size_of::<SockAddrXdp>() as libc::socklen_t,
+            )
+        };
+        if ret == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, "failed to bind XDP socket"));
+        }
+
+        Ok(socket)
+    }
+
+    /// Releases the UMEM of an XDP socket, once neither the socket nor any buffer that refers to the UMEM is used.
+    unsafe fn release_umem(umem: *mut u8) {
+        libc::munmap(umem as *mut libc::c_void, UMEM_SIZE);
+    }
+
+    /// Sets an option of an XDP socket.
+    fn setsockopt<T>(fd: libc::c_int, name: libc::c_int, value: &T) -> Result<(), Fail> {
+        let ret: libc::c_int = unsafe {
+            libc::setsockopt(
+                fd,
+                SOL_XDP,
+                name,
+                value as *const T as *const libc::c_void,
+                mem::size_of::<T>() as libc::socklen_t,
+            )
+        };
+        if ret == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, "failed to set option of XDP socket"));
+        }
+        Ok(())
+    }
+
+    /// Gets the offsets of the fields of the rings of an XDP socket in their mappings.
+    fn mmap_offsets(fd: libc::c_int) -> Result<XdpMmapOffsets, Fail> {
+        let mut offsets: XdpMmapOffsets = XdpMmapOffsets::default();
+        let mut len: libc::socklen_t = mem::size_of::<XdpMmapOffsets>() as libc::socklen_t;
+        let ret: libc::c_int = unsafe {
+            libc::getsockopt(
+                fd,
+                SOL_XDP,
+                XDP_MMAP_OFFSETS,
+                &mut offsets as *mut XdpMmapOffsets as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, "failed to get ring offsets of XDP socket"));
+        }
+        Ok(offsets)
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Descriptor trait implementation for descriptors of XDP sockets.
+impl XskDescriptor for XdpDesc {
+    fn new(addr: u64, len: u32) -> Self {
+        Self { addr, len, options: 0 }
+    }
+
+    fn data(&self) -> (u64, usize) {
+        (self.addr, self.len as usize)
+    }
+}
+
+/// Drop trait implementation for ring mappings.
+impl Drop for RingMap {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.map, self.map_len) };
+    }
+}
+
+/// Drop trait implementation for XDP sockets. The rings are unmapped afterwards, which is fine once the socket is
+/// closed, and the UMEM is released once no buffer refers to it anymore.
+impl Drop for XdpSocket {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
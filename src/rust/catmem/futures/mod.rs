@@ -102,6 +102,20 @@ impl SchedulerFuture for Operation {
     fn get_future(&self) -> &dyn Future<Output = ()> {
         todo!()
     }
+
+    fn get_kind(&self) -> &'static str {
+        match self {
+            Operation::Push(_) => "Push",
+            Operation::Pop(_) => "Pop",
+        }
+    }
+
+    fn get_qd(&self) -> Option<QDesc> {
+        match self {
+            Operation::Push(f) => Some(f.future.get_qd()),
+            Operation::Pop(f) => Some(f.future.get_qd()),
+        }
+    }
 }
 
 /// Future Trait Implementation for Operation Descriptors
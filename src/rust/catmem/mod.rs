@@ -15,33 +15,49 @@ use self::futures::{
 };
 use crate::{
     collections::shared_ring::SharedRingBuffer,
+    demikernel::config::Config,
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
-        queue::IoQueueTable,
+        queue::{
+            IoQueueLimitStats,
+            IoQueueTable,
+        },
         types::{
             demi_opcode_t,
             demi_qr_value_t,
             demi_qresult_t,
             demi_sgarray_t,
             demi_sgaseg_t,
+            DEMI_SGARRAY_MAXLEN,
         },
         QDesc,
         QToken,
     },
     scheduler::{
+        Priority,
         Scheduler,
         SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
     },
     QType,
 };
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
 use ::std::{
     any::Any,
     collections::HashMap,
+    future::Future,
     mem,
+    pin::Pin,
     ptr,
     ptr::NonNull,
     rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //======================================================================================================================
@@ -68,10 +84,18 @@ pub struct CatmemLibOS {
 /// Associated functions for Catmem LibOS.
 impl CatmemLibOS {
     /// Instantiates a new LibOS.
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_budget(config.poll_budget());
+        if let Some(policy) = config.idle_policy() {
+            scheduler.set_idle_policy(policy);
+        }
+        scheduler.set_poll_timing(config.poll_timing());
+        let mut qtable: IoQueueTable = IoQueueTable::new();
+        qtable.set_limits(config.queue_limits());
         CatmemLibOS {
-            qtable: IoQueueTable::new(),
-            scheduler: Scheduler::default(),
+            qtable,
+            scheduler,
             rings: HashMap::new(),
         }
     }
@@ -80,6 +104,7 @@ impl CatmemLibOS {
     pub fn create_pipe(&mut self, name: &str) -> Result<QDesc, Fail> {
         trace!("create_pipe() name={:?}", name);
 
+        self.qtable.check_qd_limit()?;
         let ring: SharedRingBuffer<u8> = SharedRingBuffer::<u8>::create(name, RING_BUFFER_CAPACITY)?;
 
         let qd: QDesc = self.qtable.alloc(QType::MemoryQueue.into());
@@ -92,6 +117,7 @@ impl CatmemLibOS {
     pub fn open_pipe(&mut self, name: &str) -> Result<QDesc, Fail> {
         trace!("open_pipe() name={:?}", name);
 
+        self.qtable.check_qd_limit()?;
         let ring: SharedRingBuffer<u8> = SharedRingBuffer::<u8>::open(name, RING_BUFFER_CAPACITY)?;
 
         let qd: QDesc = self.qtable.alloc(QType::MemoryQueue.into());
@@ -124,12 +150,14 @@ impl CatmemLibOS {
                 // Issue push operation.
                 match self.rings.get(&qd) {
                     Some(ring) => {
+                        self.qtable.check_operation_limit()?;
                         let future: Operation = Operation::from(PushFuture::new(qd, ring.clone(), buf));
-                        let handle: SchedulerHandle = match self.scheduler.insert(future) {
+                        let handle: SchedulerHandle = match self.scheduler.insert(future, Priority::High) {
                             Some(handle) => handle,
                             None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                         };
                         let qt: QToken = handle.into_raw().into();
+                        self.qtable.track(qt, &[qd], None);
                         trace!("push() qt={:?}", qt);
                         Ok(qt)
                     },
@@ -147,12 +175,14 @@ impl CatmemLibOS {
         // Issue pop operation.
         match self.rings.get(&qd) {
             Some(ring) => {
+                self.qtable.check_operation_limit()?;
                 let future: Operation = Operation::from(PopFuture::new(qd, ring.clone()));
-                let handle: SchedulerHandle = match self.scheduler.insert(future) {
+                let handle: SchedulerHandle = match self.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
                 let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
                 trace!("pop() qt={:?}", qt);
                 Ok(qt)
             },
@@ -178,13 +208,21 @@ impl CatmemLibOS {
             sgaseg_buf: data as *mut libc::c_void,
             sgaseg_len: size as u32,
         };
+        let mut sga_segs: [demi_sgaseg_t; DEMI_SGARRAY_MAXLEN] = [demi_sgaseg_t {
+            sgaseg_buf: ptr::null_mut(),
+            sgaseg_len: 0,
+        }; DEMI_SGARRAY_MAXLEN];
+        sga_segs[0] = sga_seg;
 
         // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
         Ok(demi_sgarray_t {
             sga_buf: buf.into_raw().as_ptr() as *mut libc::c_void,
             sga_numsegs: 1,
-            sga_segs: [sga_seg],
+            sga_segs,
             sga_addr: unsafe { mem::zeroed() },
+            sga_pktinfo: unsafe { mem::zeroed() },
+            #[cfg(target_os = "linux")]
+            sga_unaddr: unsafe { mem::zeroed() },
         })
     }
 
@@ -286,13 +324,21 @@ impl CatmemLibOS {
             sgaseg_buf: data as *mut libc::c_void,
             sgaseg_len: buf.len() as u32,
         };
+        let mut sga_segs: [demi_sgaseg_t; DEMI_SGARRAY_MAXLEN] = [demi_sgaseg_t {
+            sgaseg_buf: ptr::null_mut(),
+            sgaseg_len: 0,
+        }; DEMI_SGARRAY_MAXLEN];
+        sga_segs[0] = sga_seg;
 
         // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
         Ok(demi_sgarray_t {
             sga_buf: buf.into_raw().as_ptr() as *mut libc::c_void,
             sga_numsegs: 1,
-            sga_segs: [sga_seg],
+            sga_segs,
             sga_addr: unsafe { mem::zeroed() },
+            sga_pktinfo: unsafe { mem::zeroed() },
+            #[cfg(target_os = "linux")]
+            sga_unaddr: unsafe { mem::zeroed() },
         })
     }
 
@@ -303,14 +349,63 @@ impl CatmemLibOS {
         }
     }
 
+    /// Takes out the earliest completed operation that satisfies `pred`.
+    pub fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        self.scheduler
+            .take_completed(|key| pred(QToken::from(key)))
+            .map(QToken::from)
+    }
+
     pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
         let (qd, r): (QDesc, OperationResult) = self.take_result(handle);
+        self.qtable.untrack(qt);
         Ok(pack_result(r, qd, qt.into()))
     }
 
+    /// Inserts a co-routine of the application into the scheduler.
+    pub fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        self.scheduler.spawn(future)
+    }
+
+    /// Returns a future that completes once the clock of the scheduler reaches `deadline`.
+    pub fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        self.scheduler.sleep_until(deadline)
+    }
+
     pub fn poll(&self) {
+        self.scheduler.advance_clock(Instant::now());
         self.scheduler.poll()
     }
+
+    /// Returns an event file descriptor that becomes readable when some queue token may have completed.
+    #[cfg(target_os = "linux")]
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        self.scheduler.event_fd()
+    }
+
+    /// Parks for at most `timeout` if the scheduler has been idle for long enough. On Linux, this waits on the event
+    /// file descriptor of the scheduler, so that it stops as soon as some queue token may have completed.
+    pub fn park(&self, timeout: Option<Duration>) {
+        #[cfg(target_os = "linux")]
+        if self
+            .event_fd()
+            .and_then(|fd| self.scheduler.park_on(fd, timeout))
+            .is_ok()
+        {
+            return;
+        }
+        self.scheduler.park(timeout)
+    }
+
+    /// Lists the live tasks of the scheduler, for debugging.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.scheduler.tasks()
+    }
+
+    /// Counts the queue descriptors and operations that were refused because of the configured limits.
+    pub fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.qtable.limit_stats()
+    }
 }
 
 //======================================================================================================================
@@ -324,6 +419,8 @@ fn pack_result(result: OperationResult, qd: QDesc, qt: u64) -> demi_qresult_t {
             qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
         OperationResult::Pop(bytes) => match CatmemLibOS::into_sgarray(bytes) {
@@ -333,6 +430,8 @@ fn pack_result(result: OperationResult, qd: QDesc, qt: u64) -> demi_qresult_t {
                     qr_opcode: demi_opcode_t::DEMI_OPC_POP,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: 0,
                     qr_value,
                 }
             },
@@ -342,6 +441,8 @@ fn pack_result(result: OperationResult, qd: QDesc, qt: u64) -> demi_qresult_t {
                     qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: e.errno as i64,
                     qr_value: unsafe { mem::zeroed() },
                 }
             },
@@ -352,6 +453,8 @@ fn pack_result(result: OperationResult, qd: QDesc, qt: u64) -> demi_qresult_t {
                 qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: e.errno as i64,
                 qr_value: unsafe { mem::zeroed() },
             }
         },
@@ -0,0 +1,451 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    network::types::PacketInfo,
+};
+use ::libc::{
+    c_uint,
+    c_void,
+};
+use ::nix::{
+    errno::Errno,
+    sys::socket::{
+        SockaddrLike,
+        SockaddrStorage,
+    },
+};
+use ::std::{
+    cell::RefCell,
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    mem,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    os::unix::prelude::RawFd,
+    ptr,
+    rc::Rc,
+    task::Waker,
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Maximum number of datagrams that are sent or received with a single system call.
+const MAX_BATCH_SIZE: usize = 64;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Received datagram, along with its source address, whether it was truncated and its packet information.
+pub type Datagram = (Option<SocketAddrV4>, DemiBuffer, bool, Option<PacketInfo>);
+
+/// Completion of a batched operation, which is shared by its future and by the batch that carries it.
+pub struct Completion<T> {
+    /// Result of the operation, once the batch that carries it is issued.
+    result: Option<Result<T, Fail>>,
+    /// Task to wake up once the operation completes.
+    waker: Option<Waker>,
+}
+
+/// Shared reference to the completion of a batched operation.
+pub type CompletionRef<T> = Rc<RefCell<Completion<T>>>;
+
+/// Datagram that waits to be sent.
+struct PendingSend {
+    /// Destination address, unless the socket is connected.
+    addr: Option<SockaddrStorage>,
+    /// Buffer to send.
+    buf: DemiBuffer,
+    /// Completion of the operation.
+    completion: CompletionRef<()>,
+}
+
+/// Datagram that waits to be received.
+struct PendingRecv {
+    /// Buffer into which the datagram is received, which is handed over as is once it is received.
+    buf: DemiBuffer,
+    /// Completion of the operation.
+    completion: CompletionRef<Datagram>,
+}
+
+/// Batches of datagram operations, which are issued with `sendmmsg()` and `recvmmsg()`. Operations on the same socket
+/// are queued up until some of them is polled, which then issues all of them at once.
+#[derive(Default)]
+pub struct Batches {
+    /// Datagrams that wait to be sent on each socket.
+    sends: HashMap<RawFd, VecDeque<PendingSend>>,
+    /// Datagrams that wait to be received on each socket.
+    recvs: HashMap<RawFd, VecDeque<PendingRecv>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Completions of Batched Operations
+impl<T> Completion<T> {
+    /// Creates the completion of an operation that is pending.
+    fn new() -> CompletionRef<T> {
+        Rc::new(RefCell::new(Self {
+            result: None,
+            waker: None,
+        }))
+    }
+
+    /// Takes out the result of the target operation, if it has completed. Otherwise, the task of `waker` is woken up
+    /// once it completes.
+    pub fn poll(&mut self, waker: &Waker) -> Option<Result<T, Fail>> {
+        match self.result.take() {
+            Some(result) => Some(result),
+            None => {
+                self.waker = Some(waker.clone());
+                None
+            },
+        }
+    }
+
+    /// Completes the target operation with `result`.
+    fn complete(&mut self, result: Result<T, Fail>) {
+        self.result = Some(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Associate Functions for Batches of Datagram Operations
+impl Batches {
+    /// Queues up a datagram to be sent on the socket `fd`.
+    pub fn push(&mut self, fd: RawFd, addr: Option<SockaddrStorage>, buf: DemiBuffer) -> CompletionRef<()> {
+        let completion: CompletionRef<()> = Completion::new();
+        self.sends.entry(fd).or_default().push_back(PendingSend {
+            addr,
+            buf,
+            completion: completion.clone(),
+        });
+        completion
+    }
+
+    /// Queues up a datagram of at most `size` bytes to be received on the socket `fd`.
+    pub fn pop(&mut self, fd: RawFd, size: usize) -> CompletionRef<Datagram> {
+        let completion: CompletionRef<Datagram> = Completion::new();
+        self.recvs.entry(fd).or_default().push_back(PendingRecv {
+            buf: DemiBuffer::new(size.min(u16::MAX as usize) as u16),
+            completion: completion.clone(),
+        });
+        completion
+    }
+
+    /// Sends the datagrams that are queued up on the socket `fd` with a single system call. Returns true if the socket
+    /// would block.
+    pub fn send(&mut self, fd: RawFd) -> bool {
+        let queue: &mut VecDeque<PendingSend> = match self.sends.get_mut(&fd) {
+            Some(queue) => queue,
+            None => return false,
+        };
+
+        // Drop operations that were canceled.
+        queue.retain(|op| Rc::strong_count(&op.completion) > 1);
+        let n: usize = queue.len().min(MAX_BATCH_SIZE);
+
+        let mut iovs: Vec<libc::iovec> = queue
+            .iter()
+            .take(n)
+            .map(|op| libc::iovec {
+                iov_base: op.buf.as_ptr() as *mut c_void,
+                iov_len: op.buf.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = queue
+            .iter()
+            .zip(iovs.iter_mut())
+            .map(|(op, iov)| {
+                let mut hdr: libc::msghdr = unsafe { mem::zeroed() };
+                if let Some(ref addr) = op.addr {
+                    hdr.msg_name = addr.as_ptr() as *mut c_void;
+                    hdr.msg_namelen = addr.len();
+                }
+                hdr.msg_iov = iov;
+                hdr.msg_iovlen = 1;
+                libc::mmsghdr {
+                    msg_hdr: hdr,
+                    msg_len: 0,
+                }
+            })
+            .collect();
+
+        let blocked: bool = if n > 0 {
+            match unsafe { libc::sendmmsg(fd, msgs.as_mut_ptr(), n as c_uint, 0) } {
+                nsent if nsent >= 0 => {
+                    trace!("datagrams sent ({:?}/{:?})", nsent, n);
+                    for op in queue.drain(..nsent as usize) {
+                        op.completion.borrow_mut().complete(Ok(()));
+                    }
+                    false
+                },
+                _ => match Errno::last() {
+                    Errno::EAGAIN => true,
+                    // The first datagram could not be sent.
+                    e => {
+                        warn!("sendmmsg failed ({:?})", e);
+                        if let Some(op) = queue.pop_front() {
+                            op.completion
+                                .borrow_mut()
+                                .complete(Err(Fail::new(e as i32, "operation failed")));
+                        }
+                        false
+                    },
+                },
+            }
+        } else {
+            false
+        };
+
+        if queue.is_empty() {
+            self.sends.remove(&fd);
+        }
+        blocked
+    }
+
+    /// Receives datagrams for the operations that are queued up on the socket `fd` with a single system call. Returns
+    /// true if the socket would block.
+    pub fn recv(&mut self, fd: RawFd) -> bool {
+        let queue: &mut VecDeque<PendingRecv> = match self.recvs.get_mut(&fd) {
+            Some(queue) => queue,
+            None => return false,
+        };
+
+        // Drop operations that were canceled, so that they do not consume datagrams.
+        queue.retain(|op| Rc::strong_count(&op.completion) > 1);
+        let n: usize = queue.len().min(MAX_BATCH_SIZE);
+
+        // Ancillary data is aligned as control message headers.
+        let cmsg_len: usize = cmsg_space();
+        let mut addrs: Vec<libc::sockaddr_storage> = vec![unsafe { mem::zeroed() }; n];
+        let mut cmsgs: Vec<Vec<u64>> = (0..n).map(|_| vec![0; (cmsg_len + 7) / 8]).collect();
+        let mut iovs: Vec<libc::iovec> = queue
+            .iter_mut()
+            .take(n)
+            .map(|op| libc::iovec {
+                iov_base: op.buf.as_mut_ptr() as *mut c_void,
+                iov_len: op.buf.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = (0..n)
+            .map(|i| {
+                let mut hdr: libc::msghdr = unsafe { mem::zeroed() };
+                hdr.msg_name = &mut addrs[i] as *mut libc::sockaddr_storage as *mut c_void;
+                hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                hdr.msg_iov = &mut iovs[i];
+                hdr.msg_iovlen = 1;
+                hdr.msg_control = cmsgs[i].as_mut_ptr() as *mut c_void;
+                hdr.msg_controllen = cmsg_len as _;
+                libc::mmsghdr {
+                    msg_hdr: hdr,
+                    msg_len: 0,
+                }
+            })
+            .collect();
+
+        let blocked: bool = if n > 0 {
+            match unsafe { libc::recvmmsg(fd, msgs.as_mut_ptr(), n as c_uint, 0, ptr::null_mut()) } {
+                nrecv if nrecv >= 0 => {
+                    trace!("datagrams received ({:?}/{:?})", nrecv, n);
+                    for (i, op) in queue.drain(..nrecv as usize).enumerate() {
+                        let msg: &libc::mmsghdr = &msgs[i];
+                        let truncated: bool = msg.msg_hdr.msg_flags & libc::MSG_TRUNC != 0;
+                        let addr: Option<SocketAddrV4> = parse_addr(&addrs[i]);
+                        let info: Option<PacketInfo> = unsafe { parse_packet_info(&msg.msg_hdr) };
+                        // Trim the buffer down to the received data, without copying it.
+                        let mut buf: DemiBuffer = op.buf;
+                        let unused: usize = buf.len() - (msg.msg_len as usize).min(buf.len());
+                        let result: Result<Datagram, Fail> = match buf.trim(unused) {
+                            Ok(()) => Ok((addr, buf, truncated, info)),
+                            Err(e) => Err(e),
+                        };
+                        op.completion.borrow_mut().complete(result);
+                    }
+                    false
+                },
+                _ => match Errno::last() {
+                    Errno::EAGAIN => true,
+                    e => {
+                        warn!("recvmmsg failed ({:?})", e);
+                        if let Some(op) = queue.pop_front() {
+                            op.completion
+                                .borrow_mut()
+                                .complete(Err(Fail::new(e as i32, "operation failed")));
+                        }
+                        false
+                    },
+                },
+            }
+        } else {
+            false
+        };
+
+        if queue.is_empty() {
+            self.recvs.remove(&fd);
+        }
+        blocked
+    }
+
+    /// Fails all operations that are queued up on the socket `fd`, which is about to be closed.
+    pub fn cancel(&mut self, fd: RawFd) {
+        for op in self.sends.remove(&fd).into_iter().flatten() {
+            op.completion
+                .borrow_mut()
+                .complete(Err(Fail::new(libc::ECANCELED, "operation canceled")));
+        }
+        for op in self.recvs.remove(&fd).into_iter().flatten() {
+            op.completion
+                .borrow_mut()
+                .complete(Err(Fail::new(libc::ECANCELED, "operation canceled")));
+        }
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Returns the size of the buffer for ancillary data, which carries packet information.
+fn cmsg_space() -> usize {
+    unsafe {
+        (libc::CMSG_SPACE(mem::size_of::<libc::in_pktinfo>() as c_uint)
+            + libc::CMSG_SPACE(mem::size_of::<libc::timespec>() as c_uint)) as usize
+    }
+}
+
+/// Converts the source address of a received datagram.
+fn parse_addr(addr: &libc::sockaddr_storage) -> Option<SocketAddrV4> {
+    if addr.ss_family as i32 != libc::AF_INET {
+        return None;
+    }
+    let sin: &libc::sockaddr_in = unsafe { &*(addr as *const libc::sockaddr_storage as *const libc::sockaddr_in) };
+    let ip: Ipv4Addr = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+    let port: u16 = u16::from_be(sin.sin_port);
+    Some(SocketAddrV4::new(ip, port))
+}
+
+/// Builds packet information from the ancillary data of `hdr`. This is only reported if the destination address is
+/// present, which happens on sockets that enable `IP_PKTINFO`.
+unsafe fn parse_packet_info(hdr: &libc::msghdr) -> Option<PacketInfo> {
+    let mut dst_addr: Option<Ipv4Addr> = None;
+    let mut timestamp: Option<SystemTime> = None;
+    let mut cmsg: *const libc::cmsghdr = libc::CMSG_FIRSTHDR(hdr);
+    while !cmsg.is_null() {
+        match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+            (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                let pktinfo: libc::in_pktinfo = ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::in_pktinfo);
+                dst_addr = Some(Ipv4Addr::from(u32::from_be(pktinfo.ipi_addr.s_addr)));
+            },
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMPNS) => {
+                let ts: libc::timespec = ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::timespec);
+                timestamp = Some(UNIX_EPOCH + Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32));
+            },
+            _ => (),
+        }
+        cmsg = libc::CMSG_NXTHDR(hdr, cmsg);
+    }
+    dst_addr.map(|dst_addr| PacketInfo::new(dst_addr, None, timestamp))
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Batches,
+        CompletionRef,
+        Datagram,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        net::{
+            SocketAddr,
+            UdpSocket,
+        },
+        os::unix::prelude::AsRawFd,
+    };
+
+    /// Creates a non-blocking UDP socket that is bound to the loopback interface.
+    fn udp_socket() -> UdpSocket {
+        let socket: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_nonblocking(true).unwrap();
+        socket
+    }
+
+    #[test]
+    fn recv_completes_queued_pops_in_order() {
+        let mut batches: Batches = Batches::default();
+        let (sender, receiver): (UdpSocket, UdpSocket) = (udp_socket(), udp_socket());
+        let fd: i32 = receiver.as_raw_fd();
+
+        let completions: Vec<CompletionRef<Datagram>> = (0..4).map(|_| batches.pop(fd, 16)).collect();
+        for i in 0..3 {
+            sender.send_to(&[i; 8], receiver.local_addr().unwrap()).unwrap();
+        }
+
+        // The datagrams that are there complete the first pops, in order.
+        assert!(!batches.recv(fd));
+        for (i, completion) in completions.iter().take(3).enumerate() {
+            match completion.borrow_mut().poll(noop_waker_ref()) {
+                Some(Ok((addr, buf, truncated, _))) => {
+                    assert_eq!(addr.map(SocketAddr::V4), Some(sender.local_addr().unwrap()));
+                    assert_eq!(&buf[..], &[i as u8; 8]);
+                    assert!(!truncated);
+                },
+                _ => panic!("pop should have completed"),
+            }
+        }
+
+        // The last pop is left waiting for the next datagram.
+        assert!(completions[3].borrow_mut().poll(noop_waker_ref()).is_none());
+        assert!(batches.recv(fd));
+        sender.send_to(&[3; 8], receiver.local_addr().unwrap()).unwrap();
+        assert!(!batches.recv(fd));
+        match completions[3].borrow_mut().poll(noop_waker_ref()) {
+            Some(Ok((_, buf, _, _))) => assert_eq!(&buf[..], &[3; 8]),
+            _ => panic!("pop should have completed"),
+        }
+    }
+
+    #[test]
+    fn recv_truncates_datagrams_that_do_not_fit() {
+        let mut batches: Batches = Batches::default();
+        let (sender, receiver): (UdpSocket, UdpSocket) = (udp_socket(), udp_socket());
+        let fd: i32 = receiver.as_raw_fd();
+
+        let completion: CompletionRef<Datagram> = batches.pop(fd, 4);
+        sender.send_to(&[1; 10], receiver.local_addr().unwrap()).unwrap();
+        assert!(!batches.recv(fd));
+        match completion.borrow_mut().poll(noop_waker_ref()) {
+            Some(Ok((_, buf, truncated, _))) => {
+                assert_eq!(&buf[..], &[1; 4]);
+                assert!(truncated);
+            },
+            _ => panic!("pop should have completed"),
+        }
+    }
+}
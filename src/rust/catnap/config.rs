@@ -0,0 +1,26 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::demikernel::config::Config;
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Catnap associated functions for Demikernel configuration object.
+impl Config {
+    /// Reads the "zero-copy threshold" parameter from the underlying configuration file. If set, TCP pushes of at least
+    /// this many bytes are sent with `MSG_ZEROCOPY`, so that the kernel does not copy their payload.
+    pub fn msg_zerocopy_threshold(&self) -> Option<usize> {
+        // FIXME: this function should return a Result.
+        match self.0["catnap"]["zerocopy_threshold"].as_i64() {
+            Some(threshold) if threshold <= 0 => panic!("Invalid zero-copy threshold {}", threshold),
+            Some(threshold) => Some(threshold as usize),
+            None => None,
+        }
+    }
+}
@@ -6,6 +6,7 @@
 //==============================================================================
 
 use crate::{
+    catnap::PosixRuntime,
     pal::linux,
     runtime::{
         fail::Fail,
@@ -32,12 +33,16 @@ use ::std::{
 
 /// Accept Operation Descriptor
 pub struct AcceptFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     /// Underlying file descriptor.
     fd: RawFd,
     /// Queue descriptor of incoming connection.
     new_qd: QDesc,
+    /// Whether incoming connections are TCP ones, which get TCP socket options.
+    tcp: bool,
 }
 
 //==============================================================================
@@ -47,8 +52,14 @@ pub struct AcceptFuture {
 /// Associate Functions for Accept Operation Descriptors
 impl AcceptFuture {
     /// Creates a descriptor for an accept operation.
-    pub fn new(qd: QDesc, fd: RawFd, new_qd: QDesc) -> Self {
-        Self { qd, fd, new_qd }
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, new_qd: QDesc, tcp: bool) -> Self {
+        Self {
+            rt,
+            qd,
+            fd,
+            new_qd,
+            tcp,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [AcceptFuture].
@@ -80,13 +91,13 @@ impl Future for AcceptFuture {
 
                 // Set socket options.
                 unsafe {
-                    if linux::set_tcp_nodelay(new_fd) != 0 {
+                    if self_.tcp && linux::set_tcp_nodelay(new_fd) != 0 {
                         warn!("cannot set TCP_NONDELAY option");
                     }
                     if linux::set_nonblock(new_fd) != 0 {
                         warn!("cannot set NONBLOCK option");
                     }
-                    if linux::set_so_reuseport(new_fd) != 0 {
+                    if self_.tcp && linux::set_so_reuseport(new_fd) != 0 {
                         warn!("cannot set SO_REUSEPORT option");
                     }
                 }
@@ -95,7 +106,7 @@ impl Future for AcceptFuture {
             },
             // Operation in progress.
             Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
-                ctx.waker().wake_by_ref();
+                self_.rt.wait_readable(self_.fd, ctx.waker());
                 Poll::Pending
             },
             // Operation failed.
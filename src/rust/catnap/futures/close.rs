@@ -0,0 +1,94 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+};
+use ::nix::unistd;
+use ::std::{
+    future::Future,
+    os::unix::prelude::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Close Operation Descriptor
+pub struct CloseFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    /// Underlying file descriptor, if it should be closed.
+    fd: Option<RawFd>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Close Operation Descriptors
+impl CloseFuture {
+    /// Creates a descriptor for a close operation. The underlying file descriptor is closed only if `fd` is set.
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: Option<RawFd>) -> Self {
+        Self { rt, qd, fd }
+    }
+
+    /// Returns the queue descriptor associated to the target [CloseFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Close Operation Descriptors
+impl Future for CloseFuture {
+    type Output = Result<(), Fail>;
+
+    /// Polls the target [CloseFuture].
+    fn poll(self: Pin<&mut Self>, _ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut CloseFuture = self.get_mut();
+        match self_.fd.take().map(|fd| {
+            self_.rt.forget(fd);
+            unistd::close(fd)
+        }) {
+            // Operation completed.
+            None | Some(Ok(())) => Poll::Ready(Ok(())),
+            // Error.
+            Some(Err(e)) => {
+                warn!("close failed ({:?})", e);
+                Poll::Ready(Err(Fail::new(e as i32, "operation failed")))
+            },
+        }
+    }
+}
+
+/// Drop Trait Implementation for Close Operation Descriptors
+impl Drop for CloseFuture {
+    /// Closes the underlying file descriptor if the target [CloseFuture] was canceled before completing.
+    fn drop(&mut self) {
+        if let Some(fd) = self.fd.take() {
+            self.rt.forget(fd);
+            if let Err(e) = unistd::close(fd) {
+                warn!("failed to close socket (qd={:?}): {:?}", self.qd, e);
+            }
+        }
+    }
+}
@@ -5,9 +5,13 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    QDesc,
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+    scheduler::SleepFuture,
 };
 use ::nix::{
     errno::Errno,
@@ -24,6 +28,7 @@ use ::std::{
         Context,
         Poll,
     },
+    time::Instant,
 };
 
 //==============================================================================
@@ -32,12 +37,16 @@ use ::std::{
 
 /// Connect Operation Descriptor
 pub struct ConnectFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     // Underlying file descriptor.
     fd: RawFd,
     /// Destination address.
     addr: SockaddrStorage,
+    /// Timer that expires at the deadline for this operation, if any.
+    timeout: Option<SleepFuture>,
 }
 
 //==============================================================================
@@ -47,8 +56,15 @@ pub struct ConnectFuture {
 /// Associate Functions for Connect Operation Descriptors
 impl ConnectFuture {
     /// Creates a descriptor for a connect operation.
-    pub fn new(qd: QDesc, fd: RawFd, addr: SockaddrStorage) -> Self {
-        Self { qd, fd, addr }
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, addr: SockaddrStorage, deadline: Option<Instant>) -> Self {
+        let timeout: Option<SleepFuture> = deadline.map(|deadline| rt.scheduler.sleep_until(deadline));
+        Self {
+            rt,
+            qd,
+            fd,
+            addr,
+            timeout,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [ConnectFuture].
@@ -76,7 +92,14 @@ impl Future for ConnectFuture {
             },
             // Operation not ready yet.
             Err(e) if e == Errno::EINPROGRESS || e == Errno::EALREADY => {
-                ctx.waker().wake_by_ref();
+                // Operation timed out.
+                if let Some(ref mut timeout) = self_.timeout {
+                    if Future::poll(Pin::new(timeout), ctx).is_ready() {
+                        return Poll::Ready(Err(Fail::new(libc::ETIMEDOUT, "operation timed out")));
+                    }
+                }
+                // Wait until the socket becomes writable or the timer expires, whichever comes first.
+                self_.rt.wait_writable(self_.fd, ctx.waker());
                 Poll::Pending
             },
             // Operation failed.
@@ -6,10 +6,15 @@
 //==============================================================================
 
 pub mod accept;
+pub mod close;
 pub mod connect;
 pub mod pop;
+pub mod popbatch;
 pub mod push;
+pub mod pushbatch;
 pub mod pushto;
+pub mod pushv;
+pub mod splice;
 
 //==============================================================================
 // Imports
@@ -17,14 +22,23 @@ pub mod pushto;
 
 use self::{
     accept::AcceptFuture,
+    close::CloseFuture,
     connect::ConnectFuture,
     pop::PopFuture,
+    popbatch::BatchedPopFuture,
     push::PushFuture,
+    pushbatch::BatchedPushFuture,
     pushto::PushtoFuture,
+    pushv::PushvFuture,
+    splice::SpliceFuture,
 };
 use crate::{
     inetstack::operations::OperationResult,
-    runtime::QDesc,
+    runtime::{
+        network::types::SocketAddress,
+        types::DEMI_QR_FLAG_TRUNC,
+        QDesc,
+    },
     scheduler::{
         FutureResult,
         SchedulerFuture,
@@ -49,14 +63,24 @@ use ::std::{
 pub enum Operation {
     /// Accept operation.
     Accept(FutureResult<AcceptFuture>),
+    /// Close operation.
+    Close(FutureResult<CloseFuture>),
     /// Connection operation
     Connect(FutureResult<ConnectFuture>),
     /// Push operation
     Push(FutureResult<PushFuture>),
     /// Pushto operation.
     Pushto(FutureResult<PushtoFuture>),
+    /// Vectored push operation.
+    Pushv(FutureResult<PushvFuture>),
     /// Pop operation.
     Pop(FutureResult<PopFuture>),
+    /// Batched push operation.
+    BatchedPush(FutureResult<BatchedPushFuture>),
+    /// Batched pop operation.
+    BatchedPop(FutureResult<BatchedPopFuture>),
+    /// Splice operation.
+    Splice(FutureResult<SpliceFuture>),
 }
 
 //==============================================================================
@@ -88,6 +112,16 @@ impl Operation {
                 OperationResult::Failed(e),
             ),
 
+            // Close operation.
+            Operation::Close(FutureResult {
+                future,
+                done: Some(Ok(())),
+            }) => (future.get_qd(), None, None, OperationResult::Close),
+            Operation::Close(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             // Connect operation.
             Operation::Connect(FutureResult {
                 future,
@@ -118,16 +152,72 @@ impl Operation {
                 done: Some(Err(e)),
             }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
 
+            // Vectored push operation.
+            Operation::Pushv(FutureResult {
+                future,
+                done: Some(Ok(())),
+            }) => (future.get_qd(), None, None, OperationResult::Push),
+            Operation::Pushv(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             // Pop operation.
             Operation::Pop(FutureResult {
                 future,
-                done: Some(Ok((addr, buf))),
-            }) => (future.get_qd(), None, None, OperationResult::Pop(addr, buf)),
+                done: Some(Ok((addr, buf, truncated, info))),
+            }) => {
+                let flags: u32 = if truncated { DEMI_QR_FLAG_TRUNC } else { 0 };
+                (
+                    future.get_qd(),
+                    None,
+                    None,
+                    OperationResult::Pop(addr, buf, flags, info),
+                )
+            },
             Operation::Pop(FutureResult {
                 future,
                 done: Some(Err(e)),
             }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
 
+            // Batched push operation.
+            Operation::BatchedPush(FutureResult {
+                future,
+                done: Some(Ok(())),
+            }) => (future.get_qd(), None, None, OperationResult::Push),
+            Operation::BatchedPush(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
+            // Batched pop operation.
+            Operation::BatchedPop(FutureResult {
+                future,
+                done: Some(Ok((addr, buf, truncated, info))),
+            }) => {
+                let flags: u32 = if truncated { DEMI_QR_FLAG_TRUNC } else { 0 };
+                (
+                    future.get_qd(),
+                    None,
+                    None,
+                    OperationResult::Pop(addr.map(SocketAddress::from), buf, flags, info),
+                )
+            },
+            Operation::BatchedPop(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
+            // Splice operation.
+            Operation::Splice(FutureResult {
+                future,
+                done: Some(Ok(nbytes)),
+            }) => (future.get_qd(), None, None, OperationResult::Splice(nbytes)),
+            Operation::Splice(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             _ => panic!("future not ready"),
         }
     }
@@ -146,6 +236,36 @@ impl SchedulerFuture for Operation {
     fn get_future(&self) -> &dyn Future<Output = ()> {
         todo!()
     }
+
+    fn get_kind(&self) -> &'static str {
+        match self {
+            Operation::Accept(_) => "Accept",
+            Operation::Close(_) => "Close",
+            Operation::Connect(_) => "Connect",
+            Operation::Push(_) => "Push",
+            Operation::Pushto(_) => "Pushto",
+            Operation::Pushv(_) => "Pushv",
+            Operation::Pop(_) => "Pop",
+            Operation::BatchedPush(_) => "BatchedPush",
+            Operation::BatchedPop(_) => "BatchedPop",
+            Operation::Splice(_) => "Splice",
+        }
+    }
+
+    fn get_qd(&self) -> Option<QDesc> {
+        match self {
+            Operation::Accept(f) => Some(f.future.get_qd()),
+            Operation::Close(f) => Some(f.future.get_qd()),
+            Operation::Connect(f) => Some(f.future.get_qd()),
+            Operation::Push(f) => Some(f.future.get_qd()),
+            Operation::Pushto(f) => Some(f.future.get_qd()),
+            Operation::Pushv(f) => Some(f.future.get_qd()),
+            Operation::Pop(f) => Some(f.future.get_qd()),
+            Operation::BatchedPush(f) => Some(f.future.get_qd()),
+            Operation::BatchedPop(f) => Some(f.future.get_qd()),
+            Operation::Splice(f) => Some(f.future.get_qd()),
+        }
+    }
 }
 
 /// Future Trait Implementation for Operation Descriptors
@@ -156,10 +276,15 @@ impl Future for Operation {
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.get_mut() {
             Operation::Accept(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Close(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Connect(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Push(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Pushto(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Pushv(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Pop(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::BatchedPush(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::BatchedPop(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Splice(ref mut f) => Future::poll(Pin::new(f), ctx),
         }
     }
 }
@@ -171,6 +296,13 @@ impl From<AcceptFuture> for Operation {
     }
 }
 
+/// From Trait Implementation for Operation Descriptors
+impl From<CloseFuture> for Operation {
+    fn from(f: CloseFuture) -> Self {
+        Operation::Close(FutureResult::new(f, None))
+    }
+}
+
 /// From Trait Implementation for Operation Descriptors
 impl From<ConnectFuture> for Operation {
     fn from(f: ConnectFuture) -> Self {
@@ -192,9 +324,37 @@ impl From<PushtoFuture> for Operation {
     }
 }
 
+/// From Trait Implementation for Operation Descriptors
+impl From<PushvFuture> for Operation {
+    fn from(f: PushvFuture) -> Self {
+        Operation::Pushv(FutureResult::new(f, None))
+    }
+}
+
 /// From Trait Implementation for Operation Descriptors
 impl From<PopFuture> for Operation {
     fn from(f: PopFuture) -> Self {
         Operation::Pop(FutureResult::new(f, None))
     }
 }
+
+/// From Trait Implementation for Operation Descriptors
+impl From<BatchedPushFuture> for Operation {
+    fn from(f: BatchedPushFuture) -> Self {
+        Operation::BatchedPush(FutureResult::new(f, None))
+    }
+}
+
+/// From Trait Implementation for Operation Descriptors
+impl From<BatchedPopFuture> for Operation {
+    fn from(f: BatchedPopFuture) -> Self {
+        Operation::BatchedPop(FutureResult::new(f, None))
+    }
+}
+
+/// From Trait Implementation for Operation Descriptors
+impl From<SpliceFuture> for Operation {
+    fn from(f: SpliceFuture) -> Self {
+        Operation::Splice(FutureResult::new(f, None))
+    }
+}
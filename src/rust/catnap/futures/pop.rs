@@ -5,20 +5,35 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    memory::DemiBuffer,
-    QDesc,
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::types::{
+            PacketInfo,
+            SocketAddress,
+        },
+        QDesc,
+    },
+    scheduler::SleepFuture,
 };
 use ::nix::{
+    cmsg_space,
     errno::Errno,
     sys::{
         socket,
-        socket::SockaddrStorage,
+        socket::{
+            ControlMessageOwned,
+            MsgFlags,
+            SockaddrStorage,
+        },
+        time::TimeSpec,
     },
 };
 use ::std::{
     future::Future,
+    io::IoSliceMut,
     net::{
         Ipv4Addr,
         SocketAddrV4,
@@ -29,6 +44,12 @@ use ::std::{
         Context,
         Poll,
     },
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+        UNIX_EPOCH,
+    },
 };
 
 //==============================================================================
@@ -36,7 +57,7 @@ use ::std::{
 //==============================================================================
 
 /// Maximum Size for a Pop Operation
-const POP_SIZE: usize = 9216;
+pub const POP_SIZE: usize = 9216;
 
 //==============================================================================
 // Structures
@@ -44,10 +65,18 @@ const POP_SIZE: usize = 9216;
 
 /// Pop Operation Descriptor
 pub struct PopFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     /// Underlying file descriptor.
     fd: RawFd,
+    /// Maximum number of bytes to pop.
+    size: usize,
+    /// Timer that expires at the deadline for this operation, if any.
+    timeout: Option<SleepFuture>,
+    /// Buffer for ancillary data, which carries packet information.
+    cmsg: Vec<u8>,
 }
 
 //==============================================================================
@@ -56,9 +85,17 @@ pub struct PopFuture {
 
 /// Associate Functions for Pop Operation Descriptors
 impl PopFuture {
-    /// Creates a descriptor for a pop operation.
-    pub fn new(qd: QDesc, fd: RawFd) -> Self {
-        Self { qd, fd }
+    /// Creates a descriptor for a pop operation. At most `size` bytes are popped, capped at the maximum pop size.
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, size: Option<usize>, deadline: Option<Instant>) -> Self {
+        let timeout: Option<SleepFuture> = deadline.map(|deadline| rt.scheduler.sleep_until(deadline));
+        Self {
+            rt,
+            qd,
+            fd,
+            size: size.unwrap_or(POP_SIZE).min(POP_SIZE),
+            timeout,
+            cmsg: cmsg_space!(libc::in_pktinfo, TimeSpec),
+        }
     }
 
     /// Returns the queue descriptor associated to the target [PopFuture].
@@ -73,33 +110,49 @@ impl PopFuture {
 
 /// Future Trait Implementation for Pop Operation Descriptors
 impl Future for PopFuture {
-    type Output = Result<(Option<SocketAddrV4>, DemiBuffer), Fail>;
+    type Output = Result<(Option<SocketAddress>, DemiBuffer, bool, Option<PacketInfo>), Fail>;
 
-    /// Polls the target [PopFuture].
+    /// Polls the target [PopFuture]. The output also tells whether the received datagram was truncated, along with its
+    /// packet information if the kernel reported it.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PopFuture = self.get_mut();
         let mut bytes: [u8; POP_SIZE] = [0; POP_SIZE];
-        match socket::recvfrom::<SockaddrStorage>(self_.fd, &mut bytes[..]) {
+        let mut iov: [IoSliceMut; 1] = [IoSliceMut::new(&mut bytes[..self_.size])];
+        match socket::recvmsg::<SockaddrStorage>(self_.fd, &mut iov, Some(&mut self_.cmsg), MsgFlags::empty()) {
             // Operation completed.
-            Ok((nbytes, socketaddr)) => {
-                trace!("data received ({:?}/{:?} bytes)", nbytes, POP_SIZE);
+            Ok(msg) => {
+                let (nbytes, socketaddr): (usize, Option<SockaddrStorage>) = (msg.bytes, msg.address);
+                let truncated: bool = msg.flags.contains(MsgFlags::MSG_TRUNC);
+                let info: Option<PacketInfo> = parse_packet_info(msg.cmsgs());
+                trace!("data received ({:?}/{:?} bytes)", nbytes, self_.size);
                 let buf: DemiBuffer = DemiBuffer::from_slice(&bytes[0..nbytes])?;
-                let addr: Option<SocketAddrV4> = match socketaddr {
-                    Some(addr) => match addr.as_sockaddr_in() {
-                        Some(sin) => {
+                let addr: Option<SocketAddress> = match socketaddr {
+                    Some(addr) => match (addr.as_sockaddr_in(), addr.as_unix_addr()) {
+                        (Some(sin), _) => {
                             let ip: Ipv4Addr = Ipv4Addr::from(sin.ip());
                             let port: u16 = sin.port();
-                            Some(SocketAddrV4::new(ip, port))
+                            Some(SocketAddress::Inet(SocketAddrV4::new(ip, port)))
                         },
-                        None => None,
+                        // Datagrams from unnamed sockets come from an empty path.
+                        (None, Some(sun)) => Some(SocketAddress::Unix(
+                            sun.path().map(|path| path.to_path_buf()).unwrap_or_default(),
+                        )),
+                        (None, None) => None,
                     },
                     _ => None,
                 };
-                Poll::Ready(Ok((addr, buf)))
+                Poll::Ready(Ok((addr, buf, truncated, info)))
             },
             // Operation in progress.
             Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
-                ctx.waker().wake_by_ref();
+                // Operation timed out.
+                if let Some(ref mut timeout) = self_.timeout {
+                    if Future::poll(Pin::new(timeout), ctx).is_ready() {
+                        return Poll::Ready(Err(Fail::new(libc::ETIMEDOUT, "operation timed out")));
+                    }
+                }
+                // Wait until the socket becomes readable or the timer expires, whichever comes first.
+                self_.rt.wait_readable(self_.fd, ctx.waker());
                 Poll::Pending
             },
             // Error.
@@ -110,3 +163,26 @@ impl Future for PopFuture {
         }
     }
 }
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Builds packet information from ancillary data. This is only reported if the destination address is present, which
+/// happens on sockets that enable `IP_PKTINFO`.
+fn parse_packet_info(cmsgs: impl Iterator<Item = ControlMessageOwned>) -> Option<PacketInfo> {
+    let mut dst_addr: Option<Ipv4Addr> = None;
+    let mut timestamp: Option<SystemTime> = None;
+    for cmsg in cmsgs {
+        match cmsg {
+            ControlMessageOwned::Ipv4PacketInfo(pktinfo) => {
+                dst_addr = Some(Ipv4Addr::from(u32::from_be(pktinfo.ipi_addr.s_addr)));
+            },
+            ControlMessageOwned::ScmTimestampns(ts) => {
+                timestamp = Some(UNIX_EPOCH + Duration::new(ts.tv_sec() as u64, ts.tv_nsec() as u32));
+            },
+            _ => (),
+        }
+    }
+    dst_addr.map(|dst_addr| PacketInfo::new(dst_addr, None, timestamp))
+}
@@ -0,0 +1,90 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catnap::{
+        batch::{
+            CompletionRef,
+            Datagram,
+        },
+        PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+};
+use ::std::{
+    future::Future,
+    os::unix::prelude::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Batched Pop Operation Descriptor
+pub struct BatchedPopFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    /// Underlying file descriptor.
+    fd: RawFd,
+    /// Completion of the datagram that is queued up to be received.
+    completion: CompletionRef<Datagram>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Batched Pop Operation Descriptors
+impl BatchedPopFuture {
+    /// Creates a descriptor for a pop operation that is carried by a batch of the underlying runtime.
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, completion: CompletionRef<Datagram>) -> Self {
+        Self { rt, qd, fd, completion }
+    }
+
+    /// Returns the queue descriptor associated to the target [BatchedPopFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Batched Pop Operation Descriptors
+impl Future for BatchedPopFuture {
+    type Output = Result<Datagram, Fail>;
+
+    /// Polls the target [BatchedPopFuture]. The first operation that is polled receives the whole batch.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut BatchedPopFuture = self.get_mut();
+        if let Some(result) = self_.completion.borrow_mut().poll(ctx.waker()) {
+            return Poll::Ready(result);
+        }
+        let blocked: bool = self_.rt.recv_batch(self_.fd);
+        if let Some(result) = self_.completion.borrow_mut().poll(ctx.waker()) {
+            return Poll::Ready(result);
+        }
+        // The batch did not carry this operation, either because the socket would block or because it was full.
+        if blocked {
+            self_.rt.wait_readable(self_.fd, ctx.waker());
+        } else {
+            ctx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+}
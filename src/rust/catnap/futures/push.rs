@@ -5,37 +5,70 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    memory::DemiBuffer,
-    QDesc,
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+    scheduler::OffloadFuture,
 };
 use ::nix::{
     errno::Errno,
     sys::socket,
+    unistd,
 };
 use ::std::{
     future::Future,
-    os::unix::prelude::RawFd,
+    os::unix::prelude::{
+        AsRawFd,
+        FromRawFd,
+        OwnedFd,
+        RawFd,
+    },
     pin::Pin,
+    sync::Arc,
     task::{
         Context,
         Poll,
     },
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Minimum size of a payload whose send is offloaded to the workers of the scheduler. Smaller payloads are cheaper to
+/// send inline than to hand over to another core.
+const MIN_OFFLOAD_SIZE: usize = 16 * 1024;
+
 //==============================================================================
 // Structures
 //==============================================================================
 
 /// Push Operation Descriptor
 pub struct PushFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     // Underlying file descriptor.
     fd: RawFd,
     /// Buffer to send.
     buf: DemiBuffer,
+    /// Number of bytes that were sent so far.
+    offset: usize,
+    /// Whether the payload is sent without copying it.
+    zero_copy: bool,
+    /// Sequence numbers of the zero-copy sends that were issued, which are more than one if some of them fell short.
+    seqs: Vec<u32>,
+    /// Duplicate of the socket and copy of the payload that offloaded sends run on. They are created once per
+    /// operation, since buffers are not thread-safe, and the duplicate stays valid even if the queue descriptor is
+    /// closed in the meantime.
+    offloaded: Option<(Arc<OwnedFd>, Arc<Vec<u8>>)>,
+    /// Send that is offloaded to some worker of the scheduler.
+    send: Option<OffloadFuture<Result<usize, Errno>>>,
 }
 
 //==============================================================================
@@ -44,15 +77,64 @@ pub struct PushFuture {
 
 /// Associate Functions for Push Operation Descriptors
 impl PushFuture {
-    /// Creates a descriptor for a push operation.
-    pub fn new(qd: QDesc, fd: RawFd, buf: DemiBuffer) -> Self {
-        Self { qd, fd, buf }
+    /// Creates a descriptor for a push operation. If `zero_copy` is set, the push completes only once the kernel is
+    /// done with the payload.
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, buf: DemiBuffer, zero_copy: bool) -> Self {
+        Self {
+            rt,
+            qd,
+            fd,
+            buf,
+            offset: 0,
+            zero_copy,
+            seqs: Vec::new(),
+            offloaded: None,
+            send: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [PushFuture].
     pub fn get_qd(&self) -> QDesc {
         self.qd
     }
+
+    /// Sends what is left of the payload with `flags`. Large payloads are sent on some worker of the scheduler, if
+    /// there is any, in which case this is polled until the worker is done.
+    fn send(&mut self, flags: socket::MsgFlags, ctx: &mut Context<'_>) -> Poll<Result<usize, Errno>> {
+        if self.zero_copy || self.buf.len() < MIN_OFFLOAD_SIZE || self.rt.scheduler.nworkers() == 0 {
+            return Poll::Ready(socket::send(self.fd, &self.buf[self.offset..], flags));
+        }
+
+        if self.send.is_none() {
+            let (sock, payload): (Arc<OwnedFd>, Arc<Vec<u8>>) = match self.offloaded.as_ref() {
+                Some((sock, payload)) => (sock.clone(), payload.clone()),
+                None => {
+                    let sock: OwnedFd = match unistd::dup(self.fd) {
+                        Ok(fd) => unsafe { OwnedFd::from_raw_fd(fd) },
+                        Err(e) => return Poll::Ready(Err(e)),
+                    };
+                    let (sock, payload): &mut (Arc<OwnedFd>, Arc<Vec<u8>>) =
+                        self.offloaded.insert((Arc::new(sock), Arc::new(self.buf[..].to_vec())));
+                    (sock.clone(), payload.clone())
+                },
+            };
+            let offset: usize = self.offset;
+            let send = move || socket::send(sock.as_raw_fd(), &payload[offset..], flags);
+            match self.rt.scheduler.offload(send) {
+                Ok(job) => self.send = Some(job),
+                // There are no workers, so send inline.
+                Err(send) => return Poll::Ready(send()),
+            }
+        }
+
+        match Future::poll(Pin::new(self.send.as_mut().expect("send should be offloaded")), ctx) {
+            Poll::Ready(result) => {
+                self.send = None;
+                Poll::Ready(result)
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
 }
 
 //==============================================================================
@@ -63,25 +145,156 @@ impl PushFuture {
 impl Future for PushFuture {
     type Output = Result<(), Fail>;
 
-    /// Polls the target [PushFuture].
+    /// Polls the target [PushFuture]. Sends that fall short are resumed from where they stopped.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PushFuture = self.get_mut();
-        match socket::send(self_.fd, &self_.buf[..], socket::MsgFlags::empty()) {
-            // Operation completed.
-            Ok(nbytes) => {
-                trace!("data pushed ({:?}/{:?} bytes)", nbytes, self_.buf.len());
-                Poll::Ready(Ok(()))
-            },
-            // Operation in progress.
-            Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
-                ctx.waker().wake_by_ref();
-                Poll::Pending
-            },
-            // Error.
-            Err(e) => {
-                warn!("push failed ({:?})", e);
-                Poll::Ready(Err(Fail::new(e as i32, "operation failed")))
+        let flags: socket::MsgFlags = if self_.zero_copy {
+            unsafe { socket::MsgFlags::from_bits_unchecked(libc::MSG_ZEROCOPY) }
+        } else {
+            socket::MsgFlags::empty()
+        };
+
+        while self_.offset < self_.buf.len() {
+            let result: Result<usize, Errno> = match self_.send(flags, ctx) {
+                Poll::Ready(result) => result,
+                // Operation offloaded.
+                Poll::Pending => return Poll::Pending,
+            };
+            match result {
+                Ok(nbytes) => {
+                    self_.offset += nbytes;
+                    if self_.zero_copy {
+                        trace!(
+                            "data pushed without copying ({:?}/{:?} bytes)",
+                            self_.offset,
+                            self_.buf.len()
+                        );
+                        self_.seqs.push(self_.rt.track_zerocopy(self_.fd, self_.buf.clone()));
+                    } else {
+                        trace!("data pushed ({:?}/{:?} bytes)", self_.offset, self_.buf.len());
+                    }
+                },
+                // Operation in progress.
+                Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
+                    self_.rt.wait_writable(self_.fd, ctx.waker());
+                    return Poll::Pending;
+                },
+                // Error.
+                Err(e) => {
+                    warn!("push failed ({:?})", e);
+                    return Poll::Ready(Err(Fail::new(e as i32, "operation failed")));
+                },
+            }
+        }
+
+        // Wait until the kernel is done with the payloads of zero-copy sends. Their notifications raise an error on the
+        // socket, which wakes up tasks that wait for the socket to become writable.
+        let (rt, fd): (&PosixRuntime, RawFd) = (&self_.rt, self_.fd);
+        self_.seqs.retain(|seq: &u32| !rt.is_zerocopy_done(fd, *seq));
+        if self_.seqs.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        self_.rt.wait_writable(self_.fd, ctx.waker());
+        Poll::Pending
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::PushFuture;
+    use crate::{
+        catnap::PosixRuntime,
+        runtime::{
+            memory::DemiBuffer,
+            QDesc,
+        },
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        ffi::c_void,
+        future::Future,
+        io::{
+            ErrorKind,
+            Read,
+        },
+        net::{
+            TcpListener,
+            TcpStream,
+        },
+        os::unix::prelude::{
+            AsRawFd,
+            RawFd,
+        },
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+    };
+
+    /// Pushes more data than the send buffer of a TCP socket takes at once, so that sends fall short and are resumed,
+    /// and checks that the peer receives all of it.
+    fn push_resumes_short_sends(zero_copy: bool) {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let sender: TcpStream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut receiver, _): (TcpStream, _) = listener.accept().unwrap();
+        sender.set_nonblocking(true).unwrap();
+        receiver.set_nonblocking(true).unwrap();
+        let fd: RawFd = sender.as_raw_fd();
+        let sndbuf: libc::c_int = 4096;
+        assert_eq!(
+            unsafe {
+                libc::setsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_SNDBUF,
+                    &sndbuf as *const libc::c_int as *const c_void,
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                )
             },
+            0
+        );
+
+        let rt: PosixRuntime = PosixRuntime::new(Some(1));
+        if zero_copy {
+            rt.enable_zerocopy(fd);
+        }
+        let data: Vec<u8> = (0..u16::MAX as usize).map(|i: usize| i as u8).collect();
+        let buf: DemiBuffer = DemiBuffer::from_slice(&data).unwrap();
+        let mut push_future: PushFuture = PushFuture::new(rt, QDesc::from(0), fd, buf, zero_copy);
+
+        let mut received: Vec<u8> = Vec::with_capacity(data.len());
+        let mut chunk: [u8; 4096] = [0; 4096];
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        loop {
+            let done: bool = match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+                Poll::Ready(Ok(())) => true,
+                Poll::Ready(Err(e)) => panic!("push failed ({:?})", e),
+                Poll::Pending => false,
+            };
+            match receiver.read(&mut chunk) {
+                Ok(n) => received.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => (),
+                Err(e) => panic!("read failed ({:?})", e),
+            }
+            if done && received.len() == data.len() {
+                break;
+            }
         }
+        assert!(received == data);
+    }
+
+    #[test]
+    fn push_resumes_short_copying_sends() {
+        push_resumes_short_sends(false);
+    }
+
+    #[test]
+    fn push_resumes_short_zero_copy_sends() {
+        push_resumes_short_sends(true);
     }
 }
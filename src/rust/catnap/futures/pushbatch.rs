@@ -0,0 +1,87 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catnap::{
+        batch::CompletionRef,
+        PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+};
+use ::std::{
+    future::Future,
+    os::unix::prelude::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Batched Push Operation Descriptor
+pub struct BatchedPushFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    /// Underlying file descriptor.
+    fd: RawFd,
+    /// Completion of the datagram that is queued up to be sent.
+    completion: CompletionRef<()>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Batched Push Operation Descriptors
+impl BatchedPushFuture {
+    /// Creates a descriptor for a push operation that is carried by a batch of the underlying runtime.
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, completion: CompletionRef<()>) -> Self {
+        Self { rt, qd, fd, completion }
+    }
+
+    /// Returns the queue descriptor associated to the target [BatchedPushFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Batched Push Operation Descriptors
+impl Future for BatchedPushFuture {
+    type Output = Result<(), Fail>;
+
+    /// Polls the target [BatchedPushFuture]. The first operation that is polled sends the whole batch.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut BatchedPushFuture = self.get_mut();
+        if let Some(result) = self_.completion.borrow_mut().poll(ctx.waker()) {
+            return Poll::Ready(result);
+        }
+        let blocked: bool = self_.rt.send_batch(self_.fd);
+        if let Some(result) = self_.completion.borrow_mut().poll(ctx.waker()) {
+            return Poll::Ready(result);
+        }
+        // The batch did not carry this operation, either because the socket would block or because it was full.
+        if blocked {
+            self_.rt.wait_writable(self_.fd, ctx.waker());
+        } else {
+            ctx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+}
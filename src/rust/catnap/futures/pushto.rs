@@ -5,10 +5,13 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    memory::DemiBuffer,
-    QDesc,
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
 };
 use ::nix::{
     errno::Errno,
@@ -34,6 +37,8 @@ use ::std::{
 
 /// Pushto Operation Descriptor
 pub struct PushtoFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     /// Destination address.
@@ -51,8 +56,8 @@ pub struct PushtoFuture {
 /// Associate Functions for Pushto Operation Descriptors
 impl PushtoFuture {
     /// Creates a descriptor for a pushto operation.
-    pub fn new(qd: QDesc, fd: RawFd, addr: SockaddrStorage, buf: DemiBuffer) -> Self {
-        Self { qd, addr, fd, buf }
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, addr: SockaddrStorage, buf: DemiBuffer) -> Self {
+        Self { rt, qd, addr, fd, buf }
     }
 
     /// Returns the queue descriptor associated to the target [PushtoFuture].
@@ -80,7 +85,7 @@ impl Future for PushtoFuture {
             },
             // Operation in progress.
             Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
-                ctx.waker().wake_by_ref();
+                self_.rt.wait_writable(self_.fd, ctx.waker());
                 Poll::Pending
             },
             // Error.
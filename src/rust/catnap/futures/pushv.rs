@@ -0,0 +1,123 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+};
+use ::nix::{
+    errno::Errno,
+    sys::uio,
+};
+use ::std::{
+    future::Future,
+    io::IoSlice,
+    os::unix::prelude::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Vectored Push Operation Descriptor
+pub struct PushvFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    // Underlying file descriptor.
+    fd: RawFd,
+    /// Buffers to send.
+    bufs: Vec<DemiBuffer>,
+    /// Number of bytes that were sent so far.
+    offset: usize,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Vectored Push Operation Descriptors
+impl PushvFuture {
+    /// Creates a descriptor for a vectored push operation.
+    pub fn new(rt: PosixRuntime, qd: QDesc, fd: RawFd, bufs: Vec<DemiBuffer>) -> Self {
+        Self {
+            rt,
+            qd,
+            fd,
+            bufs,
+            offset: 0,
+        }
+    }
+
+    /// Returns the queue descriptor associated to the target [PushvFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Vectored Push Operation Descriptors
+impl Future for PushvFuture {
+    type Output = Result<(), Fail>;
+
+    /// Polls the target [PushvFuture].
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut PushvFuture = self.get_mut();
+        let total: usize = self_.bufs.iter().map(|buf| buf.len()).sum();
+        loop {
+            // Skip the bytes that were sent by previous writes.
+            let mut skip: usize = self_.offset;
+            let iov: Vec<IoSlice> = self_
+                .bufs
+                .iter()
+                .filter_map(|buf| {
+                    let start: usize = skip.min(buf.len());
+                    skip -= start;
+                    if start < buf.len() {
+                        Some(IoSlice::new(&buf[start..]))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            match uio::writev(self_.fd, &iov) {
+                Ok(nbytes) => {
+                    self_.offset += nbytes;
+                    trace!("data pushed ({:?}/{:?} bytes)", self_.offset, total);
+                    // Operation completed.
+                    if self_.offset >= total {
+                        return Poll::Ready(Ok(()));
+                    }
+                    // The write was short, so send the remaining bytes.
+                },
+                // Operation in progress.
+                Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
+                    self_.rt.wait_writable(self_.fd, ctx.waker());
+                    return Poll::Pending;
+                },
+                // Error.
+                Err(e) => {
+                    warn!("pushv failed ({:?})", e);
+                    return Poll::Ready(Err(Fail::new(e as i32, "operation failed")));
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catnap::PosixRuntime,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+};
+use ::nix::{
+    errno::Errno,
+    sys::socket,
+};
+use ::std::{
+    future::Future,
+    os::unix::prelude::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Maximum Size for a Single Receive of a Splice Operation
+const SPLICE_SIZE: usize = 9216;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Splice Operation Descriptor
+pub struct SpliceFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
+    /// Queue descriptor of the source socket.
+    qd: QDesc,
+    /// Underlying file descriptor of the source socket.
+    src: RawFd,
+    /// Underlying file descriptor of the destination socket.
+    dst: RawFd,
+    /// Data that was received but not sent yet.
+    pending: Option<DemiBuffer>,
+    /// Number of bytes forwarded so far.
+    nbytes: usize,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Splice Operation Descriptors
+impl SpliceFuture {
+    /// Creates a descriptor for a splice operation.
+    pub fn new(rt: PosixRuntime, qd: QDesc, src: RawFd, dst: RawFd) -> Self {
+        Self {
+            rt,
+            qd,
+            src,
+            dst,
+            pending: None,
+            nbytes: 0,
+        }
+    }
+
+    /// Returns the queue descriptor associated to the target [SpliceFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Splice Operation Descriptors
+impl Future for SpliceFuture {
+    type Output = Result<usize, Fail>;
+
+    /// Polls the target [SpliceFuture]. Data is forwarded until the source socket reaches end-of-stream.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut SpliceFuture = self.get_mut();
+        loop {
+            // Send data that is pending, if any.
+            if let Some(ref mut buf) = self_.pending {
+                match socket::send(self_.dst, &buf[..], socket::MsgFlags::empty()) {
+                    // Operation completed.
+                    Ok(nbytes) => {
+                        trace!("data forwarded ({:?}/{:?} bytes)", nbytes, buf.len());
+                        self_.nbytes += nbytes;
+                        if nbytes < buf.len() {
+                            buf.adjust(nbytes)?;
+                        } else {
+                            self_.pending = None;
+                        }
+                        continue;
+                    },
+                    // Operation in progress.
+                    Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
+                        self_.rt.wait_writable(self_.dst, ctx.waker());
+                        return Poll::Pending;
+                    },
+                    // Error.
+                    Err(e) => {
+                        warn!("splice failed ({:?})", e);
+                        return Poll::Ready(Err(Fail::new(e as i32, "operation failed")));
+                    },
+                }
+            }
+
+            // Receive more data.
+            let mut bytes: [u8; SPLICE_SIZE] = [0; SPLICE_SIZE];
+            match socket::recv(self_.src, &mut bytes, socket::MsgFlags::empty()) {
+                // End of stream.
+                Ok(0) => return Poll::Ready(Ok(self_.nbytes)),
+                // Operation completed.
+                Ok(nbytes) => {
+                    trace!("data received ({:?} bytes)", nbytes);
+                    self_.pending = Some(DemiBuffer::from_slice(&bytes[0..nbytes])?);
+                },
+                // Operation in progress.
+                Err(e) if e == Errno::EWOULDBLOCK || e == Errno::EAGAIN => {
+                    self_.rt.wait_readable(self_.src, ctx.waker());
+                    return Poll::Pending;
+                },
+                // Error.
+                Err(e) => {
+                    warn!("splice failed ({:?})", e);
+                    return Poll::Ready(Err(Fail::new(e as i32, "operation failed")));
+                },
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod batch;
+mod config;
 mod futures;
 mod runtime;
+mod zerocopy;
 
 //==============================================================================
 // Exports
@@ -14,52 +17,107 @@ pub use self::runtime::PosixRuntime;
 // Imports
 //==============================================================================
 
-use self::futures::{
-    accept::AcceptFuture,
-    connect::ConnectFuture,
-    pop::PopFuture,
-    push::PushFuture,
-    pushto::PushtoFuture,
-    Operation,
+use self::{
+    batch::{
+        CompletionRef,
+        Datagram,
+    },
+    futures::{
+        accept::AcceptFuture,
+        close::CloseFuture,
+        connect::ConnectFuture,
+        pop::{
+            PopFuture,
+            POP_SIZE,
+        },
+        popbatch::BatchedPopFuture,
+        push::PushFuture,
+        pushbatch::BatchedPushFuture,
+        pushto::PushtoFuture,
+        pushv::PushvFuture,
+        splice::SpliceFuture,
+        Operation,
+    },
 };
 use crate::{
-    demikernel::config::Config,
+    demikernel::{
+        config::Config,
+        libos::network::NetworkLibOSTrait,
+    },
     inetstack::operations::OperationResult,
+    pal::linux,
     runtime::{
         fail::Fail,
-        memory::MemoryRuntime,
-        queue::IoQueueTable,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketAddress,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::{
+            IoQueueLimitStats,
+            IoQueueTable,
+        },
         types::{
             demi_accept_result_t,
             demi_opcode_t,
+            demi_pktinfo_t,
             demi_qr_value_t,
             demi_qresult_t,
             demi_sgarray_t,
+            DEMI_QR_FLAG_EOF,
+            DEMI_QR_FLAG_PKTINFO,
+            DEMI_QR_FLAG_TRUNC,
+            DEMI_QR_FLAG_UNIXADDR,
         },
         QDesc,
         QToken,
         QType,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        Priority,
+        Scheduler,
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
 };
 use ::libc::{
     c_int,
     AF_INET,
+    AF_UNIX,
     EBADF,
     EINVAL,
     ENOTSUP,
     SOCK_DGRAM,
+    SOCK_RAW,
     SOCK_STREAM,
 };
 use ::nix::{
+    errno::Errno,
     sys::{
         socket,
         socket::{
+            sockopt,
             AddressFamily,
+            IpMembershipRequest,
             SockFlag,
             SockProtocol,
             SockType,
+            SockaddrIn,
+            SockaddrLike,
             SockaddrStorage,
+            UnixAddr,
         },
     },
     unistd,
@@ -67,12 +125,23 @@ use ::nix::{
 use ::std::{
     any::Any,
     collections::HashMap,
+    future::Future,
     mem,
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     os::unix::prelude::RawFd,
+    path::{
+        Path,
+        PathBuf,
+    },
+    pin::Pin,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //==============================================================================
@@ -85,6 +154,8 @@ pub struct CatnapLibOS {
     qtable: IoQueueTable, // TODO: Move this to Demikernel module.
     /// Established sockets.
     sockets: HashMap<QDesc, RawFd>,
+    /// Number of truncated datagrams on each socket.
+    truncations: HashMap<RawFd, u64>,
     /// Underlying runtime.
     runtime: PosixRuntime,
 }
@@ -96,20 +167,38 @@ pub struct CatnapLibOS {
 /// Associate Functions for Catnap LibOS
 impl CatnapLibOS {
     /// Instantiates a Catnap LibOS.
-    pub fn new(_config: &Config) -> Self {
-        let qtable: IoQueueTable = IoQueueTable::new();
+    pub fn new(config: &Config) -> Self {
+        let mut qtable: IoQueueTable = IoQueueTable::new();
+        qtable.set_limits(config.queue_limits());
         let sockets: HashMap<QDesc, RawFd> = HashMap::new();
-        let runtime: PosixRuntime = PosixRuntime::new();
+        let runtime: PosixRuntime = PosixRuntime::new(config.msg_zerocopy_threshold());
+        runtime.scheduler.set_budget(config.poll_budget());
+        if let Some(policy) = config.idle_policy() {
+            runtime.scheduler.set_idle_policy(policy);
+        }
+        runtime.scheduler.set_poll_timing(config.poll_timing());
+        let worker_cores: Vec<usize> = config.worker_cores();
+        if !worker_cores.is_empty() {
+            if let Err(e) = runtime.scheduler.set_workers(&worker_cores) {
+                panic!("cannot spawn scheduler workers ({:?})", e);
+            }
+        }
         Self {
             qtable,
             sockets,
+            truncations: HashMap::new(),
             runtime,
         }
     }
 
     /// Creates a socket.
-    pub fn socket(&mut self, domain: c_int, typ: c_int, _protocol: c_int) -> Result<QDesc, Fail> {
-        trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, _protocol);
+    pub fn socket(&mut self, domain: c_int, typ: c_int, protocol: c_int) -> Result<QDesc, Fail> {
+        trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, protocol);
+
+        // Raw sockets carry the IP protocol that is requested by the application.
+        if domain == AF_INET && typ == SOCK_RAW {
+            return self.raw_socket(protocol);
+        }
 
         // All operations are asynchronous.
         let flags: SockFlag = SockFlag::SOCK_NONBLOCK;
@@ -117,31 +206,43 @@ impl CatnapLibOS {
         // Parse communication domain.
         let domain: AddressFamily = match domain {
             AF_INET => AddressFamily::Inet,
+            AF_UNIX => AddressFamily::Unix,
             _ => return Err(Fail::new(ENOTSUP, "communication domain not supported")),
         };
 
-        // Parse socket type and protocol.
-        let (ty, protocol): (SockType, SockProtocol) = match typ {
-            SOCK_STREAM => (SockType::Stream, SockProtocol::Tcp),
-            SOCK_DGRAM => (SockType::Datagram, SockProtocol::Udp),
+        // Parse socket type and protocol. Unix domain sockets have no protocol.
+        let (ty, protocol): (SockType, Option<SockProtocol>) = match (domain, typ) {
+            (AddressFamily::Unix, SOCK_STREAM) => (SockType::Stream, None),
+            (AddressFamily::Unix, SOCK_DGRAM) => (SockType::Datagram, None),
+            (_, SOCK_STREAM) => (SockType::Stream, Some(SockProtocol::Tcp)),
+            (_, SOCK_DGRAM) => (SockType::Datagram, Some(SockProtocol::Udp)),
             _ => {
                 return Err(Fail::new(ENOTSUP, "socket type not supported"));
             },
         };
 
+        // Check if a queue descriptor may be allocated for the socket.
+        self.qtable.check_qd_limit()?;
+
         // Create socket.
         match socket::socket(domain, ty, flags, protocol) {
             Ok(fd) => {
-                let qtype: QType = match ty {
-                    SockType::Stream => QType::TcpSocket,
-                    SockType::Datagram => QType::UdpSocket,
+                let qtype: QType = match (domain, ty) {
+                    (AddressFamily::Unix, SockType::Stream) => QType::UnixStream,
+                    (AddressFamily::Unix, SockType::Datagram) => QType::UnixDatagram,
+                    (_, SockType::Stream) => QType::TcpSocket,
+                    (_, SockType::Datagram) => QType::UdpSocket,
                     _ => return Err(Fail::new(libc::ENOTSUP, "socket type not supported")),
                 };
 
                 // Try to set SO_REUSEPORT option. If we fail, keep going because this is non-critical.
-                if socket::setsockopt(fd, socket::sockopt::ReusePort, &true).is_err() {
+                if domain == AddressFamily::Inet && socket::setsockopt(fd, socket::sockopt::ReusePort, &true).is_err() {
                     warn!("cannot set SO_REUSEPORT option");
                 }
+                // Large pushes on stream sockets may be sent without copying their payload.
+                if qtype == QType::TcpSocket {
+                    self.runtime.enable_zerocopy(fd);
+                }
                 let qd: QDesc = self.qtable.alloc(qtype.into());
                 assert_eq!(self.sockets.insert(qd, fd).is_none(), true);
                 Ok(qd)
@@ -150,6 +251,18 @@ impl CatnapLibOS {
         }
     }
 
+    /// Creates a raw IP socket for `protocol`.
+    fn raw_socket(&mut self, protocol: c_int) -> Result<QDesc, Fail> {
+        self.qtable.check_qd_limit()?;
+        let fd: RawFd = unsafe { libc::socket(AF_INET, SOCK_RAW | libc::SOCK_NONBLOCK, protocol) };
+        if fd < 0 {
+            return Err(Fail::new(Errno::last() as i32, "failed to create socket"));
+        }
+        let qd: QDesc = self.qtable.alloc(QType::RawSocket.into());
+        assert_eq!(self.sockets.insert(qd, fd).is_none(), true);
+        Ok(qd)
+    }
+
     /// Binds a socket to a local endpoint.
     pub fn bind(&mut self, qd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
         trace!("bind() qd={:?}, local={:?}", qd, local);
@@ -165,6 +278,21 @@ impl CatnapLibOS {
         }
     }
 
+    /// Binds a unix domain socket to a path.
+    pub fn bind_unix(&mut self, qd: QDesc, path: &Path) -> Result<(), Fail> {
+        trace!("bind_unix() qd={:?}, path={:?}", qd, path);
+
+        let fd: RawFd = self.get_unix_fd(qd)?;
+        let addr: UnixAddr = match UnixAddr::new(path) {
+            Ok(addr) => addr,
+            Err(e) => return Err(Fail::new(e as i32, "invalid path")),
+        };
+        match socket::bind(fd, &addr) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Fail::new(e as i32, "failed to bind socket")),
+        }
+    }
+
     /// Sets a socket as a passive one.
     pub fn listen(&mut self, qd: QDesc, backlog: usize) -> Result<(), Fail> {
         trace!("listen() qd={:?}, backlog={:?}", qd, backlog);
@@ -186,35 +314,74 @@ impl CatnapLibOS {
         // Issue accept operation.
         match self.sockets.get(&qd) {
             Some(&fd) => {
-                let new_qd: QDesc = self.qtable.alloc(QType::TcpSocket.into());
-                let future: Operation = Operation::from(AcceptFuture::new(qd, fd, new_qd));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                self.qtable.check_qd_limit()?;
+                self.qtable.check_operation_limit()?;
+                // Accepted sockets belong to the communication domain of the listening one.
+                let qtype: QType = if self.qtable.get(qd) == Some(QType::UnixStream.into()) {
+                    QType::UnixStream
+                } else {
+                    QType::TcpSocket
+                };
+                let new_qd: QDesc = self.qtable.alloc(qtype.into());
+                let future: Operation = Operation::from(AcceptFuture::new(
+                    self.runtime.clone(),
+                    qd,
+                    fd,
+                    new_qd,
+                    qtype == QType::TcpSocket,
+                ));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => {
                         self.qtable.free(new_qd);
                         return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine"));
                     },
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], Some(new_qd));
+                Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
     }
 
-    /// Establishes a connection to a remote endpoint.
+    /// Establishes a connection to a remote endpoint. On UDP sockets, the kernel records `remote` as the default
+    /// destination of pushes and drops datagrams that come from other endpoints.
     pub fn connect(&mut self, qd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
         trace!("connect() qd={:?}, remote={:?}", qd, remote);
 
-        // Issue connect operation.
+        self.do_connect(qd, parse_addr(remote), None)
+    }
+
+    /// Establishes a connection to a remote endpoint, failing with `ETIMEDOUT` if it is not established by `deadline`.
+    pub fn timedconnect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        trace!("timedconnect() qd={:?}, remote={:?}", qd, remote);
+
+        self.do_connect(qd, parse_addr(remote), Some(deadline))
+    }
+
+    /// Establishes a connection to the unix domain socket that is bound to `path`.
+    pub fn connect_unix(&mut self, qd: QDesc, path: &Path) -> Result<QToken, Fail> {
+        trace!("connect_unix() qd={:?}, path={:?}", qd, path);
+
+        self.get_unix_fd(qd)?;
+        self.do_connect(qd, parse_unix_addr(path)?, None)
+    }
+
+    /// Issues a connect operation.
+    fn do_connect(&mut self, qd: QDesc, addr: SockaddrStorage, deadline: Option<Instant>) -> Result<QToken, Fail> {
         match self.sockets.get(&qd) {
             Some(&fd) => {
-                let addr: SockaddrStorage = parse_addr(remote);
-                let future: Operation = Operation::from(ConnectFuture::new(qd, fd, addr));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                self.qtable.check_operation_limit()?;
+                let future: Operation =
+                    Operation::from(ConnectFuture::new(self.runtime.clone(), qd, fd, addr, deadline));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
@@ -223,15 +390,294 @@ impl CatnapLibOS {
     /// Closes a socket.
     pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
         trace!("close() qd={:?}", qd);
+        self.cancel_operations(qd);
+        match self.sockets.get(&qd) {
+            Some(&fd) => match self.qtable.release(qd) {
+                // Other queue descriptors still refer to the socket.
+                Some(false) => {
+                    self.sockets.remove(&qd);
+                    Ok(())
+                },
+                // This was the last queue descriptor that refers to the socket.
+                Some(true) => {
+                    self.sockets.remove(&qd);
+                    self.truncations.remove(&fd);
+                    self.runtime.forget(fd);
+                    match unistd::close(fd) {
+                        Ok(_) => Ok(()),
+                        _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+                    }
+                },
+                None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Asynchronously closes a socket. The returned operation completes once the underlying file descriptor is closed.
+    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
+        trace!("async_close() qd={:?}", qd);
+        self.cancel_operations(qd);
+        let fd: Option<RawFd> = match self.sockets.get(&qd) {
+            Some(&fd) => match self.qtable.release(qd) {
+                // Other queue descriptors still refer to the socket.
+                Some(false) => None,
+                // This was the last queue descriptor that refers to the socket.
+                Some(true) => {
+                    self.truncations.remove(&fd);
+                    Some(fd)
+                },
+                None => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+            },
+            _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+        self.sockets.remove(&qd);
+
+        // Issue close operation.
+        let future: Operation = Operation::from(CloseFuture::new(self.runtime.clone(), qd, fd));
+        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        Ok(qt)
+    }
+
+    /// Duplicates a queue descriptor. The new queue descriptor refers to the same socket, which is closed only once all
+    /// queue descriptors that refer to it are closed.
+    pub fn dup(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        trace!("dup() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(&fd) => {
+                self.qtable.check_qd_limit()?;
+                match self.qtable.dup(qd) {
+                    Some(new_qd) => {
+                        self.sockets.insert(new_qd, fd);
+                        Ok(new_qd)
+                    },
+                    None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+                }
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Shuts down the receive side, send side, or both sides of a socket.
+    pub fn shutdown(&mut self, qd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        trace!("shutdown() qd={:?} how={:?}", qd, how);
+        let how: socket::Shutdown = match how {
+            Shutdown::Read => socket::Shutdown::Read,
+            Shutdown::Write => socket::Shutdown::Write,
+            Shutdown::Both => socket::Shutdown::Both,
+        };
+        match self.sockets.get(&qd) {
+            Some(&fd) => match socket::shutdown(fd, how) {
+                Ok(()) => Ok(()),
+                Err(err) => Err(Fail::new(err as i32, "failed to shutdown socket")),
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the local address of a socket.
+    pub fn local_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("local_addr() qd={:?}", qd);
+        self.check_inet(qd)?;
         match self.sockets.get(&qd) {
-            Some(&fd) => match unistd::close(fd) {
-                Ok(_) => Ok(()),
-                _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+            Some(&fd) => match socket::getsockname::<SockaddrIn>(fd) {
+                Ok(addr) => Ok(SocketAddrV4::from(addr)),
+                Err(err) => Err(Fail::new(err as i32, "failed to get local address")),
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
     }
 
+    /// Gets the remote address of a socket.
+    pub fn remote_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("remote_addr() qd={:?}", qd);
+        self.check_inet(qd)?;
+        match self.sockets.get(&qd) {
+            Some(&fd) => match socket::getpeername::<SockaddrIn>(fd) {
+                Ok(addr) => Ok(SocketAddrV4::from(addr)),
+                Err(err) => Err(Fail::new(err as i32, "failed to get remote address")),
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the path that a unix domain socket is bound to, which is empty for unnamed sockets.
+    pub fn local_unix_addr(&self, qd: QDesc) -> Result<PathBuf, Fail> {
+        trace!("local_unix_addr() qd={:?}", qd);
+        let fd: RawFd = self.get_unix_fd(qd)?;
+        match socket::getsockname::<UnixAddr>(fd) {
+            Ok(addr) => Ok(addr.path().map(Path::to_path_buf).unwrap_or_default()),
+            Err(err) => Err(Fail::new(err as i32, "failed to get local address")),
+        }
+    }
+
+    /// Gets the path of the remote peer of a unix domain socket.
+    pub fn remote_unix_addr(&self, qd: QDesc) -> Result<PathBuf, Fail> {
+        trace!("remote_unix_addr() qd={:?}", qd);
+        let fd: RawFd = self.get_unix_fd(qd)?;
+        match socket::getpeername::<UnixAddr>(fd) {
+            Ok(addr) => Ok(addr.path().map(Path::to_path_buf).unwrap_or_default()),
+            Err(err) => Err(Fail::new(err as i32, "failed to get remote address")),
+        }
+    }
+
+    /// Gets the current value of a socket option.
+    pub fn get_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        trace!("get_socket_option() qd={:?} opt={:?}", qd, opt);
+        let fd: RawFd = match self.sockets.get(&qd) {
+            Some(&fd) => fd,
+            _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+        let result: Result<SocketOption, Errno> = match opt {
+            SocketOption::SendBufferSize(_) => {
+                socket::getsockopt(fd, sockopt::SndBuf).map(SocketOption::SendBufferSize)
+            },
+            SocketOption::ReceiveBufferSize(_) => {
+                socket::getsockopt(fd, sockopt::RcvBuf).map(SocketOption::ReceiveBufferSize)
+            },
+            SocketOption::NoDelay(_) => socket::getsockopt(fd, sockopt::TcpNoDelay).map(SocketOption::NoDelay),
+            SocketOption::Cork(_) => {
+                let mut cork: bool = false;
+                Errno::result(unsafe { linux::get_tcp_flag(fd, libc::TCP_CORK, &mut cork) })
+                    .map(|_| SocketOption::Cork(cork))
+            },
+            SocketOption::QuickAck(_) => {
+                let mut quickack: bool = false;
+                Errno::result(unsafe { linux::get_tcp_flag(fd, libc::TCP_QUICKACK, &mut quickack) })
+                    .map(|_| SocketOption::QuickAck(quickack))
+            },
+            SocketOption::KeepAlive(_) => socket::getsockopt(fd, sockopt::KeepAlive).map(SocketOption::KeepAlive),
+            SocketOption::Ttl(_) => socket::getsockopt(fd, sockopt::Ipv4Ttl).map(|ttl| SocketOption::Ttl(ttl as u8)),
+            SocketOption::Broadcast(_) => socket::getsockopt(fd, sockopt::Broadcast).map(SocketOption::Broadcast),
+            SocketOption::PacketInfo(_) => {
+                socket::getsockopt(fd, sockopt::Ipv4PacketInfo).map(SocketOption::PacketInfo)
+            },
+            // The kernel runs its own congestion control algorithms, which we do not expose.
+            SocketOption::CongestionControl(_) => Err(Errno::ENOPROTOOPT),
+            // Pacing is left to the queueing discipline of the kernel.
+            SocketOption::MaxPacingRate(_) => Err(Errno::ENOPROTOOPT),
+            // The kernel only lets users switch to quick-ack mode.
+            SocketOption::AckDelay(_) | SocketOption::AckSegments(_) => Err(Errno::ENOPROTOOPT),
+            // The kernel autotunes buffers within system-wide bounds.
+            SocketOption::SendBufferBounds(..) | SocketOption::ReceiveBufferBounds(..) => Err(Errno::ENOPROTOOPT),
+        };
+        match result {
+            Ok(opt) => Ok(opt),
+            Err(err) => Err(Fail::new(err as i32, "failed to get socket option")),
+        }
+    }
+
+    /// Sets a socket option.
+    pub fn set_socket_option(&mut self, qd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        trace!("set_socket_option() qd={:?} opt={:?}", qd, opt);
+        let fd: RawFd = match self.sockets.get(&qd) {
+            Some(&fd) => fd,
+            _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+        let result: Result<(), Errno> = match opt {
+            SocketOption::SendBufferSize(size) => socket::setsockopt(fd, sockopt::SndBuf, &size),
+            SocketOption::ReceiveBufferSize(size) => socket::setsockopt(fd, sockopt::RcvBuf, &size),
+            SocketOption::NoDelay(nodelay) => socket::setsockopt(fd, sockopt::TcpNoDelay, &nodelay),
+            SocketOption::Cork(cork) => {
+                Errno::result(unsafe { linux::set_tcp_flag(fd, libc::TCP_CORK, cork) }).map(drop)
+            },
+            SocketOption::QuickAck(quickack) => {
+                Errno::result(unsafe { linux::set_tcp_flag(fd, libc::TCP_QUICKACK, quickack) }).map(drop)
+            },
+            SocketOption::KeepAlive(keepalive) => socket::setsockopt(fd, sockopt::KeepAlive, &keepalive),
+            SocketOption::Ttl(ttl) => socket::setsockopt(fd, sockopt::Ipv4Ttl, &(ttl as c_int)),
+            SocketOption::Broadcast(broadcast) => socket::setsockopt(fd, sockopt::Broadcast, &broadcast),
+            // Arrival times are reported along with the destination address.
+            SocketOption::PacketInfo(pktinfo) => socket::setsockopt(fd, sockopt::Ipv4PacketInfo, &pktinfo)
+                .and_then(|_| socket::setsockopt(fd, sockopt::ReceiveTimestampns, &pktinfo)),
+            SocketOption::CongestionControl(_)
+            | SocketOption::MaxPacingRate(_)
+            | SocketOption::AckDelay(_)
+            | SocketOption::AckSegments(_)
+            | SocketOption::SendBufferBounds(..)
+            | SocketOption::ReceiveBufferBounds(..) => Err(Errno::ENOPROTOOPT),
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Fail::new(err as i32, "failed to set socket option")),
+        }
+    }
+
+    /// Gets the number of datagrams that were truncated when popped from a UDP socket.
+    pub fn truncations(&self, qd: QDesc) -> Result<u64, Fail> {
+        trace!("truncations() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(fd) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => {
+                Ok(self.truncations.get(fd).copied().unwrap_or(0))
+            },
+            Some(_) => Err(Fail::new(EINVAL, "invalid queue type")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Joins a UDP socket to a multicast group on the interface that has the address `iface`.
+    pub fn join_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!("join_multicast_group() qd={:?} group={:?} iface={:?}", qd, group, iface);
+        let fd: RawFd = self.get_udp_fd(qd)?;
+        let request: IpMembershipRequest = IpMembershipRequest::new(group, Some(iface));
+        match socket::setsockopt(fd, sockopt::IpAddMembership, &request) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Fail::new(err as i32, "failed to join multicast group")),
+        }
+    }
+
+    /// Removes a UDP socket from a multicast group on the interface that has the address `iface`.
+    pub fn leave_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!(
+            "leave_multicast_group() qd={:?} group={:?} iface={:?}",
+            qd,
+            group,
+            iface
+        );
+        let fd: RawFd = self.get_udp_fd(qd)?;
+        let request: IpMembershipRequest = IpMembershipRequest::new(group, Some(iface));
+        match socket::setsockopt(fd, sockopt::IpDropMembership, &request) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Fail::new(err as i32, "failed to leave multicast group")),
+        }
+    }
+
+    /// Gets the underlying file descriptor of a UDP socket.
+    fn get_udp_fd(&self, qd: QDesc) -> Result<RawFd, Fail> {
+        match self.sockets.get(&qd) {
+            Some(&fd) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => Ok(fd),
+            Some(_) => Err(Fail::new(EINVAL, "invalid queue type")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Checks whether `qd` is a socket of the unix domain.
+    fn is_unix(&self, qd: QDesc) -> bool {
+        let unix: [Option<u32>; 2] = [Some(QType::UnixStream.into()), Some(QType::UnixDatagram.into())];
+        unix.contains(&self.qtable.get(qd))
+    }
+
+    /// Fails with `EAFNOSUPPORT` if `qd` is a unix domain socket, whose addresses are paths.
+    fn check_inet(&self, qd: QDesc) -> Result<(), Fail> {
+        match self.is_unix(qd) {
+            true => Err(Fail::new(libc::EAFNOSUPPORT, "socket is of the unix domain")),
+            false => Ok(()),
+        }
+    }
+
+    /// Gets the underlying file descriptor of a unix domain socket.
+    fn get_unix_fd(&self, qd: QDesc) -> Result<RawFd, Fail> {
+        match self.sockets.get(&qd) {
+            Some(&fd) if self.is_unix(qd) => Ok(fd),
+            Some(_) => Err(Fail::new(EINVAL, "invalid queue type")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
     /// Pushes a scatter-gather array to a socket.
     pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
         trace!("push() qd={:?}", qd);
@@ -245,12 +691,22 @@ impl CatnapLibOS {
                 // Issue push operation.
                 match self.sockets.get(&qd) {
                     Some(&fd) => {
-                        let future: Operation = Operation::from(PushFuture::new(qd, fd, buf));
-                        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                        self.qtable.check_operation_limit()?;
+                        // Datagrams are batched with other pushes on the same socket.
+                        let future: Operation = if self.qtable.get(qd) == Some(QType::UdpSocket.into()) {
+                            let completion: CompletionRef<()> = self.runtime.batch_push(fd, None, buf);
+                            Operation::from(BatchedPushFuture::new(self.runtime.clone(), qd, fd, completion))
+                        } else {
+                            let zero_copy: bool = self.runtime.is_zero_copy(fd, buf.len());
+                            Operation::from(PushFuture::new(self.runtime.clone(), qd, fd, buf, zero_copy))
+                        };
+                        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                             Some(handle) => handle,
                             None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                         };
-                        Ok(handle.into_raw().into())
+                        let qt: QToken = handle.into_raw().into();
+                        self.qtable.track(qt, &[qd], None);
+                        Ok(qt)
                     },
                     _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
                 }
@@ -259,10 +715,46 @@ impl CatnapLibOS {
         }
     }
 
+    /// Pushes multiple scatter-gather arrays to a socket as a single unit.
+    pub fn pushv(&mut self, qd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        trace!("pushv() qd={:?}, nsgas={:?}", qd, sgas.len());
+
+        let bufs: Vec<DemiBuffer> = self.runtime.clone_sgarrays(sgas)?;
+
+        // Issue vectored push operation.
+        match self.sockets.get(&qd) {
+            Some(&fd) => {
+                self.qtable.check_operation_limit()?;
+                let future: Operation = Operation::from(PushvFuture::new(self.runtime.clone(), qd, fd, bufs));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+                    Some(handle) => handle,
+                    None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+                };
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
     /// Pushes a scatter-gather array to a socket.
     pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, remote: SocketAddrV4) -> Result<QToken, Fail> {
         trace!("pushto() qd={:?}", qd);
 
+        self.do_pushto(qd, sga, parse_addr(remote))
+    }
+
+    /// Pushes a scatter-gather array to the unix domain socket that is bound to `path`.
+    pub fn pushto_unix(&mut self, qd: QDesc, sga: &demi_sgarray_t, path: &Path) -> Result<QToken, Fail> {
+        trace!("pushto_unix() qd={:?}, path={:?}", qd, path);
+
+        self.get_unix_fd(qd)?;
+        self.do_pushto(qd, sga, parse_unix_addr(path)?)
+    }
+
+    /// Issues a pushto operation.
+    fn do_pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, addr: SockaddrStorage) -> Result<QToken, Fail> {
         match self.runtime.clone_sgarray(sga) {
             Ok(buf) => {
                 if buf.len() == 0 {
@@ -272,13 +764,21 @@ impl CatnapLibOS {
                 // Issue pushto operation.
                 match self.sockets.get(&qd) {
                     Some(&fd) => {
-                        let addr: SockaddrStorage = parse_addr(remote);
-                        let future: Operation = Operation::from(PushtoFuture::new(qd, fd, addr, buf));
-                        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                        self.qtable.check_operation_limit()?;
+                        // Datagrams are batched with other pushes on the same socket.
+                        let future: Operation = if self.qtable.get(qd) == Some(QType::UdpSocket.into()) {
+                            let completion: CompletionRef<()> = self.runtime.batch_push(fd, Some(addr), buf);
+                            Operation::from(BatchedPushFuture::new(self.runtime.clone(), qd, fd, completion))
+                        } else {
+                            Operation::from(PushtoFuture::new(self.runtime.clone(), qd, fd, addr, buf))
+                        };
+                        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                             Some(handle) => handle,
                             None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                         };
-                        Ok(handle.into_raw().into())
+                        let qt: QToken = handle.into_raw().into();
+                        self.qtable.track(qt, &[qd], None);
+                        Ok(qt)
                     },
                     _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
                 }
@@ -291,25 +791,117 @@ impl CatnapLibOS {
     pub fn pop(&mut self, qd: QDesc) -> Result<QToken, Fail> {
         trace!("pop() qd={:?}", qd);
 
+        self.do_pop(qd, None, None)
+    }
+
+    /// Pops at most `size` bytes of data from a socket.
+    pub fn pop_sized(&mut self, qd: QDesc, size: usize) -> Result<QToken, Fail> {
+        trace!("pop_sized() qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if size == 0 || size > u16::MAX as usize {
+            return Err(Fail::new(EINVAL, "invalid pop size"));
+        }
+
+        self.do_pop(qd, Some(size), None)
+    }
+
+    /// Pops data from a socket, failing with `ETIMEDOUT` if no data arrives by `deadline`. If `size` is set, this
+    /// behaves as [CatnapLibOS::pop_sized], otherwise it behaves as [CatnapLibOS::pop].
+    pub fn timedpop(&mut self, qd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        trace!("timedpop() qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if let Some(size) = size {
+            if size == 0 || size > u16::MAX as usize {
+                return Err(Fail::new(EINVAL, "invalid pop size"));
+            }
+        }
+
+        self.do_pop(qd, size, Some(deadline))
+    }
+
+    /// Issues a pop operation.
+    fn do_pop(&mut self, qd: QDesc, size: Option<usize>, deadline: Option<Instant>) -> Result<QToken, Fail> {
         // Issue pop operation.
         match self.sockets.get(&qd) {
             Some(&fd) => {
-                let future: Operation = Operation::from(PopFuture::new(qd, fd));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                self.qtable.check_operation_limit()?;
+                // Datagrams are batched with other pops on the same socket, unless the operation is timed.
+                let future: Operation = match deadline {
+                    None if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => {
+                        let size: usize = size.unwrap_or(POP_SIZE).min(POP_SIZE);
+                        let completion: CompletionRef<Datagram> = self.runtime.batch_pop(fd, size);
+                        Operation::from(BatchedPopFuture::new(self.runtime.clone(), qd, fd, completion))
+                    },
+                    _ => Operation::from(PopFuture::new(self.runtime.clone(), qd, fd, size, deadline)),
+                };
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
                 let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
                 Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
     }
 
+    /// Forwards data from one socket to another until the remote peer of the source socket closes the connection.
+    pub fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        trace!("splice() srcqd={:?}, dstqd={:?}", srcqd, dstqd);
+
+        let (src, dst): (RawFd, RawFd) = match (self.sockets.get(&srcqd), self.sockets.get(&dstqd)) {
+            (Some(&src), Some(&dst)) => (src, dst),
+            _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+
+        // Check if queue types are valid.
+        let tcp: Option<u32> = Some(QType::TcpSocket.into());
+        if self.qtable.get(srcqd) != tcp || self.qtable.get(dstqd) != tcp {
+            return Err(Fail::new(EINVAL, "invalid queue type"));
+        }
+        if src == dst {
+            return Err(Fail::new(EINVAL, "cannot splice a queue into itself"));
+        }
+
+        // Issue splice operation.
+        self.qtable.check_operation_limit()?;
+        let future: Operation = Operation::from(SpliceFuture::new(self.runtime.clone(), srcqd, src, dst));
+        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        self.qtable.track(qt, &[srcqd, dstqd], None);
+        Ok(qt)
+    }
+
     pub fn poll(&self) {
+        self.runtime.poll_readiness();
+        self.runtime.scheduler.advance_clock(Instant::now());
         self.runtime.scheduler.poll()
     }
 
+    /// Returns a file descriptor that becomes readable when some queue token may have completed or some socket may have
+    /// become ready.
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        self.runtime.event_fd()
+    }
+
+    /// Parks for at most `timeout` if the scheduler has been idle for long enough, until some socket becomes ready or
+    /// some queue token may have completed. If the event file descriptor is not available, this sleeps instead.
+    pub fn park(&self, timeout: Option<Duration>) {
+        if let Err(e) = self
+            .event_fd()
+            .and_then(|fd| self.runtime.scheduler.park_on(fd, timeout))
+        {
+            warn!("park(): cannot park on event file descriptor ({:?})", e);
+            self.runtime.scheduler.park(timeout)
+        }
+    }
+
     pub fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
         match self.runtime.scheduler.from_raw_handle(qt.into()) {
             Some(handle) => Ok(handle),
@@ -317,8 +909,16 @@ impl CatnapLibOS {
         }
     }
 
+    /// Takes out the earliest completed operation that satisfies `pred`.
+    pub fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        self.runtime
+            .scheduler
+            .take_completed(|key| pred(QToken::from(key)))
+            .map(QToken::from)
+    }
+
     pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
-        let (qd, r): (QDesc, OperationResult) = self.take_result(handle);
+        let (qd, r): (QDesc, OperationResult) = self.take_result(handle, qt);
         Ok(pack_result(&self.runtime, r, qd, qt.into()))
     }
 
@@ -328,12 +928,44 @@ impl CatnapLibOS {
         self.runtime.alloc_sgarray(size)
     }
 
+    /// Registers application-owned memory as a scatter-gather array.
+    pub fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        trace!("sgaregister() len={:?}", len);
+        self.runtime.register_sgarray(data, len)
+    }
+
     /// Frees a scatter-gather array.
     pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
         trace!("sgafree()");
         self.runtime.free_sgarray(sga)
     }
 
+    /// Tears down the target LibOS. All pending operations are canceled and all sockets are closed. If some socket
+    /// fails to close, the first error is returned once teardown completes.
+    pub fn teardown(mut self) -> Result<(), Fail> {
+        trace!("teardown()");
+
+        // Cancel pending operations first, because they refer to sockets.
+        let ncanceled: usize = self.runtime.scheduler.cancel_all();
+        trace!("teardown(): canceled {:?} operations", ncanceled);
+
+        // Close all sockets.
+        let mut result: Result<(), Fail> = Ok(());
+        for (qd, fd) in self.sockets.drain() {
+            // Duplicated sockets are closed along with their last queue descriptor.
+            if self.qtable.release(qd) != Some(true) {
+                continue;
+            }
+            self.runtime.forget(fd);
+            if let Err(e) = unistd::close(fd) {
+                warn!("failed to close socket (qd={:?}): {:?}", qd, e);
+                result = result.and(Err(Fail::new(e as i32, "failed to close socket")));
+            }
+        }
+
+        result
+    }
+
     #[deprecated]
     pub fn local_ipv4_addr(&self) -> Ipv4Addr {
         todo!()
@@ -344,8 +976,26 @@ impl CatnapLibOS {
         &self.runtime
     }
 
-    /// Takes out the [OperationResult] associated with the target [SchedulerHandle].
-    fn take_result(&mut self, handle: SchedulerHandle) -> (QDesc, OperationResult) {
+    /// Cancels the operations that are pending on the queue descriptor `qd`, which is about to be closed. These
+    /// operations complete with `ECANCELED`, unless they have already completed.
+    fn cancel_operations(&mut self, qd: QDesc) {
+        let scheduler: &Scheduler = &self.runtime.scheduler;
+        let ncanceled: usize = self.qtable.cancel(qd, |qt| scheduler.abort(qt.into()).is_some());
+        trace!("canceled {:?} operations (qd={:?})", ncanceled, qd);
+    }
+
+    /// Takes out the [OperationResult] associated with the target [SchedulerHandle], which refers to the operation
+    /// `qt`.
+    fn take_result(&mut self, handle: SchedulerHandle, qt: QToken) -> (QDesc, OperationResult) {
+        // The operation was canceled because its queue descriptor was closed.
+        if let Some(qd) = self.qtable.untrack(qt) {
+            self.runtime.scheduler.take(handle);
+            return (
+                qd,
+                OperationResult::Failed(Fail::new(libc::ECANCELED, "operation canceled")),
+            );
+        }
+
         let boxed_future: Box<dyn Any> = self.runtime.scheduler.take(handle).as_any();
         let boxed_concrete_type: Operation = *boxed_future.downcast::<Operation>().expect("Wrong type!");
 
@@ -357,16 +1007,363 @@ impl CatnapLibOS {
             // Associate raw file descriptor with queue descriptor.
             if let Some(new_fd) = new_fd {
                 assert!(self.sockets.insert(new_qd, new_fd).is_none());
+                if self.qtable.get(new_qd) == Some(QType::TcpSocket.into()) {
+                    self.runtime.enable_zerocopy(new_fd);
+                }
             } else {
                 // Release entry in queue table.
                 self.qtable.free(new_qd);
             }
         }
 
+        // A zero-length read on a stream socket means that the remote peer has closed the connection.
+        let qr: OperationResult = match qr {
+            OperationResult::Pop(addr, buf, flags, info)
+                if buf.is_empty()
+                    && (self.qtable.get(qd) == Some(QType::TcpSocket.into())
+                        || self.qtable.get(qd) == Some(QType::UnixStream.into())) =>
+            {
+                OperationResult::Pop(addr, buf, flags | DEMI_QR_FLAG_EOF, info)
+            },
+            qr => qr,
+        };
+
+        // Account datagrams that did not fit in the receive buffer.
+        if let OperationResult::Pop(_, _, flags, _) = qr {
+            if flags & DEMI_QR_FLAG_TRUNC != 0 {
+                if let Some(&fd) = self.sockets.get(&qd) {
+                    *self.truncations.entry(fd).or_insert(0) += 1;
+                }
+            }
+        }
+
         (qd, qr)
     }
 }
 
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Network LibOS Trait Implementation for Catnap LibOS
+impl NetworkLibOSTrait for CatnapLibOS {
+    fn socket(&mut self, domain: libc::c_int, socket_type: libc::c_int, protocol: libc::c_int) -> Result<QDesc, Fail> {
+        CatnapLibOS::socket(self, domain, socket_type, protocol)
+    }
+
+    fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
+        CatnapLibOS::bind(self, sockqd, local)
+    }
+
+    fn bind_unix(&mut self, sockqd: QDesc, path: &Path) -> Result<(), Fail> {
+        CatnapLibOS::bind_unix(self, sockqd, path)
+    }
+
+    fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+        CatnapLibOS::listen(self, sockqd, backlog)
+    }
+
+    fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatnapLibOS::accept(self, sockqd)
+    }
+
+    fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
+        CatnapLibOS::connect(self, sockqd, remote)
+    }
+
+    fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        CatnapLibOS::timedconnect(self, sockqd, remote, deadline)
+    }
+
+    fn connect_unix(&mut self, sockqd: QDesc, path: &Path) -> Result<QToken, Fail> {
+        CatnapLibOS::connect_unix(self, sockqd, path)
+    }
+
+    fn close(&mut self, sockqd: QDesc) -> Result<(), Fail> {
+        CatnapLibOS::close(self, sockqd)
+    }
+
+    fn async_close(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatnapLibOS::async_close(self, sockqd)
+    }
+
+    fn dup(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        CatnapLibOS::dup(self, sockqd)
+    }
+
+    fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        CatnapLibOS::shutdown(self, sockqd, how)
+    }
+
+    fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        CatnapLibOS::local_addr(self, sockqd)
+    }
+
+    fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        CatnapLibOS::remote_addr(self, sockqd)
+    }
+
+    fn local_unix_addr(&self, sockqd: QDesc) -> Result<PathBuf, Fail> {
+        CatnapLibOS::local_unix_addr(self, sockqd)
+    }
+
+    fn remote_unix_addr(&self, sockqd: QDesc) -> Result<PathBuf, Fail> {
+        CatnapLibOS::remote_unix_addr(self, sockqd)
+    }
+
+    fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        CatnapLibOS::get_socket_option(self, sockqd, opt)
+    }
+
+    fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        CatnapLibOS::set_socket_option(self, sockqd, opt)
+    }
+
+    fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        CatnapLibOS::truncations(self, sockqd)
+    }
+
+    fn backlog_overflows(&self, _sockqd: QDesc) -> Result<u64, Fail> {
+        // The kernel only keeps track of backlog overflows system-wide.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "backlog_overflows() is not supported on kernel sockets",
+        ))
+    }
+
+    fn tcp_info(&self, _sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        // The kernel keeps this state, and only reports it through its own socket options.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "tcp_info() is not supported on kernel sockets",
+        ))
+    }
+
+    fn timestamps(&self, _sockqd: QDesc) -> Result<Timestamps, Fail> {
+        // Hardware timestamps are only reported by the kernel through SO_TIMESTAMPING control messages.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "timestamps() is not supported on kernel sockets",
+        ))
+    }
+
+    fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        CatnapLibOS::join_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        CatnapLibOS::leave_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        CatnapLibOS::push(self, sockqd, sga)
+    }
+
+    fn pushv(&mut self, sockqd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        CatnapLibOS::pushv(self, sockqd, sgas)
+    }
+
+    fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
+        CatnapLibOS::pushto(self, sockqd, sga, to)
+    }
+
+    fn pushto_unix(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, path: &Path) -> Result<QToken, Fail> {
+        CatnapLibOS::pushto_unix(self, sockqd, sga, path)
+    }
+
+    fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatnapLibOS::pop(self, sockqd)
+    }
+
+    fn pop_sized(&mut self, sockqd: QDesc, size: usize) -> Result<QToken, Fail> {
+        CatnapLibOS::pop_sized(self, sockqd, size)
+    }
+
+    fn timedpop(&mut self, sockqd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        CatnapLibOS::timedpop(self, sockqd, size, deadline)
+    }
+
+    fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        CatnapLibOS::splice(self, srcqd, dstqd)
+    }
+
+    fn ping(&mut self, _addr: Ipv4Addr, _timeout: Option<Duration>) -> Result<QToken, Fail> {
+        // Sending ICMP messages through the kernel requires raw sockets, and thus privileges that we do not assume.
+        Err(Fail::new(libc::ENOTSUP, "ping() is not supported on kernel sockets"))
+    }
+
+    fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        // The kernel keeps its own neighbor table, which it only exposes through netlink.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_entries() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_insert(&mut self, _ipv4_addr: Ipv4Addr, _link_addr: MacAddress) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_insert() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_pending_drops() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_flush(&mut self) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_flush() is not supported on kernel sockets",
+        ))
+    }
+
+    fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "ipv4_addrs() is not supported on kernel sockets",
+        ))
+    }
+
+    fn add_ipv4_addr(&mut self, _ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_ipv4_addr() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_ipv4_addr(&mut self, _ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_ipv4_addr() is not supported on kernel sockets",
+        ))
+    }
+
+    fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "dhcp_lease() is not supported on kernel sockets",
+        ))
+    }
+
+    fn link_events(&mut self) -> Result<QDesc, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "link_events() is not supported on kernel sockets",
+        ))
+    }
+
+    fn link_state(&self) -> Result<LinkState, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "link_state() is not supported on kernel sockets",
+        ))
+    }
+
+    fn routes(&self) -> Result<Vec<Route>, Fail> {
+        Err(Fail::new(libc::ENOTSUP, "routes() is not supported on kernel sockets"))
+    }
+
+    fn add_route(&mut self, _route: Route) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_route() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_route(&mut self, _destination: Ipv4Addr, _prefix_len: u8) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_route() is not supported on kernel sockets",
+        ))
+    }
+
+    fn set_arp_policy(
+        &mut self,
+        _cache_ttl: Duration,
+        _request_timeout: Duration,
+        _retry_count: usize,
+    ) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "set_arp_policy() is not supported on kernel sockets",
+        ))
+    }
+
+    fn add_flow_rule(&mut self, _rule: FlowRule) -> Result<u32, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_flow_rule() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_flow_rule(&mut self, _id: u32) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_flow_rule() is not supported on kernel sockets",
+        ))
+    }
+
+    fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        self.runtime.scheduler.spawn(future)
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        self.runtime.scheduler.sleep_until(deadline)
+    }
+
+    fn poll(&mut self) {
+        CatnapLibOS::poll(self)
+    }
+
+    fn park(&mut self, timeout: Option<Duration>) {
+        CatnapLibOS::park(self, timeout)
+    }
+
+    fn tasks(&self) -> Vec<TaskInfo> {
+        self.runtime.scheduler.tasks()
+    }
+
+    fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.qtable.limit_stats()
+    }
+
+    fn event_fd(&self) -> Result<RawFd, Fail> {
+        CatnapLibOS::event_fd(self)
+    }
+
+    fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
+        CatnapLibOS::schedule(self, qt)
+    }
+
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        CatnapLibOS::take_completed(self, pred)
+    }
+
+    fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
+        CatnapLibOS::pack_result(self, handle, qt)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        CatnapLibOS::sgaalloc(self, size)
+    }
+
+    fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        CatnapLibOS::sgaregister(self, data, len)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        CatnapLibOS::sgafree(self, sga)
+    }
+
+    fn teardown(self: Box<Self>) -> Result<(), Fail> {
+        CatnapLibOS::teardown(*self)
+    }
+}
+
 //==============================================================================
 // Standalone Functions
 //==============================================================================
@@ -379,6 +1376,18 @@ fn parse_addr(endpoint: SocketAddrV4) -> SockaddrStorage {
     SockaddrStorage::from(ipv4)
 }
 
+/// Converts the path of a unix domain socket into a [SockaddrStorage].
+fn parse_unix_addr(path: &Path) -> Result<SockaddrStorage, Fail> {
+    let addr: UnixAddr = match UnixAddr::new(path) {
+        Ok(addr) => addr,
+        Err(e) => return Err(Fail::new(e as i32, "invalid path")),
+    };
+    match unsafe { SockaddrStorage::from_raw(addr.as_ptr(), Some(addr.len())) } {
+        Some(addr) => Ok(addr),
+        None => Err(Fail::new(EINVAL, "invalid path")),
+    }
+}
+
 /// Packs a [OperationResult] into a [demi_qresult_t].
 fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -> demi_qresult_t {
     match result {
@@ -386,6 +1395,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
             qr_opcode: demi_opcode_t::DEMI_OPC_CONNECT,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
         OperationResult::Accept(new_qd) => {
@@ -400,6 +1411,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                 qr_opcode: demi_opcode_t::DEMI_OPC_ACCEPT,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: 0,
                 qr_value,
             }
         },
@@ -407,11 +1420,55 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
             qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
-        OperationResult::Pop(addr, bytes) => match rt.into_sgarray(bytes) {
+        OperationResult::Splice(_) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_SPLICE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Ping(rtt) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_PING,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                rtt: rtt.as_nanos() as i64,
+            },
+        },
+        OperationResult::Link(state) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_LINK,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                link_up: state.is_up() as u32,
+            },
+        },
+        OperationResult::Close => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_CLOSE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Pop(addr, bytes, mut flags, info) => match rt.into_sgarray(bytes) {
             Ok(mut sga) => {
-                if let Some(endpoint) = addr {
+                if let Some(SocketAddress::Unix(path)) = addr {
+                    // Paths that the kernel reported always fit.
+                    if let Ok(sun) = UnixAddr::new(path.as_path()) {
+                        sga.sga_unaddr = *sun.as_ref();
+                        flags |= DEMI_QR_FLAG_UNIXADDR;
+                    }
+                } else if let Some(SocketAddress::Inet(endpoint)) = addr {
                     let saddr: libc::sockaddr_in = {
                         // TODO: check the following byte order conversion.
                         libc::sockaddr_in {
@@ -425,11 +1482,17 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                     };
                     sga.sga_addr = unsafe { mem::transmute::<libc::sockaddr_in, libc::sockaddr>(saddr) };
                 }
+                if let Some(info) = info {
+                    sga.sga_pktinfo = demi_pktinfo_t::from(info);
+                    flags |= DEMI_QR_FLAG_PKTINFO;
+                }
                 let qr_value: demi_qr_value_t = demi_qr_value_t { sga };
                 demi_qresult_t {
                     qr_opcode: demi_opcode_t::DEMI_OPC_POP,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: flags,
+                    qr_ret: 0,
                     qr_value,
                 }
             },
@@ -439,6 +1502,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                     qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: e.errno as i64,
                     qr_value: unsafe { mem::zeroed() },
                 }
             },
@@ -449,8 +1514,132 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                 qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: e.errno as i64,
                 qr_value: unsafe { mem::zeroed() },
             }
         },
     }
 }
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::CatnapLibOS;
+    use crate::{
+        demikernel::config::Config,
+        inetstack::operations::OperationResult,
+        runtime::{
+            network::types::SocketAddress,
+            types::demi_sgarray_t,
+            QDesc,
+            QToken,
+        },
+        scheduler::SchedulerHandle,
+    };
+    use ::std::{
+        fs,
+        path::PathBuf,
+        process,
+        slice,
+    };
+    use ::yaml_rust::Yaml;
+
+    /// Creates a Catnap LibOS with the default configuration.
+    fn new_libos() -> CatnapLibOS {
+        CatnapLibOS::new(&Config(Yaml::Hash(Default::default())))
+    }
+
+    /// Builds a path for a unix domain socket that is unique to this test process.
+    fn socket_path(name: &str) -> PathBuf {
+        let path: PathBuf = std::env::temp_dir().join(format!("catnap-{}-{}.sock", process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Polls the LibOS until the operation of `qt` completes.
+    fn wait(libos: &mut CatnapLibOS, qt: QToken) -> OperationResult {
+        loop {
+            libos.poll();
+            let handle: SchedulerHandle = libos.schedule(qt).unwrap();
+            if handle.has_completed() {
+                return libos.take_result(handle, qt).1;
+            }
+            handle.into_raw();
+        }
+    }
+
+    /// Pushes `data` to the unix domain socket that is bound to `path`.
+    fn pushto_unix(libos: &mut CatnapLibOS, qd: QDesc, data: &[u8], path: &PathBuf) {
+        let sga: demi_sgarray_t = libos.sgaalloc(data.len()).unwrap();
+        let buf: &mut [u8] = unsafe { slice::from_raw_parts_mut(sga.sga_segs[0].sgaseg_buf as *mut u8, data.len()) };
+        buf.copy_from_slice(data);
+        let qt: QToken = libos.pushto_unix(qd, &sga, path).unwrap();
+        libos.sgafree(sga).unwrap();
+        match wait(libos, qt) {
+            OperationResult::Push => (),
+            qr => panic!("push failed ({:?})", qr),
+        }
+    }
+
+    /// Datagrams are pushed to a path, and popped along with the path of their sender.
+    #[test]
+    fn unix_datagram_pushto_pop() {
+        let mut libos: CatnapLibOS = new_libos();
+        let (path_a, path_b): (PathBuf, PathBuf) = (socket_path("dgram-a"), socket_path("dgram-b"));
+        let qd_a: QDesc = libos.socket(libc::AF_UNIX, libc::SOCK_DGRAM, 0).unwrap();
+        let qd_b: QDesc = libos.socket(libc::AF_UNIX, libc::SOCK_DGRAM, 0).unwrap();
+        libos.bind_unix(qd_a, &path_a).unwrap();
+        libos.bind_unix(qd_b, &path_b).unwrap();
+        assert_eq!(libos.local_unix_addr(qd_b).unwrap(), path_b);
+        assert_eq!(libos.local_addr(qd_b).unwrap_err().errno, libc::EAFNOSUPPORT);
+
+        pushto_unix(&mut libos, qd_a, b"hello", &path_b).unwrap();
+        let qt: QToken = libos.pop(qd_b).unwrap();
+        match wait(&mut libos, qt) {
+            OperationResult::Pop(addr, buf, _, _) => {
+                assert_eq!(addr, Some(SocketAddress::Unix(path_a.clone())));
+                assert_eq!(&buf[..], b"hello");
+            },
+            qr => panic!("pop failed ({:?})", qr),
+        }
+
+        libos.close(qd_a).unwrap();
+        libos.close(qd_b).unwrap();
+        fs::remove_file(&path_a).unwrap();
+        fs::remove_file(&path_b).unwrap();
+    }
+
+    /// Connected stream sockets report the path of their peer, and unnamed sockets report an empty path.
+    #[test]
+    fn unix_stream_addrs() {
+        let mut libos: CatnapLibOS = new_libos();
+        let path: PathBuf = socket_path("stream");
+        let listener: QDesc = libos.socket(libc::AF_UNIX, libc::SOCK_STREAM, 0).unwrap();
+        libos.bind_unix(listener, &path).unwrap();
+        libos.listen(listener, 1).unwrap();
+        let client: QDesc = libos.socket(libc::AF_UNIX, libc::SOCK_STREAM, 0).unwrap();
+        let qt_accept: QToken = libos.accept(listener).unwrap();
+        let qt_connect: QToken = libos.connect_unix(client, &path).unwrap();
+        let server: QDesc = match wait(&mut libos, qt_accept) {
+            OperationResult::Accept(qd) => qd,
+            qr => panic!("accept failed ({:?})", qr),
+        };
+        match wait(&mut libos, qt_connect) {
+            OperationResult::Connect => (),
+            qr => panic!("connect failed ({:?})", qr),
+        }
+
+        assert_eq!(libos.remote_unix_addr(client).unwrap(), path);
+        assert_eq!(libos.local_unix_addr(client).unwrap(), PathBuf::new());
+        assert_eq!(libos.local_unix_addr(server).unwrap(), path);
+
+        libos.close(client).unwrap();
+        libos.close(server).unwrap();
+        libos.close(listener).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
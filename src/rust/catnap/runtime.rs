@@ -5,6 +5,14 @@
 // Imports
 //==============================================================================
 
+use super::{
+    batch::{
+        Batches,
+        CompletionRef,
+        Datagram,
+    },
+    zerocopy::ZeroCopy,
+};
 use crate::{
     runtime::{
         fail::Fail,
@@ -12,32 +20,64 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        types::{
-            demi_sgarray_t,
-            demi_sgaseg_t,
-        },
         Runtime,
     },
     scheduler::scheduler::Scheduler,
 };
-use ::libc::c_void;
+use ::nix::sys::socket::SockaddrStorage;
 use ::std::{
-    mem,
-    ptr::{
-        self,
-        NonNull,
+    cell::{
+        RefCell,
+        RefMut,
+    },
+    collections::{
+        HashMap,
+        HashSet,
     },
+    os::unix::prelude::RawFd,
+    rc::Rc,
+    task::Waker,
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Maximum number of readiness events that are retrieved at once.
+const MAX_EVENTS: usize = 64;
+
+/// Tag of the readiness event of the event file descriptor of the scheduler.
+const SCHEDULER_EVENT: u64 = u64::MAX;
+
 //==============================================================================
 // Structures
 //==============================================================================
 
+/// Readiness of sockets, which is tracked with an epoll instance.
+struct Readiness {
+    /// Underlying epoll instance.
+    epfd: RawFd,
+    /// Sockets that are registered in the epoll instance.
+    registered: HashSet<RawFd>,
+    /// Tasks that wait for sockets to become readable.
+    readers: HashMap<RawFd, Vec<Waker>>,
+    /// Tasks that wait for sockets to become writable.
+    writers: HashMap<RawFd, Vec<Waker>>,
+    /// Whether the event file descriptor of the scheduler was added to the epoll instance.
+    scheduler_added: bool,
+}
+
 /// POSIX Runtime
 #[derive(Clone)]
 pub struct PosixRuntime {
     /// Scheduler
     pub scheduler: Scheduler,
+    /// Readiness of sockets.
+    readiness: Rc<RefCell<Readiness>>,
+    /// Batches of datagram operations.
+    batches: Rc<RefCell<Batches>>,
+    /// Zero-copy sends.
+    zerocopy: Rc<RefCell<ZeroCopy>>,
 }
 
 //==============================================================================
@@ -46,147 +86,221 @@ pub struct PosixRuntime {
 
 /// Associate Functions for POSIX Runtime
 impl PosixRuntime {
-    pub fn new() -> Self {
+    pub fn new(zerocopy_threshold: Option<usize>) -> Self {
+        let epfd: RawFd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if epfd < 0 {
+            panic!("cannot create epoll instance ({:?})", std::io::Error::last_os_error());
+        }
         Self {
             scheduler: Scheduler::default(),
+            readiness: Rc::new(RefCell::new(Readiness {
+                epfd,
+                registered: HashSet::new(),
+                readers: HashMap::new(),
+                writers: HashMap::new(),
+                scheduler_added: false,
+            })),
+            batches: Rc::new(RefCell::new(Batches::default())),
+            zerocopy: Rc::new(RefCell::new(ZeroCopy::new(zerocopy_threshold))),
         }
     }
-}
-
-//==============================================================================
-// Trait Implementations
-//==============================================================================
 
-/// Memory Runtime Trait Implementation for POSIX Runtime
-impl MemoryRuntime for PosixRuntime {
-    /// Converts a runtime buffer into a scatter-gather array.
-    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: buf.len() as u32,
-        };
+    /// Wakes up the task of `waker` once the socket `fd` becomes readable.
+    pub fn wait_readable(&self, fd: RawFd, waker: &Waker) {
+        let mut readiness: RefMut<Readiness> = self.readiness.borrow_mut();
+        if readiness.register(fd) {
+            push_waker(readiness.readers.entry(fd).or_default(), waker);
+        } else {
+            waker.wake_by_ref();
+        }
+    }
 
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+    /// Wakes up the task of `waker` once the socket `fd` becomes writable.
+    pub fn wait_writable(&self, fd: RawFd, waker: &Waker) {
+        let mut readiness: RefMut<Readiness> = self.readiness.borrow_mut();
+        if readiness.register(fd) {
+            push_waker(readiness.writers.entry(fd).or_default(), waker);
+        } else {
+            waker.wake_by_ref();
+        }
     }
 
-    /// Allocates a scatter-gather array.
-    fn alloc_sgarray(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // ToDo: Allocate an array of buffers if requested size is too large for a single buffer.
+    /// Queues up a datagram to be sent on the socket `fd`, along with other datagrams that are pushed before the next
+    /// batch is sent.
+    pub fn batch_push(&self, fd: RawFd, addr: Option<SockaddrStorage>, buf: DemiBuffer) -> CompletionRef<()> {
+        self.batches.borrow_mut().push(fd, addr, buf)
+    }
 
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
-        }
+    /// Queues up a datagram of at most `size` bytes to be received on the socket `fd`, along with other datagrams that
+    /// are popped before the next batch is received.
+    pub fn batch_pop(&self, fd: RawFd, size: usize) -> CompletionRef<Datagram> {
+        self.batches.borrow_mut().pop(fd, size)
+    }
 
-        // First allocate the underlying (heap-allocated) DemiBuffer.
-        let buf: DemiBuffer = DemiBuffer::new(size as u16);
+    /// Sends the datagrams that are queued up on the socket `fd`. Returns true if the socket would block.
+    pub fn send_batch(&self, fd: RawFd) -> bool {
+        self.batches.borrow_mut().send(fd)
+    }
 
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
+    /// Receives the datagrams that are queued up on the socket `fd`. Returns true if the socket would block.
+    pub fn recv_batch(&self, fd: RawFd) -> bool {
+        self.batches.borrow_mut().recv(fd)
+    }
 
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
-    }
-
-    /// Releases a scatter-gather array.
-    fn free_sgarray(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
-        // Check arguments.
-        // TODO: Drop this check once we support scatter-gather arrays with multiple segments.
-        if sga.sga_numsegs != 1 {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
-        }
+    /// Enables zero-copy sends on the socket `fd`, if these are configured.
+    pub fn enable_zerocopy(&self, fd: RawFd) {
+        self.zerocopy.borrow_mut().enable(fd)
+    }
 
-        if sga.sga_buf == ptr::null_mut() {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
-        }
+    /// Checks if a push of `len` bytes on the socket `fd` should be sent without copying its payload.
+    pub fn is_zero_copy(&self, fd: RawFd, len: usize) -> bool {
+        self.zerocopy.borrow().is_zero_copy(fd, len)
+    }
 
-        // Convert back to a DemiBuffer and drop it.
-        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
-        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
-        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
-        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
-        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
-        drop(buf);
+    /// Holds `buf` until the kernel is done with it, which was just sent without copying on the socket `fd`. Returns
+    /// the sequence number of the send.
+    pub fn track_zerocopy(&self, fd: RawFd, buf: DemiBuffer) -> u32 {
+        self.zerocopy.borrow_mut().track(fd, buf)
+    }
 
-        Ok(())
+    /// Checks if the kernel is done with the zero-copy send `seq` on the socket `fd`.
+    pub fn is_zerocopy_done(&self, fd: RawFd, seq: u32) -> bool {
+        self.zerocopy.borrow_mut().is_done(fd, seq)
     }
 
-    /// Clones a scatter-gather array into a DemiBuffer.
-    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
-        // Check arguments.
-        // TODO: Drop this check once we support scatter-gather arrays with multiple segments.
-        if sga.sga_numsegs != 1 {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+    /// Stops tracking the readiness of the socket `fd`, which is about to be closed. Tasks that wait on it are woken
+    /// up, so that they observe the socket closing, and batched operations on it are canceled.
+    pub fn forget(&self, fd: RawFd) {
+        self.batches.borrow_mut().cancel(fd);
+        self.zerocopy.borrow_mut().forget(fd);
+        let mut readiness: RefMut<Readiness> = self.readiness.borrow_mut();
+        if readiness.registered.remove(&fd) {
+            if unsafe { libc::epoll_ctl(readiness.epfd, libc::EPOLL_CTL_DEL, fd, std::ptr::null_mut()) } != 0 {
+                warn!("cannot remove socket from epoll instance (fd={:?})", fd);
+            }
         }
+        let wakers: Vec<Waker> = readiness
+            .readers
+            .remove(&fd)
+            .into_iter()
+            .chain(readiness.writers.remove(&fd))
+            .flatten()
+            .collect();
+        drop(readiness);
+        wakers.into_iter().for_each(Waker::wake);
+    }
 
-        if sga.sga_buf == ptr::null_mut() {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+    /// Retrieves readiness events without blocking and wakes up the tasks that wait on sockets that became ready.
+    pub fn poll_readiness(&self) {
+        let mut events: [libc::epoll_event; MAX_EVENTS] = [libc::epoll_event { events: 0, u64: 0 }; MAX_EVENTS];
+        loop {
+            let mut wakers: Vec<Waker> = Vec::new();
+            let nevents: usize = {
+                let mut readiness: RefMut<Readiness> = self.readiness.borrow_mut();
+                let nevents: i32 =
+                    unsafe { libc::epoll_wait(readiness.epfd, events.as_mut_ptr(), MAX_EVENTS as i32, 0) };
+                if nevents < 0 {
+                    warn!(
+                        "cannot retrieve readiness events ({:?})",
+                        std::io::Error::last_os_error()
+                    );
+                    return;
+                }
+                for event in &events[..nevents as usize] {
+                    if event.u64 == SCHEDULER_EVENT {
+                        continue;
+                    }
+                    let fd: RawFd = event.u64 as RawFd;
+                    let flags: i32 = event.events as i32;
+                    if flags & (libc::EPOLLIN | libc::EPOLLRDHUP | libc::EPOLLHUP | libc::EPOLLERR) != 0 {
+                        wakers.extend(readiness.readers.remove(&fd).unwrap_or_default());
+                    }
+                    if flags & (libc::EPOLLOUT | libc::EPOLLHUP | libc::EPOLLERR) != 0 {
+                        wakers.extend(readiness.writers.remove(&fd).unwrap_or_default());
+                    }
+                }
+                nevents as usize
+            };
+            wakers.into_iter().for_each(Waker::wake);
+            if nevents < MAX_EVENTS {
+                return;
+            }
         }
+    }
 
-        // Convert back to a DemiBuffer.
-        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
-        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
-        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
-        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
-        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
-        let mut clone: DemiBuffer = buf.clone();
-
-        // Don't drop buf, as it holds the same reference to the data as the sgarray (which should keep it).
-        mem::forget(buf);
-
-        // Check to see if the user has reduced the size of the buffer described by the sgarray segment since we
-        // provided it to them.  They could have increased the starting address of the buffer (`sgaseg_buf`),
-        // decreased the ending address of the buffer (`sgaseg_buf + sgaseg_len`), or both.
-        let sga_data: *const u8 = sga.sga_segs[0].sgaseg_buf as *const u8;
-        let sga_len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let clone_data: *const u8 = clone.as_ptr();
-        let mut clone_len: usize = clone.len();
-        if sga_data != clone_data || sga_len != clone_len {
-            // We need to adjust the DemiBuffer to match the user's changes.
-
-            // First check that the user didn't do something non-sensical, like change the buffer description to
-            // reference address space outside of the allocated memory area.
-            if sga_data < clone_data || sga_data.addr() + sga_len > clone_data.addr() + clone_len {
+    /// Returns a file descriptor that becomes readable when some socket becomes ready or some task of the scheduler is
+    /// ready to be polled.
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        let fd: RawFd = self.scheduler.event_fd()?;
+        let mut readiness: RefMut<Readiness> = self.readiness.borrow_mut();
+        if !readiness.scheduler_added {
+            let mut event: libc::epoll_event = libc::epoll_event {
+                events: libc::EPOLLIN as u32,
+                u64: SCHEDULER_EVENT,
+            };
+            if unsafe { libc::epoll_ctl(readiness.epfd, libc::EPOLL_CTL_ADD, fd, &mut event) } != 0 {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
                 return Err(Fail::new(
-                    libc::EINVAL,
-                    "demi_sgarray_t describes data outside backing buffer's allocated region",
+                    errno,
+                    "failed to add event file descriptor to epoll instance",
                 ));
             }
+            readiness.scheduler_added = true;
+        }
+        Ok(readiness.epfd)
+    }
+}
 
-            // Calculate the amount the new starting address is ahead of the old.  And then adjust `clone` to match.
-            let adjustment_amount: usize = sga_data.addr() - clone_data.addr();
-            clone.adjust(adjustment_amount)?;
-
-            // An adjustment above would have reduced clone.len() by the adjustment amount.
-            clone_len -= adjustment_amount;
-            debug_assert_eq!(clone_len, clone.len());
-
-            // Trim the clone down to size.
-            let trim_amount: usize = clone_len - sga_len;
-            clone.trim(trim_amount)?;
+/// Associate Functions for Readiness of Sockets
+impl Readiness {
+    /// Registers the socket `fd` in the epoll instance, if it is not registered yet. Sockets are registered in
+    /// edge-triggered mode for both reads and writes. Returns false if the socket cannot be registered.
+    fn register(&mut self, fd: RawFd) -> bool {
+        if self.registered.contains(&fd) {
+            return true;
+        }
+        let mut event: libc::epoll_event = libc::epoll_event {
+            events: (libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLET) as u32,
+            u64: fd as u64,
+        };
+        if unsafe { libc::epoll_ctl(self.epfd, libc::EPOLL_CTL_ADD, fd, &mut event) } != 0 {
+            let e: std::io::Error = std::io::Error::last_os_error();
+            if e.raw_os_error() != Some(libc::EEXIST) {
+                warn!("cannot add socket to epoll instance (fd={:?}): {:?}", fd, e);
+                return false;
+            }
         }
+        self.registered.insert(fd);
+        true
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
 
-        // Return the clone.
-        Ok(clone)
+/// Adds `waker` to `wakers`, unless it would wake up a task that is already there.
+fn push_waker(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
     }
 }
 
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Memory Runtime Trait Implementation for POSIX Runtime
+impl MemoryRuntime for PosixRuntime {}
+
 /// Runtime Trait Implementation for POSIX Runtime
 impl Runtime for PosixRuntime {}
+
+/// Drop Trait Implementation for Readiness of Sockets
+impl Drop for Readiness {
+    fn drop(&mut self) {
+        if unsafe { libc::close(self.epfd) } != 0 {
+            warn!("cannot close epoll instance");
+        }
+    }
+}
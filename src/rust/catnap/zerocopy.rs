@@ -0,0 +1,150 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    pal::linux,
+    runtime::memory::DemiBuffer,
+};
+use ::libc::c_void;
+use ::std::{
+    collections::HashMap,
+    mem,
+    os::unix::prelude::RawFd,
+    ptr,
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Origin of the error queue notifications that report completed zero-copy sends.
+const SO_EE_ORIGIN_ZEROCOPY: u8 = 5;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Zero-copy sends that are in flight on a socket.
+#[derive(Default)]
+struct ZeroCopySocket {
+    /// Sequence number of the next zero-copy send.
+    next: u32,
+    /// Buffers of the zero-copy sends that the kernel did not release yet, by sequence number.
+    in_flight: HashMap<u32, DemiBuffer>,
+}
+
+/// Zero-copy sends, which are issued with `MSG_ZEROCOPY`. The kernel sends the payload of these straight from their
+/// buffers, so these are held until the kernel reports through the error queue of the socket that it is done with them.
+pub struct ZeroCopy {
+    /// Minimum size of a push that is sent without copying its payload, if enabled.
+    threshold: Option<usize>,
+    /// Sockets that have zero-copy sends enabled.
+    sockets: HashMap<RawFd, ZeroCopySocket>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Zero-Copy Sends
+impl ZeroCopy {
+    /// Creates a tracker of zero-copy sends, which are used for pushes of at least `threshold` bytes.
+    pub fn new(threshold: Option<usize>) -> Self {
+        Self {
+            threshold,
+            sockets: HashMap::new(),
+        }
+    }
+
+    /// Enables zero-copy sends on the socket `fd`, if a threshold is configured.
+    pub fn enable(&mut self, fd: RawFd) {
+        if self.threshold.is_none() {
+            return;
+        }
+        if unsafe { linux::set_socket_option(fd, libc::SO_ZEROCOPY, 1) } != 0 {
+            warn!("cannot set SO_ZEROCOPY option");
+            return;
+        }
+        self.sockets.insert(fd, ZeroCopySocket::default());
+    }
+
+    /// Checks if a push of `len` bytes on the socket `fd` should be sent without copying its payload.
+    pub fn is_zero_copy(&self, fd: RawFd, len: usize) -> bool {
+        match self.threshold {
+            Some(threshold) => len >= threshold && self.sockets.contains_key(&fd),
+            None => false,
+        }
+    }
+
+    /// Holds `buf` until the kernel is done with it, which was just sent without copying on the socket `fd`. Returns
+    /// the sequence number of the send.
+    pub fn track(&mut self, fd: RawFd, buf: DemiBuffer) -> u32 {
+        let socket: &mut ZeroCopySocket = self.sockets.entry(fd).or_default();
+        let seq: u32 = socket.next;
+        socket.next = socket.next.wrapping_add(1);
+        socket.in_flight.insert(seq, buf);
+        seq
+    }
+
+    /// Checks if the kernel is done with the zero-copy send `seq` on the socket `fd`. Notifications are read from the
+    /// error queue of the socket first, and buffers that the kernel is done with are released.
+    pub fn is_done(&mut self, fd: RawFd, seq: u32) -> bool {
+        let socket: &mut ZeroCopySocket = match self.sockets.get_mut(&fd) {
+            Some(socket) => socket,
+            None => return true,
+        };
+        while let Some((lo, hi)) = unsafe { read_notification(fd) } {
+            trace!("zero-copy sends completed ({:?}-{:?})", lo, hi);
+            let mut n: u32 = lo;
+            loop {
+                socket.in_flight.remove(&n);
+                if n == hi {
+                    break;
+                }
+                n = n.wrapping_add(1);
+            }
+        }
+        !socket.in_flight.contains_key(&seq)
+    }
+
+    /// Stops tracking zero-copy sends on the socket `fd`, which is about to be closed. Buffers of sends that are still
+    /// in flight are released along with the socket.
+    pub fn forget(&mut self, fd: RawFd) {
+        self.sockets.remove(&fd);
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Reads a notification of completed zero-copy sends from the error queue of the socket `fd`. Returns the range of
+/// sequence numbers that completed, if any notification was pending.
+unsafe fn read_notification(fd: RawFd) -> Option<(u32, u32)> {
+    loop {
+        // Ancillary data is aligned as control message headers.
+        let mut control: [u64; 16] = [0; 16];
+        let mut hdr: libc::msghdr = mem::zeroed();
+        hdr.msg_control = control.as_mut_ptr() as *mut c_void;
+        hdr.msg_controllen = mem::size_of_val(&control) as _;
+        if libc::recvmsg(fd, &mut hdr, libc::MSG_ERRQUEUE) < 0 {
+            return None;
+        }
+
+        let mut cmsg: *const libc::cmsghdr = libc::CMSG_FIRSTHDR(&hdr);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_IP && (*cmsg).cmsg_type == libc::IP_RECVERR {
+                let err: libc::sock_extended_err =
+                    ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::sock_extended_err);
+                if err.ee_origin == SO_EE_ORIGIN_ZEROCOPY && err.ee_errno == 0 {
+                    return Some((err.ee_info, err.ee_data));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&hdr, cmsg);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::demikernel::config::Config;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of pre-registered buffers for Registered I/O, unless configured otherwise.
+const DEFAULT_RIO_BUFFERS: usize = 1024;
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// CatnapW associated functions for Demikernel configuration object.
+impl Config {
+    /// Reads the "Registered I/O" parameters from the underlying configuration file. If enabled, this is the number of
+    /// pre-registered buffers that sends and receives go through.
+    pub fn rio_buffers(&self) -> Option<usize> {
+        // FIXME: this function should return a Result.
+        if !self.0["catnapw"]["rio"].as_bool().unwrap_or(false) {
+            return None;
+        }
+        match self.0["catnapw"]["rio_buffers"].as_i64() {
+            Some(nbuffers) if nbuffers <= 0 || nbuffers > u16::MAX as i64 => {
+                panic!("Invalid number of RIO buffers {}", nbuffers)
+            },
+            Some(nbuffers) => Some(nbuffers as usize),
+            None => Some(DEFAULT_RIO_BUFFERS),
+        }
+    }
+}
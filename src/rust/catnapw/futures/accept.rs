@@ -5,14 +5,32 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    QDesc,
+use crate::{
+    catnapw::{
+        iocp::{
+            IoHandle,
+            IoOutput,
+        },
+        runtime::PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+};
+use ::socket2::{
+    Domain,
+    Protocol,
+    Socket,
+    Type,
 };
-use ::socket2::Socket;
 use ::std::{
     cell::RefCell,
     future::Future,
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
     pin::Pin,
     rc::Rc,
     task::{
@@ -20,7 +38,6 @@ use ::std::{
         Poll,
     },
 };
-use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 //==============================================================================
 // Structures
@@ -28,12 +45,16 @@ use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 /// Accept Operation Descriptor
 pub struct AcceptFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     /// Underlying socket.
     socket: Rc<RefCell<Socket>>,
     /// Queue descriptor of incoming connection.
     new_qd: QDesc,
+    /// Overlapped accept, once it is issued.
+    handle: Option<IoHandle>,
 }
 
 //==============================================================================
@@ -43,8 +64,14 @@ pub struct AcceptFuture {
 /// Associate Functions for Accept Operation Descriptors
 impl AcceptFuture {
     /// Creates a descriptor for an accept operation.
-    pub fn new(qd: QDesc, socket: Rc<RefCell<Socket>>, new_qd: QDesc) -> Self {
-        Self { qd, socket, new_qd }
+    pub fn new(rt: PosixRuntime, qd: QDesc, socket: Rc<RefCell<Socket>>, new_qd: QDesc) -> Self {
+        Self {
+            rt,
+            qd,
+            socket,
+            new_qd,
+            handle: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [AcceptFuture].
@@ -56,6 +83,13 @@ impl AcceptFuture {
     pub fn get_new_qd(&self) -> QDesc {
         self.new_qd
     }
+
+    /// Issues an overlapped accept. The incoming connection is taken into a socket that is created upfront.
+    fn issue(&self) -> Result<IoHandle, Fail> {
+        let new_socket: Socket = self.rt.socket(Domain::IPV4, Type::STREAM, Protocol::TCP)?;
+        let socket: RawSocket = self.socket.borrow().as_raw_socket();
+        self.rt.accept(socket, new_socket)
+    }
 }
 
 //==============================================================================
@@ -68,10 +102,27 @@ impl Future for AcceptFuture {
 
     /// Polls the target [AcceptFuture].
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
-        let self_: &AcceptFuture = self.get_mut();
-        match self_.socket.borrow().accept() {
+        let self_: &mut AcceptFuture = self.get_mut();
+
+        // Issue overlapped accept, if we have not done so yet.
+        if self_.handle.is_none() {
+            match self_.issue() {
+                Ok(handle) => self_.handle = Some(handle),
+                Err(e) => {
+                    warn!("failed to accept connection ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+            }
+        }
+
+        match self_
+            .handle
+            .as_mut()
+            .expect("accept should be issued")
+            .poll(ctx.waker())
+        {
             // Operation completed.
-            Ok((new_socket, _)) => {
+            Poll::Ready(Ok(IoOutput::Accepted(new_socket))) => {
                 trace!("connection accepted ({:?})", new_socket);
 
                 // Set async options in socket.
@@ -85,16 +136,14 @@ impl Future for AcceptFuture {
                 };
                 Poll::Ready(Ok(new_socket))
             },
-            // Operation in progress.
-            Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
-                ctx.waker().wake_by_ref();
-                Poll::Pending
-            },
+            Poll::Ready(Ok(_)) => unreachable!("accept should output an accepted socket"),
             // Operation failed.
-            Err(e) => {
+            Poll::Ready(Err(e)) => {
                 warn!("failed to accept connection ({:?})", e);
-                Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")))
+                Poll::Ready(Err(e))
             },
+            // Operation in progress.
+            Poll::Pending => Poll::Pending,
         }
     }
 }
@@ -5,28 +5,42 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    QDesc,
+use crate::{
+    catnapw::{
+        iocp::IoHandle,
+        runtime::PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+    scheduler::SleepFuture,
 };
 use ::socket2::{
     SockAddr,
     Socket,
 };
 use ::std::{
-    cell::RefCell,
+    cell::{
+        Ref,
+        RefCell,
+    },
     future::Future,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
     pin::Pin,
     rc::Rc,
     task::{
         Context,
         Poll,
     },
-};
-use ::windows::Win32::Networking::WinSock::{
-    WSAEALREADY,
-    WSAEINPROGRESS,
-    WSAEWOULDBLOCK,
+    time::Instant,
 };
 
 //==============================================================================
@@ -35,12 +49,20 @@ use ::windows::Win32::Networking::WinSock::{
 
 /// Connect Operation Descriptor
 pub struct ConnectFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     // Underlying socket.
     socket: Rc<RefCell<Socket>>,
     /// Destination address.
     addr: SockAddr,
+    /// Timer that expires at the deadline for this operation, if any.
+    timeout: Option<SleepFuture>,
+    /// Whether the underlying socket is a TCP socket.
+    tcp: bool,
+    /// Overlapped connect, once it is issued.
+    handle: Option<IoHandle>,
 }
 
 //==============================================================================
@@ -50,14 +72,46 @@ pub struct ConnectFuture {
 /// Associate Functions for Connect Operation Descriptors
 impl ConnectFuture {
     /// Creates a descriptor for a connect operation.
-    pub fn new(qd: QDesc, socket: Rc<RefCell<Socket>>, addr: SockAddr) -> Self {
-        Self { qd, socket, addr }
+    pub fn new(
+        rt: PosixRuntime,
+        qd: QDesc,
+        socket: Rc<RefCell<Socket>>,
+        addr: SockAddr,
+        deadline: Option<Instant>,
+        tcp: bool,
+    ) -> Self {
+        let timeout: Option<SleepFuture> = deadline.map(|deadline| rt.scheduler.sleep_until(deadline));
+        Self {
+            rt,
+            qd,
+            socket,
+            addr,
+            timeout,
+            tcp,
+            handle: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [ConnectFuture].
     pub fn get_qd(&self) -> QDesc {
         self.qd
     }
+
+    /// Issues an overlapped connect. This requires the socket to be bound, so it is bound to a wildcard address if it
+    /// is not bound yet.
+    fn issue(&self) -> Result<IoHandle, Fail> {
+        let socket: RawSocket = {
+            let socket: Ref<Socket> = self.socket.borrow();
+            if socket.local_addr().is_err() {
+                let any: SockAddr = SockAddr::from(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+                if let Err(e) = socket.bind(&any) {
+                    return Err(Fail::new(e.kind() as i32, "unable to bind"));
+                }
+            }
+            socket.as_raw_socket()
+        };
+        self.rt.connect(socket, self.addr.clone())
+    }
 }
 
 //==============================================================================
@@ -71,27 +125,56 @@ impl Future for ConnectFuture {
     /// Polls the target [ConnectFuture].
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut ConnectFuture = self.get_mut();
-        match self_.socket.borrow().connect(&self_.addr) {
+
+        // Connecting a UDP socket only sets its default destination, so this completes immediately.
+        if !self_.tcp {
+            return match self_.socket.borrow().connect(&self_.addr) {
+                Ok(_) => Poll::Ready(Ok(())),
+                Err(e) => {
+                    warn!("failed to establish connection ({:?})", e);
+                    Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")))
+                },
+            };
+        }
+
+        // Issue overlapped connect, if we have not done so yet.
+        if self_.handle.is_none() {
+            match self_.issue() {
+                Ok(handle) => self_.handle = Some(handle),
+                Err(e) => {
+                    warn!("failed to establish connection ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+            }
+        }
+
+        match self_
+            .handle
+            .as_mut()
+            .expect("connect should be issued")
+            .poll(ctx.waker())
+        {
             // Operation completed.
-            Ok(_) => {
+            Poll::Ready(Ok(_)) => {
                 trace!("connection established ({:?})", self_.addr);
                 Poll::Ready(Ok(()))
             },
-            Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
-                // Same as OK(_), this happens because establishing a connection may take some time.
-                trace!("connection established ({:?})", self_.addr);
-                Poll::Ready(Ok(()))
+            // Operation failed.
+            Poll::Ready(Err(e)) => {
+                warn!("failed to establish connection ({:?})", e);
+                Poll::Ready(Err(e))
             },
             // Operation not ready yet.
-            Err(e) if e.raw_os_error() == Some(WSAEINPROGRESS.0) || e.raw_os_error() == Some(WSAEALREADY.0) => {
-                ctx.waker().wake_by_ref();
+            Poll::Pending => {
+                // Operation timed out. Dropping the handle cancels the overlapped connect.
+                if let Some(ref mut timeout) = self_.timeout {
+                    if Future::poll(Pin::new(timeout), ctx).is_ready() {
+                        self_.handle = None;
+                        return Poll::Ready(Err(Fail::new(libc::ETIMEDOUT, "operation timed out")));
+                    }
+                }
                 Poll::Pending
             },
-            // Operation failed.
-            Err(e) => {
-                warn!("failed to establish connection ({:?})", e);
-                Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")))
-            },
         }
     }
 }
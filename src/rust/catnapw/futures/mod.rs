@@ -10,6 +10,8 @@ pub mod connect;
 pub mod pop;
 pub mod push;
 pub mod pushto;
+pub mod pushv;
+pub mod splice;
 
 //==============================================================================
 // Imports
@@ -21,10 +23,16 @@ use self::{
     pop::PopFuture,
     push::PushFuture,
     pushto::PushtoFuture,
+    pushv::PushvFuture,
+    splice::SpliceFuture,
 };
 use crate::{
     inetstack::operations::OperationResult,
-    runtime::QDesc,
+    runtime::{
+        network::types::SocketAddress,
+        types::DEMI_QR_FLAG_TRUNC,
+        QDesc,
+    },
     scheduler::{
         FutureResult,
         SchedulerFuture,
@@ -55,8 +63,12 @@ pub enum Operation {
     Push(FutureResult<PushFuture>),
     /// Pushto operation.
     Pushto(FutureResult<PushtoFuture>),
+    /// Vectored push operation.
+    Pushv(FutureResult<PushvFuture>),
     /// Pop operation.
     Pop(FutureResult<PopFuture>),
+    /// Splice operation.
+    Splice(FutureResult<SpliceFuture>),
 }
 
 //==============================================================================
@@ -118,16 +130,44 @@ impl Operation {
                 done: Some(Err(e)),
             }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
 
+            // Vectored push operation.
+            Operation::Pushv(FutureResult {
+                future,
+                done: Some(Ok(())),
+            }) => (future.get_qd(), None, None, OperationResult::Push),
+            Operation::Pushv(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             // Pop operation.
             Operation::Pop(FutureResult {
                 future,
-                done: Some(Ok((addr, buf))),
-            }) => (future.get_qd(), None, None, OperationResult::Pop(addr, buf)),
+                done: Some(Ok((addr, buf, truncated))),
+            }) => {
+                let flags: u32 = if truncated { DEMI_QR_FLAG_TRUNC } else { 0 };
+                (
+                    future.get_qd(),
+                    None,
+                    None,
+                    OperationResult::Pop(addr.map(SocketAddress::from), buf, flags, None),
+                )
+            },
             Operation::Pop(FutureResult {
                 future,
                 done: Some(Err(e)),
             }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
 
+            // Splice operation.
+            Operation::Splice(FutureResult {
+                future,
+                done: Some(Ok(nbytes)),
+            }) => (future.get_qd(), None, None, OperationResult::Splice(nbytes)),
+            Operation::Splice(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.get_qd(), None, None, OperationResult::Failed(e)),
+
             _ => panic!("future not ready"),
         }
     }
@@ -146,6 +186,30 @@ impl SchedulerFuture for Operation {
     fn get_future(&self) -> &dyn Future<Output = ()> {
         todo!()
     }
+
+    fn get_kind(&self) -> &'static str {
+        match self {
+            Operation::Accept(_) => "Accept",
+            Operation::Connect(_) => "Connect",
+            Operation::Push(_) => "Push",
+            Operation::Pushto(_) => "Pushto",
+            Operation::Pushv(_) => "Pushv",
+            Operation::Pop(_) => "Pop",
+            Operation::Splice(_) => "Splice",
+        }
+    }
+
+    fn get_qd(&self) -> Option<QDesc> {
+        match self {
+            Operation::Accept(f) => Some(f.future.get_qd()),
+            Operation::Connect(f) => Some(f.future.get_qd()),
+            Operation::Push(f) => Some(f.future.get_qd()),
+            Operation::Pushto(f) => Some(f.future.get_qd()),
+            Operation::Pushv(f) => Some(f.future.get_qd()),
+            Operation::Pop(f) => Some(f.future.get_qd()),
+            Operation::Splice(f) => Some(f.future.get_qd()),
+        }
+    }
 }
 
 /// Future Trait Implementation for Operation Descriptors
@@ -159,7 +223,9 @@ impl Future for Operation {
             Operation::Connect(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Push(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Pushto(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Pushv(ref mut f) => Future::poll(Pin::new(f), ctx),
             Operation::Pop(ref mut f) => Future::poll(Pin::new(f), ctx),
+            Operation::Splice(ref mut f) => Future::poll(Pin::new(f), ctx),
         }
     }
 }
@@ -192,9 +258,23 @@ impl From<PushtoFuture> for Operation {
     }
 }
 
+/// From Trait Implementation for Operation Descriptors
+impl From<PushvFuture> for Operation {
+    fn from(f: PushvFuture) -> Self {
+        Operation::Pushv(FutureResult::new(f, None))
+    }
+}
+
 /// From Trait Implementation for Operation Descriptors
 impl From<PopFuture> for Operation {
     fn from(f: PopFuture) -> Self {
         Operation::Pop(FutureResult::new(f, None))
     }
 }
+
+/// From Trait Implementation for Operation Descriptors
+impl From<SpliceFuture> for Operation {
+    fn from(f: SpliceFuture) -> Self {
+        Operation::Splice(FutureResult::new(f, None))
+    }
+}
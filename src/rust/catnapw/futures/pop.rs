@@ -5,28 +5,39 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    memory::DemiBuffer,
-    QDesc,
+use crate::{
+    catnapw::{
+        iocp::{
+            IoHandle,
+            IoOutput,
+        },
+        runtime::PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+    scheduler::SleepFuture,
 };
 use ::socket2::Socket;
 use ::std::{
     cell::RefCell,
     future::Future,
-    mem::{
-        transmute,
-        MaybeUninit,
-    },
+    io,
     net::SocketAddrV4,
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
     pin::Pin,
     rc::Rc,
     task::{
         Context,
         Poll,
     },
+    time::Instant,
 };
-use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 //==============================================================================
 // Constants
@@ -41,10 +52,20 @@ const POP_SIZE: usize = 9216;
 
 /// Pop Operation Descriptor
 pub struct PopFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     // Underlying socket.
     socket: Rc<RefCell<Socket>>,
+    /// Maximum number of bytes to pop.
+    size: usize,
+    /// Timer that expires at the deadline for this operation, if any.
+    timeout: Option<SleepFuture>,
+    /// Whether the underlying socket is a UDP socket.
+    udp: bool,
+    /// Overlapped receive, once it is issued.
+    handle: Option<IoHandle>,
 }
 
 //==============================================================================
@@ -53,9 +74,25 @@ pub struct PopFuture {
 
 /// Associate Functions for Pop Operation Descriptors
 impl PopFuture {
-    /// Creates a descriptor for a pop operation.
-    pub fn new(qd: QDesc, socket: Rc<RefCell<Socket>>) -> Self {
-        Self { qd, socket }
+    /// Creates a descriptor for a pop operation. At most `size` bytes are popped, capped at the maximum pop size.
+    pub fn new(
+        rt: PosixRuntime,
+        qd: QDesc,
+        socket: Rc<RefCell<Socket>>,
+        size: Option<usize>,
+        deadline: Option<Instant>,
+        udp: bool,
+    ) -> Self {
+        let timeout: Option<SleepFuture> = deadline.map(|deadline| rt.scheduler.sleep_until(deadline));
+        Self {
+            rt,
+            qd,
+            socket,
+            size: size.unwrap_or(POP_SIZE).min(POP_SIZE),
+            timeout,
+            udp,
+            handle: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [PopFuture].
@@ -70,32 +107,147 @@ impl PopFuture {
 
 /// Future Trait Implementation for Pop Operation Descriptors
 impl Future for PopFuture {
-    type Output = Result<(Option<SocketAddrV4>, DemiBuffer), Fail>;
+    type Output = Result<(Option<SocketAddrV4>, DemiBuffer, bool), Fail>;
 
-    /// Polls the target [PopFuture].
+    /// Polls the target [PopFuture]. The output also tells whether the received datagram was truncated.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PopFuture = self.get_mut();
-        let mut bytes: [MaybeUninit<u8>; POP_SIZE] = MaybeUninit::uninit_array();
-        match self_.socket.borrow().recv_from(&mut bytes[..]) {
-            // Operation completed.
-            Ok((nbytes, socketaddr)) => {
-                trace!("data received ({:?}/{:?} bytes)", nbytes, POP_SIZE);
-                unsafe {
-                    let bytes_recv: [u8; POP_SIZE] = transmute::<[MaybeUninit<u8>; POP_SIZE], [u8; POP_SIZE]>(bytes);
-                    let buf: DemiBuffer = DemiBuffer::from_slice(&bytes_recv[0..nbytes])?;
-                    Poll::Ready(Ok((socketaddr.as_socket_ipv4(), buf)))
+        loop {
+            // Issue overlapped receive, if we have not done so yet.
+            if self_.handle.is_none() {
+                let socket: RawSocket = self_.socket.borrow().as_raw_socket();
+                match self_.rt.recv(socket, self_.size) {
+                    Ok(handle) => self_.handle = Some(handle),
+                    Err(e) => {
+                        trace!("pop failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    },
                 }
-            },
-            // Operation in progress.
-            Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
-                ctx.waker().wake_by_ref();
-                Poll::Pending
-            },
-            // Error.
-            Err(e) => {
-                trace!("pop failed ({:?})", e);
-                Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")))
-            },
+            }
+
+            match self_
+                .handle
+                .as_mut()
+                .expect("receive should be issued")
+                .poll(ctx.waker())
+            {
+                // Operation completed.
+                Poll::Ready(Ok(IoOutput::Received(addr, buf, truncated))) => {
+                    trace!("data received ({:?}/{:?} bytes)", buf.len(), self_.size);
+                    return Poll::Ready(Ok((addr, buf, truncated)));
+                },
+                // Winsock reports ICMP port unreachable messages on UDP sockets as connection resets, while Linux
+                // ignores them on unconnected sockets, so we keep waiting for a datagram in this case.
+                Poll::Ready(Ok(IoOutput::Reset)) if self_.udp => {
+                    self_.handle = None;
+                },
+                Poll::Ready(Ok(IoOutput::Reset)) => {
+                    trace!("pop failed (connection reset)");
+                    return Poll::Ready(Err(Fail::new(
+                        io::ErrorKind::ConnectionReset as i32,
+                        "connection reset",
+                    )));
+                },
+                Poll::Ready(Ok(_)) => unreachable!("receive should output received data"),
+                // Error.
+                Poll::Ready(Err(e)) => {
+                    trace!("pop failed ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+                // Operation in progress.
+                Poll::Pending => {
+                    // Operation timed out. Dropping the handle cancels the overlapped receive.
+                    if let Some(ref mut timeout) = self_.timeout {
+                        if Future::poll(Pin::new(timeout), ctx).is_ready() {
+                            self_.handle = None;
+                            return Poll::Ready(Err(Fail::new(libc::ETIMEDOUT, "operation timed out")));
+                        }
+                    }
+                    return Poll::Pending;
+                },
+            }
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::PopFuture;
+    use crate::{
+        catnapw::runtime::PosixRuntime,
+        runtime::QDesc,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::socket2::{
+        Domain,
+        Protocol,
+        SockAddr,
+        Socket,
+        Type,
+    };
+    use ::std::{
+        cell::RefCell,
+        future::Future,
+        net::{
+            Ipv4Addr,
+            SocketAddr,
+            SocketAddrV4,
+            UdpSocket,
+        },
+        os::windows::io::AsRawSocket,
+        pin::Pin,
+        rc::Rc,
+        task::{
+            Context,
+            Poll,
+        },
+        thread,
+        time::Duration,
+    };
+
+    /// Checks that an ICMP port unreachable message, which Winsock reports on UDP sockets as a connection reset, does
+    /// not fail a pop, which keeps waiting for a datagram.
+    #[test]
+    fn udp_pop_ignores_port_unreachable() {
+        let rt: PosixRuntime = PosixRuntime::new(None);
+        let socket: Socket = rt.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP).unwrap();
+        socket
+            .bind(&SockAddr::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)))
+            .unwrap();
+        rt.associate(socket.as_raw_socket()).unwrap();
+        let local: SocketAddr = socket.local_addr().unwrap().as_socket().unwrap();
+
+        // Send a datagram to a port that nobody listens on, which is answered by an ICMP port unreachable message.
+        let closed: SocketAddr = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        socket.send_to(b"ping", &SockAddr::from(closed)).unwrap();
+
+        let socket: Rc<RefCell<Socket>> = Rc::new(RefCell::new(socket));
+        let mut pop_future: PopFuture = PopFuture::new(rt.clone(), QDesc::from(0), socket, None, None, true);
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        for _ in 0..10 {
+            rt.poll_completions();
+            assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // A datagram that arrives afterwards completes the pop.
+        let peer: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        peer.send_to(b"hello", local).unwrap();
+        loop {
+            rt.poll_completions();
+            match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+                Poll::Ready(Ok((_, buf, truncated))) => {
+                    assert_eq!(&buf[..], b"hello");
+                    assert!(!truncated);
+                    break;
+                },
+                Poll::Ready(Err(e)) => panic!("pop failed ({:?})", e),
+                Poll::Pending => thread::sleep(Duration::from_millis(1)),
+            }
         }
     }
 }
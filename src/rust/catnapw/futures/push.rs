@@ -5,15 +5,28 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    memory::DemiBuffer,
-    QDesc,
+use crate::{
+    catnapw::{
+        iocp::{
+            IoHandle,
+            IoOutput,
+        },
+        runtime::PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
 };
 use ::socket2::Socket;
 use ::std::{
     cell::RefCell,
     future::Future,
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
     pin::Pin,
     rc::Rc,
     task::{
@@ -21,7 +34,6 @@ use ::std::{
         Poll,
     },
 };
-use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 //==============================================================================
 // Structures
@@ -29,12 +41,16 @@ use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 /// Push Operation Descriptor
 pub struct PushFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     // Underlying socket.
     socket: Rc<RefCell<Socket>>,
-    /// Buffer to send.
+    /// Buffer to send, which holds the bytes that are yet to be sent.
     buf: DemiBuffer,
+    /// Overlapped send, once it is issued.
+    handle: Option<IoHandle>,
 }
 
 //==============================================================================
@@ -44,8 +60,14 @@ pub struct PushFuture {
 /// Associate Functions for Push Operation Descriptors
 impl PushFuture {
     /// Creates a descriptor for a push operation.
-    pub fn new(qd: QDesc, socket: Rc<RefCell<Socket>>, buf: DemiBuffer) -> Self {
-        Self { qd, socket, buf }
+    pub fn new(rt: PosixRuntime, qd: QDesc, socket: Rc<RefCell<Socket>>, buf: DemiBuffer) -> Self {
+        Self {
+            rt,
+            qd,
+            socket,
+            buf,
+            handle: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [PushFuture].
@@ -65,22 +87,43 @@ impl Future for PushFuture {
     /// Polls the target [PushFuture].
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PushFuture = self.get_mut();
-        match self_.socket.borrow().send(&self_.buf[..]) {
-            // Operation completed.
-            Ok(nbytes) => {
-                trace!("data pushed ({:?}/{:?} bytes)", nbytes, self_.buf.len());
-                Poll::Ready(Ok(()))
-            },
-            // Operation in progress.
-            Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
-                ctx.waker().wake_by_ref();
-                Poll::Pending
-            },
-            // Error.
-            Err(e) => {
-                warn!("push failed ({:?})", e);
-                Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")))
-            },
+        loop {
+            // Issue overlapped send, if we have not done so yet.
+            if self_.handle.is_none() {
+                let socket: RawSocket = self_.socket.borrow().as_raw_socket();
+                match self_.rt.send(socket, vec![self_.buf.clone()], None) {
+                    Ok(handle) => self_.handle = Some(handle),
+                    Err(e) => {
+                        warn!("push failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    },
+                }
+            }
+
+            match self_.handle.as_mut().expect("send should be issued").poll(ctx.waker()) {
+                // Operation completed.
+                Poll::Ready(Ok(IoOutput::Sent(nbytes))) if nbytes >= self_.buf.len() => {
+                    trace!("data pushed");
+                    return Poll::Ready(Ok(()));
+                },
+                // The send was short, so issue another one for the remaining bytes.
+                Poll::Ready(Ok(IoOutput::Sent(nbytes))) => {
+                    trace!("data pushed ({:?} bytes)", nbytes);
+                    if let Err(e) = self_.buf.adjust(nbytes) {
+                        warn!("push failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    }
+                    self_.handle = None;
+                },
+                Poll::Ready(Ok(_)) => unreachable!("send should output the number of bytes sent"),
+                // Error.
+                Poll::Ready(Err(e)) => {
+                    warn!("push failed ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+                // Operation in progress.
+                Poll::Pending => return Poll::Pending,
+            }
         }
     }
 }
@@ -5,10 +5,16 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    memory::DemiBuffer,
-    QDesc,
+use crate::{
+    catnapw::{
+        iocp::IoHandle,
+        runtime::PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
 };
 use ::socket2::{
     SockAddr,
@@ -17,6 +23,11 @@ use ::socket2::{
 use ::std::{
     cell::RefCell,
     future::Future,
+    mem,
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
     pin::Pin,
     rc::Rc,
     task::{
@@ -24,7 +35,6 @@ use ::std::{
         Poll,
     },
 };
-use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 //==============================================================================
 // Structures
@@ -32,6 +42,8 @@ use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
 
 /// Pushto Operation Descriptor
 pub struct PushtoFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
     /// Associated queue descriptor.
     qd: QDesc,
     /// Destination address.
@@ -39,7 +51,9 @@ pub struct PushtoFuture {
     // Underlying socket.
     socket: Rc<RefCell<Socket>>,
     /// Buffer to send.
-    buf: DemiBuffer,
+    bufs: Vec<DemiBuffer>,
+    /// Overlapped send, once it is issued.
+    handle: Option<IoHandle>,
 }
 
 //==============================================================================
@@ -49,8 +63,15 @@ pub struct PushtoFuture {
 /// Associate Functions for Pushto Operation Descriptors
 impl PushtoFuture {
     /// Creates a descriptor for a pushto operation.
-    pub fn new(qd: QDesc, socket: Rc<RefCell<Socket>>, addr: SockAddr, buf: DemiBuffer) -> Self {
-        Self { qd, addr, socket, buf }
+    pub fn new(rt: PosixRuntime, qd: QDesc, socket: Rc<RefCell<Socket>>, addr: SockAddr, buf: DemiBuffer) -> Self {
+        Self {
+            rt,
+            qd,
+            addr,
+            socket,
+            bufs: vec![buf],
+            handle: None,
+        }
     }
 
     /// Returns the queue descriptor associated to the target [PushtoFuture].
@@ -70,22 +91,33 @@ impl Future for PushtoFuture {
     /// Polls the target [PushtoFuture].
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let self_: &mut PushtoFuture = self.get_mut();
-        match self_.socket.borrow().send_to(&self_.buf[..], &self_.addr) {
+
+        // Issue overlapped send, if we have not done so yet.
+        if self_.handle.is_none() {
+            let bufs: Vec<DemiBuffer> = mem::take(&mut self_.bufs);
+            let socket: RawSocket = self_.socket.borrow().as_raw_socket();
+            match self_.rt.send(socket, bufs, Some(self_.addr.clone())) {
+                Ok(handle) => self_.handle = Some(handle),
+                Err(e) => {
+                    warn!("push failed ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+            }
+        }
+
+        match self_.handle.as_mut().expect("send should be issued").poll(ctx.waker()) {
             // Operation completed.
-            Ok(nbytes) => {
-                trace!("data pushed ({:?}/{:?} bytes)", nbytes, self_.buf.len());
+            Poll::Ready(Ok(_)) => {
+                trace!("data pushed");
                 Poll::Ready(Ok(()))
             },
-            // Operation in progress.
-            Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
-                ctx.waker().wake_by_ref();
-                Poll::Pending
-            },
             // Error.
-            Err(e) => {
+            Poll::Ready(Err(e)) => {
                 warn!("push failed ({:?})", e);
-                Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")))
+                Poll::Ready(Err(e))
             },
+            // Operation in progress.
+            Poll::Pending => Poll::Pending,
         }
     }
 }
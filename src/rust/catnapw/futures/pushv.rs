@@ -0,0 +1,144 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    catnapw::{
+        iocp::{
+            IoHandle,
+            IoOutput,
+        },
+        runtime::PosixRuntime,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        QDesc,
+    },
+};
+use ::socket2::Socket;
+use ::std::{
+    cell::RefCell,
+    future::Future,
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Vectored Push Operation Descriptor
+pub struct PushvFuture {
+    /// Underlying runtime.
+    rt: PosixRuntime,
+    /// Associated queue descriptor.
+    qd: QDesc,
+    // Underlying socket.
+    socket: Rc<RefCell<Socket>>,
+    /// Buffers that remain to be sent.
+    bufs: Vec<DemiBuffer>,
+    /// Overlapped send, once it is issued.
+    handle: Option<IoHandle>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Vectored Push Operation Descriptors
+impl PushvFuture {
+    /// Creates a descriptor for a vectored push operation.
+    pub fn new(rt: PosixRuntime, qd: QDesc, socket: Rc<RefCell<Socket>>, bufs: Vec<DemiBuffer>) -> Self {
+        Self {
+            rt,
+            qd,
+            socket,
+            bufs,
+            handle: None,
+        }
+    }
+
+    /// Returns the queue descriptor associated to the target [PushvFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Vectored Push Operation Descriptors
+impl Future for PushvFuture {
+    type Output = Result<(), Fail>;
+
+    /// Polls the target [PushvFuture]. Sends that fall short are resumed from where they stopped.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut PushvFuture = self.get_mut();
+        loop {
+            // Issue overlapped send, if we have not done so yet.
+            if self_.handle.is_none() {
+                let socket: RawSocket = self_.socket.borrow().as_raw_socket();
+                match self_.rt.send(socket, self_.bufs.clone(), None) {
+                    Ok(handle) => self_.handle = Some(handle),
+                    Err(e) => {
+                        warn!("pushv failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    },
+                }
+            }
+
+            match self_.handle.as_mut().expect("send should be issued").poll(ctx.waker()) {
+                // Operation completed.
+                Poll::Ready(Ok(IoOutput::Sent(nbytes))) => {
+                    trace!("data pushed ({:?} bytes)", nbytes);
+                    if let Err(e) = consume(&mut self_.bufs, nbytes) {
+                        warn!("pushv failed ({:?})", e);
+                        return Poll::Ready(Err(e));
+                    }
+                    if self_.bufs.is_empty() {
+                        return Poll::Ready(Ok(()));
+                    }
+                    // The send was short, so issue another one for the remaining bytes.
+                    self_.handle = None;
+                },
+                Poll::Ready(Ok(_)) => unreachable!("send should output the number of bytes sent"),
+                // Error.
+                Poll::Ready(Err(e)) => {
+                    warn!("pushv failed ({:?})", e);
+                    return Poll::Ready(Err(e));
+                },
+                // Operation in progress.
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Removes the first `nbytes` bytes of `bufs`, which were sent.
+fn consume(bufs: &mut Vec<DemiBuffer>, mut nbytes: usize) -> Result<(), Fail> {
+    while let Some(buf) = bufs.first_mut() {
+        if nbytes < buf.len() {
+            return buf.adjust(nbytes);
+        }
+        nbytes -= buf.len();
+        bufs.remove(0);
+    }
+    Ok(())
+}
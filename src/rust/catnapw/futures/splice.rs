@@ -0,0 +1,142 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    QDesc,
+};
+use ::socket2::Socket;
+use ::std::{
+    cell::RefCell,
+    future::Future,
+    mem::{
+        transmute,
+        MaybeUninit,
+    },
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+use ::windows::Win32::Networking::WinSock::WSAEWOULDBLOCK;
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Maximum Size for a Single Receive of a Splice Operation
+const SPLICE_SIZE: usize = 9216;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Splice Operation Descriptor
+pub struct SpliceFuture {
+    /// Queue descriptor of the source socket.
+    qd: QDesc,
+    /// Source socket.
+    src: Rc<RefCell<Socket>>,
+    /// Destination socket.
+    dst: Rc<RefCell<Socket>>,
+    /// Data that was received but not sent yet.
+    pending: Option<DemiBuffer>,
+    /// Number of bytes forwarded so far.
+    nbytes: usize,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Splice Operation Descriptors
+impl SpliceFuture {
+    /// Creates a descriptor for a splice operation.
+    pub fn new(qd: QDesc, src: Rc<RefCell<Socket>>, dst: Rc<RefCell<Socket>>) -> Self {
+        Self {
+            qd,
+            src,
+            dst,
+            pending: None,
+            nbytes: 0,
+        }
+    }
+
+    /// Returns the queue descriptor associated to the target [SpliceFuture].
+    pub fn get_qd(&self) -> QDesc {
+        self.qd
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Splice Operation Descriptors
+impl Future for SpliceFuture {
+    type Output = Result<usize, Fail>;
+
+    /// Polls the target [SpliceFuture]. Data is forwarded until the source socket reaches end-of-stream.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut SpliceFuture = self.get_mut();
+        loop {
+            // Send data that is pending, if any.
+            if let Some(ref mut buf) = self_.pending {
+                match self_.dst.borrow().send(&buf[..]) {
+                    // Operation completed.
+                    Ok(nbytes) => {
+                        trace!("data forwarded ({:?}/{:?} bytes)", nbytes, buf.len());
+                        self_.nbytes += nbytes;
+                        if nbytes < buf.len() {
+                            buf.adjust(nbytes)?;
+                        } else {
+                            self_.pending = None;
+                        }
+                        continue;
+                    },
+                    // Operation in progress.
+                    Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
+                        ctx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    },
+                    // Error.
+                    Err(e) => {
+                        warn!("splice failed ({:?})", e);
+                        return Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")));
+                    },
+                }
+            }
+
+            // Receive more data.
+            let mut bytes: [MaybeUninit<u8>; SPLICE_SIZE] = MaybeUninit::uninit_array();
+            match self_.src.borrow().recv(&mut bytes) {
+                // End of stream.
+                Ok(0) => return Poll::Ready(Ok(self_.nbytes)),
+                // Operation completed.
+                Ok(nbytes) => {
+                    trace!("data received ({:?} bytes)", nbytes);
+                    let bytes_recv: [u8; SPLICE_SIZE] =
+                        unsafe { transmute::<[MaybeUninit<u8>; SPLICE_SIZE], [u8; SPLICE_SIZE]>(bytes) };
+                    self_.pending = Some(DemiBuffer::from_slice(&bytes_recv[0..nbytes])?);
+                },
+                // Operation in progress.
+                Err(e) if e.raw_os_error() == Some(WSAEWOULDBLOCK.0) => {
+                    ctx.waker().wake_by_ref();
+                    return Poll::Pending;
+                },
+                // Error.
+                Err(e) => {
+                    warn!("splice failed ({:?})", e);
+                    return Poll::Ready(Err(Fail::new(e.kind() as i32, "operation failed")));
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,747 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+};
+use ::socket2::{
+    SockAddr,
+    Socket,
+};
+use ::std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::c_void,
+    io,
+    mem,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
+    ptr,
+    rc::Rc,
+    task::{
+        Poll,
+        Waker,
+    },
+    time::Duration,
+};
+use ::windows::Win32::Foundation::{
+    CloseHandle,
+    HANDLE,
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Maximum number of completions that are dequeued at once.
+const MAX_COMPLETIONS: usize = 64;
+
+/// Error that tells that an overlapped operation is in progress (see `WSA_IO_PENDING` in `winsock2.h`).
+const WSA_IO_PENDING: i32 = 997;
+
+/// Error that tells that a datagram did not fit in the receive buffer (see `WSAEMSGSIZE` in `winsock2.h`).
+const WSAEMSGSIZE: i32 = 10040;
+
+/// Error that tells that the connection was reset, which is also reported on UDP sockets once an ICMP port unreachable
+/// message arrives (see `WSAECONNRESET` in `winsock2.h`).
+const WSAECONNRESET: i32 = 10054;
+
+/// Socket options that update the state of sockets once an overlapped accept or connect completes (see
+/// `SO_UPDATE_ACCEPT_CONTEXT` and `SO_UPDATE_CONNECT_CONTEXT` in `mswsock.h`).
+const SOL_SOCKET: i32 = 0xffff;
+const SO_UPDATE_ACCEPT_CONTEXT: i32 = 0x700b;
+const SO_UPDATE_CONNECT_CONTEXT: i32 = 0x7010;
+
+/// Control code that retrieves extension functions of Winsock (see `SIO_GET_EXTENSION_FUNCTION_POINTER` in
+/// `ws2def.h`).
+const SIO_GET_EXTENSION_FUNCTION_POINTER: u32 = 0xc8000006;
+
+/// Identifier of the `ConnectEx` extension function (see `WSAID_CONNECTEX` in `mswsock.h`).
+const WSAID_CONNECTEX: Guid = Guid {
+    data1: 0x25a207b9,
+    data2: 0xddf3,
+    data3: 0x4660,
+    data4: [0x8e, 0xe9, 0x76, 0xe5, 0x8c, 0x74, 0x06, 0x3e],
+};
+
+/// Size of a socket address storage (see `SOCKADDR_STORAGE` in `ws2def.h`).
+pub const SOCKADDR_STORAGE_SIZE: usize = 128;
+
+/// Size of each of the addresses that `AcceptEx` writes, which must be 16 bytes larger than the largest address.
+const ACCEPT_ADDR_SIZE: usize = SOCKADDR_STORAGE_SIZE + 16;
+
+/// Overlapped operation (see `OVERLAPPED` in `minwinbase.h`).
+#[repr(C)]
+#[allow(dead_code)]
+struct Overlapped {
+    internal: usize,
+    internal_high: usize,
+    offset: u32,
+    offset_high: u32,
+    event: isize,
+}
+
+/// Completion of an overlapped operation (see `OVERLAPPED_ENTRY` in `minwinbase.h`).
+#[repr(C)]
+#[allow(dead_code)]
+struct OverlappedEntry {
+    completion_key: usize,
+    overlapped: *mut Overlapped,
+    internal: usize,
+    nbytes: u32,
+}
+
+/// Buffer of a Winsock operation (see `WSABUF` in `ws2def.h`).
+#[repr(C)]
+#[allow(dead_code)]
+struct WsaBuf {
+    len: u32,
+    buf: *mut u8,
+}
+
+/// Globally unique identifier (see `GUID` in `guiddef.h`).
+#[repr(C)]
+#[allow(dead_code)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+/// Signature of the `ConnectEx` extension function (see `LPFN_CONNECTEX` in `mswsock.h`).
+type ConnectExFn = unsafe extern "system" fn(
+    s: usize,
+    name: *const c_void,
+    namelen: i32,
+    sendbuf: *const c_void,
+    sendlen: u32,
+    nsent: *mut u32,
+    overlapped: *mut Overlapped,
+) -> i32;
+
+/// Resources of an overlapped operation, which must outlive it.
+enum Resources {
+    /// Buffers to send, and the destination address, if any.
+    Send(Vec<DemiBuffer>, Option<SockAddr>),
+    /// Buffer to receive into, along with the source address and the flags of the received data.
+    Recv {
+        buf: DemiBuffer,
+        from: [u8; SOCKADDR_STORAGE_SIZE],
+        fromlen: i32,
+        flags: u32,
+    },
+    /// Socket that takes the incoming connection, along with the addresses of both of its ends.
+    Accept {
+        socket: Option<Socket>,
+        addrs: [u8; 2 * ACCEPT_ADDR_SIZE],
+    },
+    /// Destination address.
+    Connect(SockAddr),
+}
+
+/// Overlapped operation that is issued on a completion port.
+#[repr(C)]
+struct IoOperation {
+    /// Underlying overlapped structure. This must come first, so that completions can be mapped back to operations.
+    overlapped: Overlapped,
+    /// Socket on which the operation is issued.
+    socket: usize,
+    /// Resources of the operation.
+    resources: Resources,
+    /// Number of bytes transferred by the operation, or the error that it failed with, once it completes.
+    result: Option<Result<u32, i32>>,
+    /// Task that waits for the operation to complete.
+    waker: Option<Waker>,
+    /// Whether the task that issued the operation no longer waits for it.
+    orphaned: bool,
+}
+
+/// Output of an overlapped operation.
+pub enum IoOutput {
+    /// Data was sent. This tells how many bytes were sent.
+    Sent(usize),
+    /// Data was received from some address. This also tells whether the received datagram was truncated.
+    Received(Option<SocketAddrV4>, DemiBuffer, bool),
+    /// A receive failed because the connection was reset, or, on UDP sockets, because an ICMP port unreachable message
+    /// arrived.
+    Reset,
+    /// An incoming connection was accepted.
+    Accepted(Socket),
+    /// A connection was established.
+    Connected,
+}
+
+/// I/O completion port, on which overlapped operations on sockets are issued.
+pub struct CompletionPort {
+    /// Underlying handle.
+    handle: HANDLE,
+    /// Operations that are in flight, indexed by the address of their overlapped structure.
+    operations: HashMap<usize, Box<IoOperation>>,
+    /// `ConnectEx` extension function, once it is retrieved.
+    connect_ex: Option<ConnectExFn>,
+}
+
+/// Queue on which overlapped operations are issued.
+pub trait IoQueue {
+    /// Polls the operation `key`. If it completed, this takes out its output.
+    fn poll_operation(&mut self, key: usize, waker: &Waker) -> Poll<Result<IoOutput, Fail>>;
+
+    /// Cancels the operation `key`.
+    fn cancel(&mut self, key: usize);
+}
+
+/// Handle of an overlapped operation. The operation is canceled if this is dropped before the operation completes.
+pub struct IoHandle {
+    /// Queue on which the operation is issued.
+    queue: Rc<RefCell<dyn IoQueue>>,
+    /// Key of the operation.
+    key: usize,
+    /// Whether the output of the operation was taken.
+    done: bool,
+}
+
+//==============================================================================
+// Foreign Functions
+//==============================================================================
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateIoCompletionPort(file: HANDLE, port: HANDLE, key: usize, nthreads: u32) -> HANDLE;
+    fn GetQueuedCompletionStatusEx(
+        port: HANDLE,
+        entries: *mut OverlappedEntry,
+        count: u32,
+        nremoved: *mut u32,
+        timeout: u32,
+        alertable: i32,
+    ) -> i32;
+    fn CancelIoEx(file: HANDLE, overlapped: *mut Overlapped) -> i32;
+}
+
+#[link(name = "ws2_32")]
+extern "system" {
+    fn WSASend(
+        s: usize,
+        bufs: *const WsaBuf,
+        nbufs: u32,
+        nsent: *mut u32,
+        flags: u32,
+        overlapped: *mut Overlapped,
+        routine: *const c_void,
+    ) -> i32;
+    fn WSASendTo(
+        s: usize,
+        bufs: *const WsaBuf,
+        nbufs: u32,
+        nsent: *mut u32,
+        flags: u32,
+        to: *const c_void,
+        tolen: i32,
+        overlapped: *mut Overlapped,
+        routine: *const c_void,
+    ) -> i32;
+    fn WSARecvFrom(
+        s: usize,
+        bufs: *const WsaBuf,
+        nbufs: u32,
+        nrecvd: *mut u32,
+        flags: *mut u32,
+        from: *mut c_void,
+        fromlen: *mut i32,
+        overlapped: *mut Overlapped,
+        routine: *const c_void,
+    ) -> i32;
+    fn WSAIoctl(
+        s: usize,
+        code: u32,
+        inbuf: *const c_void,
+        inlen: u32,
+        outbuf: *mut c_void,
+        outlen: u32,
+        nreturned: *mut u32,
+        overlapped: *mut c_void,
+        routine: *const c_void,
+    ) -> i32;
+    fn WSAGetOverlappedResult(
+        s: usize,
+        overlapped: *mut Overlapped,
+        nbytes: *mut u32,
+        wait: i32,
+        flags: *mut u32,
+    ) -> i32;
+    fn WSAGetLastError() -> i32;
+    fn setsockopt(s: usize, level: i32, name: i32, val: *const c_void, len: i32) -> i32;
+}
+
+#[link(name = "mswsock")]
+extern "system" {
+    fn AcceptEx(
+        listen: usize,
+        accept: usize,
+        buf: *mut c_void,
+        recvlen: u32,
+        locallen: u32,
+        remotelen: u32,
+        nbytes: *mut u32,
+        overlapped: *mut Overlapped,
+    ) -> i32;
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for I/O Completion Ports
+impl CompletionPort {
+    /// Creates a completion port.
+    pub fn new() -> Result<Self, Fail> {
+        let handle: HANDLE = unsafe { CreateIoCompletionPort(HANDLE(-1), HANDLE(0), 0, 1) };
+        if handle.0 == 0 {
+            return Err(last_error("failed to create completion port"));
+        }
+        Ok(Self {
+            handle,
+            operations: HashMap::new(),
+            connect_ex: None,
+        })
+    }
+
+    /// Associates `socket` with the target completion port, so that overlapped operations may be issued on it.
+    pub fn associate(&mut self, socket: RawSocket) -> Result<(), Fail> {
+        let handle: HANDLE = unsafe { CreateIoCompletionPort(HANDLE(socket as isize), self.handle, 0, 0) };
+        if handle.0 == 0 {
+            return Err(last_error("failed to associate socket with completion port"));
+        }
+        Ok(())
+    }
+
+    /// Sends `bufs` on `socket`, to the address `addr` if it is set.
+    pub fn send(&mut self, socket: RawSocket, bufs: Vec<DemiBuffer>, addr: Option<SockAddr>) -> Result<usize, Fail> {
+        let (key, op): (usize, &mut IoOperation) = self.insert(socket, Resources::Send(bufs, addr));
+        let result: i32 = match op.resources {
+            Resources::Send(ref bufs, ref addr) => {
+                // Winsock captures the buffer descriptors before returning, so these need not outlive the call.
+                let wsabufs: Vec<WsaBuf> = bufs
+                    .iter()
+                    .flat_map(|buf| buf.segments())
+                    .map(|seg| WsaBuf {
+                        len: seg.len() as u32,
+                        buf: seg.as_ptr() as *mut u8,
+                    })
+                    .collect();
+                match addr {
+                    Some(addr) => unsafe {
+                        WSASendTo(
+                            op.socket,
+                            wsabufs.as_ptr(),
+                            wsabufs.len() as u32,
+                            ptr::null_mut(),
+                            0,
+                            addr.as_ptr() as *const c_void,
+                            addr.len(),
+                            &mut op.overlapped,
+                            ptr::null(),
+                        )
+                    },
+                    None => unsafe {
+                        WSASend(
+                            op.socket,
+                            wsabufs.as_ptr(),
+                            wsabufs.len() as u32,
+                            ptr::null_mut(),
+                            0,
+                            &mut op.overlapped,
+                            ptr::null(),
+                        )
+                    },
+                }
+            },
+            _ => unreachable!(),
+        };
+        self.issued(key, result == 0, "failed to send data")
+    }
+
+    /// Receives at most `size` bytes from `socket`.
+    pub fn recv(&mut self, socket: RawSocket, size: usize) -> Result<usize, Fail> {
+        let resources: Resources = Resources::Recv {
+            buf: DemiBuffer::new(size as u16),
+            from: [0; SOCKADDR_STORAGE_SIZE],
+            fromlen: SOCKADDR_STORAGE_SIZE as i32,
+            flags: 0,
+        };
+        let (key, op): (usize, &mut IoOperation) = self.insert(socket, resources);
+        let result: i32 = match op.resources {
+            Resources::Recv {
+                ref mut buf,
+                ref mut from,
+                ref mut fromlen,
+                ref mut flags,
+            } => {
+                let wsabuf: WsaBuf = WsaBuf {
+                    len: buf.len() as u32,
+                    buf: buf.as_mut_ptr(),
+                };
+                unsafe {
+                    WSARecvFrom(
+                        op.socket,
+                        &wsabuf,
+                        1,
+                        ptr::null_mut(),
+                        flags,
+                        from.as_mut_ptr() as *mut c_void,
+                        fromlen,
+                        &mut op.overlapped,
+                        ptr::null(),
+                    )
+                }
+            },
+            _ => unreachable!(),
+        };
+        self.issued(key, result == 0, "failed to receive data")
+    }
+
+    /// Accepts an incoming connection on the listening `socket` into `new_socket`.
+    pub fn accept(&mut self, socket: RawSocket, new_socket: Socket) -> Result<usize, Fail> {
+        self.associate(new_socket.as_raw_socket())?;
+        let resources: Resources = Resources::Accept {
+            socket: Some(new_socket),
+            addrs: [0; 2 * ACCEPT_ADDR_SIZE],
+        };
+        let (key, op): (usize, &mut IoOperation) = self.insert(socket, resources);
+        let mut nbytes: u32 = 0;
+        let result: i32 = match op.resources {
+            Resources::Accept {
+                socket: Some(ref new_socket),
+                ref mut addrs,
+            } => unsafe {
+                AcceptEx(
+                    op.socket,
+                    new_socket.as_raw_socket() as usize,
+                    addrs.as_mut_ptr() as *mut c_void,
+                    0,
+                    ACCEPT_ADDR_SIZE as u32,
+                    ACCEPT_ADDR_SIZE as u32,
+                    &mut nbytes,
+                    &mut op.overlapped,
+                )
+            },
+            _ => unreachable!(),
+        };
+        self.issued(key, result != 0, "failed to accept connection")
+    }
+
+    /// Establishes a connection on `socket` to `addr`. The socket must be bound.
+    pub fn connect(&mut self, socket: RawSocket, addr: SockAddr) -> Result<usize, Fail> {
+        let connect_ex: ConnectExFn = self.connect_ex(socket)?;
+        let (key, op): (usize, &mut IoOperation) = self.insert(socket, Resources::Connect(addr));
+        let result: i32 = match op.resources {
+            Resources::Connect(ref addr) => unsafe {
+                connect_ex(
+                    op.socket,
+                    addr.as_ptr() as *const c_void,
+                    addr.len(),
+                    ptr::null(),
+                    0,
+                    ptr::null_mut(),
+                    &mut op.overlapped,
+                )
+            },
+            _ => unreachable!(),
+        };
+        self.issued(key, result != 0, "failed to establish connection")
+    }
+
+    /// Dequeues completions without blocking. Returns the tasks that wait on operations that completed.
+    pub fn poll(&mut self) -> Vec<Waker> {
+        self.dequeue(0)
+    }
+
+    /// Dequeues completions, waiting for at most `timeout` if there is none. Returns the tasks that wait on operations
+    /// that completed.
+    pub fn wait(&mut self, timeout: Duration) -> Vec<Waker> {
+        // Round up, so that short waits do not turn into polls, and stay clear of the infinite timeout.
+        let timeout_ms: u32 = ((timeout.as_nanos() + 999_999) / 1_000_000).min(u32::MAX as u128 - 1) as u32;
+        self.dequeue(timeout_ms)
+    }
+
+    /// Dequeues completions, waiting for at most `timeout_ms` milliseconds for the first batch of them. Returns the
+    /// tasks that wait on operations that completed.
+    fn dequeue(&mut self, mut timeout_ms: u32) -> Vec<Waker> {
+        let mut wakers: Vec<Waker> = Vec::new();
+        let mut entries: [OverlappedEntry; MAX_COMPLETIONS] = unsafe { mem::zeroed() };
+        loop {
+            let mut nentries: u32 = 0;
+            let ok: i32 = unsafe {
+                GetQueuedCompletionStatusEx(
+                    self.handle,
+                    entries.as_mut_ptr(),
+                    MAX_COMPLETIONS as u32,
+                    &mut nentries,
+                    timeout_ms,
+                    0,
+                )
+            };
+            timeout_ms = 0;
+            // This fails with a timeout if there are no completions.
+            if ok == 0 {
+                return wakers;
+            }
+            for entry in &entries[..nentries as usize] {
+                let key: usize = entry.overlapped as usize;
+                let op: &mut IoOperation = match self.operations.get_mut(&key) {
+                    Some(op) => op,
+                    None => {
+                        warn!("completion of unknown operation");
+                        continue;
+                    },
+                };
+                let mut nbytes: u32 = 0;
+                let mut flags: u32 = 0;
+                let ok: i32 =
+                    unsafe { WSAGetOverlappedResult(op.socket, &mut op.overlapped, &mut nbytes, 0, &mut flags) };
+                if op.orphaned {
+                    self.operations.remove(&key);
+                    continue;
+                }
+                op.result = Some(if ok != 0 {
+                    Ok(nbytes)
+                } else {
+                    Err(unsafe { WSAGetLastError() })
+                });
+                if let Some(waker) = op.waker.take() {
+                    wakers.push(waker);
+                }
+            }
+            if (nentries as usize) < MAX_COMPLETIONS {
+                return wakers;
+            }
+        }
+    }
+
+    /// Inserts an operation on `socket` that holds `resources`. Returns the key of the operation, along with it.
+    fn insert(&mut self, socket: RawSocket, resources: Resources) -> (usize, &mut IoOperation) {
+        let op: Box<IoOperation> = Box::new(IoOperation {
+            overlapped: unsafe { mem::zeroed() },
+            socket: socket as usize,
+            resources,
+            result: None,
+            waker: None,
+            orphaned: false,
+        });
+        let key: usize = &op.overlapped as *const Overlapped as usize;
+        let op: &mut IoOperation = self.operations.entry(key).or_insert(op);
+        (key, op)
+    }
+
+    /// Checks the outcome of issuing the operation `key`. If this failed, the operation is removed, because no
+    /// completion is queued for it.
+    fn issued(&mut self, key: usize, ok: bool, cause: &str) -> Result<usize, Fail> {
+        if !ok {
+            let errno: i32 = unsafe { WSAGetLastError() };
+            if errno != WSA_IO_PENDING {
+                self.operations.remove(&key);
+                return Err(Fail::new(io::Error::from_raw_os_error(errno).kind() as i32, cause));
+            }
+        }
+        Ok(key)
+    }
+
+    /// Retrieves the `ConnectEx` extension function through `socket`.
+    fn connect_ex(&mut self, socket: RawSocket) -> Result<ConnectExFn, Fail> {
+        if let Some(connect_ex) = self.connect_ex {
+            return Ok(connect_ex);
+        }
+        let mut connect_ex: Option<ConnectExFn> = None;
+        let mut nbytes: u32 = 0;
+        let result: i32 = unsafe {
+            WSAIoctl(
+                socket as usize,
+                SIO_GET_EXTENSION_FUNCTION_POINTER,
+                &WSAID_CONNECTEX as *const Guid as *const c_void,
+                mem::size_of::<Guid>() as u32,
+                &mut connect_ex as *mut Option<ConnectExFn> as *mut c_void,
+                mem::size_of::<Option<ConnectExFn>>() as u32,
+                &mut nbytes,
+                ptr::null_mut(),
+                ptr::null(),
+            )
+        };
+        match connect_ex {
+            Some(connect_ex) if result == 0 => {
+                self.connect_ex = Some(connect_ex);
+                Ok(connect_ex)
+            },
+            _ => Err(last_error("failed to retrieve ConnectEx")),
+        }
+    }
+}
+
+/// Associate Functions for Overlapped Operations
+impl IoOperation {
+    /// Converts the target completed operation into its output.
+    fn into_output(self) -> Result<IoOutput, Fail> {
+        let listen: usize = self.socket;
+        let result: Result<u32, i32> = self.result.expect("operation should be completed");
+        match (self.resources, result) {
+            (Resources::Send(..), Ok(nbytes)) => Ok(IoOutput::Sent(nbytes as usize)),
+            (
+                Resources::Recv {
+                    mut buf, from, fromlen, ..
+                },
+                Ok(nbytes),
+            ) => {
+                let len: usize = buf.len();
+                buf.trim(len - nbytes as usize)?;
+                Ok(IoOutput::Received(parse_sockaddr(&from, fromlen as usize), buf, false))
+            },
+            // Datagrams that do not fit in the receive buffer are truncated.
+            (Resources::Recv { buf, from, fromlen, .. }, Err(WSAEMSGSIZE)) => {
+                Ok(IoOutput::Received(parse_sockaddr(&from, fromlen as usize), buf, true))
+            },
+            (Resources::Recv { .. }, Err(WSAECONNRESET)) => Ok(IoOutput::Reset),
+            (_, Err(errno)) => Err(Fail::new(
+                io::Error::from_raw_os_error(errno).kind() as i32,
+                "operation failed",
+            )),
+            (Resources::Accept { socket, .. }, Ok(_)) => {
+                let socket: Socket = socket.expect("accepted socket should be set");
+                if unsafe {
+                    setsockopt(
+                        socket.as_raw_socket() as usize,
+                        SOL_SOCKET,
+                        SO_UPDATE_ACCEPT_CONTEXT,
+                        &listen as *const usize as *const c_void,
+                        mem::size_of::<usize>() as i32,
+                    )
+                } != 0
+                {
+                    warn!("cannot set SO_UPDATE_ACCEPT_CONTEXT option");
+                }
+                Ok(IoOutput::Accepted(socket))
+            },
+            (Resources::Connect(_), Ok(_)) => {
+                if unsafe { setsockopt(listen, SOL_SOCKET, SO_UPDATE_CONNECT_CONTEXT, ptr::null(), 0) } != 0 {
+                    warn!("cannot set SO_UPDATE_CONNECT_CONTEXT option");
+                }
+                Ok(IoOutput::Connected)
+            },
+        }
+    }
+}
+
+/// Associate Functions for Handles of Overlapped Operations
+impl IoHandle {
+    /// Creates a handle for the operation `key` that is issued on `queue`.
+    pub fn new(queue: Rc<RefCell<dyn IoQueue>>, key: usize) -> Self {
+        Self {
+            queue,
+            key,
+            done: false,
+        }
+    }
+
+    /// Polls the target operation. If it completed, this takes out its output.
+    pub fn poll(&mut self, waker: &Waker) -> Poll<Result<IoOutput, Fail>> {
+        let result: Poll<Result<IoOutput, Fail>> = self.queue.borrow_mut().poll_operation(self.key, waker);
+        if result.is_ready() {
+            self.done = true;
+        }
+        result
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Builds a [Fail] out of the last Win32 error.
+fn last_error(cause: &str) -> Fail {
+    Fail::new(io::Error::last_os_error().kind() as i32, cause)
+}
+
+/// Parses the raw socket address `addr` of `len` bytes into a [SocketAddrV4], if it is an IPv4 address.
+pub fn parse_sockaddr(addr: &[u8], len: usize) -> Option<SocketAddrV4> {
+    // See `SOCKADDR_IN` in `ws2def.h`.
+    if len < 8 || addr.len() < 8 {
+        return None;
+    }
+    let family: u16 = u16::from_le_bytes([addr[0], addr[1]]);
+    if family != libc::AF_INET as u16 {
+        return None;
+    }
+    let port: u16 = u16::from_be_bytes([addr[2], addr[3]]);
+    Some(SocketAddrV4::new(
+        Ipv4Addr::new(addr[4], addr[5], addr[6], addr[7]),
+        port,
+    ))
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// I/O Queue Trait Implementation for I/O Completion Ports
+impl IoQueue for CompletionPort {
+    fn poll_operation(&mut self, key: usize, waker: &Waker) -> Poll<Result<IoOutput, Fail>> {
+        let op: &mut IoOperation = match self.operations.get_mut(&key) {
+            Some(op) => op,
+            None => return Poll::Ready(Err(Fail::new(libc::EINVAL, "unknown operation"))),
+        };
+        if op.result.is_none() {
+            op.waker = Some(waker.clone());
+            return Poll::Pending;
+        }
+        let op: Box<IoOperation> = self.operations.remove(&key).expect("operation should be in flight");
+        Poll::Ready(op.into_output())
+    }
+
+    fn cancel(&mut self, key: usize) {
+        if let Some(op) = self.operations.get_mut(&key) {
+            if op.result.is_some() {
+                self.operations.remove(&key);
+                return;
+            }
+            op.orphaned = true;
+            op.waker = None;
+            if unsafe { CancelIoEx(HANDLE(op.socket as isize), &mut op.overlapped) } == 0 {
+                trace!("cannot cancel operation ({:?})", unsafe { WSAGetLastError() });
+            }
+        }
+    }
+}
+
+/// Drop Trait Implementation for I/O Completion Ports
+impl Drop for CompletionPort {
+    fn drop(&mut self) {
+        // Operations that are still in flight may be written by the kernel, so we leak them.
+        for (_, op) in self.operations.drain() {
+            if op.result.is_none() {
+                Box::leak(op);
+            }
+        }
+        if !unsafe { CloseHandle(self.handle) }.as_bool() {
+            warn!("cannot close completion port");
+        }
+    }
+}
+
+/// Drop Trait Implementation for Handles of Overlapped Operations
+impl Drop for IoHandle {
+    fn drop(&mut self) {
+        if !self.done {
+            self.queue.borrow_mut().cancel(self.key);
+        }
+    }
+}
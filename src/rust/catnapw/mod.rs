@@ -1,7 +1,10 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod config;
 mod futures;
+mod iocp;
+mod rio;
 mod runtime;
 
 //==============================================================================
@@ -20,10 +23,15 @@ use self::futures::{
     pop::PopFuture,
     push::PushFuture,
     pushto::PushtoFuture,
+    pushv::PushvFuture,
+    splice::SpliceFuture,
     Operation,
 };
 use crate::{
-    demikernel::config::Config,
+    demikernel::{
+        config::Config,
+        libos::network::NetworkLibOSTrait,
+    },
     inetstack::operations::OperationResult,
     pal::{
         constants::{
@@ -39,25 +47,50 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        queue::IoQueueTable,
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketAddress,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::{
+            IoQueueLimitStats,
+            IoQueueTable,
+        },
         types::{
             demi_accept_result_t,
             demi_opcode_t,
+            demi_pktinfo_t,
             demi_qr_value_t,
             demi_qresult_t,
             demi_sgarray_t,
+            DEMI_QR_FLAG_EOF,
+            DEMI_QR_FLAG_PKTINFO,
+            DEMI_QR_FLAG_TRUNC,
         },
         QDesc,
         QToken,
         QType,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        Priority,
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
 };
 use ::libc::{
     c_int,
     sockaddr,
     EBADF,
     EINVAL,
+    ENOPROTOOPT,
     ENOTSUP,
 };
 use ::socket2::{
@@ -69,15 +102,29 @@ use ::socket2::{
 };
 use ::std::{
     any::Any,
-    cell::RefCell,
+    cell::{
+        Ref,
+        RefCell,
+    },
     collections::HashMap,
+    future::Future,
+    io,
     mem,
     net::{
         Ipv4Addr,
         Shutdown,
         SocketAddrV4,
     },
+    os::windows::io::{
+        AsRawSocket,
+        RawSocket,
+    },
+    pin::Pin,
     rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 use ::windows::Win32::{
     Foundation::CHAR,
@@ -98,6 +145,8 @@ pub struct CatnapWLibOS {
     qtable: IoQueueTable, // TODO: Move this to Demikernel module.
     /// Established sockets.
     sockets: HashMap<QDesc, Rc<RefCell<Socket>>>,
+    /// Number of truncated datagrams on each socket.
+    truncations: HashMap<RawSocket, u64>,
     /// Underlying runtime.
     runtime: PosixRuntime,
 }
@@ -109,13 +158,20 @@ pub struct CatnapWLibOS {
 /// Associate Functions for CatnapW LibOS
 impl CatnapWLibOS {
     /// Instantiates a CatnapW LibOS.
-    pub fn new(_config: &Config) -> Self {
-        let qtable: IoQueueTable = IoQueueTable::new();
+    pub fn new(config: &Config) -> Self {
+        let mut qtable: IoQueueTable = IoQueueTable::new();
+        qtable.set_limits(config.queue_limits());
         let sockets: HashMap<QDesc, Rc<RefCell<Socket>>> = HashMap::new();
-        let runtime: PosixRuntime = PosixRuntime::new();
+        let runtime: PosixRuntime = PosixRuntime::new(config.rio_buffers());
+        runtime.scheduler.set_budget(config.poll_budget());
+        if let Some(policy) = config.idle_policy() {
+            runtime.scheduler.set_idle_policy(policy);
+        }
+        runtime.scheduler.set_poll_timing(config.poll_timing());
         Self {
             qtable,
             sockets,
+            truncations: HashMap::new(),
             runtime,
         }
     }
@@ -131,28 +187,33 @@ impl CatnapWLibOS {
         };
 
         // Select protocol.
-        let (ty, protocol) = match typ {
-            SOCK_STREAM => (Type::STREAM, Protocol::TCP),
-            SOCK_DGRAM => (Type::DGRAM, Protocol::UDP),
+        let (ty, protocol, qtype): (Type, Protocol, QType) = match typ {
+            SOCK_STREAM => (Type::STREAM, Protocol::TCP, QType::TcpSocket),
+            SOCK_DGRAM => (Type::DGRAM, Protocol::UDP, QType::UdpSocket),
             _ => {
                 return Err(Fail::new(ENOTSUP, "socket type not supported"));
             },
         };
 
+        // Check if a queue descriptor may be allocated for the socket.
+        self.qtable.check_qd_limit()?;
+
         // Create socket.
-        match Socket::new(domain, ty, Some(protocol)) {
-            Ok(socket) => {
-                match socket.set_nonblocking(true) {
-                    Ok(_) => {},
-                    Err(_) => warn!("cannot set NONBLOCK option"),
-                }
-                let qtype: QType = QType::TcpSocket;
-                let qd: QDesc = self.qtable.alloc(qtype.into());
-                assert_eq!(self.sockets.insert(qd, Rc::new(RefCell::new(socket))).is_none(), true);
-                Ok(qd)
-            },
-            Err(err) => Err(Fail::new(err.kind() as i32, "failed to create socket")),
+        let socket: Socket = self.runtime.socket(domain, ty, protocol)?;
+        match socket.set_nonblocking(true) {
+            Ok(_) => {},
+            Err(_) => warn!("cannot set NONBLOCK option"),
+        }
+        // Operations on the socket are issued on the completion port of the runtime.
+        self.runtime.associate(socket.as_raw_socket())?;
+        // Try to set SO_REUSEADDR option, which is what Winsock offers in place of SO_REUSEPORT. If we fail, keep going
+        // because this is non-critical.
+        if socket.set_reuse_address(true).is_err() {
+            warn!("cannot set SO_REUSEADDR option");
         }
+        let qd: QDesc = self.qtable.alloc(qtype.into());
+        assert_eq!(self.sockets.insert(qd, Rc::new(RefCell::new(socket))).is_none(), true);
+        Ok(qd)
     }
 
     /// Binds a socket to a local endpoint.
@@ -193,16 +254,21 @@ impl CatnapWLibOS {
         // Issue accept operation.
         match self.sockets.get(&qd) {
             Some(socket) => {
+                self.qtable.check_qd_limit()?;
+                self.qtable.check_operation_limit()?;
                 let new_qd: QDesc = self.qtable.alloc(QType::TcpSocket.into());
-                let future: Operation = Operation::from(AcceptFuture::new(qd, socket.clone(), new_qd));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let future: Operation =
+                    Operation::from(AcceptFuture::new(self.runtime.clone(), qd, socket.clone(), new_qd));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => {
                         self.qtable.free(new_qd);
                         return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine"));
                     },
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], Some(new_qd));
+                Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
@@ -212,16 +278,38 @@ impl CatnapWLibOS {
     pub fn connect(&mut self, qd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
         trace!("connect() qd={:?}, remote={:?}", qd, remote);
 
-        // Issue connect operation.
+        self.do_connect(qd, remote, None)
+    }
+
+    /// Establishes a connection to a remote endpoint, failing with `ETIMEDOUT` if it is not established by `deadline`.
+    pub fn timedconnect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        trace!("timedconnect() qd={:?}, remote={:?}", qd, remote);
+
+        self.do_connect(qd, remote, Some(deadline))
+    }
+
+    /// Issues a connect operation.
+    fn do_connect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Option<Instant>) -> Result<QToken, Fail> {
         match self.sockets.get(&qd) {
             Some(socket) => {
+                self.qtable.check_operation_limit()?;
                 let addr: SockAddr = parse_addr(remote);
-                let future: Operation = Operation::from(ConnectFuture::new(qd, socket.clone(), addr));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let tcp: bool = self.qtable.get(qd) == Some(QType::TcpSocket.into());
+                let future: Operation = Operation::from(ConnectFuture::new(
+                    self.runtime.clone(),
+                    qd,
+                    socket.clone(),
+                    addr,
+                    deadline,
+                    tcp,
+                ));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
@@ -231,24 +319,230 @@ impl CatnapWLibOS {
     pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
         trace!("close() qd={:?}", qd);
         match self.sockets.get(&qd) {
-            Some(socket) => match socket.borrow().shutdown(Shutdown::Both) {
-                Ok(_) => Ok(()),
-                _ => Err(Fail::new(EBADF, "unable to close socket")),
+            Some(socket) => match self.qtable.release(qd) {
+                // Other queue descriptors still refer to the socket.
+                Some(false) => {
+                    self.sockets.remove(&qd);
+                    Ok(())
+                },
+                // This was the last queue descriptor that refers to the socket.
+                Some(true) => {
+                    self.truncations.remove(&socket.borrow().as_raw_socket());
+                    self.runtime.forget(socket.borrow().as_raw_socket());
+                    let result: Result<(), Fail> = match socket.borrow().shutdown(Shutdown::Both) {
+                        Ok(_) => Ok(()),
+                        _ => Err(Fail::new(EBADF, "unable to close socket")),
+                    };
+                    self.sockets.remove(&qd);
+                    result
+                },
+                None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Asynchronously closes a socket. This is not supported yet.
+    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
+        trace!("async_close() qd={:?}", qd);
+        Err(Fail::new(ENOTSUP, "asynchronous close is not supported"))
+    }
+
+    /// Duplicates a queue descriptor. The new queue descriptor refers to the same socket, which is closed only once all
+    /// queue descriptors that refer to it are closed.
+    pub fn dup(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        trace!("dup() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(socket) => {
+                self.qtable.check_qd_limit()?;
+                match self.qtable.dup(qd) {
+                    Some(new_qd) => {
+                        let socket: Rc<RefCell<Socket>> = socket.clone();
+                        self.sockets.insert(new_qd, socket);
+                        Ok(new_qd)
+                    },
+                    None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+                }
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Shuts down the receive side, send side, or both sides of a socket.
+    pub fn shutdown(&mut self, qd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        trace!("shutdown() qd={:?} how={:?}", qd, how);
+        match self.sockets.get(&qd) {
+            Some(socket) => match socket.borrow().shutdown(how) {
+                Ok(()) => Ok(()),
+                Err(e) => Err(Fail::new(e.kind() as i32, "unable to shutdown socket")),
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the local address of a socket.
+    pub fn local_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("local_addr() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(socket) => match socket.borrow().local_addr() {
+                Ok(addr) => match addr.as_socket_ipv4() {
+                    Some(addr) => Ok(addr),
+                    None => Err(Fail::new(ENOTSUP, "communication domain not supported")),
+                },
+                Err(e) => Err(Fail::new(e.kind() as i32, "unable to get local address")),
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
     }
 
+    /// Gets the remote address of a socket.
+    pub fn remote_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("remote_addr() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(socket) => match socket.borrow().peer_addr() {
+                Ok(addr) => match addr.as_socket_ipv4() {
+                    Some(addr) => Ok(addr),
+                    None => Err(Fail::new(ENOTSUP, "communication domain not supported")),
+                },
+                Err(e) => Err(Fail::new(e.kind() as i32, "unable to get remote address")),
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the current value of a socket option.
+    pub fn get_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        trace!("get_socket_option() qd={:?} opt={:?}", qd, opt);
+        let socket: &Rc<RefCell<Socket>> = match self.sockets.get(&qd) {
+            Some(socket) => socket,
+            _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+        let socket: Ref<Socket> = socket.borrow();
+        let result: io::Result<SocketOption> = match opt {
+            SocketOption::SendBufferSize(_) => socket.send_buffer_size().map(SocketOption::SendBufferSize),
+            SocketOption::ReceiveBufferSize(_) => socket.recv_buffer_size().map(SocketOption::ReceiveBufferSize),
+            SocketOption::NoDelay(_) => socket.nodelay().map(SocketOption::NoDelay),
+            // Winsock has no equivalent of corking.
+            SocketOption::Cork(_) => return Err(Fail::new(ENOPROTOOPT, "corking is not supported")),
+            SocketOption::KeepAlive(_) => socket.keepalive().map(SocketOption::KeepAlive),
+            SocketOption::Ttl(_) => socket.ttl().map(|ttl| SocketOption::Ttl(ttl as u8)),
+            SocketOption::Broadcast(_) => socket.broadcast().map(SocketOption::Broadcast),
+            // Pops do not collect ancillary data, so packet information is never reported.
+            SocketOption::PacketInfo(_) => return Err(Fail::new(ENOPROTOOPT, "packet information is not supported")),
+            // The kernel runs its own congestion control algorithms, which we do not expose.
+            SocketOption::CongestionControl(_) => {
+                return Err(Fail::new(ENOPROTOOPT, "congestion control is not configurable"))
+            },
+            SocketOption::MaxPacingRate(_) => return Err(Fail::new(ENOPROTOOPT, "pacing is not configurable")),
+            SocketOption::AckDelay(_) | SocketOption::AckSegments(_) | SocketOption::QuickAck(_) => {
+                return Err(Fail::new(ENOPROTOOPT, "delayed ACKs are not configurable"))
+            },
+            SocketOption::SendBufferBounds(..) | SocketOption::ReceiveBufferBounds(..) => {
+                return Err(Fail::new(ENOPROTOOPT, "buffer autotuning is not configurable"))
+            },
+        };
+        match result {
+            Ok(opt) => Ok(opt),
+            Err(e) => Err(Fail::new(e.kind() as i32, "unable to get socket option")),
+        }
+    }
+
+    /// Sets a socket option.
+    pub fn set_socket_option(&mut self, qd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        trace!("set_socket_option() qd={:?} opt={:?}", qd, opt);
+        let socket: &Rc<RefCell<Socket>> = match self.sockets.get(&qd) {
+            Some(socket) => socket,
+            _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+        let socket: Ref<Socket> = socket.borrow();
+        let result: io::Result<()> = match opt {
+            SocketOption::SendBufferSize(size) => socket.set_send_buffer_size(size),
+            SocketOption::ReceiveBufferSize(size) => socket.set_recv_buffer_size(size),
+            SocketOption::NoDelay(nodelay) => socket.set_nodelay(nodelay),
+            SocketOption::Cork(_) => return Err(Fail::new(ENOPROTOOPT, "corking is not supported")),
+            SocketOption::KeepAlive(keepalive) => socket.set_keepalive(keepalive),
+            SocketOption::Ttl(ttl) => socket.set_ttl(ttl as u32),
+            SocketOption::Broadcast(broadcast) => socket.set_broadcast(broadcast),
+            SocketOption::PacketInfo(_) => return Err(Fail::new(ENOPROTOOPT, "packet information is not supported")),
+            SocketOption::CongestionControl(_) => {
+                return Err(Fail::new(ENOPROTOOPT, "congestion control is not configurable"))
+            },
+            SocketOption::MaxPacingRate(_) => return Err(Fail::new(ENOPROTOOPT, "pacing is not configurable")),
+            SocketOption::AckDelay(_) | SocketOption::AckSegments(_) | SocketOption::QuickAck(_) => {
+                return Err(Fail::new(ENOPROTOOPT, "delayed ACKs are not configurable"))
+            },
+            SocketOption::SendBufferBounds(..) | SocketOption::ReceiveBufferBounds(..) => {
+                return Err(Fail::new(ENOPROTOOPT, "buffer autotuning is not configurable"))
+            },
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Fail::new(e.kind() as i32, "unable to set socket option")),
+        }
+    }
+
+    /// Gets the number of datagrams that were truncated when popped from a UDP socket.
+    pub fn truncations(&self, qd: QDesc) -> Result<u64, Fail> {
+        trace!("truncations() qd={:?}", qd);
+        match self.sockets.get(&qd) {
+            Some(socket) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => Ok(self
+                .truncations
+                .get(&socket.borrow().as_raw_socket())
+                .copied()
+                .unwrap_or(0)),
+            Some(_) => Err(Fail::new(EINVAL, "invalid queue type")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Joins a UDP socket to a multicast group on the interface that has the address `iface`.
+    pub fn join_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!("join_multicast_group() qd={:?} group={:?} iface={:?}", qd, group, iface);
+        match self.sockets.get(&qd) {
+            Some(socket) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => {
+                match socket.borrow().join_multicast_v4(&group, &iface) {
+                    Ok(()) => Ok(()),
+                    Err(e) => Err(Fail::new(e.kind() as i32, "unable to join multicast group")),
+                }
+            },
+            Some(_) => Err(Fail::new(EINVAL, "invalid queue type")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Removes a UDP socket from a multicast group on the interface that has the address `iface`.
+    pub fn leave_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!(
+            "leave_multicast_group() qd={:?} group={:?} iface={:?}",
+            qd,
+            group,
+            iface
+        );
+        match self.sockets.get(&qd) {
+            Some(socket) if self.qtable.get(qd) == Some(QType::UdpSocket.into()) => {
+                match socket.borrow().leave_multicast_v4(&group, &iface) {
+                    Ok(()) => Ok(()),
+                    Err(e) => Err(Fail::new(e.kind() as i32, "unable to leave multicast group")),
+                }
+            },
+            Some(_) => Err(Fail::new(EINVAL, "invalid queue type")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
     // Handles a push operation.
     fn do_push(&mut self, qd: QDesc, buf: DemiBuffer) -> Result<QToken, Fail> {
         match self.sockets.get(&qd) {
             Some(socket) => {
-                let future: Operation = Operation::from(PushFuture::new(qd, socket.clone(), buf));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                self.qtable.check_operation_limit()?;
+                let future: Operation = Operation::from(PushFuture::new(self.runtime.clone(), qd, socket.clone(), buf));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
@@ -275,13 +569,41 @@ impl CatnapWLibOS {
     fn do_pushto(&mut self, qd: QDesc, buf: DemiBuffer, remote: SocketAddrV4) -> Result<QToken, Fail> {
         match self.sockets.get(&qd) {
             Some(socket) => {
+                self.qtable.check_operation_limit()?;
                 let addr: SockAddr = parse_addr(remote);
-                let future: Operation = Operation::from(PushtoFuture::new(qd, socket.clone(), addr, buf));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                let future: Operation =
+                    Operation::from(PushtoFuture::new(self.runtime.clone(), qd, socket.clone(), addr, buf));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
-                Ok(handle.into_raw().into())
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
+            },
+            _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Pushes multiple scatter-gather arrays to a socket as a single unit.
+    pub fn pushv(&mut self, qd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        trace!("pushv() qd={:?}, nsgas={:?}", qd, sgas.len());
+
+        let bufs: Vec<DemiBuffer> = self.runtime.clone_sgarrays(sgas)?;
+
+        // Issue vectored push operation.
+        match self.sockets.get(&qd) {
+            Some(socket) => {
+                self.qtable.check_operation_limit()?;
+                let future: Operation =
+                    Operation::from(PushvFuture::new(self.runtime.clone(), qd, socket.clone(), bufs));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+                    Some(handle) => handle,
+                    None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+                };
+                let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
+                Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
@@ -308,33 +630,129 @@ impl CatnapWLibOS {
     pub fn pop(&mut self, qd: QDesc) -> Result<QToken, Fail> {
         trace!("pop() qd={:?}", qd);
 
+        self.do_pop(qd, None, None)
+    }
+
+    /// Pops at most `size` bytes of data from a socket.
+    pub fn pop_sized(&mut self, qd: QDesc, size: usize) -> Result<QToken, Fail> {
+        trace!("pop_sized() qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if size == 0 || size > u16::MAX as usize {
+            return Err(Fail::new(EINVAL, "invalid pop size"));
+        }
+
+        self.do_pop(qd, Some(size), None)
+    }
+
+    /// Pops data from a socket, failing with `ETIMEDOUT` if no data arrives by `deadline`. If `size` is set, this
+    /// behaves as [CatnapWLibOS::pop_sized], otherwise it behaves as [CatnapWLibOS::pop].
+    pub fn timedpop(&mut self, qd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        trace!("timedpop() qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if let Some(size) = size {
+            if size == 0 || size > u16::MAX as usize {
+                return Err(Fail::new(EINVAL, "invalid pop size"));
+            }
+        }
+
+        self.do_pop(qd, size, Some(deadline))
+    }
+
+    /// Issues a pop operation.
+    fn do_pop(&mut self, qd: QDesc, size: Option<usize>, deadline: Option<Instant>) -> Result<QToken, Fail> {
         // Issue pop operation.
         match self.sockets.get(&qd) {
             Some(socket) => {
-                let future: Operation = Operation::from(PopFuture::new(qd, socket.clone()));
-                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future) {
+                self.qtable.check_operation_limit()?;
+                let udp: bool = self.qtable.get(qd) == Some(QType::UdpSocket.into());
+                let future: Operation = Operation::from(PopFuture::new(
+                    self.runtime.clone(),
+                    qd,
+                    socket.clone(),
+                    size,
+                    deadline,
+                    udp,
+                ));
+                let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
                     Some(handle) => handle,
                     None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
                 };
                 let qt: QToken = handle.into_raw().into();
+                self.qtable.track(qt, &[qd], None);
                 Ok(qt)
             },
             _ => Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
     }
 
+    /// Forwards data from one socket to another until the remote peer of the source socket closes the connection.
+    pub fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        trace!("splice() srcqd={:?}, dstqd={:?}", srcqd, dstqd);
+
+        let (src, dst): (Rc<RefCell<Socket>>, Rc<RefCell<Socket>>) =
+            match (self.sockets.get(&srcqd), self.sockets.get(&dstqd)) {
+                (Some(src), Some(dst)) => (src.clone(), dst.clone()),
+                _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+            };
+
+        // Check if queue types are valid.
+        let tcp: Option<u32> = Some(QType::TcpSocket.into());
+        if self.qtable.get(srcqd) != tcp || self.qtable.get(dstqd) != tcp {
+            return Err(Fail::new(EINVAL, "invalid queue type"));
+        }
+        if Rc::ptr_eq(&src, &dst) {
+            return Err(Fail::new(EINVAL, "cannot splice a queue into itself"));
+        }
+
+        // Issue splice operation.
+        self.qtable.check_operation_limit()?;
+        let future: Operation = Operation::from(SpliceFuture::new(srcqd, src, dst));
+        let handle: SchedulerHandle = match self.runtime.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        self.qtable.track(qt, &[srcqd, dstqd], None);
+        Ok(qt)
+    }
+
     /// Allocates a scatter-gather array.
     pub fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
         trace!("sgalloc() size={:?}", size);
         self.runtime.alloc_sgarray(size)
     }
 
+    /// Registers application-owned memory as a scatter-gather array.
+    pub fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        trace!("sgaregister() len={:?}", len);
+        self.runtime.register_sgarray(data, len)
+    }
+
     /// Frees a scatter-gather array.
     pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
         trace!("sgafree()");
         self.runtime.free_sgarray(sga)
     }
 
+    /// Tears down the target LibOS. All pending operations are canceled and all sockets are closed.
+    pub fn teardown(mut self) -> Result<(), Fail> {
+        trace!("teardown()");
+
+        // Cancel pending operations first, because they hold references to sockets.
+        let ncanceled: usize = self.runtime.scheduler.cancel_all();
+        trace!("teardown(): canceled {:?} operations", ncanceled);
+
+        // Close all sockets. These are closed once their last reference is dropped.
+        for (qd, socket) in self.sockets.drain() {
+            self.runtime.forget(socket.borrow().as_raw_socket());
+            self.qtable.release(qd);
+        }
+
+        Ok(())
+    }
+
     /// Takes out the [OperationResult] associated with the target [SchedulerHandle].
     fn take_result(&mut self, handle: SchedulerHandle) -> (QDesc, OperationResult) {
         let boxed_future: Box<dyn Any> = self.runtime.scheduler.take(handle).as_any();
@@ -354,10 +772,31 @@ impl CatnapWLibOS {
             }
         }
 
+        // A zero-length read on a stream socket means that the remote peer has closed the connection.
+        let qr: OperationResult = match qr {
+            OperationResult::Pop(addr, buf, flags, info)
+                if buf.is_empty() && self.qtable.get(qd) == Some(QType::TcpSocket.into()) =>
+            {
+                OperationResult::Pop(addr, buf, flags | DEMI_QR_FLAG_EOF, info)
+            },
+            qr => qr,
+        };
+
+        // Account datagrams that did not fit in the receive buffer.
+        if let OperationResult::Pop(_, _, flags, _) = qr {
+            if flags & DEMI_QR_FLAG_TRUNC != 0 {
+                if let Some(socket) = self.sockets.get(&qd) {
+                    *self.truncations.entry(socket.borrow().as_raw_socket()).or_insert(0) += 1;
+                }
+            }
+        }
+
         (qd, qr)
     }
 
     pub fn poll(&self) {
+        self.runtime.poll_completions();
+        self.runtime.scheduler.advance_clock(Instant::now());
         self.runtime.scheduler.poll()
     }
 
@@ -368,12 +807,320 @@ impl CatnapWLibOS {
         }
     }
 
+    /// Takes out the earliest completed operation that satisfies `pred`.
+    pub fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        self.runtime
+            .scheduler
+            .take_completed(|key| pred(QToken::from(key)))
+            .map(QToken::from)
+    }
+
     pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
         let (qd, r): (QDesc, OperationResult) = self.take_result(handle);
+        self.qtable.untrack(qt);
         Ok(pack_result(&self.runtime, r, qd, qt.into()))
     }
 }
 
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Network LibOS Trait Implementation for CatnapW LibOS
+impl NetworkLibOSTrait for CatnapWLibOS {
+    fn socket(&mut self, domain: libc::c_int, socket_type: libc::c_int, protocol: libc::c_int) -> Result<QDesc, Fail> {
+        CatnapWLibOS::socket(self, domain, socket_type, protocol)
+    }
+
+    fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
+        CatnapWLibOS::bind(self, sockqd, local)
+    }
+
+    fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+        CatnapWLibOS::listen(self, sockqd, backlog)
+    }
+
+    fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatnapWLibOS::accept(self, sockqd)
+    }
+
+    fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
+        CatnapWLibOS::connect(self, sockqd, remote)
+    }
+
+    fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        CatnapWLibOS::timedconnect(self, sockqd, remote, deadline)
+    }
+
+    fn close(&mut self, sockqd: QDesc) -> Result<(), Fail> {
+        CatnapWLibOS::close(self, sockqd)
+    }
+
+    fn async_close(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatnapWLibOS::async_close(self, sockqd)
+    }
+
+    fn dup(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        CatnapWLibOS::dup(self, sockqd)
+    }
+
+    fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        CatnapWLibOS::shutdown(self, sockqd, how)
+    }
+
+    fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        CatnapWLibOS::local_addr(self, sockqd)
+    }
+
+    fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        CatnapWLibOS::remote_addr(self, sockqd)
+    }
+
+    fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        CatnapWLibOS::get_socket_option(self, sockqd, opt)
+    }
+
+    fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        CatnapWLibOS::set_socket_option(self, sockqd, opt)
+    }
+
+    fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        CatnapWLibOS::truncations(self, sockqd)
+    }
+
+    fn backlog_overflows(&self, _sockqd: QDesc) -> Result<u64, Fail> {
+        // The kernel only keeps track of backlog overflows system-wide.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "backlog_overflows() is not supported on kernel sockets",
+        ))
+    }
+
+    fn tcp_info(&self, _sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        // The kernel keeps this state, and only reports it through its own socket options.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "tcp_info() is not supported on kernel sockets",
+        ))
+    }
+
+    fn timestamps(&self, _sockqd: QDesc) -> Result<Timestamps, Fail> {
+        // The kernel only reports hardware timestamps through its own socket options.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "timestamps() is not supported on kernel sockets",
+        ))
+    }
+
+    fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        CatnapWLibOS::join_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        CatnapWLibOS::leave_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        CatnapWLibOS::push(self, sockqd, sga)
+    }
+
+    fn pushv(&mut self, sockqd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        CatnapWLibOS::pushv(self, sockqd, sgas)
+    }
+
+    fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
+        CatnapWLibOS::pushto(self, sockqd, sga, to)
+    }
+
+    fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        CatnapWLibOS::pop(self, sockqd)
+    }
+
+    fn pop_sized(&mut self, sockqd: QDesc, size: usize) -> Result<QToken, Fail> {
+        CatnapWLibOS::pop_sized(self, sockqd, size)
+    }
+
+    fn timedpop(&mut self, sockqd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        CatnapWLibOS::timedpop(self, sockqd, size, deadline)
+    }
+
+    fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        CatnapWLibOS::splice(self, srcqd, dstqd)
+    }
+
+    fn ping(&mut self, _addr: Ipv4Addr, _timeout: Option<Duration>) -> Result<QToken, Fail> {
+        // Sending ICMP messages through the kernel requires raw sockets, and thus privileges that we do not assume.
+        Err(Fail::new(libc::ENOTSUP, "ping() is not supported on kernel sockets"))
+    }
+
+    fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        // Windows keeps its own neighbor table, which it only exposes through the IP Helper API.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_entries() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_insert(&mut self, _ipv4_addr: Ipv4Addr, _link_addr: MacAddress) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_insert() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_pending_drops() is not supported on kernel sockets",
+        ))
+    }
+
+    fn arp_flush(&mut self) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "arp_flush() is not supported on kernel sockets",
+        ))
+    }
+
+    fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "ipv4_addrs() is not supported on kernel sockets",
+        ))
+    }
+
+    fn add_ipv4_addr(&mut self, _ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_ipv4_addr() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_ipv4_addr(&mut self, _ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_ipv4_addr() is not supported on kernel sockets",
+        ))
+    }
+
+    fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "dhcp_lease() is not supported on kernel sockets",
+        ))
+    }
+
+    fn link_events(&mut self) -> Result<QDesc, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "link_events() is not supported on kernel sockets",
+        ))
+    }
+
+    fn link_state(&self) -> Result<LinkState, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "link_state() is not supported on kernel sockets",
+        ))
+    }
+
+    fn routes(&self) -> Result<Vec<Route>, Fail> {
+        Err(Fail::new(libc::ENOTSUP, "routes() is not supported on kernel sockets"))
+    }
+
+    fn add_route(&mut self, _route: Route) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_route() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_route(&mut self, _destination: Ipv4Addr, _prefix_len: u8) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_route() is not supported on kernel sockets",
+        ))
+    }
+
+    fn set_arp_policy(
+        &mut self,
+        _cache_ttl: Duration,
+        _request_timeout: Duration,
+        _retry_count: usize,
+    ) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "set_arp_policy() is not supported on kernel sockets",
+        ))
+    }
+
+    fn add_flow_rule(&mut self, _rule: FlowRule) -> Result<u32, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "add_flow_rule() is not supported on kernel sockets",
+        ))
+    }
+
+    fn remove_flow_rule(&mut self, _id: u32) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "remove_flow_rule() is not supported on kernel sockets",
+        ))
+    }
+
+    fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        self.runtime.scheduler.spawn(future)
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        self.runtime.scheduler.sleep_until(deadline)
+    }
+
+    fn poll(&mut self) {
+        CatnapWLibOS::poll(self)
+    }
+
+    fn park(&mut self, timeout: Option<Duration>) {
+        self.runtime.park(timeout)
+    }
+
+    fn tasks(&self) -> Vec<TaskInfo> {
+        self.runtime.scheduler.tasks()
+    }
+
+    fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.qtable.limit_stats()
+    }
+
+    fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
+        CatnapWLibOS::schedule(self, qt)
+    }
+
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        CatnapWLibOS::take_completed(self, pred)
+    }
+
+    fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
+        CatnapWLibOS::pack_result(self, handle, qt)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        CatnapWLibOS::sgaalloc(self, size)
+    }
+
+    fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        CatnapWLibOS::sgaregister(self, data, len)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        CatnapWLibOS::sgafree(self, sga)
+    }
+
+    fn teardown(self: Box<Self>) -> Result<(), Fail> {
+        CatnapWLibOS::teardown(*self)
+    }
+}
+
 //==============================================================================
 // Standalone Functions
 //==============================================================================
@@ -393,6 +1140,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
             qr_opcode: demi_opcode_t::DEMI_OPC_CONNECT,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
         OperationResult::Accept(new_qd) => {
@@ -407,6 +1156,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                 qr_opcode: demi_opcode_t::DEMI_OPC_ACCEPT,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: 0,
                 qr_value,
             }
         },
@@ -414,11 +1165,49 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
             qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
-        OperationResult::Pop(addr, bytes) => match rt.into_sgarray(bytes) {
+        OperationResult::Splice(_) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_SPLICE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Ping(rtt) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_PING,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                rtt: rtt.as_nanos() as i64,
+            },
+        },
+        OperationResult::Link(state) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_LINK,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                link_up: state.is_up() as u32,
+            },
+        },
+        OperationResult::Close => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_CLOSE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Pop(addr, bytes, mut flags, info) => match rt.into_sgarray(bytes) {
             Ok(mut sga) => {
-                if let Some(endpoint) = addr {
+                if let Some(SocketAddress::Inet(endpoint)) = addr {
                     let saddr: SockAddrIn = {
                         // TODO: check the following byte order conversion.
                         SockAddrIn {
@@ -434,11 +1223,17 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                     };
                     sga.sga_addr = unsafe { mem::transmute::<SockAddrIn, SOCKADDR>(saddr) };
                 }
+                if let Some(info) = info {
+                    sga.sga_pktinfo = demi_pktinfo_t::from(info);
+                    flags |= DEMI_QR_FLAG_PKTINFO;
+                }
                 let qr_value: demi_qr_value_t = demi_qr_value_t { sga };
                 demi_qresult_t {
                     qr_opcode: demi_opcode_t::DEMI_OPC_POP,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: flags,
+                    qr_ret: 0,
                     qr_value,
                 }
             },
@@ -448,6 +1243,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                     qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: e.errno as i64,
                     qr_value: unsafe { mem::zeroed() },
                 }
             },
@@ -458,6 +1255,8 @@ fn pack_result(rt: &PosixRuntime, result: OperationResult, qd: QDesc, qt: u64) -
                 qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: e.errno as i64,
                 qr_value: unsafe { mem::zeroed() },
             }
         },
@@ -0,0 +1,1111 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::iocp::{
+    parse_sockaddr,
+    Guid,
+    IoOutput,
+    IoQueue,
+};
+use crate::{
+    pal::arch,
+    runtime::{
+        fail::Fail,
+        memory::{
+            DemiBuffer,
+            ForeignInfo,
+        },
+    },
+};
+use ::socket2::{
+    SockAddr,
+    Socket,
+};
+use ::std::{
+    alloc::{
+        self,
+        Layout,
+    },
+    cell::RefCell,
+    collections::HashMap,
+    ffi::c_void,
+    io,
+    mem,
+    net::SocketAddrV4,
+    os::windows::io::{
+        AsRawSocket,
+        FromRawSocket,
+        RawSocket,
+    },
+    ptr::{
+        self,
+        NonNull,
+    },
+    rc::{
+        Rc,
+        Weak,
+    },
+    slice,
+    task::{
+        Poll,
+        Waker,
+    },
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Maximum number of completions that are dequeued at once.
+const MAX_COMPLETIONS: usize = 64;
+
+/// Number of sends, and of receives, that may initially be outstanding on each socket. Request queues grow as needed.
+const INITIAL_OUTSTANDING: u32 = 64;
+
+/// Initial size of the completion queue.
+const INITIAL_CQ_SIZE: u32 = 8 * 2 * INITIAL_OUTSTANDING;
+
+/// Size of the areas that hold the socket address of a request (see `SOCKADDR_INET` in `ws2ipdef.h`).
+const ADDR_SIZE: usize = 32;
+
+/// Size of the data of each pre-registered buffer. Larger sends on stream sockets are split, and larger datagrams are
+/// refused, as they would be truncated on receive anyway.
+pub const BUFFER_SIZE: usize = 9216;
+
+/// Size of each pre-registered buffer, which reserves room in front of its data for the metadata of the [DemiBuffer]
+/// that it is lent out as.
+const SLOT_SIZE: usize = DemiBuffer::FOREIGN_HEADROOM + BUFFER_SIZE;
+
+/// Error that tells that a datagram did not fit in the receive buffer (see `WSAEMSGSIZE` in `winsock2.h`).
+const WSAEMSGSIZE: i32 = 10040;
+
+/// Error that tells that the connection was reset, which is also reported on UDP sockets once an ICMP port unreachable
+/// message arrives (see `WSAECONNRESET` in `winsock2.h`).
+const WSAECONNRESET: i32 = 10054;
+
+/// Type of datagram sockets, and the socket option that retrieves the type of a socket (see `SOCK_DGRAM` and `SO_TYPE`
+/// in `winsock2.h`).
+const SOCK_DGRAM: i32 = 2;
+const SOL_SOCKET: i32 = 0xffff;
+const SO_TYPE: i32 = 0x1008;
+
+/// Flags of sockets that are created for Registered I/O (see `WSA_FLAG_OVERLAPPED` and the following ones in
+/// `winsock2.h`).
+const WSA_FLAG_OVERLAPPED: u32 = 0x01;
+const WSA_FLAG_NO_HANDLE_INHERIT: u32 = 0x80;
+const WSA_FLAG_REGISTERED_IO: u32 = 0x100;
+
+/// Invalid socket (see `INVALID_SOCKET` in `winsock2.h`).
+const INVALID_SOCKET: usize = usize::MAX;
+
+/// Control code that retrieves multiple extension functions of Winsock (see
+/// `SIO_GET_MULTIPLE_EXTENSION_FUNCTION_POINTER` in `ws2def.h`).
+const SIO_GET_MULTIPLE_EXTENSION_FUNCTION_POINTER: u32 = 0xc8000024;
+
+/// Identifier of the extension functions of Registered I/O (see `WSAID_MULTIPLE_RIO` in `mswsock.h`).
+const WSAID_MULTIPLE_RIO: Guid = Guid {
+    data1: 0x8509e081,
+    data2: 0x96dd,
+    data3: 0x4005,
+    data4: [0xb1, 0x65, 0x9e, 0x2e, 0xe8, 0xc7, 0x9e, 0x3f],
+};
+
+/// Invalid buffer identifier, which tells that a buffer could not be registered (see `RIO_INVALID_BUFFERID` in
+/// `mswsockdef.h`).
+const RIO_INVALID_BUFFERID: usize = 0xffffffff;
+
+/// Result that tells that the completion queue is corrupted (see `RIO_CORRUPT_CQ` in `mswsockdef.h`).
+const RIO_CORRUPT_CQ: u32 = 0xffffffff;
+
+/// Identifier of a registered buffer, completion queue and request queue (see `RIO_BUFFERID`, `RIO_CQ` and `RIO_RQ` in
+/// `mswsockdef.h`).
+type RioBufferId = *mut c_void;
+type RioCq = *mut c_void;
+type RioRq = *mut c_void;
+
+/// Slice of a registered buffer (see `RIO_BUF` in `mswsockdef.h`).
+#[repr(C)]
+#[allow(dead_code)]
+struct RioBuf {
+    buffer_id: RioBufferId,
+    offset: u32,
+    length: u32,
+}
+
+/// Completion of a request (see `RIORESULT` in `mswsockdef.h`).
+#[repr(C)]
+#[allow(dead_code)]
+struct RioResult {
+    status: i32,
+    nbytes: u32,
+    socket_context: u64,
+    request_context: u64,
+}
+
+/// Signatures of the extension functions of Registered I/O (see `RIO_EXTENSION_FUNCTION_TABLE` in `mswsock.h`).
+type RioTransferExFn = unsafe extern "system" fn(
+    rq: RioRq,
+    data: *const RioBuf,
+    count: u32,
+    local: *const RioBuf,
+    remote: *const RioBuf,
+    control: *const RioBuf,
+    flags_buf: *const RioBuf,
+    flags: u32,
+    context: *mut c_void,
+) -> i32;
+type RioTransferFn =
+    unsafe extern "system" fn(rq: RioRq, data: *const RioBuf, count: u32, flags: u32, context: *mut c_void) -> i32;
+type RioCloseCqFn = unsafe extern "system" fn(cq: RioCq);
+type RioCreateCqFn = unsafe extern "system" fn(size: u32, notification: *const c_void) -> RioCq;
+type RioCreateRqFn = unsafe extern "system" fn(
+    socket: usize,
+    max_recvs: u32,
+    max_recv_bufs: u32,
+    max_sends: u32,
+    max_send_bufs: u32,
+    recv_cq: RioCq,
+    send_cq: RioCq,
+    context: *mut c_void,
+) -> RioRq;
+type RioDequeueFn = unsafe extern "system" fn(cq: RioCq, results: *mut RioResult, count: u32) -> u32;
+type RioDeregisterFn = unsafe extern "system" fn(id: RioBufferId);
+type RioNotifyFn = unsafe extern "system" fn(cq: RioCq) -> i32;
+type RioRegisterFn = unsafe extern "system" fn(data: *const u8, len: u32) -> RioBufferId;
+type RioResizeCqFn = unsafe extern "system" fn(cq: RioCq, size: u32) -> i32;
+type RioResizeRqFn = unsafe extern "system" fn(rq: RioRq, max_recvs: u32, max_sends: u32) -> i32;
+
+/// Table of the extension functions of Registered I/O (see `RIO_EXTENSION_FUNCTION_TABLE` in `mswsock.h`).
+#[repr(C)]
+#[allow(dead_code)]
+struct RioFunctions {
+    size: u32,
+    receive: Option<RioTransferFn>,
+    receive_ex: Option<RioTransferExFn>,
+    send: Option<RioTransferFn>,
+    send_ex: Option<RioTransferExFn>,
+    close_cq: Option<RioCloseCqFn>,
+    create_cq: Option<RioCreateCqFn>,
+    create_rq: Option<RioCreateRqFn>,
+    dequeue: Option<RioDequeueFn>,
+    deregister: Option<RioDeregisterFn>,
+    notify: Option<RioNotifyFn>,
+    register: Option<RioRegisterFn>,
+    resize_cq: Option<RioResizeCqFn>,
+    resize_rq: Option<RioResizeRqFn>,
+}
+
+/// Extension functions of Registered I/O that we use.
+struct Rio {
+    receive_ex: RioTransferExFn,
+    send_ex: RioTransferExFn,
+    close_cq: RioCloseCqFn,
+    create_cq: RioCreateCqFn,
+    create_rq: RioCreateRqFn,
+    dequeue: RioDequeueFn,
+    deregister: RioDeregisterFn,
+    register: RioRegisterFn,
+    resize_cq: RioResizeCqFn,
+    resize_rq: RioResizeRqFn,
+}
+
+/// Slab of pre-registered memory, which holds buffers followed by as many areas for socket addresses.
+struct Slab {
+    memory: NonNull<u8>,
+    id: RioBufferId,
+}
+
+/// Area of pre-registered memory that holds the socket address of a request.
+#[derive(Clone, Copy)]
+struct AddrArea {
+    memory: NonNull<u8>,
+    id: RioBufferId,
+    offset: u32,
+}
+
+/// Pre-registered buffers
+///
+/// Buffers are lent out as [DemiBuffer]s that refer to pre-registered memory, which requests then go through without
+/// being copied. Buffers are handed back once these are dropped. The pool grows by slabs, each of which is registered
+/// once, and it is released once neither [RegisteredIo] nor any buffer that it lent out is used anymore.
+struct Pool {
+    /// Owner of the memory of the buffers. Each buffer holds a reference to the pool, which it drops once released.
+    info: ForeignInfo,
+    /// Number of buffers in each slab.
+    nbuffers: usize,
+    /// Deregisters slabs.
+    deregister: RioDeregisterFn,
+    /// Slabs of the pool.
+    slabs: RefCell<Vec<Slab>>,
+    /// Buffers that are free.
+    free: RefCell<Vec<NonNull<u8>>>,
+}
+
+/// Request that is issued on a request queue.
+struct Request {
+    /// Request queue that the request is issued on, along with its socket.
+    rq: RioRq,
+    socket: RawSocket,
+    /// Buffer that the data of the request goes through.
+    buf: DemiBuffer,
+    /// Area that holds the socket address of the request, if it has one.
+    addr: Option<AddrArea>,
+    /// Whether the request is a receive.
+    recv: bool,
+    /// Whether the request is issued on a datagram socket.
+    datagram: bool,
+    /// Status of the request and the number of bytes that it transferred, once it completes.
+    result: Option<(i32, u32)>,
+    /// Task that waits for the request to complete.
+    waker: Option<Waker>,
+    /// Whether the task that issued the request no longer waits for it.
+    orphaned: bool,
+}
+
+/// Request queue of a socket.
+struct RequestQueue {
+    /// Underlying request queue.
+    rq: RioRq,
+    /// Whether the socket is a datagram socket.
+    datagram: bool,
+    /// Number of sends, and of receives, that may be outstanding.
+    capacity: u32,
+    /// Number of sends that are outstanding.
+    sends: u32,
+    /// Number of receives that are outstanding.
+    recvs: u32,
+}
+
+/// Registered I/O, where sends and receives go through pre-registered buffers and complete on a completion queue that
+/// is polled, without system calls on their fast path. Received data is handed out in the buffers that it was received
+/// in, and buffers of the pool are sent in place. Any other data is copied into a buffer of the pool.
+pub struct RegisteredIo {
+    /// Extension functions.
+    rio: Rio,
+    /// Completion queue of all requests.
+    cq: RioCq,
+    /// Size of the completion queue.
+    cq_size: u32,
+    /// Number of entries of the completion queue that are reserved by request queues.
+    cq_reserved: u32,
+    /// Request queues of sockets.
+    rqs: HashMap<RawSocket, RequestQueue>,
+    /// Pre-registered buffers.
+    pool: Rc<Pool>,
+    /// Areas for socket addresses that are free.
+    addrs: Vec<AddrArea>,
+    /// Requests that are in flight, indexed by their key.
+    requests: HashMap<usize, Request>,
+    /// Key of the next request.
+    next_key: usize,
+}
+
+//==============================================================================
+// Foreign Functions
+//==============================================================================
+
+#[link(name = "ws2_32")]
+extern "system" {
+    fn WSASocketW(af: i32, ty: i32, protocol: i32, info: *const c_void, group: u32, flags: u32) -> usize;
+    fn WSAIoctl(
+        s: usize,
+        code: u32,
+        inbuf: *const c_void,
+        inlen: u32,
+        outbuf: *mut c_void,
+        outlen: u32,
+        nreturned: *mut u32,
+        overlapped: *mut c_void,
+        routine: *const c_void,
+    ) -> i32;
+    fn WSAGetLastError() -> i32;
+    fn getsockopt(s: usize, level: i32, name: i32, val: *mut c_void, len: *mut i32) -> i32;
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Registered I/O
+impl RegisteredIo {
+    /// Sets up Registered I/O with `nbuffers` pre-registered buffers, which are added again whenever all of them are in
+    /// use.
+    pub fn new(nbuffers: usize) -> Result<Self, Fail> {
+        let rio: Rio = Rio::load()?;
+        let cq: RioCq = unsafe { (rio.create_cq)(INITIAL_CQ_SIZE, ptr::null()) };
+        if cq.is_null() {
+            return Err(last_error("failed to create completion queue"));
+        }
+        let pool: Rc<Pool> = Rc::new_cyclic(|pool: &Weak<Pool>| Pool {
+            info: ForeignInfo {
+                release: release_buffer,
+                opaque: pool.as_ptr() as *const c_void,
+            },
+            nbuffers,
+            deregister: rio.deregister,
+            slabs: RefCell::new(Vec::new()),
+            free: RefCell::new(Vec::with_capacity(nbuffers)),
+        });
+        let mut rio: Self = Self {
+            rio,
+            cq,
+            cq_size: INITIAL_CQ_SIZE,
+            cq_reserved: 0,
+            rqs: HashMap::new(),
+            pool,
+            addrs: Vec::with_capacity(nbuffers),
+            requests: HashMap::new(),
+            next_key: 0,
+        };
+        rio.grow()?;
+        Ok(rio)
+    }
+
+    /// Creates a socket that supports Registered I/O.
+    pub fn socket(domain: i32, ty: i32, protocol: i32) -> Result<Socket, Fail> {
+        let flags: u32 = WSA_FLAG_OVERLAPPED | WSA_FLAG_NO_HANDLE_INHERIT | WSA_FLAG_REGISTERED_IO;
+        let socket: usize = unsafe { WSASocketW(domain, ty, protocol, ptr::null(), 0, flags) };
+        if socket == INVALID_SOCKET {
+            return Err(wsa_error("failed to create socket"));
+        }
+        Ok(unsafe { Socket::from_raw_socket(socket as RawSocket) })
+    }
+
+    /// Allocates a buffer of `size` bytes out of the pool, which is sent without being copied.
+    pub fn alloc(&mut self, size: usize) -> Result<DemiBuffer, Fail> {
+        if size > BUFFER_SIZE {
+            return Err(Fail::new(libc::EINVAL, "size too large for a pre-registered buffer"));
+        }
+        let mut buf: DemiBuffer = self.lend()?;
+        buf.trim(BUFFER_SIZE - size)?;
+        Ok(buf)
+    }
+
+    /// Sends `bufs` on `socket`, to the address `addr` if it is set. A single buffer of the pool is sent in place, and
+    /// anything else is copied into a buffer of the pool, so that sends on stream sockets may be short.
+    pub fn send(
+        &mut self,
+        socket: RawSocket,
+        mut bufs: Vec<DemiBuffer>,
+        addr: Option<SockAddr>,
+    ) -> Result<usize, Fail> {
+        let (rq, datagram): (RioRq, bool) = self.request_queue(socket)?;
+        let len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if datagram && len > BUFFER_SIZE {
+            return Err(Fail::new(
+                libc::EMSGSIZE,
+                "datagram does not fit in a pre-registered buffer",
+            ));
+        }
+        self.make_room(socket, false)?;
+
+        let buf: DemiBuffer = match bufs.len() {
+            1 if self.locate(&bufs[0]).is_some() => bufs.remove(0),
+            _ => {
+                let mut buf: DemiBuffer = self.lend()?;
+                let mut offset: usize = 0;
+                for seg in bufs.iter().flat_map(|buf| buf.segments()) {
+                    let n: usize = seg.len().min(BUFFER_SIZE - offset);
+                    buf[offset..offset + n].copy_from_slice(&seg[..n]);
+                    offset += n;
+                }
+                buf.trim(BUFFER_SIZE - offset)?;
+                buf
+            },
+        };
+        let (id, offset): (RioBufferId, u32) = self.locate(&buf).expect("buffer should belong to the pool");
+        let data: RioBuf = RioBuf {
+            buffer_id: id,
+            offset,
+            length: buf.len() as u32,
+        };
+
+        let area: Option<AddrArea> = match addr {
+            Some(ref addr) => {
+                let area: AddrArea = self.alloc_addr()?;
+                let raw: &[u8] = unsafe { slice::from_raw_parts(addr.as_ptr() as *const u8, addr.len() as usize) };
+                unsafe { ptr::copy_nonoverlapping(raw.as_ptr(), area.memory.as_ptr(), raw.len().min(ADDR_SIZE)) };
+                Some(area)
+            },
+            None => None,
+        };
+        let remote: Option<RioBuf> = area.map(|area| area.rio_buf());
+        let key: usize = self.next_key();
+        let ok: i32 = unsafe {
+            (self.rio.send_ex)(
+                rq,
+                &data,
+                1,
+                ptr::null(),
+                remote.as_ref().map_or(ptr::null(), |remote| remote as *const RioBuf),
+                ptr::null(),
+                ptr::null(),
+                0,
+                key as *mut c_void,
+            )
+        };
+        let request: Request = Request::new(rq, socket, buf, area, false, datagram);
+        self.issued(key, ok != 0, request, "failed to send data")
+    }
+
+    /// Receives at most `size` bytes from `socket`, capped at the size of pre-registered buffers.
+    pub fn recv(&mut self, socket: RawSocket, size: usize) -> Result<usize, Fail> {
+        let (rq, datagram): (RioRq, bool) = self.request_queue(socket)?;
+        self.make_room(socket, true)?;
+        let mut buf: DemiBuffer = self.lend()?;
+        buf.trim(BUFFER_SIZE - size.min(BUFFER_SIZE))?;
+        let (id, offset): (RioBufferId, u32) = self.locate(&buf).expect("buffer should belong to the pool");
+        let data: RioBuf = RioBuf {
+            buffer_id: id,
+            offset,
+            length: buf.len() as u32,
+        };
+        // Stream sockets have no source address to report.
+        let area: Option<AddrArea> = if datagram { Some(self.alloc_addr()?) } else { None };
+        let remote: Option<RioBuf> = area.map(|area| area.rio_buf());
+        let key: usize = self.next_key();
+        let ok: i32 = unsafe {
+            (self.rio.receive_ex)(
+                rq,
+                &data,
+                1,
+                ptr::null(),
+                remote.as_ref().map_or(ptr::null(), |remote| remote as *const RioBuf),
+                ptr::null(),
+                ptr::null(),
+                0,
+                key as *mut c_void,
+            )
+        };
+        let request: Request = Request::new(rq, socket, buf, area, true, datagram);
+        self.issued(key, ok != 0, request, "failed to receive data")
+    }
+
+    /// Dequeues completions. Returns the tasks that wait on requests that completed.
+    pub fn poll(&mut self) -> Vec<Waker> {
+        let mut wakers: Vec<Waker> = Vec::new();
+        let mut results: [RioResult; MAX_COMPLETIONS] = unsafe { mem::zeroed() };
+        loop {
+            let nresults: u32 = unsafe { (self.rio.dequeue)(self.cq, results.as_mut_ptr(), MAX_COMPLETIONS as u32) };
+            if nresults == RIO_CORRUPT_CQ {
+                warn!("completion queue is corrupted");
+                return wakers;
+            }
+            for result in &results[..nresults as usize] {
+                let key: usize = result.request_context as usize;
+                let request: &mut Request = match self.requests.get_mut(&key) {
+                    Some(request) => request,
+                    None => {
+                        warn!("completion of unknown request");
+                        continue;
+                    },
+                };
+                request.result = Some((result.status, result.nbytes));
+
+                // The request no longer takes room in its request queue, unless the socket was closed since.
+                if let Some(queue) = self.rqs.get_mut(&request.socket).filter(|queue| queue.rq == request.rq) {
+                    match request.recv {
+                        true => queue.recvs -= 1,
+                        false => queue.sends -= 1,
+                    }
+                }
+
+                if request.orphaned {
+                    let request: Request = self.requests.remove(&key).expect("request should be in flight");
+                    self.release(request);
+                    continue;
+                }
+                if let Some(waker) = request.waker.take() {
+                    wakers.push(waker);
+                }
+            }
+            if (nresults as usize) < MAX_COMPLETIONS {
+                return wakers;
+            }
+        }
+    }
+
+    /// Forgets the request queue of `socket`, which is about to be closed. The request queue itself is released along
+    /// with the socket.
+    pub fn forget(&mut self, socket: RawSocket) {
+        if let Some(queue) = self.rqs.remove(&socket) {
+            self.cq_reserved -= 2 * queue.capacity;
+        }
+    }
+
+    /// Gets the request queue of `socket`, creating it if it does not exist yet. Returns it along with whether the
+    /// socket is a datagram socket.
+    fn request_queue(&mut self, socket: RawSocket) -> Result<(RioRq, bool), Fail> {
+        if let Some(queue) = self.rqs.get(&socket) {
+            return Ok((queue.rq, queue.datagram));
+        }
+
+        let mut ty: i32 = 0;
+        let mut len: i32 = mem::size_of::<i32>() as i32;
+        if unsafe {
+            getsockopt(
+                socket as usize,
+                SOL_SOCKET,
+                SO_TYPE,
+                &mut ty as *mut i32 as *mut c_void,
+                &mut len,
+            )
+        } != 0
+        {
+            return Err(wsa_error("failed to get socket type"));
+        }
+
+        // Make room in the completion queue for completions of all requests that may be outstanding.
+        self.reserve_cq(2 * INITIAL_OUTSTANDING)?;
+        let rq: RioRq = unsafe {
+            (self.rio.create_rq)(
+                socket as usize,
+                INITIAL_OUTSTANDING,
+                1,
+                INITIAL_OUTSTANDING,
+                1,
+                self.cq,
+                self.cq,
+                ptr::null_mut(),
+            )
+        };
+        if rq.is_null() {
+            let e: Fail = wsa_error("failed to create request queue");
+            self.cq_reserved -= 2 * INITIAL_OUTSTANDING;
+            return Err(e);
+        }
+        let datagram: bool = ty == SOCK_DGRAM;
+        self.rqs.insert(
+            socket,
+            RequestQueue {
+                rq,
+                datagram,
+                capacity: INITIAL_OUTSTANDING,
+                sends: 0,
+                recvs: 0,
+            },
+        );
+        Ok((rq, datagram))
+    }
+
+    /// Makes room for another send on the request queue of `socket`, or for another receive if `recv` is set. The
+    /// request queue doubles in size whenever it is full.
+    fn make_room(&mut self, socket: RawSocket, recv: bool) -> Result<(), Fail> {
+        let queue: &RequestQueue = self.rqs.get(&socket).expect("request queue should exist");
+        let outstanding: u32 = if recv { queue.recvs } else { queue.sends };
+        if outstanding < queue.capacity {
+            return Ok(());
+        }
+        let (rq, capacity): (RioRq, u32) = (queue.rq, queue.capacity);
+        self.reserve_cq(2 * capacity)?;
+        if unsafe { (self.rio.resize_rq)(rq, 2 * capacity, 2 * capacity) } == 0 {
+            let e: Fail = wsa_error("failed to resize request queue");
+            self.cq_reserved -= 2 * capacity;
+            return Err(e);
+        }
+        self.rqs.get_mut(&socket).expect("request queue should exist").capacity = 2 * capacity;
+        Ok(())
+    }
+
+    /// Reserves `n` entries of the completion queue, which grows if they do not fit.
+    fn reserve_cq(&mut self, n: u32) -> Result<(), Fail> {
+        let needed: u32 = self.cq_reserved + n;
+        if needed > self.cq_size {
+            let size: u32 = needed.max(2 * self.cq_size);
+            if unsafe { (self.rio.resize_cq)(self.cq, size) } == 0 {
+                return Err(wsa_error("failed to resize completion queue"));
+            }
+            self.cq_size = size;
+        }
+        self.cq_reserved = needed;
+        Ok(())
+    }
+
+    /// Adds a slab of buffers to the pool, along with as many areas for socket addresses.
+    fn grow(&mut self) -> Result<(), Fail> {
+        let nbuffers: usize = self.pool.nbuffers;
+        let layout: Layout = Pool::slab_layout(nbuffers);
+        let memory: NonNull<u8> = match NonNull::new(unsafe { alloc::alloc_zeroed(layout) }) {
+            Some(memory) => memory,
+            None => return Err(Fail::new(libc::ENOMEM, "failed to allocate buffers")),
+        };
+        let id: RioBufferId = unsafe { (self.rio.register)(memory.as_ptr(), layout.size() as u32) };
+        if id as usize == RIO_INVALID_BUFFERID {
+            let e: Fail = wsa_error("failed to register buffers");
+            unsafe { alloc::dealloc(memory.as_ptr(), layout) };
+            return Err(e);
+        }
+        for i in (0..nbuffers).rev() {
+            // Safety: the areas lie within the slab.
+            self.pool
+                .free
+                .borrow_mut()
+                .push(unsafe { NonNull::new_unchecked(memory.as_ptr().add(i * SLOT_SIZE)) });
+            let offset: usize = nbuffers * SLOT_SIZE + i * ADDR_SIZE;
+            self.addrs.push(AddrArea {
+                memory: unsafe { NonNull::new_unchecked(memory.as_ptr().add(offset)) },
+                id,
+                offset: offset as u32,
+            });
+        }
+        self.pool.slabs.borrow_mut().push(Slab { memory, id });
+        Ok(())
+    }
+
+    /// Lends out a free buffer of the pool, which grows if none is free.
+    fn lend(&mut self) -> Result<DemiBuffer, Fail> {
+        if self.pool.free.borrow().is_empty() {
+            self.grow()?;
+        }
+        let base: NonNull<u8> = self
+            .pool
+            .free
+            .borrow_mut()
+            .pop()
+            .expect("pool should have a free buffer");
+        // Safety: the buffer lies within the pool, which outlives it since the buffer holds a reference to it.
+        unsafe {
+            Rc::increment_strong_count(Rc::as_ptr(&self.pool));
+            Ok(DemiBuffer::from_foreign(
+                base,
+                BUFFER_SIZE as u16,
+                0,
+                BUFFER_SIZE as u16,
+                NonNull::from(&self.pool.info),
+            ))
+        }
+    }
+
+    /// Allocates an area for a socket address, which the pool grows for if none is free.
+    fn alloc_addr(&mut self) -> Result<AddrArea, Fail> {
+        if self.addrs.is_empty() {
+            self.grow()?;
+        }
+        Ok(self.addrs.pop().expect("pool should have a free address area"))
+    }
+
+    /// Gets the identifier of the registered memory that `buf` lies within, along with the offset of `buf` in it, if
+    /// `buf` is a buffer of the pool.
+    fn locate(&self, buf: &DemiBuffer) -> Option<(RioBufferId, u32)> {
+        let data: usize = buf.as_ptr() as usize;
+        self.pool.slabs.borrow().iter().find_map(|slab: &Slab| {
+            let start: usize = slab.memory.as_ptr() as usize;
+            let end: usize = start + self.pool.nbuffers * SLOT_SIZE;
+            if buf.num_segments() == 1 && data >= start && data + buf.len() <= end {
+                Some((slab.id, (data - start) as u32))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Releases the resources of `request`, which completed.
+    fn release(&mut self, request: Request) {
+        if let Some(area) = request.addr {
+            self.addrs.push(area);
+        }
+    }
+
+    /// Returns the key of the next request.
+    fn next_key(&mut self) -> usize {
+        let key: usize = self.next_key;
+        self.next_key = self.next_key.wrapping_add(1);
+        key
+    }
+
+    /// Checks the outcome of issuing `request` under `key`. If this succeeded, the request is tracked until it
+    /// completes.
+    fn issued(&mut self, key: usize, ok: bool, request: Request, cause: &str) -> Result<usize, Fail> {
+        if !ok {
+            let e: Fail = wsa_error(cause);
+            self.release(request);
+            return Err(e);
+        }
+        let queue: &mut RequestQueue = self.rqs.get_mut(&request.socket).expect("request queue should exist");
+        match request.recv {
+            true => queue.recvs += 1,
+            false => queue.sends += 1,
+        }
+        self.requests.insert(key, request);
+        Ok(key)
+    }
+}
+
+/// Associate Functions for Requests
+impl Request {
+    /// Builds a request that goes through `buf` and `addr`, and that is issued on the request queue `rq` of `socket`.
+    fn new(rq: RioRq, socket: RawSocket, buf: DemiBuffer, addr: Option<AddrArea>, recv: bool, datagram: bool) -> Self {
+        Self {
+            rq,
+            socket,
+            buf,
+            addr,
+            recv,
+            datagram,
+            result: None,
+            waker: None,
+            orphaned: false,
+        }
+    }
+}
+
+/// Associate Functions for Areas of Socket Addresses
+impl AddrArea {
+    /// Describes the target area to Registered I/O.
+    fn rio_buf(&self) -> RioBuf {
+        RioBuf {
+            buffer_id: self.id,
+            offset: self.offset,
+            length: ADDR_SIZE as u32,
+        }
+    }
+}
+
+/// Associate Functions for Pre-Registered Buffers
+impl Pool {
+    /// Gets the layout of slabs of `nbuffers` buffers.
+    fn slab_layout(nbuffers: usize) -> Layout {
+        // This unwrap will never panic, as the size of slabs is bounded by the number of buffers.
+        Layout::from_size_align(nbuffers * (SLOT_SIZE + ADDR_SIZE), arch::CPU_DATA_CACHE_LINE_SIZE).unwrap()
+    }
+}
+
+/// Associate Functions for Extension Functions of Registered I/O
+impl Rio {
+    /// Retrieves the extension functions of Registered I/O through a scratch socket.
+    fn load() -> Result<Self, Fail> {
+        let socket: Socket = RegisteredIo::socket(libc::AF_INET, SOCK_DGRAM, 0)?;
+        let mut table: RioFunctions = unsafe { mem::zeroed() };
+        table.size = mem::size_of::<RioFunctions>() as u32;
+        let mut nbytes: u32 = 0;
+        let result: i32 = unsafe {
+            WSAIoctl(
+                socket.as_raw_socket() as usize,
+                SIO_GET_MULTIPLE_EXTENSION_FUNCTION_POINTER,
+                &WSAID_MULTIPLE_RIO as *const Guid as *const c_void,
+                mem::size_of::<Guid>() as u32,
+                &mut table as *mut RioFunctions as *mut c_void,
+                mem::size_of::<RioFunctions>() as u32,
+                &mut nbytes,
+                ptr::null_mut(),
+                ptr::null(),
+            )
+        };
+        if result != 0 {
+            return Err(wsa_error("failed to retrieve RIO functions"));
+        }
+        match (
+            table.receive_ex,
+            table.send_ex,
+            table.close_cq,
+            table.create_cq,
+            table.create_rq,
+            table.dequeue,
+            table.deregister,
+            table.register,
+            table.resize_cq,
+            table.resize_rq,
+        ) {
+            (
+                Some(receive_ex),
+                Some(send_ex),
+                Some(close_cq),
+                Some(create_cq),
+                Some(create_rq),
+                Some(dequeue),
+                Some(deregister),
+                Some(register),
+                Some(resize_cq),
+                Some(resize_rq),
+            ) => Ok(Self {
+                receive_ex,
+                send_ex,
+                close_cq,
+                create_cq,
+                create_rq,
+                dequeue,
+                deregister,
+                register,
+                resize_cq,
+                resize_rq,
+            }),
+            _ => Err(Fail::new(libc::ENOTSUP, "RIO functions are not available")),
+        }
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Builds a [Fail] out of the last Winsock error.
+fn wsa_error(cause: &str) -> Fail {
+    let errno: i32 = unsafe { WSAGetLastError() };
+    Fail::new(io::Error::from_raw_os_error(errno).kind() as i32, cause)
+}
+
+/// Builds a [Fail] out of the last Win32 error.
+fn last_error(cause: &str) -> Fail {
+    Fail::new(io::Error::last_os_error().kind() as i32, cause)
+}
+
+/// Takes back a buffer of the pool that was released. `opaque` refers to the pool, along with the reference that the
+/// buffer held.
+unsafe fn release_buffer(opaque: *const c_void, base: NonNull<u8>) {
+    let pool: Rc<Pool> = Rc::from_raw(opaque as *const Pool);
+    pool.free.borrow_mut().push(base);
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// I/O Queue Trait Implementation for Registered I/O
+impl IoQueue for RegisteredIo {
+    fn poll_operation(&mut self, key: usize, waker: &Waker) -> Poll<Result<IoOutput, Fail>> {
+        let request: &mut Request = match self.requests.get_mut(&key) {
+            Some(request) => request,
+            None => return Poll::Ready(Err(Fail::new(libc::EINVAL, "unknown operation"))),
+        };
+        let (status, nbytes): (i32, u32) = match request.result {
+            Some(result) => result,
+            None => {
+                request.waker = Some(waker.clone());
+                return Poll::Pending;
+            },
+        };
+        let Request {
+            mut buf,
+            addr: area,
+            recv,
+            datagram,
+            ..
+        } = self.requests.remove(&key).expect("request should be in flight");
+        let output: Result<IoOutput, Fail> = match (recv, status) {
+            (false, 0) => Ok(IoOutput::Sent(nbytes as usize)),
+            (true, WSAECONNRESET) => Ok(IoOutput::Reset),
+            // Datagrams that do not fit in the receive buffer are truncated. Received data is handed out in place.
+            (true, 0) | (true, WSAEMSGSIZE) => {
+                let addr: Option<SocketAddrV4> = match area {
+                    // Safety: the area belongs to the request until it is released.
+                    Some(ref area) if datagram => parse_sockaddr(
+                        unsafe { slice::from_raw_parts(area.memory.as_ptr(), ADDR_SIZE) },
+                        ADDR_SIZE,
+                    ),
+                    _ => None,
+                };
+                let nbytes: usize = (nbytes as usize).min(buf.len());
+                match buf.trim(buf.len() - nbytes) {
+                    Ok(()) => Ok(IoOutput::Received(addr, buf, status == WSAEMSGSIZE)),
+                    Err(e) => Err(e),
+                }
+            },
+            (_, errno) => Err(Fail::new(
+                io::Error::from_raw_os_error(errno).kind() as i32,
+                "operation failed",
+            )),
+        };
+        if let Some(area) = area {
+            self.addrs.push(area);
+        }
+        Poll::Ready(output)
+    }
+
+    fn cancel(&mut self, key: usize) {
+        // Requests cannot be canceled, so we wait for them to complete before releasing their memory.
+        if let Some(request) = self.requests.get_mut(&key) {
+            if request.result.is_some() {
+                let request: Request = self.requests.remove(&key).expect("request should be in flight");
+                self.release(request);
+                return;
+            }
+            request.orphaned = true;
+            request.waker = None;
+        }
+    }
+}
+
+/// Drop Trait Implementation for Pre-Registered Buffers, which are dropped once neither [RegisteredIo] nor any buffer
+/// that they lent out is used anymore.
+impl Drop for Pool {
+    fn drop(&mut self) {
+        let layout: Layout = Pool::slab_layout(self.nbuffers);
+        for slab in self.slabs.get_mut().drain(..) {
+            // Safety: no buffer of the slab is used anymore.
+            unsafe {
+                (self.deregister)(slab.id);
+                alloc::dealloc(slab.memory.as_ptr(), layout);
+            }
+        }
+    }
+}
+
+/// Drop Trait Implementation for Registered I/O
+impl Drop for RegisteredIo {
+    fn drop(&mut self) {
+        // Requests that are still in flight may be written by the kernel, so we leak their buffers, which keeps the
+        // pool from being released.
+        for (_, request) in self.requests.drain() {
+            if request.result.is_none() {
+                mem::forget(request.buf);
+            }
+        }
+        unsafe { (self.rio.close_cq)(self.cq) };
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        RegisteredIo,
+        BUFFER_SIZE,
+        INITIAL_OUTSTANDING,
+    };
+    use crate::{
+        catnapw::iocp::{
+            IoOutput,
+            IoQueue,
+        },
+        runtime::memory::DemiBuffer,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::socket2::{
+        Domain,
+        Protocol,
+        SockAddr,
+        Socket,
+        Type,
+    };
+    use ::std::{
+        net::{
+            Ipv4Addr,
+            SocketAddr,
+            SocketAddrV4,
+            UdpSocket,
+        },
+        os::windows::io::{
+            AsRawSocket,
+            RawSocket,
+        },
+        task::Poll,
+        thread,
+        time::Duration,
+    };
+
+    /// Creates a datagram socket that supports Registered I/O and that is bound to the loopback interface.
+    fn udp_socket() -> Socket {
+        let socket: Socket =
+            RegisteredIo::socket(Domain::IPV4.into(), Type::DGRAM.into(), Protocol::UDP.into()).unwrap();
+        socket
+            .bind(&SockAddr::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)))
+            .unwrap();
+        socket
+    }
+
+    /// Polls `rio` until the request `key` completes.
+    fn wait(rio: &mut RegisteredIo, key: usize) -> IoOutput {
+        loop {
+            rio.poll();
+            match rio.poll_operation(key, noop_waker_ref()) {
+                Poll::Ready(result) => return result.unwrap(),
+                Poll::Pending => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+    }
+
+    /// Checks that received data is handed out in the buffer of the pool that it was received in, which goes back to
+    /// the pool once it is dropped.
+    #[test]
+    fn recv_hands_out_pool_buffers() {
+        let mut rio: RegisteredIo = RegisteredIo::new(1).unwrap();
+        let socket: Socket = udp_socket();
+        let local: SocketAddr = socket.local_addr().unwrap().as_socket().unwrap();
+        let peer: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let key: usize = rio.recv(socket.as_raw_socket(), BUFFER_SIZE).unwrap();
+        peer.send_to(b"hello", local).unwrap();
+        let buf: DemiBuffer = match wait(&mut rio, key) {
+            IoOutput::Received(addr, buf, false) => {
+                assert_eq!(addr.map(SocketAddr::V4), Some(peer.local_addr().unwrap()));
+                buf
+            },
+            _ => panic!("receive should output a whole datagram"),
+        };
+        assert_eq!(&buf[..], b"hello");
+        assert!(rio.locate(&buf).is_some());
+        assert!(rio.pool.free.borrow().is_empty());
+
+        drop(buf);
+        assert_eq!(rio.pool.free.borrow().len(), 1);
+    }
+
+    /// Checks that buffers that are allocated out of the pool are sent in place.
+    #[test]
+    fn send_sends_pool_buffers_in_place() {
+        let mut rio: RegisteredIo = RegisteredIo::new(1).unwrap();
+        let socket: Socket = udp_socket();
+        let peer: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut buf: DemiBuffer = rio.alloc(5).unwrap();
+        buf.copy_from_slice(b"hello");
+        let addr: SockAddr = SockAddr::from(peer.local_addr().unwrap());
+        let key: usize = rio.send(socket.as_raw_socket(), vec![buf], Some(addr)).unwrap();
+        // The data was not copied into another buffer, for which the pool would have grown.
+        assert_eq!(rio.pool.slabs.borrow().len(), 1);
+        assert!(matches!(wait(&mut rio, key), IoOutput::Sent(5)));
+
+        let mut data: [u8; 16] = [0; 16];
+        let (n, _): (usize, SocketAddr) = peer.recv_from(&mut data).unwrap();
+        assert_eq!(&data[..n], b"hello");
+    }
+
+    /// Checks that the pool and request queues grow as more requests are outstanding.
+    #[test]
+    fn send_grows_pool_and_request_queues() {
+        let mut rio: RegisteredIo = RegisteredIo::new(1).unwrap();
+        let socket: Socket = udp_socket();
+        let raw: RawSocket = socket.as_raw_socket();
+        let peer: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr: SockAddr = SockAddr::from(peer.local_addr().unwrap());
+
+        // Completions are not dequeued in the meantime, so all sends remain outstanding.
+        let nsends: usize = 2 * INITIAL_OUTSTANDING as usize + 1;
+        let keys: Vec<usize> = (0..nsends)
+            .map(|i: usize| {
+                let buf: DemiBuffer = DemiBuffer::from_slice(&[i as u8; 64]).unwrap();
+                rio.send(raw, vec![buf], Some(addr.clone())).unwrap()
+            })
+            .collect();
+        assert_eq!(rio.pool.slabs.borrow().len(), nsends);
+        assert_eq!(rio.rqs[&raw].capacity, 4 * INITIAL_OUTSTANDING);
+
+        for key in keys {
+            assert!(matches!(wait(&mut rio, key), IoOutput::Sent(64)));
+        }
+        assert_eq!(rio.rqs[&raw].sends, 0);
+        assert_eq!(rio.pool.free.borrow().len(), nsends);
+    }
+
+    /// Checks that datagrams that do not fit in a buffer of the pool are refused.
+    #[test]
+    fn send_refuses_oversized_datagrams() {
+        let mut rio: RegisteredIo = RegisteredIo::new(1).unwrap();
+        let socket: Socket = udp_socket();
+        let peer: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr: SockAddr = SockAddr::from(peer.local_addr().unwrap());
+
+        let buf: DemiBuffer = DemiBuffer::new((BUFFER_SIZE + 1) as u16);
+        match rio.send(socket.as_raw_socket(), vec![buf], Some(addr)) {
+            Err(e) => assert_eq!(e.errno, libc::EMSGSIZE),
+            Ok(_) => panic!("send should be refused"),
+        }
+    }
+}
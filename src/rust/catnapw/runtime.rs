@@ -5,28 +5,42 @@
 // Imports
 //==============================================================================
 
+use super::{
+    iocp::{
+        CompletionPort,
+        IoHandle,
+    },
+    rio::{
+        RegisteredIo,
+        BUFFER_SIZE,
+    },
+};
 use crate::{
     runtime::{
         fail::Fail,
         memory::{
+            self,
             DemiBuffer,
             MemoryRuntime,
         },
-        types::{
-            demi_sgarray_t,
-            demi_sgaseg_t,
-        },
+        types::demi_sgarray_t,
         Runtime,
     },
     scheduler::scheduler::Scheduler,
 };
-use ::libc::c_void;
+use ::socket2::{
+    Domain,
+    Protocol,
+    SockAddr,
+    Socket,
+    Type,
+};
 use ::std::{
-    mem,
-    ptr::{
-        self,
-        NonNull,
-    },
+    cell::RefCell,
+    os::windows::io::RawSocket,
+    rc::Rc,
+    task::Waker,
+    time::Duration,
 };
 
 //==============================================================================
@@ -38,6 +52,10 @@ use ::std::{
 pub struct PosixRuntime {
     /// Scheduler
     pub scheduler: Scheduler,
+    /// Completion port on which operations on sockets are issued.
+    iocp: Rc<RefCell<CompletionPort>>,
+    /// Registered I/O, on which sends and receives are issued instead, if it is enabled.
+    rio: Option<Rc<RefCell<RegisteredIo>>>,
 }
 
 //==============================================================================
@@ -46,145 +64,114 @@ pub struct PosixRuntime {
 
 /// Associate Functions for POSIX Runtime
 impl PosixRuntime {
-    pub fn new() -> Self {
+    pub fn new(rio_buffers: Option<usize>) -> Self {
+        let iocp: CompletionPort = match CompletionPort::new() {
+            Ok(iocp) => iocp,
+            Err(e) => panic!("cannot create completion port ({:?})", e),
+        };
+        let rio: Option<RegisteredIo> = rio_buffers.map(|nbuffers| match RegisteredIo::new(nbuffers) {
+            Ok(rio) => rio,
+            Err(e) => panic!("cannot set up registered I/O ({:?})", e),
+        });
         Self {
             scheduler: Scheduler::default(),
+            iocp: Rc::new(RefCell::new(iocp)),
+            rio: rio.map(|rio| Rc::new(RefCell::new(rio))),
         }
     }
-}
 
-//==============================================================================
-// Trait Implementations
-//==============================================================================
-
-/// Memory Runtime Trait Implementation for POSIX Runtime
-impl MemoryRuntime for PosixRuntime {
-    /// Converts a runtime buffer into a scatter-gather array.
-    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: buf.len() as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
-    }
-
-    /// Allocates a scatter-gather array.
-    fn alloc_sgarray(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // ToDo: Allocate an array of buffers if requested size is too large for a single buffer.
-
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
+    /// Creates a socket. Sockets support registered I/O, if it is enabled.
+    pub fn socket(&self, domain: Domain, ty: Type, protocol: Protocol) -> Result<Socket, Fail> {
+        if self.rio.is_some() {
+            return RegisteredIo::socket(domain.into(), ty.into(), protocol.into());
         }
+        match Socket::new(domain, ty, Some(protocol)) {
+            Ok(socket) => Ok(socket),
+            Err(e) => Err(Fail::new(e.kind() as i32, "failed to create socket")),
+        }
+    }
 
-        // First allocate the underlying (heap-allocated) DemiBuffer.
-        let buf: DemiBuffer = DemiBuffer::new(size as u16);
-
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+    /// Associates `socket` with the completion port of the target runtime.
+    pub fn associate(&self, socket: RawSocket) -> Result<(), Fail> {
+        self.iocp.borrow_mut().associate(socket)
     }
 
-    /// Releases a scatter-gather array.
-    fn free_sgarray(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
-        // Check arguments.
-        // TODO: Drop this check once we support scatter-gather arrays with multiple segments.
-        if sga.sga_numsegs != 1 {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+    /// Issues an overlapped send of `bufs` on `socket`, to the address `addr` if it is set.
+    pub fn send(&self, socket: RawSocket, bufs: Vec<DemiBuffer>, addr: Option<SockAddr>) -> Result<IoHandle, Fail> {
+        if let Some(ref rio) = self.rio {
+            let key: usize = rio.borrow_mut().send(socket, bufs, addr)?;
+            return Ok(IoHandle::new(rio.clone(), key));
         }
+        let key: usize = self.iocp.borrow_mut().send(socket, bufs, addr)?;
+        Ok(IoHandle::new(self.iocp.clone(), key))
+    }
 
-        if sga.sga_buf == ptr::null_mut() {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+    /// Issues an overlapped receive of at most `size` bytes on `socket`.
+    pub fn recv(&self, socket: RawSocket, size: usize) -> Result<IoHandle, Fail> {
+        if let Some(ref rio) = self.rio {
+            let key: usize = rio.borrow_mut().recv(socket, size)?;
+            return Ok(IoHandle::new(rio.clone(), key));
         }
+        let key: usize = self.iocp.borrow_mut().recv(socket, size)?;
+        Ok(IoHandle::new(self.iocp.clone(), key))
+    }
 
-        // Convert back to a DemiBuffer and drop it.
-        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
-        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
-        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
-        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
-        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
-        drop(buf);
+    /// Issues an overlapped accept on the listening `socket`, which takes the incoming connection into `new_socket`.
+    pub fn accept(&self, socket: RawSocket, new_socket: Socket) -> Result<IoHandle, Fail> {
+        let key: usize = self.iocp.borrow_mut().accept(socket, new_socket)?;
+        Ok(IoHandle::new(self.iocp.clone(), key))
+    }
 
-        Ok(())
+    /// Issues an overlapped connect on the bound `socket` to `addr`.
+    pub fn connect(&self, socket: RawSocket, addr: SockAddr) -> Result<IoHandle, Fail> {
+        let key: usize = self.iocp.borrow_mut().connect(socket, addr)?;
+        Ok(IoHandle::new(self.iocp.clone(), key))
     }
 
-    /// Clones a scatter-gather array into a DemiBuffer.
-    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
-        // Check arguments.
-        // TODO: Drop this check once we support scatter-gather arrays with multiple segments.
-        if sga.sga_numsegs != 1 {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+    /// Dequeues completions without blocking and wakes up the tasks that wait on operations that completed.
+    pub fn poll_completions(&self) {
+        let mut wakers: Vec<Waker> = self.iocp.borrow_mut().poll();
+        if let Some(ref rio) = self.rio {
+            wakers.extend(rio.borrow_mut().poll());
         }
+        wakers.into_iter().for_each(Waker::wake);
+    }
 
-        if sga.sga_buf == ptr::null_mut() {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+    /// Parks the calling thread until some operation completes, for at most `timeout`, if the scheduler has been idle
+    /// for long enough. Completions of registered I/O are not posted to the completion port, so this sleeps instead
+    /// when it is enabled.
+    pub fn park(&self, timeout: Option<Duration>) {
+        if self.rio.is_some() {
+            return self.scheduler.park(timeout);
         }
+        if let Some(park_time) = self.scheduler.park_time(timeout) {
+            let wakers: Vec<Waker> = self.iocp.borrow_mut().wait(park_time);
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
 
-        // Convert back to a DemiBuffer.
-        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
-        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
-        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
-        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
-        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
-        let mut clone: DemiBuffer = buf.clone();
-
-        // Don't drop buf, as it holds the same reference to the data as the sgarray (which should keep it).
-        mem::forget(buf);
-
-        // Check to see if the user has reduced the size of the buffer described by the sgarray segment since we
-        // provided it to them.  They could have increased the starting address of the buffer (`sgaseg_buf`),
-        // decreased the ending address of the buffer (`sgaseg_buf + sgaseg_len`), or both.
-        let sga_data: *const u8 = sga.sga_segs[0].sgaseg_buf as *const u8;
-        let sga_len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let clone_data: *const u8 = clone.as_ptr();
-        let mut clone_len: usize = clone.len();
-        if sga_data != clone_data || sga_len != clone_len {
-            // We need to adjust the DemiBuffer to match the user's changes.
-
-            // First check that the user didn't do something non-sensical, like change the buffer description to
-            // reference address space outside of the allocated memory area.
-            if sga_data < clone_data || sga_data.addr() + sga_len > clone_data.addr() + clone_len {
-                return Err(Fail::new(
-                    libc::EINVAL,
-                    "demi_sgarray_t describes data outside backing buffer's allocated region",
-                ));
-            }
-
-            // Calculate the amount the new starting address is ahead of the old.  And then adjust `clone` to match.
-            let adjustment_amount: usize = sga_data.addr() - clone_data.addr();
-            clone.adjust(adjustment_amount)?;
-
-            // An adjustment above would have reduced clone.len() by the adjustment amount.
-            clone_len -= adjustment_amount;
-            debug_assert_eq!(clone_len, clone.len());
-
-            // Trim the clone down to size.
-            let trim_amount: usize = clone_len - sga_len;
-            clone.trim(trim_amount)?;
+    /// Forgets the state that is kept for `socket`, which is about to be closed.
+    pub fn forget(&self, socket: RawSocket) {
+        if let Some(ref rio) = self.rio {
+            rio.borrow_mut().forget(socket);
         }
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
 
-        // Return the clone.
-        Ok(clone)
+/// Memory Runtime Trait Implementation for POSIX Runtime
+impl MemoryRuntime for PosixRuntime {
+    /// Allocates a scatter-gather array. Arrays that fit in a pre-registered buffer are allocated out of it, if
+    /// Registered I/O is enabled, so that they are pushed without being copied.
+    fn alloc_sgarray(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        let buf: DemiBuffer = match self.rio {
+            Some(ref rio) if size <= BUFFER_SIZE => rio.borrow_mut().alloc(size)?,
+            _ => memory::alloc_buffer_chain(size)?,
+        };
+        self.into_sgarray(buf)
     }
 }
 
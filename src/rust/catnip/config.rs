@@ -7,16 +7,34 @@
 
 use crate::{
     demikernel::config::Config,
-    runtime::network::types::MacAddress,
+    runtime::{
+        libdpdk::RTE_PKTMBUF_HEADROOM,
+        network::types::{
+            CongestionControlAlgorithm,
+            MacAddress,
+            OutOfOrderDropPolicy,
+        },
+    },
 };
 use ::anyhow::Error;
 use ::std::{
     collections::HashMap,
     ffi::CString,
     net::Ipv4Addr,
+    time::Duration,
 };
 use ::yaml_rust::Yaml;
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Least number of MBufs in a memory pool, so that the per-thread caches of pools cannot drain them.
+const MIN_NUM_MBUFS: usize = 1024;
+
+/// Longest time (in microseconds) for which ACKs may be delayed, as per RFC 1122.
+const MAX_ACK_DELAY_US: i64 = 500_000;
+
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
@@ -46,22 +64,240 @@ impl Config {
         arp_table
     }
 
-    /// Reads the "DPDK EAL" parameter from the underlying configuration file.
+    /// Reads the "DPDK EAL" parameter from the underlying configuration file, and appends to it the arguments that the
+    /// "core mask", "hugepage directory", "PCI allow list" and "file prefix" parameters translate to. The "DPDK EAL"
+    /// parameter may be left out if any of these is present.
     pub fn eal_init_args(&self) -> Vec<CString> {
         // FIXME: this function should return a Result.
-
-        match self.0["dpdk"]["eal_init"] {
+        let mut args: Vec<String> = match self.0["dpdk"]["eal_init"] {
             Yaml::Array(ref arr) => arr
                 .iter()
                 .map(|a| {
                     a.as_str()
+                        .map(|s| s.to_string())
                         .ok_or_else(|| anyhow::format_err!("Non string argument"))
-                        .and_then(|s| CString::new(s).map_err(|e| e.into()))
                 })
                 .collect::<Result<Vec<_>, Error>>()
                 .unwrap(),
+            Yaml::BadValue
+                if self.core_mask().is_some()
+                    || self.huge_dir().is_some()
+                    || !self.pci_allow_list().is_empty()
+                    || self.file_prefix().is_some() =>
+            {
+                vec!["demikernel".to_string()]
+            },
             _ => panic!("Malformed YAML config"),
+        };
+        if let Some(core_mask) = self.core_mask() {
+            args.push("-c".to_string());
+            args.push(format!("{:#x}", core_mask));
+        }
+        if let Some(huge_dir) = self.huge_dir() {
+            args.push(format!("--huge-dir={}", huge_dir));
+        }
+        for pci_addr in self.pci_allow_list() {
+            args.push("-a".to_string());
+            args.push(pci_addr);
+        }
+        if let Some(file_prefix) = self.file_prefix() {
+            args.push(format!("--file-prefix={}", file_prefix));
+        }
+        args.into_iter()
+            .map(|s| CString::new(s).map_err(|e| e.into()))
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap()
+    }
+
+    /// Reads the "core mask" parameter from the underlying configuration file, if present. This is a hexadecimal mask
+    /// of the cores that DPDK may run on.
+    pub fn core_mask(&self) -> Option<u64> {
+        // FIXME: this function should return a Result.
+        let core_mask: &str = self.0["dpdk"]["core_mask"].as_str()?;
+        let digits: &str = core_mask.strip_prefix("0x").unwrap_or(core_mask);
+        match u64::from_str_radix(digits, 16) {
+            Ok(core_mask) if core_mask != 0 => Some(core_mask),
+            _ => panic!("Invalid core mask {:?}", core_mask),
+        }
+    }
+
+    /// Reads the "hugepage directory" parameter from the underlying configuration file, if present. This is the mount
+    /// point of the hugetlbfs that DPDK allocates memory from.
+    pub fn huge_dir(&self) -> Option<String> {
+        // FIXME: this function should return a Result.
+        let huge_dir: &str = self.0["dpdk"]["huge_dir"].as_str()?;
+        if !::std::path::Path::new(huge_dir).is_dir() {
+            panic!("Hugepage directory {:?} does not exist", huge_dir);
+        }
+        Some(huge_dir.to_string())
+    }
+
+    /// Reads the "PCI allow list" parameter from the underlying configuration file. These are the addresses of the PCI
+    /// devices that DPDK may probe, in the "domain:bus:device.function" or "bus:device.function" format. Each may be
+    /// followed by device arguments, after a comma.
+    pub fn pci_allow_list(&self) -> Vec<String> {
+        // FIXME: this function should return a Result.
+        let mut pci_addrs: Vec<String> = Vec::new();
+        if let Some(pci_addrs_obj) = self.0["dpdk"]["pci_allow"].as_vec() {
+            for pci_addr_obj in pci_addrs_obj {
+                let pci_addr: &str = pci_addr_obj
+                    .as_str()
+                    .ok_or_else(|| anyhow::format_err!("Couldn't parse PCI address in config"))
+                    .unwrap();
+                if !Self::is_pci_addr(pci_addr.split(',').next().unwrap_or_default()) {
+                    panic!("Invalid PCI address {:?}", pci_addr);
+                }
+                pci_addrs.push(pci_addr.to_string());
+            }
+        }
+        pci_addrs
+    }
+
+    /// Reads the "file prefix" parameter from the underlying configuration file, if present. This names the runtime
+    /// directory and hugepage files of DPDK, which secondary processes should be given to attach to this process.
+    pub fn file_prefix(&self) -> Option<String> {
+        // FIXME: this function should return a Result.
+        let file_prefix: &str = self.0["dpdk"]["file_prefix"].as_str()?;
+        if file_prefix.is_empty() || file_prefix.contains('/') {
+            panic!("Invalid file prefix {:?}", file_prefix);
+        }
+        Some(file_prefix.to_string())
+    }
+
+    /// Reads the "multi-process" parameter from the underlying configuration file. When set, DPDK is initialized so
+    /// that secondary processes (e.g. `dpdk-proc-info` or statistics collectors) may attach to its memory pools and
+    /// ports while the application runs.
+    pub fn multi_process(&self) -> bool {
+        self.0["dpdk"]["multi_process"].as_bool().unwrap_or(false)
+    }
+
+    /// Reads the "number of MBufs" parameter from the underlying configuration file, if present. This is the number of
+    /// buffers in each memory pool of an instance, and in each pool of a receive queue.
+    pub fn num_mbufs(&self) -> Option<usize> {
+        // FIXME: this function should return a Result.
+        let num_mbufs: i64 = self.0["dpdk"]["num_mbufs"].as_i64()?;
+        if num_mbufs < MIN_NUM_MBUFS as i64 || num_mbufs > u32::MAX as i64 {
+            panic!("Invalid number of MBufs {}", num_mbufs);
+        }
+        Some(num_mbufs as usize)
+    }
+
+    /// Reads the "MBuf size" parameter from the underlying configuration file, if present. This is the size of the data
+    /// room of the MBufs that hold bodies and received frames, headroom included. It is raised to fit the largest frame
+    /// if needed.
+    pub fn mbuf_size(&self) -> Option<usize> {
+        // FIXME: this function should return a Result.
+        let mbuf_size: i64 = self.0["dpdk"]["mbuf_size"].as_i64()?;
+        if mbuf_size <= RTE_PKTMBUF_HEADROOM as i64 || mbuf_size > u16::MAX as i64 {
+            panic!("Invalid MBuf size {}", mbuf_size);
+        }
+        Some(mbuf_size as usize)
+    }
+
+    /// Checks whether `s` is a PCI address, in the "domain:bus:device.function" or "bus:device.function" format.
+    fn is_pci_addr(s: &str) -> bool {
+        let is_hex = |s: &str, len: usize| -> bool { s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit()) };
+        let (location, function): (&str, &str) = match s.rsplit_once('.') {
+            Some(parts) => parts,
+            None => return false,
+        };
+        let fields: Vec<&str> = location.split(':').collect();
+        let (domain, bus, device): (&str, &str, &str) = match fields[..] {
+            [domain, bus, device] => (domain, bus, device),
+            [bus, device] => ("0000", bus, device),
+            _ => return false,
+        };
+        is_hex(domain, 4) && is_hex(bus, 2) && is_hex(device, 2) && is_hex(function, 1)
+    }
+
+    /// Reads the "ports" parameter from the underlying configuration file. Each port is named as DPDK knows it (e.g.
+    /// its PCI address), and optionally has an IPv4 address of its own, which becomes a secondary address of the LibOS.
+    /// Packets sent from that address leave through that port, and all other packets through the first one. If no port
+    /// is listed, the first available one is used.
+    pub fn ports(&self) -> Vec<(String, Option<Ipv4Addr>)> {
+        // FIXME: this function should return a Result.
+        let mut ports: Vec<(String, Option<Ipv4Addr>)> = Vec::new();
+        if let Some(ports_obj) = self.0["catnip"]["ports"].as_vec() {
+            for port_obj in ports_obj {
+                let name: String = port_obj["name"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::format_err!("Couldn't find port name in config"))
+                    .unwrap()
+                    .to_string();
+                let ipv4_addr: Option<Ipv4Addr> = port_obj["ipv4_addr"].as_str().map(|addr| addr.parse().unwrap());
+                ports.push((name, ipv4_addr));
+            }
         }
+        ports
+    }
+
+    /// Reads the "bond" parameter from the underlying configuration file, if present, and returns the names of the
+    /// ports that are bonded. These are driven as a single port, which fails over to the next member when the link of
+    /// the active one goes down. Only the "active-backup" mode is supported.
+    pub fn bond(&self) -> Option<Vec<String>> {
+        // FIXME: this function should return a Result.
+        let bond_obj: &Yaml = &self.0["catnip"]["bond"];
+        if bond_obj.is_badvalue() {
+            return None;
+        }
+        match bond_obj["mode"].as_str() {
+            Some("active-backup") => (),
+            Some(mode) => panic!("unsupported bonding mode {:?}", mode),
+            None => panic!("Couldn't find bonding mode in config"),
+        }
+        let members: Vec<String> = match bond_obj["members"].as_vec() {
+            Some(members_obj) => members_obj
+                .iter()
+                .map(|member_obj| {
+                    member_obj
+                        .as_str()
+                        .ok_or_else(|| anyhow::format_err!("Couldn't parse bond member in config"))
+                        .unwrap()
+                        .to_string()
+                })
+                .collect(),
+            None => panic!("Couldn't find bond members in config"),
+        };
+        if members.len() < 2 {
+            panic!("A bond needs at least two members");
+        }
+        Some(members)
+    }
+
+    /// Reads the "virtio-user" parameter from the underlying configuration file, if present, and returns the arguments
+    /// of the virtio-user port that it describes. This port attaches to a vhost-user switch (e.g. OVS-DPDK or VPP)
+    /// through the UNIX socket at "path", so that no physical NIC needs to be passed through. The optional "queue_size"
+    /// sets the number of descriptors of its rings, "server" makes us create the socket rather than the switch, and
+    /// "link_addr" sets its link address, which is random otherwise.
+    pub fn virtio_user(&self) -> Option<String> {
+        // FIXME: this function should return a Result.
+        let virtio_user_obj: &Yaml = &self.0["catnip"]["virtio_user"];
+        if virtio_user_obj.is_badvalue() {
+            return None;
+        }
+        let path: &str = virtio_user_obj["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::format_err!("Couldn't find virtio-user socket path in config"))
+            .unwrap();
+        if path.contains(',') {
+            panic!("Invalid virtio-user socket path {:?}", path);
+        }
+        let mut devargs: String = format!("path={}", path);
+        if let Some(queue_size) = virtio_user_obj["queue_size"].as_i64() {
+            if queue_size <= 0 || queue_size > u16::MAX as i64 || (queue_size & (queue_size - 1)) != 0 {
+                panic!("Invalid virtio-user queue size {}", queue_size);
+            }
+            devargs.push_str(&format!(",queue_size={}", queue_size));
+        }
+        if virtio_user_obj["server"].as_bool().unwrap_or(false) {
+            devargs.push_str(",server=1");
+        }
+        if let Some(link_addr) = virtio_user_obj["link_addr"].as_str() {
+            let octets: [u8; 6] = MacAddress::parse_str(link_addr).unwrap().octets();
+            let octets: Vec<String> = octets.iter().map(|octet| format!("{:02x}", octet)).collect();
+            devargs.push_str(&format!(",mac={}", octets.join(":")));
+        }
+        Some(devargs)
     }
 
     /// Reads the "ARP Disable" parameter from the underlying configuration file.
@@ -75,30 +311,119 @@ impl Config {
         disable_arp
     }
 
-    /// Gets the "MTU" parameter from environment variables.
-    pub fn mtu(&self) -> u16 {
+    /// Reads the "congestion control" parameter from the underlying configuration file.
+    pub fn congestion_control(&self) -> Option<CongestionControlAlgorithm> {
         // FIXME: this function should return a Result.
-        ::std::env::var("MTU").unwrap().parse().unwrap()
+        match self.0["catnip"]["congestion_control"].as_str() {
+            Some("none") => Some(CongestionControlAlgorithm::None),
+            Some("cubic") => Some(CongestionControlAlgorithm::Cubic),
+            Some("dctcp") => Some(CongestionControlAlgorithm::Dctcp),
+            Some(algorithm) => panic!("unknown congestion control algorithm {:?}", algorithm),
+            None => None,
+        }
     }
 
-    /// Gets the "MSS" parameter from environment variables.
-    pub fn mss(&self) -> usize {
+    /// Reads the "pacing" parameter from the underlying configuration file.
+    pub fn pacing(&self) -> Option<bool> {
+        self.0["catnip"]["pacing"].as_bool()
+    }
+
+    /// Reads the "ECN" parameter from the underlying configuration file.
+    pub fn ecn(&self) -> Option<bool> {
+        self.0["catnip"]["ecn"].as_bool()
+    }
+
+    /// Reads the "ACK delay" parameter (in microseconds) from the underlying configuration file.
+    pub fn ack_delay_timeout(&self) -> Option<Duration> {
         // FIXME: this function should return a Result.
-        ::std::env::var("MSS").unwrap().parse().unwrap()
+        let ack_delay_us: i64 = self.0["catnip"]["ack_delay_us"].as_i64()?;
+        if ack_delay_us < 0 || ack_delay_us > MAX_ACK_DELAY_US {
+            panic!("Invalid ACK delay {}", ack_delay_us);
+        }
+        Some(Duration::from_micros(ack_delay_us as u64))
     }
 
-    /// Gets the "TCP_CHECKSUM_OFFLOAD" parameter from environment variables.
-    pub fn tcp_checksum_offload(&self) -> bool {
-        ::std::env::var("TCP_CHECKSUM_OFFLOAD").is_ok()
+    /// Reads the "ACK segments" parameter from the underlying configuration file.
+    pub fn ack_segments(&self) -> Option<u32> {
+        // FIXME: this function should return a Result.
+        let ack_segments: i64 = self.0["catnip"]["ack_segments"].as_i64()?;
+        if ack_segments <= 0 || ack_segments > u32::MAX as i64 {
+            panic!("Invalid number of ACK segments {}", ack_segments);
+        }
+        Some(ack_segments as u32)
+    }
+
+    /// Reads the "quick ACK" parameter from the underlying configuration file.
+    pub fn quickack(&self) -> Option<bool> {
+        self.0["catnip"]["quickack"].as_bool()
+    }
+
+    /// Reads the "TIME-WAIT reuse" parameter from the underlying configuration file.
+    pub fn time_wait_reuse(&self) -> Option<bool> {
+        self.0["catnip"]["time_wait_reuse"].as_bool()
+    }
+
+    /// Reads the "SYN cookies" parameter from the underlying configuration file.
+    pub fn syn_cookies(&self) -> Option<bool> {
+        self.0["catnip"]["syn_cookies"].as_bool()
+    }
+
+    /// Reads the "out-of-order max segments" parameter from the underlying configuration file.
+    pub fn out_of_order_max_segments(&self) -> Option<usize> {
+        self.0["catnip"]["out_of_order_max_segments"]
+            .as_i64()
+            .map(|n| n as usize)
     }
 
-    /// Gets the "UDP_CHECKSUM_OFFLOAD" parameter from environment variables.
-    pub fn udp_checksum_offload(&self) -> bool {
-        ::std::env::var("UDP_CHECKSUM_OFFLOAD").is_ok()
+    /// Reads the "out-of-order max bytes" parameter from the underlying configuration file.
+    pub fn out_of_order_max_bytes(&self) -> Option<usize> {
+        self.0["catnip"]["out_of_order_max_bytes"].as_i64().map(|n| n as usize)
     }
 
-    /// Gets the "USE_JUMBO" parameter from environment variables.
-    pub fn use_jumbo_frames(&self) -> bool {
-        ::std::env::var("USE_JUMBO").is_ok()
+    /// Reads the "out-of-order drop policy" parameter from the underlying configuration file.
+    pub fn out_of_order_drop_policy(&self) -> Option<OutOfOrderDropPolicy> {
+        // FIXME: this function should return a Result.
+        match self.0["catnip"]["out_of_order_drop_policy"].as_str() {
+            Some("drop_highest") => Some(OutOfOrderDropPolicy::DropHighest),
+            Some("drop_new") => Some(OutOfOrderDropPolicy::DropNew),
+            Some(policy) => panic!("unknown out-of-order drop policy {:?}", policy),
+            None => None,
+        }
+    }
+
+    /// Reads the "hardware timestamps" parameter from the underlying configuration file. If set, the NIC timestamps
+    /// received frames, and its clock timestamps transmitted ones.
+    pub fn hw_timestamps(&self) -> bool {
+        self.0["catnip"]["hw_timestamps"].as_bool().unwrap_or(false)
+    }
+
+    /// Gets the "MSS" parameter from environment variables, or else from the underlying configuration file. This
+    /// overrides the MSS derived from the MTU, e.g. to leave room for the headers added by tunnels.
+    pub fn mss(&self) -> Option<usize> {
+        // FIXME: this function should return a Result.
+        match ::std::env::var("MSS") {
+            Ok(mss) => Some(mss.parse().unwrap()),
+            Err(_) => self.0["catnip"]["mss"].as_i64().map(|mss| mss as usize),
+        }
+    }
+
+    /// Gets the "TCP_CHECKSUM_OFFLOAD" parameter from environment variables, or else the "TCP checksum offload"
+    /// parameter from the underlying configuration file. If neither is set, it is detected from the capabilities of the
+    /// NIC.
+    pub fn tcp_checksum_offload(&self) -> Option<bool> {
+        match ::std::env::var("TCP_CHECKSUM_OFFLOAD") {
+            Ok(_) => Some(true),
+            Err(_) => self.0["catnip"]["tcp_checksum_offload"].as_bool(),
+        }
+    }
+
+    /// Gets the "UDP_CHECKSUM_OFFLOAD" parameter from environment variables, or else the "UDP checksum offload"
+    /// parameter from the underlying configuration file. If neither is set, it is detected from the capabilities of the
+    /// NIC.
+    pub fn udp_checksum_offload(&self) -> Option<bool> {
+        match ::std::env::var("UDP_CHECKSUM_OFFLOAD") {
+            Ok(_) => Some(true),
+            Err(_) => self.0["catnip"]["udp_checksum_offload"].as_bool(),
+        }
     }
 }
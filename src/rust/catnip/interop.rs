@@ -16,11 +16,14 @@ use crate::{
     },
     runtime::{
         memory::MemoryRuntime,
+        network::types::SocketAddress,
         types::{
             demi_accept_result_t,
             demi_opcode_t,
+            demi_pktinfo_t,
             demi_qr_value_t,
             demi_qresult_t,
+            DEMI_QR_FLAG_PKTINFO,
         },
         QDesc,
     },
@@ -37,6 +40,8 @@ pub fn pack_result(rt: Rc<DPDKRuntime>, result: OperationResult, qd: QDesc, qt:
             qr_opcode: demi_opcode_t::DEMI_OPC_CONNECT,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
         OperationResult::Accept(new_qd) => {
@@ -51,6 +56,8 @@ pub fn pack_result(rt: Rc<DPDKRuntime>, result: OperationResult, qd: QDesc, qt:
                 qr_opcode: demi_opcode_t::DEMI_OPC_ACCEPT,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: 0,
                 qr_value,
             }
         },
@@ -58,11 +65,49 @@ pub fn pack_result(rt: Rc<DPDKRuntime>, result: OperationResult, qd: QDesc, qt:
             qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
             qr_qd: qd.into(),
             qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
             qr_value: unsafe { mem::zeroed() },
         },
-        OperationResult::Pop(addr, bytes) => match rt.into_sgarray(bytes) {
+        OperationResult::Splice(_) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_SPLICE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Ping(rtt) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_PING,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                rtt: rtt.as_nanos() as i64,
+            },
+        },
+        OperationResult::Link(state) => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_LINK,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: demi_qr_value_t {
+                link_up: state.is_up() as u32,
+            },
+        },
+        OperationResult::Close => demi_qresult_t {
+            qr_opcode: demi_opcode_t::DEMI_OPC_CLOSE,
+            qr_qd: qd.into(),
+            qr_qt: qt,
+            qr_flags: 0,
+            qr_ret: 0,
+            qr_value: unsafe { mem::zeroed() },
+        },
+        OperationResult::Pop(addr, bytes, mut flags, info) => match rt.into_sgarray(bytes) {
             Ok(mut sga) => {
-                if let Some(endpoint) = addr {
+                if let Some(SocketAddress::Inet(endpoint)) = addr {
                     let saddr: SockAddrIn = {
                         // TODO: check the following byte order conversion.
                         SockAddrIn {
@@ -74,11 +119,17 @@ pub fn pack_result(rt: Rc<DPDKRuntime>, result: OperationResult, qd: QDesc, qt:
                     };
                     sga.sga_addr = unsafe { mem::transmute::<SockAddrIn, SockAddr>(saddr) };
                 }
+                if let Some(info) = info {
+                    sga.sga_pktinfo = demi_pktinfo_t::from(info);
+                    flags |= DEMI_QR_FLAG_PKTINFO;
+                }
                 let qr_value = demi_qr_value_t { sga };
                 demi_qresult_t {
                     qr_opcode: demi_opcode_t::DEMI_OPC_POP,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: flags,
+                    qr_ret: 0,
                     qr_value,
                 }
             },
@@ -88,6 +139,8 @@ pub fn pack_result(rt: Rc<DPDKRuntime>, result: OperationResult, qd: QDesc, qt:
                     qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                     qr_qd: qd.into(),
                     qr_qt: qt,
+                    qr_flags: 0,
+                    qr_ret: e.errno as i64,
                     qr_value: unsafe { mem::zeroed() },
                 }
             },
@@ -98,6 +151,8 @@ pub fn pack_result(rt: Rc<DPDKRuntime>, result: OperationResult, qd: QDesc, qt:
                 qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
                 qr_qd: qd.into(),
                 qr_qt: qt,
+                qr_flags: 0,
+                qr_ret: e.errno as i64,
                 qr_value: unsafe { mem::zeroed() },
             }
         },
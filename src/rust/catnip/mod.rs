@@ -14,7 +14,10 @@ use self::{
     runtime::DPDKRuntime,
 };
 use crate::{
-    demikernel::config::Config,
+    demikernel::{
+        config::Config,
+        libos::network::NetworkLibOSTrait,
+    },
     inetstack::{
         operations::OperationResult,
         InetStack,
@@ -22,7 +25,22 @@ use crate::{
     runtime::{
         fail::Fail,
         libdpdk::load_mlx_driver,
-        memory::MemoryRuntime,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::IoQueueLimitStats,
         timer::{
             Timer,
             TimerRc,
@@ -37,16 +55,29 @@ use crate::{
     scheduler::{
         Scheduler,
         SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
     },
 };
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
 use ::std::{
-    net::SocketAddrV4,
+    future::Future,
+    net::{
+        Ipv4Addr,
+        Shutdown,
+        SocketAddrV4,
+    },
     ops::{
         Deref,
         DerefMut,
     },
+    pin::Pin,
     rc::Rc,
-    time::Instant,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 #[cfg(feature = "profiler")]
@@ -74,19 +105,44 @@ impl CatnipLibOS {
         let rt: Rc<DPDKRuntime> = Rc::new(DPDKRuntime::new(
             config.local_ipv4_addr(),
             &config.eal_init_args(),
+            config.ports(),
+            config.bond(),
+            config.virtio_user(),
+            config.num_mbufs(),
+            config.mbuf_size(),
+            config.multi_process(),
             config.arp_table(),
             config.disable_arp(),
-            config.use_jumbo_frames(),
+            config.arp_announcements(),
+            config.routes(),
             config.mtu(),
             config.mss(),
             config.tcp_checksum_offload(),
             config.udp_checksum_offload(),
+            config.hw_timestamps(),
+            config.congestion_control(),
+            config.pacing(),
+            config.ecn(),
+            config.ack_delay_timeout(),
+            config.ack_segments(),
+            config.quickack(),
+            config.time_wait_reuse(),
+            config.syn_cookies(),
+            config.out_of_order_max_segments(),
+            config.out_of_order_max_bytes(),
+            config.out_of_order_drop_policy(),
+            config.shard(),
+            config.gro(),
         ));
-        let now: Instant = Instant::now();
-        let clock: TimerRc = TimerRc(Rc::new(Timer::new(now)));
         let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_budget(config.poll_budget());
+        if let Some(policy) = config.idle_policy() {
+            scheduler.set_idle_policy(policy);
+        }
+        scheduler.set_poll_timing(config.poll_timing());
+        let clock: TimerRc = TimerRc(Rc::new(Timer::from_scheduler(&scheduler)));
         let rng_seed: [u8; 32] = [0; 32];
-        let inetstack: InetStack = InetStack::new(
+        let mut inetstack: InetStack = InetStack::new(
             rt.clone(),
             scheduler.clone(),
             clock,
@@ -98,6 +154,25 @@ impl CatnipLibOS {
             rt.arp_options.clone(),
         )
         .unwrap();
+        inetstack.set_queue_limits(config.queue_limits());
+        for ipv4_addr in config.secondary_ipv4_addrs() {
+            inetstack
+                .add_ipv4_addr(ipv4_addr)
+                .expect("invalid secondary IPv4 address in config");
+        }
+        for ipv4_addr in config.ports().into_iter().filter_map(|(_, ipv4_addr)| ipv4_addr) {
+            if ipv4_addr != rt.ipv4_addr {
+                inetstack
+                    .add_ipv4_addr(ipv4_addr)
+                    .expect("invalid port IPv4 address in config");
+            }
+        }
+        if config.dhcp() {
+            inetstack.start_dhcp().expect("failed to start DHCP client");
+            inetstack
+                .wait_for_dhcp_lease(config.dhcp_timeout())
+                .expect("failed to get a DHCP lease");
+        }
         CatnipLibOS {
             inetstack,
             scheduler,
@@ -112,23 +187,30 @@ impl CatnipLibOS {
         #[cfg(feature = "profiler")]
         timer!("catnip::push");
         trace!("push(): qd={:?}", qd);
+
         match self.rt.clone_sgarray(sga) {
             Ok(buf) => {
                 if buf.len() == 0 {
                     return Err(Fail::new(libc::EINVAL, "zero-length buffer"));
                 }
                 let future = self.do_push(qd, buf)?;
-                let handle: SchedulerHandle = match self.scheduler.insert(future) {
-                    Some(handle) => handle,
-                    None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
-                };
-                let qt: QToken = handle.into_raw().into();
+                let qt: QToken = self.insert_operation(future, &[qd])?;
                 Ok(qt)
             },
             Err(e) => Err(e),
         }
     }
 
+    pub fn pushv(&mut self, qd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("catnip::pushv");
+        trace!("pushv(): qd={:?}, nsgas={:?}", qd, sgas.len());
+        let bufs: Vec<DemiBuffer> = self.rt.clone_sgarrays(sgas)?;
+        let future = self.do_pushv(qd, bufs)?;
+        let qt: QToken = self.insert_operation(future, &[qd])?;
+        Ok(qt)
+    }
+
     pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
         #[cfg(feature = "profiler")]
         timer!("catnip::pushto");
@@ -139,11 +221,7 @@ impl CatnipLibOS {
                     return Err(Fail::new(libc::EINVAL, "zero-length buffer"));
                 }
                 let future = self.do_pushto(qd, buf, to)?;
-                let handle: SchedulerHandle = match self.scheduler.insert(future) {
-                    Some(handle) => handle,
-                    None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
-                };
-                let qt: QToken = handle.into_raw().into();
+                let qt: QToken = self.insert_operation(future, &[qd])?;
                 Ok(qt)
             },
             Err(e) => Err(e),
@@ -158,7 +236,7 @@ impl CatnipLibOS {
     }
 
     pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
-        let (qd, r): (QDesc, OperationResult) = self.take_operation(handle);
+        let (qd, r): (QDesc, OperationResult) = self.take_operation(handle, qt);
         Ok(pack_result(self.rt.clone(), r, qd, qt.into()))
     }
 
@@ -167,10 +245,28 @@ impl CatnipLibOS {
         self.rt.alloc_sgarray(size)
     }
 
+    /// Registers application-owned memory as a scatter-gather array.
+    pub fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        self.rt.register_sgarray(data, len)
+    }
+
     /// Releases a scatter-gather array.
     pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
         self.rt.free_sgarray(sga)
     }
+
+    /// Tears down the target LibOS. All queue descriptors are closed, pending frames are flushed, pending operations
+    /// are canceled, and DPDK resources are released. Buffers that were handed out to the application must be released
+    /// beforehand.
+    pub fn teardown(mut self) -> Result<(), Fail> {
+        trace!("teardown()");
+        let result: Result<(), Fail> = self.inetstack.teardown();
+
+        // Release the network stack before DPDK, because it holds buffers that live in DPDK memory pools.
+        let rt: Rc<DPDKRuntime> = self.rt.clone();
+        drop(self);
+        result.and(rt.teardown())
+    }
 }
 
 //==============================================================================
@@ -192,3 +288,252 @@ impl DerefMut for CatnipLibOS {
         &mut self.inetstack
     }
 }
+
+/// Network LibOS Trait Implementation for Catnip LibOS
+impl NetworkLibOSTrait for CatnipLibOS {
+    fn socket(&mut self, domain: libc::c_int, socket_type: libc::c_int, protocol: libc::c_int) -> Result<QDesc, Fail> {
+        InetStack::socket(self, domain, socket_type, protocol)
+    }
+
+    fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
+        InetStack::bind(self, sockqd, local)
+    }
+
+    fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+        InetStack::listen(self, sockqd, backlog)
+    }
+
+    fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::accept(self, sockqd)
+    }
+
+    fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
+        InetStack::connect(self, sockqd, remote)
+    }
+
+    fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        InetStack::timedconnect(self, sockqd, remote, deadline)
+    }
+
+    fn close(&mut self, sockqd: QDesc) -> Result<(), Fail> {
+        InetStack::close(self, sockqd)
+    }
+
+    fn async_close(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::async_close(self, sockqd)
+    }
+
+    fn dup(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        InetStack::dup(self, sockqd)
+    }
+
+    fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        InetStack::shutdown(self, sockqd, how)
+    }
+
+    fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        InetStack::local_addr(self, sockqd)
+    }
+
+    fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        InetStack::remote_addr(self, sockqd)
+    }
+
+    fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        InetStack::get_socket_option(self, sockqd, opt)
+    }
+
+    fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        InetStack::set_socket_option(self, sockqd, opt)
+    }
+
+    fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        InetStack::truncations(self, sockqd)
+    }
+
+    fn backlog_overflows(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        InetStack::backlog_overflows(self, sockqd)
+    }
+
+    fn tcp_info(&self, sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        InetStack::tcp_info(self, sockqd)
+    }
+
+    fn timestamps(&self, sockqd: QDesc) -> Result<Timestamps, Fail> {
+        InetStack::timestamps(self, sockqd)
+    }
+
+    fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::join_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::leave_multicast_group(self, sockqd, group, iface)
+    }
+
+    fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        CatnipLibOS::push(self, sockqd, sga)
+    }
+
+    fn pushv(&mut self, sockqd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        CatnipLibOS::pushv(self, sockqd, sgas)
+    }
+
+    fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
+        CatnipLibOS::pushto(self, sockqd, sga, to)
+    }
+
+    fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::pop(self, sockqd)
+    }
+
+    fn pop_sized(&mut self, sockqd: QDesc, size: usize) -> Result<QToken, Fail> {
+        InetStack::pop_sized(self, sockqd, size)
+    }
+
+    fn timedpop(&mut self, sockqd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        InetStack::timedpop(self, sockqd, size, deadline)
+    }
+
+    fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        InetStack::splice(self, srcqd, dstqd)
+    }
+
+    fn ping(&mut self, addr: Ipv4Addr, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        InetStack::ping(self, addr, timeout)
+    }
+
+    fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        InetStack::arp_entries(self)
+    }
+
+    fn arp_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Result<(), Fail> {
+        InetStack::arp_insert(self, ipv4_addr, link_addr)
+    }
+
+    fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        InetStack::arp_pending_drops(self)
+    }
+
+    fn arp_flush(&mut self) -> Result<(), Fail> {
+        InetStack::arp_flush(self)
+    }
+
+    fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        InetStack::ipv4_addrs(self)
+    }
+
+    fn add_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::add_ipv4_addr(self, ipv4_addr)
+    }
+
+    fn remove_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        InetStack::remove_ipv4_addr(self, ipv4_addr)
+    }
+
+    fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        InetStack::dhcp_lease(self)
+    }
+
+    fn link_events(&mut self) -> Result<QDesc, Fail> {
+        InetStack::link_events(self)
+    }
+
+    fn link_state(&self) -> Result<LinkState, Fail> {
+        InetStack::link_state(self)
+    }
+
+    fn routes(&self) -> Result<Vec<Route>, Fail> {
+        InetStack::routes(self)
+    }
+
+    fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        InetStack::add_route(self, route)
+    }
+
+    fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        InetStack::remove_route(self, destination, prefix_len)
+    }
+
+    fn set_arp_policy(
+        &mut self,
+        cache_ttl: Duration,
+        request_timeout: Duration,
+        retry_count: usize,
+    ) -> Result<(), Fail> {
+        InetStack::set_arp_policy(self, cache_ttl, request_timeout, retry_count)
+    }
+
+    fn add_flow_rule(&mut self, rule: FlowRule) -> Result<u32, Fail> {
+        trace!("add_flow_rule(): rule={:?}", rule);
+        self.rt.add_flow_rule(rule)
+    }
+
+    fn remove_flow_rule(&mut self, id: u32) -> Result<(), Fail> {
+        trace!("remove_flow_rule(): id={:?}", id);
+        self.rt.remove_flow_rule(id)
+    }
+
+    fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        self.scheduler.spawn(future)
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        self.scheduler.sleep_until(deadline)
+    }
+
+    fn poll(&mut self) {
+        InetStack::poll_bg_work(self)
+    }
+
+    fn park(&mut self, timeout: Option<Duration>) {
+        // Incoming packets are only noticed when the network stack is polled, and the device runs without interrupts,
+        // so there is nothing to park on.
+        self.scheduler.park(timeout)
+    }
+
+    fn tasks(&self) -> Vec<TaskInfo> {
+        self.scheduler.tasks()
+    }
+
+    fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.inetstack.queue_limit_stats()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn event_fd(&self) -> Result<RawFd, Fail> {
+        // Incoming packets are only noticed when the network stack is polled, so there is no event to wait on.
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "event file descriptors are not supported on this libos",
+        ))
+    }
+
+    fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
+        CatnipLibOS::schedule(self, qt)
+    }
+
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        InetStack::take_completed(self, pred)
+    }
+
+    fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
+        CatnipLibOS::pack_result(self, handle, qt)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        CatnipLibOS::sgaalloc(self, size)
+    }
+
+    fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        CatnipLibOS::sgaregister(self, data, len)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        CatnipLibOS::sgafree(self, sga)
+    }
+
+    fn teardown(self: Box<Self>) -> Result<(), Fail> {
+        CatnipLibOS::teardown(*self)
+    }
+}
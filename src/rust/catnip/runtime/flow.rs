@@ -0,0 +1,222 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::DPDKRuntime;
+use crate::runtime::{
+    fail::Fail,
+    libdpdk::{
+        rte_flow,
+        rte_flow_action,
+        rte_flow_action_queue,
+        rte_flow_action_type,
+        rte_flow_action_type_RTE_FLOW_ACTION_TYPE_DROP as RTE_FLOW_ACTION_TYPE_DROP,
+        rte_flow_action_type_RTE_FLOW_ACTION_TYPE_END as RTE_FLOW_ACTION_TYPE_END,
+        rte_flow_action_type_RTE_FLOW_ACTION_TYPE_QUEUE as RTE_FLOW_ACTION_TYPE_QUEUE,
+        rte_flow_attr,
+        rte_flow_create,
+        rte_flow_destroy,
+        rte_flow_error,
+        rte_flow_item,
+        rte_flow_item_ipv4,
+        rte_flow_item_tcp,
+        rte_flow_item_type,
+        rte_flow_item_type_RTE_FLOW_ITEM_TYPE_END as RTE_FLOW_ITEM_TYPE_END,
+        rte_flow_item_type_RTE_FLOW_ITEM_TYPE_ETH as RTE_FLOW_ITEM_TYPE_ETH,
+        rte_flow_item_type_RTE_FLOW_ITEM_TYPE_IPV4 as RTE_FLOW_ITEM_TYPE_IPV4,
+        rte_flow_item_type_RTE_FLOW_ITEM_TYPE_TCP as RTE_FLOW_ITEM_TYPE_TCP,
+        rte_flow_item_type_RTE_FLOW_ITEM_TYPE_UDP as RTE_FLOW_ITEM_TYPE_UDP,
+        rte_flow_item_udp,
+    },
+    network::types::{
+        FlowAction,
+        FlowProtocol,
+        FlowRule,
+    },
+};
+use ::std::{
+    cell::RefMut,
+    collections::HashMap,
+    ffi::{
+        c_void,
+        CStr,
+    },
+    mem::MaybeUninit,
+    net::Ipv4Addr,
+    ptr,
+};
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Flow Rule Functions for DPDK Runtime
+impl DPDKRuntime {
+    /// Installs a flow rule on every port, and returns its identifier. Rules that some port cannot enforce in hardware
+    /// are rejected.
+    pub fn add_flow_rule(&self, rule: FlowRule) -> Result<u32, Fail> {
+        if let FlowAction::Queue(queue_id) = rule.action {
+            if queue_id >= self.num_queues {
+                return Err(Fail::new(libc::EINVAL, "invalid receive queue"));
+            }
+        }
+
+        // Safety: all of these structures are plain C structures, for which zero is a valid value.
+        let mut attr: rte_flow_attr = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut ipv4_spec: rte_flow_item_ipv4 = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut ipv4_mask: rte_flow_item_ipv4 = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut tcp_spec: rte_flow_item_tcp = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut tcp_mask: rte_flow_item_tcp = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut udp_spec: rte_flow_item_udp = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut udp_mask: rte_flow_item_udp = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut queue: rte_flow_action_queue = unsafe { MaybeUninit::zeroed().assume_init() };
+        attr.set_ingress(1);
+
+        // Fields that are masked out match any value.
+        (ipv4_spec.hdr.src_addr, ipv4_mask.hdr.src_addr) = Self::match_addr(rule.remote_addr);
+        (ipv4_spec.hdr.dst_addr, ipv4_mask.hdr.dst_addr) = Self::match_addr(rule.local_addr);
+        let l4_item: rte_flow_item = match rule.protocol {
+            FlowProtocol::Tcp => {
+                (tcp_spec.hdr.src_port, tcp_mask.hdr.src_port) = Self::match_port(rule.remote_port);
+                (tcp_spec.hdr.dst_port, tcp_mask.hdr.dst_port) = Self::match_port(rule.local_port);
+                Self::flow_item(RTE_FLOW_ITEM_TYPE_TCP, &tcp_spec, &tcp_mask)
+            },
+            FlowProtocol::Udp => {
+                (udp_spec.hdr.src_port, udp_mask.hdr.src_port) = Self::match_port(rule.remote_port);
+                (udp_spec.hdr.dst_port, udp_mask.hdr.dst_port) = Self::match_port(rule.local_port);
+                Self::flow_item(RTE_FLOW_ITEM_TYPE_UDP, &udp_spec, &udp_mask)
+            },
+        };
+        let pattern: [rte_flow_item; 4] = [
+            Self::flow_item::<u8>(RTE_FLOW_ITEM_TYPE_ETH, ptr::null(), ptr::null()),
+            Self::flow_item(RTE_FLOW_ITEM_TYPE_IPV4, &ipv4_spec, &ipv4_mask),
+            l4_item,
+            Self::flow_item::<u8>(RTE_FLOW_ITEM_TYPE_END, ptr::null(), ptr::null()),
+        ];
+
+        let action: rte_flow_action = match rule.action {
+            FlowAction::Queue(queue_id) => {
+                queue.index = queue_id;
+                Self::flow_action(RTE_FLOW_ACTION_TYPE_QUEUE, &queue)
+            },
+            FlowAction::Drop => Self::flow_action::<u8>(RTE_FLOW_ACTION_TYPE_DROP, ptr::null()),
+        };
+        let actions: [rte_flow_action; 2] = [action, Self::flow_action::<u8>(RTE_FLOW_ACTION_TYPE_END, ptr::null())];
+
+        // The rule is removed from the ports on which it was installed if any other port rejects it.
+        let mut flows: Vec<(u16, *mut rte_flow)> = Vec::with_capacity(self.port_ids.len());
+        for &port_id in &self.port_ids {
+            // Safety: the pattern and actions are terminated, and the structures that they point to outlive this call.
+            let mut error: rte_flow_error = unsafe { MaybeUninit::zeroed().assume_init() };
+            let flow: *mut rte_flow =
+                unsafe { rte_flow_create(port_id, &attr, pattern.as_ptr(), actions.as_ptr(), &mut error) };
+            if flow.is_null() {
+                let e: Fail = Self::flow_error(unsafe { dpdk_rs::rte_errno() }, &error);
+                for (port_id, flow) in flows {
+                    if let Err(e) = Self::destroy_flow(port_id, flow) {
+                        warn!(
+                            "add_flow_rule(): failed to roll back flow rule on port {} ({:?})",
+                            port_id, e
+                        );
+                    }
+                }
+                return Err(e);
+            }
+            flows.push((port_id, flow));
+        }
+
+        let id: u32 = self.next_flow_id.get();
+        self.next_flow_id.set(id.wrapping_add(1));
+        self.flows.borrow_mut().insert(id, flows);
+        Ok(id)
+    }
+
+    /// Removes a flow rule that was installed on the ports.
+    pub fn remove_flow_rule(&self, id: u32) -> Result<(), Fail> {
+        let mut all_flows: RefMut<HashMap<u32, Vec<(u16, *mut rte_flow)>>> = self.flows.borrow_mut();
+        let flows: &mut Vec<(u16, *mut rte_flow)> = match all_flows.get_mut(&id) {
+            Some(flows) => flows,
+            None => return Err(Fail::new(libc::ENOENT, "no such flow rule")),
+        };
+        // The rule is only forgotten once it is gone from every port, so that removing it can be retried otherwise.
+        while let Some(&(port_id, flow)) = flows.last() {
+            Self::destroy_flow(port_id, flow)?;
+            flows.pop();
+        }
+        all_flows.remove(&id);
+        Ok(())
+    }
+
+    /// Removes all flow rules that were installed on the ports by this instance. Every rule is attempted, and the
+    /// last failure, if any, is returned.
+    pub fn remove_flow_rules(&self) -> Result<(), Fail> {
+        let ids: Vec<u32> = self.flows.borrow().keys().copied().collect();
+        let mut result: Result<(), Fail> = Ok(());
+        for id in ids {
+            if let Err(e) = self.remove_flow_rule(id) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Destroys the handle of a flow rule on a port.
+    fn destroy_flow(port_id: u16, flow: *mut rte_flow) -> Result<(), Fail> {
+        let mut error: rte_flow_error = unsafe { MaybeUninit::zeroed().assume_init() };
+        let ret: libc::c_int = unsafe { rte_flow_destroy(port_id, flow, &mut error) };
+        if ret != 0 {
+            return Err(Self::flow_error(-ret, &error));
+        }
+        Ok(())
+    }
+
+    /// Builds the specification and mask that match an IPv4 address, if any.
+    fn match_addr(addr: Option<Ipv4Addr>) -> (u32, u32) {
+        match addr {
+            Some(addr) => (u32::from(addr).to_be(), u32::MAX),
+            None => (0, 0),
+        }
+    }
+
+    /// Builds the specification and mask that match a port number, if any.
+    fn match_port(port: Option<u16>) -> (u16, u16) {
+        match port {
+            Some(port) => (port.to_be(), u16::MAX),
+            None => (0, 0),
+        }
+    }
+
+    /// Builds an item of the pattern of a flow rule.
+    fn flow_item<T>(item_type: rte_flow_item_type, spec: *const T, mask: *const T) -> rte_flow_item {
+        let mut item: rte_flow_item = unsafe { MaybeUninit::zeroed().assume_init() };
+        item.type_ = item_type;
+        item.spec = spec as *const c_void;
+        item.mask = mask as *const c_void;
+        item
+    }
+
+    /// Builds an action of a flow rule.
+    fn flow_action<T>(action_type: rte_flow_action_type, conf: *const T) -> rte_flow_action {
+        let mut action: rte_flow_action = unsafe { MaybeUninit::zeroed().assume_init() };
+        action.type_ = action_type;
+        action.conf = conf as *const c_void;
+        action
+    }
+
+    /// Converts an error that DPDK reported on a flow rule.
+    fn flow_error(errno: libc::c_int, error: &rte_flow_error) -> Fail {
+        let errno: libc::c_int = if errno > 0 { errno } else { libc::EINVAL };
+        if error.message.is_null() {
+            return Fail::new(errno, "failed to apply flow rule");
+        }
+        // Safety: DPDK sets the message to a static, null-terminated string.
+        let message: &CStr = unsafe { CStr::from_ptr(error.message) };
+        Fail::new(
+            errno,
+            &format!("failed to apply flow rule: {}", message.to_string_lossy()),
+        )
+    }
+}
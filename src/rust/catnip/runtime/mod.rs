@@ -1,6 +1,7 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod flow;
 pub mod memory;
 mod network;
 
@@ -12,52 +13,74 @@ use self::memory::{
     consts::DEFAULT_MAX_BODY_SIZE,
     MemoryManager,
 };
-use crate::runtime::{
-    libdpdk::{
-        rte_delay_us_block,
-        rte_eal_init,
-        rte_eth_conf,
-        rte_eth_dev_configure,
-        rte_eth_dev_count_avail,
-        rte_eth_dev_get_mtu,
-        rte_eth_dev_info_get,
-        rte_eth_dev_is_valid_port,
-        rte_eth_dev_set_mtu,
-        rte_eth_dev_start,
-        rte_eth_find_next_owned_by,
-        rte_eth_link,
-        rte_eth_link_get_nowait,
-        rte_eth_macaddr_get,
-        rte_eth_promiscuous_enable,
-        rte_eth_rss_ip,
-        rte_eth_rx_mq_mode_RTE_ETH_MQ_RX_RSS as RTE_ETH_MQ_RX_RSS,
-        rte_eth_rx_offload_tcp_cksum,
-        rte_eth_rx_offload_udp_cksum,
-        rte_eth_rx_queue_setup,
-        rte_eth_rxconf,
-        rte_eth_tx_mq_mode_RTE_ETH_MQ_TX_NONE as RTE_ETH_MQ_TX_NONE,
-        rte_eth_tx_offload_multi_segs,
-        rte_eth_tx_offload_tcp_cksum,
-        rte_eth_tx_offload_udp_cksum,
-        rte_eth_tx_queue_setup,
-        rte_eth_txconf,
-        rte_ether_addr,
-        RTE_ETHER_MAX_JUMBO_FRAME_LEN,
-        RTE_ETHER_MAX_LEN,
-        RTE_ETH_DEV_NO_OWNER,
-        RTE_ETH_LINK_FULL_DUPLEX,
-        RTE_ETH_LINK_UP,
-        RTE_PKTMBUF_HEADROOM,
-    },
-    network::{
-        config::{
-            ArpConfig,
-            TcpConfig,
-            UdpConfig,
+use crate::{
+    inetstack::protocols::ethernet2::ETHERNET2_HEADER_SIZE,
+    runtime::{
+        fail::Fail,
+        libdpdk::{
+            rte_delay_us_block,
+            rte_eal_cleanup,
+            rte_eal_init,
+            rte_eal_process_type,
+            rte_eth_conf,
+            rte_eth_dev_close,
+            rte_eth_dev_configure,
+            rte_eth_dev_count_avail,
+            rte_eth_dev_default_mac_addr_set,
+            rte_eth_dev_get_mtu,
+            rte_eth_dev_get_port_by_name,
+            rte_eth_dev_info_get,
+            rte_eth_dev_is_valid_port,
+            rte_eth_dev_rss_reta_update,
+            rte_eth_dev_rx_offload_name,
+            rte_eth_dev_set_mtu,
+            rte_eth_dev_start,
+            rte_eth_dev_stop,
+            rte_eth_find_next_owned_by,
+            rte_eth_link,
+            rte_eth_link_get_nowait,
+            rte_eth_macaddr_get,
+            rte_eth_promiscuous_enable,
+            rte_eth_read_clock,
+            rte_eth_rss_ip,
+            rte_eth_rss_reta_entry64,
+            rte_eth_rx_mq_mode_RTE_ETH_MQ_RX_RSS as RTE_ETH_MQ_RX_RSS,
+            rte_eth_rx_offload_tcp_cksum,
+            rte_eth_rx_offload_udp_cksum,
+            rte_eth_rx_queue_setup,
+            rte_eth_rxconf,
+            rte_eth_tx_mq_mode_RTE_ETH_MQ_TX_NONE as RTE_ETH_MQ_TX_NONE,
+            rte_eth_tx_offload_multi_segs,
+            rte_eth_tx_offload_tcp_cksum,
+            rte_eth_tx_offload_udp_cksum,
+            rte_eth_tx_queue_setup,
+            rte_eth_txconf,
+            rte_ether_addr,
+            rte_flow,
+            rte_mbuf_dyn_rx_timestamp_register,
+            rte_proc_type_t_RTE_PROC_PRIMARY as RTE_PROC_PRIMARY,
+            RTE_ETHER_MAX_LEN,
+            RTE_ETH_DEV_NO_OWNER,
+            RTE_ETH_LINK_FULL_DUPLEX,
+            RTE_ETH_LINK_UP,
+            RTE_PKTMBUF_HEADROOM,
+        },
+        network::{
+            config::{
+                ArpConfig,
+                TcpConfig,
+                UdpConfig,
+            },
+            types::{
+                CongestionControlAlgorithm,
+                MacAddress,
+                OutOfOrderDropPolicy,
+                Route,
+                Rss,
+            },
         },
-        types::MacAddress,
+        Runtime,
     },
-    Runtime,
 };
 use ::anyhow::{
     bail,
@@ -65,13 +88,63 @@ use ::anyhow::{
     Error,
 };
 use ::std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    cmp,
     collections::HashMap,
-    ffi::CString,
+    ffi::{
+        CStr,
+        CString,
+    },
     mem::MaybeUninit,
     net::Ipv4Addr,
-    time::Duration,
+    sync::{
+        Mutex,
+        MutexGuard,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Size of the CRC that trails Ethernet frames (in bytes).
+const ETHERNET2_CRC_SIZE: usize = 4;
+
+/// Name of the receive offload that timestamps frames in hardware (see `RTE_ETH_RX_OFFLOAD_TIMESTAMP` in DPDK).
+const RX_OFFLOAD_TIMESTAMP_NAME: &[u8] = b"TIMESTAMP";
+
+/// Time during which the clock of the NIC is sampled to estimate its frequency.
+const HW_CLOCK_CALIBRATION_TIME: Duration = Duration::from_millis(100);
+
+/// Flows that ports hash on the ports of TCP segments (see `RTE_ETH_RSS_NONFRAG_IPV4_TCP` in DPDK).
+const RTE_ETH_RSS_NONFRAG_IPV4_TCP: u64 = 1 << 4;
+
+/// Flows that ports hash on the ports of UDP datagrams (see `RTE_ETH_RSS_NONFRAG_IPV4_UDP` in DPDK).
+const RTE_ETH_RSS_NONFRAG_IPV4_UDP: u64 = 1 << 5;
+
+/// Number of entries of the redirection table that are updated at once (see `RTE_ETH_RETA_GROUP_SIZE` in DPDK).
+const RTE_ETH_RETA_GROUP_SIZE: usize = 64;
+
+/// Length of the RSS key of ports that do not report it (in bytes).
+const DEFAULT_RSS_KEY_LEN: usize = 40;
+
+/// Name of the port that bonds other ports together.
+const BOND_PORT_NAME: &str = "net_bonding0";
+
+/// Name of the virtio-user port that is attached to a vhost-user switch.
+const VIRTIO_USER_PORT_NAME: &str = "virtio_user0";
+
+/// EAL arguments that keep the memory and ports of DPDK private to the process, so that no secondary process can
+/// attach to it.
+const PRIVATE_EAL_ARGS: [&str; 3] = ["--in-memory", "--no-shconf", "--no-huge"];
+
 //==============================================================================
 // Macros
 //==============================================================================
@@ -91,11 +164,55 @@ macro_rules! expect_zero {
 // Structures
 //==============================================================================
 
+/// Hardware Clock of a DPDK Port
+#[derive(Clone, Copy, Debug)]
+pub struct HwClock {
+    /// Offset of the dynamic field of MBufs that holds their receive timestamp.
+    rx_offset: i32,
+    /// Flag that is set on MBufs whose receive timestamp is valid.
+    rx_flag: u64,
+    /// Frequency of the clock (in ticks per second).
+    hz: u64,
+}
+
+/// DPDK Ports
+///
+/// The ports are initialized along with the DPDK environment abstraction layer by the first instance of the LibOS in
+/// the process. Each instance then uses its own pair of receive and transmit queues on every port, to which Receive
+/// Side Scaling steers the flows of its shard.
+#[derive(Clone)]
+struct DPDKPorts {
+    /// Identifiers of the ports. The first one is the primary port, whose link address all ports share.
+    port_ids: Vec<u16>,
+    link_addr: MacAddress,
+    tcp_checksum_offload: bool,
+    udp_checksum_offload: bool,
+    hw_clock: Option<HwClock>,
+    /// Receive side scaling of the ports, if they have more than one pair of queues.
+    rss: Option<Rss>,
+    /// Whether each pair of queues is used by an instance.
+    queues_in_use: Vec<bool>,
+}
+
 /// DPDK Runtime
 #[derive(Clone)]
 pub struct DPDKRuntime {
     mm: MemoryManager,
-    port_id: u16,
+    /// Identifiers of the ports, starting with the primary one.
+    port_ids: Vec<u16>,
+    /// Ports through which packets from a given source address leave. Other packets leave through the primary port.
+    port_addrs: HashMap<Ipv4Addr, u16>,
+    /// Port that is polled first on the next receive.
+    next_rx_port: Cell<usize>,
+    /// Pair of receive and transmit queues of the ports that this instance uses.
+    queue_id: u16,
+    /// Number of pairs of queues of the ports.
+    num_queues: u16,
+    /// Flow rules that this instance installed, along with their handle on each port.
+    flows: RefCell<HashMap<u32, Vec<(u16, *mut rte_flow)>>>,
+    /// Identifier of the next flow rule.
+    next_flow_id: Cell<u32>,
+    hw_clock: Option<HwClock>,
     pub link_addr: MacAddress,
     pub ipv4_addr: Ipv4Addr,
     pub arp_options: ArpConfig,
@@ -103,6 +220,13 @@ pub struct DPDKRuntime {
     pub udp_options: UdpConfig,
 }
 
+//==============================================================================
+// Static Variables
+//==============================================================================
+
+/// Ports that are shared by the instances of the LibOS in this process, once they are initialized.
+static DPDK_PORTS: Mutex<Option<DPDKPorts>> = Mutex::new(None);
+
 //==============================================================================
 // Associate Functions
 //==============================================================================
@@ -112,22 +236,78 @@ impl DPDKRuntime {
     pub fn new(
         ipv4_addr: Ipv4Addr,
         eal_init_args: &[CString],
+        ports: Vec<(String, Option<Ipv4Addr>)>,
+        bond: Option<Vec<String>>,
+        virtio_user: Option<String>,
+        num_mbufs: Option<usize>,
+        mbuf_size: Option<usize>,
+        multi_process: bool,
         arp_table: HashMap<Ipv4Addr, MacAddress>,
         disable_arp: bool,
-        use_jumbo_frames: bool,
+        arp_announcements: Option<usize>,
+        routes: Vec<Route>,
         mtu: u16,
-        mss: usize,
-        tcp_checksum_offload: bool,
-        udp_checksum_offload: bool,
+        mss: Option<usize>,
+        tcp_checksum_offload: Option<bool>,
+        udp_checksum_offload: Option<bool>,
+        hw_timestamps: bool,
+        congestion_control: Option<CongestionControlAlgorithm>,
+        pacing: Option<bool>,
+        ecn: Option<bool>,
+        ack_delay_timeout: Option<Duration>,
+        ack_segments: Option<u32>,
+        quickack: Option<bool>,
+        time_wait_reuse: Option<bool>,
+        syn_cookies: Option<bool>,
+        out_of_order_max_segments: Option<usize>,
+        out_of_order_max_bytes: Option<usize>,
+        out_of_order_drop_policy: Option<OutOfOrderDropPolicy>,
+        shard: Option<(usize, usize)>,
+        gro: Option<bool>,
     ) -> DPDKRuntime {
-        let (mm, port_id, link_addr) = Self::initialize_dpdk(
+        // Each shard gets its own pair of queues.
+        let (queue_id, num_queues): (u16, u16) = match shard {
+            Some((index, count)) => (index as u16, count as u16),
+            None => (0, 1),
+        };
+        let port_names: Vec<String> = ports.iter().map(|(name, _)| name.clone()).collect();
+        let (mm, dpdk_ports): (MemoryManager, DPDKPorts) = Self::attach_port(
             eal_init_args,
-            use_jumbo_frames,
+            &port_names,
+            bond,
+            virtio_user,
+            num_mbufs,
+            mbuf_size,
+            multi_process,
             mtu,
             tcp_checksum_offload,
             udp_checksum_offload,
+            hw_timestamps,
+            queue_id,
+            num_queues,
         )
         .unwrap();
+        let DPDKPorts {
+            port_ids,
+            link_addr,
+            tcp_checksum_offload,
+            udp_checksum_offload,
+            hw_clock,
+            rss,
+            ..
+        } = dpdk_ports;
+        // Ports are listed in the same order as they were configured.
+        let port_addrs: HashMap<Ipv4Addr, u16> = ports
+            .iter()
+            .zip(port_ids.iter())
+            .filter_map(|((_, addr), port_id)| addr.map(|addr| (addr, *port_id)))
+            .collect();
+        if rss.is_some() && queue_id != 0 && !disable_arp {
+            warn!(
+                "Frames other than IP ones are only received on queue 0, thus queue {} relies on static ARP entries",
+                queue_id
+            );
+        }
 
         let arp_options = ArpConfig::new(
             Some(Duration::from_secs(15)),
@@ -135,24 +315,78 @@ impl DPDKRuntime {
             Some(5),
             Some(arp_table),
             Some(disable_arp),
+            arp_announcements,
+            Some(routes),
         );
 
-        let tcp_options = TcpConfig::new(
-            Some(mss),
-            None,
-            None,
-            Some(0xffff),
-            Some(0),
-            None,
-            Some(tcp_checksum_offload),
-            Some(tcp_checksum_offload),
-        );
+        let mut tcp_options: TcpConfig = TcpConfig::default()
+            .set_mtu(mtu as usize)
+            .set_receive_window_size(0xffff)
+            .set_window_scale(0)
+            .set_rx_checksum_offload(tcp_checksum_offload)
+            .set_tx_checksum_offload(tcp_checksum_offload);
+        if let Some(mss) = mss {
+            tcp_options = tcp_options.set_advertised_mss(mss);
+        }
+        if let Some(timeout) = ack_delay_timeout {
+            tcp_options = tcp_options.set_ack_delay_timeout(timeout);
+        }
+        if let Some(algorithm) = congestion_control {
+            tcp_options = tcp_options.set_congestion_control(algorithm);
+        }
+        if let Some(pacing) = pacing {
+            tcp_options = tcp_options.set_pacing(pacing);
+        }
+        if let Some(ecn) = ecn {
+            tcp_options = tcp_options.set_ecn(ecn);
+        }
+        if let Some(segments) = ack_segments {
+            tcp_options = tcp_options.set_ack_segments(segments);
+        }
+        if let Some(quickack) = quickack {
+            tcp_options = tcp_options.set_quickack(quickack);
+        }
+        if let Some(reuse) = time_wait_reuse {
+            tcp_options = tcp_options.set_time_wait_reuse(reuse);
+        }
+        if let Some(syn_cookies) = syn_cookies {
+            tcp_options = tcp_options.set_syn_cookies(syn_cookies);
+        }
+        if let Some(segments) = out_of_order_max_segments {
+            tcp_options = tcp_options.set_out_of_order_max_segments(segments);
+        }
+        if let Some(bytes) = out_of_order_max_bytes {
+            tcp_options = tcp_options.set_out_of_order_max_bytes(bytes);
+        }
+        if let Some(policy) = out_of_order_drop_policy {
+            tcp_options = tcp_options.set_out_of_order_drop_policy(policy);
+        }
+        if let Some((index, count)) = shard {
+            tcp_options = tcp_options.set_shard(index, count);
+        }
+        if let Some(gro) = gro {
+            tcp_options = tcp_options.set_gro(gro);
+        }
+        if let Some(rss) = rss {
+            tcp_options = tcp_options.set_rss(rss);
+        }
 
-        let udp_options = UdpConfig::new(Some(udp_checksum_offload), Some(udp_checksum_offload));
+        let udp_options = UdpConfig::new(
+            Some(udp_checksum_offload),
+            Some(udp_checksum_offload),
+            Some(mtu as usize),
+        );
 
         Self {
             mm,
-            port_id,
+            port_ids,
+            port_addrs,
+            next_rx_port: Cell::new(0),
+            queue_id,
+            num_queues,
+            flows: RefCell::new(HashMap::new()),
+            next_flow_id: Cell::new(0),
+            hw_clock,
             link_addr,
             ipv4_addr,
             arp_options,
@@ -161,77 +395,314 @@ impl DPDKRuntime {
         }
     }
 
-    /// Initializes DPDK.
+    /// Removes the flow rules of this instance, and releases the queues of the underlying DPDK ports. Once no other
+    /// instance uses the ports, they are stopped and closed, and the resources of the DPDK environment abstraction
+    /// layer are released. No DPDK resource may be used afterwards, thus this should only be called once all buffers
+    /// are released.
+    pub fn teardown(&self) -> Result<(), Fail> {
+        // The flow rules go away with the ports anyway, so failing to remove them must not keep the ports and the
+        // environment abstraction layer from being released.
+        if let Err(e) = self.remove_flow_rules() {
+            warn!("teardown(): failed to remove flow rules ({:?})", e);
+        }
+
+        let mut shared: MutexGuard<Option<DPDKPorts>> = match DPDK_PORTS.lock() {
+            Ok(shared) => shared,
+            Err(_) => return Err(Fail::new(libc::EIO, "DPDK ports are in an inconsistent state")),
+        };
+        if let Some(ports) = shared.as_mut() {
+            ports.queues_in_use[self.queue_id as usize] = false;
+            if ports.queues_in_use.iter().any(|in_use| *in_use) {
+                return Ok(());
+            }
+        }
+        *shared = None;
+
+        // Failures do not stop the teardown, so that the environment abstraction layer is released in any case. The
+        // first one is reported once done.
+        let mut result: Result<(), Fail> = Ok(());
+        for &port_id in &self.port_ids {
+            let ret: libc::c_int = unsafe { rte_eth_dev_stop(port_id) };
+            if ret != 0 && result.is_ok() {
+                result = Err(Fail::new(-ret, "failed to stop DPDK port"));
+            }
+
+            let ret: libc::c_int = unsafe { rte_eth_dev_close(port_id) };
+            if ret != 0 && result.is_ok() {
+                result = Err(Fail::new(-ret, "failed to close DPDK port"));
+            }
+        }
+
+        let ret: libc::c_int = unsafe { rte_eal_cleanup() };
+        if ret != 0 && result.is_ok() {
+            result = Err(Fail::new(-ret, "failed to clean up DPDK environment"));
+        }
+
+        result
+    }
+
+    /// Computes the length of the largest frame that carries an IP datagram of `mtu` bytes, including the Ethernet
+    /// header and CRC. This is never less than the length of standard Ethernet frames.
+    fn max_frame_len(mtu: u16) -> u32 {
+        cmp::max(
+            mtu as u32 + (ETHERNET2_HEADER_SIZE + ETHERNET2_CRC_SIZE) as u32,
+            RTE_ETHER_MAX_LEN,
+        )
+    }
+
+    /// Attaches to the pair of queues `queue_id` out of `num_queues` of the DPDK ports, and returns the memory manager
+    /// of this instance along with the properties of the ports. If no other instance in the process did so already,
+    /// DPDK and the ports are initialized first, in which case the configuration of this instance applies to the ports.
+    fn attach_port(
+        eal_init_args: &[CString],
+        port_names: &[String],
+        bond: Option<Vec<String>>,
+        virtio_user: Option<String>,
+        num_mbufs: Option<usize>,
+        mbuf_size: Option<usize>,
+        multi_process: bool,
+        mtu: u16,
+        tcp_checksum_offload: Option<bool>,
+        udp_checksum_offload: Option<bool>,
+        hw_timestamps: bool,
+        queue_id: u16,
+        num_queues: u16,
+    ) -> Result<(MemoryManager, DPDKPorts), Error> {
+        // Receive buffers should fit the largest frame, jumbo frames included.
+        let max_body_size: usize = cmp::max(
+            Self::max_frame_len(mtu) as usize + RTE_PKTMBUF_HEADROOM as usize,
+            mbuf_size.unwrap_or(DEFAULT_MAX_BODY_SIZE),
+        );
+
+        let mut shared: MutexGuard<Option<DPDKPorts>> = DPDK_PORTS
+            .lock()
+            .map_err(|_| format_err!("DPDK ports are in an inconsistent state"))?;
+        if shared.is_none() {
+            *shared = Some(Self::initialize_dpdk(
+                eal_init_args,
+                port_names,
+                bond,
+                virtio_user,
+                multi_process,
+                mtu,
+                tcp_checksum_offload,
+                udp_checksum_offload,
+                hw_timestamps,
+                max_body_size,
+                num_mbufs,
+                num_queues,
+            )?);
+        }
+        let ports: &mut DPDKPorts = shared.as_mut().expect("DPDK ports should be initialized");
+
+        if ports.queues_in_use.len() != num_queues as usize {
+            bail!(
+                "Ports have {} queues, but {} were requested",
+                ports.queues_in_use.len(),
+                num_queues
+            );
+        }
+        if ports.queues_in_use[queue_id as usize] {
+            bail!("Queue {} of ports is already in use", queue_id);
+        }
+        let memory_manager: MemoryManager = MemoryManager::new(max_body_size, num_mbufs, queue_id)?;
+        ports.queues_in_use[queue_id as usize] = true;
+
+        Ok((memory_manager, ports.clone()))
+    }
+
+    /// Initializes DPDK and the ports named `port_names`, each with `num_queues` pairs of queues. If no port is named,
+    /// the first available one is used. If `bond` is set, its members are bonded in active-backup mode, and the bond is
+    /// the only port. If `virtio_user` is set, a virtio-user port is attached to the vhost-user socket that it
+    /// describes, and it is the only port unless others are named. If `multi_process` is set, DPDK shares its memory
+    /// and ports with secondary processes, such as monitoring tools. Checksum offloads that are not configured are
+    /// enabled if the primary port supports them, in which case the other ports must support them too.
     fn initialize_dpdk(
         eal_init_args: &[CString],
-        use_jumbo_frames: bool,
+        port_names: &[String],
+        bond: Option<Vec<String>>,
+        virtio_user: Option<String>,
+        multi_process: bool,
         mtu: u16,
-        tcp_checksum_offload: bool,
-        udp_checksum_offload: bool,
-    ) -> Result<(MemoryManager, u16, MacAddress), Error> {
+        tcp_checksum_offload: Option<bool>,
+        udp_checksum_offload: Option<bool>,
+        hw_timestamps: bool,
+        max_body_size: usize,
+        num_mbufs: Option<usize>,
+        num_queues: u16,
+    ) -> Result<DPDKPorts, Error> {
         std::env::set_var("MLX5_SHUT_UP_BF", "1");
-        std::env::set_var("MLX5_SINGLE_THREADED", "1");
-        std::env::set_var("MLX4_SINGLE_THREADED", "1");
+        // Drivers may only skip locking when a single thread uses the port.
+        if num_queues == 1 {
+            std::env::set_var("MLX5_SINGLE_THREADED", "1");
+            std::env::set_var("MLX4_SINGLE_THREADED", "1");
+        }
+        // The bonding driver creates the bond from a virtual device, in which the primary member is the active one.
+        let mut eal_init_args: Vec<CString> = eal_init_args.to_vec();
+        if let Some(members) = bond.as_ref() {
+            if !port_names.is_empty() {
+                bail!("Ports cannot be listed along with a bond");
+            }
+            let members_arg: String = members.iter().map(|member| format!(",slave={}", member)).collect();
+            eal_init_args.push(CString::new(format!(
+                "--vdev={},mode=1{},primary={}",
+                BOND_PORT_NAME, members_arg, members[0]
+            ))?);
+        }
+        // The virtio-user port negotiates one pair of queues per instance with the switch. The switch maps the memory
+        // of our pools, and it can only map a few files, hence hugepages are backed by a single file per segment list.
+        if let Some(devargs) = virtio_user.as_ref() {
+            if bond.is_some() {
+                bail!("A virtio-user port cannot be used along with a bond");
+            }
+            eal_init_args.push(CString::new(format!(
+                "--vdev={},{},queues={}",
+                VIRTIO_USER_PORT_NAME, devargs, num_queues
+            ))?);
+            let single_file_segments: CString = CString::new("--single-file-segments")?;
+            if !eal_init_args.contains(&single_file_segments) {
+                eal_init_args.push(single_file_segments);
+            }
+        }
+        // Secondary processes find the memory pools and ports of the primary one through files in its runtime
+        // directory, which is named after the file prefix.
+        if multi_process {
+            for arg in &eal_init_args {
+                let arg: &str = arg.to_str()?;
+                if PRIVATE_EAL_ARGS.contains(&arg) {
+                    bail!(
+                        "Secondary processes cannot attach to DPDK when it is initialized with {}",
+                        arg
+                    );
+                }
+            }
+            if !eal_init_args
+                .iter()
+                .any(|arg| arg.as_bytes().starts_with(b"--proc-type"))
+            {
+                eal_init_args.push(CString::new("--proc-type=primary")?);
+            }
+        }
         let eal_init_refs = eal_init_args.iter().map(|s| s.as_ptr() as *mut u8).collect::<Vec<_>>();
         let ret: libc::c_int = unsafe { rte_eal_init(eal_init_refs.len() as i32, eal_init_refs.as_ptr() as *mut _) };
         if ret < 0 {
             let rte_errno: libc::c_int = unsafe { dpdk_rs::rte_errno() };
             bail!("EAL initialization failed (rte_errno={:?})", rte_errno);
         }
+        // Ports may only be configured by the primary process.
+        if unsafe { rte_eal_process_type() } != RTE_PROC_PRIMARY {
+            bail!("Another primary DPDK process runs with the same file prefix");
+        }
+        if multi_process {
+            info!("DPDK accepts secondary processes.");
+        }
         let nb_ports: u16 = unsafe { rte_eth_dev_count_avail() };
         if nb_ports == 0 {
             bail!("No ethernet ports available");
         }
-        eprintln!("DPDK reports that {} ports (interfaces) are available.", nb_ports);
-
-        let max_body_size: usize = if use_jumbo_frames {
-            (RTE_ETHER_MAX_JUMBO_FRAME_LEN + RTE_PKTMBUF_HEADROOM) as usize
-        } else {
-            DEFAULT_MAX_BODY_SIZE
+        info!("DPDK reports that {} ports (interfaces) are available.", nb_ports);
+
+        let port_ids: Vec<u16> = match (bond.as_ref(), port_names) {
+            (Some(_), _) => vec![Self::find_port(BOND_PORT_NAME)?],
+            (None, []) if virtio_user.is_some() => vec![Self::find_port(VIRTIO_USER_PORT_NAME)?],
+            (None, []) => {
+                let owner: u64 = RTE_ETH_DEV_NO_OWNER as u64;
+                vec![unsafe { rte_eth_find_next_owned_by(0, owner) as u16 }]
+            },
+            (None, port_names) => port_names
+                .iter()
+                .map(|port_name| Self::find_port(port_name))
+                .collect::<Result<Vec<u16>, Error>>()?,
         };
+        if hw_timestamps && port_ids.len() > 1 {
+            bail!("Hardware timestamps are only supported on a single port");
+        }
 
-        let memory_manager = MemoryManager::new(max_body_size)?;
-
-        let owner: u64 = RTE_ETH_DEV_NO_OWNER as u64;
-        let port_id: u16 = unsafe { rte_eth_find_next_owned_by(0, owner) as u16 };
-        Self::initialize_dpdk_port(
-            port_id,
-            &memory_manager,
-            use_jumbo_frames,
-            mtu,
-            tcp_checksum_offload,
-            udp_checksum_offload,
-        )?;
-
-        // TODO: Where is this function?
-        // if unsafe { rte_lcore_count() } > 1 {
-        //     eprintln!("WARNING: Too many lcores enabled. Only 1 used.");
-        // }
-
-        let local_link_addr: MacAddress = unsafe {
+        // Secondary ports offload the same checksums as the primary one, so that packets are alike on every port.
+        let primary_port_id: u16 = port_ids[0];
+        let (tcp_checksum_offload, udp_checksum_offload, hw_clock, rss): (bool, bool, Option<HwClock>, Option<Rss>) =
+            Self::initialize_dpdk_port(
+                primary_port_id,
+                max_body_size,
+                num_mbufs,
+                mtu,
+                tcp_checksum_offload,
+                udp_checksum_offload,
+                hw_timestamps,
+                num_queues,
+            )?;
+
+        let link_addr: MacAddress = unsafe {
             let mut m: MaybeUninit<rte_ether_addr> = MaybeUninit::zeroed();
             // TODO: Why does bindgen say this function doesn't return an int?
-            rte_eth_macaddr_get(port_id, m.as_mut_ptr());
+            rte_eth_macaddr_get(primary_port_id, m.as_mut_ptr());
             MacAddress::new(m.assume_init().addr_bytes)
         };
-        if local_link_addr.is_nil() || !local_link_addr.is_unicast() {
+        if link_addr.is_nil() || !link_addr.is_unicast() {
             Err(format_err!("Invalid mac address"))?;
         }
 
-        Ok((memory_manager, port_id, local_link_addr))
+        // Secondary ports take the link address of the primary one, so that the interface has a single link address.
+        for &port_id in &port_ids[1..] {
+            let (_, _, _, port_rss): (bool, bool, Option<HwClock>, Option<Rss>) = Self::initialize_dpdk_port(
+                port_id,
+                max_body_size,
+                num_mbufs,
+                mtu,
+                Some(tcp_checksum_offload),
+                Some(udp_checksum_offload),
+                false,
+                num_queues,
+            )?;
+            if port_rss != rss {
+                bail!(
+                    "Port {} spreads flows differently than port {}",
+                    port_id,
+                    primary_port_id
+                );
+            }
+            let mut addr: rte_ether_addr = rte_ether_addr {
+                addr_bytes: link_addr.octets(),
+            };
+            unsafe { expect_zero!(rte_eth_dev_default_mac_addr_set(port_id, &mut addr))? };
+        }
+
+        Ok(DPDKPorts {
+            port_ids,
+            link_addr,
+            tcp_checksum_offload,
+            udp_checksum_offload,
+            hw_clock,
+            rss,
+            queues_in_use: vec![false; num_queues as usize],
+        })
     }
 
-    /// Initializes a DPDK port.
+    /// Looks up the identifier of the DPDK port named `port_name`.
+    fn find_port(port_name: &str) -> Result<u16, Error> {
+        let name: CString = CString::new(port_name)?;
+        let mut port_id: u16 = 0;
+        if unsafe { rte_eth_dev_get_port_by_name(name.as_ptr(), &mut port_id) } != 0 {
+            bail!("No such port {:?}", port_name);
+        }
+        Ok(port_id)
+    }
+
+    /// Initializes a DPDK port, and returns whether TCP and UDP checksums are offloaded to it. If `hw_timestamps` is
+    /// set, the port timestamps received frames, and its clock is returned as well. If the port has more than one pair
+    /// of queues, flows are spread among them with Receive Side Scaling, which is returned too.
     fn initialize_dpdk_port(
         port_id: u16,
-        memory_manager: &MemoryManager,
-        use_jumbo_frames: bool,
+        max_body_size: usize,
+        num_mbufs: Option<usize>,
         mtu: u16,
-        tcp_checksum_offload: bool,
-        udp_checksum_offload: bool,
-    ) -> Result<(), Error> {
-        let rx_rings: u16 = 1;
-        let tx_rings: u16 = 1;
+        tcp_checksum_offload: Option<bool>,
+        udp_checksum_offload: Option<bool>,
+        hw_timestamps: bool,
+        num_queues: u16,
+    ) -> Result<(bool, bool, Option<HwClock>, Option<Rss>), Error> {
+        let rx_rings: u16 = num_queues;
+        let tx_rings: u16 = num_queues;
         let rx_ring_size: u16 = 2048;
         let tx_ring_size: u16 = 2048;
         let nb_rxd: u16 = rx_ring_size;
@@ -252,20 +723,76 @@ impl DPDKRuntime {
         };
 
         println!("dev_info: {:?}", dev_info);
-        let mut port_conf: rte_eth_conf = unsafe { MaybeUninit::zeroed().assume_init() };
-        port_conf.rxmode.max_lro_pkt_size = if use_jumbo_frames {
-            RTE_ETHER_MAX_JUMBO_FRAME_LEN
-        } else {
-            RTE_ETHER_MAX_LEN
+        let tcp_checksum_offload: bool = unsafe {
+            Self::check_checksum_offload(
+                port_id,
+                &dev_info,
+                "TCP",
+                tcp_checksum_offload,
+                rte_eth_rx_offload_tcp_cksum() as u64,
+                rte_eth_tx_offload_tcp_cksum() as u64,
+            )?
         };
+        let udp_checksum_offload: bool = unsafe {
+            Self::check_checksum_offload(
+                port_id,
+                &dev_info,
+                "UDP",
+                udp_checksum_offload,
+                rte_eth_rx_offload_udp_cksum() as u64,
+                rte_eth_tx_offload_udp_cksum() as u64,
+            )?
+        };
+
+        let mut port_conf: rte_eth_conf = unsafe { MaybeUninit::zeroed().assume_init() };
+        port_conf.rxmode.max_lro_pkt_size = Self::max_frame_len(mtu);
         if tcp_checksum_offload {
             port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_tcp_cksum() as u64 };
         }
         if udp_checksum_offload {
             port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_udp_cksum() as u64 };
         }
-        port_conf.rxmode.mq_mode = RTE_ETH_MQ_RX_RSS;
-        port_conf.rx_adv_conf.rss_conf.rss_hf = unsafe { rte_eth_rss_ip() as u64 } | dev_info.flow_type_rss_offloads;
+        if hw_timestamps {
+            match Self::find_rx_offload(&dev_info, RX_OFFLOAD_TIMESTAMP_NAME) {
+                Some(offload) => port_conf.rxmode.offloads |= offload,
+                None => bail!("Port {} does not support hardware timestamps", port_id),
+            }
+        }
+        // Ports that cannot hash flows, such as virtio ones that did not negotiate it, reject any request for RSS.
+        if dev_info.flow_type_rss_offloads != 0 {
+            port_conf.rxmode.mq_mode = RTE_ETH_MQ_RX_RSS;
+            port_conf.rx_adv_conf.rss_conf.rss_hf =
+                unsafe { rte_eth_rss_ip() as u64 } | dev_info.flow_type_rss_offloads;
+        }
+
+        // Flows are steered to queues with a known key, so that instances can tell which queue a flow lands on.
+        if rx_rings > dev_info.max_rx_queues || tx_rings > dev_info.max_tx_queues {
+            bail!(
+                "Port {} supports up to {} receive and {} transmit queues, but {} were requested",
+                port_id,
+                dev_info.max_rx_queues,
+                dev_info.max_tx_queues,
+                num_queues
+            );
+        }
+        let mut rss_key: Vec<u8> = match dev_info.hash_key_size {
+            0 => Rss::key(DEFAULT_RSS_KEY_LEN),
+            len => Rss::key(len as usize),
+        };
+        if num_queues > 1 {
+            if dev_info.flow_type_rss_offloads & RTE_ETH_RSS_NONFRAG_IPV4_TCP == 0 {
+                bail!("Port {} cannot steer TCP flows by port number", port_id);
+            }
+            // Otherwise, all datagrams between two hosts would land on the same queue.
+            if dev_info.flow_type_rss_offloads & RTE_ETH_RSS_NONFRAG_IPV4_UDP == 0 {
+                bail!("Port {} cannot steer UDP flows by port number", port_id);
+            }
+            if dev_info.reta_size < num_queues {
+                bail!("Port {} cannot spread flows among {} queues", port_id, num_queues);
+            }
+            port_conf.rx_adv_conf.rss_conf.rss_key = rss_key.as_mut_ptr();
+            port_conf.rx_adv_conf.rss_conf.rss_key_len = rss_key.len() as u8;
+        }
 
         port_conf.txmode.mq_mode = RTE_ETH_MQ_TX_NONE;
         if tcp_checksum_offload {
@@ -306,6 +833,15 @@ impl DPDKRuntime {
             }
         }
 
+        // Receive timestamps are stored in a dynamic field of MBufs, which must be registered before queues are set up.
+        let mut rx_timestamp: Option<(i32, u64)> = None;
+        if hw_timestamps {
+            let mut rx_offset: libc::c_int = 0;
+            let mut rx_flag: u64 = 0;
+            unsafe { expect_zero!(rte_mbuf_dyn_rx_timestamp_register(&mut rx_offset, &mut rx_flag))? };
+            rx_timestamp = Some((rx_offset, rx_flag));
+        }
+
         let socket_id: u32 = 0;
 
         unsafe {
@@ -316,7 +852,7 @@ impl DPDKRuntime {
                     nb_rxd,
                     socket_id,
                     &rx_conf as *const _,
-                    memory_manager.body_pool(),
+                    MemoryManager::new_rx_pool(max_body_size, num_mbufs, port_id, i)?,
                 ))?;
             }
             for i in 0..tx_rings {
@@ -332,6 +868,13 @@ impl DPDKRuntime {
             rte_eth_promiscuous_enable(port_id);
         }
 
+        let rss: Option<Rss> = if num_queues > 1 {
+            Self::update_reta(port_id, dev_info.reta_size, num_queues)?;
+            Some(Rss::new(dev_info.reta_size as usize, num_queues as usize))
+        } else {
+            None
+        };
+
         if unsafe { rte_eth_dev_is_valid_port(port_id) } == 0 {
             bail!("Invalid port");
         }
@@ -350,7 +893,7 @@ impl DPDKRuntime {
                     } else {
                         "half"
                     };
-                    eprintln!(
+                    info!(
                         "Port {} Link Up - speed {} Mbps - {} duplex",
                         port_id, link.link_speed, duplex
                     );
@@ -364,8 +907,113 @@ impl DPDKRuntime {
             retry_count -= 1;
         }
 
+        let hw_clock: Option<HwClock> = match rx_timestamp {
+            Some((rx_offset, rx_flag)) => Some(HwClock {
+                rx_offset,
+                rx_flag,
+                hz: Self::calibrate_hw_clock(port_id)?,
+            }),
+            None => None,
+        };
+
+        Ok((tcp_checksum_offload, udp_checksum_offload, hw_clock, rss))
+    }
+
+    /// Assigns the entries of the redirection table of a DPDK port to its `num_queues` receive queues in a round-robin
+    /// fashion, as [Rss] expects.
+    fn update_reta(port_id: u16, reta_size: u16, num_queues: u16) -> Result<(), Error> {
+        let reta_size: usize = reta_size as usize;
+        let num_groups: usize = (reta_size + RTE_ETH_RETA_GROUP_SIZE - 1) / RTE_ETH_RETA_GROUP_SIZE;
+        let mut reta_conf: Vec<rte_eth_rss_reta_entry64> = (0..num_groups)
+            .map(|_| unsafe { MaybeUninit::zeroed().assume_init() })
+            .collect();
+        for i in 0..reta_size {
+            let group: &mut rte_eth_rss_reta_entry64 = &mut reta_conf[i / RTE_ETH_RETA_GROUP_SIZE];
+            group.mask |= 1 << (i % RTE_ETH_RETA_GROUP_SIZE);
+            group.reta[i % RTE_ETH_RETA_GROUP_SIZE] = (i % num_queues as usize) as u16;
+        }
+        unsafe {
+            expect_zero!(rte_eth_dev_rss_reta_update(
+                port_id,
+                reta_conf.as_mut_ptr(),
+                reta_size as u16
+            ))?;
+        }
         Ok(())
     }
+
+    /// Checks whether the checksums of `protocol` should be offloaded to a DPDK port, given the receive and transmit
+    /// offloads that compute them. If this was not configured, checksums are offloaded whenever the port supports it.
+    /// Otherwise, the configuration must match the capabilities of the port: offloading checksums that the port does
+    /// not compute would corrupt packets, whereas computing checksums that the port could compute only wastes cycles.
+    fn check_checksum_offload(
+        port_id: u16,
+        dev_info: &dpdk_rs::rte_eth_dev_info,
+        protocol: &str,
+        configured: Option<bool>,
+        rx_offload: u64,
+        tx_offload: u64,
+    ) -> Result<bool, Error> {
+        let supported: bool =
+            dev_info.rx_offload_capa & rx_offload == rx_offload && dev_info.tx_offload_capa & tx_offload == tx_offload;
+        match configured {
+            None => {
+                info!(
+                    "Port {} {} checksum offload: {}",
+                    port_id,
+                    protocol,
+                    if supported { "enabled" } else { "not supported" }
+                );
+                Ok(supported)
+            },
+            Some(true) if !supported => bail!("Port {} does not support {} checksum offload", port_id, protocol),
+            Some(false) if supported => {
+                warn!(
+                    "Port {} supports {} checksum offload, but it is disabled",
+                    port_id, protocol
+                );
+                Ok(false)
+            },
+            Some(enabled) => Ok(enabled),
+        }
+    }
+
+    /// Looks up a receive offload that a DPDK port supports by its name, since DPDK defines offload flags with macros
+    /// that are not part of its bindings. Returns `None` if the port does not support the offload.
+    fn find_rx_offload(dev_info: &dpdk_rs::rte_eth_dev_info, name: &[u8]) -> Option<u64> {
+        (0..u64::BITS)
+            .map(|bit| 1u64 << bit)
+            .filter(|offload| dev_info.rx_offload_capa & offload != 0)
+            .find(|&offload| unsafe { CStr::from_ptr(rte_eth_dev_rx_offload_name(offload)) }.to_bytes() == name)
+    }
+
+    /// Estimates the frequency of the clock of a DPDK port (in ticks per second), by sampling it against the clock of
+    /// the host.
+    fn calibrate_hw_clock(port_id: u16) -> Result<u64, Error> {
+        let mut start_ticks: u64 = 0;
+        let mut end_ticks: u64 = 0;
+        let start: Instant = Instant::now();
+        unsafe {
+            expect_zero!(rte_eth_read_clock(port_id, &mut start_ticks))?;
+            rte_delay_us_block(HW_CLOCK_CALIBRATION_TIME.as_micros() as u32);
+            expect_zero!(rte_eth_read_clock(port_id, &mut end_ticks))?;
+        }
+        let elapsed: Duration = start.elapsed();
+        let hz: u128 = (end_ticks.wrapping_sub(start_ticks) as u128) * 1_000_000_000 / elapsed.as_nanos();
+        if hz == 0 {
+            bail!("Clock of port {} does not tick", port_id);
+        }
+        info!("Port {} hardware clock runs at {} Hz", port_id, hz);
+        Ok(hz as u64)
+    }
+}
+
+/// Associate Functions for Hardware Clocks
+impl HwClock {
+    /// Converts a reading of the target clock into nanoseconds.
+    pub fn to_nanos(&self, ticks: u64) -> u64 {
+        ((ticks as u128) * 1_000_000_000 / (self.hz as u128)) as u64
+    }
 }
 
 //==============================================================================
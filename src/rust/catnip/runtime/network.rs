@@ -5,15 +5,29 @@
 // Imports
 //==============================================================================
 
-use super::DPDKRuntime;
+use super::{
+    DPDKRuntime,
+    HwClock,
+};
 use crate::{
-    inetstack::protocols::ethernet2::MIN_PAYLOAD_SIZE,
+    inetstack::protocols::{
+        ethernet2::{
+            EtherType2,
+            ETHERNET2_HEADER_SIZE,
+            MIN_PAYLOAD_SIZE,
+        },
+        ip::IpProtocol,
+    },
     runtime::{
         libdpdk::{
+            rte_eth_link,
+            rte_eth_link_get_nowait,
+            rte_eth_read_clock,
             rte_eth_rx_burst,
             rte_eth_tx_burst,
             rte_mbuf,
             rte_pktmbuf_chain,
+            RTE_ETH_LINK_UP,
         },
         memory::{
             DPDKBuffer,
@@ -21,17 +35,161 @@ use crate::{
         },
         network::{
             consts::RECEIVE_BATCH_SIZE,
+            types::LinkState,
             NetworkRuntime,
             PacketBuf,
         },
     },
 };
 use ::arrayvec::ArrayVec;
-use ::std::mem;
+use ::byteorder::{
+    ByteOrder,
+    NetworkEndian,
+};
+use ::std::{
+    mem::{
+        self,
+        MaybeUninit,
+    },
+    net::Ipv4Addr,
+    ptr,
+};
 
 #[cfg(feature = "profiler")]
 use crate::timer;
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// MBuf flag that requests the NIC to compute the TCP checksum (see `RTE_MBUF_F_TX_TCP_CKSUM` in DPDK).
+const RTE_MBUF_F_TX_TCP_CKSUM: u64 = 1 << 52;
+
+/// MBuf flag that requests the NIC to compute the UDP checksum (see `RTE_MBUF_F_TX_UDP_CKSUM` in DPDK).
+const RTE_MBUF_F_TX_UDP_CKSUM: u64 = 3 << 52;
+
+/// MBuf flag of IPv4 packets, which checksum offloads rely on (see `RTE_MBUF_F_TX_IPV4` in DPDK).
+const RTE_MBUF_F_TX_IPV4: u64 = 1 << 55;
+
+/// Width of the `l2_len` bit field, which leads the `tx_offload` field of MBufs.
+const RTE_MBUF_L2_LEN_BITS: u64 = 7;
+
+/// Size of IPv4 headers without options (in bytes).
+const IPV4_HEADER_MIN_SIZE: usize = 20;
+
+/// Offset of the source address in IPv4 headers (in bytes).
+const IPV4_SRC_ADDR_OFFSET: usize = 12;
+
+/// Offset of the destination address in IPv4 headers (in bytes).
+const IPV4_DST_ADDR_OFFSET: usize = 16;
+
+/// Offset of the sender protocol address in ARP messages (in bytes).
+const ARP_SENDER_PROTOCOL_ADDR_OFFSET: usize = 14;
+
+/// Offset of the target protocol address in ARP messages (in bytes).
+const ARP_TARGET_PROTOCOL_ADDR_OFFSET: usize = 24;
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for DPDK Runtime
+impl DPDKRuntime {
+    /// Prepares the frame whose headers are in `header` for transport checksum offload, if it is enabled for its
+    /// protocol. The NIC expects the checksum field to hold the checksum of the pseudo-header, and `mbuf` to tell
+    /// where headers lie. Fragments are left alone, as the NIC cannot checksum datagrams that span several frames.
+    unsafe fn prepare_checksum_offload(&self, mbuf: *mut rte_mbuf, header: &mut [u8]) {
+        let l2_len: usize = ETHERNET2_HEADER_SIZE;
+        if header.len() < l2_len + IPV4_HEADER_MIN_SIZE
+            || NetworkEndian::read_u16(&header[12..14]) != EtherType2::Ipv4 as u16
+        {
+            return;
+        }
+        let ipv4_hdr: &[u8] = &header[l2_len..];
+        let l3_len: usize = ((ipv4_hdr[0] & 0x0f) as usize) * 4;
+        let is_fragment: bool = NetworkEndian::read_u16(&ipv4_hdr[6..8]) & 0x3fff != 0;
+        let protocol: u8 = ipv4_hdr[9];
+        let (flag, checksum_offset): (u64, usize) =
+            if protocol == IpProtocol::TCP as u8 && self.tcp_options.get_tx_checksum_offload() {
+                (RTE_MBUF_F_TX_TCP_CKSUM, 16)
+            } else if protocol == IpProtocol::UDP as u8 && self.udp_options.get_tx_checksum_offload() {
+                (RTE_MBUF_F_TX_UDP_CKSUM, 6)
+            } else {
+                return;
+            };
+        let checksum_offset: usize = l2_len + l3_len + checksum_offset;
+        if is_fragment || header.len() < checksum_offset + 2 {
+            return;
+        }
+
+        // Checksum of the pseudo-header, which is not complemented.
+        let l4_len: u32 = (NetworkEndian::read_u16(&ipv4_hdr[2..4]) as u32).saturating_sub(l3_len as u32);
+        let mut sum: u32 = protocol as u32 + l4_len;
+        for i in (12..20).step_by(2) {
+            sum += NetworkEndian::read_u16(&ipv4_hdr[i..(i + 2)]) as u32;
+        }
+        while sum >> 16 != 0 {
+            sum = (sum & 0xffff) + (sum >> 16);
+        }
+        NetworkEndian::write_u16(&mut header[checksum_offset..(checksum_offset + 2)], sum as u16);
+
+        // The `tx_offload` field immediately follows `next`, in an anonymous union that bindgen does not name.
+        (*mbuf).ol_flags |= RTE_MBUF_F_TX_IPV4 | flag;
+        let tx_offload: *mut u64 =
+            (ptr::addr_of_mut!((*mbuf).next) as *mut u8).add(mem::size_of::<*mut rte_mbuf>()) as *mut u64;
+        *tx_offload = (l2_len as u64) | ((l3_len as u64) << RTE_MBUF_L2_LEN_BITS);
+    }
+
+    /// Picks the port that the frame whose headers are in `header` leaves through. This is the port that the source
+    /// address of the frame is assigned to, so that the route that selected this address also selects the port, and
+    /// the primary port otherwise.
+    fn select_port(&self, header: &[u8]) -> u16 {
+        let primary_port_id: u16 = self.port_ids[0];
+        if self.port_addrs.is_empty() {
+            return primary_port_id;
+        }
+        match Self::read_frame_addr(header, IPV4_SRC_ADDR_OFFSET, ARP_SENDER_PROTOCOL_ADDR_OFFSET) {
+            Some(src_addr) => self.port_addrs.get(&src_addr).copied().unwrap_or(primary_port_id),
+            None => primary_port_id,
+        }
+    }
+
+    /// Checks whether the frame in `frame`, which was received on `port_id`, is addressed to another port. As all ports
+    /// share a link address, a switch may deliver frames for the address that is assigned to a port on any of them.
+    /// Frames for addresses that are not assigned to a port in particular, such as the primary one, are accepted on
+    /// every port.
+    fn is_for_other_port(&self, port_id: u16, frame: &[u8]) -> bool {
+        if self.port_addrs.is_empty() {
+            return false;
+        }
+        match Self::read_frame_addr(frame, IPV4_DST_ADDR_OFFSET, ARP_TARGET_PROTOCOL_ADDR_OFFSET) {
+            Some(dst_addr) => self
+                .port_addrs
+                .get(&dst_addr)
+                .map_or(false, |&dst_port_id| dst_port_id != port_id),
+            None => false,
+        }
+    }
+
+    /// Reads the IPv4 address at `ipv4_offset` in the IPv4 header, or at `arp_offset` in the ARP message, that follows
+    /// the Ethernet header in `header`, if the frame carries either.
+    fn read_frame_addr(header: &[u8], ipv4_offset: usize, arp_offset: usize) -> Option<Ipv4Addr> {
+        if header.len() < ETHERNET2_HEADER_SIZE {
+            return None;
+        }
+        let ether_type: u16 = NetworkEndian::read_u16(&header[12..14]);
+        let offset: usize = if ether_type == EtherType2::Ipv4 as u16 {
+            ETHERNET2_HEADER_SIZE + ipv4_offset
+        } else if ether_type == EtherType2::Arp as u16 {
+            ETHERNET2_HEADER_SIZE + arp_offset
+        } else {
+            return None;
+        };
+        let addr: &[u8] = header.get(offset..(offset + 4))?;
+        Some(Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]))
+    }
+}
+
 //==============================================================================
 // Trait Implementations
 //==============================================================================
@@ -62,6 +220,9 @@ impl NetworkRuntime for DPDKRuntime {
         let header_size = buf.header_size();
         assert!(header_size <= header_mbuf.len());
         buf.write_header(unsafe { &mut header_mbuf.slice_mut()[..header_size] });
+        let mbuf: *mut rte_mbuf = header_mbuf.get_ptr();
+        unsafe { self.prepare_checksum_offload(mbuf, &mut header_mbuf.slice_mut()[..header_size]) };
+        let port_id: u16 = self.select_port(unsafe { &header_mbuf.slice_mut()[..header_size] });
 
         if let Some(body) = buf.take_body() {
             // Next, see how much space we have remaining and inline the body if we have room.
@@ -96,7 +257,7 @@ impl NetworkRuntime for DPDKRuntime {
                     assert_eq!(rte_pktmbuf_chain(header_mbuf.get_ptr(), body_mbuf), 0);
                 }
                 let mut header_mbuf_ptr = header_mbuf.into_raw();
-                let num_sent = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut header_mbuf_ptr, 1) };
+                let num_sent = unsafe { rte_eth_tx_burst(port_id, self.queue_id, &mut header_mbuf_ptr, 1) };
                 assert_eq!(num_sent, 1);
             }
             // Otherwise, write in the inline space.
@@ -117,7 +278,7 @@ impl NetworkRuntime for DPDKRuntime {
                 header_mbuf.trim(header_mbuf.len() - frame_size);
 
                 let mut header_mbuf_ptr = header_mbuf.into_raw();
-                let num_sent = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut header_mbuf_ptr, 1) };
+                let num_sent = unsafe { rte_eth_tx_burst(port_id, self.queue_id, &mut header_mbuf_ptr, 1) };
                 assert_eq!(num_sent, 1);
             }
         }
@@ -133,7 +294,7 @@ impl NetworkRuntime for DPDKRuntime {
             let frame_size = std::cmp::max(header_size, MIN_PAYLOAD_SIZE);
             header_mbuf.trim(header_mbuf.len() - frame_size);
             let mut header_mbuf_ptr = header_mbuf.into_raw();
-            let num_sent = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut header_mbuf_ptr, 1) };
+            let num_sent = unsafe { rte_eth_tx_burst(port_id, self.queue_id, &mut header_mbuf_ptr, 1) };
             assert_eq!(num_sent, 1);
         }
     }
@@ -142,24 +303,92 @@ impl NetworkRuntime for DPDKRuntime {
         let mut out = ArrayVec::new();
 
         let mut packets: [*mut rte_mbuf; RECEIVE_BATCH_SIZE] = unsafe { mem::zeroed() };
-        let nb_rx = unsafe {
+        let mut rx_port_ids: [u16; RECEIVE_BATCH_SIZE] = [0; RECEIVE_BATCH_SIZE];
+        let mut nb_rx: usize = 0;
+        {
             #[cfg(feature = "profiler")]
             timer!("catnip_libos::receive::rte_eth_rx_burst");
 
-            rte_eth_rx_burst(self.port_id, 0, packets.as_mut_ptr(), RECEIVE_BATCH_SIZE as u16)
-        };
-        assert!(nb_rx as usize <= RECEIVE_BATCH_SIZE);
+            // Ports take turns at being polled first, so that a busy port does not starve the others.
+            let num_ports: usize = self.port_ids.len();
+            let first_port: usize = self.next_rx_port.get();
+            self.next_rx_port.set((first_port + 1) % num_ports);
+            for i in 0..num_ports {
+                if nb_rx == RECEIVE_BATCH_SIZE {
+                    break;
+                }
+                let port_id: u16 = self.port_ids[(first_port + i) % num_ports];
+                let nb_port_rx: usize = unsafe {
+                    rte_eth_rx_burst(
+                        port_id,
+                        self.queue_id,
+                        packets[nb_rx..].as_mut_ptr(),
+                        (RECEIVE_BATCH_SIZE - nb_rx) as u16,
+                    )
+                } as usize;
+                rx_port_ids[nb_rx..(nb_rx + nb_port_rx)].fill(port_id);
+                nb_rx += nb_port_rx;
+            }
+        }
+        assert!(nb_rx <= RECEIVE_BATCH_SIZE);
 
         {
             #[cfg(feature = "profiler")]
             timer!("catnip_libos:receive::for");
-            for &packet in &packets[..nb_rx as usize] {
+            for (&packet, &port_id) in packets[..nb_rx].iter().zip(rx_port_ids.iter()) {
                 // Safety: `packet` is a valid pointer to a properly initialized `rte_mbuf` struct.
                 let buf: DemiBuffer = unsafe { DemiBuffer::from_mbuf(packet) };
+                // Dropping the buffer gives the MBuf back to its pool.
+                if self.is_for_other_port(port_id, &buf) {
+                    continue;
+                }
                 out.push(buf);
             }
         }
 
         out
     }
+
+    /// Gets the state of the link, without waiting for the device to complete link negotiation. The link is up as long
+    /// as any of the ports is.
+    fn link_state(&self) -> LinkState {
+        for &port_id in &self.port_ids {
+            // Safety: the link status is initialized by `rte_eth_link_get_nowait()`, and is zeroed (i.e. down) if it
+            // fails.
+            let link: rte_eth_link = unsafe {
+                let mut link: MaybeUninit<rte_eth_link> = MaybeUninit::zeroed();
+                rte_eth_link_get_nowait(port_id, link.as_mut_ptr());
+                link.assume_init()
+            };
+            if link.link_status() as u32 == RTE_ETH_LINK_UP {
+                return LinkState::Up;
+            }
+        }
+        LinkState::Down
+    }
+
+    /// Gets the receive timestamp that the NIC stored in the MBuf of `buf`, if hardware timestamps are enabled.
+    fn rx_timestamp(&self, buf: &DemiBuffer) -> Option<u64> {
+        let hw_clock: &HwClock = self.hw_clock.as_ref()?;
+        let mbuf: *mut rte_mbuf = buf.get_mbuf()?;
+        // Safety: the MBuf is valid while `buf` lives, and its dynamic field was registered when the port was set up.
+        unsafe {
+            if (*mbuf).ol_flags & hw_clock.rx_flag == 0 {
+                return None;
+            }
+            let field: *const u64 = (mbuf as *const u8).offset(hw_clock.rx_offset as isize) as *const u64;
+            Some(hw_clock.to_nanos(ptr::read_unaligned(field)))
+        }
+    }
+
+    /// Reads the clock of the NIC, if hardware timestamps are enabled. DPDK does not report when frames actually leave
+    /// the wire, so this is the time at which they were handed to the NIC.
+    fn tx_timestamp(&self) -> Option<u64> {
+        let hw_clock: &HwClock = self.hw_clock.as_ref()?;
+        let mut ticks: u64 = 0;
+        match unsafe { rte_eth_read_clock(self.port_ids[0], &mut ticks) } {
+            0 => Some(hw_clock.to_nanos(ticks)),
+            _ => None,
+        }
+    }
 }
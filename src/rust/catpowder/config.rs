@@ -15,19 +15,6 @@ use crate::{
 //======================================================================================================================
 
 impl Config {
-    /// Reads the "local interface name" parameter from the underlying configuration file.
-    pub fn local_interface_name(&self) -> String {
-        // FIXME: this function should return a Result.
-
-        // FIXME: Change the follow key from "catnip" to "catpowder".
-        let local_interface_name: &str = self.0["catnip"]["my_interface_name"]
-            .as_str()
-            .ok_or_else(|| anyhow::format_err!("Couldn't find my_interface_name config"))
-            .unwrap();
-
-        local_interface_name.to_string()
-    }
-
     /// Reads the "local link address" parameter from the underlying configuration file.
     pub fn local_link_addr(&self) -> MacAddress {
         // FIXME: this function should return a Result.
@@ -43,4 +30,40 @@ impl Config {
         .unwrap();
         local_link_addr
     }
+
+    /// Reads the "fanout group" parameter from the underlying configuration file, if present. Processes that share the
+    /// same group spread the flows of the interface among them.
+    #[cfg(target_os = "linux")]
+    pub fn fanout_group(&self) -> Option<u16> {
+        // FIXME: this function should return a Result.
+        match self.0["catpowder"]["fanout_group"].as_i64() {
+            Some(group_id) if group_id < 0 || group_id > u16::MAX as i64 => panic!("Invalid fanout group {}", group_id),
+            Some(group_id) => Some(group_id as u16),
+            None => None,
+        }
+    }
+
+    /// Reads the "local interface index" parameter from the underlying configuration file. This is the index of the
+    /// network interface that XDP for Windows is attached to.
+    #[cfg(target_os = "windows")]
+    pub fn local_interface_index(&self) -> u32 {
+        // FIXME: this function should return a Result.
+        match self.0["catpowder"]["my_interface_index"].as_i64() {
+            Some(ifindex) if ifindex > 0 && ifindex <= u32::MAX as i64 => ifindex as u32,
+            Some(ifindex) => panic!("Invalid interface index {}", ifindex),
+            None => panic!("Couldn't find my_interface_index in config"),
+        }
+    }
+
+    /// Reads the "local queue" parameter from the underlying configuration file. This is the queue of the network
+    /// interface that the XDP socket is bound to, which defaults to the first one.
+    #[cfg(target_os = "windows")]
+    pub fn local_queue_id(&self) -> u32 {
+        // FIXME: this function should return a Result.
+        match self.0["catpowder"]["queue_id"].as_i64() {
+            Some(queue_id) if queue_id < 0 || queue_id > u32::MAX as i64 => panic!("Invalid queue {}", queue_id),
+            Some(queue_id) => queue_id as u32,
+            None => 0,
+        }
+    }
 }
@@ -2,6 +2,7 @@
 // Licensed under the MIT license.
 
 mod memory;
+mod netlink;
 mod network;
 mod rawsocket;
 
@@ -9,25 +10,39 @@ mod rawsocket;
 // Imports
 //==============================================================================
 
-use self::rawsocket::{
-    RawSocket,
-    RawSocketAddr,
+use self::{
+    netlink::NetlinkSocket,
+    rawsocket::{
+        RawSocket,
+        RawSocketAddr,
+    },
 };
-use crate::runtime::{
-    network::{
-        config::{
-            ArpConfig,
-            TcpConfig,
-            UdpConfig,
+use crate::{
+    inetstack::protocols::ethernet2::ETHERNET2_HEADER_SIZE,
+    runtime::{
+        network::{
+            config::{
+                ArpConfig,
+                TcpConfig,
+                UdpConfig,
+            },
+            types::{
+                LinkState,
+                MacAddress,
+                Route,
+            },
         },
-        types::MacAddress,
+        Runtime,
     },
-    Runtime,
 };
 use ::std::{
-    cell::RefCell,
+    cell::{
+        Cell,
+        RefCell,
+    },
     collections::HashMap,
     fs,
+    mem::MaybeUninit,
     net::Ipv4Addr,
     num::ParseIntError,
     rc::Rc,
@@ -46,8 +61,17 @@ pub struct LinuxRuntime {
     pub arp_options: ArpConfig,
     pub link_addr: MacAddress,
     pub ipv4_addr: Ipv4Addr,
+    /// Receive buffer, which fits the largest frame that the MTU of the interface allows, jumbo frames included.
+    recv_buffer: Rc<RefCell<Vec<MaybeUninit<u8>>>>,
     ifindex: i32,
     socket: Rc<RefCell<RawSocket>>,
+    /// Raw socket that receives all ARP frames of the interface, when the main one is in a fanout group and thus only
+    /// receives the IPv4 frames of some flows.
+    arp_socket: Option<Rc<RawSocket>>,
+    /// Netlink socket that reports changes of the link state.
+    netlink: Rc<NetlinkSocket>,
+    /// Last known state of the link.
+    link_state: Rc<Cell<LinkState>>,
 }
 
 //==============================================================================
@@ -56,31 +80,82 @@ pub struct LinuxRuntime {
 
 /// Associate Functions for Linux Runtime
 impl LinuxRuntime {
-    /// Instantiates a Linux Runtime.
-    pub fn new(link_addr: MacAddress, ipv4_addr: Ipv4Addr, ifname: &str, arp: HashMap<Ipv4Addr, MacAddress>) -> Self {
+    /// Instantiates a Linux Runtime. If `fanout` is set, the raw socket joins that fanout group of the interface, so
+    /// that several processes may share the interface, each of them receiving a consistent subset of the flows. They
+    /// should then only accept connections, since the segments of outgoing connections may reach another process.
+    pub fn new(
+        link_addr: MacAddress,
+        ipv4_addr: Ipv4Addr,
+        ifname: &str,
+        arp: HashMap<Ipv4Addr, MacAddress>,
+        arp_announcements: Option<usize>,
+        routes: Vec<Route>,
+        mtu: u16,
+        shard: Option<(usize, usize)>,
+        gro: Option<bool>,
+        fanout: Option<u16>,
+    ) -> Self {
+        // Instances in a fanout group already see disjoint sets of flows, so they must not drop those of other shards.
+        if fanout.is_some() && shard.is_some() {
+            panic!("fanout groups and shards are mutually exclusive");
+        }
         let arp_options: ArpConfig = ArpConfig::new(
             Some(Duration::from_secs(600)),
             Some(Duration::from_secs(1)),
             Some(2),
             Some(arp),
             Some(false),
+            arp_announcements,
+            Some(routes),
         );
 
         // TODO: Make this constructor return a Result and drop expect() calls bellow.
         let mac_addr: [u8; 6] = [0; 6];
         let ifindex: i32 = Self::get_ifindex(ifname).expect("could not parse ifindex");
         let socket: RawSocket = RawSocket::new().expect("could not create raw socket");
-        let sockaddr: RawSocketAddr = RawSocketAddr::new(ifindex, &mac_addr);
-        socket.bind(&sockaddr).expect("could not bind raw socket");
+        let arp_socket: Option<Rc<RawSocket>> = match fanout {
+            Some(group_id) => {
+                // Fanout groups spread non-IP frames arbitrarily, thus ARP frames are received on a separate socket.
+                let sockaddr: RawSocketAddr = RawSocketAddr::with_protocol(ifindex, &mac_addr, libc::ETH_P_IP as u16);
+                socket.bind(&sockaddr).expect("could not bind raw socket");
+                socket.join_fanout(group_id).expect("could not join fanout group");
+                let arp_socket: RawSocket = RawSocket::new().expect("could not create ARP socket");
+                let sockaddr: RawSocketAddr = RawSocketAddr::with_protocol(ifindex, &mac_addr, libc::ETH_P_ARP as u16);
+                arp_socket.bind(&sockaddr).expect("could not bind ARP socket");
+                Some(Rc::new(arp_socket))
+            },
+            None => {
+                let sockaddr: RawSocketAddr = RawSocketAddr::new(ifindex, &mac_addr);
+                socket.bind(&sockaddr).expect("could not bind raw socket");
+                None
+            },
+        };
+        // Subscribe to link notifications before reading the current state of the link, so that no change is missed.
+        let netlink: NetlinkSocket = NetlinkSocket::new().expect("could not create netlink socket");
+        let link_state: LinkState = Self::get_link_state(ifname);
+
+        let mut tcp_options: TcpConfig = TcpConfig::default().set_mtu(mtu as usize);
+        if let Some((index, count)) = shard {
+            tcp_options = tcp_options.set_shard(index, count);
+        }
+        if let Some(gro) = gro {
+            tcp_options = tcp_options.set_gro(gro);
+        }
+
+        let recv_buffer: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); mtu as usize + ETHERNET2_HEADER_SIZE];
 
         Self {
-            tcp_options: TcpConfig::default(),
-            udp_options: UdpConfig::default(),
+            tcp_options,
+            udp_options: UdpConfig::new(None, None, Some(mtu as usize)),
             arp_options,
             link_addr,
             ipv4_addr,
+            recv_buffer: Rc::new(RefCell::new(recv_buffer)),
             ifindex,
             socket: Rc::new(RefCell::new(socket)),
+            arp_socket,
+            netlink: Rc::new(netlink),
+            link_state: Rc::new(Cell::new(link_state)),
         }
     }
 
@@ -89,6 +164,16 @@ impl LinuxRuntime {
         let path: String = format!("/sys/class/net/{}/ifindex", ifname);
         fs::read_to_string(path).expect("could not read ifname").trim().parse()
     }
+
+    /// Gets the state of the link of the network interface named `ifname`. The carrier cannot be read while the
+    /// interface is administratively down, in which case the link is down.
+    fn get_link_state(ifname: &str) -> LinkState {
+        let path: String = format!("/sys/class/net/{}/carrier", ifname);
+        match fs::read_to_string(path) {
+            Ok(carrier) if carrier.trim() == "1" => LinkState::Up,
+            _ => LinkState::Down,
+        }
+    }
 }
 
 //==============================================================================
@@ -0,0 +1,150 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::types::LinkState,
+};
+use ::libc;
+use ::std::{
+    mem,
+    ptr,
+};
+
+//======================================================================================================================
+// Constants & Structures
+//======================================================================================================================
+
+/// Size of the buffer that receives netlink messages.
+const NETLINK_BUFFER_SIZE: usize = 8192;
+
+/// Alignment of netlink messages.
+const NLMSG_ALIGNTO: usize = 4;
+
+/// Interface information message, which follows the header of `RTM_NEWLINK` and `RTM_DELLINK` messages.
+#[repr(C)]
+#[allow(dead_code)]
+struct IfInfoMsg {
+    ifi_family: u8,
+    __ifi_pad: u8,
+    ifi_type: u16,
+    ifi_index: i32,
+    ifi_flags: u32,
+    ifi_change: u32,
+}
+
+/// Netlink socket that is subscribed to link notifications of the kernel.
+pub struct NetlinkSocket(libc::c_int);
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associated functions for netlink sockets.
+impl NetlinkSocket {
+    /// Creates a netlink socket that receives link notifications.
+    pub fn new() -> Result<Self, Fail> {
+        let domain: i32 = libc::AF_NETLINK;
+        let ty: i32 = libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC; // Non-blocking, raw socket.
+        let protocol: i32 = libc::NETLINK_ROUTE; // Routing and link notifications.
+        let sockfd: i32 = unsafe { libc::socket(domain, ty, protocol) };
+
+        // Check if we failed to create the underlying netlink socket.
+        if sockfd == -1 {
+            return Err(Fail::new(libc::EAGAIN, "failed to create netlink socket"));
+        }
+        let socket: NetlinkSocket = NetlinkSocket(sockfd);
+
+        // Subscribe to link notifications.
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = libc::RTMGRP_LINK as u32;
+        let ret: i32 = unsafe {
+            libc::bind(
+                socket.0,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+
+        // Check if we failed to bind the underlying netlink socket.
+        if ret == -1 {
+            return Err(Fail::new(libc::EAGAIN, "failed to bind netlink socket"));
+        }
+
+        Ok(socket)
+    }
+
+    /// Receives pending link notifications, and returns the last reported state of the link of the interface
+    /// `ifindex`, if any.
+    pub fn recv_link_state(&self, ifindex: i32) -> Option<LinkState> {
+        let mut state: Option<LinkState> = None;
+        let mut buf: [u8; NETLINK_BUFFER_SIZE] = [0; NETLINK_BUFFER_SIZE];
+        loop {
+            let nbytes: isize = unsafe {
+                libc::recv(
+                    self.0,
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            // There are no more notifications.
+            if nbytes <= 0 {
+                break;
+            }
+            if let Some(s) = Self::parse(&buf[..nbytes as usize], ifindex) {
+                state = Some(s);
+            }
+        }
+        state
+    }
+
+    /// Parses a batch of netlink messages, and returns the last reported state of the link of the interface `ifindex`,
+    /// if any.
+    fn parse(buf: &[u8], ifindex: i32) -> Option<LinkState> {
+        let header_size: usize = mem::size_of::<libc::nlmsghdr>();
+        let mut state: Option<LinkState> = None;
+        let mut offset: usize = 0;
+        while offset + header_size <= buf.len() {
+            // Safety: the header lies within the buffer, and it is read without any alignment requirement.
+            let hdr: libc::nlmsghdr = unsafe { ptr::read_unaligned(buf[offset..].as_ptr() as *const libc::nlmsghdr) };
+            let msg_len: usize = hdr.nlmsg_len as usize;
+            if msg_len < header_size || offset + msg_len > buf.len() {
+                break;
+            }
+            let is_link_msg: bool = hdr.nlmsg_type == libc::RTM_NEWLINK || hdr.nlmsg_type == libc::RTM_DELLINK;
+            if is_link_msg && msg_len >= header_size + mem::size_of::<IfInfoMsg>() {
+                // Safety: the message lies within the buffer, and it is read without any alignment requirement.
+                let ifinfo: IfInfoMsg =
+                    unsafe { ptr::read_unaligned(buf[offset + header_size..].as_ptr() as *const IfInfoMsg) };
+                if ifinfo.ifi_index == ifindex {
+                    let lower_up: bool = ifinfo.ifi_flags & (libc::IFF_LOWER_UP as u32) != 0;
+                    state = Some(if hdr.nlmsg_type == libc::RTM_NEWLINK && lower_up {
+                        LinkState::Up
+                    } else {
+                        LinkState::Down
+                    });
+                }
+            }
+            offset += (msg_len + NLMSG_ALIGNTO - 1) & !(NLMSG_ALIGNTO - 1);
+        }
+        state
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Drop trait implementation for netlink sockets.
+impl Drop for NetlinkSocket {
+    /// Closes the target netlink socket.
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
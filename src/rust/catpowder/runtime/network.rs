@@ -6,7 +6,10 @@
 //==============================================================================
 
 use super::{
-    rawsocket::RawSocketAddr,
+    rawsocket::{
+        RawSocket,
+        RawSocketAddr,
+    },
     LinuxRuntime,
 };
 use crate::{
@@ -15,17 +18,39 @@ use crate::{
         memory::DemiBuffer,
         network::{
             consts::RECEIVE_BATCH_SIZE,
+            types::LinkState,
             NetworkRuntime,
             PacketBuf,
         },
     },
 };
 use ::arrayvec::ArrayVec;
-use ::std::mem::{
-    self,
-    MaybeUninit,
+use ::std::{
+    cell::RefMut,
+    mem::MaybeUninit,
+    slice,
 };
 
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Linux Runtime
+impl LinuxRuntime {
+    /// Receives a single frame from `socket` through the buffer `out`, if any is pending. Frames that do not fit in
+    /// the buffer are dropped.
+    fn recv_frame(socket: &RawSocket, out: &[MaybeUninit<u8>]) -> Option<DemiBuffer> {
+        let (nbytes, _origin_addr): (usize, RawSocketAddr) = socket.recvfrom(out).ok()?;
+        if nbytes > out.len() {
+            warn!("dropping oversized frame (len={:?}, max={:?})", nbytes, out.len());
+            return None;
+        }
+        // Safety: the first `nbytes` bytes of the buffer were written by the kernel.
+        let bytes: &[u8] = unsafe { slice::from_raw_parts(out.as_ptr() as *const u8, nbytes) };
+        Some(DemiBuffer::from_slice(bytes).expect("'bytes' should fit"))
+    }
+}
+
 //==============================================================================
 // Trait Implementations
 //==============================================================================
@@ -61,25 +86,26 @@ impl NetworkRuntime for LinuxRuntime {
     /// Receives a batch of [DemiBuffer].
     // ToDo: This routine currently only tries to receive a single packet buffer, not a batch of them.
     fn receive(&self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
-        // 4096B buffer size chosen arbitrarily, seems fine for now.
-        // REVIEW: Won't this fail for Ethernet jumbo frames?  Conversely, it seems wastefully big for standard frames.
-        const BUFFER_SIZE: usize = 4096;
-
         // ToDo: This routine contains an extra copy of the entire incoming packet that could potentially be removed.
-
-        // This use-case is an example for MaybeUninit in the docs.
-        let mut out: [MaybeUninit<u8>; BUFFER_SIZE] = [unsafe { MaybeUninit::uninit().assume_init() }; BUFFER_SIZE];
-        if let Ok((nbytes, _origin_addr)) = self.socket.borrow().recvfrom(&mut out[..]) {
-            let mut ret: ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> = ArrayVec::new();
-            unsafe {
-                let bytes: [u8; BUFFER_SIZE] = mem::transmute::<[MaybeUninit<u8>; BUFFER_SIZE], [u8; BUFFER_SIZE]>(out);
-                let mut dbuf: DemiBuffer = DemiBuffer::from_slice(&bytes).expect("'bytes' should fit");
-                dbuf.trim(BUFFER_SIZE - nbytes).expect("'bytes' <= BUFFER_SIZE");
+        let out: RefMut<Vec<MaybeUninit<u8>>> = self.recv_buffer.borrow_mut();
+        let mut ret: ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> = ArrayVec::new();
+        if let Some(dbuf) = Self::recv_frame(&self.socket.borrow(), &out) {
+            ret.push(dbuf);
+        }
+        // ARP frames are received on a separate socket when the main one is in a fanout group.
+        if let Some(arp_socket) = self.arp_socket.as_ref() {
+            if let Some(dbuf) = Self::recv_frame(arp_socket, &out) {
                 ret.push(dbuf);
             }
-            ret
-        } else {
-            ArrayVec::new()
         }
+        ret
+    }
+
+    /// Gets the state of the link, as last reported by netlink.
+    fn link_state(&self) -> LinkState {
+        if let Some(state) = self.netlink.recv_link_state(self.ifindex) {
+            self.link_state.set(state);
+        }
+        self.link_state.get()
     }
 }
@@ -26,15 +26,20 @@ pub struct RawSocketAddr(libc::sockaddr_ll);
 
 /// Associated functions for raw socket addresses.
 impl RawSocketAddr {
-    /// Creates a raw socket address.
+    /// Creates a raw socket address for all protocols.
     pub fn new(ifindex: i32, mac_addr: &[u8; 6]) -> Self {
+        Self::with_protocol(ifindex, mac_addr, libc::ETH_P_ALL as u16)
+    }
+
+    /// Creates a raw socket address for the protocol `protocol` only.
+    pub fn with_protocol(ifindex: i32, mac_addr: &[u8; 6], protocol: u16) -> Self {
         // Pad MAC address.
         let mut addr: [u8; 8] = [0_u8; 8];
         addr[..6].copy_from_slice(mac_addr);
 
         RawSocketAddr(libc::sockaddr_ll {
             sll_family: libc::AF_PACKET.try_into().unwrap(),
-            sll_protocol: (libc::ETH_P_ALL as u16).to_be(),
+            sll_protocol: protocol.to_be(),
             sll_ifindex: ifindex,
             sll_hatype: 0,
             sll_pkttype: 0,
@@ -8,12 +8,25 @@
 use super::RawSocketAddr;
 use crate::runtime::fail::Fail;
 use ::libc;
-use ::std::mem::MaybeUninit;
+use ::std::mem::{
+    self,
+    MaybeUninit,
+};
 
 //======================================================================================================================
 // Constants & Structures
 //======================================================================================================================
 
+/// Option of packet sockets that joins a fanout group (see `PACKET_FANOUT` in Linux).
+const PACKET_FANOUT: libc::c_int = 18;
+
+/// Fanout mode that spreads frames among the sockets of a group by flow hash (see `PACKET_FANOUT_HASH` in Linux).
+const PACKET_FANOUT_HASH: libc::c_int = 0;
+
+/// Fanout flag that reassembles IP fragments before hashing, so that all fragments of a datagram reach the same socket
+/// (see `PACKET_FANOUT_FLAG_DEFRAG` in Linux).
+const PACKET_FANOUT_FLAG_DEFRAG: libc::c_int = 0x8000;
+
 /// Raw socket.
 pub struct RawSocket(libc::c_int);
 
@@ -53,6 +66,29 @@ impl RawSocket {
         Ok(())
     }
 
+    /// Joins the fanout group `group_id` of the interface that the target raw socket is bound to. Frames are then
+    /// spread among the sockets of the group by flow hash, so all frames of a flow reach the same socket.
+    pub fn join_fanout(&self, group_id: u16) -> Result<(), Fail> {
+        let arg: libc::c_int = (group_id as libc::c_int) | ((PACKET_FANOUT_HASH | PACKET_FANOUT_FLAG_DEFRAG) << 16);
+        let ret: i32 = unsafe {
+            libc::setsockopt(
+                self.0,
+                libc::SOL_PACKET,
+                PACKET_FANOUT,
+                &arg as *const libc::c_int as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+
+        // Check if we failed to join the fanout group.
+        if ret == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            return Err(Fail::new(errno, "failed to join fanout group"));
+        }
+
+        Ok(())
+    }
+
     /// Sends data through a raw socket.
     pub fn sendto(&self, buf: &[u8], rawaddr: &RawSocketAddr) -> Result<usize, Fail> {
         let buf_len: usize = buf.len();
@@ -70,7 +106,8 @@ impl RawSocket {
         Ok(nbytes as usize)
     }
 
-    /// Receives data from a raw socket.
+    /// Receives data from a raw socket. The returned length is that of the whole frame, which exceeds the length of
+    /// `buf` if the frame was truncated.
     pub fn recvfrom(&self, buf: &[MaybeUninit<u8>]) -> Result<(usize, RawSocketAddr), Fail> {
         let buf_ptr: *mut libc::c_void = buf.as_ptr() as *mut libc::c_void;
         let buf_len: usize = buf.len();
@@ -84,7 +121,7 @@ impl RawSocket {
                 self.0,
                 buf_ptr,
                 buf_len,
-                libc::MSG_DONTWAIT,
+                libc::MSG_DONTWAIT | libc::MSG_TRUNC,
                 addr_ptr,
                 addrlen_ptr as *mut u32,
             ) as i32
@@ -98,3 +135,15 @@ impl RawSocket {
         Ok((nbytes as usize, rawaddr))
     }
 }
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Drop trait implementation for raw sockets.
+impl Drop for RawSocket {
+    /// Closes the target raw socket.
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    ffi::c_void,
+    mem,
+    ptr,
+};
+use ::windows::{
+    core::{
+        HRESULT,
+        PCSTR,
+    },
+    Win32::{
+        Foundation::{
+            CloseHandle,
+            HANDLE,
+            HINSTANCE,
+        },
+        System::LibraryLoader::{
+            FreeLibrary,
+            GetProcAddress,
+            LoadLibraryA,
+        },
+    },
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Version of the API of XDP for Windows that we are built against (see `XDP_API_VERSION_1` in `xdpapi.h`).
+const XDP_API_VERSION_1: u32 = 1;
+
+/// Options of XDP sockets (see `XSK_SOCKOPT_UMEM_REG` and the following ones in `afxdp.h`).
+pub const XSK_SOCKOPT_UMEM_REG: u32 = 1;
+pub const XSK_SOCKOPT_RX_RING_SIZE: u32 = 2;
+pub const XSK_SOCKOPT_RX_FILL_RING_SIZE: u32 = 3;
+pub const XSK_SOCKOPT_TX_RING_SIZE: u32 = 4;
+pub const XSK_SOCKOPT_TX_COMPLETION_RING_SIZE: u32 = 5;
+pub const XSK_SOCKOPT_RING_INFO: u32 = 6;
+
+/// Flags of [XdpApi::bind] (see `XSK_BIND_FLAG_RX` and the following ones in `afxdp.h`).
+pub const XSK_BIND_FLAG_RX: u32 = 0x1;
+pub const XSK_BIND_FLAG_TX: u32 = 0x2;
+
+/// Flags of [XdpApi::notify] (see `XSK_NOTIFY_FLAG_POKE_RX` and the following ones in `afxdp.h`).
+pub const XSK_NOTIFY_FLAG_POKE_RX: u32 = 0x1;
+pub const XSK_NOTIFY_FLAG_POKE_TX: u32 = 0x2;
+
+/// Flag of rings that is set when the driver should be poked to process them (see `XSK_RING_FLAG_NEED_POKE` in
+/// `afxdp.h`).
+pub const XSK_RING_FLAG_NEED_POKE: u32 = 0x2;
+
+/// Hook of XDP programs on the receive path of the network interface (see `XDP_HOOK_L2`, `XDP_HOOK_RX` and
+/// `XDP_HOOK_INSPECT` in `xdp/hookid.h`).
+const XDP_HOOK_L2: u32 = 0;
+const XDP_HOOK_RX: u32 = 0;
+const XDP_HOOK_INSPECT: u32 = 0;
+
+/// Match type of the rules of XDP programs that matches all frames (see `XDP_MATCH_ALL` in `xdp/program.h`).
+pub const XDP_MATCH_ALL: u32 = 0;
+
+/// Action of the rules of XDP programs that redirects frames (see `XDP_PROGRAM_ACTION_REDIRECT` in `xdp/program.h`).
+pub const XDP_PROGRAM_ACTION_REDIRECT: u32 = 2;
+
+/// Target of redirections to XDP sockets (see `XDP_REDIRECT_TARGET_TYPE_XSK` in `xdp/program.h`).
+pub const XDP_REDIRECT_TARGET_TYPE_XSK: u32 = 0;
+
+/// Registration of a UMEM (see `XSK_UMEM_REG` in `afxdp.h`).
+#[repr(C)]
+pub struct XskUmemReg {
+    pub total_size: u64,
+    pub chunk_size: u32,
+    pub headroom: u32,
+    pub address: *mut c_void,
+}
+
+/// Layout of a ring that is shared with XDP (see `XSK_RING_INFO` in `afxdp.h`).
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct XskRingInfo {
+    pub ring: *mut u8,
+    pub descriptors_offset: u32,
+    pub producer_index_offset: u32,
+    pub consumer_index_offset: u32,
+    pub flags_offset: u32,
+    pub size: u32,
+    pub element_stride: u32,
+    pub reserved: u32,
+}
+
+/// Layouts of all rings of an XDP socket (see `XSK_RING_INFO_SET` in `afxdp.h`).
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct XskRingInfoSet {
+    pub fill: XskRingInfo,
+    pub completion: XskRingInfo,
+    pub rx: XskRingInfo,
+    pub tx: XskRingInfo,
+}
+
+/// Hook that XDP programs are attached to (see `XDP_HOOK_ID` in `xdp/hookid.h`).
+#[repr(C)]
+struct XdpHookId {
+    layer: u32,
+    direction: u32,
+    sub_layer: u32,
+}
+
+/// Parameters of redirections (see `XDP_REDIRECT_PARAMS` in `xdp/program.h`).
+#[repr(C)]
+pub struct XdpRedirectParams {
+    pub target_type: u32,
+    pub target: HANDLE,
+}
+
+/// Rule of XDP programs (see `XDP_RULE` in `xdp/program.h`). The pattern is left opaque, since it is ignored by the
+/// rules that match all frames, but it is sized after the largest one so that the action lands at the right offset.
+#[repr(C)]
+pub struct XdpRule {
+    pub match_type: u32,
+    pub pattern: [u64; 6],
+    pub action: u32,
+    pub redirect: XdpRedirectParams,
+}
+
+/// Functions of XDP for Windows (see `XDP_API_TABLE` in `xdpapi.h`). Those that we do not use are left opaque.
+#[repr(C)]
+struct XdpApiTable {
+    xdp_open_api: *const c_void,
+    xdp_close_api: unsafe extern "system" fn(table: *const XdpApiTable),
+    xdp_get_routine: *const c_void,
+    xdp_create_program: unsafe extern "system" fn(
+        ifindex: u32,
+        hook_id: *const XdpHookId,
+        queue_id: u32,
+        flags: u32,
+        rules: *const XdpRule,
+        rule_count: u32,
+        program: *mut HANDLE,
+    ) -> HRESULT,
+    xdp_interface_open: *const c_void,
+    xsk_create: unsafe extern "system" fn(socket: *mut HANDLE) -> HRESULT,
+    xsk_bind: unsafe extern "system" fn(socket: HANDLE, ifindex: u32, queue_id: u32, flags: u32) -> HRESULT,
+    xsk_activate: unsafe extern "system" fn(socket: HANDLE, flags: u32) -> HRESULT,
+    xsk_notify_socket:
+        unsafe extern "system" fn(socket: HANDLE, flags: u32, timeout_ms: u32, result: *mut u32) -> HRESULT,
+    xsk_notify_async: *const c_void,
+    xsk_get_notify_async_result: *const c_void,
+    xsk_set_sockopt: unsafe extern "system" fn(socket: HANDLE, name: u32, value: *const c_void, len: u32) -> HRESULT,
+    xsk_get_sockopt: unsafe extern "system" fn(socket: HANDLE, name: u32, value: *mut c_void, len: *mut u32) -> HRESULT,
+    xsk_ioctl: *const c_void,
+}
+
+/// Signature of the entry point of XDP for Windows (see `XdpOpenApi` in `xdpapi.h`).
+type XdpOpenApiFn = unsafe extern "system" fn(version: u32, table: *mut *const XdpApiTable) -> HRESULT;
+
+/// XDP API
+///
+/// Functions of XDP for Windows, which are loaded at run time from `xdpapi.dll`, so that Demikernel does not depend on
+/// XDP for Windows unless Catpowder is actually used.
+pub struct XdpApi {
+    module: HINSTANCE,
+    table: *const XdpApiTable,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associated functions for the XDP API.
+impl XdpApi {
+    /// Loads the XDP API.
+    pub fn new() -> Result<Self, Fail> {
+        let module: HINSTANCE = match unsafe { LoadLibraryA(PCSTR(b"xdpapi.dll\0".as_ptr())) } {
+            Ok(module) => module,
+            Err(_) => return Err(Fail::new(libc::ENOENT, "failed to load xdpapi.dll")),
+        };
+        let open: XdpOpenApiFn = match unsafe { GetProcAddress(module, PCSTR(b"XdpOpenApi\0".as_ptr())) } {
+            // Safety: this is the documented signature of the entry point.
+            Some(open) => unsafe { mem::transmute(open) },
+            None => {
+                unsafe { FreeLibrary(module) };
+                return Err(Fail::new(libc::ENOSYS, "failed to find XdpOpenApi in xdpapi.dll"));
+            },
+        };
+        let mut table: *const XdpApiTable = ptr::null();
+        let hr: HRESULT = unsafe { open(XDP_API_VERSION_1, &mut table) };
+        if hr.is_err() {
+            unsafe { FreeLibrary(module) };
+            return Err(Self::fail(hr, "failed to open XDP API"));
+        }
+        Ok(Self { module, table })
+    }
+
+    /// Creates an XDP socket.
+    pub fn create_socket(&self) -> Result<HANDLE, Fail> {
+        let mut socket: HANDLE = HANDLE::default();
+        let hr: HRESULT = unsafe { ((*self.table).xsk_create)(&mut socket) };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to create XDP socket"));
+        }
+        Ok(socket)
+    }
+
+    /// Binds the XDP socket `socket` to the queue `queue_id` of the network interface with index `ifindex`.
+    pub fn bind(&self, socket: HANDLE, ifindex: u32, queue_id: u32, flags: u32) -> Result<(), Fail> {
+        let hr: HRESULT = unsafe { ((*self.table).xsk_bind)(socket, ifindex, queue_id, flags) };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to bind XDP socket"));
+        }
+        Ok(())
+    }
+
+    /// Activates the XDP socket `socket`, once its UMEM and rings are set up.
+    pub fn activate(&self, socket: HANDLE) -> Result<(), Fail> {
+        let hr: HRESULT = unsafe { ((*self.table).xsk_activate)(socket, 0) };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to activate XDP socket"));
+        }
+        Ok(())
+    }
+
+    /// Pokes the driver to process the rings of the XDP socket `socket`, without waiting.
+    pub fn notify(&self, socket: HANDLE, flags: u32) -> Result<(), Fail> {
+        let mut result: u32 = 0;
+        let hr: HRESULT = unsafe { ((*self.table).xsk_notify_socket)(socket, flags, 0, &mut result) };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to notify XDP socket"));
+        }
+        Ok(())
+    }
+
+    /// Sets an option of the XDP socket `socket`.
+    pub fn set_sockopt<T>(&self, socket: HANDLE, name: u32, value: &T) -> Result<(), Fail> {
+        let hr: HRESULT = unsafe {
+            ((*self.table).xsk_set_sockopt)(
+                socket,
+                name,
+                value as *const T as *const c_void,
+                mem::size_of::<T>() as u32,
+            )
+        };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to set option of XDP socket"));
+        }
+        Ok(())
+    }
+
+    /// Gets an option of the XDP socket `socket`.
+    pub fn get_sockopt<T>(&self, socket: HANDLE, name: u32) -> Result<T, Fail> {
+        let mut value: mem::MaybeUninit<T> = mem::MaybeUninit::uninit();
+        let mut len: u32 = mem::size_of::<T>() as u32;
+        let hr: HRESULT =
+            unsafe { ((*self.table).xsk_get_sockopt)(socket, name, value.as_mut_ptr() as *mut c_void, &mut len) };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to get option of XDP socket"));
+        }
+        if len as usize != mem::size_of::<T>() {
+            return Err(Fail::new(libc::EINVAL, "unexpected size of option of XDP socket"));
+        }
+        // Safety: XDP filled in the whole value.
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Creates an XDP program on the receive path of the queue `queue_id` of the network interface with index
+    /// `ifindex`. The program is detached once its handle is closed.
+    pub fn create_program(&self, ifindex: u32, queue_id: u32, rules: &[XdpRule]) -> Result<HANDLE, Fail> {
+        let hook_id: XdpHookId = XdpHookId {
+            layer: XDP_HOOK_L2,
+            direction: XDP_HOOK_RX,
+            sub_layer: XDP_HOOK_INSPECT,
+        };
+        let mut program: HANDLE = HANDLE::default();
+        let hr: HRESULT = unsafe {
+            ((*self.table).xdp_create_program)(
+                ifindex,
+                &hook_id,
+                queue_id,
+                0,
+                rules.as_ptr(),
+                rules.len() as u32,
+                &mut program,
+            )
+        };
+        if hr.is_err() {
+            return Err(Self::fail(hr, "failed to create XDP program"));
+        }
+        Ok(program)
+    }
+
+    /// Closes a handle that was returned by the XDP API.
+    pub fn close(&self, handle: HANDLE) {
+        unsafe { CloseHandle(handle) };
+    }
+
+    /// Builds a failure out of the error code `hr`.
+    fn fail(hr: HRESULT, msg: &str) -> Fail {
+        Fail::new(libc::EIO, &format!("{} (HRESULT {:#x})", msg, hr.0))
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Drop trait implementation for the XDP API.
+impl Drop for XdpApi {
+    fn drop(&mut self) {
+        unsafe {
+            ((*self.table).xdp_close_api)(self.table);
+            FreeLibrary(self.module);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::XdpRuntime;
+use crate::runtime::memory::MemoryRuntime;
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Memory Runtime Trait Implementation for XDP Runtime
+impl MemoryRuntime for XdpRuntime {}
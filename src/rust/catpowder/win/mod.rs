@@ -0,0 +1,129 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod api;
+mod memory;
+mod network;
+mod program;
+mod socket;
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use self::{
+    api::XdpApi,
+    program::XdpProgram,
+    socket::XdpSocket,
+};
+use crate::{
+    inetstack::protocols::ethernet2::ETHERNET2_HEADER_SIZE,
+    runtime::{
+        network::{
+            config::{
+                ArpConfig,
+                TcpConfig,
+                UdpConfig,
+            },
+            types::{
+                MacAddress,
+                Route,
+            },
+            xsk::FRAME_SIZE,
+        },
+        Runtime,
+    },
+};
+use ::std::{
+    cell::RefCell,
+    collections::HashMap,
+    net::Ipv4Addr,
+    rc::Rc,
+    time::Duration,
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// XDP Runtime
+///
+/// Sends and receives frames through an XDP socket of XDP for Windows, which is bound to a queue of a network
+/// interface. An XDP program redirects all frames that arrive on that queue to the socket, thus traffic of Windows
+/// should be steered to other queues (e.g. with RSS).
+#[derive(Clone)]
+pub struct XdpRuntime {
+    pub tcp_options: TcpConfig,
+    pub udp_options: UdpConfig,
+    pub arp_options: ArpConfig,
+    pub link_addr: MacAddress,
+    pub ipv4_addr: Ipv4Addr,
+    /// XDP program that redirects frames to the socket. It comes first so that it is detached before the socket closes.
+    _program: Rc<XdpProgram>,
+    socket: Rc<RefCell<XdpSocket>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for XDP Runtime
+impl XdpRuntime {
+    /// Instantiates an XDP Runtime on the queue `queue_id` of the network interface with index `ifindex`.
+    pub fn new(
+        link_addr: MacAddress,
+        ipv4_addr: Ipv4Addr,
+        ifindex: u32,
+        queue_id: u32,
+        arp: HashMap<Ipv4Addr, MacAddress>,
+        arp_announcements: Option<usize>,
+        routes: Vec<Route>,
+        mtu: u16,
+        shard: Option<(usize, usize)>,
+        gro: Option<bool>,
+    ) -> Self {
+        if mtu as usize + ETHERNET2_HEADER_SIZE > FRAME_SIZE {
+            panic!("MTU {} does not fit in UMEM frames", mtu);
+        }
+        let arp_options: ArpConfig = ArpConfig::new(
+            Some(Duration::from_secs(600)),
+            Some(Duration::from_secs(1)),
+            Some(2),
+            Some(arp),
+            Some(false),
+            arp_announcements,
+            Some(routes),
+        );
+
+        // TODO: Make this constructor return a Result and drop expect() calls bellow.
+        let api: Rc<XdpApi> = Rc::new(XdpApi::new().expect("could not load XDP API"));
+        let socket: XdpSocket = XdpSocket::new(api.clone(), ifindex, queue_id).expect("could not create XDP socket");
+        let program: XdpProgram =
+            XdpProgram::attach(api, ifindex, queue_id, socket.handle()).expect("could not attach XDP program");
+
+        let mut tcp_options: TcpConfig = TcpConfig::default().set_mtu(mtu as usize);
+        if let Some((index, count)) = shard {
+            tcp_options = tcp_options.set_shard(index, count);
+        }
+        if let Some(gro) = gro {
+            tcp_options = tcp_options.set_gro(gro);
+        }
+
+        Self {
+            tcp_options,
+            udp_options: UdpConfig::new(None, None, Some(mtu as usize)),
+            arp_options,
+            link_addr,
+            ipv4_addr,
+            _program: Rc::new(program),
+            socket: Rc::new(RefCell::new(socket)),
+        }
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Runtime Trait Implementation for XDP Runtime
+impl Runtime for XdpRuntime {}
@@ -0,0 +1,65 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::api::{
+    XdpApi,
+    XdpRedirectParams,
+    XdpRule,
+    XDP_MATCH_ALL,
+    XDP_PROGRAM_ACTION_REDIRECT,
+    XDP_REDIRECT_TARGET_TYPE_XSK,
+};
+use crate::runtime::fail::Fail;
+use ::std::rc::Rc;
+use ::windows::Win32::Foundation::HANDLE;
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// XDP Program
+///
+/// Redirects all frames that are received on a queue of a network interface to an XDP socket. The program is detached
+/// once dropped.
+pub struct XdpProgram {
+    api: Rc<XdpApi>,
+    handle: HANDLE,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associated functions for XDP programs.
+impl XdpProgram {
+    /// Attaches a program to the queue `queue_id` of the network interface with index `ifindex`, which redirects frames
+    /// to the XDP socket `socket`.
+    pub fn attach(api: Rc<XdpApi>, ifindex: u32, queue_id: u32, socket: HANDLE) -> Result<Self, Fail> {
+        let rule: XdpRule = XdpRule {
+            match_type: XDP_MATCH_ALL,
+            pattern: [0; 6],
+            action: XDP_PROGRAM_ACTION_REDIRECT,
+            redirect: XdpRedirectParams {
+                target_type: XDP_REDIRECT_TARGET_TYPE_XSK,
+                target: socket,
+            },
+        };
+        let handle: HANDLE = api.create_program(ifindex, queue_id, &[rule])?;
+        Ok(Self { api, handle })
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Drop trait implementation for XDP programs. Closing the program detaches it from the network interface.
+impl Drop for XdpProgram {
+    fn drop(&mut self) {
+        self.api.close(self.handle);
+    }
+}
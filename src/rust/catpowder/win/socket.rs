@@ -0,0 +1,217 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::api::{
+    XdpApi,
+    XskRingInfo,
+    XskRingInfoSet,
+    XskUmemReg,
+    XSK_BIND_FLAG_RX,
+    XSK_BIND_FLAG_TX,
+    XSK_NOTIFY_FLAG_POKE_RX,
+    XSK_NOTIFY_FLAG_POKE_TX,
+    XSK_RING_FLAG_NEED_POKE,
+    XSK_SOCKOPT_RING_INFO,
+    XSK_SOCKOPT_RX_FILL_RING_SIZE,
+    XSK_SOCKOPT_RX_RING_SIZE,
+    XSK_SOCKOPT_TX_COMPLETION_RING_SIZE,
+    XSK_SOCKOPT_TX_RING_SIZE,
+    XSK_SOCKOPT_UMEM_REG,
+};
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        xsk::{
+            XskDescriptor,
+            XskFrames,
+            XskRing,
+            FRAME_HEADROOM,
+            FRAME_SIZE,
+            RING_SIZE,
+            UMEM_SIZE,
+        },
+        PacketBuf,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    alloc::{
+        self,
+        Layout,
+    },
+    ffi::c_void,
+    rc::Rc,
+};
+use ::windows::Win32::Foundation::HANDLE;
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Number of bits of buffer addresses that hold the base address of the frame, while the upper ones hold the offset
+/// of the data within it (see `XSK_BUFFER_ADDRESS` in `afxdp.h`).
+const BUFFER_ADDRESS_BASE_BITS: u32 = 48;
+
+/// Descriptor of the receive and transmit rings (see `XSK_BUFFER_DESCRIPTOR` in `afxdp.h`).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct XskBufferDescriptor {
+    address: u64,
+    length: u32,
+    reserved: u32,
+}
+
+/// XDP Socket
+///
+/// Frames are exchanged with XDP through the rings of the socket (see [XskFrames]), which own the UMEM.
+pub struct XdpSocket {
+    api: Rc<XdpApi>,
+    handle: HANDLE,
+    frames: XskFrames<XskBufferDescriptor>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associated functions for XDP sockets.
+impl XdpSocket {
+    /// Creates an XDP socket that is bound to the queue `queue_id` of the network interface with index `ifindex`.
+    pub fn new(api: Rc<XdpApi>, ifindex: u32, queue_id: u32) -> Result<Self, Fail> {
+        let handle: HANDLE = api.create_socket()?;
+
+        // Should anything fail from here on, the UMEM is released and the socket is closed.
+        let umem: *mut u8 = unsafe { alloc::alloc_zeroed(Self::umem_layout()) };
+        if umem.is_null() {
+            api.close(handle);
+            return Err(Fail::new(libc::ENOMEM, "failed to allocate UMEM"));
+        }
+        let umem_reg: XskUmemReg = XskUmemReg {
+            total_size: UMEM_SIZE as u64,
+            chunk_size: FRAME_SIZE as u32,
+            headroom: FRAME_HEADROOM,
+            address: umem as *mut c_void,
+        };
+        let rings: Result<XskRingInfoSet, Fail> = api
+            .set_sockopt(handle, XSK_SOCKOPT_UMEM_REG, &umem_reg)
+            .and_then(|_| api.set_sockopt(handle, XSK_SOCKOPT_RX_FILL_RING_SIZE, &RING_SIZE))
+            .and_then(|_| api.set_sockopt(handle, XSK_SOCKOPT_TX_COMPLETION_RING_SIZE, &RING_SIZE))
+            .and_then(|_| api.set_sockopt(handle, XSK_SOCKOPT_RX_RING_SIZE, &RING_SIZE))
+            .and_then(|_| api.set_sockopt(handle, XSK_SOCKOPT_TX_RING_SIZE, &RING_SIZE))
+            .and_then(|_| api.bind(handle, ifindex, queue_id, XSK_BIND_FLAG_RX | XSK_BIND_FLAG_TX))
+            .and_then(|_| api.activate(handle))
+            .and_then(|_| api.get_sockopt::<XskRingInfoSet>(handle, XSK_SOCKOPT_RING_INFO));
+        let rings: XskRingInfoSet = match rings {
+            Ok(rings) => rings,
+            Err(e) => {
+                api.close(handle);
+                unsafe { alloc::dealloc(umem, Self::umem_layout()) };
+                return Err(e);
+            },
+        };
+
+        // Safety: the rings live as long as the socket, and the UMEM is aligned on pages.
+        let frames: XskFrames<XskBufferDescriptor> = unsafe {
+            XskFrames::new(
+                umem,
+                Self::release_umem,
+                Self::ring(&rings.fill),
+                Self::ring(&rings.completion),
+                Self::ring(&rings.rx),
+                Self::ring(&rings.tx),
+            )
+        };
+
+        Ok(XdpSocket { api, handle, frames })
+    }
+
+    /// Gets the handle of the target XDP socket.
+    pub fn handle(&self) -> HANDLE {
+        self.handle
+    }
+
+    /// Transmits a [PacketBuf].
+    pub fn transmit(&mut self, pkt: Box<dyn PacketBuf>) -> Result<(), Fail> {
+        // The driver only processes the transmit ring when it is poked.
+        if self.frames.transmit_packet(pkt)? {
+            self.api.notify(self.handle, XSK_NOTIFY_FLAG_POKE_TX)?;
+        }
+        Ok(())
+    }
+
+    /// Receives a batch of frames, without copying them out of the UMEM.
+    pub fn receive(&mut self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
+        let (bufs, needs_poke): (ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>, bool) = self.frames.receive_batch();
+
+        // The driver stops receiving frames when it runs out of frames to fill, until it is poked.
+        if needs_poke {
+            if let Err(e) = self.api.notify(self.handle, XSK_NOTIFY_FLAG_POKE_RX) {
+                warn!("failed to poke XDP socket: {:?}", e);
+            }
+        }
+        bufs
+    }
+
+    /// Builds a ring out of the layout that XDP reported.
+    ///
+    /// # Safety
+    ///
+    /// The ring must live as long as it is used.
+    unsafe fn ring(info: &XskRingInfo) -> XskRing {
+        debug_assert_eq!(info.size, RING_SIZE);
+        XskRing::new(
+            info.ring.add(info.producer_index_offset as usize),
+            info.ring.add(info.consumer_index_offset as usize),
+            info.ring.add(info.flags_offset as usize),
+            info.ring.add(info.descriptors_offset as usize),
+            info.element_stride as usize,
+            XSK_RING_FLAG_NEED_POKE,
+        )
+    }
+
+    /// Releases the UMEM of an XDP socket, once neither the socket nor any buffer that refers to the UMEM is used.
+    unsafe fn release_umem(umem: *mut u8) {
+        alloc::dealloc(umem, Self::umem_layout());
+    }
+
+    /// Gets the layout of the UMEM, whose frames are aligned on pages.
+    fn umem_layout() -> Layout {
+        Layout::from_size_align(UMEM_SIZE, FRAME_SIZE).expect("UMEM layout should be valid")
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Descriptor trait implementation for buffer descriptors of XDP. Their address holds the offset of the data above
+/// the base address of its frame.
+impl XskDescriptor for XskBufferDescriptor {
+    fn new(addr: u64, len: u32) -> Self {
+        Self {
+            address: addr,
+            length: len,
+            reserved: 0,
+        }
+    }
+
+    fn data(&self) -> (u64, usize) {
+        let base: u64 = self.address & ((1 << BUFFER_ADDRESS_BASE_BITS) - 1);
+        let offset: u64 = self.address >> BUFFER_ADDRESS_BASE_BITS;
+        (base + offset, self.length as usize)
+    }
+}
+
+/// Drop trait implementation for XDP sockets. The UMEM is released once no buffer refers to it anymore, as XDP no
+/// longer accesses it once the socket is closed.
+impl Drop for XdpSocket {
+    fn drop(&mut self) {
+        self.api.close(self.handle);
+    }
+}
@@ -3,4 +3,3 @@
 
 pub mod double_linked_list;
 pub mod intrusive_queue;
-pub mod pairing_heap;
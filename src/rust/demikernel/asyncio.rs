@@ -0,0 +1,384 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Native async/await front-end.
+//!
+//! This module wraps sockets of a [LibOS] in [TcpListener], [TcpStream] and [UdpSocket] types, whose I/O operations
+//! return futures instead of queue tokens. These futures are driven by the scheduler of the underlying LibOS, which is
+//! polled every time that a future is polled, so they may be run by any single-threaded executor. For instance:
+//!
+//! ```no_run
+//! use ::demikernel::{
+//!     demikernel::asyncio::TcpListener,
+//!     LibOS,
+//! };
+//! use ::std::{
+//!     cell::RefCell,
+//!     net::SocketAddrV4,
+//!     rc::Rc,
+//! };
+//!
+//! let libos: Rc<RefCell<LibOS>> = Rc::new(RefCell::new(LibOS::from_config().unwrap()));
+//! let local: SocketAddrV4 = "127.0.0.1:12345".parse().unwrap();
+//! futures::executor::block_on(async {
+//!     let listener: TcpListener = TcpListener::bind(&libos, local).unwrap();
+//!     let (stream, _) = listener.accept().await.unwrap();
+//!     let mut buf: [u8; 64] = [0; 64];
+//!     let nbytes: usize = stream.read(&mut buf).await.unwrap();
+//!     stream.write(&buf[..nbytes]).await.unwrap();
+//! });
+//! ```
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::{
+        bindings::sockaddr_to_socketaddrv4,
+        libos::LibOS,
+    },
+    pal::data_structures::SockAddr,
+    runtime::{
+        fail::Fail,
+        memory::{
+            copy_from_sgarray,
+            copy_to_sgarray,
+        },
+        types::{
+            demi_opcode_t,
+            demi_qresult_t,
+            demi_sgarray_t,
+            DEMI_SGARRAY_MAXLEN,
+        },
+        QDesc,
+        QToken,
+    },
+};
+use ::libc::sockaddr;
+use ::std::{
+    cell::{
+        RefCell,
+        RefMut,
+    },
+    cmp,
+    future::Future,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Backlog of listening sockets.
+const LISTEN_BACKLOG: usize = 16;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Pending I/O Operation
+///
+/// Resolves to the result of the operation once it completes. If dropped
+/// before that, the result is released by the socket on which the operation
+/// was issued, once it comes.
+struct Operation<'a> {
+    /// Socket on which the operation was issued.
+    socket: &'a Socket,
+    /// Queue token of the operation, until its result is taken.
+    qt: Option<QToken>,
+}
+
+/// Socket that is closed once dropped.
+struct Socket {
+    /// Underlying LibOS.
+    libos: Rc<RefCell<LibOS>>,
+    /// Queue descriptor of the socket.
+    qd: QDesc,
+    /// Queue tokens of operations that were dropped before they completed.
+    orphans: RefCell<Vec<QToken>>,
+}
+
+/// Listening TCP Socket
+pub struct TcpListener {
+    socket: Socket,
+}
+
+/// Connected TCP Socket
+pub struct TcpStream {
+    socket: Socket,
+}
+
+/// UDP Socket
+pub struct UdpSocket {
+    socket: Socket,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Associated Functions for Pending I/O Operations
+impl<'a> Operation<'a> {
+    /// Creates a future for the operation that is identified by `qt`, which was issued on `socket`.
+    fn new(socket: &'a Socket, qt: QToken) -> Self {
+        Self { socket, qt: Some(qt) }
+    }
+}
+
+/// Associated Functions for Sockets
+impl Socket {
+    /// Creates a socket of the given type.
+    fn new(libos: &Rc<RefCell<LibOS>>, socket_type: libc::c_int) -> Result<Self, Fail> {
+        let qd: QDesc = libos.borrow_mut().socket(libc::AF_INET, socket_type, 0)?;
+        Ok(Self::from_qd(libos, qd))
+    }
+
+    /// Wraps the socket that is identified by `qd`.
+    fn from_qd(libos: &Rc<RefCell<LibOS>>, qd: QDesc) -> Self {
+        Self {
+            libos: libos.clone(),
+            qd,
+            orphans: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Takes the results of orphaned operations that have completed, and releases them.
+    fn reap(&self, libos: &mut LibOS) {
+        self.orphans.borrow_mut().retain(|&qt| match libos.try_wait(qt) {
+            Ok(Some(qr)) => {
+                Self::release(libos, qr);
+                false
+            },
+            Ok(None) => true,
+            Err(e) => {
+                warn!("failed to take orphaned operation (qt={:?}): {:?}", qt, e);
+                false
+            },
+        });
+    }
+
+    /// Releases the resources that are held by the result of an operation that nobody waits for.
+    fn release(libos: &mut LibOS, qr: demi_qresult_t) {
+        let result: Result<(), Fail> = match qr.qr_opcode {
+            // Safety: The scatter-gather array is the active field of the union for pop operations.
+            demi_opcode_t::DEMI_OPC_POP => libos.sgafree(unsafe { qr.qr_value.sga }),
+            // Safety: The accept result is the active field of the union for accept operations.
+            demi_opcode_t::DEMI_OPC_ACCEPT => libos.close(QDesc::from(unsafe { qr.qr_value.ares.qd })),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!("failed to release result of orphaned operation: {:?}", e);
+        }
+    }
+
+    /// Creates a socket and binds it to a local address.
+    fn bind(libos: &Rc<RefCell<LibOS>>, socket_type: libc::c_int, local: SocketAddrV4) -> Result<Self, Fail> {
+        let socket: Socket = Socket::new(libos, socket_type)?;
+        libos.borrow_mut().bind(socket.qd, local)?;
+        Ok(socket)
+    }
+
+    /// Pushes data to the socket, optionally to a given remote address. This returns the number of bytes that were
+    /// pushed, which may be less than the length of `buf` if it does not fit in a single scatter-gather array.
+    async fn push(&self, buf: &[u8], to: Option<SocketAddrV4>) -> Result<usize, Fail> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let len: usize = cmp::min(buf.len(), DEMI_SGARRAY_MAXLEN * u16::MAX as usize);
+        let qt: QToken = {
+            let mut libos: RefMut<LibOS> = self.libos.borrow_mut();
+            let sga: demi_sgarray_t = libos.sgaalloc(len)?;
+            copy_to_sgarray(&sga, &buf[..len]);
+            let result: Result<QToken, Fail> = match to {
+                Some(remote) => libos.pushto(self.qd, &sga, remote),
+                None => libos.push(self.qd, &sga),
+            };
+            // The LibOS holds its own reference to the pushed data, so we may release the scatter-gather array now.
+            libos.sgafree(sga)?;
+            result?
+        };
+        Operation::new(self, qt).await?;
+        Ok(len)
+    }
+
+    /// Pops data from the socket. This returns the number of bytes that were popped, along with the address of the
+    /// remote peer that sent them.
+    async fn pop(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), Fail> {
+        let size: usize = cmp::min(buf.len(), u16::MAX as usize);
+        let qt: QToken = self.libos.borrow_mut().pop_sized(self.qd, size)?;
+        let qr: demi_qresult_t = Operation::new(self, qt).await?;
+
+        // Safety: The scatter-gather array is the active field of the union for pop operations.
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+        let nbytes: usize = copy_from_sgarray(&sga, buf);
+        let remote: Result<SocketAddrV4, Fail> =
+            sockaddr_to_socketaddrv4(&sga.sga_addr as *const SockAddr as *const sockaddr);
+        self.libos.borrow_mut().sgafree(sga)?;
+        Ok((
+            nbytes,
+            remote.unwrap_or_else(|_| SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)),
+        ))
+    }
+}
+
+/// Associated Functions for Listening TCP Sockets
+impl TcpListener {
+    /// Creates a TCP socket that listens for incoming connections on a local address.
+    pub fn bind(libos: &Rc<RefCell<LibOS>>, local: SocketAddrV4) -> Result<Self, Fail> {
+        let socket: Socket = Socket::bind(libos, libc::SOCK_STREAM, local)?;
+        libos.borrow_mut().listen(socket.qd, LISTEN_BACKLOG)?;
+        Ok(Self { socket })
+    }
+
+    /// Accepts an incoming connection, returning the new stream along with the address of the remote peer.
+    pub async fn accept(&self) -> Result<(TcpStream, SocketAddrV4), Fail> {
+        let qt: QToken = self.socket.libos.borrow_mut().accept(self.socket.qd)?;
+        let qr: demi_qresult_t = Operation::new(&self.socket, qt).await?;
+
+        // Safety: The accept result is the active field of the union for accept operations.
+        let (qd, remote): (QDesc, sockaddr) = unsafe { (QDesc::from(qr.qr_value.ares.qd), qr.qr_value.ares.addr) };
+        let stream: TcpStream = TcpStream {
+            socket: Socket::from_qd(&self.socket.libos, qd),
+        };
+        Ok((stream, sockaddr_to_socketaddrv4(&remote)?))
+    }
+
+    /// Returns the local address of the target listener.
+    pub fn local_addr(&self) -> Result<SocketAddrV4, Fail> {
+        self.socket.libos.borrow().local_addr(self.socket.qd)
+    }
+}
+
+/// Associated Functions for Connected TCP Sockets
+impl TcpStream {
+    /// Opens a TCP connection to a remote peer.
+    pub async fn connect(libos: &Rc<RefCell<LibOS>>, remote: SocketAddrV4) -> Result<Self, Fail> {
+        let socket: Socket = Socket::new(libos, libc::SOCK_STREAM)?;
+        let qt: QToken = libos.borrow_mut().connect(socket.qd, remote)?;
+        Operation::new(&socket, qt).await?;
+        Ok(Self { socket })
+    }
+
+    /// Reads data from the target stream into `buf`, returning the number of bytes that were read. A return value of
+    /// zero means that the remote peer has closed the connection.
+    pub async fn read(&self, buf: &mut [u8]) -> Result<usize, Fail> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let (nbytes, _): (usize, SocketAddrV4) = self.socket.pop(buf).await?;
+        Ok(nbytes)
+    }
+
+    /// Writes data from `buf` to the target stream, returning the number of bytes that were written.
+    pub async fn write(&self, buf: &[u8]) -> Result<usize, Fail> {
+        self.socket.push(buf, None).await
+    }
+
+    /// Returns the local address of the target stream.
+    pub fn local_addr(&self) -> Result<SocketAddrV4, Fail> {
+        self.socket.libos.borrow().local_addr(self.socket.qd)
+    }
+
+    /// Returns the address of the remote peer of the target stream.
+    pub fn peer_addr(&self) -> Result<SocketAddrV4, Fail> {
+        self.socket.libos.borrow().remote_addr(self.socket.qd)
+    }
+}
+
+/// Associated Functions for UDP Sockets
+impl UdpSocket {
+    /// Creates a UDP socket that is bound to a local address.
+    pub fn bind(libos: &Rc<RefCell<LibOS>>, local: SocketAddrV4) -> Result<Self, Fail> {
+        let socket: Socket = Socket::bind(libos, libc::SOCK_DGRAM, local)?;
+        Ok(Self { socket })
+    }
+
+    /// Sends a datagram to a remote address, returning the number of bytes that were sent.
+    pub async fn send_to(&self, buf: &[u8], to: SocketAddrV4) -> Result<usize, Fail> {
+        self.socket.push(buf, Some(to)).await
+    }
+
+    /// Receives a datagram into `buf`, returning the number of bytes that were received along with the address of the
+    /// sender.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), Fail> {
+        self.socket.pop(buf).await
+    }
+
+    /// Returns the local address of the target socket.
+    pub fn local_addr(&self) -> Result<SocketAddrV4, Fail> {
+        self.socket.libos.borrow().local_addr(self.socket.qd)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Future Trait Implementation for Pending I/O Operations
+impl<'a> Future for Operation<'a> {
+    type Output = Result<demi_qresult_t, Fail>;
+
+    /// Polls the target [Operation]. This polls the underlying LibOS, so as to drive the operation to completion.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut Operation = self.get_mut();
+        let qt: QToken = self_.qt.expect("operation polled after completion");
+        let mut libos: RefMut<LibOS> = self_.socket.libos.borrow_mut();
+        let result: Result<Option<demi_qresult_t>, Fail> = libos.try_wait(qt);
+        self_.socket.reap(&mut libos);
+        if !matches!(result, Ok(None)) {
+            self_.qt = None;
+        }
+        match result {
+            // Operation completed.
+            Ok(Some(qr)) => match qr.qr_opcode {
+                demi_opcode_t::DEMI_OPC_FAILED => Poll::Ready(Err(Fail::new(qr.qr_ret as i32, "operation failed"))),
+                _ => Poll::Ready(Ok(qr)),
+            },
+            // Operation in progress. The LibOS has no means to notify us, so we ask to be polled again.
+            Ok(None) => {
+                ctx.waker().wake_by_ref();
+                Poll::Pending
+            },
+            // Error.
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+}
+
+/// Drop Trait Implementation for Pending I/O Operations
+impl<'a> Drop for Operation<'a> {
+    /// Takes the result of the target [Operation] if it has completed, and otherwise leaves it to the socket on which
+    /// the operation was issued, so that its queue token is not leaked.
+    fn drop(&mut self) {
+        if let Some(qt) = self.qt.take() {
+            self.socket.orphans.borrow_mut().push(qt);
+            self.socket.reap(&mut self.socket.libos.borrow_mut());
+        }
+    }
+}
+
+/// Drop Trait Implementation for Sockets
+impl Drop for Socket {
+    /// Closes the target [Socket], which cancels its pending operations, and then releases orphaned operations.
+    fn drop(&mut self) {
+        let mut libos: RefMut<LibOS> = self.libos.borrow_mut();
+        if let Err(e) = libos.close(self.qd) {
+            warn!("failed to close socket (qd={:?}): {:?}", self.qd, e);
+        }
+        self.reap(&mut libos);
+        if !self.orphans.borrow().is_empty() {
+            warn!("leaking {} operations (qd={:?})", self.orphans.borrow().len(), self.qd);
+        }
+    }
+}
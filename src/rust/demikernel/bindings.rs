@@ -11,25 +11,55 @@ use crate::{
         LibOS,
     },
     pal::{
-        constants::AF_INET,
+        constants::{
+            AF_INET,
+            IPPROTO_IP,
+            IPPROTO_TCP,
+            IP_PKTINFO,
+            IP_TTL,
+            SHUT_RD,
+            SHUT_RDWR,
+            SHUT_WR,
+            SOL_SOCKET,
+            SO_BROADCAST,
+            SO_KEEPALIVE,
+            SO_MAX_PACING_RATE,
+            SO_RCVBUF,
+            SO_SNDBUF,
+            TCP_NODELAY,
+        },
         data_structures::{
             SockAddrIn,
             Socklen,
         },
-        functions::get_addr_from_sock_addr_in,
+        functions::{
+            create_sin_addr,
+            create_sin_zero,
+            get_addr_from_sock_addr_in,
+        },
     },
     runtime::{
         fail::Fail,
         logging,
+        network::types::SocketOption,
         types::{
             demi_qresult_t,
             demi_qtoken_t,
             demi_sgarray_t,
             demi_sgaseg_t,
+            demi_timestamps_t,
+            DEMI_SGARRAY_MAXLEN,
         },
         QToken,
     },
 };
+// Windows has no equivalent of `TCP_CORK` and `TCP_QUICKACK`, and the values that Linux uses are taken by
+// `TCP_KEEPALIVE` and `TCP_CONGESTION_ALGORITHM` there.
+#[cfg(target_os = "linux")]
+use crate::pal::constants::{
+    TCP_CORK,
+    TCP_QUICKACK,
+};
 use ::libc::{
     c_char,
     c_int,
@@ -42,6 +72,7 @@ use ::std::{
     mem,
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     ptr,
@@ -51,6 +82,15 @@ use ::std::{
         SystemTime,
     },
 };
+#[cfg(target_os = "linux")]
+use ::std::{
+    ffi::OsStr,
+    os::unix::ffi::OsStrExt,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
 
 //======================================================================================================================
 // DEMIKERNEL
@@ -69,13 +109,14 @@ pub extern "C" fn demi_init(argc: c_int, argv: *mut *mut c_char) -> c_int {
     logging::initialize();
     trace!("demi_init()");
 
-    let libos_name: LibOSName = match LibOSName::from_env() {
-        Ok(libos_name) => libos_name.into(),
-        Err(e) => panic!("{:?}", e),
+    // The LIBOS environment variable is optional, as the LibOS may also be selected at runtime.
+    let libos: Result<LibOS, Fail> = match LibOSName::from_env() {
+        Ok(libos_name) => LibOS::new(libos_name),
+        Err(_) => LibOS::from_config(),
     };
 
     // TODO: Pass arguments to the underlying libOS.
-    let libos: LibOS = match LibOS::new(libos_name) {
+    let libos: LibOS = match libos {
         Ok(libos) => libos,
         Err(e) => {
             trace!("demi_init() failed: {:?}", e);
@@ -88,6 +129,34 @@ pub extern "C" fn demi_init(argc: c_int, argv: *mut *mut c_char) -> c_int {
     0
 }
 
+//======================================================================================================================
+// teardown
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_teardown() -> c_int {
+    trace!("demi_teardown()");
+
+    match do_teardown() {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_teardown() failed: {:?}", e);
+            e.errno
+        },
+    }
+}
+
+//======================================================================================================================
+// request_teardown
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_request_teardown() -> c_int {
+    // Nothing else may be done here, because this is meant to be called from signal handlers.
+    LibOS::request_teardown();
+    0
+}
+
 //======================================================================================================================
 // create
 //======================================================================================================================
@@ -191,6 +260,29 @@ pub extern "C" fn demi_bind(qd: c_int, saddr: *const sockaddr, size: Socklen) ->
         return libc::EINVAL;
     }
 
+    // Unix domain sockets are bound to a path.
+    #[cfg(target_os = "linux")]
+    if is_unix_sockaddr(saddr, size) {
+        let path: PathBuf = match sockaddr_to_unix_path(saddr, size) {
+            Ok(path) => path,
+            Err(e) => {
+                trace!("demi_bind() failed: {:?}", e);
+                return e.errno;
+            },
+        };
+        let ret: Result<i32, Fail> = do_syscall(|libos| match libos.bind_unix(qd.into(), &path) {
+            Ok(..) => 0,
+            Err(e) => {
+                trace!("demi_bind() failed: {:?}", e);
+                e.errno
+            },
+        });
+        return match ret {
+            Ok(ret) => ret,
+            Err(e) => e.errno,
+        };
+    }
+
     // Check if socket address length is invalid.
     if size as usize != mem::size_of::<SockAddrIn>() {
         return libc::EINVAL;
@@ -294,6 +386,32 @@ pub extern "C" fn demi_connect(
         return libc::EINVAL;
     }
 
+    // Unix domain sockets are connected to a path.
+    #[cfg(target_os = "linux")]
+    if is_unix_sockaddr(saddr, size) {
+        let path: PathBuf = match sockaddr_to_unix_path(saddr, size) {
+            Ok(path) => path,
+            Err(e) => {
+                trace!("demi_connect() failed: {:?}", e);
+                return e.errno;
+            },
+        };
+        let ret: Result<i32, Fail> = do_syscall(|libos| match libos.connect_unix(sockqd.into(), &path) {
+            Ok(qt) => {
+                unsafe { *qtok_out = qt.into() };
+                0
+            },
+            Err(e) => {
+                trace!("demi_connect() failed: {:?}", e);
+                e.errno
+            },
+        });
+        return match ret {
+            Ok(ret) => ret,
+            Err(e) => e.errno,
+        };
+    }
+
     // Check if socket address length is invalid.
     if size as usize != mem::size_of::<SockAddrIn>() {
         return libc::EINVAL;
@@ -326,6 +444,66 @@ pub extern "C" fn demi_connect(
     }
 }
 
+//======================================================================================================================
+// timedconnect
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_timedconnect(
+    qtok_out: *mut demi_qtoken_t,
+    sockqd: c_int,
+    saddr: *const sockaddr,
+    size: Socklen,
+    abstime: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedconnect()");
+
+    // Check if socket address is invalid.
+    if saddr.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Check if socket address length is invalid.
+    if size as usize != mem::size_of::<SockAddrIn>() {
+        return libc::EINVAL;
+    }
+
+    // Check for invalid deadline.
+    if abstime.is_null() {
+        warn!("abstime is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Get socket address.
+    let endpoint: SocketAddrV4 = match sockaddr_to_socketaddrv4(saddr) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            trace!("demi_timedconnect() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Convert timespec to SystemTime.
+    let abstime: SystemTime = timespec_to_systemtime(abstime);
+
+    // Issue connect operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timedconnect(sockqd.into(), endpoint, abstime) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_timedconnect() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // close
 //======================================================================================================================
@@ -349,6 +527,100 @@ pub extern "C" fn demi_close(qd: c_int) -> c_int {
     }
 }
 
+//======================================================================================================================
+// async_close
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_async_close(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
+    trace!("demi_async_close()");
+
+    // Check for invalid storage location for queue token.
+    if qtok_out.is_null() {
+        warn!("qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue close operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.async_close(qd.into()) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_async_close() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// dup
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_dup(qd_out: *mut c_int, qd: c_int) -> c_int {
+    trace!("demi_dup() qd={:?}", qd);
+
+    // Check if queue descriptor output is invalid.
+    if qd_out.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Issue dup operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.dup(qd.into()) {
+        Ok(new_qd) => {
+            unsafe { *qd_out = new_qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_dup() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// shutdown
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_shutdown(sockqd: c_int, how: c_int) -> c_int {
+    trace!("demi_shutdown()");
+
+    // Parse shutdown mode.
+    let how: Shutdown = match how {
+        SHUT_RD => Shutdown::Read,
+        SHUT_WR => Shutdown::Write,
+        SHUT_RDWR => Shutdown::Both,
+        _ => return libc::EINVAL,
+    };
+
+    // Issue shutdown operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.shutdown(sockqd.into(), how) {
+        Ok(..) => 0,
+        Err(e) => {
+            trace!("demi_shutdown() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // pushto
 //======================================================================================================================
@@ -373,13 +645,39 @@ pub extern "C" fn demi_pushto(
         return libc::EINVAL;
     }
 
+    let sga: &demi_sgarray_t = unsafe { &*sga };
+
+    // Datagrams of unix domain sockets are pushed to a path.
+    #[cfg(target_os = "linux")]
+    if is_unix_sockaddr(saddr, size) {
+        let path: PathBuf = match sockaddr_to_unix_path(saddr, size) {
+            Ok(path) => path,
+            Err(e) => {
+                trace!("demi_pushto() failed: {:?}", e);
+                return e.errno;
+            },
+        };
+        let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pushto_unix(sockqd.into(), sga, &path) {
+            Ok(qt) => {
+                unsafe { *qtok_out = qt.into() };
+                0
+            },
+            Err(e) => {
+                trace!("demi_pushto() failed: {:?}", e);
+                e.errno
+            },
+        });
+        return match ret {
+            Ok(ret) => ret,
+            Err(e) => e.errno,
+        };
+    }
+
     // Check if socket address length is invalid.
     if size as usize != mem::size_of::<SockAddrIn>() {
         return libc::EINVAL;
     }
 
-    let sga: &demi_sgarray_t = unsafe { &*sga };
-
     // Get socket address.
     let endpoint: SocketAddrV4 = match sockaddr_to_socketaddrv4(saddr) {
         Ok(endpoint) => endpoint,
@@ -440,21 +738,28 @@ pub extern "C" fn demi_push(qtok_out: *mut demi_qtoken_t, qd: c_int, sga: *const
 }
 
 //======================================================================================================================
-// pop
+// pushpop
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_pop(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
-    trace!("demi_pop()");
+pub extern "C" fn demi_pushpop(qtok_out: *mut demi_qtoken_t, sockqd: c_int, sga: *const demi_sgarray_t) -> c_int {
+    trace!("demi_pushpop()");
 
-    // Issue pop operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pop(qd.into()) {
+    // Check if scatter-gather array is invalid.
+    if sga.is_null() {
+        return libc::EINVAL;
+    }
+
+    let sga: &demi_sgarray_t = unsafe { &*sga };
+
+    // Issue push-pop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pushpop(sockqd.into(), sga) {
         Ok(qt) => {
             unsafe { *qtok_out = qt.into() };
             0
         },
         Err(e) => {
-            trace!("demi_pop() failed: {:?}", e);
+            trace!("demi_pushpop() failed: {:?}", e);
             e.errno
         },
     });
@@ -466,44 +771,33 @@ pub extern "C" fn demi_pop(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
 }
 
 //======================================================================================================================
-// timedwait
+// pushv
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_timedwait(
-    qr_out: *mut demi_qresult_t,
-    qt: demi_qtoken_t,
-    abstime: *const libc::timespec,
+pub extern "C" fn demi_pushv(
+    qtok_out: *mut demi_qtoken_t,
+    qd: c_int,
+    sgas: *const demi_sgarray_t,
+    num_sgas: c_int,
 ) -> c_int {
-    trace!("demi_timedwait() {:?} {:?} {:?}", qr_out, qt, abstime);
+    trace!("demi_pushv()");
 
-    // Check for invalid timeout.
-    if abstime.is_null() {
-        warn!("abstime is a null pointer");
+    // Check if scatter-gather arrays are invalid.
+    if sgas.is_null() || num_sgas <= 0 {
         return libc::EINVAL;
     }
 
-    // Convert timespec to SystemTime.
-    let abstime: Option<SystemTime> = {
-        let timeout: Duration = Duration::from_nanos(
-            unsafe { (*abstime).tv_sec } as u64 * 1_000_000_000_ + unsafe { (*abstime).tv_nsec } as u64,
-        );
-        match SystemTime::UNIX_EPOCH.checked_add(timeout) {
-            Some(abstime) => Some(abstime),
-            None => Some(SystemTime::now()),
-        }
-    };
+    let sgas: &[demi_sgarray_t] = unsafe { slice::from_raw_parts(sgas, num_sgas as usize) };
 
-    // Issue operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timedwait(qt.into(), abstime) {
-        Ok(r) => {
-            if !qr_out.is_null() {
-                unsafe { *qr_out = r };
-            }
+    // Issue vectored push operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pushv(qd.into(), sgas) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
             0
         },
         Err(e) => {
-            trace!("demi_timedwait() failed: {:?}", e);
+            trace!("demi_pushv() failed: {:?}", e);
             e.errno
         },
     });
@@ -515,31 +809,21 @@ pub extern "C" fn demi_timedwait(
 }
 
 //======================================================================================================================
-// wait
+// pop
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_wait(qr_out: *mut demi_qresult_t, qt: demi_qtoken_t, timeout: *const libc::timespec) -> c_int {
-    trace!("demi_wait() {:?} {:?} {:?}", qr_out, qt, timeout);
-
-    // Convert timespec to Duration.
-    let duration: Option<Duration> = if timeout.is_null() {
-        None
-    } else {
-        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
-        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
-    };
+pub extern "C" fn demi_pop(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
+    trace!("demi_pop()");
 
-    // Issue wait operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.wait(qt.into(), duration) {
-        Ok(r) => {
-            if !qr_out.is_null() {
-                unsafe { *qr_out = r };
-            }
+    // Issue pop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pop(qd.into()) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
             0
         },
         Err(e) => {
-            trace!("demi_wait() failed: {:?}", e);
+            trace!("demi_pop() failed: {:?}", e);
             e.errno
         },
     });
@@ -551,34 +835,274 @@ pub extern "C" fn demi_wait(qr_out: *mut demi_qresult_t, qt: demi_qtoken_t, time
 }
 
 //======================================================================================================================
-// wait_any
+// timedpop
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_wait_any(
-    qr_out: *mut demi_qresult_t,
-    ready_offset: *mut c_int,
-    qts: *mut demi_qtoken_t,
-    num_qts: c_int,
-    timeout: *const libc::timespec,
-) -> c_int {
-    trace!(
-        "demi_wait_any() {:?} {:?} {:?} {:?} {:?}",
-        qr_out,
-        ready_offset,
-        qts,
-        num_qts,
-        timeout
-    );
+pub extern "C" fn demi_timedpop(qtok_out: *mut demi_qtoken_t, qd: c_int, abstime: *const libc::timespec) -> c_int {
+    trace!("demi_timedpop()");
 
-    // Check arguments.
-    if num_qts < 0 {
+    // Check for invalid deadline.
+    if abstime.is_null() {
+        warn!("abstime is a null pointer");
         return libc::EINVAL;
     }
 
-    // Get queue tokens.
-    let qts: Vec<QToken> = {
-        let raw_qts: &[u64] = unsafe { slice::from_raw_parts(qts, num_qts as usize) };
+    // Convert timespec to SystemTime.
+    let abstime: SystemTime = timespec_to_systemtime(abstime);
+
+    // Issue pop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timedpop(qd.into(), None, abstime) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_timedpop() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// splice
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_splice(qtok_out: *mut demi_qtoken_t, srcqd: c_int, dstqd: c_int) -> c_int {
+    trace!("demi_splice()");
+
+    // Check for invalid storage location for queue token.
+    if qtok_out.is_null() {
+        warn!("qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue splice operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.splice(srcqd.into(), dstqd.into()) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_splice() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ping
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ping(
+    qtok_out: *mut demi_qtoken_t,
+    saddr: *const sockaddr,
+    size: Socklen,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!("demi_ping()");
+
+    // Check for invalid storage location for queue token.
+    if qtok_out.is_null() {
+        warn!("qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check if socket address is invalid.
+    if saddr.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Check if socket address length is invalid.
+    if size as usize != mem::size_of::<SockAddrIn>() {
+        return libc::EINVAL;
+    }
+
+    // Get remote address. The port is ignored.
+    let endpoint: SocketAddrV4 = match sockaddr_to_socketaddrv4(saddr) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            trace!("demi_ping() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Convert timespec to Duration.
+    let timeout: Option<Duration> = if timeout.is_null() {
+        None
+    } else {
+        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
+        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
+    };
+
+    // Issue ping operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ping(*endpoint.ip(), timeout) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_ping() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// link_events
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_link_events(qd_out: *mut c_int) -> c_int {
+    trace!("demi_link_events()");
+
+    // Check for invalid storage location for queue descriptor.
+    if qd_out.is_null() {
+        warn!("qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue link_events operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.link_events() {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_link_events() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// timedwait
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_timedwait(
+    qr_out: *mut demi_qresult_t,
+    qt: demi_qtoken_t,
+    abstime: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedwait() {:?} {:?} {:?}", qr_out, qt, abstime);
+
+    // Check for invalid timeout.
+    if abstime.is_null() {
+        warn!("abstime is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert timespec to SystemTime.
+    let abstime: Option<SystemTime> = Some(timespec_to_systemtime(abstime));
+
+    // Issue operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timedwait(qt.into(), abstime) {
+        Ok(r) => {
+            if !qr_out.is_null() {
+                unsafe { *qr_out = r };
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_timedwait() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// wait
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_wait(qr_out: *mut demi_qresult_t, qt: demi_qtoken_t, timeout: *const libc::timespec) -> c_int {
+    trace!("demi_wait() {:?} {:?} {:?}", qr_out, qt, timeout);
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = if timeout.is_null() {
+        None
+    } else {
+        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
+        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
+    };
+
+    // Issue wait operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.wait(qt.into(), duration) {
+        Ok(r) => {
+            if !qr_out.is_null() {
+                unsafe { *qr_out = r };
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_wait() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// wait_any
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_wait_any(
+    qr_out: *mut demi_qresult_t,
+    ready_offset: *mut c_int,
+    qts: *mut demi_qtoken_t,
+    num_qts: c_int,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!(
+        "demi_wait_any() {:?} {:?} {:?} {:?} {:?}",
+        qr_out,
+        ready_offset,
+        qts,
+        num_qts,
+        timeout
+    );
+
+    // Check arguments.
+    if num_qts < 0 {
+        return libc::EINVAL;
+    }
+
+    // Get queue tokens.
+    let qts: Vec<QToken> = {
+        let raw_qts: &[u64] = unsafe { slice::from_raw_parts(qts, num_qts as usize) };
         raw_qts.iter().map(|i| QToken::from(*i)).collect()
     };
 
@@ -611,6 +1135,40 @@ pub extern "C" fn demi_wait_any(
     }
 }
 
+//======================================================================================================================
+// eventfd
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_eventfd(fd_out: *mut c_int) -> c_int {
+    trace!("demi_eventfd() {:?}", fd_out);
+
+    // Check arguments.
+    if fd_out.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Retrieve event file descriptor.
+    #[cfg(target_os = "linux")]
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.event_fd() {
+        Ok(fd) => {
+            unsafe { *fd_out = fd };
+            0
+        },
+        Err(e) => {
+            trace!("demi_eventfd() failed: {:?}", e);
+            e.errno
+        },
+    });
+    #[cfg(not(target_os = "linux"))]
+    let ret: Result<i32, Fail> = Err(Fail::new(libc::ENOTSUP, "event file descriptors are not supported"));
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // sgaalloc
 //======================================================================================================================
@@ -626,8 +1184,11 @@ pub extern "C" fn demi_sgaalloc(size: libc::size_t) -> demi_sgarray_t {
             sga_segs: [demi_sgaseg_t {
                 sgaseg_buf: ptr::null_mut() as *mut c_void,
                 sgaseg_len: 0,
-            }; 1],
+            }; DEMI_SGARRAY_MAXLEN],
             sga_addr: unsafe { mem::zeroed() },
+            sga_pktinfo: unsafe { mem::zeroed() },
+            #[cfg(target_os = "linux")]
+            sga_unaddr: unsafe { mem::zeroed() },
         }
     };
 
@@ -651,6 +1212,54 @@ pub extern "C" fn demi_sgaalloc(size: libc::size_t) -> demi_sgarray_t {
     }
 }
 
+//======================================================================================================================
+// sgaregister
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_sgaregister(buf: *mut c_void, size: libc::size_t) -> demi_sgarray_t {
+    trace!("demi_sgaregister()");
+
+    let null_sga: demi_sgarray_t = {
+        demi_sgarray_t {
+            sga_buf: ptr::null_mut() as *mut _,
+            sga_numsegs: 0,
+            sga_segs: [demi_sgaseg_t {
+                sgaseg_buf: ptr::null_mut() as *mut c_void,
+                sgaseg_len: 0,
+            }; DEMI_SGARRAY_MAXLEN],
+            sga_addr: unsafe { mem::zeroed() },
+            sga_pktinfo: unsafe { mem::zeroed() },
+            #[cfg(target_os = "linux")]
+            sga_unaddr: unsafe { mem::zeroed() },
+        }
+    };
+
+    // Check if memory region is invalid.
+    if buf.is_null() {
+        return null_sga;
+    }
+
+    // Issue sgaregister operation.
+    let ret: Result<demi_sgarray_t, Fail> = do_syscall(|libos| -> demi_sgarray_t {
+        match libos.sgaregister(buf as *mut u8, size) {
+            Ok(sga) => sga,
+            Err(e) => {
+                trace!("demi_sgaregister() failed: {:?}", e);
+                null_sga
+            },
+        }
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => {
+            trace!("demi_sgaregister() failed: {:?}", e);
+            null_sga
+        },
+    }
+}
+
 //======================================================================================================================
 // sgafree
 //======================================================================================================================
@@ -683,18 +1292,148 @@ pub extern "C" fn demi_sgafree(sga: *mut demi_sgarray_t) -> c_int {
 // getsockname
 //======================================================================================================================
 
-#[allow(unused)]
 #[no_mangle]
 pub extern "C" fn demi_getsockname(qd: c_int, saddr: *mut sockaddr, size: *mut Socklen) -> c_int {
-    // TODO: Implement this system call.
-    libc::ENOSYS
+    trace!("demi_getsockname()");
+
+    // Check if socket address is invalid.
+    if saddr.is_null() || size.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Check if socket address length is invalid.
+    if (unsafe { *size } as usize) < mem::size_of::<SockAddrIn>() {
+        return libc::EINVAL;
+    }
+
+    // Issue getsockname operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.local_addr(qd.into()) {
+        Ok(endpoint) => {
+            unsafe {
+                *(saddr as *mut SockAddrIn) = socketaddrv4_to_sockaddr(&endpoint);
+                *size = mem::size_of::<SockAddrIn>() as Socklen;
+            }
+            0
+        },
+        // Unix domain sockets are named by a path.
+        #[cfg(target_os = "linux")]
+        Err(e) if e.errno == libc::EAFNOSUPPORT => {
+            match libos
+                .local_unix_addr(qd.into())
+                .and_then(|path| unix_path_to_sockaddr(&path, saddr, size))
+            {
+                Ok(()) => 0,
+                Err(e) => {
+                    trace!("demi_getsockname() failed: {:?}", e);
+                    e.errno
+                },
+            }
+        },
+        Err(e) => {
+            trace!("demi_getsockname() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// getpeername
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_getpeername(qd: c_int, saddr: *mut sockaddr, size: *mut Socklen) -> c_int {
+    trace!("demi_getpeername()");
+
+    // Check if socket address is invalid.
+    if saddr.is_null() || size.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Check if socket address length is invalid.
+    if (unsafe { *size } as usize) < mem::size_of::<SockAddrIn>() {
+        return libc::EINVAL;
+    }
+
+    // Issue getpeername operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.remote_addr(qd.into()) {
+        Ok(endpoint) => {
+            unsafe {
+                *(saddr as *mut SockAddrIn) = socketaddrv4_to_sockaddr(&endpoint);
+                *size = mem::size_of::<SockAddrIn>() as Socklen;
+            }
+            0
+        },
+        // Unix domain sockets are named by a path.
+        #[cfg(target_os = "linux")]
+        Err(e) if e.errno == libc::EAFNOSUPPORT => {
+            match libos
+                .remote_unix_addr(qd.into())
+                .and_then(|path| unix_path_to_sockaddr(&path, saddr, size))
+            {
+                Ok(()) => 0,
+                Err(e) => {
+                    trace!("demi_getpeername() failed: {:?}", e);
+                    e.errno
+                },
+            }
+        },
+        Err(e) => {
+            trace!("demi_getpeername() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// gettimestamps
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_gettimestamps(qd: c_int, ts_out: *mut demi_timestamps_t) -> c_int {
+    trace!("demi_gettimestamps()");
+
+    // Check if the store location is invalid.
+    if ts_out.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Issue gettimestamps operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timestamps(qd.into()) {
+        Ok(timestamps) => {
+            unsafe {
+                *ts_out = demi_timestamps_t {
+                    ts_rx: timestamps.rx.unwrap_or(0),
+                    ts_tx: timestamps.tx.unwrap_or(0),
+                };
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_gettimestamps() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
 }
 
 //======================================================================================================================
 // setsockopt
 //======================================================================================================================
 
-#[allow(unused)]
 #[no_mangle]
 pub extern "C" fn demi_setsockopt(
     qd: c_int,
@@ -703,15 +1442,42 @@ pub extern "C" fn demi_setsockopt(
     optval: *const c_void,
     optlen: Socklen,
 ) -> c_int {
-    // TODO: Implement this system call.
-    libc::ENOSYS
+    trace!("demi_setsockopt() level={:?} optname={:?}", level, optname);
+
+    // Check if option value is invalid.
+    if optval.is_null() || (optlen as usize) < mem::size_of::<c_int>() {
+        return libc::EINVAL;
+    }
+
+    // Parse socket option.
+    let value: c_int = unsafe { *(optval as *const c_int) };
+    let opt: SocketOption = match c_to_socket_option(level, optname, value) {
+        Ok(opt) => opt,
+        Err(e) => {
+            trace!("demi_setsockopt() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue setsockopt operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.set_socket_option(qd.into(), opt) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_setsockopt() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
 }
 
 //======================================================================================================================
 // getsockopt
 //======================================================================================================================
 
-#[allow(unused)]
 #[no_mangle]
 pub extern "C" fn demi_getsockopt(
     qd: c_int,
@@ -720,8 +1486,46 @@ pub extern "C" fn demi_getsockopt(
     optval: *mut c_void,
     optlen: *mut Socklen,
 ) -> c_int {
-    // TODO: Implement this system call.
-    libc::ENOSYS
+    trace!("demi_getsockopt() level={:?} optname={:?}", level, optname);
+
+    // Check if option value is invalid.
+    if optval.is_null() || optlen.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Check if option value length is invalid.
+    if (unsafe { *optlen } as usize) < mem::size_of::<c_int>() {
+        return libc::EINVAL;
+    }
+
+    // Parse socket option.
+    let opt: SocketOption = match c_to_socket_option(level, optname, 0) {
+        Ok(opt) => opt,
+        Err(e) => {
+            trace!("demi_getsockopt() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue getsockopt operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_socket_option(qd.into(), opt) {
+        Ok(opt) => {
+            unsafe {
+                *(optval as *mut c_int) = socket_option_to_c(&opt);
+                *optlen = mem::size_of::<c_int>() as Socklen;
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_getsockopt() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
 }
 
 //======================================================================================================================
@@ -730,17 +1534,40 @@ pub extern "C" fn demi_getsockopt(
 
 /// Issues a system call.
 fn do_syscall<T>(f: impl FnOnce(&mut LibOS) -> T) -> Result<T, Fail> {
-    match unsafe { DEMIKERNEL.try_borrow_mut() } {
+    let result: Result<T, Fail> = match unsafe { DEMIKERNEL.try_borrow_mut() } {
         Ok(mut libos) => match libos.as_mut() {
+            Some(_) if LibOS::teardown_requested() => Err(Fail::new(libc::ECANCELED, "teardown requested")),
             Some(libos) => Ok(f(libos)),
             None => Err(Fail::new(libc::ENOSYS, "Demikernel is not initialized")),
         },
         Err(_) => Err(Fail::new(libc::EBUSY, "Demikernel is busy")),
+    };
+
+    // Teardown that was requested from a signal handler is done here, once back in the flow that it interrupted.
+    if LibOS::teardown_requested() {
+        if let Err(e) = do_teardown() {
+            warn!("failed to tear down Demikernel: {:?}", e);
+        }
+    }
+
+    result
+}
+
+/// Takes out the LibOS, so that it is no longer reachable, and tears it down.
+fn do_teardown() -> Result<(), Fail> {
+    let libos: Option<LibOS> = match unsafe { DEMIKERNEL.try_borrow_mut() } {
+        Ok(mut libos) => libos.take(),
+        Err(_) => return Err(Fail::new(libc::EBUSY, "Demikernel is busy")),
+    };
+
+    match libos {
+        Some(libos) => libos.teardown(),
+        None => Err(Fail::new(libc::ENOSYS, "Demikernel is not initialized")),
     }
 }
 
 /// Converts a [sockaddr] into a [SocketAddrV4].
-fn sockaddr_to_socketaddrv4(saddr: *const sockaddr) -> Result<SocketAddrV4, Fail> {
+pub(crate) fn sockaddr_to_socketaddrv4(saddr: *const sockaddr) -> Result<SocketAddrV4, Fail> {
     // TODO: Change the logic bellow and rename this function once we support V6 addresses as well.
     let sin: SockAddrIn = unsafe { *mem::transmute::<*const sockaddr, *const SockAddrIn>(saddr) };
     if sin.sin_family != AF_INET as u16 {
@@ -751,6 +1578,132 @@ fn sockaddr_to_socketaddrv4(saddr: *const sockaddr) -> Result<SocketAddrV4, Fail
     Ok(SocketAddrV4::new(addr, port))
 }
 
+/// Checks whether `saddr`, which spans `size` bytes, is long enough to tell its communication domain and belongs to the
+/// unix domain.
+#[cfg(target_os = "linux")]
+fn is_unix_sockaddr(saddr: *const sockaddr, size: Socklen) -> bool {
+    (size as usize) >= mem::size_of::<libc::sa_family_t>()
+        && unsafe { *(saddr as *const libc::sa_family_t) } == libc::AF_UNIX as libc::sa_family_t
+}
+
+/// Converts a [sockaddr] of the unix domain, which spans `size` bytes, into the path that it names. Abstract
+/// addresses are not supported.
+#[cfg(target_os = "linux")]
+fn sockaddr_to_unix_path(saddr: *const sockaddr, size: Socklen) -> Result<PathBuf, Fail> {
+    let offset: usize = mem::size_of::<libc::sa_family_t>();
+    if (size as usize) <= offset || (size as usize) > mem::size_of::<libc::sockaddr_un>() {
+        return Err(Fail::new(libc::EINVAL, "invalid socket address length"));
+    }
+    let bytes: &[u8] = unsafe { slice::from_raw_parts((saddr as *const u8).add(offset), size as usize - offset) };
+    let len: usize = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    if len == 0 {
+        return Err(Fail::new(libc::EINVAL, "abstract socket addresses are not supported"));
+    }
+    Ok(PathBuf::from(OsStr::from_bytes(&bytes[..len])))
+}
+
+/// Stores the unix domain address of `path` in `saddr`, which spans `*size` bytes. As with getsockname(), the address
+/// is truncated if it does not fit, and `*size` is set to its actual length.
+#[cfg(target_os = "linux")]
+fn unix_path_to_sockaddr(path: &Path, saddr: *mut sockaddr, size: *mut Socklen) -> Result<(), Fail> {
+    let mut sun: libc::sockaddr_un = unsafe { mem::zeroed() };
+    let bytes: &[u8] = path.as_os_str().as_bytes();
+    // Leave room for the terminating null byte.
+    if bytes.len() >= sun.sun_path.len() {
+        return Err(Fail::new(libc::ENAMETOOLONG, "path of unix domain socket is too long"));
+    }
+    sun.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (dst, src) in sun.sun_path.iter_mut().zip(bytes) {
+        *dst = *src as libc::c_char;
+    }
+    let len: usize = match bytes.len() {
+        // Unnamed sockets only have a family.
+        0 => mem::size_of::<libc::sa_family_t>(),
+        n => mem::size_of::<libc::sa_family_t>() + n + 1,
+    };
+    unsafe {
+        let nbytes: usize = len.min(*size as usize);
+        ptr::copy_nonoverlapping(&sun as *const libc::sockaddr_un as *const u8, saddr as *mut u8, nbytes);
+        *size = len as Socklen;
+    }
+    Ok(())
+}
+
+/// Converts a [SocketAddrV4] into a [SockAddrIn].
+fn socketaddrv4_to_sockaddr(endpoint: &SocketAddrV4) -> SockAddrIn {
+    SockAddrIn {
+        sin_family: AF_INET as u16,
+        sin_port: endpoint.port().to_be(),
+        sin_addr: create_sin_addr(&endpoint.ip().octets()),
+        sin_zero: create_sin_zero(),
+    }
+}
+
+/// Converts a C socket option into a [SocketOption] carrying `value`.
+fn c_to_socket_option(level: c_int, optname: c_int, value: c_int) -> Result<SocketOption, Fail> {
+    let size = || -> Result<usize, Fail> {
+        usize::try_from(value).map_err(|_| Fail::new(libc::EINVAL, "buffer size may not be negative"))
+    };
+    match (level, optname) {
+        (SOL_SOCKET, SO_SNDBUF) => Ok(SocketOption::SendBufferSize(size()?)),
+        (SOL_SOCKET, SO_RCVBUF) => Ok(SocketOption::ReceiveBufferSize(size()?)),
+        (SOL_SOCKET, SO_KEEPALIVE) => Ok(SocketOption::KeepAlive(value != 0)),
+        (SOL_SOCKET, SO_BROADCAST) => Ok(SocketOption::Broadcast(value != 0)),
+        // The rate is an unsigned 32-bit value, where all ones lift the cap.
+        (SOL_SOCKET, SO_MAX_PACING_RATE) => match value as u32 {
+            u32::MAX => Ok(SocketOption::MaxPacingRate(u64::MAX)),
+            rate => Ok(SocketOption::MaxPacingRate(rate as u64)),
+        },
+        (IPPROTO_TCP, TCP_NODELAY) => Ok(SocketOption::NoDelay(value != 0)),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_CORK) => Ok(SocketOption::Cork(value != 0)),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_QUICKACK) => Ok(SocketOption::QuickAck(value != 0)),
+        (IPPROTO_IP, IP_TTL) => match u8::try_from(value) {
+            Ok(ttl) => Ok(SocketOption::Ttl(ttl)),
+            Err(_) => Err(Fail::new(libc::EINVAL, "time-to-live out of range")),
+        },
+        (IPPROTO_IP, IP_PKTINFO) => Ok(SocketOption::PacketInfo(value != 0)),
+        _ => Err(Fail::new(libc::ENOPROTOOPT, "socket option not supported")),
+    }
+}
+
+/// Converts the value carried by a [SocketOption] into a C socket option value.
+fn socket_option_to_c(opt: &SocketOption) -> c_int {
+    match *opt {
+        SocketOption::SendBufferSize(size) | SocketOption::ReceiveBufferSize(size) => {
+            c_int::try_from(size).unwrap_or(c_int::MAX)
+        },
+        SocketOption::NoDelay(flag)
+        | SocketOption::Cork(flag)
+        | SocketOption::QuickAck(flag)
+        | SocketOption::KeepAlive(flag)
+        | SocketOption::Broadcast(flag)
+        | SocketOption::PacketInfo(flag) => flag as c_int,
+        SocketOption::Ttl(ttl) => ttl as c_int,
+        // Not parsed from C, as `TCP_CONGESTION` takes the name of the algorithm rather than an integer.
+        SocketOption::CongestionControl(algorithm) => algorithm as c_int,
+        SocketOption::MaxPacingRate(rate) => u32::try_from(rate).unwrap_or(u32::MAX) as c_int,
+        // Not parsed from C either, as there are no standard names for these options.
+        SocketOption::AckDelay(delay) => c_int::try_from(delay.as_millis()).unwrap_or(c_int::MAX),
+        SocketOption::AckSegments(segments) => c_int::try_from(segments).unwrap_or(c_int::MAX),
+        SocketOption::SendBufferBounds(_, max) | SocketOption::ReceiveBufferBounds(_, max) => {
+            c_int::try_from(max).unwrap_or(c_int::MAX)
+        },
+    }
+}
+
+/// Converts an absolute C timespec into a [SystemTime]. Points in time that are not representable are clamped to now.
+fn timespec_to_systemtime(abstime: *const libc::timespec) -> SystemTime {
+    let timeout: Duration = Duration::from_nanos(
+        unsafe { (*abstime).tv_sec } as u64 * 1_000_000_000_ + unsafe { (*abstime).tv_nsec } as u64,
+    );
+    match SystemTime::UNIX_EPOCH.checked_add(timeout) {
+        Some(abstime) => abstime,
+        None => SystemTime::now(),
+    }
+}
+
 #[test]
 fn test_sockaddr_to_socketaddrv4() {
     // TODO: assign something meaningful to sa_family and check it once we support V6 addresses as well.
@@ -770,3 +1723,87 @@ fn test_sockaddr_to_socketaddrv4() {
         _ => panic!("failed to convert"),
     }
 }
+
+#[test]
+fn test_socketaddrv4_to_sockaddr() {
+    // SocketAddrV4: 127.0.0.1:80
+    let endpoint: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80);
+    let sin: SockAddrIn = socketaddrv4_to_sockaddr(&endpoint);
+    let saddr: *const sockaddr = &sin as *const SockAddrIn as *const sockaddr;
+    match sockaddr_to_socketaddrv4(saddr) {
+        Ok(addr) => assert_eq!(addr, endpoint),
+        _ => panic!("failed to convert"),
+    }
+}
+
+#[test]
+fn test_c_to_socket_option() {
+    match c_to_socket_option(IPPROTO_TCP, TCP_NODELAY, 1) {
+        Ok(opt) => {
+            assert_eq!(opt, SocketOption::NoDelay(true));
+            assert_eq!(socket_option_to_c(&opt), 1);
+        },
+        _ => panic!("failed to convert"),
+    }
+    #[cfg(target_os = "linux")]
+    assert_eq!(
+        c_to_socket_option(IPPROTO_TCP, TCP_CORK, 1).ok(),
+        Some(SocketOption::Cork(true))
+    );
+    #[cfg(target_os = "linux")]
+    assert_eq!(
+        c_to_socket_option(IPPROTO_TCP, TCP_QUICKACK, 1).ok(),
+        Some(SocketOption::QuickAck(true))
+    );
+    // On Windows, these are `TCP_KEEPALIVE` and `TCP_CONGESTION_ALGORITHM`, which are not supported.
+    #[cfg(target_os = "windows")]
+    for optname in [3, 12] {
+        assert_eq!(
+            c_to_socket_option(IPPROTO_TCP, optname, 1).map_err(|e| e.errno).err(),
+            Some(libc::ENOPROTOOPT)
+        );
+    }
+    assert!(c_to_socket_option(SOL_SOCKET, SO_RCVBUF, -1).is_err());
+    assert!(c_to_socket_option(IPPROTO_IP, IP_TTL, 256).is_err());
+    assert_eq!(
+        c_to_socket_option(SOL_SOCKET, SO_BROADCAST, 1).ok(),
+        Some(SocketOption::Broadcast(true))
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_unix_sockaddr() {
+    let path: &Path = Path::new("/tmp/demikernel.sock");
+    let mut sun: libc::sockaddr_un = unsafe { mem::zeroed() };
+    let mut size: Socklen = mem::size_of::<libc::sockaddr_un>() as Socklen;
+    let saddr: *mut sockaddr = &mut sun as *mut libc::sockaddr_un as *mut sockaddr;
+    match unix_path_to_sockaddr(path, saddr, &mut size) {
+        Ok(()) => assert_eq!(
+            size as usize,
+            mem::size_of::<libc::sa_family_t>() + path.as_os_str().len() + 1
+        ),
+        _ => panic!("failed to convert"),
+    }
+    assert!(is_unix_sockaddr(saddr, size));
+    match sockaddr_to_unix_path(saddr, size) {
+        Ok(addr) => assert_eq!(addr, path),
+        _ => panic!("failed to convert"),
+    }
+
+    // Addresses that are too short to tell their family are not read.
+    assert!(!is_unix_sockaddr(saddr, 1));
+
+    // Addresses that do not fit are truncated.
+    let mut size: Socklen = 4;
+    sun = unsafe { mem::zeroed() };
+    match unix_path_to_sockaddr(path, saddr, &mut size) {
+        Ok(()) => assert_eq!(
+            size as usize,
+            mem::size_of::<libc::sa_family_t>() + path.as_os_str().len() + 1
+        ),
+        _ => panic!("failed to convert"),
+    }
+    assert_eq!(sun.sun_path[1], b'm' as libc::c_char);
+    assert_eq!(sun.sun_path[2], 0);
+}
@@ -5,9 +5,17 @@
 // Imports
 //======================================================================================================================
 
+use crate::{
+    runtime::queue::IoQueueLimits,
+    scheduler::{
+        IdlePolicy,
+        PollBudget,
+    },
+};
 use ::std::{
     fs::File,
     io::Read,
+    time::Duration,
 };
 use ::yaml_rust::{
     Yaml,
@@ -41,13 +49,119 @@ impl Config {
         Self { 0: config_obj.clone() }
     }
 
+    /// Reads the name of the LibOS to instantiate from the underlying configuration file, if present.
+    pub fn libos(&self) -> Option<&str> {
+        self.0["libos"].as_str()
+    }
+
+    /// Reads the "poll budget" parameters from the underlying configuration file, if present. These bound how many
+    /// tasks are polled on every poll of the scheduler, and for how long, in microseconds.
+    pub fn poll_budget(&self) -> PollBudget {
+        // FIXME: this function should return a Result.
+        let max_tasks: Option<usize> = match self.0["scheduler"]["max_tasks_per_poll"].as_i64() {
+            Some(max_tasks) if max_tasks <= 0 => panic!("Invalid number of tasks per poll {}", max_tasks),
+            max_tasks => max_tasks.map(|max_tasks| max_tasks as usize),
+        };
+        let max_time: Option<Duration> = match self.0["scheduler"]["max_poll_time_us"].as_i64() {
+            Some(max_time) if max_time <= 0 => panic!("Invalid poll time {}", max_time),
+            max_time => max_time.map(|max_time| Duration::from_micros(max_time as u64)),
+        };
+        PollBudget { max_tasks, max_time }
+    }
+
+    /// Reads the "idle" parameters from the underlying configuration file, if present. Once the scheduler had
+    /// `idle_spin_polls` idle polls in a row, the LibOS parks for at most `max_idle_park_us` microseconds (1000 by
+    /// default) whenever it waits, until it had `idle_busy_polls` busy polls in a row (1 by default). LibOSes that have
+    /// nothing to park on, such as the ones that run on DPDK, sleep with an exponential backoff instead. Without these
+    /// parameters, the LibOS always busy-polls.
+    pub fn idle_policy(&self) -> Option<IdlePolicy> {
+        // FIXME: this function should return a Result.
+        let spin_polls: usize = match self.0["scheduler"]["idle_spin_polls"].as_i64()? {
+            spin_polls if spin_polls <= 0 => panic!("Invalid number of idle polls {}", spin_polls),
+            spin_polls => spin_polls as usize,
+        };
+        let busy_polls: usize = match self.0["scheduler"]["idle_busy_polls"].as_i64() {
+            Some(busy_polls) if busy_polls <= 0 => panic!("Invalid number of busy polls {}", busy_polls),
+            busy_polls => busy_polls.map_or(1, |busy_polls| busy_polls as usize),
+        };
+        let max_park: Duration = match self.0["scheduler"]["max_idle_park_us"].as_i64() {
+            Some(max_park) if max_park <= 0 => panic!("Invalid park time {}", max_park),
+            max_park => Duration::from_micros(max_park.map_or(1000, |max_park| max_park as u64)),
+        };
+        Some(IdlePolicy {
+            spin_polls,
+            busy_polls,
+            max_park,
+        })
+    }
+
+    /// Reads the "poll timing" parameter from the underlying configuration file, if present. If set, the scheduler
+    /// measures how long every poll of each task takes, which is then reported along with the live tasks of the LibOS.
+    pub fn poll_timing(&self) -> bool {
+        self.0["scheduler"]["poll_timing"].as_bool().unwrap_or(false)
+    }
+
+    /// Reads the "worker cores" parameter from the underlying configuration file. The scheduler spawns one worker on
+    /// each listed core, on which thread-safe backend operations are offloaded. If no core is listed, everything runs
+    /// on the thread of the LibOS.
+    pub fn worker_cores(&self) -> Vec<usize> {
+        // FIXME: this function should return a Result.
+        let mut cores: Vec<usize> = Vec::new();
+        if let Some(cores_obj) = self.0["scheduler"]["worker_cores"].as_vec() {
+            for core_obj in cores_obj {
+                match core_obj.as_i64() {
+                    Some(core) if core >= 0 => cores.push(core as usize),
+                    _ => panic!("Invalid worker core {:?}", core_obj),
+                }
+            }
+        }
+        cores
+    }
+
+    /// Reads the "limits" parameters from the underlying configuration file, if present. These bound how many queue
+    /// descriptors may be open at once, and how many operations may be pending at once. Past them, the LibOS refuses
+    /// new descriptors with `ENFILE` and new operations with `EAGAIN`.
+    pub fn queue_limits(&self) -> IoQueueLimits {
+        // FIXME: this function should return a Result.
+        let max_qds: Option<usize> = match self.0["limits"]["max_qds"].as_i64() {
+            Some(max_qds) if max_qds <= 0 => panic!("Invalid number of queue descriptors {}", max_qds),
+            max_qds => max_qds.map(|max_qds| max_qds as usize),
+        };
+        let max_operations: Option<usize> = match self.0["limits"]["max_operations"].as_i64() {
+            Some(max_operations) if max_operations <= 0 => panic!("Invalid number of operations {}", max_operations),
+            max_operations => max_operations.map(|max_operations| max_operations as usize),
+        };
+        IoQueueLimits {
+            max_qds,
+            max_operations,
+        }
+    }
+
+    /// Reads the "local interface name" parameter from the underlying configuration file.
+    #[cfg(any(feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn local_interface_name(&self) -> String {
+        // FIXME: this function should return a Result.
+
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        let local_interface_name: &str = self.0["catnip"]["my_interface_name"]
+            .as_str()
+            .ok_or_else(|| anyhow::format_err!("Couldn't find my_interface_name config"))
+            .unwrap();
+
+        local_interface_name.to_string()
+    }
+
     /// Reads the local IPv4 address parameter from the underlying configuration file.
-    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
     pub fn local_ipv4_addr(&self) -> ::std::net::Ipv4Addr {
         // FIXME: this function should return a result.
         use ::std::net::Ipv4Addr;
 
         // FIXME: Change the follow key from "catnip" to "demikernel".
+        // The address may be left out when it is leased from a DHCP server.
+        if self.dhcp() && self.0["catnip"]["my_ipv4_addr"].is_badvalue() {
+            return Ipv4Addr::UNSPECIFIED;
+        }
         let local_ipv4_addr: Ipv4Addr = self.0["catnip"]["my_ipv4_addr"]
             .as_str()
             .ok_or_else(|| anyhow::format_err!("Couldn't find my_ipv4_addr in config"))
@@ -59,4 +173,140 @@ impl Config {
         }
         local_ipv4_addr
     }
+
+    /// Reads the "secondary IPv4 addresses" parameter from the underlying configuration file, if present. These are
+    /// assigned to the interface along with the local IPv4 address.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn secondary_ipv4_addrs(&self) -> Vec<::std::net::Ipv4Addr> {
+        // FIXME: this function should return a Result.
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        let mut addrs: Vec<::std::net::Ipv4Addr> = Vec::new();
+        if let Some(addrs_obj) = self.0["catnip"]["secondary_ipv4_addrs"].as_vec() {
+            for addr_obj in addrs_obj {
+                let addr: ::std::net::Ipv4Addr = addr_obj
+                    .as_str()
+                    .ok_or_else(|| anyhow::format_err!("Couldn't parse secondary IPv4 address in config"))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                addrs.push(addr);
+            }
+        }
+        addrs
+    }
+
+    /// Reads the "ARP announcements" parameter from the underlying configuration file, if present. This is the number
+    /// of gratuitous ARP requests that are broadcast on startup, so that neighbors learn our link address right away.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn arp_announcements(&self) -> Option<usize> {
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        // Counts that do not fit saturate, so that negative ones disable announcements.
+        self.0["catnip"]["arp_announcements"]
+            .as_i64()
+            .map(|n| usize::try_from(n.max(0)).unwrap_or(usize::MAX))
+    }
+
+    /// Reads the "routes" parameter from the underlying configuration file. Each route has a destination in CIDR
+    /// notation, and optionally a gateway. Destinations without a gateway are on the local link.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn routes(&self) -> Vec<crate::runtime::network::types::Route> {
+        // FIXME: this function should return a Result.
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        use crate::runtime::network::types::Route;
+
+        let mut routes: Vec<Route> = Vec::new();
+        if let Some(routes_obj) = self.0["catnip"]["routes"].as_vec() {
+            for route_obj in routes_obj {
+                let destination: &str = route_obj["destination"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::format_err!("Couldn't find route destination in config"))
+                    .unwrap();
+                let gateway: Option<&str> = route_obj["gateway"].as_str();
+                routes.push(Route::parse(destination, gateway).expect("invalid route in config"));
+            }
+        }
+        routes
+    }
+
+    /// Reads the "DHCP" parameter from the underlying configuration file, if present. When set, the configuration of
+    /// the interface is leased from a DHCP server on startup, and the local IPv4 address parameter may be left out.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn dhcp(&self) -> bool {
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        self.0["catnip"]["dhcp"].as_bool().unwrap_or(false)
+    }
+
+    /// Reads the "DHCP timeout" parameter from the underlying configuration file, if present. This is how long to wait
+    /// for a DHCP lease on startup, in seconds.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn dhcp_timeout(&self) -> ::std::time::Duration {
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        // FIXME: this function should return a Result.
+        match self.0["catnip"]["dhcp_timeout"].as_i64() {
+            Some(secs) if secs < 0 => panic!("Invalid DHCP timeout {}", secs),
+            secs => ::std::time::Duration::from_secs(secs.map_or(30, |secs| secs as u64)),
+        }
+    }
+
+    /// Gets the "MTU" parameter from environment variables, or else from the underlying configuration file, or else
+    /// falls back to the default MTU. This is the MTU of the interface, which sizes receive buffers, and bounds the TCP
+    /// MSS and the size of unfragmented UDP datagrams.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn mtu(&self) -> u16 {
+        // FIXME: this function should return a Result.
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        use crate::runtime::network::consts::{
+            DEFAULT_MTU,
+            MAX_JUMBO_MTU,
+            MIN_MTU,
+        };
+
+        let mtu: usize = match ::std::env::var("MTU") {
+            Ok(mtu) => mtu.parse().unwrap(),
+            Err(_) => self.0["catnip"]["mtu"].as_i64().map_or(DEFAULT_MTU, |mtu| mtu as usize),
+        };
+        if mtu < MIN_MTU || mtu > MAX_JUMBO_MTU {
+            panic!("Invalid MTU {}", mtu);
+        }
+        mtu as u16
+    }
+
+    /// Reads the "shard index" and "shard count" parameters from the underlying configuration file, if present. LibOS
+    /// instances that share the same local address steer TCP connections among them by 4-tuple hash, each one owning
+    /// the shard with the given index. In Catnip, each shard is a pair of queues of the port, which spreads flows among
+    /// them with Receive Side Scaling.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn shard(&self) -> Option<(usize, usize)> {
+        // FIXME: this function should return a Result.
+        // FIXME: Change the follow keys from "catnip" to "demikernel".
+        let index: i64 = self.0["catnip"]["shard_index"].as_i64()?;
+        let count: i64 = self.0["catnip"]["shard_count"].as_i64()?;
+        if count <= 0 || index < 0 || index >= count {
+            panic!("Invalid shard {} of {}", index, count);
+        }
+        Some((index as usize, count as usize))
+    }
+
+    /// Overrides the "shard index" parameter of the underlying configuration, so that instances that are created from
+    /// the same configuration file may own different shards.
+    pub fn set_shard_index(&mut self, index: usize) {
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        if let Yaml::Hash(root) = &mut self.0 {
+            let catnip: &mut Yaml = root
+                .entry(Yaml::String("catnip".to_string()))
+                .or_insert_with(|| Yaml::Hash(Default::default()));
+            if let Yaml::Hash(catnip) = catnip {
+                catnip.insert(Yaml::String("shard_index".to_string()), Yaml::Integer(index as i64));
+            }
+        }
+    }
+
+    /// Reads the "GRO" parameter from the underlying configuration file, if present. When enabled, consecutive
+    /// in-order TCP segments of a connection that are received in the same batch are coalesced before processing. This
+    /// is disabled by default.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos", feature = "catloon-libos"))]
+    pub fn gro(&self) -> Option<bool> {
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        self.0["catnip"]["gro"].as_bool()
+    }
 }
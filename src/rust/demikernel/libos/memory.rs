@@ -8,6 +8,7 @@
 use crate::{
     runtime::{
         fail::Fail,
+        queue::IoQueueLimitStats,
         types::{
             demi_qresult_t,
             demi_sgarray_t,
@@ -15,7 +16,21 @@ use crate::{
         QDesc,
         QToken,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
+};
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
+use ::std::{
+    future::Future,
+    pin::Pin,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 #[cfg(feature = "catmem-libos")]
@@ -117,6 +132,16 @@ impl MemoryLibOS {
         }
     }
 
+    /// Takes out the earliest completed operation that satisfies `pred`.
+    #[allow(unreachable_patterns)]
+    pub fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.take_completed(pred),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
     #[allow(unreachable_patterns)]
     pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
         match self {
@@ -126,6 +151,67 @@ impl MemoryLibOS {
         }
     }
 
+    /// Returns an event file descriptor that becomes readable when some queue token may have completed.
+    #[cfg(target_os = "linux")]
+    #[allow(unreachable_patterns)]
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.event_fd(),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Inserts a co-routine of the application into the scheduler.
+    #[allow(unused_variables, unreachable_patterns)]
+    pub fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.spawn(future),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Returns a future that completes once the clock of the scheduler reaches `deadline`.
+    #[allow(unused_variables, unreachable_patterns)]
+    pub fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.sleep_until(deadline),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Parks the calling thread for at most `timeout`, if the scheduler has been idle for long enough.
+    #[allow(unused_variables, unreachable_patterns)]
+    pub fn park(&mut self, timeout: Option<Duration>) {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.park(timeout),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Lists the live tasks of the scheduler, for debugging.
+    #[allow(unreachable_patterns)]
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.tasks(),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Counts the queue descriptors and operations that were refused because of the configured limits.
+    #[allow(unreachable_patterns)]
+    pub fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.queue_limit_stats(),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
     /// Waits for any operation in an I/O queue.
     #[allow(unreachable_patterns)]
     pub fn poll(&mut self) {
@@ -12,13 +12,29 @@ pub mod network;
 use self::{
     memory::MemoryLibOS,
     name::LibOSName,
-    network::NetworkLibOS,
+    network::{
+        NetworkLibOS,
+        NetworkLibOSRegistry,
+        NetworkLibOSTrait,
+    },
 };
 use crate::{
     demikernel::config::Config,
     runtime::{
         fail::Fail,
         logging,
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::IoQueueLimitStats,
         types::{
             demi_qresult_t,
             demi_sgarray_t,
@@ -26,11 +42,33 @@ use crate::{
         QDesc,
         QToken,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
 };
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
 use ::std::{
+    collections::HashMap,
     env,
-    net::SocketAddrV4,
+    future::Future,
+    net::{
+        Ipv4Addr,
+        Shutdown,
+        SocketAddrV4,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    pin::Pin,
+    slice,
+    sync::atomic::{
+        AtomicBool,
+        Ordering,
+    },
     time::{
         Duration,
         Instant,
@@ -38,18 +76,15 @@ use ::std::{
     },
 };
 
-#[cfg(feature = "catcollar-libos")]
-use crate::catcollar::CatcollarLibOS;
 #[cfg(feature = "catmem-libos")]
 use crate::catmem::CatmemLibOS;
-#[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-use crate::catnap::CatnapLibOS;
-#[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-use crate::catnapw::CatnapWLibOS;
-#[cfg(feature = "catnip-libos")]
-use crate::catnip::CatnipLibOS;
-#[cfg(feature = "catpowder-libos")]
-use crate::catpowder::CatpowderLibOS;
+
+//======================================================================================================================
+// Static Variables
+//======================================================================================================================
+
+/// Whether teardown was requested, most likely from a signal handler. Waits that are under way give up once it is set.
+static TEARDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
 
 //======================================================================================================================
 // Structures
@@ -69,8 +104,41 @@ pub enum LibOS {
 
 /// Associated functions for LibOS.
 impl LibOS {
-    /// Instantiates a new LibOS.
+    /// Instantiates a new LibOS. The requested LibOS may be overridden at runtime, see [LibOS::select()].
     pub fn new(libos_name: LibOSName) -> Result<Self, Fail> {
+        Self::create(Some(libos_name), None)
+    }
+
+    /// Instantiates the LibOS that is selected by the `DEMI_LIBOS` environment variable or by the configuration file.
+    pub fn from_config() -> Result<Self, Fail> {
+        Self::create(None, None)
+    }
+
+    /// Instantiates a new LibOS that owns the shard of connections with the given index, out of the number of shards
+    /// in the configuration file. This way, one instance may run per core, each one on its own thread: Catnip gives
+    /// each instance its own pair of queues, to which the port steers the connections of its shard.
+    pub fn new_shard(libos_name: LibOSName, shard_index: usize) -> Result<Self, Fail> {
+        Self::create(Some(libos_name), Some(shard_index))
+    }
+
+    /// Selects the LibOS to instantiate among the ones that are compiled into this build. The `DEMI_LIBOS` environment
+    /// variable takes precedence over the `libos` key of the configuration file, which in turn takes precedence over
+    /// the requested LibOS.
+    pub fn select(requested: Option<LibOSName>, config: &Config) -> Result<LibOSName, Fail> {
+        if let Ok(name) = env::var("DEMI_LIBOS") {
+            return LibOSName::try_from(name.as_str());
+        }
+        if let Some(name) = config.libos() {
+            return LibOSName::try_from(name);
+        }
+        match requested {
+            Some(libos_name) => Ok(libos_name),
+            None => Err(Fail::new(libc::EINVAL, "no libos was selected")),
+        }
+    }
+
+    /// Instantiates a new LibOS, optionally requesting a specific one and the shard of connections that it owns.
+    fn create(requested: Option<LibOSName>, shard_index: Option<usize>) -> Result<Self, Fail> {
         logging::initialize();
 
         // Read in configuration file.
@@ -83,24 +151,22 @@ impl LibOS {
                 ))
             },
         };
-        let config: Config = Config::new(config_path);
+        let mut config: Config = Config::new(config_path);
+        if let Some(shard_index) = shard_index {
+            config.set_shard_index(shard_index);
+        }
+        let libos_name: LibOSName = Self::select(requested, &config)?;
 
         // Instantiate LibOS.
-        #[allow(unreachable_patterns)]
         let libos: LibOS = match libos_name {
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            LibOSName::Catnap => Self::NetworkLibOS(NetworkLibOS::Catnap(CatnapLibOS::new(&config))),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            LibOSName::CatnapW => Self::NetworkLibOS(NetworkLibOS::CatnapW(CatnapWLibOS::new(&config))),
-            #[cfg(feature = "catcollar-libos")]
-            LibOSName::Catcollar => Self::NetworkLibOS(NetworkLibOS::Catcollar(CatcollarLibOS::new(&config))),
-            #[cfg(feature = "catpowder-libos")]
-            LibOSName::Catpowder => Self::NetworkLibOS(NetworkLibOS::Catpowder(CatpowderLibOS::new(&config))),
-            #[cfg(feature = "catnip-libos")]
-            LibOSName::Catnip => Self::NetworkLibOS(NetworkLibOS::Catnip(CatnipLibOS::new(&config))),
             #[cfg(feature = "catmem-libos")]
-            LibOSName::Catmem => Self::MemoryLibOS(MemoryLibOS::Catmem(CatmemLibOS::new())),
-            _ => panic!("unsupported libos"),
+            LibOSName::Catmem => Self::MemoryLibOS(MemoryLibOS::Catmem(CatmemLibOS::new(&config))),
+            // Network LibOSes are looked up in the registry of the ones that are compiled into this build.
+            _ => {
+                let registry: NetworkLibOSRegistry = NetworkLibOSRegistry::default();
+                let libos: NetworkLibOS = registry.build(libos_name.as_str(), &config)?;
+                Self::NetworkLibOS(libos)
+            },
         };
 
         Ok(libos)
@@ -149,6 +215,14 @@ impl LibOS {
         }
     }
 
+    /// Binds a unix domain socket to a path.
+    pub fn bind_unix(&mut self, sockqd: QDesc, path: &Path) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.bind_unix(sockqd, path),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "bind() is not supported on memory liboses")),
+        }
+    }
+
     /// Marks a socket as a passive one.
     pub fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
         match self {
@@ -165,7 +239,7 @@ impl LibOS {
         }
     }
 
-    /// Initiates a connection with a remote TCP socket.
+    /// Initiates a connection with a remote TCP socket, or sets the default remote peer of a UDP socket.
     pub fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
         match self {
             LibOS::NetworkLibOS(libos) => libos.connect(sockqd, remote),
@@ -173,6 +247,26 @@ impl LibOS {
         }
     }
 
+    /// Initiates a connection with a remote TCP socket, which fails with `ETIMEDOUT` if it is not established by
+    /// `abstime`.
+    pub fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, abstime: SystemTime) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.timedconnect(sockqd, remote, to_deadline(abstime)),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "timedconnect() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Initiates a connection with the unix domain socket that is bound to a path.
+    pub fn connect_unix(&mut self, sockqd: QDesc, path: &Path) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.connect_unix(sockqd, path),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "connect() is not supported on memory liboses")),
+        }
+    }
+
     /// Closes an I/O queue.
     pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
         match self {
@@ -181,14 +275,199 @@ impl LibOS {
         }
     }
 
+    /// Asynchronously closes an I/O queue. The returned operation completes with `DEMI_OPC_CLOSE` once
+    /// the I/O queue is closed.
+    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.async_close(qd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "async_close() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Duplicates an I/O queue descriptor. The underlying I/O queue is closed only once all queue descriptors that
+    /// refer to it are closed.
+    pub fn dup(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.dup(qd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "dup() is not supported on memory liboses")),
+        }
+    }
+
+    /// Shuts down a socket.
+    pub fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.shutdown(sockqd, how),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "shutdown() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the local address of a socket.
+    pub fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.local_addr(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "local_addr() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the remote address of a socket.
+    pub fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.remote_addr(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "remote_addr() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the path that a unix domain socket is bound to.
+    pub fn local_unix_addr(&self, sockqd: QDesc) -> Result<PathBuf, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.local_unix_addr(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "local_addr() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the path of the remote peer of a unix domain socket.
+    pub fn remote_unix_addr(&self, sockqd: QDesc) -> Result<PathBuf, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.remote_unix_addr(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "remote_addr() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the current value of a socket option.
+    pub fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.get_socket_option(sockqd, opt),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "get_socket_option() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Sets a socket option.
+    pub fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.set_socket_option(sockqd, opt),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "set_socket_option() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the number of datagrams that were truncated when popped from a socket.
+    pub fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.truncations(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "truncations() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the number of connection requests that a listening socket dropped because its backlog was full.
+    pub fn backlog_overflows(&self, sockqd: QDesc) -> Result<u64, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.backlog_overflows(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "backlog_overflows() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets a snapshot of the state of an established TCP connection, including its round-trip time estimates,
+    /// congestion control state, retransmissions and delivery rate.
+    pub fn tcp_info(&self, sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.tcp_info(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "tcp_info() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the hardware timestamps of the last datagram or segment that was received and sent on a socket, as read
+    /// from the clock of the network interface (in nanoseconds).
+    pub fn timestamps(&self, sockqd: QDesc) -> Result<Timestamps, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.timestamps(sockqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "timestamps() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Joins a socket to a multicast group. The unspecified address for `iface` selects the default interface.
+    pub fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.join_multicast_group(sockqd, group, iface),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "join_multicast_group() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Removes a socket from a multicast group.
+    pub fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.leave_multicast_group(sockqd, group, iface),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "leave_multicast_group() is not supported on memory liboses",
+            )),
+        }
+    }
+
     /// Pushes a scatter-gather array to an I/O queue.
     pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
         match self {
+            // Multi-segment scatter-gather arrays are pushed to sockets as a single vectored push.
+            LibOS::NetworkLibOS(libos) if sga.sga_numsegs > 1 => libos.pushv(qd, slice::from_ref(sga)),
             LibOS::NetworkLibOS(libos) => libos.push(qd, sga),
             LibOS::MemoryLibOS(libos) => libos.push(qd, sga),
         }
     }
 
+    /// Pushes multiple scatter-gather arrays to an I/O queue as a single unit.
+    pub fn pushv(&mut self, qd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.pushv(qd, sgas),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "pushv() is not supported on memory liboses")),
+        }
+    }
+
+    /// Pushes a scatter-gather array to a socket, and pops the response from it, as a single operation that completes
+    /// as a pop.
+    pub fn pushpop(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.pushpop(sockqd, sga),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "pushpop() is not supported on memory liboses")),
+        }
+    }
+
     /// Pushes a scatter-gather array to a UDP socket.
     pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
         match self {
@@ -197,6 +476,14 @@ impl LibOS {
         }
     }
 
+    /// Pushes a scatter-gather array to the unix domain socket that is bound to a path.
+    pub fn pushto_unix(&mut self, qd: QDesc, sga: &demi_sgarray_t, path: &Path) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.pushto_unix(qd, sga, path),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "pushto() is not supported on memory liboses")),
+        }
+    }
+
     /// Pops data from a an I/O queue.
     pub fn pop(&mut self, qd: QDesc) -> Result<QToken, Fail> {
         match self {
@@ -205,6 +492,232 @@ impl LibOS {
         }
     }
 
+    /// Pops at most `size` bytes of data from an I/O queue.
+    pub fn pop_sized(&mut self, qd: QDesc, size: usize) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.pop_sized(qd, size),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "pop_sized() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Pops data from an I/O queue, failing with `ETIMEDOUT` if no data arrives by `abstime`. If `size` is set, at most
+    /// `size` bytes are popped.
+    pub fn timedpop(&mut self, qd: QDesc, size: Option<usize>, abstime: SystemTime) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.timedpop(qd, size, to_deadline(abstime)),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "timedpop() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Forwards data from one I/O queue to another, without copying it, until the remote peer of the source queue
+    /// closes the connection. The returned operation completes once all data has been forwarded.
+    pub fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.splice(srcqd, dstqd),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "splice() is not supported on memory liboses")),
+        }
+    }
+
+    /// Sends an ICMP echo request to `addr`. The returned operation completes with `DEMI_OPC_PING` and the round-trip
+    /// time once the echo reply arrives, or fails with `ETIMEDOUT` if it does not arrive within `timeout`.
+    pub fn ping(&mut self, addr: Ipv4Addr, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.ping(addr, timeout),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "ping() is not supported on memory liboses")),
+        }
+    }
+
+    /// Lists address resolutions that are stored in the ARP cache, along with the time that they have left to live.
+    pub fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.arp_entries(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "arp_entries() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Adds a static address resolution to the ARP cache. Static address resolutions never expire, and are neither
+    /// overwritten by ARP traffic nor flushed.
+    pub fn arp_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.arp_insert(ipv4_addr, link_addr),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "arp_insert() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the number of packets that were dropped while waiting for ARP to resolve the link address of their next
+    /// hop, either because too many of them were waiting, or because the link address could not be resolved.
+    pub fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.arp_pending_drops(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "arp_pending_drops() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Removes all address resolutions from the ARP cache, except for static ones.
+    pub fn arp_flush(&mut self) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.arp_flush(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "arp_flush() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Lists local IPv4 addresses, starting with the primary one.
+    pub fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.ipv4_addrs(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "ipv4_addrs() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Assigns a secondary IPv4 address to the interface. Sockets that are not bound to an address send from the local
+    /// address that suits the next hop towards their remote peer.
+    pub fn add_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.add_ipv4_addr(ipv4_addr),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "add_ipv4_addr() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Removes a secondary IPv4 address from the interface. Fails with `EADDRINUSE` while sockets use the address.
+    pub fn remove_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.remove_ipv4_addr(ipv4_addr),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "remove_ipv4_addr() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the current DHCP lease, if any. The DHCP client is started on startup, when enabled in the configuration.
+    pub fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.dhcp_lease(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "dhcp_lease() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Creates a queue that reports changes of the link state. Each pop on it completes with `DEMI_OPC_LINK` and the
+    /// new state of the link. While the link is down, pending connect and pop operations fail with `ENETDOWN`.
+    pub fn link_events(&mut self) -> Result<QDesc, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.link_events(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "link_events() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the last known state of the link.
+    pub fn link_state(&self) -> Result<LinkState, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.link_state(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "link_state() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Lists static routes, from the longest prefix to the shortest one.
+    pub fn routes(&self) -> Result<Vec<Route>, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.routes(),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "routes() is not supported on memory liboses")),
+        }
+    }
+
+    /// Adds a static route, replacing any route for the same destinations. Packets to destinations that the route
+    /// covers are sent to its gateway, or straight to the destination if the route has no gateway.
+    pub fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.add_route(route),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "add_route() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Removes the static route for the given destinations.
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.remove_route(destination, prefix_len),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "remove_route() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Sets the time to live of address resolutions that are cached from now on, along with how long to wait for a
+    /// reply to an ARP request, and how many times to retry it.
+    pub fn set_arp_policy(
+        &mut self,
+        cache_ttl: Duration,
+        request_timeout: Duration,
+        retry_count: usize,
+    ) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.set_arp_policy(cache_ttl, request_timeout, retry_count),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "set_arp_policy() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Installs a hardware flow rule on the network interface, and returns its identifier. Matching packets are
+    /// steered to a given receive queue, or dropped, before they cost any CPU time.
+    pub fn add_flow_rule(&mut self, rule: FlowRule) -> Result<u32, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.add_flow_rule(rule),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "add_flow_rule() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Removes a hardware flow rule from the network interface.
+    pub fn remove_flow_rule(&mut self, id: u32) -> Result<(), Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.remove_flow_rule(id),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "remove_flow_rule() is not supported on memory liboses",
+            )),
+        }
+    }
+
     /// Waits for a pending I/O operation to complete or a timeout to expire.
     /// This is just a single-token convenience wrapper for wait_any().
     pub fn wait(&mut self, qt: QToken, timeout: Option<Duration>) -> Result<demi_qresult_t, Fail> {
@@ -235,12 +748,55 @@ impl LibOS {
                 return Ok(self.pack_result(handle, qt)?);
             }
 
+            // Give up once teardown is requested, so that the LibOS is not kept busy.
+            if Self::teardown_requested() {
+                handle.take_key();
+                return Err(Fail::new(libc::ECANCELED, "teardown requested"));
+            }
+
             if abstime.is_none() || SystemTime::now() >= abstime.unwrap() {
                 // Return this operation to the scheduling queue by removing the associated key
                 // (which would otherwise cause the operation to be freed).
                 handle.take_key();
                 return Err(Fail::new(libc::ETIMEDOUT, "timer expired"));
             }
+
+            // Park until the deadline at most, if the LibOS has been idle for long enough.
+            let remaining: Option<Duration> =
+                abstime.map(|abstime| abstime.duration_since(SystemTime::now()).unwrap_or_default());
+            self.park(remaining);
+        }
+    }
+
+    /// Checks if an I/O operation has completed, without blocking. This polls the underlying LibOS once, and returns
+    /// the result of the operation if it has completed.
+    pub fn try_wait(&mut self, qt: QToken) -> Result<Option<demi_qresult_t>, Fail> {
+        trace!("try_wait(): qt={:?}", qt);
+
+        // Retrieve associated schedule handle.
+        let mut handle: SchedulerHandle = self.schedule(qt)?;
+
+        // Poll first, so as to give the operation a chance to complete.
+        self.poll();
+
+        if handle.has_completed() {
+            return Ok(Some(self.pack_result(handle, qt)?));
+        }
+
+        // Return this operation to the scheduling queue by removing the associated key
+        // (which would otherwise cause the operation to be freed).
+        handle.take_key();
+        Ok(None)
+    }
+
+    /// Returns an event file descriptor that becomes readable when some queue token may have completed. This enables
+    /// applications to wait on Demikernel queues along with other file descriptors (e.g. with `epoll_wait()`), and to
+    /// only call [LibOS::wait] once woken up. The descriptor is owned by the LibOS and must not be closed.
+    #[cfg(target_os = "linux")]
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.event_fd(),
+            LibOS::MemoryLibOS(libos) => libos.event_fd(),
         }
     }
 
@@ -251,24 +807,34 @@ impl LibOS {
         // Get the wait start time, but only if we have a timeout.  We don't care when we started if we wait forever.
         let start: Option<Instant> = if timeout.is_none() { None } else { Some(Instant::now()) };
 
+        // Index queue tokens by their offset, so that completed operations are found without scanning all of them.
+        let mut offsets: HashMap<QToken, usize> = HashMap::with_capacity(qts.len());
+        for (i, &qt) in qts.iter().enumerate() {
+            // Check if the queue token is valid. This also catches operations that completed before we got here.
+            let mut handle: SchedulerHandle = self.schedule(qt)?;
+            if handle.has_completed() {
+                return Ok((i, self.pack_result(handle, qt)?));
+            }
+
+            // Return this operation to the scheduling queue by removing the associated key
+            // (which would otherwise cause the operation to be freed).
+            handle.take_key();
+            offsets.entry(qt).or_insert(i);
+        }
+
         loop {
             // Poll first, so as to give pending operations a chance to complete.
             self.poll();
 
-            // Search for any operation that has completed.
-            for (i, &qt) in qts.iter().enumerate() {
-                // Retrieve associated schedule handle.
-                // TODO: move this out of the loop.
-                let mut handle: SchedulerHandle = self.schedule(qt)?;
-
-                // Found one, so extract the result and return.
-                if handle.has_completed() {
-                    return Ok((i, self.pack_result(handle, qt)?));
-                }
+            // Take out the earliest operation that has completed, if any.
+            if let Some(qt) = self.take_completed(&|qt| offsets.contains_key(&qt)) {
+                let handle: SchedulerHandle = self.schedule(qt)?;
+                return Ok((offsets[&qt], self.pack_result(handle, qt)?));
+            }
 
-                // Return this operation to the scheduling queue by removing the associated key
-                // (which would otherwise cause the operation to be freed).
-                handle.take_key();
+            // Give up once teardown is requested, so that the LibOS is not kept busy.
+            if Self::teardown_requested() {
+                return Err(Fail::new(libc::ECANCELED, "teardown requested"));
             }
 
             // If we have a timeout, check for expiration.
@@ -278,6 +844,60 @@ impl LibOS {
             {
                 return Err(Fail::new(libc::ETIMEDOUT, "timer expired"));
             }
+
+            // Park until the timeout expires at most, if the LibOS has been idle for long enough.
+            let remaining: Option<Duration> = timeout
+                .map(|timeout| timeout.saturating_sub(start.expect("start should be set if timeout is").elapsed()));
+            self.park(remaining);
+        }
+    }
+
+    /// Inserts a co-routine of the application into the scheduler, so that it runs on the thread of the LibOS and is
+    /// driven by the same polls as I/O operations (e.g. within [LibOS::wait]). Co-routines are polled with low
+    /// priority, and dropping the returned handle cancels the co-routine. Periodic co-routines wait on a future from
+    /// [LibOS::sleep], which they rearm with [SleepFuture::reset] after each run.
+    pub fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) -> Result<SchedulerHandle, Fail> {
+        trace!("spawn()");
+        let future: Pin<Box<dyn Future<Output = ()>>> = Box::pin(future);
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.spawn(future),
+            LibOS::MemoryLibOS(libos) => libos.spawn(future),
+        }
+    }
+
+    /// Returns a future that completes once `timeout` has elapsed, for co-routines that are inserted with
+    /// [LibOS::spawn]. Dropping the future cancels its timer.
+    pub fn sleep(&self, timeout: Duration) -> SleepFuture {
+        self.sleep_until(SystemTime::now() + timeout)
+    }
+
+    /// Returns a future that completes once `abstime` is reached, for co-routines that are inserted with
+    /// [LibOS::spawn]. Dropping the future cancels its timer.
+    pub fn sleep_until(&self, abstime: SystemTime) -> SleepFuture {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.sleep_until(to_deadline(abstime)),
+            LibOS::MemoryLibOS(libos) => libos.sleep_until(to_deadline(abstime)),
+        }
+    }
+
+    /// Lists the live tasks of the scheduler, such as pending I/O operations and co-routines, along with their kind,
+    /// the queue descriptor that owns them, how many times they were polled and how long ago they were inserted. This
+    /// is meant for finding leaked or stuck operations while debugging. Poll times are only measured if `poll_timing`
+    /// is set in the configuration file.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.tasks(),
+            LibOS::MemoryLibOS(libos) => libos.tasks(),
+        }
+    }
+
+    /// Counts the queue descriptors that were refused with `ENFILE` and the operations that were refused with `EAGAIN`
+    /// because the `limits` that are set in the configuration file were reached. A growing count usually means that the
+    /// application leaks queue descriptors or never waits on some queue tokens.
+    pub fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.queue_limit_stats(),
+            LibOS::MemoryLibOS(libos) => libos.queue_limit_stats(),
         }
     }
 
@@ -289,6 +909,17 @@ impl LibOS {
         }
     }
 
+    /// Registers application-owned memory as a scatter-gather array.
+    pub fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.sgaregister(data, len),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "sgaregister() is not supported on memory liboses",
+            )),
+        }
+    }
+
     /// Releases a scatter-gather array.
     pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
         match self {
@@ -297,6 +928,30 @@ impl LibOS {
         }
     }
 
+    /// Tears down the LibOS. All pending operations are canceled, all queue descriptors are closed, and the resources
+    /// of the underlying runtime are released. This is not async-signal-safe, see [LibOS::request_teardown] instead.
+    pub fn teardown(self) -> Result<(), Fail> {
+        trace!("teardown()");
+        TEARDOWN_REQUESTED.store(false, Ordering::SeqCst);
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.teardown(),
+            // Memory liboses release their resources once dropped.
+            LibOS::MemoryLibOS(_) => Ok(()),
+        }
+    }
+
+    /// Requests the LibOS to be torn down, so that waits that are under way give up with `ECANCELED`. This only sets a
+    /// flag, thus it is async-signal-safe, unlike [LibOS::teardown]. Signal handlers call this, and the flow that they
+    /// interrupted tears down the LibOS once it checks [LibOS::teardown_requested].
+    pub fn request_teardown() {
+        TEARDOWN_REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks whether teardown was requested, and is yet to be done.
+    pub fn teardown_requested() -> bool {
+        TEARDOWN_REQUESTED.load(Ordering::SeqCst)
+    }
+
     /// Waits for any operation in an I/O queue.
     fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
         match self {
@@ -312,10 +967,41 @@ impl LibOS {
         }
     }
 
-    fn poll(&mut self) {
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.take_completed(pred),
+            LibOS::MemoryLibOS(libos) => libos.take_completed(pred),
+        }
+    }
+
+    /// Parks the calling thread for at most `timeout`, if the LibOS has been idle for long enough. This never parks,
+    /// unless an idle policy is configured.
+    fn park(&mut self, timeout: Option<Duration>) {
+        match self {
+            LibOS::NetworkLibOS(libos) => libos.park(timeout),
+            LibOS::MemoryLibOS(libos) => libos.park(timeout),
+        }
+    }
+
+    /// Polls the scheduler once, driving pending operations and co-routines that were inserted with [LibOS::spawn].
+    /// This is only needed by applications that do not otherwise wait on the LibOS.
+    pub fn poll(&mut self) {
         match self {
             LibOS::NetworkLibOS(libos) => libos.poll(),
             LibOS::MemoryLibOS(libos) => libos.poll(),
         }
     }
 }
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts a wall-clock deadline into a monotonic one. Deadlines in the past expire immediately.
+fn to_deadline(abstime: SystemTime) -> Instant {
+    let now: Instant = Instant::now();
+    match abstime.duration_since(SystemTime::now()) {
+        Ok(timeout) => now + timeout,
+        Err(_) => now,
+    }
+}
@@ -15,6 +15,7 @@ use ::std::env;
 /// Names of LibOSes.
 pub enum LibOSName {
     Catpowder,
+    Catloon,
     Catnap,
     CatnapW,
     Catcollar,
@@ -34,23 +35,49 @@ impl LibOSName {
             Err(_) => Err(Fail::new(libc::EINVAL, "missing value for LIBOS environment variable")),
         }
     }
+
+    /// Returns the name of the target LibOS.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LibOSName::Catpowder => "catpowder",
+            LibOSName::Catloon => "catloon",
+            LibOSName::Catnap => "catnap",
+            LibOSName::CatnapW => "catnapw",
+            LibOSName::Catcollar => "catcollar",
+            LibOSName::Catnip => "catnip",
+            LibOSName::Catmem => "catmem",
+        }
+    }
 }
 
 //======================================================================================================================
 // Trait Implementations
 //======================================================================================================================
 
+/// Fallible conversion trait implementation for LibOSName.
+impl TryFrom<&str> for LibOSName {
+    type Error = Fail;
+
+    fn try_from(str: &str) -> Result<Self, Self::Error> {
+        match str.to_lowercase().as_str() {
+            "catpowder" => Ok(LibOSName::Catpowder),
+            "catloon" => Ok(LibOSName::Catloon),
+            "catnap" => Ok(LibOSName::Catnap),
+            "catnapw" => Ok(LibOSName::CatnapW),
+            "catcollar" => Ok(LibOSName::Catcollar),
+            "catnip" => Ok(LibOSName::Catnip),
+            "catmem" => Ok(LibOSName::Catmem),
+            _ => Err(Fail::new(libc::EINVAL, "unknown libos")),
+        }
+    }
+}
+
 /// Conversion trait implementation for LibOSName.
 impl From<String> for LibOSName {
     fn from(str: String) -> Self {
-        match str.to_lowercase().as_str() {
-            "catpowder" => LibOSName::Catpowder,
-            "catnap" => LibOSName::Catnap,
-            "catnapw" => LibOSName::CatnapW,
-            "catcollar" => LibOSName::Catcollar,
-            "catnip" => LibOSName::Catnip,
-            "catmem" => LibOSName::Catmem,
-            _ => panic!("unkown libos"),
+        match LibOSName::try_from(str.as_str()) {
+            Ok(libos_name) => libos_name,
+            Err(_) => panic!("unkown libos"),
         }
     }
 }
@@ -6,8 +6,21 @@
 //======================================================================================================================
 
 use crate::{
+    demikernel::config::Config,
     runtime::{
         fail::Fail,
+        network::types::{
+            ArpEntry,
+            DhcpLease,
+            FlowRule,
+            LinkState,
+            MacAddress,
+            Route,
+            SocketOption,
+            TcpInfo,
+            Timestamps,
+        },
+        queue::IoQueueLimitStats,
         types::{
             demi_qresult_t,
             demi_sgarray_t,
@@ -15,12 +28,36 @@ use crate::{
         QDesc,
         QToken,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        SchedulerHandle,
+        SleepFuture,
+        TaskInfo,
+    },
+};
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
+use ::std::{
+    future::Future,
+    net::{
+        Ipv4Addr,
+        Shutdown,
+        SocketAddrV4,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    pin::Pin,
+    time::{
+        Duration,
+        Instant,
+    },
 };
-use ::std::net::SocketAddrV4;
 
 #[cfg(feature = "catcollar-libos")]
 use crate::catcollar::CatcollarLibOS;
+#[cfg(all(feature = "catloon-libos", target_os = "linux"))]
+use crate::catloon::CatloonLibOS;
 #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
 use crate::catnap::CatnapLibOS;
 #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
@@ -37,254 +74,316 @@ use crate::catpowder::CatpowderLibOS;
 pub use crate::inetstack::operations::OperationResult;
 
 //======================================================================================================================
-// Structures
-//======================================================================================================================
-
-/// Network LIBOS.
-pub enum NetworkLibOS {
-    #[cfg(feature = "catpowder-libos")]
-    Catpowder(CatpowderLibOS),
-    #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-    Catnap(CatnapLibOS),
-    #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-    CatnapW(CatnapWLibOS),
-    #[cfg(feature = "catcollar-libos")]
-    Catcollar(CatcollarLibOS),
-    #[cfg(feature = "catnip-libos")]
-    Catnip(CatnipLibOS),
-}
-
-//======================================================================================================================
-// Associated Functions
+// Traits
 //======================================================================================================================
 
-/// Associated functions for network LibOSes.
-impl NetworkLibOS {
+/// Network LibOS
+pub trait NetworkLibOSTrait {
     /// Creates a socket.
-    pub fn socket(
-        &mut self,
-        domain: libc::c_int,
-        socket_type: libc::c_int,
-        protocol: libc::c_int,
-    ) -> Result<QDesc, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.socket(domain, socket_type, protocol),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.socket(domain, socket_type, protocol),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.socket(domain, socket_type, protocol),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.socket(domain, socket_type, protocol),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.socket(domain, socket_type, protocol),
-        }
-    }
+    fn socket(&mut self, domain: libc::c_int, socket_type: libc::c_int, protocol: libc::c_int) -> Result<QDesc, Fail>;
 
     /// Binds a socket to a local address.
-    pub fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.bind(sockqd, local),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.bind(sockqd, local),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.bind(sockqd, local),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.bind(sockqd, local),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.bind(sockqd, local),
-        }
+    fn bind(&mut self, sockqd: QDesc, local: SocketAddrV4) -> Result<(), Fail>;
+
+    /// Binds a unix domain socket to a path. Only LibOSes that run on the kernel support unix domain sockets.
+    fn bind_unix(&mut self, _sockqd: QDesc, _path: &Path) -> Result<(), Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "unix domain sockets are not supported on this libos",
+        ))
     }
 
     /// Marks a socket as a passive one.
-    pub fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.listen(sockqd, backlog),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.listen(sockqd, backlog),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.listen(sockqd, backlog),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.listen(sockqd, backlog),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.listen(sockqd, backlog),
-        }
-    }
+    fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail>;
 
     /// Accepts an incoming connection on a TCP socket.
-    pub fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.accept(sockqd),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.accept(sockqd),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.accept(sockqd),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.accept(sockqd),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.accept(sockqd),
-        }
-    }
+    fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail>;
 
-    /// Initiates a connection with a remote TCP pper.
-    pub fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.connect(sockqd, remote),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.connect(sockqd, remote),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.connect(sockqd, remote),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.connect(sockqd, remote),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.connect(sockqd, remote),
-        }
+    /// Initiates a connection with a remote TCP pper, or sets the default remote peer of a UDP socket.
+    fn connect(&mut self, sockqd: QDesc, remote: SocketAddrV4) -> Result<QToken, Fail>;
+
+    /// Initiates a connection with a remote TCP peer, which fails with `ETIMEDOUT` if not established by `deadline`.
+    fn timedconnect(&mut self, sockqd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail>;
+
+    /// Initiates a connection with the unix domain socket that is bound to a path.
+    fn connect_unix(&mut self, _sockqd: QDesc, _path: &Path) -> Result<QToken, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "unix domain sockets are not supported on this libos",
+        ))
     }
 
     /// Closes a socket.
-    pub fn close(&mut self, sockqd: QDesc) -> Result<(), Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.close(sockqd),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.close(sockqd),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.close(sockqd),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.close(sockqd),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.close(sockqd),
-        }
+    fn close(&mut self, sockqd: QDesc) -> Result<(), Fail>;
+
+    /// Asynchronously closes a socket.
+    fn async_close(&mut self, sockqd: QDesc) -> Result<QToken, Fail>;
+
+    /// Duplicates the queue descriptor of a socket.
+    fn dup(&mut self, sockqd: QDesc) -> Result<QDesc, Fail>;
+
+    /// Shuts down a socket.
+    fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail>;
+
+    /// Gets the local address of a socket.
+    fn local_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail>;
+
+    /// Gets the address of the remote peer of a socket.
+    fn remote_addr(&self, sockqd: QDesc) -> Result<SocketAddrV4, Fail>;
+
+    /// Gets the path that a unix domain socket is bound to, which is empty for unnamed sockets.
+    fn local_unix_addr(&self, _sockqd: QDesc) -> Result<PathBuf, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "unix domain sockets are not supported on this libos",
+        ))
     }
 
-    /// Pushes a scatter-gather array to a TCP socket.
-    pub fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.push(sockqd, sga),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.push(sockqd, sga),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.push(sockqd, sga),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.push(sockqd, sga),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.push(sockqd, sga),
-        }
+    /// Gets the path of the remote peer of a unix domain socket.
+    fn remote_unix_addr(&self, _sockqd: QDesc) -> Result<PathBuf, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "unix domain sockets are not supported on this libos",
+        ))
+    }
+
+    /// Gets the value of a socket option.
+    fn get_socket_option(&self, sockqd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail>;
+
+    /// Sets the value of a socket option.
+    fn set_socket_option(&mut self, sockqd: QDesc, opt: SocketOption) -> Result<(), Fail>;
+
+    /// Gets the number of datagrams that were truncated when popped from a UDP socket.
+    fn truncations(&self, sockqd: QDesc) -> Result<u64, Fail>;
+
+    /// Gets the number of connection requests that a listening TCP socket dropped because its backlog was full.
+    fn backlog_overflows(&self, sockqd: QDesc) -> Result<u64, Fail>;
+
+    /// Gets a snapshot of the state of an established TCP connection, in the spirit of Linux's TCP_INFO.
+    fn tcp_info(&self, sockqd: QDesc) -> Result<TcpInfo, Fail>;
+
+    /// Gets the hardware timestamps of the last datagram or segment received and sent on a socket.
+    fn timestamps(&self, sockqd: QDesc) -> Result<Timestamps, Fail>;
+
+    /// Joins a UDP socket to a multicast group on the interface that has the address `iface`.
+    fn join_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail>;
+
+    /// Removes a UDP socket from a multicast group on the interface that has the address `iface`.
+    fn leave_multicast_group(&mut self, sockqd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail>;
+
+    /// Pushes a scatter-gather array to a TCP socket or to a connected UDP socket.
+    fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail>;
+
+    /// Pushes multiple scatter-gather arrays to a TCP socket as a single operation, or to a connected UDP socket as a
+    /// single datagram.
+    fn pushv(&mut self, sockqd: QDesc, sgas: &[demi_sgarray_t]) -> Result<QToken, Fail>;
+
+    /// Pushes a scatter-gather array to a socket, and pops the response from it, as a single operation that completes
+    /// as a pop.
+    fn pushpop(&mut self, _sockqd: QDesc, _sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "linked push-pop operations are not supported on this libos",
+        ))
     }
 
     /// Pushes a scatter-gather array to a UDP socket.
-    pub fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.pushto(sockqd, sga, to),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.pushto(sockqd, sga, to),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.pushto(sockqd, sga, to),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.pushto(sockqd, sga, to),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.pushto(sockqd, sga, to),
-        }
+    fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddrV4) -> Result<QToken, Fail>;
+
+    /// Pushes a scatter-gather array to the unix domain socket that is bound to a path.
+    fn pushto_unix(&mut self, _sockqd: QDesc, _sga: &demi_sgarray_t, _path: &Path) -> Result<QToken, Fail> {
+        Err(Fail::new(
+            libc::ENOTSUP,
+            "unix domain sockets are not supported on this libos",
+        ))
     }
 
     /// Pops data from a socket.
-    pub fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.pop(sockqd),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.pop(sockqd),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.pop(sockqd),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.pop(sockqd),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.pop(sockqd),
-        }
-    }
+    fn pop(&mut self, sockqd: QDesc) -> Result<QToken, Fail>;
+
+    /// Pops at most `size` bytes of data from a socket.
+    fn pop_sized(&mut self, sockqd: QDesc, size: usize) -> Result<QToken, Fail>;
+
+    /// Pops data from a socket, failing with `ETIMEDOUT` if no data arrives by `deadline`. If `size` is set, at most
+    /// `size` bytes are popped.
+    fn timedpop(&mut self, sockqd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail>;
+
+    /// Forwards data from one socket to another, until the remote peer of the source socket closes the connection.
+    fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail>;
+
+    /// Sends an ICMP echo request to a remote host. The operation completes with the round-trip time, or fails with
+    /// `ETIMEDOUT` if no reply arrives within `timeout`.
+    fn ping(&mut self, addr: Ipv4Addr, timeout: Option<Duration>) -> Result<QToken, Fail>;
+
+    /// Lists address resolutions that are stored in the ARP cache.
+    fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail>;
+
+    /// Adds a static address resolution to the ARP cache, which never expires and is not flushed.
+    fn arp_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Result<(), Fail>;
+
+    /// Gets the number of packets that were dropped while waiting for ARP to resolve the link address of their next
+    /// hop.
+    fn arp_pending_drops(&self) -> Result<u64, Fail>;
+
+    /// Removes all address resolutions from the ARP cache, except for static ones.
+    fn arp_flush(&mut self) -> Result<(), Fail>;
+
+    /// Lists local IPv4 addresses, starting with the primary one.
+    fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail>;
+
+    /// Assigns a secondary IPv4 address to the interface.
+    fn add_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail>;
+
+    /// Removes a secondary IPv4 address from the interface. Fails with `EADDRINUSE` while sockets use the address.
+    fn remove_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail>;
+
+    /// Gets the current DHCP lease, if any.
+    fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail>;
+
+    /// Creates a queue that reports changes of the link state.
+    fn link_events(&mut self) -> Result<QDesc, Fail>;
+
+    /// Gets the last known state of the link.
+    fn link_state(&self) -> Result<LinkState, Fail>;
+
+    /// Lists static routes, from the longest prefix to the shortest one.
+    fn routes(&self) -> Result<Vec<Route>, Fail>;
+
+    /// Adds a static route, replacing any route for the same destinations.
+    fn add_route(&mut self, route: Route) -> Result<(), Fail>;
+
+    /// Removes the static route for the given destinations.
+    fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail>;
+
+    /// Sets the time to live of ARP cache entries, and how ARP requests are retried.
+    fn set_arp_policy(
+        &mut self,
+        cache_ttl: Duration,
+        request_timeout: Duration,
+        retry_count: usize,
+    ) -> Result<(), Fail>;
+
+    /// Installs a hardware flow rule on the network interface, and returns its identifier.
+    fn add_flow_rule(&mut self, rule: FlowRule) -> Result<u32, Fail>;
+
+    /// Removes a hardware flow rule from the network interface.
+    fn remove_flow_rule(&mut self, id: u32) -> Result<(), Fail>;
+
+    /// Inserts a co-routine of the application into the scheduler, which is then driven along with I/O operations.
+    fn spawn(&mut self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail>;
+
+    /// Returns a future that completes once the clock of the scheduler reaches `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> SleepFuture;
 
     /// Waits for any operation in an I/O queue.
-    pub fn poll(&mut self) {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.poll_bg_work(),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.poll(),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.poll(),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.poll(),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.poll_bg_work(),
-        }
-    }
+    fn poll(&mut self);
+
+    /// Parks the calling thread for at most `timeout`, if the scheduler has been idle for long enough.
+    fn park(&mut self, timeout: Option<Duration>);
+
+    /// Lists the live tasks of the scheduler, for debugging.
+    fn tasks(&self) -> Vec<TaskInfo>;
+
+    /// Counts the queue descriptors and operations that were refused because of the configured limits.
+    fn queue_limit_stats(&self) -> IoQueueLimitStats;
+
+    /// Returns an event file descriptor that becomes readable when some queue token may have completed.
+    #[cfg(target_os = "linux")]
+    fn event_fd(&self) -> Result<RawFd, Fail>;
 
     /// Waits for any operation in an I/O queue.
-    pub fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.schedule(qt),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.schedule(qt),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.schedule(qt),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.schedule(qt),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.schedule(qt),
-        }
+    fn schedule(&mut self, qt: QToken) -> Result<SchedulerHandle, Fail>;
+
+    /// Takes out the earliest completed operation that satisfies `pred`, without scanning pending operations.
+    fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken>;
+
+    /// Packs the result of a completed operation.
+    fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail>;
+
+    /// Allocates a scatter-gather array.
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail>;
+
+    /// Registers application-owned memory as a scatter-gather array.
+    fn sgaregister(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail>;
+
+    /// Releases a scatter-gather array.
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail>;
+
+    /// Tears down the LibOS. All pending operations are canceled, all queue descriptors are closed, and the resources
+    /// of the underlying runtime are released.
+    fn teardown(self: Box<Self>) -> Result<(), Fail>;
+}
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Network LibOS.
+pub type NetworkLibOS = Box<dyn NetworkLibOSTrait>;
+
+/// Constructor for a network LibOS.
+pub type NetworkLibOSBuilder = fn(&Config) -> NetworkLibOS;
+
+/// Registry of network LibOSes that may be selected at runtime.
+pub struct NetworkLibOSRegistry {
+    /// Registered network LibOSes, keyed by name.
+    builders: Vec<(&'static str, NetworkLibOSBuilder)>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Associated functions for network LibOS registries.
+impl NetworkLibOSRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self { builders: Vec::new() }
     }
 
-    pub fn pack_result(&mut self, handle: SchedulerHandle, qt: QToken) -> Result<demi_qresult_t, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.pack_result(handle, qt),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.pack_result(handle, qt),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.pack_result(handle, qt),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.pack_result(handle, qt),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.pack_result(handle, qt),
-        }
+    /// Registers a network LibOS under the given name. Names are case-insensitive, and registering a name again
+    /// replaces the previous registration.
+    pub fn register(&mut self, name: &'static str, builder: NetworkLibOSBuilder) {
+        self.builders.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.builders.push((name, builder));
     }
 
-    /// Allocates a scatter-gather array.
-    pub fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.sgaalloc(size),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.sgaalloc(size),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.sgaalloc(size),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.sgaalloc(size),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.sgaalloc(size),
-        }
+    /// Returns the names of all registered network LibOSes.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.builders.iter().map(|(name, _)| *name).collect()
     }
 
-    /// Releases a scatter-gather array.
-    pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
-        match self {
-            #[cfg(feature = "catpowder-libos")]
-            NetworkLibOS::Catpowder(libos) => libos.sgafree(sga),
-            #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
-            NetworkLibOS::Catnap(libos) => libos.sgafree(sga),
-            #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
-            NetworkLibOS::CatnapW(libos) => libos.sgafree(sga),
-            #[cfg(feature = "catcollar-libos")]
-            NetworkLibOS::Catcollar(libos) => libos.sgafree(sga),
-            #[cfg(feature = "catnip-libos")]
-            NetworkLibOS::Catnip(libos) => libos.sgafree(sga),
+    /// Instantiates the network LibOS that is registered under the given name.
+    pub fn build(&self, name: &str, config: &Config) -> Result<NetworkLibOS, Fail> {
+        match self.builders.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            Some((_, builder)) => Ok(builder(config)),
+            None => Err(Fail::new(libc::ENOTSUP, "unsupported libos")),
         }
     }
 }
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Default trait implementation for network LibOS registries. This registers all network LibOSes that are compiled
+/// into this build.
+impl Default for NetworkLibOSRegistry {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut registry: NetworkLibOSRegistry = NetworkLibOSRegistry::new();
+        #[cfg(feature = "catpowder-libos")]
+        registry.register("catpowder", |config| Box::new(CatpowderLibOS::new(config)));
+        #[cfg(all(feature = "catnap-libos", target_os = "linux"))]
+        registry.register("catnap", |config| Box::new(CatnapLibOS::new(config)));
+        #[cfg(all(feature = "catnapw-libos", target_os = "windows"))]
+        registry.register("catnapw", |config| Box::new(CatnapWLibOS::new(config)));
+        #[cfg(feature = "catcollar-libos")]
+        registry.register("catcollar", |config| Box::new(CatcollarLibOS::new(config)));
+        #[cfg(all(feature = "catloon-libos", target_os = "linux"))]
+        registry.register("catloon", |config| Box::new(CatloonLibOS::new(config)));
+        #[cfg(feature = "catnip-libos")]
+        registry.register("catnip", |config| Box::new(CatnipLibOS::new(config)));
+        registry
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+pub mod asyncio;
 pub mod bindings;
 pub mod config;
 pub mod libos;
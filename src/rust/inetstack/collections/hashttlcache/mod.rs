@@ -76,6 +76,14 @@ where
         self.map.clear();
     }
 
+    /// Sets the expiration of entries that are inserted from now on with the default TTL value.
+    pub fn set_default_ttl(&mut self, default_ttl: Option<Duration>) {
+        if let Some(ttl) = default_ttl {
+            assert!(ttl > Duration::new(0, 0));
+        };
+        self.default_ttl = default_ttl;
+    }
+
     // Advances the internal clock of the cache.
     pub fn advance_clock(&mut self, now: Instant) {
         assert!(now >= self.clock);
@@ -146,6 +154,19 @@ where
         })
     }
 
+    /// Iterates over living entries, along with the time that they have left to live. Entries that do not expire have
+    /// `None` time to live.
+    pub fn iter_with_ttl(&self) -> impl Iterator<Item = (&K, &V, Option<Duration>)> {
+        let clock = self.clock;
+        self.map.iter().flat_map(move |(key, record)| {
+            if record.has_expired(clock) {
+                None
+            } else {
+                Some((key, &record.value, record.expiration.map(|e| e - clock)))
+            }
+        })
+    }
+
     /// Collect dead entries in the cache.
     pub fn cleanup(&mut self) {
         let mut dead_entries: Vec<K> = Vec::new();
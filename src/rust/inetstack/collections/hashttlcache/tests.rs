@@ -150,3 +150,29 @@ fn add_and_remove_object() {
     assert!(cache.get(&"a").is_none());
     assert!(cache.get(&"b").is_none());
 }
+
+/// Tests that the default TTL only applies to objects inserted after it changed.
+#[test]
+fn change_default_ttl() {
+    let now = Instant::now();
+    let ttl = Duration::from_secs(1);
+    let mut cache = HashTtlCache::new(now, Some(ttl));
+
+    cache.insert("a", 'a');
+    cache.set_default_ttl(Some(ttl * 4));
+    cache.insert("b", 'b');
+    cache.insert_with_ttl("c", 'c', None);
+
+    // Advance clock and make sure that each object has the time to live it was inserted with.
+    cache.advance_clock(now + ttl / 2);
+    let mut entries: Vec<(&str, char, Option<Duration>)> = cache.iter_with_ttl().map(|(k, v, t)| (*k, *v, t)).collect();
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            ("a", 'a', Some(ttl / 2)),
+            ("b", 'b', Some(ttl * 4 - ttl / 2)),
+            ("c", 'c', None)
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    inetstack::link::{
+        LinkMonitor,
+        LinkWatch,
+    },
+    runtime::{
+        fail::Fail,
+        timer::{
+            TimerRc,
+            WaitFuture,
+        },
+    },
+};
+use ::std::{
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+    time::Instant,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Operation Deadline
+///
+/// Optional point in time after which an operation is failed with `ETIMEDOUT`. This is backed by the timer of the
+/// network stack, so it is checked against the clock of the stack rather than the wall clock. A deadline may also
+/// watch the link, in which case the operation is failed with `ENETDOWN` as soon as the link goes down.
+#[derive(Default)]
+pub struct Deadline {
+    /// Underlying timer, if any.
+    timer: Option<Pin<Box<WaitFuture<TimerRc>>>>,
+    /// Watch on the link, if any.
+    link: Option<LinkWatch>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Operation Deadlines
+impl Deadline {
+    /// Creates a deadline that expires at `expiry`, according to `clock`.
+    pub fn new(clock: &TimerRc, expiry: Option<Instant>) -> Self {
+        Self {
+            timer: expiry.map(|expiry| Box::pin(clock.wait_until(clock.clone(), expiry))),
+            link: None,
+        }
+    }
+
+    /// Makes the target [Deadline] also expire while `link` is down.
+    pub fn with_link(self, link: &LinkMonitor) -> Self {
+        Self {
+            link: Some(link.watch()),
+            ..self
+        }
+    }
+
+    /// Polls the target [Deadline]. This completes with `ETIMEDOUT` once the deadline has expired, or with `ENETDOWN`
+    /// if the watched link is down. Otherwise, the task of `ctx` is registered to be woken up when either happens.
+    pub fn poll_expired(&mut self, ctx: &mut Context) -> Poll<Fail> {
+        if let Some(ref link) = self.link {
+            if let Poll::Ready(e) = link.poll_down(ctx) {
+                return Poll::Ready(e);
+            }
+        }
+        match self.timer {
+            Some(ref mut timer) => match Future::poll(timer.as_mut(), ctx) {
+                Poll::Ready(()) => {
+                    self.timer = None;
+                    Poll::Ready(Fail::new(libc::ETIMEDOUT, "operation timed out"))
+                },
+                Poll::Pending => Poll::Pending,
+            },
+            None => Poll::Pending,
+        }
+    }
+}
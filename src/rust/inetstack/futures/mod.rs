@@ -1,8 +1,10 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+pub mod deadline;
 pub mod operation;
 pub mod utilities;
 
+pub use deadline::Deadline;
 pub use operation::FutureOperation;
 pub use utilities::UtilityMethods;
@@ -2,11 +2,21 @@
 // Licensed under the MIT license.
 
 use crate::{
-    inetstack::protocols::{
-        tcp::operations::TcpOperation,
-        udp::UdpOperation,
+    inetstack::{
+        link::LinkEventFuture,
+        protocols::{
+            tcp::operations::TcpOperation,
+            udp::UdpOperation,
+        },
+    },
+    runtime::{
+        fail::Fail,
+        QDesc,
+    },
+    scheduler::{
+        FutureResult,
+        SchedulerFuture,
     },
-    scheduler::SchedulerFuture,
 };
 use ::futures::Future;
 use ::std::{
@@ -16,6 +26,7 @@ use ::std::{
         Context,
         Poll,
     },
+    time::Duration,
 };
 
 //==============================================================================
@@ -35,6 +46,10 @@ use ::std::{
 pub enum FutureOperation {
     Tcp(TcpOperation),
     Udp(UdpOperation),
+    /// Echo request, which resolves to the round-trip time.
+    Ping(FutureResult<Pin<Box<dyn Future<Output = Result<Duration, Fail>>>>>),
+    /// Link event, which resolves to the new state of the link.
+    Link(FutureResult<LinkEventFuture>),
 
     // These are expected to have long lifetimes and be large enough to justify another allocation.
     Background(Pin<Box<dyn Future<Output = ()>>>),
@@ -48,6 +63,24 @@ impl SchedulerFuture for FutureOperation {
     fn get_future(&self) -> &dyn Future<Output = ()> {
         todo!()
     }
+
+    fn get_kind(&self) -> &'static str {
+        match self {
+            FutureOperation::Tcp(op) => op.get_kind(),
+            FutureOperation::Udp(op) => op.get_kind(),
+            FutureOperation::Ping(_) => "Ping",
+            FutureOperation::Link(_) => "Link",
+            FutureOperation::Background(_) => "Background",
+        }
+    }
+
+    fn get_qd(&self) -> Option<QDesc> {
+        match self {
+            FutureOperation::Tcp(op) => Some(op.get_qd()),
+            FutureOperation::Udp(op) => Some(op.get_qd()),
+            FutureOperation::Ping(_) | FutureOperation::Link(_) | FutureOperation::Background(_) => None,
+        }
+    }
 }
 
 //==============================================================================
@@ -63,6 +96,8 @@ impl Future for FutureOperation {
         match self.get_mut() {
             FutureOperation::Tcp(ref mut f) => Future::poll(Pin::new(f), ctx),
             FutureOperation::Udp(ref mut f) => Future::poll(Pin::new(f), ctx),
+            FutureOperation::Ping(ref mut f) => Future::poll(Pin::new(f), ctx),
+            FutureOperation::Link(ref mut f) => Future::poll(Pin::new(f), ctx),
             FutureOperation::Background(ref mut f) => Future::poll(Pin::new(f), ctx),
         }
     }
@@ -0,0 +1,261 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::types::LinkState,
+    QDesc,
+};
+use ::libc::{
+    EBADF,
+    ENETDOWN,
+};
+use ::std::{
+    cell::RefCell,
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Inner State of a Link Monitor
+struct Inner {
+    /// Last known state of the link.
+    state: LinkState,
+    /// Events that are yet to be popped, for each queue that subscribed to link events.
+    subscribers: HashMap<QDesc, VecDeque<LinkState>>,
+    /// Tasks to wake up when the state of the link changes, indexed by watch.
+    wakers: HashMap<u64, Waker>,
+    /// Identifier of the next watch.
+    next_watch: u64,
+}
+
+/// Link Monitor
+///
+/// Keeps track of the state of the link, as polled from the network runtime. Changes are reported to queues that
+/// subscribed to link events, and operations that watch the link fail with `ENETDOWN` while it is down.
+#[derive(Clone)]
+pub struct LinkMonitor(Rc<RefCell<Inner>>);
+
+/// Link Watch
+///
+/// Registration of an operation that must be woken up when the state of the link changes. It is unregistered on drop.
+pub struct LinkWatch {
+    /// Monitor that the target watch is registered with.
+    link: LinkMonitor,
+    /// Identifier of the target watch.
+    id: u64,
+}
+
+/// Link Event Future
+///
+/// Pops the next link event of a queue that subscribed to link events.
+pub struct LinkEventFuture {
+    /// Queue that subscribed to link events.
+    qd: QDesc,
+    /// Watch that wakes up the target future on link events.
+    watch: LinkWatch,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Link Monitors
+impl LinkMonitor {
+    /// Creates a link monitor for a link that is initially in the given state.
+    pub fn new(state: LinkState) -> Self {
+        Self(Rc::new(RefCell::new(Inner {
+            state,
+            subscribers: HashMap::new(),
+            wakers: HashMap::new(),
+            next_watch: 0,
+        })))
+    }
+
+    /// Gets the last known state of the link.
+    pub fn state(&self) -> LinkState {
+        self.0.borrow().state
+    }
+
+    /// Records the current state of the link. If it changed, an event is queued for each subscriber, and all watching
+    /// operations are woken up. Returns whether the state changed.
+    pub fn update(&self, state: LinkState) -> bool {
+        let wakers: Vec<Waker> = {
+            let mut inner = self.0.borrow_mut();
+            if inner.state == state {
+                return false;
+            }
+            inner.state = state;
+            for events in inner.subscribers.values_mut() {
+                events.push_back(state);
+            }
+            inner.wakers.drain().map(|(_, waker)| waker).collect()
+        };
+        // Wake up tasks once the state is released, as they may be polled right away.
+        for waker in wakers {
+            waker.wake();
+        }
+        true
+    }
+
+    /// Subscribes the queue `qd` to link events. Only changes that happen from now on are reported.
+    pub fn subscribe(&self, qd: QDesc) {
+        self.0.borrow_mut().subscribers.insert(qd, VecDeque::new());
+    }
+
+    /// Unsubscribes the queue `qd` from link events, dropping the events that it did not pop yet.
+    pub fn unsubscribe(&self, qd: QDesc) {
+        self.0.borrow_mut().subscribers.remove(&qd);
+    }
+
+    /// Registers a new watch on the target link.
+    pub fn watch(&self) -> LinkWatch {
+        let mut inner = self.0.borrow_mut();
+        let id: u64 = inner.next_watch;
+        inner.next_watch += 1;
+        LinkWatch { link: self.clone(), id }
+    }
+
+    /// Pops the next link event of the queue `qd`, which fails with `EBADF` if it did not subscribe to link events.
+    pub fn pop_event(&self, qd: QDesc) -> Result<LinkEventFuture, Fail> {
+        if !self.0.borrow().subscribers.contains_key(&qd) {
+            return Err(Fail::new(EBADF, "queue did not subscribe to link events"));
+        }
+        Ok(LinkEventFuture {
+            qd,
+            watch: self.watch(),
+        })
+    }
+}
+
+/// Associate Functions for Link Watches
+impl LinkWatch {
+    /// Polls the target [LinkWatch]. This completes with `ENETDOWN` if the link is down. Otherwise, the task of `ctx`
+    /// is registered to be woken up when the state of the link changes.
+    pub fn poll_down(&self, ctx: &mut Context) -> Poll<Fail> {
+        let mut inner = self.link.0.borrow_mut();
+        if !inner.state.is_up() {
+            return Poll::Ready(Fail::new(ENETDOWN, "link is down"));
+        }
+        inner.wakers.insert(self.id, ctx.waker().clone());
+        Poll::Pending
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Drop Trait Implementation for Link Watches
+impl Drop for LinkWatch {
+    fn drop(&mut self) {
+        self.link.0.borrow_mut().wakers.remove(&self.id);
+    }
+}
+
+/// Future Trait Implementation for Link Event Futures
+impl Future for LinkEventFuture {
+    type Output = (QDesc, Result<LinkState, Fail>);
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let self_: &mut Self = self.get_mut();
+        let mut inner = self_.watch.link.0.borrow_mut();
+        match inner.subscribers.get_mut(&self_.qd).map(|events| events.pop_front()) {
+            Some(Some(state)) => Poll::Ready((self_.qd, Ok(state))),
+            Some(None) => {
+                inner.wakers.insert(self_.watch.id, ctx.waker().clone());
+                Poll::Pending
+            },
+            // The queue was closed while the operation was pending.
+            None => Poll::Ready((self_.qd, Err(Fail::new(EBADF, "queue was closed")))),
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        LinkEventFuture,
+        LinkMonitor,
+    };
+    use crate::runtime::{
+        fail::Fail,
+        network::types::LinkState,
+        QDesc,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::libc::{
+        EBADF,
+        ENETDOWN,
+    };
+    use ::std::{
+        future::Future,
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+    };
+
+    /// Tests that subscribers get one event per change of the link state, and that watches fail while the link is
+    /// down.
+    #[test]
+    fn link_events() {
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        let link: LinkMonitor = LinkMonitor::new(LinkState::Up);
+        let qd: QDesc = QDesc::from(0_usize);
+        link.subscribe(qd);
+        let mut event: LinkEventFuture = link.pop_event(qd).unwrap();
+        assert!(Future::poll(Pin::new(&mut event), &mut ctx).is_pending());
+        assert!(link.watch().poll_down(&mut ctx).is_pending());
+
+        // The link goes down and comes back up.
+        assert!(link.update(LinkState::Down));
+        assert!(!link.update(LinkState::Down));
+        match link.watch().poll_down(&mut ctx) {
+            Poll::Ready(e) if e.errno == ENETDOWN => (),
+            _ => panic!("watch should fail while the link is down"),
+        }
+        assert!(link.update(LinkState::Up));
+        for state in [LinkState::Down, LinkState::Up] {
+            match Future::poll(Pin::new(&mut event), &mut ctx) {
+                Poll::Ready((event_qd, Ok(event_state))) => {
+                    assert_eq!(event_qd, qd);
+                    assert_eq!(event_state, state);
+                },
+                _ => panic!("link event should be reported"),
+            }
+        }
+        assert!(Future::poll(Pin::new(&mut event), &mut ctx).is_pending());
+
+        // Pending operations fail once the queue is closed.
+        link.unsubscribe(qd);
+        match Future::poll(Pin::new(&mut event), &mut ctx) {
+            Poll::Ready((_, Err(e))) if e.errno == EBADF => (),
+            _ => panic!("link event should fail on closed queues"),
+        }
+        let e: Fail = link.pop_event(qd).err().unwrap();
+        assert_eq!(e.errno, EBADF);
+    }
+}
@@ -7,16 +7,30 @@
 
 use crate::{
     inetstack::{
-        futures::operation::FutureOperation,
+        futures::{
+            operation::FutureOperation,
+            Deadline,
+        },
+        link::{
+            LinkEventFuture,
+            LinkMonitor,
+        },
         operations::OperationResult,
         protocols::{
             arp::ArpPeer,
             ethernet2::{
                 EtherType2,
                 Ethernet2Header,
+                Loopback,
+            },
+            tcp::operations::{
+                ConnectFuture,
+                PopFuture,
+            },
+            udp::{
+                UdpOperation,
+                UdpPopFuture,
             },
-            tcp::operations::ConnectFuture,
-            udp::UdpOperation,
             Peer,
         },
     },
@@ -27,17 +41,34 @@ use crate::{
     },
     runtime::{
         fail::Fail,
-        memory::DemiBuffer,
+        memory::{
+            coalesce_buffers,
+            DemiBuffer,
+        },
         network::{
             config::{
                 ArpConfig,
                 TcpConfig,
                 UdpConfig,
             },
-            types::MacAddress,
+            types::{
+                check_stack_option,
+                ArpEntry,
+                DhcpLease,
+                LinkState,
+                MacAddress,
+                Route,
+                SocketOption,
+                TcpInfo,
+                Timestamps,
+            },
             NetworkRuntime,
         },
-        queue::IoQueueTable,
+        queue::{
+            IoQueueLimitStats,
+            IoQueueLimits,
+            IoQueueTable,
+        },
         timer::TimerRc,
         QDesc,
         QToken,
@@ -45,6 +76,7 @@ use crate::{
     },
     scheduler::{
         FutureResult,
+        Priority,
         Scheduler,
         SchedulerHandle,
     },
@@ -54,16 +86,22 @@ use ::libc::{
     EBADF,
     EINVAL,
     ENOTSUP,
+    ETIMEDOUT,
 };
 use ::std::{
     any::Any,
     convert::TryFrom,
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     rc::Rc,
-    time::Instant,
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 #[cfg(feature = "profiler")]
@@ -78,6 +116,7 @@ pub mod test_helpers;
 
 pub mod collections;
 pub mod futures;
+pub mod link;
 pub mod operations;
 pub mod options;
 pub mod protocols;
@@ -89,12 +128,20 @@ pub mod protocols;
 const TIMER_RESOLUTION: usize = 64;
 const MAX_RECV_ITERS: usize = 2;
 
+/// Time to sleep between polls while waiting for a DHCP lease on startup.
+const DHCP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// Number of times that background work is polled on teardown, so that pending frames are flushed. This is enough
+/// for the clock to advance at least once.
+const TEARDOWN_POLL_ITERS: usize = TIMER_RESOLUTION;
+
 pub struct InetStack {
     arp: ArpPeer,
     ipv4: Peer,
     file_table: IoQueueTable,
     rt: Rc<dyn NetworkRuntime>,
     local_link_addr: MacAddress,
+    /// State of the link, which is polled along with the clock.
+    link: LinkMonitor,
     scheduler: Scheduler,
     clock: TimerRc,
     ts_iters: usize,
@@ -112,7 +159,10 @@ impl InetStack {
         rng_seed: [u8; 32],
         arp_config: ArpConfig,
     ) -> Result<Self, Fail> {
+        // Frames that we send to ourselves never reach the underlying runtime.
+        let rt: Rc<dyn NetworkRuntime> = Rc::new(Loopback::new(rt, local_link_addr));
         let file_table: IoQueueTable = IoQueueTable::new();
+        let link: LinkMonitor = LinkMonitor::new(rt.link_state());
         let arp: ArpPeer = ArpPeer::new(
             rt.clone(),
             scheduler.clone(),
@@ -138,6 +188,7 @@ impl InetStack {
             file_table,
             rt,
             local_link_addr,
+            link,
             scheduler,
             clock,
             ts_iters: 0,
@@ -175,6 +226,7 @@ impl InetStack {
         if domain != AF_INET {
             return Err(Fail::new(ENOTSUP, "address family not supported"));
         }
+        self.file_table.check_qd_limit()?;
         match socket_type {
             SOCK_STREAM => {
                 let qd: QDesc = self.file_table.alloc(QType::TcpSocket.into());
@@ -213,8 +265,8 @@ impl InetStack {
         #[cfg(feature = "profiler")]
         timer!("inetstack::bind");
         trace!("bind(): qd={:?} local={:?}", qd, local);
-        match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
                 Ok(QType::TcpSocket) => self.ipv4.tcp.bind(qd, local),
                 Ok(QType::UdpSocket) => self.ipv4.udp.do_bind(qd, local),
                 _ => Err(Fail::new(EINVAL, "invalid queue type")),
@@ -246,8 +298,8 @@ impl InetStack {
         if backlog == 0 {
             return Err(Fail::new(EINVAL, "invalid backlog length"));
         }
-        match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
                 Ok(QType::TcpSocket) => self.ipv4.tcp.listen(qd, backlog),
                 _ => Err(Fail::new(EINVAL, "invalid queue type")),
             },
@@ -273,21 +325,25 @@ impl InetStack {
         trace!("accept(): {:?}", qd);
 
         // Search for target queue descriptor.
-        match self.file_table.get(qd) {
+        match self.file_table.resolve(qd) {
             // Found, check if it concerns a TCP socket.
-            Some(qtype) => match QType::try_from(qtype) {
+            Some((queue, qtype)) => match QType::try_from(qtype) {
                 // It does, so allocate a new queue descriptor and issue accept operation.
                 Ok(QType::TcpSocket) => {
+                    self.file_table.check_qd_limit()?;
+                    self.file_table.check_operation_limit()?;
                     let new_qd: QDesc = self.file_table.alloc(QType::TcpSocket.into());
-                    let future: FutureOperation = FutureOperation::from(self.ipv4.tcp.do_accept(qd, new_qd));
-                    let handle: SchedulerHandle = match self.scheduler.insert(future) {
+                    let future: FutureOperation = FutureOperation::from(self.ipv4.tcp.do_accept(queue, new_qd));
+                    let handle: SchedulerHandle = match self.scheduler.insert(future, Priority::High) {
                         Some(handle) => handle,
                         None => {
                             self.file_table.free(new_qd);
                             return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine"));
                         },
                     };
-                    Ok(handle.into_raw().into())
+                    let qt: QToken = handle.into_raw().into();
+                    self.file_table.track(qt, &[qd], Some(new_qd));
+                    Ok(qt)
                 },
                 // This queue descriptor does not concern a TCP socket.
                 _ => Err(Fail::new(libc::EINVAL, "invalid queue type")),
@@ -313,22 +369,42 @@ impl InetStack {
         #[cfg(feature = "profiler")]
         timer!("inetstack::connect");
         trace!("connect(): qd={:?} remote={:?}", qd, remote);
-        let future = match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
+
+        self.do_connect(qd, remote, None)
+    }
+
+    /// Connects the socket referred to by `qd` to the remote endpoint specified by `remote`. The operation fails with
+    /// `ETIMEDOUT` if the connection is not established by `deadline`.
+    pub fn timedconnect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Instant) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::timedconnect");
+        trace!("timedconnect(): qd={:?} remote={:?}", qd, remote);
+
+        self.do_connect(qd, remote, Some(deadline))
+    }
+
+    /// Issues a connect operation.
+    fn do_connect(&mut self, qd: QDesc, remote: SocketAddrV4, deadline: Option<Instant>) -> Result<QToken, Fail> {
+        let future = match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
                 Ok(QType::TcpSocket) => {
-                    let fut: ConnectFuture = self.ipv4.tcp.connect(qd, remote)?;
+                    self.file_table.check_operation_limit()?;
+                    let mut fut: ConnectFuture = self.ipv4.tcp.connect(qd, remote)?;
+                    fut.deadline = Deadline::new(&self.clock, deadline).with_link(&self.link);
                     Ok(FutureOperation::from(fut))
                 },
+                // Connecting a UDP socket completes right away, so the deadline does not apply.
+                Ok(QType::UdpSocket) => {
+                    self.file_table.check_operation_limit()?;
+                    let udp_op = UdpOperation::Connect(qd, self.ipv4.udp.do_connect(qd, remote));
+                    Ok(FutureOperation::Udp(udp_op))
+                },
                 _ => Err(Fail::new(EINVAL, "invalid queue type")),
             },
             _ => Err(Fail::new(EBADF, "bad queue descriptor")),
         }?;
 
-        let handle: SchedulerHandle = match self.scheduler.insert(future) {
-            Some(handle) => handle,
-            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
-        };
-        let qt: QToken = handle.into_raw().into();
+        let qt: QToken = self.insert_operation(future, &[qd])?;
         trace!("connect() qt={:?}", qt);
         Ok(qt)
     }
@@ -348,26 +424,402 @@ impl InetStack {
         timer!("inetstack::close");
         trace!("close(): qd={:?}", qd);
 
-        match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
-                Ok(QType::TcpSocket) => self.ipv4.tcp.do_close(qd)?,
-                Ok(QType::UdpSocket) => self.ipv4.udp.do_close(qd)?,
-                _ => Err(Fail::new(EINVAL, "invalid queue type"))?,
+        let (queue, qtype): (QDesc, QType) = match self.file_table.resolve(qd) {
+            Some((queue, qtype)) => match QType::try_from(qtype) {
+                Ok(qtype @ (QType::TcpSocket | QType::UdpSocket | QType::LinkEvents)) => (queue, qtype),
+                _ => return Err(Fail::new(EINVAL, "invalid queue type")),
             },
-            _ => Err(Fail::new(EBADF, "bad queue descriptor"))?,
-        }
+            _ => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
 
-        self.file_table.free(qd);
+        // Cancel pending operations on the descriptor, then close the underlying socket along with the last descriptor
+        // that refers to it.
+        self.cancel_operations(qd);
+        if self.file_table.release(qd) == Some(true) {
+            match qtype {
+                QType::TcpSocket => self.ipv4.tcp.do_close(queue)?,
+                QType::LinkEvents => self.link.unsubscribe(queue),
+                _ => self.ipv4.udp.do_close(queue)?,
+            }
+        }
 
         Ok(())
     }
 
-    /// Pushes a buffer to a TCP socket.
+    ///
+    /// **Brief**
+    ///
+    /// Asynchronously closes a connection referred to by `qd`. On TCP sockets,
+    /// the returned operation completes once the remote peer acknowledges our
+    /// FIN (i.e. once the close handshake completes). Otherwise, it completes
+    /// right away.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, a queue token is returned. Upon failure,
+    /// `Fail` is returned instead.
+    ///
+    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::async_close");
+        trace!("async_close(): qd={:?}", qd);
+
+        let (queue, qtype): (QDesc, QType) = match self.file_table.resolve(qd) {
+            Some((queue, qtype)) => match QType::try_from(qtype) {
+                Ok(qtype @ (QType::TcpSocket | QType::UdpSocket)) => (queue, qtype),
+                _ => return Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+
+        // Cancel pending operations on the descriptor, then close the underlying socket along with the last descriptor
+        // that refers to it.
+        self.cancel_operations(qd);
+        let last: bool = self.file_table.release(qd) == Some(true);
+        let future: FutureOperation = match qtype {
+            QType::TcpSocket => FutureOperation::from(self.ipv4.tcp.do_async_close(queue, last)?),
+            _ => {
+                let result: Result<(), Fail> = if last { self.ipv4.udp.do_close(queue) } else { Ok(()) };
+                FutureOperation::Udp(UdpOperation::Close(queue, result))
+            },
+        };
+
+        let handle: SchedulerHandle = match self.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        trace!("async_close() qt={:?}", qt);
+        Ok(qt)
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Duplicates the queue descriptor `qd`. The new queue descriptor refers to
+    /// the same socket as `qd`, which is closed only once all queue descriptors
+    /// that refer to it are closed.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the new queue descriptor is returned. Upon
+    /// failure, `Fail` is returned instead.
+    ///
+    pub fn dup(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::dup");
+        trace!("dup(): qd={:?}", qd);
+
+        self.file_table.check_qd_limit()?;
+        match self.file_table.dup(qd) {
+            Some(new_qd) => Ok(new_qd),
+            None => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Shuts down the receive side, send side, or both sides of the connection
+    /// referred to by `qd`, without releasing the queue descriptor. Shutting
+    /// down the send side sends a FIN to the remote peer, while data that is
+    /// still in flight may continue to be popped. Once no more data is
+    /// available, pops complete with a zero-length buffer (i.e. end-of-stream).
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, `Ok(())` is returned. Upon failure, `Fail` is
+    /// returned instead.
+    ///
+    pub fn shutdown(&mut self, qd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::shutdown");
+        trace!("shutdown(): qd={:?} how={:?}", qd, how);
+
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.shutdown(qd, how),
+                Ok(QType::UdpSocket) => self.ipv4.udp.shutdown(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets the local endpoint address to which the socket referred to by `qd` is bound.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the local endpoint address is returned. Upon
+    /// failure, `Fail` is returned instead.
+    ///
+    pub fn local_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("local_addr(): qd={:?}", qd);
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.local_addr(qd),
+                Ok(QType::UdpSocket) => self.ipv4.udp.local_addr(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets the remote endpoint address to which the socket referred to by `qd` is connected.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the remote endpoint address is returned. Upon
+    /// failure, `Fail` is returned instead.
+    ///
+    pub fn remote_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        trace!("remote_addr(): qd={:?}", qd);
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.remote_addr(qd),
+                Ok(QType::UdpSocket) => self.ipv4.udp.remote_addr(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets the current value of the option `opt` on the socket referred to by
+    /// `qd`. The payload of `opt` is ignored.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, `opt` filled with its current value is
+    /// returned. Upon failure, `Fail` is returned instead.
+    ///
+    pub fn get_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        trace!("get_socket_option(): qd={:?} opt={:?}", qd, opt);
+        check_stack_option(&opt)?;
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.get_socket_option(qd, opt),
+                Ok(QType::UdpSocket) => self.ipv4.udp.get_socket_option(qd, opt),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Sets the option `opt` on the socket referred to by `qd`.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, `Ok(())` is returned. Upon failure, `Fail`
+    /// is returned instead.
+    ///
+    pub fn set_socket_option(&mut self, qd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        trace!("set_socket_option(): qd={:?} opt={:?}", qd, opt);
+        check_stack_option(&opt)?;
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.set_socket_option(qd, opt),
+                Ok(QType::UdpSocket) => self.ipv4.udp.set_socket_option(qd, opt),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets the number of datagrams that were truncated when popped from the
+    /// UDP socket referred to by `qd`.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the number of truncated datagrams is
+    /// returned. Upon failure, `Fail` is returned instead.
+    ///
+    pub fn truncations(&self, qd: QDesc) -> Result<u64, Fail> {
+        trace!("truncations(): qd={:?}", qd);
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::UdpSocket) => self.ipv4.udp.get_truncations(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets the number of connection requests that the listening TCP socket
+    /// referred to by `qd` dropped because its backlog was full.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the number of dropped connection requests
+    /// is returned. Upon failure, `Fail` is returned instead.
+    ///
+    pub fn backlog_overflows(&self, qd: QDesc) -> Result<u64, Fail> {
+        trace!("backlog_overflows(): qd={:?}", qd);
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.backlog_overflows(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets a snapshot of the state of the established TCP connection
+    /// referred to by `qd`, including its round-trip time estimates,
+    /// congestion control state, retransmissions and delivery rate.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the connection information is returned.
+    /// Upon failure, `Fail` is returned instead.
+    ///
+    pub fn tcp_info(&self, qd: QDesc) -> Result<TcpInfo, Fail> {
+        trace!("tcp_info(): qd={:?}", qd);
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.tcp_info(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Gets the hardware timestamps of the last datagram or segment that was
+    /// received and sent on the socket referred to by `qd`. Timestamps are
+    /// read from the clock of the network interface, in nanoseconds, and they
+    /// are unset if it does not timestamp frames.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, the timestamps are returned. Upon failure,
+    /// `Fail` is returned instead.
+    ///
+    pub fn timestamps(&self, qd: QDesc) -> Result<Timestamps, Fail> {
+        trace!("timestamps(): qd={:?}", qd);
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => self.ipv4.tcp.timestamps(qd),
+                Ok(QType::UdpSocket) => self.ipv4.udp.get_timestamps(qd),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Joins the UDP socket referred to by `qd` to the multicast group
+    /// `group`, on the interface that has the address `iface`. The
+    /// unspecified address selects the local interface. The group is reported
+    /// to multicast routers with IGMP when it is first joined.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, `Ok(())` is returned. Upon failure, `Fail`
+    /// is returned instead.
+    ///
+    pub fn join_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!(
+            "join_multicast_group(): qd={:?} group={:?} iface={:?}",
+            qd,
+            group,
+            iface
+        );
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::UdpSocket) => self.ipv4.udp.join_multicast_group(qd, group, iface),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    ///
+    /// **Brief**
+    ///
+    /// Removes the UDP socket referred to by `qd` from the multicast group
+    /// `group`, on the interface that has the address `iface`. Multicast
+    /// routers are notified with IGMP when the group is last left.
+    ///
+    /// **Return Value**
+    ///
+    /// Upon successful completion, `Ok(())` is returned. Upon failure, `Fail`
+    /// is returned instead.
+    ///
+    pub fn leave_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        trace!(
+            "leave_multicast_group(): qd={:?} group={:?} iface={:?}",
+            qd,
+            group,
+            iface
+        );
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::UdpSocket) => self.ipv4.udp.leave_multicast_group(qd, group, iface),
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    /// Pushes a buffer to a TCP socket, or to a connected UDP socket.
     /// TODO: Rename this function to push() once we have a common representation across all libOSes.
     pub fn do_push(&mut self, qd: QDesc, buf: DemiBuffer) -> Result<FutureOperation, Fail> {
-        match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
-                Ok(QType::TcpSocket) => Ok(FutureOperation::from(self.ipv4.tcp.push(qd, buf))),
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => {
+                    self.file_table.check_operation_limit()?;
+                    Ok(FutureOperation::from(self.ipv4.tcp.push(qd, buf)))
+                },
+                Ok(QType::UdpSocket) => {
+                    self.file_table.check_operation_limit()?;
+                    let udp_op = UdpOperation::Pushto(qd, self.ipv4.udp.do_push(qd, buf));
+                    Ok(FutureOperation::Udp(udp_op))
+                },
+                _ => Err(Fail::new(EINVAL, "invalid queue type")),
+            },
+            _ => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    /// Pushes multiple buffers to a TCP socket, back-to-back, or to a connected UDP socket, as a single datagram.
+    pub fn do_pushv(&mut self, qd: QDesc, bufs: Vec<DemiBuffer>) -> Result<FutureOperation, Fail> {
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => {
+                    self.file_table.check_operation_limit()?;
+                    Ok(FutureOperation::from(self.ipv4.tcp.pushv(qd, bufs)))
+                },
+                Ok(QType::UdpSocket) => {
+                    self.file_table.check_operation_limit()?;
+                    let buf: DemiBuffer = coalesce_buffers(bufs)?;
+                    let udp_op = UdpOperation::Pushto(qd, self.ipv4.udp.do_push(qd, buf));
+                    Ok(FutureOperation::Udp(udp_op))
+                },
                 _ => Err(Fail::new(EINVAL, "invalid queue type")),
             },
             _ => Err(Fail::new(EBADF, "bad queue descriptor")),
@@ -389,11 +841,7 @@ impl InetStack {
 
         // Issue operation.
         let future: FutureOperation = self.do_push(qd, buf)?;
-        let handle: SchedulerHandle = match self.scheduler.insert(future) {
-            Some(handle) => handle,
-            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
-        };
-        let qt: QToken = handle.into_raw().into();
+        let qt: QToken = self.insert_operation(future, &[qd])?;
         trace!("push2() qt={:?}", qt);
         Ok(qt)
     }
@@ -401,9 +849,10 @@ impl InetStack {
     /// Pushes a buffer to a UDP socket.
     /// TODO: Rename this function to pushto() once we have a common buffer representation across all libOSes.
     pub fn do_pushto(&mut self, qd: QDesc, buf: DemiBuffer, to: SocketAddrV4) -> Result<FutureOperation, Fail> {
-        match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
+        match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
                 Ok(QType::UdpSocket) => {
+                    self.file_table.check_operation_limit()?;
                     let udp_op = UdpOperation::Pushto(qd, self.ipv4.udp.do_pushto(qd, buf, to));
                     Ok(FutureOperation::Udp(udp_op))
                 },
@@ -428,11 +877,7 @@ impl InetStack {
 
         // Issue operation.
         let future: FutureOperation = self.do_pushto(qd, buf, remote)?;
-        let handle: SchedulerHandle = match self.scheduler.insert(future) {
-            Some(handle) => handle,
-            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
-        };
-        let qt: QToken = handle.into_raw().into();
+        let qt: QToken = self.insert_operation(future, &[qd])?;
         trace!("pushto2() qt={:?}", qt);
         Ok(qt)
     }
@@ -445,27 +890,307 @@ impl InetStack {
 
         trace!("pop(): qd={:?}", qd);
 
-        let future = match self.file_table.get(qd) {
-            Some(qtype) => match QType::try_from(qtype) {
-                Ok(QType::TcpSocket) => Ok(FutureOperation::from(self.ipv4.tcp.pop(qd))),
+        self.do_pop(qd, None, None)
+    }
+
+    /// Create a pop request that reads at most `size` bytes from the IO connection represented by `qd`. On TCP
+    /// sockets, this request only completes once `size` bytes are available or the remote peer closes the connection
+    /// (i.e. `size` acts as a low watermark). On UDP sockets, datagrams larger than `size` are truncated.
+    pub fn pop_sized(&mut self, qd: QDesc, size: usize) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::pop_sized");
+
+        trace!("pop_sized(): qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if size == 0 || size > u16::MAX as usize {
+            return Err(Fail::new(EINVAL, "invalid pop size"));
+        }
+
+        self.do_pop(qd, Some(size), None)
+    }
+
+    /// Create a pop request that fails with `ETIMEDOUT` if no data arrives by `deadline`. If `size` is set, this
+    /// behaves as [InetStack::pop_sized], otherwise it behaves as [InetStack::pop].
+    pub fn timedpop(&mut self, qd: QDesc, size: Option<usize>, deadline: Instant) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::timedpop");
+
+        trace!("timedpop(): qd={:?}, size={:?}", qd, size);
+
+        // Check if pop size is valid.
+        if let Some(size) = size {
+            if size == 0 || size > u16::MAX as usize {
+                return Err(Fail::new(EINVAL, "invalid pop size"));
+            }
+        }
+
+        self.do_pop(qd, size, Some(deadline))
+    }
+
+    /// Issues a pop operation.
+    fn do_pop(&mut self, qd: QDesc, size: Option<usize>, deadline: Option<Instant>) -> Result<QToken, Fail> {
+        let deadline: Deadline = Deadline::new(&self.clock, deadline).with_link(&self.link);
+        let future = match self.file_table.resolve(qd) {
+            Some((qd, qtype)) => match QType::try_from(qtype) {
+                Ok(QType::TcpSocket) => {
+                    self.file_table.check_operation_limit()?;
+                    let mut fut: PopFuture = self.ipv4.tcp.pop(qd, size);
+                    fut.deadline = deadline;
+                    Ok(FutureOperation::from(fut))
+                },
                 Ok(QType::UdpSocket) => {
-                    let udp_op = UdpOperation::Pop(FutureResult::new(self.ipv4.udp.do_pop(qd), None));
+                    self.file_table.check_operation_limit()?;
+                    let fut: UdpPopFuture = self.ipv4.udp.do_pop(qd, size).with_deadline(deadline);
+                    let udp_op = UdpOperation::Pop(FutureResult::new(fut, None));
                     Ok(FutureOperation::Udp(udp_op))
                 },
+                // Link events keep coming while the link is down, so they have no deadline.
+                Ok(QType::LinkEvents) => {
+                    self.file_table.check_operation_limit()?;
+                    let fut: LinkEventFuture = self.link.pop_event(qd)?;
+                    Ok(FutureOperation::Link(FutureResult::new(fut, None)))
+                },
                 _ => Err(Fail::new(EINVAL, "invalid queue type")),
             },
             _ => Err(Fail::new(EBADF, "bad queue descriptor")),
         }?;
 
-        let handle: SchedulerHandle = match self.scheduler.insert(future) {
+        let qt: QToken = self.insert_operation(future, &[qd])?;
+        trace!("pop() qt={:?}", qt);
+        Ok(qt)
+    }
+
+    /// Forwards data from the TCP socket referred to by `srcqd` to the one referred to by `dstqd`, without copying it,
+    /// until the remote peer of the source socket closes the connection. The returned operation completes once all
+    /// data has been forwarded.
+    pub fn splice(&mut self, srcqd: QDesc, dstqd: QDesc) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::splice");
+        trace!("splice(): srcqd={:?}, dstqd={:?}", srcqd, dstqd);
+
+        let (src, dst): (QDesc, QDesc) = match (self.file_table.resolve(srcqd), self.file_table.resolve(dstqd)) {
+            (Some((src, srctype)), Some((dst, dsttype))) => {
+                match (QType::try_from(srctype), QType::try_from(dsttype)) {
+                    (Ok(QType::TcpSocket), Ok(QType::TcpSocket)) => (src, dst),
+                    _ => return Err(Fail::new(EINVAL, "invalid queue type")),
+                }
+            },
+            _ => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        if src == dst {
+            return Err(Fail::new(EINVAL, "cannot splice a queue into itself"));
+        }
+
+        self.file_table.check_operation_limit()?;
+        let future: FutureOperation = FutureOperation::from(self.ipv4.tcp.splice(src, dst));
+        let qt: QToken = self.insert_operation(future, &[srcqd, dstqd])?;
+        trace!("splice() qt={:?}", qt);
+        Ok(qt)
+    }
+
+    /// Sends an ICMP echo request to `addr`. The returned operation completes with the round-trip time once the echo
+    /// reply arrives, or fails with `ETIMEDOUT` if it does not arrive within `timeout`.
+    pub fn ping(&mut self, addr: Ipv4Addr, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::ping");
+        trace!("ping(): addr={:?}, timeout={:?}", addr, timeout);
+
+        let future: FutureOperation =
+            FutureOperation::Ping(FutureResult::new(Box::pin(self.ipv4.ping(addr, timeout)), None));
+        let handle: SchedulerHandle = match self.scheduler.insert(future, Priority::High) {
             Some(handle) => handle,
             None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
         };
         let qt: QToken = handle.into_raw().into();
-        trace!("pop() qt={:?}", qt);
+        trace!("ping() qt={:?}", qt);
+        Ok(qt)
+    }
+
+    /// Creates a queue that reports changes of the link state. Each pop on it completes with the new state of the link,
+    /// once the link goes up or down. While the link is down, pending connect and pop operations on sockets fail with
+    /// `ENETDOWN`.
+    pub fn link_events(&mut self) -> Result<QDesc, Fail> {
+        trace!("link_events()");
+        self.file_table.check_qd_limit()?;
+        let qd: QDesc = self.file_table.alloc(QType::LinkEvents.into());
+        self.link.subscribe(qd);
+        Ok(qd)
+    }
+
+    /// Gets the last known state of the link.
+    pub fn link_state(&self) -> Result<LinkState, Fail> {
+        trace!("link_state()");
+        Ok(self.link.state())
+    }
+
+    /// Lists address resolutions that are stored in the ARP cache.
+    pub fn arp_entries(&self) -> Result<Vec<ArpEntry>, Fail> {
+        trace!("arp_entries()");
+        Ok(self.arp.entries())
+    }
+
+    /// Adds a static address resolution to the ARP cache. Static address resolutions never expire, and are neither
+    /// overwritten by ARP traffic nor flushed.
+    pub fn arp_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Result<(), Fail> {
+        trace!("arp_insert(): ipv4_addr={:?}, link_addr={:?}", ipv4_addr, link_addr);
+        if ipv4_addr.is_unspecified() || ipv4_addr.is_broadcast() || ipv4_addr.is_multicast() {
+            return Err(Fail::new(EINVAL, "invalid IPv4 address"));
+        }
+        if link_addr.is_nil() || !link_addr.is_unicast() {
+            return Err(Fail::new(EINVAL, "invalid link address"));
+        }
+        self.arp.insert_static(ipv4_addr, link_addr);
+        Ok(())
+    }
+
+    /// Gets the number of packets that were dropped while waiting for ARP to resolve the link address of their next
+    /// hop, either because too many of them were waiting, or because the link address could not be resolved.
+    pub fn arp_pending_drops(&self) -> Result<u64, Fail> {
+        trace!("arp_pending_drops()");
+        Ok(self.arp.pending_drops())
+    }
+
+    /// Removes all address resolutions from the ARP cache, except for static ones.
+    pub fn arp_flush(&mut self) -> Result<(), Fail> {
+        trace!("arp_flush()");
+        self.arp.flush();
+        Ok(())
+    }
+
+    /// Lists local IPv4 addresses, starting with the primary one.
+    pub fn ipv4_addrs(&self) -> Result<Vec<Ipv4Addr>, Fail> {
+        trace!("ipv4_addrs()");
+        Ok(self.arp.local_addrs())
+    }
+
+    /// Assigns a secondary IPv4 address to the interface. Traffic to any local address is accepted and handed to the
+    /// socket that is bound to it, while connections and datagrams from sockets that are not bound to an address are
+    /// sent from the local address that shares the longest prefix with the next hop.
+    pub fn add_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        trace!("add_ipv4_addr(): ipv4_addr={:?}", ipv4_addr);
+        self.arp.add_local_addr(ipv4_addr)
+    }
+
+    /// Removes a secondary IPv4 address from the interface. The primary address cannot be removed, and neither can an
+    /// address that sockets are bound to or connections use, as they would be left stranded.
+    pub fn remove_ipv4_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        trace!("remove_ipv4_addr(): ipv4_addr={:?}", ipv4_addr);
+        if self.ipv4.tcp.is_addr_in_use(ipv4_addr) || self.ipv4.udp.is_addr_in_use(ipv4_addr) {
+            return Err(Fail::new(libc::EADDRINUSE, "address in use"));
+        }
+        self.arp.remove_local_addr(ipv4_addr)
+    }
+
+    /// Starts acquiring the configuration of the interface from a DHCP server. The leased address replaces the primary
+    /// address, and routes to the subnet and through the default gateway are added. The lease is renewed until it
+    /// expires. Fails with `EALREADY` if the DHCP client was already started.
+    pub fn start_dhcp(&mut self) -> Result<(), Fail> {
+        trace!("start_dhcp()");
+        self.ipv4.dhcp.start()
+    }
+
+    /// Gets the current DHCP lease, if any.
+    pub fn dhcp_lease(&self) -> Result<Option<DhcpLease>, Fail> {
+        trace!("dhcp_lease()");
+        Ok(self.ipv4.dhcp.lease())
+    }
+
+    /// Runs background work until the DHCP client gets a lease, or fails with `ETIMEDOUT` if it does not get one
+    /// within `timeout`.
+    pub fn wait_for_dhcp_lease(&mut self, timeout: Duration) -> Result<DhcpLease, Fail> {
+        trace!("wait_for_dhcp_lease(): timeout={:?}", timeout);
+        // Timeouts that are too long to be represented never expire.
+        let deadline: Option<Instant> = Instant::now().checked_add(timeout);
+        loop {
+            self.poll_bg_work();
+            if let Some(lease) = self.ipv4.dhcp.lease() {
+                return Ok(lease);
+            }
+            let left: Duration = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => DHCP_POLL_INTERVAL,
+            };
+            if left.is_zero() {
+                return Err(Fail::new(ETIMEDOUT, "no DHCP lease"));
+            }
+            // Leases take round trips to servers, so there is no point in spinning while waiting for one.
+            thread::sleep(left.min(DHCP_POLL_INTERVAL));
+        }
+    }
+
+    /// Lists static routes, from the longest prefix to the shortest one.
+    pub fn routes(&self) -> Result<Vec<Route>, Fail> {
+        trace!("routes()");
+        Ok(self.arp.routes())
+    }
+
+    /// Adds a static route, replacing any route for the same destinations. Packets to destinations that the route
+    /// covers are sent to its gateway, or straight to the destination if the route has no gateway.
+    pub fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        trace!("add_route(): route={:?}", route);
+        self.arp.add_route(route);
+        Ok(())
+    }
+
+    /// Removes the static route for the given destinations. Fails with `ESRCH` if there is no such route.
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        trace!(
+            "remove_route(): destination={:?}, prefix_len={:?}",
+            destination,
+            prefix_len
+        );
+        self.arp.remove_route(destination, prefix_len)
+    }
+
+    /// Sets the time to live of address resolutions that are cached from now on, along with how long to wait for a
+    /// reply to an ARP request, and how many times to retry it.
+    pub fn set_arp_policy(
+        &mut self,
+        cache_ttl: Duration,
+        request_timeout: Duration,
+        retry_count: usize,
+    ) -> Result<(), Fail> {
+        trace!(
+            "set_arp_policy(): cache_ttl={:?}, request_timeout={:?}, retry_count={:?}",
+            cache_ttl,
+            request_timeout,
+            retry_count
+        );
+        self.arp.set_policy(cache_ttl, request_timeout, retry_count)
+    }
+
+    /// Schedules the operation `future`, which was issued on the queue descriptors `qds`, so that it is canceled if any
+    /// of them is closed. Callers check the limit on pending operations before building `future`, since building it
+    /// may already have side effects.
+    pub fn insert_operation(&mut self, future: FutureOperation, qds: &[QDesc]) -> Result<QToken, Fail> {
+        let handle: SchedulerHandle = match self.scheduler.insert(future, Priority::High) {
+            Some(handle) => handle,
+            None => return Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        };
+        let qt: QToken = handle.into_raw().into();
+        self.file_table.track(qt, qds, None);
         Ok(qt)
     }
 
+    /// Sets the limits on the queue descriptors and operations of the stack.
+    pub fn set_queue_limits(&mut self, limits: IoQueueLimits) {
+        self.file_table.set_limits(limits);
+    }
+
+    /// Counts the queue descriptors and operations that were refused because of the configured limits.
+    pub fn queue_limit_stats(&self) -> IoQueueLimitStats {
+        self.file_table.limit_stats()
+    }
+
+    /// Cancels the operations that are pending on the queue descriptor `qd`, which is about to be closed. These
+    /// operations complete with `ECANCELED`, unless they have already completed.
+    fn cancel_operations(&mut self, qd: QDesc) {
+        let scheduler: &Scheduler = &self.scheduler;
+        let ncanceled: usize = self.file_table.cancel(qd, |qt| scheduler.abort(qt.into()).is_some());
+        trace!("canceled {:?} operations (qd={:?})", ncanceled, qd);
+    }
+
     /// Waits for an operation to complete.
     #[deprecated]
     pub fn wait2(&mut self, qt: QToken) -> Result<(QDesc, OperationResult), Fail> {
@@ -486,7 +1211,7 @@ impl InetStack {
             // The operation has completed, so extract the result and return.
             if handle.has_completed() {
                 trace!("wait2() qt={:?} completed!", qt);
-                return Ok(self.take_operation(handle));
+                return Ok(self.take_operation(handle, qt));
             }
         }
     }
@@ -513,7 +1238,7 @@ impl InetStack {
 
                 // Found one, so extract the result and return.
                 if handle.has_completed() {
-                    let (qd, r): (QDesc, OperationResult) = self.take_operation(handle);
+                    let (qd, r): (QDesc, OperationResult) = self.take_operation(handle, qt);
                     return Ok((i, qd, r));
                 }
 
@@ -524,11 +1249,35 @@ impl InetStack {
         }
     }
 
-    /// Given a handle representing a task in our scheduler. Return the results of this future
-    /// and the file descriptor for this connection.
+    /// Takes out the earliest completed operation that satisfies `pred`, without scanning pending operations.
+    pub fn take_completed(&mut self, pred: &dyn Fn(QToken) -> bool) -> Option<QToken> {
+        self.scheduler
+            .take_completed(|key| pred(QToken::from(key)))
+            .map(QToken::from)
+    }
+
+    /// Given a handle representing a task in our scheduler, which refers to the operation `qt`. Return the results of
+    /// this future and the file descriptor for this connection. Operations that were canceled because their queue
+    /// descriptor was closed fail with `ECANCELED`. Futures refer to the underlying socket, so results report the
+    /// descriptor on which the operation was issued instead, which differs for duplicated descriptors.
     ///
     /// This function will panic if the specified future had not completed or is _background_ future.
-    pub fn take_operation(&mut self, handle: SchedulerHandle) -> (QDesc, OperationResult) {
+    pub fn take_operation(&mut self, handle: SchedulerHandle, qt: QToken) -> (QDesc, OperationResult) {
+        let issuer: Option<QDesc> = self.file_table.get_issuer(qt);
+        let (qd, qr): (QDesc, OperationResult) = self.do_take_operation(handle, qt);
+        (issuer.unwrap_or(qd), qr)
+    }
+
+    /// Takes the result of the operation `qt`, as reported by its future.
+    fn do_take_operation(&mut self, handle: SchedulerHandle, qt: QToken) -> (QDesc, OperationResult) {
+        if let Some(qd) = self.file_table.untrack(qt) {
+            self.scheduler.take(handle);
+            return (
+                qd,
+                OperationResult::Failed(Fail::new(libc::ECANCELED, "operation canceled")),
+            );
+        }
+
         let boxed_future: Box<dyn Any> = self.scheduler.take(handle).as_any();
         let boxed_concrete_type: FutureOperation = *boxed_future.downcast::<FutureOperation>().expect("Wrong type!");
 
@@ -551,6 +1300,23 @@ impl InetStack {
                 (qd, qr)
             },
             FutureOperation::Udp(f) => f.get_result(),
+            // Pings are not bound to any queue.
+            FutureOperation::Ping(FutureResult {
+                done: Some(Ok(rtt)), ..
+            }) => (QDesc::from(-1_i32), OperationResult::Ping(rtt)),
+            FutureOperation::Ping(FutureResult { done: Some(Err(e)), .. }) => {
+                (QDesc::from(-1_i32), OperationResult::Failed(e))
+            },
+            FutureOperation::Ping(FutureResult { done: None, .. }) => panic!("ping has not completed"),
+            FutureOperation::Link(FutureResult {
+                done: Some((qd, Ok(state))),
+                ..
+            }) => (qd, OperationResult::Link(state)),
+            FutureOperation::Link(FutureResult {
+                done: Some((qd, Err(e))),
+                ..
+            }) => (qd, OperationResult::Failed(e)),
+            FutureOperation::Link(FutureResult { done: None, .. }) => panic!("link event has not completed"),
             FutureOperation::Background(..) => {
                 panic!("`take_operation` attempted on background task!")
             },
@@ -610,6 +1376,10 @@ impl InetStack {
                         break;
                     }
 
+                    // Send at most one ACK per connection for the segments in this batch.
+                    self.ipv4.tcp.begin_ack_coalescing();
+                    // Read the arrival time of the datagrams in this batch only once.
+                    self.ipv4.udp.begin_batch();
                     for pkt in batch {
                         if let Err(e) = self.do_receive(pkt) {
                             warn!("Dropped packet: {:?}", e);
@@ -617,13 +1387,52 @@ impl InetStack {
                         // TODO: This is a workaround for https://github.com/demikernel/inetstack/issues/149.
                         self.scheduler.poll();
                     }
+                    self.ipv4.tcp.flush_coalesced_acks();
                 }
             }
         }
 
         if self.ts_iters == 0 {
             self.clock.advance_clock(Instant::now());
+            self.poll_link();
         }
         self.ts_iters = (self.ts_iters + 1) % TIMER_RESOLUTION;
     }
+
+    /// Polls the state of the link, so as to report changes and fail pending operations when the link goes down.
+    fn poll_link(&mut self) {
+        let state: LinkState = self.rt.link_state();
+        if self.link.update(state) {
+            info!("link is now {:?}", state);
+        }
+    }
+
+    /// Tears down the network stack. All queue descriptors are closed, frames that are pending transmission are
+    /// flushed, and all pending operations are canceled. If some queue descriptor fails to close, the first error is
+    /// returned once teardown completes.
+    pub fn teardown(&mut self) -> Result<(), Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("inetstack::teardown");
+        trace!("teardown()");
+
+        // Close all queue descriptors.
+        let mut result: Result<(), Fail> = Ok(());
+        for qd in self.file_table.qds() {
+            if let Err(e) = self.close(qd) {
+                warn!("failed to close queue descriptor (qd={:?}): {:?}", qd, e);
+                result = result.and(Err(e));
+            }
+        }
+
+        // Flush pending frames, such as those that close connections.
+        for _ in 0..TEARDOWN_POLL_ITERS {
+            self.poll_bg_work();
+        }
+
+        // Cancel all pending operations, including background ones.
+        let ncanceled: usize = self.scheduler.cancel_all();
+        trace!("teardown(): canceled {:?} operations", ncanceled);
+
+        result
+    }
 }
@@ -8,11 +8,16 @@
 use crate::runtime::{
     fail::Fail,
     memory::DemiBuffer,
+    network::types::{
+        LinkState,
+        PacketInfo,
+        SocketAddress,
+    },
     QDesc,
 };
 use ::std::{
     fmt,
-    net::SocketAddrV4,
+    time::Duration,
 };
 
 //==============================================================================
@@ -22,9 +27,18 @@ use ::std::{
 pub enum OperationResult {
     Connect,
     Accept(QDesc),
+    Close,
     Push,
     // TODO: Drop wrapping Option.
-    Pop(Option<SocketAddrV4>, DemiBuffer),
+    /// The third field holds result flags (`DEMI_QR_FLAG_*`), such as end-of-stream or datagram truncation. The last
+    /// field holds packet information, for datagrams popped from sockets that report it.
+    Pop(Option<SocketAddress>, DemiBuffer, u32, Option<PacketInfo>),
+    /// Forwarding between queues completed, after the given number of bytes.
+    Splice(usize),
+    /// An echo request was answered, after the given round-trip time.
+    Ping(Duration),
+    /// The link changed to the given state.
+    Link(LinkState),
     Failed(Fail),
 }
 
@@ -37,8 +51,13 @@ impl fmt::Debug for OperationResult {
         match self {
             OperationResult::Connect => write!(f, "Connect"),
             OperationResult::Accept(..) => write!(f, "Accept"),
+            OperationResult::Close => write!(f, "Close"),
             OperationResult::Push => write!(f, "Push"),
-            OperationResult::Pop(..) => write!(f, "Pop"),
+            OperationResult::Pop(_, _, 0, _) => write!(f, "Pop"),
+            OperationResult::Pop(_, _, flags, _) => write!(f, "Pop({:#x})", flags),
+            OperationResult::Splice(nbytes) => write!(f, "Splice({})", nbytes),
+            OperationResult::Ping(rtt) => write!(f, "Ping({:?})", rtt),
+            OperationResult::Link(state) => write!(f, "Link({:?})", state),
             OperationResult::Failed(ref e) => write!(f, "Failed({:?})", e),
         }
     }
@@ -7,7 +7,10 @@ mod tests;
 use crate::{
     inetstack::collections::HashTtlCache,
     runtime::{
-        network::types::MacAddress,
+        network::types::{
+            ArpEntry,
+            MacAddress,
+        },
         timer::TimerRc,
     },
 };
@@ -33,14 +36,13 @@ const DUMMY_MAC_ADDRESS: MacAddress = MacAddress::new([0; 6]);
 #[derive(Debug)]
 struct Record {
     link_addr: MacAddress,
+    /// Static records are set by the user, and are neither overwritten by ARP traffic nor flushed.
+    is_static: bool,
 }
 
 ///
 /// # ARP Cache
-/// - TODO: Allow multiple waiters for the same address
-/// - TODO: Deregister waiters here when the receiver goes away.
 /// - TODO: Implement eviction.
-/// - TODO: Implement remove.
 pub struct ArpCache {
     /// Cache for IPv4 Addresses
     cache: HashTtlCache<Ipv4Addr, Record>,
@@ -76,12 +78,34 @@ impl ArpCache {
         peer
     }
 
-    /// Caches an address resolution.
+    /// Caches an address resolution. Static address resolutions are left untouched.
     pub fn insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Option<MacAddress> {
-        let record = Record { link_addr };
+        if let Some(record) = self.cache.get(&ipv4_addr) {
+            if record.is_static {
+                return Some(record.link_addr);
+            }
+        }
+        let record = Record {
+            link_addr,
+            is_static: false,
+        };
         self.cache.insert(ipv4_addr, record).map(|r| r.link_addr)
     }
 
+    /// Caches an address resolution that never expires.
+    pub fn insert_static(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Option<MacAddress> {
+        let record = Record {
+            link_addr,
+            is_static: true,
+        };
+        self.cache.insert_with_ttl(ipv4_addr, record, None).map(|r| r.link_addr)
+    }
+
+    /// Sets the time to live of address resolutions that are cached from now on.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.cache.set_default_ttl(Some(ttl));
+    }
+
     /// Gets the MAC address of given IPv4 address.
     pub fn get(&self, ipv4_addr: Ipv4Addr) -> Option<&MacAddress> {
         if self.disable {
@@ -102,6 +126,31 @@ impl ArpCache {
         self.cache.clear();
     }
 
+    /// Removes all address resolutions from the ARP cache, except for static ones.
+    pub fn flush(&mut self) {
+        let dynamic: Vec<Ipv4Addr> = self
+            .cache
+            .iter()
+            .filter(|(_, r)| !r.is_static)
+            .map(|(k, _)| *k)
+            .collect();
+        for ipv4_addr in dynamic {
+            self.cache.remove(&ipv4_addr);
+        }
+    }
+
+    /// Lists address resolutions that are stored in the ARP cache.
+    pub fn entries(&self) -> Vec<ArpEntry> {
+        self.cache
+            .iter_with_ttl()
+            .map(|(k, r, ttl)| ArpEntry {
+                ipv4_addr: *k,
+                link_addr: r.link_addr,
+                ttl,
+            })
+            .collect()
+    }
+
     // Exports address resolutions that are stored in the ARP cache.
     #[cfg(test)]
     pub fn export(&self) -> HashMap<Ipv4Addr, MacAddress> {
@@ -6,7 +6,10 @@ use crate::{
     inetstack::protocols::ethernet2::Ethernet2Header,
     runtime::{
         memory::DemiBuffer,
-        network::PacketBuf,
+        network::{
+            types::MacAddress,
+            PacketBuf,
+        },
     },
 };
 
@@ -62,4 +65,8 @@ impl PacketBuf for ArpMessage {
     fn take_body(&self) -> Option<DemiBuffer> {
         None
     }
+
+    fn dst_link_addr(&self) -> MacAddress {
+        self.ethernet2_hdr.dst_addr()
+    }
 }
@@ -15,9 +15,12 @@ use crate::{
             FutureOperation,
             UtilityMethods,
         },
-        protocols::ethernet2::{
-            EtherType2,
-            Ethernet2Header,
+        protocols::{
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+            },
+            ipv4::RoutingTable,
         },
     },
     runtime::{
@@ -25,12 +28,17 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::ArpConfig,
-            types::MacAddress,
+            types::{
+                ArpEntry,
+                MacAddress,
+                Route,
+            },
             NetworkRuntime,
         },
         timer::TimerRc,
     },
     scheduler::{
+        Priority,
         Scheduler,
         SchedulerHandle,
     },
@@ -44,39 +52,82 @@ use ::futures::{
     FutureExt,
 };
 use ::libc::{
+    EADDRNOTAVAIL,
     EBADMSG,
+    EEXIST,
+    EINVAL,
     ETIMEDOUT,
 };
 use ::std::{
-    cell::RefCell,
-    collections::HashMap,
+    cell::{
+        Cell,
+        RefCell,
+    },
+    collections::{
+        HashMap,
+        VecDeque,
+    },
     future::Future,
     net::Ipv4Addr,
     rc::Rc,
     time::Duration,
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Time between ARP announcements (see RFC 5227).
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest time to wait for a reply to an ARP request, as resolutions for pending packets back off.
+const MAX_RESOLVE_TIMEOUT: Duration = Duration::from_secs(16);
+
+/// Maximum number of packets that wait for the link address of the same next hop to be resolved.
+pub const MAX_PENDING_PACKETS: usize = 64;
+
+//==============================================================================
+// Types
+//==============================================================================
+
+/// Packet that waits for the link address of its next hop to be resolved. It transmits itself once given that address.
+pub type PendingPacket = Box<dyn FnOnce(MacAddress)>;
+
 //==============================================================================
 // Structures
 //==============================================================================
 
 ///
 /// Arp Peer
-/// - TODO: Allow multiple waiters for the same address
 #[derive(Clone)]
 pub struct ArpPeer {
     rt: Rc<dyn NetworkRuntime>,
+    scheduler: Scheduler,
     clock: TimerRc,
     local_link_addr: MacAddress,
-    local_ipv4_addr: Ipv4Addr,
+    /// Local IPv4 addresses, starting with the primary one.
+    local_ipv4_addrs: Rc<RefCell<Vec<Ipv4Addr>>>,
     cache: Rc<RefCell<ArpCache>>,
-    waiters: Rc<RefCell<HashMap<Ipv4Addr, Sender<MacAddress>>>>,
-    arp_config: ArpConfig,
+    waiters: Rc<RefCell<HashMap<Ipv4Addr, Vec<Sender<MacAddress>>>>>,
+    arp_config: Rc<RefCell<ArpConfig>>,
+    /// Routes that pick the next hop to resolve for each destination.
+    routes: Rc<RefCell<RoutingTable>>,
+
+    /// Packets that wait for the link address of their next hop to be resolved.
+    pending: Rc<RefCell<HashMap<Ipv4Addr, VecDeque<PendingPacket>>>>,
+    /// Co-routines that resolve the link addresses of next hops for pending packets. Each one removes itself once done.
+    resolvers: Rc<RefCell<HashMap<Ipv4Addr, SchedulerHandle>>>,
+    /// Number of pending packets that were dropped, either because too many of them waited for the same next hop, or
+    /// because the link address of their next hop could not be resolved.
+    pending_drops: Rc<Cell<u64>>,
 
     /// The background co-routine cleans up the ARP cache from time to time.
     /// We annotate it as unused because the compiler believes that it is never called which is not the case.
     #[allow(unused)]
     background: Rc<SchedulerHandle>,
+
+    /// The co-routine that announces our address, if any announcement is under way.
+    announcement: Rc<RefCell<Option<SchedulerHandle>>>,
 }
 
 //==============================================================================
@@ -100,38 +151,115 @@ impl ArpPeer {
         )));
 
         let future = Self::background(clock.clone(), cache.clone());
-        let handle: SchedulerHandle = match scheduler.insert(FutureOperation::Background(future.boxed_local())) {
-            Some(handle) => handle,
-            None => {
-                return Err(Fail::new(
-                    libc::EAGAIN,
-                    "failed to schedule background co-routine for ARP module",
-                ))
-            },
-        };
-        let peer = ArpPeer {
+        let handle: SchedulerHandle =
+            match scheduler.insert(FutureOperation::Background(future.boxed_local()), Priority::High) {
+                Some(handle) => handle,
+                None => {
+                    return Err(Fail::new(
+                        libc::EAGAIN,
+                        "failed to schedule background co-routine for ARP module",
+                    ))
+                },
+            };
+        let mut peer = ArpPeer {
             rt,
+            scheduler,
             clock,
             local_link_addr,
-            local_ipv4_addr,
+            local_ipv4_addrs: Rc::new(RefCell::new(vec![local_ipv4_addr])),
             cache,
             waiters: Rc::new(RefCell::new(HashMap::default())),
-            arp_config,
+            routes: Rc::new(RefCell::new(RoutingTable::new(arp_config.get_routes()))),
+            arp_config: Rc::new(RefCell::new(arp_config)),
+            pending: Rc::new(RefCell::new(HashMap::default())),
+            resolvers: Rc::new(RefCell::new(HashMap::default())),
+            pending_drops: Rc::new(Cell::new(0)),
             background: Rc::new(handle),
+            announcement: Rc::new(RefCell::new(None)),
         };
+        peer.announce()?;
 
         Ok(peer)
     }
 
-    /// Drops a waiter for a target IP address.
+    /// Broadcasts ARP announcements for our addresses (see RFC 5227), so that neighbors and switches learn our link
+    /// address right away instead of waiting for their stale cache entries to expire. This is done on startup, and
+    /// should be done again whenever our addresses change. Any announcement that is still under way is cancelled.
+    pub fn announce(&mut self) -> Result<(), Fail> {
+        let count: usize = self.arp_config.borrow().get_announce_count();
+        if count == 0 || self.arp_config.borrow().get_disable_arp() {
+            return Ok(());
+        }
+        let msgs: Vec<ArpMessage> = self
+            .local_ipv4_addrs
+            .borrow()
+            .iter()
+            .filter(|addr| !addr.is_unspecified())
+            .map(|&local_ipv4_addr| {
+                ArpMessage::new(
+                    Ethernet2Header::new(MacAddress::broadcast(), self.local_link_addr, EtherType2::Arp),
+                    ArpHeader::new(
+                        ArpOperation::Request,
+                        self.local_link_addr,
+                        local_ipv4_addr,
+                        MacAddress::nil(),
+                        local_ipv4_addr,
+                    ),
+                )
+            })
+            .collect();
+        let future = Self::do_announce(self.rt.clone(), self.clock.clone(), msgs, count);
+        let handle: SchedulerHandle = match self
+            .scheduler
+            .insert(FutureOperation::Background(future.boxed_local()), Priority::High)
+        {
+            Some(handle) => handle,
+            None => {
+                return Err(Fail::new(
+                    libc::EAGAIN,
+                    "failed to schedule announcement co-routine for ARP module",
+                ))
+            },
+        };
+        *self.announcement.borrow_mut() = Some(handle);
+        Ok(())
+    }
+
+    /// Co-routine that broadcasts `count` rounds of ARP announcements.
+    async fn do_announce(rt: Rc<dyn NetworkRuntime>, clock: TimerRc, msgs: Vec<ArpMessage>, count: usize) {
+        for i in 0..count {
+            if i > 0 {
+                clock.wait(clock.clone(), ANNOUNCE_INTERVAL).await;
+            }
+            for msg in &msgs {
+                debug!("Announcing {:?}", msg);
+                rt.transmit(Box::new(msg.clone()));
+            }
+        }
+    }
+
+    /// Drops waiters for a target IP address that gave up.
     fn do_drop(&mut self, ipv4_addr: Ipv4Addr) {
-        self.waiters.borrow_mut().remove(&ipv4_addr);
+        let mut waiters = self.waiters.borrow_mut();
+        if let Some(senders) = waiters.get_mut(&ipv4_addr) {
+            senders.retain(|sender| !sender.is_canceled());
+            if senders.is_empty() {
+                waiters.remove(&ipv4_addr);
+            }
+        }
     }
 
-    fn do_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Option<MacAddress> {
-        if let Some(sender) = self.waiters.borrow_mut().remove(&ipv4_addr) {
-            let _ = sender.send(link_addr);
+    /// Wakes up all waiters for a target IP address.
+    fn do_wake(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) {
+        if let Some(senders) = self.waiters.borrow_mut().remove(&ipv4_addr) {
+            for sender in senders {
+                let _ = sender.send(link_addr);
+            }
         }
+    }
+
+    fn do_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Option<MacAddress> {
+        self.do_wake(ipv4_addr, link_addr);
         self.cache.borrow_mut().insert(ipv4_addr, link_addr)
     }
 
@@ -140,11 +268,7 @@ impl ArpPeer {
         if let Some(&link_addr) = self.cache.borrow().get(ipv4_addr) {
             let _ = tx.send(link_addr);
         } else {
-            assert!(
-                self.waiters.borrow_mut().insert(ipv4_addr, tx).is_none(),
-                "Duplicate waiter for {:?}",
-                ipv4_addr
-            );
+            self.waiters.borrow_mut().entry(ipv4_addr).or_default().push(tx);
         }
         rx.map(|r| r.expect("Dropped waiter?"))
     }
@@ -172,6 +296,32 @@ impl ArpPeer {
         let header = ArpHeader::parse(buf)?;
         debug!("Received {:?}", header);
 
+        // from RFC 5227:
+        // > ARP Probe: An ARP Request packet, broadcast on the local link,
+        // > with an all-zero 'sender IP address'.
+        // Probes say nothing about the sender, so we do not cache them. We still answer probes for our address, so
+        // that the sender finds out that it is taken.
+        if header.get_sender_protocol_addr().is_unspecified() {
+            if header.get_operation() != ArpOperation::Request
+                || !self.is_link_local_addr(header.get_destination_protocol_addr())
+            {
+                return Err(Fail::new(EBADMSG, "unrecognized IP address"));
+            }
+            let reply = ArpMessage::new(
+                Ethernet2Header::new(header.get_sender_hardware_addr(), self.local_link_addr, EtherType2::Arp),
+                ArpHeader::new(
+                    ArpOperation::Reply,
+                    self.local_link_addr,
+                    header.get_destination_protocol_addr(),
+                    header.get_sender_hardware_addr(),
+                    header.get_sender_protocol_addr(),
+                ),
+            );
+            debug!("Responding to probe {:?}", reply);
+            self.rt.transmit(Box::new(reply));
+            return Ok(());
+        }
+
         // from RFC 826:
         // > Merge_flag := false
         // > If the pair <protocol type, sender protocol address> is
@@ -187,7 +337,7 @@ impl ArpPeer {
             }
         };
         // from RFC 826: ?Am I the target protocol address?
-        if header.get_destination_protocol_addr() != self.local_ipv4_addr {
+        if !self.is_link_local_addr(header.get_destination_protocol_addr()) {
             if merge_flag {
                 // we did do something.
                 return Ok(());
@@ -214,7 +364,7 @@ impl ArpPeer {
                     ArpHeader::new(
                         ArpOperation::Reply,
                         self.local_link_addr,
-                        self.local_ipv4_addr,
+                        header.get_destination_protocol_addr(),
                         header.get_sender_hardware_addr(),
                         header.get_sender_protocol_addr(),
                     ),
@@ -237,19 +387,48 @@ impl ArpPeer {
         }
     }
 
+    /// Returns the link address of the next hop towards `ipv4_addr`, if it is cached. Our own addresses resolve to our
+    /// link address, so that traffic to them is looped back.
     pub fn try_query(&self, ipv4_addr: Ipv4Addr) -> Option<MacAddress> {
-        self.cache.borrow().get(ipv4_addr).cloned()
+        self.lookup(self.next_hop(ipv4_addr))
     }
 
+    /// Resolves the link address of the next hop towards `ipv4_addr`.
     pub fn query(&self, ipv4_addr: Ipv4Addr) -> impl Future<Output = Result<MacAddress, Fail>> {
+        self.do_query(self.next_hop(ipv4_addr), false)
+    }
+
+    /// Returns the next hop towards `ipv4_addr`, which is the address itself if it is one of ours.
+    fn next_hop(&self, ipv4_addr: Ipv4Addr) -> Ipv4Addr {
+        match self.is_local_addr(ipv4_addr) {
+            true => ipv4_addr,
+            false => self.routes.borrow().next_hop(ipv4_addr),
+        }
+    }
+
+    /// Returns the link address of a neighbor, if it is cached or if the neighbor is ourselves.
+    fn lookup(&self, ipv4_addr: Ipv4Addr) -> Option<MacAddress> {
+        match self.is_local_addr(ipv4_addr) {
+            true => Some(self.local_link_addr),
+            false => self.cache.borrow().get(ipv4_addr).cloned(),
+        }
+    }
+
+    /// Resolves the link address of a neighbor. If `backoff` is set, the time to wait for a reply doubles on every
+    /// retry, up to [MAX_RESOLVE_TIMEOUT].
+    fn do_query(&self, ipv4_addr: Ipv4Addr, backoff: bool) -> impl Future<Output = Result<MacAddress, Fail>> {
         let rt = self.rt.clone();
         let mut arp = self.clone();
         let cache = self.cache.clone();
-        let arp_options = self.arp_config.clone();
+        let arp_options: ArpConfig = self.arp_config.borrow().clone();
         let clock: TimerRc = self.clock.clone();
         let local_link_addr: MacAddress = self.local_link_addr.clone();
-        let local_ipv4_addr: Ipv4Addr = self.local_ipv4_addr.clone();
+        let local_ipv4_addr: Ipv4Addr = self.neighbor_source(ipv4_addr);
+        let is_local_addr: bool = self.is_local_addr(ipv4_addr);
         async move {
+            if is_local_addr {
+                return Ok(local_link_addr);
+            }
             if let Some(&link_addr) = cache.borrow().get(ipv4_addr) {
                 return Ok(link_addr);
             }
@@ -269,9 +448,13 @@ impl ArpPeer {
             // > The frequency of the ARP request is very close to one per
             // > second, the maximum suggested by [RFC1122].
             let result = {
+                let mut timeout: Duration = arp_options.get_request_timeout();
                 for i in 0..arp_options.get_retry_count() + 1 {
                     rt.transmit(Box::new(msg.clone()));
-                    let timer = clock.wait(clock.clone(), arp_options.get_request_timeout());
+                    let timer = clock.wait(clock.clone(), timeout);
+                    if backoff && timeout < MAX_RESOLVE_TIMEOUT {
+                        timeout = (timeout * 2).min(MAX_RESOLVE_TIMEOUT);
+                    }
 
                     match arp_response.with_timeout(timer).await {
                         Ok(link_addr) => {
@@ -286,12 +469,232 @@ impl ArpPeer {
                 Err(Fail::new(ETIMEDOUT, "ARP query timeout"))
             };
 
+            drop(arp_response);
             arp.do_drop(ipv4_addr);
 
             result
         }
     }
 
+    /// Sends a packet to `ipv4_addr` once the link address of the next hop towards it is resolved. If the link address
+    /// is cached, the packet is sent right away. Otherwise, it is queued until ARP resolves the address, and dropped if
+    /// this fails. At most [MAX_PENDING_PACKETS] packets are queued for the same next hop, and the oldest ones make
+    /// room for new ones.
+    pub fn send_when_resolved(&self, ipv4_addr: Ipv4Addr, packet: PendingPacket) -> Result<(), Fail> {
+        let ipv4_addr: Ipv4Addr = self.next_hop(ipv4_addr);
+        if let Some(link_addr) = self.lookup(ipv4_addr) {
+            packet(link_addr);
+            return Ok(());
+        }
+
+        let mut pending = self.pending.borrow_mut();
+        match pending.get_mut(&ipv4_addr) {
+            Some(queue) => {
+                if queue.len() >= MAX_PENDING_PACKETS {
+                    warn!("too many packets waiting for {:?}, dropping the oldest one", ipv4_addr);
+                    queue.pop_front();
+                    self.pending_drops.set(self.pending_drops.get() + 1);
+                }
+                queue.push_back(packet);
+            },
+            None => {
+                // Only the first packet for an address starts resolving it.
+                let future = Self::resolve(self.clone(), ipv4_addr);
+                let handle: SchedulerHandle = match self
+                    .scheduler
+                    .insert(FutureOperation::Background(future.boxed_local()), Priority::High)
+                {
+                    Some(handle) => handle,
+                    None => {
+                        return Err(Fail::new(
+                            libc::EAGAIN,
+                            "failed to schedule resolution co-routine for ARP module",
+                        ))
+                    },
+                };
+                self.resolvers.borrow_mut().insert(ipv4_addr, handle);
+                pending.insert(ipv4_addr, VecDeque::from(vec![packet]));
+            },
+        }
+        Ok(())
+    }
+
+    /// Co-routine that resolves the link address of `ipv4_addr`, backing off between retries, and then flushes the
+    /// packets that wait for it.
+    async fn resolve(arp: ArpPeer, ipv4_addr: Ipv4Addr) {
+        let result: Result<MacAddress, Fail> = arp.do_query(ipv4_addr, true).await;
+        let queue: VecDeque<PendingPacket> = arp.pending.borrow_mut().remove(&ipv4_addr).unwrap_or_default();
+        // Dropping our own handle only flags this co-routine, which the scheduler removes once it completes.
+        arp.resolvers.borrow_mut().remove(&ipv4_addr);
+        match result {
+            Ok(link_addr) => {
+                for packet in queue {
+                    packet(link_addr);
+                }
+            },
+            Err(e) => {
+                warn!("dropping {} packets for {:?}: {:?}", queue.len(), ipv4_addr, e);
+                arp.pending_drops.set(arp.pending_drops.get() + queue.len() as u64);
+            },
+        }
+    }
+
+    /// Gets the number of packets that were dropped while waiting for the link address of their next hop.
+    pub fn pending_drops(&self) -> u64 {
+        self.pending_drops.get()
+    }
+
+    /// Lists routes, from the longest prefix to the shortest one.
+    pub fn routes(&self) -> Vec<Route> {
+        self.routes.borrow().routes()
+    }
+
+    /// Adds a route, replacing any route for the same destinations. Link addresses that are already resolved for
+    /// packets in flight are not affected.
+    pub fn add_route(&mut self, route: Route) {
+        self.routes.borrow_mut().insert(route);
+    }
+
+    /// Removes the route for the given destinations.
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        match self.routes.borrow_mut().remove(destination, prefix_len) {
+            Some(_) => Ok(()),
+            None => Err(Fail::new(libc::ESRCH, "no such route")),
+        }
+    }
+
+    /// Checks whether `ipv4_addr` is one of our addresses. Loopback addresses (`127.0.0.0/8`) are always ours.
+    pub fn is_local_addr(&self, ipv4_addr: Ipv4Addr) -> bool {
+        ipv4_addr.is_loopback() || self.is_link_local_addr(ipv4_addr)
+    }
+
+    /// Checks whether `ipv4_addr` is one of the addresses that we use on the link, which we answer ARP requests for.
+    fn is_link_local_addr(&self, ipv4_addr: Ipv4Addr) -> bool {
+        !ipv4_addr.is_unspecified() && self.local_ipv4_addrs.borrow().contains(&ipv4_addr)
+    }
+
+    /// Lists our addresses, starting with the primary one.
+    pub fn local_addrs(&self) -> Vec<Ipv4Addr> {
+        self.local_ipv4_addrs.borrow().clone()
+    }
+
+    /// Adds a secondary address, and announces our addresses.
+    pub fn add_local_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        if ipv4_addr.is_unspecified() || ipv4_addr.is_broadcast() || ipv4_addr.is_multicast() || ipv4_addr.is_loopback()
+        {
+            return Err(Fail::new(EINVAL, "invalid IPv4 address"));
+        }
+        if self.is_local_addr(ipv4_addr) {
+            return Err(Fail::new(EEXIST, "address already assigned"));
+        }
+        self.local_ipv4_addrs.borrow_mut().push(ipv4_addr);
+        self.announce()
+    }
+
+    /// Removes a secondary address. The primary address cannot be removed.
+    pub fn remove_local_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        let mut local_ipv4_addrs = self.local_ipv4_addrs.borrow_mut();
+        match local_ipv4_addrs.iter().position(|&addr| addr == ipv4_addr) {
+            Some(0) => Err(Fail::new(EINVAL, "cannot remove the primary address")),
+            Some(index) => {
+                local_ipv4_addrs.remove(index);
+                Ok(())
+            },
+            None => Err(Fail::new(EADDRNOTAVAIL, "address not assigned")),
+        }
+    }
+
+    /// Replaces the primary address, and announces our addresses. The unspecified address stands for no address at
+    /// all, until one is assigned (e.g. by DHCP).
+    pub fn set_primary_addr(&mut self, ipv4_addr: Ipv4Addr) -> Result<(), Fail> {
+        if ipv4_addr.is_broadcast() || ipv4_addr.is_multicast() || ipv4_addr.is_loopback() {
+            return Err(Fail::new(EINVAL, "invalid IPv4 address"));
+        }
+        {
+            let mut local_ipv4_addrs = self.local_ipv4_addrs.borrow_mut();
+            if local_ipv4_addrs.iter().skip(1).any(|&addr| addr == ipv4_addr) {
+                return Err(Fail::new(EEXIST, "address already assigned"));
+            }
+            local_ipv4_addrs[0] = ipv4_addr;
+        }
+        self.announce()
+    }
+
+    /// Picks the source address of packets to `ipv4_addr`, which is the one that shares the longest prefix with the
+    /// next hop towards it. This is the address on the subnet of the next hop if there is one, and the primary address
+    /// otherwise.
+    pub fn select_source(&self, ipv4_addr: Ipv4Addr) -> Ipv4Addr {
+        // Traffic to ourselves comes from the same address, or from the usual loopback address.
+        if ipv4_addr.is_loopback() {
+            return Ipv4Addr::LOCALHOST;
+        }
+        if self.is_local_addr(ipv4_addr) {
+            return ipv4_addr;
+        }
+        let next_hop: Ipv4Addr = self.routes.borrow().next_hop(ipv4_addr);
+        self.neighbor_source(next_hop)
+    }
+
+    /// Picks the source address of packets to a neighbor.
+    fn neighbor_source(&self, ipv4_addr: Ipv4Addr) -> Ipv4Addr {
+        let common_prefix_len = |addr: Ipv4Addr| (u32::from(addr) ^ u32::from(ipv4_addr)).leading_zeros();
+        let local_ipv4_addrs = self.local_ipv4_addrs.borrow();
+        local_ipv4_addrs
+            .iter()
+            .skip(1)
+            .fold(local_ipv4_addrs[0], |best, &addr| {
+                if common_prefix_len(addr) > common_prefix_len(best) {
+                    addr
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// Lists address resolutions that are stored in the ARP cache.
+    pub fn entries(&self) -> Vec<ArpEntry> {
+        self.cache.borrow().entries()
+    }
+
+    /// Adds an address resolution that never expires, and is neither overwritten by ARP traffic nor flushed. Pending
+    /// queries for the same address complete right away.
+    pub fn insert_static(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) {
+        self.do_wake(ipv4_addr, link_addr);
+        self.cache.borrow_mut().insert_static(ipv4_addr, link_addr);
+    }
+
+    /// Removes all address resolutions from the ARP cache, except for static ones.
+    pub fn flush(&mut self) {
+        self.cache.borrow_mut().flush();
+    }
+
+    /// Sets the time to live of address resolutions that are cached from now on, and how queries that follow are
+    /// retried.
+    pub fn set_policy(
+        &mut self,
+        cache_ttl: Duration,
+        request_timeout: Duration,
+        retry_count: usize,
+    ) -> Result<(), Fail> {
+        if cache_ttl.is_zero() {
+            return Err(Fail::new(EINVAL, "cache TTL must be positive"));
+        }
+        if request_timeout.is_zero() {
+            return Err(Fail::new(EINVAL, "request timeout must be positive"));
+        }
+        self.cache.borrow_mut().set_ttl(cache_ttl);
+        let mut arp_config = self.arp_config.borrow_mut();
+        arp_config.set_cache_ttl(cache_ttl);
+        arp_config.set_request_timeout(request_timeout);
+        arp_config.set_retry_count(retry_count);
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn resolvers(&self) -> usize {
+        self.resolvers.borrow().len()
+    }
+
     #[cfg(test)]
     pub fn export_cache(&self) -> HashMap<Ipv4Addr, MacAddress> {
         self.cache.borrow().export()
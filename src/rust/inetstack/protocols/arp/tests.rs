@@ -1,16 +1,44 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-use super::packet::{
-    ArpHeader,
-    ArpOperation,
+use super::{
+    packet::{
+        ArpHeader,
+        ArpMessage,
+        ArpOperation,
+    },
+    peer::MAX_PENDING_PACKETS,
 };
 use crate::{
     inetstack::{
-        protocols::ethernet2::Ethernet2Header,
-        test_helpers::{self,},
+        protocols::ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+        },
+        test_helpers::{
+            self,
+            Engine,
+            TestRuntime,
+        },
     },
-    runtime::network::types::MacAddress,
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            config::{
+                ArpConfig,
+                TcpConfig,
+                UdpConfig,
+            },
+            types::{
+                ArpEntry,
+                MacAddress,
+                Route,
+            },
+            PacketBuf,
+        },
+        timer::TimerRc,
+    },
+    scheduler::Scheduler,
 };
 use ::futures::{
     task::{
@@ -21,10 +49,17 @@ use ::futures::{
 };
 use ::libc::{
     EBADMSG,
+    EEXIST,
+    EINVAL,
+    ESRCH,
     ETIMEDOUT,
 };
 use ::std::{
+    cell::RefCell,
+    collections::HashMap,
     future::Future,
+    net::Ipv4Addr,
+    rc::Rc,
     task::Poll,
     time::{
         Duration,
@@ -175,3 +210,454 @@ fn no_reply() {
     }
     .unwrap();
 }
+
+/// Tests that static entries resolve pending queries, and survive flushes.
+#[test]
+fn static_entries() {
+    let now = Instant::now();
+    let mut alice = test_helpers::new_alice(now);
+    let bob = test_helpers::new_bob(now);
+
+    // A static entry completes a pending query.
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut fut = alice.arp_query(test_helpers::CARRIE_IPV4).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    alice.rt.pop_frame();
+    alice
+        .arp
+        .insert_static(test_helpers::CARRIE_IPV4, test_helpers::CARRIE_MAC);
+    match Future::poll(fut.as_mut(), &mut ctx) {
+        Poll::Ready(Ok(link_addr)) if link_addr == test_helpers::CARRIE_MAC => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Alice learns about Bob when he asks for her.
+    let mut fut = bob.arp_query(test_helpers::ALICE_IPV4).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    alice.rt.pop_frame();
+    let mut entries: Vec<ArpEntry> = alice.arp.entries();
+    entries.sort_by_key(|e| e.ipv4_addr);
+    assert_eq!(
+        entries,
+        vec![
+            ArpEntry {
+                ipv4_addr: test_helpers::BOB_IPV4,
+                link_addr: test_helpers::BOB_MAC,
+                ttl: Some(alice.rt.arp_options.get_cache_ttl()),
+            },
+            ArpEntry {
+                ipv4_addr: test_helpers::CARRIE_IPV4,
+                link_addr: test_helpers::CARRIE_MAC,
+                ttl: None,
+            },
+        ]
+    );
+
+    // Flushing only removes what Alice learned.
+    alice.arp.flush();
+    assert_eq!(
+        alice.arp.entries(),
+        vec![ArpEntry {
+            ipv4_addr: test_helpers::CARRIE_IPV4,
+            link_addr: test_helpers::CARRIE_MAC,
+            ttl: None,
+        }]
+    );
+}
+
+/// Tests that changes to the ARP policy apply to entries and queries that follow.
+#[test]
+fn change_policy() {
+    let mut now = Instant::now();
+    let mut alice = test_helpers::new_alice(now);
+    let bob = test_helpers::new_bob(now);
+
+    // Invalid policies are rejected.
+    match alice.arp.set_policy(Duration::ZERO, Duration::from_secs(1), 0) {
+        Err(e) if e.errno == EINVAL => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    let cache_ttl: Duration = Duration::from_secs(30);
+    let request_timeout: Duration = Duration::from_secs(2);
+    alice.arp.set_policy(cache_ttl, request_timeout, 0).unwrap();
+
+    // New entries get the new time to live.
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut fut = bob.arp_query(test_helpers::ALICE_IPV4).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    alice.rt.pop_frame();
+    assert_eq!(
+        alice.arp.entries(),
+        vec![ArpEntry {
+            ipv4_addr: test_helpers::BOB_IPV4,
+            link_addr: test_helpers::BOB_MAC,
+            ttl: Some(cache_ttl),
+        }]
+    );
+
+    // New queries time out after a single request.
+    let mut fut = alice.arp_query(Ipv4Addr::new(192, 168, 1, 99)).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    alice.rt.pop_frame();
+    now += request_timeout;
+    alice.clock.advance_clock(now);
+    match Future::poll(fut.as_mut(), &mut ctx) {
+        Poll::Ready(Err(error)) if error.errno == ETIMEDOUT => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+}
+
+/// Tests that our address is announced on startup.
+#[test]
+fn announce_on_startup() {
+    let mut now = Instant::now();
+    let arp_options: ArpConfig = ArpConfig::new(
+        Some(Duration::from_secs(600)),
+        Some(Duration::from_secs(1)),
+        Some(2),
+        Some(HashMap::new()),
+        Some(false),
+        Some(2),
+        None,
+    );
+    let rt: TestRuntime = TestRuntime::new(
+        now,
+        arp_options,
+        UdpConfig::default(),
+        TcpConfig::default(),
+        test_helpers::ALICE_MAC,
+        test_helpers::ALICE_IPV4,
+    );
+    let scheduler: Scheduler = rt.scheduler.clone();
+    let clock: TimerRc = rt.clock.clone();
+    let alice: Engine = Engine::new(rt, scheduler, clock).unwrap();
+
+    for i in 0..2 {
+        if i > 0 {
+            // The next announcement waits for its turn.
+            alice.rt.poll_scheduler();
+            assert!(alice.rt.pop_frame_unchecked().is_none());
+            now += Duration::from_secs(2);
+            alice.clock.advance_clock(now);
+        }
+        alice.rt.poll_scheduler();
+        let (eth_hdr, payload) = Ethernet2Header::parse(alice.rt.pop_frame()).unwrap();
+        assert_eq!(eth_hdr.dst_addr(), MacAddress::broadcast());
+        let arp = ArpHeader::parse(payload).unwrap();
+        assert_eq!(arp.get_operation(), ArpOperation::Request);
+        assert_eq!(arp.get_sender_hardware_addr(), test_helpers::ALICE_MAC);
+        assert_eq!(arp.get_sender_protocol_addr(), test_helpers::ALICE_IPV4);
+        assert_eq!(arp.get_destination_protocol_addr(), test_helpers::ALICE_IPV4);
+    }
+
+    // There are no more announcements.
+    now += Duration::from_secs(2);
+    alice.clock.advance_clock(now);
+    alice.rt.poll_scheduler();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+}
+
+/// Tests that probes for our address get answered, without polluting the ARP cache.
+#[test]
+fn reply_to_probe() {
+    let now = Instant::now();
+    let mut alice = test_helpers::new_alice(now);
+
+    // Carrie probes for the address of Alice before taking it.
+    let probe: ArpMessage = ArpMessage::new(
+        Ethernet2Header::new(MacAddress::broadcast(), test_helpers::CARRIE_MAC, EtherType2::Arp),
+        ArpHeader::new(
+            ArpOperation::Request,
+            test_helpers::CARRIE_MAC,
+            Ipv4Addr::UNSPECIFIED,
+            MacAddress::nil(),
+            test_helpers::ALICE_IPV4,
+        ),
+    );
+    let header_size: usize = probe.header_size();
+    let mut buf: DemiBuffer = DemiBuffer::new(header_size as u16);
+    probe.write_header(&mut buf[..]);
+    alice.receive(buf).unwrap();
+
+    // Alice tells Carrie that the address is taken.
+    let (eth_hdr, payload) = Ethernet2Header::parse(alice.rt.pop_frame()).unwrap();
+    assert_eq!(eth_hdr.dst_addr(), test_helpers::CARRIE_MAC);
+    let arp = ArpHeader::parse(payload).unwrap();
+    assert_eq!(arp.get_operation(), ArpOperation::Reply);
+    assert_eq!(arp.get_sender_hardware_addr(), test_helpers::ALICE_MAC);
+    assert_eq!(arp.get_sender_protocol_addr(), test_helpers::ALICE_IPV4);
+    assert!(alice.arp.entries().is_empty());
+}
+
+/// Tests that packets wait for the link address of their next hop, and that the oldest ones are dropped when too many
+/// of them are waiting.
+#[test]
+fn pending_packets() {
+    let now = Instant::now();
+    let mut alice = test_helpers::new_alice(now);
+    let mut carrie = test_helpers::new_carrie(now);
+
+    // Alice sends more packets to Carrie than can wait for her link address.
+    let sent: Rc<RefCell<Vec<(usize, MacAddress)>>> = Rc::new(RefCell::new(Vec::new()));
+    for i in 0..(MAX_PENDING_PACKETS + 2) {
+        let sent: Rc<RefCell<Vec<(usize, MacAddress)>>> = sent.clone();
+        alice
+            .arp
+            .send_when_resolved(
+                test_helpers::CARRIE_IPV4,
+                Box::new(move |link_addr: MacAddress| sent.borrow_mut().push((i, link_addr))),
+            )
+            .unwrap();
+    }
+    assert_eq!(alice.arp.pending_drops(), 2);
+
+    // Other queries for the same address may wait alongside.
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut fut = alice.arp_query(test_helpers::CARRIE_IPV4).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    alice.rt.pop_frame();
+
+    // A single resolution is under way for all packets.
+    alice.rt.poll_scheduler();
+    let request: DemiBuffer = alice.rt.pop_frame();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+    assert!(sent.borrow().is_empty());
+
+    // Once Carrie replies, the packets that were not dropped are sent in order.
+    carrie.receive(request).unwrap();
+    alice.receive(carrie.rt.pop_frame()).unwrap();
+    alice.rt.poll_scheduler();
+    let expected: Vec<(usize, MacAddress)> = (2..(MAX_PENDING_PACKETS + 2))
+        .map(|i| (i, test_helpers::CARRIE_MAC))
+        .collect();
+    assert_eq!(*sent.borrow(), expected);
+    match Future::poll(fut.as_mut(), &mut ctx) {
+        Poll::Ready(Ok(link_addr)) if link_addr == test_helpers::CARRIE_MAC => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Packets that follow are sent right away.
+    let sent_now: Rc<RefCell<Option<MacAddress>>> = Rc::new(RefCell::new(None));
+    let sent_now_clone: Rc<RefCell<Option<MacAddress>>> = sent_now.clone();
+    alice
+        .arp
+        .send_when_resolved(
+            test_helpers::CARRIE_IPV4,
+            Box::new(move |link_addr: MacAddress| *sent_now_clone.borrow_mut() = Some(link_addr)),
+        )
+        .unwrap();
+    assert_eq!(*sent_now.borrow(), Some(test_helpers::CARRIE_MAC));
+    assert_eq!(alice.arp.pending_drops(), 2);
+    assert_eq!(alice.arp.resolvers(), 0);
+}
+
+/// Tests that packets are dropped when the link address of their next hop cannot be resolved.
+#[test]
+fn pending_packets_no_reply() {
+    let mut now = Instant::now();
+    let alice = test_helpers::new_alice(now);
+
+    for _ in 0..3 {
+        alice
+            .arp
+            .send_when_resolved(
+                test_helpers::CARRIE_IPV4,
+                Box::new(|_: MacAddress| panic!("packet should not be sent")),
+            )
+            .unwrap();
+    }
+
+    // Alice waits twice as long for every reply as for the one before, and gives up after her last retry.
+    let mut timeout: Duration = alice.rt.arp_options.get_request_timeout();
+    for _ in 0..(alice.rt.arp_options.get_retry_count() + 1) {
+        alice.rt.poll_scheduler();
+        alice.rt.pop_frame();
+        assert_eq!(alice.arp.pending_drops(), 0);
+        now += timeout / 2;
+        alice.clock.advance_clock(now);
+        alice.rt.poll_scheduler();
+        assert!(alice.rt.pop_frame_unchecked().is_none());
+        now += timeout - timeout / 2;
+        alice.clock.advance_clock(now);
+        timeout *= 2;
+    }
+    alice.rt.poll_scheduler();
+    assert_eq!(alice.arp.pending_drops(), 3);
+    assert_eq!(alice.arp.resolvers(), 0);
+}
+
+/// Tests that ARP resolves the gateway of the route towards a remote destination.
+#[test]
+fn route_through_gateway() {
+    let now = Instant::now();
+    let arp_options: ArpConfig = ArpConfig::new(
+        Some(Duration::from_secs(600)),
+        Some(Duration::from_secs(1)),
+        Some(2),
+        Some(HashMap::new()),
+        Some(false),
+        Some(0),
+        Some(vec![Route::new(
+            Ipv4Addr::UNSPECIFIED,
+            0,
+            Some(test_helpers::CARRIE_IPV4),
+        )
+        .unwrap()]),
+    );
+    let rt: TestRuntime = TestRuntime::new(
+        now,
+        arp_options,
+        UdpConfig::default(),
+        TcpConfig::default(),
+        test_helpers::ALICE_MAC,
+        test_helpers::ALICE_IPV4,
+    );
+    let scheduler: Scheduler = rt.scheduler.clone();
+    let clock: TimerRc = rt.clock.clone();
+    let mut alice: Engine = Engine::new(rt, scheduler, clock).unwrap();
+    let mut carrie = test_helpers::new_carrie(now);
+    let remote: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+
+    // Alice asks for the link address of her gateway, rather than that of the remote host.
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut fut = alice.arp_query(remote).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    let request: DemiBuffer = alice.rt.pop_frame();
+    let (_, payload) = Ethernet2Header::parse(request.clone()).unwrap();
+    let arp = ArpHeader::parse(payload).unwrap();
+    assert_eq!(arp.get_destination_protocol_addr(), test_helpers::CARRIE_IPV4);
+
+    carrie.receive(request).unwrap();
+    alice.receive(carrie.rt.pop_frame()).unwrap();
+    match Future::poll(fut.as_mut(), &mut ctx) {
+        Poll::Ready(Ok(link_addr)) if link_addr == test_helpers::CARRIE_MAC => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(alice.arp.try_query(remote), Some(test_helpers::CARRIE_MAC));
+
+    // A more specific route puts the remote host on the local link.
+    alice
+        .arp
+        .add_route(Route::new(Ipv4Addr::new(10, 0, 0, 0), 8, None).unwrap());
+    assert_eq!(alice.arp.routes().len(), 2);
+    assert!(alice.arp.try_query(remote).is_none());
+    let mut fut = alice.arp_query(remote).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    let (_, payload) = Ethernet2Header::parse(alice.rt.pop_frame()).unwrap();
+    let arp = ArpHeader::parse(payload).unwrap();
+    assert_eq!(arp.get_destination_protocol_addr(), remote);
+
+    // Routes must exist to be removed.
+    alice.arp.remove_route(Ipv4Addr::new(10, 0, 0, 0), 8).unwrap();
+    assert_eq!(
+        alice.arp.remove_route(Ipv4Addr::new(10, 0, 0, 0), 8).unwrap_err().errno,
+        ESRCH
+    );
+    assert_eq!(alice.arp.try_query(remote), Some(test_helpers::CARRIE_MAC));
+}
+
+/// Tests that ARP answers for secondary addresses, and that sources are picked among our addresses by next hop.
+#[test]
+fn secondary_addresses() {
+    let now = Instant::now();
+    let mut alice = test_helpers::new_alice(now);
+    let mut carrie = test_helpers::new_carrie(now);
+    let secondary: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    alice.arp.add_local_addr(secondary).unwrap();
+    assert_eq!(alice.arp.local_addrs(), vec![test_helpers::ALICE_IPV4, secondary]);
+    assert_eq!(alice.arp.add_local_addr(secondary).unwrap_err().errno, EEXIST);
+
+    // Carrie resolves the secondary address.
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut fut = carrie.arp_query(secondary).boxed_local();
+    assert!(Future::poll(fut.as_mut(), &mut ctx).is_pending());
+    alice.receive(carrie.rt.pop_frame()).unwrap();
+    let reply: DemiBuffer = alice.rt.pop_frame();
+    let (_, payload) = Ethernet2Header::parse(reply.clone()).unwrap();
+    assert_eq!(ArpHeader::parse(payload).unwrap().get_sender_protocol_addr(), secondary);
+    carrie.receive(reply).unwrap();
+    match Future::poll(fut.as_mut(), &mut ctx) {
+        Poll::Ready(Ok(link_addr)) if link_addr == test_helpers::ALICE_MAC => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Sources share the longest prefix with the next hop.
+    assert_eq!(alice.arp.select_source(Ipv4Addr::new(10, 0, 0, 2)), secondary);
+    assert_eq!(
+        alice.arp.select_source(test_helpers::CARRIE_IPV4),
+        test_helpers::ALICE_IPV4
+    );
+    alice
+        .arp
+        .add_route(Route::new(Ipv4Addr::UNSPECIFIED, 0, Some(Ipv4Addr::new(10, 0, 0, 254))).unwrap());
+    assert_eq!(alice.arp.select_source(Ipv4Addr::new(8, 8, 8, 8)), secondary);
+
+    // Only secondary addresses can be removed.
+    assert_eq!(
+        alice.arp.remove_local_addr(test_helpers::ALICE_IPV4).unwrap_err().errno,
+        EINVAL
+    );
+    alice.arp.remove_local_addr(secondary).unwrap();
+    assert!(!alice.arp.is_local_addr(secondary));
+    assert_eq!(
+        alice.arp.select_source(Ipv4Addr::new(10, 0, 0, 2)),
+        test_helpers::ALICE_IPV4
+    );
+}
+
+/// Tests that our own addresses and loopback addresses resolve to our link address without any ARP traffic, and that
+/// we do not answer ARP requests for loopback addresses.
+#[test]
+fn resolve_local_addresses() {
+    let now = Instant::now();
+    let mut alice = test_helpers::new_alice(now);
+    let loopback: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 5);
+    assert_eq!(
+        alice.arp.try_query(test_helpers::ALICE_IPV4),
+        Some(test_helpers::ALICE_MAC)
+    );
+    assert_eq!(alice.arp.try_query(loopback), Some(test_helpers::ALICE_MAC));
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut fut = alice.arp_query(Ipv4Addr::LOCALHOST).boxed_local();
+    match Future::poll(fut.as_mut(), &mut ctx) {
+        Poll::Ready(Ok(link_addr)) if link_addr == test_helpers::ALICE_MAC => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+
+    // Traffic to ourselves comes from the destination address, or from the usual loopback address.
+    assert_eq!(alice.arp.select_source(loopback), Ipv4Addr::LOCALHOST);
+    assert_eq!(
+        alice.arp.select_source(test_helpers::ALICE_IPV4),
+        test_helpers::ALICE_IPV4
+    );
+    assert_eq!(alice.arp.add_local_addr(loopback).unwrap_err().errno, EINVAL);
+
+    // Carrie asks for a loopback address.
+    let request: ArpMessage = ArpMessage::new(
+        Ethernet2Header::new(MacAddress::broadcast(), test_helpers::CARRIE_MAC, EtherType2::Arp),
+        ArpHeader::new(
+            ArpOperation::Request,
+            test_helpers::CARRIE_MAC,
+            test_helpers::CARRIE_IPV4,
+            MacAddress::nil(),
+            Ipv4Addr::LOCALHOST,
+        ),
+    );
+    let header_size: usize = request.header_size();
+    let mut buf: DemiBuffer = DemiBuffer::new(header_size as u16);
+    request.write_header(&mut buf[..]);
+    assert_eq!(alice.receive(buf).unwrap_err().errno, EBADMSG);
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+}
@@ -0,0 +1,453 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    inetstack::{
+        futures::{
+            FutureOperation,
+            UtilityMethods,
+        },
+        protocols::{
+            arp::ArpPeer,
+            dhcp::datagram::{
+                DhcpHeader,
+                DhcpMessageType,
+                DHCP_CLIENT_PORT,
+                DHCP_SERVER_PORT,
+            },
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+            },
+            ip::IpProtocol,
+            ipv4::Ipv4Header,
+            udp::{
+                UdpDatagram,
+                UdpHeader,
+                UDP_HEADER_SIZE,
+            },
+        },
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            types::{
+                DhcpLease,
+                MacAddress,
+                Route,
+            },
+            NetworkRuntime,
+        },
+        timer::TimerRc,
+    },
+    scheduler::{
+        Priority,
+        Scheduler,
+        SchedulerHandle,
+    },
+};
+use ::byteorder::{
+    ByteOrder,
+    NetworkEndian,
+};
+use ::futures::{
+    channel::mpsc,
+    FutureExt,
+    StreamExt,
+};
+use ::libc::{
+    EAGAIN,
+    EALREADY,
+    EBADMSG,
+};
+use ::rand::{
+    prelude::SmallRng,
+    Rng,
+    SeedableRng,
+};
+use ::std::{
+    cell::RefCell,
+    cmp,
+    iter,
+    net::Ipv4Addr,
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Time to wait for the first reply to a message, which doubles with each retransmission (see RFC 2131 Section 4.1).
+const DHCP_INITIAL_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Maximum time to wait for a reply to a message.
+const DHCP_MAX_TIMEOUT: Duration = Duration::from_secs(64);
+
+/// Number of times that we ask for an offered address before looking for servers again.
+const DHCP_REQUEST_ATTEMPTS: usize = 4;
+
+/// Minimum time between retransmissions while renewing a lease (see RFC 2131 Section 4.4.5).
+const DHCP_MIN_RENEW_INTERVAL: Duration = Duration::from_secs(60);
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+///
+/// Dynamic Host Configuration Protocol (DHCP) Client
+///
+/// This protocol is used by hosts to obtain their network configuration from
+/// a server. Once started, the client acquires an IPv4 address, along with the
+/// network mask, default gateway, and DNS servers, and keeps renewing the
+/// lease until it expires. The address becomes our primary address, and the
+/// subnet and default gateway turn into routes.
+///
+/// This is an implementation of the client side of DHCPv4, which is defined
+/// in RFC 2131, with the following simplifications:
+/// - Offered addresses are not probed for conflicts before they are used, so
+///   DHCPDECLINE messages are never sent.
+/// - Retransmissions are not randomized.
+/// - Leases are not released when the stack shuts down.
+///
+#[derive(Clone)]
+pub struct DhcpClient {
+    /// Underlying Runtime
+    rt: Rc<dyn NetworkRuntime>,
+    scheduler: Scheduler,
+    clock: TimerRc,
+    local_link_addr: MacAddress,
+    /// Underlying ARP Peer, which owns our addresses and routes.
+    arp: ArpPeer,
+    rng: Rc<RefCell<SmallRng>>,
+
+    /// Replies from servers, for the co-routine that maintains the lease.
+    tx: mpsc::UnboundedSender<DhcpHeader>,
+    rx: Rc<RefCell<Option<mpsc::UnboundedReceiver<DhcpHeader>>>>,
+
+    /// Current lease, if any.
+    lease: Rc<RefCell<Option<DhcpLease>>>,
+    /// Routes that the current lease added, which are removed along with it.
+    routes: Rc<RefCell<Vec<Route>>>,
+
+    /// The co-routine that acquires and renews the lease, once the client is started.
+    background: Rc<RefCell<Option<SchedulerHandle>>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for DHCP Clients
+impl DhcpClient {
+    /// Creates a DHCP client, which does nothing until it is started.
+    pub fn new(
+        rt: Rc<dyn NetworkRuntime>,
+        scheduler: Scheduler,
+        clock: TimerRc,
+        local_link_addr: MacAddress,
+        arp: ArpPeer,
+        rng_seed: [u8; 32],
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded();
+        Self {
+            rt,
+            scheduler,
+            clock,
+            local_link_addr,
+            arp,
+            rng: Rc::new(RefCell::new(SmallRng::from_seed(rng_seed))),
+            tx,
+            rx: Rc::new(RefCell::new(Some(rx))),
+            lease: Rc::new(RefCell::new(None)),
+            routes: Rc::new(RefCell::new(Vec::new())),
+            background: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Starts looking for a DHCP server, and keeps the lease that it grants up to date.
+    pub fn start(&self) -> Result<(), Fail> {
+        let rx: mpsc::UnboundedReceiver<DhcpHeader> = match self.rx.borrow_mut().take() {
+            Some(rx) => rx,
+            None => return Err(Fail::new(EALREADY, "DHCP client already started")),
+        };
+        let future = Self::background(self.clone(), rx);
+        let handle: SchedulerHandle = match self
+            .scheduler
+            .insert(FutureOperation::Background(future.boxed_local()), Priority::Low)
+        {
+            Some(handle) => handle,
+            None => {
+                return Err(Fail::new(
+                    EAGAIN,
+                    "failed to schedule background co-routine for DHCP module",
+                ))
+            },
+        };
+        *self.background.borrow_mut() = Some(handle);
+        Ok(())
+    }
+
+    /// Returns the current lease, if any.
+    pub fn lease(&self) -> Option<DhcpLease> {
+        self.lease.borrow().clone()
+    }
+
+    /// Checks whether a UDP datagram is meant for the DHCP client, which is the case of datagrams sent to the DHCP
+    /// client port once the client is started.
+    pub fn is_client_datagram(&self, buf: &DemiBuffer) -> bool {
+        self.background.borrow().is_some()
+            && buf.len() >= UDP_HEADER_SIZE
+            && NetworkEndian::read_u16(&buf[2..4]) == DHCP_CLIENT_PORT
+    }
+
+    /// Parses a reply from a DHCP server, and hands it to the co-routine that maintains the lease.
+    pub fn receive(&self, ipv4_hdr: &Ipv4Header, buf: DemiBuffer) -> Result<(), Fail> {
+        let (udp_hdr, data): (UdpHeader, DemiBuffer) = UdpHeader::parse(ipv4_hdr, buf, false)?;
+        if udp_hdr.src_port() != DHCP_SERVER_PORT {
+            return Err(Fail::new(EBADMSG, "DHCP message not sent by a server"));
+        }
+        let dhcp_hdr: DhcpHeader = DhcpHeader::parse(&data[..])?;
+        debug!("DHCP received {:?}", dhcp_hdr);
+        if dhcp_hdr.chaddr != self.local_link_addr {
+            return Err(Fail::new(EBADMSG, "DHCP message for another client"));
+        }
+        let _ = self.tx.unbounded_send(dhcp_hdr);
+        Ok(())
+    }
+
+    /// Co-routine that acquires a lease, and renews it until it expires, over and over again.
+    async fn background(client: DhcpClient, mut rx: mpsc::UnboundedReceiver<DhcpHeader>) {
+        loop {
+            // Look for servers, and ask the first one that answers for the address that it offers.
+            let mut discover: DhcpHeader = client.new_request(DhcpMessageType::Discover);
+            discover.broadcast = true;
+            let offer: DhcpHeader = match client.transact(&mut rx, &discover, None, Self::backoff()).await {
+                Some(offer) => offer,
+                None => continue,
+            };
+            let mut request: DhcpHeader =
+                DhcpHeader::new(DhcpMessageType::Request, discover.xid, client.local_link_addr);
+            request.broadcast = true;
+            request.requested_addr = Some(offer.yiaddr);
+            request.server_id = offer.server_id;
+            let mut ack: DhcpHeader = match client
+                .transact(&mut rx, &request, None, Self::backoff().take(DHCP_REQUEST_ATTEMPTS))
+                .await
+            {
+                Some(ack) if ack.msg_type == DhcpMessageType::Ack => ack,
+                _ => continue,
+            };
+
+            // Keep the lease up to date, first with the server that granted it, then with any server.
+            loop {
+                let (renew_at, rebind_at, expire_at): (Instant, Instant, Instant) = client.bind(&ack);
+                client.clock.wait_until(client.clock.clone(), renew_at).await;
+                let server: Option<Ipv4Addr> = ack.server_id;
+                let reply: Option<DhcpHeader> = match client.renew(&mut rx, server, rebind_at).await {
+                    Some(reply) => Some(reply),
+                    None => client.renew(&mut rx, None, expire_at).await,
+                };
+                match reply {
+                    Some(reply) if reply.msg_type == DhcpMessageType::Ack => ack = reply,
+                    _ => {
+                        client.unbind();
+                        break;
+                    },
+                }
+            }
+        }
+    }
+
+    /// Asks a server to extend our lease, until `deadline` passes. The request is unicast to `server` if given, and
+    /// broadcast otherwise.
+    async fn renew(
+        &self,
+        rx: &mut mpsc::UnboundedReceiver<DhcpHeader>,
+        server: Option<Ipv4Addr>,
+        deadline: Instant,
+    ) -> Option<DhcpHeader> {
+        let mut request: DhcpHeader = self.new_request(DhcpMessageType::Request);
+        request.ciaddr = self.arp.local_addrs()[0];
+        // Wait for half of the time that is left, but not too little (see RFC 2131 Section 4.4.5).
+        let clock: TimerRc = self.clock.clone();
+        let timeouts = iter::from_fn(move || {
+            let left: Duration = deadline.saturating_duration_since(clock.now());
+            match left.is_zero() {
+                true => None,
+                false => Some(cmp::min(cmp::max(left / 2, DHCP_MIN_RENEW_INTERVAL), left)),
+            }
+        });
+        self.transact(rx, &request, server, timeouts).await
+    }
+
+    /// Sends `request` until a reply to it arrives, waiting for each of the given timeouts in turn.
+    async fn transact(
+        &self,
+        rx: &mut mpsc::UnboundedReceiver<DhcpHeader>,
+        request: &DhcpHeader,
+        server: Option<Ipv4Addr>,
+        timeouts: impl Iterator<Item = Duration>,
+    ) -> Option<DhcpHeader> {
+        for timeout in timeouts {
+            if let Err(e) = self.send(request, server).await {
+                warn!("failed to send {:?}: {:?}", request.msg_type, e);
+            }
+            let deadline: Instant = self.clock.now() + timeout;
+            loop {
+                let mut next = rx.next().fuse();
+                match next
+                    .with_timeout(self.clock.wait_until(self.clock.clone(), deadline))
+                    .await
+                {
+                    Ok(Some(reply)) if reply.xid == request.xid && Self::answers(request, &reply) => {
+                        return Some(reply)
+                    },
+                    Ok(Some(reply)) => debug!("ignoring unexpected DHCP reply {:?}", reply),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+        }
+        None
+    }
+
+    /// Checks whether `reply` answers `request`.
+    fn answers(request: &DhcpHeader, reply: &DhcpHeader) -> bool {
+        match request.msg_type {
+            DhcpMessageType::Discover => reply.msg_type == DhcpMessageType::Offer,
+            DhcpMessageType::Request => matches!(reply.msg_type, DhcpMessageType::Ack | DhcpMessageType::Nak),
+            _ => false,
+        }
+    }
+
+    /// Sends a message to `server` if given, or broadcasts it otherwise.
+    async fn send(&self, msg: &DhcpHeader, server: Option<Ipv4Addr>) -> Result<(), Fail> {
+        let (dst_ipv4_addr, dst_link_addr): (Ipv4Addr, MacAddress) = match server {
+            Some(server) => (server, self.arp.query(server).await?),
+            None => (Ipv4Addr::BROADCAST, MacAddress::broadcast()),
+        };
+        let mut buf: Vec<u8> = vec![0; msg.size()];
+        msg.serialize(&mut buf);
+        debug!("DHCP sending {:?}", msg);
+        self.rt.transmit(Box::new(UdpDatagram::new(
+            Ethernet2Header::new(dst_link_addr, self.local_link_addr, EtherType2::Ipv4),
+            Ipv4Header::new(msg.ciaddr, dst_ipv4_addr, IpProtocol::UDP),
+            UdpHeader::new(DHCP_CLIENT_PORT, DHCP_SERVER_PORT),
+            DemiBuffer::from_slice(&buf)?,
+            false,
+        )));
+        Ok(())
+    }
+
+    /// Creates a request that starts a new transaction.
+    fn new_request(&self, msg_type: DhcpMessageType) -> DhcpHeader {
+        let xid: u32 = self.rng.borrow_mut().gen();
+        DhcpHeader::new(msg_type, xid, self.local_link_addr)
+    }
+
+    /// Returns the sequence of timeouts of retransmissions, which back off exponentially.
+    fn backoff() -> impl Iterator<Item = Duration> {
+        iter::successors(Some(DHCP_INITIAL_TIMEOUT), |timeout| {
+            Some(cmp::min(*timeout * 2, DHCP_MAX_TIMEOUT))
+        })
+    }
+
+    /// Applies the configuration that a server acknowledged, and returns when to renew the lease, when to ask any
+    /// server to extend it, and when it expires.
+    fn bind(&self, ack: &DhcpHeader) -> (Instant, Instant, Instant) {
+        let prefix_len: Option<u8> = ack.subnet_mask.map(|mask| u32::from(mask).leading_ones() as u8);
+        let lease: DhcpLease = DhcpLease {
+            ipv4_addr: ack.yiaddr,
+            prefix_len: prefix_len.unwrap_or(32),
+            gateway: ack.routers.first().copied(),
+            dns_servers: ack.dns_servers.clone(),
+            server: ack.server_id.unwrap_or(Ipv4Addr::UNSPECIFIED),
+            lease_time: Duration::from_secs(ack.lease_time.unwrap_or(u32::MAX) as u64),
+        };
+        info!("DHCP lease acquired: {:?}", lease);
+
+        // Replace the configuration of the previous lease, if it changed.
+        if self.lease.borrow().as_ref() != Some(&lease) {
+            self.forget_routes();
+            if let Err(e) = self.arp.clone().set_primary_addr(lease.ipv4_addr) {
+                warn!("failed to announce leased address: {:?}", e);
+            }
+            if let Some(prefix_len) = prefix_len {
+                match Route::new(Self::subnet(lease.ipv4_addr, prefix_len), prefix_len, None) {
+                    Ok(route) => self.add_route(route),
+                    Err(e) => warn!("invalid subnet in DHCP lease: {:?}", e),
+                }
+            }
+            if let Some(gateway) = lease.gateway {
+                match Route::new(Ipv4Addr::UNSPECIFIED, 0, Some(gateway)) {
+                    Ok(route) => self.add_route(route),
+                    Err(e) => warn!("invalid gateway in DHCP lease: {:?}", e),
+                }
+            }
+        }
+
+        // Servers may leave out renewal and rebinding times, which default to half and seven eighths of the lease time
+        // (see RFC 2131 Section 4.4.5).
+        let now: Instant = self.clock.now();
+        let lease_time: u64 = lease.lease_time.as_secs();
+        let renewal_time: u64 = ack.renewal_time.map_or(lease_time / 2, |t| t as u64);
+        let rebinding_time: u64 = ack.rebinding_time.map_or(lease_time * 7 / 8, |t| t as u64);
+        *self.lease.borrow_mut() = Some(lease);
+        (
+            now + Duration::from_secs(renewal_time),
+            now + Duration::from_secs(rebinding_time),
+            now + Duration::from_secs(lease_time),
+        )
+    }
+
+    /// Gives up on the current lease, once it expires or a server refuses to extend it.
+    fn unbind(&self) {
+        warn!("DHCP lease lost: {:?}", self.lease.borrow());
+        self.forget_routes();
+        if let Err(e) = self.arp.clone().set_primary_addr(Ipv4Addr::UNSPECIFIED) {
+            warn!("failed to remove leased address: {:?}", e);
+        }
+        *self.lease.borrow_mut() = None;
+    }
+
+    /// Adds a route of the current lease, unless some route for the same destinations was configured otherwise.
+    fn add_route(&self, route: Route) {
+        let mut arp: ArpPeer = self.arp.clone();
+        if arp
+            .routes()
+            .iter()
+            .any(|r| r.destination == route.destination && r.prefix_len == route.prefix_len)
+        {
+            warn!("keeping configured route instead of {:?} from DHCP lease", route);
+            return;
+        }
+        arp.add_route(route);
+        self.routes.borrow_mut().push(route);
+    }
+
+    /// Removes the routes that the current lease added, unless they were replaced since.
+    fn forget_routes(&self) {
+        let mut arp: ArpPeer = self.arp.clone();
+        for route in self.routes.borrow_mut().drain(..) {
+            if arp.routes().contains(&route) {
+                let _ = arp.remove_route(route.destination, route.prefix_len);
+            }
+        }
+    }
+
+    /// Computes the address of the subnet that `ipv4_addr` belongs to.
+    fn subnet(ipv4_addr: Ipv4Addr, prefix_len: u8) -> Ipv4Addr {
+        match prefix_len {
+            0 => Ipv4Addr::UNSPECIFIED,
+            n => Ipv4Addr::from(u32::from(ipv4_addr) & (u32::MAX << (32 - n as u32))),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::types::MacAddress,
+};
+use ::byteorder::{
+    ByteOrder,
+    NetworkEndian,
+};
+use ::libc::{
+    EBADMSG,
+    ENOTSUP,
+};
+use ::std::net::Ipv4Addr;
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// UDP port of DHCP servers.
+pub const DHCP_SERVER_PORT: u16 = 67;
+
+/// UDP port of DHCP clients.
+pub const DHCP_CLIENT_PORT: u16 = 68;
+
+/// Size of the fixed part of DHCP messages, which is inherited from BOOTP (in bytes).
+const DHCP_FIXED_SIZE: usize = 236;
+
+/// Magic cookie that starts the options field (see RFC 2131 Section 3).
+const DHCP_MAGIC_COOKIE: u32 = 0x63825363;
+
+/// Minimum size of DHCP messages, since some relays drop smaller BOOTP messages (see RFC 1542 Section 2.1).
+const DHCP_MIN_SIZE: usize = 300;
+
+/// BOOTP operation codes.
+const BOOTREQUEST: u8 = 1;
+const BOOTREPLY: u8 = 2;
+
+/// Hardware address type and length of Ethernet.
+const HTYPE_ETHERNET: u8 = 1;
+const HLEN_ETHERNET: u8 = 6;
+
+/// Flag that asks servers to broadcast their replies, since we cannot receive unicast datagrams until our address is
+/// configured.
+const FLAG_BROADCAST: u16 = 0x8000;
+
+/// DHCP options that we use (see RFC 2132).
+const OPTION_PAD: u8 = 0;
+const OPTION_SUBNET_MASK: u8 = 1;
+const OPTION_ROUTER: u8 = 3;
+const OPTION_DNS_SERVER: u8 = 6;
+const OPTION_REQUESTED_ADDR: u8 = 50;
+const OPTION_LEASE_TIME: u8 = 51;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_SERVER_ID: u8 = 54;
+const OPTION_PARAMETER_REQUEST_LIST: u8 = 55;
+const OPTION_RENEWAL_TIME: u8 = 58;
+const OPTION_REBINDING_TIME: u8 = 59;
+const OPTION_END: u8 = 255;
+
+/// Options that clients ask for.
+const PARAMETER_REQUEST_LIST: [u8; 5] = [
+    OPTION_SUBNET_MASK,
+    OPTION_ROUTER,
+    OPTION_DNS_SERVER,
+    OPTION_RENEWAL_TIME,
+    OPTION_REBINDING_TIME,
+];
+
+//==============================================================================
+// Enumerations
+//==============================================================================
+
+/// DHCP Message Type
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DhcpMessageType {
+    Discover = 1,
+    Offer = 2,
+    Request = 3,
+    Decline = 4,
+    Ack = 5,
+    Nak = 6,
+    Release = 7,
+}
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// DHCP Header
+///
+/// DHCP message, along with the options that we care about. Other options are ignored.
+#[derive(Clone, Debug)]
+pub struct DhcpHeader {
+    /// Type of the message.
+    pub msg_type: DhcpMessageType,
+    /// Transaction identifier, which matches replies with requests.
+    pub xid: u32,
+    /// Whether replies should be broadcast.
+    pub broadcast: bool,
+    /// Address of a client that is already configured.
+    pub ciaddr: Ipv4Addr,
+    /// Address that is given to the client.
+    pub yiaddr: Ipv4Addr,
+    /// Link address of the client.
+    pub chaddr: MacAddress,
+    /// Address that the client asks for.
+    pub requested_addr: Option<Ipv4Addr>,
+    /// Identifier of the server.
+    pub server_id: Option<Ipv4Addr>,
+    /// Duration of the lease (in seconds).
+    pub lease_time: Option<u32>,
+    /// Time after which the client renews its lease with the server that granted it (in seconds).
+    pub renewal_time: Option<u32>,
+    /// Time after which the client renews its lease with any server (in seconds).
+    pub rebinding_time: Option<u32>,
+    /// Network mask of the subnet.
+    pub subnet_mask: Option<Ipv4Addr>,
+    /// Routers on the subnet, in order of preference.
+    pub routers: Vec<Ipv4Addr>,
+    /// DNS servers, in order of preference.
+    pub dns_servers: Vec<Ipv4Addr>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for DHCP Message Types
+impl DhcpMessageType {
+    /// Converts an option value into a message type.
+    fn from_u8(value: u8) -> Result<Self, Fail> {
+        match value {
+            1 => Ok(DhcpMessageType::Discover),
+            2 => Ok(DhcpMessageType::Offer),
+            3 => Ok(DhcpMessageType::Request),
+            4 => Ok(DhcpMessageType::Decline),
+            5 => Ok(DhcpMessageType::Ack),
+            6 => Ok(DhcpMessageType::Nak),
+            7 => Ok(DhcpMessageType::Release),
+            _ => Err(Fail::new(ENOTSUP, "unsupported DHCP message type")),
+        }
+    }
+
+    /// Checks whether messages of the target type are sent by servers.
+    fn is_reply(&self) -> bool {
+        matches!(
+            self,
+            DhcpMessageType::Offer | DhcpMessageType::Ack | DhcpMessageType::Nak
+        )
+    }
+}
+
+/// Associate Functions for DHCP Headers
+impl DhcpHeader {
+    /// Creates a DHCP header without options.
+    pub fn new(msg_type: DhcpMessageType, xid: u32, chaddr: MacAddress) -> Self {
+        Self {
+            msg_type,
+            xid,
+            broadcast: false,
+            ciaddr: Ipv4Addr::UNSPECIFIED,
+            yiaddr: Ipv4Addr::UNSPECIFIED,
+            chaddr,
+            requested_addr: None,
+            server_id: None,
+            lease_time: None,
+            renewal_time: None,
+            rebinding_time: None,
+            subnet_mask: None,
+            routers: Vec::new(),
+            dns_servers: Vec::new(),
+        }
+    }
+
+    /// Returns the size of the target DHCP header.
+    pub fn size(&self) -> usize {
+        // Magic cookie and message type, along with the end option.
+        let mut size: usize = DHCP_FIXED_SIZE + 4 + 3 + 1;
+        size += self.requested_addr.map_or(0, |_| 6);
+        size += self.server_id.map_or(0, |_| 6);
+        size += self.lease_time.map_or(0, |_| 6);
+        size += self.renewal_time.map_or(0, |_| 6);
+        size += self.rebinding_time.map_or(0, |_| 6);
+        size += self.subnet_mask.map_or(0, |_| 6);
+        size += Self::addrs_option_size(&self.routers);
+        size += Self::addrs_option_size(&self.dns_servers);
+        if !self.msg_type.is_reply() {
+            size += 2 + PARAMETER_REQUEST_LIST.len();
+        }
+        size.max(DHCP_MIN_SIZE)
+    }
+
+    /// Returns the size of an option that carries a list of addresses.
+    fn addrs_option_size(addrs: &[Ipv4Addr]) -> usize {
+        match addrs.len() {
+            0 => 0,
+            n => 2 + 4 * n,
+        }
+    }
+
+    /// Parses a buffer into a DHCP header.
+    pub fn parse(buf: &[u8]) -> Result<Self, Fail> {
+        if buf.len() < DHCP_FIXED_SIZE + 4 {
+            return Err(Fail::new(EBADMSG, "DHCP message too small"));
+        }
+        if buf[1] != HTYPE_ETHERNET || buf[2] != HLEN_ETHERNET {
+            return Err(Fail::new(ENOTSUP, "unsupported hardware address type"));
+        }
+        if NetworkEndian::read_u32(&buf[DHCP_FIXED_SIZE..DHCP_FIXED_SIZE + 4]) != DHCP_MAGIC_COOKIE {
+            return Err(Fail::new(EBADMSG, "invalid DHCP magic cookie"));
+        }
+        let op: u8 = buf[0];
+        let mut hdr: DhcpHeader = Self::new(
+            DhcpMessageType::Discover,
+            NetworkEndian::read_u32(&buf[4..8]),
+            MacAddress::from_bytes(&buf[28..34]),
+        );
+        hdr.broadcast = NetworkEndian::read_u16(&buf[10..12]) & FLAG_BROADCAST != 0;
+        hdr.ciaddr = Ipv4Addr::from(NetworkEndian::read_u32(&buf[12..16]));
+        hdr.yiaddr = Ipv4Addr::from(NetworkEndian::read_u32(&buf[16..20]));
+
+        // Parse options.
+        let mut msg_type: Option<DhcpMessageType> = None;
+        let mut options: &[u8] = &buf[DHCP_FIXED_SIZE + 4..];
+        while let Some((&code, rest)) = options.split_first() {
+            match code {
+                OPTION_PAD => {
+                    options = rest;
+                    continue;
+                },
+                OPTION_END => break,
+                _ => (),
+            }
+            let (&len, rest) = rest
+                .split_first()
+                .ok_or_else(|| Fail::new(EBADMSG, "truncated DHCP option"))?;
+            if rest.len() < len as usize {
+                return Err(Fail::new(EBADMSG, "truncated DHCP option"));
+            }
+            let (value, rest) = rest.split_at(len as usize);
+            match (code, len) {
+                (OPTION_MESSAGE_TYPE, 1) => msg_type = Some(DhcpMessageType::from_u8(value[0])?),
+                (OPTION_SUBNET_MASK, 4) => hdr.subnet_mask = Some(Self::read_addr(value)),
+                (OPTION_REQUESTED_ADDR, 4) => hdr.requested_addr = Some(Self::read_addr(value)),
+                (OPTION_SERVER_ID, 4) => hdr.server_id = Some(Self::read_addr(value)),
+                (OPTION_LEASE_TIME, 4) => hdr.lease_time = Some(NetworkEndian::read_u32(value)),
+                (OPTION_RENEWAL_TIME, 4) => hdr.renewal_time = Some(NetworkEndian::read_u32(value)),
+                (OPTION_REBINDING_TIME, 4) => hdr.rebinding_time = Some(NetworkEndian::read_u32(value)),
+                (OPTION_ROUTER, _) if len % 4 == 0 => hdr.routers = value.chunks(4).map(Self::read_addr).collect(),
+                (OPTION_DNS_SERVER, _) if len % 4 == 0 => {
+                    hdr.dns_servers = value.chunks(4).map(Self::read_addr).collect()
+                },
+                _ => trace!("ignoring DHCP option {}", code),
+            }
+            options = rest;
+        }
+        hdr.msg_type = msg_type.ok_or_else(|| Fail::new(EBADMSG, "missing DHCP message type"))?;
+        let expected_op: u8 = if hdr.msg_type.is_reply() {
+            BOOTREPLY
+        } else {
+            BOOTREQUEST
+        };
+        if op != expected_op {
+            return Err(Fail::new(EBADMSG, "DHCP operation mismatch"));
+        }
+        Ok(hdr)
+    }
+
+    /// Serializes the target DHCP header.
+    pub fn serialize(&self, buf: &mut [u8]) {
+        let buf: &mut [u8] = &mut buf[..self.size()];
+        buf.fill(0);
+        buf[0] = if self.msg_type.is_reply() {
+            BOOTREPLY
+        } else {
+            BOOTREQUEST
+        };
+        buf[1] = HTYPE_ETHERNET;
+        buf[2] = HLEN_ETHERNET;
+        NetworkEndian::write_u32(&mut buf[4..8], self.xid);
+        if self.broadcast {
+            NetworkEndian::write_u16(&mut buf[10..12], FLAG_BROADCAST);
+        }
+        buf[12..16].copy_from_slice(&self.ciaddr.octets());
+        buf[16..20].copy_from_slice(&self.yiaddr.octets());
+        buf[28..34].copy_from_slice(self.chaddr.as_bytes());
+        NetworkEndian::write_u32(&mut buf[DHCP_FIXED_SIZE..DHCP_FIXED_SIZE + 4], DHCP_MAGIC_COOKIE);
+
+        // Write options.
+        let mut cur_pos: usize = DHCP_FIXED_SIZE + 4;
+        let mut write_option = |code: u8, value: &[u8]| {
+            buf[cur_pos] = code;
+            buf[cur_pos + 1] = value.len() as u8;
+            buf[cur_pos + 2..cur_pos + 2 + value.len()].copy_from_slice(value);
+            cur_pos += 2 + value.len();
+        };
+        write_option(OPTION_MESSAGE_TYPE, &[self.msg_type as u8]);
+        if let Some(addr) = self.requested_addr {
+            write_option(OPTION_REQUESTED_ADDR, &addr.octets());
+        }
+        if let Some(addr) = self.server_id {
+            write_option(OPTION_SERVER_ID, &addr.octets());
+        }
+        if let Some(time) = self.lease_time {
+            write_option(OPTION_LEASE_TIME, &time.to_be_bytes());
+        }
+        if let Some(time) = self.renewal_time {
+            write_option(OPTION_RENEWAL_TIME, &time.to_be_bytes());
+        }
+        if let Some(time) = self.rebinding_time {
+            write_option(OPTION_REBINDING_TIME, &time.to_be_bytes());
+        }
+        if let Some(mask) = self.subnet_mask {
+            write_option(OPTION_SUBNET_MASK, &mask.octets());
+        }
+        if !self.routers.is_empty() {
+            write_option(OPTION_ROUTER, &Self::addrs_to_bytes(&self.routers));
+        }
+        if !self.dns_servers.is_empty() {
+            write_option(OPTION_DNS_SERVER, &Self::addrs_to_bytes(&self.dns_servers));
+        }
+        if !self.msg_type.is_reply() {
+            write_option(OPTION_PARAMETER_REQUEST_LIST, &PARAMETER_REQUEST_LIST);
+        }
+        // The rest of the buffer is padding.
+        buf[cur_pos] = OPTION_END;
+    }
+
+    /// Reads an IPv4 address.
+    fn read_addr(buf: &[u8]) -> Ipv4Addr {
+        Ipv4Addr::from(NetworkEndian::read_u32(buf))
+    }
+
+    /// Concatenates IPv4 addresses.
+    fn addrs_to_bytes(addrs: &[Ipv4Addr]) -> Vec<u8> {
+        addrs.iter().flat_map(|addr| addr.octets()).collect()
+    }
+}
@@ -0,0 +1,10 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod client;
+mod datagram;
+
+#[cfg(test)]
+mod tests;
+
+pub use client::DhcpClient;
@@ -0,0 +1,298 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    inetstack::{
+        protocols::{
+            dhcp::datagram::{
+                DhcpHeader,
+                DhcpMessageType,
+                DHCP_CLIENT_PORT,
+                DHCP_SERVER_PORT,
+            },
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+            },
+            ip::IpProtocol,
+            ipv4::Ipv4Header,
+            udp::{
+                UdpDatagram,
+                UdpHeader,
+            },
+        },
+        test_helpers::{
+            self,
+            Engine,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            types::{
+                DhcpLease,
+                MacAddress,
+                Route,
+            },
+            PacketBuf,
+        },
+    },
+};
+use ::libc::EALREADY;
+use ::std::{
+    net::Ipv4Addr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Address that the server leases to Alice.
+const LEASED_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 100);
+
+/// Default gateway that the server hands out.
+const GATEWAY_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 254);
+
+/// Duration of leases (in seconds).
+const LEASE_TIME: u32 = 600;
+
+//==============================================================================
+// Helper Functions
+//==============================================================================
+
+/// Parses a frame that carries a DHCP message from Alice, checking that it is sent to the server `dst_ipv4_addr` from
+/// `src_ipv4_addr`.
+fn parse_dhcp_frame(
+    buf: DemiBuffer,
+    dst_link_addr: MacAddress,
+    src_ipv4_addr: Ipv4Addr,
+    dst_ipv4_addr: Ipv4Addr,
+) -> DhcpHeader {
+    let (eth2_hdr, eth2_payload): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(buf).unwrap();
+    assert_eq!(eth2_hdr.dst_addr(), dst_link_addr);
+    assert_eq!(eth2_hdr.src_addr(), test_helpers::ALICE_MAC);
+    let (ipv4_hdr, ipv4_payload): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(eth2_payload).unwrap();
+    assert_eq!(ipv4_hdr.get_protocol(), IpProtocol::UDP);
+    assert_eq!(ipv4_hdr.get_src_addr(), src_ipv4_addr);
+    assert_eq!(ipv4_hdr.get_dest_addr(), dst_ipv4_addr);
+    let (udp_hdr, udp_payload): (UdpHeader, DemiBuffer) = UdpHeader::parse(&ipv4_hdr, ipv4_payload, false).unwrap();
+    assert_eq!(udp_hdr.src_port(), DHCP_CLIENT_PORT);
+    assert_eq!(udp_hdr.dest_port(), DHCP_SERVER_PORT);
+    DhcpHeader::parse(&udp_payload[..]).unwrap()
+}
+
+/// Builds a reply of the given type from Bob, who acts as the DHCP server, to a request of Alice.
+fn new_reply(msg_type: DhcpMessageType, request: &DhcpHeader) -> DhcpHeader {
+    let mut reply: DhcpHeader = DhcpHeader::new(msg_type, request.xid, test_helpers::ALICE_MAC);
+    reply.server_id = Some(test_helpers::BOB_IPV4);
+    if msg_type != DhcpMessageType::Nak {
+        reply.yiaddr = LEASED_IPV4;
+        reply.lease_time = Some(LEASE_TIME);
+        reply.subnet_mask = Some(Ipv4Addr::new(255, 255, 255, 0));
+        reply.routers = vec![GATEWAY_IPV4];
+        reply.dns_servers = vec![test_helpers::BOB_IPV4];
+    }
+    reply
+}
+
+/// Forges a frame that carries a reply from Bob to `dst_ipv4_addr`.
+fn forge_reply(reply: &DhcpHeader, dst_ipv4_addr: Ipv4Addr) -> DemiBuffer {
+    let mut bytes: Vec<u8> = vec![0; reply.size()];
+    reply.serialize(&mut bytes);
+    let datagram: UdpDatagram = UdpDatagram::new(
+        Ethernet2Header::new(MacAddress::broadcast(), test_helpers::BOB_MAC, EtherType2::Ipv4),
+        Ipv4Header::new(test_helpers::BOB_IPV4, dst_ipv4_addr, IpProtocol::UDP),
+        UdpHeader::new(DHCP_SERVER_PORT, DHCP_CLIENT_PORT),
+        DemiBuffer::from_slice(&bytes).unwrap(),
+        false,
+    );
+    let header_size: usize = datagram.header_size();
+    let body_size: usize = datagram.body_size();
+    let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
+    datagram.write_header(&mut buf[..header_size]);
+    if let Some(body) = datagram.take_body() {
+        buf[header_size..].copy_from_slice(&body[..]);
+    }
+    buf
+}
+
+/// Lets Alice acquire a lease from Bob, and returns the acknowledgement.
+fn acquire_lease(alice: &mut Engine) -> DhcpHeader {
+    alice.ipv4.dhcp.start().unwrap();
+    alice.rt.poll_scheduler();
+
+    // Alice looks for servers.
+    let discover: DhcpHeader = parse_dhcp_frame(
+        alice.rt.pop_frame(),
+        MacAddress::broadcast(),
+        Ipv4Addr::UNSPECIFIED,
+        Ipv4Addr::BROADCAST,
+    );
+    assert_eq!(discover.msg_type, DhcpMessageType::Discover);
+    assert!(discover.broadcast);
+
+    // Alice asks for the address that Bob offers.
+    let offer: DhcpHeader = new_reply(DhcpMessageType::Offer, &discover);
+    alice.receive(forge_reply(&offer, Ipv4Addr::BROADCAST)).unwrap();
+    alice.rt.poll_scheduler();
+    let request: DhcpHeader = parse_dhcp_frame(
+        alice.rt.pop_frame(),
+        MacAddress::broadcast(),
+        Ipv4Addr::UNSPECIFIED,
+        Ipv4Addr::BROADCAST,
+    );
+    assert_eq!(request.msg_type, DhcpMessageType::Request);
+    assert_eq!(request.xid, discover.xid);
+    assert_eq!(request.requested_addr, Some(LEASED_IPV4));
+    assert_eq!(request.server_id, Some(test_helpers::BOB_IPV4));
+
+    // Bob acknowledges.
+    let ack: DhcpHeader = new_reply(DhcpMessageType::Ack, &request);
+    alice.receive(forge_reply(&ack, Ipv4Addr::BROADCAST)).unwrap();
+    alice.rt.poll_scheduler();
+    ack
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+/// Tests that a lease configures the address and routes of the interface, and that losing it removes them.
+#[test]
+fn dhcp_lease_lifecycle() {
+    let mut now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    acquire_lease(&mut alice);
+
+    let subnet: Route = Route::new(Ipv4Addr::new(192, 168, 1, 0), 24, None).unwrap();
+    let default: Route = Route::new(Ipv4Addr::UNSPECIFIED, 0, Some(GATEWAY_IPV4)).unwrap();
+    assert_eq!(
+        alice.ipv4.dhcp.lease(),
+        Some(DhcpLease {
+            ipv4_addr: LEASED_IPV4,
+            prefix_len: 24,
+            gateway: Some(GATEWAY_IPV4),
+            dns_servers: vec![test_helpers::BOB_IPV4],
+            server: test_helpers::BOB_IPV4,
+            lease_time: Duration::from_secs(LEASE_TIME as u64),
+        })
+    );
+    assert_eq!(alice.arp.local_addrs(), vec![LEASED_IPV4]);
+    assert!(alice.arp.routes().contains(&subnet));
+    assert!(alice.arp.routes().contains(&default));
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+
+    // Halfway through the lease, Alice asks Bob to extend it.
+    now += Duration::from_secs((LEASE_TIME / 2) as u64);
+    alice.clock.advance_clock(now);
+    alice.rt.poll_scheduler();
+    let renew: DhcpHeader = parse_dhcp_frame(
+        alice.rt.pop_frame(),
+        test_helpers::BOB_MAC,
+        LEASED_IPV4,
+        test_helpers::BOB_IPV4,
+    );
+    assert_eq!(renew.msg_type, DhcpMessageType::Request);
+    assert_eq!(renew.ciaddr, LEASED_IPV4);
+    assert_eq!(renew.requested_addr, None);
+
+    // Bob refuses, so Alice drops the lease and looks for servers again.
+    let nak: DhcpHeader = new_reply(DhcpMessageType::Nak, &renew);
+    alice.receive(forge_reply(&nak, LEASED_IPV4)).unwrap();
+    alice.rt.poll_scheduler();
+    assert_eq!(alice.ipv4.dhcp.lease(), None);
+    assert_eq!(alice.arp.local_addrs(), vec![Ipv4Addr::UNSPECIFIED]);
+    assert!(!alice.arp.routes().contains(&subnet));
+    assert!(!alice.arp.routes().contains(&default));
+    let discover: DhcpHeader = parse_dhcp_frame(
+        alice.rt.pop_frame(),
+        MacAddress::broadcast(),
+        Ipv4Addr::UNSPECIFIED,
+        Ipv4Addr::BROADCAST,
+    );
+    assert_eq!(discover.msg_type, DhcpMessageType::Discover);
+}
+
+/// Tests that a lease neither replaces a route that was configured by hand, nor removes it once lost.
+#[test]
+fn dhcp_keeps_configured_routes() {
+    let mut now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let default: Route = Route::new(Ipv4Addr::UNSPECIFIED, 0, Some(test_helpers::BOB_IPV4)).unwrap();
+    alice.arp.add_route(default);
+    acquire_lease(&mut alice);
+    let subnet: Route = Route::new(Ipv4Addr::new(192, 168, 1, 0), 24, None).unwrap();
+    assert!(alice.arp.routes().contains(&subnet));
+    assert!(alice.arp.routes().contains(&default));
+
+    // Bob refuses to extend the lease, so Alice drops it.
+    now += Duration::from_secs((LEASE_TIME / 2) as u64);
+    alice.clock.advance_clock(now);
+    alice.rt.poll_scheduler();
+    let renew: DhcpHeader = parse_dhcp_frame(
+        alice.rt.pop_frame(),
+        test_helpers::BOB_MAC,
+        LEASED_IPV4,
+        test_helpers::BOB_IPV4,
+    );
+    let nak: DhcpHeader = new_reply(DhcpMessageType::Nak, &renew);
+    alice.receive(forge_reply(&nak, LEASED_IPV4)).unwrap();
+    alice.rt.poll_scheduler();
+    assert_eq!(alice.ipv4.dhcp.lease(), None);
+    assert!(!alice.arp.routes().contains(&subnet));
+    assert!(alice.arp.routes().contains(&default));
+}
+
+/// Tests that messages are retransmitted with exponential backoff, and that stray replies are ignored.
+#[test]
+fn dhcp_retransmit_discover() {
+    let mut now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    alice.ipv4.dhcp.start().unwrap();
+    alice.rt.poll_scheduler();
+    let discover: DhcpHeader = parse_dhcp_frame(
+        alice.rt.pop_frame(),
+        MacAddress::broadcast(),
+        Ipv4Addr::UNSPECIFIED,
+        Ipv4Addr::BROADCAST,
+    );
+
+    // An offer for another transaction is ignored.
+    let mut offer: DhcpHeader = new_reply(DhcpMessageType::Offer, &discover);
+    offer.xid = discover.xid.wrapping_add(1);
+    alice.receive(forge_reply(&offer, Ipv4Addr::BROADCAST)).unwrap();
+    alice.rt.poll_scheduler();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+
+    // Alice waits for 4 seconds, then 8 seconds.
+    for timeout in [4, 8] {
+        now += Duration::from_secs(timeout - 1);
+        alice.clock.advance_clock(now);
+        alice.rt.poll_scheduler();
+        assert!(alice.rt.pop_frame_unchecked().is_none());
+        now += Duration::from_secs(1);
+        alice.clock.advance_clock(now);
+        alice.rt.poll_scheduler();
+        let retransmission: DhcpHeader = parse_dhcp_frame(
+            alice.rt.pop_frame(),
+            MacAddress::broadcast(),
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::BROADCAST,
+        );
+        assert_eq!(retransmission.msg_type, DhcpMessageType::Discover);
+        assert_eq!(retransmission.xid, discover.xid);
+    }
+}
+
+/// Tests that the DHCP client cannot be started twice.
+#[test]
+fn dhcp_start_twice() {
+    let now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    acquire_lease(&mut alice);
+    assert_eq!(alice.ipv4.dhcp.start().unwrap_err().errno, EALREADY);
+}
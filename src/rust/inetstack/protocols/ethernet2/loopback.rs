@@ -0,0 +1,113 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    memory::DemiBuffer,
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        types::{
+            LinkState,
+            MacAddress,
+        },
+        NetworkRuntime,
+        PacketBuf,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Loopback
+///
+/// Network runtime that hands frames sent to our own link address back to us, instead of sending them to the
+/// underlying runtime. Since the ARP peer resolves our addresses (and loopback addresses) to our link address, this
+/// carries traffic between sockets of the same stack, e.g. a client that connects to a server on `127.0.0.1` or on
+/// one of our addresses. Looped frames are received before frames from the underlying runtime.
+pub struct Loopback {
+    /// Underlying Runtime
+    rt: Rc<dyn NetworkRuntime>,
+    /// Our link address.
+    local_link_addr: MacAddress,
+    /// Frames that we sent to ourselves, and that are yet to be received.
+    frames: RefCell<VecDeque<DemiBuffer>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Loopback
+impl Loopback {
+    /// Creates a loopback on top of an underlying runtime.
+    pub fn new(rt: Rc<dyn NetworkRuntime>, local_link_addr: MacAddress) -> Self {
+        Self {
+            rt,
+            local_link_addr,
+            frames: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Serializes a packet into a frame.
+    fn serialize(pkt: Box<dyn PacketBuf>) -> DemiBuffer {
+        let header_size: usize = pkt.header_size();
+        let body_size: usize = pkt.body_size();
+        let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
+        pkt.write_header(&mut buf[..header_size]);
+        if let Some(body) = pkt.take_body() {
+            buf[header_size..].copy_from_slice(&body[..]);
+        }
+        buf
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Network Runtime Trait Implementation for Loopback
+impl NetworkRuntime for Loopback {
+    /// Transmits a single [PacketBuf], looping it back if it is sent to us.
+    fn transmit(&self, pkt: Box<dyn PacketBuf>) {
+        if pkt.dst_link_addr() == self.local_link_addr {
+            self.frames.borrow_mut().push_back(Self::serialize(pkt));
+        } else {
+            self.rt.transmit(pkt);
+        }
+    }
+
+    /// Receives a batch of [DemiBuffer], starting with looped frames.
+    fn receive(&self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
+        let mut frames = self.frames.borrow_mut();
+        if frames.is_empty() {
+            return self.rt.receive();
+        }
+        let count: usize = frames.len().min(RECEIVE_BATCH_SIZE);
+        frames.drain(..count).collect()
+    }
+
+    /// Gets the state of the link of the underlying runtime.
+    fn link_state(&self) -> LinkState {
+        self.rt.link_state()
+    }
+
+    /// Gets the hardware timestamp of a frame from the underlying runtime.
+    fn rx_timestamp(&self, buf: &DemiBuffer) -> Option<u64> {
+        self.rt.rx_timestamp(buf)
+    }
+
+    /// Reads the clock of the network interface of the underlying runtime.
+    fn tx_timestamp(&self) -> Option<u64> {
+        self.rt.tx_timestamp()
+    }
+}
@@ -2,6 +2,7 @@
 // Licensed under the MIT license.
 
 mod frame;
+mod loopback;
 mod protocol;
 
 pub use self::{
@@ -10,5 +11,6 @@ pub use self::{
         ETHERNET2_HEADER_SIZE,
         MIN_PAYLOAD_SIZE,
     },
+    loopback::Loopback,
     protocol::EtherType2,
 };
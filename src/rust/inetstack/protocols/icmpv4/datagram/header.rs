@@ -63,14 +63,14 @@ impl Icmpv4Header {
         ))
     }
 
-    pub fn serialize(&self, buf: &mut [u8]) {
+    pub fn serialize(&self, buf: &mut [u8], body: &[u8]) {
         let buf: &mut [u8; ICMPV4_HEADER_SIZE] = (&mut buf[..ICMPV4_HEADER_SIZE]).try_into().unwrap();
         let (type_byte, rest_of_header) = self.protocol.serialize();
         buf[0] = type_byte;
         buf[1] = self.code;
         // Skip the checksum for now.
         buf[4..8].copy_from_slice(&rest_of_header[..]);
-        let checksum = Self::checksum(buf, &[]);
+        let checksum = Self::checksum(buf, body);
         NetworkEndian::write_u16(&mut buf[2..4], checksum);
     }
 
@@ -99,4 +99,8 @@ impl Icmpv4Header {
     pub fn get_protocol(&self) -> Icmpv4Type2 {
         self.protocol
     }
+
+    pub fn get_code(&self) -> u8 {
+        self.code
+    }
 }
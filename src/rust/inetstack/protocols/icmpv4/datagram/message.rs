@@ -9,7 +9,10 @@ use crate::{
     },
     runtime::{
         memory::DemiBuffer,
-        network::PacketBuf,
+        network::{
+            types::MacAddress,
+            PacketBuf,
+        },
     },
 };
 
@@ -18,6 +21,7 @@ pub struct Icmpv4Message {
     ethernet2_hdr: Ethernet2Header,
     ipv4_hdr: Ipv4Header,
     icmpv4_hdr: Icmpv4Header,
+    data: Option<DemiBuffer>,
 }
 
 /// Associated Functions for Icmpv4Message
@@ -28,8 +32,15 @@ impl Icmpv4Message {
             ethernet2_hdr,
             ipv4_hdr,
             icmpv4_hdr,
+            data: None,
         }
     }
+
+    /// Attaches a payload to the target ICMP message.
+    pub fn with_data(mut self, data: DemiBuffer) -> Self {
+        self.data = Some(data);
+        self
+    }
 }
 
 /// PacketBuf Trait Implementation for Icmpv4Message
@@ -39,7 +50,7 @@ impl PacketBuf for Icmpv4Message {
     }
 
     fn body_size(&self) -> usize {
-        0
+        self.data.as_ref().map_or(0, |data| data.len())
     }
 
     fn write_header(&self, buf: &mut [u8]) {
@@ -52,16 +63,21 @@ impl PacketBuf for Icmpv4Message {
             .serialize(&mut buf[cur_pos..(cur_pos + eth_hdr_size)]);
         cur_pos += eth_hdr_size;
 
-        let ipv4_payload_len = icmpv4_hdr_size;
+        let ipv4_payload_len = icmpv4_hdr_size + self.body_size();
         self.ipv4_hdr
             .serialize(&mut buf[cur_pos..(cur_pos + ipv4_hdr_size)], ipv4_payload_len);
         cur_pos += ipv4_hdr_size;
 
+        let body: &[u8] = self.data.as_ref().map_or(&[], |data| &data[..]);
         self.icmpv4_hdr
-            .serialize(&mut buf[cur_pos..(cur_pos + icmpv4_hdr_size)]);
+            .serialize(&mut buf[cur_pos..(cur_pos + icmpv4_hdr_size)], body);
     }
 
     fn take_body(&self) -> Option<DemiBuffer> {
-        None
+        self.data.clone()
+    }
+
+    fn dst_link_addr(&self) -> MacAddress {
+        self.ethernet2_hdr.dst_addr()
     }
 }
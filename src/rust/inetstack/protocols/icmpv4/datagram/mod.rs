@@ -7,6 +7,11 @@ mod protocol;
 
 pub use header::Icmpv4Header;
 pub use message::Icmpv4Message;
-pub use protocol::Icmpv4Type2;
+pub use protocol::{
+    Icmpv4Type2,
+    ICMPV4_CODE_FRAGMENTATION_NEEDED,
+    ICMPV4_CODE_PORT_UNREACHABLE,
+    ICMPV4_CODE_PROTOCOL_UNREACHABLE,
+};
 
 pub use self::header::ICMPV4_HEADER_SIZE;
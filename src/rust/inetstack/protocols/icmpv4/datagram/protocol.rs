@@ -8,6 +8,19 @@ use ::byteorder::{
 };
 use ::libc::EBADMSG;
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Destination Unreachable code: the destination host does not support the transport protocol.
+pub const ICMPV4_CODE_PROTOCOL_UNREACHABLE: u8 = 2;
+
+/// Destination Unreachable code: the destination transport protocol has no listener on the port.
+pub const ICMPV4_CODE_PORT_UNREACHABLE: u8 = 3;
+
+/// Destination Unreachable code: fragmentation needed and Don't Fragment was set.
+pub const ICMPV4_CODE_FRAGMENTATION_NEEDED: u8 = 4;
+
 //==============================================================================
 // Icmpv4Type2
 //==============================================================================
@@ -15,7 +28,7 @@ use ::libc::EBADMSG;
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Icmpv4Type2 {
     EchoReply { id: u16, seq_num: u16 },
-    DestinationUnreachable,
+    DestinationUnreachable { next_hop_mtu: u16 },
     SourceQuench,
     RedirectMessage,
     EchoRequest { id: u16, seq_num: u16 },
@@ -36,7 +49,11 @@ impl Icmpv4Type2 {
                 let seq_num = NetworkEndian::read_u16(&rest_of_header[2..4]);
                 Ok(EchoReply { id, seq_num })
             },
-            3 => Ok(DestinationUnreachable),
+            3 => {
+                // The MTU of the next hop is only meaningful for fragmentation needed messages (see RFC 1191).
+                let next_hop_mtu = NetworkEndian::read_u16(&rest_of_header[2..4]);
+                Ok(DestinationUnreachable { next_hop_mtu })
+            },
             4 => Ok(SourceQuench),
             5 => Ok(RedirectMessage),
             8 => {
@@ -63,7 +80,10 @@ impl Icmpv4Type2 {
                 let [seq1, seq2] = seq_num.to_be_bytes();
                 (0, [id1, id2, seq1, seq2])
             },
-            DestinationUnreachable => (3, zero),
+            DestinationUnreachable { next_hop_mtu } => {
+                let [mtu1, mtu2] = next_hop_mtu.to_be_bytes();
+                (3, [0, 0, mtu1, mtu2])
+            },
             SourceQuench => (4, zero),
             RedirectMessage => (5, zero),
             EchoRequest { id, seq_num } => {
@@ -7,4 +7,15 @@ mod peer;
 #[cfg(test)]
 mod tests;
 
-pub use peer::Icmpv4Peer;
+pub use datagram::{
+    Icmpv4Header,
+    Icmpv4Message,
+    Icmpv4Type2,
+    ICMPV4_CODE_FRAGMENTATION_NEEDED,
+    ICMPV4_CODE_PORT_UNREACHABLE,
+    ICMPV4_CODE_PROTOCOL_UNREACHABLE,
+};
+pub use peer::{
+    Icmpv4Error,
+    Icmpv4Peer,
+};
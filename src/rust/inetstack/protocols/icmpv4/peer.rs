@@ -17,9 +17,16 @@ use crate::{
                 Icmpv4Header,
                 Icmpv4Message,
                 Icmpv4Type2,
+                ICMPV4_CODE_FRAGMENTATION_NEEDED,
+                ICMPV4_CODE_PORT_UNREACHABLE,
+                ICMPV4_CODE_PROTOCOL_UNREACHABLE,
             },
             ip::IpProtocol,
-            ipv4::Ipv4Header,
+            ipv4::{
+                Ipv4Header,
+                IPV4_HEADER_DEFAULT_SIZE,
+                IPV4_PMTU_MIN,
+            },
         },
     },
     runtime::{
@@ -32,6 +39,7 @@ use crate::{
         timer::TimerRc,
     },
     scheduler::{
+        Priority,
         Scheduler,
         SchedulerHandle,
     },
@@ -58,15 +66,50 @@ use ::rand::{
 };
 use ::std::{
     cell::RefCell,
+    cmp,
     collections::HashMap,
     future::Future,
-    net::Ipv4Addr,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
     num::Wrapping,
     process,
     rc::Rc,
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
-use libc::EAGAIN;
+use libc::{
+    EAGAIN,
+    ECONNREFUSED,
+    EHOSTUNREACH,
+    EMSGSIZE,
+    ENETUNREACH,
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Number of ICMP error messages that we send per second, in the long run. This matches the default of Linux.
+const ICMPV4_ERROR_RATE: u32 = 1000;
+
+/// Number of ICMP error messages that we send back to back, before being held to [ICMPV4_ERROR_RATE].
+const ICMPV4_ERROR_BURST: u32 = 50;
+
+/// Number of bytes of the offending datagram that we quote in an error message, past its IPv4 header (see RFC 792).
+const ICMPV4_ERROR_QUOTE_SIZE: usize = 8;
+
+/// Version field of a quoted IPv4 header.
+const ICMPV4_QUOTE_IPV4_VERSION: u8 = 4;
+
+/// Don't fragment flag of a quoted IPv4 header, in its flags and fragment offset field.
+const ICMPV4_QUOTE_FLAG_DF: u16 = 0x4000;
+
+/// Mask of the fragment offset of a quoted IPv4 header, in its flags and fragment offset field.
+const ICMPV4_QUOTE_FRAGMENT_OFFSET_MASK: u16 = 0x1fff;
 
 //==============================================================================
 // ReqQueue
@@ -93,6 +136,69 @@ impl ReqQueue {
     }
 }
 
+//==============================================================================
+// Icmpv4Error
+//==============================================================================
+
+/// Error that a remote host or router reported about a datagram that we sent.
+#[derive(Debug)]
+pub struct Icmpv4Error {
+    /// Transport protocol of the offending datagram.
+    pub protocol: IpProtocol,
+    /// Local endpoint of the offending datagram.
+    pub local: SocketAddrV4,
+    /// Remote endpoint of the offending datagram.
+    pub remote: SocketAddrV4,
+    /// Bytes 4 to 8 of the transport header of the offending datagram, which hold the sequence number of a TCP
+    /// segment.
+    pub quoted_seq_num: u32,
+    /// Error to report on the affected socket.
+    pub errno: i32,
+    /// Path MTU towards the remote endpoint, if the offending datagram did not fit in the MTU of some hop.
+    pub path_mtu: Option<usize>,
+}
+
+//==============================================================================
+// ErrorRateLimiter
+//==============================================================================
+
+/// Token bucket that bounds the rate at which we send ICMP error messages, so that a flood of offending datagrams
+/// does not turn into a flood of errors (see RFC 1812, section 4.3.2.8).
+struct ErrorRateLimiter {
+    /// Number of messages that may be sent right away.
+    tokens: u32,
+    /// Last time at which tokens were added to the bucket.
+    last_refill: Instant,
+}
+
+/// Associate Implementation for ErrorRateLimiter
+impl ErrorRateLimiter {
+    /// Creates a full bucket.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            tokens: ICMPV4_ERROR_BURST,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token out of the bucket, if there is any left.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed: Duration = now.saturating_duration_since(self.last_refill);
+        let refill: u32 = (elapsed.as_secs_f64() * ICMPV4_ERROR_RATE as f64) as u32;
+        if refill > 0 {
+            self.tokens = cmp::min(self.tokens.saturating_add(refill), ICMPV4_ERROR_BURST);
+            self.last_refill = now;
+        }
+        match self.tokens {
+            0 => false,
+            _ => {
+                self.tokens -= 1;
+                true
+            },
+        }
+    }
+}
+
 //==============================================================================
 // Icmpv4Peer
 //==============================================================================
@@ -120,7 +226,10 @@ pub struct Icmpv4Peer {
     arp: ArpPeer,
 
     /// Transmitter
-    tx: mpsc::UnboundedSender<(Ipv4Addr, u16, u16)>,
+    tx: mpsc::UnboundedSender<(Ipv4Addr, Ipv4Addr, Icmpv4Header, Option<DemiBuffer>)>,
+
+    /// Rate Limiter for Error Messages
+    error_limiter: ErrorRateLimiter,
 
     /// Queue of Requests
     requests: Rc<RefCell<ReqQueue>>,
@@ -150,16 +259,18 @@ impl Icmpv4Peer {
         let (tx, rx) = mpsc::unbounded();
         let requests = ReqQueue::new();
         let rng: Rc<RefCell<SmallRng>> = Rc::new(RefCell::new(SmallRng::from_seed(rng_seed)));
-        let future = Self::background(rt.clone(), local_link_addr, local_ipv4_addr, arp.clone(), rx);
-        let handle: SchedulerHandle = match scheduler.insert(FutureOperation::Background(future.boxed_local())) {
-            Some(handle) => handle,
-            None => {
-                return Err(Fail::new(
-                    EAGAIN,
-                    "failed to schedule background co-routine for ICMPv4 module",
-                ))
-            },
-        };
+        let error_limiter: ErrorRateLimiter = ErrorRateLimiter::new(clock.now());
+        let future = Self::background(rt.clone(), local_link_addr, arp.clone(), rx);
+        let handle: SchedulerHandle =
+            match scheduler.insert(FutureOperation::Background(future.boxed_local()), Priority::Low) {
+                Some(handle) => handle,
+                None => {
+                    return Err(Fail::new(
+                        EAGAIN,
+                        "failed to schedule background co-routine for ICMPv4 module",
+                    ))
+                },
+            };
         Ok(Icmpv4Peer {
             rt,
             clock,
@@ -167,6 +278,7 @@ impl Icmpv4Peer {
             local_ipv4_addr,
             arp,
             tx,
+            error_limiter,
             requests: Rc::new(RefCell::new(requests)),
             seq: Wrapping(0),
             rng,
@@ -174,43 +286,51 @@ impl Icmpv4Peer {
         })
     }
 
-    /// Background task for replying to ICMP messages.
+    /// Background task for sending replies to ICMP messages, as well as error messages.
     async fn background(
         rt: Rc<dyn NetworkRuntime>,
         local_link_addr: MacAddress,
-        local_ipv4_addr: Ipv4Addr,
         arp: ArpPeer,
-        mut rx: mpsc::UnboundedReceiver<(Ipv4Addr, u16, u16)>,
+        mut rx: mpsc::UnboundedReceiver<(Ipv4Addr, Ipv4Addr, Icmpv4Header, Option<DemiBuffer>)>,
     ) {
-        // Reply requests.
-        while let Some((dst_ipv4_addr, id, seq_num)) = rx.next().await {
+        while let Some((src_ipv4_addr, dst_ipv4_addr, icmpv4_hdr, data)) = rx.next().await {
             debug!("initiating ARP query");
             let dst_link_addr: MacAddress = match arp.query(dst_ipv4_addr).await {
                 Ok(dst_link_addr) => dst_link_addr,
                 Err(e) => {
-                    warn!("reply_to_ping({}, {}, {}) failed: {:?}", dst_ipv4_addr, id, seq_num, e);
+                    warn!("failed to send {:?} to {}: {:?}", icmpv4_hdr, dst_ipv4_addr, e);
                     continue;
                 },
             };
             debug!("ARP query complete ({} -> {})", dst_ipv4_addr, dst_link_addr);
-            debug!("reply ping ({}, {}, {})", dst_ipv4_addr, id, seq_num);
-            // Send reply message.
-            rt.transmit(Box::new(Icmpv4Message::new(
+            debug!("send {:?} to {}", icmpv4_hdr, dst_ipv4_addr);
+            let msg: Icmpv4Message = Icmpv4Message::new(
                 Ethernet2Header::new(dst_link_addr, local_link_addr, EtherType2::Ipv4),
-                Ipv4Header::new(local_ipv4_addr, dst_ipv4_addr, IpProtocol::ICMPv4),
-                Icmpv4Header::new(Icmpv4Type2::EchoReply { id, seq_num }, 0),
-            )));
+                Ipv4Header::new(src_ipv4_addr, dst_ipv4_addr, IpProtocol::ICMPv4),
+                icmpv4_hdr,
+            );
+            match data {
+                Some(data) => rt.transmit(Box::new(msg.with_data(data))),
+                None => rt.transmit(Box::new(msg)),
+            }
         }
     }
 
-    /// Parses and handles a ICMP message.
-    pub fn receive(&mut self, ipv4_header: &Ipv4Header, buf: DemiBuffer) -> Result<(), Fail> {
-        let (icmpv4_hdr, _) = Icmpv4Header::parse(buf)?;
+    /// Parses and handles a ICMP message. Errors about datagrams that we sent are returned, so that they get reported
+    /// on the affected socket.
+    pub fn receive(&mut self, ipv4_header: &Ipv4Header, buf: DemiBuffer) -> Result<Option<Icmpv4Error>, Fail> {
+        let (icmpv4_hdr, payload) = Icmpv4Header::parse(buf)?;
         debug!("ICMPv4 received {:?}", icmpv4_hdr);
         match icmpv4_hdr.get_protocol() {
             Icmpv4Type2::EchoRequest { id, seq_num } => {
+                let reply: Icmpv4Header = Icmpv4Header::new(Icmpv4Type2::EchoReply { id, seq_num }, 0);
+                // Reply from the address that the request was sent to, unless it was broadcast.
+                let src_addr: Ipv4Addr = match self.arp.is_local_addr(ipv4_header.get_dest_addr()) {
+                    true => ipv4_header.get_dest_addr(),
+                    false => self.arp.select_source(ipv4_header.get_src_addr()),
+                };
                 self.tx
-                    .unbounded_send((ipv4_header.get_src_addr(), id, seq_num))
+                    .unbounded_send((src_addr, ipv4_header.get_src_addr(), reply, None))
                     .unwrap();
             },
             Icmpv4Type2::EchoReply { id, seq_num } => {
@@ -218,11 +338,146 @@ impl Icmpv4Peer {
                     let _ = tx.send(());
                 }
             },
+            Icmpv4Type2::DestinationUnreachable { next_hop_mtu }
+                if icmpv4_hdr.get_code() == ICMPV4_CODE_FRAGMENTATION_NEEDED =>
+            {
+                return Ok(self.fragmentation_needed(next_hop_mtu, &payload[..]));
+            },
+            Icmpv4Type2::DestinationUnreachable { .. } => {
+                let errno: i32 = match icmpv4_hdr.get_code() {
+                    0 => ENETUNREACH,
+                    ICMPV4_CODE_PROTOCOL_UNREACHABLE | ICMPV4_CODE_PORT_UNREACHABLE => ECONNREFUSED,
+                    _ => EHOSTUNREACH,
+                };
+                return Ok(self.parse_error(&payload[..], errno));
+            },
+            Icmpv4Type2::TimeExceeded => return Ok(self.parse_error(&payload[..], EHOSTUNREACH)),
             _ => {
                 warn!("Unsupported ICMPv4 message: {:?}", icmpv4_hdr);
             },
         }
-        Ok(())
+        Ok(None)
+    }
+
+    /// Parses the start of a datagram that we sent, as quoted by an error message (see RFC 792). The quote is only
+    /// trusted if it holds a well-formed IPv4 header, followed by the start of a TCP or UDP header, that may belong to
+    /// one of our sockets. The affected socket, if any, is then looked up by the caller.
+    fn parse_error(&self, payload: &[u8], errno: i32) -> Option<Icmpv4Error> {
+        if payload.len() < IPV4_HEADER_DEFAULT_SIZE {
+            warn!("ICMPv4 error message is too small");
+            return None;
+        }
+        if payload[0] >> 4 != ICMPV4_QUOTE_IPV4_VERSION {
+            warn!("ICMPv4 error message does not quote an IPv4 datagram");
+            return None;
+        }
+        let header_len: usize = ((payload[0] & 0xf) as usize) * 4;
+        if header_len < IPV4_HEADER_DEFAULT_SIZE || payload.len() < header_len + ICMPV4_ERROR_QUOTE_SIZE {
+            warn!("ICMPv4 error message does not quote enough of the datagram");
+            return None;
+        }
+        let total_len: usize = NetworkEndian::read_u16(&payload[2..4]) as usize;
+        if total_len < header_len + ICMPV4_ERROR_QUOTE_SIZE {
+            warn!("ICMPv4 error message quotes a datagram that is too small");
+            return None;
+        }
+        // Only the first fragment of a datagram carries its transport header.
+        if NetworkEndian::read_u16(&payload[6..8]) & ICMPV4_QUOTE_FRAGMENT_OFFSET_MASK != 0 {
+            warn!("ICMPv4 error message quotes a fragment without transport header");
+            return None;
+        }
+        let src_addr: Ipv4Addr = Ipv4Addr::from(NetworkEndian::read_u32(&payload[12..16]));
+        let dst_addr: Ipv4Addr = Ipv4Addr::from(NetworkEndian::read_u32(&payload[16..20]));
+        if !self.arp.is_local_addr(src_addr) {
+            warn!("ICMPv4 error message for a datagram that we did not send");
+            return None;
+        }
+        if dst_addr.is_unspecified() || dst_addr.is_broadcast() || dst_addr.is_multicast() {
+            warn!("ICMPv4 error message for a datagram that was not sent to a single host");
+            return None;
+        }
+        let protocol: IpProtocol = match IpProtocol::try_from(payload[9]) {
+            Ok(protocol @ (IpProtocol::TCP | IpProtocol::UDP)) => protocol,
+            _ => {
+                debug!("ignoring ICMPv4 error message for protocol {:?}", payload[9]);
+                return None;
+            },
+        };
+        let quote: &[u8] = &payload[header_len..];
+        let local_port: u16 = NetworkEndian::read_u16(&quote[0..2]);
+        let remote_port: u16 = NetworkEndian::read_u16(&quote[2..4]);
+        if local_port == 0 || remote_port == 0 {
+            warn!("ICMPv4 error message quotes an invalid transport header");
+            return None;
+        }
+        Some(Icmpv4Error {
+            protocol,
+            local: SocketAddrV4::new(src_addr, local_port),
+            remote: SocketAddrV4::new(dst_addr, remote_port),
+            quoted_seq_num: NetworkEndian::read_u32(&quote[4..8]),
+            errno,
+            path_mtu: None,
+        })
+    }
+
+    /// Parses a fragmentation needed message, which carries the header of a datagram that we sent and that did not
+    /// fit in the MTU of some hop along the path (see RFC 1191). The path MTU is only updated once the affected socket
+    /// confirms that it sent the quoted datagram.
+    fn fragmentation_needed(&self, next_hop_mtu: u16, payload: &[u8]) -> Option<Icmpv4Error> {
+        let mut error: Icmpv4Error = self.parse_error(payload, EMSGSIZE)?;
+
+        // Routers only drop datagrams that may not be fragmented, and only if they are larger than the MTU.
+        let total_len: usize = NetworkEndian::read_u16(&payload[2..4]) as usize;
+        if NetworkEndian::read_u16(&payload[6..8]) & ICMPV4_QUOTE_FLAG_DF == 0 {
+            warn!("fragmentation needed message for a datagram that may be fragmented");
+            return None;
+        }
+        if next_hop_mtu as usize >= total_len {
+            warn!("fragmentation needed message for a datagram that fits in the MTU");
+            return None;
+        }
+
+        // Routers that predate RFC 1191 do not report their MTU, so fall back to the smallest one.
+        error.path_mtu = Some(match next_hop_mtu {
+            0 => IPV4_PMTU_MIN,
+            mtu => mtu as usize,
+        });
+        Some(error)
+    }
+
+    /// Tells the sender of a UDP datagram that no socket is bound to its destination port (see RFC 1122, section
+    /// 4.1.3.1). `datagram` is the payload of the IPv4 datagram, which is quoted in the error message.
+    pub fn send_port_unreachable(&mut self, ipv4_hdr: &Ipv4Header, datagram: &[u8]) {
+        // Never answer datagrams that were not sent to us alone, nor ones whose sender we could not address (see RFC
+        // 1122, section 3.2.2).
+        let src_addr: Ipv4Addr = ipv4_hdr.get_src_addr();
+        let dst_addr: Ipv4Addr = ipv4_hdr.get_dest_addr();
+        if !self.arp.is_local_addr(dst_addr)
+            || src_addr.is_unspecified()
+            || src_addr.is_broadcast()
+            || src_addr.is_multicast()
+        {
+            return;
+        }
+        if !self.error_limiter.try_acquire(self.clock.now()) {
+            debug!("rate limiting port unreachable message to {}", src_addr);
+            return;
+        }
+
+        // Quote the IPv4 header of the offending datagram, followed by the start of its payload.
+        let quote_len: usize = cmp::min(datagram.len(), ICMPV4_ERROR_QUOTE_SIZE);
+        let mut quote: Vec<u8> = vec![0; IPV4_HEADER_DEFAULT_SIZE + quote_len];
+        ipv4_hdr.serialize(&mut quote[..IPV4_HEADER_DEFAULT_SIZE], datagram.len());
+        quote[IPV4_HEADER_DEFAULT_SIZE..].copy_from_slice(&datagram[..quote_len]);
+        let data: DemiBuffer = DemiBuffer::from_slice(&quote).expect("quote should fit in a DemiBuffer");
+
+        let icmpv4_hdr: Icmpv4Header = Icmpv4Header::new(
+            Icmpv4Type2::DestinationUnreachable { next_hop_mtu: 0 },
+            ICMPV4_CODE_PORT_UNREACHABLE,
+        );
+        self.tx
+            .unbounded_send((dst_addr, src_addr, icmpv4_hdr, Some(data)))
+            .unwrap();
     }
 
     /// Computes the identifier for an ICPM message.
@@ -268,7 +523,7 @@ impl Icmpv4Peer {
         let clock: TimerRc = self.clock.clone();
         let requests = self.requests.clone();
         let local_link_addr: MacAddress = self.local_link_addr.clone();
-        let local_ipv4_addr: Ipv4Addr = self.local_ipv4_addr.clone();
+        let local_ipv4_addr: Ipv4Addr = self.arp.select_source(dst_ipv4_addr);
         async move {
             let t0 = clock.now();
             debug!("initiating ARP query");
@@ -288,7 +543,11 @@ impl Icmpv4Peer {
             };
             // TODO: Handle cancellation here and unregister the completion in `requests`.
             let timer = clock.wait(clock.clone(), timeout);
-            let _ = rx.fuse().with_timeout(timer).await?;
+            if let Err(e) = rx.fuse().with_timeout(timer).await {
+                // Forget about the request, so that a late reply is ignored.
+                requests.borrow_mut().remove(&(id, seq_num));
+                return Err(e);
+            }
             Ok(clock.now() - t0)
         }
     }
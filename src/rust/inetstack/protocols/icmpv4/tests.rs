@@ -1,13 +1,46 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-use crate::inetstack::test_helpers;
+use crate::{
+    inetstack::{
+        protocols::{
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+                ETHERNET2_HEADER_SIZE,
+            },
+            icmpv4::{
+                Icmpv4Header,
+                Icmpv4Message,
+                Icmpv4Type2,
+                ICMPV4_CODE_PORT_UNREACHABLE,
+            },
+            ip::IpProtocol,
+            ipv4::{
+                Ipv4Header,
+                IPV4_HEADER_DEFAULT_SIZE,
+            },
+            tcp::operations::ConnectFuture,
+        },
+        test_helpers,
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::PacketBuf,
+        QDesc,
+    },
+};
 use ::futures::task::{
     noop_waker_ref,
     Context,
 };
+use ::libc::{
+    ECONNREFUSED,
+    EHOSTUNREACH,
+};
 use ::std::{
     future::Future,
+    net::SocketAddrV4,
     pin::Pin,
     task::Poll,
     time::{
@@ -105,3 +138,217 @@ fn ipv4_ping_loop() {
         assert_eq!(latency, Duration::from_secs(2));
     }
 }
+
+#[test]
+fn ipv4_ping_timeout() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    let mut alice = test_helpers::new_alice2(now);
+
+    let mut bob = test_helpers::new_bob2(now);
+
+    // Alice pings Bob.
+    let mut ping_fut = Box::pin(alice.ipv4_ping(test_helpers::BOB_IPV4, Some(Duration::from_secs(1))));
+    match Future::poll(Pin::new(&mut ping_fut), &mut ctx) {
+        Poll::Pending => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Bob receives ping request from Alice, and replies to it.
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    bob.rt.poll_scheduler();
+
+    // The reply gets to Alice too late.
+    now += Duration::from_secs(2);
+    alice.clock.advance_clock(now);
+    bob.clock.advance_clock(now);
+    match Future::poll(Pin::new(&mut ping_fut), &mut ctx) {
+        Poll::Ready(Err(e)) if e.errno == libc::ETIMEDOUT => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Alice ignores the late reply.
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    alice.rt.poll_scheduler();
+}
+
+//==============================================================================
+// Port Unreachable
+//==============================================================================
+
+#[test]
+fn ipv4_port_unreachable() {
+    let now = Instant::now();
+
+    let mut alice = test_helpers::new_alice2(now);
+    let alice_addr = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Bob does not create a socket.
+    let mut bob = test_helpers::new_bob2(now);
+    let bob_addr = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+
+    // Alice sends a datagram to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf, bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    assert!(bob.receive(alice.rt.pop_frame()).is_err());
+
+    // Bob tells Alice that the port is unreachable, and quotes the start of the datagram.
+    bob.rt.poll_scheduler();
+    let (_, payload) = Ethernet2Header::parse(bob.rt.pop_frame()).unwrap();
+    let (ipv4_hdr, payload) = Ipv4Header::parse(payload).unwrap();
+    assert_eq!(ipv4_hdr.get_dest_addr(), test_helpers::ALICE_IPV4);
+    let (icmpv4_hdr, payload) = Icmpv4Header::parse(payload).unwrap();
+    assert!(matches!(
+        icmpv4_hdr.get_protocol(),
+        Icmpv4Type2::DestinationUnreachable { .. }
+    ));
+    assert_eq!(icmpv4_hdr.get_code(), ICMPV4_CODE_PORT_UNREACHABLE);
+    let (quoted_hdr, quoted_payload) = Ipv4Header::parse(payload).unwrap();
+    assert_eq!(quoted_hdr.get_src_addr(), test_helpers::ALICE_IPV4);
+    assert_eq!(quoted_hdr.get_dest_addr(), test_helpers::BOB_IPV4);
+    assert_eq!(quoted_payload.len(), 8);
+}
+
+#[test]
+fn ipv4_port_unreachable_rate_limit() {
+    let now = Instant::now();
+
+    let mut alice = test_helpers::new_alice2(now);
+    let alice_addr = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Bob does not create a socket.
+    let mut bob = test_helpers::new_bob2(now);
+    let bob_addr = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+
+    // Alice floods Bob with datagrams, without the clock moving.
+    for _ in 0..100 {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+        alice.udp_pushto(alice_fd, buf, bob_addr).unwrap();
+        alice.rt.poll_scheduler();
+        assert!(bob.receive(alice.rt.pop_frame()).is_err());
+    }
+
+    // Bob only answers a burst of them.
+    bob.rt.poll_scheduler();
+    let mut nerrors: usize = 0;
+    while bob.rt.pop_frame_unchecked().is_some() {
+        nerrors += 1;
+    }
+    assert_eq!(nerrors, 50);
+}
+
+//==============================================================================
+// Error Reporting
+//==============================================================================
+
+/// Forges an ICMP error that Bob sends to Alice about `frame`, which Alice sent.
+fn forge_error(frame: &[u8], icmpv4_type: Icmpv4Type2, code: u8) -> DemiBuffer {
+    let quote: &[u8] = &frame[ETHERNET2_HEADER_SIZE..(ETHERNET2_HEADER_SIZE + IPV4_HEADER_DEFAULT_SIZE + 8)];
+    let msg: Icmpv4Message = Icmpv4Message::new(
+        Ethernet2Header::new(test_helpers::ALICE_MAC, test_helpers::BOB_MAC, EtherType2::Ipv4),
+        Ipv4Header::new(test_helpers::BOB_IPV4, test_helpers::ALICE_IPV4, IpProtocol::ICMPv4),
+        Icmpv4Header::new(icmpv4_type, code),
+    )
+    .with_data(DemiBuffer::from_slice(quote).expect("quote should fit in DemiBuffer"));
+    let header_size: usize = msg.header_size();
+    let body_size: usize = msg.body_size();
+    let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
+    msg.write_header(&mut buf[..header_size]);
+    if let Some(body) = msg.take_body() {
+        buf[header_size..].copy_from_slice(&body[..]);
+    }
+    buf
+}
+
+#[test]
+fn ipv4_error_aborts_tcp_connect() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let now = Instant::now();
+
+    let mut alice = test_helpers::new_alice2(now);
+    let bob_addr = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+
+    // Alice tries to connect to Bob.
+    let alice_fd: QDesc = alice.tcp_socket().unwrap();
+    let mut connect_future: ConnectFuture = alice.tcp_connect(alice_fd, bob_addr);
+    alice.rt.poll_scheduler();
+    let syn: DemiBuffer = alice.rt.pop_frame();
+
+    // An error that does not quote the SYN of Alice is ignored.
+    let mut forged_syn: Vec<u8> = syn[..].to_vec();
+    forged_syn[ETHERNET2_HEADER_SIZE + IPV4_HEADER_DEFAULT_SIZE + 4] ^= 0xff;
+    let unreachable: Icmpv4Type2 = Icmpv4Type2::DestinationUnreachable { next_hop_mtu: 0 };
+    alice.receive(forge_error(&forged_syn, unreachable, 1)).unwrap();
+    match Future::poll(Pin::new(&mut connect_future), &mut ctx) {
+        Poll::Pending => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // A host unreachable error about the SYN aborts the connection attempt.
+    alice.receive(forge_error(&syn[..], unreachable, 1)).unwrap();
+    match Future::poll(Pin::new(&mut connect_future), &mut ctx) {
+        Poll::Ready(Err(e)) if e.errno == EHOSTUNREACH => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+}
+
+#[test]
+fn ipv4_error_fails_udp_operations() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let now = Instant::now();
+
+    let mut alice = test_helpers::new_alice2(now);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice
+        .udp_connect(alice_fd, SocketAddrV4::new(test_helpers::BOB_IPV4, 80))
+        .unwrap();
+
+    // Bob does not create a socket.
+    let mut bob = test_helpers::new_bob2(now);
+
+    // Alice waits for a reply.
+    let mut pop_future = alice.udp_pop(alice_fd);
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Pending => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Bob tells Alice that the port is unreachable, which fails the pending pop.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_push(alice_fd, buf).unwrap();
+    alice.rt.poll_scheduler();
+    assert!(bob.receive(alice.rt.pop_frame()).is_err());
+    bob.rt.poll_scheduler();
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Err(e)) if e.errno == ECONNREFUSED => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Next time, the error is reported by the following push, and only once.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_push(alice_fd, buf).unwrap();
+    alice.rt.poll_scheduler();
+    assert!(bob.receive(alice.rt.pop_frame()).is_err());
+    bob.rt.poll_scheduler();
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    match alice.udp_push(alice_fd, buf.clone()) {
+        Err(e) if e.errno == ECONNREFUSED => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    alice.udp_push(alice_fd, buf).unwrap();
+}
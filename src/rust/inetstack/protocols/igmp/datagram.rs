@@ -0,0 +1,220 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    inetstack::protocols::{
+        ethernet2::Ethernet2Header,
+        ipv4::Ipv4Header,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            types::MacAddress,
+            PacketBuf,
+        },
+    },
+};
+use ::byteorder::{
+    ByteOrder,
+    NetworkEndian,
+};
+use ::libc::{
+    EBADMSG,
+    ENOTSUP,
+};
+use ::std::net::Ipv4Addr;
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Size of IGMP Headers (in bytes)
+pub const IGMP_HEADER_SIZE: usize = 8;
+
+//==============================================================================
+// Enumerations
+//==============================================================================
+
+/// IGMP Message Type
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IgmpType {
+    /// Membership query, either general or group-specific.
+    MembershipQuery = 0x11,
+    /// Membership report from an IGMPv1 host.
+    V1MembershipReport = 0x12,
+    /// Membership report from an IGMPv2 host.
+    V2MembershipReport = 0x16,
+    /// Leave group message.
+    LeaveGroup = 0x17,
+}
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// IGMP Header
+#[derive(Copy, Clone, Debug)]
+pub struct IgmpHeader {
+    /// Type of the message.
+    typ: IgmpType,
+    /// Maximum response time for queries (in tenths of a second).
+    max_resp_time: u8,
+    /// Group address that the message refers to.
+    group: Ipv4Addr,
+}
+
+/// IGMP Message
+pub struct IgmpMessage {
+    /// Ethernet header.
+    ethernet2_hdr: Ethernet2Header,
+    /// IPv4 header.
+    ipv4_hdr: Ipv4Header,
+    /// IGMP header.
+    igmp_hdr: IgmpHeader,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for IGMP Headers
+impl IgmpHeader {
+    /// Creates an IGMP header.
+    pub fn new(typ: IgmpType, max_resp_time: u8, group: Ipv4Addr) -> Self {
+        Self {
+            typ,
+            max_resp_time,
+            group,
+        }
+    }
+
+    /// Returns the size of the target IGMP header.
+    pub fn size(&self) -> usize {
+        IGMP_HEADER_SIZE
+    }
+
+    /// Returns the type of the target IGMP header.
+    pub fn get_type(&self) -> IgmpType {
+        self.typ
+    }
+
+    /// Returns the group address stored in the target IGMP header.
+    pub fn get_group(&self) -> Ipv4Addr {
+        self.group
+    }
+
+    /// Parses a buffer into an IGMP header. IGMPv3 queries carry extra fields after the header, which are ignored, so
+    /// that they are handled as IGMPv2 queries (see RFC 3376).
+    pub fn parse(buf: DemiBuffer) -> Result<Self, Fail> {
+        if buf.len() < IGMP_HEADER_SIZE {
+            return Err(Fail::new(EBADMSG, "IGMP message too small for header"));
+        }
+        if Self::checksum(&buf[..]) != 0 {
+            return Err(Fail::new(EBADMSG, "IGMP checksum mismatch"));
+        }
+        let typ: IgmpType = match buf[0] {
+            0x11 => IgmpType::MembershipQuery,
+            0x12 => IgmpType::V1MembershipReport,
+            0x16 => IgmpType::V2MembershipReport,
+            0x17 => IgmpType::LeaveGroup,
+            _ => return Err(Fail::new(ENOTSUP, "unsupported IGMP message type")),
+        };
+        let max_resp_time: u8 = buf[1];
+        let group: Ipv4Addr = Ipv4Addr::from(NetworkEndian::read_u32(&buf[4..8]));
+        Ok(Self::new(typ, max_resp_time, group))
+    }
+
+    /// Serializes the target IGMP header.
+    pub fn serialize(&self, buf: &mut [u8]) {
+        let buf: &mut [u8] = &mut buf[..IGMP_HEADER_SIZE];
+        buf[0] = self.typ as u8;
+        buf[1] = self.max_resp_time;
+        // Zero the checksum while computing it.
+        NetworkEndian::write_u16(&mut buf[2..4], 0);
+        buf[4..8].copy_from_slice(&self.group.octets());
+        let checksum: u16 = Self::checksum(buf);
+        NetworkEndian::write_u16(&mut buf[2..4], checksum);
+    }
+
+    /// Computes the Internet checksum of an IGMP message. This is zero for messages that carry a valid checksum.
+    fn checksum(buf: &[u8]) -> u16 {
+        let mut state: u32 = 0;
+        let mut chunks_iter = buf.chunks_exact(2);
+        while let Some(chunk) = chunks_iter.next() {
+            state += NetworkEndian::read_u16(chunk) as u32;
+        }
+        if let Some(&b) = chunks_iter.remainder().get(0) {
+            state += NetworkEndian::read_u16(&[b, 0]) as u32;
+        }
+        while state > 0xFFFF {
+            state = (state & 0xFFFF) + (state >> 16);
+        }
+        !state as u16
+    }
+}
+
+/// Associate Functions for IGMP Messages
+impl IgmpMessage {
+    /// Creates an IGMP message.
+    pub fn new(ethernet2_hdr: Ethernet2Header, ipv4_hdr: Ipv4Header, igmp_hdr: IgmpHeader) -> Self {
+        Self {
+            ethernet2_hdr,
+            ipv4_hdr,
+            igmp_hdr,
+        }
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Packet Buffer Trait Implementation for IGMP Messages
+impl PacketBuf for IgmpMessage {
+    /// Computes the header size of the target IGMP message.
+    fn header_size(&self) -> usize {
+        self.ethernet2_hdr.compute_size() + self.ipv4_hdr.compute_size() + self.igmp_hdr.size()
+    }
+
+    /// Computes the payload size of the target IGMP message.
+    fn body_size(&self) -> usize {
+        0
+    }
+
+    /// Serializes the header of the target IGMP message.
+    fn write_header(&self, buf: &mut [u8]) {
+        let mut cur_pos: usize = 0;
+        let eth_hdr_size: usize = self.ethernet2_hdr.compute_size();
+        let ipv4_hdr_size: usize = self.ipv4_hdr.compute_size();
+        let igmp_hdr_size: usize = self.igmp_hdr.size();
+
+        // Ethernet header.
+        self.ethernet2_hdr
+            .serialize(&mut buf[cur_pos..(cur_pos + eth_hdr_size)]);
+        cur_pos += eth_hdr_size;
+
+        // IPv4 header.
+        self.ipv4_hdr
+            .serialize(&mut buf[cur_pos..(cur_pos + ipv4_hdr_size)], igmp_hdr_size);
+        cur_pos += ipv4_hdr_size;
+
+        // IGMP header.
+        self.igmp_hdr.serialize(&mut buf[cur_pos..(cur_pos + igmp_hdr_size)]);
+    }
+
+    /// Returns the payload of the target IGMP message.
+    fn take_body(&self) -> Option<DemiBuffer> {
+        None
+    }
+
+    /// Returns the destination link address of the target IGMP message.
+    fn dst_link_addr(&self) -> MacAddress {
+        self.ethernet2_hdr.dst_addr()
+    }
+}
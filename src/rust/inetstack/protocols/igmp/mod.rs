@@ -0,0 +1,10 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod datagram;
+mod peer;
+
+#[cfg(test)]
+mod tests;
+
+pub use peer::IgmpPeer;
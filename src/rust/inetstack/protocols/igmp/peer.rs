@@ -0,0 +1,174 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    inetstack::protocols::{
+        ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+        },
+        igmp::datagram::{
+            IgmpHeader,
+            IgmpMessage,
+            IgmpType,
+        },
+        ip::IpProtocol,
+        ipv4::Ipv4Header,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            types::MacAddress,
+            NetworkRuntime,
+        },
+    },
+};
+use ::libc::{
+    EADDRNOTAVAIL,
+    EINVAL,
+};
+use ::std::{
+    cell::RefCell,
+    collections::HashMap,
+    net::Ipv4Addr,
+    rc::Rc,
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// All-systems multicast group, which every host is a member of.
+pub const IGMP_ALL_SYSTEMS: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 1);
+
+/// All-routers multicast group, to which leave messages are sent.
+pub const IGMP_ALL_ROUTERS: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 2);
+
+/// Time to Live of IGMP messages, which never leave the local network.
+const IGMP_TTL: u8 = 1;
+
+//==============================================================================
+// IgmpPeer
+//==============================================================================
+
+///
+/// Internet Group Management Protocol (IGMP)
+///
+/// This protocol is used by hosts to report their IPv4 multicast group
+/// memberships to neighboring multicast routers.
+///
+/// This is an implementation of the host side of IGMPv2, which is defined in
+/// RFC 2236, with the following simplifications:
+/// - Reports are sent as soon as a query is received, instead of after a
+///   random delay bounded by the maximum response time of the query.
+/// - Messages do not carry the IP Router Alert option.
+///
+#[derive(Clone)]
+pub struct IgmpPeer {
+    /// Underlying Runtime
+    rt: Rc<dyn NetworkRuntime>,
+
+    local_link_addr: MacAddress,
+    local_ipv4_addr: Ipv4Addr,
+
+    /// Joined groups, along with the number of memberships to each of them.
+    groups: Rc<RefCell<HashMap<Ipv4Addr, usize>>>,
+}
+
+impl IgmpPeer {
+    /// Creates a new peer for handling IGMP.
+    pub fn new(rt: Rc<dyn NetworkRuntime>, local_link_addr: MacAddress, local_ipv4_addr: Ipv4Addr) -> Self {
+        Self {
+            rt,
+            local_link_addr,
+            local_ipv4_addr,
+            groups: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Adds a membership to a multicast group. A report is sent when the first membership to the group is added.
+    pub fn join(&self, group: Ipv4Addr) -> Result<(), Fail> {
+        if !group.is_multicast() {
+            return Err(Fail::new(EINVAL, "not a multicast address"));
+        }
+        let count: usize = {
+            let mut groups = self.groups.borrow_mut();
+            let count: &mut usize = groups.entry(group).or_insert(0);
+            *count += 1;
+            *count
+        };
+        if count == 1 && group != IGMP_ALL_SYSTEMS {
+            self.send(IgmpType::V2MembershipReport, group, group);
+        }
+        Ok(())
+    }
+
+    /// Removes a membership to a multicast group. A leave message is sent when the last membership to the group is
+    /// removed.
+    pub fn leave(&self, group: Ipv4Addr) -> Result<(), Fail> {
+        let count: usize = {
+            let mut groups = self.groups.borrow_mut();
+            let count: &mut usize = match groups.get_mut(&group) {
+                Some(count) => count,
+                None => return Err(Fail::new(EADDRNOTAVAIL, "not a member of this group")),
+            };
+            *count -= 1;
+            if *count == 0 {
+                groups.remove(&group);
+                0
+            } else {
+                *count
+            }
+        };
+        if count == 0 && group != IGMP_ALL_SYSTEMS {
+            self.send(IgmpType::LeaveGroup, group, IGMP_ALL_ROUTERS);
+        }
+        Ok(())
+    }
+
+    /// Queries whether or not datagrams sent to `addr` should be accepted.
+    pub fn is_member(&self, addr: Ipv4Addr) -> bool {
+        addr == IGMP_ALL_SYSTEMS || self.groups.borrow().contains_key(&addr)
+    }
+
+    /// Parses and handles an IGMP message. Only membership queries are answered.
+    pub fn receive(&self, _ipv4_header: &Ipv4Header, buf: DemiBuffer) -> Result<(), Fail> {
+        let igmp_hdr: IgmpHeader = IgmpHeader::parse(buf)?;
+        debug!("IGMP received {:?}", igmp_hdr);
+        match igmp_hdr.get_type() {
+            IgmpType::MembershipQuery => {
+                let group: Ipv4Addr = igmp_hdr.get_group();
+                let groups: Vec<Ipv4Addr> = if group.is_unspecified() {
+                    // General query, so report all groups.
+                    self.groups.borrow().keys().copied().collect()
+                } else if self.groups.borrow().contains_key(&group) {
+                    // Group-specific query.
+                    vec![group]
+                } else {
+                    vec![]
+                };
+                for group in groups.into_iter().filter(|group| *group != IGMP_ALL_SYSTEMS) {
+                    self.send(IgmpType::V2MembershipReport, group, group);
+                }
+            },
+            // Reports from other hosts are only relevant to routers.
+            _ => trace!("ignoring IGMP message: {:?}", igmp_hdr),
+        }
+        Ok(())
+    }
+
+    /// Sends an IGMP message about `group` to the multicast address `dst_ipv4_addr`.
+    fn send(&self, typ: IgmpType, group: Ipv4Addr, dst_ipv4_addr: Ipv4Addr) {
+        debug!("IGMP sending {:?} (group={:?})", typ, group);
+        self.rt.transmit(Box::new(IgmpMessage::new(
+            Ethernet2Header::new(
+                MacAddress::from_ipv4_multicast(dst_ipv4_addr),
+                self.local_link_addr,
+                EtherType2::Ipv4,
+            ),
+            Ipv4Header::new(self.local_ipv4_addr, dst_ipv4_addr, IpProtocol::IGMP).with_ttl(IGMP_TTL),
+            IgmpHeader::new(typ, 0, group),
+        )));
+    }
+}
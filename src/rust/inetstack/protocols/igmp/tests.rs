@@ -0,0 +1,263 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    inetstack::{
+        protocols::{
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+            },
+            igmp::{
+                datagram::{
+                    IgmpHeader,
+                    IgmpMessage,
+                    IgmpType,
+                },
+                peer::{
+                    IGMP_ALL_ROUTERS,
+                    IGMP_ALL_SYSTEMS,
+                },
+            },
+            ip::IpProtocol,
+            ipv4::Ipv4Header,
+        },
+        test_helpers::{
+            self,
+            Engine,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            types::MacAddress,
+            NetworkRuntime,
+        },
+        QDesc,
+    },
+};
+use ::futures::task::{
+    noop_waker_ref,
+    Context,
+};
+use ::libc::{
+    EADDRINUSE,
+    EADDRNOTAVAIL,
+    EINVAL,
+};
+use ::std::{
+    future::Future,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    pin::Pin,
+    task::Poll,
+    time::Instant,
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Multicast group used in tests.
+const GROUP: Ipv4Addr = Ipv4Addr::new(239, 1, 2, 3);
+
+//==============================================================================
+// Helper Functions
+//==============================================================================
+
+/// Parses a frame that carries an IGMP message, checking that it is sent to the multicast address `dst_ipv4_addr`.
+fn parse_igmp_frame(buf: DemiBuffer, dst_ipv4_addr: Ipv4Addr) -> IgmpHeader {
+    let (eth2_hdr, eth2_payload): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(buf).unwrap();
+    assert_eq!(eth2_hdr.dst_addr(), MacAddress::from_ipv4_multicast(dst_ipv4_addr));
+    assert_eq!(eth2_hdr.src_addr(), test_helpers::ALICE_MAC);
+    let (ipv4_hdr, ipv4_payload): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(eth2_payload).unwrap();
+    assert_eq!(ipv4_hdr.get_protocol(), IpProtocol::IGMP);
+    assert_eq!(ipv4_hdr.get_src_addr(), test_helpers::ALICE_IPV4);
+    assert_eq!(ipv4_hdr.get_dest_addr(), dst_ipv4_addr);
+    IgmpHeader::parse(ipv4_payload).unwrap()
+}
+
+/// Sends a membership query about `group` from Bob to Alice.
+fn send_query(bob: &mut Engine, alice: &mut Engine, group: Ipv4Addr) {
+    bob.rt.transmit(Box::new(IgmpMessage::new(
+        Ethernet2Header::new(
+            MacAddress::from_ipv4_multicast(IGMP_ALL_SYSTEMS),
+            test_helpers::BOB_MAC,
+            EtherType2::Ipv4,
+        ),
+        Ipv4Header::new(test_helpers::BOB_IPV4, IGMP_ALL_SYSTEMS, IpProtocol::IGMP).with_ttl(1),
+        IgmpHeader::new(IgmpType::MembershipQuery, 100, group),
+    )));
+    alice.receive(bob.rt.pop_frame()).unwrap();
+}
+
+//==============================================================================
+// Join & Leave
+//==============================================================================
+
+#[test]
+fn igmp_join_leave() {
+    let now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+
+    // Joining a group sends a report to it.
+    alice.udp_join_multicast_group(alice_fd, GROUP).unwrap();
+    let igmp_hdr: IgmpHeader = parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+    assert_eq!(igmp_hdr.get_type(), IgmpType::V2MembershipReport);
+    assert_eq!(igmp_hdr.get_group(), GROUP);
+
+    // Joining the same group again fails.
+    match alice.udp_join_multicast_group(alice_fd, GROUP) {
+        Err(e) if e.errno == EADDRINUSE => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Leaving the group sends a leave message to all routers.
+    alice.udp_leave_multicast_group(alice_fd, GROUP).unwrap();
+    let igmp_hdr: IgmpHeader = parse_igmp_frame(alice.rt.pop_frame(), IGMP_ALL_ROUTERS);
+    assert_eq!(igmp_hdr.get_type(), IgmpType::LeaveGroup);
+    assert_eq!(igmp_hdr.get_group(), GROUP);
+
+    // Leaving a group that was not joined fails.
+    match alice.udp_leave_multicast_group(alice_fd, GROUP) {
+        Err(e) if e.errno == EADDRNOTAVAIL => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+}
+
+#[test]
+fn igmp_join_shared_group() {
+    let now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_fd_a: QDesc = alice.udp_socket().unwrap();
+    let alice_fd_b: QDesc = alice.udp_socket().unwrap();
+
+    // Unicast addresses are not groups.
+    match alice.udp_join_multicast_group(alice_fd_a, test_helpers::BOB_IPV4) {
+        Err(e) if e.errno == EINVAL => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Only the first membership to a group is reported.
+    alice.udp_join_multicast_group(alice_fd_a, GROUP).unwrap();
+    parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+    alice.udp_join_multicast_group(alice_fd_b, GROUP).unwrap();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+
+    // Only the last membership to a group sends a leave message.
+    alice.udp_leave_multicast_group(alice_fd_a, GROUP).unwrap();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+    alice.udp_leave_multicast_group(alice_fd_b, GROUP).unwrap();
+    let igmp_hdr: IgmpHeader = parse_igmp_frame(alice.rt.pop_frame(), IGMP_ALL_ROUTERS);
+    assert_eq!(igmp_hdr.get_type(), IgmpType::LeaveGroup);
+}
+
+#[test]
+fn igmp_close_leaves_groups() {
+    let now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 5000);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    alice.udp_join_multicast_group(alice_fd, GROUP).unwrap();
+    parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+
+    // Closing the socket leaves its groups.
+    alice.udp_close(alice_fd).unwrap();
+    let igmp_hdr: IgmpHeader = parse_igmp_frame(alice.rt.pop_frame(), IGMP_ALL_ROUTERS);
+    assert_eq!(igmp_hdr.get_type(), IgmpType::LeaveGroup);
+    assert_eq!(igmp_hdr.get_group(), GROUP);
+}
+
+//==============================================================================
+// Membership Queries
+//==============================================================================
+
+#[test]
+fn igmp_query_report() {
+    let now: Instant = Instant::now();
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_join_multicast_group(alice_fd, GROUP).unwrap();
+    parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+
+    // General queries are answered with a report for each joined group.
+    send_query(&mut bob, &mut alice, Ipv4Addr::UNSPECIFIED);
+    let igmp_hdr: IgmpHeader = parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+    assert_eq!(igmp_hdr.get_type(), IgmpType::V2MembershipReport);
+    assert_eq!(igmp_hdr.get_group(), GROUP);
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+
+    // Group-specific queries are answered only for joined groups.
+    send_query(&mut bob, &mut alice, Ipv4Addr::new(239, 9, 9, 9));
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+    send_query(&mut bob, &mut alice, GROUP);
+    let igmp_hdr: IgmpHeader = parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+    assert_eq!(igmp_hdr.get_type(), IgmpType::V2MembershipReport);
+}
+
+//==============================================================================
+// Multicast Datagrams
+//==============================================================================
+
+#[test]
+fn igmp_udp_multicast_pushto_pop() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+    let port: u16 = 5000;
+
+    // Setup Alice, and have her join the group on the wildcard address.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice
+        .udp_bind(alice_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))
+        .unwrap();
+    alice.udp_join_multicast_group(alice_fd, GROUP).unwrap();
+    parse_igmp_frame(alice.rt.pop_frame(), GROUP);
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Send data to the group. This does not need address resolution.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    bob.udp_pushto(bob_fd, buf.clone(), SocketAddrV4::new(GROUP, port))
+        .unwrap();
+    let frame: DemiBuffer = bob.rt.pop_frame();
+    let (eth2_hdr, _): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(frame.clone()).unwrap();
+    assert_eq!(eth2_hdr.dst_addr(), MacAddress::from_ipv4_multicast(GROUP));
+
+    // Receive data from Bob.
+    alice.receive(frame).unwrap();
+    let mut pop_future = alice.udp_pop(alice_fd);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, bob_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // Datagrams sent to groups that were not joined are dropped.
+    bob.udp_pushto(
+        bob_fd,
+        buf.clone(),
+        SocketAddrV4::new(Ipv4Addr::new(239, 9, 9, 9), port),
+    )
+    .unwrap();
+    assert!(alice.receive(bob.rt.pop_frame()).is_err());
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
@@ -51,6 +51,17 @@ impl EphemeralPorts {
         ))
     }
 
+    /// Allocates any port from the pool that satisfies `predicate`.
+    pub fn alloc_matching<F: Fn(u16) -> bool>(&mut self, predicate: F) -> Result<u16, Fail> {
+        match self.ports.iter().rposition(|port| predicate(*port)) {
+            Some(index) => Ok(self.ports.swap_remove(index)),
+            None => Err(Fail::new(
+                libc::EADDRINUSE,
+                "no port number in the ephemeral port range is available",
+            )),
+        }
+    }
+
     /// Allocates the specified port from the pool.
     pub fn alloc_port(&mut self, port: u16) -> Result<(), Fail> {
         // Check if port is not in the pool.
@@ -16,10 +16,12 @@ use ::std::convert::TryFrom;
 
 /// Ipv4 Protocol
 #[repr(u8)]
-#[derive(FromPrimitive, Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(FromPrimitive, Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum IpProtocol {
     /// Internet Control Message Protocol
     ICMPv4 = 0x01,
+    /// Internet Group Management Protocol
+    IGMP = 0x02,
     /// Transmission Control Protocol
     TCP = 0x06,
     /// User Datagram Protocol
@@ -45,11 +45,17 @@ const IPV4_HEADER_MIN_SIZE: u16 = IPV4_DATAGRAM_MIN_SIZE;
 const IPV4_IHL_NO_OPTIONS: u8 = (IPV4_HEADER_MIN_SIZE as u8) / 4;
 
 /// Default time to live value.
-const DEFAULT_IPV4_TTL: u8 = 255;
+pub const DEFAULT_IPV4_TTL: u8 = 255;
 
 /// Version number for IPv4.
 const IPV4_VERSION: u8 = 4;
 
+/// ECN Codepoint: ECN-Capable Transport, ECT(0) (see RFC 3168).
+pub const IPV4_ECN_ECT0: u8 = 2;
+
+/// ECN Codepoint: Congestion Experienced.
+pub const IPV4_ECN_CE: u8 = 3;
+
 /// IPv4 Control Flag: Datagram has evil intent (see RFC 3514).
 const IPV4_CTRL_FLAG_EVIL: u8 = 0x4;
 
@@ -121,6 +127,27 @@ impl Ipv4Header {
         }
     }
 
+    /// Sets the Time to Live field of the target IPv4 header.
+    pub fn with_ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the Explicit Congestion Notification field of the target IPv4 header.
+    pub fn with_ecn(mut self, ecn: u8) -> Self {
+        self.ecn = ecn & 3;
+        self
+    }
+
+    /// Sets the fragmentation fields of the target IPv4 header. The `offset` is given in bytes and should be a
+    /// multiple of 8. The Don't Fragment flag is cleared.
+    pub fn with_fragment(mut self, identification: u16, offset: usize, more_fragments: bool) -> Self {
+        self.identification = identification;
+        self.fragment_offset = (offset >> 3) as u16;
+        self.flags = if more_fragments { IPV4_CTRL_FLAG_MF } else { 0 };
+        self
+    }
+
     /// Computes the size of the target IPv4 header.
     pub fn compute_size(&self) -> usize {
         IPV4_HEADER_MIN_SIZE as usize
@@ -158,9 +185,6 @@ impl Ipv4Header {
 
         // Explicit congestion notification.
         let ecn: u8 = hdr_buf[1] & 3;
-        if ecn != 0 {
-            warn!("ignoring ecn field (ecn={:?})", ecn);
-        }
 
         // Total length.
         let total_length: u16 = NetworkEndian::read_u16(&hdr_buf[2..4]);
@@ -192,19 +216,10 @@ impl Ipv4Header {
             return Err(Fail::new(EBADMSG, "ipv4 datagram is marked as evil"));
         }
 
-        // TODO: drop this check once we support fragmentation.
-        if flags & IPV4_CTRL_FLAG_MF != 0 {
-            warn!("fragmentation is not supported flags={:?}", flags);
-            return Err(Fail::new(ENOTSUP, "ipv4 fragmentation is not supported"));
-        }
-
         // Fragment offset.
+        //
+        // Note: fragments are handed over as they are, reassembling them is up to the caller.
         let fragment_offset: u16 = NetworkEndian::read_u16(&hdr_buf[6..8]) & 0x1fff;
-        // TODO: drop this check once we support fragmentation.
-        if fragment_offset != 0 {
-            warn!("fragmentation is not supported offset={:?}", fragment_offset);
-            return Err(Fail::new(ENOTSUP, "ipv4 fragmentation is not supported"));
-        }
 
         // Time to live.
         let time_to_live: u8 = hdr_buf[8];
@@ -305,6 +320,41 @@ impl Ipv4Header {
         self.dst_addr
     }
 
+    /// Returns the time to live field stored in the target IPv4 header.
+    pub fn get_ttl(&self) -> u8 {
+        self.ttl
+    }
+
+    /// Returns the Explicit Congestion Notification field stored in the target IPv4 header.
+    pub fn get_ecn(&self) -> u8 {
+        self.ecn
+    }
+
+    /// Returns the identification field stored in the target IPv4 header.
+    pub fn get_identification(&self) -> u16 {
+        self.identification
+    }
+
+    /// Returns the offset (in bytes) of the data carried by the target IPv4 header within the original datagram.
+    pub fn get_fragment_offset(&self) -> usize {
+        (self.fragment_offset as usize) << 3
+    }
+
+    /// Checks if the Don't Fragment flag is set in the target IPv4 header.
+    pub fn get_dont_fragment(&self) -> bool {
+        self.flags & IPV4_CTRL_FLAG_DF != 0
+    }
+
+    /// Checks if the More Fragments flag is set in the target IPv4 header.
+    pub fn has_more_fragments(&self) -> bool {
+        self.flags & IPV4_CTRL_FLAG_MF != 0
+    }
+
+    /// Checks if the target IPv4 header belongs to a fragment of a larger datagram.
+    pub fn is_fragment(&self) -> bool {
+        self.has_more_fragments() || self.fragment_offset != 0
+    }
+
     /// Returns the protocol field stored in the target IPv4 header.
     pub fn get_protocol(&self) -> IpProtocol {
         self.protocol
@@ -0,0 +1,372 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    inetstack::protocols::{
+        ethernet2::{
+            Ethernet2Header,
+            ETHERNET2_HEADER_SIZE,
+        },
+        ip::IpProtocol,
+        ipv4::Ipv4Header,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            types::MacAddress,
+            NetworkRuntime,
+            PacketBuf,
+        },
+    },
+};
+use ::libc::{
+    EBADMSG,
+    EMSGSIZE,
+    ENOBUFS,
+};
+use ::std::{
+    cell::Cell,
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    net::Ipv4Addr,
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Time after which incomplete datagrams are discarded from the reassembly cache.
+pub const IPV4_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of datagrams that may be under reassembly at the same time.
+pub const IPV4_REASSEMBLY_MAX_DATAGRAMS: usize = 256;
+
+/// Maximum number of bytes held by fragments under reassembly.
+pub const IPV4_REASSEMBLY_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Maximum size of an IPv4 datagram (in bytes).
+const IPV4_DATAGRAM_MAX_SIZE: usize = u16::MAX as usize;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// IPv4 Fragment
+#[derive(Debug)]
+pub struct Ipv4Fragment {
+    /// Ethernet header.
+    ethernet2_hdr: Ethernet2Header,
+    /// IPv4 header.
+    ipv4_hdr: Ipv4Header,
+    /// Fragment of the original payload.
+    data: DemiBuffer,
+}
+
+/// IPv4 Fragmenter
+///
+/// Splits datagrams that do not fit in the Maximum Transmission Unit (MTU) of
+/// the link into fragments. Fragments of the same datagram share an
+/// identification, which is drawn from a counter.
+#[derive(Clone)]
+pub struct Ipv4Fragmenter {
+    /// Maximum Transmission Unit (in bytes).
+    mtu: usize,
+    /// Identification of the next fragmented datagram.
+    next_id: Rc<Cell<u16>>,
+}
+
+/// Identifies the datagram to which a fragment belongs (see RFC 791).
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+struct ReassemblyKey {
+    src_addr: Ipv4Addr,
+    dst_addr: Ipv4Addr,
+    protocol: IpProtocol,
+    identification: u16,
+}
+
+/// Datagram under reassembly.
+struct PartialDatagram {
+    /// Header of the first fragment, once it has arrived.
+    header: Option<Ipv4Header>,
+    /// Received fragments, keyed by offset.
+    fragments: BTreeMap<usize, DemiBuffer>,
+    /// Length of the datagram payload, once the last fragment has arrived.
+    total_len: Option<usize>,
+    /// Number of bytes held by received fragments.
+    nbytes: usize,
+    /// Time after which this datagram is discarded.
+    deadline: Instant,
+}
+
+/// IPv4 Reassembly Cache
+///
+/// Holds fragments of inbound datagrams until all of them have arrived. Both
+/// the time a datagram may spend under reassembly and the memory used by the
+/// cache are bounded, so that lost or forged fragments cannot pile up.
+/// Datagrams with overlapping fragments are discarded altogether.
+pub struct ReassemblyCache {
+    /// Datagrams under reassembly.
+    datagrams: HashMap<ReassemblyKey, PartialDatagram>,
+    /// Number of bytes held by all datagrams under reassembly.
+    nbytes: usize,
+    /// Time after which incomplete datagrams are discarded.
+    timeout: Duration,
+    /// Maximum number of datagrams under reassembly.
+    max_datagrams: usize,
+    /// Maximum number of bytes held by the cache.
+    max_bytes: usize,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for IPv4 Fragmenters
+impl Ipv4Fragmenter {
+    /// Creates a fragmenter for a link with the given MTU.
+    pub fn new(mtu: usize) -> Self {
+        Self {
+            mtu,
+            next_id: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Transmits a packet, splitting it into fragments if it does not fit in the MTU.
+    pub fn transmit(&self, rt: &Rc<dyn NetworkRuntime>, pkt: Box<dyn PacketBuf>) -> Result<(), Fail> {
+        if pkt.header_size() + pkt.body_size() <= ETHERNET2_HEADER_SIZE + self.mtu {
+            rt.transmit(pkt);
+            return Ok(());
+        }
+
+        let identification: u16 = self.next_id.get();
+        self.next_id.set(identification.wrapping_add(1));
+        for fragment in self.fragment(pkt.as_ref(), identification)? {
+            rt.transmit(Box::new(fragment));
+        }
+
+        Ok(())
+    }
+
+    /// Splits a packet into fragments that fit in the MTU.
+    fn fragment(&self, pkt: &dyn PacketBuf, identification: u16) -> Result<Vec<Ipv4Fragment>, Fail> {
+        let header_size: usize = pkt.header_size();
+        let size: usize = header_size + pkt.body_size();
+        if size > ETHERNET2_HEADER_SIZE + IPV4_DATAGRAM_MAX_SIZE {
+            return Err(Fail::new(EMSGSIZE, "ipv4 datagram too large"));
+        }
+
+        // Serialize the original packet, so that upper-layer headers get fragmented along with the payload.
+        let mut buf: Vec<u8> = vec![0; size];
+        pkt.write_header(&mut buf[..header_size]);
+        if let Some(body) = pkt.take_body() {
+            buf[header_size..].copy_from_slice(&body[..]);
+        }
+        let (ethernet2_hdr, _) = Ethernet2Header::parse(DemiBuffer::from_slice(&buf[..ETHERNET2_HEADER_SIZE])?)?;
+        let mut datagram: DemiBuffer = DemiBuffer::new((size - ETHERNET2_HEADER_SIZE) as u16);
+        datagram.copy_from_slice(&buf[ETHERNET2_HEADER_SIZE..]);
+        let (ipv4_hdr, payload) = Ipv4Header::parse(datagram)?;
+
+        // All fragments but the last one carry a multiple of 8 bytes.
+        let max_fragment_size: usize = (self.mtu - ipv4_hdr.compute_size()) & !7;
+        let mut fragments: Vec<Ipv4Fragment> = Vec::new();
+        let mut offset: usize = 0;
+        while offset < payload.len() {
+            let end: usize = usize::min(offset + max_fragment_size, payload.len());
+            fragments.push(Ipv4Fragment {
+                ethernet2_hdr: ethernet2_hdr.clone(),
+                ipv4_hdr: ipv4_hdr.with_fragment(identification, offset, end < payload.len()),
+                data: DemiBuffer::from_slice(&payload[offset..end])?,
+            });
+            offset = end;
+        }
+
+        Ok(fragments)
+    }
+}
+
+/// Associate Functions for IPv4 Reassembly Caches
+impl ReassemblyCache {
+    /// Creates a reassembly cache.
+    pub fn new(timeout: Duration, max_datagrams: usize, max_bytes: usize) -> Self {
+        Self {
+            datagrams: HashMap::new(),
+            nbytes: 0,
+            timeout,
+            max_datagrams,
+            max_bytes,
+        }
+    }
+
+    /// Inserts a fragment in the target reassembly cache. Once all fragments of a datagram have arrived, returns the
+    /// header and payload of the reassembled datagram.
+    pub fn insert(
+        &mut self,
+        header: Ipv4Header,
+        payload: DemiBuffer,
+        now: Instant,
+    ) -> Result<Option<(Ipv4Header, DemiBuffer)>, Fail> {
+        self.evict_expired(now);
+
+        let offset: usize = header.get_fragment_offset();
+        let len: usize = payload.len();
+        let end: usize = offset + len;
+        if header.has_more_fragments() && (len == 0 || len % 8 != 0) {
+            return Err(Fail::new(EBADMSG, "ipv4 fragment size is not a multiple of 8"));
+        }
+        if header.compute_size() + end > IPV4_DATAGRAM_MAX_SIZE {
+            return Err(Fail::new(EBADMSG, "ipv4 fragment exceeds maximum datagram size"));
+        }
+
+        let key: ReassemblyKey = ReassemblyKey {
+            src_addr: header.get_src_addr(),
+            dst_addr: header.get_dest_addr(),
+            protocol: header.get_protocol(),
+            identification: header.get_identification(),
+        };
+        if !self.datagrams.contains_key(&key) && self.datagrams.len() >= self.max_datagrams {
+            return Err(Fail::new(ENOBUFS, "too many ipv4 datagrams under reassembly"));
+        }
+        if self.nbytes + len > self.max_bytes {
+            return Err(Fail::new(ENOBUFS, "ipv4 reassembly cache is full"));
+        }
+
+        let deadline: Instant = now + self.timeout;
+        let datagram: &mut PartialDatagram = self.datagrams.entry(key).or_insert_with(|| PartialDatagram {
+            header: None,
+            fragments: BTreeMap::new(),
+            total_len: None,
+            nbytes: 0,
+            deadline,
+        });
+
+        // Silently drop exact duplicates, which may be caused by retransmissions in lower layers.
+        if datagram.fragments.get(&offset).map(|f| f.len()) == Some(len) {
+            return Ok(None);
+        }
+
+        // Check for fragments that are overlapping or that disagree on the length of the datagram.
+        let overlaps_prev: bool = match datagram.fragments.range(..offset).next_back() {
+            Some((prev_offset, prev)) => prev_offset + prev.len() > offset,
+            None => false,
+        };
+        let overlaps_next: bool = match datagram.fragments.range(offset..).next() {
+            Some((next_offset, _)) => *next_offset < end,
+            None => false,
+        };
+        let inconsistent: bool = match datagram.total_len {
+            Some(total_len) => end > total_len || (!header.has_more_fragments() && end != total_len),
+            None => false,
+        };
+        if overlaps_prev || overlaps_next || inconsistent {
+            self.remove(&key);
+            return Err(Fail::new(EBADMSG, "inconsistent ipv4 fragments"));
+        }
+
+        if offset == 0 {
+            datagram.header = Some(header);
+        }
+        if !header.has_more_fragments() {
+            datagram.total_len = Some(end);
+        }
+        datagram.fragments.insert(offset, payload);
+        datagram.nbytes += len;
+        self.nbytes += len;
+
+        if !Self::is_complete(datagram) {
+            return Ok(None);
+        }
+
+        // Reassemble the datagram.
+        let datagram: PartialDatagram = self.remove(&key).expect("datagram should be in the cache");
+        let header: Ipv4Header = datagram.header.expect("first fragment should have arrived");
+        let mut buf: DemiBuffer = DemiBuffer::new(datagram.nbytes as u16);
+        for (offset, fragment) in datagram.fragments.iter() {
+            buf[*offset..(*offset + fragment.len())].copy_from_slice(&fragment[..]);
+        }
+
+        Ok(Some((header.with_fragment(header.get_identification(), 0, false), buf)))
+    }
+
+    /// Checks if all fragments of a datagram have arrived.
+    fn is_complete(datagram: &PartialDatagram) -> bool {
+        match (datagram.header, datagram.total_len) {
+            (Some(_), Some(total_len)) => {
+                let mut expected: usize = 0;
+                for (offset, fragment) in datagram.fragments.iter() {
+                    if *offset != expected {
+                        return false;
+                    }
+                    expected += fragment.len();
+                }
+                expected == total_len
+            },
+            _ => false,
+        }
+    }
+
+    /// Removes a datagram from the target reassembly cache.
+    fn remove(&mut self, key: &ReassemblyKey) -> Option<PartialDatagram> {
+        let datagram: PartialDatagram = self.datagrams.remove(key)?;
+        self.nbytes -= datagram.nbytes;
+        Some(datagram)
+    }
+
+    /// Discards datagrams whose reassembly has timed out.
+    fn evict_expired(&mut self, now: Instant) {
+        let nbytes: &mut usize = &mut self.nbytes;
+        self.datagrams.retain(|key, datagram| {
+            if datagram.deadline > now {
+                return true;
+            }
+            debug!("ipv4 reassembly timed out {:?}", key);
+            *nbytes -= datagram.nbytes;
+            false
+        });
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Packet Buffer Trait Implementation for IPv4 Fragments
+impl PacketBuf for Ipv4Fragment {
+    /// Computes the header size of the target IPv4 fragment.
+    fn header_size(&self) -> usize {
+        self.ethernet2_hdr.compute_size() + self.ipv4_hdr.compute_size()
+    }
+
+    /// Computes the payload size of the target IPv4 fragment.
+    fn body_size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Serializes the header of the target IPv4 fragment.
+    fn write_header(&self, buf: &mut [u8]) {
+        let eth_hdr_size: usize = self.ethernet2_hdr.compute_size();
+        self.ethernet2_hdr.serialize(&mut buf[..eth_hdr_size]);
+        self.ipv4_hdr.serialize(&mut buf[eth_hdr_size..], self.data.len());
+    }
+
+    /// Returns the payload of the target IPv4 fragment.
+    fn take_body(&self) -> Option<DemiBuffer> {
+        Some(self.data.clone())
+    }
+
+    /// Returns the destination link address of the target IPv4 fragment.
+    fn dst_link_addr(&self) -> MacAddress {
+        self.ethernet2_hdr.dst_addr()
+    }
+}
@@ -2,6 +2,9 @@
 // Licensed under the MIT license.
 
 mod datagram;
+mod fragmentation;
+mod pmtu;
+mod routing;
 
 #[cfg(test)]
 mod tests;
@@ -10,7 +13,25 @@ mod tests;
 // Exports
 //==============================================================================
 
-pub use self::datagram::{
-    Ipv4Header,
-    IPV4_HEADER_DEFAULT_SIZE,
+pub use self::{
+    datagram::{
+        Ipv4Header,
+        DEFAULT_IPV4_TTL,
+        IPV4_ECN_CE,
+        IPV4_ECN_ECT0,
+        IPV4_HEADER_DEFAULT_SIZE,
+    },
+    fragmentation::{
+        Ipv4Fragmenter,
+        ReassemblyCache,
+        IPV4_REASSEMBLY_MAX_BYTES,
+        IPV4_REASSEMBLY_MAX_DATAGRAMS,
+        IPV4_REASSEMBLY_TIMEOUT,
+    },
+    pmtu::{
+        PathMtuCache,
+        IPV4_PMTU_MIN,
+        IPV4_PMTU_TIMEOUT,
+    },
+    routing::RoutingTable,
 };
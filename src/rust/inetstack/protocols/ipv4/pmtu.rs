@@ -0,0 +1,105 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::{
+    cell::RefCell,
+    collections::HashMap,
+    net::Ipv4Addr,
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Smallest path MTU that we honor (in bytes). This is the size of the largest datagram that every host must be able
+/// to reassemble (see RFC 1122), so reports of smaller values are most likely bogus.
+pub const IPV4_PMTU_MIN: usize = 576;
+
+/// Time after which a path MTU estimate expires (see RFC 1191). Once it does, the link MTU is tried again, so that
+/// increases in the path MTU are eventually detected.
+pub const IPV4_PMTU_TIMEOUT: Duration = Duration::from_secs(600);
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Path MTU estimate for a destination.
+struct PathMtuEntry {
+    /// Path MTU (in bytes).
+    mtu: usize,
+    /// Time after which this estimate expires.
+    deadline: Instant,
+}
+
+/// Path MTU Cache
+///
+/// Holds the path MTU learned for each destination from ICMP Fragmentation
+/// Needed messages (see RFC 1191). Destinations without an estimate are
+/// assumed to be reachable with the MTU of the local link. This cache is
+/// shared between the protocols that learn and use path MTUs.
+#[derive(Clone)]
+pub struct PathMtuCache {
+    /// MTU of the local link (in bytes).
+    link_mtu: usize,
+    /// Time after which path MTU estimates expire.
+    timeout: Duration,
+    /// Path MTU estimates, keyed by destination.
+    entries: Rc<RefCell<HashMap<Ipv4Addr, PathMtuEntry>>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Path MTU Caches
+impl PathMtuCache {
+    /// Creates a path MTU cache for a link with the given MTU.
+    pub fn new(link_mtu: usize, timeout: Duration) -> Self {
+        Self {
+            link_mtu,
+            timeout,
+            entries: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the path MTU towards a destination.
+    pub fn get(&self, dst: Ipv4Addr, now: Instant) -> usize {
+        let mut entries = self.entries.borrow_mut();
+        match entries.get(&dst) {
+            Some(entry) if entry.deadline > now => entry.mtu,
+            Some(_) => {
+                debug!("path mtu estimate expired for {:?}", dst);
+                entries.remove(&dst);
+                self.link_mtu
+            },
+            None => self.link_mtu,
+        }
+    }
+
+    /// Records that the path MTU towards a destination is `mtu`. Since path MTU discovery only learns about decreases,
+    /// reports that do not lower the current estimate are ignored. Returns whether the estimate has changed.
+    pub fn update(&self, dst: Ipv4Addr, mtu: usize, now: Instant) -> bool {
+        let mtu: usize = mtu.max(IPV4_PMTU_MIN).min(self.link_mtu);
+        if mtu >= self.get(dst, now) {
+            return false;
+        }
+        debug!("path mtu for {:?} is now {:?}", dst, mtu);
+        self.entries.borrow_mut().insert(
+            dst,
+            PathMtuEntry {
+                mtu,
+                deadline: now + self.timeout,
+            },
+        );
+        true
+    }
+}
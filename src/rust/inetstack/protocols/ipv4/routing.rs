@@ -0,0 +1,76 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::network::types::Route;
+use ::std::net::Ipv4Addr;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Routing Table
+///
+/// Picks the next hop towards a destination, by longest prefix match among
+/// static routes. Destinations that no route covers are assumed to be on the
+/// local link, which is how the stack behaves when no route is configured.
+#[derive(Debug, Default)]
+pub struct RoutingTable {
+    /// Routes, from the longest prefix to the shortest one.
+    routes: Vec<Route>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Routing Tables
+impl RoutingTable {
+    /// Creates a routing table with the given routes.
+    pub fn new(routes: &[Route]) -> Self {
+        let mut table: RoutingTable = Self::default();
+        for route in routes {
+            table.insert(*route);
+        }
+        table
+    }
+
+    /// Adds a route. If there is a route for the same destinations, it is replaced and returned.
+    pub fn insert(&mut self, route: Route) -> Option<Route> {
+        let old: Option<Route> = self.remove(route.destination, route.prefix_len);
+        let index: usize = self
+            .routes
+            .iter()
+            .position(|r| r.prefix_len < route.prefix_len)
+            .unwrap_or(self.routes.len());
+        self.routes.insert(index, route);
+        old
+    }
+
+    /// Removes the route for the given destinations, if any.
+    pub fn remove(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Option<Route> {
+        let index: usize = self
+            .routes
+            .iter()
+            .position(|r| r.destination == destination && r.prefix_len == prefix_len)?;
+        Some(self.routes.remove(index))
+    }
+
+    /// Lists routes, from the longest prefix to the shortest one.
+    pub fn routes(&self) -> Vec<Route> {
+        self.routes.clone()
+    }
+
+    /// Returns the next hop towards `dst`.
+    pub fn next_hop(&self, dst: Ipv4Addr) -> Ipv4Addr {
+        match self.routes.iter().find(|r| r.contains(dst)) {
+            Some(Route {
+                gateway: Some(gateway), ..
+            }) => *gateway,
+            _ => dst,
+        }
+    }
+}
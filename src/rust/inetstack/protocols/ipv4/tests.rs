@@ -9,19 +9,43 @@ use crate::{
     inetstack::{
         protocols::{
             ip::IpProtocol,
-            ipv4::Ipv4Header,
+            ipv4::{
+                Ipv4Header,
+                PathMtuCache,
+                ReassemblyCache,
+                RoutingTable,
+                IPV4_PMTU_MIN,
+                IPV4_PMTU_TIMEOUT,
+                IPV4_REASSEMBLY_MAX_BYTES,
+                IPV4_REASSEMBLY_MAX_DATAGRAMS,
+                IPV4_REASSEMBLY_TIMEOUT,
+            },
         },
         test_helpers::{
             ALICE_IPV4,
             BOB_IPV4,
         },
     },
-    runtime::memory::DemiBuffer,
+    runtime::{
+        memory::DemiBuffer,
+        network::types::Route,
+    },
 };
 use ::byteorder::{
     ByteOrder,
     NetworkEndian,
 };
+use ::libc::{
+    EBADMSG,
+    ENOBUFS,
+};
+use ::std::{
+    net::Ipv4Addr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
 
 //==============================================================================
 // Helper Functions
@@ -79,6 +103,23 @@ fn build_ipv4_header(
     NetworkEndian::write_u16(&mut buf[10..12], checksum.unwrap());
 }
 
+/// Builds a fragment of the datagram `id` that carries `data` at `offset`.
+fn build_fragment(id: u16, offset: usize, more_fragments: bool, data: &[u8]) -> (Ipv4Header, DemiBuffer) {
+    let header: Ipv4Header =
+        Ipv4Header::new(ALICE_IPV4, BOB_IPV4, IpProtocol::UDP).with_fragment(id, offset, more_fragments);
+    let payload: DemiBuffer = DemiBuffer::from_slice(data).expect("'data' should fit in a DemiBuffer");
+    (header, payload)
+}
+
+/// Builds a reassembly cache with default limits.
+fn build_reassembly_cache() -> ReassemblyCache {
+    ReassemblyCache::new(
+        IPV4_REASSEMBLY_TIMEOUT,
+        IPV4_REASSEMBLY_MAX_DATAGRAMS,
+        IPV4_REASSEMBLY_MAX_BYTES,
+    )
+}
+
 //==============================================================================
 // Unit-Tests for Happy Path
 //==============================================================================
@@ -131,6 +172,48 @@ fn test_ipv4_header_parse_good() {
     }
 }
 
+/// Parses IPv4 headers of fragments.
+#[test]
+fn test_ipv4_header_parse_fragment() {
+    const HEADER_SIZE: usize = 20;
+    const PAYLOAD_SIZE: usize = 8;
+    const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    // Fragments are detected by having either the MF bit set in Flags or a non-zero Fragment Offset field.
+    for (flags, fragment_offset) in [(0x1, 0), (0x1, 3), (0x0, 3)] {
+        build_ipv4_header(
+            &mut buf[..HEADER_SIZE],
+            4,
+            5,
+            0,
+            0,
+            DATAGRAM_SIZE as u16,
+            0x1d,
+            flags,
+            fragment_offset,
+            1,
+            IpProtocol::UDP as u8,
+            &ALICE_IPV4.octets(),
+            &BOB_IPV4.octets(),
+            None,
+        );
+
+        // Do it.
+        let buf_bytes: DemiBuffer = DemiBuffer::from_slice(&buf).expect("'buf' should fit in a DemiBuffer");
+        match Ipv4Header::parse(buf_bytes) {
+            Ok((ipv4_hdr, datagram)) => {
+                assert!(ipv4_hdr.is_fragment());
+                assert_eq!(ipv4_hdr.get_identification(), 0x1d);
+                assert_eq!(ipv4_hdr.has_more_fragments(), flags == 0x1);
+                assert_eq!(ipv4_hdr.get_fragment_offset(), (fragment_offset as usize) << 3);
+                assert_eq!(datagram.len(), PAYLOAD_SIZE);
+            },
+            Err(e) => assert!(false, "{:?}", e),
+        }
+    }
+}
+
 //==============================================================================
 // Unit-Tests for Invalid Path
 //==============================================================================
@@ -428,16 +511,16 @@ fn test_ipv4_header_parse_unsupported_dscp() {
     }
 }
 
-/// Parses a malformed IPv4 header with unsupported ECN field.
+/// Parses IPv4 headers with every value of the ECN field.
 #[test]
-fn test_ipv4_header_parse_unsupported_ecn() {
+fn test_ipv4_header_parse_ecn() {
     const HEADER_SIZE: usize = 20;
     const PAYLOAD_SIZE: usize = 0;
     const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
     let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
 
-    // Iterate over unsupported values for ECN.
-    for ecn in 1..3 {
+    // Iterate over all values for ECN.
+    for ecn in 0..4 {
         build_ipv4_header(
             &mut buf,
             4,
@@ -458,85 +541,12 @@ fn test_ipv4_header_parse_unsupported_ecn() {
         // Do it.
         let buf_bytes: DemiBuffer = DemiBuffer::from_slice(&buf).expect("'buf' should fit in a DemiBuffer");
         match Ipv4Header::parse(buf_bytes) {
-            Ok(_) => {},
-            Err(_) => panic!("ecn field should be ignored (ecn={:?})", ecn),
+            Ok((header, _)) => assert_eq!(header.get_ecn(), ecn),
+            Err(_) => panic!("ecn field should be parsed (ecn={:?})", ecn),
         };
     }
 }
 
-/// Parses a malformed IPv4 header with unsupported fragmentation fields.
-///
-/// TODO: Drop this test once we support fragmentation.
-#[test]
-fn test_ipv4_header_parse_unsupported_fragmentation() {
-    const HEADER_SIZE: usize = 20;
-    const PAYLOAD_SIZE: usize = 0;
-    const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
-    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
-
-    // Fragmented packets are unsupported.
-    // Fragments are detected by having either the MF bit set in Flags or a non-zero Fragment Offset field.
-    let flags: u8 = 0x1; // Set MF bit.
-    build_ipv4_header(
-        &mut buf,
-        4,
-        5,
-        0,
-        0,
-        DATAGRAM_SIZE as u16,
-        0x1d,
-        flags,
-        0,
-        1,
-        IpProtocol::UDP as u8,
-        &ALICE_IPV4.octets(),
-        &BOB_IPV4.octets(),
-        None,
-    );
-
-    // Do it.
-    let buf_bytes: DemiBuffer = DemiBuffer::from_slice(&buf).expect("'buf' should fit in a DemiBuffer");
-    match Ipv4Header::parse(buf_bytes) {
-        Ok(_) => assert!(
-            false,
-            "parsed ipv4 header with Flags={:?}. Do we support it now?",
-            flags,
-        ),
-        Err(_) => {},
-    };
-
-    // Fragmented packets are unsupported.
-    // Fragments are detected by having either the MF bit set in Flags or a non-zero Fragment Offset field.
-    let fragment_offset: u16 = 1;
-    build_ipv4_header(
-        &mut buf,
-        4,
-        5,
-        0,
-        0,
-        DATAGRAM_SIZE as u16,
-        0x1d,
-        0x2,
-        fragment_offset,
-        1,
-        IpProtocol::UDP as u8,
-        &ALICE_IPV4.octets(),
-        &BOB_IPV4.octets(),
-        None,
-    );
-
-    // Do it.
-    let buf_bytes: DemiBuffer = DemiBuffer::from_slice(&buf).expect("'buf' should fit in a DemiBuffer");
-    match Ipv4Header::parse(buf_bytes) {
-        Ok(_) => assert!(
-            false,
-            "parsed ipv4 header with fragment_offset={:?}. Do we support it now?",
-            fragment_offset,
-        ),
-        Err(_) => {},
-    };
-}
-
 /// Parses a malformed IPv4 header with unsupported protocol field.
 ///
 /// TODO: Drop this test once we support them.
@@ -551,7 +561,7 @@ fn test_ipv4_header_parse_unsupported_protocol() {
     for protocol in 0..143 {
         match protocol {
             // Skip supported protocols.
-            1 | 6 | 17 => continue,
+            1 | 2 | 6 | 17 => continue,
             _ => {
                 build_ipv4_header(
                     &mut buf,
@@ -584,3 +594,165 @@ fn test_ipv4_header_parse_unsupported_protocol() {
         };
     }
 }
+
+//==============================================================================
+// Unit-Tests for Reassembly
+//==============================================================================
+
+/// Reassembles a datagram whose fragments arrive out of order.
+#[test]
+fn test_ipv4_reassembly_out_of_order() {
+    let data: Vec<u8> = (0..24).collect();
+    let mut cache: ReassemblyCache = build_reassembly_cache();
+    let now: Instant = Instant::now();
+
+    for (offset, more_fragments) in [(16, false), (0, true)] {
+        let (header, payload) = build_fragment(1, offset, more_fragments, &data[offset..(offset + 8)]);
+        match cache.insert(header, payload, now) {
+            Ok(None) => {},
+            Ok(Some(_)) => panic!("datagram should not be complete yet"),
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    // Duplicates are ignored.
+    let (header, payload) = build_fragment(1, 0, true, &data[..8]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+
+    let (header, payload) = build_fragment(1, 8, true, &data[8..16]);
+    match cache.insert(header, payload, now) {
+        Ok(Some((ipv4_hdr, datagram))) => {
+            assert!(!ipv4_hdr.is_fragment());
+            assert_eq!(ipv4_hdr.get_src_addr(), ALICE_IPV4);
+            assert_eq!(ipv4_hdr.get_dest_addr(), BOB_IPV4);
+            assert_eq!(ipv4_hdr.get_protocol(), IpProtocol::UDP);
+            assert_eq!(datagram[..], data[..]);
+        },
+        Ok(None) => panic!("datagram should be complete"),
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
+/// Discards fragments of datagrams that are not reassembled in time.
+#[test]
+fn test_ipv4_reassembly_timeout() {
+    let data: [u8; 16] = [0xa; 16];
+    let mut cache: ReassemblyCache = build_reassembly_cache();
+    let now: Instant = Instant::now();
+
+    let (header, payload) = build_fragment(1, 0, true, &data[..8]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+
+    // The first fragment is gone once the timeout expires.
+    let now: Instant = now + IPV4_REASSEMBLY_TIMEOUT;
+    let (header, payload) = build_fragment(1, 8, false, &data[8..]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+
+    let (header, payload) = build_fragment(1, 0, true, &data[..8]);
+    assert!(cache.insert(header, payload, now).unwrap().is_some());
+}
+
+/// Bounds the number of datagrams and bytes held by the reassembly cache.
+#[test]
+fn test_ipv4_reassembly_limits() {
+    let data: [u8; 16] = [0xa; 16];
+    let now: Instant = Instant::now();
+
+    let mut cache: ReassemblyCache = ReassemblyCache::new(Duration::from_secs(1), 1, IPV4_REASSEMBLY_MAX_BYTES);
+    let (header, payload) = build_fragment(1, 0, true, &data[..8]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+    let (header, payload) = build_fragment(2, 0, true, &data[..8]);
+    assert_eq!(cache.insert(header, payload, now).unwrap_err().errno, ENOBUFS);
+
+    let mut cache: ReassemblyCache = ReassemblyCache::new(Duration::from_secs(1), 8, 8);
+    let (header, payload) = build_fragment(1, 0, true, &data[..8]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+    let (header, payload) = build_fragment(2, 0, true, &data[..8]);
+    assert_eq!(cache.insert(header, payload, now).unwrap_err().errno, ENOBUFS);
+}
+
+/// Discards datagrams with malformed or overlapping fragments.
+#[test]
+fn test_ipv4_reassembly_invalid_fragments() {
+    let data: [u8; 24] = [0xa; 24];
+    let mut cache: ReassemblyCache = build_reassembly_cache();
+    let now: Instant = Instant::now();
+
+    // Fragments other than the last one carry a multiple of 8 bytes.
+    let (header, payload) = build_fragment(1, 0, true, &data[..12]);
+    assert_eq!(cache.insert(header, payload, now).unwrap_err().errno, EBADMSG);
+
+    // Overlapping fragments discard the whole datagram.
+    let (header, payload) = build_fragment(2, 0, true, &data[..16]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+    let (header, payload) = build_fragment(2, 8, false, &data[8..24]);
+    assert_eq!(cache.insert(header, payload, now).unwrap_err().errno, EBADMSG);
+    let (header, payload) = build_fragment(2, 16, false, &data[16..]);
+    assert!(cache.insert(header, payload, now).unwrap().is_none());
+}
+
+//==============================================================================
+// Unit-Tests for Path MTU Discovery
+//==============================================================================
+
+/// Lowers the path MTU of a destination and restores it once the estimate expires.
+#[test]
+fn test_ipv4_pmtu_aging() {
+    const LINK_MTU: usize = 1500;
+    let cache: PathMtuCache = PathMtuCache::new(LINK_MTU, IPV4_PMTU_TIMEOUT);
+    let now: Instant = Instant::now();
+
+    // Only decreases are learned, and bogus values are clamped.
+    assert_eq!(cache.get(BOB_IPV4, now), LINK_MTU);
+    assert!(cache.update(BOB_IPV4, 1280, now));
+    assert!(!cache.update(BOB_IPV4, 1400, now));
+    assert!(cache.update(BOB_IPV4, 68, now));
+    assert_eq!(cache.get(BOB_IPV4, now), IPV4_PMTU_MIN);
+    assert_eq!(cache.get(ALICE_IPV4, now), LINK_MTU);
+
+    // The estimate expires.
+    assert_eq!(cache.get(BOB_IPV4, now + IPV4_PMTU_TIMEOUT), LINK_MTU);
+}
+
+//==============================================================================
+// Unit-Tests for Routing
+//==============================================================================
+
+/// Picks the next hop of the most specific route, and falls back to the destination itself when no route matches.
+#[test]
+fn test_ipv4_routing_longest_prefix_match() {
+    let gateway: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 1);
+    let other_gateway: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 254);
+    let mut table: RoutingTable = RoutingTable::new(&[
+        Route::parse("0.0.0.0/0", Some("192.168.1.1")).unwrap(),
+        Route::parse("10.0.0.0/8", Some("192.168.1.254")).unwrap(),
+        Route::parse("10.1.0.0/16", None).unwrap(),
+    ]);
+    assert_eq!(table.next_hop(Ipv4Addr::new(8, 8, 8, 8)), gateway);
+    assert_eq!(table.next_hop(Ipv4Addr::new(10, 2, 0, 1)), other_gateway);
+    assert_eq!(table.next_hop(Ipv4Addr::new(10, 1, 0, 1)), Ipv4Addr::new(10, 1, 0, 1));
+
+    // Routes are listed from the most specific one.
+    let prefixes: Vec<u8> = table.routes().iter().map(|r| r.prefix_len).collect();
+    assert_eq!(prefixes, vec![16, 8, 0]);
+
+    // A route for the same destinations replaces the existing one.
+    let old: Option<Route> = table.insert(Route::parse("10.0.0.0/8", Some("192.168.1.1")).unwrap());
+    assert_eq!(old.and_then(|r| r.gateway), Some(other_gateway));
+    assert_eq!(table.next_hop(Ipv4Addr::new(10, 2, 0, 1)), gateway);
+    assert_eq!(table.routes().len(), 3);
+
+    // Without a default route, unmatched destinations are on the local link.
+    assert!(table.remove(Ipv4Addr::UNSPECIFIED, 0).is_some());
+    assert!(table.remove(Ipv4Addr::UNSPECIFIED, 0).is_none());
+    assert_eq!(table.next_hop(Ipv4Addr::new(8, 8, 8, 8)), Ipv4Addr::new(8, 8, 8, 8));
+}
+
+/// Rejects malformed routes.
+#[test]
+fn test_ipv4_routing_invalid_routes() {
+    assert!(Route::parse("10.0.0.0/33", None).is_err());
+    assert!(Route::parse("10.0.0.1/8", None).is_err());
+    assert!(Route::parse("10.0.0.0", None).is_err());
+    assert!(Route::parse("0.0.0.0/0", Some("255.255.255.255")).is_err());
+}
@@ -2,8 +2,10 @@
 // Licensed under the MIT license.
 
 pub mod arp;
+pub mod dhcp;
 pub mod ethernet2;
 pub mod icmpv4;
+pub mod igmp;
 pub mod ip;
 pub mod ipv4;
 mod peer;
@@ -4,10 +4,23 @@
 use crate::{
     inetstack::protocols::{
         arp::ArpPeer,
+        dhcp::DhcpClient,
         icmpv4::Icmpv4Peer,
+        igmp::IgmpPeer,
         ip::IpProtocol,
-        ipv4::Ipv4Header,
-        tcp::TcpPeer,
+        ipv4::{
+            Ipv4Header,
+            PathMtuCache,
+            ReassemblyCache,
+            IPV4_PMTU_TIMEOUT,
+            IPV4_REASSEMBLY_MAX_BYTES,
+            IPV4_REASSEMBLY_MAX_DATAGRAMS,
+            IPV4_REASSEMBLY_TIMEOUT,
+        },
+        tcp::{
+            ConnectionSteering,
+            TcpPeer,
+        },
         udp::UdpPeer,
     },
     runtime::{
@@ -37,8 +50,12 @@ use ::std::{
 use crate::runtime::QDesc;
 
 pub struct Peer {
-    local_ipv4_addr: Ipv4Addr,
+    clock: TimerRc,
+    arp: ArpPeer,
+    reassembly: ReassemblyCache,
     icmpv4: Icmpv4Peer,
+    igmp: IgmpPeer,
+    pub dhcp: DhcpClient,
     pub tcp: TcpPeer,
     pub udp: UdpPeer,
 }
@@ -56,14 +73,18 @@ impl Peer {
         rng_seed: [u8; 32],
     ) -> Result<Peer, Fail> {
         let udp_offload_checksum: bool = udp_config.get_tx_checksum_offload();
+        let pmtu: PathMtuCache = PathMtuCache::new(tcp_config.get_mtu(), IPV4_PMTU_TIMEOUT);
+        let igmp: IgmpPeer = IgmpPeer::new(rt.clone(), local_link_addr, local_ipv4_addr);
+        let (shard_index, shard_count): (usize, usize) = tcp_config.get_shard();
         let udp: UdpPeer = UdpPeer::new(
             rt.clone(),
-            scheduler.clone(),
             rng_seed,
             local_link_addr,
-            local_ipv4_addr,
             udp_offload_checksum,
+            udp_config.get_mtu(),
             arp.clone(),
+            igmp.clone(),
+            ConnectionSteering::new(shard_index, shard_count, tcp_config.get_rss()),
         )?;
         let icmpv4: Icmpv4Peer = Icmpv4Peer::new(
             rt.clone(),
@@ -79,15 +100,31 @@ impl Peer {
             scheduler.clone(),
             clock.clone(),
             local_link_addr,
-            local_ipv4_addr,
             tcp_config,
-            arp,
+            arp.clone(),
+            pmtu,
             rng_seed,
         )?;
+        let dhcp: DhcpClient = DhcpClient::new(
+            rt.clone(),
+            scheduler,
+            clock.clone(),
+            local_link_addr,
+            arp.clone(),
+            rng_seed,
+        );
 
         Ok(Peer {
-            local_ipv4_addr,
+            clock,
+            arp,
+            reassembly: ReassemblyCache::new(
+                IPV4_REASSEMBLY_TIMEOUT,
+                IPV4_REASSEMBLY_MAX_DATAGRAMS,
+                IPV4_REASSEMBLY_MAX_BYTES,
+            ),
             icmpv4,
+            igmp,
+            dhcp,
             tcp,
             udp,
         })
@@ -96,13 +133,41 @@ impl Peer {
     pub fn receive(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
         let (header, payload) = Ipv4Header::parse(buf)?;
         debug!("Ipv4 received {:?}", header);
-        if header.get_dest_addr() != self.local_ipv4_addr && !header.get_dest_addr().is_broadcast() {
+        let dst_addr: Ipv4Addr = header.get_dest_addr();
+        if !self.arp.is_local_addr(dst_addr) && !dst_addr.is_broadcast() && !self.igmp.is_member(dst_addr) {
             return Err(Fail::new(ENOTCONN, "invalid destination address"));
         }
+        // Hold fragments back until the whole datagram has arrived.
+        let (header, payload) = match header.is_fragment() {
+            true => match self.reassembly.insert(header, payload, self.clock.now())? {
+                Some((header, payload)) => (header, payload),
+                None => return Ok(()),
+            },
+            false => (header, payload),
+        };
         match header.get_protocol() {
-            IpProtocol::ICMPv4 => self.icmpv4.receive(&header, payload),
+            IpProtocol::ICMPv4 => {
+                // Report errors about datagrams that we sent on the affected socket.
+                if let Some(error) = self.icmpv4.receive(&header, payload)? {
+                    match error.protocol {
+                        IpProtocol::TCP => self.tcp.receive_icmp_error(&error),
+                        IpProtocol::UDP => self.udp.receive_icmp_error(&error),
+                        _ => debug!("ignoring ICMPv4 error: {:?}", error),
+                    }
+                }
+                Ok(())
+            },
+            IpProtocol::IGMP => self.igmp.receive(&header, payload),
             IpProtocol::TCP => self.tcp.receive(&header, payload),
-            IpProtocol::UDP => self.udp.do_receive(&header, payload),
+            IpProtocol::UDP if self.dhcp.is_client_datagram(&payload) => self.dhcp.receive(&header, payload),
+            IpProtocol::UDP => match self.udp.do_receive(&header, payload.clone()) {
+                // No socket is bound to the destination port.
+                Err(e) if e.errno == ENOTCONN => {
+                    self.icmpv4.send_port_unreachable(&header, &payload[..]);
+                    Err(e)
+                },
+                result => result,
+            },
         }
     }
 
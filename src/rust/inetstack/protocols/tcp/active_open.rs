@@ -11,14 +11,14 @@ use crate::{
                 Ethernet2Header,
             },
             ip::IpProtocol,
-            ipv4::Ipv4Header,
+            ipv4::{
+                Ipv4Header,
+                PathMtuCache,
+            },
             tcp::{
                 constants::FALLBACK_MSS,
                 established::{
-                    congestion_control::{
-                        self,
-                        CongestionControl,
-                    },
+                    congestion_control::CongestionControlConstructor,
                     ControlBlock,
                 },
                 segment::{
@@ -26,6 +26,7 @@ use crate::{
                     TcpOptions2,
                     TcpSegment,
                 },
+                timestamp::timestamp_value,
                 SeqNumber,
             },
         },
@@ -40,6 +41,7 @@ use crate::{
         timer::TimerRc,
     },
     scheduler::{
+        Priority,
         Scheduler,
         SchedulerHandle,
     },
@@ -60,6 +62,7 @@ use ::std::{
         Poll,
         Waker,
     },
+    time::Instant,
 };
 
 struct ConnectResult {
@@ -79,6 +82,13 @@ pub struct ActiveOpenSocket {
     local_link_addr: MacAddress,
     tcp_config: TcpConfig,
     arp: ArpPeer,
+    pmtu: PathMtuCache,
+
+    // Origin of our timestamp clock for this connection.
+    ts_base: Instant,
+
+    // Congestion control algorithm for this connection.
+    cc_constructor: CongestionControlConstructor,
 
     #[allow(unused)]
     handle: SchedulerHandle,
@@ -96,12 +106,15 @@ impl ActiveOpenSocket {
         local_link_addr: MacAddress,
         clock: TimerRc,
         arp: ArpPeer,
+        pmtu: PathMtuCache,
+        cc_constructor: CongestionControlConstructor,
     ) -> Self {
         let result = ConnectResult {
             waker: None,
             result: None,
         };
         let result = Rc::new(RefCell::new(result));
+        let ts_base: Instant = clock.now();
 
         let future = Self::background(
             local_isn,
@@ -112,12 +125,14 @@ impl ActiveOpenSocket {
             local_link_addr,
             tcp_config.clone(),
             arp.clone(),
+            ts_base,
             result.clone(),
         );
-        let handle: SchedulerHandle = match scheduler.insert(FutureOperation::Background(future.boxed_local())) {
-            Some(handle) => handle,
-            None => panic!("failed to insert task in the scheduler"),
-        };
+        let handle: SchedulerHandle =
+            match scheduler.insert(FutureOperation::Background(future.boxed_local()), Priority::High) {
+                Some(handle) => handle,
+                None => panic!("failed to insert task in the scheduler"),
+            };
 
         // TODO: Add fast path here when remote is already in the ARP cache (and subtract one retry).
         Self {
@@ -130,6 +145,9 @@ impl ActiveOpenSocket {
             local_link_addr,
             tcp_config,
             arp,
+            pmtu,
+            ts_base,
+            cc_constructor,
             handle,
             result,
         }
@@ -154,11 +172,45 @@ impl ActiveOpenSocket {
         r.result.replace(result);
     }
 
+    fn send_rst(&self, header: &TcpHeader) {
+        let tcp_hdr: TcpHeader = match TcpHeader::new_reset(header, 0) {
+            Some(tcp_hdr) => tcp_hdr,
+            None => return,
+        };
+        let remote_link_addr: MacAddress = match self.arp.try_query(self.remote.ip().clone()) {
+            Some(r) => r,
+            None => return,
+        };
+        debug!("Sending RST: {:?}", tcp_hdr);
+        let segment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
+        };
+        self.rt.transmit(Box::new(segment));
+    }
+
+    /// Handles an ICMP error about a SYN that carried `seq_num`, which aborts the connection attempt. Errors that do
+    /// not quote our SYN are ignored, so that blind attackers cannot forge them (see RFC 5927).
+    pub fn receive_icmp_error(&mut self, seq_num: SeqNumber, error: Fail) {
+        if seq_num != self.local_isn {
+            debug!("Ignoring ICMP error for a segment that we did not send");
+            return;
+        }
+        self.set_result(Err(error));
+    }
+
     pub fn receive(&mut self, header: &TcpHeader) {
         let expected_seq = self.local_isn + SeqNumber::from(1);
 
-        // Bail if we didn't receive a ACK packet with the right sequence number.
+        // Bail if we didn't receive a ACK packet with the right sequence number.  An ACK for something that we did not
+        // send belongs to some old connection, so we answer it with a RST (see RFC 793 Section 3.4).
         if !(header.ack && header.ack_num == expected_seq) {
+            if header.ack {
+                self.send_rst(header);
+            }
             return;
         }
 
@@ -182,11 +234,23 @@ impl ActiveOpenSocket {
         };
         let remote_seq_num = header.seq_num + SeqNumber::from(1);
 
+        // Our peer agrees to use timestamps if it echoes ours (see RFC 7323).
+        let ts_recent: Option<u32> = header.iter_options().find_map(|option| match option {
+            TcpOptions2::Timestamp { sender_timestamp, .. } => Some(*sender_timestamp),
+            _ => None,
+        });
+
         let mut tcp_hdr = TcpHeader::new(self.local.port(), self.remote.port());
         tcp_hdr.ack = true;
         tcp_hdr.ack_num = remote_seq_num;
         tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
         tcp_hdr.seq_num = self.local_isn + SeqNumber::from(1);
+        if let Some(ts_recent) = ts_recent {
+            tcp_hdr.push_option(TcpOptions2::Timestamp {
+                sender_timestamp: timestamp_value(self.ts_base, self.clock.now()),
+                echo_timestamp: ts_recent,
+            });
+        }
         debug!("Sending ACK: {:?}", tcp_hdr);
 
         let segment = TcpSegment {
@@ -200,6 +264,7 @@ impl ActiveOpenSocket {
 
         let mut remote_window_scale = None;
         let mut mss = FALLBACK_MSS;
+        let mut sack_permitted: bool = false;
         for option in header.iter_options() {
             match option {
                 TcpOptions2::WindowScale(w) => {
@@ -210,10 +275,17 @@ impl ActiveOpenSocket {
                     info!("Received advertised MSS: {}", m);
                     mss = *m as usize;
                 },
+                TcpOptions2::SelectiveAcknowlegementPermitted => {
+                    info!("Received SACK permitted");
+                    sack_permitted = true;
+                },
                 _ => continue,
             }
         }
 
+        // Our peer agrees to use ECN if it answers our request with ECE alone (see RFC 3168).
+        let ecn: bool = self.tcp_config.get_ecn() && header.ece && !header.cwr;
+
         let (local_window_scale, remote_window_scale) = match remote_window_scale {
             Some(w) => (self.tcp_config.get_window_scale() as u32, w),
             None => (0, 0),
@@ -249,6 +321,7 @@ impl ActiveOpenSocket {
             self.local_link_addr,
             self.tcp_config.clone(),
             self.arp.clone(),
+            self.pmtu.clone(),
             remote_seq_num,
             self.tcp_config.get_ack_delay_timeout(),
             rx_window_size,
@@ -257,7 +330,11 @@ impl ActiveOpenSocket {
             tx_window_size,
             remote_window_scale,
             mss,
-            congestion_control::None::new,
+            sack_permitted,
+            self.ts_base,
+            ts_recent,
+            ecn,
+            self.cc_constructor,
             None,
         );
         self.set_result(Ok(cb));
@@ -272,6 +349,7 @@ impl ActiveOpenSocket {
         local_link_addr: MacAddress,
         tcp_config: TcpConfig,
         arp: ArpPeer,
+        ts_base: Instant,
         result: Rc<RefCell<ConnectResult>>,
     ) -> impl Future<Output = ()> {
         let handshake_retries: usize = tcp_config.get_handshake_retries();
@@ -299,6 +377,20 @@ impl ActiveOpenSocket {
                 tcp_hdr.push_option(TcpOptions2::WindowScale(tcp_config.get_window_scale()));
                 info!("Advertising window scale: {}", tcp_config.get_window_scale());
 
+                tcp_hdr.push_option(TcpOptions2::SelectiveAcknowlegementPermitted);
+                info!("Advertising SACK permitted");
+
+                // Request Explicit Congestion Notification (see RFC 3168).
+                if tcp_config.get_ecn() {
+                    tcp_hdr.ece = true;
+                    tcp_hdr.cwr = true;
+                }
+
+                tcp_hdr.push_option(TcpOptions2::Timestamp {
+                    sender_timestamp: timestamp_value(ts_base, clock.now()),
+                    echo_timestamp: 0,
+                });
+
                 debug!("Sending SYN {:?}", tcp_hdr);
                 let segment = TcpSegment {
                     ethernet2_hdr: Ethernet2Header::new(remote_link_addr, local_link_addr, EtherType2::Ipv4),
@@ -0,0 +1,130 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file implements the autotuning of send and receive buffers.
+//
+// Fixed-size buffers either waste memory on slow connections, or throttle connections with a large bandwidth-delay
+// product.  Instead, we size buffers after the rate at which data actually flows, in the spirit of Linux's
+// tcp_moderate_rcvbuf.  The receive buffer is grown to twice the amount of data that the user read over the last round
+// trip (see "Dynamic Right-Sizing for TCP", Fisk and Feng), so that our advertised window does not hold back the
+// sender.  The send buffer is grown to twice the congestion window, so that the user can keep the pipe full.  Buffers
+// never shrink on their own, and always stay within the bounds set by the user.
+//
+
+use crate::inetstack::protocols::tcp::SeqNumber;
+use ::std::{
+    cell::Cell,
+    cmp,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+// Size of a buffer that grows within user-set bounds.
+#[derive(Debug)]
+pub struct BufferSize {
+    size: Cell<u32>,
+    min: Cell<u32>,
+    max: Cell<u32>,
+}
+
+impl BufferSize {
+    // Creates a buffer of `size` bytes, which does not grow until it is given wider bounds.
+    pub fn new(size: u32) -> Self {
+        Self {
+            size: Cell::new(size),
+            min: Cell::new(size),
+            max: Cell::new(size),
+        }
+    }
+
+    pub fn get(&self) -> u32 {
+        self.size.get()
+    }
+
+    // Resizes the buffer to `size` bytes, and sets the bounds within which it grows from there.
+    pub fn set(&self, size: u32, min: u32, max: u32) {
+        debug_assert!(min <= max);
+        self.min.set(min);
+        self.max.set(max);
+        self.size.set(size.clamp(min, max));
+    }
+
+    // Grows the buffer to `target` bytes, if it is smaller and the bounds allow it.
+    pub fn grow(&self, target: u32) {
+        let target: u32 = cmp::min(target, self.max.get());
+        if target > self.size.get() {
+            self.size.set(target);
+        }
+    }
+}
+
+// Measures the rate at which the user reads data from a connection, to right-size its receive buffer.
+#[derive(Debug)]
+pub struct ReceiveAutotuner {
+    // Receive-side estimate of the round-trip time: the time it takes our peer to send a full window of data after we
+    // advertised it.  This also works when we send no data, and thus do not get RTT samples from ACKs.
+    rtt: Cell<Option<Duration>>,
+    // Sequence number that ends the window being timed, and when we advertised it.
+    rtt_probe: Cell<Option<(SeqNumber, Instant)>>,
+    // Sequence number of the first byte read in the current round trip, and when the round trip started.
+    read_start: Cell<(SeqNumber, Instant)>,
+    // Largest amount of data read in a single round trip so far.
+    read_space: Cell<u32>,
+}
+
+impl ReceiveAutotuner {
+    pub fn new(reader_next: SeqNumber, now: Instant) -> Self {
+        Self {
+            rtt: Cell::new(None),
+            rtt_probe: Cell::new(None),
+            read_start: Cell::new((reader_next, now)),
+            read_space: Cell::new(0),
+        }
+    }
+
+    pub fn get_rtt(&self) -> Option<Duration> {
+        self.rtt.get()
+    }
+
+    // Accounts for in-order data received at `now`, given our current receive window.
+    pub fn on_receive(&self, receive_next: SeqNumber, receive_window: u32, now: Instant) {
+        match self.rtt_probe.get() {
+            Some((end, start)) if receive_next >= end => {
+                let sample: Duration = now.saturating_duration_since(start);
+                // Like Linux, we favor smaller samples, since the window may not have been the only thing that held
+                // our peer back.
+                let rtt: Duration = match self.rtt.get() {
+                    Some(rtt) if sample >= rtt => (rtt * 7 + sample) / 8,
+                    _ => sample,
+                };
+                self.rtt.set(Some(rtt));
+                self.rtt_probe.set(None);
+            },
+            Some(_) => (),
+            None => self
+                .rtt_probe
+                .set(Some((receive_next + SeqNumber::from(receive_window), now))),
+        }
+    }
+
+    // Accounts for data read by the user up to `reader_next` at `now`.  Once per round trip, returns the size that the
+    // receive buffer should have to keep up with the user.
+    pub fn on_read(&self, reader_next: SeqNumber, now: Instant) -> Option<u32> {
+        let rtt: Duration = self.rtt.get()?;
+        let (start_seq_no, start_time): (SeqNumber, Instant) = self.read_start.get();
+        if now.saturating_duration_since(start_time) < rtt {
+            return None;
+        }
+        self.read_start.set((reader_next, now));
+
+        let copied: u32 = (reader_next - start_seq_no).into();
+        if copied <= self.read_space.get() {
+            return None;
+        }
+        self.read_space.set(copied);
+        // Leave room for the sender to grow its congestion window over the next round trip.
+        Some(copied.saturating_mul(2))
+    }
+}
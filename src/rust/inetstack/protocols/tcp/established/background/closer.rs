@@ -0,0 +1,35 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use super::ControlBlock;
+use crate::runtime::fail::Fail;
+use ::futures::{
+    future::{
+        self,
+        Either,
+    },
+    FutureExt,
+};
+use ::std::rc::Rc;
+
+pub async fn closer(cb: Rc<ControlBlock>) -> Result<!, Fail> {
+    loop {
+        // Wait in TIME-WAIT for 2 MSL, so that any segments of this connection still in the network are gone before
+        // its endpoints get reused.  The timer is restarted whenever our peer retransmits its FIN.
+        let (time_wait_deadline, time_wait_deadline_changed) = cb.watch_time_wait_deadline();
+        futures::pin_mut!(time_wait_deadline_changed);
+
+        let time_wait_future = match time_wait_deadline {
+            Some(t) => Either::Left(cb.clock.wait_until(cb.clock.clone(), t).fuse()),
+            None => Either::Right(future::pending()),
+        };
+        futures::pin_mut!(time_wait_future);
+
+        futures::select_biased! {
+            _ = time_wait_deadline_changed => continue,
+            _ = time_wait_future => {
+                cb.finish_time_wait();
+            },
+        }
+    }
+}
@@ -2,11 +2,13 @@
 // Licensed under the MIT license.
 
 mod acknowledger;
+mod closer;
 mod retransmitter;
 mod sender;
 
 use self::{
     acknowledger::acknowledger,
+    closer::closer,
     retransmitter::retransmitter,
     sender::sender,
 };
@@ -23,7 +25,7 @@ use ::std::{
 
 pub type BackgroundFuture = impl Future<Output = ()>;
 
-pub fn background(cb: Rc<ControlBlock>, fd: QDesc, _dead_socket_tx: mpsc::UnboundedSender<QDesc>) -> BackgroundFuture {
+pub fn background(cb: Rc<ControlBlock>, fd: QDesc, dead_socket_tx: mpsc::UnboundedSender<QDesc>) -> BackgroundFuture {
     async move {
         let acknowledger = acknowledger(cb.clone()).fuse();
         futures::pin_mut!(acknowledger);
@@ -34,16 +36,20 @@ pub fn background(cb: Rc<ControlBlock>, fd: QDesc, _dead_socket_tx: mpsc::Unboun
         let sender = sender(cb.clone()).fuse();
         futures::pin_mut!(sender);
 
+        let closer = closer(cb.clone()).fuse();
+        futures::pin_mut!(closer);
+
         let r = futures::select_biased! {
             r = acknowledger => r,
             r = retransmitter => r,
             r = sender => r,
+            r = closer => r,
         };
         error!("Connection (fd {:?}) terminated: {:?}", fd, r);
 
-        // TODO Properly clean up Peer state for this connection.
-        // dead_socket_tx
-        //     .unbounded_send(fd)
-        //     .expect("Failed to terminate connection");
+        // Let the peer forget about this connection once its socket is closed. The peer is gone if this fails.
+        if dead_socket_tx.unbounded_send(fd).is_err() {
+            debug!("peer of connection (fd {:?}) is gone", fd);
+        }
     }
 }
@@ -6,6 +6,7 @@ use crate::{
     inetstack::protocols::tcp::segment::TcpHeader,
     runtime::{
         fail::Fail,
+        memory::DemiBuffer,
         network::types::MacAddress,
     },
 };
@@ -16,6 +17,7 @@ use ::futures::{
     },
     FutureExt,
 };
+use ::libc::ETIMEDOUT;
 use ::std::{
     rc::Rc,
     time::{
@@ -31,14 +33,9 @@ pub enum RetransmitCause {
 }
 
 async fn retransmit(cause: RetransmitCause, cb: &Rc<ControlBlock>) -> Result<(), Fail> {
-    // ToDo: Handle retransmission of FIN.
-
-    // ToDo: Fix this routine.  It is currently trashing our unacknowledged queue state.  It shouldn't remove any
-    // unacknowledged data from the unacknowledged queue, as retransmiting data doesn't magically make it acknowledged.
-    // Any sent data, whether sent once or multiple times, must remain on the unacknowledged queue until it is ACKed.
-
-    // Pop unack'ed segment.
-    let mut segment = match cb.pop_unacked_segment() {
+    // Fetch the oldest unack'ed segment.  Any sent data, whether sent once or multiple times, remains on the
+    // unacknowledged queue until it is ACKed.
+    let bytes: DemiBuffer = match cb.first_unacked_segment() {
         Some(s) => s,
         None => {
             // We shouldn't enter the retransmit routine with an empty unacknowledged queue.  So maybe we should assert
@@ -57,21 +54,26 @@ async fn retransmit(cause: RetransmitCause, cb: &Rc<ControlBlock>) -> Result<(),
 
     // NOTE: Congestion Control Don't think we record a failure on Fast Retransmit, but can't find a definitive source.
     match cause {
-        RetransmitCause::TimeOut => cb.rto_record_failure(),
+        RetransmitCause::TimeOut => {
+            cb.rto_record_failure();
+            cb.restart_loss_recovery();
+        },
         RetransmitCause::FastRetransmit => (),
     };
 
     // Our retransmission timer fired, so we need to resend a packet.
     let remote_link_addr: MacAddress = cb.arp().query(cb.get_remote().ip().clone()).await?;
 
-    // Unset the initial timestamp so we don't use this for RTT estimation.
-    segment.initial_tx.take();
-
     // Prepare and send the segment.
     let (seq_no, _) = cb.get_send_unacked();
     let mut header: TcpHeader = cb.tcp_header();
     header.seq_num = seq_no;
-    cb.emit(header, Some(segment.bytes), remote_link_addr);
+    if bytes.len() == 0 {
+        // This buffer is the end-of-send marker, so retransmit our FIN.
+        header.fin = true;
+    }
+    cb.emit_retransmission(header, Some(bytes), remote_link_addr);
+    cb.record_retransmitted_segment();
 
     // Set new retransmit deadline.
     // ToDo: Review this.  Shouldn't we only do this for RetransmitCause::Timeout?
@@ -97,7 +99,10 @@ pub async fn retransmitter(cb: Rc<ControlBlock>) -> Result<!, Fail> {
         let (rtx_fast_retransmit, rtx_fast_retransmit_changed) = cb.congestion_control_watch_retransmit_now_flag();
         if rtx_fast_retransmit {
             cb.congestion_control_on_fast_retransmit();
-            retransmit(RetransmitCause::FastRetransmit, &cb).await?;
+            // With SACK, lost segments are retransmitted as their loss is detected on the receive path.
+            if !cb.sack_permitted() {
+                retransmit(RetransmitCause::FastRetransmit, &cb).await?;
+            }
             continue;
         }
         futures::pin_mut!(rtx_fast_retransmit_changed);
@@ -107,11 +112,14 @@ pub async fn retransmitter(cb: Rc<ControlBlock>) -> Result<!, Fail> {
             _ = rtx_fast_retransmit_changed => continue,
             _ = rtx_future => {
                 trace!("Retransmission Timer Expired");
+                if !cb.record_retransmission() {
+                    // Our peer has not acknowledged anything for too long, so give up on this connection.
+                    cb.abort(ETIMEDOUT);
+                    return Err(Fail::new(ETIMEDOUT, "connection timed out"));
+                }
                 let (send_unacknowledged, _) = cb.get_send_unacked();
                 cb.congestion_control_on_rto(send_unacknowledged);
-                // ToDo: Fix retransmit routine, uncomment next line and delete subsequent line.
-                // retransmit(RetransmitCause::TimeOut, &cb).await?;
-                cb.set_retransmit_deadline(None);
+                retransmit(RetransmitCause::TimeOut, &cb).await?;
             },
         }
     }
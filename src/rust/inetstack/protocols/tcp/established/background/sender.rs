@@ -60,6 +60,7 @@ pub async fn sender(cb: Rc<ControlBlock>) -> Result<!, Fail> {
             let unacked_segment = UnackedSegment {
                 bytes: buf.clone(),
                 initial_tx: Some(cb.clock.now()),
+                sacked: false,
             };
             cb.push_unacked_segment(unacked_segment);
 
@@ -80,7 +81,7 @@ pub async fn sender(cb: Rc<ControlBlock>) -> Result<!, Fail> {
                 // Retransmit our window probe.
                 let mut header: TcpHeader = cb.tcp_header();
                 header.seq_num = send_next;
-                cb.emit(header, Some(buf.clone()), remote_link_addr);
+                cb.emit_retransmission(header, Some(buf.clone()), remote_link_addr);
             }
         }
 
@@ -114,9 +115,30 @@ pub async fn sender(cb: Rc<ControlBlock>) -> Result<!, Fail> {
             }
         }
 
+        // Hold partial segments back until more data arrives to fill them, the data in flight gets acknowledged, or the
+        // user changes their mind.
+        let unsent_bytes: u32 = (unsent_seq - send_next).into();
+        if cb.hold_partial_segment(unsent_bytes as usize, sent_data) {
+            let (_, nodelay_changed) = cb.watch_nodelay();
+            futures::pin_mut!(nodelay_changed);
+            let (_, cork_changed) = cb.watch_cork();
+            futures::pin_mut!(cork_changed);
+            futures::select_biased! {
+                _ = unsent_seq_changed => continue 'top,
+                _ = send_unacked_changed => continue 'top,
+                _ = nodelay_changed => continue 'top,
+                _ = cork_changed => continue 'top,
+            }
+        }
+
+        // Hold new segments back until the pacer releases them.
+        if let Some(delay) = cb.pacing_delay() {
+            cb.clock.wait(cb.clock.clone(), delay).await;
+            continue 'top;
+        }
+
         // Past this point we have data to send and it's valid to send it!
 
-        // TODO: Nagle's algorithm - We need to coalese small buffers together to send MSS sized packets.
         // TODO: Silly window syndrome - See RFC 1122's discussion of the SWS avoidance algorithm.
 
         // ToDo: Link-level concerns don't belong here, we should call an IP-level send routine below.
@@ -146,6 +168,7 @@ pub async fn sender(cb: Rc<ControlBlock>) -> Result<!, Fail> {
             segment_data_len = 1;
         }
         cb.emit(header, Some(segment_data.clone()), remote_link_addr);
+        cb.pacing_on_send(segment_data_len);
 
         // Update SND.NXT.
         cb.modify_send_next(|s| s + SeqNumber::from(segment_data_len));
@@ -154,6 +177,7 @@ pub async fn sender(cb: Rc<ControlBlock>) -> Result<!, Fail> {
         let unacked_segment = UnackedSegment {
             bytes: segment_data,
             initial_tx: Some(cb.clock.now()),
+            sacked: false,
         };
         cb.push_unacked_segment(unacked_segment);
 
@@ -58,6 +58,7 @@ pub struct Cubic {
     pub in_fast_recovery: Cell<bool>,   // Are we currently in the `fast recovery` algorithm.
     pub prev_ack_seq_no: Cell<SeqNumber>, // The previous highest ACK sequence number.
     pub recover: Cell<SeqNumber>, // If we receive dup ACKs with sequence numbers greater than this we'll attempt fast recovery.
+    pub ecn_recover: Cell<SeqNumber>, // Congestion marks for data sent before this were already responded to.
 
     pub limited_transmit_cwnd_increase: WatchedValue<u32>, // The amount by which cwnd should be increased due to the limited transit algorithm.
 }
@@ -94,6 +95,7 @@ impl CongestionControl for Cubic {
             recover: Cell::new(seq_no), // Recover set to initial send sequence number according to RFC6582.
             prev_ack_seq_no: Cell::new(seq_no), // RFC6582 doesn't specify the initial value, but this seems sensible.
             duplicate_ack_count: Cell::new(0),
+            ecn_recover: Cell::new(seq_no),
 
             limited_transmit_cwnd_increase: WatchedValue::new(0),
         })
@@ -141,17 +143,7 @@ impl Cubic {
             && (ack_covers_recover || retransmitted_packet_dropped_heuristic)
         {
             // Check against recover specified in RFC6582.
-            self.in_fast_recovery.set(true);
-            self.recover.set(send_next);
-            let reduced_cwnd: u32 = (cwnd as f32 * Self::BETA_CUBIC) as u32;
-
-            if self.fast_convergence {
-                self.fast_convergence();
-            } else {
-                self.w_max.set(cwnd);
-            }
-            self.ssthresh.set(max(reduced_cwnd, 2 * self.mss));
-            self.cwnd.set(reduced_cwnd);
+            self.enter_fast_recovery(send_next);
             self.fast_retransmit_now.set(true);
             // We don't reset ca_start here even though cwnd has been shrunk because we aren't going
             // straight back into congestion avoidance.
@@ -160,6 +152,21 @@ impl Cubic {
         }
     }
 
+    fn enter_fast_recovery(&self, send_next: SeqNumber) {
+        let cwnd: u32 = self.cwnd.get();
+        self.in_fast_recovery.set(true);
+        self.recover.set(send_next);
+        let reduced_cwnd: u32 = (cwnd as f32 * Self::BETA_CUBIC) as u32;
+
+        if self.fast_convergence {
+            self.fast_convergence();
+        } else {
+            self.w_max.set(cwnd);
+        }
+        self.ssthresh.set(max(reduced_cwnd, 2 * self.mss));
+        self.cwnd.set(reduced_cwnd);
+    }
+
     fn on_ack_received_fast_recovery(&self, send_unacked: SeqNumber, send_next: SeqNumber, ack_seq_no: SeqNumber) {
         let bytes_outstanding: u32 = (send_next - send_unacked).into();
         let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
@@ -283,6 +290,10 @@ impl SlowStartCongestionAvoidance for Cubic {
         self.cwnd.watch()
     }
 
+    fn get_ssthresh(&self) -> u32 {
+        self.ssthresh.get()
+    }
+
     fn on_cwnd_check_before_send(&self) {
         let long_time_since_send: bool =
             Instant::now().duration_since(self.last_send_time.get()) > self.rtt_at_last_send.get();
@@ -328,6 +339,40 @@ impl SlowStartCongestionAvoidance for Cubic {
         self.on_rto_ss_ca();
         self.on_rto_fast_recovery(send_unacked);
     }
+
+    fn on_loss(&self, send_next: SeqNumber) {
+        // We only reduce cwnd once per window of data, so losses detected during recovery are ignored.
+        if !self.in_fast_recovery.get() {
+            self.enter_fast_recovery(send_next);
+        }
+    }
+
+    fn on_ecn_feedback(
+        &self,
+        _send_unacked: SeqNumber,
+        send_next: SeqNumber,
+        ack_seq_no: SeqNumber,
+        ece: bool,
+    ) -> bool {
+        // Congestion marks call for the same reduction of cwnd as losses, but nothing needs to be retransmitted.  As
+        // with losses, we only respond once per window of data (see RFC 3168).
+        if !ece || self.in_fast_recovery.get() || ack_seq_no <= self.ecn_recover.get() {
+            return false;
+        }
+        let cwnd: u32 = self.cwnd.get();
+        if self.fast_convergence {
+            self.fast_convergence();
+        } else {
+            self.w_max.set(cwnd);
+        }
+        self.ssthresh
+            .set(max((cwnd as f32 * Self::BETA_CUBIC) as u32, 2 * self.mss));
+        self.cwnd.set(self.ssthresh.get());
+        self.ca_start.set(Instant::now());
+        self.last_congestion_was_rto.set(false);
+        self.ecn_recover.set(send_next);
+        true
+    }
 }
 
 impl FastRetransmitRecovery for Cubic {
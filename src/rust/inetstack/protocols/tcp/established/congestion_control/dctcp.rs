@@ -0,0 +1,258 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This is an implementation of DCTCP (see RFC 8257), on top of the standard congestion control algorithm (see RFC 5681)
+// and the NewReno modification to fast recovery (see RFC 6582).  Rather than halving cwnd whenever the network signals
+// congestion through Explicit Congestion Notification, DCTCP estimates the fraction of data that got marked over the
+// last window (alpha), and shrinks cwnd in proportion to it.  This keeps switch queues short without giving up much
+// throughput, provided that switches mark packets as soon as their queue exceeds a shallow threshold.  DCTCP is meant
+// for datacenter networks, and it should not compete with other algorithms on the open Internet.
+
+use super::{
+    CongestionControl,
+    FastRetransmitRecovery,
+    LimitedTransmit,
+    Options,
+    SlowStartCongestionAvoidance,
+};
+use crate::{
+    inetstack::protocols::tcp::SeqNumber,
+    runtime::watched::{
+        WatchFuture,
+        WatchedValue,
+    },
+};
+use ::std::{
+    cell::Cell,
+    cmp::{
+        max,
+        min,
+    },
+    convert::TryInto,
+    fmt::Debug,
+    time::Duration,
+};
+
+#[derive(Debug)]
+pub struct Dctcp {
+    mss: u32,
+    // Slow Start / Congestion Avoidance State.
+    cwnd: WatchedValue<u32>, // Congestion window: Max number of bytes that may be in flight to prevent congestion.
+    ssthresh: Cell<u32>,     // The size of cwnd at which we will change from using slow start to congestion avoidance.
+    bytes_acked_in_ca: Cell<u32>, // Bytes acknowledged during congestion avoidance since cwnd was last increased.
+
+    // Fast Recovery / Fast Retransmit State
+    duplicate_ack_count: Cell<u32>, // The number of consecutive duplicate ACKs we've received.
+    fast_retransmit_now: WatchedValue<bool>, // Flag to cause the retransmitter to retransmit a segment now.
+    in_fast_recovery: Cell<bool>,   // Are we currently in the `fast recovery` algorithm.
+    recover: Cell<SeqNumber>,       // Highest sequence number sent when we entered fast recovery.
+
+    // Congestion Marking State
+    alpha: Cell<f64>,             // Estimate of the fraction of bytes that get marked.
+    g: f64,                       // Weight given to new samples when updating alpha.
+    bytes_acked: Cell<u32>,       // Bytes acknowledged in the current observation window.
+    bytes_marked: Cell<u32>,      // Bytes acknowledged with a congestion mark in the current observation window.
+    window_end: Cell<SeqNumber>,  // The current observation window ends once this sequence number is acknowledged.
+    ecn_recover: Cell<SeqNumber>, // Congestion marks for data sent before this were already responded to.
+}
+
+impl CongestionControl for Dctcp {
+    fn new(mss: usize, seq_no: SeqNumber, options: Option<Options>) -> Box<dyn CongestionControl> {
+        let mss: u32 = mss.try_into().unwrap();
+        // The initial value of cwnd is set according to RFC5681, section 3.1, page 7.
+        let initial_cwnd: u32 = match mss {
+            0..=1095 => 4 * mss,
+            1096..=2190 => 3 * mss,
+            _ => 2 * mss,
+        };
+
+        let options: Options = options.unwrap_or_default();
+        let g: f64 = options.get_float("g").unwrap_or(Self::DEFAULT_G);
+
+        Box::new(Self {
+            mss,
+            cwnd: WatchedValue::new(initial_cwnd),
+            ssthresh: Cell::new(u32::MAX), // According to RFC5681 ssthresh should be initialised 'arbitrarily high'.
+            bytes_acked_in_ca: Cell::new(0),
+
+            duplicate_ack_count: Cell::new(0),
+            fast_retransmit_now: WatchedValue::new(false),
+            in_fast_recovery: Cell::new(false),
+            recover: Cell::new(seq_no),
+
+            alpha: Cell::new(1.0), // Start out conservatively, as if every byte was marked (see RFC 8257).
+            g,
+            bytes_acked: Cell::new(0),
+            bytes_marked: Cell::new(0),
+            window_end: Cell::new(seq_no),
+            ecn_recover: Cell::new(seq_no),
+        })
+    }
+}
+
+impl Dctcp {
+    const DEFAULT_G: f64 = 1.0 / 16.0;
+    const DUP_ACK_THRESHOLD: u32 = 3;
+
+    // Halves cwnd in response to a loss (see RFC 5681).
+    fn reduce_cwnd_on_loss(&self, send_next: SeqNumber) {
+        self.ssthresh.set(max(self.cwnd.get() / 2, 2 * self.mss));
+        self.cwnd.set(self.ssthresh.get());
+        self.in_fast_recovery.set(true);
+        self.recover.set(send_next);
+    }
+
+    fn on_dup_ack_received(&self, send_next: SeqNumber) {
+        let duplicate_ack_count: u32 = self.duplicate_ack_count.get() + 1;
+        self.duplicate_ack_count.set(duplicate_ack_count);
+
+        if duplicate_ack_count == Self::DUP_ACK_THRESHOLD && !self.in_fast_recovery.get() {
+            self.reduce_cwnd_on_loss(send_next);
+            // Account for the segments that have left the network.
+            self.cwnd.modify(|c| c + Self::DUP_ACK_THRESHOLD * self.mss);
+            self.fast_retransmit_now.set(true);
+        } else if self.in_fast_recovery.get() {
+            self.cwnd.modify(|c| c + self.mss);
+        }
+    }
+
+    fn on_ack_received_fast_recovery(&self, send_unacked: SeqNumber, send_next: SeqNumber, ack_seq_no: SeqNumber) {
+        let bytes_outstanding: u32 = (send_next - send_unacked).into();
+        let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
+
+        if ack_seq_no > self.recover.get() {
+            // Full acknowledgement.
+            self.cwnd
+                .set(min(self.ssthresh.get(), max(bytes_outstanding, self.mss) + self.mss));
+            self.in_fast_recovery.set(false);
+        } else {
+            // Partial acknowledgement.
+            self.fast_retransmit_now.set(true);
+            self.cwnd
+                .modify(|c| c.saturating_sub(bytes_acknowledged) + min(bytes_acknowledged, self.mss));
+        }
+    }
+
+    fn on_ack_received_ss_ca(&self, bytes_acknowledged: u32) {
+        let cwnd: u32 = self.cwnd.get();
+        if cwnd < self.ssthresh.get() {
+            // Slow start.
+            self.cwnd.modify(|c| c + min(bytes_acknowledged, self.mss));
+        } else {
+            // Congestion avoidance: grow cwnd by one MSS per window of acknowledged data.
+            let bytes_acked_in_ca: u32 = self.bytes_acked_in_ca.get() + bytes_acknowledged;
+            if bytes_acked_in_ca >= cwnd {
+                self.bytes_acked_in_ca.set(bytes_acked_in_ca - cwnd);
+                self.cwnd.modify(|c| c + self.mss);
+            } else {
+                self.bytes_acked_in_ca.set(bytes_acked_in_ca);
+            }
+        }
+    }
+}
+
+impl SlowStartCongestionAvoidance for Dctcp {
+    fn get_cwnd(&self) -> u32 {
+        self.cwnd.get()
+    }
+
+    fn watch_cwnd(&self) -> (u32, WatchFuture<'_, u32>) {
+        self.cwnd.watch()
+    }
+
+    fn get_ssthresh(&self) -> u32 {
+        self.ssthresh.get()
+    }
+
+    fn on_ack_received(&self, _rto: Duration, send_unacked: SeqNumber, send_next: SeqNumber, ack_seq_no: SeqNumber) {
+        if ack_seq_no > send_next {
+            // This ACK acknowledges data we have yet to send.
+            return;
+        }
+        let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
+        if bytes_acknowledged == 0 {
+            if send_next != send_unacked {
+                self.on_dup_ack_received(send_next);
+            }
+        } else {
+            self.duplicate_ack_count.set(0);
+            if self.in_fast_recovery.get() {
+                self.on_ack_received_fast_recovery(send_unacked, send_next, ack_seq_no);
+            } else {
+                self.on_ack_received_ss_ca(bytes_acknowledged);
+            }
+        }
+    }
+
+    fn on_rto(&self, send_unacked: SeqNumber) {
+        self.ssthresh.set(max(self.cwnd.get() / 2, 2 * self.mss));
+        self.cwnd.set(self.mss);
+        self.bytes_acked_in_ca.set(0);
+        self.recover.set(send_unacked);
+        self.in_fast_recovery.set(false);
+    }
+
+    fn on_loss(&self, send_next: SeqNumber) {
+        // We only reduce cwnd once per window of data, so losses detected during recovery are ignored.
+        if !self.in_fast_recovery.get() {
+            self.reduce_cwnd_on_loss(send_next);
+        }
+    }
+
+    fn on_ecn_feedback(&self, send_unacked: SeqNumber, send_next: SeqNumber, ack_seq_no: SeqNumber, ece: bool) -> bool {
+        // Count the bytes that this ACK acknowledges, and whether the network marked them.
+        if send_unacked < ack_seq_no && ack_seq_no <= send_next {
+            let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
+            self.bytes_acked.set(self.bytes_acked.get() + bytes_acknowledged);
+            if ece {
+                self.bytes_marked.set(self.bytes_marked.get() + bytes_acknowledged);
+            }
+        }
+
+        // Once per window of data, fold the fraction of marked bytes into alpha.
+        if ack_seq_no > self.window_end.get() {
+            let bytes_acked: u32 = self.bytes_acked.get();
+            if bytes_acked > 0 {
+                let fraction: f64 = self.bytes_marked.get() as f64 / bytes_acked as f64;
+                self.alpha.set((1.0 - self.g) * self.alpha.get() + self.g * fraction);
+            }
+            self.bytes_acked.set(0);
+            self.bytes_marked.set(0);
+            self.window_end.set(send_next);
+        }
+
+        // Shrink cwnd in proportion to the extent of congestion, at most once per window of data.
+        if !ece || self.in_fast_recovery.get() || ack_seq_no <= self.ecn_recover.get() {
+            return false;
+        }
+        let reduced_cwnd: u32 = (self.cwnd.get() as f64 * (1.0 - self.alpha.get() / 2.0)) as u32;
+        self.ssthresh.set(max(reduced_cwnd, 2 * self.mss));
+        self.cwnd.set(self.ssthresh.get());
+        self.ecn_recover.set(send_next);
+        true
+    }
+
+    fn echoes_every_congestion_mark(&self) -> bool {
+        true
+    }
+}
+
+impl FastRetransmitRecovery for Dctcp {
+    fn get_duplicate_ack_count(&self) -> u32 {
+        self.duplicate_ack_count.get()
+    }
+
+    fn get_retransmit_now_flag(&self) -> bool {
+        self.fast_retransmit_now.get()
+    }
+
+    fn watch_retransmit_now_flag(&self) -> (bool, WatchFuture<'_, bool>) {
+        self.fast_retransmit_now.watch()
+    }
+
+    fn on_fast_retransmit(&self) {
+        self.fast_retransmit_now.set_without_notify(false);
+    }
+}
+
+impl LimitedTransmit for Dctcp {}
@@ -2,12 +2,16 @@
 // Licensed under the MIT license.
 
 mod cubic;
+mod dctcp;
 mod none;
 mod options;
 
 use crate::{
     inetstack::protocols::tcp::SeqNumber,
-    runtime::watched::WatchFuture,
+    runtime::{
+        network::types::CongestionControlAlgorithm,
+        watched::WatchFuture,
+    },
 };
 use ::std::{
     fmt::Debug,
@@ -16,6 +20,7 @@ use ::std::{
 
 pub use self::{
     cubic::Cubic,
+    dctcp::Dctcp,
     none::None,
     options::{
         OptionValue,
@@ -30,6 +35,9 @@ pub trait SlowStartCongestionAvoidance {
     fn watch_cwnd(&self) -> (u32, WatchFuture<'_, u32>) {
         (u32::MAX, WatchFuture::Pending)
     }
+    fn get_ssthresh(&self) -> u32 {
+        u32::MAX
+    }
 
     // Called immediately before the cwnd check is performed before data is sent.
     fn on_cwnd_check_before_send(&self) {}
@@ -40,8 +48,31 @@ pub trait SlowStartCongestionAvoidance {
     // Called immediately before retransmit after RTO.
     fn on_rto(&self, _send_unacked: SeqNumber) {}
 
+    // Called when loss is detected other than by duplicate ACK counting or RTO (e.g. from selective acknowledgements),
+    // immediately before the lost data is retransmitted.
+    fn on_loss(&self, _send_next: SeqNumber) {}
+
     // Called immediately before a segment is sent for the 1st time.
     fn on_send(&self, _rto: Duration, _num_sent_bytes: u32) {}
+
+    // Called for every ACK received on connections that negotiated ECN, immediately before on_ack_received(), with
+    // whether the ACK echoes a congestion mark.  Returns whether cwnd was reduced in response, in which case our peer
+    // is told so through the CWR flag of the next segment that we send (see RFC 3168).
+    fn on_ecn_feedback(
+        &self,
+        _send_unacked: SeqNumber,
+        _send_next: SeqNumber,
+        _ack_seq_no: SeqNumber,
+        _ece: bool,
+    ) -> bool {
+        false
+    }
+
+    // Whether our ACKs should echo every congestion mark that we receive, as DCTCP requires, rather than keep echoing
+    // a mark until our peer reduces its cwnd (see RFC 3168).
+    fn echoes_every_congestion_mark(&self) -> bool {
+        false
+    }
 }
 
 pub trait FastRetransmitRecovery
@@ -81,3 +112,12 @@ pub trait CongestionControl: SlowStartCongestionAvoidance + FastRetransmitRecove
 }
 
 pub type CongestionControlConstructor = fn(usize, SeqNumber, Option<options::Options>) -> Box<dyn CongestionControl>;
+
+/// Returns the constructor of a congestion control algorithm.
+pub fn get_constructor(algorithm: CongestionControlAlgorithm) -> CongestionControlConstructor {
+    match algorithm {
+        CongestionControlAlgorithm::None => None::new,
+        CongestionControlAlgorithm::Cubic => Cubic::new,
+        CongestionControlAlgorithm::Dctcp => Dctcp::new,
+    }
+}
@@ -2,10 +2,15 @@
 // Licensed under the MIT license.
 
 use super::{
+    autotune::{
+        BufferSize,
+        ReceiveAutotuner,
+    },
     congestion_control::{
         self,
         CongestionControlConstructor,
     },
+    pacer::Pacer,
     rto::RtoCalculator,
     sender::{
         Sender,
@@ -20,11 +25,26 @@ use crate::{
             Ethernet2Header,
         },
         ip::IpProtocol,
-        ipv4::Ipv4Header,
+        ipv4::{
+            Ipv4Header,
+            PathMtuCache,
+            IPV4_ECN_ECT0,
+            IPV4_HEADER_DEFAULT_SIZE,
+        },
         tcp::{
+            gro::Payloads,
             segment::{
+                SelectiveAcknowlegement,
                 TcpHeader,
+                TcpOptions2,
                 TcpSegment,
+                MIN_TCP_HEADER_SIZE,
+            },
+            timestamp::{
+                timestamp_before,
+                timestamp_value,
+                PAWS_IDLE_TIMEOUT,
+                TIMESTAMP_OPTION_SPACE,
             },
             SeqNumber,
         },
@@ -34,7 +54,12 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
-            types::MacAddress,
+            types::{
+                MacAddress,
+                OutOfOrderDropPolicy,
+                TcpInfo,
+                Timestamps,
+            },
             NetworkRuntime,
         },
         timer::TimerRc,
@@ -45,11 +70,15 @@ use crate::{
     },
     scheduler::scheduler::Scheduler,
 };
+use ::libc::ECONNRESET;
 use ::std::{
     cell::{
         Cell,
+        Ref,
         RefCell,
+        RefMut,
     },
+    cmp,
     collections::VecDeque,
     convert::TryInto,
     net::SocketAddrV4,
@@ -71,10 +100,13 @@ use ::std::{
 // mechanism used to manage the receive queue (a VecDeque) than anything else.
 const RECV_QUEUE_SZ: usize = 2048;
 
-// ToDo: Review this value (and its purpose).  It (16 segments) seems awfully small (would make fast retransmit less
-// useful), and this mechanism isn't the best way to protect ourselves against deliberate out-of-order segment attacks.
-// Ideally, we'd limit out-of-order data to that which (along with the unread data) will fit in the receive window.
-const MAX_OUT_OF_ORDER: usize = 16;
+// Maximum number of SACK blocks that we report in a single segment.  This is the most that fits in the TCP option space
+// (see RFC 2018).  One less block fits alongside the Timestamps option.
+const MAX_SACK_BLOCKS: usize = 4;
+
+// Maximum number of challenge ACKs that a connection sends per second.  This keeps attackers from using us to flood our
+// peer with ACKs (see RFC 5961 Section 7).
+const CHALLENGE_ACK_LIMIT: u32 = 10;
 
 // TCP Connection State.
 // Note: This ControlBlock structure is only used after we've reached the ESTABLISHED state, so states LISTEN,
@@ -91,6 +123,17 @@ pub enum State {
     Closed,
 }
 
+// Counters for the out-of-order store of a connection.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OutOfOrderStats {
+    // Number of out-of-order segments received.
+    pub segments: u64,
+    // Number of stored segments that were delivered once the hole before them got filled.
+    pub merges: u64,
+    // Number of segments that were dropped (or evicted) because the out-of-order store was full.
+    pub drops: u64,
+}
+
 // ToDo: Consider incorporating this directly into ControlBlock.
 struct Receiver {
     //
@@ -134,6 +177,71 @@ impl Receiver {
         Some(buf)
     }
 
+    // Returns the number of bytes that are ready to be read, along with whether the end-of-stream marker (i.e. an
+    // empty buffer pushed on receiving a FIN) is in the receive queue.
+    pub fn readable(&self) -> (usize, bool) {
+        let mut nbytes: usize = 0;
+        for buf in self.recv_queue.borrow().iter() {
+            if buf.is_empty() {
+                return (nbytes, true);
+            }
+            nbytes += buf.len();
+        }
+        (nbytes, false)
+    }
+
+    // Pops at most `size` bytes of data from the receive queue.  A segment larger than `size` is split, leaving its
+    // tail at the front of the queue, while smaller segments are coalesced into a single buffer.  The end-of-stream
+    // marker is only popped when it is at the front of the queue.
+    pub fn pop_sized(&self, size: usize) -> Result<Option<DemiBuffer>, Fail> {
+        let mut recv_queue = self.recv_queue.borrow_mut();
+        let front_len: usize = match recv_queue.front() {
+            Some(buf) => buf.len(),
+            None => return Ok(None),
+        };
+
+        let buf: DemiBuffer = if front_len == 0 || front_len == size {
+            recv_queue.pop_front().expect("receive queue cannot be empty")
+        } else if front_len > size {
+            let back: DemiBuffer = recv_queue
+                .front_mut()
+                .expect("receive queue cannot be empty")
+                .split_off(size)?;
+            let front: DemiBuffer = recv_queue.pop_front().expect("receive queue cannot be empty");
+            recv_queue.push_front(back);
+            front
+        } else {
+            // Coalesce segments up to the end-of-stream marker (if any).
+            let mut nbytes: usize = 0;
+            for buf in recv_queue.iter() {
+                if buf.is_empty() || nbytes >= size {
+                    break;
+                }
+                nbytes += buf.len();
+            }
+            let nbytes: usize = nbytes.min(size);
+            let mut buf: DemiBuffer = DemiBuffer::new(nbytes as u16);
+            let mut offset: usize = 0;
+            while offset < nbytes {
+                let front: &mut DemiBuffer = recv_queue.front_mut().expect("receive queue cannot be empty");
+                let n: usize = front.len().min(nbytes - offset);
+                buf[offset..offset + n].copy_from_slice(&front[..n]);
+                offset += n;
+                if n == front.len() {
+                    recv_queue.pop_front();
+                } else {
+                    front.adjust(n)?;
+                }
+            }
+            buf
+        };
+
+        self.reader_next
+            .set(self.reader_next.get() + SeqNumber::from(buf.len() as u32));
+
+        Ok(Some(buf))
+    }
+
     pub fn push(&self, buf: DemiBuffer) {
         let buf_len: u32 = buf.len() as u32;
         self.recv_queue.borrow_mut().push_back(buf);
@@ -158,19 +266,69 @@ pub struct ControlBlock {
     // this along with other remote IP information (such as routing, path MTU, etc).
     arp: Rc<ArpPeer>,
 
+    // Path MTU estimates, shared with the IP layer.  Our MSS is clamped to the path MTU towards the remote peer.
+    pmtu: PathMtuCache,
+
     // Send-side state information.  ToDo: Consider incorporating this directly into ControlBlock.
     sender: Sender,
 
+    // Whether both ends agreed to use selective acknowledgements (see RFC 2018).
+    sack_permitted: bool,
+
+    // Whether both ends agreed to use the Timestamps option (see RFC 7323).
+    timestamps: bool,
+
+    // Origin of our timestamp clock.
+    ts_base: Instant,
+
+    // Most recent timestamp received from our peer that we should echo, and when we received it.  In RFC 7323 terms,
+    // this is TS.Recent.
+    ts_recent: Cell<u32>,
+    ts_recent_age: Cell<Instant>,
+
+    // Acknowledgement number of the last segment we sent.  In RFC 7323 terms, this is Last.ACK.sent.
+    last_ack_sent: Cell<SeqNumber>,
+
+    // Whether both ends agreed to use Explicit Congestion Notification (see RFC 3168).
+    ecn: bool,
+
+    // Whether our ACKs currently echo congestion marks (i.e. set ECE).
+    ece: Cell<bool>,
+
+    // Whether we reduced cwnd in response to congestion marks, and are yet to tell our peer so through CWR.
+    cwr: Cell<bool>,
+
     // TCP Connection State.
     state: Cell<State>,
 
-    ack_delay_timeout: Duration,
+    // Maximum time for which we delay ACKs.  Zero disables delayed ACKs.
+    ack_delay_timeout: Cell<Duration>,
+
+    // Number of segments that we may receive before we have to ACK them right away, rather than delay the ACK.
+    ack_segments: Cell<u32>,
+
+    // Whether to ACK every segment right away (i.e. quick-ack mode).
+    quickack: Cell<bool>,
+
+    // Number of segments that we received since we last sent an ACK.
+    segments_unacked: Cell<u32>,
+
+    // Whether we owe our peer an ACK, which is held back until the current batch of received segments is processed.
+    ack_deferred: Cell<bool>,
 
     ack_deadline: WatchedValue<Option<Instant>>,
 
-    // This is our receive buffer size, which is also the maximum size of our receive window.
+    // This is our receive buffer size, which is also the maximum size of our receive window.  It is autotuned within
+    // the bounds set by the user.
     // Note: The maximum possible advertised window is 1 GiB with window scaling and 64 KiB without.
-    receive_buffer_size: u32,
+    receive_buffer: BufferSize,
+
+    // Measures how fast the user reads data, to right-size the receive buffer.
+    receive_autotuner: ReceiveAutotuner,
+
+    // Upper bound on the amount of data that the user may queue for sending (including unacknowledged data).  It is
+    // autotuned within the bounds set by the user.
+    send_buffer: BufferSize,
 
     // ToDo: Review how this is used.  We could have separate window scale factors, so there should be one for the
     // receiver and one for the sender.
@@ -181,22 +339,39 @@ pub struct ControlBlock {
 
     waker: RefCell<Option<Waker>>,
 
+    // Waker of the task that waits for the close handshake to complete, if any.
+    close_waker: RefCell<Option<Waker>>,
+
+    // Wakers of the tasks that wait for sent data to be acknowledged, if any.
+    ack_wakers: RefCell<Vec<Waker>>,
+
     // Queue of out-of-order segments.  This is where we hold onto data that we've received (because it was within our
     // receive window) but can't yet present to the user because we're missing some other data that comes between this
-    // and what we've already presented to the user.
+    // and what we've already presented to the user.  Its size is bounded by the TCP configuration, so that a
+    // misbehaving peer cannot make us hold onto an unbounded amount of memory.
     //
     out_of_order: RefCell<VecDeque<(SeqNumber, DemiBuffer)>>,
 
+    // Counters for the out-of-order queue.
+    out_of_order_stats: Cell<OutOfOrderStats>,
+
     // The sequence number of the FIN, if we received it out-of-order.
     // Note: This could just be a boolean to remember if we got a FIN; the sequence number is for checking correctness.
     pub out_of_order_fin: Cell<Option<SeqNumber>>,
 
+    // The sequence number of the most recently received out-of-order segment.  The first SACK block that we report
+    // must contain it (see RFC 2018).
+    last_out_of_order: Cell<Option<SeqNumber>>,
+
     // Receive-side state information.  ToDo: Consider incorporating this directly into ControlBlock.
     receiver: Receiver,
 
     // Whether the user has called close.
     pub user_is_done_sending: Cell<bool>,
 
+    // Whether the user has shut down the receive side of this connection.
+    user_is_done_receiving: Cell<bool>,
+
     // Congestion control trait implementation we're currently using.
     // ToDo: Consider switching this to a static implementation to avoid V-table call overhead.
     cc: Box<dyn congestion_control::CongestionControl>,
@@ -207,6 +382,50 @@ pub struct ControlBlock {
 
     // Retransmission Timeout (RTO) calculator.
     rto: RefCell<RtoCalculator>,
+
+    // Number of consecutive retransmission timeouts without any new data being acknowledged.
+    retransmissions: Cell<usize>,
+
+    // Number of consecutive retransmission timeouts after which we give up on the connection.
+    max_retransmissions: usize,
+
+    // Number of segments retransmitted over the lifetime of this connection.
+    total_retransmissions: Cell<u64>,
+
+    // Number of bytes that our peer acknowledged over the lifetime of this connection.
+    bytes_acked: Cell<u64>,
+
+    // Start of the current delivery rate sample, and the number of bytes acknowledged by then.  There is no sample
+    // while nothing is in flight, so that idle time does not drag the rate down.
+    delivery_sample: Cell<Option<(Instant, u64)>>,
+
+    // Most recent delivery rate (in bytes per second).
+    delivery_rate: Cell<u64>,
+
+    // Hardware timestamps of the last segment received and sent on this connection.
+    hw_timestamps: Cell<Timestamps>,
+
+    // Error that aborted this connection, if any.
+    error: Cell<Option<i32>>,
+
+    // How long to linger in TIME-WAIT (i.e. 2 MSL).
+    time_wait_timeout: Duration,
+
+    // Time at which we leave TIME-WAIT, if we are in it.
+    time_wait_deadline: WatchedValue<Option<Instant>>,
+
+    // Spreads out the transmission of new segments.
+    pacer: Pacer,
+
+    // Whether to send partial segments right away, rather than holding them back while data is in flight (i.e. whether
+    // Nagle's algorithm is disabled).
+    nodelay: WatchedValue<bool>,
+
+    // Whether to hold back partial segments until the user uncorks the connection.
+    cork: WatchedValue<bool>,
+
+    // Start of the current one-second interval, and the number of challenge ACKs that we sent during it.
+    challenge_acks: Cell<(Instant, u32)>,
 }
 
 //==============================================================================
@@ -221,6 +440,7 @@ impl ControlBlock {
         local_link_addr: MacAddress,
         tcp_config: TcpConfig,
         arp: ArpPeer,
+        pmtu: PathMtuCache,
         receiver_seq_no: SeqNumber,
         ack_delay_timeout: Duration,
         receiver_window_size: u32,
@@ -229,10 +449,21 @@ impl ControlBlock {
         sender_window_size: u32,
         sender_window_scale: u8,
         sender_mss: usize,
+        sack_permitted: bool,
+        ts_base: Instant,
+        ts_recent: Option<u32>,
+        ecn: bool,
         cc_constructor: CongestionControlConstructor,
         congestion_control_options: Option<congestion_control::Options>,
     ) -> Self {
         let sender = Sender::new(sender_seq_no, sender_window_size, sender_window_scale, sender_mss);
+        let pacer: Pacer = Pacer::new(tcp_config.get_pacing());
+        let ack_segments: u32 = tcp_config.get_ack_segments();
+        let quickack: bool = tcp_config.get_quickack();
+        let rto: RtoCalculator = RtoCalculator::new(tcp_config.get_rto_min(), tcp_config.get_rto_max());
+        let max_retransmissions: usize = tcp_config.get_max_retransmissions();
+        let time_wait_timeout: Duration = 2 * tcp_config.get_msl();
+        let now: Instant = clock.now();
         Self {
             local,
             remote,
@@ -242,20 +473,55 @@ impl ControlBlock {
             local_link_addr,
             tcp_config,
             arp: Rc::new(arp),
+            pmtu,
             sender: sender,
+            sack_permitted,
+            timestamps: ts_recent.is_some(),
+            ts_base,
+            ts_recent: Cell::new(ts_recent.unwrap_or(0)),
+            ts_recent_age: Cell::new(now),
+            last_ack_sent: Cell::new(receiver_seq_no),
+            ecn,
+            ece: Cell::new(false),
+            cwr: Cell::new(false),
             state: Cell::new(State::Established),
-            ack_delay_timeout,
+            ack_delay_timeout: Cell::new(ack_delay_timeout),
+            ack_segments: Cell::new(ack_segments),
+            quickack: Cell::new(quickack),
+            segments_unacked: Cell::new(0),
+            ack_deferred: Cell::new(false),
             ack_deadline: WatchedValue::new(None),
-            receive_buffer_size: receiver_window_size,
+            receive_buffer: BufferSize::new(receiver_window_size),
+            receive_autotuner: ReceiveAutotuner::new(receiver_seq_no, now),
+            send_buffer: BufferSize::new(receiver_window_size),
             window_scale: receiver_window_scale,
             waker: RefCell::new(None),
+            close_waker: RefCell::new(None),
+            ack_wakers: RefCell::new(Vec::new()),
             out_of_order: RefCell::new(VecDeque::new()),
+            out_of_order_stats: Cell::new(OutOfOrderStats::default()),
             out_of_order_fin: Cell::new(Option::None),
+            last_out_of_order: Cell::new(Option::None),
             receiver: Receiver::new(receiver_seq_no, receiver_seq_no),
             user_is_done_sending: Cell::new(false),
+            user_is_done_receiving: Cell::new(false),
             cc: cc_constructor(sender_mss, sender_seq_no, congestion_control_options),
             retransmit_deadline: WatchedValue::new(None),
-            rto: RefCell::new(RtoCalculator::new()),
+            rto: RefCell::new(rto),
+            retransmissions: Cell::new(0),
+            max_retransmissions,
+            total_retransmissions: Cell::new(0),
+            bytes_acked: Cell::new(0),
+            delivery_sample: Cell::new(None),
+            delivery_rate: Cell::new(0),
+            hw_timestamps: Cell::new(Timestamps::default()),
+            error: Cell::new(None),
+            time_wait_timeout,
+            time_wait_deadline: WatchedValue::new(None),
+            pacer,
+            nodelay: WatchedValue::new(true),
+            cork: WatchedValue::new(false),
+            challenge_acks: Cell::new((now, 0)),
         }
     }
 
@@ -267,15 +533,61 @@ impl ControlBlock {
         self.remote
     }
 
+    pub fn get_state(&self) -> State {
+        self.state.get()
+    }
+
+    pub fn get_receive_next(&self) -> SeqNumber {
+        self.receiver.receive_next.get()
+    }
+
     // ToDo: Remove this.  ARP doesn't belong at this layer.
     pub fn arp(&self) -> Rc<ArpPeer> {
         self.arp.clone()
     }
 
     pub fn send(&self, buf: DemiBuffer) -> Result<(), Fail> {
+        if let Some(errno) = self.error.get() {
+            return Err(Fail::new(errno, "connection aborted"));
+        }
         self.sender.send(buf, self)
     }
 
+    /// Polls for the acknowledgement of all data that precedes `seq_no`. This completes once the remote peer has
+    /// acknowledged it, or fails once the connection is aborted.
+    pub fn poll_acked(&self, seq_no: SeqNumber, ctx: &mut Context) -> Poll<Result<(), Fail>> {
+        if let Some(errno) = self.error.get() {
+            return Poll::Ready(Err(Fail::new(errno, "connection aborted")));
+        }
+        if seq_no <= self.sender.send_unacked.get() {
+            return Poll::Ready(Ok(()));
+        }
+        let mut ack_wakers: RefMut<Vec<Waker>> = self.ack_wakers.borrow_mut();
+        if !ack_wakers.iter().any(|w| w.will_wake(ctx.waker())) {
+            ack_wakers.push(ctx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    // Wakes up the tasks that wait for sent data to be acknowledged, so that they check for it again.
+    fn wake_ack_wakers(&self) {
+        for w in self.ack_wakers.borrow_mut().drain(..) {
+            w.wake()
+        }
+    }
+
+    pub fn sack_permitted(&self) -> bool {
+        self.sack_permitted
+    }
+
+    pub fn timestamps_enabled(&self) -> bool {
+        self.timestamps
+    }
+
+    pub fn ecn_enabled(&self) -> bool {
+        self.ecn
+    }
+
     pub fn congestion_control_watch_retransmit_now_flag(&self) -> (bool, WatchFuture<bool>) {
         self.cc.watch_retransmit_now_flag()
     }
@@ -312,8 +624,19 @@ impl ControlBlock {
         self.cc.watch_limited_transmit_cwnd_increase()
     }
 
+    // Maximum Segment Size to use for outgoing segments.  This is the lesser of the MSS negotiated with our peer and
+    // the one that we advertised, clamped so that segments fit in the path MTU (see RFC 1191), less the space taken by
+    // the options that we send on every segment (see RFC 6691).
     pub fn get_mss(&self) -> usize {
-        self.sender.get_mss()
+        let path_mtu: usize = self.pmtu.get(*self.remote.ip(), self.clock.now());
+        let path_mss: usize = path_mtu - IPV4_HEADER_DEFAULT_SIZE - MIN_TCP_HEADER_SIZE;
+        let negotiated_mss: usize = cmp::min(self.sender.get_mss(), self.tcp_config.get_advertised_mss());
+        let mss: usize = cmp::min(negotiated_mss, path_mss);
+        if self.timestamps {
+            mss - TIMESTAMP_OPTION_SPACE
+        } else {
+            mss
+        }
     }
 
     pub fn get_send_window(&self) -> (u32, WatchFuture<u32>) {
@@ -348,8 +671,8 @@ impl ControlBlock {
         self.retransmit_deadline.watch()
     }
 
-    pub fn pop_unacked_segment(&self) -> Option<UnackedSegment> {
-        self.sender.pop_unacked_segment()
+    pub fn first_unacked_segment(&self) -> Option<DemiBuffer> {
+        self.sender.first_unacked_segment()
     }
 
     pub fn push_unacked_segment(&self, segment: UnackedSegment) {
@@ -368,6 +691,148 @@ impl ControlBlock {
         self.rto.borrow_mut().record_failure()
     }
 
+    /// Restarts SACK-based loss recovery after a retransmission timeout (see RFC 6675).
+    pub fn restart_loss_recovery(&self) {
+        self.sender.on_rto()
+    }
+
+    /// Accounts for a retransmission timeout. Returns whether we may retransmit again, or should rather give up on
+    /// this connection because our peer has not acknowledged anything for too many retransmissions.
+    pub fn record_retransmission(&self) -> bool {
+        let retransmissions: usize = self.retransmissions.get() + 1;
+        self.retransmissions.set(retransmissions);
+        retransmissions <= self.max_retransmissions
+    }
+
+    /// Accounts for a segment that we retransmitted.
+    pub fn record_retransmitted_segment(&self) {
+        self.total_retransmissions.set(self.total_retransmissions.get() + 1);
+    }
+
+    /// Accounts for `bytes_acknowledged` new bytes acknowledged at `now`.  Once per round trip, or when nothing is left
+    /// in flight, this takes a sample of the rate at which our peer acknowledges data.
+    fn update_delivery_rate(&self, bytes_acknowledged: u32, all_acknowledged: bool, now: Instant) {
+        let bytes_acked: u64 = self.bytes_acked.get() + bytes_acknowledged as u64;
+        self.bytes_acked.set(bytes_acked);
+
+        if let Some((start, start_bytes_acked)) = self.delivery_sample.get() {
+            let elapsed: Duration = now.saturating_duration_since(start);
+            let round_trip: Duration = self.rto.borrow().srtt().unwrap_or_default();
+            if elapsed.is_zero() || (elapsed < round_trip && !all_acknowledged) {
+                return;
+            }
+            let rate: f64 = (bytes_acked - start_bytes_acked) as f64 / elapsed.as_secs_f64();
+            self.delivery_rate.set(rate as u64);
+            self.delivery_sample.set(if all_acknowledged {
+                None
+            } else {
+                Some((now, bytes_acked))
+            });
+        }
+    }
+
+    /// Gets a snapshot of the state of this connection.
+    pub fn get_tcp_info(&self) -> TcpInfo {
+        let (send_unacked, _): (SeqNumber, _) = self.sender.get_send_unacked();
+        let (send_next, _): (SeqNumber, _) = self.sender.get_send_next();
+        let rto: Ref<RtoCalculator> = self.rto.borrow();
+        TcpInfo {
+            mss: self.get_mss(),
+            srtt: rto.srtt(),
+            rttvar: rto.rttvar(),
+            rto: rto.estimate(),
+            cwnd: self.cc.get_cwnd(),
+            ssthresh: self.cc.get_ssthresh(),
+            bytes_in_flight: (send_next - send_unacked).into(),
+            bytes_acked: self.bytes_acked.get(),
+            retransmits: self.total_retransmissions.get(),
+            delivery_rate: self.delivery_rate.get(),
+        }
+    }
+
+    /// Gets the hardware timestamps of the last segment received and sent on this connection.
+    pub fn get_hw_timestamps(&self) -> Timestamps {
+        self.hw_timestamps.get()
+    }
+
+    /// Aborts this connection with the given error. All timers are stopped, and pending as well as future operations
+    /// on this connection fail with this error.
+    pub fn abort(&self, errno: i32) {
+        warn!(
+            "aborting connection {:?} -> {:?} (errno={:?})",
+            self.local, self.remote, errno
+        );
+        self.error.set(Some(errno));
+        self.retransmit_deadline.set(None);
+        self.ack_deadline.set(None);
+        self.set_state(State::Closed);
+
+        // Wake up any pending reader, so that it observes the error.
+        if let Some(w) = self.waker.borrow_mut().take() {
+            w.wake()
+        }
+        self.wake_ack_wakers();
+    }
+
+    pub fn set_max_pacing_rate(&self, rate: u64) {
+        self.pacer.set_max_rate(rate)
+    }
+
+    // Returns how long the sender has to wait before sending a new segment.
+    pub fn pacing_delay(&self) -> Option<Duration> {
+        self.pacer.delay(self.clock.now())
+    }
+
+    pub fn pacing_on_send(&self, num_sent_bytes: u32) {
+        let rate: Option<u64> = self.pacer.rate(self.cc.get_cwnd(), self.rto.borrow().srtt());
+        self.pacer.on_send(self.clock.now(), num_sent_bytes, rate)
+    }
+
+    pub fn get_receive_buffer_size(&self) -> u32 {
+        self.receive_buffer.get()
+    }
+
+    // Resizes the receive buffer, and sets the bounds within which it is autotuned from there.
+    pub fn set_receive_buffer(&self, size: usize, min: usize, max: usize) {
+        let max: u32 = max.try_into().unwrap_or(u32::MAX);
+        let min: u32 = cmp::min(min.try_into().unwrap_or(u32::MAX), max);
+        self.receive_buffer.set(size.try_into().unwrap_or(u32::MAX), min, max)
+    }
+
+    pub fn get_send_buffer_size(&self) -> u32 {
+        self.send_buffer.get()
+    }
+
+    // Resizes the send buffer, and sets the bounds within which it is autotuned from there.
+    pub fn set_send_buffer(&self, size: usize, min: usize, max: usize) {
+        let max: u32 = max.try_into().unwrap_or(u32::MAX);
+        let min: u32 = cmp::min(min.try_into().unwrap_or(u32::MAX), max);
+        self.send_buffer.set(size.try_into().unwrap_or(u32::MAX), min, max)
+    }
+
+    pub fn set_nodelay(&self, nodelay: bool) {
+        self.nodelay.set(nodelay)
+    }
+
+    pub fn set_cork(&self, cork: bool) {
+        self.cork.set(cork)
+    }
+
+    // Checks whether a segment of `len` bytes should be held back, waiting for more data to fill it.  Nagle's algorithm
+    // (see RFC 896 and RFC 1122 Section 4.2.3.4) holds back partial segments while previously sent data has yet to be
+    // acknowledged, and corking holds them back altogether.  The end-of-send marker is never held back.
+    pub fn hold_partial_segment(&self, len: usize, in_flight: u32) -> bool {
+        len != 0 && len < self.get_mss() && (self.cork.get() || (!self.nodelay.get() && in_flight > 0))
+    }
+
+    pub fn watch_nodelay(&self) -> (bool, WatchFuture<bool>) {
+        self.nodelay.watch()
+    }
+
+    pub fn watch_cork(&self) -> (bool, WatchFuture<bool>) {
+        self.cork.watch()
+    }
+
     pub fn unsent_top_size(&self) -> Option<usize> {
         self.sender.top_size_unsent()
     }
@@ -380,22 +845,104 @@ impl ControlBlock {
         self.sender.pop_one_unsent_byte()
     }
 
-    // This is the main TCP receive routine.
+    // Removes `nbytes` bytes from the beginning of the data carried by `payloads`.
+    fn adjust_payloads(payloads: &mut Payloads, mut nbytes: usize) {
+        while nbytes > 0 {
+            let first_len: usize = payloads.first().expect("'payloads' should not be empty").len();
+            if first_len <= nbytes && payloads.len() > 1 {
+                payloads.remove(0);
+                nbytes -= first_len;
+            } else {
+                payloads[0]
+                    .adjust(nbytes)
+                    .expect("'payloads' should contain at least 'nbytes' bytes");
+                nbytes = 0;
+            }
+        }
+    }
+
+    // Removes `nbytes` bytes from the end of the data carried by `payloads`.
+    fn trim_payloads(payloads: &mut Payloads, mut nbytes: usize) {
+        while nbytes > 0 {
+            let last: usize = payloads.len() - 1;
+            let last_len: usize = payloads[last].len();
+            if last_len <= nbytes && last > 0 {
+                payloads.pop();
+                nbytes -= last_len;
+            } else {
+                payloads[last]
+                    .trim(nbytes)
+                    .expect("'payloads' should contain at least 'nbytes' bytes");
+                nbytes = 0;
+            }
+        }
+    }
+
+    // This is the main TCP receive routine.  The data of the segment comes in one or more payloads, which are more than
+    // one for a segment that was coalesced from a run of segments.
     //
-    pub fn receive(&self, mut header: &mut TcpHeader, mut data: DemiBuffer) {
+    pub fn receive(
+        &self,
+        mut header: &mut TcpHeader,
+        mut payloads: Payloads,
+        congestion_experienced: bool,
+        defer_ack: bool,
+    ) {
+        let data_len: usize = payloads.iter().map(|payload| payload.len()).sum();
         debug!(
             "{:?} Connection Receiving {} bytes + {:?}",
             self.state.get(),
-            data.len(),
+            data_len,
             header
         );
 
+        // Aborted connections are gone as far as the user is concerned, so there is nothing left to do.
+        if self.error.get().is_some() {
+            warn!("Dropping segment received on aborted connection");
+            return;
+        }
+
         let mut should_schedule_ack: bool = false;
 
+        if let Some(rx) = payloads.first().and_then(|payload| self.rt.rx_timestamp(payload)) {
+            self.hw_timestamps.set(Timestamps {
+                rx: Some(rx),
+                ..self.hw_timestamps.get()
+            });
+        }
+
         // ToDo: We're probably getting "now" here in order to get a timestamp as close as possible to when we received
         // the packet.  However, this is wasteful if we don't take a path below that actually uses it.  Review this.
         let now: Instant = self.clock.now();
 
+        // Protect Against Wrapped Sequence numbers (PAWS, see RFC 7323).  A segment that carries a timestamp older than
+        // the most recent one we received may be an old duplicate whose sequence number wrapped around.  ACK (if not
+        // RST) and drop it.
+        let ts_option: Option<(u32, u32)> = match self.timestamps {
+            true => header.iter_options().find_map(|option| match option {
+                TcpOptions2::Timestamp {
+                    sender_timestamp,
+                    echo_timestamp,
+                } => Some((*sender_timestamp, *echo_timestamp)),
+                _ => None,
+            }),
+            false => None,
+        };
+        if let Some((ts_val, _)) = ts_option {
+            if !header.rst && timestamp_before(ts_val, self.ts_recent.get()) {
+                if now - self.ts_recent_age.get() > PAWS_IDLE_TIMEOUT {
+                    // The connection has been idle for so long that our peer's timestamp clock may have wrapped
+                    // around.  Our most recent timestamp can't be trusted anymore, so start over from this one.
+                    self.ts_recent.set(ts_val);
+                    self.ts_recent_age.set(now);
+                } else {
+                    debug!("Dropping segment with old timestamp {} (PAWS)", ts_val);
+                    self.send_ack();
+                    return;
+                }
+            }
+        }
+
         // Check to see if the segment is acceptable sequence-wise (i.e. contains some data that fits within the receive
         // window, or is a non-data segment with a sequence number that falls within the window).  Unacceptable segments
         // should be ACK'd (unless they are RSTs), and then dropped.
@@ -422,7 +969,7 @@ impl ControlBlock {
         let mut seg_start: SeqNumber = header.seq_num;
 
         let mut seg_end: SeqNumber = seg_start;
-        let mut seg_len: u32 = data.len() as u32;
+        let mut seg_len: u32 = data_len as u32;
         if header.syn {
             seg_len += 1;
         }
@@ -453,6 +1000,11 @@ impl ControlBlock {
                     if !header.rst {
                         self.send_ack();
                     }
+                    // In TIME-WAIT, this is most likely a retransmission of our peer's FIN (i.e. our ACK got lost).
+                    // Restart the 2 MSL time-wait timeout.
+                    if header.fin && self.state.get() == State::TimeWait {
+                        self.set_state(State::TimeWait);
+                    }
                     return;
                 } else {
                     // Some of this segment's data is new.  Cut the duplicate data off of the front.
@@ -465,8 +1017,7 @@ impl ControlBlock {
                         header.syn = false;
                         duplicate -= 1;
                     }
-                    data.adjust(duplicate as usize)
-                        .expect("'data' should contain at least 'duplicate' bytes");
+                    Self::adjust_payloads(&mut payloads, duplicate as usize);
                 }
             } else {
                 // This segment contains entirely new data, but is later in the sequence than what we're expecting.
@@ -499,8 +1050,7 @@ impl ControlBlock {
                 header.fin = false;
                 excess -= 1;
             }
-            data.trim(excess as usize)
-                .expect("'data' should contain at least 'excess' bytes");
+            Self::trim_payloads(&mut payloads, excess as usize);
         }
 
         // From here on, the entire new segment (including any SYN or FIN flag remaining) is in the window.
@@ -510,21 +1060,54 @@ impl ControlBlock {
         // when we get to processing the data (and FIN) that we store aside any out-of-order segments for later.
         debug_assert!(receive_next <= seg_start && seg_end < after_receive_window);
 
+        // Remember our peer's timestamp, so that we echo it back.  We only do so for segments that start at or before
+        // what we last acknowledged, so that the echoed timestamp covers the RTT of any delayed ACK (see RFC 7323).
+        if let Some((ts_val, _)) = ts_option {
+            if !timestamp_before(ts_val, self.ts_recent.get()) && header.seq_num <= self.last_ack_sent.get() {
+                self.ts_recent.set(ts_val);
+                self.ts_recent_age.set(now);
+            }
+        }
+
+        // Echo congestion marks from the network back to our peer through the ECE flag of our ACKs.
+        if self.ecn {
+            if self.cc.echoes_every_congestion_mark() {
+                // Every ACK reflects whether the data that it acknowledges was marked.  So when this changes, first
+                // ACK what we already owe an ACK for (see RFC 8257).
+                if congestion_experienced != self.ece.get() {
+                    if self.ack_deadline.get().is_some() {
+                        self.send_ack();
+                    }
+                    self.ece.set(congestion_experienced);
+                }
+            } else {
+                // Keep echoing congestion until our peer tells us that it reduced its cwnd (see RFC 3168).
+                if header.cwr {
+                    self.ece.set(false);
+                }
+                if congestion_experienced {
+                    self.ece.set(true);
+                }
+            }
+        }
+
         // Check the RST bit.
         if header.rst {
-            // ToDo: RFC 5961 "Blind Reset Attack Using the RST Bit" prevention would have us ACK and drop if the new
-            // segment doesn't start precisely on RCV.NXT.
+            // Only a RST that starts precisely on RCV.NXT resets the connection.  Other in-window RSTs may have been
+            // forged by a blind attacker, so we challenge our peer with an ACK instead.  If the RST really came from
+            // our peer, it answers with a RST that carries the sequence number we expect (see RFC 5961 Section 3).
+            if header.seq_num != receive_next {
+                debug!("Received out-of-sequence RST, sending challenge ACK");
+                self.send_challenge_ack(now);
+                return;
+            }
 
             // Our peer has given up.  Shut the connection down hard.
             info!("Received RST");
             match self.state.get() {
-                // Data transfer states.
+                // Data transfer states.  Outstanding user requests fail with ECONNRESET.
                 State::Established | State::FinWait1 | State::FinWait2 | State::CloseWait => {
-                    // ToDo: Return all outstanding user Receive and Send requests with "reset" responses.
-                    // ToDo: Flush all segment queues.
-
-                    // Enter Closed state.
-                    self.state.set(State::Closed);
+                    self.abort(ECONNRESET);
 
                     // ToDo: Delete the ControlBlock.
                     return;
@@ -533,7 +1116,7 @@ impl ControlBlock {
                 // Closing states.
                 State::Closing | State::LastAck | State::TimeWait => {
                     // Enter Closed state.
-                    self.state.set(State::Closed);
+                    self.set_state(State::Closed);
 
                     // ToDo: Delete the ControlBlock.
                     return;
@@ -550,18 +1133,11 @@ impl ControlBlock {
 
         // Check the SYN bit.
         if header.syn {
-            // ToDo: RFC 5961 "Blind Reset Attack Using the SYN Bit" prevention would have us always ACK and drop here.
-
-            // Receiving a SYN here is an error.
-            warn!("Received in-window SYN on established connection.");
-            // ToDo: Send Reset.
-            // ToDo: Return all outstanding Receive and Send requests with "reset" responses.
-            // ToDo: Flush all segment queues.
-
-            // Enter Closed state.
-            self.state.set(State::Closed);
-
-            // ToDo: Delete the ControlBlock.
+            // Rather than resetting the connection, as RFC 793 would have us do, we challenge our peer with an ACK,
+            // since the SYN may have been forged by a blind attacker.  If our peer really restarted, it answers our ACK
+            // with a RST that carries the sequence number we expect (see RFC 5961 Section 4).
+            warn!("Received in-window SYN on established connection, sending challenge ACK");
+            self.send_challenge_ack(now);
             return;
         }
 
@@ -585,6 +1161,16 @@ impl ControlBlock {
         let (send_unacknowledged, _): (SeqNumber, _) = self.sender.get_send_unacked();
         let (send_next, _): (SeqNumber, _) = self.sender.get_send_next();
 
+        // Let congestion control react to congestion marks that our peer echoes.  If it reduces cwnd in response, we
+        // tell our peer so, and it stops echoing them.
+        if self.ecn
+            && self
+                .cc
+                .on_ecn_feedback(send_unacknowledged, send_next, header.ack_num, header.ece)
+        {
+            self.cwr.set(true);
+        }
+
         // ToDo: Restructure this call into congestion control to either integrate it directly or make it more fine-
         // grained.  It currently duplicates the new/duplicate ack check itself internally, which is inefficient.
         // We should either make separate calls for each case or integrate those cases directly.
@@ -595,16 +1181,33 @@ impl ControlBlock {
             header.ack_num,
         );
 
+        // Let the user queue up to two congestion windows of data, so that the sender never runs dry.
+        self.send_buffer.grow(self.cc.get_cwnd().saturating_mul(2));
+
         if send_unacknowledged < header.ack_num {
             if header.ack_num <= send_next {
                 // This segment acknowledges new data (possibly and/or FIN).
                 let bytes_acknowledged: u32 = (header.ack_num - send_unacknowledged).into();
 
+                // With timestamps, our peer echoes the timestamp of the segment that it acknowledges.  This gives us
+                // an RTT sample for every ACK, including those for retransmitted data (see RFC 7323).
+                if let Some((_, ts_ecr)) = ts_option {
+                    let ts_now: u32 = timestamp_value(self.ts_base, now);
+                    if !timestamp_before(ts_now, ts_ecr) {
+                        self.rto_add_sample(Duration::from_millis(ts_now.wrapping_sub(ts_ecr) as u64));
+                    }
+                }
+
                 // Remove the now acknowledged data from the unacknowledged queue.
                 self.sender.remove_acknowledged_data(self, bytes_acknowledged, now);
+                self.update_delivery_rate(bytes_acknowledged, header.ack_num == send_next, now);
 
                 // Update SND.UNA to SEG.ACK.
                 self.sender.send_unacked.set(header.ack_num);
+                self.wake_ack_wakers();
+
+                // Our peer is making progress, so start counting retransmissions anew.
+                self.retransmissions.set(0);
 
                 // Update our send window (SND.WND).
                 self.sender.update_send_window(header);
@@ -620,18 +1223,18 @@ impl ControlBlock {
                         State::Established => (), // Common case.  Nothing more to do.
                         State::FinWait1 => {
                             // Our FIN is now ACK'd, so enter FIN-WAIT-2.
-                            self.state.set(State::FinWait2);
+                            self.set_state(State::FinWait2);
                         },
                         State::Closing => {
                             // Our FIN is now ACK'd, so enter TIME-WAIT.
-                            self.state.set(State::TimeWait);
+                            self.set_state(State::TimeWait);
                         },
                         State::LastAck => {
                             // Our FIN is now ACK'd, so this connection can be safely closed.  In LAST-ACK state we
                             // were just waiting for all of our sent data (including FIN) to be ACK'd, so now that it
                             // is, we can delete our state (we maintained it in case we needed to retransmit something,
                             // but we had already sent everything we're ever going to send (incl. FIN) at least once).
-                            self.state.set(State::Closed);
+                            self.set_state(State::Closed);
 
                             // ToDo: Delete the ControlBlock.
                         },
@@ -646,10 +1249,10 @@ impl ControlBlock {
                     self.retransmit_deadline.set(Some(deadline));
                 }
             } else {
-                // This segment acknowledges data we have yet to send!?  Send an ACK and drop the segment.
-                // ToDo: See RFC 5961, this could be a Blind Data Injection Attack.
+                // This segment acknowledges data we have yet to send!?  This could be a Blind Data Injection Attack, so
+                // send a challenge ACK and drop the segment (see RFC 5961 Section 5).
                 warn!("Received segment acknowledging data we have yet to send!");
-                self.send_ack();
+                self.send_challenge_ack(now);
                 return;
             }
         } else {
@@ -657,6 +1260,13 @@ impl ControlBlock {
             // ToDo: Implement fast-retransmit.  In which case, we'd increment our dup-ack counter here.
         }
 
+        // With SACK, our peer tells us which data it holds beyond the cumulative ACK, so we can retransmit just the
+        // holes in its receive sequence space rather than everything after the first loss.
+        if self.sack_permitted {
+            self.sender.update_scoreboard(header);
+            self.retransmit_lost_segments();
+        }
+
         // ToDo: Check the URG bit.  If we decide to support this, how should we do it?
         if header.urg {
             warn!("Got packet with URG bit set!");
@@ -676,9 +1286,21 @@ impl ControlBlock {
                             self.store_out_of_order_fin(seg_end);
                             seg_end = seg_end - SeqNumber::from(1);
                         }
-                        debug_assert_eq!(seg_len, data.len() as u32);
+                        debug_assert_eq!(
+                            seg_len,
+                            payloads.iter().map(|payload| payload.len() as u32).sum::<u32>()
+                        );
                         if seg_len > 0 {
-                            self.store_out_of_order_segment(seg_start, seg_end, data);
+                            let mut payload_start: SeqNumber = seg_start;
+                            for payload in payloads {
+                                let payload_len: u32 = payload.len() as u32;
+                                if payload_len > 0 {
+                                    let payload_end: SeqNumber = payload_start + SeqNumber::from(payload_len - 1);
+                                    self.store_out_of_order_segment(payload_start, payload_end, payload);
+                                    payload_start = payload_start + SeqNumber::from(payload_len);
+                                }
+                            }
+                            self.last_out_of_order.set(Some(seg_start));
                         }
                         // Sending an ACK here is only a "MAY" according to the RFCs, but helpful for fast retransmit.
                         self.send_ack();
@@ -692,11 +1314,15 @@ impl ControlBlock {
         }
 
         // Process the segment text (if any).
-        if !data.is_empty() {
+        let mut should_ack_now: bool = false;
+        if payloads.iter().any(|payload| !payload.is_empty()) {
             match self.state.get() {
                 State::Established | State::FinWait1 | State::FinWait2 => {
                     // We can only legitimately receive data in ESTABLISHED, FIN-WAIT-1, and FIN-WAIT-2.
-                    header.fin |= self.receive_data(seg_start, data);
+                    // A segment that fills (part of) a hole in the sequence space is ACK'd immediately, so that our
+                    // peer learns about it quickly (see RFC 5681).
+                    should_ack_now = !self.out_of_order.borrow().is_empty();
+                    header.fin |= self.receive_data(seg_start, payloads);
                     should_schedule_ack = true;
                 },
                 state => warn!("Ignoring data received after FIN (in state {:?}).", state),
@@ -713,23 +1339,23 @@ impl ControlBlock {
                 .set(self.receiver.receive_next.get() + SeqNumber::from(1));
 
             match self.state.get() {
-                State::Established => self.state.set(State::CloseWait),
+                State::Established => self.set_state(State::CloseWait),
                 State::FinWait1 => {
                     // RFC 793 has a benign logic flaw.  It says "If our FIN has been ACKed (perhaps in this segment),
                     // then enter TIME-WAIT, start the time-wait timer, turn off the other timers;".  But if our FIN
                     // has been ACK'd, we'd be in FIN-WAIT-2 here as a result of processing that ACK (see ACK handling
                     // above) and will enter TIME-WAIT in the FIN-WAIT-2 case below.  So we can skip that clause and go
                     // straight to "otherwise enter the CLOSING state".
-                    self.state.set(State::Closing);
+                    self.set_state(State::Closing);
                 },
                 State::FinWait2 => {
-                    // Enter TIME-WAIT.
-                    self.state.set(State::TimeWait);
-                    // ToDo: Start the time-wait timer and turn off the other timers.
+                    // Enter TIME-WAIT.  This starts the time-wait timer and turns off the other timers.
+                    self.set_state(State::TimeWait);
                 },
                 State::CloseWait | State::Closing | State::LastAck => (), // Remain in current state.
                 State::TimeWait => {
-                    // ToDo: Remain in TIME-WAIT.  Restart the 2 MSL time-wait timeout.
+                    // Remain in TIME-WAIT.  Restart the 2 MSL time-wait timeout.
+                    self.set_state(State::TimeWait);
                 },
                 state => panic!("Bad TCP state {:?}", state), // Should never happen.
             }
@@ -751,13 +1377,58 @@ impl ControlBlock {
         if should_schedule_ack {
             // We should ACK this segment, preferably via piggybacking on a response.
             // ToDo: Consider replacing the delayed ACK timer with a simple flag.
-            if self.ack_deadline.get().is_none() {
-                // Start the delayed ACK timer to ensure an ACK gets sent soon even if no piggyback opportunity occurs.
-                self.ack_deadline.set(Some(now + self.ack_delay_timeout));
-            } else {
-                // We already owe our peer an ACK (the timer was already running), so cancel the timer and ACK now.
-                self.ack_deadline.set(None);
+            let segments_unacked: u32 = self.segments_unacked.get() + 1;
+            self.segments_unacked.set(segments_unacked);
+            if should_ack_now || self.quickack.get() || self.ack_delay_timeout.get().is_zero() {
                 self.send_ack();
+            } else if segments_unacked >= self.ack_segments.get() {
+                // We owe our peer an ACK for enough segments that we should ACK now (see RFC 1122 Section 4.2.3.2).
+                // When processing a batch of segments, we hold it back until the end of the batch, so that a single
+                // ACK covers all the segments in it.
+                if defer_ack {
+                    self.ack_deferred.set(true);
+                } else {
+                    self.send_ack();
+                }
+            } else if self.ack_deadline.get().is_none() {
+                // Start the delayed ACK timer to ensure an ACK gets sent soon even if no piggyback opportunity occurs.
+                self.ack_deadline.set(Some(now + self.ack_delay_timeout.get()));
+            }
+        }
+    }
+
+    /// Polls for the completion of the close handshake that was initiated by [ControlBlock::close]. This completes
+    /// once our FIN has been acknowledged by the remote peer, or once the connection is closed otherwise.
+    pub fn poll_close(&self, ctx: &mut Context) -> Poll<Result<(), Fail>> {
+        // If the connection was aborted, the close handshake will never complete.
+        if let Some(errno) = self.error.get() {
+            return Poll::Ready(Err(Fail::new(errno, "connection aborted")));
+        }
+        if self.is_closed() {
+            return Poll::Ready(Ok(()));
+        }
+        *self.close_waker.borrow_mut() = Some(ctx.waker().clone());
+        Poll::Pending
+    }
+
+    // Checks whether we are done closing our side of the connection (i.e. whether our FIN has been ACK'd).
+    fn is_closed(&self) -> bool {
+        matches!(self.state.get(), State::FinWait2 | State::TimeWait | State::Closed)
+    }
+
+    // Sets the state of the connection, waking up the task that waits for the close handshake to complete, if done.
+    // Entering TIME-WAIT (again) (re)starts the time-wait timer, and turns off the other timers.
+    fn set_state(&self, state: State) {
+        self.state.set(state);
+        if state == State::TimeWait {
+            self.retransmit_deadline.set(None);
+            self.ack_deadline.set(None);
+            self.time_wait_deadline
+                .set(Some(self.clock.now() + self.time_wait_timeout));
+        }
+        if self.is_closed() {
+            if let Some(w) = self.close_waker.borrow_mut().take() {
+                w.wake()
             }
         }
     }
@@ -770,8 +1441,9 @@ impl ControlBlock {
     /// Note this routine will only be called for connections with a ControlBlock (i.e. in state ESTABLISHED or later).
     ///
     pub fn close(&self) -> Result<(), Fail> {
-        // Check to see if close has already been called, as we should only do this once.
-        if self.user_is_done_sending.get() {
+        // Check to see if close has already been called, as we should only do this once.  There is nothing left to
+        // close on aborted connections either.
+        if self.user_is_done_sending.get() || self.error.get().is_some() {
             // Review: Should we return an error here instead?  RFC 793 recommends a "connection closing" error.
             return Ok(());
         }
@@ -781,6 +1453,9 @@ impl ControlBlock {
         // only change state to FIN-WAIT-1 or LAST_ACK after we've actually been able to send the FIN.
         debug_assert!((self.state.get() == State::Established) || (self.state.get() == State::CloseWait));
 
+        // Flush any corked data along with the FIN.
+        self.cork.set(false);
+
         // Send a FIN.
         let fin_buf: DemiBuffer = DemiBuffer::new(0);
         self.send(fin_buf).expect("send failed");
@@ -791,6 +1466,20 @@ impl ControlBlock {
         Ok(())
     }
 
+    /// Handle the user's request to shut down the receive side of this connection.
+    ///
+    /// Data that is already in the receive queue may still be read.  Once it has been drained, reads complete
+    /// immediately with an empty buffer (i.e. end-of-stream), as if our peer had sent a FIN.
+    ///
+    pub fn shutdown_read(&self) {
+        self.user_is_done_receiving.set(true);
+
+        // Wake up any pending reader, so that it observes the end-of-stream.
+        if let Some(w) = self.waker.borrow_mut().take() {
+            w.wake()
+        }
+    }
+
     /// Fetch a TCP header filling out various values based on our current state.
     /// ToDo: Fix the "filling out various values based on our current state" part to actually do that correctly.
     pub fn tcp_header(&self) -> TcpHeader {
@@ -801,10 +1490,37 @@ impl ControlBlock {
         header.ack = true;
         header.ack_num = self.receiver.receive_next.get();
 
+        // Echo congestion marks.
+        header.ece = self.ece.get();
+
+        // Timestamp this segment, and echo the most recent timestamp from our peer.
+        if self.timestamps {
+            header.push_option(TcpOptions2::Timestamp {
+                sender_timestamp: timestamp_value(self.ts_base, self.clock.now()),
+                echo_timestamp: self.ts_recent.get(),
+            });
+        }
+
         // Return this header.
         header
     }
 
+    // Sends an ACK that makes our peer prove that it is in sync with us, unless we already sent too many of them lately
+    // (see RFC 5961).
+    fn send_challenge_ack(&self, now: Instant) {
+        let (mut start, mut count): (Instant, u32) = self.challenge_acks.get();
+        if now.saturating_duration_since(start) >= Duration::from_secs(1) {
+            start = now;
+            count = 0;
+        }
+        if count >= CHALLENGE_ACK_LIMIT {
+            debug!("Challenge ACK limit reached");
+            return;
+        }
+        self.challenge_acks.set((start, count + 1));
+        self.send_ack();
+    }
+
     /// Send an ACK to our peer, reflecting our current state.
     pub fn send_ack(&self) {
         let mut header: TcpHeader = self.tcp_header();
@@ -813,6 +1529,11 @@ impl ControlBlock {
         let (seq_num, _): (SeqNumber, _) = self.get_send_next();
         header.seq_num = seq_num;
 
+        // Report the out-of-order data that we hold, if any.
+        if let Some(option) = self.sack_option() {
+            header.push_option(option);
+        }
+
         // ToDo: Remove this if clause once emit() is fixed to not require the remote hardware addr (this should be
         // left to the ARP layer and not exposed to TCP).
         if let Some(remote_link_addr) = self.arp().try_query(self.remote.ip().clone()) {
@@ -820,9 +1541,97 @@ impl ControlBlock {
         }
     }
 
+    /// Builds a SACK option describing the out-of-order data that we hold (see RFC 2018). Each block covers a
+    /// contiguous range of out-of-order data, and the first one contains the most recently received segment.
+    fn sack_option(&self) -> Option<TcpOptions2> {
+        if !self.sack_permitted {
+            return None;
+        }
+
+        // Coalesce the out-of-order store (which is sorted and contains no duplicate data) into contiguous blocks.
+        let receive_next: SeqNumber = self.receiver.receive_next.get();
+        let mut blocks: Vec<SelectiveAcknowlegement> = Vec::new();
+        for (start, buf) in self.out_of_order.borrow().iter() {
+            let end: SeqNumber = *start + SeqNumber::from(buf.len() as u32);
+            if end <= receive_next {
+                continue;
+            }
+            match blocks.last_mut() {
+                Some(block) if block.end == *start => block.end = end,
+                _ => blocks.push(SelectiveAcknowlegement { begin: *start, end }),
+            }
+        }
+        if blocks.is_empty() {
+            return None;
+        }
+
+        // Report the block containing the most recently received segment first.
+        if let Some(last) = self.last_out_of_order.get() {
+            if let Some(index) = blocks.iter().position(|b| b.begin <= last && last < b.end) {
+                let block: SelectiveAcknowlegement = blocks.remove(index);
+                blocks.insert(0, block);
+            }
+        }
+
+        let max_sacks: usize = if self.timestamps {
+            MAX_SACK_BLOCKS - 1
+        } else {
+            MAX_SACK_BLOCKS
+        };
+        let num_sacks: usize = cmp::min(blocks.len(), max_sacks);
+        let mut sacks: [SelectiveAcknowlegement; MAX_SACK_BLOCKS] = [blocks[0]; MAX_SACK_BLOCKS];
+        sacks[..num_sacks].copy_from_slice(&blocks[..num_sacks]);
+        Some(TcpOptions2::SelectiveAcknowlegement { num_sacks, sacks })
+    }
+
+    /// Retransmits the segments that the SACK scoreboard deems lost, without retransmitting the data that our peer
+    /// already holds.  Retransmissions are clocked by the estimate of the data in the network, so that it stays within
+    /// cwnd (see RFC 6675).
+    fn retransmit_lost_segments(&self) {
+        let mss: usize = self.get_mss();
+        if !self.sender.has_lost_segments(mss) {
+            return;
+        }
+        // ToDo: Remove this if clause once emit() is fixed to not require the remote hardware addr.
+        if let Some(remote_link_addr) = self.arp().try_query(self.remote.ip().clone()) {
+            let (send_next, _): (SeqNumber, _) = self.sender.get_send_next();
+            self.cc.on_loss(send_next);
+            let cwnd: usize = self.cc.get_cwnd() as usize;
+            let mut pipe: usize = self.sender.get_pipe(mss);
+            while cwnd.saturating_sub(pipe) >= mss {
+                let (seq_num, bytes): (SeqNumber, DemiBuffer) = match self.sender.next_segment_to_retransmit(mss) {
+                    Some(segment) => segment,
+                    None => break,
+                };
+                debug!("Retransmitting lost segment at {}", seq_num);
+                pipe += bytes.len();
+                let mut header: TcpHeader = self.tcp_header();
+                header.seq_num = seq_num;
+                self.emit_retransmission(header, Some(bytes), remote_link_addr);
+                self.record_retransmitted_segment();
+            }
+        }
+    }
+
     /// Transmit this message to our connected peer.
     ///
     pub fn emit(&self, header: TcpHeader, body: Option<DemiBuffer>, remote_link_addr: MacAddress) {
+        self.emit_segment(header, body, remote_link_addr, false)
+    }
+
+    /// Transmit this message, which carries data that was sent before, to our connected peer.
+    ///
+    pub fn emit_retransmission(&self, header: TcpHeader, body: Option<DemiBuffer>, remote_link_addr: MacAddress) {
+        self.emit_segment(header, body, remote_link_addr, true)
+    }
+
+    fn emit_segment(
+        &self,
+        mut header: TcpHeader,
+        body: Option<DemiBuffer>,
+        remote_link_addr: MacAddress,
+        retransmission: bool,
+    ) {
         // Only perform this debug print in debug builds.  debug_assertions is compiler set in non-optimized builds.
         #[cfg(debug_assertions)]
         if body.is_some() {
@@ -835,12 +1644,24 @@ impl ControlBlock {
         debug_assert!(header.ack);
 
         let sent_fin: bool = header.fin;
+        let sent_data: bool = body.as_ref().map_or(false, |body| !body.is_empty());
+        self.last_ack_sent.set(header.ack_num);
+
+        // With ECN, segments that carry new data are ECN-capable, and the first one that we send after reducing cwnd in
+        // response to congestion carries CWR.  Pure ACKs and retransmissions are not ECN-capable, as a mark on them
+        // would not tell whether the original transmission was lost to congestion (see RFC 3168 Section 6.1.5).
+        let mut ipv4_hdr: Ipv4Header =
+            Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP);
+        if self.ecn && sent_data && !retransmission {
+            ipv4_hdr = ipv4_hdr.with_ecn(IPV4_ECN_ECT0);
+            header.cwr = self.cwr.replace(false);
+        }
 
         // Prepare description of TCP segment to send.
         // ToDo: Change this to call lower levels to fill in their header information, handle routing, ARPing, etc.
         let segment = TcpSegment {
             ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
-            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP),
+            ipv4_hdr,
             tcp_hdr: header,
             data: body,
             tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
@@ -848,20 +1669,34 @@ impl ControlBlock {
 
         // Call the runtime to send the segment.
         self.rt.transmit(Box::new(segment));
+        if let Some(tx) = self.rt.tx_timestamp() {
+            self.hw_timestamps.set(Timestamps {
+                tx: Some(tx),
+                ..self.hw_timestamps.get()
+            });
+        }
 
         // Post-send operations follow.
         // Review: We perform these after the send, in order to keep send latency as low as possible.
 
+        // Start a new delivery rate sample once data is in flight again.
+        if sent_data && self.delivery_sample.get().is_none() {
+            self.delivery_sample
+                .set(Some((self.clock.now(), self.bytes_acked.get())));
+        }
+
         // Since we sent an ACK, cancel any outstanding delayed ACK request.
         self.set_ack_deadline(None);
+        self.segments_unacked.set(0);
+        self.ack_deferred.set(false);
 
         // If we sent a FIN, update our protocol state.
         if sent_fin {
             match self.state.get() {
                 // Active close.
-                State::Established => self.state.set(State::FinWait1),
+                State::Established => self.set_state(State::FinWait1),
                 // Passive close.
-                State::CloseWait => self.state.set(State::LastAck),
+                State::CloseWait => self.set_state(State::LastAck),
                 // We can legitimately retransmit the FIN in these states.  And we stay there until the FIN is ACK'd.
                 State::FinWait1 | State::LastAck => {},
                 // We shouldn't be sending a FIN from any other state.
@@ -874,6 +1709,54 @@ impl ControlBlock {
         self.sender.remote_mss()
     }
 
+    pub fn get_out_of_order_stats(&self) -> OutOfOrderStats {
+        self.out_of_order_stats.get()
+    }
+
+    fn update_out_of_order_stats<F: FnOnce(&mut OutOfOrderStats)>(&self, f: F) {
+        let mut stats: OutOfOrderStats = self.out_of_order_stats.get();
+        f(&mut stats);
+        self.out_of_order_stats.set(stats);
+    }
+
+    pub fn set_ack_delay_timeout(&self, timeout: Duration) {
+        self.ack_delay_timeout.set(timeout);
+    }
+
+    pub fn set_ack_segments(&self, segments: u32) {
+        self.ack_segments.set(segments);
+    }
+
+    pub fn set_quickack(&self, quickack: bool) {
+        self.quickack.set(quickack);
+        // Don't keep our peer waiting for the ACK that we already owe it.
+        if quickack && self.ack_deadline.get().is_some() {
+            self.send_ack();
+        }
+    }
+
+    /// Sends the ACK that was held back while processing a batch of received segments, if any.
+    pub fn flush_deferred_ack(&self) {
+        if self.ack_deferred.replace(false) {
+            self.send_ack();
+        }
+    }
+
+    pub fn has_deferred_ack(&self) -> bool {
+        self.ack_deferred.get()
+    }
+
+    pub fn watch_time_wait_deadline(&self) -> (Option<Instant>, WatchFuture<Option<Instant>>) {
+        self.time_wait_deadline.watch()
+    }
+
+    /// Leaves TIME-WAIT once the time-wait timer expires. The connection is closed from then on.
+    pub fn finish_time_wait(&self) {
+        debug!("{:?} -> {:?} leaves TIME-WAIT", self.local, self.remote);
+        self.time_wait_deadline.set(None);
+        self.set_state(State::Closed);
+    }
+
     pub fn get_ack_deadline(&self) -> (Option<Instant>, WatchFuture<Option<Instant>>) {
         self.ack_deadline.watch()
     }
@@ -884,7 +1767,10 @@ impl ControlBlock {
 
     pub fn get_receive_window_size(&self) -> u32 {
         let bytes_unread: u32 = (self.receiver.receive_next.get() - self.receiver.reader_next.get()).into();
-        self.receive_buffer_size - bytes_unread
+        // The user may have shrunk the receive buffer below the amount of data that is waiting to be read.  It may also
+        // have grown it beyond the largest window that we can advertise with our window scale.
+        let max_window: u32 = (u16::MAX as u32) << self.window_scale;
+        cmp::min(self.receive_buffer.get().saturating_sub(bytes_unread), max_window)
     }
 
     pub fn hdr_window_size(&self) -> u16 {
@@ -901,7 +1787,7 @@ impl ControlBlock {
         hdr_window_size
     }
 
-    pub fn poll_recv(&self, ctx: &mut Context) -> Poll<Result<DemiBuffer, Fail>> {
+    pub fn poll_recv(&self, ctx: &mut Context, size: Option<usize>) -> Poll<Result<DemiBuffer, Fail>> {
         // ToDo: Need to add a way to indicate that the other side closed (i.e. that we've received a FIN).
         // Should we do this via a zero-sized buffer?  Same as with the unsent and unacked queues on the send side?
         //
@@ -910,14 +1796,47 @@ impl ControlBlock {
         // But that will think data is available to be read once we've received a FIN, because FINs consume sequence
         // number space.  Now we call is_empty() on the receive queue instead.
         if self.receiver.recv_queue.borrow().is_empty() {
+            // If the connection was aborted, no more data is coming.
+            if let Some(errno) = self.error.get() {
+                return Poll::Ready(Err(Fail::new(errno, "connection aborted")));
+            }
+            // If the user has shut down the receive side, report end-of-stream rather than waiting for more data.
+            if self.user_is_done_receiving.get() {
+                return Poll::Ready(Ok(DemiBuffer::new(0)));
+            }
             *self.waker.borrow_mut() = Some(ctx.waker().clone());
             return Poll::Pending;
         }
 
-        let segment: DemiBuffer = self
-            .receiver
-            .pop()
-            .expect("poll_recv failed to pop data from receive queue");
+        let segment: DemiBuffer = match size {
+            None => self
+                .receiver
+                .pop()
+                .expect("poll_recv failed to pop data from receive queue"),
+            Some(size) => {
+                // Wait until at least `size` bytes are ready to be read, unless no more data is coming.
+                let (nbytes, eof): (usize, bool) = self.receiver.readable();
+                if nbytes < size && !eof && !self.user_is_done_receiving.get() {
+                    if let Some(errno) = self.error.get() {
+                        return Poll::Ready(Err(Fail::new(errno, "connection aborted")));
+                    }
+                    *self.waker.borrow_mut() = Some(ctx.waker().clone());
+                    return Poll::Pending;
+                }
+                match self.receiver.pop_sized(size) {
+                    Ok(buf) => buf.expect("poll_recv failed to pop data from receive queue"),
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            },
+        };
+
+        // Once per round trip, grow the receive buffer to keep up with the user.
+        if let Some(target) = self
+            .receive_autotuner
+            .on_read(self.receiver.reader_next.get(), self.clock.now())
+        {
+            self.receive_buffer.grow(target);
+        }
 
         Poll::Ready(Ok(segment))
     }
@@ -933,6 +1852,7 @@ impl ControlBlock {
     // Note: Since this is not the "fast path", this is written for clarity over efficiency.
     //
     pub fn store_out_of_order_segment(&self, mut new_start: SeqNumber, mut new_end: SeqNumber, mut buf: DemiBuffer) {
+        self.update_out_of_order_stats(|stats| stats.segments += 1);
         let mut out_of_order = self.out_of_order.borrow_mut();
         let mut action_index: usize = out_of_order.len();
         let mut another_pass_neeeded: bool = true;
@@ -1020,11 +1940,33 @@ impl ControlBlock {
         // Insert the new segment into the correct position.
         out_of_order.insert(action_index, (new_start, buf));
 
-        // If the out-of-order store now contains too many entries, delete the later entries.
-        // ToDo: The out-of-order store is already limited (in size) by our receive window, while the below check
-        // imposes a limit on the number of entries.  Do we need this?  Presumably for attack mitigation?
-        while out_of_order.len() > MAX_OUT_OF_ORDER {
-            out_of_order.pop_back();
+        // If the out-of-order store is now over its limits, drop data according to the configured policy.
+        let max_segments: usize = self.tcp_config.get_out_of_order_max_segments();
+        let max_bytes: usize = self.tcp_config.get_out_of_order_max_bytes();
+        let over_limits = |out_of_order: &VecDeque<(SeqNumber, DemiBuffer)>| -> bool {
+            out_of_order.len() > max_segments
+                || out_of_order.iter().map(|(_, buf)| buf.len()).sum::<usize>() > max_bytes
+        };
+        match self.tcp_config.get_out_of_order_drop_policy() {
+            OutOfOrderDropPolicy::DropHighest => {
+                // Delete the later entries, as they are the furthest from being delivered.  Any out-of-order FIN comes
+                // after them, so we will have to receive it again.
+                while over_limits(&out_of_order) {
+                    out_of_order.pop_back();
+                    self.out_of_order_fin.set(None);
+                    self.update_out_of_order_stats(|stats| stats.drops += 1);
+                }
+            },
+            OutOfOrderDropPolicy::DropNew => {
+                if over_limits(&out_of_order) {
+                    let (start, buf) = out_of_order.remove(action_index).unwrap();
+                    // Likewise, if the new segment carried a FIN.
+                    if self.out_of_order_fin.get() == Some(start + SeqNumber::from(buf.len() as u32)) {
+                        self.out_of_order_fin.set(None);
+                    }
+                    self.update_out_of_order_stats(|stats| stats.drops += 1);
+                }
+            },
         }
     }
 
@@ -1036,15 +1978,19 @@ impl ControlBlock {
     //
     // Returns true if a previously out-of-order segment containing a FIN has now been received.
     //
-    pub fn receive_data(&self, seg_start: SeqNumber, buf: DemiBuffer) -> bool {
-        let recv_next: SeqNumber = self.receiver.receive_next.get();
+    pub fn receive_data(&self, seg_start: SeqNumber, payloads: Payloads) -> bool {
+        let mut recv_next: SeqNumber = self.receiver.receive_next.get();
 
         // This routine should only be called with in-order segment data.
         debug_assert_eq!(seg_start, recv_next);
 
         // Push the new segment data onto the end of the receive queue.
-        let mut recv_next: SeqNumber = recv_next + SeqNumber::from(buf.len() as u32);
-        self.receiver.push(buf);
+        for buf in payloads {
+            if !buf.is_empty() {
+                recv_next = recv_next + SeqNumber::from(buf.len() as u32);
+                self.receiver.push(buf);
+            }
+        }
 
         // Okay, we've successfully received some new data.  Check if any of the formerly out-of-order data waiting in
         // the out-of-order queue is now in-order.  If so, we can move it to the receive queue.
@@ -1059,6 +2005,7 @@ impl ControlBlock {
                     if let Some(temp) = out_of_order.pop_front() {
                         recv_next = recv_next + SeqNumber::from(temp.1.len() as u32);
                         self.receiver.push(temp.1);
+                        self.update_out_of_order_stats(|stats| stats.merges += 1);
                         added_out_of_order = true;
                     }
                 } else {
@@ -1068,6 +2015,10 @@ impl ControlBlock {
             }
         }
 
+        // Time how long it takes our peer to fill the window that we advertised.
+        self.receive_autotuner
+            .on_receive(recv_next, self.get_receive_window_size(), self.clock.now());
+
         // ToDo: Review recent change to update control block copy of recv_next upon each push to the receiver.
         // When receiving a retransmitted segment that fills a "hole" in the receive space, thus allowing a number
         // (potentially large number) of out-of-order segments to be added, we'll be modifying the TCB copy of
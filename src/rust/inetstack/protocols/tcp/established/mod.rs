@@ -1,14 +1,17 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod autotune;
 mod background;
 pub mod congestion_control;
 mod ctrlblk;
+mod pacer;
 mod rto;
 mod sender;
 
 pub use self::ctrlblk::{
     ControlBlock,
+    OutOfOrderStats,
     State,
 };
 
@@ -16,14 +19,24 @@ use self::background::background;
 use crate::{
     inetstack::{
         futures::FutureOperation,
-        protocols::tcp::segment::TcpHeader,
+        protocols::tcp::{
+            gro::Payloads,
+            segment::TcpHeader,
+        },
     },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
+        network::types::{
+            TcpInfo,
+            Timestamps,
+        },
         QDesc,
     },
-    scheduler::SchedulerHandle,
+    scheduler::{
+        Priority,
+        SchedulerHandle,
+    },
 };
 use ::futures::{
     channel::mpsc,
@@ -51,7 +64,10 @@ impl EstablishedSocket {
     pub fn new(cb: ControlBlock, fd: QDesc, dead_socket_tx: mpsc::UnboundedSender<QDesc>) -> Self {
         let cb = Rc::new(cb);
         let future = background(cb.clone(), fd, dead_socket_tx);
-        let handle: SchedulerHandle = match cb.scheduler.insert(FutureOperation::Background(future.boxed_local())) {
+        let handle: SchedulerHandle = match cb
+            .scheduler
+            .insert(FutureOperation::Background(future.boxed_local()), Priority::High)
+        {
             Some(handle) => handle,
             None => panic!("failed to insert task in the scheduler"),
         };
@@ -61,26 +77,46 @@ impl EstablishedSocket {
         }
     }
 
-    pub fn receive(&self, header: &mut TcpHeader, data: DemiBuffer) {
-        self.cb.receive(header, data)
+    pub fn receive(&self, header: &mut TcpHeader, payloads: Payloads, congestion_experienced: bool, defer_ack: bool) {
+        self.cb.receive(header, payloads, congestion_experienced, defer_ack)
     }
 
     pub fn send(&self, buf: DemiBuffer) -> Result<(), Fail> {
         self.cb.send(buf)
     }
 
-    pub fn poll_recv(&self, ctx: &mut Context) -> Poll<Result<DemiBuffer, Fail>> {
-        self.cb.poll_recv(ctx)
+    pub fn poll_recv(&self, ctx: &mut Context, size: Option<usize>) -> Poll<Result<DemiBuffer, Fail>> {
+        self.cb.poll_recv(ctx, size)
     }
 
     pub fn close(&self) -> Result<(), Fail> {
         self.cb.close()
     }
 
+    pub fn poll_close(&self, ctx: &mut Context) -> Poll<Result<(), Fail>> {
+        self.cb.poll_close(ctx)
+    }
+
+    pub fn shutdown_read(&self) {
+        self.cb.shutdown_read()
+    }
+
     pub fn remote_mss(&self) -> usize {
         self.cb.remote_mss()
     }
 
+    pub fn out_of_order_stats(&self) -> OutOfOrderStats {
+        self.cb.get_out_of_order_stats()
+    }
+
+    pub fn tcp_info(&self) -> TcpInfo {
+        self.cb.get_tcp_info()
+    }
+
+    pub fn timestamps(&self) -> Timestamps {
+        self.cb.get_hw_timestamps()
+    }
+
     pub fn current_rto(&self) -> Duration {
         self.cb.rto_estimate()
     }
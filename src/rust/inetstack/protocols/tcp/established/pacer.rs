@@ -0,0 +1,73 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use ::std::{
+    cell::Cell,
+    cmp,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+// Ratio between the pacing rate and the rate at which the congestion window is sent per round trip.  Pacing a bit
+// faster than cwnd / SRTT keeps the pacer from holding back the growth of cwnd during slow start.
+const PACING_GAIN: f64 = 2.0;
+
+// Spreads the transmission of segments over time, so that a full congestion window does not leave at line rate in a
+// single burst and overflow the queues of shallow-buffered switches along the path.  Segments are released at the
+// pacing rate: the lower of the rate derived from cwnd and SRTT (if enabled), and the cap set by the user.
+#[derive(Debug)]
+pub struct Pacer {
+    // Whether to pace based on the congestion window.
+    enabled: bool,
+    // Upper bound on the pacing rate (in bytes per second).  u64::MAX means no bound.
+    max_rate: Cell<u64>,
+    // Earliest time at which the next segment may be sent.
+    next_send: Cell<Option<Instant>>,
+}
+
+impl Pacer {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            max_rate: Cell::new(u64::MAX),
+            next_send: Cell::new(None),
+        }
+    }
+
+    pub fn set_max_rate(&self, rate: u64) {
+        self.max_rate.set(rate);
+    }
+
+    // Returns the pacing rate (in bytes per second), or None if segments are not paced.
+    pub fn rate(&self, cwnd: u32, srtt: Option<Duration>) -> Option<u64> {
+        let cwnd_rate: u64 = match srtt {
+            // Congestion control algorithms that do not limit cwnd do not limit the pacing rate either.
+            Some(srtt) if self.enabled && cwnd != u32::MAX => (PACING_GAIN * cwnd as f64 / srtt.as_secs_f64()) as u64,
+            _ => u64::MAX,
+        };
+        match cmp::min(cwnd_rate, self.max_rate.get()) {
+            u64::MAX => None,
+            rate => Some(cmp::max(rate, 1)),
+        }
+    }
+
+    // Returns how long to wait before sending the next segment, if at all.
+    pub fn delay(&self, now: Instant) -> Option<Duration> {
+        match self.next_send.get() {
+            Some(next_send) if next_send > now => Some(next_send - now),
+            _ => None,
+        }
+    }
+
+    // Accounts for a segment of `len` bytes sent at `now`, given the current pacing rate.
+    pub fn on_send(&self, now: Instant, len: u32, rate: Option<u64>) {
+        let next_send: Option<Instant> = rate.map(|rate| {
+            // Idle time is not credited, so that the connection cannot burst after it.
+            let start: Instant = cmp::max(self.next_send.get().unwrap_or(now), now);
+            start + Duration::from_secs_f64(len as f64 / rate as f64)
+        });
+        self.next_send.set(next_send);
+    }
+}
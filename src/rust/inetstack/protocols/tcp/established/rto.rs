@@ -14,18 +14,28 @@ pub struct RtoCalculator {
     rttvar: f64,
     rto: f64,
 
+    // Bounds of the RTO (in seconds).
+    lbound: f64,
+    ubound: f64,
+
     received_sample: bool,
 }
 
 impl RtoCalculator {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(min_rto: Duration, max_rto: Duration) -> Self {
+        let mut calculator = Self {
             srtt: 1.0,
             rttvar: 0.0,
             rto: 1.0,
 
+            lbound: FloatDuration::from(min_rto).as_seconds(),
+            ubound: FloatDuration::from(max_rto).as_seconds(),
+
             received_sample: false,
-        }
+        };
+        // The initial RTO is one second (see RFC 6298 Section 2.1), within bounds.
+        calculator.update_rto(1.0);
+        calculator
     }
 
     pub fn add_sample(&mut self, rtt: Duration) {
@@ -47,26 +57,44 @@ impl RtoCalculator {
         let rttvar_x4: f64 = match (4.0 * self.rttvar).partial_cmp(&GRANULARITY) {
             Some(cmp::Ordering::Less) => GRANULARITY,
             None => panic!("NaN rttvar: {:?}", self.rttvar),
-            _ => self.rttvar,
+            _ => 4.0 * self.rttvar,
         };
         self.update_rto(self.srtt + rttvar_x4);
     }
 
     fn update_rto(&mut self, new_rto: f64) {
-        const UBOUND_SEC: f64 = 60.0f64;
-        const LBOUND_SEC: f64 = 0.100f64;
-        self.rto = match (new_rto.partial_cmp(&LBOUND_SEC), new_rto.partial_cmp(&UBOUND_SEC)) {
-            (Some(cmp::Ordering::Less), _) => LBOUND_SEC,
-            (_, Some(cmp::Ordering::Greater)) => UBOUND_SEC,
+        self.rto = match (new_rto.partial_cmp(&self.lbound), new_rto.partial_cmp(&self.ubound)) {
+            (Some(cmp::Ordering::Less), _) => self.lbound,
+            (_, Some(cmp::Ordering::Greater)) => self.ubound,
             (None, _) | (_, None) => panic!("NaN RTO: {:?}", new_rto),
             _ => new_rto,
         };
     }
 
+    // Backs off the timer after a retransmission timeout (see RFC 6298 Section 5.5).  The backed-off RTO is kept until
+    // a new RTT sample comes in.
     pub fn record_failure(&mut self) {
         self.update_rto(self.rto * 2.0);
     }
 
+    // Smoothed round-trip time, once a sample has been taken.
+    pub fn srtt(&self) -> Option<Duration> {
+        if self.received_sample {
+            Some(FloatDuration::seconds(self.srtt).to_std().unwrap())
+        } else {
+            None
+        }
+    }
+
+    // Round-trip time variation, once a sample has been taken.
+    pub fn rttvar(&self) -> Option<Duration> {
+        if self.received_sample {
+            Some(FloatDuration::seconds(self.rttvar).to_std().unwrap())
+        } else {
+            None
+        }
+    }
+
     pub fn estimate(&self) -> Duration {
         FloatDuration::seconds(self.rto).to_std().unwrap()
     }
@@ -4,7 +4,10 @@
 use super::ControlBlock;
 use crate::{
     inetstack::protocols::tcp::{
-        segment::TcpHeader,
+        segment::{
+            TcpHeader,
+            TcpOptions2,
+        },
         SeqNumber,
     },
     runtime::{
@@ -16,15 +19,13 @@ use crate::{
         },
     },
 };
-use ::libc::{
-    EBUSY,
-    EINVAL,
-};
+use ::libc::EINVAL;
 use ::std::{
     cell::{
         Cell,
         RefCell,
     },
+    cmp,
     collections::VecDeque,
     convert::TryInto,
     fmt,
@@ -42,12 +43,13 @@ pub struct UnackedSegment {
     pub bytes: DemiBuffer,
     // Set to `None` on retransmission to implement Karn's algorithm.
     pub initial_tx: Option<Instant>,
+    // Whether our peer has selectively acknowledged this segment (see RFC 2018).
+    pub sacked: bool,
 }
 
-/// Hard limit for unsent queue.
-/// ToDo: Remove this.  We should limit the unsent queue by either having a (configurable) send buffer size (in bytes,
-/// not segments) and rejecting send requests that exceed that, or by limiting the user's send buffer allocations.
-const UNSENT_QUEUE_CUTOFF: usize = 1024;
+/// Number of segments that must be selectively acknowledged above an unacknowledged segment before we deem it lost
+/// (see RFC 6675).
+const DUP_THRESH: usize = 3;
 
 // ToDo: Consider moving retransmit timer and congestion control fields out of this structure.
 // ToDo: Make all public fields in this structure private.
@@ -71,6 +73,10 @@ pub struct Sender {
     // Queue of unacknowledged sent data.  RFC 793 calls this the "retransmission queue".
     unacked_queue: RefCell<VecDeque<UnackedSegment>>,
 
+    // Sequence Number following the highest data retransmitted during SACK-based loss recovery.  In RFC 6675 terms,
+    // this is HighRxt.  Holes below it have already been retransmitted.
+    high_retransmitted: Cell<SeqNumber>,
+
     // Sequence Number of the next data to be sent.  In RFC 793 terms, this is SND.NXT.
     send_next: WatchedValue<SeqNumber>,
 
@@ -88,8 +94,7 @@ pub struct Sender {
     // RFC 1323: Number of bits to shift advertised window, defaults to zero.
     window_scale: u8,
 
-    // Maximum Segment Size currently in use for this connection.
-    // ToDo: Revisit this once we support path MTU discovery.
+    // Maximum Segment Size negotiated for this connection.  The control block further clamps it to the path MTU.
     mss: usize,
 }
 
@@ -111,6 +116,7 @@ impl Sender {
         Self {
             send_unacked: WatchedValue::new(seq_no),
             unacked_queue: RefCell::new(VecDeque::new()),
+            high_retransmitted: Cell::new(seq_no),
             send_next: WatchedValue::new(seq_no),
             unsent_queue: RefCell::new(VecDeque::new()),
             unsent_seq_no: WatchedValue::new(seq_no),
@@ -148,8 +154,13 @@ impl Sender {
         self.unsent_seq_no.watch()
     }
 
-    pub fn pop_unacked_segment(&self) -> Option<UnackedSegment> {
-        self.unacked_queue.borrow_mut().pop_front()
+    // Returns the data of the oldest unacknowledged segment, for retransmission.  The segment stays on the
+    // unacknowledged queue until it is acknowledged, but is no longer used for RTT estimation (see Karn's algorithm).
+    pub fn first_unacked_segment(&self) -> Option<DemiBuffer> {
+        let mut unacked_queue = self.unacked_queue.borrow_mut();
+        let segment: &mut UnackedSegment = unacked_queue.front_mut()?;
+        segment.initial_tx.take();
+        Some(segment.bytes.clone())
     }
 
     pub fn push_unacked_segment(&self, segment: UnackedSegment) {
@@ -202,7 +213,6 @@ impl Sender {
             let send_next: SeqNumber = self.send_next.get();
             let sent_data: u32 = (send_next - send_unacknowledged).into();
 
-            // ToDo: What limits buffer len to MSS?
             let in_flight_after_send: u32 = sent_data + buf_len;
 
             // Before we get cwnd for the check, we prompt it to shrink it if the connection has been idle.
@@ -214,7 +224,22 @@ impl Sender {
 
             let win_sz: u32 = self.send_window.get();
 
-            if win_sz > 0 && win_sz >= in_flight_after_send && effective_cwnd >= in_flight_after_send {
+            // Buffers larger than the MSS are left to the background sender, which splits them into segments.
+            let fits_in_segment: bool = buf_len as usize <= cb.get_mss();
+
+            // Segments held back by the pacer are also left to the background sender.
+            let paced: bool = cb.pacing_delay().is_some();
+
+            // So are partial segments held back by Nagle's algorithm or corking.
+            let held_back: bool = cb.hold_partial_segment(buf_len as usize, sent_data);
+
+            if fits_in_segment
+                && !paced
+                && !held_back
+                && win_sz > 0
+                && win_sz >= in_flight_after_send
+                && effective_cwnd >= in_flight_after_send
+            {
                 if let Some(remote_link_addr) = cb.arp().try_query(cb.get_remote().ip().clone()) {
                     // This hook is primarily intended to record the last time we sent data, so we can later tell if
                     // the connection has been idle.
@@ -232,6 +257,7 @@ impl Sender {
                     }
                     trace!("Send immediate");
                     cb.emit(header, Some(buf.clone()), remote_link_addr);
+                    cb.pacing_on_send(buf_len);
 
                     // Update SND.NXT.
                     self.send_next.modify(|s| s + SeqNumber::from(buf_len));
@@ -243,6 +269,7 @@ impl Sender {
                     let unacked_segment = UnackedSegment {
                         bytes: buf,
                         initial_tx: Some(cb.clock.now()),
+                        sacked: false,
                     };
                     self.unacked_queue.borrow_mut().push_back(unacked_segment);
 
@@ -259,12 +286,6 @@ impl Sender {
             }
         }
 
-        // Too fast.
-        // ToDo: We need to fix this the correct way: limit our send buffer size to the amount we're willing to buffer.
-        if self.unsent_queue.borrow().len() > UNSENT_QUEUE_CUTOFF {
-            return Err(Fail::new(EBUSY, "too many packets to send"));
-        }
-
         // Slow path: Delegating sending the data to background processing.
         trace!("Queueing Send for background processing");
         self.unsent_queue.borrow_mut().push_back(buf);
@@ -282,8 +303,8 @@ impl Sender {
             if let Some(segment) = self.unacked_queue.borrow_mut().front_mut() {
                 // Add sample for RTO if we have an initial transmit time.
                 // Note that in the case of repacketization, an ack for the first byte is enough for the time sample.
-                // ToDo: TCP timestamp support.
-                if let Some(initial_tx) = segment.initial_tx {
+                // With timestamps, RTT samples come from the echoed timestamps instead.
+                if let (false, Some(initial_tx)) = (cb.timestamps_enabled(), segment.initial_tx) {
                     cb.rto_add_sample(now - initial_tx);
                 }
 
@@ -317,6 +338,136 @@ impl Sender {
         }
     }
 
+    // Update the SACK scoreboard (see RFC 6675), marking the unacknowledged segments that are covered by the SACK
+    // blocks in an incoming segment as selectively acknowledged.
+    //
+    pub fn update_scoreboard(&self, header: &TcpHeader) {
+        let mut seg_start: SeqNumber = self.send_unacked.get();
+        for segment in self.unacked_queue.borrow_mut().iter_mut() {
+            let seg_end: SeqNumber = seg_start + SeqNumber::from(segment.bytes.len() as u32);
+            for option in header.iter_options() {
+                if let TcpOptions2::SelectiveAcknowlegement { num_sacks, sacks } = option {
+                    for block in sacks.iter().take(*num_sacks) {
+                        if !segment.bytes.is_empty() && block.begin <= seg_start && seg_end <= block.end {
+                            segment.sacked = true;
+                        }
+                    }
+                }
+            }
+            seg_start = seg_end;
+        }
+    }
+
+    // Returns the sequence number of each unacknowledged segment, and whether the SACK scoreboard deems it lost.  A
+    // segment is deemed lost once DUP_THRESH segments, or more than (DUP_THRESH - 1) * MSS bytes, above it have been
+    // selectively acknowledged.  In RFC 6675 terms, this is IsLost().
+    //
+    fn scan_scoreboard(&self, mss: usize) -> Vec<(SeqNumber, bool)> {
+        let unacked_queue = self.unacked_queue.borrow();
+        let mut scoreboard: Vec<(SeqNumber, bool)> = Vec::with_capacity(unacked_queue.len());
+        let mut seg_start: SeqNumber = self.send_unacked.get();
+        for segment in unacked_queue.iter() {
+            scoreboard.push((seg_start, false));
+            seg_start = seg_start + SeqNumber::from(segment.bytes.len() as u32);
+        }
+
+        // Walk the queue backwards, counting the data selectively acknowledged above each segment.
+        let mut sacked_segments: usize = 0;
+        let mut sacked_bytes: usize = 0;
+        for (segment, (_, is_lost)) in unacked_queue.iter().zip(scoreboard.iter_mut()).rev() {
+            if segment.sacked {
+                sacked_segments += 1;
+                sacked_bytes += segment.bytes.len();
+            } else if !segment.bytes.is_empty() {
+                *is_lost = sacked_segments >= DUP_THRESH || sacked_bytes > (DUP_THRESH - 1) * mss;
+            }
+        }
+        scoreboard
+    }
+
+    // Returns HighRxt, which never lags behind SND.UNA.
+    //
+    fn get_high_retransmitted(&self) -> SeqNumber {
+        cmp::max(self.high_retransmitted.get(), self.send_unacked.get())
+    }
+
+    // Returns the number of bytes that are still in the network according to the SACK scoreboard.  Segments that are
+    // neither selectively acknowledged nor deemed lost count once, and those that we retransmitted count once more.  In
+    // RFC 6675 terms, this is SetPipe().
+    //
+    pub fn get_pipe(&self, mss: usize) -> usize {
+        let high_retransmitted: SeqNumber = self.get_high_retransmitted();
+        let scoreboard: Vec<(SeqNumber, bool)> = self.scan_scoreboard(mss);
+        let mut pipe: usize = 0;
+        for (segment, (seg_start, is_lost)) in self.unacked_queue.borrow().iter().zip(scoreboard) {
+            if segment.sacked {
+                continue;
+            }
+            if !is_lost {
+                pipe += segment.bytes.len();
+            }
+            if seg_start < high_retransmitted {
+                pipe += segment.bytes.len();
+            }
+        }
+        pipe
+    }
+
+    // Returns whether the SACK scoreboard deems lost some data that we did not retransmit yet.
+    //
+    pub fn has_lost_segments(&self, mss: usize) -> bool {
+        let high_retransmitted: SeqNumber = self.get_high_retransmitted();
+        self.scan_scoreboard(mss)
+            .into_iter()
+            .any(|(seg_start, is_lost)| is_lost && high_retransmitted <= seg_start)
+    }
+
+    // Returns the next unacknowledged segment to retransmit during SACK-based loss recovery, along with its sequence
+    // number, and moves HighRxt past it.  This is the first segment above HighRxt that is deemed lost or, if there is
+    // no unsent data, the first one above HighRxt that has yet to be selectively acknowledged while data above it was.
+    // In RFC 6675 terms, these are rules (1) and (3) of NextSeg(), while rule (2) is up to the regular send path.
+    //
+    pub fn next_segment_to_retransmit(&self, mss: usize) -> Option<(SeqNumber, DemiBuffer)> {
+        let high_retransmitted: SeqNumber = self.get_high_retransmitted();
+        let scoreboard: Vec<(SeqNumber, bool)> = self.scan_scoreboard(mss);
+        let mut unacked_queue = self.unacked_queue.borrow_mut();
+
+        // Only segments below the highest selectively acknowledged data are candidates.
+        let highest_sacked: usize = unacked_queue.iter().rposition(|segment| segment.sacked)?;
+        let candidates: Vec<usize> = (0..highest_sacked)
+            .filter(|i| {
+                let segment: &UnackedSegment = &unacked_queue[*i];
+                !segment.sacked && !segment.bytes.is_empty() && high_retransmitted <= scoreboard[*i].0
+            })
+            .collect();
+        let index: usize = match candidates.iter().find(|i| scoreboard[**i].1) {
+            Some(index) => *index,
+            None if self.unsent_queue.borrow().is_empty() => *candidates.first()?,
+            None => return None,
+        };
+
+        // Unset the initial timestamp so we don't use this for RTT estimation.
+        let segment: &mut UnackedSegment = &mut unacked_queue[index];
+        segment.initial_tx.take();
+        let seg_start: SeqNumber = scoreboard[index].0;
+        self.high_retransmitted
+            .set(seg_start + SeqNumber::from(segment.bytes.len() as u32));
+        Some((seg_start, segment.bytes.clone()))
+    }
+
+    // Restarts SACK-based loss recovery after a retransmission timeout, which retransmits the first unacknowledged
+    // segment.  The holes above it are retransmitted again, as their earlier retransmissions were likely lost too.
+    //
+    pub fn on_rto(&self) {
+        let send_unacked: SeqNumber = self.send_unacked.get();
+        let first_len: u32 = self
+            .unacked_queue
+            .borrow()
+            .front()
+            .map_or(0, |segment| segment.bytes.len() as u32);
+        self.high_retransmitted.set(send_unacked + SeqNumber::from(first_len));
+    }
+
     pub fn pop_one_unsent_byte(&self) -> Option<DemiBuffer> {
         let mut queue = self.unsent_queue.borrow_mut();
 
@@ -334,12 +485,35 @@ impl Sender {
     }
 
     pub fn pop_unsent(&self, max_bytes: usize) -> Option<DemiBuffer> {
-        // TODO: Use a scatter/gather array to coalesce multiple buffers into a single segment.
         let mut unsent_queue = self.unsent_queue.borrow_mut();
         let mut buf: DemiBuffer = unsent_queue.pop_front()?;
         let buf_len: usize = buf.len();
 
-        if buf_len > max_bytes {
+        if buf_len > 0 && buf_len < max_bytes {
+            // Coalesce the small buffers that follow (e.g. the ones held back by Nagle's algorithm) into this segment.
+            // The end-of-send marker is left on the queue, so that it gets a segment of its own.
+            // TODO: Use a scatter/gather array to avoid copying the data.
+            let mut coalesced: Option<Vec<u8>> = None;
+            while let Some(next_buf) = unsent_queue.front_mut() {
+                let len: usize = coalesced.as_ref().map_or(buf_len, |data| data.len());
+                if next_buf.len() == 0 || len == max_bytes {
+                    break;
+                }
+                let data: &mut Vec<u8> = coalesced.get_or_insert_with(|| buf.to_vec());
+                let nbytes: usize = cmp::min(next_buf.len(), max_bytes - len);
+                data.extend_from_slice(&next_buf[..nbytes]);
+                if nbytes == next_buf.len() {
+                    unsent_queue.pop_front();
+                } else {
+                    next_buf
+                        .adjust(nbytes)
+                        .expect("'next_buf' should contain at least 'nbytes'");
+                }
+            }
+            if let Some(data) = coalesced {
+                buf = DemiBuffer::from_slice(&data).expect("coalesced data should fit in a segment");
+            }
+        } else if buf_len > max_bytes {
             let mut cloned_buf: DemiBuffer = buf.clone();
 
             buf.adjust(max_bytes)
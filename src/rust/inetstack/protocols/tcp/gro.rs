@@ -0,0 +1,137 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file implements Generic Receive Offload (GRO)-style coalescing of TCP segments.
+//
+// While a batch of frames is received, the data segments of a connection are held back instead of being processed one
+// at a time.  Each following segment that continues the held one (same connection, contiguous sequence number, same
+// acknowledgement, window and timestamp) is appended to it, so that the connection processes a single large segment
+// for the whole run.  Payloads are not copied: the coalesced segment carries the payloads of the run, in order.  The
+// held segment is delivered as soon as a segment that does not continue it arrives, and at the end of the batch.
+// Segments that carry anything but data and an ACK (e.g. SYN, FIN, RST, urgent data or SACK blocks) are never held
+// back, and a PSH ends the run, as it marks the end of a message.
+//
+
+use crate::{
+    inetstack::protocols::tcp::{
+        segment::{
+            TcpHeader,
+            TcpOptions2,
+        },
+        SeqNumber,
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::consts::RECEIVE_BATCH_SIZE,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::net::SocketAddrV4;
+
+/// Payloads of a segment, which are more than one for a segment that was coalesced from a run of segments. Runs never
+/// span batches, so they never hold more payloads than a batch holds frames.
+pub type Payloads = ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>;
+
+pub struct GroSegment {
+    // Connection that the held segment belongs to.
+    key: (SocketAddrV4, SocketAddrV4),
+    // Header of the first segment of the run.
+    header: TcpHeader,
+    // Payloads of the segments of the run, in order.
+    payloads: Payloads,
+    // Total length of the payloads.
+    len: usize,
+    // Whether the segments of the run were marked with Congestion Experienced.
+    congestion_experienced: bool,
+}
+
+impl GroSegment {
+    /// Holds back a data segment, if it may be coalesced with the following ones.
+    pub fn new(
+        key: (SocketAddrV4, SocketAddrV4),
+        header: TcpHeader,
+        data: DemiBuffer,
+        congestion_experienced: bool,
+    ) -> Result<Self, (TcpHeader, DemiBuffer)> {
+        if !Self::is_eligible(&header, &data) {
+            return Err((header, data));
+        }
+        let len: usize = data.len();
+        let mut payloads: Payloads = ArrayVec::new();
+        payloads.push(data);
+        Ok(Self {
+            key,
+            header,
+            len,
+            payloads,
+            congestion_experienced,
+        })
+    }
+
+    /// Appends a segment to the held one, if it continues it. Otherwise, the segment is handed back.
+    pub fn try_append(
+        &mut self,
+        key: &(SocketAddrV4, SocketAddrV4),
+        header: TcpHeader,
+        data: DemiBuffer,
+        congestion_experienced: bool,
+    ) -> Result<(), (TcpHeader, DemiBuffer)> {
+        let continues: bool = *key == self.key
+            && !self.header.psh
+            && Self::is_eligible(&header, &data)
+            && !header.cwr
+            && header.seq_num == self.header.seq_num + SeqNumber::from(self.len as u32)
+            && header.ack_num == self.header.ack_num
+            && header.window_size == self.header.window_size
+            && header.ece == self.header.ece
+            && Self::timestamp(&header) == Self::timestamp(&self.header)
+            && congestion_experienced == self.congestion_experienced
+            && !self.payloads.is_full();
+        if !continues {
+            return Err((header, data));
+        }
+        self.header.psh = header.psh;
+        self.len += data.len();
+        self.payloads.push(data);
+        Ok(())
+    }
+
+    /// Gets the connection that the held segment belongs to.
+    pub fn get_key(&self) -> (SocketAddrV4, SocketAddrV4) {
+        self.key
+    }
+
+    /// Gets the number of segments that were coalesced into the held one.
+    pub fn num_segments(&self) -> usize {
+        self.payloads.len()
+    }
+
+    /// Releases the held segment, which carries the payloads of the run.
+    pub fn into_segment(self) -> (TcpHeader, Payloads, bool) {
+        (self.header, self.payloads, self.congestion_experienced)
+    }
+
+    // Checks whether a segment only carries data and an ACK, with no option other than a timestamp.
+    fn is_eligible(header: &TcpHeader, data: &DemiBuffer) -> bool {
+        !data.is_empty()
+            && header.ack
+            && !header.syn
+            && !header.fin
+            && !header.rst
+            && !header.urg
+            && header
+                .iter_options()
+                .all(|option| matches!(option, TcpOptions2::NoOperation | TcpOptions2::Timestamp { .. }))
+    }
+
+    // Gets the timestamp option of a segment, if any.
+    fn timestamp(header: &TcpHeader) -> Option<(u32, u32)> {
+        header.iter_options().find_map(|option| match option {
+            TcpOptions2::Timestamp {
+                sender_timestamp,
+                echo_timestamp,
+            } => Some((*sender_timestamp, *echo_timestamp)),
+            _ => None,
+        })
+    }
+}
@@ -4,22 +4,30 @@
 mod active_open;
 pub mod constants;
 mod established;
+mod gro;
 mod isn_generator;
 pub mod operations;
 mod passive_open;
 pub mod peer;
 pub mod segment;
 mod sequence_number;
+pub mod steering;
+mod syn_cookie;
+mod timestamp;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::{
-    established::congestion_control,
+    established::{
+        congestion_control,
+        OutOfOrderStats,
+    },
     peer::TcpPeer,
     segment::{
         MAX_TCP_HEADER_SIZE,
         MIN_TCP_HEADER_SIZE,
     },
     sequence_number::SeqNumber,
+    steering::ConnectionSteering,
 };
@@ -1,15 +1,23 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-use super::peer::{
-    Inner,
-    TcpPeer,
+use super::{
+    established::ControlBlock,
+    peer::{
+        Inner,
+        TcpPeer,
+    },
+    SeqNumber,
 };
 use crate::{
-    inetstack::operations::OperationResult,
+    inetstack::{
+        futures::Deadline,
+        operations::OperationResult,
+    },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
+        types::DEMI_QR_FLAG_EOF,
         QDesc,
     },
     scheduler::FutureResult,
@@ -18,6 +26,7 @@ use ::std::{
     cell::RefCell,
     fmt,
     future::Future,
+    net::SocketAddrV4,
     pin::Pin,
     rc::Rc,
     task::{
@@ -28,9 +37,11 @@ use ::std::{
 
 pub enum TcpOperation {
     Accept(FutureResult<AcceptFuture>),
+    Close(FutureResult<CloseFuture>),
     Connect(FutureResult<ConnectFuture>),
     Pop(FutureResult<PopFuture>),
     Push(FutureResult<PushFuture>),
+    Splice(FutureResult<SpliceFuture>),
 }
 
 impl From<AcceptFuture> for TcpOperation {
@@ -39,6 +50,12 @@ impl From<AcceptFuture> for TcpOperation {
     }
 }
 
+impl From<CloseFuture> for TcpOperation {
+    fn from(f: CloseFuture) -> Self {
+        TcpOperation::Close(FutureResult::new(f, None))
+    }
+}
+
 impl From<ConnectFuture> for TcpOperation {
     fn from(f: ConnectFuture) -> Self {
         TcpOperation::Connect(FutureResult::new(f, None))
@@ -57,20 +74,52 @@ impl From<PopFuture> for TcpOperation {
     }
 }
 
+impl From<SpliceFuture> for TcpOperation {
+    fn from(f: SpliceFuture) -> Self {
+        TcpOperation::Splice(FutureResult::new(f, None))
+    }
+}
+
 impl Future for TcpOperation {
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<()> {
         match self.get_mut() {
             TcpOperation::Accept(ref mut f) => Future::poll(Pin::new(f), ctx),
+            TcpOperation::Close(ref mut f) => Future::poll(Pin::new(f), ctx),
             TcpOperation::Connect(ref mut f) => Future::poll(Pin::new(f), ctx),
             TcpOperation::Push(ref mut f) => Future::poll(Pin::new(f), ctx),
             TcpOperation::Pop(ref mut f) => Future::poll(Pin::new(f), ctx),
+            TcpOperation::Splice(ref mut f) => Future::poll(Pin::new(f), ctx),
         }
     }
 }
 
 impl TcpOperation {
+    /// Gets the kind of the target [TcpOperation], for introspection.
+    pub fn get_kind(&self) -> &'static str {
+        match self {
+            TcpOperation::Accept(_) => "Accept",
+            TcpOperation::Close(_) => "Close",
+            TcpOperation::Connect(_) => "Connect",
+            TcpOperation::Push(_) => "Push",
+            TcpOperation::Pop(_) => "Pop",
+            TcpOperation::Splice(_) => "Splice",
+        }
+    }
+
+    /// Gets the queue descriptor that owns the target [TcpOperation].
+    pub fn get_qd(&self) -> QDesc {
+        match self {
+            TcpOperation::Accept(f) => f.future.qd,
+            TcpOperation::Close(f) => f.future.fd,
+            TcpOperation::Connect(f) => f.future.fd,
+            TcpOperation::Push(f) => f.future.fd,
+            TcpOperation::Pop(f) => f.future.fd,
+            TcpOperation::Splice(f) => f.future.fd,
+        }
+    }
+
     pub fn expect_result(self) -> (QDesc, Option<QDesc>, OperationResult) {
         match self {
             // Connect operation.
@@ -93,6 +142,16 @@ impl TcpOperation {
                 done: Some(Err(e)),
             }) => (future.qd, Some(future.new_qd), OperationResult::Failed(e)),
 
+            // Close operation.
+            TcpOperation::Close(FutureResult {
+                future,
+                done: Some(Ok(())),
+            }) => (future.fd, None, OperationResult::Close),
+            TcpOperation::Close(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.fd, None, OperationResult::Failed(e)),
+
             // Push operation
             TcpOperation::Push(FutureResult {
                 future,
@@ -107,20 +166,71 @@ impl TcpOperation {
             TcpOperation::Pop(FutureResult {
                 future,
                 done: Some(Ok(bytes)),
-            }) => (future.fd, None, OperationResult::Pop(None, bytes)),
+            }) => {
+                // The receive path only ever hands out an empty buffer once it has reached end-of-stream.
+                let flags: u32 = if bytes.is_empty() { DEMI_QR_FLAG_EOF } else { 0 };
+                (future.fd, None, OperationResult::Pop(None, bytes, flags, None))
+            },
             TcpOperation::Pop(FutureResult {
                 future,
                 done: Some(Err(e)),
             }) => (future.fd, None, OperationResult::Failed(e)),
 
+            // Splice operation.
+            TcpOperation::Splice(FutureResult {
+                future,
+                done: Some(Ok(nbytes)),
+            }) => (future.fd, None, OperationResult::Splice(nbytes)),
+            TcpOperation::Splice(FutureResult {
+                future,
+                done: Some(Err(e)),
+            }) => (future.fd, None, OperationResult::Failed(e)),
+
             _ => panic!("Future not ready"),
         }
     }
 }
 
+/// Close Operation Descriptor
+///
+/// Waits for the close handshake of a connection to complete, once the local FIN has been sent.
+pub struct CloseFuture {
+    /// Queue descriptor of the closed socket.
+    pub fd: QDesc,
+    /// Endpoints of the connection whose close handshake is awaited, if any.
+    pub key: Option<(SocketAddrV4, SocketAddrV4)>,
+    /// Reference to associated inner TCP peer.
+    pub inner: Rc<RefCell<Inner>>,
+}
+
+impl fmt::Debug for CloseFuture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CloseFuture({:?})", self.fd)
+    }
+}
+
+impl Future for CloseFuture {
+    type Output = Result<(), Fail>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+        match self_.key {
+            Some(key) => {
+                let peer = TcpPeer {
+                    inner: self_.inner.clone(),
+                };
+                peer.poll_close(key, ctx)
+            },
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
 pub struct ConnectFuture {
     pub fd: QDesc,
     pub inner: Rc<RefCell<Inner>>,
+    /// Deadline for this operation.
+    pub deadline: Deadline,
 }
 
 impl fmt::Debug for ConnectFuture {
@@ -134,7 +244,10 @@ impl Future for ConnectFuture {
 
     fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
         let self_ = self.get_mut();
-        self_.inner.borrow_mut().poll_connect_finished(self_.fd, context)
+        match self_.inner.borrow_mut().poll_connect_finished(self_.fd, context) {
+            Poll::Pending => self_.deadline.poll_expired(context).map(Err),
+            result => result,
+        }
     }
 }
 
@@ -181,6 +294,11 @@ impl Future for AcceptFuture {
 pub struct PushFuture {
     pub fd: QDesc,
     pub err: Option<Fail>,
+    /// Connection along with the sequence number up to which the remote peer must acknowledge data before the push
+    /// completes, if it cannot complete right away. This is the case for data in externally-owned memory, which the
+    /// application may reuse as soon as the push completes, while it still sits in the retransmission queue, and for
+    /// pushes that overshoot the send buffer.
+    pub ack: Option<(Rc<ControlBlock>, SeqNumber)>,
 }
 
 impl fmt::Debug for PushFuture {
@@ -192,17 +310,22 @@ impl fmt::Debug for PushFuture {
 impl Future for PushFuture {
     type Output = Result<(), Fail>;
 
-    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<Self::Output> {
-        match self.get_mut().err.take() {
-            None => Poll::Ready(Ok(())),
-            Some(e) => Poll::Ready(Err(e)),
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let self_: &mut PushFuture = self.get_mut();
+        match (self_.err.take(), &self_.ack) {
+            (Some(e), _) => Poll::Ready(Err(e)),
+            (None, Some((cb, seq_no))) => cb.poll_acked(*seq_no, context),
+            (None, None) => Poll::Ready(Ok(())),
         }
     }
 }
 
 pub struct PopFuture {
     pub fd: QDesc,
+    pub size: Option<usize>,
     pub inner: Rc<RefCell<Inner>>,
+    /// Deadline for this operation.
+    pub deadline: Deadline,
 }
 
 impl fmt::Debug for PopFuture {
@@ -219,6 +342,69 @@ impl Future for PopFuture {
         let peer = TcpPeer {
             inner: self_.inner.clone(),
         };
-        peer.poll_recv(self_.fd, ctx)
+        match peer.poll_recv(self_.fd, ctx, self_.size) {
+            Poll::Pending => self_.deadline.poll_expired(ctx).map(Err),
+            result => result,
+        }
+    }
+}
+
+/// Splice Operation Descriptor
+///
+/// Forwards data from a source socket to a destination socket until the remote peer of the source socket closes the
+/// connection. Buffers that are popped from the source socket are pushed as is to the destination socket. Data is only
+/// popped from the source socket once the destination socket can take it, and once the data queued for sending on the
+/// destination socket fits in its send buffer, so that a slow destination holds back the source.
+pub struct SpliceFuture {
+    /// Queue descriptor of the source socket.
+    pub fd: QDesc,
+    /// Queue descriptor of the destination socket.
+    pub dst: QDesc,
+    /// Reference to associated inner TCP peer.
+    pub inner: Rc<RefCell<Inner>>,
+    /// Number of bytes forwarded so far.
+    pub nbytes: usize,
+}
+
+impl fmt::Debug for SpliceFuture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SpliceFuture({:?} -> {:?})", self.fd, self.dst)
+    }
+}
+
+impl Future for SpliceFuture {
+    type Output = Result<usize, Fail>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+        let peer = TcpPeer {
+            inner: self_.inner.clone(),
+        };
+        loop {
+            // Check that the destination socket can take data before popping any, so that none of it is lost.
+            peer.check_send(self_.dst)?;
+
+            // Wait for the data queued on the destination socket to fit in its send buffer.
+            if let Some((cb, seq_no)) = peer.get_ack_point(self_.dst, false) {
+                match cb.poll_acked(seq_no, ctx) {
+                    Poll::Ready(Ok(())) => (),
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            match peer.poll_recv(self_.fd, ctx, None) {
+                // End of stream.
+                Poll::Ready(Ok(buf)) if buf.is_empty() => return Poll::Ready(Ok(self_.nbytes)),
+                // Forward data without copying it.
+                Poll::Ready(Ok(buf)) => {
+                    let len: usize = buf.len();
+                    peer.send(self_.dst, buf)?;
+                    self_.nbytes += len;
+                },
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
     }
 }
@@ -5,6 +5,10 @@ use super::{
     constants::FALLBACK_MSS,
     established::ControlBlock,
     isn_generator::IsnGenerator,
+    syn_cookie::{
+        SynCookieGenerator,
+        SYN_COOKIE_KEY_SIZE,
+    },
 };
 use crate::{
     inetstack::{
@@ -16,17 +20,18 @@ use crate::{
                 Ethernet2Header,
             },
             ip::IpProtocol,
-            ipv4::Ipv4Header,
+            ipv4::{
+                Ipv4Header,
+                PathMtuCache,
+            },
             tcp::{
-                established::{
-                    congestion_control,
-                    congestion_control::CongestionControl,
-                },
+                established::congestion_control::CongestionControlConstructor,
                 segment::{
                     TcpHeader,
                     TcpOptions2,
                     TcpSegment,
                 },
+                timestamp::timestamp_value,
                 SeqNumber,
             },
         },
@@ -41,6 +46,7 @@ use crate::{
         timer::TimerRc,
     },
     scheduler::{
+        Priority,
         Scheduler,
         SchedulerHandle,
     },
@@ -67,15 +73,29 @@ use ::std::{
         Poll,
         Waker,
     },
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 struct InflightAccept {
+    // Local address that our peer connects to, which is one of ours if we listen on the unspecified address.
+    local: SocketAddrV4,
     local_isn: SeqNumber,
     remote_isn: SeqNumber,
     header_window_size: u16,
     remote_window_scale: Option<u8>,
     mss: usize,
+    sack_permitted: bool,
+    // Origin of our timestamp clock for this connection.
+    ts_base: Instant,
+    // Timestamp received in the SYN, if our peer offered to use timestamps.
+    remote_timestamp: Option<u32>,
+    // Whether we agreed to use Explicit Congestion Notification.
+    ecn: bool,
+    // Time after which we give up on the handshake.
+    deadline: Instant,
 
     #[allow(unused)]
     handle: SchedulerHandle,
@@ -127,8 +147,12 @@ pub struct PassiveSocket {
     ready: Rc<RefCell<ReadySockets>>,
 
     max_backlog: usize,
+    // Number of connection requests that were dropped because the backlog was full.
+    backlog_overflows: u64,
     isn_generator: IsnGenerator,
+    syn_cookie_generator: SynCookieGenerator,
 
+    // Address that we listen on.  Its IPv4 address may be unspecified, to accept connections to any of ours.
     local: SocketAddrV4,
     rt: Rc<dyn NetworkRuntime>,
     scheduler: Scheduler,
@@ -136,6 +160,8 @@ pub struct PassiveSocket {
     tcp_config: TcpConfig,
     local_link_addr: MacAddress,
     arp: ArpPeer,
+    pmtu: PathMtuCache,
+    cc_constructor: CongestionControlConstructor,
 }
 
 impl PassiveSocket {
@@ -148,7 +174,10 @@ impl PassiveSocket {
         tcp_config: TcpConfig,
         local_link_addr: MacAddress,
         arp: ArpPeer,
+        pmtu: PathMtuCache,
+        cc_constructor: CongestionControlConstructor,
         nonce: u32,
+        cookie_key: [u8; SYN_COOKIE_KEY_SIZE],
     ) -> Self {
         let ready = ReadySockets {
             ready: VecDeque::new(),
@@ -160,7 +189,9 @@ impl PassiveSocket {
             inflight: HashMap::new(),
             ready,
             max_backlog,
+            backlog_overflows: 0,
             isn_generator: IsnGenerator::new(nonce),
+            syn_cookie_generator: SynCookieGenerator::new(cookie_key, clock.now()),
             local,
             local_link_addr,
             rt,
@@ -168,109 +199,152 @@ impl PassiveSocket {
             clock,
             tcp_config,
             arp,
+            pmtu,
+            cc_constructor,
         }
     }
 
+    /// Sets the congestion control algorithm of the connections that are established from now on.
+    pub fn set_congestion_control(&mut self, cc_constructor: CongestionControlConstructor) {
+        self.cc_constructor = cc_constructor;
+    }
+
+    /// Gets the number of connection requests that were dropped because the backlog was full.
+    pub fn get_backlog_overflows(&self) -> u64 {
+        self.backlog_overflows
+    }
+
     pub fn poll_accept(&mut self, ctx: &mut Context) -> Poll<Result<ControlBlock, Fail>> {
         self.ready.borrow_mut().poll(ctx)
     }
 
     pub fn receive(&mut self, ip_header: &Ipv4Header, header: &TcpHeader) -> Result<(), Fail> {
+        let local: SocketAddrV4 = SocketAddrV4::new(ip_header.get_dest_addr(), self.local.port());
         let remote = SocketAddrV4::new(ip_header.get_src_addr(), header.src_port);
         if self.ready.borrow().endpoints.contains(&remote) {
             // TODO: What should we do if a packet shows up for a connection that hasn't been `accept`ed yet?
             return Ok(());
         }
-        let inflight_len = self.inflight.len();
 
         // If the packet is for an inflight connection, route it there.
         if self.inflight.contains_key(&remote) {
+            // Our peer gave up on the handshake, so forget about it.  Like on synchronized connections, only a RST that
+            // carries the exact sequence number that we expect is accepted (see RFC 5961 Section 3).
+            if header.rst {
+                let remote_isn: SeqNumber = self.inflight.get(&remote).unwrap().remote_isn;
+                if header.seq_num != remote_isn + SeqNumber::from(1) {
+                    return Err(Fail::new(EBADMSG, "RST out of sequence"));
+                }
+                debug!("Received RST: {:?}", header);
+                self.inflight.remove(&remote);
+                return Ok(());
+            }
             if !header.ack {
                 return Err(Fail::new(EBADMSG, "expeting ACK"));
             }
             debug!("Received ACK: {:?}", header);
             let &InflightAccept {
+                local,
                 local_isn,
                 remote_isn,
                 header_window_size,
                 remote_window_scale,
                 mss,
+                sack_permitted,
+                ts_base,
+                remote_timestamp,
+                ecn,
                 ..
             } = self.inflight.get(&remote).unwrap();
             if header.ack_num != local_isn + SeqNumber::from(1) {
+                self.send_rst(local, remote, header);
                 return Err(Fail::new(EBADMSG, "invalid SYN+ACK seq num"));
             }
-
-            let (local_window_scale, remote_window_scale) = match remote_window_scale {
-                Some(w) => (self.tcp_config.get_window_scale() as u32, w),
-                None => (0, 0),
-            };
-            let remote_window_size = (header_window_size)
-                .checked_shl(remote_window_scale as u32)
-                .expect("TODO: Window size overflow")
-                .try_into()
-                .expect("TODO: Window size overflow");
-            let local_window_size = (self.tcp_config.get_receive_window_size() as u32)
-                .checked_shl(local_window_scale as u32)
-                .expect("TODO: Window size overflow");
-            info!(
-                "Window sizes: local {}, remote {}",
-                local_window_size, remote_window_size
-            );
-            info!(
-                "Window scale: local {}, remote {}",
-                local_window_scale, remote_window_scale
-            );
+            // If the accept queue is full, drop the ACK. The handshake completes once our peer acknowledges one of our
+            // SYN+ACK retransmissions, provided that there is room by then.
+            if self.ready.borrow().len() >= self.max_backlog {
+                self.backlog_overflows += 1;
+                return Err(Fail::new(ECONNREFUSED, "accept queue is full"));
+            }
 
             self.inflight.remove(&remote);
-            let cb = ControlBlock::new(
-                self.local,
+            let cb: ControlBlock = self.establish(
+                local,
                 remote,
-                self.rt.clone(),
-                self.scheduler.clone(),
-                self.clock.clone(),
-                self.local_link_addr,
-                self.tcp_config.clone(),
-                self.arp.clone(),
-                remote_isn + SeqNumber::from(1),
-                self.tcp_config.get_ack_delay_timeout(),
-                local_window_size,
-                local_window_scale,
-                local_isn + SeqNumber::from(1),
-                remote_window_size,
+                header,
+                local_isn,
+                remote_isn,
+                header_window_size,
                 remote_window_scale,
                 mss,
-                congestion_control::None::new,
-                None,
+                sack_permitted,
+                ts_base,
+                remote_timestamp,
+                ecn,
             );
             self.ready.borrow_mut().push_ok(cb);
             return Ok(());
         }
 
-        // Otherwise, start a new connection.
+        // It may also be the ACK that completes a handshake that we answered with a SYN cookie.
+        if header.ack && !header.syn && !header.rst && self.tcp_config.get_syn_cookies() {
+            return self.receive_syn_cookie(local, remote, header);
+        }
+
+        // Otherwise, start a new connection.  Stray ACKs are answered with a RST, so that our peer learns that there is
+        // no such connection, while any other segment is dropped (see RFC 793 Section 3.9).
         if !header.syn || header.ack || header.rst {
+            if header.ack {
+                self.send_rst(local, remote, header);
+            }
             return Err(Fail::new(EBADMSG, "invalid flags"));
         }
         debug!("Received SYN: {:?}", header);
-        if inflight_len + self.ready.borrow().len() >= self.max_backlog {
+
+        // Give up on handshakes that timed out, so that they no longer take up room in the backlog.
+        let now: Instant = self.clock.now();
+        self.inflight.retain(|_, accept| accept.deadline > now);
+        if self.inflight.len() + self.ready.borrow().len() >= self.max_backlog {
+            if self.tcp_config.get_syn_cookies() {
+                // Answer without keeping any state, so that a SYN flood cannot lock out legitimate peers.
+                return self.send_syn_cookie(local, remote, header);
+            }
             // TODO: Should we send a RST here?
+            self.backlog_overflows += 1;
             return Err(Fail::new(ECONNREFUSED, "connection refused"));
         }
-        let local_isn = self.isn_generator.generate(&self.local, &remote);
+        let local_isn = self.isn_generator.generate(&local, &remote);
         let remote_isn = header.seq_num;
+        let sack_permitted: bool = header
+            .iter_options()
+            .any(|option| matches!(option, TcpOptions2::SelectiveAcknowlegementPermitted));
+        let remote_timestamp: Option<u32> = header.iter_options().find_map(|option| match option {
+            TcpOptions2::Timestamp { sender_timestamp, .. } => Some(*sender_timestamp),
+            _ => None,
+        });
+        // Our peer requests Explicit Congestion Notification by setting both ECE and CWR (see RFC 3168).
+        let ecn: bool = self.tcp_config.get_ecn() && header.ece && header.cwr;
+        let ts_base: Instant = now;
         let future = Self::background(
             local_isn,
             remote_isn,
-            self.local,
+            local,
             remote,
             self.rt.clone(),
             self.clock.clone(),
             self.tcp_config.clone(),
             self.local_link_addr,
             self.arp.clone(),
+            sack_permitted,
+            ts_base,
+            remote_timestamp,
+            ecn,
             self.ready.clone(),
         );
-        let handle: SchedulerHandle = match self.scheduler.insert(FutureOperation::Background(future.boxed_local())) {
+        let handle: SchedulerHandle = match self
+            .scheduler
+            .insert(FutureOperation::Background(future.boxed_local()), Priority::High)
+        {
             Some(handle) => handle,
             None => panic!("failed to insert task in the scheduler"),
         };
@@ -287,21 +361,211 @@ impl PassiveSocket {
                     info!("Received advertised MSS: {}", m);
                     mss = *m as usize;
                 },
+                TcpOptions2::SelectiveAcknowlegementPermitted => info!("Received SACK permitted"),
                 _ => continue,
             }
         }
         let accept = InflightAccept {
+            local,
             local_isn,
             remote_isn,
             header_window_size: header.window_size,
             remote_window_scale,
             mss,
+            sack_permitted,
+            ts_base,
+            remote_timestamp,
+            ecn,
+            deadline: now + self.tcp_config.get_handshake_timeout() * self.tcp_config.get_handshake_retries() as u32,
             handle,
         };
         self.inflight.insert(remote, accept);
         Ok(())
     }
 
+    /// Handles the ACK that completes a handshake that we answered with a SYN cookie.
+    fn receive_syn_cookie(
+        &mut self,
+        local: SocketAddrV4,
+        remote: SocketAddrV4,
+        header: &TcpHeader,
+    ) -> Result<(), Fail> {
+        let local_isn: SeqNumber = header.ack_num - SeqNumber::from(1);
+        let remote_isn: SeqNumber = header.seq_num - SeqNumber::from(1);
+        let now: Instant = self.clock.now();
+        let mss: usize = match self
+            .syn_cookie_generator
+            .validate(&local, &remote, remote_isn, local_isn, now)
+        {
+            Some(mss) => mss,
+            None => {
+                self.send_rst(local, remote, header);
+                return Err(Fail::new(EBADMSG, "invalid SYN cookie"));
+            },
+        };
+        if self.ready.borrow().len() >= self.max_backlog {
+            self.backlog_overflows += 1;
+            return Err(Fail::new(ECONNREFUSED, "accept queue is full"));
+        }
+        debug!("Received ACK for SYN cookie: {:?}", header);
+
+        // The cookie has no room for other options, so we do without them.
+        let cb: ControlBlock = self.establish(
+            local,
+            remote,
+            header,
+            local_isn,
+            remote_isn,
+            header.window_size,
+            None,
+            mss,
+            false,
+            now,
+            None,
+            false,
+        );
+        self.ready.borrow_mut().push_ok(cb);
+        Ok(())
+    }
+
+    /// Answers a SYN with a SYN+ACK whose sequence number is a SYN cookie.
+    fn send_syn_cookie(&mut self, local: SocketAddrV4, remote: SocketAddrV4, header: &TcpHeader) -> Result<(), Fail> {
+        // We cannot wait for the ARP query to complete without keeping state, so only answer peers that we know of.
+        let remote_link_addr: MacAddress = match self.arp.try_query(remote.ip().clone()) {
+            Some(r) => r,
+            None => return Err(Fail::new(ECONNREFUSED, "connection refused")),
+        };
+        let mss: usize = header
+            .iter_options()
+            .find_map(|option| match option {
+                TcpOptions2::MaximumSegmentSize(m) => Some(*m as usize),
+                _ => None,
+            })
+            .unwrap_or(FALLBACK_MSS);
+        let remote_isn: SeqNumber = header.seq_num;
+        let local_isn: SeqNumber =
+            self.syn_cookie_generator
+                .generate(&local, &remote, remote_isn, mss, self.clock.now());
+
+        let mut tcp_hdr = TcpHeader::new(local.port(), remote.port());
+        tcp_hdr.syn = true;
+        tcp_hdr.seq_num = local_isn;
+        tcp_hdr.ack = true;
+        tcp_hdr.ack_num = remote_isn + SeqNumber::from(1);
+        tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
+        tcp_hdr.push_option(TcpOptions2::MaximumSegmentSize(
+            self.tcp_config.get_advertised_mss() as u16
+        ));
+
+        debug!("Sending SYN+ACK with SYN cookie: {:?}", tcp_hdr);
+        let segment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
+            ipv4_hdr: Ipv4Header::new(local.ip().clone(), remote.ip().clone(), IpProtocol::TCP),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
+        };
+        self.rt.transmit(Box::new(segment));
+        Ok(())
+    }
+
+    /// Answers a segment that does not belong to any connection with a RST.  As with SYN cookies, we only answer peers
+    /// that we know of.
+    fn send_rst(&self, local: SocketAddrV4, remote: SocketAddrV4, header: &TcpHeader) {
+        let tcp_hdr: TcpHeader = match TcpHeader::new_reset(header, 0) {
+            Some(tcp_hdr) => tcp_hdr,
+            None => return,
+        };
+        let remote_link_addr: MacAddress = match self.arp.try_query(remote.ip().clone()) {
+            Some(r) => r,
+            None => return,
+        };
+        debug!("Sending RST: {:?}", tcp_hdr);
+        let segment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
+            ipv4_hdr: Ipv4Header::new(local.ip().clone(), remote.ip().clone(), IpProtocol::TCP),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
+        };
+        self.rt.transmit(Box::new(segment));
+    }
+
+    /// Creates the control block of a connection whose handshake has just completed.
+    fn establish(
+        &self,
+        local: SocketAddrV4,
+        remote: SocketAddrV4,
+        header: &TcpHeader,
+        local_isn: SeqNumber,
+        remote_isn: SeqNumber,
+        header_window_size: u16,
+        remote_window_scale: Option<u8>,
+        mss: usize,
+        sack_permitted: bool,
+        ts_base: Instant,
+        remote_timestamp: Option<u32>,
+        ecn: bool,
+    ) -> ControlBlock {
+        let (local_window_scale, remote_window_scale) = match remote_window_scale {
+            Some(w) => (self.tcp_config.get_window_scale() as u32, w),
+            None => (0, 0),
+        };
+        let remote_window_size = (header_window_size)
+            .checked_shl(remote_window_scale as u32)
+            .expect("TODO: Window size overflow")
+            .try_into()
+            .expect("TODO: Window size overflow");
+        let local_window_size = (self.tcp_config.get_receive_window_size() as u32)
+            .checked_shl(local_window_scale as u32)
+            .expect("TODO: Window size overflow");
+        info!(
+            "Window sizes: local {}, remote {}",
+            local_window_size, remote_window_size
+        );
+        info!(
+            "Window scale: local {}, remote {}",
+            local_window_scale, remote_window_scale
+        );
+
+        // Echo the most recent timestamp from our peer, if we agreed to use timestamps (see RFC 7323).
+        let ts_recent: Option<u32> = remote_timestamp.map(|ts_val| {
+            header
+                .iter_options()
+                .find_map(|option| match option {
+                    TcpOptions2::Timestamp { sender_timestamp, .. } => Some(*sender_timestamp),
+                    _ => None,
+                })
+                .unwrap_or(ts_val)
+        });
+
+        ControlBlock::new(
+            local,
+            remote,
+            self.rt.clone(),
+            self.scheduler.clone(),
+            self.clock.clone(),
+            self.local_link_addr,
+            self.tcp_config.clone(),
+            self.arp.clone(),
+            self.pmtu.clone(),
+            remote_isn + SeqNumber::from(1),
+            self.tcp_config.get_ack_delay_timeout(),
+            local_window_size,
+            local_window_scale,
+            local_isn + SeqNumber::from(1),
+            remote_window_size,
+            remote_window_scale,
+            mss,
+            sack_permitted,
+            ts_base,
+            ts_recent,
+            ecn,
+            self.cc_constructor,
+            None,
+        )
+    }
+
     fn background(
         local_isn: SeqNumber,
         remote_isn: SeqNumber,
@@ -312,6 +576,10 @@ impl PassiveSocket {
         tcp_config: TcpConfig,
         local_link_addr: MacAddress,
         arp: ArpPeer,
+        sack_permitted: bool,
+        ts_base: Instant,
+        remote_timestamp: Option<u32>,
+        ecn: bool,
         ready: Rc<RefCell<ReadySockets>>,
     ) -> impl Future<Output = ()> {
         let handshake_retries: usize = tcp_config.get_handshake_retries();
@@ -340,6 +608,23 @@ impl PassiveSocket {
                 tcp_hdr.push_option(TcpOptions2::WindowScale(tcp_config.get_window_scale()));
                 info!("Advertising window scale: {}", tcp_config.get_window_scale());
 
+                // Only agree to use SACK if our peer offered it (see RFC 2018).
+                if sack_permitted {
+                    tcp_hdr.push_option(TcpOptions2::SelectiveAcknowlegementPermitted);
+                    info!("Advertising SACK permitted");
+                }
+
+                // Likewise for timestamps, in which case we echo the one from the SYN.
+                if let Some(remote_timestamp) = remote_timestamp {
+                    tcp_hdr.push_option(TcpOptions2::Timestamp {
+                        sender_timestamp: timestamp_value(ts_base, clock.now()),
+                        echo_timestamp: remote_timestamp,
+                    });
+                }
+
+                // Agree to use ECN by setting ECE alone.
+                tcp_hdr.ece = ecn;
+
                 debug!("Sending SYN+ACK: {:?}", tcp_hdr);
                 let segment = TcpSegment {
                     ethernet2_hdr: Ethernet2Header::new(remote_link_addr, local_link_addr, EtherType2::Ipv4),
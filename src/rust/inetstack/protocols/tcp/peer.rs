@@ -10,32 +10,55 @@ use super::{
     established::EstablishedSocket,
     isn_generator::IsnGenerator,
     passive_open::PassiveSocket,
+    steering::ConnectionSteering,
+    syn_cookie::SYN_COOKIE_KEY_SIZE,
 };
 use crate::{
-    inetstack::protocols::{
-        arp::ArpPeer,
-        ethernet2::{
-            EtherType2,
-            Ethernet2Header,
-        },
-        ip::{
-            EphemeralPorts,
-            IpProtocol,
-        },
-        ipv4::Ipv4Header,
-        tcp::{
-            established::ControlBlock,
-            operations::{
-                AcceptFuture,
-                ConnectFuture,
-                PopFuture,
-                PushFuture,
+    inetstack::{
+        futures::Deadline,
+        protocols::{
+            arp::ArpPeer,
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+            },
+            icmpv4::Icmpv4Error,
+            ip::{
+                EphemeralPorts,
+                IpProtocol,
+            },
+            ipv4::{
+                Ipv4Header,
+                PathMtuCache,
+                DEFAULT_IPV4_TTL,
+                IPV4_ECN_CE,
             },
-            segment::{
-                TcpHeader,
-                TcpSegment,
+            tcp::{
+                established::{
+                    congestion_control,
+                    congestion_control::CongestionControlConstructor,
+                    ControlBlock,
+                    OutOfOrderStats,
+                    State,
+                },
+                gro::{
+                    GroSegment,
+                    Payloads,
+                },
+                operations::{
+                    AcceptFuture,
+                    CloseFuture,
+                    ConnectFuture,
+                    PopFuture,
+                    PushFuture,
+                    SpliceFuture,
+                },
+                segment::{
+                    TcpHeader,
+                    TcpSegment,
+                },
+                SeqNumber,
             },
-            SeqNumber,
         },
     },
     runtime::{
@@ -43,7 +66,17 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
-            types::MacAddress,
+            consts::{
+                DEFAULT_MAX_RECEIVE_BUFFER_SIZE,
+                DEFAULT_MAX_SEND_BUFFER_SIZE,
+            },
+            types::{
+                MacAddress,
+                SocketOption,
+                SocketOptions,
+                TcpInfo,
+                Timestamps,
+            },
             NetworkRuntime,
         },
         timer::TimerRc,
@@ -53,28 +86,36 @@ use crate::{
 };
 use ::futures::channel::mpsc;
 use ::libc::{
+    EADDRINUSE,
     EAGAIN,
     EBADF,
     EBUSY,
     EINPROGRESS,
     EINVAL,
+    EISCONN,
     ENOTCONN,
     ENOTSUP,
     EOPNOTSUPP,
 };
 use ::rand::{
     prelude::SmallRng,
+    rngs::OsRng,
     Rng,
+    RngCore,
     SeedableRng,
 };
 use ::std::{
     cell::{
+        Ref,
         RefCell,
         RefMut,
     },
+    cmp,
     collections::HashMap,
+    mem,
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     rc::Rc,
@@ -108,9 +149,15 @@ pub struct Inner {
 
     ephemeral_ports: EphemeralPorts,
 
+    // Which connections this instance handles, among the instances that share its local address.
+    steering: ConnectionSteering,
+
     // FD -> local port
     sockets: HashMap<QDesc, Socket>,
 
+    // FD -> socket options
+    options: HashMap<QDesc, SocketOptions>,
+
     passive: HashMap<SocketAddrV4, PassiveSocket>,
     connecting: HashMap<(SocketAddrV4, SocketAddrV4), ActiveOpenSocket>,
     established: HashMap<(SocketAddrV4, SocketAddrV4), EstablishedSocket>,
@@ -119,12 +166,21 @@ pub struct Inner {
     scheduler: Scheduler,
     clock: TimerRc,
     local_link_addr: MacAddress,
-    local_ipv4_addr: Ipv4Addr,
     tcp_config: TcpConfig,
     arp: ArpPeer,
+    pmtu: PathMtuCache,
     rng: Rc<RefCell<SmallRng>>,
 
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
+    // Queue descriptors of connections whose background tasks terminated, e.g. because they were aborted.
+    dead_socket_rx: mpsc::UnboundedReceiver<QDesc>,
+
+    // Whether to hold back the ACKs that become due while receiving a batch of segments.
+    coalesce_acks: bool,
+    // Connections that hold back an ACK until the end of the current batch.
+    deferred_acks: Vec<(SocketAddrV4, SocketAddrV4)>,
+    // Data segment held back to coalesce it with the next segments of the current batch.
+    gro: Option<GroSegment>,
 }
 
 pub struct TcpPeer {
@@ -141,9 +197,9 @@ impl TcpPeer {
         scheduler: Scheduler,
         clock: TimerRc,
         local_link_addr: MacAddress,
-        local_ipv4_addr: Ipv4Addr,
         tcp_config: TcpConfig,
         arp: ArpPeer,
+        pmtu: PathMtuCache,
         rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
         let (tx, rx) = mpsc::unbounded();
@@ -152,9 +208,9 @@ impl TcpPeer {
             scheduler,
             clock,
             local_link_addr,
-            local_ipv4_addr,
             tcp_config,
             arp,
+            pmtu,
             rng_seed,
             tx,
             rx,
@@ -170,7 +226,9 @@ impl TcpPeer {
         match inner.sockets.contains_key(&qd) {
             false => {
                 let socket: Socket = Socket::Inactive { local: None };
+                let options: SocketOptions = inner.default_socket_options();
                 inner.sockets.insert(qd, socket);
+                inner.options.insert(qd, options);
                 Ok(())
             },
             true => return Err(Fail::new(EBUSY, "queue descriptor in use")),
@@ -234,10 +292,53 @@ impl TcpPeer {
         }
     }
 
+    /// Gets the number of connections whose state is kept, including those that are done but not forgotten yet.
+    #[cfg(test)]
+    pub fn num_connections(&self) -> usize {
+        self.inner.borrow().established.len()
+    }
+
     pub fn receive(&self, ip_header: &Ipv4Header, buf: DemiBuffer) -> Result<(), Fail> {
         self.inner.borrow_mut().receive(ip_header, buf)
     }
 
+    /// Checks whether a socket is bound to `ipv4_addr`, or a connection that is not forgotten yet uses it.
+    pub fn is_addr_in_use(&self, ipv4_addr: Ipv4Addr) -> bool {
+        let inner: Ref<Inner> = self.inner.borrow();
+        inner.passive.keys().any(|local| *local.ip() == ipv4_addr)
+            || inner.connecting.keys().any(|(local, _)| *local.ip() == ipv4_addr)
+            || inner.established.keys().any(|(local, _)| *local.ip() == ipv4_addr)
+            || inner.sockets.values().any(|socket| match socket {
+                Socket::Inactive { local: Some(local) } => *local.ip() == ipv4_addr,
+                _ => false,
+            })
+    }
+
+    /// Handles an ICMP error about a segment that we sent.
+    pub fn receive_icmp_error(&self, error: &Icmpv4Error) {
+        self.inner.borrow_mut().receive_icmp_error(error)
+    }
+
+    /// Holds back the ACKs that become due while receiving a batch of segments, until
+    /// [TcpPeer::flush_coalesced_acks] is called. This way, each connection sends at most one ACK per batch. Unless GRO
+    /// is disabled, in-order data segments of a connection are also coalesced until then.
+    pub fn begin_ack_coalescing(&self) {
+        self.inner.borrow_mut().coalesce_acks = true;
+    }
+
+    /// Processes the segment that is still held back for coalescing, and sends the ACKs that were held back since
+    /// [TcpPeer::begin_ack_coalescing] was called.
+    pub fn flush_coalesced_acks(&self) {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        inner.flush_gro();
+        inner.coalesce_acks = false;
+        for key in mem::take(&mut inner.deferred_acks) {
+            if let Some(established) = inner.established.get(&key) {
+                established.cb.flush_deferred_ack();
+            }
+        }
+    }
+
     // Marks the target socket as passive.
     pub fn listen(&self, qd: QDesc, backlog: usize) -> Result<(), Fail> {
         let mut inner: RefMut<Inner> = self.inner.borrow_mut();
@@ -267,6 +368,16 @@ impl TcpPeer {
         }
 
         let nonce: u32 = inner.rng.borrow_mut().gen();
+        // SYN cookies would be forgeable if their key were predictable, so it is not drawn from the seeded generator of
+        // the stack.
+        let mut cookie_key: [u8; SYN_COOKIE_KEY_SIZE] = [0; SYN_COOKIE_KEY_SIZE];
+        if let Err(e) = OsRng.try_fill_bytes(&mut cookie_key) {
+            return Err(Fail::new(
+                e.raw_os_error().unwrap_or(libc::EIO),
+                "cannot draw key of SYN cookies",
+            ));
+        }
+        let cc_constructor: CongestionControlConstructor = inner.congestion_control(qd);
         let socket = PassiveSocket::new(
             local,
             backlog,
@@ -276,7 +387,10 @@ impl TcpPeer {
             inner.tcp_config.clone(),
             inner.local_link_addr,
             inner.arp.clone(),
+            inner.pmtu.clone(),
+            cc_constructor,
             nonce,
+            cookie_key,
         );
         assert!(inner.passive.insert(local, socket).is_none());
         inner.sockets.insert(qd, Socket::Listening { local });
@@ -318,6 +432,25 @@ impl TcpPeer {
             panic!("duplicate queue descriptor in sockets table");
         }
 
+        // Accepted sockets inherit the options of the listening socket.
+        let options: SocketOptions = match inner.options.get(&qd) {
+            Some(options) => *options,
+            None => inner.default_socket_options(),
+        };
+        established.cb.set_max_pacing_rate(options.get_max_pacing_rate());
+        established.cb.set_nodelay(options.get_nodelay());
+        established.cb.set_cork(options.get_cork());
+        established.cb.set_ack_delay_timeout(options.get_ack_delay());
+        established.cb.set_ack_segments(options.get_ack_segments());
+        established.cb.set_quickack(options.get_quickack());
+        let (min, max): (usize, usize) = options.get_send_buffer_bounds();
+        established.cb.set_send_buffer(options.get_send_buffer_size(), min, max);
+        let (min, max): (usize, usize) = options.get_receive_buffer_bounds();
+        established
+            .cb
+            .set_receive_buffer(options.get_receive_buffer_size(), min, max);
+        inner.options.insert(new_qd, options);
+
         // TODO: Reset the connection if the following following check fails, instead of panicking.
         if inner.established.insert(key, established).is_some() {
             panic!("duplicate queue descriptor in established sockets table");
@@ -329,21 +462,58 @@ impl TcpPeer {
     pub fn connect(&self, qd: QDesc, remote: SocketAddrV4) -> Result<ConnectFuture, Fail> {
         let mut inner: RefMut<Inner> = self.inner.borrow_mut();
 
+        // Give the ports of connections that are done back to the pool.
+        inner.reap_closed_connections();
+
         // Get local address bound to socket.
         let local: SocketAddrV4 = match inner.sockets.get_mut(&qd) {
             // Handle unbound socket.
             Some(Socket::Inactive { local: None }) => {
-                // TODO: we should free this when closing.
-                let local_port: u16 = inner.ephemeral_ports.alloc_any()?;
-                SocketAddrV4::new(inner.local_ipv4_addr, local_port)
+                // The port is given back to the pool once the connection is done.  It is picked so that the segments
+                // of our peer are steered to this instance.
+                let local_ipv4_addr: Ipv4Addr = inner.arp.select_source(*remote.ip());
+                let steering: ConnectionSteering = inner.steering;
+                let local_port: u16 = match inner
+                    .ephemeral_ports
+                    .alloc_matching(|port| steering.owns(&SocketAddrV4::new(local_ipv4_addr, port), &remote))
+                {
+                    Ok(port) => port,
+                    // Once all ports are taken, take over one from a previous connection to the same remote endpoint
+                    // that lingers in TIME-WAIT, if allowed.
+                    Err(e) => inner.time_wait_port(local_ipv4_addr, remote).ok_or(e)?,
+                };
+                SocketAddrV4::new(local_ipv4_addr, local_port)
+            },
+            // Handle bound socket.  Sockets bound to the unspecified address connect from the address that we would
+            // pick for the remote endpoint.
+            Some(Socket::Inactive { local: Some(local) }) if local.ip().is_unspecified() => {
+                let local_port: u16 = local.port();
+                SocketAddrV4::new(inner.arp.select_source(*remote.ip()), local_port)
             },
-            // Handle bound socket.
             Some(Socket::Inactive { local: Some(local) }) => *local,
             Some(Socket::Connecting { local: _, remote: _ }) => Err(Fail::new(libc::EALREADY, "socket is connecting"))?,
             Some(Socket::Established { local: _, remote: _ }) => Err(Fail::new(libc::EISCONN, "socket is connected"))?,
             _ => Err(Fail::new(libc::EBADF, "invalid queue descriptor"))?,
         };
 
+        // The endpoint pair may still be in use by a previous connection, which lingers in TIME-WAIT.
+        let key: (SocketAddrV4, SocketAddrV4) = (local, remote);
+        let mut local_isn: SeqNumber = inner.isn_generator.generate(&local, &remote);
+        if let Some(established) = inner.established.get(&key) {
+            let reusable: bool = inner.tcp_config.get_time_wait_reuse()
+                && established.cb.get_state() == State::TimeWait
+                && inner.is_orphaned(&key);
+            if !reusable {
+                return Err(Fail::new(EADDRINUSE, "address already in use"));
+            }
+            // Start the new connection well beyond the sequence space of the previous one, so that stray segments of
+            // the previous connection cannot be mistaken for segments of the new one.
+            let (send_next, _): (SeqNumber, _) = established.cb.get_send_next();
+            local_isn = send_next + SeqNumber::from(65535 + 2);
+            debug!("reusing endpoints {:?} in TIME-WAIT", key);
+            inner.established.remove(&key);
+        }
+
         // Update socket state.
         match inner.sockets.get_mut(&qd) {
             Some(socket) => {
@@ -357,7 +527,6 @@ impl TcpPeer {
         };
 
         // Create active socket.
-        let local_isn: SeqNumber = inner.isn_generator.generate(&local, &remote);
         let socket: ActiveOpenSocket = ActiveOpenSocket::new(
             inner.scheduler.clone(),
             local_isn,
@@ -368,6 +537,8 @@ impl TcpPeer {
             inner.local_link_addr,
             inner.clock.clone(),
             inner.arp.clone(),
+            inner.pmtu.clone(),
+            inner.congestion_control(qd),
         );
 
         // Insert socket in connecting table.
@@ -380,10 +551,11 @@ impl TcpPeer {
         Ok(ConnectFuture {
             fd: qd,
             inner: self.inner.clone(),
+            deadline: Deadline::default(),
         })
     }
 
-    pub fn poll_recv(&self, fd: QDesc, ctx: &mut Context) -> Poll<Result<DemiBuffer, Fail>> {
+    pub fn poll_recv(&self, fd: QDesc, ctx: &mut Context, size: Option<usize>) -> Poll<Result<DemiBuffer, Fail>> {
         let inner = self.inner.borrow_mut();
         let key = match inner.sockets.get(&fd) {
             Some(Socket::Established { local, remote }) => (*local, *remote),
@@ -393,27 +565,103 @@ impl TcpPeer {
             None => return Poll::Ready(Err(Fail::new(EBADF, "bad queue descriptor"))),
         };
         match inner.established.get(&key) {
-            Some(ref s) => s.poll_recv(ctx),
+            Some(ref s) => s.poll_recv(ctx, size),
             None => Poll::Ready(Err(Fail::new(ENOTCONN, "connection not established"))),
         }
     }
 
     pub fn push(&self, fd: QDesc, buf: DemiBuffer) -> PushFuture {
-        let err: Option<Fail> = match self.send(fd, buf) {
-            Ok(()) => None,
-            Err(e) => Some(e),
+        let external: bool = buf.is_external();
+        match self.send(fd, buf) {
+            Ok(()) => PushFuture {
+                fd,
+                err: None,
+                ack: self.get_ack_point(fd, external),
+            },
+            Err(e) => PushFuture {
+                fd,
+                err: Some(e),
+                ack: None,
+            },
+        }
+    }
+
+    pub fn pushv(&self, fd: QDesc, bufs: Vec<DemiBuffer>) -> PushFuture {
+        let external: bool = bufs.iter().any(|buf| buf.is_external());
+        match self.sendv(fd, bufs) {
+            Ok(()) => PushFuture {
+                fd,
+                err: None,
+                ack: self.get_ack_point(fd, external),
+            },
+            Err(e) => PushFuture {
+                fd,
+                err: Some(e),
+                ack: None,
+            },
+        }
+    }
+
+    // Gets the connection of the socket referred to by `fd`, along with the sequence number up to which data must be
+    // acknowledged before a push that was just queued completes, if it cannot complete right away. Data in
+    // externally-owned memory is not copied, so pushes of such data only complete once all of it is acknowledged, as
+    // it remains in the retransmission queue until then. Other pushes complete once the data queued so far fits in the
+    // send buffer, which holds the application back while our peer falls behind.
+    pub(super) fn get_ack_point(&self, fd: QDesc, external: bool) -> Option<(Rc<ControlBlock>, SeqNumber)> {
+        let inner: Ref<Inner> = self.inner.borrow();
+        let key: (SocketAddrV4, SocketAddrV4) = match inner.sockets.get(&fd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            _ => return None,
         };
-        PushFuture { fd, err }
+        let cb: Rc<ControlBlock> = inner.established.get(&key)?.cb.clone();
+        let (seq_no, _): (SeqNumber, _) = cb.get_unsent_seq_no();
+        if external {
+            return Some((cb, seq_no));
+        }
+        let (send_unacked, _): (SeqNumber, _) = cb.get_send_unacked();
+        let bytes_queued: u32 = (seq_no - send_unacked).into();
+        let send_buffer_size: u32 = cb.get_send_buffer_size();
+        if bytes_queued <= send_buffer_size {
+            return None;
+        }
+        Some((cb, seq_no - SeqNumber::from(send_buffer_size)))
     }
 
-    pub fn pop(&self, fd: QDesc) -> PopFuture {
+    pub fn pop(&self, fd: QDesc, size: Option<usize>) -> PopFuture {
         PopFuture {
             fd,
+            size,
+            inner: self.inner.clone(),
+            deadline: Deadline::default(),
+        }
+    }
+
+    /// Forwards data from the socket referred to by `src` to the one referred to by `dst`, until end-of-stream.
+    pub fn splice(&self, src: QDesc, dst: QDesc) -> SpliceFuture {
+        SpliceFuture {
+            fd: src,
+            dst,
             inner: self.inner.clone(),
+            nbytes: 0,
+        }
+    }
+
+    // Checks that data can be pushed to the socket referred to by `fd`.
+    pub(super) fn check_send(&self, fd: QDesc) -> Result<(), Fail> {
+        let inner: Ref<Inner> = self.inner.borrow();
+        let key: (SocketAddrV4, SocketAddrV4) = match inner.sockets.get(&fd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            Some(..) => return Err(Fail::new(ENOTCONN, "connection not established")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        match inner.established.get(&key) {
+            Some(s) if s.cb.user_is_done_sending.get() => Err(Fail::new(EINVAL, "Connection is closing")),
+            Some(_) => Ok(()),
+            None => Err(Fail::new(ENOTCONN, "connection not established")),
         }
     }
 
-    fn send(&self, fd: QDesc, buf: DemiBuffer) -> Result<(), Fail> {
+    pub(super) fn send(&self, fd: QDesc, buf: DemiBuffer) -> Result<(), Fail> {
         let inner = self.inner.borrow_mut();
         let key = match inner.sockets.get(&fd) {
             Some(Socket::Established { local, remote }) => (*local, *remote),
@@ -426,16 +674,44 @@ impl TcpPeer {
         }
     }
 
+    // Sends multiple buffers back-to-back, so that no other data gets interleaved with them in the stream.
+    fn sendv(&self, fd: QDesc, bufs: Vec<DemiBuffer>) -> Result<(), Fail> {
+        let inner = self.inner.borrow_mut();
+        let key = match inner.sockets.get(&fd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            Some(..) => return Err(Fail::new(ENOTCONN, "connection not established")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        match inner.established.get(&key) {
+            Some(ref s) => {
+                for buf in bufs {
+                    s.send(buf)?;
+                }
+                Ok(())
+            },
+            None => Err(Fail::new(ENOTCONN, "connection not established")),
+        }
+    }
+
     /// Closes a TCP socket.
     pub fn do_close(&self, qd: QDesc) -> Result<(), Fail> {
         let mut inner: RefMut<Inner> = self.inner.borrow_mut();
 
+        inner.options.remove(&qd);
         match inner.sockets.remove(&qd) {
             Some(Socket::Established { local, remote }) => {
                 let key: (SocketAddrV4, SocketAddrV4) = (local, remote);
-                match inner.established.get(&key) {
-                    Some(ref s) => s.close()?,
+                let aborted: bool = match inner.established.get(&key) {
+                    Some(ref s) if s.cb.get_state() != State::Closed => {
+                        s.close()?;
+                        false
+                    },
+                    Some(_) => true,
                     None => return Err(Fail::new(ENOTCONN, "connection not established")),
+                };
+                // There is nothing left to close on connections that were aborted, so they are forgotten right away.
+                if aborted {
+                    inner.remove_connection(&key);
                 }
             },
 
@@ -446,6 +722,56 @@ impl TcpPeer {
         Ok(())
     }
 
+    /// Closes a TCP socket, like [TcpPeer::do_close], returning a future that completes once the close handshake of the
+    /// underlying connection completes. If `last` is not set, other queue descriptors still refer to the socket, so it
+    /// is left open and the returned future completes right away.
+    pub fn do_async_close(&self, qd: QDesc, last: bool) -> Result<CloseFuture, Fail> {
+        let mut key: Option<(SocketAddrV4, SocketAddrV4)> = None;
+        if last {
+            if let Some(Socket::Established { local, remote }) = self.inner.borrow().sockets.get(&qd) {
+                key = Some((*local, *remote));
+            }
+            self.do_close(qd)?;
+        }
+        Ok(CloseFuture {
+            fd: qd,
+            key,
+            inner: self.inner.clone(),
+        })
+    }
+
+    /// Polls for the completion of the close handshake of the connection identified by `key`.
+    pub fn poll_close(&self, key: (SocketAddrV4, SocketAddrV4), ctx: &mut Context) -> Poll<Result<(), Fail>> {
+        let inner: Ref<Inner> = self.inner.borrow();
+        match inner.established.get(&key) {
+            Some(s) => s.poll_close(ctx),
+            // The connection is gone, so there is nothing left to wait for.
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Shuts down the receive side, send side, or both sides of a TCP connection.
+    pub fn shutdown(&self, qd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        let inner: Ref<Inner> = self.inner.borrow();
+        let key: (SocketAddrV4, SocketAddrV4) = match inner.sockets.get(&qd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            Some(..) => return Err(Fail::new(ENOTCONN, "connection not established")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        let socket: &EstablishedSocket = match inner.established.get(&key) {
+            Some(s) => s,
+            None => return Err(Fail::new(ENOTCONN, "connection not established")),
+        };
+        if how == Shutdown::Read || how == Shutdown::Both {
+            socket.shutdown_read();
+        }
+        if how == Shutdown::Write || how == Shutdown::Both {
+            // Sends a FIN, while still allowing the remaining data to be received.
+            socket.close()?;
+        }
+        Ok(())
+    }
+
     pub fn remote_mss(&self, fd: QDesc) -> Result<usize, Fail> {
         let inner = self.inner.borrow();
         let key = match inner.sockets.get(&fd) {
@@ -472,6 +798,61 @@ impl TcpPeer {
         }
     }
 
+    /// Gets the number of connection requests that a listening TCP socket dropped because its backlog was full.
+    pub fn backlog_overflows(&self, qd: QDesc) -> Result<u64, Fail> {
+        let inner = self.inner.borrow();
+        let local: SocketAddrV4 = match inner.sockets.get(&qd) {
+            Some(Socket::Listening { local }) => *local,
+            Some(..) => return Err(Fail::new(EINVAL, "socket is not listening")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        match inner.passive.get(&local) {
+            Some(socket) => Ok(socket.get_backlog_overflows()),
+            None => Err(Fail::new(EINVAL, "socket is not listening")),
+        }
+    }
+
+    pub fn out_of_order_stats(&self, fd: QDesc) -> Result<OutOfOrderStats, Fail> {
+        let inner = self.inner.borrow();
+        let key = match inner.sockets.get(&fd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            Some(..) => return Err(Fail::new(ENOTCONN, "connection not established")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        match inner.established.get(&key) {
+            Some(ref s) => Ok(s.out_of_order_stats()),
+            None => Err(Fail::new(ENOTCONN, "connection not established")),
+        }
+    }
+
+    /// Gets a snapshot of the state of an established TCP connection.
+    pub fn tcp_info(&self, fd: QDesc) -> Result<TcpInfo, Fail> {
+        let inner = self.inner.borrow();
+        let key = match inner.sockets.get(&fd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            Some(..) => return Err(Fail::new(ENOTCONN, "connection not established")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        match inner.established.get(&key) {
+            Some(ref s) => Ok(s.tcp_info()),
+            None => Err(Fail::new(ENOTCONN, "connection not established")),
+        }
+    }
+
+    /// Gets the hardware timestamps of the last segment received and sent on an established TCP connection.
+    pub fn timestamps(&self, fd: QDesc) -> Result<Timestamps, Fail> {
+        let inner = self.inner.borrow();
+        let key = match inner.sockets.get(&fd) {
+            Some(Socket::Established { local, remote }) => (*local, *remote),
+            Some(..) => return Err(Fail::new(ENOTCONN, "connection not established")),
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+        match inner.established.get(&key) {
+            Some(ref s) => Ok(s.timestamps()),
+            None => Err(Fail::new(ENOTCONN, "connection not established")),
+        }
+    }
+
     pub fn endpoints(&self, fd: QDesc) -> Result<(SocketAddrV4, SocketAddrV4), Fail> {
         let inner = self.inner.borrow();
         let key = match inner.sockets.get(&fd) {
@@ -484,6 +865,122 @@ impl TcpPeer {
             None => Err(Fail::new(ENOTCONN, "connection not established")),
         }
     }
+
+    /// Gets the local endpoint address of a TCP socket.
+    pub fn local_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        let inner = self.inner.borrow();
+        match inner.sockets.get(&qd) {
+            Some(Socket::Inactive { local: Some(local) })
+            | Some(Socket::Listening { local })
+            | Some(Socket::Connecting { local, remote: _ })
+            | Some(Socket::Established { local, remote: _ }) => Ok(*local),
+            // Unbound sockets report the wildcard address, as POSIX getsockname() does.
+            Some(Socket::Inactive { local: None }) => Ok(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)),
+            None => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    /// Gets the remote endpoint address of a TCP socket.
+    pub fn remote_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        let inner = self.inner.borrow();
+        match inner.sockets.get(&qd) {
+            Some(Socket::Connecting { local: _, remote }) | Some(Socket::Established { local: _, remote }) => {
+                Ok(*remote)
+            },
+            Some(..) => Err(Fail::new(ENOTCONN, "socket is not connected")),
+            None => Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+    }
+
+    /// Gets the current value of a socket option.
+    pub fn get_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        let inner = self.inner.borrow();
+        let options: &SocketOptions = match inner.options.get(&qd) {
+            Some(options) => options,
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        };
+
+        // Buffers of established connections are autotuned, so we report their current size.
+        if let Some(Socket::Established { local, remote }) = inner.sockets.get(&qd) {
+            let established: &EstablishedSocket = inner
+                .established
+                .get(&(*local, *remote))
+                .expect("sockets/established inconsistency");
+            match opt {
+                SocketOption::SendBufferSize(_) => {
+                    return Ok(SocketOption::SendBufferSize(
+                        established.cb.get_send_buffer_size() as usize
+                    ))
+                },
+                SocketOption::ReceiveBufferSize(_) => {
+                    return Ok(SocketOption::ReceiveBufferSize(
+                        established.cb.get_receive_buffer_size() as usize,
+                    ))
+                },
+                _ => (),
+            }
+        }
+
+        Ok(options.get(opt))
+    }
+
+    /// Sets a socket option.
+    pub fn set_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        let inner: &mut Inner = &mut *inner;
+
+        // The congestion control algorithm is picked when a connection is opened. Listening sockets pass it on to
+        // the connections that they accept from now on.
+        if let SocketOption::CongestionControl(algorithm) = opt {
+            match inner.sockets.get(&qd) {
+                Some(Socket::Listening { local }) => {
+                    let passive: &mut PassiveSocket =
+                        inner.passive.get_mut(local).expect("sockets/local inconsistency");
+                    passive.set_congestion_control(congestion_control::get_constructor(algorithm));
+                },
+                Some(Socket::Connecting { .. }) | Some(Socket::Established { .. }) => {
+                    return Err(Fail::new(EISCONN, "socket is already connected"))
+                },
+                _ => (),
+            }
+        }
+
+        match inner.options.get_mut(&qd) {
+            Some(options) => options.set(opt)?,
+            None => return Err(Fail::new(EBADF, "bad queue descriptor")),
+        }
+
+        // The pacing rate, the coalescing of small segments, delayed ACKs and the bounds of buffers can be changed on
+        // the fly on established connections.
+        if let Some(Socket::Established { local, remote }) = inner.sockets.get(&qd) {
+            let established: &EstablishedSocket = inner
+                .established
+                .get(&(*local, *remote))
+                .expect("sockets/established inconsistency");
+            match opt {
+                SocketOption::MaxPacingRate(rate) => established.cb.set_max_pacing_rate(rate),
+                SocketOption::NoDelay(nodelay) => established.cb.set_nodelay(nodelay),
+                SocketOption::Cork(cork) => established.cb.set_cork(cork),
+                SocketOption::AckDelay(delay) => established.cb.set_ack_delay_timeout(delay),
+                SocketOption::AckSegments(segments) => established.cb.set_ack_segments(segments),
+                SocketOption::QuickAck(quickack) => established.cb.set_quickack(quickack),
+                SocketOption::SendBufferSize(size) => established.cb.set_send_buffer(size, size, size),
+                SocketOption::ReceiveBufferSize(size) => established.cb.set_receive_buffer(size, size, size),
+                // Autotuning carries on from the current size of the buffer.
+                SocketOption::SendBufferBounds(min, max) => {
+                    let size: usize = established.cb.get_send_buffer_size() as usize;
+                    established.cb.set_send_buffer(size, min, max)
+                },
+                SocketOption::ReceiveBufferBounds(min, max) => {
+                    let size: usize = established.cb.get_receive_buffer_size() as usize;
+                    established.cb.set_receive_buffer(size, min, max)
+                },
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Inner {
@@ -492,20 +989,23 @@ impl Inner {
         scheduler: Scheduler,
         clock: TimerRc,
         local_link_addr: MacAddress,
-        local_ipv4_addr: Ipv4Addr,
         tcp_config: TcpConfig,
         arp: ArpPeer,
+        pmtu: PathMtuCache,
         rng_seed: [u8; 32],
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
-        _dead_socket_rx: mpsc::UnboundedReceiver<QDesc>,
+        dead_socket_rx: mpsc::UnboundedReceiver<QDesc>,
     ) -> Self {
         let mut rng: SmallRng = SmallRng::from_seed(rng_seed);
         let ephemeral_ports: EphemeralPorts = EphemeralPorts::new(&mut rng);
         let nonce: u32 = rng.gen();
+        let (shard_index, shard_count): (usize, usize) = tcp_config.get_shard();
         Self {
             isn_generator: IsnGenerator::new(nonce),
             ephemeral_ports,
+            steering: ConnectionSteering::new(shard_index, shard_count, tcp_config.get_rss()),
             sockets: HashMap::new(),
+            options: HashMap::new(),
             passive: HashMap::new(),
             connecting: HashMap::new(),
             established: HashMap::new(),
@@ -513,16 +1013,173 @@ impl Inner {
             scheduler,
             clock,
             local_link_addr,
-            local_ipv4_addr,
             tcp_config,
             arp,
+            pmtu,
             rng: Rc::new(RefCell::new(rng)),
             dead_socket_tx,
+            dead_socket_rx,
+            coalesce_acks: false,
+            deferred_acks: Vec::new(),
+            gro: None,
+        }
+    }
+
+    // Socket options for a newly created socket, derived from the TCP configuration.
+    fn default_socket_options(&self) -> SocketOptions {
+        let buffer_size: usize =
+            (self.tcp_config.get_receive_window_size() as usize) << self.tcp_config.get_window_scale();
+        let mut options: SocketOptions = SocketOptions::new(buffer_size, buffer_size, DEFAULT_IPV4_TTL);
+        options
+            .set(SocketOption::CongestionControl(
+                self.tcp_config.get_congestion_control(),
+            ))
+            .expect("congestion control algorithm should be valid");
+        // Like the other LibOSes, we send small segments right away unless the user asks for Nagle's algorithm.
+        options
+            .set(SocketOption::NoDelay(true))
+            .expect("no-delay flag should be valid");
+        options
+            .set(SocketOption::AckDelay(self.tcp_config.get_ack_delay_timeout()))
+            .expect("ACK delay should be valid");
+        options
+            .set(SocketOption::AckSegments(self.tcp_config.get_ack_segments()))
+            .expect("number of ACK segments should be valid");
+        options
+            .set(SocketOption::QuickAck(self.tcp_config.get_quickack()))
+            .expect("quick ACK flag should be valid");
+        // Buffers start out at the size of the window that we advertise, and are autotuned from there.
+        options
+            .set(SocketOption::SendBufferBounds(
+                buffer_size,
+                cmp::max(buffer_size, DEFAULT_MAX_SEND_BUFFER_SIZE),
+            ))
+            .expect("send buffer bounds should be valid");
+        options
+            .set(SocketOption::ReceiveBufferBounds(
+                buffer_size,
+                cmp::max(buffer_size, DEFAULT_MAX_RECEIVE_BUFFER_SIZE),
+            ))
+            .expect("receive buffer bounds should be valid");
+        options
+    }
+
+    // Checks whether no socket refers to the connection identified by `key` anymore (i.e. the user has closed it).
+    fn is_orphaned(&self, key: &(SocketAddrV4, SocketAddrV4)) -> bool {
+        !self.sockets.values().any(|socket| match socket {
+            Socket::Established { local, remote } => (*local, *remote) == *key,
+            _ => false,
+        })
+    }
+
+    // Removes the connection identified by `key`, and gives its local port back to the pool of ephemeral ports, unless
+    // something else still uses it.
+    fn remove_connection(&mut self, key: &(SocketAddrV4, SocketAddrV4)) {
+        self.established.remove(key);
+        let (local, _) = *key;
+        if !EphemeralPorts::is_private(local.port()) {
+            return;
+        }
+        let in_use: bool = self.passive.contains_key(&local)
+            || self.connecting.keys().any(|(l, _)| *l == local)
+            || self.established.keys().any(|(l, _)| *l == local)
+            || self.sockets.values().any(|socket| match socket {
+                Socket::Inactive { local: Some(l) }
+                | Socket::Listening { local: l }
+                | Socket::Connecting { local: l, .. }
+                | Socket::Established { local: l, .. } => *l == local,
+                Socket::Inactive { local: None } => false,
+            });
+        if !in_use {
+            self.ephemeral_ports.free(local.port());
+        }
+    }
+
+    // Looks for an ephemeral port of `local_ipv4_addr` that we may take over from a connection to `remote` in
+    // TIME-WAIT.
+    fn time_wait_port(&self, local_ipv4_addr: Ipv4Addr, remote: SocketAddrV4) -> Option<u16> {
+        if !self.tcp_config.get_time_wait_reuse() {
+            return None;
+        }
+        self.established
+            .iter()
+            .find(|((local, r), s)| {
+                *r == remote
+                    && *local.ip() == local_ipv4_addr
+                    && EphemeralPorts::is_private(local.port())
+                    && s.cb.get_state() == State::TimeWait
+                    && self.is_orphaned(&(*local, *r))
+            })
+            .map(|((local, _), _)| local.port())
+    }
+
+    // Removes the connections that are closed, once the user has closed them too.  Connections in TIME-WAIT are kept
+    // around until the time-wait timer expires, so that their endpoints don't get reused too early.
+    // Removes the connections whose background tasks terminated since the last call, unless a socket still refers to
+    // them. Those are removed once their socket is closed.
+    fn reap_dead_connections(&mut self) {
+        let mut dead: bool = false;
+        while let Ok(Some(qd)) = self.dead_socket_rx.try_next() {
+            debug!("connection of {:?} terminated", qd);
+            dead = true;
+        }
+        if dead {
+            self.reap_closed_connections();
+        }
+    }
+
+    fn reap_closed_connections(&mut self) {
+        let closed: Vec<(SocketAddrV4, SocketAddrV4)> = self
+            .established
+            .iter()
+            .filter(|(key, s)| s.cb.get_state() == State::Closed && self.is_orphaned(key))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in closed {
+            debug!("removing closed connection {:?}", key);
+            self.remove_connection(&key);
+        }
+    }
+
+    // Constructor of the congestion control algorithm selected for a socket.
+    fn congestion_control(&self, qd: QDesc) -> CongestionControlConstructor {
+        let algorithm = match self.options.get(&qd) {
+            Some(options) => options.get_congestion_control(),
+            None => self.tcp_config.get_congestion_control(),
+        };
+        congestion_control::get_constructor(algorithm)
+    }
+
+    fn receive_icmp_error(&mut self, error: &Icmpv4Error) {
+        let key: (SocketAddrV4, SocketAddrV4) = (error.local, error.remote);
+        if let Some(path_mtu) = error.path_mtu {
+            // Only trust reports about segments that are still in flight, lest anyone could shrink our segments by
+            // forging them (see RFC 5927 Section 4.1).
+            let seq_num: SeqNumber = SeqNumber::from(error.quoted_seq_num);
+            match self.established.get(&key) {
+                Some(s) if s.cb.get_send_unacked().0 <= seq_num && seq_num < s.cb.get_send_next().0 => {
+                    self.pmtu.update(*error.remote.ip(), path_mtu, self.clock.now());
+                },
+                _ => debug!("Ignoring path MTU report for {:?}: {:?}", key, error),
+            }
+            return;
         }
+        if let Some(s) = self.connecting.get_mut(&key) {
+            debug!("Routing ICMP error to connecting connection: {:?}", key);
+            s.receive_icmp_error(
+                SeqNumber::from(error.quoted_seq_num),
+                Fail::new(error.errno, "destination unreachable"),
+            );
+            return;
+        }
+        // Errors are soft once a connection is synchronized, since the network may recover before the connection
+        // times out (see RFC 1122 Section 4.2.3.9).
+        debug!("Ignoring ICMP error for {:?}: {:?}", key, error);
     }
 
     fn receive(&mut self, ip_hdr: &Ipv4Header, buf: DemiBuffer) -> Result<(), Fail> {
-        let (mut tcp_hdr, data) = TcpHeader::parse(ip_hdr, buf, self.tcp_config.get_rx_checksum_offload())?;
+        self.reap_dead_connections();
+        let (tcp_hdr, data) = TcpHeader::parse(ip_hdr, buf, self.tcp_config.get_rx_checksum_offload())?;
         debug!("TCP received {:?}", tcp_hdr);
         let local = SocketAddrV4::new(ip_hdr.get_dest_addr(), tcp_hdr.dst_port);
         let remote = SocketAddrV4::new(ip_hdr.get_src_addr(), tcp_hdr.src_port);
@@ -532,9 +1189,40 @@ impl Inner {
         }
         let key = (local, remote);
 
+        // Connections that are done are forgotten about once the user has closed them.  A new connection request may
+        // also take over an endpoint pair in TIME-WAIT, provided that its sequence numbers are beyond those of the
+        // previous connection (see RFC 1122 Section 4.2.2.13).
         if let Some(s) = self.established.get(&key) {
+            let reap: bool = match s.cb.get_state() {
+                State::Closed => true,
+                State::TimeWait => tcp_hdr.syn && !tcp_hdr.ack && tcp_hdr.seq_num > s.cb.get_receive_next(),
+                _ => false,
+            };
+            if reap && self.is_orphaned(&key) {
+                self.remove_connection(&key);
+            }
+        }
+
+        if self.established.contains_key(&key) {
             debug!("Routing to established connection: {:?}", key);
-            s.receive(&mut tcp_hdr, data);
+            let congestion_experienced: bool = ip_hdr.get_ecn() == IPV4_ECN_CE;
+            if !self.coalesce_acks || !self.tcp_config.get_gro() {
+                self.deliver(key, tcp_hdr, Payloads::from_iter([data]), congestion_experienced);
+                return Ok(());
+            }
+            // Coalesce the segment with the one that is held back, or else hold it back in turn.
+            let (tcp_hdr, data): (TcpHeader, DemiBuffer) = match self.gro.as_mut() {
+                Some(held) => match held.try_append(&key, tcp_hdr, data, congestion_experienced) {
+                    Ok(()) => return Ok(()),
+                    Err(segment) => segment,
+                },
+                None => (tcp_hdr, data),
+            };
+            self.flush_gro();
+            match GroSegment::new(key, tcp_hdr, data, congestion_experienced) {
+                Ok(held) => self.gro = Some(held),
+                Err((tcp_hdr, data)) => self.deliver(key, tcp_hdr, Payloads::from_iter([data]), congestion_experienced),
+            }
             return Ok(());
         }
         if let Some(s) = self.connecting.get_mut(&key) {
@@ -542,28 +1230,82 @@ impl Inner {
             s.receive(&tcp_hdr);
             return Ok(());
         }
+        // Connections that hash to the shard of another instance are left to that instance.
+        if !self.steering.accepts(&local, &remote) {
+            debug!("Leaving segment to another shard: {:?}", key);
+            return Ok(());
+        }
+
+        // Listeners on a specific address take precedence over those on the unspecified address.
         let (local, _) = key;
-        if let Some(s) = self.passive.get_mut(&local) {
-            debug!("Routing to passive connection: {:?}", local);
+        let wildcard: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, local.port());
+        let listener: SocketAddrV4 = if self.passive.contains_key(&local) {
+            local
+        } else {
+            wildcard
+        };
+        if let Some(s) = self.passive.get_mut(&listener) {
+            debug!("Routing to passive connection: {:?}", listener);
             return s.receive(ip_hdr, &tcp_hdr);
         }
 
         // The packet isn't for an open port; send a RST segment.
-        debug!("Sending RST for {:?}, {:?}", local, remote);
-        self.send_rst(&local, &remote)?;
+        self.send_rst(&local, &remote, &tcp_hdr, data.len())?;
         Ok(())
     }
 
-    fn send_rst(&mut self, local: &SocketAddrV4, remote: &SocketAddrV4) -> Result<(), Fail> {
+    // Hands a segment over to an established connection.
+    fn deliver(
+        &mut self,
+        key: (SocketAddrV4, SocketAddrV4),
+        mut tcp_hdr: TcpHeader,
+        payloads: Payloads,
+        congestion_experienced: bool,
+    ) {
+        let s: &EstablishedSocket = match self.established.get(&key) {
+            Some(s) => s,
+            None => {
+                warn!("Dropping segment of closed connection: {:?}", key);
+                return;
+            },
+        };
+        let had_deferred_ack: bool = s.cb.has_deferred_ack();
+        s.receive(&mut tcp_hdr, payloads, congestion_experienced, self.coalesce_acks);
+        if !had_deferred_ack && s.cb.has_deferred_ack() {
+            self.deferred_acks.push(key);
+        }
+    }
+
+    // Hands the segment that is held back for coalescing over to its connection.
+    fn flush_gro(&mut self) {
+        if let Some(held) = self.gro.take() {
+            let key: (SocketAddrV4, SocketAddrV4) = held.get_key();
+            debug!("Coalesced {} segments of {:?}", held.num_segments(), key);
+            let (tcp_hdr, payloads, congestion_experienced): (TcpHeader, Payloads, bool) = held.into_segment();
+            self.deliver(key, tcp_hdr, payloads, congestion_experienced);
+        }
+    }
+
+    // Answers a segment that does not belong to any connection with a RST.
+    fn send_rst(
+        &mut self,
+        local: &SocketAddrV4,
+        remote: &SocketAddrV4,
+        header: &TcpHeader,
+        data_len: usize,
+    ) -> Result<(), Fail> {
+        let tcp_hdr: TcpHeader = match TcpHeader::new_reset(header, data_len) {
+            Some(tcp_hdr) => tcp_hdr,
+            None => return Ok(()),
+        };
+        debug!("Sending RST for {:?}, {:?}", local, remote);
+
         // TODO: Make this work pending on ARP resolution if needed.
         let remote_link_addr = self
             .arp
             .try_query(remote.ip().clone())
             .ok_or(Fail::new(EINVAL, "detination not in ARP cache"))?;
 
-        let mut tcp_hdr = TcpHeader::new(local.port(), remote.port());
-        tcp_hdr.rst = true;
-
         let segment = TcpSegment {
             ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
             ipv4_hdr: Ipv4Header::new(local.ip().clone(), remote.ip().clone(), IpProtocol::TCP),
@@ -596,6 +1338,18 @@ impl Inner {
         self.connecting.remove(&key);
 
         let cb = result?;
+        if let Some(options) = self.options.get(&fd) {
+            cb.set_max_pacing_rate(options.get_max_pacing_rate());
+            cb.set_nodelay(options.get_nodelay());
+            cb.set_cork(options.get_cork());
+            cb.set_ack_delay_timeout(options.get_ack_delay());
+            cb.set_ack_segments(options.get_ack_segments());
+            cb.set_quickack(options.get_quickack());
+            let (min, max): (usize, usize) = options.get_send_buffer_bounds();
+            cb.set_send_buffer(options.get_send_buffer_size(), min, max);
+            let (min, max): (usize, usize) = options.get_receive_buffer_bounds();
+            cb.set_receive_buffer(options.get_receive_buffer_size(), min, max);
+        }
         let socket = EstablishedSocket::new(cb, fd, self.dead_socket_tx.clone());
         assert!(self.established.insert(key, socket).is_none());
         let (local, remote) = key;
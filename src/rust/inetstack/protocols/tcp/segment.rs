@@ -11,7 +11,10 @@ use crate::{
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
-        network::PacketBuf,
+        network::{
+            types::MacAddress,
+            PacketBuf,
+        },
     },
 };
 use ::byteorder::{
@@ -82,6 +85,10 @@ impl PacketBuf for TcpSegment {
             None => None,
         }
     }
+
+    fn dst_link_addr(&self) -> MacAddress {
+        self.ethernet2_hdr.dst_addr()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -223,6 +230,27 @@ impl TcpHeader {
         }
     }
 
+    /// Creates the header of the RST that answers `incoming`, a segment that carries `data_len` bytes and does not
+    /// belong to any connection (see RFC 793 Section 3.4). Returns `None` if `incoming` is a RST, as RSTs are never
+    /// answered.
+    pub fn new_reset(incoming: &TcpHeader, data_len: usize) -> Option<Self> {
+        if incoming.rst {
+            return None;
+        }
+        let mut header: TcpHeader = TcpHeader::new(incoming.dst_port, incoming.src_port);
+        header.rst = true;
+        if incoming.ack {
+            // The RST takes its sequence number from the ACK field, so that our peer accepts it.
+            header.seq_num = incoming.ack_num;
+        } else {
+            // Otherwise, the RST acknowledges everything in the incoming segment.
+            let seg_len: usize = data_len + incoming.syn as usize + incoming.fin as usize;
+            header.ack = true;
+            header.ack_num = incoming.seq_num + SeqNumber::from(seg_len as u32);
+        }
+        Some(header)
+    }
+
     pub fn parse(
         ipv4_header: &Ipv4Header,
         mut buf: DemiBuffer,
@@ -0,0 +1,67 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file implements the steering of TCP connections among instances of the stack that share the same local address
+// (e.g. one LibOS per core, all of which see the frames that arrive on the interface).
+//
+// Each instance owns one shard of the space of 4-tuples, and only handles the segments of connections that hash to its
+// shard.  Listeners bound to the same port in every instance thus behave as if they were bound with SO_REUSEPORT: each
+// one accepts the connections that hash to its shard, and segments that hash to the shard of another instance are left
+// to that instance, rather than being answered with a RST.  Outgoing connections pick an ephemeral port that makes
+// their 4-tuple hash to the local shard, so that the segments of our peer come back to the instance that connected.
+// Connected UDP sockets pick their ephemeral port the same way.
+//
+// When the port steers flows to one receive queue per instance with Receive Side Scaling (RSS), the shard of a
+// connection is the queue that the port delivers its segments to instead.  Each instance then only sees the segments of
+// its own shard, and outgoing connections pick an ephemeral port whose RSS hash leads back to the local queue.
+//
+
+use crate::runtime::network::types::Rss;
+use ::std::net::SocketAddrV4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectionSteering {
+    index: u32,
+    count: u32,
+    rss: Option<Rss>,
+}
+
+impl ConnectionSteering {
+    /// Creates the steering of the instance that owns shard `index` out of `count` shards. If `rss` is set, shards are
+    /// the receive queues of the port.
+    pub fn new(index: usize, count: usize, rss: Option<Rss>) -> Self {
+        debug_assert!(index < count);
+        Self {
+            index: index as u32,
+            count: count as u32,
+            rss,
+        }
+    }
+
+    /// Checks whether the connection between `local` and `remote` belongs to the shard of this instance.
+    pub fn owns(&self, local: &SocketAddrV4, remote: &SocketAddrV4) -> bool {
+        if self.count == 1 {
+            return true;
+        }
+        match self.rss {
+            Some(rss) => rss.queue(remote, local) == self.index as usize,
+            None => Self::hash(local, remote) % self.count == self.index,
+        }
+    }
+
+    /// Checks whether a segment received on the connection between `local` and `remote` should be handled by this
+    /// instance. Segments that the port steered to the receive queue of this instance always are.
+    pub fn accepts(&self, local: &SocketAddrV4, remote: &SocketAddrV4) -> bool {
+        self.rss.is_some() || self.owns(local, remote)
+    }
+
+    fn hash(local: &SocketAddrV4, remote: &SocketAddrV4) -> u32 {
+        let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_CKSUM);
+        let mut digest = crc.digest();
+        digest.update(&remote.ip().octets());
+        digest.update(&remote.port().to_be_bytes());
+        digest.update(&local.ip().octets());
+        digest.update(&local.port().to_be_bytes());
+        digest.finalize()
+    }
+}
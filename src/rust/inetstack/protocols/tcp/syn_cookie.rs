@@ -0,0 +1,182 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file implements SYN cookies (see RFC 4987).
+//
+// When the pending-connection table of a listener is full, we answer a SYN without keeping any state for it.  Instead,
+// everything that we need to establish the connection is encoded in the initial sequence number of our SYN+ACK (the
+// cookie), which our peer echoes back when it acknowledges it.  A cookie is laid out as follows:
+//
+//   - Bits 31-27: a counter that ticks every COOKIE_TICK, so that cookies expire.
+//   - Bits 26-24: an index into MSS_TABLE, which approximates the MSS advertised by our peer.
+//   - Bits 23-0: a MAC of the connection's endpoints, its remote ISN and the full counter.
+//
+// The MAC is SipHash-2-4, which is a keyed pseudo-random function, so cookies cannot be forged from the ones that were
+// seen before.  Its key must be unpredictable, so it is drawn from the entropy of the OS by the caller.
+//
+// Since there is no room left for other options, connections established this way do without window scaling,
+// selective acknowledgements, timestamps and ECN.
+//
+
+use crate::inetstack::protocols::tcp::SeqNumber;
+use ::std::{
+    net::SocketAddrV4,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// MSS values that can be encoded in a cookie.  These cover the most common MSS values seen on the Internet, as well
+/// as jumbo frames.
+const MSS_TABLE: [usize; 6] = [536, 1300, 1440, 1460, 4312, 8960];
+
+/// Time between ticks of the cookie counter.  Cookies are accepted for one to two ticks after they were sent.
+const COOKIE_TICK: Duration = Duration::from_secs(64);
+
+const COUNTER_SHIFT: u32 = 27;
+const MSS_SHIFT: u32 = 24;
+const MSS_MASK: u32 = 0x7;
+const HASH_MASK: u32 = 0x00ff_ffff;
+const COUNTER_MASK: u32 = 0x1f;
+
+/// Size of the key of the MAC, in bytes.
+pub const SYN_COOKIE_KEY_SIZE: usize = 16;
+
+pub struct SynCookieGenerator {
+    key: [u8; SYN_COOKIE_KEY_SIZE],
+    epoch: Instant,
+}
+
+impl SynCookieGenerator {
+    pub fn new(key: [u8; SYN_COOKIE_KEY_SIZE], epoch: Instant) -> Self {
+        Self { key, epoch }
+    }
+
+    /// Generates the cookie to use as our ISN when answering a SYN that carries `remote_isn` and advertises `mss`.
+    pub fn generate(
+        &self,
+        local: &SocketAddrV4,
+        remote: &SocketAddrV4,
+        remote_isn: SeqNumber,
+        mss: usize,
+        now: Instant,
+    ) -> SeqNumber {
+        // Pick the largest MSS from the table that does not exceed the one of our peer.
+        let mss_index: usize = MSS_TABLE.iter().rposition(|m| *m <= mss).unwrap_or(0);
+        let counter: u32 = self.counter(now);
+        let hash: u32 = self.hash(local, remote, remote_isn, counter);
+        SeqNumber::from(
+            ((counter & COUNTER_MASK) << COUNTER_SHIFT) | ((mss_index as u32) << MSS_SHIFT) | (hash & HASH_MASK),
+        )
+    }
+
+    /// Checks whether `cookie` is one we recently generated for a SYN that carried `remote_isn`. If so, returns the MSS
+    /// that was encoded in it.
+    pub fn validate(
+        &self,
+        local: &SocketAddrV4,
+        remote: &SocketAddrV4,
+        remote_isn: SeqNumber,
+        cookie: SeqNumber,
+        now: Instant,
+    ) -> Option<usize> {
+        let cookie: u32 = cookie.into();
+        let counter: u32 = self.counter(now);
+        let age: u32 = counter.wrapping_sub(cookie >> COUNTER_SHIFT) & COUNTER_MASK;
+        if age > 1 || age > counter {
+            return None;
+        }
+        if self.hash(local, remote, remote_isn, counter - age) & HASH_MASK != cookie & HASH_MASK {
+            return None;
+        }
+        MSS_TABLE.get(((cookie >> MSS_SHIFT) & MSS_MASK) as usize).copied()
+    }
+
+    fn counter(&self, now: Instant) -> u32 {
+        ((now - self.epoch).as_secs() / COOKIE_TICK.as_secs()) as u32
+    }
+
+    fn hash(&self, local: &SocketAddrV4, remote: &SocketAddrV4, remote_isn: SeqNumber, counter: u32) -> u32 {
+        let remote_isn: u32 = remote_isn.into();
+        let mut message: [u8; 20] = [0; 20];
+        message[0..4].copy_from_slice(&remote.ip().octets());
+        message[4..6].copy_from_slice(&remote.port().to_be_bytes());
+        message[6..10].copy_from_slice(&local.ip().octets());
+        message[10..12].copy_from_slice(&local.port().to_be_bytes());
+        message[12..16].copy_from_slice(&remote_isn.to_be_bytes());
+        message[16..20].copy_from_slice(&counter.to_be_bytes());
+        siphash24(&self.key, &message) as u32
+    }
+}
+
+/// Computes the SipHash-2-4 of `data` under `key`.
+fn siphash24(key: &[u8; SYN_COOKIE_KEY_SIZE], data: &[u8]) -> u64 {
+    let k0: u64 = u64::from_le_bytes(key[0..8].try_into().unwrap());
+    let k1: u64 = u64::from_le_bytes(key[8..16].try_into().unwrap());
+    let mut v: [u64; 4] = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    // Compress the message 8 bytes at a time. The last block carries the remaining bytes and the length of the message.
+    let chunks = data.chunks_exact(8);
+    let tail: &[u8] = chunks.remainder();
+    for chunk in chunks {
+        let m: u64 = u64::from_le_bytes(chunk.try_into().unwrap());
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+    let mut last: [u8; 8] = [0; 8];
+    last[..tail.len()].copy_from_slice(tail);
+    last[7] = data.len() as u8;
+    let m: u64 = u64::from_le_bytes(last);
+    v[3] ^= m;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= m;
+
+    // Finalize.
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+/// Runs a round of SipHash on the state `v`.
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        siphash24,
+        SYN_COOKIE_KEY_SIZE,
+    };
+
+    /// Checks SipHash-2-4 against the test vectors of its reference implementation.
+    #[test]
+    fn test_siphash24() {
+        let mut key: [u8; SYN_COOKIE_KEY_SIZE] = [0; SYN_COOKIE_KEY_SIZE];
+        key.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        let message: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(&key, &[]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(siphash24(&key, &message[..8]), 0x93f5_f579_9a93_2462);
+        assert_eq!(siphash24(&key, &message), 0xa129_ca61_49be_45e5);
+    }
+}
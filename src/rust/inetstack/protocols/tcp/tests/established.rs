@@ -3,17 +3,50 @@
 
 use crate::{
     inetstack::{
-        protocols::tcp::{
-            operations::PushFuture,
-            tests::{
-                check_packet_data,
-                check_packet_pure_ack,
-                setup::{
-                    advance_clock,
-                    connection_setup,
+        protocols::{
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+                ETHERNET2_HEADER_SIZE,
+            },
+            icmpv4::{
+                Icmpv4Header,
+                Icmpv4Message,
+                Icmpv4Type2,
+                ICMPV4_CODE_FRAGMENTATION_NEEDED,
+            },
+            ip::IpProtocol,
+            ipv4::{
+                Ipv4Header,
+                IPV4_ECN_CE,
+                IPV4_ECN_ECT0,
+                IPV4_HEADER_DEFAULT_SIZE,
+            },
+            tcp::{
+                operations::{
+                    AcceptFuture,
+                    CloseFuture,
+                    ConnectFuture,
+                    PopFuture,
+                    PushFuture,
+                    SpliceFuture,
+                },
+                segment::{
+                    TcpHeader,
+                    TcpOptions2,
+                    TcpSegment,
                 },
+                tests::{
+                    check_packet_data,
+                    check_packet_pure_ack,
+                    setup::{
+                        advance_clock,
+                        connection_setup,
+                    },
+                },
+                OutOfOrderStats,
+                SeqNumber,
             },
-            SeqNumber,
         },
         test_helpers::{
             self,
@@ -22,21 +55,40 @@ use crate::{
     },
     runtime::{
         memory::DemiBuffer,
+        network::{
+            config::TcpConfig,
+            types::{
+                CongestionControlAlgorithm,
+                OutOfOrderDropPolicy,
+                SocketOption,
+                TcpInfo,
+            },
+            NetworkRuntime,
+        },
         QDesc,
     },
 };
 use ::futures::task::noop_waker_ref;
+use ::libc::{
+    EADDRINUSE,
+    ECONNRESET,
+    ETIMEDOUT,
+};
 use ::rand;
 use ::std::{
     collections::VecDeque,
     future::Future,
     net::SocketAddrV4,
     pin::Pin,
+    ptr::NonNull,
     task::{
         Context,
         Poll,
     },
-    time::Instant,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //=============================================================================
@@ -313,6 +365,169 @@ pub fn test_send_recv_loop() {
 
 //=============================================================================
 
+/// Tests that a sized pop waits until enough data is available, and that it
+/// coalesces and splits segments accordingly.
+#[test]
+pub fn test_pop_sized() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+    let window_scale: u8 = client.rt.tcp_config.get_window_scale();
+    let max_window_size: u32 = (client.rt.tcp_config.get_receive_window_size() as u32)
+        .checked_shl(window_scale as u32)
+        .unwrap();
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    let bufsize: u32 = 64;
+    let mut pop_future = server.tcp_pop_sized(server_fd, 96);
+
+    // Send first segment. This is not enough to complete the pop.
+    let (bytes, _): (DemiBuffer, usize) = send_data(
+        &mut ctx,
+        &mut now,
+        &mut server,
+        &mut client,
+        client_fd,
+        max_window_size as u16,
+        SeqNumber::from(1),
+        None,
+        cook_buffer(bufsize as usize, Some(1)),
+    );
+    server.receive(bytes).unwrap();
+    assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+
+    // Send second segment. Now the pop completes with data from both segments.
+    let (bytes, _): (DemiBuffer, usize) = send_data(
+        &mut ctx,
+        &mut now,
+        &mut server,
+        &mut client,
+        client_fd,
+        max_window_size as u16,
+        SeqNumber::from(1 + bufsize),
+        None,
+        cook_buffer(bufsize as usize, Some(2)),
+    );
+    server.receive(bytes).unwrap();
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok(buf)) => {
+            assert_eq!(buf.len(), 96);
+            assert!(buf[..64].iter().all(|b| *b == 1));
+            assert!(buf[64..].iter().all(|b| *b == 2));
+        },
+        _ => panic!("sized pop should have completed"),
+    }
+
+    // The tail of the second segment is still available.
+    let mut pop_future = server.tcp_pop_sized(server_fd, 32);
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok(buf)) => {
+            assert_eq!(buf.len(), 32);
+            assert!(buf.iter().all(|b| *b == 2));
+        },
+        _ => panic!("sized pop should have completed"),
+    }
+}
+
+//=============================================================================
+
+/// Tests that a vectored push sends all buffers back-to-back.
+#[test]
+pub fn test_pushv() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Push header and payload.
+    let bufs: Vec<DemiBuffer> = vec![cook_buffer(16, Some(1)), cook_buffer(64, Some(2))];
+    let mut push_future: PushFuture = client.tcp_pushv(client_fd, bufs);
+    match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+        Poll::Ready(Ok(())) => (),
+        _ => panic!("vectored push should have completed"),
+    }
+
+    // Deliver all segments to the server.
+    while let Some(bytes) = client.rt.pop_frame_unchecked() {
+        server.receive(bytes).unwrap();
+    }
+
+    // Both buffers are received in order.
+    let mut pop_future = server.tcp_pop_sized(server_fd, 80);
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok(buf)) => {
+            assert_eq!(buf.len(), 80);
+            assert!(buf[..16].iter().all(|b| *b == 1));
+            assert!(buf[16..].iter().all(|b| *b == 2));
+        },
+        _ => panic!("pop should have completed"),
+    }
+}
+
+//=============================================================================
+
+/// Tests that a push of externally-owned memory only completes once the remote peer acknowledges the data, as the data
+/// is not copied and remains in the retransmission queue until then.
+#[test]
+pub fn test_push_external_completes_on_ack() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Push external memory, which is not acknowledged yet.
+    let mut data: Vec<u8> = vec![7; 32];
+    let ptr: NonNull<u8> = NonNull::new(data.as_mut_ptr()).unwrap();
+    let buf: DemiBuffer = unsafe { DemiBuffer::from_external(ptr, data.len() as u16) };
+    let mut push_future: PushFuture = client.tcp_push(client_fd, buf);
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_pending());
+
+    // Deliver the data to the server, and its acknowledgement back to the client.
+    while let Some(bytes) = client.rt.pop_frame_unchecked() {
+        server.receive(bytes).unwrap();
+    }
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    while let Some(bytes) = server.rt.pop_frame_unchecked() {
+        client.receive(bytes).unwrap();
+    }
+
+    // The push completes once the data is acknowledged.
+    match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+        Poll::Ready(Ok(())) => (),
+        _ => panic!("push should have completed once acknowledged"),
+    }
+}
+
+//=============================================================================
+
 #[test]
 pub fn test_send_recv_round_loop() {
     let mut ctx = Context::from_waker(noop_waker_ref());
@@ -443,3 +658,1490 @@ fn test_connect_disconnect() {
 
     connection_hangup(&mut ctx, &mut now, &mut server, &mut client, server_fd, client_fd);
 }
+
+//=============================================================================
+
+/// Tests that an asynchronous close completes once the remote peer acknowledges our FIN.
+#[test]
+fn test_async_close() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send FIN: Client -> Server
+    let mut close_future: CloseFuture = client
+        .tcp_async_close(client_fd)
+        .expect("client tcp_async_close returned error");
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    assert!(Future::poll(Pin::new(&mut close_future), &mut ctx).is_pending());
+
+    // ACK FIN: Server -> Client
+    server.receive(bytes).expect("server receive returned error");
+    server.rt.poll_scheduler();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // Receive ACK FIN. Now the close completes.
+    client.receive(bytes).expect("client receive (of ACK) returned error");
+    match Future::poll(Pin::new(&mut close_future), &mut ctx) {
+        Poll::Ready(Ok(())) => (),
+        _ => panic!("close should have completed"),
+    }
+}
+
+//=============================================================================
+
+/// Forges a fragmentation needed message that a router on the path from Alice to Bob sends about the datagram that
+/// `quote` starts with.
+fn forge_fragmentation_needed(server: &mut Engine, quote: &[u8], path_mtu: u16) -> DemiBuffer {
+    let message: Icmpv4Message = Icmpv4Message::new(
+        Ethernet2Header::new(test_helpers::ALICE_MAC, test_helpers::BOB_MAC, EtherType2::Ipv4),
+        Ipv4Header::new(test_helpers::BOB_IPV4, test_helpers::ALICE_IPV4, IpProtocol::ICMPv4),
+        Icmpv4Header::new(
+            Icmpv4Type2::DestinationUnreachable { next_hop_mtu: path_mtu },
+            ICMPV4_CODE_FRAGMENTATION_NEEDED,
+        ),
+    )
+    .with_data(DemiBuffer::from_slice(quote).unwrap());
+    server.rt.transmit(Box::new(message));
+    server.rt.pop_frame()
+}
+
+/// Tests that the MSS is clamped to the path MTU once a router reports that our segments are too large.
+#[test]
+fn test_path_mtu_discovery() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let path_mtu: u16 = 1000;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+    let mss: usize = client.tcp_mss(client_fd).unwrap();
+
+    // The client sends a full-sized segment, which a router drops.
+    let _push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(mss, None));
+    client.rt.poll_scheduler();
+    let segment: DemiBuffer = client.rt.pop_frame();
+    let quote: &[u8] = &segment[ETHERNET2_HEADER_SIZE..(ETHERNET2_HEADER_SIZE + IPV4_HEADER_DEFAULT_SIZE + 8)];
+
+    // A report about a segment that is not in flight is ignored, so the next segment is still full-sized.
+    let mut forged: Vec<u8> = quote.to_vec();
+    forged[IPV4_HEADER_DEFAULT_SIZE + 4] ^= 0x80;
+    client
+        .receive(forge_fragmentation_needed(&mut server, &forged, path_mtu))
+        .unwrap();
+    let _push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(mss, None));
+    client.rt.poll_scheduler();
+    let (_, data) = extract_tcp_segment(client.rt.pop_frame());
+    assert_eq!(data.len(), mss);
+
+    // A router reports that the segment did not fit in the MTU of the next hop.
+    client
+        .receive(forge_fragmentation_needed(&mut server, quote, path_mtu))
+        .unwrap();
+
+    // Data is now sent in segments that fit in the path MTU, and that routers should not fragment.
+    let bufsize: usize = 2000;
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    client.rt.poll_scheduler();
+    let mut sent: usize = 0;
+    while let Some(bytes) = client.rt.pop_frame_unchecked() {
+        let (_, ipv4_payload) = Ethernet2Header::parse(bytes).unwrap();
+        let (ipv4_header, ipv4_payload) = Ipv4Header::parse(ipv4_payload).unwrap();
+        let (_, data) = TcpHeader::parse(&ipv4_header, ipv4_payload, false).unwrap();
+        assert!(ipv4_header.get_dont_fragment());
+        assert!(data.len() <= path_mtu as usize - 40);
+        sent += data.len();
+    }
+    assert_eq!(sent, bufsize);
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+}
+
+//=============================================================================
+
+/// Tests that segments sent by either peer are no larger than the lesser of the MSS that both peers advertise.
+#[test]
+fn test_advertised_mss() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let advertised_mss: usize = 1000;
+    let tcp_config: TcpConfig = TcpConfig::default().set_advertised_mss(advertised_mss);
+
+    // Setup peers.  Only the client advertises a smaller MSS.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2_with_tcp_config(now, tcp_config);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+    assert!(server.tcp_mss(server_fd).unwrap() <= advertised_mss);
+    assert!(client.tcp_mss(client_fd).unwrap() <= advertised_mss);
+
+    // Data that the client sends fits in the MSS that it advertised, although the server advertised a larger one.
+    let bufsize: usize = 2000;
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    client.rt.poll_scheduler();
+    let mut sent: usize = 0;
+    while let Some(bytes) = client.rt.pop_frame_unchecked() {
+        let (_, data) = extract_tcp_segment(bytes);
+        assert!(data.len() <= advertised_mss);
+        sent += data.len();
+    }
+    assert_eq!(sent, bufsize);
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+}
+
+//=============================================================================
+
+/// Parses a TCP segment sent by one of the peers.
+fn extract_tcp_segment(bytes: DemiBuffer) -> (TcpHeader, DemiBuffer) {
+    let (_, ipv4_payload) = Ethernet2Header::parse(bytes).unwrap();
+    let (ipv4_header, ipv4_payload) = Ipv4Header::parse(ipv4_payload).unwrap();
+    TcpHeader::parse(&ipv4_header, ipv4_payload, false).unwrap()
+}
+
+/// Tests that with selective acknowledgements, a single lost segment is retransmitted on its own.
+#[test]
+fn test_sack_loss_recovery() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 100;
+    let nsegments: usize = 4;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send some segments: Client -> Server
+    let mut segments: Vec<DemiBuffer> = Vec::new();
+    for _ in 0..nsegments {
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        segments.push(client.rt.pop_frame());
+    }
+    let (tcp_header, _) = extract_tcp_segment(segments[0].clone());
+    let seq_no: SeqNumber = tcp_header.seq_num;
+
+    // The first segment is lost.  The server selectively acknowledges the ones that follow.
+    let mut acks: Vec<DemiBuffer> = Vec::new();
+    for i in 1..nsegments {
+        server.receive(segments[i].clone()).unwrap();
+        let bytes: DemiBuffer = server.rt.pop_frame();
+        let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+        assert_eq!(tcp_header.ack_num, seq_no);
+        let sack: Option<&TcpOptions2> = tcp_header
+            .iter_options()
+            .find(|option| matches!(option, TcpOptions2::SelectiveAcknowlegement { .. }));
+        match sack {
+            Some(TcpOptions2::SelectiveAcknowlegement { num_sacks, sacks }) => {
+                assert_eq!(*num_sacks, 1);
+                assert_eq!(sacks[0].begin, seq_no + SeqNumber::from(bufsize as u32));
+                assert_eq!(sacks[0].end, seq_no + SeqNumber::from(((i + 1) * bufsize) as u32));
+            },
+            option => panic!("expected a SACK option, got {:?}", option),
+        }
+        acks.push(bytes);
+    }
+
+    // Once enough data above the hole is acknowledged, the client retransmits just the lost segment.
+    let nacks: usize = acks.len();
+    for (i, bytes) in acks.into_iter().enumerate() {
+        client.receive(bytes).unwrap();
+        if i + 1 < nacks {
+            assert!(client.rt.pop_frame_unchecked().is_none());
+        }
+    }
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    assert!(client.rt.pop_frame_unchecked().is_none());
+    let (tcp_header, data) = extract_tcp_segment(bytes.clone());
+    assert_eq!(tcp_header.seq_num, seq_no);
+    assert_eq!(data.len(), bufsize);
+
+    // The server now acknowledges everything right away.
+    server.receive(bytes).unwrap();
+    let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+    assert_eq!(
+        tcp_header.ack_num,
+        seq_no + SeqNumber::from((nsegments * bufsize) as u32)
+    );
+    assert!(!tcp_header
+        .iter_options()
+        .any(|option| matches!(option, TcpOptions2::SelectiveAcknowlegement { .. })));
+
+    // All data is delivered in order.
+    for _ in 0..nsegments {
+        let mut pop_future = server.tcp_pop(server_fd);
+        match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+            Poll::Ready(Ok(buf)) => assert_eq!(buf.len(), bufsize),
+            _ => panic!("pop should have completed"),
+        }
+    }
+}
+
+/// Tests that after a retransmission timeout, the holes that were already retransmitted during SACK-based loss recovery
+/// are retransmitted again.
+#[test]
+fn test_sack_rto_restarts_recovery() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 100;
+    let nsegments: usize = 6;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send some segments: Client -> Server
+    let mut segments: Vec<DemiBuffer> = Vec::new();
+    for _ in 0..nsegments {
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        segments.push(client.rt.pop_frame());
+    }
+    let (tcp_header, _) = extract_tcp_segment(segments[0].clone());
+    let seq_no: SeqNumber = tcp_header.seq_num;
+
+    // The first two segments are lost.  Once the server selectively acknowledges the ones that follow, the client
+    // retransmits both holes, but these retransmissions are lost too.
+    for i in 2..nsegments {
+        server.receive(segments[i].clone()).unwrap();
+        client.receive(server.rt.pop_frame()).unwrap();
+    }
+    for i in 0..2 {
+        let (tcp_header, _) = extract_tcp_segment(client.rt.pop_frame());
+        assert_eq!(tcp_header.seq_num, seq_no + SeqNumber::from((i * bufsize) as u32));
+    }
+    assert!(client.rt.pop_frame_unchecked().is_none());
+
+    // The retransmission timer expires, so the client retransmits the first hole.
+    client.clock.advance_clock(now + Duration::from_millis(1500));
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+    assert_eq!(tcp_header.seq_num, seq_no);
+
+    // Once the server acknowledges it, the client retransmits the second hole right away.
+    server.receive(bytes).unwrap();
+    client.receive(server.rt.pop_frame()).unwrap();
+    let (tcp_header, data) = extract_tcp_segment(client.rt.pop_frame());
+    assert_eq!(tcp_header.seq_num, seq_no + SeqNumber::from(bufsize as u32));
+    assert_eq!(data.len(), bufsize);
+}
+
+//=============================================================================
+
+/// Tests that the out-of-order store holds a bounded number of segments, and drops the ones over the limit according to
+/// the configured policy.
+#[test]
+fn test_out_of_order_limits() {
+    for policy in [OutOfOrderDropPolicy::DropHighest, OutOfOrderDropPolicy::DropNew] {
+        let mut ctx = Context::from_waker(noop_waker_ref());
+        let mut now = Instant::now();
+
+        // Connection parameters
+        let listen_port: u16 = 80;
+        let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+        let bufsize: usize = 100;
+        let nsegments: usize = 5;
+        let max_segments: usize = 2;
+        let tcp_config: TcpConfig = TcpConfig::default()
+            .set_out_of_order_max_segments(max_segments)
+            .set_out_of_order_drop_policy(policy);
+
+        // Setup peers.
+        let mut server: Engine = test_helpers::new_bob2_with_tcp_config(now, tcp_config);
+        let mut client: Engine = test_helpers::new_alice2(now);
+
+        let (server_fd, client_fd): (QDesc, QDesc) =
+            connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+        // Send some segments: Client -> Server
+        let mut segments: Vec<DemiBuffer> = Vec::new();
+        for _ in 0..nsegments {
+            let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+            assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+            segments.push(client.rt.pop_frame());
+        }
+        let (tcp_header, _) = extract_tcp_segment(segments[0].clone());
+        let seq_no: SeqNumber = tcp_header.seq_num;
+
+        // The first segment is lost, and the others arrive in reverse order.  Only some of them fit in the store.
+        for i in (1..nsegments).rev() {
+            server.receive(segments[i].clone()).unwrap();
+            server.rt.pop_frame();
+        }
+        let stats: OutOfOrderStats = server.tcp_out_of_order_stats(server_fd).unwrap();
+        assert_eq!(stats.segments, (nsegments - 1) as u64);
+        assert_eq!(stats.drops, (nsegments - 1 - max_segments) as u64);
+        assert_eq!(stats.merges, 0);
+
+        // Once the hole is filled, the stored segments that follow it are delivered as well.  Evicting the segments
+        // with the highest sequence numbers keeps those.
+        server.receive(segments[0].clone()).unwrap();
+        let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+        let stats: OutOfOrderStats = server.tcp_out_of_order_stats(server_fd).unwrap();
+        let delivered: usize = match policy {
+            OutOfOrderDropPolicy::DropHighest => 1 + max_segments,
+            OutOfOrderDropPolicy::DropNew => 1,
+        };
+        assert_eq!(
+            tcp_header.ack_num,
+            seq_no + SeqNumber::from((delivered * bufsize) as u32)
+        );
+        assert_eq!(stats.merges, (delivered - 1) as u64);
+    }
+}
+
+//=============================================================================
+
+/// Tests that the receive buffer grows with the rate at which the user reads data, and that the send buffer bounds the
+/// amount of data queued for sending until it grows with the congestion window.
+#[test]
+fn test_buffer_autotuning() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 500;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Start the server out with a receive buffer that holds two segments, and let it grow from there.
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::ReceiveBufferSize(2 * bufsize))
+        .unwrap();
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::ReceiveBufferBounds(2 * bufsize, 0xffff))
+        .unwrap();
+
+    // The client fills the receive window over one round trip, while the server reads everything right away.
+    for _ in 0..2 {
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        let bytes: DemiBuffer = client.rt.pop_frame();
+        let mut pop_future: PopFuture = server.tcp_pop(server_fd);
+        server.receive(bytes).unwrap();
+        server.rt.pop_frame();
+        match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+            Poll::Ready(Ok(buf)) => assert_eq!(buf.len(), bufsize),
+            _ => panic!("pop should complete"),
+        }
+        advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    }
+
+    // The receive buffer grows to twice the amount of data read in that round trip.
+    assert_eq!(
+        server
+            .ipv4
+            .tcp
+            .get_socket_option(server_fd, SocketOption::ReceiveBufferSize(0))
+            .unwrap(),
+        SocketOption::ReceiveBufferSize(4 * bufsize)
+    );
+
+    // Pin the server's send buffer, so that it only takes two buffers.
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::SendBufferSize(2 * bufsize))
+        .unwrap();
+    let mut segments: Vec<DemiBuffer> = Vec::new();
+    for _ in 0..2 {
+        let mut push_future: PushFuture = server.tcp_push(server_fd, cook_buffer(bufsize, None));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        segments.push(server.rt.pop_frame());
+    }
+    // A push that overshoots the send buffer is queued, but only completes once there is room for it.
+    let mut blocked_push_future: PushFuture = server.tcp_push(server_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut blocked_push_future), &mut ctx).is_pending());
+
+    // Once autotuning is back on, the send buffer grows with the congestion window as data gets acknowledged.
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::SendBufferBounds(2 * bufsize, 1 << 20))
+        .unwrap();
+    for segment in segments {
+        client.receive(segment).unwrap();
+        server.receive(client.rt.pop_frame()).unwrap();
+    }
+    match Future::poll(Pin::new(&mut blocked_push_future), &mut ctx) {
+        Poll::Ready(Ok(())) => (),
+        _ => panic!("push should complete once there is room in the send buffer"),
+    }
+    match server
+        .ipv4
+        .tcp
+        .get_socket_option(server_fd, SocketOption::SendBufferSize(0))
+        .unwrap()
+    {
+        SocketOption::SendBufferSize(size) => assert!(size > 2 * bufsize),
+        _ => unreachable!(),
+    }
+    let mut push_future: PushFuture = server.tcp_push(server_fd, cook_buffer(bufsize, None));
+    match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+        Poll::Ready(Ok(())) => (),
+        _ => panic!("push should succeed"),
+    }
+}
+
+//=============================================================================
+
+/// Tests that a splice forwards data until end-of-stream, and that it stops popping data from the source socket while
+/// the data queued on the destination socket overshoots its send buffer. The server splices its socket into itself, so
+/// that it echoes back whatever the client sends.
+#[test]
+fn test_splice() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers. The client acknowledges data right away.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2_with_tcp_config(now, TcpConfig::default().set_quickack(true));
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Pin the server's send buffer, so that it only takes one buffer.
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::SendBufferSize(bufsize))
+        .unwrap();
+
+    let mut splice_future: SpliceFuture = server.tcp_splice(server_fd, server_fd);
+    assert!(Future::poll(Pin::new(&mut splice_future), &mut ctx).is_pending());
+
+    // Sends a buffer from the client to the server.
+    let send_data = |client: &mut Engine, server: &mut Engine, stamp: u8| {
+        let mut ctx = Context::from_waker(noop_waker_ref());
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, Some(stamp)));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        server.receive(client.rt.pop_frame()).unwrap();
+    };
+
+    // The first two buffers are forwarded, the second one overshooting the send buffer.
+    for stamp in 1..=2 {
+        send_data(&mut client, &mut server, stamp);
+        assert!(Future::poll(Pin::new(&mut splice_future), &mut ctx).is_pending());
+    }
+    assert_eq!(splice_future.nbytes, 2 * bufsize);
+
+    // The third buffer is held back in the server's receive queue until the client acknowledges echoed data.
+    send_data(&mut client, &mut server, 3);
+    assert!(Future::poll(Pin::new(&mut splice_future), &mut ctx).is_pending());
+    assert_eq!(splice_future.nbytes, 2 * bufsize);
+    while let Some(frame) = server.rt.pop_frame_unchecked() {
+        client.receive(frame).unwrap();
+    }
+    while let Some(frame) = client.rt.pop_frame_unchecked() {
+        server.receive(frame).unwrap();
+    }
+    assert!(Future::poll(Pin::new(&mut splice_future), &mut ctx).is_pending());
+    assert_eq!(splice_future.nbytes, 3 * bufsize);
+    while let Some(frame) = server.rt.pop_frame_unchecked() {
+        client.receive(frame).unwrap();
+    }
+
+    // Everything is echoed back, in order.
+    for stamp in 1..=3 {
+        let mut pop_future: PopFuture = client.tcp_pop(client_fd);
+        match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+            Poll::Ready(Ok(buf)) => {
+                assert_eq!(buf.len(), bufsize);
+                assert!(buf.iter().all(|b| *b == stamp));
+            },
+            _ => panic!("pop should have completed"),
+        }
+    }
+
+    // The splice completes once the client closes the connection.
+    client.tcp_close(client_fd).unwrap();
+    client.rt.poll_scheduler();
+    while let Some(frame) = client.rt.pop_frame_unchecked() {
+        server.receive(frame).unwrap();
+    }
+    match Future::poll(Pin::new(&mut splice_future), &mut ctx) {
+        Poll::Ready(Ok(nbytes)) => assert_eq!(nbytes, 3 * bufsize),
+        _ => panic!("splice should complete at end-of-stream"),
+    }
+}
+
+//=============================================================================
+
+/// Returns the values of the Timestamps option in a TCP header.
+fn get_timestamps(tcp_header: &TcpHeader) -> (u32, u32) {
+    tcp_header
+        .iter_options()
+        .find_map(|option| match option {
+            TcpOptions2::Timestamp {
+                sender_timestamp,
+                echo_timestamp,
+            } => Some((*sender_timestamp, *echo_timestamp)),
+            _ => None,
+        })
+        .expect("expected a Timestamps option")
+}
+
+/// Tests that timestamps are echoed, and that segments carrying old timestamps are dropped (PAWS).
+#[test]
+fn test_timestamps_paws() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send data: Client -> Server
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+    let (ts_val, _): (u32, u32) = get_timestamps(&tcp_header);
+    let seq_no: SeqNumber = tcp_header.seq_num + SeqNumber::from(bufsize as u32);
+    server.receive(bytes).unwrap();
+
+    // The server echoes the client's timestamp.
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+    assert_eq!(tcp_header.ack_num, seq_no);
+    assert_eq!(get_timestamps(&tcp_header).1, ts_val);
+    client.receive(bytes).unwrap();
+
+    // Send more data: Client -> Server
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (ethernet2_hdr, ipv4_payload) = Ethernet2Header::parse(bytes.clone()).unwrap();
+    let (ipv4_hdr, ipv4_payload) = Ipv4Header::parse(ipv4_payload).unwrap();
+    let (mut tcp_hdr, data) = TcpHeader::parse(&ipv4_hdr, ipv4_payload, false).unwrap();
+    let (new_ts_val, _): (u32, u32) = get_timestamps(&tcp_hdr);
+    assert!(new_ts_val > ts_val);
+
+    // A copy of this segment that carries an older timestamp is dropped, even though its sequence number is fine.
+    for i in 0..tcp_hdr.num_options {
+        if let TcpOptions2::Timestamp {
+            ref mut sender_timestamp,
+            ..
+        } = tcp_hdr.option_list[i]
+        {
+            *sender_timestamp = ts_val.wrapping_sub(1);
+        }
+    }
+    let segment: TcpSegment = TcpSegment {
+        ethernet2_hdr,
+        ipv4_hdr,
+        tcp_hdr,
+        data: Some(data),
+        tx_checksum_offload: false,
+    };
+    client.rt.transmit(Box::new(segment));
+    server.receive(client.rt.pop_frame()).unwrap();
+    let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+    assert_eq!(tcp_header.ack_num, seq_no);
+
+    // The original segment is accepted.
+    server.receive(bytes).unwrap();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+    assert_eq!(tcp_header.ack_num, seq_no + SeqNumber::from(bufsize as u32));
+    assert_eq!(get_timestamps(&tcp_header).1, new_ts_val);
+}
+
+//=============================================================================
+
+/// Tests that connections accepted by a listening socket use the congestion control algorithm selected for it, and
+/// that the algorithm cannot be changed once a connection is open.
+#[test]
+fn test_congestion_control_option() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    // Select CUBIC on the listening socket.
+    let listen_fd: QDesc = server.tcp_socket().unwrap();
+    server.tcp_bind(listen_fd, listen_addr).unwrap();
+    server.tcp_listen(listen_fd, 1).unwrap();
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(
+            listen_fd,
+            SocketOption::CongestionControl(CongestionControlAlgorithm::Cubic),
+        )
+        .unwrap();
+    let mut accept_future: AcceptFuture = server.tcp_accept(listen_fd);
+    server.rt.poll_scheduler();
+
+    // Open a connection.
+    let client_fd: QDesc = client.tcp_socket().unwrap();
+    let mut connect_future: ConnectFuture = client.tcp_connect(client_fd, listen_addr);
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.receive(bytes).unwrap();
+    server.rt.poll_scheduler();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    client.receive(bytes).unwrap();
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.receive(bytes).unwrap();
+    server.rt.poll_scheduler();
+
+    let server_fd: QDesc = match Future::poll(Pin::new(&mut accept_future), &mut ctx) {
+        Poll::Ready(Ok(server_fd)) => Ok(server_fd),
+        _ => Err(()),
+    }
+    .unwrap();
+    match Future::poll(Pin::new(&mut connect_future), &mut ctx) {
+        Poll::Ready(Ok(())) => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // The accepted connection inherits the algorithm of the listening socket.
+    match server.ipv4.tcp.get_socket_option(
+        server_fd,
+        SocketOption::CongestionControl(CongestionControlAlgorithm::None),
+    ) {
+        Ok(SocketOption::CongestionControl(algorithm)) => assert_eq!(algorithm, CongestionControlAlgorithm::Cubic),
+        _ => panic!("server should report its congestion control algorithm"),
+    }
+
+    // The client uses the default algorithm, which cannot be changed now that it is connected.
+    match client.ipv4.tcp.get_socket_option(
+        client_fd,
+        SocketOption::CongestionControl(CongestionControlAlgorithm::Cubic),
+    ) {
+        Ok(SocketOption::CongestionControl(algorithm)) => assert_eq!(algorithm, CongestionControlAlgorithm::None),
+        _ => panic!("client should report its congestion control algorithm"),
+    }
+    assert!(client
+        .ipv4
+        .tcp
+        .set_socket_option(
+            client_fd,
+            SocketOption::CongestionControl(CongestionControlAlgorithm::Cubic)
+        )
+        .is_err());
+}
+
+//=============================================================================
+
+/// Tests that segments are spread out over time once the pacing rate of a connection is capped.
+#[test]
+fn test_max_pacing_rate() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 100;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Allow one segment every 100 ms.
+    client
+        .ipv4
+        .tcp
+        .set_socket_option(client_fd, SocketOption::MaxPacingRate(10 * bufsize as u64))
+        .unwrap();
+
+    // The first segment leaves right away.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    client.rt.pop_frame();
+
+    // The second one is held back.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    client.rt.poll_scheduler();
+    assert!(client.rt.pop_frame_unchecked().is_none());
+
+    // Until the pacer releases it.
+    now += Duration::from_millis(50);
+    client.clock.advance_clock(now);
+    client.rt.poll_scheduler();
+    assert!(client.rt.pop_frame_unchecked().is_none());
+    now += Duration::from_millis(50);
+    client.clock.advance_clock(now);
+    client.rt.poll_scheduler();
+    let (_, data) = extract_tcp_segment(client.rt.pop_frame());
+    assert_eq!(data.len(), bufsize);
+}
+
+//=============================================================================
+
+/// Tests that congestion marks are echoed to the sender, which reduces its window and acknowledges the echo.
+#[test]
+fn test_ecn_congestion_echo() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+    let ecn_config = |congestion_control: CongestionControlAlgorithm| -> TcpConfig {
+        TcpConfig::default()
+            .set_congestion_control(congestion_control)
+            .set_ecn(true)
+    };
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2_with_tcp_config(now, ecn_config(CongestionControlAlgorithm::None));
+    let mut client: Engine =
+        test_helpers::new_alice2_with_tcp_config(now, ecn_config(CongestionControlAlgorithm::Cubic));
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send data: Client -> Server.  The segment is ECN-capable.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (ethernet2_hdr, ipv4_payload) = Ethernet2Header::parse(bytes).unwrap();
+    let (ipv4_hdr, ipv4_payload) = Ipv4Header::parse(ipv4_payload).unwrap();
+    let (tcp_hdr, data) = TcpHeader::parse(&ipv4_hdr, ipv4_payload, false).unwrap();
+    assert_eq!(ipv4_hdr.get_ecn(), IPV4_ECN_ECT0);
+    assert!(!tcp_hdr.cwr);
+
+    // A router on the way marks it.
+    let segment: TcpSegment = TcpSegment {
+        ethernet2_hdr,
+        ipv4_hdr: ipv4_hdr.with_ecn(IPV4_ECN_CE),
+        tcp_hdr,
+        data: Some(data),
+        tx_checksum_offload: false,
+    };
+    client.rt.transmit(Box::new(segment));
+    server.receive(client.rt.pop_frame()).unwrap();
+
+    // The server echoes the mark in its ACK, which is not ECN-capable.
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    let (_, ipv4_payload) = Ethernet2Header::parse(bytes.clone()).unwrap();
+    let (ipv4_hdr, _) = Ipv4Header::parse(ipv4_payload).unwrap();
+    let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+    assert_eq!(ipv4_hdr.get_ecn(), 0);
+    assert!(tcp_header.ece);
+    client.receive(bytes).unwrap();
+
+    // The client reduces its window in response, and says so on the next segment that it sends.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+    assert!(tcp_header.cwr);
+    server.receive(bytes).unwrap();
+
+    // So the server stops echoing the mark.
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+    assert!(!tcp_header.ece);
+}
+
+//=============================================================================
+
+/// Tests that retransmitted segments are not ECN-capable.
+#[test]
+fn test_ecn_retransmission() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+    let tcp_config: TcpConfig = TcpConfig::default().set_ecn(true);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2_with_tcp_config(now, tcp_config.clone());
+    let mut client: Engine = test_helpers::new_alice2_with_tcp_config(now, tcp_config);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send data: Client -> Server.  The segment is ECN-capable, but it gets lost.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (_, ipv4_payload) = Ethernet2Header::parse(bytes).unwrap();
+    let (ipv4_hdr, _) = Ipv4Header::parse(ipv4_payload).unwrap();
+    assert_eq!(ipv4_hdr.get_ecn(), IPV4_ECN_ECT0);
+
+    // Its retransmission, once the initial RTO (of one second) expires, is not.
+    client.clock.advance_clock(now + Duration::from_millis(1500));
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (_, ipv4_payload) = Ethernet2Header::parse(bytes.clone()).unwrap();
+    let (ipv4_hdr, _) = Ipv4Header::parse(ipv4_payload).unwrap();
+    let (_, data) = extract_tcp_segment(bytes);
+    assert_eq!(ipv4_hdr.get_ecn(), 0);
+    assert_eq!(data.len(), bufsize);
+}
+
+//=============================================================================
+
+/// Tests that small segments are coalesced while data is in flight once Nagle's algorithm is enabled, and held back
+/// altogether while the connection is corked.
+#[test]
+fn test_nagle_and_cork() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Small segments are sent right away by default.
+    assert_eq!(
+        client
+            .ipv4
+            .tcp
+            .get_socket_option(client_fd, SocketOption::NoDelay(false))
+            .unwrap(),
+        SocketOption::NoDelay(true)
+    );
+    client
+        .ipv4
+        .tcp
+        .set_socket_option(client_fd, SocketOption::NoDelay(false))
+        .unwrap();
+
+    // The first segment leaves right away, as there is nothing in flight.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    server.receive(client.rt.pop_frame()).unwrap();
+
+    // The next ones are held back until it gets acknowledged.
+    for _ in 0..2 {
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    }
+    client.rt.poll_scheduler();
+    assert!(client.rt.pop_frame_unchecked().is_none());
+
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    client.receive(server.rt.pop_frame()).unwrap();
+
+    // And then sent together.
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (_, data) = extract_tcp_segment(bytes.clone());
+    assert_eq!(data.len(), 2 * bufsize);
+    server.receive(bytes).unwrap();
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+    server.rt.poll_scheduler();
+    client.receive(server.rt.pop_frame()).unwrap();
+
+    // Once corked, small segments are held back even though nothing is in flight.
+    client
+        .ipv4
+        .tcp
+        .set_socket_option(client_fd, SocketOption::Cork(true))
+        .unwrap();
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    client.rt.poll_scheduler();
+    assert!(client.rt.pop_frame_unchecked().is_none());
+
+    // Until the connection is uncorked.
+    client
+        .ipv4
+        .tcp
+        .set_socket_option(client_fd, SocketOption::Cork(false))
+        .unwrap();
+    client.rt.poll_scheduler();
+    let (_, data) = extract_tcp_segment(client.rt.pop_frame());
+    assert_eq!(data.len(), bufsize);
+}
+
+//=============================================================================
+
+/// Tests that delayed ACKs can be tuned per socket, and that ACKs are coalesced across a batch of received segments.
+#[test]
+fn test_delayed_ack_options() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Sends a segment from the client, and returns it.
+    let mut send_segment = |client: &mut Engine| -> DemiBuffer {
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        client.rt.pop_frame()
+    };
+
+    // ACK every third segment.
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::AckSegments(3))
+        .unwrap();
+    for _ in 0..2 {
+        server.receive(send_segment(&mut client)).unwrap();
+        assert!(server.rt.pop_frame_unchecked().is_none());
+    }
+    server.receive(send_segment(&mut client)).unwrap();
+    client.receive(server.rt.pop_frame()).unwrap();
+
+    // When a batch of segments is received, a single ACK covers all of them.
+    server.ipv4.tcp.begin_ack_coalescing();
+    let mut last_seq_num: Option<SeqNumber> = None;
+    for _ in 0..6 {
+        let bytes: DemiBuffer = send_segment(&mut client);
+        last_seq_num = Some(extract_tcp_segment(bytes.clone()).0.seq_num);
+        server.receive(bytes).unwrap();
+    }
+    assert!(server.rt.pop_frame_unchecked().is_none());
+    server.ipv4.tcp.flush_coalesced_acks();
+    let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+    assert!(server.rt.pop_frame_unchecked().is_none());
+    assert_eq!(
+        tcp_header.ack_num,
+        last_seq_num.unwrap() + SeqNumber::from(bufsize as u32)
+    );
+
+    // In quick-ack mode, every segment is acknowledged right away.
+    server
+        .ipv4
+        .tcp
+        .set_socket_option(server_fd, SocketOption::QuickAck(true))
+        .unwrap();
+    server.receive(send_segment(&mut client)).unwrap();
+    server.rt.pop_frame();
+}
+
+//=============================================================================
+
+/// Tests that in-order data segments received in the same batch are coalesced into a single segment, whose payloads
+/// are handed over to the application as they were received.
+#[test]
+fn test_gro() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2_with_tcp_config(now, TcpConfig::default().set_gro(true));
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Sends a segment from the client, and returns it.
+    let send_segment = |client: &mut Engine, stamp: u8| -> DemiBuffer {
+        let mut ctx = Context::from_waker(noop_waker_ref());
+        let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, Some(stamp)));
+        assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+        client.rt.pop_frame()
+    };
+
+    // Receive a batch of segments.
+    server.ipv4.tcp.begin_ack_coalescing();
+    for stamp in 1..=3 {
+        server.receive(send_segment(&mut client, stamp)).unwrap();
+    }
+    server.ipv4.tcp.flush_coalesced_acks();
+
+    // The payloads of the batch are not copied into a single buffer, so they are popped one at a time, in order.
+    for stamp in 1..=3 {
+        let mut pop_future = server.tcp_pop(server_fd);
+        match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+            Poll::Ready(Ok(buf)) => {
+                assert_eq!(buf.len(), bufsize);
+                assert!(buf.iter().all(|b| *b == stamp));
+            },
+            _ => panic!("pop should have completed"),
+        }
+    }
+
+    // Segments received outside of a batch are not coalesced.
+    for stamp in 4..=5 {
+        server.receive(send_segment(&mut client, stamp)).unwrap();
+    }
+    for stamp in 4..=5 {
+        let mut pop_future = server.tcp_pop(server_fd);
+        match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+            Poll::Ready(Ok(buf)) => {
+                assert_eq!(buf.len(), bufsize);
+                assert!(buf.iter().all(|b| *b == stamp));
+            },
+            _ => panic!("pop should have completed"),
+        }
+    }
+}
+
+//=============================================================================
+
+/// Tests that unacknowledged data is retransmitted with exponential backoff, and that the connection is aborted once
+/// the maximum number of retransmissions is exceeded.
+#[test]
+fn test_retransmission_timeout() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+    let tcp_config: TcpConfig = TcpConfig::default().set_max_retransmissions(2);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2_with_tcp_config(now, tcp_config);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Send data: Client -> Server.  The segment gets lost, and so do all of its retransmissions.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let (tcp_header, _) = extract_tcp_segment(client.rt.pop_frame());
+    let seq_num: SeqNumber = tcp_header.seq_num;
+    let mut pop_future = client.tcp_pop(client_fd);
+    assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+
+    // Advances the clock of the client to `t` past the first transmission.
+    let start: Instant = now;
+    let advance_client_clock = |client: &mut Engine, t: Duration| {
+        client.clock.advance_clock(start + t);
+        client.rt.poll_scheduler();
+    };
+
+    // The first retransmission happens once the initial RTO (of one second) expires.
+    advance_client_clock(&mut client, Duration::from_millis(1500));
+    let (tcp_header, data) = extract_tcp_segment(client.rt.pop_frame());
+    assert_eq!(tcp_header.seq_num, seq_num);
+    assert_eq!(data.len(), bufsize);
+
+    // The RTO doubles after each retransmission.
+    advance_client_clock(&mut client, Duration::from_millis(2500));
+    assert!(client.rt.pop_frame_unchecked().is_none());
+    advance_client_clock(&mut client, Duration::from_millis(4000));
+    let (tcp_header, _) = extract_tcp_segment(client.rt.pop_frame());
+    assert_eq!(tcp_header.seq_num, seq_num);
+
+    // Once the retransmissions are exhausted, the connection is aborted.
+    advance_client_clock(&mut client, Duration::from_millis(8500));
+    assert!(client.rt.pop_frame_unchecked().is_none());
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Err(e)) => assert_eq!(e.errno, ETIMEDOUT),
+        _ => panic!("pop should have failed"),
+    }
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+        Poll::Ready(Err(e)) => assert_eq!(e.errno, ETIMEDOUT),
+        _ => panic!("push should have failed"),
+    }
+
+    // The aborted connection is forgotten once its socket is closed.
+    assert_eq!(client.ipv4.tcp.num_connections(), 1);
+    client.tcp_close(client_fd).unwrap();
+    assert_eq!(client.ipv4.tcp.num_connections(), 0);
+}
+
+//=============================================================================
+
+/// Tests that the connection information reflects data in flight, retransmissions and acknowledgements.
+#[test]
+fn test_tcp_info() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (_, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    let info: TcpInfo = client.tcp_info(client_fd).unwrap();
+    assert_eq!(info.bytes_in_flight, 0);
+    assert_eq!(info.bytes_acked, 0);
+    assert_eq!(info.retransmits, 0);
+    assert_eq!(info.delivery_rate, 0);
+    assert!(info.cwnd > 0);
+
+    // Send data: Client -> Server.  The segment gets lost.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    client.rt.pop_frame();
+    let info: TcpInfo = client.tcp_info(client_fd).unwrap();
+    assert_eq!(info.bytes_in_flight, bufsize as u32);
+    assert_eq!(info.retransmits, 0);
+
+    // Advances the clocks of both peers to `t` past the first transmission.
+    let start: Instant = now;
+    let advance_clocks = |server: &mut Engine, client: &mut Engine, t: Duration| {
+        server.clock.advance_clock(start + t);
+        client.clock.advance_clock(start + t);
+        server.rt.poll_scheduler();
+        client.rt.poll_scheduler();
+    };
+
+    // The retransmission makes it to the server.
+    advance_clocks(&mut server, &mut client, Duration::from_millis(1500));
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let info: TcpInfo = client.tcp_info(client_fd).unwrap();
+    assert_eq!(info.bytes_in_flight, bufsize as u32);
+    assert_eq!(info.retransmits, 1);
+    server.receive(bytes).unwrap();
+
+    // The server acknowledges the data.
+    advance_clocks(&mut server, &mut client, Duration::from_millis(2000));
+    while let Some(bytes) = server.rt.pop_frame_unchecked() {
+        client.receive(bytes).unwrap();
+    }
+    let info: TcpInfo = client.tcp_info(client_fd).unwrap();
+    assert_eq!(info.bytes_in_flight, 0);
+    assert_eq!(info.bytes_acked, bufsize as u64);
+    assert_eq!(info.retransmits, 1);
+    assert!(info.srtt.is_some());
+    assert!(info.rttvar.is_some());
+    assert!(info.delivery_rate > 0);
+}
+
+//=============================================================================
+
+/// Forges a segment with the given sequence number and flags, which goes the same way as `template`.
+fn forge_segment(client: &mut Engine, template: &DemiBuffer, seq_num: SeqNumber, rst: bool, syn: bool) -> DemiBuffer {
+    let (ethernet2_hdr, ipv4_payload) = Ethernet2Header::parse(template.clone()).unwrap();
+    let (ipv4_hdr, ipv4_payload) = Ipv4Header::parse(ipv4_payload).unwrap();
+    let (template_hdr, _) = TcpHeader::parse(&ipv4_hdr, ipv4_payload, false).unwrap();
+    let mut tcp_hdr: TcpHeader = TcpHeader::new(template_hdr.src_port, template_hdr.dst_port);
+    tcp_hdr.seq_num = seq_num;
+    tcp_hdr.rst = rst;
+    tcp_hdr.syn = syn;
+    let segment: TcpSegment = TcpSegment {
+        ethernet2_hdr,
+        ipv4_hdr,
+        tcp_hdr,
+        data: None,
+        tx_checksum_offload: false,
+    };
+    client.rt.transmit(Box::new(segment));
+    client.rt.pop_frame()
+}
+
+/// Tests that a RST only resets a connection if it carries the exact sequence number that we expect, and that other
+/// in-window RSTs and SYNs are answered with challenge ACKs (see RFC 5961).
+#[test]
+fn test_reset_connection() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let bufsize: usize = 64;
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2(now);
+
+    let (server_fd, client_fd): (QDesc, QDesc) =
+        connection_setup(&mut ctx, &mut now, &mut server, &mut client, listen_port, listen_addr);
+
+    // Grab a segment of the client to forge others from.  It never reaches the server.
+    let mut push_future: PushFuture = client.tcp_push(client_fd, cook_buffer(bufsize, None));
+    assert!(Future::poll(Pin::new(&mut push_future), &mut ctx).is_ready());
+    let template: DemiBuffer = client.rt.pop_frame();
+    let (tcp_header, _) = extract_tcp_segment(template.clone());
+    let receive_next: SeqNumber = tcp_header.seq_num;
+    let mut pop_future = server.tcp_pop(server_fd);
+    assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+
+    // In-window RSTs and SYNs that do not start precisely on RCV.NXT are answered with a challenge ACK.
+    for (rst, syn) in [(true, false), (false, true)] {
+        let bytes: DemiBuffer = forge_segment(
+            &mut client,
+            &template,
+            receive_next + SeqNumber::from(bufsize as u32),
+            rst,
+            syn,
+        );
+        server.receive(bytes).unwrap();
+        let (tcp_header, _) = extract_tcp_segment(server.rt.pop_frame());
+        assert!(tcp_header.ack);
+        assert!(!tcp_header.rst);
+        assert_eq!(tcp_header.ack_num, receive_next);
+        assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+    }
+
+    // A RST that does resets the connection, and fails pending and new operations.
+    let bytes: DemiBuffer = forge_segment(&mut client, &template, receive_next, true, false);
+    server.receive(bytes).unwrap();
+    assert!(server.rt.pop_frame_unchecked().is_none());
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Err(e)) => assert_eq!(e.errno, ECONNRESET),
+        _ => panic!("pop should have failed"),
+    }
+    let mut push_future: PushFuture = server.tcp_push(server_fd, cook_buffer(bufsize, None));
+    match Future::poll(Pin::new(&mut push_future), &mut ctx) {
+        Poll::Ready(Err(e)) => assert_eq!(e.errno, ECONNRESET),
+        _ => panic!("push should have failed"),
+    }
+}
+
+//=============================================================================
+
+/// Returns a TCP configuration with a short maximum segment lifetime, and the given TIME-WAIT reuse option.
+fn time_wait_config(time_wait_reuse: bool) -> TcpConfig {
+    TcpConfig::default()
+        .set_msl(Duration::from_secs(10))
+        .set_time_wait_reuse(time_wait_reuse)
+}
+
+/// Opens a connection from `local_addr` to a server listening on `listen_fd`. Returns the queue descriptors of both
+/// ends, and the initial sequence number of the client.
+fn connect_from(
+    ctx: &mut Context,
+    server: &mut Engine,
+    client: &mut Engine,
+    listen_fd: QDesc,
+    local_addr: SocketAddrV4,
+    listen_addr: SocketAddrV4,
+) -> (QDesc, QDesc, SeqNumber) {
+    let mut accept_future: AcceptFuture = server.tcp_accept(listen_fd);
+    let client_fd: QDesc = client.tcp_socket().unwrap();
+    client.tcp_bind(client_fd, local_addr).unwrap();
+    let mut connect_future: ConnectFuture = client.tcp_connect(client_fd, listen_addr);
+    client.rt.poll_scheduler();
+    let bytes: DemiBuffer = client.rt.pop_frame();
+    let (tcp_header, _) = extract_tcp_segment(bytes.clone());
+    assert!(tcp_header.syn);
+    server.receive(bytes).unwrap();
+    server.rt.poll_scheduler();
+    client.receive(server.rt.pop_frame()).unwrap();
+    client.rt.poll_scheduler();
+    server.receive(client.rt.pop_frame()).unwrap();
+    server.rt.poll_scheduler();
+
+    let server_fd: QDesc = match Future::poll(Pin::new(&mut accept_future), ctx) {
+        Poll::Ready(Ok(server_fd)) => server_fd,
+        _ => panic!("accept should have completed"),
+    };
+    match Future::poll(Pin::new(&mut connect_future), ctx) {
+        Poll::Ready(Ok(())) => (),
+        _ => panic!("connect should have completed"),
+    }
+    (server_fd, client_fd, tcp_header.seq_num)
+}
+
+/// Closes a connection, starting from the client, so that the client ends up in TIME-WAIT. Returns the FIN sent by the
+/// server.
+fn close_from_client(server: &mut Engine, client: &mut Engine, server_fd: QDesc, client_fd: QDesc) -> DemiBuffer {
+    client.tcp_close(client_fd).unwrap();
+    client.rt.poll_scheduler();
+    server.receive(client.rt.pop_frame()).unwrap();
+    client.receive(server.rt.pop_frame()).unwrap();
+    server.tcp_close(server_fd).unwrap();
+    server.rt.poll_scheduler();
+    let fin: DemiBuffer = server.rt.pop_frame();
+    client.receive(fin.clone()).unwrap();
+    server.receive(client.rt.pop_frame()).unwrap();
+    fin
+}
+
+/// Tests that connections linger in TIME-WAIT for 2 MSL after an active close, so that their endpoints cannot be
+/// reused in the meantime.
+#[test]
+fn test_time_wait() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let local_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 1234);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2_with_tcp_config(now, time_wait_config(false));
+    let listen_fd: QDesc = server.tcp_socket().unwrap();
+    server.tcp_bind(listen_fd, listen_addr).unwrap();
+    server.tcp_listen(listen_fd, 1).unwrap();
+
+    let (server_fd, client_fd, _): (QDesc, QDesc, SeqNumber) =
+        connect_from(&mut ctx, &mut server, &mut client, listen_fd, local_addr, listen_addr);
+    let fin: DemiBuffer = close_from_client(&mut server, &mut client, server_fd, client_fd);
+
+    // The endpoints of the connection cannot be reused while it is in TIME-WAIT.
+    let client_fd: QDesc = client.tcp_socket().unwrap();
+    client.tcp_bind(client_fd, local_addr).unwrap();
+    match client.ipv4.tcp.connect(client_fd, listen_addr) {
+        Err(e) => assert_eq!(e.errno, EADDRINUSE),
+        Ok(_) => panic!("connect should have failed"),
+    }
+
+    // The server retransmits its FIN, as if our ACK got lost.  We acknowledge it again, and restart the timer.
+    now += Duration::from_secs(15);
+    client.clock.advance_clock(now);
+    client.rt.poll_scheduler();
+    client.receive(fin).unwrap();
+    let (tcp_header, _) = extract_tcp_segment(client.rt.pop_frame());
+    assert!(tcp_header.ack && !tcp_header.rst);
+
+    // So the connection outlives its original 2 MSL.
+    now += Duration::from_secs(15);
+    client.clock.advance_clock(now);
+    client.rt.poll_scheduler();
+    match client.ipv4.tcp.connect(client_fd, listen_addr) {
+        Err(e) => assert_eq!(e.errno, EADDRINUSE),
+        Ok(_) => panic!("connect should have failed"),
+    }
+
+    // Once the timer expires, the endpoints are free again.
+    now += Duration::from_secs(10);
+    client.clock.advance_clock(now);
+    client.rt.poll_scheduler();
+    let _connect_future: ConnectFuture = client.tcp_connect(client_fd, listen_addr);
+    client.rt.poll_scheduler();
+    let (tcp_header, _) = extract_tcp_segment(client.rt.pop_frame());
+    assert!(tcp_header.syn);
+}
+
+//=============================================================================
+
+/// Tests that endpoints in TIME-WAIT can be reused for outgoing connections when enabled, with sequence numbers beyond
+/// those of the previous connection.
+#[test]
+fn test_time_wait_reuse() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let local_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 1234);
+
+    // Setup peers.
+    let mut server: Engine = test_helpers::new_bob2(now);
+    let mut client: Engine = test_helpers::new_alice2_with_tcp_config(now, time_wait_config(true));
+    let listen_fd: QDesc = server.tcp_socket().unwrap();
+    server.tcp_bind(listen_fd, listen_addr).unwrap();
+    server.tcp_listen(listen_fd, 1).unwrap();
+
+    let (server_fd, client_fd, isn): (QDesc, QDesc, SeqNumber) =
+        connect_from(&mut ctx, &mut server, &mut client, listen_fd, local_addr, listen_addr);
+    close_from_client(&mut server, &mut client, server_fd, client_fd);
+
+    // Reconnect right away.  The new connection starts beyond the sequence space of the previous one (i.e. its SYN and
+    // FIN).
+    let (_, _, new_isn): (QDesc, QDesc, SeqNumber) =
+        connect_from(&mut ctx, &mut server, &mut client, listen_fd, local_addr, listen_addr);
+    assert!(new_isn > isn + SeqNumber::from(2));
+}
@@ -18,6 +18,7 @@ use crate::{
                     TcpHeader,
                     TcpSegment,
                 },
+                steering::ConnectionSteering,
                 SeqNumber,
             },
         },
@@ -29,7 +30,10 @@ use crate::{
     runtime::{
         memory::DemiBuffer,
         network::{
-            types::MacAddress,
+            types::{
+                MacAddress,
+                Rss,
+            },
             PacketBuf,
         },
         QDesc,
@@ -38,6 +42,7 @@ use crate::{
 use ::futures::task::noop_waker_ref;
 use ::libc::{
     EBADMSG,
+    ECONNREFUSED,
     ETIMEDOUT,
 };
 use ::std::{
@@ -321,6 +326,350 @@ fn test_refuse_connection_missing_syn() {
 
 //=============================================================================
 
+/// Tests that connection requests are answered with SYN cookies once the backlog is full.
+#[test]
+fn test_syn_cookies() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server = test_helpers::new_bob2(now);
+    let mut client = test_helpers::new_alice2(now);
+
+    // Server: LISTEN state at T(0), with room for a single connection.
+    let mut accept_future: AcceptFuture = connection_setup_closed_listen(&mut server, listen_addr);
+
+    // T(0) -> T(1)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // The first connection request fills up the backlog, and is never completed.
+    let (_, _, bytes): (QDesc, ConnectFuture, DemiBuffer) = connection_setup_listen_syn_sent(&mut client, listen_addr);
+    let bytes: DemiBuffer = connection_setup_listen_syn_rcvd(&mut server, bytes);
+    check_packet_syn_ack(
+        bytes,
+        test_helpers::BOB_MAC,
+        test_helpers::ALICE_MAC,
+        test_helpers::BOB_IPV4,
+        test_helpers::ALICE_IPV4,
+        listen_port,
+    );
+
+    // T(1) -> T(2)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // The second one gets a SYN cookie, which only carries the MSS option.
+    let (_, mut connect_future, bytes): (QDesc, ConnectFuture, DemiBuffer) =
+        connection_setup_listen_syn_sent(&mut client, listen_addr);
+    server.receive(bytes).unwrap();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    let (_, _, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(bytes.clone());
+    assert_eq!(tcp_header.syn, true);
+    assert_eq!(tcp_header.ack, true);
+    assert_eq!(tcp_header.ack_num, SeqNumber::from(1));
+    assert_eq!(tcp_header.num_options, 1);
+
+    // T(2) -> T(3)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // Client: ESTABLISHED at T(3).
+    let bytes: DemiBuffer = connection_setup_syn_sent_established(&mut client, bytes);
+    match Future::poll(Pin::new(&mut connect_future), &mut ctx) {
+        Poll::Ready(Ok(())) => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // T(3) -> T(4)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // An ACK that does not echo the cookie is refused.
+    let (eth2_header, ipv4_header, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) =
+        extract_headers(bytes.clone());
+    let segment: TcpSegment = TcpSegment {
+        ethernet2_hdr: eth2_header,
+        ipv4_hdr: ipv4_header,
+        tcp_hdr: TcpHeader {
+            src_port: tcp_header.src_port,
+            dst_port: tcp_header.dst_port,
+            seq_num: tcp_header.seq_num,
+            ack_num: tcp_header.ack_num + SeqNumber::from(1),
+            ns: tcp_header.ns,
+            cwr: tcp_header.cwr,
+            ece: tcp_header.ece,
+            urg: tcp_header.urg,
+            ack: tcp_header.ack,
+            psh: tcp_header.psh,
+            rst: tcp_header.rst,
+            syn: tcp_header.syn,
+            fin: tcp_header.fin,
+            window_size: tcp_header.window_size,
+            urgent_pointer: tcp_header.urgent_pointer,
+            num_options: tcp_header.num_options,
+            option_list: tcp_header.option_list,
+        },
+        data: None,
+        tx_checksum_offload: false,
+    };
+    match server.receive(serialize_segment(segment)) {
+        Err(error) if error.errno == EBADMSG => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    let (_, _, rst_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(server.rt.pop_frame());
+    assert!(rst_header.rst);
+    assert_eq!(rst_header.seq_num, tcp_header.ack_num + SeqNumber::from(1));
+
+    // Server: ESTABLISHED at T(4), once the cookie comes back.
+    connection_setup_sync_rcvd_established(&mut server, bytes);
+    match Future::poll(Pin::new(&mut accept_future), &mut ctx) {
+        Poll::Ready(Ok(_)) => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+}
+
+//=============================================================================
+
+/// Tests that a connection request to a port that nobody listens on is refused with a RST.
+#[test]
+fn test_refuse_connection_closed_port() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server = test_helpers::new_bob2(now);
+    let mut client = test_helpers::new_alice2(now);
+
+    // T(0) -> T(1)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // Client: SYN_SENT state at T(1).
+    let (_, mut connect_future, bytes): (QDesc, ConnectFuture, DemiBuffer) =
+        connection_setup_listen_syn_sent(&mut client, listen_addr);
+    let (_, _, syn_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(bytes.clone());
+
+    // Server: answers with a RST that acknowledges the SYN, as the SYN has no ACK to take a sequence number from.
+    server.receive(bytes).unwrap();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    let (_, _, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(bytes.clone());
+    assert!(tcp_header.rst);
+    assert!(tcp_header.ack);
+    assert_eq!(tcp_header.ack_num, syn_header.seq_num + SeqNumber::from(1));
+
+    // Client: the connection is refused.
+    client.receive(bytes).unwrap();
+    match Future::poll(Pin::new(&mut connect_future), &mut ctx) {
+        Poll::Ready(Err(error)) if error.errno == ECONNREFUSED => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+}
+
+//=============================================================================
+
+/// Tests that connections are dropped once the accept queue is full.
+#[test]
+fn test_accept_queue_overflow() {
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+
+    // Setup peers.
+    let mut server = test_helpers::new_bob2(now);
+    let mut client = test_helpers::new_alice2(now);
+
+    // Server: LISTEN state at T(0), with room for a single connection that is never accepted.
+    let listen_fd: QDesc = server.tcp_socket().unwrap();
+    server.tcp_bind(listen_fd, listen_addr).unwrap();
+    server.tcp_listen(listen_fd, 1).unwrap();
+
+    // T(0) -> T(1)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // The first connection fills up the accept queue.
+    let (_, _, bytes): (QDesc, ConnectFuture, DemiBuffer) = connection_setup_listen_syn_sent(&mut client, listen_addr);
+    let bytes: DemiBuffer = connection_setup_listen_syn_rcvd(&mut server, bytes);
+    let bytes: DemiBuffer = connection_setup_syn_sent_established(&mut client, bytes);
+    connection_setup_sync_rcvd_established(&mut server, bytes);
+    assert_eq!(server.tcp_backlog_overflows(listen_fd).unwrap(), 0);
+
+    // T(1) -> T(2)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    // The second one is answered with a SYN cookie, but dropped when it completes.
+    let (_, _, bytes): (QDesc, ConnectFuture, DemiBuffer) = connection_setup_listen_syn_sent(&mut client, listen_addr);
+    server.receive(bytes).unwrap();
+    let bytes: DemiBuffer = server.rt.pop_frame();
+    let bytes: DemiBuffer = connection_setup_syn_sent_established(&mut client, bytes);
+    match server.receive(bytes) {
+        Err(error) if error.errno == ECONNREFUSED => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(server.tcp_backlog_overflows(listen_fd).unwrap(), 1);
+}
+
+//=============================================================================
+
+/// Tests that instances that share the same address steer connections among them by 4-tuple hash.
+#[test]
+fn test_connection_steering() {
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let nshards: usize = 2;
+    let nconnections: usize = 32;
+
+    // Setup peers: one server instance per shard, all of them listening on the same port.
+    let mut servers: Vec<Engine> = (0..nshards)
+        .map(|index| {
+            test_helpers::new_bob2_with_tcp_config(now, test_helpers::sharded_tcp_config(index, nshards, None))
+        })
+        .collect();
+    for server in servers.iter_mut() {
+        let listen_fd: QDesc = server.tcp_socket().unwrap();
+        server.tcp_bind(listen_fd, listen_addr).unwrap();
+        server.tcp_listen(listen_fd, nconnections).unwrap();
+    }
+    let mut client = test_helpers::new_alice2(now);
+
+    // T(0) -> T(1)
+    advance_clock(None, Some(&mut client), &mut now);
+
+    // Every connection request is answered by exactly one server, and all servers get their share.
+    let mut accepted: Vec<usize> = vec![0; nshards];
+    for _ in 0..nconnections {
+        let (_, _, bytes): (QDesc, ConnectFuture, DemiBuffer) =
+            connection_setup_listen_syn_sent(&mut client, listen_addr);
+        let mut owners: Vec<usize> = Vec::new();
+        for (index, server) in servers.iter_mut().enumerate() {
+            server.receive(bytes.clone()).unwrap();
+            server.rt.poll_scheduler();
+            if let Some(reply) = server.rt.pop_frame_unchecked() {
+                let (_, _, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(reply);
+                assert!(tcp_header.syn && tcp_header.ack);
+                owners.push(index);
+            }
+        }
+        assert_eq!(owners.len(), 1);
+        accepted[owners[0]] += 1;
+    }
+    assert!(accepted.iter().all(|n| *n > 0));
+
+    // A sharded client picks local ports that steer the segments of its peer back to it.
+    let mut client = test_helpers::new_alice2_with_tcp_config(now, test_helpers::sharded_tcp_config(1, 4, None));
+    let steering: ConnectionSteering = ConnectionSteering::new(1, 4, None);
+    for _ in 0..nconnections {
+        let (_, _, bytes): (QDesc, ConnectFuture, DemiBuffer) =
+            connection_setup_listen_syn_sent(&mut client, listen_addr);
+        let (_, ipv4_header, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(bytes);
+        let local: SocketAddrV4 = SocketAddrV4::new(ipv4_header.get_src_addr(), tcp_header.src_port);
+        assert!(steering.owns(&local, &listen_addr));
+    }
+}
+
+/// Tests that instances whose shards are the receive queues of the port handle every segment that they receive, and
+/// pick local ports that make the port steer the segments of their peer back to them.
+#[test]
+fn test_rss_connection_steering() {
+    let mut now = Instant::now();
+
+    // Connection parameters
+    let listen_port: u16 = 80;
+    let listen_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, listen_port);
+    let nqueues: usize = 4;
+    let nconnections: usize = 32;
+    let rss: Rss = Rss::new(128, nqueues);
+
+    // Setup peers.
+    let mut server: Engine =
+        test_helpers::new_bob2_with_tcp_config(now, test_helpers::sharded_tcp_config(0, nqueues, Some(rss)));
+    let listen_fd: QDesc = server.tcp_socket().unwrap();
+    server.tcp_bind(listen_fd, listen_addr).unwrap();
+    server.tcp_listen(listen_fd, nconnections).unwrap();
+    let mut client: Engine =
+        test_helpers::new_alice2_with_tcp_config(now, test_helpers::sharded_tcp_config(1, nqueues, Some(rss)));
+
+    // T(0) -> T(1)
+    advance_clock(Some(&mut server), Some(&mut client), &mut now);
+
+    for _ in 0..nconnections {
+        let (_, _, bytes): (QDesc, ConnectFuture, DemiBuffer) =
+            connection_setup_listen_syn_sent(&mut client, listen_addr);
+
+        // The segments of the server are steered to the receive queue of the client.
+        let (_, ipv4_header, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(bytes.clone());
+        let local: SocketAddrV4 = SocketAddrV4::new(ipv4_header.get_src_addr(), tcp_header.src_port);
+        assert_eq!(rss.queue(&listen_addr, &local), 1);
+
+        // The server answers the connection request, even though it does not hash to its queue.
+        server.receive(bytes).unwrap();
+        server.rt.poll_scheduler();
+        let (_, _, tcp_header): (Ethernet2Header, Ipv4Header, TcpHeader) = extract_headers(server.rt.pop_frame());
+        assert!(tcp_header.syn && tcp_header.ack);
+    }
+}
+
+/// Tests that connections to our own addresses, including loopback ones, are looped back to a listener on the
+/// unspecified address without reaching the wire.
+#[test]
+fn test_loopback_connect() {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let now = Instant::now();
+
+    // Setup a single peer, which listens on every address.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let listen_port: u16 = 80;
+    let listen_fd: QDesc = alice.tcp_socket().unwrap();
+    alice
+        .tcp_bind(listen_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, listen_port))
+        .unwrap();
+    alice.tcp_listen(listen_fd, 2).unwrap();
+
+    for ipv4_addr in [Ipv4Addr::LOCALHOST, test_helpers::ALICE_IPV4] {
+        let listen_addr: SocketAddrV4 = SocketAddrV4::new(ipv4_addr, listen_port);
+        let client_fd: QDesc = alice.tcp_socket().unwrap();
+        let mut connect_future: ConnectFuture = alice.tcp_connect(client_fd, listen_addr);
+        let mut accept_future: AcceptFuture = alice.tcp_accept(listen_fd);
+
+        // SYN, SYN+ACK and ACK never reach the wire.
+        for _ in 0..3 {
+            alice.rt.poll_scheduler();
+            alice.poll_loopback();
+        }
+        assert!(alice.rt.pop_frame_unchecked().is_none());
+        let server_fd: QDesc = match Future::poll(Pin::new(&mut accept_future), &mut ctx) {
+            Poll::Ready(Ok(server_fd)) => server_fd,
+            _ => panic!("accept should have completed"),
+        };
+        match Future::poll(Pin::new(&mut connect_future), &mut ctx) {
+            Poll::Ready(Ok(())) => (),
+            _ => panic!("connect should have completed"),
+        }
+
+        // The accepted connection is on the address that the client connected to.
+        assert_eq!(alice.ipv4.tcp.local_addr(server_fd).unwrap(), listen_addr);
+        assert_eq!(
+            alice.ipv4.tcp.remote_addr(server_fd).unwrap(),
+            alice.ipv4.tcp.local_addr(client_fd).unwrap()
+        );
+    }
+}
+
+//=============================================================================
+
 /// Extracts headers of a TCP packet.
 fn extract_headers(bytes: DemiBuffer) -> (Ethernet2Header, Ipv4Header, TcpHeader) {
     let (eth2_header, eth2_payload) = Ethernet2Header::parse(bytes).unwrap();
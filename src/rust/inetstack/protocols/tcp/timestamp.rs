@@ -0,0 +1,32 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file defines helpers for the TCP Timestamps option.
+//
+// RFC 7323 defines the Timestamps option.  Each end stamps the segments it sends with the current value of its
+// timestamp clock, and echoes the most recent timestamp it received from its peer.  Echoed timestamps yield RTT
+// samples for every acknowledgement (RTTM), and allow receivers to discard old duplicate segments whose sequence
+// numbers have wrapped around (PAWS).  Like sequence numbers, timestamps are compared modulo 2^32.
+//
+
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+/// Space taken by the Timestamps option in a TCP header (in bytes), including padding.
+pub const TIMESTAMP_OPTION_SPACE: usize = 12;
+
+/// Time after which the most recent timestamp received from a peer is too old to be used by PAWS (see RFC 7323
+/// Section 5.5).  This is shorter than the time it takes for a timestamp clock ticking once per millisecond to wrap.
+pub const PAWS_IDLE_TIMEOUT: Duration = Duration::from_secs(24 * 24 * 60 * 60);
+
+/// Returns the value of a timestamp clock that started at `base`.  Our timestamp clock ticks once per millisecond.
+pub fn timestamp_value(base: Instant, now: Instant) -> u32 {
+    (now - base).as_millis() as u32
+}
+
+/// Checks whether timestamp `a` is earlier than timestamp `b`.
+pub fn timestamp_before(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
@@ -14,7 +14,10 @@ use crate::{
     },
     runtime::{
         memory::DemiBuffer,
-        network::PacketBuf,
+        network::{
+            types::MacAddress,
+            PacketBuf,
+        },
     },
 };
 
@@ -115,6 +118,11 @@ impl PacketBuf for UdpDatagram {
     fn take_body(&self) -> Option<DemiBuffer> {
         Some(self.data.clone())
     }
+
+    /// Returns the destination link address of the target UDP datagram.
+    fn dst_link_addr(&self) -> MacAddress {
+        self.ethernet2_hdr.dst_addr()
+    }
 }
 
 //==============================================================================
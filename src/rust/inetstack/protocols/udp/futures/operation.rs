@@ -12,6 +12,7 @@ use crate::{
     },
     runtime::{
         fail::Fail,
+        types::DEMI_QR_FLAG_TRUNC,
         QDesc,
     },
     scheduler::FutureResult,
@@ -31,6 +32,10 @@ use ::std::{
 
 /// UDP Operation Descriptor
 pub enum UdpOperation {
+    /// Close operation.
+    Close(QDesc, Result<(), Fail>),
+    /// Connect operation.
+    Connect(QDesc, Result<(), Fail>),
     /// Pushto operation.
     Pushto(QDesc, Result<(), Fail>),
     /// Pop operation.
@@ -43,8 +48,34 @@ pub enum UdpOperation {
 
 /// Associate Functions for UDP Operation Descriptors
 impl UdpOperation {
+    /// Gets the kind of the target [UdpOperation], for introspection.
+    pub fn get_kind(&self) -> &'static str {
+        match self {
+            UdpOperation::Close(..) => "Close",
+            UdpOperation::Connect(..) => "Connect",
+            UdpOperation::Pushto(..) => "Pushto",
+            UdpOperation::Pop(_) => "Pop",
+        }
+    }
+
+    /// Gets the queue descriptor that owns the target [UdpOperation].
+    pub fn get_qd(&self) -> QDesc {
+        match self {
+            UdpOperation::Close(qd, _) | UdpOperation::Connect(qd, _) | UdpOperation::Pushto(qd, _) => *qd,
+            UdpOperation::Pop(f) => f.future.get_qd(),
+        }
+    }
+
     pub fn get_result(self) -> (QDesc, OperationResult) {
         match self {
+            // Close operation.
+            UdpOperation::Close(fd, Ok(())) => (fd, OperationResult::Close),
+            UdpOperation::Close(fd, Err(e)) => (fd, OperationResult::Failed(e)),
+
+            // Connect operation.
+            UdpOperation::Connect(fd, Ok(())) => (fd, OperationResult::Connect),
+            UdpOperation::Connect(fd, Err(e)) => (fd, OperationResult::Failed(e)),
+
             // Pushto operation.
             UdpOperation::Pushto(fd, Ok(())) => (fd, OperationResult::Push),
             UdpOperation::Pushto(fd, Err(e)) => (fd, OperationResult::Failed(e)),
@@ -52,8 +83,14 @@ impl UdpOperation {
             // Pop operation.
             UdpOperation::Pop(FutureResult {
                 future,
-                done: Some(Ok((addr, bytes))),
-            }) => (future.get_qd(), OperationResult::Pop(Some(addr), bytes)),
+                done: Some(Ok((addr, bytes, truncated, info))),
+            }) => {
+                let flags: u32 = if truncated { DEMI_QR_FLAG_TRUNC } else { 0 };
+                (
+                    future.get_qd(),
+                    OperationResult::Pop(Some(addr.into()), bytes, flags, info),
+                )
+            },
             UdpOperation::Pop(FutureResult {
                 future,
                 done: Some(Err(e)),
@@ -76,7 +113,7 @@ impl Future for UdpOperation {
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<()> {
         match self.get_mut() {
             UdpOperation::Pop(ref mut f) => Future::poll(Pin::new(f), ctx),
-            UdpOperation::Pushto(..) => Poll::Ready(()),
+            UdpOperation::Close(..) | UdpOperation::Connect(..) | UdpOperation::Pushto(..) => Poll::Ready(()),
         }
     }
 }
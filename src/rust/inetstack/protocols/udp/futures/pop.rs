@@ -6,20 +6,29 @@
 //==============================================================================
 
 use crate::{
-    inetstack::protocols::udp::queue::{
-        SharedQueue,
-        SharedQueueSlot,
+    inetstack::{
+        futures::Deadline,
+        protocols::udp::queue::{
+            SharedQueue,
+            SharedQueueSlot,
+        },
     },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
+        network::types::{
+            PacketInfo,
+            Timestamps,
+        },
         QDesc,
     },
 };
 use ::std::{
+    cell::Cell,
     future::Future,
     net::SocketAddrV4,
     pin::Pin,
+    rc::Rc,
     task::{
         Context,
         Poll,
@@ -37,6 +46,18 @@ pub struct UdpPopFuture {
     qd: QDesc,
     /// Shared receiving queue.
     recv_queue: SharedQueue<SharedQueueSlot<DemiBuffer>>,
+    /// Maximum number of bytes to pop.
+    size: Option<usize>,
+    /// Number of datagrams truncated on the associated socket.
+    truncations: Rc<Cell<u64>>,
+    /// Hardware timestamps of the associated socket.
+    timestamps: Rc<Cell<Timestamps>>,
+    /// Pending error on the associated socket.
+    error: Rc<Cell<Option<i32>>>,
+    /// Report packet information?
+    pktinfo: bool,
+    /// Deadline for this operation.
+    deadline: Deadline,
 }
 
 //==============================================================================
@@ -45,9 +66,33 @@ pub struct UdpPopFuture {
 
 /// Associate Functions for Pop Operation Descriptor
 impl UdpPopFuture {
-    /// Creates a pop operation descritor.
-    pub fn new(qd: QDesc, recv_queue: SharedQueue<SharedQueueSlot<DemiBuffer>>) -> Self {
-        Self { qd, recv_queue }
+    /// Creates a pop operation descritor. Datagrams that get truncated are accounted in `truncations`, the hardware
+    /// timestamp of the popped datagram is recorded in `timestamps`, packet information is reported only if `pktinfo`
+    /// is set, and the operation fails as soon as an error is set in `error`.
+    pub fn new(
+        qd: QDesc,
+        recv_queue: SharedQueue<SharedQueueSlot<DemiBuffer>>,
+        size: Option<usize>,
+        truncations: Rc<Cell<u64>>,
+        timestamps: Rc<Cell<Timestamps>>,
+        error: Rc<Cell<Option<i32>>>,
+        pktinfo: bool,
+    ) -> Self {
+        Self {
+            qd,
+            recv_queue,
+            size,
+            truncations,
+            timestamps,
+            error,
+            pktinfo,
+            deadline: Deadline::default(),
+        }
+    }
+
+    /// Sets a deadline for the target pop operation descriptor.
+    pub fn with_deadline(self, deadline: Deadline) -> Self {
+        Self { deadline, ..self }
     }
 
     /// Returns the queue descriptor that is associated to the target pop operation descriptor.
@@ -62,13 +107,40 @@ impl UdpPopFuture {
 
 /// Future Trait implementation for Pop Operation Descriptor
 impl Future for UdpPopFuture {
-    type Output = Result<(SocketAddrV4, DemiBuffer), Fail>;
+    type Output = Result<(SocketAddrV4, DemiBuffer, bool, Option<PacketInfo>), Fail>;
 
     /// Polls the target pop operation descriptor.
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
-        match self.get_mut().recv_queue.try_pop() {
-            Ok(Some(msg)) => Poll::Ready(Ok((msg.remote, msg.data))),
+        let self_: &mut UdpPopFuture = self.get_mut();
+        // Errors reported by the network take precedence over queued datagrams, as they do on Linux.
+        if let Some(errno) = self_.error.take() {
+            return Poll::Ready(Err(Fail::new(errno, "destination unreachable")));
+        }
+        match self_.recv_queue.try_pop() {
+            Ok(Some(mut msg)) => {
+                // Truncate the datagram if it does not fit in the requested size.
+                let mut truncated: bool = false;
+                if let Some(size) = self_.size {
+                    if msg.data.len() > size {
+                        let nbytes: usize = msg.data.len() - size;
+                        msg.data.trim(nbytes)?;
+                        self_.truncations.set(self_.truncations.get() + 1);
+                        truncated = true;
+                    }
+                }
+                if let Some(rx) = msg.info.and_then(|info| info.get_hw_timestamp()) {
+                    self_.timestamps.set(Timestamps {
+                        rx: Some(rx),
+                        ..self_.timestamps.get()
+                    });
+                }
+                let info: Option<PacketInfo> = if self_.pktinfo { msg.info } else { None };
+                Poll::Ready(Ok((msg.remote, msg.data, truncated, info)))
+            },
             Ok(None) => {
+                if let Poll::Ready(e) = self_.deadline.poll_expired(ctx) {
+                    return Poll::Ready(Err(e));
+                }
                 let waker: &Waker = ctx.waker();
                 waker.wake_by_ref();
                 Poll::Pending
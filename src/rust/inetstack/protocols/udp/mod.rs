@@ -20,7 +20,11 @@ mod tests;
 //==============================================================================
 
 pub use self::{
-    datagram::UdpHeader,
+    datagram::{
+        UdpDatagram,
+        UdpHeader,
+        UDP_HEADER_SIZE,
+    },
     futures::{
         UdpOperation,
         UdpPopFuture,
@@ -9,6 +9,7 @@ use super::{
     datagram::{
         UdpDatagram,
         UdpHeader,
+        UDP_HEADER_SIZE,
     },
     futures::UdpPopFuture,
     queue::{
@@ -17,52 +18,68 @@ use super::{
     },
 };
 use crate::{
-    inetstack::{
-        futures::FutureOperation,
-        protocols::{
-            arp::ArpPeer,
-            ethernet2::{
-                EtherType2,
-                Ethernet2Header,
-            },
-            ip::{
-                EphemeralPorts,
-                IpProtocol,
-            },
-            ipv4::Ipv4Header,
+    inetstack::protocols::{
+        arp::ArpPeer,
+        ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+        },
+        icmpv4::Icmpv4Error,
+        igmp::IgmpPeer,
+        ip::{
+            EphemeralPorts,
+            IpProtocol,
+        },
+        ipv4::{
+            Ipv4Fragmenter,
+            Ipv4Header,
+            DEFAULT_IPV4_TTL,
+            IPV4_HEADER_DEFAULT_SIZE,
         },
+        tcp::ConnectionSteering,
     },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
         network::{
-            types::MacAddress,
+            types::{
+                check_datagram_option,
+                MacAddress,
+                PacketInfo,
+                SocketOption,
+                SocketOptions,
+                Timestamps,
+            },
             NetworkRuntime,
         },
         QDesc,
     },
-    scheduler::{
-        Scheduler,
-        SchedulerHandle,
-    },
 };
-use ::futures::FutureExt;
 use ::libc::{
-    EAGAIN,
+    EACCES,
+    EADDRINUSE,
+    EADDRNOTAVAIL,
     EBADF,
     EEXIST,
+    EINVAL,
+    EMSGSIZE,
 };
 use ::rand::{
     prelude::SmallRng,
     SeedableRng,
 };
 use ::std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::{
+        HashMap,
+        HashSet,
+    },
     net::{
         Ipv4Addr,
         SocketAddrV4,
     },
     rc::Rc,
+    time::SystemTime,
 };
 
 #[cfg(feature = "profiler")]
@@ -75,8 +92,11 @@ use crate::timer;
 // Maximum size for receive queues (in messages).
 const RECV_QUEUE_MAX_SIZE: usize = 1024;
 
-// Maximum size for send queues (in messages).
-const SEND_QUEUE_MAX_SIZE: usize = 1024;
+// Default size for send and receive buffers (in bytes).
+const DEFAULT_BUFFER_SIZE: usize = 212992;
+
+// Maximum size for the payload of a datagram (in bytes).
+const UDP_MAX_PAYLOAD_SIZE: usize = u16::MAX as usize - IPV4_HEADER_DEFAULT_SIZE - UDP_HEADER_SIZE;
 
 //======================================================================================================================
 // Structures
@@ -88,25 +108,36 @@ pub struct UdpPeer {
     rt: Rc<dyn NetworkRuntime>,
     /// Underlying ARP peer.
     arp: ArpPeer,
+    /// Fragmenter for datagrams that do not fit in the MTU.
+    fragmenter: Ipv4Fragmenter,
+    /// Underlying IGMP peer.
+    igmp: IgmpPeer,
     /// Ephemeral ports.
     ephemeral_ports: EphemeralPorts,
+    /// Steering of flows among instances that share the local address.
+    steering: ConnectionSteering,
     /// Opened sockets.
     sockets: HashMap<QDesc, Option<SocketAddrV4>>,
+    /// Socket options.
+    options: HashMap<QDesc, SocketOptions>,
+    /// Number of truncated datagrams on each socket.
+    truncations: HashMap<QDesc, Rc<Cell<u64>>>,
+    /// Hardware timestamps of the last datagram popped from and sent on each socket.
+    timestamps: HashMap<QDesc, Rc<Cell<Timestamps>>>,
+    /// Errors reported by ICMP on each socket, until they are returned by the next push or pop.
+    errors: HashMap<QDesc, Rc<Cell<Option<i32>>>>,
     /// Bound sockets.
     bound: HashMap<SocketAddrV4, SharedQueue<SharedQueueSlot<DemiBuffer>>>,
-    /// Queue of unset datagrams. This is shared across fast/slow paths.
-    send_queue: SharedQueue<SharedQueueSlot<DemiBuffer>>,
+    /// Remote endpoints of connected sockets, keyed by local endpoint.
+    connected: HashMap<SocketAddrV4, SocketAddrV4>,
+    /// Multicast groups joined by each socket.
+    memberships: HashMap<QDesc, HashSet<Ipv4Addr>>,
+    /// Arrival time of the datagrams in the batch of frames being received, which is read once for the whole batch.
+    batch_time: Option<SystemTime>,
     /// Local link address.
     local_link_addr: MacAddress,
-    /// Local IPv4 address.
-    local_ipv4_addr: Ipv4Addr,
     /// Offload checksum to hardware?
     checksum_offload: bool,
-
-    /// The background co-routine sends unset UDP packets.
-    /// We annotate it as unused because the compiler believes that it is never called which is not the case.
-    #[allow(unused)]
-    background: SchedulerHandle,
 }
 
 //======================================================================================================================
@@ -118,84 +149,38 @@ impl UdpPeer {
     /// Creates a Udp peer.
     pub fn new(
         rt: Rc<dyn NetworkRuntime>,
-        scheduler: Scheduler,
         rng_seed: [u8; 32],
         local_link_addr: MacAddress,
-        local_ipv4_addr: Ipv4Addr,
         offload_checksum: bool,
+        mtu: usize,
         arp: ArpPeer,
+        igmp: IgmpPeer,
+        steering: ConnectionSteering,
     ) -> Result<Self, Fail> {
-        let send_queue: SharedQueue<SharedQueueSlot<DemiBuffer>> =
-            SharedQueue::<SharedQueueSlot<DemiBuffer>>::new(SEND_QUEUE_MAX_SIZE);
-        let future = Self::background_sender(
-            rt.clone(),
-            local_ipv4_addr,
-            local_link_addr,
-            offload_checksum,
-            arp.clone(),
-            send_queue.clone(),
-        );
-        let handle: SchedulerHandle = match scheduler.insert(FutureOperation::Background(future.boxed_local())) {
-            Some(handle) => handle,
-            None => {
-                return Err(Fail::new(
-                    EAGAIN,
-                    "failed to schedule background co-routine for UDP module",
-                ))
-            },
-        };
+        let fragmenter: Ipv4Fragmenter = Ipv4Fragmenter::new(mtu);
         let mut rng: SmallRng = SmallRng::from_seed(rng_seed);
         let ephemeral_ports: EphemeralPorts = EphemeralPorts::new(&mut rng);
         Ok(Self {
             rt: rt.clone(),
             arp,
+            fragmenter,
+            igmp,
             ephemeral_ports,
+            steering,
             sockets: HashMap::new(),
+            options: HashMap::new(),
+            truncations: HashMap::new(),
+            timestamps: HashMap::new(),
+            errors: HashMap::new(),
             bound: HashMap::new(),
-            send_queue,
+            connected: HashMap::new(),
+            memberships: HashMap::new(),
+            batch_time: None,
             local_link_addr,
-            local_ipv4_addr,
             checksum_offload: offload_checksum,
-            background: handle,
         })
     }
 
-    /// Asynchronously send unsent datagrams to remote peer.
-    async fn background_sender(
-        rt: Rc<dyn NetworkRuntime>,
-        local_ipv4_addr: Ipv4Addr,
-        local_link_addr: MacAddress,
-        offload_checksum: bool,
-        arp: ArpPeer,
-        mut rx: SharedQueue<SharedQueueSlot<DemiBuffer>>,
-    ) {
-        loop {
-            // Grab next unsent datagram.
-            match rx.pop().await {
-                // Resolve remote address.
-                Ok(SharedQueueSlot { local, remote, data }) => match arp.query(remote.ip().clone()).await {
-                    // Send datagram.
-                    Ok(link_addr) => {
-                        Self::do_send(
-                            rt.clone(),
-                            local_ipv4_addr,
-                            local_link_addr,
-                            link_addr,
-                            data,
-                            &local,
-                            &remote,
-                            offload_checksum,
-                        );
-                    },
-                    // ARP query failed.
-                    Err(e) => warn!("Failed to send UDP datagram: {:?}", e),
-                },
-                // Pop from shared queue failed.
-                Err(e) => warn!("Failed to send UDP datagram: {:?}", e),
-            }
-        }
-    }
-
     /// Opens a UDP socket.
     pub fn do_socket(&mut self, qd: QDesc) -> Result<(), Fail> {
         #[cfg(feature = "profiler")]
@@ -207,6 +192,13 @@ impl UdpPeer {
             false => {
                 let socket: Option<SocketAddrV4> = None;
                 self.sockets.insert(qd, socket);
+                self.options.insert(
+                    qd,
+                    SocketOptions::new(DEFAULT_BUFFER_SIZE, DEFAULT_BUFFER_SIZE, DEFAULT_IPV4_TTL),
+                );
+                self.truncations.insert(qd, Rc::new(Cell::new(0)));
+                self.timestamps.insert(qd, Rc::new(Cell::new(Timestamps::default())));
+                self.errors.insert(qd, Rc::new(Cell::new(None)));
                 Ok(())
             },
             // Queue descriptor in use.
@@ -272,6 +264,15 @@ impl UdpPeer {
         timer!("udp::close");
 
         // Lookup associated endpoint.
+        self.options.remove(&qd);
+        self.truncations.remove(&qd);
+        self.timestamps.remove(&qd);
+        self.errors.remove(&qd);
+        if let Some(groups) = self.memberships.remove(&qd) {
+            for group in groups {
+                self.igmp.leave(group)?;
+            }
+        }
         let socket: Option<SocketAddrV4> = match self.sockets.remove(&qd) {
             Some(s) => s,
             None => return Err(Fail::new(EBADF, "invalid queue descriptor")),
@@ -279,11 +280,186 @@ impl UdpPeer {
 
         // Remove endpoint binding.
         match socket {
-            Some(local) if self.bound.remove(&local).is_some() => Ok(()),
+            Some(local) if self.bound.remove(&local).is_some() => {
+                self.connected.remove(&local);
+                Ok(())
+            },
             _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
         }
     }
 
+    /// Connects a UDP socket to a remote endpoint address. Datagrams pushed without an address are sent to it, and only
+    /// datagrams that come from it are received. Unbound sockets are first bound to an ephemeral port, which is picked
+    /// so that the datagrams of the remote endpoint are steered to this instance.
+    pub fn do_connect(&mut self, qd: QDesc, remote: SocketAddrV4) -> Result<(), Fail> {
+        #[cfg(feature = "profiler")]
+        timer!("udp::connect");
+
+        // Check if remote endpoint address is valid.
+        if remote.ip().is_unspecified() || remote.port() == 0 {
+            return Err(Fail::new(libc::EINVAL, "invalid remote address"));
+        }
+
+        // Lookup associated endpoint, binding the socket if needed.
+        let local: SocketAddrV4 = match self.sockets.get(&qd) {
+            Some(Some(local)) => *local,
+            Some(None) => {
+                let local_ipv4_addr: Ipv4Addr = self.arp.select_source(*remote.ip());
+                let steering: ConnectionSteering = self.steering;
+                let local_port: u16 = self
+                    .ephemeral_ports
+                    .alloc_matching(|port| steering.owns(&SocketAddrV4::new(local_ipv4_addr, port), &remote))?;
+                let local: SocketAddrV4 = SocketAddrV4::new(local_ipv4_addr, local_port);
+                // The port is already taken from the pool, thus binding to it reserves it again.
+                self.ephemeral_ports.free(local_port);
+                self.do_bind(qd, local)?;
+                local
+            },
+            None => return Err(Fail::new(EBADF, "invalid queue descriptor")),
+        };
+
+        // Connecting again replaces the previous remote endpoint.
+        self.connected.insert(local, remote);
+        Ok(())
+    }
+
+    /// Checks whether a socket is bound to `ipv4_addr`.
+    pub fn is_addr_in_use(&self, ipv4_addr: Ipv4Addr) -> bool {
+        self.sockets
+            .values()
+            .any(|local| local.map_or(false, |local| *local.ip() == ipv4_addr))
+    }
+
+    /// Gets the local endpoint address of a UDP socket.
+    pub fn local_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        match self.sockets.get(&qd) {
+            Some(Some(local)) => Ok(*local),
+            // Unbound sockets report the wildcard address, as POSIX getsockname() does.
+            Some(None) => Ok(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the remote endpoint address of a UDP socket.
+    pub fn remote_addr(&self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+        match self.sockets.get(&qd) {
+            Some(Some(local)) => match self.connected.get(local) {
+                Some(remote) => Ok(*remote),
+                None => Err(Fail::new(libc::ENOTCONN, "socket is not connected")),
+            },
+            Some(None) => Err(Fail::new(libc::ENOTCONN, "socket is not connected")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Shuts down a UDP socket.
+    pub fn shutdown(&self, qd: QDesc) -> Result<(), Fail> {
+        match self.sockets.get(&qd) {
+            // UDP sockets are not connection-oriented.
+            Some(_) => Err(Fail::new(libc::ENOTCONN, "socket is not connected")),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the current value of a socket option.
+    pub fn get_socket_option(&self, qd: QDesc, opt: SocketOption) -> Result<SocketOption, Fail> {
+        check_datagram_option(&opt)?;
+        match self.options.get(&qd) {
+            Some(options) => Ok(options.get(opt)),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Sets a socket option.
+    pub fn set_socket_option(&mut self, qd: QDesc, opt: SocketOption) -> Result<(), Fail> {
+        check_datagram_option(&opt)?;
+        match self.options.get_mut(&qd) {
+            Some(options) => options.set(opt),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the number of datagrams that were truncated when popped from a UDP socket.
+    pub fn get_truncations(&self, qd: QDesc) -> Result<u64, Fail> {
+        match self.truncations.get(&qd) {
+            Some(truncations) => Ok(truncations.get()),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Gets the hardware timestamps of the last datagram that was popped from and sent on a socket.
+    pub fn get_timestamps(&self, qd: QDesc) -> Result<Timestamps, Fail> {
+        match self.timestamps.get(&qd) {
+            Some(timestamps) => Ok(timestamps.get()),
+            None => Err(Fail::new(EBADF, "invalid queue descriptor")),
+        }
+    }
+
+    /// Joins a UDP socket to a multicast group on the interface that has the address `iface`. The unspecified address
+    /// selects the default interface.
+    pub fn join_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        if !self.sockets.contains_key(&qd) {
+            return Err(Fail::new(EBADF, "invalid queue descriptor"));
+        }
+        if !group.is_multicast() {
+            return Err(Fail::new(EINVAL, "not a multicast address"));
+        }
+        if !iface.is_unspecified() && !self.arp.is_local_addr(iface) {
+            return Err(Fail::new(EADDRNOTAVAIL, "invalid interface address"));
+        }
+        if !self.memberships.entry(qd).or_default().insert(group) {
+            return Err(Fail::new(EADDRINUSE, "already a member of this group"));
+        }
+        self.igmp.join(group)
+    }
+
+    /// Removes a UDP socket from a multicast group on the interface that has the address `iface`.
+    pub fn leave_multicast_group(&mut self, qd: QDesc, group: Ipv4Addr, iface: Ipv4Addr) -> Result<(), Fail> {
+        if !self.sockets.contains_key(&qd) {
+            return Err(Fail::new(EBADF, "invalid queue descriptor"));
+        }
+        if !iface.is_unspecified() && !self.arp.is_local_addr(iface) {
+            return Err(Fail::new(EADDRNOTAVAIL, "invalid interface address"));
+        }
+        match self.memberships.get_mut(&qd) {
+            Some(groups) if groups.remove(&group) => self.igmp.leave(group),
+            _ => Err(Fail::new(EADDRNOTAVAIL, "not a member of this group")),
+        }
+    }
+
+    /// Handles an ICMP error about a datagram that we sent. As on Linux, the error is only reported on sockets that are
+    /// connected to the remote endpoint of the datagram, since others could not tell which datagram it was about.
+    pub fn receive_icmp_error(&self, error: &Icmpv4Error) {
+        // Datagrams are fragmented against the MTU of the local link, so the path MTU does not matter to them.
+        if error.path_mtu.is_some() {
+            debug!("ignoring path MTU report for {:?}", error.remote);
+            return;
+        }
+        for (qd, local) in self.sockets.iter() {
+            let local: &SocketAddrV4 = match local {
+                Some(local) if *local == error.local => local,
+                Some(local) if local.ip().is_unspecified() && local.port() == error.local.port() => local,
+                _ => continue,
+            };
+            if self.connected.get(local) == Some(&error.remote) {
+                debug!("reporting {:?} on socket {:?}", error, qd);
+                self.errors[qd].set(Some(error.errno));
+            }
+        }
+    }
+
+    /// Pushes data to the remote UDP peer that a socket is connected to.
+    pub fn do_push(&self, qd: QDesc, data: DemiBuffer) -> Result<(), Fail> {
+        let remote: SocketAddrV4 = match self.remote_addr(qd) {
+            Ok(remote) => remote,
+            Err(e) if e.errno == libc::ENOTCONN => {
+                return Err(Fail::new(libc::EDESTADDRREQ, "destination address required"))
+            },
+            Err(e) => return Err(e),
+        };
+        self.do_pushto(qd, data, remote)
+    }
+
     /// Pushes data to a remote UDP peer.
     pub fn do_pushto(&self, qd: QDesc, data: DemiBuffer, remote: SocketAddrV4) -> Result<(), Fail> {
         #[cfg(feature = "profiler")]
@@ -295,29 +471,84 @@ impl UdpPeer {
             _ => return Err(Fail::new(EBADF, "invalid queue descriptor")),
         };
 
+        // Report errors that the network sent about previous datagrams.
+        if let Some(errno) = self.errors[&qd].take() {
+            return Err(Fail::new(errno, "destination unreachable"));
+        }
+
+        // The datagram should fit in a single IPv4 datagram, even if the latter gets fragmented.
+        if data.len() > UDP_MAX_PAYLOAD_SIZE {
+            return Err(Fail::new(EMSGSIZE, "datagram too large"));
+        }
+
+        // Sending to the broadcast address must be explicitly permitted.
+        if remote.ip().is_broadcast() && !self.options[&qd].get_broadcast() {
+            return Err(Fail::new(EACCES, "broadcast not permitted on this socket"));
+        }
+
+        // Broadcast and multicast addresses map to link addresses directly, so they do not need to be resolved.
+        let link_addr: Option<MacAddress> = if remote.ip().is_broadcast() {
+            Some(MacAddress::broadcast())
+        } else if remote.ip().is_multicast() {
+            Some(MacAddress::from_ipv4_multicast(*remote.ip()))
+        } else {
+            self.arp.try_query(remote.ip().clone())
+        };
+
+        // Sockets that are bound to the wildcard address send from the address that suits the remote peer.
+        let local_ipv4_addr: Ipv4Addr = match local.ip().is_unspecified() {
+            true => self.arp.select_source(*remote.ip()),
+            false => *local.ip(),
+        };
+
         // Fast path: try to send the datagram immediately.
-        if let Some(link_addr) = self.arp.try_query(remote.ip().clone()) {
+        let timestamps: Rc<Cell<Timestamps>> = self.timestamps[&qd].clone();
+        if let Some(link_addr) = link_addr {
             Self::do_send(
                 self.rt.clone(),
-                self.local_ipv4_addr,
+                &self.fragmenter,
+                &timestamps,
+                local_ipv4_addr,
                 self.local_link_addr,
                 link_addr,
                 data,
                 &local,
                 &remote,
                 self.checksum_offload,
-            );
+            )?;
         }
-        // Slow path: Defer send operation to the async path.
+        // Slow path: send the datagram once the link address of the remote peer is resolved.
         else {
-            self.send_queue.push(SharedQueueSlot { local, remote, data })?
+            let rt: Rc<dyn NetworkRuntime> = self.rt.clone();
+            let fragmenter: Ipv4Fragmenter = self.fragmenter.clone();
+            let local_link_addr: MacAddress = self.local_link_addr;
+            let checksum_offload: bool = self.checksum_offload;
+            self.arp.send_when_resolved(
+                *remote.ip(),
+                Box::new(move |link_addr: MacAddress| {
+                    if let Err(e) = Self::do_send(
+                        rt,
+                        &fragmenter,
+                        &timestamps,
+                        local_ipv4_addr,
+                        local_link_addr,
+                        link_addr,
+                        data,
+                        &local,
+                        &remote,
+                        checksum_offload,
+                    ) {
+                        warn!("Failed to send UDP datagram: {:?}", e);
+                    }
+                }),
+            )?;
         }
 
         Ok(())
     }
 
-    /// Pops data from a socket.
-    pub fn do_pop(&self, qd: QDesc) -> UdpPopFuture {
+    /// Pops data from a socket. If `size` is given, datagrams larger than it are truncated.
+    pub fn do_pop(&self, qd: QDesc, size: Option<usize>) -> UdpPopFuture {
         #[cfg(feature = "profiler")]
         timer!("udp::pop");
 
@@ -326,9 +557,19 @@ impl UdpPeer {
             Some(s) if s.is_some() => self.bound.get(&s.unwrap()).unwrap().clone(),
             _ => panic!("invalid queue descriptor"),
         };
+        let truncations: Rc<Cell<u64>> = self.truncations[&qd].clone();
+        let timestamps: Rc<Cell<Timestamps>> = self.timestamps[&qd].clone();
+        let error: Rc<Cell<Option<i32>>> = self.errors[&qd].clone();
+        let pktinfo: bool = self.options[&qd].get_pktinfo();
 
         // Issue pop operation.
-        UdpPopFuture::new(qd, recv_queue)
+        UdpPopFuture::new(qd, recv_queue, size, truncations, timestamps, error, pktinfo)
+    }
+
+    /// Starts receiving a new batch of frames. The datagrams of the batch all arrive at the time when the first of them
+    /// is received.
+    pub fn begin_batch(&mut self) {
+        self.batch_time = None;
     }
 
     /// Consumes the payload from a buffer.
@@ -336,6 +577,9 @@ impl UdpPeer {
         #[cfg(feature = "profiler")]
         timer!("udp::receive");
 
+        // Read the hardware timestamp before parsing, as it is attached to the frame that carried the datagram.
+        let hw_timestamp: Option<u64> = self.rt.rx_timestamp(&buf);
+
         // Parse datagram.
         let (hdr, data): (UdpHeader, DemiBuffer) = UdpHeader::parse(ipv4_hdr, buf, self.checksum_offload)?;
         debug!("UDP received {:?}", hdr);
@@ -343,32 +587,50 @@ impl UdpPeer {
         let local: SocketAddrV4 = SocketAddrV4::new(ipv4_hdr.get_dest_addr(), hdr.dest_port());
         let remote: SocketAddrV4 = SocketAddrV4::new(ipv4_hdr.get_src_addr(), hdr.src_port());
 
+        // Handle wildcard address.
+        let endpoint: SocketAddrV4 = match self.bound.contains_key(&local) {
+            true => local,
+            false => SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, hdr.dest_port()),
+        };
+
+        // Connected sockets only receive datagrams from their remote peer.
+        if let Some(peer) = self.connected.get(&endpoint) {
+            if *peer != remote {
+                debug!("dropping datagram from {:?} (connected to {:?})", remote, peer);
+                return Ok(());
+            }
+        }
+
         // Lookup associated receiver-side shared queue.
-        let recv_queue: &mut SharedQueue<SharedQueueSlot<DemiBuffer>> = match self.bound.get_mut(&local) {
+        let recv_queue: &mut SharedQueue<SharedQueueSlot<DemiBuffer>> = match self.bound.get_mut(&endpoint) {
             Some(q) => q,
-            None => {
-                // Handle wildcard address.
-                let local: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, hdr.dest_port());
-                match self.bound.get_mut(&local) {
-                    Some(q) => q,
-                    // TODO: Send ICMPv4 error in this condition.
-                    None => Err(Fail::new(libc::ENOTCONN, "port not bound"))?,
-                }
-            },
+            // The datagram is dropped, and the caller answers with an ICMPv4 port unreachable message.
+            None => Err(Fail::new(libc::ENOTCONN, "port not bound"))?,
         };
 
-        // TODO: Drop this packet if local address/port pair is not bound.
-
         // Push data to the receiver-side shared queue. This will cause the
         // associated pool operation to be ready.
-        recv_queue.push(SharedQueueSlot { local, remote, data }).unwrap();
+        let timestamp: SystemTime = *self.batch_time.get_or_insert_with(SystemTime::now);
+        let info: PacketInfo =
+            PacketInfo::new(*local.ip(), Some(ipv4_hdr.get_ttl()), Some(timestamp)).with_hw_timestamp(hw_timestamp);
+        recv_queue
+            .push(SharedQueueSlot {
+                local,
+                remote,
+                data,
+                info: Some(info),
+            })
+            .unwrap();
 
         Ok(())
     }
 
-    /// Sends a UDP datagram.
+    /// Sends a UDP datagram, fragmenting it if it does not fit in the MTU. Its hardware timestamp is recorded in
+    /// `timestamps`.
     fn do_send(
         rt: Rc<dyn NetworkRuntime>,
+        fragmenter: &Ipv4Fragmenter,
+        timestamps: &Cell<Timestamps>,
         local_ipv4_addr: Ipv4Addr,
         local_link_addr: MacAddress,
         remote_link_addr: MacAddress,
@@ -376,7 +638,7 @@ impl UdpPeer {
         local: &SocketAddrV4,
         remote: &SocketAddrV4,
         offload_checksum: bool,
-    ) {
+    ) -> Result<(), Fail> {
         let udp_header: UdpHeader = UdpHeader::new(local.port(), remote.port());
         debug!("UDP send {:?}", udp_header);
         let datagram = UdpDatagram::new(
@@ -386,6 +648,13 @@ impl UdpPeer {
             buf,
             offload_checksum,
         );
-        rt.transmit(Box::new(datagram));
+        fragmenter.transmit(&rt, Box::new(datagram))?;
+        if let Some(tx) = rt.tx_timestamp() {
+            timestamps.set(Timestamps {
+                tx: Some(tx),
+                ..timestamps.get()
+            });
+        }
+        Ok(())
     }
 }
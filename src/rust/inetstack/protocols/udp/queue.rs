@@ -5,14 +5,14 @@
 // Imports
 //======================================================================================================================
 
-use crate::runtime::fail::Fail;
-use ::futures::{
-    channel::mpsc::{
-        self,
-        Receiver,
-        Sender,
-    },
-    StreamExt,
+use crate::runtime::{
+    fail::Fail,
+    network::types::PacketInfo,
+};
+use ::futures::channel::mpsc::{
+    self,
+    Receiver,
+    Sender,
 };
 use ::libc::EIO;
 use ::std::{
@@ -33,6 +33,8 @@ pub struct SharedQueueSlot<T> {
     pub remote: SocketAddrV4,
     /// Associated data.
     pub data: T,
+    /// Packet information, for received datagrams.
+    pub info: Option<PacketInfo>,
 }
 
 /// Shared Queue
@@ -95,17 +97,6 @@ impl<T> SharedQueue<T> {
             Err(_) => Ok(None),
         }
     }
-
-    /// Asynchronously pops a message from the target shared queue.
-    pub async fn pop(&mut self) -> Result<T, Fail> {
-        match self.rx.borrow_mut().next().await {
-            Some(msg) => {
-                *self.length.borrow_mut() -= 1;
-                Ok(msg)
-            },
-            None => Err(Fail::new(EIO, "failed to pop from shared queue")),
-        }
-    }
 }
 
 //======================================================================================================================
@@ -2,12 +2,35 @@
 // // Licensed under the MIT license.
 
 use crate::{
-    inetstack::test_helpers::{
-        self,
-        Engine,
+    inetstack::{
+        futures::Deadline,
+        protocols::{
+            ethernet2::Ethernet2Header,
+            ipv4::{
+                Ipv4Header,
+                DEFAULT_IPV4_TTL,
+            },
+            tcp::ConnectionSteering,
+        },
+        test_helpers::{
+            self,
+            Engine,
+        },
     },
     runtime::{
         memory::DemiBuffer,
+        network::{
+            config::{
+                TcpConfig,
+                UdpConfig,
+            },
+            types::{
+                LinkState,
+                PacketInfo,
+                SocketOption,
+                Timestamps,
+            },
+        },
         QDesc,
     },
 };
@@ -16,9 +39,14 @@ use ::futures::task::{
     Context,
 };
 use ::libc::{
+    EACCES,
     EADDRINUSE,
     EBADF,
+    EDESTADDRREQ,
+    EMSGSIZE,
+    ENETDOWN,
     ENOTCONN,
+    ETIMEDOUT,
 };
 use ::std::{
     convert::TryFrom,
@@ -98,7 +126,7 @@ fn udp_push_pop() {
     bob.receive(alice.rt.pop_frame()).unwrap();
     let mut pop_future = bob.udp_pop(bob_fd);
     let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-        Poll::Ready(Ok((remote_addr, received_buf))) => Ok((remote_addr, received_buf)),
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
         _ => Err(()),
     }
     .unwrap();
@@ -145,7 +173,7 @@ fn udp_push_pop_wildcard_address() {
     bob.receive(alice.rt.pop_frame()).unwrap();
     let mut pop_future = bob.udp_pop(bob_fd);
     let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-        Poll::Ready(Ok((remote_addr, received_buf))) => Ok((remote_addr, received_buf)),
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
         _ => Err(()),
     }
     .unwrap();
@@ -157,6 +185,64 @@ fn udp_push_pop_wildcard_address() {
     bob.udp_close(bob_fd).unwrap();
 }
 
+/// Tests that a socket bound to a secondary address sends from it and receives datagrams that are sent to it.
+#[test]
+fn udp_push_pop_secondary_address() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice, with a secondary address.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_ipv4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 101);
+    alice.arp.add_local_addr(alice_ipv4).unwrap();
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(alice_ipv4, 80);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Send data to Bob, who sees it coming from the secondary address.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let remote_addr: SocketAddrV4 = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, _, _, _))) => Ok(remote_addr),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr);
+
+    // Bob resolves the secondary address before replying to it.
+    bob.udp_pushto(bob_fd, buf.clone(), alice_addr).unwrap();
+    bob.rt.poll_scheduler();
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    bob.rt.poll_scheduler();
+    alice.receive(bob.rt.pop_frame()).unwrap();
+    let mut pop_future = alice.udp_pop(alice_fd);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, bob_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // The secondary address is in use until the socket that is bound to it is closed.
+    assert!(alice.ipv4.udp.is_addr_in_use(alice_ipv4));
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+    assert!(!alice.ipv4.udp.is_addr_in_use(alice_ipv4));
+}
+
 //==============================================================================
 // Ping Pong
 //==============================================================================
@@ -191,7 +277,7 @@ fn udp_ping_pong() {
     bob.receive(alice.rt.pop_frame()).unwrap();
     let mut pop_future = bob.udp_pop(bob_fd);
     let (remote_addr, received_buf_a) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-        Poll::Ready(Ok((remote_addr, received_buf_a))) => Ok((remote_addr, received_buf_a)),
+        Poll::Ready(Ok((remote_addr, received_buf_a, _, _))) => Ok((remote_addr, received_buf_a)),
         _ => Err(()),
     }
     .unwrap();
@@ -211,7 +297,7 @@ fn udp_ping_pong() {
     alice.receive(bob.rt.pop_frame()).unwrap();
     let mut pop_future = alice.udp_pop(alice_fd);
     let (remote_addr, received_buf_b) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-        Poll::Ready(Ok((remote_addr, received_buf_b))) => Ok((remote_addr, received_buf_b)),
+        Poll::Ready(Ok((remote_addr, received_buf_b, _, _))) => Ok((remote_addr, received_buf_b)),
         _ => Err(()),
     }
     .unwrap();
@@ -310,7 +396,7 @@ fn udp_loop2_push_pop() {
         bob.receive(alice.rt.pop_frame()).unwrap();
         let mut pop_future = bob.udp_pop(bob_fd);
         let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-            Poll::Ready(Ok((remote_addr, received_buf))) => Ok((remote_addr, received_buf)),
+            Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
             _ => Err(()),
         }
         .unwrap();
@@ -367,7 +453,7 @@ fn udp_loop2_ping_pong() {
         bob.receive(alice.rt.pop_frame()).unwrap();
         let mut pop_future = bob.udp_pop(bob_fd);
         let (remote_addr, received_buf_a) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-            Poll::Ready(Ok((remote_addr, received_buf_a))) => Ok((remote_addr, received_buf_a)),
+            Poll::Ready(Ok((remote_addr, received_buf_a, _, _))) => Ok((remote_addr, received_buf_a)),
             _ => Err(()),
         }
         .unwrap();
@@ -387,7 +473,7 @@ fn udp_loop2_ping_pong() {
         alice.receive(bob.rt.pop_frame()).unwrap();
         let mut pop_future = alice.udp_pop(alice_fd);
         let (remote_addr, received_buf_b) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
-            Poll::Ready(Ok((remote_addr, received_buf_b))) => Ok((remote_addr, received_buf_b)),
+            Poll::Ready(Ok((remote_addr, received_buf_b, _, _))) => Ok((remote_addr, received_buf_b)),
             _ => Err(()),
         }
         .unwrap();
@@ -515,10 +601,630 @@ fn udp_pop_not_bound() {
     // Bob does not have a socket.
 }
 
+//==============================================================================
+// Pop Timeout
+//==============================================================================
+
+#[test]
+fn udp_pop_deadline() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Pop data with a deadline, but nobody sends anything.
+    let deadline: Deadline = Deadline::new(&bob.clock, Some(now + Duration::from_secs(1)));
+    let mut pop_future = bob.udp_pop(bob_fd).with_deadline(deadline);
+    assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+
+    // Advance clock past the deadline.
+    now += Duration::from_secs(2);
+    bob.clock.advance_clock(now);
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Err(e)) if e.errno == ETIMEDOUT => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Close peers.
+    bob.udp_close(bob_fd).unwrap();
+}
+
+/// Tests that a pending pop fails once the link goes down, and that data that was already received is still popped.
+#[test]
+fn udp_pop_link_down() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_port: u16 = 80;
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, alice_port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Bob waits for data that never comes, until his link goes down.
+    let deadline: Deadline = Deadline::default().with_link(&bob.link);
+    let mut pop_future = bob.udp_pop(bob_fd).with_deadline(deadline);
+    assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+    bob.rt.set_link_state(LinkState::Down);
+    bob.poll_link();
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Err(e)) if e.errno == ENETDOWN => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // A datagram that was received before is still popped.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    let deadline: Deadline = Deadline::default().with_link(&bob.link);
+    let mut pop_future = bob.udp_pop(bob_fd).with_deadline(deadline);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+//==============================================================================
+// Pop Truncation
+//==============================================================================
+
+#[test]
+fn udp_pop_sized_truncated() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_port: u16 = 80;
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, alice_port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Send two datagrams to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+
+    // Pop the first datagram into a buffer that is too small.
+    let mut pop_future = bob.udp_pop_sized(bob_fd, 16);
+    let (received_buf, truncated) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((_, received_buf, truncated, _))) => Ok((received_buf, truncated)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(received_buf[..], buf[..16]);
+    assert!(truncated);
+    assert_eq!(bob.udp_truncations(bob_fd).unwrap(), 1);
+
+    // Pop the second datagram into a buffer that is large enough.
+    let mut pop_future = bob.udp_pop_sized(bob_fd, 32);
+    let (received_buf, truncated) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((_, received_buf, truncated, _))) => Ok((received_buf, truncated)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(received_buf[..], buf[..]);
+    assert!(!truncated);
+    assert_eq!(bob.udp_truncations(bob_fd).unwrap(), 1);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+//==============================================================================
+// Packet Information
+//==============================================================================
+
+#[test]
+fn udp_pop_packet_info() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+    let port: u16 = 80;
+
+    // Setup Alice.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob, bound to the wildcard address.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))
+        .unwrap();
+
+    // Send two datagrams to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    for _ in 0..2 {
+        alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+        alice.rt.poll_scheduler();
+        bob.receive(alice.rt.pop_frame()).unwrap();
+    }
+
+    // Packet information is not reported by default.
+    let mut pop_future = bob.udp_pop(bob_fd);
+    match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((_, _, _, None))) => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Packet information carries the address that the datagram was sent to.
+    bob.ipv4
+        .udp
+        .set_socket_option(bob_fd, SocketOption::PacketInfo(true))
+        .unwrap();
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let info: PacketInfo = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((_, _, _, Some(info)))) => Ok(info),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(info.get_dst_addr(), test_helpers::BOB_IPV4);
+    assert_eq!(info.get_ttl(), Some(DEFAULT_IPV4_TTL));
+    assert!(info.get_timestamp().is_some());
+    assert_eq!(info.get_hw_timestamp(), None);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+#[test]
+fn udp_hw_timestamps() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+    let port: u16 = 80;
+
+    // Setup Alice, whose network interface timestamps frames.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+    alice.rt.set_hw_clock(Some(1_000));
+
+    // Setup Bob, whose network interface timestamps frames too.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+    bob.rt.set_hw_clock(Some(2_000));
+    bob.ipv4
+        .udp
+        .set_socket_option(bob_fd, SocketOption::PacketInfo(true))
+        .unwrap();
+
+    // Nothing was timestamped yet.
+    assert_eq!(alice.udp_timestamps(alice_fd).unwrap(), Timestamps::default());
+
+    // Alice sends a datagram to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf, bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    assert_eq!(
+        alice.udp_timestamps(alice_fd).unwrap(),
+        Timestamps {
+            rx: None,
+            tx: Some(1_000)
+        }
+    );
+
+    // The receive timestamp is reported along with the datagram, and recorded once it is popped.
+    assert_eq!(bob.udp_timestamps(bob_fd).unwrap(), Timestamps::default());
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let info: PacketInfo = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((_, _, _, Some(info)))) => Ok(info),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(info.get_hw_timestamp(), Some(2_000));
+    assert_eq!(
+        bob.udp_timestamps(bob_fd).unwrap(),
+        Timestamps {
+            rx: Some(2_000),
+            tx: None
+        }
+    );
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+//==============================================================================
+// Connected Sockets
+//==============================================================================
+
+#[test]
+fn udp_connect_push_pop() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice, but do not bind her socket.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Pushing without an address requires a connected socket.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    match alice.udp_push(alice_fd, buf.clone()) {
+        Err(e) if e.errno == EDESTADDRREQ => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    // Connect Alice to Bob. This binds her socket to an ephemeral port.
+    alice.udp_connect(alice_fd, bob_addr).unwrap();
+    let alice_addr: SocketAddrV4 = alice.ipv4.udp.local_addr(alice_fd).unwrap();
+    assert_eq!(*alice_addr.ip(), test_helpers::ALICE_IPV4);
+    assert_ne!(alice_addr.port(), 0);
+    assert_eq!(alice.ipv4.udp.remote_addr(alice_fd).unwrap(), bob_addr);
+
+    // Send data to Bob, without an address.
+    alice.udp_push(alice_fd, buf.clone()).unwrap();
+    alice.rt.poll_scheduler();
+
+    // Receive data from Alice.
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+#[test]
+fn udp_connect_steered_port() {
+    let now: Instant = Instant::now();
+    let nsockets: usize = 32;
+
+    // Setup Alice, as the instance that owns the second shard out of four.
+    let mut alice: Engine = test_helpers::new_alice2_with_tcp_config(now, test_helpers::sharded_tcp_config(1, 4, None));
+    let steering: ConnectionSteering = ConnectionSteering::new(1, 4, None);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+
+    // Unbound sockets pick ephemeral ports that steer the datagrams of Bob back to Alice.
+    for _ in 0..nsockets {
+        let alice_fd: QDesc = alice.udp_socket().unwrap();
+        alice.udp_connect(alice_fd, bob_addr).unwrap();
+        let alice_addr: SocketAddrV4 = alice.ipv4.udp.local_addr(alice_fd).unwrap();
+        assert!(steering.owns(&alice_addr, &bob_addr));
+    }
+}
+
+#[test]
+fn udp_connect_filters_inbound() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice, with two sockets.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_addr_a: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd_a: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd_a, alice_addr_a).unwrap();
+    let alice_addr_b: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 81);
+    let alice_fd_b: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd_b, alice_addr_b).unwrap();
+
+    // Setup Bob, and connect him to the first socket of Alice.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+    bob.udp_connect(bob_fd, alice_addr_a).unwrap();
+
+    // Send data to Bob from the second socket of Alice. This should be dropped.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd_b, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    let mut pop_future = bob.udp_pop(bob_fd);
+    assert!(Future::poll(Pin::new(&mut pop_future), &mut ctx).is_pending());
+
+    // Send data to Bob from the first socket of Alice. This should be received.
+    alice.udp_pushto(alice_fd_a, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    bob.receive(alice.rt.pop_frame()).unwrap();
+    let remote_addr: SocketAddrV4 = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, _, _, _))) => Ok(remote_addr),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr_a);
+
+    // Close peers.
+    alice.udp_close(alice_fd_a).unwrap();
+    alice.udp_close(alice_fd_b).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+//==============================================================================
+// Broadcast
+//==============================================================================
+
+#[test]
+fn udp_broadcast_pushto_pop() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+    let port: u16 = 80;
+    let broadcast_addr: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::BROADCAST, port);
+
+    // Setup Alice.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob, bound to the wildcard address.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))
+        .unwrap();
+
+    // Sending to the broadcast address requires the broadcast option.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    match alice.udp_pushto(alice_fd, buf.clone(), broadcast_addr) {
+        Err(e) if e.errno == EACCES => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+    alice
+        .ipv4
+        .udp
+        .set_socket_option(alice_fd, SocketOption::Broadcast(true))
+        .unwrap();
+
+    // Send data to everyone. This does not need address resolution.
+    alice.udp_pushto(alice_fd, buf.clone(), broadcast_addr).unwrap();
+    let frame: DemiBuffer = alice.rt.pop_frame();
+    let (eth2_hdr, _): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(frame.clone()).unwrap();
+    assert!(eth2_hdr.dst_addr().is_broadcast());
+
+    // Receive data from Alice.
+    bob.receive(frame).unwrap();
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+//==============================================================================
+// Fragmentation
+//==============================================================================
+
+#[test]
+fn udp_pushto_pop_fragmented() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_port: u16 = 80;
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, alice_port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // Send a datagram that does not fit in the MTU to Bob.
+    let data: Vec<u8> = (0..4000).map(|i| i as u8).collect();
+    let buf: DemiBuffer = DemiBuffer::from_slice(&data[..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+
+    // The datagram is split into fragments.
+    let mut frames: Vec<DemiBuffer> = Vec::new();
+    while let Some(frame) = alice.rt.pop_frame_unchecked() {
+        let (_, ipv4_buf): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(frame.clone()).unwrap();
+        let (ipv4_hdr, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(ipv4_buf).unwrap();
+        assert!(ipv4_hdr.is_fragment());
+        frames.push(frame);
+    }
+    assert_eq!(frames.len(), 3);
+
+    // Receive fragments from Alice, out of order.
+    for frame in frames.into_iter().rev() {
+        bob.receive(frame).unwrap();
+    }
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+#[test]
+fn udp_pushto_jumbo_mtu() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice, on a link with jumbo frames.
+    let udp_config: UdpConfig = UdpConfig::new(None, None, Some(9000));
+    let mut alice: Engine = test_helpers::new_alice2_with_config(now, udp_config, TcpConfig::default());
+    let alice_port: u16 = 80;
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, alice_port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Setup Bob.
+    let mut bob: Engine = test_helpers::new_bob2(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket().unwrap();
+    bob.udp_bind(bob_fd, bob_addr).unwrap();
+
+    // A datagram that does not fit in the default MTU is sent in a single frame.
+    let data: Vec<u8> = (0..8000).map(|i| i as u8).collect();
+    let buf: DemiBuffer = DemiBuffer::from_slice(&data[..]).expect("slice should fit in DemiBuffer");
+    alice.udp_pushto(alice_fd, buf.clone(), bob_addr).unwrap();
+    alice.rt.poll_scheduler();
+    let frame: DemiBuffer = alice.rt.pop_frame();
+    assert!(alice.rt.pop_frame_unchecked().is_none());
+    let (_, ipv4_buf): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(frame.clone()).unwrap();
+    let (ipv4_hdr, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(ipv4_buf).unwrap();
+    assert!(!ipv4_hdr.is_fragment());
+
+    // Receive the datagram from Alice.
+    bob.receive(frame).unwrap();
+    let mut pop_future = bob.udp_pop(bob_fd);
+    let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+        Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+        _ => Err(()),
+    }
+    .unwrap();
+    assert_eq!(remote_addr, alice_addr);
+    assert_eq!(received_buf[..], buf[..]);
+
+    // Close peers.
+    alice.udp_close(alice_fd).unwrap();
+    bob.udp_close(bob_fd).unwrap();
+}
+
+//==============================================================================
+// Loopback
+//==============================================================================
+
+#[test]
+fn udp_pushto_pop_loopback() {
+    let mut ctx: Context = Context::from_waker(noop_waker_ref());
+    let now: Instant = Instant::now();
+
+    // Setup Alice, with a client and two servers, one of which listens on every address.
+    let mut alice: Engine = test_helpers::new_alice(now);
+    let client_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let client_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(client_fd, client_addr).unwrap();
+    let server_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 81);
+    let server_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(server_fd, server_addr).unwrap();
+    let any_server_fd: QDesc = alice.udp_socket().unwrap();
+    alice
+        .udp_bind(any_server_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 82))
+        .unwrap();
+
+    // Datagrams to our own addresses never reach the wire, and get received.
+    for (fd, dst_addr) in [
+        (server_fd, server_addr),
+        (any_server_fd, SocketAddrV4::new(Ipv4Addr::LOCALHOST, 82)),
+    ] {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32]).expect("slice should fit in DemiBuffer");
+        alice.udp_pushto(client_fd, buf.clone(), dst_addr).unwrap();
+        alice.rt.poll_scheduler();
+        assert!(alice.rt.pop_frame_unchecked().is_none());
+        alice.poll_loopback();
+        let mut pop_future = alice.udp_pop(fd);
+        let (remote_addr, received_buf) = match Future::poll(Pin::new(&mut pop_future), &mut ctx) {
+            Poll::Ready(Ok((remote_addr, received_buf, _, _))) => Ok((remote_addr, received_buf)),
+            _ => Err(()),
+        }
+        .unwrap();
+        assert_eq!(remote_addr, client_addr);
+        assert_eq!(received_buf[..], buf[..]);
+    }
+
+    // Close sockets.
+    alice.udp_close(client_fd).unwrap();
+    alice.udp_close(server_fd).unwrap();
+    alice.udp_close(any_server_fd).unwrap();
+}
+
 //==============================================================================
 // Bad Push
 //==============================================================================
 
+#[test]
+fn udp_pushto_too_large() {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: Engine = test_helpers::new_alice2(now);
+    let alice_port: u16 = 80;
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, alice_port);
+    let alice_fd: QDesc = alice.udp_socket().unwrap();
+    alice.udp_bind(alice_fd, alice_addr).unwrap();
+
+    // Datagrams should fit in a single IPv4 datagram.
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let buf: DemiBuffer = DemiBuffer::new(u16::MAX - 1);
+    match alice.udp_pushto(alice_fd, buf, bob_addr) {
+        Err(e) if e.errno == EMSGSIZE => Ok(()),
+        _ => Err(()),
+    }
+    .unwrap();
+
+    alice.udp_close(alice_fd).unwrap();
+}
+
 #[test]
 fn udp_push_bad_file_descriptor() {
     let mut now = Instant::now();
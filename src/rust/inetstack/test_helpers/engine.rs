@@ -2,25 +2,41 @@
 // Licensed under the MIT license.
 
 use crate::{
-    inetstack::protocols::{
-        arp::ArpPeer,
-        ethernet2::{
-            EtherType2,
-            Ethernet2Header,
+    inetstack::{
+        link::LinkMonitor,
+        protocols::{
+            arp::ArpPeer,
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+                Loopback,
+            },
+            tcp::{
+                operations::{
+                    AcceptFuture,
+                    CloseFuture,
+                    ConnectFuture,
+                    PopFuture,
+                    PushFuture,
+                    SpliceFuture,
+                },
+                OutOfOrderStats,
+            },
+            udp::UdpPopFuture,
+            Peer,
         },
-        tcp::operations::{
-            AcceptFuture,
-            ConnectFuture,
-            PopFuture,
-            PushFuture,
-        },
-        udp::UdpPopFuture,
-        Peer,
     },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
-        network::types::MacAddress,
+        network::{
+            types::{
+                MacAddress,
+                TcpInfo,
+                Timestamps,
+            },
+            NetworkRuntime,
+        },
         queue::IoQueueTable,
         timer::TimerRc,
         QDesc,
@@ -44,6 +60,8 @@ use super::TestRuntime;
 
 pub struct Engine {
     pub rt: Rc<TestRuntime>,
+    pub loopback: Rc<Loopback>,
+    pub link: LinkMonitor,
     pub clock: TimerRc,
     pub arp: ArpPeer,
     pub ipv4: Peer,
@@ -59,8 +77,10 @@ impl Engine {
         let udp_config = rt.udp_config.clone();
         let tcp_config = rt.tcp_config.clone();
         let file_table = IoQueueTable::new();
+        let loopback = Rc::new(Loopback::new(rt.clone(), link_addr));
+        let link = LinkMonitor::new(loopback.link_state());
         let arp = ArpPeer::new(
-            rt.clone(),
+            loopback.clone(),
             scheduler.clone(),
             clock.clone(),
             link_addr,
@@ -69,7 +89,7 @@ impl Engine {
         )?;
         let rng_seed: [u8; 32] = [0; 32];
         let ipv4 = Peer::new(
-            rt.clone(),
+            loopback.clone(),
             scheduler.clone(),
             clock.clone(),
             link_addr,
@@ -81,6 +101,8 @@ impl Engine {
         )?;
         Ok(Engine {
             rt,
+            loopback,
+            link,
             clock,
             arp,
             ipv4,
@@ -91,7 +113,11 @@ impl Engine {
     pub fn receive(&mut self, bytes: DemiBuffer) -> Result<(), Fail> {
         let (header, payload) = Ethernet2Header::parse(bytes)?;
         debug!("Engine received {:?}", header);
-        if self.rt.link_addr != header.dst_addr() && !header.dst_addr().is_broadcast() {
+        self.ipv4.udp.begin_batch();
+        if self.rt.link_addr != header.dst_addr()
+            && !header.dst_addr().is_broadcast()
+            && !header.dst_addr().is_multicast()
+        {
             return Err(Fail::new(EBADMSG, "physical destination address mismatch"));
         }
         match header.ether_type() {
@@ -101,6 +127,26 @@ impl Engine {
         }
     }
 
+    /// Receives the frames that we sent to ourselves, along with any frame that is pushed to the test runtime.
+    pub fn poll_loopback(&mut self) {
+        loop {
+            let frames = self.loopback.receive();
+            if frames.is_empty() {
+                break;
+            }
+            for frame in frames {
+                if let Err(e) = self.receive(frame) {
+                    warn!("Dropped packet: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Polls the state of the link of the test runtime.
+    pub fn poll_link(&mut self) {
+        self.link.update(self.loopback.link_state());
+    }
+
     pub fn ipv4_ping(
         &mut self,
         dest_ipv4_addr: Ipv4Addr,
@@ -113,8 +159,36 @@ impl Engine {
         self.ipv4.udp.do_pushto(fd, buf, to)
     }
 
+    pub fn udp_push(&self, fd: QDesc, buf: DemiBuffer) -> Result<(), Fail> {
+        self.ipv4.udp.do_push(fd, buf)
+    }
+
+    pub fn udp_connect(&mut self, fd: QDesc, remote: SocketAddrV4) -> Result<(), Fail> {
+        self.ipv4.udp.do_connect(fd, remote)
+    }
+
+    pub fn udp_join_multicast_group(&mut self, fd: QDesc, group: Ipv4Addr) -> Result<(), Fail> {
+        self.ipv4.udp.join_multicast_group(fd, group, Ipv4Addr::UNSPECIFIED)
+    }
+
+    pub fn udp_leave_multicast_group(&mut self, fd: QDesc, group: Ipv4Addr) -> Result<(), Fail> {
+        self.ipv4.udp.leave_multicast_group(fd, group, Ipv4Addr::UNSPECIFIED)
+    }
+
     pub fn udp_pop(&mut self, fd: QDesc) -> UdpPopFuture {
-        self.ipv4.udp.do_pop(fd)
+        self.ipv4.udp.do_pop(fd, None)
+    }
+
+    pub fn udp_pop_sized(&mut self, fd: QDesc, size: usize) -> UdpPopFuture {
+        self.ipv4.udp.do_pop(fd, Some(size))
+    }
+
+    pub fn udp_truncations(&self, fd: QDesc) -> Result<u64, Fail> {
+        self.ipv4.udp.get_truncations(fd)
+    }
+
+    pub fn udp_timestamps(&self, fd: QDesc) -> Result<Timestamps, Fail> {
+        self.ipv4.udp.get_timestamps(fd)
     }
 
     pub fn udp_socket(&mut self) -> Result<QDesc, Fail> {
@@ -154,14 +228,30 @@ impl Engine {
         self.ipv4.tcp.push(socket_fd, buf)
     }
 
+    pub fn tcp_pushv(&mut self, socket_fd: QDesc, bufs: Vec<DemiBuffer>) -> PushFuture {
+        self.ipv4.tcp.pushv(socket_fd, bufs)
+    }
+
     pub fn tcp_pop(&mut self, socket_fd: QDesc) -> PopFuture {
-        self.ipv4.tcp.pop(socket_fd)
+        self.ipv4.tcp.pop(socket_fd, None)
+    }
+
+    pub fn tcp_pop_sized(&mut self, socket_fd: QDesc, size: usize) -> PopFuture {
+        self.ipv4.tcp.pop(socket_fd, Some(size))
+    }
+
+    pub fn tcp_splice(&mut self, src_fd: QDesc, dst_fd: QDesc) -> SpliceFuture {
+        self.ipv4.tcp.splice(src_fd, dst_fd)
     }
 
     pub fn tcp_close(&mut self, socket_fd: QDesc) -> Result<(), Fail> {
         self.ipv4.tcp.do_close(socket_fd)
     }
 
+    pub fn tcp_async_close(&mut self, socket_fd: QDesc) -> Result<CloseFuture, Fail> {
+        self.ipv4.tcp.do_async_close(socket_fd, true)
+    }
+
     pub fn tcp_listen(&mut self, socket_fd: QDesc, backlog: usize) -> Result<(), Fail> {
         self.ipv4.tcp.listen(socket_fd, backlog)
     }
@@ -178,6 +268,18 @@ impl Engine {
         self.ipv4.tcp_rto(handle)
     }
 
+    pub fn tcp_out_of_order_stats(&self, fd: QDesc) -> Result<OutOfOrderStats, Fail> {
+        self.ipv4.tcp.out_of_order_stats(fd)
+    }
+
+    pub fn tcp_backlog_overflows(&self, fd: QDesc) -> Result<u64, Fail> {
+        self.ipv4.tcp.backlog_overflows(fd)
+    }
+
+    pub fn tcp_info(&self, fd: QDesc) -> Result<TcpInfo, Fail> {
+        self.ipv4.tcp.tcp_info(fd)
+    }
+
     pub fn export_arp_cache(&self) -> HashMap<Ipv4Addr, MacAddress> {
         self.arp.export_cache()
     }
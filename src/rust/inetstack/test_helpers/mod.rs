@@ -15,7 +15,10 @@ use crate::{
                 TcpConfig,
                 UdpConfig,
             },
-            types::MacAddress,
+            types::{
+                MacAddress,
+                Rss,
+            },
         },
         timer::TimerRc,
     },
@@ -46,6 +49,16 @@ pub const CARRIE_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 3);
 // Standalone Functions
 //==============================================================================
 
+/// Returns a TCP configuration for the instance that owns shard `index` out of `count` shards, which are steered by
+/// `rss` if set.
+pub fn sharded_tcp_config(index: usize, count: usize, rss: Option<Rss>) -> TcpConfig {
+    let mut config: TcpConfig = TcpConfig::default().set_shard(index, count);
+    if let Some(rss) = rss {
+        config = config.set_rss(rss);
+    }
+    config
+}
+
 pub fn new_alice(now: Instant) -> Engine {
     let arp_options = ArpConfig::new(
         Some(Duration::from_secs(600)),
@@ -53,6 +66,8 @@ pub fn new_alice(now: Instant) -> Engine {
         Some(2),
         Some(HashMap::new()),
         Some(false),
+        Some(0),
+        None,
     );
     let udp_config = UdpConfig::default();
     let tcp_config = TcpConfig::default();
@@ -69,6 +84,8 @@ pub fn new_bob(now: Instant) -> Engine {
         Some(2),
         Some(HashMap::new()),
         Some(false),
+        Some(0),
+        None,
     );
     let udp_config = UdpConfig::default();
     let tcp_config = TcpConfig::default();
@@ -79,6 +96,14 @@ pub fn new_bob(now: Instant) -> Engine {
 }
 
 pub fn new_alice2(now: Instant) -> Engine {
+    new_alice2_with_tcp_config(now, TcpConfig::default())
+}
+
+pub fn new_alice2_with_tcp_config(now: Instant, tcp_config: TcpConfig) -> Engine {
+    new_alice2_with_config(now, UdpConfig::default(), tcp_config)
+}
+
+pub fn new_alice2_with_config(now: Instant, udp_config: UdpConfig, tcp_config: TcpConfig) -> Engine {
     let mut arp: HashMap<Ipv4Addr, MacAddress> = HashMap::<Ipv4Addr, MacAddress>::new();
     arp.insert(ALICE_IPV4, ALICE_MAC);
     arp.insert(BOB_IPV4, BOB_MAC);
@@ -88,9 +113,9 @@ pub fn new_alice2(now: Instant) -> Engine {
         Some(2),
         Some(arp),
         Some(false),
+        Some(0),
+        None,
     );
-    let udp_config = UdpConfig::default();
-    let tcp_config = TcpConfig::default();
     let rt = TestRuntime::new(now, arp_options, udp_config, tcp_config, ALICE_MAC, ALICE_IPV4);
     let scheduler: Scheduler = rt.scheduler.clone();
     let clock: TimerRc = rt.clock.clone();
@@ -98,6 +123,10 @@ pub fn new_alice2(now: Instant) -> Engine {
 }
 
 pub fn new_bob2(now: Instant) -> Engine {
+    new_bob2_with_tcp_config(now, TcpConfig::default())
+}
+
+pub fn new_bob2_with_tcp_config(now: Instant, tcp_config: TcpConfig) -> Engine {
     let mut arp: HashMap<Ipv4Addr, MacAddress> = HashMap::<Ipv4Addr, MacAddress>::new();
     arp.insert(BOB_IPV4, BOB_MAC);
     arp.insert(ALICE_IPV4, ALICE_MAC);
@@ -107,9 +136,10 @@ pub fn new_bob2(now: Instant) -> Engine {
         Some(2),
         Some(arp),
         Some(false),
+        Some(0),
+        None,
     );
     let udp_config = UdpConfig::default();
-    let tcp_config = TcpConfig::default();
     let rt = TestRuntime::new(now, arp_options, udp_config, tcp_config, BOB_MAC, BOB_IPV4);
     let scheduler: Scheduler = rt.scheduler.clone();
     let clock: TimerRc = rt.clock.clone();
@@ -123,6 +153,8 @@ pub fn new_carrie(now: Instant) -> Engine {
         Some(2),
         Some(HashMap::new()),
         Some(false),
+        Some(0),
+        None,
     );
     let udp_config = UdpConfig::default();
     let tcp_config = TcpConfig::default();
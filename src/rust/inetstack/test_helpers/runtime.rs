@@ -12,7 +12,10 @@ use crate::{
                 UdpConfig,
             },
             consts::RECEIVE_BATCH_SIZE,
-            types::MacAddress,
+            types::{
+                LinkState,
+                MacAddress,
+            },
             NetworkRuntime,
             PacketBuf,
         },
@@ -42,6 +45,8 @@ pub struct Inner {
     timer: TimerRc,
     incoming: VecDeque<DemiBuffer>,
     outgoing: VecDeque<DemiBuffer>,
+    link_state: LinkState,
+    hw_clock: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -75,6 +80,8 @@ impl TestRuntime {
             timer: TimerRc(Rc::new(Timer::new(now))),
             incoming: VecDeque::new(),
             outgoing: VecDeque::new(),
+            link_state: LinkState::Up,
+            hw_clock: None,
         };
         Self {
             link_addr,
@@ -104,6 +111,16 @@ impl TestRuntime {
         self.inner.borrow_mut().incoming.push_back(buf);
     }
 
+    pub fn set_link_state(&self, state: LinkState) {
+        self.inner.borrow_mut().link_state = state;
+    }
+
+    /// Sets the clock of the emulated network interface, which timestamps all frames that are received or transmitted
+    /// from now on.
+    pub fn set_hw_clock(&self, clock: Option<u64>) {
+        self.inner.borrow_mut().hw_clock = clock;
+    }
+
     pub fn poll_scheduler(&self) {
         // let mut ctx = Context::from_waker(noop_waker_ref());
         self.scheduler.poll();
@@ -138,4 +155,16 @@ impl NetworkRuntime for TestRuntime {
         }
         out
     }
+
+    fn link_state(&self) -> LinkState {
+        self.inner.borrow().link_state
+    }
+
+    fn rx_timestamp(&self, _buf: &DemiBuffer) -> Option<u64> {
+        self.inner.borrow().hw_clock
+    }
+
+    fn tx_timestamp(&self) -> Option<u64> {
+        self.inner.borrow().hw_clock
+    }
 }
@@ -46,6 +46,9 @@ mod catnip;
 #[cfg(feature = "catpowder-libos")]
 mod catpowder;
 
+#[cfg(all(feature = "catloon-libos", target_os = "linux"))]
+mod catloon;
+
 #[cfg(feature = "catcollar-libos")]
 mod catcollar;
 
@@ -58,6 +61,9 @@ mod catnapw;
 #[cfg(feature = "catmem-libos")]
 mod catmem;
 
+#[cfg(all(feature = "shim", target_os = "linux"))]
+mod shim;
+
 pub use crate::demikernel::libos::network::OperationResult;
 
 pub use self::demikernel::libos::{
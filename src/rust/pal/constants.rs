@@ -17,6 +17,50 @@ pub const SOCK_STREAM: i32 = WinSock::SOCK_STREAM as i32;
 #[cfg(target_os = "windows")]
 pub const SOCK_DGRAM: i32 = WinSock::SOCK_DGRAM as i32;
 
+#[cfg(target_os = "windows")]
+pub const SOL_SOCKET: i32 = WinSock::SOL_SOCKET as i32;
+
+#[cfg(target_os = "windows")]
+pub const SO_SNDBUF: i32 = WinSock::SO_SNDBUF as i32;
+
+#[cfg(target_os = "windows")]
+pub const SO_RCVBUF: i32 = WinSock::SO_RCVBUF as i32;
+
+#[cfg(target_os = "windows")]
+pub const SO_KEEPALIVE: i32 = WinSock::SO_KEEPALIVE as i32;
+
+#[cfg(target_os = "windows")]
+pub const SO_BROADCAST: i32 = WinSock::SO_BROADCAST as i32;
+
+// Windows has no equivalent of this option, so we borrow the value used by Linux.
+#[cfg(target_os = "windows")]
+pub const SO_MAX_PACING_RATE: i32 = 47;
+
+#[cfg(target_os = "windows")]
+pub const IPPROTO_IP: i32 = WinSock::IPPROTO_IP.0 as i32;
+
+#[cfg(target_os = "windows")]
+pub const IP_TTL: i32 = WinSock::IP_TTL as i32;
+
+#[cfg(target_os = "windows")]
+pub const IP_PKTINFO: i32 = WinSock::IP_PKTINFO as i32;
+
+#[cfg(target_os = "windows")]
+pub const IPPROTO_TCP: i32 = WinSock::IPPROTO_TCP.0 as i32;
+
+#[cfg(target_os = "windows")]
+pub const TCP_NODELAY: i32 = WinSock::TCP_NODELAY as i32;
+
+// Windows names these SD_RECEIVE, SD_SEND and SD_BOTH.
+#[cfg(target_os = "windows")]
+pub const SHUT_RD: i32 = 0;
+
+#[cfg(target_os = "windows")]
+pub const SHUT_WR: i32 = 1;
+
+#[cfg(target_os = "windows")]
+pub const SHUT_RDWR: i32 = 2;
+
 //==============================================================================
 // Linux constants
 //==============================================================================
@@ -29,3 +73,51 @@ pub const SOCK_STREAM: i32 = libc::SOCK_STREAM;
 
 #[cfg(target_os = "linux")]
 pub const SOCK_DGRAM: i32 = libc::SOCK_DGRAM;
+
+#[cfg(target_os = "linux")]
+pub const SOL_SOCKET: i32 = libc::SOL_SOCKET;
+
+#[cfg(target_os = "linux")]
+pub const SO_SNDBUF: i32 = libc::SO_SNDBUF;
+
+#[cfg(target_os = "linux")]
+pub const SO_RCVBUF: i32 = libc::SO_RCVBUF;
+
+#[cfg(target_os = "linux")]
+pub const SO_KEEPALIVE: i32 = libc::SO_KEEPALIVE;
+
+#[cfg(target_os = "linux")]
+pub const SO_BROADCAST: i32 = libc::SO_BROADCAST;
+
+#[cfg(target_os = "linux")]
+pub const SO_MAX_PACING_RATE: i32 = libc::SO_MAX_PACING_RATE;
+
+#[cfg(target_os = "linux")]
+pub const IPPROTO_IP: i32 = libc::IPPROTO_IP;
+
+#[cfg(target_os = "linux")]
+pub const IP_TTL: i32 = libc::IP_TTL;
+
+#[cfg(target_os = "linux")]
+pub const IP_PKTINFO: i32 = libc::IP_PKTINFO;
+
+#[cfg(target_os = "linux")]
+pub const IPPROTO_TCP: i32 = libc::IPPROTO_TCP;
+
+#[cfg(target_os = "linux")]
+pub const TCP_NODELAY: i32 = libc::TCP_NODELAY;
+
+#[cfg(target_os = "linux")]
+pub const TCP_CORK: i32 = libc::TCP_CORK;
+
+#[cfg(target_os = "linux")]
+pub const TCP_QUICKACK: i32 = libc::TCP_QUICKACK;
+
+#[cfg(target_os = "linux")]
+pub const SHUT_RD: i32 = libc::SHUT_RD;
+
+#[cfg(target_os = "linux")]
+pub const SHUT_WR: i32 = libc::SHUT_WR;
+
+#[cfg(target_os = "linux")]
+pub const SHUT_RDWR: i32 = libc::SHUT_RDWR;
@@ -34,6 +34,49 @@ pub unsafe fn set_tcp_nodelay(fd: RawFd) -> i32 {
     )
 }
 
+/// Sets a boolean TCP-level option (e.g. TCP_CORK) in a socket.
+pub unsafe fn set_tcp_flag(fd: RawFd, optname: libc::c_int, flag: bool) -> i32 {
+    let value: u32 = flag as u32;
+    let value_ptr: *const u32 = &value as *const u32;
+    let option_len: libc::socklen_t = mem::size_of_val(&value) as libc::socklen_t;
+    libc::setsockopt(
+        fd,
+        libc::IPPROTO_TCP,
+        optname,
+        value_ptr as *const libc::c_void,
+        option_len,
+    )
+}
+
+/// Gets a boolean TCP-level option (e.g. TCP_CORK) of a socket.
+pub unsafe fn get_tcp_flag(fd: RawFd, optname: libc::c_int, flag: &mut bool) -> i32 {
+    let mut value: u32 = 0;
+    let value_ptr: *mut u32 = &mut value as *mut u32;
+    let mut option_len: libc::socklen_t = mem::size_of_val(&value) as libc::socklen_t;
+    let ret: i32 = libc::getsockopt(
+        fd,
+        libc::IPPROTO_TCP,
+        optname,
+        value_ptr as *mut libc::c_void,
+        &mut option_len as *mut libc::socklen_t,
+    );
+    *flag = value != 0;
+    ret
+}
+
+/// Sets an integer socket-level option (e.g. SO_BUSY_POLL) in a socket.
+pub unsafe fn set_socket_option(fd: RawFd, optname: libc::c_int, value: u32) -> i32 {
+    let value_ptr: *const u32 = &value as *const u32;
+    let option_len: libc::socklen_t = mem::size_of_val(&value) as libc::socklen_t;
+    libc::setsockopt(
+        fd,
+        libc::SOL_SOCKET,
+        optname,
+        value_ptr as *const libc::c_void,
+        option_len,
+    )
+}
+
 /// Sets SO_REUSEPORT option in a socket.
 pub unsafe fn set_so_reuseport(fd: RawFd) -> i32 {
     let value: u32 = 1;
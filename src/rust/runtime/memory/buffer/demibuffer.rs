@@ -13,10 +13,18 @@
 // Note: if compiled without the "libdpdk" feature defined, the DPDK-specific functionality won't be present.
 
 // Note on buffer chain support:
-// DPDK has a concept of MBuf chaining where multiple MBufs may be linked together to form a "packet".  While the
-// DemiBuffer routines for heap-allocated buffers also now support this functionality, it isn't yet exposed via the
-// DemiBuffer interface.
-// ToDo: Expose buffer chain support once we have a solid use case.
+// DPDK has a concept of MBuf chaining where multiple MBufs may be linked together to form a "packet".  The DemiBuffer
+// routines for heap-allocated buffers also support this functionality, and it is exposed via the append(), segments()
+// and into_segments() routines, which back multi-segment scatter-gather arrays.  Note that len(), adjust(), trim(),
+// split_off() and the Deref implementations still only operate on the first segment of a chain (this matches the
+// corresponding DPDK routines).
+// ToDo: Support chaining of DPDK-allocated buffers.
+
+// Note on foreign buffers:
+// Heap-allocated buffers may also reside in memory that is owned elsewhere (e.g. the frames of a UMEM that received
+// data is written to), in which case their MetaData is laid out in front of the data, as for buffers allocated off of
+// the heap.  Instead of being freed, such memory is handed back to its owner once the last reference to it is dropped
+// (see the "from_foreign" constructor).
 
 // Note on intrusive queueing:
 // Since all DemiBuffer types keep the metadata for each "view" in a separate allocated region, they can be queued
@@ -48,6 +56,7 @@ use ::std::{
         handle_alloc_error,
         Layout,
     },
+    ffi::c_void,
     marker::PhantomData,
     mem::{
         self,
@@ -149,6 +158,23 @@ const _: () = assert!(std::mem::size_of::<MetaData>() == 2 * arch::CPU_DATA_CACH
 // points to another MetaData's directly attached data.
 const METADATA_F_INDIRECT: u64 = 1 << 62;
 
+// Indicates this MetaData struct doesn't have the actual data directly attached, but rather this MetaData's buf_addr
+// points to externally-owned memory (e.g. memory registered by the application).  External data is neither reference
+// counted nor freed.
+const METADATA_F_EXTERNAL: u64 = 1 << 61;
+
+// Indicates this MetaData struct and its directly attached data reside in memory that is owned elsewhere, rather than
+// in an allocation off of the heap.  Its _shinfo field points to the ForeignInfo of the owner of that memory.
+const METADATA_F_FOREIGN: u64 = 1 << 60;
+
+/// Owner of memory that foreign [DemiBuffer]s reside in, akin to the shared information of external MBufs in DPDK
+/// (see [DemiBuffer::from_foreign]). Once the last reference to such a buffer is dropped, `release` is called with
+/// `opaque` and the address of the memory of the buffer, so that its owner takes it back.
+pub struct ForeignInfo {
+    pub release: unsafe fn(*const c_void, NonNull<u8>),
+    pub opaque: *const c_void,
+}
+
 impl MetaData {
     // Note on Reference Counts:
     // Since we are currently single-threaded, there is no need to use atomic operations for refcnt manipulations.
@@ -243,6 +269,9 @@ pub struct DemiBuffer {
 unsafe impl Send for DemiBuffer {}
 
 impl DemiBuffer {
+    /// Number of bytes that foreign memory must reserve in front of the data of a `DemiBuffer`, for its MetaData.
+    pub const FOREIGN_HEADROOM: usize = size_of::<MetaData>();
+
     // ------------
     // Constructors
     // ------------
@@ -301,6 +330,55 @@ impl DemiBuffer {
         }
     }
 
+    /// Creates a new Heap-allocated `DemiBuffer` that resides in foreign memory, without copying its data.
+    // The MetaData is laid out at `base`, which must be aligned on a cache line, and the `len` bytes of data start
+    // `data_off` bytes after the `buf_len` bytes of headroom that follow it.  Once the last reference to the data is
+    // dropped, `base` is handed back through `info`.
+    // Note: It is the caller's responsibility to guarantee that the memory remains valid until it is handed back, and
+    // that `info` outlives it, which is why this function is marked "unsafe".
+    pub unsafe fn from_foreign(
+        base: NonNull<u8>,
+        buf_len: u16,
+        data_off: u16,
+        len: u16,
+        info: NonNull<ForeignInfo>,
+    ) -> Self {
+        debug_assert_eq!(base.as_ptr() as usize % arch::CPU_DATA_CACHE_LINE_SIZE, 0);
+        debug_assert!(data_off as usize + len as usize <= buf_len as usize);
+        let mut temp: NonNull<MetaData> = base.cast::<MetaData>();
+
+        // Initialize the MetaData.
+        {
+            // Safety: This is safe, as temp is aligned, dereferenceable, and metadata isn't aliased in this block.
+            let metadata: &mut MetaData = temp.as_mut();
+
+            // The data follows the MetaData struct, as for buffers allocated off of the heap.
+            metadata.buf_addr = base.as_ptr().add(size_of::<MetaData>());
+
+            // Set field values as appropriate.
+            metadata.data_off = data_off;
+            metadata.refcnt = 1;
+            metadata.nb_segs = 1;
+            metadata.ol_flags = METADATA_F_FOREIGN;
+            metadata.pkt_len = len as u32;
+            metadata.data_len = len;
+            metadata.buf_len = buf_len;
+            metadata.next = None;
+            metadata._pool = 0;
+            metadata._priv_size = 0;
+            metadata._shinfo = info.as_ptr() as u64;
+        }
+
+        // Embed the buffer type into the lower bits of the pointer.
+        let tagged: NonNull<MetaData> = temp.with_addr(temp.addr() | Tag::Heap);
+
+        // Return the new DemiBuffer.
+        DemiBuffer {
+            tagged_ptr: tagged,
+            _phantom: PhantomData,
+        }
+    }
+
     /// Create a new Heap-allocated `DemiBuffer` from a byte slice.
     pub fn from_slice(slice: &[u8]) -> Result<Self, Fail> {
         // Note: The implementation of the TryFrom trait (see below, under "Trait Implementations") automatically
@@ -332,10 +410,56 @@ impl DemiBuffer {
         }
     }
 
+    /// Creates a new Heap-allocated `DemiBuffer` that refers to externally-owned memory, without copying it.
+    // The external memory is neither reference counted nor freed by the DemiBuffer (or any of its clones).
+    // Note: It is the caller's responsibility to guarantee that the memory remains valid for the lifetime of the
+    // returned DemiBuffer and all of its clones, which is why this function is marked "unsafe".
+    pub unsafe fn from_external(data: NonNull<u8>, len: u16) -> Self {
+        // Allocate space for a new MetaData struct without any direct data.
+        let mut temp: NonNull<MetaData> = allocate_metadata_data(0);
+
+        // Initialize the MetaData.
+        {
+            // Safety: This is safe, as temp is aligned, dereferenceable, and metadata isn't aliased in this block.
+            let metadata: &mut MetaData = temp.as_mut();
+
+            // Point buf_addr at the external data.
+            metadata.buf_addr = data.as_ptr();
+
+            // Set field values as appropriate.
+            metadata.data_off = 0;
+            metadata.refcnt = 1;
+            metadata.nb_segs = 1;
+            metadata.ol_flags = METADATA_F_EXTERNAL;
+            metadata.pkt_len = len as u32;
+            metadata.data_len = len;
+            metadata.buf_len = len;
+            metadata.next = None;
+        }
+
+        // Embed the buffer type into the lower bits of the pointer.
+        let tagged: NonNull<MetaData> = temp.with_addr(temp.addr() | Tag::Heap);
+
+        // Return the new DemiBuffer.
+        DemiBuffer {
+            tagged_ptr: tagged,
+            _phantom: PhantomData,
+        }
+    }
+
     // ----------------
     // Public Functions
     // ----------------
 
+    /// Returns `true` if this `DemiBuffer` refers to externally-owned memory, and `false` otherwise.
+    pub fn is_external(&self) -> bool {
+        match self.get_tag() {
+            Tag::Heap => self.as_metadata().ol_flags & METADATA_F_EXTERNAL != 0,
+            #[cfg(feature = "libdpdk")]
+            Tag::Dpdk => false,
+        }
+    }
+
     /// Returns `true` if this `DemiBuffer` was allocated off of the heap, and `false` otherwise.
     pub fn is_heap_allocated(&self) -> bool {
         self.get_tag() == Tag::Heap
@@ -474,6 +598,121 @@ impl DemiBuffer {
         Ok(back_half)
     }
 
+    /// Returns the number of segments in the `DemiBuffer` chain.
+    pub fn num_segments(&self) -> usize {
+        // ToDo: Review having this "match", since MetaData and MBuf are laid out the same, these are equivalent cases.
+        match self.get_tag() {
+            Tag::Heap => self.as_metadata().nb_segs as usize,
+            #[cfg(feature = "libdpdk")]
+            Tag::Dpdk => {
+                let mbuf: *mut rte_mbuf = self.as_mbuf();
+                // Safety: The `mbuf` dereference below is safe, as it is aligned and dereferenceable.
+                unsafe { (*mbuf).nb_segs as usize }
+            },
+        }
+    }
+
+    /// Appends the segments of `tail` to the end of the `DemiBuffer` chain.
+    // Note: Only heap-allocated DemiBuffers can currently be chained together.
+    pub fn append(&mut self, tail: DemiBuffer) -> Result<(), Fail> {
+        if self.get_tag() != Tag::Heap || tail.get_tag() != Tag::Heap {
+            return Err(Fail::new(libc::ENOTSUP, "only heap-allocated buffers can be chained"));
+        }
+
+        // Update the segment count and total length of the chain.
+        {
+            let md_tail: &MetaData = tail.as_metadata();
+            let md_first: &mut MetaData = self.as_metadata();
+            let nb_segs: usize = md_first.nb_segs as usize + md_tail.nb_segs as usize;
+            if nb_segs > u16::MAX as usize {
+                return Err(Fail::new(libc::EINVAL, "too many segments in buffer chain"));
+            }
+            // The above check means that nb_segs fits in a u16.  So this cast is safe.
+            md_first.nb_segs = nb_segs as u16;
+            md_first.pkt_len += md_tail.pkt_len;
+        }
+
+        // Link the tail to the last segment of the chain.  The chain takes over the tail's reference.
+        let md_last: &mut MetaData = self.as_metadata().get_last_segment();
+        md_last.next = Some(tail.get_ptr::<MetaData>());
+        mem::forget(tail);
+
+        Ok(())
+    }
+
+    /// Returns the data held by each segment of the `DemiBuffer` chain.
+    pub fn segments(&self) -> Vec<&[u8]> {
+        let mut segments: Vec<&[u8]> = Vec::with_capacity(self.num_segments());
+
+        // Since MetaData and MBuf have equivalent layouts for the buf_addr, data_off, data_len, and next fields, we
+        // walk both types of buffer chains in the same way.
+        let mut next_entry: Option<NonNull<MetaData>> = Some(self.get_ptr::<MetaData>());
+        while let Some(entry) = next_entry {
+            // Safety: This is safe, as `entry` is aligned, dereferenceable, and the MetaData struct it points to is
+            // initialized.
+            let metadata: &MetaData = unsafe { entry.as_ref() };
+            if metadata.data_len == 0 {
+                segments.push(&[]);
+            } else {
+                // Safety: The call to offset is safe, as its argument is known to remain within the allocated region.
+                // Safety: The call to from_raw_parts is safe, as its arguments refer to a valid readable memory region
+                // of the size specified and is contained within a single allocated object.
+                segments.push(unsafe {
+                    slice::from_raw_parts(
+                        metadata.buf_addr.offset(metadata.data_off as isize),
+                        metadata.data_len as usize,
+                    )
+                });
+            }
+            next_entry = metadata.next;
+        }
+
+        segments
+    }
+
+    /// Consumes the `DemiBuffer` chain, returning each of its segments as a separate `DemiBuffer`.
+    pub fn into_segments(self) -> Result<Vec<DemiBuffer>, Fail> {
+        if self.num_segments() == 1 {
+            return Ok(vec![self]);
+        }
+
+        match self.get_tag() {
+            Tag::Heap => {
+                let mut segments: Vec<DemiBuffer> = Vec::with_capacity(self.num_segments());
+                let mut next_entry: Option<NonNull<MetaData>> = Some(self.get_ptr::<MetaData>());
+
+                // The segments take over the references held by the chain.
+                mem::forget(self);
+
+                while let Some(mut entry) = next_entry {
+                    // Safety: This is safe, as `entry` is aligned, dereferenceable, and the MetaData struct it points
+                    // to is initialized.
+                    let metadata: &mut MetaData = unsafe { entry.as_mut() };
+
+                    // Unlink this segment from the chain.
+                    next_entry = metadata.next;
+                    metadata.next = None;
+                    metadata.nb_segs = 1;
+                    metadata.pkt_len = metadata.data_len as u32;
+
+                    // Embed the buffer type into the lower bits of the pointer.
+                    let tagged: NonNull<MetaData> = entry.with_addr(entry.addr() | Tag::Heap);
+                    segments.push(DemiBuffer {
+                        tagged_ptr: tagged,
+                        _phantom: PhantomData,
+                    });
+                }
+
+                Ok(segments)
+            },
+            #[cfg(feature = "libdpdk")]
+            Tag::Dpdk => Err(Fail::new(
+                libc::ENOTSUP,
+                "cannot split chains of DPDK-allocated buffers",
+            )),
+        }
+    }
+
     /// Provides a raw pointer to the buffer data.
     ///
     /// The reference count is not affected in any way and the DemiBuffer is not consumed.  The pointer is valid for as
@@ -511,6 +750,15 @@ impl DemiBuffer {
         }
     }
 
+    /// Gets the contained MBuf pointer, without consuming the `DemiBuffer`. The MBuf must not outlive it.
+    #[cfg(feature = "libdpdk")]
+    pub fn get_mbuf(&self) -> Option<*mut rte_mbuf> {
+        match self.get_tag() {
+            Tag::Dpdk => Some(self.as_mbuf()),
+            _ => None,
+        }
+    }
+
     // ------------------
     // Internal Functions
     // ------------------
@@ -611,6 +859,14 @@ fn free_metadata_data(buffer: NonNull<MetaData>) {
     // Safety: This is safe, as `buffer` is aligned, dereferenceable, and we don't let `metadata` escape this function.
     let metadata: &MetaData = unsafe { buffer.as_ref() };
 
+    // Foreign memory is handed back to its owner instead.
+    if metadata.ol_flags & METADATA_F_FOREIGN != 0 {
+        // Safety: This is safe, as the owner of the memory guarantees that its ForeignInfo outlives it.
+        let info: &ForeignInfo = unsafe { &*(metadata._shinfo as *const ForeignInfo) };
+        unsafe { (info.release)(info.opaque, buffer.cast::<u8>()) };
+        return;
+    }
+
     // Check in debug builds that we weren't accidentally passed a DPDK-allocated MBuf to free.
     debug_assert_eq!(metadata._pool, 0);
 
@@ -689,8 +945,14 @@ impl Clone for DemiBuffer {
                             // increment any reference counts.  Instead we just create a new zero-length direct buffer.
                             clone.ol_flags = original.ol_flags;
                             continue;
+                        } else if original.ol_flags & METADATA_F_EXTERNAL != 0 {
+                            // Since external data isn't reference counted, we just create a new buffer that refers to
+                            // the same external data.
+                            clone.ol_flags = original.ol_flags;
+                            continue;
                         } else {
-                            clone.ol_flags = original.ol_flags | METADATA_F_INDIRECT; // Add indirect flag to clone.
+                            // Add indirect flag to clone.  The clone itself is allocated off of the heap.
+                            clone.ol_flags = (original.ol_flags | METADATA_F_INDIRECT) & !METADATA_F_FOREIGN;
                         }
                     }
 
@@ -809,8 +1071,14 @@ impl Drop for DemiBuffer {
 
                     // Decrement the reference count.
                     if metadata.dec_refcnt() == 0 {
-                        // See if the data is directly attached, or indirectly attached.
-                        if metadata.ol_flags & METADATA_F_INDIRECT != 0 {
+                        // See if the data is directly attached, indirectly attached, or external.
+                        if metadata.ol_flags & METADATA_F_EXTERNAL != 0 {
+                            // External data isn't ours to free.  Restore buf_addr and buf_len to their unattached
+                            // values.
+                            metadata.buf_addr = null_mut();
+                            metadata.buf_len = 0;
+                            metadata.ol_flags = metadata.ol_flags & !METADATA_F_EXTERNAL;
+                        } else if metadata.ol_flags & METADATA_F_INDIRECT != 0 {
                             // This is an indirect buffer.  Find the direct buffer that holds the actual data.
                             let offset: isize = -(size_of::<MetaData>() as isize);
                             let direct: &mut MetaData = unsafe {
@@ -920,9 +1188,23 @@ impl TryFrom<&[u8]> for DemiBuffer {
 // Note that due to DPDK being a configurable option, all of these unit tests are only for heap-allocated `DemiBuffer`s.
 #[cfg(test)]
 mod tests {
-    use super::DemiBuffer;
+    use super::{
+        DemiBuffer,
+        ForeignInfo,
+    };
     use crate::runtime::fail::Fail;
-    use std::ptr::NonNull;
+    use std::{
+        alloc::{
+            self,
+            Layout,
+        },
+        cell::Cell,
+        ffi::c_void,
+        ptr::{
+            self,
+            NonNull,
+        },
+    };
 
     // Test basic allocation, len, adjust, and trim.
     #[test]
@@ -1036,4 +1318,127 @@ mod tests {
         assert_eq!(&*split_buf, split_str.as_bytes());
         assert_eq!(&*another_buf, another_str.as_bytes());
     }
+
+    // Test chaining buffers together and splitting chains apart.
+    #[test]
+    fn chain() {
+        // Create three (heap-allocated) `DemiBuffer`s with distinct contents.
+        let first: DemiBuffer = DemiBuffer::from_slice(b"first").expect("buffer should be valid");
+        let second: DemiBuffer = DemiBuffer::from_slice(b"second").expect("buffer should be valid");
+        let third: DemiBuffer = DemiBuffer::from_slice(b"third").expect("buffer should be valid");
+
+        // Chain them together.  The first segment should be unaffected.
+        let mut chain: DemiBuffer = first;
+        assert!(chain.append(second).is_ok());
+        assert!(chain.append(third).is_ok());
+        assert_eq!(chain.num_segments(), 3);
+        assert_eq!(chain.len(), 5);
+        assert_eq!(&*chain, b"first");
+
+        // Inspect each segment of the chain.
+        let segments: Vec<&[u8]> = chain.segments();
+        assert_eq!(segments, vec![&b"first"[..], &b"second"[..], &b"third"[..]]);
+
+        // Clone the chain, so that we can check that clones are unaffected by splitting the original.
+        let clone: DemiBuffer = chain.clone();
+        assert_eq!(clone.num_segments(), 3);
+
+        // Split the chain into its segments.
+        let result: Result<Vec<DemiBuffer>, Fail> = chain.into_segments();
+        assert!(result.is_ok());
+        let bufs: Vec<DemiBuffer> = result.expect("DemiBuffer::into_segments shouldn't fail for heap buffers");
+        assert_eq!(bufs.len(), 3);
+        assert_eq!(&*bufs[0], b"first");
+        assert_eq!(&*bufs[1], b"second");
+        assert_eq!(&*bufs[2], b"third");
+        for buf in bufs.iter() {
+            assert_eq!(buf.num_segments(), 1);
+        }
+
+        // Compare contents of the clone.
+        assert_eq!(clone.segments(), vec![&b"first"[..], &b"second"[..], &b"third"[..]]);
+    }
+
+    // Test buffers that refer to externally-owned memory.
+    #[test]
+    fn external() {
+        let mut data: Vec<u8> = b"externally-owned data".to_vec();
+        let len: usize = data.len();
+        // This unwrap won't panic, as the pointer of a Vec is never null.
+        let ptr: NonNull<u8> = NonNull::new(data.as_mut_ptr()).unwrap();
+
+        // Create a `DemiBuffer` that refers to the external data.
+        // Safety: `data` outlives the `DemiBuffer` and all of its clones.
+        let mut buf: DemiBuffer = unsafe { DemiBuffer::from_external(ptr, len as u16) };
+        assert!(buf.is_heap_allocated());
+        assert!(buf.is_external());
+        assert_eq!(buf.len(), len);
+        assert_eq!(buf.as_ptr(), data.as_ptr());
+
+        // Clones and splits should refer to the same external data.
+        let clone: DemiBuffer = buf.clone();
+        assert!(clone.is_external());
+        assert_eq!(clone.as_ptr(), data.as_ptr());
+        let result: Result<DemiBuffer, Fail> = buf.split_off(8);
+        assert!(result.is_ok());
+        let split_buf: DemiBuffer = result.expect("DemiBuffer::split_off shouldn't fail for this offset");
+        assert_eq!(&*buf, b"external");
+        assert_eq!(&*split_buf, b"ly-owned data");
+
+        // Dropping the buffers should leave the external data untouched.
+        drop(buf);
+        drop(clone);
+        drop(split_buf);
+        assert_eq!(&data[..], b"externally-owned data");
+    }
+
+    // Test buffers that reside in foreign memory.
+    #[test]
+    fn foreign() {
+        unsafe fn release(opaque: *const c_void, base: NonNull<u8>) {
+            let released: &Cell<Option<NonNull<u8>>> = &*(opaque as *const Cell<Option<NonNull<u8>>>);
+            assert!(released.replace(Some(base)).is_none());
+        }
+
+        // Lay out some data in memory that leaves room for the MetaData in front of it.
+        let layout: Layout = Layout::from_size_align(256, 64).unwrap();
+        // Safety: the layout has a non-zero size.
+        let base: NonNull<u8> = NonNull::new(unsafe { alloc::alloc_zeroed(layout) }).unwrap();
+        let data: &[u8] = b"foreign data";
+        unsafe {
+            ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                base.as_ptr().add(DemiBuffer::FOREIGN_HEADROOM + 16),
+                data.len(),
+            )
+        };
+        let released: Cell<Option<NonNull<u8>>> = Cell::new(None);
+        let info: ForeignInfo = ForeignInfo {
+            release,
+            opaque: &released as *const Cell<Option<NonNull<u8>>> as *const c_void,
+        };
+
+        // Create a `DemiBuffer` that resides in the memory.
+        // Safety: the memory and `info` outlive the `DemiBuffer` and all of its clones.
+        let buf_len: u16 = (256 - DemiBuffer::FOREIGN_HEADROOM) as u16;
+        let mut buf: DemiBuffer =
+            unsafe { DemiBuffer::from_foreign(base, buf_len, 16, data.len() as u16, NonNull::from(&info)) };
+        assert!(buf.is_heap_allocated());
+        assert!(!buf.is_external());
+        assert_eq!(&*buf, data);
+
+        // Clones and splits refer to the same data, which is only handed back once the last of them is dropped.
+        let clone: DemiBuffer = buf.clone();
+        let result: Result<DemiBuffer, Fail> = buf.split_off(8);
+        let split_buf: DemiBuffer = result.expect("DemiBuffer::split_off shouldn't fail for this offset");
+        assert_eq!(&*buf, b"foreign ");
+        assert_eq!(&*split_buf, b"data");
+        drop(buf);
+        drop(split_buf);
+        assert!(released.get().is_none());
+        drop(clone);
+        assert_eq!(released.get(), Some(base));
+
+        unsafe { alloc::dealloc(base.as_ptr(), layout) };
+    }
 }
@@ -24,7 +24,10 @@ use ::std::fmt::Debug;
 pub use self::dpdkbuffer::DPDKBuffer;
 pub use self::{
     databuffer::DataBuffer,
-    demibuffer::DemiBuffer,
+    demibuffer::{
+        DemiBuffer,
+        ForeignInfo,
+    },
 };
 
 //==============================================================================
@@ -9,7 +9,20 @@ mod buffer;
 
 use crate::runtime::{
     fail::Fail,
-    types::demi_sgarray_t,
+    types::{
+        demi_sgarray_t,
+        demi_sgaseg_t,
+        DEMI_SGARRAY_MAXLEN,
+    },
+};
+use ::libc::c_void;
+use ::std::{
+    cmp,
+    mem,
+    ptr::{
+        self,
+        NonNull,
+    },
 };
 
 //==============================================================================
@@ -24,15 +37,269 @@ pub use self::buffer::*;
 
 /// Memory Runtime
 pub trait MemoryRuntime {
-    /// Creates a [demi_sgarray_t] from a [DemiBuffer].
-    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail>;
+    /// Converts a runtime buffer into a scatter-gather array. Each segment of a buffer chain is exposed as a separate
+    /// scatter-gather array segment.
+    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
+        let segments: Vec<&[u8]> = buf.segments();
+        if segments.len() > DEMI_SGARRAY_MAXLEN {
+            return Err(Fail::new(libc::EINVAL, "too many segments for a demi_sgarray_t"));
+        }
+
+        // Create a scatter-gather segment to expose each segment of the DemiBuffer to the user.
+        let mut sga_segs: [demi_sgaseg_t; DEMI_SGARRAY_MAXLEN] = [demi_sgaseg_t {
+            sgaseg_buf: ptr::null_mut(),
+            sgaseg_len: 0,
+        }; DEMI_SGARRAY_MAXLEN];
+        for (sga_seg, segment) in sga_segs.iter_mut().zip(segments.iter()) {
+            sga_seg.sgaseg_buf = segment.as_ptr() as *mut c_void;
+            sga_seg.sgaseg_len = segment.len() as u32;
+        }
+        let numsegs: u32 = segments.len() as u32;
+
+        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
+        Ok(demi_sgarray_t {
+            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
+            sga_numsegs: numsegs,
+            sga_segs,
+            sga_addr: unsafe { mem::zeroed() },
+            sga_pktinfo: unsafe { mem::zeroed() },
+            #[cfg(target_os = "linux")]
+            sga_unaddr: unsafe { mem::zeroed() },
+        })
+    }
+
+    /// Allocates a scatter-gather array. Sizes that do not fit in a single buffer are backed by a buffer chain.
+    fn alloc_sgarray(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        let buf: DemiBuffer = alloc_buffer_chain(size)?;
+        self.into_sgarray(buf)
+    }
+
+    /// Registers application-owned memory as a scatter-gather array, so that it can be pushed without being copied.
+    /// The memory must remain valid until the scatter-gather array is released and all pushes of it have completed.
+    // ToDo: Register the memory with the underlying I/O subsystem (e.g. io_uring fixed buffers or DPDK external
+    // memory) so that transmission avoids copies all the way down to the device.
+    fn register_sgarray(&self, data: *mut u8, len: usize) -> Result<demi_sgarray_t, Fail> {
+        // Check arguments.
+        if data.is_null() {
+            return Err(Fail::new(libc::EINVAL, "cannot register null memory"));
+        }
+        if len == 0 || len > DEMI_SGARRAY_MAXLEN * u16::MAX as usize {
+            return Err(Fail::new(libc::EINVAL, "invalid size for registered memory"));
+        }
+
+        // Expose the memory through a chain of external buffers, each of which is at most u16::MAX bytes long.
+        let mut chain: Option<DemiBuffer> = None;
+        let mut offset: usize = 0;
+        while offset < len {
+            let seg_len: usize = (len - offset).min(u16::MAX as usize);
+            // Safety: The call to add is safe, as `offset` is within the memory region described by the caller.
+            // Safety: The call to NonNull::new_unchecked is safe, as `data` was checked for null above.
+            let seg_data: NonNull<u8> = unsafe { NonNull::new_unchecked(data.add(offset)) };
+            // Safety: The caller guarantees that the memory remains valid while it is registered.
+            let seg: DemiBuffer = unsafe { DemiBuffer::from_external(seg_data, seg_len as u16) };
+            match chain.as_mut() {
+                Some(buf) => buf.append(seg)?,
+                None => chain = Some(seg),
+            }
+            offset += seg_len;
+        }
+
+        // This unwrap won't panic, as `len` is non-zero and thus at least one segment was created.
+        self.into_sgarray(chain.unwrap())
+    }
+
+    /// Releases a scatter-gather array.
+    fn free_sgarray(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        // Check arguments.
+        if sga.sga_numsegs == 0 || sga.sga_numsegs as usize > DEMI_SGARRAY_MAXLEN {
+            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+        }
+
+        if sga.sga_buf == ptr::null_mut() {
+            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+        }
+
+        // Convert back to a DemiBuffer and drop it.
+        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
+        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
+        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
+        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
+        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
+        drop(buf);
+
+        Ok(())
+    }
+
+    /// Clones each segment of a scatter-gather array into a separate DemiBuffer.
+    fn clone_sgarray_segments(&self, sga: &demi_sgarray_t) -> Result<Vec<DemiBuffer>, Fail> {
+        // Check arguments.
+        if sga.sga_numsegs == 0 || sga.sga_numsegs as usize > DEMI_SGARRAY_MAXLEN {
+            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+        }
+
+        if sga.sga_buf == ptr::null_mut() {
+            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+        }
+
+        // Convert back to a DemiBuffer.
+        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
+        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
+        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
+        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
+        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
+        let clone: DemiBuffer = buf.clone();
+
+        // Don't drop buf, as it holds the same reference to the data as the sgarray (which should keep it).
+        mem::forget(buf);
+
+        // Split the clone into its segments, which should match the segments that we provided to the user.
+        let mut clones: Vec<DemiBuffer> = clone.into_segments()?;
+        if clones.len() != sga.sga_numsegs as usize {
+            return Err(Fail::new(
+                libc::EINVAL,
+                "demi_sgarray_t segment count does not match backing buffer",
+            ));
+        }
+
+        for (clone, sga_seg) in clones.iter_mut().zip(sga.sga_segs.iter()) {
+            adjust_to_sgaseg(clone, sga_seg)?;
+        }
+
+        Ok(clones)
+    }
+
+    /// Clones a scatter-gather array into a DemiBuffer. Multi-segment scatter-gather arrays are coalesced into a
+    /// single buffer.
+    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+        let clones: Vec<DemiBuffer> = self.clone_sgarray_segments(sga)?;
+        coalesce_buffers(clones)
+    }
+
+    /// Clones multiple [demi_sgarray_t]s into [DemiBuffer]s, one per segment. None of them may be empty.
+    fn clone_sgarrays(&self, sgas: &[demi_sgarray_t]) -> Result<Vec<DemiBuffer>, Fail> {
+        if sgas.is_empty() {
+            return Err(Fail::new(libc::EINVAL, "no scatter-gather arrays"));
+        }
+
+        let mut bufs: Vec<DemiBuffer> = Vec::with_capacity(sgas.len());
+        for sga in sgas {
+            for buf in self.clone_sgarray_segments(sga)? {
+                if buf.len() == 0 {
+                    return Err(Fail::new(libc::EINVAL, "zero-length buffer"));
+                }
+                bufs.push(buf);
+            }
+        }
+
+        Ok(bufs)
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Allocates a (heap-allocated) buffer chain that is large enough to hold `size` bytes.
+pub fn alloc_buffer_chain(size: usize) -> Result<DemiBuffer, Fail> {
+    // We can't allocate more than the maximum number of segments.
+    if size > DEMI_SGARRAY_MAXLEN * u16::MAX as usize {
+        return Err(Fail::new(libc::EINVAL, "size too large for a demi_sgarray_t"));
+    }
+
+    let mut remaining: usize = size;
+    let mut buf: DemiBuffer = DemiBuffer::new(remaining.min(u16::MAX as usize) as u16);
+    remaining -= buf.len();
+    while remaining > 0 {
+        let tail: DemiBuffer = DemiBuffer::new(remaining.min(u16::MAX as usize) as u16);
+        remaining -= tail.len();
+        buf.append(tail)?;
+    }
+
+    Ok(buf)
+}
+
+/// Coalesces buffers into a single one, copying their data unless there is a single buffer.
+pub fn coalesce_buffers(mut bufs: Vec<DemiBuffer>) -> Result<DemiBuffer, Fail> {
+    if bufs.len() == 1 {
+        return Ok(bufs.remove(0));
+    }
+
+    let len: usize = bufs.iter().map(|buf| buf.len()).sum();
+    if len > u16::MAX as usize {
+        return Err(Fail::new(libc::EINVAL, "buffers too large to coalesce"));
+    }
+    let mut coalesced: DemiBuffer = DemiBuffer::new(len as u16);
+    let mut offset: usize = 0;
+    for buf in bufs.iter() {
+        coalesced[offset..(offset + buf.len())].copy_from_slice(&buf[..]);
+        offset += buf.len();
+    }
+
+    Ok(coalesced)
+}
+
+/// Copies data into the segments of a scatter-gather array.
+pub fn copy_to_sgarray(sga: &demi_sgarray_t, data: &[u8]) {
+    let mut offset: usize = 0;
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let len: usize = cmp::min(seg.sgaseg_len as usize, data.len() - offset);
+        // Safety: The segment describes a buffer of `sgaseg_len` bytes that was allocated by the runtime.
+        unsafe { ptr::copy_nonoverlapping(data[offset..].as_ptr(), seg.sgaseg_buf as *mut u8, len) };
+        offset += len;
+    }
+}
+
+/// Copies data out of the segments of a scatter-gather array, returning the number of bytes that were copied.
+pub fn copy_from_sgarray(sga: &demi_sgarray_t, data: &mut [u8]) -> usize {
+    let mut offset: usize = 0;
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let len: usize = cmp::min(seg.sgaseg_len as usize, data.len() - offset);
+        // Safety: The segment describes a buffer of `sgaseg_len` bytes that was handed out by the LibOS.
+        unsafe { ptr::copy_nonoverlapping(seg.sgaseg_buf as *const u8, data[offset..].as_mut_ptr(), len) };
+        offset += len;
+    }
+    offset
+}
+
+/// Adjusts a cloned buffer to match the user's changes to the scatter-gather segment that describes it.
+fn adjust_to_sgaseg(clone: &mut DemiBuffer, sga_seg: &demi_sgaseg_t) -> Result<(), Fail> {
+    // Check to see if the user has reduced the size of the buffer described by the sgarray segment since we
+    // provided it to them.  They could have increased the starting address of the buffer (`sgaseg_buf`),
+    // decreased the ending address of the buffer (`sgaseg_buf + sgaseg_len`), or both.
+    let sga_data: *const u8 = sga_seg.sgaseg_buf as *const u8;
+    let sga_len: usize = sga_seg.sgaseg_len as usize;
+    let clone_data: *const u8 = clone.as_ptr();
+    let mut clone_len: usize = clone.len();
+
+    // There is nothing to adjust in zero-length segments.
+    if sga_len == 0 && clone_len == 0 {
+        return Ok(());
+    }
+
+    if sga_data != clone_data || sga_len != clone_len {
+        // We need to adjust the DemiBuffer to match the user's changes.
+
+        // First check that the user didn't do something non-sensical, like change the buffer description to
+        // reference address space outside of the allocated memory area.
+        if sga_data < clone_data || sga_data.addr() + sga_len > clone_data.addr() + clone_len {
+            return Err(Fail::new(
+                libc::EINVAL,
+                "demi_sgarray_t describes data outside backing buffer's allocated region",
+            ));
+        }
+
+        // Calculate the amount the new starting address is ahead of the old.  And then adjust `clone` to match.
+        let adjustment_amount: usize = sga_data.addr() - clone_data.addr();
+        clone.adjust(adjustment_amount)?;
 
-    /// Allocates a [demi_sgarray_t].
-    fn alloc_sgarray(&self, size: usize) -> Result<demi_sgarray_t, Fail>;
+        // An adjustment above would have reduced clone.len() by the adjustment amount.
+        clone_len -= adjustment_amount;
+        debug_assert_eq!(clone_len, clone.len());
 
-    /// Releases a [demi_sgarray_t].
-    fn free_sgarray(&self, sga: demi_sgarray_t) -> Result<(), Fail>;
+        // Trim the clone down to size.
+        let trim_amount: usize = clone_len - sga_len;
+        clone.trim(trim_amount)?;
+    }
 
-    /// Clones a [demi_sgarray_t] into a [DemiBuffer].
-    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail>;
+    Ok(())
 }
@@ -5,7 +5,10 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::network::types::MacAddress;
+use crate::runtime::network::types::{
+    MacAddress,
+    Route,
+};
 use ::std::{
     collections::HashMap,
     net::Ipv4Addr,
@@ -29,6 +32,10 @@ pub struct ArpConfig {
     initial_values: HashMap<Ipv4Addr, MacAddress>,
     /// Disable ARP?
     disable_arp: bool,
+    /// Number of ARP Announcements on Startup
+    announce_count: usize,
+    /// Static Routes, used to pick the next hop to resolve
+    routes: Vec<Route>,
 }
 
 //==============================================================================
@@ -44,6 +51,8 @@ impl ArpConfig {
         retry_count: Option<usize>,
         initial_values: Option<HashMap<Ipv4Addr, MacAddress>>,
         disable_arp: Option<bool>,
+        announce_count: Option<usize>,
+        routes: Option<Vec<Route>>,
     ) -> Self {
         let mut config: ArpConfig = Self::default();
 
@@ -62,6 +71,12 @@ impl ArpConfig {
         if let Some(disable_arp) = disable_arp {
             config.set_disable_arp(disable_arp);
         }
+        if let Some(announce_count) = announce_count {
+            config.set_announce_count(announce_count);
+        }
+        if let Some(routes) = routes {
+            config.set_routes(routes);
+        }
 
         config
     }
@@ -91,18 +106,28 @@ impl ArpConfig {
         self.disable_arp
     }
 
+    /// Gets the number of ARP announcements that are broadcast on startup in the target [ArpConfig].
+    pub fn get_announce_count(&self) -> usize {
+        self.announce_count
+    }
+
+    /// Gets the static routes in the target [ArpConfig].
+    pub fn get_routes(&self) -> &[Route] {
+        &self.routes
+    }
+
     /// Sets the time to live for entries of the ARP Cache in the target [ArpConfig].
-    fn set_cache_ttl(&mut self, cache_ttl: Duration) {
+    pub fn set_cache_ttl(&mut self, cache_ttl: Duration) {
         self.cache_ttl = cache_ttl
     }
 
     /// Sets the request timeout for ARP requests in the target [ArpConfig].
-    fn set_request_timeout(&mut self, request_timeout: Duration) {
+    pub fn set_request_timeout(&mut self, request_timeout: Duration) {
         self.request_timeout = request_timeout
     }
 
     /// Sets the retry count for ARP requests in the target [ArpConfig].
-    fn set_retry_count(&mut self, retry_count: usize) {
+    pub fn set_retry_count(&mut self, retry_count: usize) {
         self.retry_count = retry_count
     }
 
@@ -115,6 +140,16 @@ impl ArpConfig {
     fn set_disable_arp(&mut self, disable_arp: bool) {
         self.disable_arp = disable_arp
     }
+
+    /// Sets the number of ARP announcements that are broadcast on startup in the target [ArpConfig].
+    fn set_announce_count(&mut self, announce_count: usize) {
+        self.announce_count = announce_count
+    }
+
+    /// Sets the static routes in the target [ArpConfig].
+    fn set_routes(&mut self, routes: Vec<Route>) {
+        self.routes = routes
+    }
 }
 
 //==============================================================================
@@ -131,6 +166,8 @@ impl Default for ArpConfig {
             retry_count: 5,
             initial_values: HashMap::new(),
             disable_arp: false,
+            announce_count: 2,
+            routes: Vec::new(),
         }
     }
 }
@@ -156,5 +193,7 @@ mod tests {
         assert_eq!(config.get_retry_count(), 5);
         assert_eq!(config.get_initial_values(), &HashMap::new());
         assert_eq!(config.get_disable_arp(), false);
+        assert_eq!(config.get_announce_count(), 2);
+        assert!(config.get_routes().is_empty());
     }
 }
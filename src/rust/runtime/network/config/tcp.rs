@@ -5,10 +5,18 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::network::consts::{
-    DEFAULT_MSS,
-    MAX_MSS,
-    MIN_MSS,
+use crate::runtime::network::{
+    consts::{
+        DEFAULT_MTU,
+        IPV4_TCP_HEADER_SIZE,
+        MAX_MSS,
+        MIN_MSS,
+    },
+    types::{
+        CongestionControlAlgorithm,
+        OutOfOrderDropPolicy,
+        Rss,
+    },
 };
 use ::std::time::Duration;
 
@@ -17,10 +25,14 @@ use ::std::time::Duration;
 //==============================================================================
 
 /// TCP Configuration Descriptor
+///
+/// Starts from the [Default] values, which are overridden with the setters.
 #[derive(Clone, Debug)]
 pub struct TcpConfig {
-    /// Advertised Maximum Segment Size
-    advertised_mss: usize,
+    /// Maximum Transmission Unit of the Interface
+    mtu: usize,
+    /// Advertised Maximum Segment Size (Derived From the MTU Unless Overridden)
+    advertised_mss: Option<usize>,
     /// Number of Retries for TCP Handshake Algorithm
     handshake_retries: usize,
     /// Timeout for TCP Handshake Algorithm
@@ -31,10 +43,44 @@ pub struct TcpConfig {
     window_scale: u8,
     /// Timeout for Delayed ACKs
     ack_delay_timeout: Duration,
+    /// Number of Segments Received Before Forcing an ACK
+    ack_segments: u32,
+    /// ACK Every Segment Right Away?
+    quickack: bool,
+    /// Number of Retransmissions Before Giving Up on a Connection
+    max_retransmissions: usize,
+    /// Lower Bound for the Retransmission Timeout
+    rto_min: Duration,
+    /// Upper Bound for the Retransmission Timeout
+    rto_max: Duration,
+    /// Maximum Segment Lifetime (Connections Linger in TIME-WAIT for Twice as Long)
+    msl: Duration,
+    /// Reuse Endpoints in TIME-WAIT for Outgoing Connections?
+    time_wait_reuse: bool,
+    /// Answer Connection Requests With SYN Cookies When the Backlog of a Listener is Full?
+    syn_cookies: bool,
+    /// Maximum Number of Out-of-Order Segments Stored per Connection
+    out_of_order_max_segments: usize,
+    /// Maximum Number of Out-of-Order Bytes Stored per Connection
+    out_of_order_max_bytes: usize,
+    /// What to Drop When the Out-of-Order Store is Full
+    out_of_order_drop_policy: OutOfOrderDropPolicy,
+    /// Shard of Connections Owned by This Instance Among Instances That Share the Local Address (Index, Count)
+    shard: (usize, usize),
+    /// Coalesce In-Order Segments Received in the Same Batch?
+    gro: bool,
+    /// Receive Side Scaling of the Port, if Shards are Steered to Receive Queues by the Hardware
+    rss: Option<Rss>,
     /// Offload Checksum to Hardware When Receiving?
     rx_checksum_offload: bool,
     /// Offload Checksum to Hardware When Sending?
     tx_checksum_offload: bool,
+    /// Default Congestion Control Algorithm
+    congestion_control: CongestionControlAlgorithm,
+    /// Pace Transmissions Based on the Congestion Window?
+    pacing: bool,
+    /// Negotiate Explicit Congestion Notification?
+    ecn: bool,
 }
 
 //==============================================================================
@@ -43,50 +89,15 @@ pub struct TcpConfig {
 
 /// Associate Functions for TCP Configuration Descriptor
 impl TcpConfig {
-    /// Creates a TCP Configuration Descriptor.
-    pub fn new(
-        advertised_mss: Option<usize>,
-        handshake_retries: Option<usize>,
-        handshake_timeout: Option<Duration>,
-        receive_window_size: Option<u16>,
-        window_scale: Option<u8>,
-        ack_delay_timeout: Option<Duration>,
-        rx_checksum_offload: Option<bool>,
-        tx_checksum_offload: Option<bool>,
-    ) -> Self {
-        let mut options = Self::default();
-
-        if let Some(value) = advertised_mss {
-            options = options.set_advertised_mss(value);
-        }
-        if let Some(value) = handshake_retries {
-            options = options.set_handshake_retries(value);
-        }
-        if let Some(value) = handshake_timeout {
-            options = options.set_handshake_timeout(value);
-        }
-        if let Some(value) = receive_window_size {
-            options = options.set_receive_window_size(value);
-        }
-        if let Some(value) = window_scale {
-            options = options.set_window_scale(value);
-        }
-        if let Some(value) = ack_delay_timeout {
-            options = options.set_ack_delay_timeout(value);
-        }
-        if let Some(value) = rx_checksum_offload {
-            options.rx_checksum_offload = value;
-        }
-        if let Some(value) = tx_checksum_offload {
-            options.tx_checksum_offload = value;
-        }
-
-        options
+    /// Gets the maximum transmission unit of the interface in the target [TcpConfig].
+    pub fn get_mtu(&self) -> usize {
+        self.mtu
     }
 
-    /// Gets the advertised maximum segment size in the target [TcpConfig].
+    /// Gets the advertised maximum segment size in the target [TcpConfig]. Unless it was overridden, this is the size
+    /// of the largest segment that fits in the MTU of the interface.
     pub fn get_advertised_mss(&self) -> usize {
-        self.advertised_mss
+        self.advertised_mss.unwrap_or(self.mtu - IPV4_TCP_HEADER_SIZE)
     }
 
     /// Gets the number of TCP handshake retries in the target [TcpConfig].
@@ -114,6 +125,80 @@ impl TcpConfig {
         self.ack_delay_timeout
     }
 
+    /// Gets the number of segments received before forcing an acknowledgement in the target [TcpConfig].
+    pub fn get_ack_segments(&self) -> u32 {
+        self.ack_segments
+    }
+
+    /// Gets the quick acknowledgement option in the target [TcpConfig].
+    pub fn get_quickack(&self) -> bool {
+        self.quickack
+    }
+
+    /// Gets the number of retransmissions before giving up on a connection in the target [TcpConfig].
+    pub fn get_max_retransmissions(&self) -> usize {
+        self.max_retransmissions
+    }
+
+    /// Gets the lower bound for the retransmission timeout in the target [TcpConfig].
+    pub fn get_rto_min(&self) -> Duration {
+        self.rto_min
+    }
+
+    /// Gets the upper bound for the retransmission timeout in the target [TcpConfig].
+    pub fn get_rto_max(&self) -> Duration {
+        self.rto_max
+    }
+
+    /// Gets the maximum segment lifetime in the target [TcpConfig].
+    pub fn get_msl(&self) -> Duration {
+        self.msl
+    }
+
+    /// Gets the option to reuse endpoints in TIME-WAIT for outgoing connections in the target [TcpConfig].
+    pub fn get_time_wait_reuse(&self) -> bool {
+        self.time_wait_reuse
+    }
+
+    /// Gets the option to answer connection requests with SYN cookies when the backlog of a listener is full in the
+    /// target [TcpConfig].
+    pub fn get_syn_cookies(&self) -> bool {
+        self.syn_cookies
+    }
+
+    /// Gets the maximum number of out-of-order segments stored per connection in the target [TcpConfig].
+    pub fn get_out_of_order_max_segments(&self) -> usize {
+        self.out_of_order_max_segments
+    }
+
+    /// Gets the maximum number of out-of-order bytes stored per connection in the target [TcpConfig].
+    pub fn get_out_of_order_max_bytes(&self) -> usize {
+        self.out_of_order_max_bytes
+    }
+
+    /// Gets the policy for dropping out-of-order data that does not fit in the store in the target [TcpConfig].
+    pub fn get_out_of_order_drop_policy(&self) -> OutOfOrderDropPolicy {
+        self.out_of_order_drop_policy
+    }
+
+    /// Gets the shard of connections owned by this instance in the target [TcpConfig], as an index and the number of
+    /// shards. Instances that share the same local address steer connections among them by 4-tuple hash.
+    pub fn get_shard(&self) -> (usize, usize) {
+        self.shard
+    }
+
+    /// Gets whether in-order segments of a connection that are received in the same batch are coalesced before they
+    /// are processed, in the target [TcpConfig].
+    pub fn get_gro(&self) -> bool {
+        self.gro
+    }
+
+    /// Gets the receive side scaling of the port in the target [TcpConfig], if the hardware steers each shard of
+    /// connections to its own receive queue.
+    pub fn get_rss(&self) -> Option<Rss> {
+        self.rss
+    }
+
     /// Gets the TX hardware checksum offload option in the target [TcpConfig].
     pub fn get_tx_checksum_offload(&self) -> bool {
         self.tx_checksum_offload
@@ -124,47 +209,196 @@ impl TcpConfig {
         self.rx_checksum_offload
     }
 
+    /// Gets the default congestion control algorithm in the target [TcpConfig].
+    pub fn get_congestion_control(&self) -> CongestionControlAlgorithm {
+        self.congestion_control
+    }
+
+    /// Gets the pacing option in the target [TcpConfig].
+    pub fn get_pacing(&self) -> bool {
+        self.pacing
+    }
+
+    /// Gets the Explicit Congestion Notification option in the target [TcpConfig].
+    pub fn get_ecn(&self) -> bool {
+        self.ecn
+    }
+
     /// Sets the advertised maximum segment size in the target [TcpConfig].
-    fn set_advertised_mss(mut self, value: usize) -> Self {
+    pub fn set_advertised_mss(mut self, value: usize) -> Self {
         assert!(value >= MIN_MSS);
         assert!(value <= MAX_MSS);
-        self.advertised_mss = value;
+        self.advertised_mss = Some(value);
+        self
+    }
+
+    /// Sets the maximum transmission unit of the interface in the target [TcpConfig].
+    pub fn set_mtu(mut self, value: usize) -> Self {
+        assert!(value >= MIN_MSS + IPV4_TCP_HEADER_SIZE);
+        assert!(value <= MAX_MSS + IPV4_TCP_HEADER_SIZE);
+        self.mtu = value;
+        self
+    }
+
+    /// Sets the maximum number of out-of-order segments stored per connection in the target [TcpConfig].
+    pub fn set_out_of_order_max_segments(mut self, value: usize) -> Self {
+        assert!(value > 0);
+        self.out_of_order_max_segments = value;
+        self
+    }
+
+    /// Sets the maximum number of out-of-order bytes stored per connection in the target [TcpConfig].
+    pub fn set_out_of_order_max_bytes(mut self, value: usize) -> Self {
+        assert!(value > 0);
+        self.out_of_order_max_bytes = value;
+        self
+    }
+
+    /// Sets the shard of connections owned by this instance in the target [TcpConfig].
+    pub fn set_shard(mut self, index: usize, count: usize) -> Self {
+        assert!(count > 0);
+        assert!(index < count);
+        self.shard = (index, count);
+        self
+    }
+
+    /// Sets the receive side scaling of the port in the target [TcpConfig]. There must be one receive queue per shard.
+    pub fn set_rss(mut self, rss: Rss) -> Self {
+        assert_eq!(rss.get_num_queues(), self.shard.1);
+        self.rss = Some(rss);
         self
     }
 
     /// Sets the number of TCP handshake retries in the target [TcpConfig].
-    fn set_handshake_retries(mut self, value: usize) -> Self {
+    pub fn set_handshake_retries(mut self, value: usize) -> Self {
         assert!(value > 0);
         self.handshake_retries = value;
         self
     }
 
     /// Sets the handshake TCP timeout in the target [TcpConfig].
-    fn set_handshake_timeout(mut self, value: Duration) -> Self {
+    pub fn set_handshake_timeout(mut self, value: Duration) -> Self {
         assert!(value > Duration::new(0, 0));
         self.handshake_timeout = value;
         self
     }
 
     /// Sets the receiver window size in the target [TcpConfig].
-    fn set_receive_window_size(mut self, value: u16) -> Self {
+    pub fn set_receive_window_size(mut self, value: u16) -> Self {
         assert!(value > 0);
         self.receive_window_size = value;
         self
     }
 
-    /// Gets the window scale in the target [TcpConfig]
-    fn set_window_scale(mut self, value: u8) -> Self {
+    /// Sets the window scale in the target [TcpConfig].
+    pub fn set_window_scale(mut self, value: u8) -> Self {
         self.window_scale = value;
         self
     }
 
     /// Sets the acknowledgement delay timeout in the target [TcpConfig].
-    fn set_ack_delay_timeout(mut self, value: Duration) -> Self {
+    pub fn set_ack_delay_timeout(mut self, value: Duration) -> Self {
         assert!(value <= Duration::from_millis(500));
         self.ack_delay_timeout = value;
         self
     }
+
+    /// Sets the number of segments received before forcing an acknowledgement in the target [TcpConfig].
+    pub fn set_ack_segments(mut self, value: u32) -> Self {
+        assert!(value > 0);
+        self.ack_segments = value;
+        self
+    }
+
+    /// Sets the number of retransmissions before giving up on a connection in the target [TcpConfig].
+    pub fn set_max_retransmissions(mut self, value: usize) -> Self {
+        assert!(value > 0);
+        self.max_retransmissions = value;
+        self
+    }
+
+    /// Sets the lower bound for the retransmission timeout in the target [TcpConfig].
+    pub fn set_rto_min(mut self, value: Duration) -> Self {
+        assert!(value > Duration::new(0, 0));
+        assert!(value <= self.rto_max);
+        self.rto_min = value;
+        self
+    }
+
+    /// Sets the upper bound for the retransmission timeout in the target [TcpConfig].
+    pub fn set_rto_max(mut self, value: Duration) -> Self {
+        assert!(value >= self.rto_min);
+        self.rto_max = value;
+        self
+    }
+
+    /// Sets the maximum segment lifetime in the target [TcpConfig].
+    pub fn set_msl(mut self, value: Duration) -> Self {
+        assert!(value > Duration::new(0, 0));
+        self.msl = value;
+        self
+    }
+
+    /// Sets the quick acknowledgement option in the target [TcpConfig].
+    pub fn set_quickack(mut self, value: bool) -> Self {
+        self.quickack = value;
+        self
+    }
+
+    /// Sets the option to reuse endpoints in TIME-WAIT for outgoing connections in the target [TcpConfig].
+    pub fn set_time_wait_reuse(mut self, value: bool) -> Self {
+        self.time_wait_reuse = value;
+        self
+    }
+
+    /// Sets the option to answer connection requests with SYN cookies when the backlog of a listener is full in the
+    /// target [TcpConfig].
+    pub fn set_syn_cookies(mut self, value: bool) -> Self {
+        self.syn_cookies = value;
+        self
+    }
+
+    /// Sets the policy for dropping out-of-order data that does not fit in the store in the target [TcpConfig].
+    pub fn set_out_of_order_drop_policy(mut self, value: OutOfOrderDropPolicy) -> Self {
+        self.out_of_order_drop_policy = value;
+        self
+    }
+
+    /// Sets whether in-order segments received in the same batch are coalesced in the target [TcpConfig].
+    pub fn set_gro(mut self, value: bool) -> Self {
+        self.gro = value;
+        self
+    }
+
+    /// Sets the TX hardware checksum offload option in the target [TcpConfig].
+    pub fn set_tx_checksum_offload(mut self, value: bool) -> Self {
+        self.tx_checksum_offload = value;
+        self
+    }
+
+    /// Sets the RX hardware checksum offload option in the target [TcpConfig].
+    pub fn set_rx_checksum_offload(mut self, value: bool) -> Self {
+        self.rx_checksum_offload = value;
+        self
+    }
+
+    /// Sets the default congestion control algorithm in the target [TcpConfig].
+    pub fn set_congestion_control(mut self, value: CongestionControlAlgorithm) -> Self {
+        self.congestion_control = value;
+        self
+    }
+
+    /// Sets the pacing option in the target [TcpConfig].
+    pub fn set_pacing(mut self, value: bool) -> Self {
+        self.pacing = value;
+        self
+    }
+
+    /// Sets the Explicit Congestion Notification option in the target [TcpConfig].
+    pub fn set_ecn(mut self, value: bool) -> Self {
+        self.ecn = value;
+        self
+    }
 }
 
 //==============================================================================
@@ -176,14 +410,32 @@ impl Default for TcpConfig {
     /// Creates a TCP Configuration Descriptor with the default values.
     fn default() -> Self {
         TcpConfig {
-            advertised_mss: DEFAULT_MSS,
+            mtu: DEFAULT_MTU,
+            advertised_mss: None,
             handshake_retries: 5,
             handshake_timeout: Duration::from_secs(3),
             receive_window_size: 0xffff,
             ack_delay_timeout: Duration::from_millis(5),
+            ack_segments: 2,
+            quickack: false,
+            max_retransmissions: 15,
+            rto_min: Duration::from_millis(100),
+            rto_max: Duration::from_secs(60),
+            msl: Duration::from_secs(30),
+            time_wait_reuse: false,
+            syn_cookies: true,
+            out_of_order_max_segments: 16,
+            out_of_order_max_bytes: 1 << 20,
+            out_of_order_drop_policy: OutOfOrderDropPolicy::default(),
+            shard: (0, 1),
+            gro: false,
+            rss: None,
             window_scale: 0,
             rx_checksum_offload: false,
             tx_checksum_offload: false,
+            congestion_control: CongestionControlAlgorithm::default(),
+            pacing: false,
+            ecn: false,
         }
     }
 }
@@ -196,7 +448,14 @@ impl Default for TcpConfig {
 mod tests {
     use crate::runtime::network::{
         config::TcpConfig,
-        consts::DEFAULT_MSS,
+        consts::{
+            DEFAULT_MSS,
+            DEFAULT_MTU,
+        },
+        types::{
+            CongestionControlAlgorithm,
+            OutOfOrderDropPolicy,
+        },
     };
     use ::std::time::Duration;
 
@@ -204,6 +463,7 @@ mod tests {
     #[test]
     fn test_tcp_config_default() {
         let config: TcpConfig = TcpConfig::default();
+        assert_eq!(config.get_mtu(), DEFAULT_MTU);
         assert_eq!(config.get_advertised_mss(), DEFAULT_MSS);
         assert_eq!(config.get_handshake_retries(), 5);
         assert_eq!(config.get_handshake_timeout(), Duration::from_secs(3));
@@ -211,5 +471,30 @@ mod tests {
         assert_eq!(config.get_window_scale(), 0);
         assert_eq!(config.get_rx_checksum_offload(), false);
         assert_eq!(config.get_tx_checksum_offload(), false);
+        assert_eq!(config.get_congestion_control(), CongestionControlAlgorithm::None);
+        assert_eq!(config.get_pacing(), false);
+        assert_eq!(config.get_ecn(), false);
+        assert_eq!(config.get_ack_segments(), 2);
+        assert_eq!(config.get_quickack(), false);
+        assert_eq!(config.get_max_retransmissions(), 15);
+        assert_eq!(config.get_rto_min(), Duration::from_millis(100));
+        assert_eq!(config.get_rto_max(), Duration::from_secs(60));
+        assert_eq!(config.get_msl(), Duration::from_secs(30));
+        assert_eq!(config.get_time_wait_reuse(), false);
+        assert_eq!(config.get_syn_cookies(), true);
+        assert_eq!(config.get_out_of_order_max_segments(), 16);
+        assert_eq!(config.get_out_of_order_max_bytes(), 1 << 20);
+        assert_eq!(config.get_out_of_order_drop_policy(), OutOfOrderDropPolicy::DropHighest);
+        assert_eq!(config.get_shard(), (0, 1));
+        assert_eq!(config.get_gro(), false);
+        assert_eq!(config.get_rss(), None);
+    }
+
+    /// Tests that the advertised MSS is derived from the MTU, unless it is overridden.
+    #[test]
+    fn test_tcp_config_advertised_mss() {
+        let jumbo_config: TcpConfig = TcpConfig::default().set_mtu(9000);
+        assert_eq!(jumbo_config.get_advertised_mss(), 8960);
+        assert_eq!(jumbo_config.set_advertised_mss(1400).get_advertised_mss(), 1400);
     }
 }
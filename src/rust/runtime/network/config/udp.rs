@@ -1,6 +1,16 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::network::consts::{
+    DEFAULT_MTU,
+    MAX_JUMBO_MTU,
+    MIN_MTU,
+};
+
 //==============================================================================
 // Constants & Structures
 //==============================================================================
@@ -12,6 +22,8 @@ pub struct UdpConfig {
     rx_checksum: bool,
     /// Offload Checksum to Hardware When Sending?
     tx_checksum: bool,
+    /// Maximum Transmission Unit of the Interface (Larger Datagrams Are Fragmented)
+    mtu: usize,
 }
 
 //==============================================================================
@@ -21,7 +33,7 @@ pub struct UdpConfig {
 /// Associate functions for UDP Configuration Descriptor
 impl UdpConfig {
     /// Creates a UDP Configuration Descriptor.
-    pub fn new(rx_checksum: Option<bool>, tx_checksum: Option<bool>) -> Self {
+    pub fn new(rx_checksum: Option<bool>, tx_checksum: Option<bool>, mtu: Option<usize>) -> Self {
         let mut config = Self::default();
         if let Some(rx_checksum) = rx_checksum {
             config.set_rx_checksum_offload(rx_checksum);
@@ -29,6 +41,9 @@ impl UdpConfig {
         if let Some(tx_checksum) = tx_checksum {
             config.set_tx_checksum_offload(tx_checksum);
         }
+        if let Some(mtu) = mtu {
+            config.set_mtu(mtu);
+        }
         config
     }
 
@@ -42,6 +57,11 @@ impl UdpConfig {
         self.tx_checksum
     }
 
+    /// Gets the maximum transmission unit of the interface in the target [UdpConfig].
+    pub fn get_mtu(&self) -> usize {
+        self.mtu
+    }
+
     /// Sets the RX hardware checksum offload option in the target [UdpConfig].
     fn set_rx_checksum_offload(&mut self, rx_checksum: bool) {
         self.rx_checksum = rx_checksum;
@@ -51,6 +71,13 @@ impl UdpConfig {
     fn set_tx_checksum_offload(&mut self, tx_checksum: bool) {
         self.tx_checksum = tx_checksum;
     }
+
+    /// Sets the maximum transmission unit of the interface in the target [UdpConfig].
+    fn set_mtu(&mut self, mtu: usize) {
+        assert!(mtu >= MIN_MTU);
+        assert!(mtu <= MAX_JUMBO_MTU);
+        self.mtu = mtu;
+    }
 }
 
 //==============================================================================
@@ -64,6 +91,7 @@ impl Default for UdpConfig {
         UdpConfig {
             rx_checksum: false,
             tx_checksum: false,
+            mtu: DEFAULT_MTU,
         }
     }
 }
@@ -75,6 +103,7 @@ impl Default for UdpConfig {
 #[cfg(test)]
 mod tests {
     use super::UdpConfig;
+    use crate::runtime::network::consts::DEFAULT_MTU;
 
     /// Tests default instantiation for [UdpConfig].
     #[test]
@@ -82,13 +111,15 @@ mod tests {
         let config: UdpConfig = UdpConfig::default();
         assert!(!config.get_rx_checksum_offload());
         assert!(!config.get_tx_checksum_offload());
+        assert_eq!(config.get_mtu(), DEFAULT_MTU);
     }
 
     /// Tests custom instantiation for [UdpConfig].
     #[test]
     fn test_udp_config_custom() {
-        let config: UdpConfig = UdpConfig::new(Some(true), Some(true));
+        let config: UdpConfig = UdpConfig::new(Some(true), Some(true), Some(9000));
         assert!(config.get_rx_checksum_offload());
         assert!(config.get_tx_checksum_offload());
+        assert_eq!(config.get_mtu(), 9000);
     }
 }
@@ -16,10 +16,32 @@ pub const MAX_MSS: usize = u16::max_value() as usize;
 
 /// Default MSS Parameter for TCP
 ///
+/// This is the MSS of segments that fit in the default MTU.
+pub const DEFAULT_MSS: usize = DEFAULT_MTU - IPV4_TCP_HEADER_SIZE;
+
+/// Size of the IPv4 and TCP Headers of a Segment Without Options (in bytes)
+pub const IPV4_TCP_HEADER_SIZE: usize = 40;
+
+/// Default Maximum Transmission Unit (in bytes)
+///
 /// TODO: Auto-Discovery MTU Size
-pub const DEFAULT_MSS: usize = 1450;
+pub const DEFAULT_MTU: usize = 1500;
+
+/// Minimum Maximum Transmission Unit (in bytes)
+///
+/// This is the MTU of links that carry segments of the minimum MSS.
+pub const MIN_MTU: usize = MIN_MSS + IPV4_TCP_HEADER_SIZE;
+
+/// Maximum Transmission Unit of Jumbo Frames (in bytes)
+pub const MAX_JUMBO_MTU: usize = 9216;
 
 /// Length of a [crate::memory::DemiBuffer] batch.
 ///
 /// TODO: This Should be Generic
 pub const RECEIVE_BATCH_SIZE: usize = 4;
+
+/// Default Upper Bound on the Size of Autotuned TCP Send Buffers (in bytes)
+pub const DEFAULT_MAX_SEND_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Default Upper Bound on the Size of Autotuned TCP Receive Buffers (in bytes)
+pub const DEFAULT_MAX_RECEIVE_BUFFER_SIZE: usize = 6 * 1024 * 1024;
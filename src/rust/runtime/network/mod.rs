@@ -7,7 +7,13 @@
 
 use crate::runtime::{
     memory::DemiBuffer,
-    network::consts::RECEIVE_BATCH_SIZE,
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        types::{
+            LinkState,
+            MacAddress,
+        },
+    },
 };
 use ::arrayvec::ArrayVec;
 
@@ -18,6 +24,11 @@ use ::arrayvec::ArrayVec;
 pub mod config;
 pub mod consts;
 pub mod types;
+#[cfg(any(
+    all(feature = "catloon-libos", target_os = "linux"),
+    all(feature = "catpowder-libos", target_os = "windows")
+))]
+pub mod xsk;
 
 //==============================================================================
 // Traits
@@ -33,6 +44,8 @@ pub trait PacketBuf {
     fn body_size(&self) -> usize;
     /// Consumes and returns the body of the target [PacketBuf].
     fn take_body(&self) -> Option<DemiBuffer>;
+    /// Returns the destination link address of the target [PacketBuf].
+    fn dst_link_addr(&self) -> MacAddress;
 }
 
 /// Network Runtime
@@ -42,4 +55,22 @@ pub trait NetworkRuntime {
 
     /// Receives a batch of [DemiBuffer].
     fn receive(&self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>;
+
+    /// Gets the state of the link. This is polled periodically, so it should be cheap. Runtimes that cannot tell
+    /// report the link as up.
+    fn link_state(&self) -> LinkState {
+        LinkState::Up
+    }
+
+    /// Gets the time at which the network interface received the frame in `buf` (in nanoseconds), if it was
+    /// timestamped by hardware.
+    fn rx_timestamp(&self, _buf: &DemiBuffer) -> Option<u64> {
+        None
+    }
+
+    /// Gets the time at which the last frame was handed to the network interface for transmission (in nanoseconds),
+    /// as read from the clock of the network interface, if it has one.
+    fn tx_timestamp(&self) -> Option<u64> {
+        None
+    }
 }
@@ -0,0 +1,29 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::network::types::MacAddress;
+use ::std::{
+    net::Ipv4Addr,
+    time::Duration,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// ARP Cache Entry
+///
+/// Address resolution stored in the ARP cache, as reported when inspecting it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ArpEntry {
+    /// IPv4 address that is resolved.
+    pub ipv4_addr: Ipv4Addr,
+    /// Link address that the IPv4 address resolves to.
+    pub link_addr: MacAddress,
+    /// Time left before the entry expires, or `None` for static entries, which never expire.
+    pub ttl: Option<Duration>,
+}
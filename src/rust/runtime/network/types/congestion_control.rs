@@ -0,0 +1,32 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Enumerations
+//==============================================================================
+
+/// Congestion Control Algorithm
+///
+/// Algorithm that a TCP connection uses to adapt its sending rate to the capacity of the network. This is selected
+/// through [super::SocketOption::CongestionControl] or the TCP configuration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CongestionControlAlgorithm {
+    /// No congestion control: only the window advertised by the remote peer limits the data in flight.
+    None,
+    /// CUBIC (see RFC 8312).
+    Cubic,
+    /// DCTCP (see RFC 8257), which reacts to the fraction of packets marked by Explicit Congestion Notification. Only
+    /// suitable for datacenter networks.
+    Dctcp,
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Default Trait Implementation for Congestion Control Algorithms
+impl Default for CongestionControlAlgorithm {
+    fn default() -> Self {
+        CongestionControlAlgorithm::None
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::{
+    net::Ipv4Addr,
+    time::Duration,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// DHCP Lease
+///
+/// Network configuration that a DHCP server granted to us, as reported when inspecting it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DhcpLease {
+    /// IPv4 address that is leased.
+    pub ipv4_addr: Ipv4Addr,
+    /// Length of the prefix of the subnet.
+    pub prefix_len: u8,
+    /// Default gateway, if any.
+    pub gateway: Option<Ipv4Addr>,
+    /// DNS servers, in order of preference.
+    pub dns_servers: Vec<Ipv4Addr>,
+    /// Server that granted the lease.
+    pub server: Ipv4Addr,
+    /// Duration of the lease, from the time that it was last renewed.
+    pub lease_time: Duration,
+}
@@ -0,0 +1,50 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::net::Ipv4Addr;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Transport Protocol That a Flow Rule Matches
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FlowProtocol {
+    /// Transmission Control Protocol
+    Tcp,
+    /// User Datagram Protocol
+    Udp,
+}
+
+/// What the Network Interface Does With Packets That Match a Flow Rule
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FlowAction {
+    /// Deliver them to the receive queue with the given index.
+    Queue(u16),
+    /// Drop them.
+    Drop,
+}
+
+/// Hardware Flow Rule
+///
+/// Incoming IPv4 packets of `protocol` whose addresses and ports match the ones that are set are handled by the
+/// network interface according to `action`, before they reach the host. Fields that are left unset match any value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FlowRule {
+    /// Transport protocol of matching packets.
+    pub protocol: FlowProtocol,
+    /// Source address of matching packets.
+    pub remote_addr: Option<Ipv4Addr>,
+    /// Source port of matching packets.
+    pub remote_port: Option<u16>,
+    /// Destination address of matching packets.
+    pub local_addr: Option<Ipv4Addr>,
+    /// Destination port of matching packets.
+    pub local_port: Option<u16>,
+    /// What to do with matching packets.
+    pub action: FlowAction,
+}
@@ -0,0 +1,29 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Link State
+///
+/// State of the link of a network interface, as reported by link events.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LinkState {
+    /// The link is down, so frames can neither be sent nor received.
+    Down,
+    /// The link is up.
+    Up,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Link States
+impl LinkState {
+    /// Checks whether the target [LinkState] is up.
+    pub fn is_up(&self) -> bool {
+        *self == LinkState::Up
+    }
+}
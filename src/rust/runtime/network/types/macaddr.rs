@@ -8,7 +8,10 @@
 use crate::runtime::fail::Fail;
 use ::eui48;
 use ::libc::EINVAL;
-use ::std::fmt;
+use ::std::{
+    fmt,
+    net::Ipv4Addr,
+};
 
 //==============================================================================
 // Structures
@@ -44,6 +47,12 @@ impl MacAddress {
         MacAddress(eui48::MacAddress::broadcast())
     }
 
+    /// Returns the MAC Address that an IPv4 multicast group maps to (see RFC 1112).
+    pub fn from_ipv4_multicast(group: Ipv4Addr) -> MacAddress {
+        let octets: [u8; 4] = group.octets();
+        MacAddress::new([0x01, 0x00, 0x5e, octets[1] & 0x7f, octets[2], octets[3]])
+    }
+
     /// Returns a MAC Address that matches the null one.
     pub fn nil() -> MacAddress {
         MacAddress(eui48::MacAddress::nil())
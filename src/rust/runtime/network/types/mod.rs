@@ -1,14 +1,49 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod arp_entry;
+mod congestion_control;
+mod dhcp_lease;
+mod flow_rule;
+mod link_state;
 mod macaddr;
+mod out_of_order;
+mod packet_info;
 mod portnum;
+mod route;
+mod rss;
+mod socket_address;
+mod socket_option;
+mod tcp_info;
+mod timestamps;
 
 //==============================================================================
 // Exports
 //==============================================================================
 
 pub use self::{
+    arp_entry::ArpEntry,
+    congestion_control::CongestionControlAlgorithm,
+    dhcp_lease::DhcpLease,
+    flow_rule::{
+        FlowAction,
+        FlowProtocol,
+        FlowRule,
+    },
+    link_state::LinkState,
     macaddr::MacAddress,
+    out_of_order::OutOfOrderDropPolicy,
+    packet_info::PacketInfo,
     portnum::Port16,
+    route::Route,
+    rss::Rss,
+    socket_address::SocketAddress,
+    socket_option::{
+        check_datagram_option,
+        check_stack_option,
+        SocketOption,
+        SocketOptions,
+    },
+    tcp_info::TcpInfo,
+    timestamps::Timestamps,
 };
@@ -0,0 +1,29 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Enumerations
+//==============================================================================
+
+/// Out-of-Order Drop Policy
+///
+/// What a TCP connection does with out-of-order data that does not fit in its out-of-order store, which is bounded
+/// both in number of segments and in bytes through the TCP configuration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutOfOrderDropPolicy {
+    /// Evict the data with the highest sequence numbers, which is the furthest from being delivered.
+    DropHighest,
+    /// Drop the data that just arrived, and keep what is already stored.
+    DropNew,
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Default Trait Implementation for Out-of-Order Drop Policies
+impl Default for OutOfOrderDropPolicy {
+    fn default() -> Self {
+        OutOfOrderDropPolicy::DropHighest
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::types::demi_pktinfo_t;
+use ::libc::c_int;
+use ::std::{
+    net::Ipv4Addr,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Packet Information
+///
+/// Metadata of a popped datagram, which is reported on sockets that enable [super::SocketOption::PacketInfo].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PacketInfo {
+    /// Local address that the datagram was sent to.
+    dst_addr: Ipv4Addr,
+    /// Time-to-live of the datagram, if known.
+    ttl: Option<u8>,
+    /// Arrival time of the datagram, if known.
+    timestamp: Option<SystemTime>,
+    /// Time at which the network interface received the datagram (in nanoseconds), if it was timestamped by hardware.
+    hw_timestamp: Option<u64>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Packet Information
+impl PacketInfo {
+    /// Creates packet information for a datagram.
+    pub fn new(dst_addr: Ipv4Addr, ttl: Option<u8>, timestamp: Option<SystemTime>) -> Self {
+        Self {
+            dst_addr,
+            ttl,
+            timestamp,
+            hw_timestamp: None,
+        }
+    }
+
+    /// Sets the time at which the network interface received the datagram (in nanoseconds).
+    pub fn with_hw_timestamp(self, hw_timestamp: Option<u64>) -> Self {
+        Self { hw_timestamp, ..self }
+    }
+
+    /// Gets the local address that the datagram was sent to.
+    pub fn get_dst_addr(&self) -> Ipv4Addr {
+        self.dst_addr
+    }
+
+    /// Gets the time-to-live of the datagram.
+    pub fn get_ttl(&self) -> Option<u8> {
+        self.ttl
+    }
+
+    /// Gets the arrival time of the datagram.
+    pub fn get_timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Gets the time at which the network interface received the datagram.
+    pub fn get_hw_timestamp(&self) -> Option<u64> {
+        self.hw_timestamp
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// From Trait Implementation for Packet Information
+impl From<PacketInfo> for demi_pktinfo_t {
+    /// Converts the target [PacketInfo] into a [demi_pktinfo_t]. Unknown fields are set to `-1` and zero, respectively.
+    fn from(info: PacketInfo) -> Self {
+        let timestamp: i64 = match info.timestamp.map(|timestamp| timestamp.duration_since(UNIX_EPOCH)) {
+            Some(Ok(elapsed)) => elapsed.as_nanos() as i64,
+            _ => 0,
+        };
+        demi_pktinfo_t {
+            pi_dstaddr: u32::from_ne_bytes(info.dst_addr.octets()),
+            pi_ttl: info.ttl.map_or(-1, |ttl| ttl as c_int),
+            pi_timestamp: timestamp,
+            pi_hwtimestamp: info.hw_timestamp.unwrap_or(0),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::fail::Fail;
+use ::libc::EINVAL;
+use ::std::net::Ipv4Addr;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// IPv4 Route
+///
+/// Traffic to destinations within `destination/prefix_len` is sent through `gateway`, or straight to them if there is
+/// no gateway, because they are on the local link.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Route {
+    /// Network address of the destinations.
+    pub destination: Ipv4Addr,
+    /// Length of the network prefix (in bits).
+    pub prefix_len: u8,
+    /// Next hop towards the destinations, if they are not on the local link.
+    pub gateway: Option<Ipv4Addr>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for IPv4 Routes
+impl Route {
+    /// Creates a route.
+    pub fn new(destination: Ipv4Addr, prefix_len: u8, gateway: Option<Ipv4Addr>) -> Result<Self, Fail> {
+        if prefix_len > 32 {
+            return Err(Fail::new(EINVAL, "invalid prefix length"));
+        }
+        if u32::from(destination) & !Self::mask(prefix_len) != 0 {
+            return Err(Fail::new(EINVAL, "destination has host bits set"));
+        }
+        if let Some(gateway) = gateway {
+            if gateway.is_unspecified() || gateway.is_broadcast() || gateway.is_multicast() {
+                return Err(Fail::new(EINVAL, "invalid gateway address"));
+            }
+        }
+        Ok(Self {
+            destination,
+            prefix_len,
+            gateway,
+        })
+    }
+
+    /// Parses a route from a destination in CIDR notation (e.g. `10.0.0.0/8`) and an optional gateway.
+    pub fn parse(destination: &str, gateway: Option<&str>) -> Result<Self, Fail> {
+        let (addr, prefix_len): (&str, &str) = destination
+            .split_once('/')
+            .ok_or_else(|| Fail::new(EINVAL, "destination is not in CIDR notation"))?;
+        let addr: Ipv4Addr = addr
+            .parse()
+            .map_err(|_| Fail::new(EINVAL, "invalid destination address"))?;
+        let prefix_len: u8 = prefix_len
+            .parse()
+            .map_err(|_| Fail::new(EINVAL, "invalid prefix length"))?;
+        let gateway: Option<Ipv4Addr> = match gateway {
+            Some(gateway) => Some(
+                gateway
+                    .parse()
+                    .map_err(|_| Fail::new(EINVAL, "invalid gateway address"))?,
+            ),
+            None => None,
+        };
+        Self::new(addr, prefix_len, gateway)
+    }
+
+    /// Checks whether `addr` is one of the destinations of the target route.
+    pub fn contains(&self, addr: Ipv4Addr) -> bool {
+        u32::from(addr) & Self::mask(self.prefix_len) == u32::from(self.destination)
+    }
+
+    /// Computes the network mask for a prefix length.
+    fn mask(prefix_len: u8) -> u32 {
+        match prefix_len {
+            0 => 0,
+            n => u32::MAX << (32 - n as u32),
+        }
+    }
+}
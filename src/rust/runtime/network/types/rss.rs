@@ -0,0 +1,133 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::net::SocketAddrV4;
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Pattern that the RSS key of ports is made of. Repeating it makes the Toeplitz hash symmetric, so that both
+/// directions of a flow hash to the same value.
+const RSS_KEY_PATTERN: [u8; 2] = [0x6d, 0x5a];
+
+/// Length of the RSS key that is used to compute hashes (in bytes). Only its first 16 bytes matter for 4-tuples.
+const RSS_KEY_LEN: usize = 40;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Receive Side Scaling (RSS)
+///
+/// Describes how a port spreads flows among its receive queues: it hashes their 4-tuple with the Toeplitz function
+/// and the key returned by [Rss::key], and looks the hash up in a redirection table whose entries are assigned to the
+/// queues in a round-robin fashion.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rss {
+    /// Number of entries in the redirection table.
+    reta_size: usize,
+    /// Number of receive queues.
+    num_queues: usize,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Receive Side Scaling
+impl Rss {
+    /// Creates a description of a port with `num_queues` receive queues and a redirection table of `reta_size` entries.
+    pub fn new(reta_size: usize, num_queues: usize) -> Self {
+        assert!(num_queues > 0);
+        assert!(reta_size >= num_queues);
+        Self { reta_size, num_queues }
+    }
+
+    /// Returns the RSS key that ports should be programmed with, for a key of `len` bytes.
+    pub fn key(len: usize) -> Vec<u8> {
+        (0..len).map(|i| RSS_KEY_PATTERN[i % RSS_KEY_PATTERN.len()]).collect()
+    }
+
+    /// Gets the number of receive queues.
+    pub fn get_num_queues(&self) -> usize {
+        self.num_queues
+    }
+
+    /// Computes the queue that receives the TCP or UDP segments sent from `src` to `dst`.
+    pub fn queue(&self, src: &SocketAddrV4, dst: &SocketAddrV4) -> usize {
+        let hash: u32 = Self::hash(&Self::key(RSS_KEY_LEN), src, dst);
+        (hash as usize % self.reta_size) % self.num_queues
+    }
+
+    /// Computes the Toeplitz hash of the 4-tuple of a segment sent from `src` to `dst`, with the given key.
+    fn hash(key: &[u8], src: &SocketAddrV4, dst: &SocketAddrV4) -> u32 {
+        let mut input: Vec<u8> = Vec::with_capacity(12);
+        input.extend_from_slice(&src.ip().octets());
+        input.extend_from_slice(&dst.ip().octets());
+        input.extend_from_slice(&src.port().to_be_bytes());
+        input.extend_from_slice(&dst.port().to_be_bytes());
+        Self::toeplitz(key, &input)
+    }
+
+    /// Computes the Toeplitz hash of `input`. For each bit of the input that is set, the 32 bits of the key that start
+    /// at the position of that bit are added to the hash.
+    fn toeplitz(key: &[u8], input: &[u8]) -> u32 {
+        assert!(key.len() >= input.len() + 4);
+        let mut hash: u32 = 0;
+        let mut window: u32 = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+        for (i, byte) in input.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    hash ^= window;
+                }
+                window = (window << 1) | ((key[i + 4] >> (7 - bit)) & 1) as u32;
+            }
+        }
+        hash
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Rss;
+    use ::std::net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    };
+
+    /// Tests the hash against the verification suite of the Toeplitz function, which uses a key that is not symmetric.
+    #[test]
+    fn test_toeplitz_hash() {
+        let key: [u8; 40] = [
+            0x6d, 0x5a, 0x56, 0xda, 0x25, 0x5b, 0x0e, 0xc2, 0x41, 0x67, 0x25, 0x3d, 0x43, 0xa3, 0x8f, 0xb0, 0xd0, 0xca,
+            0x2b, 0xcb, 0xae, 0x7b, 0x30, 0xb4, 0x77, 0xcb, 0x2d, 0xa3, 0x80, 0x30, 0xf2, 0x0c, 0x6a, 0x42, 0xb7, 0x3b,
+            0xbe, 0xac, 0x01, 0xfa,
+        ];
+        let src: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(66, 9, 149, 187), 2794);
+        let dst: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(161, 142, 100, 80), 1766);
+        assert_eq!(Rss::hash(&key, &src, &dst), 0x51ccc178);
+    }
+
+    /// Tests that both directions of a flow are steered to the same queue.
+    #[test]
+    fn test_rss_symmetric() {
+        let rss: Rss = Rss::new(128, 3);
+        let alice: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 1), 49152);
+        let bob: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 80);
+        for port in 49152..49252 {
+            let alice: SocketAddrV4 = SocketAddrV4::new(*alice.ip(), port);
+            let queue: usize = rss.queue(&alice, &bob);
+            assert!(queue < 3);
+            assert_eq!(rss.queue(&bob, &alice), queue);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::{
+    net::SocketAddrV4,
+    path::PathBuf,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Socket Address
+///
+/// Address of the remote endpoint of a popped datagram, in the communication domain of the socket.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SocketAddress {
+    /// Endpoint of the IPv4 domain.
+    Inet(SocketAddrV4),
+    /// Path of the unix domain, which is empty for unnamed sockets.
+    Unix(PathBuf),
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Conversion Trait Implementation for Socket Addresses
+impl From<SocketAddrV4> for SocketAddress {
+    fn from(addr: SocketAddrV4) -> Self {
+        SocketAddress::Inet(addr)
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use super::CongestionControlAlgorithm;
+use crate::runtime::fail::Fail;
+use ::libc::{
+    EINVAL,
+    ENOPROTOOPT,
+};
+use ::std::time::Duration;
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Upper bound on the delay of ACKs (see RFC 1122 Section 4.2.3.2).
+const MAX_ACK_DELAY: Duration = Duration::from_millis(500);
+
+//==============================================================================
+// Enumerations
+//==============================================================================
+
+/// Socket Option
+///
+/// When setting an option, the payload carries the new value. When getting an option, the payload of the argument is
+/// ignored, and the same variant is returned filled with the current value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SocketOption {
+    /// Size of the send buffer (in bytes). Setting it also pins the buffer to that size, turning off autotuning. Maps
+    /// to `SO_SNDBUF`.
+    SendBufferSize(usize),
+    /// Size of the receive buffer (in bytes). Setting it also pins the buffer to that size, turning off autotuning.
+    /// Maps to `SO_RCVBUF`.
+    ReceiveBufferSize(usize),
+    /// Lower and upper bounds (in bytes) within which the send buffer is autotuned (TCP only).
+    SendBufferBounds(usize, usize),
+    /// Lower and upper bounds (in bytes) within which the receive buffer is autotuned (TCP only).
+    ReceiveBufferBounds(usize, usize),
+    /// Disables coalescing of small segments (TCP only). Maps to `TCP_NODELAY`.
+    NoDelay(bool),
+    /// Holds back partial segments until the option is cleared (TCP only). Maps to `TCP_CORK`.
+    Cork(bool),
+    /// Enables keepalive probes (TCP only). Maps to `SO_KEEPALIVE`.
+    KeepAlive(bool),
+    /// Time-to-live of outgoing IPv4 packets. Maps to `IP_TTL`.
+    Ttl(u8),
+    /// Permits sending datagrams to the broadcast address (UDP only). Maps to `SO_BROADCAST`.
+    Broadcast(bool),
+    /// Reports the packet information of popped datagrams (UDP only). Maps to `IP_PKTINFO`.
+    PacketInfo(bool),
+    /// Congestion control algorithm of new connections (TCP only). Maps to `TCP_CONGESTION`.
+    CongestionControl(CongestionControlAlgorithm),
+    /// Maximum rate (in bytes per second) at which segments are paced out (TCP only). `u64::MAX` means that the rate
+    /// is not capped. Maps to `SO_MAX_PACING_RATE`.
+    MaxPacingRate(u64),
+    /// Maximum time for which ACKs are delayed (TCP only). Zero disables delayed ACKs.
+    AckDelay(Duration),
+    /// Number of segments received after which an ACK is sent right away, rather than delayed (TCP only).
+    AckSegments(u32),
+    /// ACKs every segment right away (TCP only). Unlike on Linux, this is not reset by the stack. Maps to
+    /// `TCP_QUICKACK`.
+    QuickAck(bool),
+}
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Socket Options
+///
+/// Holds the current value of every [SocketOption] for a single socket. This is used by LibOSes that do not rely on
+/// the kernel to keep track of socket options.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SocketOptions {
+    /// Size of the send buffer.
+    send_buffer_size: usize,
+    /// Size of the receive buffer.
+    receive_buffer_size: usize,
+    /// Bounds of the send buffer.
+    send_buffer_bounds: (usize, usize),
+    /// Bounds of the receive buffer.
+    receive_buffer_bounds: (usize, usize),
+    /// Disable coalescing of small segments?
+    nodelay: bool,
+    /// Hold back partial segments?
+    cork: bool,
+    /// Enable keepalive probes?
+    keepalive: bool,
+    /// Time-to-live of outgoing packets.
+    ttl: u8,
+    /// Permit sending to the broadcast address?
+    broadcast: bool,
+    /// Report packet information?
+    pktinfo: bool,
+    /// Congestion control algorithm.
+    congestion_control: CongestionControlAlgorithm,
+    /// Maximum pacing rate.
+    max_pacing_rate: u64,
+    /// Maximum delay of ACKs.
+    ack_delay: Duration,
+    /// Number of segments that force an ACK.
+    ack_segments: u32,
+    /// ACK every segment right away?
+    quickack: bool,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Socket Options
+impl SocketOptions {
+    /// Creates a set of socket options with the given buffer sizes and time-to-live. Buffers are not autotuned, and
+    /// ACKs are not delayed.
+    pub fn new(send_buffer_size: usize, receive_buffer_size: usize, ttl: u8) -> Self {
+        Self {
+            send_buffer_size,
+            receive_buffer_size,
+            send_buffer_bounds: (send_buffer_size, send_buffer_size),
+            receive_buffer_bounds: (receive_buffer_size, receive_buffer_size),
+            nodelay: false,
+            cork: false,
+            keepalive: false,
+            ttl,
+            broadcast: false,
+            pktinfo: false,
+            congestion_control: CongestionControlAlgorithm::default(),
+            max_pacing_rate: u64::MAX,
+            ack_delay: Duration::ZERO,
+            ack_segments: 1,
+            quickack: false,
+        }
+    }
+
+    /// Gets the current value of the option that matches the variant of `opt`.
+    pub fn get(&self, opt: SocketOption) -> SocketOption {
+        match opt {
+            SocketOption::SendBufferSize(_) => SocketOption::SendBufferSize(self.send_buffer_size),
+            SocketOption::ReceiveBufferSize(_) => SocketOption::ReceiveBufferSize(self.receive_buffer_size),
+            SocketOption::SendBufferBounds(..) => {
+                SocketOption::SendBufferBounds(self.send_buffer_bounds.0, self.send_buffer_bounds.1)
+            },
+            SocketOption::ReceiveBufferBounds(..) => {
+                SocketOption::ReceiveBufferBounds(self.receive_buffer_bounds.0, self.receive_buffer_bounds.1)
+            },
+            SocketOption::NoDelay(_) => SocketOption::NoDelay(self.nodelay),
+            SocketOption::Cork(_) => SocketOption::Cork(self.cork),
+            SocketOption::KeepAlive(_) => SocketOption::KeepAlive(self.keepalive),
+            SocketOption::Ttl(_) => SocketOption::Ttl(self.ttl),
+            SocketOption::Broadcast(_) => SocketOption::Broadcast(self.broadcast),
+            SocketOption::PacketInfo(_) => SocketOption::PacketInfo(self.pktinfo),
+            SocketOption::CongestionControl(_) => SocketOption::CongestionControl(self.congestion_control),
+            SocketOption::MaxPacingRate(_) => SocketOption::MaxPacingRate(self.max_pacing_rate),
+            SocketOption::AckDelay(_) => SocketOption::AckDelay(self.ack_delay),
+            SocketOption::AckSegments(_) => SocketOption::AckSegments(self.ack_segments),
+            SocketOption::QuickAck(_) => SocketOption::QuickAck(self.quickack),
+        }
+    }
+
+    /// Sets the value of an option.
+    pub fn set(&mut self, opt: SocketOption) -> Result<(), Fail> {
+        match opt {
+            SocketOption::SendBufferSize(0) | SocketOption::ReceiveBufferSize(0) => {
+                return Err(Fail::new(EINVAL, "buffer size may not be zero"))
+            },
+            SocketOption::SendBufferBounds(0, _) | SocketOption::ReceiveBufferBounds(0, _) => {
+                return Err(Fail::new(EINVAL, "buffer size may not be zero"))
+            },
+            SocketOption::SendBufferBounds(min, max) | SocketOption::ReceiveBufferBounds(min, max) if min > max => {
+                return Err(Fail::new(EINVAL, "lower bound exceeds upper bound"))
+            },
+            SocketOption::Ttl(0) => return Err(Fail::new(EINVAL, "time-to-live may not be zero")),
+            SocketOption::MaxPacingRate(0) => return Err(Fail::new(EINVAL, "pacing rate may not be zero")),
+            SocketOption::AckDelay(delay) if delay > MAX_ACK_DELAY => {
+                return Err(Fail::new(EINVAL, "ACK delay is too long"))
+            },
+            SocketOption::AckSegments(0) => return Err(Fail::new(EINVAL, "number of segments may not be zero")),
+            SocketOption::SendBufferSize(size) => {
+                self.send_buffer_size = size;
+                self.send_buffer_bounds = (size, size);
+            },
+            SocketOption::ReceiveBufferSize(size) => {
+                self.receive_buffer_size = size;
+                self.receive_buffer_bounds = (size, size);
+            },
+            SocketOption::SendBufferBounds(min, max) => {
+                self.send_buffer_size = self.send_buffer_size.clamp(min, max);
+                self.send_buffer_bounds = (min, max);
+            },
+            SocketOption::ReceiveBufferBounds(min, max) => {
+                self.receive_buffer_size = self.receive_buffer_size.clamp(min, max);
+                self.receive_buffer_bounds = (min, max);
+            },
+            SocketOption::NoDelay(nodelay) => self.nodelay = nodelay,
+            SocketOption::Cork(cork) => self.cork = cork,
+            SocketOption::KeepAlive(keepalive) => self.keepalive = keepalive,
+            SocketOption::Ttl(ttl) => self.ttl = ttl,
+            SocketOption::Broadcast(broadcast) => self.broadcast = broadcast,
+            SocketOption::PacketInfo(pktinfo) => self.pktinfo = pktinfo,
+            SocketOption::CongestionControl(algorithm) => self.congestion_control = algorithm,
+            SocketOption::MaxPacingRate(rate) => self.max_pacing_rate = rate,
+            SocketOption::AckDelay(delay) => self.ack_delay = delay,
+            SocketOption::AckSegments(segments) => self.ack_segments = segments,
+            SocketOption::QuickAck(quickack) => self.quickack = quickack,
+        }
+        Ok(())
+    }
+
+    /// Gets the send buffer size.
+    pub fn get_send_buffer_size(&self) -> usize {
+        self.send_buffer_size
+    }
+
+    /// Gets the receive buffer size.
+    pub fn get_receive_buffer_size(&self) -> usize {
+        self.receive_buffer_size
+    }
+
+    /// Gets the bounds of the send buffer.
+    pub fn get_send_buffer_bounds(&self) -> (usize, usize) {
+        self.send_buffer_bounds
+    }
+
+    /// Gets the bounds of the receive buffer.
+    pub fn get_receive_buffer_bounds(&self) -> (usize, usize) {
+        self.receive_buffer_bounds
+    }
+
+    /// Gets the no-delay flag.
+    pub fn get_nodelay(&self) -> bool {
+        self.nodelay
+    }
+
+    /// Gets the cork flag.
+    pub fn get_cork(&self) -> bool {
+        self.cork
+    }
+
+    /// Gets the keepalive flag.
+    pub fn get_keepalive(&self) -> bool {
+        self.keepalive
+    }
+
+    /// Gets the time-to-live.
+    pub fn get_ttl(&self) -> u8 {
+        self.ttl
+    }
+
+    /// Gets the broadcast flag.
+    pub fn get_broadcast(&self) -> bool {
+        self.broadcast
+    }
+
+    /// Gets the packet information flag.
+    pub fn get_pktinfo(&self) -> bool {
+        self.pktinfo
+    }
+
+    /// Gets the congestion control algorithm.
+    pub fn get_congestion_control(&self) -> CongestionControlAlgorithm {
+        self.congestion_control
+    }
+
+    /// Gets the maximum pacing rate.
+    pub fn get_max_pacing_rate(&self) -> u64 {
+        self.max_pacing_rate
+    }
+
+    /// Gets the maximum delay of ACKs.
+    pub fn get_ack_delay(&self) -> Duration {
+        self.ack_delay
+    }
+
+    /// Gets the number of segments that force an ACK.
+    pub fn get_ack_segments(&self) -> u32 {
+        self.ack_segments
+    }
+
+    /// Gets the quick ACK flag.
+    pub fn get_quickack(&self) -> bool {
+        self.quickack
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Fails with `ENOPROTOOPT` for options that only make sense on TCP sockets.
+pub fn check_datagram_option(opt: &SocketOption) -> Result<(), Fail> {
+    match opt {
+        SocketOption::SendBufferBounds(..)
+        | SocketOption::ReceiveBufferBounds(..)
+        | SocketOption::NoDelay(_)
+        | SocketOption::Cork(_)
+        | SocketOption::KeepAlive(_)
+        | SocketOption::CongestionControl(_)
+        | SocketOption::MaxPacingRate(_)
+        | SocketOption::AckDelay(_)
+        | SocketOption::AckSegments(_)
+        | SocketOption::QuickAck(_) => Err(Fail::new(ENOPROTOOPT, "option not supported on datagram sockets")),
+        _ => Ok(()),
+    }
+}
+
+/// Fails with `ENOPROTOOPT` for options that the network stack of Demikernel does not implement. The stack does not
+/// send keepalive probes, and it sends every packet with the default time-to-live.
+pub fn check_stack_option(opt: &SocketOption) -> Result<(), Fail> {
+    match opt {
+        SocketOption::KeepAlive(_) | SocketOption::Ttl(_) => {
+            Err(Fail::new(ENOPROTOOPT, "option not supported by the network stack"))
+        },
+        _ => Ok(()),
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_stack_option,
+        SocketOption,
+        SocketOptions,
+    };
+    use ::std::time::Duration;
+
+    #[test]
+    fn test_socket_options_get_set() {
+        let mut options: SocketOptions = SocketOptions::new(1024, 2048, 64);
+        assert_eq!(
+            options.get(SocketOption::ReceiveBufferSize(0)),
+            SocketOption::ReceiveBufferSize(2048)
+        );
+        assert!(options.set(SocketOption::NoDelay(true)).is_ok());
+        assert_eq!(options.get(SocketOption::NoDelay(false)), SocketOption::NoDelay(true));
+        assert!(!options.get_cork());
+        assert!(options.set(SocketOption::Ttl(0)).is_err());
+        assert_eq!(options.get_ttl(), 64);
+        assert_eq!(
+            options.get(SocketOption::Broadcast(true)),
+            SocketOption::Broadcast(false)
+        );
+        assert_eq!(options.get_max_pacing_rate(), u64::MAX);
+        assert!(options.set(SocketOption::MaxPacingRate(0)).is_err());
+        assert!(options.set(SocketOption::AckDelay(Duration::from_millis(501))).is_err());
+        assert!(options.set(SocketOption::AckSegments(0)).is_err());
+    }
+
+    #[test]
+    fn test_socket_options_buffer_bounds() {
+        let mut options: SocketOptions = SocketOptions::new(1024, 2048, 64);
+        assert_eq!(options.get_receive_buffer_bounds(), (2048, 2048));
+        assert!(options.set(SocketOption::ReceiveBufferBounds(4096, 1024)).is_err());
+        assert!(options.set(SocketOption::ReceiveBufferBounds(0, 1024)).is_err());
+        assert!(options.set(SocketOption::ReceiveBufferBounds(4096, 8192)).is_ok());
+        assert_eq!(options.get_receive_buffer_size(), 4096);
+        assert_eq!(
+            options.get(SocketOption::ReceiveBufferBounds(0, 0)),
+            SocketOption::ReceiveBufferBounds(4096, 8192)
+        );
+        // Setting the size pins the buffer.
+        assert!(options.set(SocketOption::SendBufferSize(512)).is_ok());
+        assert_eq!(options.get_send_buffer_bounds(), (512, 512));
+    }
+
+    #[test]
+    fn test_check_stack_option() {
+        assert_eq!(
+            check_stack_option(&SocketOption::KeepAlive(true)).unwrap_err().errno,
+            libc::ENOPROTOOPT
+        );
+        assert_eq!(
+            check_stack_option(&SocketOption::Ttl(64)).unwrap_err().errno,
+            libc::ENOPROTOOPT
+        );
+        assert!(check_stack_option(&SocketOption::NoDelay(true)).is_ok());
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::time::Duration;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// TCP Connection Information
+///
+/// Snapshot of the state of a TCP connection, in the spirit of Linux's `TCP_INFO` socket option. It is meant to help
+/// debugging throughput problems on a given connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TcpInfo {
+    /// Maximum segment size that we send.
+    pub mss: usize,
+    /// Smoothed round-trip time, once a sample has been taken.
+    pub srtt: Option<Duration>,
+    /// Round-trip time variation, once a sample has been taken.
+    pub rttvar: Option<Duration>,
+    /// Current retransmission timeout.
+    pub rto: Duration,
+    /// Congestion window (in bytes). `u32::MAX` means that the congestion control algorithm does not limit it.
+    pub cwnd: u32,
+    /// Slow start threshold (in bytes). `u32::MAX` means that slow start did not end yet.
+    pub ssthresh: u32,
+    /// Number of bytes sent but not acknowledged yet.
+    pub bytes_in_flight: u32,
+    /// Number of bytes that our peer acknowledged so far.
+    pub bytes_acked: u64,
+    /// Number of segments retransmitted so far, whether after a timeout or as part of loss recovery.
+    pub retransmits: u64,
+    /// Rate at which our peer recently acknowledged data (in bytes per second), or zero if not measured yet.
+    pub delivery_rate: u64,
+}
@@ -0,0 +1,19 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Hardware Timestamps
+///
+/// Times at which the network interface last received and transmitted a frame on a given socket. They are read from
+/// the clock of the network interface, and they are expressed in nanoseconds. Runtimes that cannot timestamp frames
+/// leave them unset.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Timestamps {
+    /// Time at which the last frame was received, if known.
+    pub rx: Option<u64>,
+    /// Time at which the last frame was transmitted, if known.
+    pub tx: Option<u64>,
+}
@@ -0,0 +1,569 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::{
+        DemiBuffer,
+        ForeignInfo,
+    },
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        PacketBuf,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    cell::{
+        RefCell,
+        RefMut,
+    },
+    ffi::c_void,
+    marker::PhantomData,
+    ptr::NonNull,
+    rc::{
+        Rc,
+        Weak,
+    },
+    slice,
+    sync::atomic::{
+        AtomicU32,
+        Ordering,
+    },
+};
+
+//==============================================================================
+// Constants & Structures
+//==============================================================================
+
+/// Size of the frames of the UMEM (in bytes). Each frame holds a single Ethernet frame.
+pub const FRAME_SIZE: usize = 4096;
+
+/// Number of frames in the UMEM. The first half receives frames, and the second half transmits them.
+pub const NUM_FRAMES: usize = 4096;
+
+/// Number of entries in each ring. The fill ring can thus hold all receive frames at once.
+pub const RING_SIZE: u32 = (NUM_FRAMES / 2) as u32;
+
+/// Size of the UMEM (in bytes).
+pub const UMEM_SIZE: usize = NUM_FRAMES * FRAME_SIZE;
+
+/// Headroom that the UMEM reserves in front of the data of received frames (in bytes). Received frames are lent out as
+/// buffers that lay out their metadata there.
+pub const FRAME_HEADROOM: u32 = DemiBuffer::FOREIGN_HEADROOM as u32;
+
+/// Descriptor of the receive and transmit rings, whose layout depends on the platform.
+pub trait XskDescriptor: Copy {
+    /// Builds a descriptor for `len` bytes at address `addr` of the UMEM.
+    fn new(addr: u64, len: u32) -> Self;
+    /// Gets the address of the data in the UMEM, along with its length.
+    fn data(&self) -> (u64, usize);
+}
+
+/// Ring that is shared with the kernel or the XDP driver. The producer and consumer indexes grow without bounds, and
+/// are masked to index descriptors.
+pub struct XskRing {
+    producer: *const AtomicU32,
+    consumer: *const AtomicU32,
+    flags: *const AtomicU32,
+    descs: *mut u8,
+    stride: usize,
+    mask: u32,
+    /// Flag that is set when the ring should be processed explicitly.
+    wakeup_flag: u32,
+}
+
+/// Frames of an XDP socket
+///
+/// Frames are received and transmitted through a UMEM, which is memory that is shared with the kernel or the XDP
+/// driver and, in zero-copy mode, with the network interface. Frames of the UMEM are handed over through the fill ring
+/// and come back through the receive ring once they hold a received frame. Likewise, frames to transmit go through the
+/// transmit ring and come back through the completion ring once they are sent.
+///
+/// Received frames are lent out as buffers that refer to the UMEM, and they are handed back through the fill ring once
+/// these buffers are dropped. Thus the UMEM is released once the frames are dropped along with all of these buffers.
+pub struct XskFrames<D: XskDescriptor> {
+    umem: *mut u8,
+    fill: XskRing,
+    completion: XskRing,
+    rx: XskRing,
+    tx: XskRing,
+    /// Frames of the UMEM that are free for transmission.
+    tx_frames: Vec<u64>,
+    /// Frames of the UMEM that are lent out, which share the ownership of the UMEM.
+    rx_frames: Rc<RxFrames>,
+    _desc: PhantomData<D>,
+}
+
+/// Receive frames of a UMEM that are lent out as buffers, which own the UMEM until the last of them is dropped.
+struct RxFrames {
+    /// Owner of the memory of the buffers. Each buffer holds a reference to the frames, which it drops once released.
+    info: ForeignInfo,
+    umem: *mut u8,
+    /// Releases the UMEM.
+    release_umem: unsafe fn(*mut u8),
+    /// Frames that were released, and that are yet to be handed back through the fill ring.
+    released: RefCell<Vec<u64>>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associated functions for rings.
+impl XskRing {
+    /// Builds a ring whose fields live at the given addresses, and whose descriptors are `stride` bytes apart.
+    /// `wakeup_flag` is the flag that is set when the ring should be processed explicitly.
+    ///
+    /// # Safety
+    ///
+    /// The fields of the ring must remain valid as long as the ring is used, and the ring must have [RING_SIZE]
+    /// entries.
+    pub unsafe fn new(
+        producer: *mut u8,
+        consumer: *mut u8,
+        flags: *mut u8,
+        descs: *mut u8,
+        stride: usize,
+        wakeup_flag: u32,
+    ) -> Self {
+        Self {
+            producer: producer as *const AtomicU32,
+            consumer: consumer as *const AtomicU32,
+            flags: flags as *const AtomicU32,
+            descs,
+            stride,
+            mask: RING_SIZE - 1,
+            wakeup_flag,
+        }
+    }
+
+    /// Gets the number of entries that a producer may fill in.
+    fn free_entries(&self) -> u32 {
+        // Safety: the indexes live as long as the ring.
+        let (producer, consumer): (u32, u32) = unsafe {
+            (
+                (*self.producer).load(Ordering::Relaxed),
+                (*self.consumer).load(Ordering::Acquire),
+            )
+        };
+        (self.mask + 1) - producer.wrapping_sub(consumer)
+    }
+
+    /// Gets the number of entries that a consumer may read.
+    fn available_entries(&self) -> u32 {
+        // Safety: the indexes live as long as the ring.
+        let (producer, consumer): (u32, u32) = unsafe {
+            (
+                (*self.producer).load(Ordering::Acquire),
+                (*self.consumer).load(Ordering::Relaxed),
+            )
+        };
+        producer.wrapping_sub(consumer)
+    }
+
+    /// Gets a pointer to the `i`-th descriptor after the producer index.
+    fn producer_desc<T>(&self, i: u32) -> *mut T {
+        let producer: u32 = unsafe { (*self.producer).load(Ordering::Relaxed) };
+        unsafe {
+            self.descs
+                .add((producer.wrapping_add(i) & self.mask) as usize * self.stride) as *mut T
+        }
+    }
+
+    /// Gets a pointer to the `i`-th descriptor after the consumer index.
+    fn consumer_desc<T>(&self, i: u32) -> *const T {
+        let consumer: u32 = unsafe { (*self.consumer).load(Ordering::Relaxed) };
+        unsafe {
+            self.descs
+                .add((consumer.wrapping_add(i) & self.mask) as usize * self.stride) as *const T
+        }
+    }
+
+    /// Hands `n` filled in descriptors over to the consumer.
+    fn submit(&self, n: u32) {
+        unsafe {
+            let producer: u32 = (*self.producer).load(Ordering::Relaxed);
+            (*self.producer).store(producer.wrapping_add(n), Ordering::Release);
+        }
+    }
+
+    /// Hands `n` read descriptors back to the producer.
+    fn release(&self, n: u32) {
+        unsafe {
+            let consumer: u32 = (*self.consumer).load(Ordering::Relaxed);
+            (*self.consumer).store(consumer.wrapping_add(n), Ordering::Release);
+        }
+    }
+
+    /// Checks whether the ring should be processed explicitly.
+    fn needs_wakeup(&self) -> bool {
+        unsafe { (*self.flags).load(Ordering::Relaxed) & self.wakeup_flag != 0 }
+    }
+}
+
+/// Associated functions for frames of XDP sockets.
+impl<D: XskDescriptor> XskFrames<D> {
+    /// Sets up the frames of the UMEM at `umem`, which spans [UMEM_SIZE] bytes and reserves [FRAME_HEADROOM] bytes in
+    /// front of received frames. All receive frames are handed over through the fill ring right away. The UMEM is
+    /// handed to `release_umem` once neither the frames nor any of the buffers that they lent out are used anymore.
+    ///
+    /// # Safety
+    ///
+    /// The UMEM must be aligned on [FRAME_SIZE] bytes, and it must remain valid until it is released.
+    pub unsafe fn new(
+        umem: *mut u8,
+        release_umem: unsafe fn(*mut u8),
+        fill: XskRing,
+        completion: XskRing,
+        rx: XskRing,
+        tx: XskRing,
+    ) -> Self {
+        for i in 0..RING_SIZE {
+            *fill.producer_desc::<u64>(i) = i as u64 * FRAME_SIZE as u64;
+        }
+        fill.submit(RING_SIZE);
+
+        let rx_frames: Rc<RxFrames> = Rc::new_cyclic(|rx_frames: &Weak<RxFrames>| RxFrames {
+            info: ForeignInfo {
+                release: release_rx_frame,
+                opaque: rx_frames.as_ptr() as *const c_void,
+            },
+            umem,
+            release_umem,
+            released: RefCell::new(Vec::with_capacity(RING_SIZE as usize)),
+        });
+
+        Self {
+            umem,
+            fill,
+            completion,
+            rx,
+            tx,
+            tx_frames: (NUM_FRAMES / 2..NUM_FRAMES).map(|i| (i * FRAME_SIZE) as u64).collect(),
+            rx_frames,
+            _desc: PhantomData,
+        }
+    }
+
+    /// Transmits a [PacketBuf], whose headers and body are written straight into a frame of the UMEM. Returns whether
+    /// the transmit ring should be processed explicitly.
+    pub fn transmit_packet(&mut self, pkt: Box<dyn PacketBuf>) -> Result<bool, Fail> {
+        let header_size: usize = pkt.header_size();
+        let body_size: usize = pkt.body_size();
+        self.transmit(header_size + body_size, |frame: &mut [u8]| {
+            pkt.write_header(&mut frame[..header_size]);
+            if let Some(body) = pkt.take_body() {
+                frame[header_size..].copy_from_slice(&body[..]);
+            }
+        })
+    }
+
+    /// Receives a batch of frames, without copying them out of the UMEM. Returns whether the fill ring should be
+    /// processed explicitly.
+    pub fn receive_batch(&mut self) -> (ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>, bool) {
+        let mut out: ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> = ArrayVec::new();
+        let (_, needs_wakeup): (usize, bool) = self.receive(RECEIVE_BATCH_SIZE, |buf: DemiBuffer| out.push(buf));
+        (out, needs_wakeup)
+    }
+
+    /// Transmits a frame of `len` bytes, which `write` fills in. Returns whether the transmit ring should be processed
+    /// explicitly.
+    pub fn transmit<F: FnOnce(&mut [u8])>(&mut self, len: usize, write: F) -> Result<bool, Fail> {
+        if len > FRAME_SIZE {
+            return Err(Fail::new(libc::EMSGSIZE, "frame does not fit in UMEM"));
+        }
+        self.reclaim_tx_frames();
+        if self.tx.free_entries() == 0 {
+            return Err(Fail::new(libc::EAGAIN, "transmit ring is full"));
+        }
+        let addr: u64 = match self.tx_frames.pop() {
+            Some(addr) => addr,
+            None => return Err(Fail::new(libc::ENOBUFS, "no free frame in UMEM")),
+        };
+
+        // Safety: the frame belongs to us until it comes back through the completion ring.
+        write(unsafe { slice::from_raw_parts_mut(self.umem.add(addr as usize), len) });
+        unsafe { *self.tx.producer_desc::<D>(0) = D::new(addr, len as u32) };
+        self.tx.submit(1);
+
+        Ok(self.tx.needs_wakeup())
+    }
+
+    /// Receives up to `max` frames, and hands each of them to `read` as a buffer that refers to the UMEM. Frames are
+    /// handed back through the fill ring once their buffer is dropped. Returns the number of frames that were received,
+    /// along with whether the fill ring should be processed explicitly.
+    pub fn receive<F: FnMut(DemiBuffer)>(&mut self, max: usize, mut read: F) -> (usize, bool) {
+        self.refill();
+        let n: u32 = self.rx.available_entries().min(max as u32);
+        for i in 0..n {
+            // Safety: the descriptor was filled in by the other end, and its frame belongs to us until it is handed
+            // back through the fill ring.
+            let (addr, len): (u64, usize) = unsafe { (*self.rx.consumer_desc::<D>(i)).data() };
+            // Frames may be received at an offset within their frame of the UMEM.
+            let base: u64 = addr - addr % FRAME_SIZE as u64;
+            read(self.lend(base, (addr - base) as usize, len));
+        }
+        if n > 0 {
+            self.rx.release(n);
+        }
+        (n as usize, self.fill.needs_wakeup())
+    }
+
+    /// Lends out the receive frame at address `base` of the UMEM, which holds `len` bytes of data at `offset`, as a
+    /// buffer. Frames that leave no room for the metadata of the buffer are copied out of the UMEM instead.
+    fn lend(&self, base: u64, offset: usize, len: usize) -> DemiBuffer {
+        // Safety: the frame lies within the UMEM, which outlives the buffer since the buffer holds a reference to it.
+        unsafe {
+            let frame: *mut u8 = self.umem.add(base as usize);
+            if offset < DemiBuffer::FOREIGN_HEADROOM {
+                let buf: DemiBuffer = DemiBuffer::from_slice(slice::from_raw_parts(frame.add(offset), len))
+                    .expect("frame should fit in a buffer");
+                self.rx_frames.released.borrow_mut().push(base);
+                return buf;
+            }
+            Rc::increment_strong_count(Rc::as_ptr(&self.rx_frames));
+            DemiBuffer::from_foreign(
+                NonNull::new_unchecked(frame),
+                (FRAME_SIZE - DemiBuffer::FOREIGN_HEADROOM) as u16,
+                (offset - DemiBuffer::FOREIGN_HEADROOM) as u16,
+                len as u16,
+                NonNull::from(&self.rx_frames.info),
+            )
+        }
+    }
+
+    /// Hands the receive frames that were released back through the fill ring.
+    fn refill(&mut self) {
+        let mut released: RefMut<Vec<u64>> = self.rx_frames.released.borrow_mut();
+        let n: u32 = (released.len() as u32).min(self.fill.free_entries());
+        for (i, addr) in released.drain(..n as usize).enumerate() {
+            // Safety: there are enough free entries in the ring.
+            unsafe { *self.fill.producer_desc::<u64>(i as u32) = addr };
+        }
+        if n > 0 {
+            self.fill.submit(n);
+        }
+    }
+
+    /// Takes the frames that were transmitted back from the completion ring.
+    fn reclaim_tx_frames(&mut self) {
+        let n: u32 = self.completion.available_entries();
+        for i in 0..n {
+            self.tx_frames.push(unsafe { *self.completion.consumer_desc::<u64>(i) });
+        }
+        if n > 0 {
+            self.completion.release(n);
+        }
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Drop trait implementation for receive frames, which are dropped once neither the frames of the UMEM nor any buffer
+/// that they lent out are used anymore.
+impl Drop for RxFrames {
+    fn drop(&mut self) {
+        // Safety: no frame of the UMEM is used anymore.
+        unsafe { (self.release_umem)(self.umem) };
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Takes back a receive frame whose buffer was released. `opaque` refers to the receive frames of its UMEM, along with
+/// the reference that the buffer held.
+unsafe fn release_rx_frame(opaque: *const c_void, frame: NonNull<u8>) {
+    let rx_frames: Rc<RxFrames> = Rc::from_raw(opaque as *const RxFrames);
+    let base: u64 = frame.as_ptr().offset_from(rx_frames.umem) as u64;
+    rx_frames.released.borrow_mut().push(base);
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        XskDescriptor,
+        XskFrames,
+        XskRing,
+        FRAME_SIZE,
+        RING_SIZE,
+        UMEM_SIZE,
+    };
+    use crate::runtime::memory::DemiBuffer;
+    use ::std::{
+        alloc::{
+            self,
+            Layout,
+        },
+        cell::Cell,
+        mem,
+        slice,
+        sync::atomic::{
+            AtomicU32,
+            Ordering,
+        },
+    };
+
+    thread_local! {
+        /// Whether the UMEM of the test was released.
+        static UMEM_RELEASED: Cell<bool> = Cell::new(false);
+    }
+
+    /// Gets the layout of the UMEM of the test.
+    fn umem_layout() -> Layout {
+        Layout::from_size_align(UMEM_SIZE, FRAME_SIZE).unwrap()
+    }
+
+    /// Releases the UMEM of the test.
+    unsafe fn release_umem(umem: *mut u8) {
+        alloc::dealloc(umem, umem_layout());
+        UMEM_RELEASED.with(|released| released.set(true));
+    }
+
+    /// Descriptor that holds the address of the data as is.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Desc {
+        addr: u64,
+        len: u32,
+        options: u32,
+    }
+
+    impl XskDescriptor for Desc {
+        fn new(addr: u64, len: u32) -> Self {
+            Self { addr, len, options: 0 }
+        }
+
+        fn data(&self) -> (u64, usize) {
+            (self.addr, self.len as usize)
+        }
+    }
+
+    /// Memory of a ring: producer index, consumer index and flags, followed by descriptors.
+    struct RingMem {
+        indexes: Box<[AtomicU32; 3]>,
+        descs: Vec<u8>,
+        stride: usize,
+    }
+
+    impl RingMem {
+        fn new(stride: usize) -> Self {
+            Self {
+                indexes: Box::new([AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)]),
+                descs: vec![0; RING_SIZE as usize * stride],
+                stride,
+            }
+        }
+
+        fn ring(&mut self) -> XskRing {
+            let base: *mut u8 = self.indexes.as_ptr() as *mut u8;
+            unsafe {
+                XskRing::new(
+                    base,
+                    base.add(mem::size_of::<u32>()),
+                    base.add(2 * mem::size_of::<u32>()),
+                    self.descs.as_mut_ptr(),
+                    self.stride,
+                    1,
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn xsk_frames() {
+        let umem_ptr: *mut u8 = unsafe { alloc::alloc_zeroed(umem_layout()) };
+        assert!(!umem_ptr.is_null());
+        let umem: &mut [u8] = unsafe { slice::from_raw_parts_mut(umem_ptr, UMEM_SIZE) };
+        let (mut fill, mut completion, mut rx, mut tx): (RingMem, RingMem, RingMem, RingMem) = (
+            RingMem::new(mem::size_of::<u64>()),
+            RingMem::new(mem::size_of::<u64>()),
+            RingMem::new(mem::size_of::<Desc>()),
+            RingMem::new(mem::size_of::<Desc>()),
+        );
+        let mut frames: XskFrames<Desc> = unsafe {
+            XskFrames::new(
+                umem_ptr,
+                release_umem,
+                fill.ring(),
+                completion.ring(),
+                rx.ring(),
+                tx.ring(),
+            )
+        };
+
+        // All receive frames are handed over right away.
+        assert_eq!(frames.fill.available_entries(), RING_SIZE);
+
+        // Frames that are transmitted are written into the UMEM, and referred to by the transmit ring.
+        let needs_wakeup: bool = frames.transmit(3, |frame| frame.copy_from_slice(b"abc")).unwrap();
+        assert!(!needs_wakeup);
+        assert_eq!(frames.tx.available_entries(), 1);
+        let (addr, len): (u64, usize) = unsafe { (*frames.tx.consumer_desc::<Desc>(0)).data() };
+        assert_eq!(len, 3);
+        assert_eq!(&umem[addr as usize..addr as usize + len], b"abc");
+        assert!(frames.transmit(FRAME_SIZE + 1, |_| ()).is_err());
+
+        // Received frames are read at their offset. Frames that leave room for the metadata of a buffer are lent out
+        // without copying them, and other ones are copied.
+        frames.fill.release(2);
+        let offset: usize = FRAME_SIZE + 16;
+        umem[offset..offset + 3].copy_from_slice(b"xyz");
+        unsafe { *frames.rx.producer_desc::<Desc>(0) = Desc::new(offset as u64, 3) };
+        let lent_offset: usize = 2 * FRAME_SIZE + 2 * DemiBuffer::FOREIGN_HEADROOM;
+        umem[lent_offset..lent_offset + 3].copy_from_slice(b"uvw");
+        unsafe { *frames.rx.producer_desc::<Desc>(1) = Desc::new(lent_offset as u64, 3) };
+        frames.rx.submit(2);
+        fill.indexes[2].store(1, Ordering::Relaxed);
+        let mut received: Vec<DemiBuffer> = Vec::new();
+        let (n, needs_wakeup): (usize, bool) = frames.receive(8, |buf| received.push(buf));
+        assert_eq!(n, 2);
+        assert!(needs_wakeup);
+        assert_eq!(&received[0][..], b"xyz");
+        assert_ne!(received[0].as_ptr(), umem[offset..].as_ptr());
+        assert_eq!(&received[1][..], b"uvw");
+        assert_eq!(received[1].as_ptr(), umem[lent_offset..].as_ptr());
+        assert_eq!(frames.fill.available_entries(), RING_SIZE - 2);
+
+        // Frames are handed back through the fill ring once their buffer is dropped.
+        let lent: DemiBuffer = received.pop().unwrap();
+        frames.receive(8, |_| ());
+        assert_eq!(frames.fill.available_entries(), RING_SIZE - 1);
+        assert_eq!(
+            unsafe { *frames.fill.consumer_desc::<u64>(RING_SIZE - 2) },
+            FRAME_SIZE as u64
+        );
+        let clone: DemiBuffer = lent.clone();
+        drop(lent);
+        drop(clone);
+        frames.receive(8, |_| ());
+        assert_eq!(frames.fill.available_entries(), RING_SIZE);
+        assert_eq!(
+            unsafe { *frames.fill.consumer_desc::<u64>(RING_SIZE - 1) },
+            2 * FRAME_SIZE as u64
+        );
+
+        // The UMEM is released once no buffer refers to it anymore.
+        frames.fill.release(1);
+        unsafe { *frames.rx.producer_desc::<Desc>(0) = Desc::new(lent_offset as u64, 3) };
+        frames.rx.submit(1);
+        let mut received: Vec<DemiBuffer> = Vec::new();
+        frames.receive(8, |buf| received.push(buf));
+        drop(frames);
+        assert!(!UMEM_RELEASED.with(|released| released.get()));
+        drop(received);
+        assert!(UMEM_RELEASED.with(|released| released.get()));
+    }
+}
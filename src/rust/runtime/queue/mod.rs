@@ -10,7 +10,15 @@ mod qtype;
 // Imports
 //======================================================================================================================
 
-use ::slab::Slab;
+use crate::runtime::fail::Fail;
+use ::std::{
+    collections::{
+        HashMap,
+        HashSet,
+        VecDeque,
+    },
+    mem,
+};
 
 //======================================================================================================================
 // Exports
@@ -27,10 +35,74 @@ pub use self::{
 // Structures
 //======================================================================================================================
 
+/// Limits of an I/O queue descriptors table, which are unbounded by default. Regardless of these, no more descriptors
+/// are allocated at once than can be encoded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IoQueueLimits {
+    /// Maximum number of I/O queue descriptors that are allocated at once.
+    pub max_qds: Option<usize>,
+    /// Maximum number of operations that are pending at once, i.e. whose result was not taken yet.
+    pub max_operations: Option<usize>,
+}
+
+/// Counters of the requests that were refused because some limit of an I/O queue descriptors table was reached.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IoQueueLimitStats {
+    /// Number of I/O queue descriptors that could not be allocated.
+    pub refused_qds: u64,
+    /// Number of operations that could not be issued.
+    pub refused_operations: u64,
+}
+
+/// Entry of an I/O queue descriptors table.
+struct IoQueueEntry {
+    /// Type of the underlying I/O queue.
+    qtype: u32,
+    /// I/O queue descriptor of the underlying I/O queue. This differs from the descriptor of the entry itself only for
+    /// duplicated descriptors.
+    queue: QDesc,
+    /// Whether the entry is open. Closed entries are retained while duplicated descriptors refer to their I/O queue,
+    /// so that the descriptor of the I/O queue is not reused.
+    open: bool,
+    /// Operations that were issued on the entry and whose result was not taken yet.
+    pending: HashSet<QToken>,
+}
+
+/// Operation that was issued on some entries of an I/O queue descriptors table.
+struct IoQueueOperation {
+    /// I/O queue descriptors on which the operation was issued.
+    qds: Vec<QDesc>,
+    /// I/O queue descriptor that was allocated for the outcome of the operation, if any (e.g. for accepts).
+    new_qd: Option<QDesc>,
+    /// Whether the operation was canceled, because some of its descriptors was closed.
+    canceled: bool,
+}
+
 /// I/O queue descriptors table.
+///
+/// Entries are reused once released, so I/O queue descriptors also encode the generation of their entry, which is
+/// bumped on every release. This way, a stale descriptor that is held after it was closed does not alias a descriptor
+/// that was later allocated on the same entry, and lookups of stale descriptors fail instead. Released entries are
+/// reused in the order that they were released, so that generations of an entry only wrap around after as many releases
+/// of every other vacant entry.
+///
+/// The table also tracks the operations that are pending on each descriptor, so that they are canceled when the
+/// descriptor is closed, instead of completing later against a descriptor that was released.
 pub struct IoQueueTable {
-    // TODO: Store a QType in the slab.
-    table: Slab<u32>,
+    // TODO: Store a QType in the table.
+    table: Vec<Option<IoQueueEntry>>,
+    /// Indices of vacant entries, in the order that they were released.
+    vacant: VecDeque<usize>,
+    /// Current generation of each entry, indexed as the table.
+    generations: Vec<usize>,
+    /// Number of open descriptors that refer to each I/O queue, for I/O queues that were duplicated.
+    refcounts: HashMap<QDesc, usize>,
+    /// Operations that are tracked, indexed by their queue tokens.
+    operations: HashMap<QToken, IoQueueOperation>,
+    /// Limits on the descriptors and operations of the table.
+    limits: IoQueueLimits,
+    /// Counters of the requests that were refused because of these limits.
+    limit_stats: IoQueueLimitStats,
 }
 
 //======================================================================================================================
@@ -45,41 +117,276 @@ impl IoQueueTable {
     /// this offset enables us to distinguish I/O queue descriptors from
     /// file descriptors.
     const BASE_QD: usize = 1_000_000;
+    /// Number of bits of I/O queue descriptors that encode the generation of their entry, above the index. Together,
+    /// these fit in a positive 32-bit integer along with the offset, so that descriptors may be handed over C.
+    const GENERATION_BITS: u32 = 10;
+    /// Number of low bits of I/O queue descriptors that encode the index of their entry.
+    const INDEX_BITS: u32 = 20;
+    /// Maximum number of I/O queue descriptors that may be allocated at once, as bounded by the bits of their index.
+    const MAX_QDS: usize = 1 << Self::INDEX_BITS;
 
     /// Creates an I/O queue descriptors table.
     pub fn new() -> Self {
-        Self { table: Slab::new() }
+        Self {
+            table: Vec::new(),
+            vacant: VecDeque::new(),
+            generations: Vec::new(),
+            refcounts: HashMap::new(),
+            operations: HashMap::new(),
+            limits: IoQueueLimits::default(),
+            limit_stats: IoQueueLimitStats::default(),
+        }
+    }
+
+    /// Sets the limits of the target I/O queue descriptors table.
+    pub fn set_limits(&mut self, limits: IoQueueLimits) {
+        self.limits = limits;
+    }
+
+    /// Gets the counters of the requests that were refused because some limit of the target I/O queue descriptors
+    /// table was reached.
+    pub fn limit_stats(&self) -> IoQueueLimitStats {
+        self.limit_stats
+    }
+
+    /// Checks that one more I/O queue descriptor may be allocated, failing with `ENFILE` otherwise. This must be
+    /// called before any resource is set up for the new descriptor, and in any case before [IoQueueTable::alloc] or
+    /// [IoQueueTable::dup]. Entries that are retained for duplicated descriptors count towards the limit.
+    pub fn check_qd_limit(&mut self) -> Result<(), Fail> {
+        let max_qds: usize = self
+            .limits
+            .max_qds
+            .map_or(Self::MAX_QDS, |max_qds| max_qds.min(Self::MAX_QDS));
+        if self.table.len() - self.vacant.len() >= max_qds {
+            self.limit_stats.refused_qds += 1;
+            return Err(Fail::new(libc::ENFILE, "too many queue descriptors"));
+        }
+        Ok(())
+    }
+
+    /// Checks that one more operation may be issued, failing with `EAGAIN` otherwise. This should be called before the
+    /// operation has any side effect. Only tracked operations count towards the limit.
+    pub fn check_operation_limit(&mut self) -> Result<(), Fail> {
+        match self.limits.max_operations {
+            Some(max_operations) if self.operations.len() >= max_operations => {
+                self.limit_stats.refused_operations += 1;
+                Err(Fail::new(libc::EAGAIN, "too many pending operations"))
+            },
+            _ => Ok(()),
+        }
     }
 
-    /// Allocates a new entry in the target I/O queue descriptors table.
+    /// Allocates a new entry in the target I/O queue descriptors table. Callers must first check that the limit of
+    /// I/O queue descriptors is not reached with [IoQueueTable::check_qd_limit].
     pub fn alloc(&mut self, qtype: u32) -> QDesc {
-        let index: usize = self.table.insert(qtype);
-        QDesc::from(index + Self::BASE_QD)
+        let qd: QDesc = self.next_qd();
+        self.insert(IoQueueEntry {
+            qtype,
+            queue: qd,
+            open: true,
+            pending: HashSet::new(),
+        });
+        qd
     }
 
     /// Gets the entry associated with an I/O queue descriptor.
     pub fn get(&self, qd: QDesc) -> Option<u32> {
-        let index: usize = self.get_index(qd)? as usize;
-        self.table.get(index).cloned()
+        let (_, qtype): (QDesc, u32) = self.resolve(qd)?;
+        Some(qtype)
     }
 
-    /// Releases the entry associated with an I/O queue descriptor.
+    /// Releases the entry associated with an I/O queue descriptor. This does not account for duplicated descriptors,
+    /// so it should only be used to roll back an allocation.
     pub fn free(&mut self, qd: QDesc) -> Option<u32> {
         let index: usize = self.get_index(qd)?;
-        Some(self.table.remove(index))
+        Some(self.remove(index).qtype)
     }
 
-    /// Gets the index in the I/O queue descriptors table to which a given I/O queue descriptor refers to.
-    fn get_index(&self, qd: QDesc) -> Option<usize> {
-        if Into::<usize>::into(qd) < Self::BASE_QD {
-            None
+    /// Duplicates an I/O queue descriptor. The new descriptor refers to the same I/O queue as `qd`. Callers must first
+    /// check that the limit of I/O queue descriptors is not reached with [IoQueueTable::check_qd_limit].
+    pub fn dup(&mut self, qd: QDesc) -> Option<QDesc> {
+        let (queue, qtype): (QDesc, u32) = self.resolve(qd)?;
+        let new_qd: QDesc = self.next_qd();
+        self.insert(IoQueueEntry {
+            qtype,
+            queue,
+            open: true,
+            pending: HashSet::new(),
+        });
+        *self.refcounts.entry(queue).or_insert(1) += 1;
+        Some(new_qd)
+    }
+
+    /// Resolves an I/O queue descriptor into the descriptor of the I/O queue that it refers to, along with the type of
+    /// that queue. For descriptors that were not duplicated, the descriptor of the queue is `qd` itself.
+    pub fn resolve(&self, qd: QDesc) -> Option<(QDesc, u32)> {
+        let index: usize = self.get_index(qd)?;
+        match self.table[index].as_ref() {
+            Some(entry) if entry.open => Some((entry.queue, entry.qtype)),
+            _ => None,
+        }
+    }
+
+    /// Releases an I/O queue descriptor, which may have been duplicated. This returns `Some(true)` if it was the last
+    /// descriptor that referred to its I/O queue, meaning that the queue itself should be closed.
+    pub fn release(&mut self, qd: QDesc) -> Option<bool> {
+        let index: usize = self.get_index(qd)?;
+        let (queue, _): (QDesc, u32) = self.resolve(qd)?;
+        let last: bool = match self.refcounts.get_mut(&queue) {
+            // The queue was not duplicated.
+            None => true,
+            Some(refcount) => {
+                *refcount -= 1;
+                *refcount == 0
+            },
+        };
+
+        if last {
+            // Release the entry of the queue itself, which may have been retained.
+            self.refcounts.remove(&queue);
+            if let Some(queue_index) = self.get_index(queue) {
+                self.remove(queue_index);
+            }
+        }
+
+        // Retain the entry of the queue itself while other descriptors refer to it.
+        match self.table[index].as_mut() {
+            Some(entry) if qd == queue && !last => entry.open = false,
+            Some(_) => {
+                self.remove(index);
+            },
+            None => (),
+        }
+
+        Some(last)
+    }
+
+    /// Tracks the operation `qt`, which was issued on the I/O queue descriptors `qds`, until its result is taken. If
+    /// the operation allocated the descriptor `new_qd` for its outcome, that descriptor is freed if the operation is
+    /// canceled.
+    pub fn track(&mut self, qt: QToken, qds: &[QDesc], new_qd: Option<QDesc>) {
+        for &qd in qds {
+            if let Some(index) = self.get_index(qd) {
+                self.entry_mut(index).pending.insert(qt);
+            }
+        }
+        self.operations.insert(
+            qt,
+            IoQueueOperation {
+                qds: qds.to_vec(),
+                new_qd,
+                canceled: false,
+            },
+        );
+    }
+
+    /// Stops tracking the operation `qt`, once its result is taken. If the operation was canceled, this returns the
+    /// I/O queue descriptor on which it was issued.
+    pub fn untrack(&mut self, qt: QToken) -> Option<QDesc> {
+        let operation: IoQueueOperation = self.operations.remove(&qt)?;
+        for &qd in &operation.qds {
+            if let Some(index) = self.get_index(qd) {
+                self.entry_mut(index).pending.remove(&qt);
+            }
+        }
+        if operation.canceled {
+            operation.qds.first().copied()
         } else {
-            let rawqd: usize = Into::<usize>::into(qd) - Self::BASE_QD;
-            if !self.table.contains(rawqd) {
-                return None;
+            None
+        }
+    }
+
+    /// Gets the I/O queue descriptor on which the operation `qt` was issued, as opposed to the descriptor of the I/O
+    /// queue that it refers to, while the operation is tracked.
+    pub fn get_issuer(&self, qt: QToken) -> Option<QDesc> {
+        self.operations.get(&qt)?.qds.first().copied()
+    }
+
+    /// Cancels the operations that are pending on the I/O queue descriptor `qd`, which is about to be closed. Each
+    /// operation is handed to `abort`, which returns false if the operation has already completed, in which case its
+    /// result is delivered as usual. Returns how many operations were canceled.
+    pub fn cancel<F: FnMut(QToken) -> bool>(&mut self, qd: QDesc, mut abort: F) -> usize {
+        let pending: HashSet<QToken> = match self.get_index(qd) {
+            Some(index) => mem::take(&mut self.entry_mut(index).pending),
+            None => return 0,
+        };
+
+        let mut ncanceled: usize = 0;
+        for qt in pending {
+            // Operations that were issued on multiple descriptors may have been canceled already.
+            let operation: &mut IoQueueOperation = match self.operations.get_mut(&qt) {
+                Some(operation) if !operation.canceled => operation,
+                _ => continue,
+            };
+            if !abort(qt) {
+                continue;
             }
-            Some(rawqd)
+            operation.canceled = true;
+            if let Some(new_qd) = operation.new_qd.take() {
+                self.free(new_qd);
+            }
+            ncanceled += 1;
         }
+        ncanceled
+    }
+
+    /// Gets all open I/O queue descriptors in the target I/O queue descriptors table.
+    pub fn qds(&self) -> Vec<QDesc> {
+        self.table
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.as_ref().map_or(false, |entry| entry.open))
+            .map(|(index, _)| self.get_qd(index))
+            .collect()
+    }
+
+    /// Gets the I/O queue descriptor of the next entry to be inserted in the target I/O queue descriptors table.
+    fn next_qd(&mut self) -> QDesc {
+        // Vacant entries are reused before the table grows, so this holds as long as the limit is checked.
+        let index: usize = self.vacant.front().copied().unwrap_or(self.table.len());
+        debug_assert!(index < Self::MAX_QDS, "too many I/O queue descriptors");
+        if index >= self.generations.len() {
+            self.generations.resize(index + 1, 0);
+        }
+        self.get_qd(index)
+    }
+
+    /// Removes an entry from the target I/O queue descriptors table, so that its I/O queue descriptor becomes stale.
+    fn remove(&mut self, index: usize) -> IoQueueEntry {
+        self.generations[index] = (self.generations[index] + 1) & ((1 << Self::GENERATION_BITS) - 1);
+        let entry: IoQueueEntry = self.table[index].take().expect("entry should be occupied");
+        self.vacant.push_back(index);
+        entry
+    }
+
+    /// Inserts an entry in the target I/O queue descriptors table, at the index that [IoQueueTable::next_qd] refers
+    /// to. Entries that were released first are reused first.
+    fn insert(&mut self, entry: IoQueueEntry) {
+        match self.vacant.pop_front() {
+            Some(index) => self.table[index] = Some(entry),
+            None => self.table.push(Some(entry)),
+        }
+    }
+
+    /// Gets the entry at a given index in the target I/O queue descriptors table, which must be occupied.
+    fn entry_mut(&mut self, index: usize) -> &mut IoQueueEntry {
+        self.table[index].as_mut().expect("entry should be occupied")
+    }
+
+    /// Gets the I/O queue descriptor that refers to the current generation of a given entry.
+    fn get_qd(&self, index: usize) -> QDesc {
+        QDesc::from(((self.generations[index] << Self::INDEX_BITS) | index) + Self::BASE_QD)
+    }
+
+    /// Gets the index in the I/O queue descriptors table to which a given I/O queue descriptor refers to, unless the
+    /// descriptor is stale.
+    fn get_index(&self, qd: QDesc) -> Option<usize> {
+        let rawqd: usize = Into::<usize>::into(qd).checked_sub(Self::BASE_QD)?;
+        let index: usize = rawqd & ((1 << Self::INDEX_BITS) - 1);
+        if self.table.get(index).map_or(true, Option::is_none) || self.get_qd(index) != qd {
+            return None;
+        }
+        Some(index)
     }
 }
 
@@ -89,9 +396,14 @@ impl IoQueueTable {
 
 #[cfg(test)]
 mod tests {
-    use super::IoQueueTable;
+    use super::{
+        IoQueueLimitStats,
+        IoQueueLimits,
+        IoQueueTable,
+    };
     use crate::{
         QDesc,
+        QToken,
         QType,
     };
     use ::test::{
@@ -99,6 +411,171 @@ mod tests {
         Bencher,
     };
 
+    #[test]
+    fn qds() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        let qd1: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        let qd2: QDesc = ioqueue_table.alloc(QType::UdpSocket.into());
+        ioqueue_table.free(qd1);
+        assert_eq!(ioqueue_table.qds(), vec![qd2]);
+    }
+
+    #[test]
+    fn dup_release() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        let qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        let dup_qd: QDesc = ioqueue_table.dup(qd).expect("dup() should succeed");
+        let dup_dup_qd: QDesc = ioqueue_table.dup(dup_qd).expect("dup() should succeed");
+        assert_eq!(ioqueue_table.resolve(dup_qd), Some((qd, QType::TcpSocket.into())));
+        assert_eq!(ioqueue_table.resolve(dup_dup_qd), Some((qd, QType::TcpSocket.into())));
+
+        // The queue outlives the descriptor that it was created with.
+        assert_eq!(ioqueue_table.release(qd), Some(false));
+        assert_eq!(ioqueue_table.get(qd), None);
+        assert_eq!(ioqueue_table.resolve(dup_qd), Some((qd, QType::TcpSocket.into())));
+
+        // The descriptor of the queue is not reused while the queue is alive.
+        let other_qd: QDesc = ioqueue_table.alloc(QType::UdpSocket.into());
+        assert_ne!(other_qd, qd);
+
+        // The queue is closed along with its last descriptor.
+        assert_eq!(ioqueue_table.release(dup_dup_qd), Some(false));
+        assert_eq!(ioqueue_table.release(dup_qd), Some(true));
+        assert_eq!(ioqueue_table.release(dup_qd), None);
+        assert_eq!(ioqueue_table.qds(), vec![other_qd]);
+    }
+
+    #[test]
+    fn stale_qd() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        let qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        assert_eq!(ioqueue_table.release(qd), Some(true));
+
+        // The entry is reused, but with a different descriptor.
+        let new_qd: QDesc = ioqueue_table.alloc(QType::UdpSocket.into());
+        assert_ne!(new_qd, qd);
+        assert_eq!(ioqueue_table.get(qd), None);
+        assert_eq!(ioqueue_table.get(new_qd), Some(QType::UdpSocket.into()));
+
+        // Stale descriptors are not accepted by any operation.
+        assert_eq!(ioqueue_table.dup(qd), None);
+        assert_eq!(ioqueue_table.release(qd), None);
+        assert_eq!(ioqueue_table.free(qd), None);
+        assert_eq!(ioqueue_table.qds(), vec![new_qd]);
+    }
+
+    #[test]
+    fn stale_qd_reuse_order() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        let qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        let other_qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        ioqueue_table.free(other_qd);
+        assert_eq!(ioqueue_table.release(qd), Some(true));
+
+        // Entries are reused in the order that they were released, so generations of an entry do not wrap around while
+        // descriptors are opened and closed in a loop.
+        for _ in 0..(1 << IoQueueTable::GENERATION_BITS) {
+            let new_qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+            assert_ne!(new_qd, qd);
+            assert_eq!(ioqueue_table.get(qd), None);
+            ioqueue_table.free(new_qd);
+        }
+    }
+
+    #[test]
+    fn qd_fits_in_i32() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        for _ in 0..(1 << IoQueueTable::GENERATION_BITS) + 1 {
+            let qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+            assert!(i32::from(qd) > 0);
+            assert_eq!(QDesc::from(i32::from(qd)), qd);
+            ioqueue_table.free(qd);
+        }
+    }
+
+    #[test]
+    fn cancel_pending() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        let qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        let other_qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        let new_qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        let (accept_qt, splice_qt, pop_qt, completed_qt): (QToken, QToken, QToken, QToken) =
+            (QToken::from(1), QToken::from(2), QToken::from(3), QToken::from(4));
+        ioqueue_table.track(accept_qt, &[qd], Some(new_qd));
+        ioqueue_table.track(splice_qt, &[other_qd, qd], None);
+        ioqueue_table.track(pop_qt, &[other_qd], None);
+        ioqueue_table.track(completed_qt, &[qd], None);
+
+        // Operations on the descriptor are canceled, unless they have already completed.
+        let ncanceled: usize = ioqueue_table.cancel(qd, |qt| qt != completed_qt);
+        assert_eq!(ncanceled, 2);
+        assert_eq!(ioqueue_table.release(qd), Some(true));
+
+        // Descriptors that were allocated by canceled operations are freed.
+        assert_eq!(ioqueue_table.get(new_qd), None);
+
+        // Canceled operations report the descriptor on which they were issued.
+        assert_eq!(ioqueue_table.untrack(accept_qt), Some(qd));
+        assert_eq!(ioqueue_table.untrack(splice_qt), Some(other_qd));
+        assert_eq!(ioqueue_table.get_issuer(completed_qt), Some(qd));
+        assert_eq!(ioqueue_table.untrack(completed_qt), None);
+        assert_eq!(ioqueue_table.get_issuer(completed_qt), None);
+
+        // Operations on other descriptors are left untouched.
+        assert_eq!(ioqueue_table.cancel(other_qd, |_| true), 1);
+        assert_eq!(ioqueue_table.untrack(pop_qt), Some(other_qd));
+        assert_eq!(ioqueue_table.untrack(pop_qt), None);
+    }
+
+    #[test]
+    fn limits() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        ioqueue_table.set_limits(IoQueueLimits {
+            max_qds: Some(2),
+            max_operations: Some(1),
+        });
+
+        // Descriptors are refused once the limit is reached, and accepted again once some is released.
+        let qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        assert!(ioqueue_table.check_qd_limit().is_ok());
+        let other_qd: QDesc = ioqueue_table.alloc(QType::TcpSocket.into());
+        assert_eq!(ioqueue_table.check_qd_limit().unwrap_err().errno, libc::ENFILE);
+        assert_eq!(ioqueue_table.release(other_qd), Some(true));
+        assert!(ioqueue_table.check_qd_limit().is_ok());
+
+        // Operations are refused while too many of them are pending.
+        let qt: QToken = QToken::from(1);
+        assert!(ioqueue_table.check_operation_limit().is_ok());
+        ioqueue_table.track(qt, &[qd], None);
+        assert_eq!(ioqueue_table.check_operation_limit().unwrap_err().errno, libc::EAGAIN);
+        ioqueue_table.untrack(qt);
+        assert!(ioqueue_table.check_operation_limit().is_ok());
+
+        assert_eq!(
+            ioqueue_table.limit_stats(),
+            IoQueueLimitStats {
+                refused_qds: 1,
+                refused_operations: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn limits_cap_qds() {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
+        ioqueue_table.set_limits(IoQueueLimits {
+            max_qds: Some(usize::MAX),
+            max_operations: None,
+        });
+
+        // Descriptors are refused once their index can no longer be encoded, even if the limit is higher.
+        for _ in 0..IoQueueTable::MAX_QDS {
+            assert!(ioqueue_table.check_qd_limit().is_ok());
+            ioqueue_table.alloc(QType::TcpSocket.into());
+        }
+        assert_eq!(ioqueue_table.check_qd_limit().unwrap_err().errno, libc::ENFILE);
+    }
+
     #[bench]
     fn bench_alloc_free(b: &mut Bencher) {
         let mut ioqueue_table: IoQueueTable = IoQueueTable::new();
@@ -14,6 +14,14 @@ pub enum QType {
     UdpSocket = 0x0001,
     TcpSocket = 0x0002,
     MemoryQueue = 0x003,
+    /// Queue that reports link events.
+    LinkEvents = 0x0004,
+    /// Stream socket of the unix domain.
+    UnixStream = 0x0005,
+    /// Datagram socket of the unix domain.
+    UnixDatagram = 0x0006,
+    /// Raw IP socket, which carries an arbitrary IP protocol.
+    RawSocket = 0x0007,
 }
 
 //==============================================================================
@@ -27,6 +35,10 @@ impl From<QType> for u32 {
             QType::UdpSocket => 0x0001,
             QType::TcpSocket => 0x0002,
             QType::MemoryQueue => 0x0003,
+            QType::LinkEvents => 0x0004,
+            QType::UnixStream => 0x0005,
+            QType::UnixDatagram => 0x0006,
+            QType::RawSocket => 0x0007,
         }
     }
 }
@@ -40,6 +52,10 @@ impl TryFrom<u32> for QType {
             0x0001 => Ok(QType::UdpSocket),
             0x0002 => Ok(QType::TcpSocket),
             0x0003 => Ok(QType::MemoryQueue),
+            0x0004 => Ok(QType::LinkEvents),
+            0x0005 => Ok(QType::UnixStream),
+            0x0006 => Ok(QType::UnixDatagram),
+            0x0007 => Ok(QType::RawSocket),
             _ => Err("invalid qtype"),
         }
     }
@@ -5,9 +5,10 @@
 // Imports
 //==============================================================================
 
-use crate::collections::intrusive::pairing_heap::{
-    HeapNode,
-    PairingHeap,
+use crate::scheduler::{
+    Scheduler,
+    SleepFuture,
+    TimerWheel,
 };
 use ::futures::future::FusedFuture;
 use ::std::{
@@ -36,33 +37,13 @@ pub trait TimerPtr: Sized {
     fn timer(&self) -> &Timer<Self>;
 }
 
-//==============================================================================
-// Enumerations
-//==============================================================================
-
-enum PollState {
-    Unregistered,
-    Registered,
-    Expired,
-}
-
 //==============================================================================
 // Structures
 //==============================================================================
 
-struct TimerQueueEntry {
-    expiry: Instant,
-    task: Option<Waker>,
-    state: PollState,
-}
-
-struct TimerInner {
-    now: Instant,
-    heap: PairingHeap<TimerQueueEntry>,
-}
-
+/// Clock that is backed by a hierarchical timer wheel.
 pub struct Timer<P: TimerPtr> {
-    inner: RefCell<TimerInner>,
+    timers: Rc<RefCell<TimerWheel>>,
     _marker: PhantomData<P>,
 }
 
@@ -71,7 +52,7 @@ pub struct TimerRc(pub Rc<Timer<TimerRc>>);
 
 pub struct WaitFuture<P: TimerPtr> {
     ptr: Option<P>,
-    wait_node: HeapNode<TimerQueueEntry>,
+    sleep: SleepFuture,
 }
 
 //==============================================================================
@@ -80,39 +61,27 @@ pub struct WaitFuture<P: TimerPtr> {
 
 impl<P: TimerPtr> Timer<P> {
     pub fn new(now: Instant) -> Self {
-        let inner = TimerInner {
-            now,
-            heap: PairingHeap::new(),
-        };
         Self {
-            inner: RefCell::new(inner),
+            timers: Rc::new(RefCell::new(TimerWheel::new(now))),
             _marker: PhantomData,
         }
     }
 
-    pub fn advance_clock(&self, now: Instant) {
-        let mut inner = self.inner.borrow_mut();
-        assert!(inner.now <= now);
-
-        while let Some(mut first) = inner.heap.peek_min() {
-            unsafe {
-                let entry = first.as_mut();
-                let first_expiry = entry.expiry;
-                if now < first_expiry {
-                    break;
-                }
-                entry.state = PollState::Expired;
-                if let Some(task) = entry.task.take() {
-                    task.wake();
-                }
-                inner.heap.remove(entry);
-            }
+    /// Creates a clock that shares the timer wheel of `scheduler`, so that advancing either of them advances both.
+    pub fn from_scheduler(scheduler: &Scheduler) -> Self {
+        Self {
+            timers: scheduler.timers(),
+            _marker: PhantomData,
         }
-        inner.now = now;
+    }
+
+    pub fn advance_clock(&self, now: Instant) {
+        let wakers: Vec<Waker> = self.timers.borrow_mut().advance(now);
+        wakers.into_iter().for_each(Waker::wake);
     }
 
     pub fn now(&self) -> Instant {
-        self.inner.borrow().now
+        self.timers.borrow().now()
     }
 
     pub fn wait(&self, ptr: P, timeout: Duration) -> WaitFuture<P> {
@@ -120,14 +89,9 @@ impl<P: TimerPtr> Timer<P> {
     }
 
     pub fn wait_until(&self, ptr: P, expiry: Instant) -> WaitFuture<P> {
-        let entry = TimerQueueEntry {
-            expiry,
-            task: None,
-            state: PollState::Unregistered,
-        };
         WaitFuture {
             ptr: Some(ptr),
-            wait_node: HeapNode::new(entry),
+            sleep: SleepFuture::new(self.timers.clone(), expiry),
         }
     }
 }
@@ -150,65 +114,14 @@ impl TimerPtr for TimerRc {
     }
 }
 
-impl PartialEq for TimerQueueEntry {
-    fn eq(&self, other: &TimerQueueEntry) -> bool {
-        // This is technically not correct. However for the usage in this module
-        // we only need to compare timers by expiration.
-        self.expiry == other.expiry
-    }
-}
-
-impl Eq for TimerQueueEntry {}
-
-impl PartialOrd for TimerQueueEntry {
-    fn partial_cmp(&self, other: &TimerQueueEntry) -> Option<core::cmp::Ordering> {
-        // Compare timer queue entries by expiration time
-        self.expiry.partial_cmp(&other.expiry)
-    }
-}
-
-impl Ord for TimerQueueEntry {
-    fn cmp(&self, other: &TimerQueueEntry) -> core::cmp::Ordering {
-        self.expiry.cmp(&other.expiry)
-    }
-}
-
 impl<P: TimerPtr> Future for WaitFuture<P> {
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
         let mut_self: &mut Self = unsafe { Pin::get_unchecked_mut(self) };
+        assert!(mut_self.ptr.is_some(), "Polled future after completion");
 
-        let result = {
-            let ptr = mut_self.ptr.as_ref().expect("Polled future after completion");
-            let timer = ptr.timer();
-
-            let mut inner = timer.inner.borrow_mut();
-            let wait_node = &mut mut_self.wait_node;
-
-            match wait_node.state {
-                PollState::Unregistered => {
-                    if inner.now >= wait_node.expiry {
-                        wait_node.state = PollState::Expired;
-                        Poll::Ready(())
-                    } else {
-                        wait_node.task = Some(cx.waker().clone());
-                        wait_node.state = PollState::Registered;
-                        unsafe {
-                            inner.heap.insert(wait_node);
-                        }
-                        Poll::Pending
-                    }
-                },
-                PollState::Registered => {
-                    if wait_node.task.as_ref().map_or(true, |w| !w.will_wake(cx.waker())) {
-                        wait_node.task = Some(cx.waker().clone());
-                    }
-                    Poll::Pending
-                },
-                PollState::Expired => Poll::Ready(()),
-            }
-        };
+        let result: Poll<()> = Future::poll(Pin::new(&mut mut_self.sleep), cx);
         if result.is_ready() {
             mut_self.ptr = None;
         }
@@ -222,20 +135,6 @@ impl<P: TimerPtr> FusedFuture for WaitFuture<P> {
     }
 }
 
-impl<P: TimerPtr> Drop for WaitFuture<P> {
-    fn drop(&mut self) {
-        // If this TimerFuture has been polled and it was added to the
-        // wait queue at the timer, it must be removed before dropping.
-        // Otherwise the timer would access invalid memory.
-        if let Some(ptr) = &self.ptr {
-            if let PollState::Registered = self.wait_node.state {
-                unsafe { ptr.timer().inner.borrow_mut().heap.remove(&mut self.wait_node) };
-                self.wait_node.state = PollState::Unregistered;
-            }
-        }
-    }
-}
-
 //==============================================================================
 // Unit Tests
 //==============================================================================
@@ -7,7 +7,10 @@
 // Imports
 //==============================================================================
 
-use ::libc::c_void;
+use ::libc::{
+    c_int,
+    c_void,
+};
 
 use crate::pal::data_structures::SockAddr;
 
@@ -16,7 +19,7 @@ use crate::pal::data_structures::SockAddr;
 //==============================================================================
 
 /// Maximum Length for Scatter-Gather Arrays
-pub const DEMI_SGARRAY_MAXLEN: usize = 1;
+pub const DEMI_SGARRAY_MAXLEN: usize = 16;
 
 //==============================================================================
 // Structures
@@ -32,6 +35,33 @@ pub struct demi_sgaseg_t {
     pub sgaseg_len: u32,
 }
 
+/// Packet Information of a Popped Datagram
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_pktinfo_t {
+    /// Local address that the datagram was sent to (in network byte order).
+    pub pi_dstaddr: u32,
+    /// Time-to-live of the datagram, or `-1` if unknown.
+    pub pi_ttl: c_int,
+    /// Arrival time of the datagram (in nanoseconds since the Unix epoch), or zero if unknown.
+    pub pi_timestamp: i64,
+    /// Time at which the network interface received the datagram (in nanoseconds, as read from its clock), or zero if
+    /// it was not timestamped by hardware.
+    pub pi_hwtimestamp: u64,
+}
+
+/// Hardware Timestamps of a Socket
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct demi_timestamps_t {
+    /// Time at which the network interface last received a frame on the socket (in nanoseconds, as read from its
+    /// clock), or zero if unknown.
+    pub ts_rx: u64,
+    /// Time at which the network interface last transmitted a frame on the socket (in nanoseconds, as read from its
+    /// clock), or zero if unknown.
+    pub ts_tx: u64,
+}
+
 /// Scatter-Gather Array
 // ToDo: Review the inclusion of the sga_addr field (only used for recvfrom?) in this structure.
 #[repr(C)]
@@ -45,4 +75,10 @@ pub struct demi_sgarray_t {
     pub sga_segs: [demi_sgaseg_t; DEMI_SGARRAY_MAXLEN],
     /// Source address of the data contained in this scatter-gather array (if present).
     pub sga_addr: SockAddr,
+    /// Packet information of the data contained in this scatter-gather array (if flagged in the operation result).
+    pub sga_pktinfo: demi_pktinfo_t,
+    /// Source address of the data contained in this scatter-gather array, if it was popped from a unix domain socket
+    /// (see [super::DEMI_QR_FLAG_UNIXADDR]).
+    #[cfg(target_os = "linux")]
+    pub sga_unaddr: libc::sockaddr_un,
 }
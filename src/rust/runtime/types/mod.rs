@@ -11,8 +11,10 @@ mod queue;
 
 pub use self::{
     memory::{
+        demi_pktinfo_t,
         demi_sgarray_t,
         demi_sgaseg_t,
+        demi_timestamps_t,
         DEMI_SGARRAY_MAXLEN,
     },
     ops::{
@@ -20,6 +22,10 @@ pub use self::{
         demi_opcode_t,
         demi_qr_value_t,
         demi_qresult_t,
+        DEMI_QR_FLAG_EOF,
+        DEMI_QR_FLAG_PKTINFO,
+        DEMI_QR_FLAG_TRUNC,
+        DEMI_QR_FLAG_UNIXADDR,
     },
     queue::demi_qtoken_t,
 };
@@ -16,6 +16,23 @@ use ::libc::{
     sockaddr,
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Result flag set on a pop that hit end-of-stream (i.e. the remote peer closed the connection).
+pub const DEMI_QR_FLAG_EOF: u32 = 1 << 0;
+
+/// Result flag set on a pop that returned a datagram truncated to fit the receive buffer.
+pub const DEMI_QR_FLAG_TRUNC: u32 = 1 << 1;
+
+/// Result flag set on a pop that carries packet information in the popped scatter-gather array.
+pub const DEMI_QR_FLAG_PKTINFO: u32 = 1 << 2;
+
+/// Result flag set on a pop from a unix domain socket, whose source address is in the unix address of the popped
+/// scatter-gather array.
+pub const DEMI_QR_FLAG_UNIXADDR: u32 = 1 << 3;
+
 //==============================================================================
 // Structures
 //==============================================================================
@@ -30,6 +47,10 @@ pub enum demi_opcode_t {
     DEMI_OPC_ACCEPT,
     DEMI_OPC_CONNECT,
     DEMI_OPC_FAILED,
+    DEMI_OPC_SPLICE,
+    DEMI_OPC_CLOSE,
+    DEMI_OPC_PING,
+    DEMI_OPC_LINK,
 }
 
 /// Result for `accept()`
@@ -44,6 +65,10 @@ pub struct demi_accept_result_t {
 pub union demi_qr_value_t {
     pub sga: demi_sgarray_t,
     pub ares: demi_accept_result_t,
+    /// Round-trip time of a ping, in nanoseconds.
+    pub rtt: i64,
+    /// State of the link after a link event: one if it is up, zero if it is down.
+    pub link_up: u32,
 }
 
 /// Result
@@ -52,5 +77,8 @@ pub struct demi_qresult_t {
     pub qr_opcode: demi_opcode_t,
     pub qr_qd: c_int,
     pub qr_qt: demi_qtoken_t,
+    pub qr_flags: u32,
+    /// Error code of a failed operation, or zero otherwise.
+    pub qr_ret: i64,
     pub qr_value: demi_qr_value_t,
 }
@@ -0,0 +1,84 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    runtime::QDesc,
+    scheduler::SchedulerFuture,
+};
+use ::std::{
+    any::Any,
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Aborted Task
+///
+/// Takes the place of a task that was aborted, so that the task completes on
+/// the next poll without its original future being polled again. The kind and
+/// owner of the original task are kept for introspection.
+pub struct AbortedTask {
+    /// Kind of the original task.
+    kind: &'static str,
+    /// Queue descriptor that owned the original task, if any.
+    qd: Option<QDesc>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Aborted Tasks
+impl AbortedTask {
+    /// Creates a task that takes the place of `task`, once aborted.
+    pub fn new(task: &dyn SchedulerFuture) -> Self {
+        Self {
+            kind: task.get_kind(),
+            qd: task.get_qd(),
+        }
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Aborted Tasks
+impl Future for AbortedTask {
+    type Output = ();
+
+    /// Completes right away.
+    fn poll(self: Pin<&mut Self>, _ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(())
+    }
+}
+
+/// Scheduler Future Trait Implementation for Aborted Tasks
+impl SchedulerFuture for AbortedTask {
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn get_future(&self) -> &dyn Future<Output = ()> {
+        todo!()
+    }
+
+    fn get_kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn get_qd(&self) -> Option<QDesc> {
+        self.qd
+    }
+}
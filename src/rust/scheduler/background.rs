@@ -0,0 +1,68 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::scheduler::SchedulerFuture;
+use ::std::{
+    any::Any,
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Background Task
+///
+/// Wraps an arbitrary future, such as a co-routine of an application, so that
+/// it may be inserted into the scheduler and driven by its polls.
+pub struct BackgroundTask(Pin<Box<dyn Future<Output = ()>>>);
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Background Tasks
+impl BackgroundTask {
+    /// Creates a background task that drives `future`.
+    pub fn new(future: Pin<Box<dyn Future<Output = ()>>>) -> Self {
+        Self(future)
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Background Tasks
+impl Future for BackgroundTask {
+    type Output = ();
+
+    /// Polls the underlying future of the target [BackgroundTask].
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        Future::poll(self.get_mut().0.as_mut(), ctx)
+    }
+}
+
+/// Scheduler Future Trait Implementation for Background Tasks
+impl SchedulerFuture for BackgroundTask {
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn get_future(&self) -> &dyn Future<Output = ()> {
+        todo!()
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "Background"
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Poll Budget
+///
+/// Bounds the work that is done on a single poll of the scheduler. Notified
+/// tasks that are left over are polled first on the next poll, so that tasks
+/// are served in a round-robin fashion. At least one task is always polled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PollBudget {
+    /// Maximum number of tasks that are polled, if any.
+    pub max_tasks: Option<usize>,
+    /// Maximum time that is spent polling tasks, if any.
+    pub max_time: Option<Duration>,
+}
+
+/// Budget that is left in an ongoing poll.
+pub struct Allowance {
+    /// Number of tasks that were polled so far.
+    polled: usize,
+    /// Maximum number of tasks that may be polled.
+    max_tasks: usize,
+    /// Point in time after which no more tasks are polled, if any.
+    deadline: Option<Instant>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Poll Budgets
+impl PollBudget {
+    /// Starts spending the target [PollBudget].
+    pub fn start(&self) -> Allowance {
+        Allowance {
+            polled: 0,
+            max_tasks: self.max_tasks.unwrap_or(usize::MAX),
+            deadline: self.max_time.map(|max_time| Instant::now() + max_time),
+        }
+    }
+}
+
+/// Associate Functions for Allowances
+impl Allowance {
+    /// Takes the allowance to poll one more task, unless the budget is exhausted.
+    pub fn take(&mut self) -> bool {
+        if self.polled > 0 {
+            if self.polled >= self.max_tasks {
+                return false;
+            }
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    return false;
+                }
+            }
+        }
+        self.polled += 1;
+        true
+    }
+
+    /// Returns the number of tasks that were polled so far.
+    pub fn polled(&self) -> usize {
+        self.polled
+    }
+}
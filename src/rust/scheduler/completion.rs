@@ -0,0 +1,122 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::collections::{
+    HashSet,
+    VecDeque,
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Number of stale entries that are tolerated in a [CompletionQueue] before it is compacted.
+const MAX_STALE_ENTRIES: usize = 64;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Completion Queue
+///
+/// Records the keys of tasks that have completed, in completion order, so that
+/// they may be found without scanning all pending tasks. Keys that are removed
+/// are lazily discarded from the queue.
+#[derive(Default)]
+pub struct CompletionQueue {
+    /// Keys of completed tasks, in completion order. This may contain stale entries.
+    queue: VecDeque<u64>,
+    /// Keys of completed tasks that were not removed yet.
+    live: HashSet<u64>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Completion Queues
+impl CompletionQueue {
+    /// Records that the task identified by `key` has completed.
+    pub fn push(&mut self, key: u64) {
+        if self.live.insert(key) {
+            self.queue.push_back(key);
+        }
+    }
+
+    /// Removes the task identified by `key` from the target [CompletionQueue].
+    pub fn remove(&mut self, key: u64) {
+        if self.live.remove(&key) && self.queue.len() > 2 * self.live.len() + MAX_STALE_ENTRIES {
+            let live: &HashSet<u64> = &self.live;
+            self.queue.retain(|key| live.contains(key));
+        }
+    }
+
+    /// Removes and returns the key of the earliest completed task that satisfies `pred`.
+    pub fn take<P: Fn(u64) -> bool>(&mut self, pred: P) -> Option<u64> {
+        // Drop stale entries at the front of the queue.
+        while let Some(key) = self.queue.front() {
+            if self.live.contains(key) {
+                break;
+            }
+            self.queue.pop_front();
+        }
+
+        let ix: usize = self
+            .queue
+            .iter()
+            .position(|key| self.live.contains(key) && pred(*key))?;
+        let key: u64 = self.queue.remove(ix)?;
+        self.live.remove(&key);
+        Some(key)
+    }
+
+    /// Returns the number of completed tasks in the target [CompletionQueue].
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::CompletionQueue;
+
+    #[test]
+    fn completion_queue_order() {
+        let mut queue: CompletionQueue = CompletionQueue::default();
+        queue.push(3);
+        queue.push(1);
+        queue.push(2);
+        queue.push(1);
+        assert_eq!(queue.len(), 3);
+
+        // Keys are taken in completion order, skipping those that do not match.
+        assert_eq!(queue.take(|key| key != 3), Some(1));
+        assert_eq!(queue.take(|_| true), Some(3));
+
+        // Removed keys are never taken.
+        queue.remove(2);
+        assert_eq!(queue.take(|_| true), None);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn completion_queue_compaction() {
+        let mut queue: CompletionQueue = CompletionQueue::default();
+        for key in 0..1024 {
+            queue.push(key);
+        }
+        for key in 1..1024 {
+            queue.remove(key);
+        }
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.take(|_| true), Some(0));
+    }
+}
@@ -5,6 +5,7 @@
 // Imports
 //==============================================================================
 
+use crate::runtime::QDesc;
 use ::std::{
     any::Any,
     future::Future,
@@ -23,4 +24,14 @@ pub trait SchedulerFuture: Any + Future<Output = ()> + Unpin {
 
     /// Gets the underlying future in the target [SchedulerFuture].
     fn get_future(&self) -> &dyn Future<Output = ()>;
+
+    /// Gets the kind of the target [SchedulerFuture] (e.g. "Accept" or "Pop"), for introspection.
+    fn get_kind(&self) -> &'static str {
+        "Task"
+    }
+
+    /// Gets the queue descriptor that owns the target [SchedulerFuture], if any, for introspection.
+    fn get_qd(&self) -> Option<QDesc> {
+        None
+    }
 }
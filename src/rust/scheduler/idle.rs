@@ -0,0 +1,168 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::std::time::Duration;
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Time that is slept on the first park of an idle period, when there is no descriptor to park on.
+const MIN_SLEEP: Duration = Duration::from_micros(1);
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Idle Policy
+///
+/// Tells when the thread that polls the scheduler may be parked instead of
+/// busy-polling. The scheduler starts parking after some number of idle polls
+/// in a row, and stops only after some number of busy polls in a row, so that
+/// it does not flip between both modes on sporadic work.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IdlePolicy {
+    /// Number of idle polls in a row after which the thread is parked.
+    pub spin_polls: usize,
+    /// Number of busy polls in a row after which the thread is no longer parked.
+    pub busy_polls: usize,
+    /// Maximum time that the thread is parked at once.
+    pub max_park: Duration,
+}
+
+/// Tracks whether the scheduler is idle.
+#[derive(Default)]
+pub struct IdleState {
+    /// Number of idle polls in a row.
+    idle_polls: usize,
+    /// Number of busy polls in a row.
+    busy_polls: usize,
+    /// Whether the thread is parked on idle polls.
+    parking: bool,
+    /// Time that is slept on the next park, when there is no descriptor to park on.
+    sleep: Duration,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Idle States
+impl IdleState {
+    /// Records whether the last poll was `busy`, switching into or out of parking as per `policy`.
+    pub fn record(&mut self, policy: &IdlePolicy, busy: bool) {
+        if busy {
+            self.idle_polls = 0;
+            self.busy_polls += 1;
+            if self.parking && self.busy_polls >= policy.busy_polls {
+                self.parking = false;
+            }
+        } else {
+            self.busy_polls = 0;
+            self.idle_polls += 1;
+            if !self.parking && self.idle_polls >= policy.spin_polls {
+                self.parking = true;
+                self.sleep = MIN_SLEEP;
+            }
+        }
+    }
+
+    /// Returns for how long the thread may be parked as per `policy`, if the last poll was idle and the thread is
+    /// parked on idle polls. This is further bounded by `limit`, if any.
+    pub fn park_time(&self, policy: &IdlePolicy, limit: Option<Duration>) -> Option<Duration> {
+        if !self.parking || self.idle_polls == 0 {
+            return None;
+        }
+        Some(limit.map_or(policy.max_park, |limit| limit.min(policy.max_park)))
+    }
+
+    /// Returns for how long the thread should sleep as per `policy`, when there is no descriptor to park on. Sleeps
+    /// double on every park of an idle period, up to the maximum park time, since there is no way to tell that some
+    /// work has arrived in the meantime.
+    pub fn sleep_time(&mut self, policy: &IdlePolicy, limit: Option<Duration>) -> Option<Duration> {
+        let park_time: Duration = self.park_time(policy, limit)?;
+        let sleep: Duration = self.sleep.min(park_time);
+        self.sleep = (self.sleep * 2).min(policy.max_park);
+        Some(sleep)
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::scheduler::idle::{
+        IdlePolicy,
+        IdleState,
+        MIN_SLEEP,
+    };
+    use ::std::time::Duration;
+
+    const POLICY: IdlePolicy = IdlePolicy {
+        spin_polls: 3,
+        busy_polls: 2,
+        max_park: Duration::from_millis(1),
+    };
+
+    #[test]
+    fn idle_hysteresis() {
+        let mut state: IdleState = IdleState::default();
+
+        // Spin until there were enough idle polls in a row.
+        for _ in 0..2 {
+            state.record(&POLICY, false);
+            assert_eq!(state.park_time(&POLICY, None), None);
+        }
+        state.record(&POLICY, true);
+        for _ in 0..2 {
+            state.record(&POLICY, false);
+            assert_eq!(state.park_time(&POLICY, None), None);
+        }
+        state.record(&POLICY, false);
+        assert_eq!(state.park_time(&POLICY, None), Some(POLICY.max_park));
+
+        // A single busy poll does not stop parking on idle polls.
+        state.record(&POLICY, true);
+        assert_eq!(state.park_time(&POLICY, None), None);
+        state.record(&POLICY, false);
+        assert_eq!(state.park_time(&POLICY, None), Some(POLICY.max_park));
+
+        // Enough busy polls in a row do.
+        state.record(&POLICY, true);
+        state.record(&POLICY, true);
+        state.record(&POLICY, false);
+        assert_eq!(state.park_time(&POLICY, None), None);
+    }
+
+    #[test]
+    fn idle_park_limit() {
+        let mut state: IdleState = IdleState::default();
+        for _ in 0..POLICY.spin_polls {
+            state.record(&POLICY, false);
+        }
+        let limit: Duration = Duration::from_micros(10);
+        assert_eq!(state.park_time(&POLICY, Some(limit)), Some(limit));
+    }
+
+    #[test]
+    fn idle_sleep_backoff() {
+        let mut state: IdleState = IdleState::default();
+        for _ in 0..POLICY.spin_polls {
+            state.record(&POLICY, false);
+        }
+
+        // Sleeps double up to the maximum park time.
+        let mut expected: Duration = MIN_SLEEP;
+        for _ in 0..16 {
+            assert_eq!(state.sleep_time(&POLICY, None), Some(expected));
+            expected = (expected * 2).min(POLICY.max_park);
+        }
+        assert_eq!(state.sleep_time(&POLICY, None), Some(POLICY.max_park));
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::{
+    runtime::QDesc,
+    scheduler::Priority,
+};
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Task Information
+///
+/// Describes a live task of the scheduler, so that leaked or stuck operations
+/// may be found without attaching a debugger. Poll times are only measured if
+/// poll timing is enabled on the scheduler.
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    /// Key of the task, which is the queue token of I/O operations.
+    pub key: u64,
+    /// Kind of the task (e.g. "Accept" or "Pop").
+    pub kind: &'static str,
+    /// Queue descriptor that owns the task, if any.
+    pub qd: Option<QDesc>,
+    /// Priority of the task.
+    pub priority: Priority,
+    /// Whether the task has completed, but its result was not taken yet.
+    pub completed: bool,
+    /// Number of times that the task was polled.
+    pub polls: usize,
+    /// Time elapsed since the task was inserted, on the clock of the scheduler.
+    pub age: Duration,
+    /// Total time spent polling the task.
+    pub poll_time: Duration,
+    /// Longest time spent on a single poll of the task.
+    pub max_poll_time: Duration,
+}
+
+/// Statistics that are kept for every task of the scheduler.
+pub struct TaskStats {
+    /// Point in time at which the task was inserted, on the clock of the scheduler.
+    pub inserted: Instant,
+    /// Number of times that the task was polled.
+    pub polls: usize,
+    /// Total time spent polling the task.
+    pub poll_time: Duration,
+    /// Longest time spent on a single poll of the task.
+    pub max_poll_time: Duration,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Task Statistics
+impl TaskStats {
+    /// Creates statistics for a task that is inserted at `now`.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            inserted: now,
+            polls: 0,
+            poll_time: Duration::ZERO,
+            max_poll_time: Duration::ZERO,
+        }
+    }
+
+    /// Records a poll of the task, which took `elapsed`, if measured.
+    pub fn record(&mut self, elapsed: Option<Duration>) {
+        self.polls += 1;
+        if let Some(elapsed) = elapsed {
+            self.poll_time += elapsed;
+            self.max_poll_time = self.max_poll_time.max(elapsed);
+        }
+    }
+}
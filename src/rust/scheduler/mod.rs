@@ -1,12 +1,21 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod aborted;
+mod background;
+mod budget;
+mod completion;
 mod future;
 mod handle;
+mod idle;
+mod info;
 mod page;
 mod pin_slab;
+mod pool;
+mod priority;
 mod result;
 pub mod scheduler;
+mod timer;
 mod waker64;
 
 //==============================================================================
@@ -14,8 +23,21 @@ mod waker64;
 //==============================================================================
 
 pub use self::{
+    background::BackgroundTask,
+    budget::PollBudget,
     future::SchedulerFuture,
     handle::SchedulerHandle,
+    idle::IdlePolicy,
+    info::TaskInfo,
+    pool::{
+        OffloadFuture,
+        WorkerPool,
+    },
+    priority::Priority,
     result::FutureResult,
     scheduler::Scheduler,
+    timer::{
+        SleepFuture,
+        TimerWheel,
+    },
 };
@@ -101,6 +101,17 @@ impl WakerPage {
         self.dropped.load() & (1 << ix) != 0
     }
 
+    /// Queries whether or not any future in the target [WakerPage] has been notified or has completed, and has not been
+    /// dropped.
+    pub fn has_ready(&self) -> bool {
+        (self.notified.load() | self.completed.load()) & !self.dropped.load() != 0
+    }
+
+    /// Queries whether or not any future in the target [WakerPage] has been notified, and has not been dropped.
+    pub fn has_notified(&self) -> bool {
+        self.notified.load() & !self.dropped.load() != 0
+    }
+
     /// Resets all flags in the target [WakerPage].
     /// The reference count for the target page is reset to one.
     pub fn reset(&mut self) {
@@ -0,0 +1,425 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Implementation of a pool of worker threads, on which thread-safe backend
+//! operations are offloaded from the scheduler.
+//!
+//! Each worker has its own run queue, and it may be pinned to a core. Jobs are
+//! spread across run queues in a round-robin fashion, and workers that run out
+//! of jobs steal them from the back of the run queues of other workers. Tasks
+//! of the scheduler are not thread-safe, so workers never wake them directly.
+//! Instead, completed jobs are reported back in a mailbox, which is reaped on
+//! the thread of the scheduler with [WorkerPool::reap]. Jobs whose future is
+//! dropped before some worker claims them are skipped, so jobs should own the
+//! resources that they act on.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::fail::Fail;
+use ::slab::Slab;
+use ::std::{
+    cell::{
+        Cell,
+        RefCell,
+        RefMut,
+    },
+    collections::VecDeque,
+    future::Future,
+    mem,
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicI32,
+            Ordering,
+        },
+        Arc,
+        Condvar,
+        Mutex,
+        MutexGuard,
+    },
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+    thread::{
+        self,
+        JoinHandle,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Job that is run by some worker.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// State that is shared between a [WorkerPool] and its workers.
+struct Shared {
+    /// Run queue of each worker.
+    queues: Vec<Mutex<VecDeque<Job>>>,
+    /// Number of jobs that are queued and not yet claimed by some worker, and whether the pool is shutting down.
+    pending: Mutex<(usize, bool)>,
+    /// Signaled when jobs are queued or when the pool shuts down.
+    available: Condvar,
+    /// Keys of the jobs that have completed but were not reaped yet.
+    completed: Mutex<Vec<usize>>,
+    /// Event file descriptor that is signaled when some job completes, or `-1` if none.
+    eventfd: AtomicI32,
+}
+
+/// Worker Pool
+pub struct WorkerPool {
+    /// State shared with the workers.
+    shared: Arc<Shared>,
+    /// Worker threads.
+    threads: RefCell<Vec<JoinHandle<()>>>,
+    /// Run queue in which the next job is placed.
+    next: Cell<usize>,
+    /// Wakers of the tasks that wait on offloaded jobs.
+    waiters: RefCell<Slab<Option<Waker>>>,
+}
+
+/// Future that completes once an offloaded job has run on some worker.
+pub struct OffloadFuture<T: Send + 'static> {
+    /// Pool on which the job was offloaded.
+    pool: Rc<WorkerPool>,
+    /// Key of the job.
+    key: usize,
+    /// Output of the job, once it has run.
+    output: Arc<Mutex<Option<T>>>,
+    /// Set once the future is dropped, so that the job is skipped if it did not run yet.
+    canceled: Arc<AtomicBool>,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Shared State
+impl Shared {
+    /// Claims a job, blocking until one is available. The run queue of the worker `ix` is served first, and jobs are
+    /// stolen from the other run queues otherwise. Returns `None` once the pool shuts down and no job is left.
+    fn claim(&self, ix: usize) -> Option<Job> {
+        {
+            let mut pending: MutexGuard<(usize, bool)> = self.pending.lock().unwrap();
+            while pending.0 == 0 {
+                if pending.1 {
+                    return None;
+                }
+                pending = self.available.wait(pending).unwrap();
+            }
+            pending.0 -= 1;
+        }
+
+        // Jobs are queued before they are counted, so the one that was claimed is in some run queue.
+        let nqueues: usize = self.queues.len();
+        loop {
+            if let Some(job) = self.queues[ix].lock().unwrap().pop_front() {
+                return Some(job);
+            }
+            for i in 1..nqueues {
+                if let Some(job) = self.queues[(ix + i) % nqueues].lock().unwrap().pop_back() {
+                    return Some(job);
+                }
+            }
+        }
+    }
+
+    /// Reports that the job `key` has completed.
+    fn complete(&self, key: usize) {
+        self.completed.lock().unwrap().push(key);
+        #[cfg(target_os = "linux")]
+        {
+            let fd: i32 = self.eventfd.load(Ordering::Acquire);
+            if fd >= 0 {
+                let value: u64 = 1;
+                // This may only fail if the counter would overflow, in which case the descriptor is readable anyways.
+                unsafe { libc::write(fd, &value as *const u64 as *const libc::c_void, 8) };
+            }
+        }
+    }
+}
+
+/// Associate Functions for Worker Pools
+impl WorkerPool {
+    /// Spawns one worker for each entry of `cores`. On Linux, each worker is pinned to its core.
+    pub fn new(cores: &[usize]) -> Result<Self, Fail> {
+        if cores.is_empty() {
+            return Err(Fail::new(libc::EINVAL, "worker pool should have some worker"));
+        }
+
+        let shared: Arc<Shared> = Arc::new(Shared {
+            queues: cores.iter().map(|_| Mutex::new(VecDeque::new())).collect(),
+            pending: Mutex::new((0, false)),
+            available: Condvar::new(),
+            completed: Mutex::new(Vec::new()),
+            eventfd: AtomicI32::new(-1),
+        });
+        let pool: WorkerPool = Self {
+            shared: shared.clone(),
+            threads: RefCell::new(Vec::with_capacity(cores.len())),
+            next: Cell::new(0),
+            waiters: RefCell::new(Slab::new()),
+        };
+        for (ix, core) in cores.iter().copied().enumerate() {
+            let shared: Arc<Shared> = shared.clone();
+            let thread: JoinHandle<()> = thread::Builder::new()
+                .name(format!("demikernel-worker-{}", ix))
+                .spawn(move || {
+                    #[cfg(target_os = "linux")]
+                    pin_to_core(core);
+                    #[cfg(not(target_os = "linux"))]
+                    let _ = core;
+                    while let Some(job) = shared.claim(ix) {
+                        job();
+                    }
+                })
+                .map_err(|e| Fail::new(e.raw_os_error().unwrap_or(libc::EAGAIN), "failed to spawn worker"))?;
+            pool.threads.borrow_mut().push(thread);
+        }
+        Ok(pool)
+    }
+
+    /// Returns the number of workers in the target [WorkerPool].
+    pub fn nworkers(&self) -> usize {
+        self.shared.queues.len()
+    }
+
+    /// Runs `job` on some worker of the target [WorkerPool], returning a future that completes with its output.
+    pub fn offload<T, F>(self: &Rc<Self>, job: F) -> OffloadFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let key: usize = self.waiters.borrow_mut().insert(None);
+        let output: Arc<Mutex<Option<T>>> = Arc::new(Mutex::new(None));
+        let canceled: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let job: Job = {
+            let shared: Arc<Shared> = self.shared.clone();
+            let output: Arc<Mutex<Option<T>>> = output.clone();
+            let canceled: Arc<AtomicBool> = canceled.clone();
+            Box::new(move || {
+                // Nobody waits on the job anymore, so drop it along with the resources that it owns.
+                if canceled.load(Ordering::Acquire) {
+                    return;
+                }
+                let value: T = job();
+                *output.lock().unwrap() = Some(value);
+                shared.complete(key);
+            })
+        };
+
+        // Place the job in the next run queue, and only then make it available to workers.
+        let ix: usize = self.next.get();
+        self.next.set((ix + 1) % self.nworkers());
+        self.shared.queues[ix].lock().unwrap().push_back(job);
+        self.shared.pending.lock().unwrap().0 += 1;
+        self.shared.available.notify_one();
+
+        OffloadFuture {
+            pool: self.clone(),
+            key,
+            output,
+            canceled,
+        }
+    }
+
+    /// Wakes up the tasks that wait on jobs that have completed since the last call, returning how many jobs did so.
+    pub fn reap(&self) -> usize {
+        let completed: Vec<usize> = mem::take(&mut *self.shared.completed.lock().unwrap());
+        let wakers: Vec<Waker> = {
+            let mut waiters: RefMut<Slab<Option<Waker>>> = self.waiters.borrow_mut();
+            completed
+                .iter()
+                .filter_map(|key| waiters.get_mut(*key).and_then(Option::take))
+                .collect()
+        };
+        // Tasks are woken up only after waiters are released, because they may be polled right away.
+        wakers.into_iter().for_each(Waker::wake);
+        completed.len()
+    }
+
+    /// Sets the event file descriptor that is signaled whenever some job completes.
+    pub fn set_event_fd(&self, fd: i32) {
+        self.shared.eventfd.store(fd, Ordering::Release);
+    }
+
+    /// Shuts down the target [WorkerPool], waiting for workers to run all jobs that are queued. Tasks that wait on
+    /// offloaded jobs are not woken up anymore.
+    pub fn shutdown(&self) {
+        self.shared.pending.lock().unwrap().1 = true;
+        self.shared.available.notify_all();
+        for thread in self.threads.borrow_mut().drain(..) {
+            if thread.join().is_err() {
+                warn!("worker panicked");
+            }
+        }
+        self.shared.eventfd.store(-1, Ordering::Release);
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Offloaded Jobs
+impl<T: Send + 'static> Future for OffloadFuture<T> {
+    type Output = T;
+
+    /// Polls the target [OffloadFuture].
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut OffloadFuture<T> = self.get_mut();
+        if let Some(value) = self_.output.lock().unwrap().take() {
+            return Poll::Ready(value);
+        }
+        if let Some(waker) = self_.pool.waiters.borrow_mut().get_mut(self_.key) {
+            *waker = Some(ctx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Drop Trait Implementation for Offloaded Jobs
+impl<T: Send + 'static> Drop for OffloadFuture<T> {
+    /// Stops waiting on the job. The job is skipped if no worker claimed it yet, otherwise its output is discarded.
+    fn drop(&mut self) {
+        self.canceled.store(true, Ordering::Release);
+        self.pool.waiters.borrow_mut().try_remove(self.key);
+    }
+}
+
+/// Drop Trait Implementation for Worker Pools
+impl Drop for WorkerPool {
+    /// Shuts down the target [WorkerPool].
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Pins the calling thread to `core`.
+#[cfg(target_os = "linux")]
+fn pin_to_core(core: usize) {
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            warn!(
+                "cannot pin worker to core {:?} ({:?})",
+                core,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::scheduler::pool::{
+        OffloadFuture,
+        WorkerPool,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        sync::{
+            atomic::{
+                AtomicUsize,
+                Ordering,
+            },
+            mpsc::{
+                self,
+                Receiver,
+                Sender,
+            },
+            Arc,
+        },
+        task::{
+            Context,
+            Poll,
+        },
+    };
+
+    /// Polls `future` until it completes, reaping completed jobs in between.
+    fn block_on<T: Send + 'static>(pool: &WorkerPool, future: &mut OffloadFuture<T>) -> T {
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        loop {
+            if let Poll::Ready(value) = Pin::new(&mut *future).poll(&mut ctx) {
+                return value;
+            }
+            pool.reap();
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn pool_offload() {
+        let pool: Rc<WorkerPool> = Rc::new(WorkerPool::new(&[0, 0]).unwrap());
+        assert_eq!(pool.nworkers(), 2);
+
+        let mut futures: Vec<OffloadFuture<usize>> = (0..64).map(|i| pool.offload(move || i * 2)).collect();
+        for (i, future) in futures.iter_mut().enumerate() {
+            assert_eq!(block_on(&pool, future), i * 2);
+        }
+    }
+
+    #[test]
+    fn pool_shutdown_runs_queued_jobs() {
+        let ran: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let pool: Rc<WorkerPool> = Rc::new(WorkerPool::new(&[0]).unwrap());
+        let futures: Vec<OffloadFuture<()>> = (0..16)
+            .map(|_| {
+                let ran: Arc<AtomicUsize> = ran.clone();
+                pool.offload(move || {
+                    ran.fetch_add(1, Ordering::Relaxed);
+                })
+            })
+            .collect();
+
+        pool.shutdown();
+        assert_eq!(ran.load(Ordering::Relaxed), 16);
+        drop(futures);
+    }
+
+    #[test]
+    fn pool_skips_dropped_jobs() {
+        let ran: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let pool: Rc<WorkerPool> = Rc::new(WorkerPool::new(&[0]).unwrap());
+
+        // Keep the only worker busy until the other job is dropped.
+        let (sender, receiver): (Sender<()>, Receiver<()>) = mpsc::channel();
+        let mut busy: OffloadFuture<()> = pool.offload(move || receiver.recv().unwrap());
+        let future: OffloadFuture<()> = {
+            let ran: Arc<AtomicUsize> = ran.clone();
+            pool.offload(move || {
+                ran.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        drop(future);
+        sender.send(()).unwrap();
+        block_on(&pool, &mut busy);
+
+        pool.shutdown();
+        assert_eq!(ran.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn pool_new_without_workers() {
+        assert!(WorkerPool::new(&[]).is_err());
+    }
+}
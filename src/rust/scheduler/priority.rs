@@ -0,0 +1,21 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Enumerations
+//==============================================================================
+
+/// Task Priority
+///
+/// Notified tasks of high priority are polled before those of low priority.
+/// While high-priority tasks keep the scheduler busy, low-priority tasks may
+/// be deferred to later polls, but only for a bounded number of them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Priority {
+    /// Foreground work, such as I/O operations that applications wait on, and
+    /// protocol-critical background work, such as the co-routines that drive
+    /// TCP connections and ARP resolution.
+    High,
+    /// Housekeeping work, such as application background tasks.
+    Low,
+}
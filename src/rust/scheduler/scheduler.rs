@@ -6,25 +6,84 @@
 //! Our scheduler uses a pinned memory slab to store tasks ([SchedulerFuture]s).
 //! As background tasks are polled, they notify task in our scheduler via the
 //! [crate::page::WakerPage]s.
+//!
+//! Each task has a [Priority]. On every poll, notified high-priority tasks are
+//! polled first, and notified low-priority tasks are polled next, unless they
+//! are deferred because there was high-priority work. How many tasks are
+//! polled on every poll, and for how long, may be bounded with a [PollBudget].
+//! Notified tasks that do not fit in the budget are polled first on the next
+//! poll.
+//!
+//! The scheduler also holds a [TimerWheel], on which tasks may sleep until
+//! some deadline. Its clock is advanced with [Scheduler::advance_clock].
+//!
+//! Thread-safe backend operations may be offloaded to a [WorkerPool], so that
+//! a single scheduler may use multiple cores. Offloaded operations complete on
+//! the thread of the scheduler, once they are reaped on some poll.
+//!
+//! On Linux, the scheduler may also expose an event file descriptor, which is
+//! readable whenever some task is ready to be polled or has completed. This
+//! enables applications to park in `epoll_wait()` instead of busy-polling.
+//!
+//! For introspection, the scheduler keeps how many times each task was polled
+//! and when it was inserted, and optionally how long its polls took. These
+//! are listed along with the kind and owner of each task by
+//! [Scheduler::tasks].
+//!
+//! Finally, the scheduler tracks whether its polls find some work to do. With
+//! an [IdlePolicy], callers may park on some descriptor, or sleep, once the
+//! scheduler has been idle for long enough, so that idle services do not burn
+//! a full core.
 
 //==============================================================================
 // Imports
 //==============================================================================
 
-use crate::scheduler::{
-    page::{
-        WakerPageRef,
-        WakerRef,
-    },
-    pin_slab::PinSlab,
-    waker64::{
-        WAKER_BIT_LENGTH,
-        WAKER_BIT_LENGTH_SHIFT,
+use crate::{
+    runtime::fail::Fail,
+    scheduler::{
+        aborted::AbortedTask,
+        background::BackgroundTask,
+        budget::{
+            Allowance,
+            PollBudget,
+        },
+        completion::CompletionQueue,
+        idle::{
+            IdlePolicy,
+            IdleState,
+        },
+        info::{
+            TaskInfo,
+            TaskStats,
+        },
+        page::{
+            WakerPageRef,
+            WakerRef,
+        },
+        pin_slab::PinSlab,
+        pool::{
+            OffloadFuture,
+            WorkerPool,
+        },
+        priority::Priority,
+        timer::{
+            SleepFuture,
+            TimerWheel,
+        },
+        waker64::{
+            WAKER_BIT_LENGTH,
+            WAKER_BIT_LENGTH_SHIFT,
+        },
+        SchedulerFuture,
+        SchedulerHandle,
     },
-    SchedulerFuture,
-    SchedulerHandle,
 };
 use ::bit_iter::BitIter;
+#[cfg(target_os = "linux")]
+use ::nix::errno;
+#[cfg(target_os = "linux")]
+use ::std::os::unix::prelude::RawFd;
 use ::std::{
     cell::{
         Ref,
@@ -32,6 +91,7 @@ use ::std::{
         RefMut,
     },
     future::Future,
+    mem,
     pin::Pin,
     ptr::NonNull,
     rc::Rc,
@@ -40,8 +100,20 @@ use ::std::{
         Poll,
         Waker,
     },
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Maximum number of consecutive polls in which low-priority tasks may be deferred, so that they do not starve.
+const MAX_LOW_PRIORITY_DEFERRALS: usize = 16;
+
 //==============================================================================
 // Structures
 //==============================================================================
@@ -52,12 +124,37 @@ struct Inner<F: Future<Output = ()> + Unpin> {
     slab: PinSlab<F>,
     /// Holds the status tasks.
     pages: Vec<WakerPageRef>,
+    /// Flags low-priority tasks, with one bitmap per page.
+    low_priority: Vec<u64>,
+    /// Number of consecutive polls in which low-priority tasks were deferred.
+    deferrals: usize,
+    /// Bounds the work that is done on every poll.
+    budget: PollBudget,
+    /// Key of the task from which the next poll resumes.
+    cursor: usize,
+    /// Tasks that have completed, in completion order.
+    completions: CompletionQueue,
+    /// Statistics of the tasks, indexed by their keys.
+    stats: Vec<TaskStats>,
+    /// Whether the time spent polling tasks is measured.
+    poll_timing: bool,
+    /// Workers on which thread-safe operations are offloaded, if any.
+    workers: Option<Rc<WorkerPool>>,
+    /// Tells when callers may park instead of busy-polling, if ever.
+    idle_policy: Option<IdlePolicy>,
+    /// Tracks whether polls find some work to do.
+    idle: IdleState,
+    /// Event file descriptor that is signaled when some task is ready to be polled or has completed.
+    #[cfg(target_os = "linux")]
+    eventfd: Option<RawFd>,
 }
 
 /// Future Scheduler
 #[derive(Clone)]
 pub struct Scheduler {
     inner: Rc<RefCell<Inner<Box<dyn SchedulerFuture>>>>,
+    /// Timers on which tasks sleep.
+    timers: Rc<RefCell<TimerWheel>>,
 }
 
 //==============================================================================
@@ -73,18 +170,68 @@ impl<F: Future<Output = ()> + Unpin> Inner<F> {
         (&self.pages[page_ix], subpage_ix)
     }
 
-    /// Insert a task into our scheduler returning a key that may be used to drive its status.
-    fn insert(&mut self, future: F) -> Option<u64> {
+    /// Insert a task into our scheduler at `now`, returning a key that may be used to drive its status.
+    fn insert(&mut self, future: F, priority: Priority, now: Instant) -> Option<u64> {
         let key: usize = self.slab.insert(future)?;
+        if key >= self.stats.len() {
+            self.stats.resize_with(key + 1, || TaskStats::new(now));
+        }
+        self.stats[key] = TaskStats::new(now);
 
         // Add a new page to hold this future's status if the current page is filled.
         while key >= self.pages.len() << WAKER_BIT_LENGTH_SHIFT {
             self.pages.push(WakerPageRef::default());
+            self.low_priority.push(0);
         }
         let (page, subpage_ix): (&WakerPageRef, usize) = self.get_page(key as u64);
         page.initialize(subpage_ix);
+        let page_ix: usize = key >> WAKER_BIT_LENGTH_SHIFT;
+        match priority {
+            Priority::High => self.low_priority[page_ix] &= !(1 << subpage_ix),
+            Priority::Low => self.low_priority[page_ix] |= 1 << subpage_ix,
+        }
         Some(key as u64)
     }
+
+    /// Sets the notification flags of the tasks in the page `page_ix` that are flagged in `notified`, so that they are
+    /// polled on the next poll.
+    fn renotify(&self, page_ix: usize, notified: u64) {
+        for subpage_ix in BitIter::from(notified) {
+            self.pages[page_ix].notify(subpage_ix);
+        }
+    }
+
+    /// Returns for how long the caller may park, at most for `limit`, if idle polls are tracked and the target [Inner]
+    /// has been idle for long enough. Callers never park while some task is ready to be polled.
+    fn park_time(&self, limit: Option<Duration>) -> Option<Duration> {
+        let policy: &IdlePolicy = self.idle_policy.as_ref()?;
+        if self.pages.iter().any(|page| page.has_notified()) {
+            return None;
+        }
+        self.idle.park_time(policy, limit)
+    }
+
+    /// Signals the event file descriptor, if some task is ready to be polled or has completed.
+    #[cfg(target_os = "linux")]
+    fn signal_if_ready(&self) {
+        if let Some(fd) = self.eventfd {
+            if self.pages.iter().any(|page| page.has_ready()) {
+                let value: u64 = 1;
+                // This may only fail if the counter would overflow, in which case the descriptor is readable anyways.
+                unsafe { libc::write(fd, &value as *const u64 as *const libc::c_void, 8) };
+            }
+        }
+    }
+
+    /// Resets the event file descriptor, if any.
+    #[cfg(target_os = "linux")]
+    fn drain(&self) {
+        if let Some(fd) = self.eventfd {
+            let mut value: u64 = 0;
+            // This fails with EAGAIN if the descriptor was not signaled.
+            unsafe { libc::read(fd, &mut value as *mut u64 as *mut libc::c_void, 8) };
+        }
+    }
 }
 
 /// Associate Functions for Scheduler
@@ -96,6 +243,7 @@ impl Scheduler {
         let (page, subpage_ix): (&WakerPageRef, usize) = inner.get_page(key);
         assert!(!page.was_dropped(subpage_ix));
         page.clear(subpage_ix);
+        inner.completions.remove(key);
         inner.slab.remove_unpin(key as usize).unwrap()
     }
 
@@ -108,65 +256,453 @@ impl Scheduler {
         Some(handle)
     }
 
-    /// Insert a new task into our scheduler returning a handle corresponding to it.
-    pub fn insert<F: SchedulerFuture>(&self, future: F) -> Option<SchedulerHandle> {
+    /// Takes out the key of the earliest completed task that satisfies `pred`, without scanning tasks that are still
+    /// pending. The task itself is left in the scheduler, so that its result may be later taken out with
+    /// [Scheduler::take].
+    pub fn take_completed<P: Fn(u64) -> bool>(&self, pred: P) -> Option<u64> {
+        self.inner.borrow_mut().completions.take(pred)
+    }
+
+    /// Insert a new task with the given `priority` into our scheduler returning a handle corresponding to it.
+    pub fn insert<F: SchedulerFuture>(&self, future: F, priority: Priority) -> Option<SchedulerHandle> {
         let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
-        let key: u64 = inner.insert(Box::new(future))?;
+        let key: u64 = inner.insert(Box::new(future), priority, self.now())?;
+        // New tasks are always ready to be polled.
+        #[cfg(target_os = "linux")]
+        inner.signal_if_ready();
         let (page, _): (&WakerPageRef, usize) = inner.get_page(key);
         Some(SchedulerHandle::new(key, page.clone()))
     }
 
+    /// Inserts a co-routine of the application into the target [Scheduler], so that it is driven by the same polls as
+    /// I/O operations. Co-routines are polled with low priority, and dropping the returned handle cancels them.
+    pub fn spawn(&self, future: Pin<Box<dyn Future<Output = ()>>>) -> Result<SchedulerHandle, Fail> {
+        match self.insert(BackgroundTask::new(future), Priority::Low) {
+            Some(handle) => Ok(handle),
+            None => Err(Fail::new(libc::EAGAIN, "cannot schedule co-routine")),
+        }
+    }
+
+    /// Cancels all tasks in the target scheduler, returning how many were canceled. Tasks are dropped without being
+    /// polled again, and handles that refer to them become stale.
+    pub fn cancel_all(&self) -> usize {
+        let canceled: Vec<Box<dyn SchedulerFuture>> = {
+            let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+            let mut canceled: Vec<Box<dyn SchedulerFuture>> = Vec::new();
+            for key in 0..(inner.pages.len() << WAKER_BIT_LENGTH_SHIFT) as u64 {
+                if let Some(future) = inner.slab.remove_unpin(key as usize) {
+                    let (page, subpage_ix): (&WakerPageRef, usize) = inner.get_page(key);
+                    page.clear(subpage_ix);
+                    canceled.push(future);
+                }
+            }
+            inner.completions = CompletionQueue::default();
+            canceled
+        };
+
+        // Tasks are dropped only after the scheduler is released, because they may hold handles to other tasks.
+        canceled.len()
+    }
+
+    /// Aborts the task `key`, which completes on the next poll without being polled again. The future of the task is
+    /// returned, so that callers may release the resources that it holds before dropping it. Returns `None` if there is
+    /// no such task or if it has already completed.
+    pub fn abort(&self, key: u64) -> Option<Box<dyn SchedulerFuture>> {
+        let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+        inner.slab.get(key as usize)?;
+        let (page, subpage_ix): (&WakerPageRef, usize) = inner.get_page(key);
+        if page.has_completed(subpage_ix) || page.was_dropped(subpage_ix) {
+            return None;
+        }
+        page.notify(subpage_ix);
+
+        // Put an aborted task in place of the original one, so that handles to the task remain valid.
+        let task: &mut Box<dyn SchedulerFuture> = Pin::into_inner(inner.slab.get_pin_mut(key as usize)?);
+        let aborted: AbortedTask = AbortedTask::new(task.as_ref());
+        let future: Box<dyn SchedulerFuture> = mem::replace(task, Box::new(aborted));
+        #[cfg(target_os = "linux")]
+        inner.signal_if_ready();
+        Some(future)
+    }
+
+    /// Sets the budget that bounds the work that is done on every poll of the target [Scheduler].
+    pub fn set_budget(&self, budget: PollBudget) {
+        self.inner.borrow_mut().budget = budget;
+    }
+
+    /// Sets whether the time spent polling each task of the target [Scheduler] is measured. This is disabled by
+    /// default, because it reads the clock twice on every poll of a task.
+    pub fn set_poll_timing(&self, enabled: bool) {
+        self.inner.borrow_mut().poll_timing = enabled;
+    }
+
+    /// Lists the live tasks of the target [Scheduler], along with their statistics, so that leaked or stuck tasks may
+    /// be found. Tasks that completed are listed until their results are taken.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let inner: Ref<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow();
+        let now: Instant = self.now();
+        let mut tasks: Vec<TaskInfo> = Vec::new();
+        for key in 0..(inner.pages.len() << WAKER_BIT_LENGTH_SHIFT) as u64 {
+            let future: &dyn SchedulerFuture = match inner.slab.get(key as usize) {
+                Some(future) => future.as_ref(),
+                None => continue,
+            };
+            let (page, subpage_ix): (&WakerPageRef, usize) = inner.get_page(key);
+            if page.was_dropped(subpage_ix) {
+                continue;
+            }
+            let page_ix: usize = key as usize >> WAKER_BIT_LENGTH_SHIFT;
+            let priority: Priority = if inner.low_priority[page_ix] & (1 << subpage_ix) != 0 {
+                Priority::Low
+            } else {
+                Priority::High
+            };
+            let stats: &TaskStats = &inner.stats[key as usize];
+            tasks.push(TaskInfo {
+                key,
+                kind: future.get_kind(),
+                qd: future.get_qd(),
+                priority,
+                completed: page.has_completed(subpage_ix),
+                polls: stats.polls,
+                age: now.saturating_duration_since(stats.inserted),
+                poll_time: stats.poll_time,
+                max_poll_time: stats.max_poll_time,
+            });
+        }
+        tasks
+    }
+
+    /// Sets the policy that tells when callers of the target [Scheduler] may park instead of busy-polling. Without one,
+    /// callers never park.
+    pub fn set_idle_policy(&self, policy: IdlePolicy) {
+        let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+        inner.idle_policy = Some(policy);
+        inner.idle = IdleState::default();
+    }
+
+    /// Sleeps if the target [Scheduler] has been idle for long enough, as per its [IdlePolicy], for at most `limit`.
+    /// This is meant for callers that have no descriptor to park on, so sleeps grow on every park of an idle period,
+    /// up to the maximum park time of the policy. Expired timers are only noticed once the caller wakes up.
+    pub fn park(&self, limit: Option<Duration>) {
+        let sleep: Option<Duration> = {
+            let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+            match (inner.park_time(limit), inner.idle_policy) {
+                (Some(_), Some(policy)) => inner.idle.sleep_time(&policy, limit),
+                _ => None,
+            }
+        };
+        if let Some(sleep) = sleep {
+            thread::sleep(sleep);
+        }
+    }
+
+    /// Returns for how long callers that wait on some event source of their own may park, if the target [Scheduler] has
+    /// been idle for long enough, as per its [IdlePolicy]. This is at most the maximum park time of the policy and
+    /// `limit`. Expired timers are only noticed once the caller wakes up.
+    pub fn park_time(&self, limit: Option<Duration>) -> Option<Duration> {
+        self.inner.borrow().park_time(limit)
+    }
+
+    /// Waits until `fd` becomes readable if the target [Scheduler] has been idle for long enough, as per its
+    /// [IdlePolicy], for at most the maximum park time of the policy and `limit`. Expired timers are only noticed once
+    /// the caller wakes up.
+    #[cfg(target_os = "linux")]
+    pub fn park_on(&self, fd: RawFd, limit: Option<Duration>) -> Result<(), Fail> {
+        let park_time: Duration = match self.park_time(limit) {
+            Some(park_time) => park_time,
+            None => return Ok(()),
+        };
+        let mut pollfd: libc::pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout: libc::timespec = libc::timespec {
+            tv_sec: park_time.as_secs() as libc::time_t,
+            tv_nsec: park_time.subsec_nanos() as libc::c_long,
+        };
+        if unsafe { libc::ppoll(&mut pollfd, 1, &timeout, ::std::ptr::null()) } < 0 {
+            let errno: libc::c_int = errno::errno();
+            // Signals only cut the park short.
+            if errno != libc::EINTR {
+                return Err(Fail::new(errno, "failed to park on file descriptor"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Spawns one worker for each entry of `cores`, on which thread-safe operations may be offloaded with
+    /// [Scheduler::offload]. This may only be done once.
+    pub fn set_workers(&self, cores: &[usize]) -> Result<(), Fail> {
+        let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+        if inner.workers.is_some() {
+            return Err(Fail::new(libc::EBUSY, "scheduler already has workers"));
+        }
+        let workers: WorkerPool = WorkerPool::new(cores)?;
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = inner.eventfd {
+            workers.set_event_fd(fd);
+        }
+        inner.workers = Some(Rc::new(workers));
+        Ok(())
+    }
+
+    /// Returns the number of workers of the target [Scheduler].
+    pub fn nworkers(&self) -> usize {
+        self.inner
+            .borrow()
+            .workers
+            .as_ref()
+            .map_or(0, |workers| workers.nworkers())
+    }
+
+    /// Runs `job` on some worker of the target [Scheduler], returning a future that completes with its output. If the
+    /// scheduler has no workers, `job` is handed back, so that the caller may run it inline instead. If the future is
+    /// dropped before some worker claims `job`, the job is dropped without running.
+    pub fn offload<T, F>(&self, job: F) -> Result<OffloadFuture<T>, F>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let workers: Option<Rc<WorkerPool>> = self.inner.borrow().workers.clone();
+        match workers {
+            Some(workers) => Ok(workers.offload(job)),
+            None => Err(job),
+        }
+    }
+
+    /// Returns the current time of the clock of the target [Scheduler].
+    pub fn now(&self) -> Instant {
+        self.timers.borrow().now()
+    }
+
+    /// Advances the clock of the target [Scheduler] to `now`, waking up tasks whose timers expired.
+    pub fn advance_clock(&self, now: Instant) {
+        let wakers: Vec<Waker> = self.timers.borrow_mut().advance(now);
+        self.wake(wakers);
+    }
+
+    /// Wakes up tasks with `wakers`, on behalf of some event source that is reaped outside of the target [Scheduler].
+    /// On Linux, the event file descriptor is signaled as well, so that applications that wait on it do not miss events
+    /// that are reaped after the descriptor was reset.
+    pub fn wake(&self, wakers: Vec<Waker>) {
+        if wakers.is_empty() {
+            return;
+        }
+        wakers.into_iter().for_each(Waker::wake);
+        #[cfg(target_os = "linux")]
+        self.inner.borrow().signal_if_ready();
+    }
+
+    /// Returns a future that completes once the clock of the target [Scheduler] reaches `deadline`. Dropping the
+    /// future cancels its timer.
+    pub fn sleep_until(&self, deadline: Instant) -> SleepFuture {
+        SleepFuture::new(self.timers.clone(), deadline)
+    }
+
+    /// Returns a future that completes once `timeout` has elapsed on the clock of the target [Scheduler].
+    pub fn sleep(&self, timeout: Duration) -> SleepFuture {
+        self.sleep_until(self.now() + timeout)
+    }
+
+    /// Returns the timer wheel of the target [Scheduler], so that it may be shared with other clocks.
+    pub fn timers(&self) -> Rc<RefCell<TimerWheel>> {
+        self.timers.clone()
+    }
+
+    /// Returns an event file descriptor that becomes readable whenever some task is ready to be polled or has
+    /// completed. The descriptor is reset on every call to [Scheduler::poll], and it is owned by the scheduler.
+    #[cfg(target_os = "linux")]
+    pub fn event_fd(&self) -> Result<RawFd, Fail> {
+        let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+        if let Some(fd) = inner.eventfd {
+            return Ok(fd);
+        }
+
+        let fd: RawFd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            let errno: libc::c_int = errno::errno();
+            return Err(Fail::new(errno, "failed to create event file descriptor"));
+        }
+        inner.eventfd = Some(fd);
+        if let Some(workers) = inner.workers.as_ref() {
+            workers.set_event_fd(fd);
+        }
+        inner.signal_if_ready();
+        Ok(fd)
+    }
+
     /// Poll all futures which are ready to run again. Tasks in our scheduler are notified when
     /// relevant data or events happen. The relevant event have callback function (the waker) which
     /// they can invoke to notify the scheduler that future should be polled again.
     pub fn poll(&self) {
         let mut inner: RefMut<Inner<Box<dyn SchedulerFuture>>> = self.inner.borrow_mut();
+        #[cfg(target_os = "linux")]
+        inner.drain();
+
+        // Wake up tasks whose offloaded jobs have completed, so that they are polled right away.
+        if let Some(workers) = inner.workers.as_ref() {
+            workers.reap();
+        }
 
-        // Iterate through pages.
-        for page_ix in 0..inner.pages.len() {
-            let (notified, dropped): (u64, u64) = {
-                let page: &mut WakerPageRef = &mut inner.pages[page_ix];
-                (page.take_notified(), page.take_dropped())
+        let mut allowance: Allowance = inner.budget.start();
+        let npages: usize = inner.pages.len();
+        let cursor: usize = if inner.cursor < npages << WAKER_BIT_LENGTH_SHIFT {
+            inner.cursor
+        } else {
+            0
+        };
+        let (start_page_ix, start_subpage_ix): (usize, usize) =
+            (cursor >> WAKER_BIT_LENGTH_SHIFT, cursor & (WAKER_BIT_LENGTH - 1));
+        // Notified tasks in the first page that come before the cursor, which are polled last.
+        let mut wrapped: u64 = 0;
+        // Notified low-priority tasks, which are polled after high-priority ones.
+        let mut low_priority: Vec<(usize, u64)> = Vec::new();
+        let mut busy: bool = false;
+        let mut exhausted: bool = false;
+
+        // Iterate through pages, starting from the cursor and wrapping around.
+        for i in 0..=npages {
+            // The first page is visited again at the end, if some of its tasks come before the cursor.
+            if i == npages && wrapped == 0 {
+                break;
+            }
+            let page_ix: usize = (start_page_ix + i) % npages;
+            let notified: u64 = if i == npages {
+                mem::take(&mut wrapped)
+            } else {
+                let (notified, dropped): (u64, u64) = {
+                    let page: &mut WakerPageRef = &mut inner.pages[page_ix];
+                    (page.take_notified(), page.take_dropped())
+                };
+                // There is some dropped task in this page, so iterate through it.
+                if dropped != 0 {
+                    // Handle dropped tasks only.
+                    for subpage_ix in BitIter::from(dropped) {
+                        if subpage_ix != 0 {
+                            let ix: usize = (page_ix << WAKER_BIT_LENGTH_SHIFT) + subpage_ix;
+                            inner.slab.remove(ix);
+                            inner.pages[page_ix].clear(subpage_ix);
+                            inner.completions.remove(ix as u64);
+                        }
+                    }
+                }
+                if i == 0 {
+                    wrapped = notified & ((1 << start_subpage_ix) - 1);
+                }
+                notified & !wrapped
             };
-            // There is some notified task in this page, so iterate through it.
+            let low_priority_mask: u64 = inner.low_priority[page_ix];
+            if notified & low_priority_mask != 0 {
+                low_priority.push((page_ix, notified & low_priority_mask));
+            }
+
+            // There is some notified high-priority task in this page, so iterate through it.
+            let notified: u64 = notified & !low_priority_mask;
             if notified != 0 {
-                for subpage_ix in BitIter::from(notified) {
-                    // Handle notified tasks only.
-                    // Get future using our page indices and poll it!
-                    let ix: usize = (page_ix << WAKER_BIT_LENGTH_SHIFT) + subpage_ix;
-                    let waker: Waker = unsafe {
-                        let raw_waker: NonNull<u8> = inner.pages[page_ix].into_raw_waker_ref(subpage_ix);
-                        Waker::from_raw(WakerRef::new(raw_waker).into())
-                    };
-                    let mut sub_ctx: Context = Context::from_waker(&waker);
-
-                    let pinned_ref: Pin<&mut Box<dyn SchedulerFuture>> = inner.slab.get_pin_mut(ix).unwrap();
-                    let pinned_ptr = unsafe { Pin::into_inner_unchecked(pinned_ref) as *mut _ };
-
-                    // Poll future.
-                    drop(inner);
-                    let pinned_ref = unsafe { Pin::new_unchecked(&mut *pinned_ptr) };
-                    let poll_result: Poll<()> = Future::poll(pinned_ref, &mut sub_ctx);
-                    inner = self.inner.borrow_mut();
-
-                    match poll_result {
-                        Poll::Ready(()) => inner.pages[page_ix].mark_completed(subpage_ix),
-                        Poll::Pending => (),
-                    }
+                busy = true;
+                let left: u64;
+                (inner, left) = self.poll_page(inner, page_ix, notified, &mut allowance);
+                if left != 0 {
+                    inner.renotify(page_ix, left);
+                    inner.cursor = (page_ix << WAKER_BIT_LENGTH_SHIFT) + left.trailing_zeros() as usize;
+                    exhausted = true;
+                    break;
                 }
             }
-            // There is some dropped task in this page, so iterate through it.
-            if dropped != 0 {
-                // Handle dropped tasks only.
-                for subpage_ix in BitIter::from(dropped) {
-                    if subpage_ix != 0 {
-                        let ix: usize = (page_ix << WAKER_BIT_LENGTH_SHIFT) + subpage_ix;
-                        inner.slab.remove(ix);
-                        inner.pages[page_ix].clear(subpage_ix);
+        }
+        if wrapped != 0 {
+            inner.renotify(start_page_ix, wrapped);
+        }
+
+        // Defer low-priority tasks if there was high-priority work, unless they were deferred for too long already. In
+        // that case, they get an allowance of their own, even if high-priority tasks exhausted the budget.
+        let starved: bool = inner.deferrals >= MAX_LOW_PRIORITY_DEFERRALS;
+        if !low_priority.is_empty() && (exhausted || busy) && !starved {
+            inner.deferrals += 1;
+            for (page_ix, notified) in low_priority {
+                inner.renotify(page_ix, notified);
+            }
+        } else {
+            inner.deferrals = 0;
+            let reserving: bool = exhausted;
+            let mut reserved: Allowance = inner.budget.start();
+            let allowance: &mut Allowance = if reserving { &mut reserved } else { &mut allowance };
+            let mut exhausted: bool = false;
+            for (page_ix, notified) in low_priority {
+                if exhausted {
+                    inner.renotify(page_ix, notified);
+                    continue;
+                }
+                let left: u64;
+                (inner, left) = self.poll_page(inner, page_ix, notified, allowance);
+                if left != 0 {
+                    inner.renotify(page_ix, left);
+                    // Keep the cursor of high-priority tasks, if they are the ones that exhausted the budget.
+                    if !reserving {
+                        inner.cursor = (page_ix << WAKER_BIT_LENGTH_SHIFT) + left.trailing_zeros() as usize;
                     }
+                    exhausted = true;
                 }
             }
         }
+
+        // Track whether this poll found some work to do, so that callers may park once idle.
+        if let Some(policy) = inner.idle_policy {
+            inner.idle.record(&policy, allowance.polled() > 0);
+        }
+
+        // Signal tasks that are still ready, so that they are not missed by applications that wait on the event file
+        // descriptor.
+        #[cfg(target_os = "linux")]
+        inner.signal_if_ready();
+    }
+
+    /// Polls the tasks in the page `page_ix` that are flagged in `notified`, as long as `allowance` is not exhausted.
+    /// The scheduler is released while each task is polled, so the borrow is handed back to the caller, along with the
+    /// flags of the tasks that were left over.
+    fn poll_page<'a>(
+        &'a self,
+        mut inner: RefMut<'a, Inner<Box<dyn SchedulerFuture>>>,
+        page_ix: usize,
+        notified: u64,
+        allowance: &mut Allowance,
+    ) -> (RefMut<'a, Inner<Box<dyn SchedulerFuture>>>, u64) {
+        let mut left: u64 = notified;
+        while left != 0 && allowance.take() {
+            let subpage_ix: usize = left.trailing_zeros() as usize;
+            left &= left - 1;
+
+            // Get future using our page indices and poll it!
+            let ix: usize = (page_ix << WAKER_BIT_LENGTH_SHIFT) + subpage_ix;
+            let waker: Waker = unsafe {
+                let raw_waker: NonNull<u8> = inner.pages[page_ix].into_raw_waker_ref(subpage_ix);
+                Waker::from_raw(WakerRef::new(raw_waker).into())
+            };
+            let mut sub_ctx: Context = Context::from_waker(&waker);
+
+            let pinned_ref: Pin<&mut Box<dyn SchedulerFuture>> = inner.slab.get_pin_mut(ix).unwrap();
+            let pinned_ptr = unsafe { Pin::into_inner_unchecked(pinned_ref) as *mut _ };
+
+            // Poll future.
+            let start: Option<Instant> = if inner.poll_timing { Some(Instant::now()) } else { None };
+            drop(inner);
+            let pinned_ref = unsafe { Pin::new_unchecked(&mut *pinned_ptr) };
+            let poll_result: Poll<()> = Future::poll(pinned_ref, &mut sub_ctx);
+            let elapsed: Option<Duration> = start.map(|start| start.elapsed());
+            inner = self.inner.borrow_mut();
+            inner.stats[ix].record(elapsed);
+
+            match poll_result {
+                Poll::Ready(()) => {
+                    inner.pages[page_ix].mark_completed(subpage_ix);
+                    inner.completions.push(ix as u64);
+                },
+                Poll::Pending => (),
+            }
+        }
+        (inner, left)
     }
 }
 
@@ -181,9 +717,36 @@ impl Default for Scheduler {
         let inner: Inner<Box<dyn SchedulerFuture>> = Inner {
             slab: PinSlab::new(),
             pages: vec![],
+            low_priority: vec![],
+            deferrals: 0,
+            budget: PollBudget::default(),
+            cursor: 0,
+            completions: CompletionQueue::default(),
+            stats: vec![],
+            poll_timing: false,
+            workers: None,
+            idle_policy: None,
+            idle: IdleState::default(),
+            #[cfg(target_os = "linux")]
+            eventfd: None,
         };
         Self {
             inner: Rc::new(RefCell::new(inner)),
+            timers: Rc::new(RefCell::new(TimerWheel::new(Instant::now()))),
+        }
+    }
+}
+
+/// Drop Trait Implementation for Inner
+#[cfg(target_os = "linux")]
+impl<F: Future<Output = ()> + Unpin> Drop for Inner<F> {
+    /// Closes the event file descriptor, if any. Workers are shut down first, so that they do not signal it afterwards.
+    fn drop(&mut self) {
+        if let Some(workers) = self.workers.take() {
+            workers.shutdown();
+        }
+        if let Some(fd) = self.eventfd.take() {
+            unsafe { libc::close(fd) };
         }
     }
 }
@@ -194,20 +757,38 @@ impl Default for Scheduler {
 
 #[cfg(test)]
 mod tests {
-    use crate::scheduler::scheduler::{
-        Scheduler,
-        SchedulerFuture,
-        SchedulerHandle,
+    use crate::scheduler::{
+        scheduler::{
+            Scheduler,
+            SchedulerFuture,
+            SchedulerHandle,
+            MAX_LOW_PRIORITY_DEFERRALS,
+        },
+        BackgroundTask,
+        IdlePolicy,
+        OffloadFuture,
+        PollBudget,
+        Priority,
+        SleepFuture,
+        TaskInfo,
     };
+    #[cfg(target_os = "linux")]
+    use ::std::os::unix::prelude::RawFd;
     use ::std::{
         any::Any,
+        cell::Cell,
         future::Future,
         pin::Pin,
+        rc::Rc,
         task::{
             Context,
             Poll,
             Waker,
         },
+        time::{
+            Duration,
+            Instant,
+        },
     };
     use ::test::{
         black_box,
@@ -252,13 +833,91 @@ mod tests {
         }
     }
 
+    struct SpinFuture {
+        pub polls: Rc<Cell<usize>>,
+    }
+
+    impl Future for SpinFuture {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+            self.polls.set(self.polls.get() + 1);
+            ctx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    impl SchedulerFuture for SpinFuture {
+        fn as_any(self: Box<Self>) -> Box<dyn Any> {
+            self
+        }
+
+        fn get_future(&self) -> &dyn Future<Output = ()> {
+            todo!()
+        }
+    }
+
+    struct SleepyFuture {
+        pub sleep: SleepFuture,
+    }
+
+    impl Future for SleepyFuture {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+            Future::poll(Pin::new(&mut self.get_mut().sleep), ctx)
+        }
+    }
+
+    impl SchedulerFuture for SleepyFuture {
+        fn as_any(self: Box<Self>) -> Box<dyn Any> {
+            self
+        }
+
+        fn get_future(&self) -> &dyn Future<Output = ()> {
+            todo!()
+        }
+    }
+
+    struct OffloadedFuture {
+        pub job: OffloadFuture<usize>,
+        pub output: Rc<Cell<usize>>,
+    }
+
+    impl Future for OffloadedFuture {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+            let self_: &mut OffloadedFuture = self.get_mut();
+            match Future::poll(Pin::new(&mut self_.job), ctx) {
+                Poll::Ready(output) => {
+                    self_.output.set(output);
+                    Poll::Ready(())
+                },
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    impl SchedulerFuture for OffloadedFuture {
+        fn as_any(self: Box<Self>) -> Box<dyn Any> {
+            self
+        }
+
+        fn get_future(&self) -> &dyn Future<Output = ()> {
+            todo!()
+        }
+    }
+
     #[bench]
     fn bench_scheduler_insert(b: &mut Bencher) {
         let scheduler: Scheduler = Scheduler::default();
 
         b.iter(|| {
             let future: DummyFuture = black_box(DummyFuture::default());
-            let handle: SchedulerHandle = scheduler.insert(future).expect("couldn't insert future in scheduler");
+            let handle: SchedulerHandle = scheduler
+                .insert(future, Priority::High)
+                .expect("couldn't insert future in scheduler");
             black_box(handle);
         });
     }
@@ -270,7 +929,7 @@ mod tests {
         // Insert a single future in the scheduler. This future shall complete
         // with a single pool operation.
         let future: DummyFuture = DummyFuture::new(0);
-        let handle: SchedulerHandle = match scheduler.insert(future) {
+        let handle: SchedulerHandle = match scheduler.insert(future, Priority::High) {
             Some(handle) => handle,
             None => panic!("insert() failed"),
         };
@@ -289,7 +948,7 @@ mod tests {
         // Insert a single future in the scheduler. This future shall complete
         // with two poll operations.
         let future: DummyFuture = DummyFuture::new(1);
-        let handle: SchedulerHandle = match scheduler.insert(future) {
+        let handle: SchedulerHandle = match scheduler.insert(future, Priority::High) {
             Some(handle) => handle,
             None => panic!("insert() failed"),
         };
@@ -306,6 +965,468 @@ mod tests {
         assert_eq!(handle.has_completed(), true);
     }
 
+    #[test]
+    fn scheduler_take_completed() {
+        let scheduler: Scheduler = Scheduler::default();
+
+        // Insert two futures that complete after a different number of poll operations.
+        let slow: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(1), Priority::High)
+            .expect("insert() failed");
+        let fast: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::High)
+            .expect("insert() failed");
+        let (slow_key, fast_key): (u64, u64) = (slow.into_raw(), fast.into_raw());
+
+        // Only the fast future completes with a single poll operation.
+        scheduler.poll();
+        assert_eq!(scheduler.take_completed(|key| key == slow_key), None);
+        assert_eq!(scheduler.take_completed(|_| true), Some(fast_key));
+        assert_eq!(scheduler.take_completed(|_| true), None);
+
+        // Now the slow future completes as well.
+        scheduler.poll();
+        assert_eq!(scheduler.take_completed(|_| true), Some(slow_key));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn scheduler_event_fd() {
+        let scheduler: Scheduler = Scheduler::default();
+        let fd: RawFd = scheduler.event_fd().expect("event_fd() failed");
+        let mut value: u64 = 0;
+        let mut read = || unsafe { libc::read(fd, &mut value as *mut u64 as *mut libc::c_void, 8) };
+
+        // No tasks, so the event file descriptor should not be signaled.
+        assert_eq!(read(), -1);
+
+        // Insert a future that shall complete with two poll operations.
+        let handle: SchedulerHandle = match scheduler.insert(DummyFuture::new(1), Priority::High) {
+            Some(handle) => handle,
+            None => panic!("insert() failed"),
+        };
+        assert_eq!(read(), 8);
+
+        // The future is still ready to be polled after the first poll operation.
+        scheduler.poll();
+        assert_eq!(read(), 8);
+
+        // The future has completed, but its result was not taken yet.
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), true);
+        assert_eq!(read(), 8);
+
+        // Nothing is left to do.
+        scheduler.take(handle);
+        scheduler.poll();
+        assert_eq!(read(), -1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn scheduler_wake_event_fd() {
+        let scheduler: Scheduler = Scheduler::default();
+        let fd: RawFd = scheduler.event_fd().expect("event_fd() failed");
+        let mut value: u64 = 0;
+        let mut read = || unsafe { libc::read(fd, &mut value as *mut u64 as *mut libc::c_void, 8) };
+
+        // Insert a future that sleeps, so that it is not ready once polled.
+        let now: Instant = scheduler.now();
+        let sleep: SleepFuture = scheduler.sleep(Duration::from_millis(10));
+        let handle: SchedulerHandle = scheduler
+            .insert(SleepyFuture { sleep }, Priority::High)
+            .expect("insert() failed");
+        scheduler.poll();
+        assert_eq!(read(), -1);
+
+        // Waking up the future signals the event file descriptor, even though it was reset already.
+        scheduler.advance_clock(now + Duration::from_millis(10));
+        assert_eq!(read(), 8);
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_cancel_all() {
+        let scheduler: Scheduler = Scheduler::default();
+
+        // Insert a future that completes and another one that never does.
+        let done: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::High)
+            .expect("insert() failed");
+        let pending: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(1), Priority::High)
+            .expect("insert() failed");
+        let (done_key, pending_key): (u64, u64) = (done.into_raw(), pending.into_raw());
+        scheduler.poll();
+
+        // All tasks are gone, whether they have completed or not.
+        assert_eq!(scheduler.cancel_all(), 2);
+        assert!(scheduler.from_raw_handle(done_key).is_none());
+        assert!(scheduler.from_raw_handle(pending_key).is_none());
+        assert_eq!(scheduler.take_completed(|_| true), None);
+
+        // The scheduler remains usable.
+        let handle: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::High)
+            .expect("insert() failed");
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_abort() {
+        let scheduler: Scheduler = Scheduler::default();
+
+        // Insert a co-routine that never completes, and a future that completes on the first poll operation.
+        let handle: SchedulerHandle = scheduler
+            .insert(
+                BackgroundTask::new(Box::pin(::futures::future::pending::<()>())),
+                Priority::High,
+            )
+            .expect("insert() failed");
+        let pending_key: u64 = handle.into_raw();
+        let handle: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::High)
+            .expect("insert() failed");
+        let completed_key: u64 = handle.into_raw();
+        scheduler.poll();
+
+        // Completed tasks cannot be aborted.
+        assert!(scheduler.abort(completed_key).is_none());
+
+        // Aborted tasks complete on the next poll operation, and keep their kind.
+        let future: Box<dyn SchedulerFuture> = scheduler.abort(pending_key).expect("abort() failed");
+        assert_eq!(future.get_kind(), "Background");
+        scheduler.poll();
+        let handle: SchedulerHandle = scheduler.from_raw_handle(pending_key).unwrap();
+        assert_eq!(handle.has_completed(), true);
+        assert!(scheduler.abort(pending_key).is_none());
+        let info: TaskInfo = scheduler
+            .tasks()
+            .into_iter()
+            .find(|task| task.key == pending_key)
+            .unwrap();
+        assert_eq!(info.kind, "Background");
+        scheduler.take(handle);
+    }
+
+    #[test]
+    fn scheduler_priority() {
+        let scheduler: Scheduler = Scheduler::default();
+
+        // Insert a low-priority future and a high-priority one that is always ready.
+        let low: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::Low)
+            .expect("insert() failed");
+        let high: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(1), Priority::High)
+            .expect("insert() failed");
+
+        // The low-priority future is deferred while there is high-priority work.
+        scheduler.poll();
+        assert_eq!(low.has_completed(), false);
+        scheduler.poll();
+        assert_eq!(high.has_completed(), true);
+        assert_eq!(low.has_completed(), false);
+
+        // The low-priority future runs once there is no high-priority work left.
+        scheduler.poll();
+        assert_eq!(low.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_priority_starvation() {
+        let scheduler: Scheduler = Scheduler::default();
+        let low: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::Low)
+            .expect("insert() failed");
+
+        // Keep high-priority work around on every poll.
+        for i in 0..MAX_LOW_PRIORITY_DEFERRALS {
+            let high: SchedulerHandle = scheduler
+                .insert(DummyFuture::new(0), Priority::High)
+                .expect("insert() failed");
+            scheduler.poll();
+            assert_eq!(high.has_completed(), true);
+            assert_eq!(
+                low.has_completed(),
+                false,
+                "deferred low-priority task ran on poll {}",
+                i
+            );
+        }
+
+        // The low-priority future is not deferred any longer.
+        let _high: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::High)
+            .expect("insert() failed");
+        scheduler.poll();
+        assert_eq!(low.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_budget() {
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_budget(PollBudget {
+            max_tasks: Some(2),
+            max_time: None,
+        });
+        let handles: Vec<SchedulerHandle> = (0..4)
+            .map(|_| {
+                scheduler
+                    .insert(DummyFuture::new(0), Priority::High)
+                    .expect("insert() failed")
+            })
+            .collect();
+
+        // Only two futures are polled on every poll.
+        scheduler.poll();
+        assert_eq!(handles.iter().filter(|handle| handle.has_completed()).count(), 2);
+        scheduler.poll();
+        assert_eq!(handles.iter().filter(|handle| handle.has_completed()).count(), 4);
+    }
+
+    #[test]
+    fn scheduler_budget_starvation() {
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_budget(PollBudget {
+            max_tasks: Some(1),
+            max_time: None,
+        });
+        let low: SchedulerHandle = scheduler
+            .insert(DummyFuture::new(0), Priority::Low)
+            .expect("insert() failed");
+        let polls: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+        let _high: SchedulerHandle = scheduler
+            .insert(SpinFuture { polls: polls.clone() }, Priority::High)
+            .expect("insert() failed");
+
+        // High-priority work exhausts the budget on every poll, so the low-priority future is deferred.
+        for i in 0..MAX_LOW_PRIORITY_DEFERRALS {
+            scheduler.poll();
+            assert_eq!(polls.get(), i + 1);
+            assert_eq!(
+                low.has_completed(),
+                false,
+                "deferred low-priority task ran on poll {}",
+                i
+            );
+        }
+
+        // The low-priority future is not deferred any longer, even though the budget is exhausted.
+        scheduler.poll();
+        assert_eq!(low.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_budget_round_robin() {
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_budget(PollBudget {
+            max_tasks: Some(1),
+            max_time: None,
+        });
+        let counters: Vec<Rc<Cell<usize>>> = (0..3).map(|_| Rc::new(Cell::new(0))).collect();
+        let _handles: Vec<SchedulerHandle> = counters
+            .iter()
+            .map(|polls| {
+                let future: SpinFuture = SpinFuture { polls: polls.clone() };
+                scheduler.insert(future, Priority::High).expect("insert() failed")
+            })
+            .collect();
+
+        // Futures are always ready, but they take turns.
+        for round in 1..=4 {
+            for i in 0..counters.len() {
+                scheduler.poll();
+                for (j, polls) in counters.iter().enumerate() {
+                    assert_eq!(polls.get(), if j <= i { round } else { round - 1 });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scheduler_sleep() {
+        let scheduler: Scheduler = Scheduler::default();
+        let now: Instant = scheduler.now();
+
+        // Insert a future that sleeps for a while.
+        let sleep: SleepFuture = scheduler.sleep(Duration::from_millis(10));
+        let handle: SchedulerHandle = scheduler
+            .insert(SleepyFuture { sleep }, Priority::High)
+            .expect("insert() failed");
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), false);
+
+        // The future is not woken up before its deadline.
+        scheduler.advance_clock(now + Duration::from_millis(5));
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), false);
+
+        // The future completes once its deadline is reached.
+        scheduler.advance_clock(now + Duration::from_millis(10));
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_spawn() {
+        let scheduler: Scheduler = Scheduler::default();
+        let runs: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+
+        // Spawn a co-routine that sleeps in between runs.
+        let future: Pin<Box<dyn Future<Output = ()>>> = {
+            let scheduler: Scheduler = scheduler.clone();
+            let runs: Rc<Cell<usize>> = runs.clone();
+            Box::pin(async move {
+                for _ in 0..2 {
+                    runs.set(runs.get() + 1);
+                    scheduler.sleep(Duration::from_millis(10)).await;
+                }
+            })
+        };
+        let now: Instant = scheduler.now();
+        let handle: SchedulerHandle = scheduler.spawn(future).expect("spawn() failed");
+        scheduler.poll();
+        assert_eq!(runs.get(), 1);
+        assert_eq!(handle.has_completed(), false);
+
+        // The co-routine runs again once it wakes up.
+        scheduler.advance_clock(now + Duration::from_millis(10));
+        scheduler.poll();
+        assert_eq!(runs.get(), 2);
+        assert_eq!(handle.has_completed(), false);
+
+        scheduler.advance_clock(now + Duration::from_millis(20));
+        scheduler.poll();
+        assert_eq!(handle.has_completed(), true);
+    }
+
+    #[test]
+    fn scheduler_tasks() {
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_poll_timing(true);
+        let now: Instant = scheduler.now();
+
+        // Insert a future that completes after two poll operations, and a co-routine that never does.
+        let dummy_key: u64 = scheduler
+            .insert(DummyFuture::new(1), Priority::High)
+            .expect("insert() failed")
+            .into_raw();
+        let background_key: u64 = scheduler
+            .insert(
+                BackgroundTask::new(Box::pin(::futures::future::pending::<()>())),
+                Priority::Low,
+            )
+            .expect("insert() failed")
+            .into_raw();
+        scheduler.advance_clock(now + Duration::from_millis(10));
+        scheduler.poll();
+        scheduler.poll();
+
+        let tasks: Vec<TaskInfo> = scheduler.tasks();
+        assert_eq!(tasks.len(), 2);
+        let dummy_info: &TaskInfo = tasks.iter().find(|task| task.key == dummy_key).unwrap();
+        assert_eq!(dummy_info.kind, "Task");
+        assert_eq!(dummy_info.qd, None);
+        assert_eq!(dummy_info.priority, Priority::High);
+        assert_eq!(dummy_info.completed, true);
+        assert_eq!(dummy_info.polls, 2);
+        assert_eq!(dummy_info.age, Duration::from_millis(10));
+        assert!(dummy_info.max_poll_time <= dummy_info.poll_time);
+        let background_info: &TaskInfo = tasks.iter().find(|task| task.key == background_key).unwrap();
+        assert_eq!(background_info.kind, "Background");
+        assert_eq!(background_info.priority, Priority::Low);
+        assert_eq!(background_info.completed, false);
+        assert_eq!(background_info.polls, 1);
+
+        // Tasks that were dropped are no longer listed.
+        drop(scheduler.from_raw_handle(background_key).unwrap());
+        assert_eq!(scheduler.tasks().len(), 1);
+    }
+
+    #[test]
+    fn scheduler_offload() {
+        let scheduler: Scheduler = Scheduler::default();
+
+        // Jobs are handed back if there are no workers.
+        assert!(scheduler.offload(|| 42).is_err());
+
+        scheduler.set_workers(&[0, 0]).expect("set_workers() failed");
+        assert_eq!(scheduler.nworkers(), 2);
+        assert!(scheduler.set_workers(&[0]).is_err());
+
+        // Insert futures that wait on offloaded jobs.
+        let mut handles: Vec<(SchedulerHandle, Rc<Cell<usize>>)> = Vec::new();
+        for i in 0..16 {
+            let job: OffloadFuture<usize> = match scheduler.offload(move || i * 2) {
+                Ok(job) => job,
+                Err(_) => panic!("offload() failed"),
+            };
+            let output: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+            let future: OffloadedFuture = OffloadedFuture {
+                job,
+                output: output.clone(),
+            };
+            let handle: SchedulerHandle = scheduler.insert(future, Priority::High).expect("insert() failed");
+            handles.push((handle, output));
+        }
+
+        // Futures complete once their jobs are reaped.
+        while !handles.iter().all(|(handle, _)| handle.has_completed()) {
+            scheduler.poll();
+            std::thread::yield_now();
+        }
+        for (i, (_, output)) in handles.iter().enumerate() {
+            assert_eq!(output.get(), i * 2);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn scheduler_idle_park() {
+        let scheduler: Scheduler = Scheduler::default();
+        scheduler.set_idle_policy(IdlePolicy {
+            spin_polls: 2,
+            busy_polls: 1,
+            max_park: Duration::from_secs(10),
+        });
+        let fd: RawFd = scheduler.event_fd().expect("event_fd() failed");
+        let park_time: Duration = Duration::from_millis(20);
+
+        // A task that keeps the scheduler busy is never parked on.
+        let polls: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+        let handle: SchedulerHandle = scheduler
+            .insert(SpinFuture { polls: polls.clone() }, Priority::High)
+            .expect("insert() failed");
+        for _ in 0..4 {
+            scheduler.poll();
+        }
+        let start: Instant = Instant::now();
+        scheduler.park_on(fd, Some(park_time)).expect("park_on() failed");
+        assert!(start.elapsed() < park_time);
+
+        // Park once there were enough idle polls in a row.
+        drop(handle);
+        for _ in 0..3 {
+            scheduler.poll();
+        }
+        let start: Instant = Instant::now();
+        scheduler.park_on(fd, Some(park_time)).expect("park_on() failed");
+        assert!(start.elapsed() >= park_time);
+
+        // Parking stops as soon as the descriptor is signaled.
+        let value: u64 = 1;
+        assert_eq!(
+            unsafe { libc::write(fd, &value as *const u64 as *const libc::c_void, 8) },
+            8
+        );
+        let start: Instant = Instant::now();
+        scheduler.park_on(fd, None).expect("park_on() failed");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
     #[bench]
     fn bench_scheduler_poll(b: &mut Bencher) {
         let scheduler: Scheduler = Scheduler::default();
@@ -315,7 +1436,7 @@ mod tests {
         // Half of them will be ready.
         for val in 0..1024 {
             let future: DummyFuture = DummyFuture::new(val);
-            let handle: SchedulerHandle = match scheduler.insert(future) {
+            let handle: SchedulerHandle = match scheduler.insert(future, Priority::High) {
                 Some(handle) => handle,
                 None => panic!("insert() failed"),
             };
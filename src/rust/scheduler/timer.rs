@@ -0,0 +1,427 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Implementation of a hierarchical timer wheel.
+//!
+//! Time is split into ticks of one millisecond. Each level of the wheel has
+//! [NUM_SLOTS] slots, and a slot in some level covers as many ticks as all
+//! slots in the level below it. Timers are placed in the lowest level in which
+//! they fall into a future slot, so that arming and canceling a timer takes
+//! constant time. As the clock advances, timers in higher levels are cascaded
+//! into lower ones, until they finally expire.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use ::slab::Slab;
+use ::std::{
+    cell::{
+        RefCell,
+        RefMut,
+    },
+    future::Future,
+    mem,
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+    time::Instant,
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Number of bits of a tick that index the slots of a level.
+const SLOT_BITS: u32 = 6;
+
+/// Number of slots in a level.
+const NUM_SLOTS: usize = 1 << SLOT_BITS;
+
+/// Number of levels. Timers that are farther away than what all levels cover are parked in the last slot of the top
+/// level, and they are placed again once it is reached.
+const NUM_LEVELS: usize = 6;
+
+/// Number of ticks covered by all levels.
+const MAX_TICKS: u64 = 1 << (SLOT_BITS * NUM_LEVELS as u32);
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Timer that is armed in a [TimerWheel].
+struct Entry {
+    /// Point in time at which the timer expires.
+    deadline: Instant,
+    /// Tick in which the timer expires.
+    tick: u64,
+    /// Waker of the task that waits on the timer.
+    waker: Option<Waker>,
+    /// Index of the slot that holds the timer, or `None` if it has expired.
+    slot: Option<usize>,
+}
+
+/// Timer Wheel
+pub struct TimerWheel {
+    /// Point in time of tick zero.
+    origin: Instant,
+    /// Current time.
+    now: Instant,
+    /// Current tick.
+    tick: u64,
+    /// Armed timers, including those that have expired but were not removed yet.
+    entries: Slab<Entry>,
+    /// Keys of the timers in each slot, level after level.
+    slots: Vec<Vec<usize>>,
+    /// Bitmap of slots that hold some timer, for each level.
+    occupied: [u64; NUM_LEVELS],
+}
+
+/// Sleep Future
+///
+/// Completes once the clock of the underlying [TimerWheel] reaches some
+/// deadline. Dropping a [SleepFuture] cancels its timer.
+pub struct SleepFuture {
+    /// Underlying timer wheel.
+    timers: Rc<RefCell<TimerWheel>>,
+    /// Point in time at which this future completes.
+    deadline: Instant,
+    /// Key of the armed timer, if any.
+    key: Option<usize>,
+    /// Whether the deadline was reached.
+    expired: bool,
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+/// Associate Functions for Timer Wheels
+impl TimerWheel {
+    /// Creates a timer wheel whose clock starts at `now`.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            origin: now,
+            now,
+            tick: 0,
+            entries: Slab::new(),
+            slots: (0..NUM_LEVELS * NUM_SLOTS).map(|_| Vec::new()).collect(),
+            occupied: [0; NUM_LEVELS],
+        }
+    }
+
+    /// Returns the current time of the target [TimerWheel].
+    pub fn now(&self) -> Instant {
+        self.now
+    }
+
+    /// Arms a timer that expires at `deadline`, which must be later than the current time, and returns its key. Once
+    /// the timer expires, `waker` is woken up.
+    pub fn insert(&mut self, deadline: Instant, waker: Waker) -> usize {
+        debug_assert!(deadline > self.now);
+        let entry: Entry = Entry {
+            deadline,
+            tick: self.to_tick(deadline),
+            waker: Some(waker),
+            slot: None,
+        };
+        let key: usize = self.entries.insert(entry);
+        self.place(key);
+        key
+    }
+
+    /// Replaces the waker of the timer identified by `key`, unless they would wake up the same task.
+    pub fn register(&mut self, key: usize, waker: &Waker) {
+        let entry: &mut Entry = &mut self.entries[key];
+        if entry.waker.as_ref().map_or(true, |w| !w.will_wake(waker)) {
+            entry.waker = Some(waker.clone());
+        }
+    }
+
+    /// Queries whether the timer identified by `key` has expired.
+    pub fn is_expired(&self, key: usize) -> bool {
+        self.entries[key].slot.is_none()
+    }
+
+    /// Removes the timer identified by `key`, whether it has expired or not.
+    pub fn remove(&mut self, key: usize) {
+        let entry: Entry = self.entries.remove(key);
+        if let Some(ix) = entry.slot {
+            let slot: &mut Vec<usize> = &mut self.slots[ix];
+            if let Some(pos) = slot.iter().position(|k| *k == key) {
+                slot.swap_remove(pos);
+            }
+            if slot.is_empty() {
+                self.occupied[ix / NUM_SLOTS] &= !(1 << (ix % NUM_SLOTS));
+            }
+        }
+    }
+
+    /// Advances the clock to `now`, and returns the wakers of the timers that expired.
+    pub fn advance(&mut self, now: Instant) -> Vec<Waker> {
+        assert!(self.now <= now);
+        self.now = now;
+        let target: u64 = self.to_tick(now);
+        let mut wakers: Vec<Waker> = Vec::new();
+        // Timers that expire later in the current tick.
+        let mut deferred: Vec<usize> = Vec::new();
+
+        // Visit slots in expiration order, expiring or cascading their timers.
+        while let Some((level, slot, start)) = self.next_slot() {
+            if start > target {
+                break;
+            }
+            self.tick = start;
+            self.occupied[level] &= !(1 << slot);
+            for key in mem::take(&mut self.slots[level * NUM_SLOTS + slot]) {
+                let entry: &mut Entry = &mut self.entries[key];
+                entry.slot = None;
+                if entry.deadline <= now {
+                    if let Some(waker) = entry.waker.take() {
+                        wakers.push(waker);
+                    }
+                } else if entry.tick <= self.tick {
+                    deferred.push(key);
+                } else {
+                    self.place(key);
+                }
+            }
+        }
+
+        self.tick = target;
+        for key in deferred {
+            self.place(key);
+        }
+        wakers
+    }
+
+    /// Converts `instant` into a tick of the target [TimerWheel].
+    fn to_tick(&self, instant: Instant) -> u64 {
+        instant.saturating_duration_since(self.origin).as_millis() as u64
+    }
+
+    /// Places the timer identified by `key` in the lowest level in which it falls into a slot that is not in the past.
+    fn place(&mut self, key: usize) {
+        let tick: u64 = self.entries[key].tick.clamp(self.tick, self.tick | (MAX_TICKS - 1));
+        let significant: u32 = 63 - ((self.tick ^ tick) | (NUM_SLOTS as u64 - 1)).leading_zeros();
+        let level: usize = (significant / SLOT_BITS) as usize;
+        let slot: usize = (tick >> (level as u32 * SLOT_BITS)) as usize & (NUM_SLOTS - 1);
+        self.slots[level * NUM_SLOTS + slot].push(key);
+        self.occupied[level] |= 1 << slot;
+        self.entries[key].slot = Some(level * NUM_SLOTS + slot);
+    }
+
+    /// Returns the level, slot, and first tick of the earliest slot that holds some timer. Slots in lower levels always
+    /// come before slots in higher levels, because the latter are never the current slot of their level.
+    fn next_slot(&self) -> Option<(usize, usize, u64)> {
+        for level in 0..NUM_LEVELS {
+            let shift: u32 = level as u32 * SLOT_BITS;
+            let current: usize = (self.tick >> shift) as usize & (NUM_SLOTS - 1);
+            let occupied: u64 = self.occupied[level] >> current;
+            if occupied != 0 {
+                let slot: usize = current + occupied.trailing_zeros() as usize;
+                let level_start: u64 = self.tick & !((1 << (shift + SLOT_BITS)) - 1);
+                let start: u64 = (level_start + ((slot as u64) << shift)).max(self.tick);
+                return Some((level, slot, start));
+            }
+        }
+        None
+    }
+}
+
+/// Associate Functions for Sleep Futures
+impl SleepFuture {
+    /// Creates a future that completes once the clock of `timers` reaches `deadline`.
+    pub fn new(timers: Rc<RefCell<TimerWheel>>, deadline: Instant) -> Self {
+        Self {
+            timers,
+            deadline,
+            key: None,
+            expired: false,
+        }
+    }
+
+    /// Returns the deadline of the target [SleepFuture].
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Rearms the target [SleepFuture], so that it completes once the clock reaches `deadline`, whether it has already
+    /// completed or not. This enables periodic co-routines to wait on the same future over and over.
+    pub fn reset(&mut self, deadline: Instant) {
+        if let Some(key) = self.key.take() {
+            self.timers.borrow_mut().remove(key);
+        }
+        self.deadline = deadline;
+        self.expired = false;
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Future Trait Implementation for Sleep Futures
+impl Future for SleepFuture {
+    type Output = ();
+
+    /// Polls the target [SleepFuture]. The timer is only armed once the future is first polled.
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut SleepFuture = self.get_mut();
+        if self_.expired {
+            return Poll::Ready(());
+        }
+
+        let mut timers: RefMut<TimerWheel> = self_.timers.borrow_mut();
+        match self_.key {
+            None if self_.deadline <= timers.now() => {},
+            None => {
+                self_.key = Some(timers.insert(self_.deadline, ctx.waker().clone()));
+                return Poll::Pending;
+            },
+            Some(key) if timers.is_expired(key) => {
+                timers.remove(key);
+                self_.key = None;
+            },
+            Some(key) => {
+                timers.register(key, ctx.waker());
+                return Poll::Pending;
+            },
+        }
+        self_.expired = true;
+        Poll::Ready(())
+    }
+}
+
+/// Drop Trait Implementation for Sleep Futures
+impl Drop for SleepFuture {
+    /// Cancels the timer of the target [SleepFuture], if it is armed.
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.timers.borrow_mut().remove(key);
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SleepFuture,
+        TimerWheel,
+    };
+    use ::futures::task::noop_waker_ref;
+    use ::std::{
+        cell::RefCell,
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::Context,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    /// Polls `future` once with a waker that does nothing.
+    fn poll(future: &mut SleepFuture) -> bool {
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        Future::poll(Pin::new(future), &mut ctx).is_ready()
+    }
+
+    #[test]
+    fn timer_wheel_expiration_order() {
+        let start: Instant = Instant::now();
+        let timers: Rc<RefCell<TimerWheel>> = Rc::new(RefCell::new(TimerWheel::new(start)));
+
+        // Arm timers that land in different levels.
+        let timeouts: [u64; 5] = [5, 70, 4_100, 300_000, 20_000_000];
+        let mut futures: Vec<SleepFuture> = timeouts
+            .iter()
+            .map(|ms| SleepFuture::new(timers.clone(), start + Duration::from_millis(*ms)))
+            .collect();
+        assert!(futures.iter_mut().all(|future| !poll(future)));
+
+        // Each timer expires exactly at its deadline, and not a millisecond earlier.
+        for (i, ms) in timeouts.iter().enumerate() {
+            timers.borrow_mut().advance(start + Duration::from_millis(ms - 1));
+            assert!(!poll(&mut futures[i]));
+            timers.borrow_mut().advance(start + Duration::from_millis(*ms));
+            assert!(poll(&mut futures[i]));
+            assert!(futures[i + 1..].iter_mut().all(|future| !poll(future)));
+        }
+    }
+
+    #[test]
+    fn timer_wheel_sub_tick_deadline() {
+        let start: Instant = Instant::now();
+        let timers: Rc<RefCell<TimerWheel>> = Rc::new(RefCell::new(TimerWheel::new(start)));
+        let mut future: SleepFuture = SleepFuture::new(timers.clone(), start + Duration::from_micros(2_500));
+        assert!(!poll(&mut future));
+
+        // The deadline is in the middle of a tick.
+        timers.borrow_mut().advance(start + Duration::from_micros(2_400));
+        assert!(!poll(&mut future));
+        timers.borrow_mut().advance(start + Duration::from_micros(2_500));
+        assert!(poll(&mut future));
+    }
+
+    #[test]
+    fn timer_wheel_cancel() {
+        let start: Instant = Instant::now();
+        let timers: Rc<RefCell<TimerWheel>> = Rc::new(RefCell::new(TimerWheel::new(start)));
+        let mut canceled: SleepFuture = SleepFuture::new(timers.clone(), start + Duration::from_millis(10));
+        let mut future: SleepFuture = SleepFuture::new(timers.clone(), start + Duration::from_millis(10));
+        assert!(!poll(&mut canceled));
+        assert!(!poll(&mut future));
+
+        // Dropping a future removes its timer.
+        drop(canceled);
+        assert_eq!(timers.borrow().entries.len(), 1);
+        timers.borrow_mut().advance(start + Duration::from_millis(10));
+        assert!(poll(&mut future));
+        assert_eq!(timers.borrow().entries.len(), 0);
+    }
+
+    #[test]
+    fn timer_wheel_past_deadline() {
+        let start: Instant = Instant::now();
+        let timers: Rc<RefCell<TimerWheel>> = Rc::new(RefCell::new(TimerWheel::new(start)));
+        timers.borrow_mut().advance(start + Duration::from_secs(1));
+
+        // Deadlines that have already passed complete without arming a timer.
+        let mut future: SleepFuture = SleepFuture::new(timers.clone(), start);
+        assert!(poll(&mut future));
+        assert_eq!(timers.borrow().entries.len(), 0);
+    }
+
+    #[test]
+    fn timer_wheel_reset() {
+        let start: Instant = Instant::now();
+        let timers: Rc<RefCell<TimerWheel>> = Rc::new(RefCell::new(TimerWheel::new(start)));
+        let mut future: SleepFuture = SleepFuture::new(timers.clone(), start + Duration::from_millis(10));
+        assert!(!poll(&mut future));
+
+        // Rearming an armed future replaces its timer.
+        future.reset(start + Duration::from_millis(20));
+        assert!(!poll(&mut future));
+        assert_eq!(timers.borrow().entries.len(), 1);
+        timers.borrow_mut().advance(start + Duration::from_millis(10));
+        assert!(!poll(&mut future));
+        timers.borrow_mut().advance(start + Duration::from_millis(20));
+        assert!(poll(&mut future));
+
+        // Rearming a completed future makes it wait for the new deadline.
+        future.reset(future.deadline() + Duration::from_millis(10));
+        assert!(!poll(&mut future));
+        timers.borrow_mut().advance(start + Duration::from_millis(30));
+        assert!(poll(&mut future));
+    }
+}
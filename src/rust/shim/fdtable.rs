@@ -0,0 +1,75 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::QDesc;
+use ::std::{
+    collections::HashMap,
+    os::unix::prelude::RawFd,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// File Descriptor Table
+///
+/// Translates the file descriptors that are handed out to the application into the queue descriptors of the
+/// underlying LibOS. Each file descriptor is backed by a placeholder kernel file descriptor, so that it never collides
+/// with the ones that the application obtains from the kernel.
+pub struct FdTable {
+    /// Queue descriptors, keyed by file descriptor.
+    table: HashMap<RawFd, QDesc>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Associated Functions for File Descriptor Tables
+impl FdTable {
+    /// Creates an empty file descriptor table.
+    pub fn new() -> Self {
+        Self { table: HashMap::new() }
+    }
+
+    /// Associates a file descriptor to a queue descriptor.
+    pub fn insert(&mut self, fd: RawFd, qd: QDesc) {
+        let prev: Option<QDesc> = self.table.insert(fd, qd);
+        debug_assert!(prev.is_none(), "file descriptor is already in use");
+    }
+
+    /// Returns the queue descriptor that is associated to a file descriptor, if any.
+    pub fn get(&self, fd: RawFd) -> Option<QDesc> {
+        self.table.get(&fd).copied()
+    }
+
+    /// Removes a file descriptor from the table, returning its associated queue descriptor.
+    pub fn remove(&mut self, fd: RawFd) -> Option<QDesc> {
+        self.table.remove(&fd)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::FdTable;
+    use crate::runtime::QDesc;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut fds: FdTable = FdTable::new();
+        fds.insert(42, QDesc::from(7_usize));
+        assert_eq!(fds.get(42), Some(QDesc::from(7_usize)));
+        assert_eq!(fds.get(43), None);
+        assert_eq!(fds.remove(42), Some(QDesc::from(7_usize)));
+        assert_eq!(fds.get(42), None);
+        assert_eq!(fds.remove(42), None);
+    }
+}
@@ -0,0 +1,447 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! LD_PRELOAD socket interposition shim.
+//!
+//! This module exports the `socket()`, `bind()`, `listen()`, `accept()`, `connect()`, `send()`, `recv()` and `close()`
+//! symbols, so that unmodified POSIX applications may be run on top of Demikernel by preloading it. IPv4 TCP and UDP
+//! sockets are translated onto queue operations of the underlying LibOS, and blocking semantics are emulated by waiting
+//! on the resulting queue tokens. All other file descriptors are forwarded to the next definition of each symbol
+//! (i.e. to the C library).
+//!
+//! Note that non-blocking sockets and send/recv flags are not supported, and that sockets are only translated on the
+//! thread that created them.
+
+mod fdtable;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use self::fdtable::FdTable;
+use crate::{
+    demikernel::libos::{
+        name::LibOSName,
+        LibOS,
+    },
+    runtime::{
+        fail::Fail,
+        memory::{
+            copy_from_sgarray,
+            copy_to_sgarray,
+        },
+        types::{
+            demi_opcode_t,
+            demi_qresult_t,
+            demi_sgarray_t,
+            DEMI_SGARRAY_MAXLEN,
+        },
+        QDesc,
+        QToken,
+    },
+};
+use ::libc::{
+    c_char,
+    c_int,
+    c_void,
+    size_t,
+    sockaddr,
+    sockaddr_in,
+    socklen_t,
+    ssize_t,
+    AF_INET,
+    EFD_CLOEXEC,
+    RTLD_NEXT,
+    SOCK_CLOEXEC,
+    SOCK_DGRAM,
+    SOCK_NONBLOCK,
+    SOCK_STREAM,
+};
+use ::std::{
+    cell::{
+        RefCell,
+        RefMut,
+    },
+    cmp,
+    mem,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    os::unix::prelude::RawFd,
+    panic::{
+        self,
+        AssertUnwindSafe,
+    },
+    ptr,
+    slice,
+};
+
+//======================================================================================================================
+// Macros
+//======================================================================================================================
+
+/// Resolves the next definition of a symbol (i.e. the one that the shim interposes).
+macro_rules! real {
+    ($name:ident: $type:ty) => {{
+        let symbol: *mut c_void = libc::dlsym(RTLD_NEXT, concat!(stringify!($name), "\0").as_ptr() as *const c_char);
+        if symbol.is_null() {
+            panic!("failed to resolve {}()", stringify!($name));
+        }
+        mem::transmute::<*mut c_void, $type>(symbol)
+    }};
+}
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Shim state.
+struct Shim {
+    /// Underlying LibOS.
+    libos: LibOS,
+    /// Translation table from file descriptors to queue descriptors.
+    fds: FdTable,
+}
+
+thread_local! {
+    /// Shim state. This is lazily initialized on the first interposed call of each thread, as LibOSes are not
+    /// thread-safe. A failure to initialize it is kept, so that subsequent calls are forwarded to the C library.
+    static SHIM: RefCell<Option<Result<Shim, Fail>>> = RefCell::new(None);
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Associated Functions for the Shim
+impl Shim {
+    /// Instantiates the shim on top of the LibOS that is selected by the environment or by the configuration file.
+    fn new() -> Result<Self, Fail> {
+        let libos: LibOS = match LibOSName::from_env() {
+            Ok(libos_name) => LibOS::new(libos_name)?,
+            Err(_) => LibOS::from_config()?,
+        };
+        Ok(Self {
+            libos,
+            fds: FdTable::new(),
+        })
+    }
+
+    /// Hands out a file descriptor for a queue descriptor.
+    fn alloc_fd(&mut self, qd: QDesc) -> Result<RawFd, Fail> {
+        // Reserve a kernel file descriptor, so that the one that we hand out never collides with the ones that the
+        // application obtains from the kernel.
+        let fd: RawFd = unsafe { libc::eventfd(0, EFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(Fail::new(errno(), "failed to reserve file descriptor"));
+        }
+        self.fds.insert(fd, qd);
+        Ok(fd)
+    }
+
+    /// Waits for an operation to complete, failing with its error code if it completed with an unexpected operation
+    /// code.
+    fn wait(&mut self, qt: QToken, opcode: demi_opcode_t) -> Result<demi_qresult_t, Fail> {
+        let qr: demi_qresult_t = self.libos.wait(qt, None)?;
+        if qr.qr_opcode != opcode {
+            let errno: c_int = if qr.qr_ret != 0 { qr.qr_ret as c_int } else { libc::EIO };
+            return Err(Fail::new(errno, "operation failed"));
+        }
+        Ok(qr)
+    }
+
+    /// Emulates a blocking `socket()` call.
+    fn socket(&mut self, domain: c_int, socket_type: c_int, protocol: c_int) -> Result<RawFd, Fail> {
+        let qd: QDesc = self.libos.socket(domain, socket_type, protocol)?;
+        match self.alloc_fd(qd) {
+            Ok(fd) => Ok(fd),
+            Err(e) => {
+                self.libos.close(qd)?;
+                Err(e)
+            },
+        }
+    }
+
+    /// Emulates a blocking `accept()` call.
+    fn accept(&mut self, qd: QDesc, addr: *mut sockaddr, addrlen: *mut socklen_t) -> Result<RawFd, Fail> {
+        let qt: QToken = self.libos.accept(qd)?;
+        let qr: demi_qresult_t = self.wait(qt, demi_opcode_t::DEMI_OPC_ACCEPT)?;
+
+        // Safety: The accept result is the active field of the union, given the operation code that we checked above.
+        let (new_qd, remote): (QDesc, sockaddr) = unsafe { (QDesc::from(qr.qr_value.ares.qd), qr.qr_value.ares.addr) };
+        if !addr.is_null() && !addrlen.is_null() {
+            // Safety: The application provided us with a buffer of `addrlen` bytes.
+            unsafe {
+                let len: usize = cmp::min(*addrlen as usize, mem::size_of::<sockaddr>());
+                ptr::copy_nonoverlapping(&remote as *const sockaddr as *const u8, addr as *mut u8, len);
+                *addrlen = mem::size_of::<sockaddr_in>() as socklen_t;
+            }
+        }
+
+        self.alloc_fd(new_qd)
+    }
+
+    /// Emulates a blocking `connect()` call.
+    fn connect(&mut self, qd: QDesc, remote: SocketAddrV4) -> Result<(), Fail> {
+        let qt: QToken = self.libos.connect(qd, remote)?;
+        self.wait(qt, demi_opcode_t::DEMI_OPC_CONNECT)?;
+        Ok(())
+    }
+
+    /// Emulates a blocking `send()` call. This returns the number of bytes that were sent.
+    fn send(&mut self, qd: QDesc, buf: &[u8]) -> Result<usize, Fail> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // Send at most as much data as fits in a single scatter-gather array.
+        let len: usize = cmp::min(buf.len(), DEMI_SGARRAY_MAXLEN * u16::MAX as usize);
+        let sga: demi_sgarray_t = self.libos.sgaalloc(len)?;
+        copy_to_sgarray(&sga, &buf[..len]);
+
+        let result: Result<usize, Fail> = match self.libos.push(qd, &sga) {
+            Ok(qt) => self.wait(qt, demi_opcode_t::DEMI_OPC_PUSH).map(|_| len),
+            Err(e) => Err(e),
+        };
+        self.libos.sgafree(sga)?;
+        result
+    }
+
+    /// Emulates a blocking `recv()` call. This returns the number of bytes that were received.
+    fn recv(&mut self, qd: QDesc, buf: &mut [u8]) -> Result<usize, Fail> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // Receive at most as much data as fits in the application's buffer.
+        let size: usize = cmp::min(buf.len(), u16::MAX as usize);
+        let qt: QToken = self.libos.pop_sized(qd, size)?;
+        let qr: demi_qresult_t = self.wait(qt, demi_opcode_t::DEMI_OPC_POP)?;
+
+        // Safety: The scatter-gather array is the active field of the union, given the operation code checked above.
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+        let nbytes: usize = copy_from_sgarray(&sga, buf);
+        self.libos.sgafree(sga)?;
+        Ok(nbytes)
+    }
+
+    /// Closes a file descriptor that is backed by a queue descriptor.
+    fn close(&mut self, fd: RawFd, qd: QDesc) -> Result<(), Fail> {
+        self.fds.remove(fd);
+        let result: Result<(), Fail> = self.libos.close(qd);
+        // Release the placeholder file descriptor.
+        unsafe { real!(close: unsafe extern "C" fn(c_int) -> c_int)(fd) };
+        result
+    }
+}
+
+//======================================================================================================================
+// Interposed Functions
+//======================================================================================================================
+
+#[no_mangle]
+pub unsafe extern "C" fn socket(domain: c_int, socket_type: c_int, protocol: c_int) -> c_int {
+    catch_unwind(|| {
+        // Only IPv4 TCP and UDP sockets are handled by Demikernel.
+        let typ: c_int = socket_type & !(SOCK_NONBLOCK | SOCK_CLOEXEC);
+        if domain == AF_INET && (typ == SOCK_STREAM || typ == SOCK_DGRAM) && socket_type & SOCK_NONBLOCK == 0 {
+            if let Some(ret) = with_shim(|shim| Some(shim.socket(domain, typ, protocol))) {
+                return to_ret(ret);
+            }
+        }
+        real!(socket: unsafe extern "C" fn(c_int, c_int, c_int) -> c_int)(domain, socket_type, protocol)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn bind(sockfd: c_int, addr: *const sockaddr, addrlen: socklen_t) -> c_int {
+    catch_unwind(|| {
+        let ret: Option<Result<(), Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(sockfd)?;
+            Some(sockaddr_to_socketaddrv4(addr, addrlen).and_then(|local| shim.libos.bind(qd, local)))
+        });
+        match ret {
+            Some(ret) => to_ret(ret.map(|_| 0)),
+            None => {
+                real!(bind: unsafe extern "C" fn(c_int, *const sockaddr, socklen_t) -> c_int)(sockfd, addr, addrlen)
+            },
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn listen(sockfd: c_int, backlog: c_int) -> c_int {
+    catch_unwind(|| {
+        let ret: Option<Result<(), Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(sockfd)?;
+            Some(shim.libos.listen(qd, cmp::max(backlog, 1) as usize))
+        });
+        match ret {
+            Some(ret) => to_ret(ret.map(|_| 0)),
+            None => real!(listen: unsafe extern "C" fn(c_int, c_int) -> c_int)(sockfd, backlog),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn accept(sockfd: c_int, addr: *mut sockaddr, addrlen: *mut socklen_t) -> c_int {
+    catch_unwind(|| {
+        let ret: Option<Result<RawFd, Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(sockfd)?;
+            Some(shim.accept(qd, addr, addrlen))
+        });
+        match ret {
+            Some(ret) => to_ret(ret),
+            None => real!(accept: unsafe extern "C" fn(c_int, *mut sockaddr, *mut socklen_t) -> c_int)(
+                sockfd, addr, addrlen,
+            ),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn connect(sockfd: c_int, addr: *const sockaddr, addrlen: socklen_t) -> c_int {
+    catch_unwind(|| {
+        let ret: Option<Result<(), Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(sockfd)?;
+            Some(sockaddr_to_socketaddrv4(addr, addrlen).and_then(|remote| shim.connect(qd, remote)))
+        });
+        match ret {
+            Some(ret) => to_ret(ret.map(|_| 0)),
+            None => {
+                real!(connect: unsafe extern "C" fn(c_int, *const sockaddr, socklen_t) -> c_int)(sockfd, addr, addrlen)
+            },
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn send(sockfd: c_int, buf: *const c_void, len: size_t, flags: c_int) -> ssize_t {
+    catch_unwind(|| {
+        let ret: Option<Result<usize, Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(sockfd)?;
+            if buf.is_null() && len > 0 {
+                return Some(Err(Fail::new(libc::EFAULT, "invalid buffer")));
+            }
+            let data: &[u8] = if len == 0 {
+                &[]
+            } else {
+                slice::from_raw_parts(buf as *const u8, len)
+            };
+            Some(shim.send(qd, data))
+        });
+        match ret {
+            Some(Ok(nbytes)) => nbytes as ssize_t,
+            Some(Err(e)) => to_ret::<c_int>(Err(e)) as ssize_t,
+            None => real!(send: unsafe extern "C" fn(c_int, *const c_void, size_t, c_int) -> ssize_t)(
+                sockfd, buf, len, flags,
+            ),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn recv(sockfd: c_int, buf: *mut c_void, len: size_t, flags: c_int) -> ssize_t {
+    catch_unwind(|| {
+        let ret: Option<Result<usize, Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(sockfd)?;
+            if buf.is_null() && len > 0 {
+                return Some(Err(Fail::new(libc::EFAULT, "invalid buffer")));
+            }
+            let data: &mut [u8] = if len == 0 {
+                &mut []
+            } else {
+                slice::from_raw_parts_mut(buf as *mut u8, len)
+            };
+            Some(shim.recv(qd, data))
+        });
+        match ret {
+            Some(Ok(nbytes)) => nbytes as ssize_t,
+            Some(Err(e)) => to_ret::<c_int>(Err(e)) as ssize_t,
+            None => {
+                real!(recv: unsafe extern "C" fn(c_int, *mut c_void, size_t, c_int) -> ssize_t)(sockfd, buf, len, flags)
+            },
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn close(fd: c_int) -> c_int {
+    catch_unwind(|| {
+        let ret: Option<Result<(), Fail>> = with_shim(|shim| {
+            let qd: QDesc = shim.fds.get(fd)?;
+            Some(shim.close(fd, qd))
+        });
+        match ret {
+            Some(ret) => to_ret(ret.map(|_| 0)),
+            None => real!(close: unsafe extern "C" fn(c_int) -> c_int)(fd),
+        }
+    })
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Runs a function on the shim state, initializing it if needed. This returns `None` if the shim is unavailable or if
+/// the function declines to handle the call, in which case the call should be forwarded to the C library.
+fn with_shim<T>(f: impl FnOnce(&mut Shim) -> Option<T>) -> Option<T> {
+    SHIM.try_with(|shim| {
+        // Calls that are issued while the shim is busy come from the LibOS itself, so they are forwarded.
+        let mut shim: RefMut<Option<Result<Shim, Fail>>> = shim.try_borrow_mut().ok()?;
+        let shim: &mut Result<Shim, Fail> = shim.get_or_insert_with(|| {
+            Shim::new().map_err(|e| {
+                warn!("failed to initialize shim: {:?}", e);
+                e
+            })
+        });
+        match shim {
+            Ok(shim) => f(shim),
+            Err(_) => None,
+        }
+    })
+    .ok()
+    .flatten()
+}
+
+/// Runs the body of an interposed function, failing the call with `EIO` if it panics, so that panics never unwind into
+/// the application.
+fn catch_unwind<T: From<i8>>(f: impl FnOnce() -> T) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(ret) => ret,
+        Err(_) => to_ret(Err(Fail::new(libc::EIO, "shim call panicked"))),
+    }
+}
+
+/// Converts a result into a return value, setting `errno` on failure.
+fn to_ret<T: From<i8>>(result: Result<T, Fail>) -> T {
+    match result {
+        Ok(ret) => ret,
+        Err(e) => {
+            trace!("shim call failed: {:?}", e);
+            unsafe { *libc::__errno_location() = e.errno };
+            T::from(-1)
+        },
+    }
+}
+
+/// Returns the current value of `errno`.
+fn errno() -> c_int {
+    unsafe { *libc::__errno_location() }
+}
+
+/// Converts a [sockaddr] into a [SocketAddrV4].
+fn sockaddr_to_socketaddrv4(addr: *const sockaddr, addrlen: socklen_t) -> Result<SocketAddrV4, Fail> {
+    if addr.is_null() || (addrlen as usize) < mem::size_of::<sockaddr_in>() {
+        return Err(Fail::new(libc::EINVAL, "invalid socket address"));
+    }
+    // Safety: The application provided us with a buffer that is large enough to hold a `sockaddr_in`.
+    let sin: sockaddr_in = unsafe { *(addr as *const sockaddr_in) };
+    if sin.sin_family != AF_INET as u16 {
+        return Err(Fail::new(libc::EAFNOSUPPORT, "communication domain not supported"));
+    }
+    let ip: Ipv4Addr = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+    let port: u16 = u16::from_be(sin.sin_port);
+    Ok(SocketAddrV4::new(ip, port))
+}
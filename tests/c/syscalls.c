@@ -76,6 +76,20 @@ static bool inval_connect(void)
     return (demi_connect(qt, qd, saddr, size) != 0);
 }
 
+/**
+ * @brief Issues an invalid call to demi_timedconnect().
+ */
+static bool inval_timedconnect(void)
+{
+    demi_qtoken_t *qt = NULL;
+    int qd = -1;
+    struct sockaddr *saddr = NULL;
+    socklen_t size = -1;
+    struct timespec *abstime = NULL;
+
+    return (demi_timedconnect(qt, qd, saddr, size, abstime) != 0);
+}
+
 /**
  * @brief Issues an invalid call to demi_close().
  */
@@ -86,6 +100,39 @@ static bool inval_close(void)
     return (demi_close(qd) != 0);
 }
 
+/**
+ * @brief Issues an invalid call to demi_async_close().
+ */
+static bool inval_async_close(void)
+{
+    demi_qtoken_t *qt = NULL;
+    int qd = -1;
+
+    return (demi_async_close(qt, qd) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_dup().
+ */
+static bool inval_dup(void)
+{
+    int *qd_out = NULL;
+    int qd = -1;
+
+    return (demi_dup(qd_out, qd) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_shutdown().
+ */
+static bool inval_shutdown(void)
+{
+    int qd = -1;
+    int how = -1;
+
+    return (demi_shutdown(qd, how) != 0);
+}
+
 /**
  * @brief Issues an invalid call to demi_push().
  */
@@ -98,6 +145,19 @@ static bool inval_push(void)
     return (demi_push(qt, qd, sga) != 0);
 }
 
+/**
+ * @brief Issues an invalid call to demi_pushv().
+ */
+static bool inval_pushv(void)
+{
+    demi_qtoken_t *qt = NULL;
+    int qd = -1;
+    demi_sgarray_t *sgas = NULL;
+    int num_sgas = -1;
+
+    return (demi_pushv(qt, qd, sgas, num_sgas) != 0);
+}
+
 /**
  * @brief Issues an invalid call to demi_pushto().
  */
@@ -123,6 +183,116 @@ static bool inval_pop(void)
     return (demi_pop(qt, qd) != 0);
 }
 
+/**
+ * @brief Issues an invalid call to demi_timedpop().
+ */
+static bool inval_timedpop(void)
+{
+    demi_qtoken_t *qt = NULL;
+    int qd = -1;
+    struct timespec *abstime = NULL;
+
+    return (demi_timedpop(qt, qd, abstime) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_splice().
+ */
+static bool inval_splice(void)
+{
+    demi_qtoken_t *qt = NULL;
+    int srcqd = -1;
+    int dstqd = -1;
+
+    return (demi_splice(qt, srcqd, dstqd) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_ping().
+ */
+static bool inval_ping(void)
+{
+    demi_qtoken_t *qt = NULL;
+    struct sockaddr *saddr = NULL;
+    socklen_t size = -1;
+    struct timespec *timeout = NULL;
+
+    return (demi_ping(qt, saddr, size, timeout) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_link_events().
+ */
+static bool inval_link_events(void)
+{
+    int *qd = NULL;
+
+    return (demi_link_events(qd) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_getsockname().
+ */
+static bool inval_getsockname(void)
+{
+    int qd = -1;
+    struct sockaddr *saddr = NULL;
+    socklen_t *size = NULL;
+
+    return (demi_getsockname(qd, saddr, size) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_getpeername().
+ */
+static bool inval_getpeername(void)
+{
+    int qd = -1;
+    struct sockaddr *saddr = NULL;
+    socklen_t *size = NULL;
+
+    return (demi_getpeername(qd, saddr, size) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_gettimestamps().
+ */
+static bool inval_gettimestamps(void)
+{
+    int qd = -1;
+    demi_timestamps_t *ts = NULL;
+
+    return (demi_gettimestamps(qd, ts) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_setsockopt().
+ */
+static bool inval_setsockopt(void)
+{
+    int qd = -1;
+    int level = -1;
+    int optname = -1;
+    const void *optval = NULL;
+    socklen_t optlen = 0;
+
+    return (demi_setsockopt(qd, level, optname, optval, optlen) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_getsockopt().
+ */
+static bool inval_getsockopt(void)
+{
+    int qd = -1;
+    int level = -1;
+    int optname = -1;
+    void *optval = NULL;
+    socklen_t *optlen = NULL;
+
+    return (demi_getsockopt(qd, level, optname, optval, optlen) != 0);
+}
+
 /*===================================================================================================================*
  * System Calls in demi/sga.h                                                                                        *
  *===================================================================================================================*/
@@ -138,6 +308,18 @@ static bool inval_sgaalloc(void)
     return (sga.sga_buf == NULL);
 }
 
+/**
+ * @brief Issues an invalid call to demi_sgaregister().
+ */
+static bool inval_sgaregister(void)
+{
+    void *buf = NULL;
+    size_t len = 0;
+
+    demi_sgarray_t sga = demi_sgaregister(buf, len);
+    return (sga.sga_buf == NULL);
+}
+
 /**
  * @brief Issues an invalid call to demi_sgafree().
  */
@@ -190,6 +372,16 @@ static bool inval_wait_any(void)
     return (demi_wait_any(qr, ready_offset, qts, num_qts, timeout) != 0);
 }
 
+/**
+ * @brief Issues an invalid system call to demi_eventfd().
+ */
+static bool inval_eventfd(void)
+{
+    int *fd = NULL;
+
+    return (demi_eventfd(fd) != 0);
+}
+
 /*===================================================================================================================*
  * main()                                                                                                            *
  *===================================================================================================================*/
@@ -210,12 +402,27 @@ static struct test tests_libos[] = {{inval_socket, "invalid demi_socket()"},   {
                                     {inval_bind, "invalid demi_bind()"},       {inval_close, "invalid_demi_close()"},
                                     {inval_connect, "invalid demi_connect()"}, {inval_listen, "invalid demi_listen()"},
                                     {inval_pop, "invalid demi_pop()"},         {inval_push, "invalid demi_push()"},
-                                    {inval_pushto, "invalid demi_pushto()"}};
+                                    {inval_pushto, "invalid demi_pushto()"},
+                                    {inval_pushv, "invalid demi_pushv()"},
+                                    {inval_shutdown, "invalid demi_shutdown()"},
+                                    {inval_getsockname, "invalid demi_getsockname()"},
+                                    {inval_getpeername, "invalid demi_getpeername()"},
+                                    {inval_gettimestamps, "invalid demi_gettimestamps()"},
+                                    {inval_setsockopt, "invalid demi_setsockopt()"},
+                                    {inval_getsockopt, "invalid demi_getsockopt()"},
+                                    {inval_timedconnect, "invalid demi_timedconnect()"},
+                                    {inval_timedpop, "invalid demi_timedpop()"},
+                                    {inval_dup, "invalid demi_dup()"},
+                                    {inval_splice, "invalid demi_splice()"},
+                                    {inval_ping, "invalid demi_ping()"},
+                                    {inval_link_events, "invalid demi_link_events()"},
+                                    {inval_async_close, "invalid demi_async_close()"}};
 
 /**
  * @brief Tests for system calls in demi/sga.h
  */
 static struct test tests_sga[] = {{inval_sgaalloc, "invalid demi_sgaalloc()"},
+                                  {inval_sgaregister, "invalid demi_sgaregister()"},
                                   {inval_sgafree, "invalid demi_sgafree()"}};
 
 /**
@@ -223,7 +430,8 @@ static struct test tests_sga[] = {{inval_sgaalloc, "invalid demi_sgaalloc()"},
  */
 static struct test tests_wait[] = {{inval_timedwait, "invalid demi_timedwait()"},
                                    {inval_wait, "invalid demi_wait()"},
-                                   {inval_wait_any, "invalid demi_wait_any()"}};
+                                   {inval_wait_any, "invalid demi_wait_any()"},
+                                   {inval_eventfd, "invalid demi_eventfd()"}};
 
 /**
  * @brief Drives the application.
@@ -282,5 +490,11 @@ int main(int argc, char *const argv[])
         }
     }
 
+    /* This shall never fail. */
+    assert(demi_teardown() == 0);
+
+    /* Demikernel is no longer initialized. */
+    assert(demi_teardown() != 0);
+
     return (EXIT_SUCCESS);
 }
@@ -50,6 +50,7 @@ pub const SOCK_STREAM: i32 = libc::SOCK_STREAM;
 use std::{
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     thread::{
@@ -240,7 +241,7 @@ fn tcp_push_remote() {
         let qt: QToken = safe_pop(&mut libos, qd);
         let (qd, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
         match qr {
-            OperationResult::Pop(_, _) => (),
+            OperationResult::Pop(_, _, _) => (),
             _ => panic!("pop() has has failed {:?}", qr),
         }
 
@@ -681,7 +682,7 @@ fn tcp_bad_push() {
         let qt: QToken = safe_pop(&mut libos, qd);
         let (qd, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
         match qr {
-            OperationResult::Pop(_, _) => (),
+            OperationResult::Pop(_, _, _) => (),
             _ => panic!("pop() has has failed {:?}", qr),
         }
 
@@ -777,7 +778,7 @@ fn tcp_bad_pop() {
         let qt: QToken = safe_pop(&mut libos, qd);
         let (qd, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
         match qr {
-            OperationResult::Pop(_, _) => (),
+            OperationResult::Pop(_, _, _) => (),
             _ => panic!("pop() has has failed {:?}", qr),
         }
 
@@ -820,6 +821,175 @@ fn tcp_bad_pop() {
     bob.join().unwrap();
 }
 
+//======================================================================================================================
+// Shutdown
+//======================================================================================================================
+
+/// Tests if a connection can be half-closed, and end-of-stream is reported by `pop()`.
+#[test]
+fn tcp_shutdown_half_close() {
+    let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+    let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+    let alice: JoinHandle<()> = thread::spawn(move || {
+        let mut libos: InetStack = DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp());
+
+        let local: SocketAddrV4 = SocketAddrV4::new(ALICE_IPV4, PORT_BASE);
+
+        // Open connection.
+        let sockqd: QDesc = safe_socket(&mut libos);
+        safe_bind(&mut libos, sockqd, local);
+        safe_listen(&mut libos, sockqd);
+        let qt: QToken = safe_accept(&mut libos, sockqd);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        let qd: QDesc = match qr {
+            OperationResult::Accept(qd) => qd,
+            _ => panic!("accept() has failed"),
+        };
+
+        // Pop data sent before the remote peer shut down its sending side.
+        let qt: QToken = safe_pop(&mut libos, qd);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        match qr {
+            OperationResult::Pop(_, buf, eof) => {
+                assert_eq!(buf.len(), 32);
+                assert!(!eof);
+            },
+            _ => panic!("pop() has has failed {:?}", qr),
+        }
+
+        // Pop end-of-stream.
+        let qt: QToken = safe_pop(&mut libos, qd);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        match qr {
+            OperationResult::Pop(_, buf, eof) => {
+                assert_eq!(buf.len(), 0);
+                assert!(eof);
+            },
+            _ => panic!("pop() has has failed {:?}", qr),
+        }
+
+        // Close connection.
+        safe_close_active(&mut libos, qd);
+        safe_close_passive(&mut libos, sockqd);
+    });
+
+    let bob: JoinHandle<()> = thread::spawn(move || {
+        let mut libos: InetStack = DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp());
+
+        let remote: SocketAddrV4 = SocketAddrV4::new(ALICE_IPV4, PORT_BASE);
+
+        // Open connection.
+        let sockqd: QDesc = safe_socket(&mut libos);
+        let qt: QToken = safe_connect(&mut libos, sockqd, remote);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        match qr {
+            OperationResult::Connect => (),
+            _ => panic!("connect() has failed"),
+        }
+
+        // Push data.
+        let bytes: DemiBuffer = DummyLibOS::cook_data(32);
+        let qt: QToken = safe_push2(&mut libos, sockqd, &bytes);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        match qr {
+            OperationResult::Push => (),
+            _ => panic!("push() has failed"),
+        }
+
+        // Shut down the sending side.
+        match libos.shutdown(sockqd, Shutdown::Write) {
+            Ok(()) => (),
+            Err(e) => panic!("shutdown() has failed: {:?}", e),
+        }
+        let qt: QToken = safe_push2(&mut libos, sockqd, &bytes);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        match qr {
+            OperationResult::Failed(_) => (),
+            _ => panic!("push() after shutdown() should fail"),
+        }
+
+        // Close connection.
+        safe_close_active(&mut libos, sockqd);
+    });
+
+    alice.join().unwrap();
+    bob.join().unwrap();
+}
+
+//======================================================================================================================
+// Endpoint Addresses
+//======================================================================================================================
+
+/// Tests if local and remote endpoint addresses are reported for connected sockets.
+#[test]
+fn tcp_endpoint_addresses() {
+    let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+    let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+    let alice: JoinHandle<()> = thread::spawn(move || {
+        let mut libos: InetStack = DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp());
+
+        let local: SocketAddrV4 = SocketAddrV4::new(ALICE_IPV4, PORT_BASE);
+
+        // Open connection.
+        let sockqd: QDesc = safe_socket(&mut libos);
+        safe_bind(&mut libos, sockqd, local);
+        safe_listen(&mut libos, sockqd);
+        assert_eq!(
+            libos.local_addr(sockqd).expect("listening socket should be bound"),
+            local
+        );
+        if libos.remote_addr(sockqd).is_ok() {
+            panic!("remote_addr() on listening socket should fail");
+        }
+        let qt: QToken = safe_accept(&mut libos, sockqd);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        let qd: QDesc = match qr {
+            OperationResult::Accept(qd) => qd,
+            _ => panic!("accept() has failed"),
+        };
+
+        // Check endpoint addresses.
+        assert_eq!(libos.local_addr(qd).expect("accepted socket should be bound"), local);
+        let remote: SocketAddrV4 = libos.remote_addr(qd).expect("accepted socket should be connected");
+        assert_eq!(remote.ip(), &BOB_IPV4);
+
+        // Close connection.
+        safe_close_active(&mut libos, qd);
+        safe_close_passive(&mut libos, sockqd);
+    });
+
+    let bob: JoinHandle<()> = thread::spawn(move || {
+        let mut libos: InetStack = DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp());
+
+        let remote: SocketAddrV4 = SocketAddrV4::new(ALICE_IPV4, PORT_BASE);
+
+        // Open connection.
+        let sockqd: QDesc = safe_socket(&mut libos);
+        let unbound: SocketAddrV4 = libos.local_addr(sockqd).expect("unbound socket should have an address");
+        assert_eq!(unbound, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+        let qt: QToken = safe_connect(&mut libos, sockqd, remote);
+        let (_, qr): (QDesc, OperationResult) = safe_wait2(&mut libos, qt);
+        match qr {
+            OperationResult::Connect => (),
+            _ => panic!("connect() has failed"),
+        }
+
+        // Check endpoint addresses.
+        let local: SocketAddrV4 = libos.local_addr(sockqd).expect("connected socket should be bound");
+        assert_eq!(local.ip(), &BOB_IPV4);
+        assert_ne!(local.port(), 0);
+        assert_eq!(libos.remote_addr(sockqd).expect("socket should be connected"), remote);
+
+        // Close connection.
+        safe_close_active(&mut libos, sockqd);
+    });
+
+    alice.join().unwrap();
+    bob.join().unwrap();
+}
+
 //======================================================================================================================
 // Standalone Functions
 //======================================================================================================================
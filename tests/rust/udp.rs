@@ -16,6 +16,7 @@ use ::demikernel::{
     },
     runtime::{
         memory::DemiBuffer,
+        network::types::SocketOption,
         QDesc,
         QToken,
     },
@@ -109,6 +110,81 @@ fn udp_connect_loopback() {
     libos.close(sockfd).unwrap();
 }
 
+/// Tests if operations that are pending on a socket fail with `ECANCELED` once the socket is closed.
+#[test]
+fn udp_close_cancels_pending() {
+    let (tx, rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+    let mut libos: InetStack = DummyLibOS::new(ALICE_MAC, ALICE_IPV4, tx, rx, arp());
+
+    let port: u16 = PORT_BASE;
+    let local: SocketAddrV4 = SocketAddrV4::new(ALICE_IPV4, port);
+
+    // Issue a pop that no datagram completes, then close the socket.
+    let sockfd: QDesc = libos.socket(AF_INET, SOCK_DGRAM, 0).unwrap();
+    libos.bind(sockfd, local).unwrap();
+    let qt: QToken = libos.pop(sockfd).unwrap();
+    libos.close(sockfd).unwrap();
+
+    let (qd, qr): (QDesc, OperationResult) = match libos.wait2(qt) {
+        Ok((qd, qr)) => (qd, qr),
+        Err(e) => panic!("operation failed: {:?}", e.cause),
+    };
+    assert_eq!(qd, sockfd);
+    match qr {
+        OperationResult::Failed(e) => assert_eq!(e.errno, libc::ECANCELED),
+        _ => panic!("pop() should have been canceled"),
+    }
+}
+
+//==============================================================================
+// Socket Options
+//==============================================================================
+
+/// Tests if socket options can be set and retrieved.
+#[test]
+fn udp_socket_options() {
+    let (tx, rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+    let mut libos: InetStack = DummyLibOS::new(ALICE_MAC, ALICE_IPV4, tx, rx, arp());
+
+    let local: SocketAddrV4 = SocketAddrV4::new(ALICE_IPV4, PORT_BASE);
+    let sockfd: QDesc = libos.socket(AF_INET, SOCK_DGRAM, 0).unwrap();
+    libos.bind(sockfd, local).unwrap();
+
+    // Set and get supported options.
+    libos.set_socket_option(sockfd, SocketOption::Broadcast(true)).unwrap();
+    assert_eq!(
+        libos.get_socket_option(sockfd, SocketOption::Broadcast(false)).unwrap(),
+        SocketOption::Broadcast(true)
+    );
+    libos
+        .set_socket_option(sockfd, SocketOption::ReceiveBufferSize(4096))
+        .unwrap();
+    assert_eq!(
+        libos
+            .get_socket_option(sockfd, SocketOption::ReceiveBufferSize(0))
+            .unwrap(),
+        SocketOption::ReceiveBufferSize(4096)
+    );
+
+    // Options that the network stack does not implement should be rejected.
+    assert_eq!(
+        libos
+            .set_socket_option(sockfd, SocketOption::Ttl(64))
+            .unwrap_err()
+            .errno,
+        libc::ENOPROTOOPT
+    );
+    assert_eq!(
+        libos.get_socket_option(sockfd, SocketOption::Ttl(0)).unwrap_err().errno,
+        libc::ENOPROTOOPT
+    );
+
+    // TCP-only options should be rejected.
+    assert!(libos.set_socket_option(sockfd, SocketOption::NoDelay(true)).is_err());
+
+    libos.close(sockfd).unwrap();
+}
+
 //==============================================================================
 // Push
 //==============================================================================
@@ -153,7 +229,7 @@ fn udp_push_remote() {
             Err(e) => panic!("operation failed: {:?}", e.cause),
         };
         match qr {
-            OperationResult::Pop(_, _) => (),
+            OperationResult::Pop(_, _, _) => (),
             _ => panic!("pop() failed"),
         }
 
@@ -175,7 +251,7 @@ fn udp_push_remote() {
             Err(e) => panic!("operation failed: {:?}", e.cause),
         };
         let bytes: DemiBuffer = match qr {
-            OperationResult::Pop(_, bytes) => bytes,
+            OperationResult::Pop(_, bytes, _) => bytes,
             _ => panic!("pop() failed"),
         };
 
@@ -237,7 +313,7 @@ fn udp_loopback() {
             Err(e) => panic!("operation failed: {:?}", e.cause),
         };
         match qr {
-            OperationResult::Pop(_, _) => (),
+            OperationResult::Pop(_, _, _) => (),
             _ => panic!("pop() failed"),
         }
 
@@ -259,7 +335,7 @@ fn udp_loopback() {
             Err(e) => panic!("operation failed: {:?}", e.cause),
         };
         let bytes: DemiBuffer = match qr {
-            OperationResult::Pop(_, bytes) => bytes,
+            OperationResult::Pop(_, bytes, _) => bytes,
             _ => panic!("pop() failed"),
         };
 